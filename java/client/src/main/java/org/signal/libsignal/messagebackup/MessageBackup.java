@@ -9,7 +9,9 @@ import static org.signal.libsignal.internal.FilterExceptions.filterExceptions;
 
 import java.io.IOException;
 import java.io.InputStream;
+import java.io.OutputStream;
 import java.util.function.Supplier;
+import org.signal.libsignal.internal.BackupProgressListener;
 import org.signal.libsignal.internal.Native;
 import org.signal.libsignal.internal.NativeHandleGuard;
 import org.signal.libsignal.protocol.util.Pair;
@@ -24,11 +26,22 @@ public class MessageBackup {
    */
   public static class ValidationResult {
     public ValidationResult(String[] unknownFieldMessages) {
+      this(unknownFieldMessages, "[]");
+    }
+
+    public ValidationResult(String[] unknownFieldMessages, String unknownFieldsJson) {
       this.unknownFieldMessages = unknownFieldMessages;
+      this.unknownFieldsJson = unknownFieldsJson;
     }
 
     /** Information about unknown fields encountered while validating. */
     public String[] unknownFieldMessages;
+
+    /**
+     * The same unknown fields as {@link #unknownFieldMessages}, as a JSON array of objects with
+     * {@code frameIndex}, {@code path}, and {@code value} keys.
+     */
+    public String unknownFieldsJson;
   }
 
   public static enum Purpose {
@@ -53,31 +66,106 @@ public class MessageBackup {
   public static ValidationResult validate(
       MessageBackupKey key, Purpose purpose, Supplier<InputStream> streamFactory, long streamLength)
       throws ValidationError, IOException {
+    return validate(key, purpose, streamFactory, streamLength, null);
+  }
+
+  /** Receives progress updates during {@link #validate}. */
+  @FunctionalInterface
+  public static interface ProgressListener {
+    /**
+     * Called periodically while the backup is read, and once more when the whole input has been
+     * consumed.
+     *
+     * @param framesRead the number of frames read so far
+     * @param bytesRead the number of (encrypted) input bytes read so far
+     * @param totalBytes the total number of input bytes
+     */
+    void onProgress(long framesRead, long bytesRead, long totalBytes);
+  }
+
+  /**
+   * Validates an encrypted message backup bundle, reporting progress as it goes.
+   *
+   * <p>Behaves like {@link #validate(MessageBackupKey, Purpose, Supplier, long)}, but also calls
+   * {@code progressListener} (if not null) on the validating thread as the input is read.
+   *
+   * @param key the key to use to decrypt the backup
+   * @param purpose whether the input was created for device-to-device transfer or remote backup
+   * @param streamFactory a factory for <code>InputStream</code>s that produce the input
+   * @param streamLength the number of bytes each <code>InputStream</code> will produce
+   * @param progressListener receives progress updates, or null to skip reporting
+   * @return informational result about the successful validation
+   * @throws ValidationError with an error message if the input is invalid
+   * @throws IOException if the input could not be read
+   */
+  public static ValidationResult validate(
+      MessageBackupKey key,
+      Purpose purpose,
+      Supplier<InputStream> streamFactory,
+      long streamLength,
+      ProgressListener progressListener)
+      throws ValidationError, IOException {
     InputStream first = streamFactory.get();
     InputStream second = streamFactory.get();
 
-    Pair<String, String[]> result;
+    Pair<Pair<Integer, String>, Pair<String[], String>> result;
     try (NativeHandleGuard keyGuard = new NativeHandleGuard(key)) {
 
       Object output =
           filterExceptions(
               IOException.class,
               ValidationError.class,
-              () ->
-                  Native.MessageBackupValidator_Validate(
-                      keyGuard.nativeHandle(), first, second, streamLength, purpose.ordinal()));
+              () -> {
+                if (progressListener == null) {
+                  return Native.MessageBackupValidator_Validate(
+                      keyGuard.nativeHandle(), first, second, streamLength, purpose.ordinal());
+                }
+                BackupProgressListener listener = progressListener::onProgress;
+                return Native.MessageBackupValidator_ValidateWithProgress(
+                    keyGuard.nativeHandle(),
+                    first,
+                    second,
+                    streamLength,
+                    purpose.ordinal(),
+                    listener);
+              });
 
       // Rust conversion code is generating an instance of this class.
       @SuppressWarnings("unchecked")
-      Pair<String, String[]> outputPair = (Pair<String, String[]>) output;
+      Pair<Pair<Integer, String>, Pair<String[], String>> outputPair =
+          (Pair<Pair<Integer, String>, Pair<String[], String>>) output;
       result = outputPair;
     }
 
-    String errorMessage = result.first();
-    if (errorMessage != null) {
-      throw new ValidationError(errorMessage, result.second());
+    Pair<Integer, String> error = result.first();
+    String[] unknownFieldMessages = result.second().first();
+    String unknownFieldsJson = result.second().second();
+    if (error != null) {
+      ValidationError.Kind kind = ValidationError.Kind.values()[error.first()];
+      throw new ValidationError(kind, error.second(), unknownFieldMessages, unknownFieldsJson);
     }
 
-    return new ValidationResult(result.second());
+    return new ValidationResult(unknownFieldMessages, unknownFieldsJson);
+  }
+
+  /**
+   * Encrypts a message backup bundle, writing the result to {@code output}.
+   *
+   * <p>The frames are compressed, padded, and encrypted into the same format that {@link
+   * #validate} reads. {@code output} is flushed at the end but not closed.
+   *
+   * @param key the key to use to encrypt the backup
+   * @param frames the serialized frames of the backup, starting with the backup info, each
+   *     prefixed with its varint-encoded length
+   * @param output where to write the encrypted backup
+   * @throws IOException if the frames could not be read or the output could not be written
+   */
+  public static void writeEncrypted(MessageBackupKey key, InputStream frames, OutputStream output)
+      throws IOException {
+    try (NativeHandleGuard keyGuard = new NativeHandleGuard(key)) {
+      filterExceptions(
+          IOException.class,
+          () -> Native.MessageBackup_WriteEncrypted(frames, keyGuard.nativeHandle(), output));
+    }
   }
 }
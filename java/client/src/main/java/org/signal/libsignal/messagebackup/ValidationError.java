@@ -11,11 +11,46 @@ package org.signal.libsignal.messagebackup;
  * <p>{@link Throwable#getMessage} returns the validation error message.
  */
 public class ValidationError extends Exception {
+  /** Why validation failed. */
+  public static enum Kind {
+    // This needs to be kept in sync with the corresponding Rust enum.
+    /** The backup couldn't be authenticated, usually because the wrong key was used. */
+    HMAC_MISMATCH,
+    /** The input is too short to be an encrypted backup. */
+    TOO_SHORT,
+    /** The decrypted contents couldn't be parsed as backup frames. */
+    CORRUPTED,
+    /** The backup doesn't contain any frames. */
+    NO_FRAMES,
+    /** A frame's contents are invalid. */
+    INVALID_CONTENTS,
+    /** The backup is missing required frames. */
+    INCOMPLETE,
+    /**
+     * The backup uses a version of the format that isn't supported.
+     *
+     * <p>If the backup is newer than the library, updating the app may allow it to be read.
+     */
+    UNSUPPORTED_VERSION,
+  }
+
+  /** The kind of error that caused validation to fail. */
+  public Kind kind;
+
   /** Contains messages about unknown fields found while parsing. */
   public String[] unknownFieldMessages;
 
-  ValidationError(String message, String[] unknownFields) {
+  /**
+   * The same unknown fields as {@link #unknownFieldMessages}, as JSON.
+   *
+   * @see MessageBackup.ValidationResult#unknownFieldsJson
+   */
+  public String unknownFieldsJson;
+
+  ValidationError(Kind kind, String message, String[] unknownFields, String unknownFieldsJson) {
     super(message);
+    this.kind = kind;
     this.unknownFieldMessages = unknownFields;
+    this.unknownFieldsJson = unknownFieldsJson;
   }
 }
@@ -6,6 +6,7 @@
 package org.signal.libsignal.net;
 
 import java.io.IOException;
+import java.time.Duration;
 import java.util.concurrent.ExecutionException;
 import org.signal.libsignal.internal.CompletableFuture;
 import org.signal.libsignal.internal.Native;
@@ -13,7 +14,11 @@ import org.signal.libsignal.internal.NativeHandleGuard;
 
 class CdsiLookup implements NativeHandleGuard.Owner {
   public static CompletableFuture<CdsiLookup> start(
-      Network network, String username, String password, CdsiLookupRequest request)
+      Network network,
+      String username,
+      String password,
+      CdsiLookupRequest request,
+      Duration timeout)
       throws IOException, InterruptedException, ExecutionException {
 
     CdsiLookupRequest.NativeRequest nativeRequest = request.makeNative();
@@ -26,7 +31,8 @@ class CdsiLookup implements NativeHandleGuard.Owner {
               connectionManager.nativeHandle(),
               username,
               password,
-              nativeRequest.getHandle())
+              nativeRequest.getHandle(),
+              Math.toIntExact(timeout.toMillis()))
           .thenApply((Long nativeHandle) -> new CdsiLookup(nativeHandle, network));
     }
   }
@@ -39,6 +45,14 @@ class CdsiLookup implements NativeHandleGuard.Owner {
     }
   }
 
+  public CompletableFuture<CdsiLookupEntries> completeEntries() {
+    try (NativeHandleGuard asyncRuntime = new NativeHandleGuard(this.network.getAsyncContext());
+        NativeHandleGuard self = new NativeHandleGuard(this)) {
+      return Native.CdsiLookup_completeEntries(asyncRuntime.nativeHandle(), self.nativeHandle())
+          .thenApply(response -> (CdsiLookupEntries) response);
+    }
+  }
+
   public byte[] getToken() {
     return Native.CdsiLookup_token(this.nativeHandle);
   }
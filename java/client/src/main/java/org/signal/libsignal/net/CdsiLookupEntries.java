@@ -0,0 +1,100 @@
+//
+// Copyright 2024 Signal Messenger, LLC.
+// SPDX-License-Identifier: AGPL-3.0-only
+//
+
+package org.signal.libsignal.net;
+
+import java.util.ArrayList;
+import java.util.Arrays;
+import java.util.Collections;
+import java.util.List;
+import java.util.Objects;
+import java.util.stream.Collectors;
+import org.signal.libsignal.internal.CalledFromNative;
+import org.signal.libsignal.protocol.ServiceId;
+
+/**
+ * The result of a CDSI lookup, as a list of entries in the order the server sent them.
+ *
+ * <p>Unlike {@link CdsiLookupResponse}, each entry carries its own phone number, and a missing ACI
+ * or PNI is always {@code null}.
+ */
+public class CdsiLookupEntries {
+  public static class Entry {
+    @CalledFromNative
+    Entry(String e164, byte[] aci, byte[] pni) throws ServiceId.InvalidServiceIdException {
+      this(
+          e164,
+          aci != null ? ServiceId.Aci.parseFromFixedWidthBinary(aci) : null,
+          pni != null ? ServiceId.Pni.parseFromFixedWidthBinary(pni) : null);
+    }
+
+    public Entry(String e164, ServiceId.Aci aci, ServiceId.Pni pni) {
+      this.e164 = e164;
+      this.aci = aci;
+      this.pni = pni;
+    }
+
+    public String toString() {
+      return "{e164: " + e164 + ", aci: " + aci + ", pni: " + pni + "}";
+    }
+
+    public boolean equals(Object obj) {
+      if (obj instanceof Entry) {
+        Entry other = (Entry) obj;
+        return Objects.equals(this.e164, other.e164)
+            && Objects.equals(this.aci, other.aci)
+            && Objects.equals(this.pni, other.pni);
+      }
+      return false;
+    }
+
+    public int hashCode() {
+      return Objects.hash(this.e164, this.aci, this.pni);
+    }
+
+    public final String e164;
+    public final ServiceId.Aci aci;
+    public final ServiceId.Pni pni;
+  }
+
+  @CalledFromNative
+  CdsiLookupEntries(Entry[] entries, int debugPermitsUsed) {
+    this(Arrays.asList(entries), debugPermitsUsed);
+  }
+
+  public CdsiLookupEntries(List<Entry> entries, int debugPermitsUsed) {
+    this.entries = Collections.unmodifiableList(new ArrayList<>(entries));
+    this.debugPermitsUsed = debugPermitsUsed;
+  }
+
+  public List<Entry> entries() {
+    return this.entries;
+  }
+
+  /** Returns only the entries that have an ACI, in order. */
+  public List<Entry> entriesWithAci() {
+    return this.entries.stream().filter(e -> e.aci != null).collect(Collectors.toList());
+  }
+
+  public String toString() {
+    return "{entries: " + entries + ", debugPermitsUsed: " + debugPermitsUsed + "}";
+  }
+
+  public boolean equals(Object obj) {
+    if (obj instanceof CdsiLookupEntries) {
+      CdsiLookupEntries other = (CdsiLookupEntries) obj;
+      return Objects.equals(this.entries, other.entries)
+          && Objects.equals(this.debugPermitsUsed, other.debugPermitsUsed);
+    }
+    return false;
+  }
+
+  public int hashCode() {
+    return Objects.hash(this.entries, this.debugPermitsUsed);
+  }
+
+  private final List<Entry> entries;
+  public final int debugPermitsUsed;
+}
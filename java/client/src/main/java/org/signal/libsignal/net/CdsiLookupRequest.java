@@ -44,6 +44,16 @@ public class CdsiLookupRequest {
     this.token = token.orElse(null);
   }
 
+  /**
+   * Estimates the number of rate-limit permits the server will charge for this request.
+   *
+   * <p>This is a best-effort estimate, suitable for warning the user before a large lookup; the
+   * server may change how it charges at any time.
+   */
+  public int permitCostEstimate() {
+    return Native.LookupRequest_permitCostEstimate(makeNative().getHandle());
+  }
+
   NativeRequest makeNative() {
     return new NativeRequest(
         this.previousE164s,
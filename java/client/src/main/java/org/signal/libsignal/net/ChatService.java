@@ -6,6 +6,7 @@
 package org.signal.libsignal.net;
 
 import java.net.MalformedURLException;
+import java.time.Duration;
 import java.util.Map;
 import org.signal.libsignal.internal.CalledFromNative;
 import org.signal.libsignal.internal.CompletableFuture;
@@ -55,6 +56,20 @@ public class ChatService extends NativeHandleGuard.SimpleOwner {
                     Native.ChatService_disconnect_unauth(asyncContextHandle, chatServiceHandle)));
   }
 
+  /**
+   * Returns a smoothed estimate of the round-trip time to the Chat Service, measured passively over
+   * the current unauthenticated connection.
+   *
+   * <p>The estimate starts over whenever the service reconnects.
+   *
+   * @return the estimate, or {@code null} if there's no connection or the estimate isn't available
+   *     yet.
+   */
+  public Duration getRoundTripTimeEstimate() {
+    final int millis = guardedMap(Native::ChatService_rtt_estimate_millis_unauth);
+    return millis != -1 ? Duration.ofMillis(Integer.toUnsignedLong(millis)) : null;
+  }
+
   /**
    * Initiates establishing of the underlying authenticated connection to the Chat Service. Once the
    * service is connected, all the requests will be using the established connection. Also, if the
@@ -226,10 +241,37 @@ public class ChatService extends NativeHandleGuard.SimpleOwner {
 
   public record Response(int status, String message, Map<String, String> headers, byte[] body) {}
 
-  public record DebugInfo(IpType ipType, int durationMs, String connectionInfo) {
+  /**
+   * Information about a chat connection, for logging.
+   *
+   * <p>{@code remoteIp} is the address of the proxy for proxied connections, and is {@code null}
+   * (with a {@code remotePort} of 0) if no connection was made.
+   *
+   * <p>{@code connectionInfo} is superseded by the other fields and will be removed in a future
+   * release.
+   */
+  public record DebugInfo(
+      IpType ipType,
+      int durationMs,
+      String connectionInfo,
+      RouteType routeType,
+      String remoteIp,
+      int remotePort) {
     @CalledFromNative
-    DebugInfo(byte ipTypeCode, int durationMs, String connectionInfo) {
-      this(IpType.values()[ipTypeCode], durationMs, connectionInfo);
+    DebugInfo(
+        byte ipTypeCode,
+        int durationMs,
+        String connectionInfo,
+        byte routeTypeCode,
+        String remoteIp,
+        int remotePort) {
+      this(
+          IpType.values()[ipTypeCode],
+          durationMs,
+          connectionInfo,
+          RouteType.values()[routeTypeCode],
+          remoteIp,
+          remotePort);
     }
   }
 
@@ -8,6 +8,7 @@ package org.signal.libsignal.net;
 import static org.signal.libsignal.internal.FilterExceptions.filterExceptions;
 
 import java.io.IOException;
+import java.time.Duration;
 import java.util.concurrent.ExecutionException;
 import java.util.function.Consumer;
 import org.signal.libsignal.internal.CompletableFuture;
@@ -26,6 +27,27 @@ public class Network {
     }
   }
 
+  /**
+   * The kind of network the device is using, as passed to {@link #setChatNetworkHint}.
+   *
+   * <p>The values of this enum should match {@code NetworkTransport} in the Rust code.
+   */
+  public enum NetworkTransport {
+    /** Unknown, or anything other than Wi-Fi and cellular (such as Ethernet). */
+    OTHER(0),
+    WIFI(1),
+    CELLULAR(2);
+
+    private final int value;
+
+    NetworkTransport(int value) {
+      this.value = value;
+    }
+  }
+
+  /** How long {@link #cdsiLookup} keeps trying to connect if no timeout is given. */
+  public static final Duration DEFAULT_CDSI_CONNECT_TIMEOUT = Duration.ofMinutes(3);
+
   private final TokioAsyncContext tokioAsyncContext;
 
   private final ConnectionManager connectionManager;
@@ -75,6 +97,22 @@ public class Network {
     connectionManager.guardedRun(Native::ConnectionManager_on_network_change);
   }
 
+  /**
+   * Describes the current network, so that new chat connections can be tuned for it.
+   *
+   * <p>This adjusts the chat connection timeout and how often an idle chat connection is kept
+   * alive. It also decides whether IPv6 is tried before IPv4, which applies to every kind of
+   * connection, not just chat. Existing connections are unaffected. Apps should call this whenever
+   * the OS reports a change in the default network's capabilities.
+   *
+   * @param transport the kind of network the device is using
+   * @param constrained whether the network is metered or has background data restricted
+   */
+  public void setChatNetworkHint(NetworkTransport transport, boolean constrained) {
+    connectionManager.guardedRun(
+        h -> Native.ConnectionManager_set_chat_network_hint(h, transport.value, constrained));
+  }
+
   public Svr3 svr3() {
     return this.svr3;
   }
@@ -82,7 +120,21 @@ public class Network {
   public CompletableFuture<CdsiLookupResponse> cdsiLookup(
       String username, String password, CdsiLookupRequest request, Consumer<byte[]> tokenConsumer)
       throws IOException, InterruptedException, ExecutionException {
-    return CdsiLookup.start(this, username, password, request)
+    return cdsiLookup(username, password, request, DEFAULT_CDSI_CONNECT_TIMEOUT, tokenConsumer);
+  }
+
+  /**
+   * Looks up users in CDSI, giving up if a connection can't be established within {@code
+   * connectTimeout}.
+   */
+  public CompletableFuture<CdsiLookupResponse> cdsiLookup(
+      String username,
+      String password,
+      CdsiLookupRequest request,
+      Duration connectTimeout,
+      Consumer<byte[]> tokenConsumer)
+      throws IOException, InterruptedException, ExecutionException {
+    return CdsiLookup.start(this, username, password, request, connectTimeout)
         .thenCompose(
             (CdsiLookup lookup) -> {
               tokenConsumer.accept(lookup.getToken());
@@ -90,6 +142,25 @@ public class Network {
             });
   }
 
+  /**
+   * Looks up users in CDSI, like {@link #cdsiLookup}, but returns the results as a list of entries
+   * with explicitly optional ACIs and PNIs.
+   */
+  public CompletableFuture<CdsiLookupEntries> cdsiLookupEntries(
+      String username,
+      String password,
+      CdsiLookupRequest request,
+      Duration connectTimeout,
+      Consumer<byte[]> tokenConsumer)
+      throws IOException, InterruptedException, ExecutionException {
+    return CdsiLookup.start(this, username, password, request, connectTimeout)
+        .thenCompose(
+            (CdsiLookup lookup) -> {
+              tokenConsumer.accept(lookup.getToken());
+              return lookup.completeEntries();
+            });
+  }
+
   /**
    * Try to load several libsignal classes asynchronously, using the same mechanism as native (Rust)
    * code.
@@ -101,6 +172,7 @@ public class Network {
     // This doesn't need to be comprehensive, just check a few classes.
     final String[] classesToLoad = {
       "org.signal.libsignal.net.CdsiLookupResponse$Entry",
+      "org.signal.libsignal.net.CdsiLookupEntries$Entry",
       "org.signal.libsignal.net.NetworkException",
       "org.signal.libsignal.net.ChatServiceException",
       "org.signal.libsignal.protocol.ServiceId",
@@ -0,0 +1,59 @@
+//
+// Copyright 2024 Signal Messenger, LLC.
+// SPDX-License-Identifier: AGPL-3.0-only
+//
+
+package org.signal.libsignal.net;
+
+import org.signal.libsignal.internal.Native;
+import org.signal.libsignal.internal.NativeHandleGuard;
+
+/**
+ * The result of moving a pin-protected secret from SVR2 to SVR3, returned by {@link
+ * Svr3#migrateFromSvr2}.
+ *
+ * <p>Once the secret has been restored from SVR2 it is always available through {@link
+ * #getSecret}, even if a later step of the migration failed. An incomplete migration can be picked
+ * up where it stopped by passing this object to {@link Svr3#resumeMigrationFromSvr2}.
+ */
+public final class PinMigrationOutcome extends NativeHandleGuard.SimpleOwner {
+  PinMigrationOutcome(long nativeHandle) {
+    super(nativeHandle);
+  }
+
+  @Override
+  protected void release(long nativeHandle) {
+    Native.PinMigrationOutcome_Destroy(nativeHandle);
+  }
+
+  /**
+   * Whether the secret has been backed up to SVR3 and deleted from SVR2.
+   *
+   * <p>If not, {@link #getErrorMessage} describes what went wrong.
+   */
+  public boolean isComplete() {
+    return guardedMap(Native::PinMigrationOutcome_IsComplete);
+  }
+
+  /** The 32-byte secret restored from SVR2. */
+  public byte[] getSecret() {
+    return guardedMap(Native::PinMigrationOutcome_GetSecret);
+  }
+
+  /**
+   * The serialized masked share set produced by backing up to SVR3, to be used with {@link
+   * Svr3#restore}, or {@code null} if the backup step has not succeeded yet.
+   */
+  public byte[] getShareSet() {
+    byte[] shareSet = guardedMap(Native::PinMigrationOutcome_GetShareSet);
+    return shareSet.length == 0 ? null : shareSet;
+  }
+
+  /**
+   * A log-safe description of the error that stopped the migration, or {@code null} if it is
+   * complete.
+   */
+  public String getErrorMessage() {
+    return guardedMap(Native::PinMigrationOutcome_GetErrorMessage);
+  }
+}
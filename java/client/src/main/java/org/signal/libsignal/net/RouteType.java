@@ -0,0 +1,23 @@
+//
+// Copyright 2024 Signal Messenger, LLC.
+// SPDX-License-Identifier: AGPL-3.0-only
+//
+
+package org.signal.libsignal.net;
+
+/**
+ * How a chat connection reached the server.
+ *
+ * <p>The order of values in this enum should match {@code route_type_code} in the Rust bridge.
+ */
+public enum RouteType {
+  /** No connection was made. */
+  UNKNOWN,
+  DIRECT,
+  /** Domain fronting through the first fronting provider. */
+  PROXY_F,
+  /** Domain fronting through the second fronting provider. */
+  PROXY_G,
+  TLS_PROXY,
+  SOCKS_PROXY
+}
@@ -225,7 +225,8 @@ public final class Svr3 {
   /**
    * Remove a value stored in SVR3.
    *
-   * <p>This method will succeed even if the data has never been backed up in the first place.
+   * <p>This method will succeed even if the data has never been backed up in the first place. It
+   * will also succeed if the data could be removed from only some of the enclaves.
    *
    * <p>As noted above due to the asynchronous nature of the API all the expected errors will only
    * be thrown when the Future is awaited, and furthermore will be wrapped in {@link
@@ -257,10 +258,11 @@ public final class Svr3 {
             new NativeHandleGuard(this.network.getConnectionManager())) {
 
       return Native.Svr3Remove(
-          asyncRuntime.nativeHandle(),
-          connectionManager.nativeHandle(),
-          auth.username,
-          auth.password);
+              asyncRuntime.nativeHandle(),
+              connectionManager.nativeHandle(),
+              auth.username,
+              auth.password)
+          .thenApply(removedFromEachEnclave -> null);
     }
   }
 
@@ -319,6 +321,84 @@ public final class Svr3 {
     }
   }
 
+  /**
+   * Move a pin-protected secret from SVR2 to SVR3.
+   *
+   * <p>The secret is restored from SVR2 using {@code pin}, backed up to SVR3 with {@code pin} as
+   * the password, and finally deleted from SVR2. Transient failures while backing up or deleting
+   * are retried a few times before giving up.
+   *
+   * <p>If restoring from SVR2 fails, the returned Future fails with the corresponding exception,
+   * and there is nothing to resume. Any later failure instead produces an incomplete {@link
+   * PinMigrationOutcome}, which holds the restored secret and can be passed to {@link
+   * #resumeMigrationFromSvr2} to continue from the step that failed.
+   *
+   * <p>Exception messages are log-safe and do not contain any sensitive data.
+   *
+   * @param pin the user's pin, used both to restore from SVR2 and as the SVR3 password.
+   * @param maxTries number of times the secret will be allowed to be guessed in SVR3. Must be
+   *     positive.
+   * @param svr2Auth credentials for SVR2 obtained from the Chat Server.
+   * @param svr3Auth credentials for SVR3 obtained from the Chat Server.
+   * @return an instance of {@link org.signal.libsignal.internal.CompletableFuture} which-when
+   *     awaited-will return the outcome of the migration.
+   * @throws {@link org.signal.libsignal.svr.RestoreFailedException} when the pin is wrong.
+   * @throws {@link org.signal.libsignal.svr.DataMissingException} when there is nothing stored in
+   *     SVR2 for this user.
+   * @throws {@link org.signal.libsignal.net.NetworkException} in case of network connection errors
+   *     while restoring from SVR2.
+   */
+  public final CompletableFuture<PinMigrationOutcome> migrateFromSvr2(
+      String pin, int maxTries, EnclaveAuth svr2Auth, EnclaveAuth svr3Auth) {
+    try (NativeHandleGuard asyncRuntime = new NativeHandleGuard(this.network.getAsyncContext());
+        NativeHandleGuard connectionManager =
+            new NativeHandleGuard(this.network.getConnectionManager())) {
+
+      return Native.Svr2MigrateToSvr3(
+              asyncRuntime.nativeHandle(),
+              connectionManager.nativeHandle(),
+              pin,
+              maxTries,
+              svr2Auth.username,
+              svr2Auth.password,
+              svr3Auth.username,
+              svr3Auth.password)
+          .thenApply(PinMigrationOutcome::new);
+    }
+  }
+
+  /**
+   * Continue a migration started by {@link #migrateFromSvr2} from the step that failed.
+   *
+   * <p>If {@code previousOutcome} is already complete, it is returned unchanged. Otherwise the
+   * arguments are the same as for {@link #migrateFromSvr2}; the credentials do not have to be the
+   * ones used originally.
+   */
+  public final CompletableFuture<PinMigrationOutcome> resumeMigrationFromSvr2(
+      PinMigrationOutcome previousOutcome,
+      String pin,
+      int maxTries,
+      EnclaveAuth svr2Auth,
+      EnclaveAuth svr3Auth) {
+    try (NativeHandleGuard asyncRuntime = new NativeHandleGuard(this.network.getAsyncContext());
+        NativeHandleGuard connectionManager =
+            new NativeHandleGuard(this.network.getConnectionManager());
+        NativeHandleGuard previous = new NativeHandleGuard(previousOutcome)) {
+
+      return Native.Svr2ResumeMigrationToSvr3(
+              asyncRuntime.nativeHandle(),
+              connectionManager.nativeHandle(),
+              previous.nativeHandle(),
+              pin,
+              maxTries,
+              svr2Auth.username,
+              svr2Auth.password,
+              svr3Auth.username,
+              svr3Auth.password)
+          .thenApply(PinMigrationOutcome::new);
+    }
+  }
+
   /** The value containing restored secret returned from {@link #restore}. */
   public record RestoredSecret(int triesRemaining, byte[] value) {
 
@@ -5,6 +5,7 @@
 
 package org.signal.libsignal.net;
 
+import java.time.Duration;
 import org.signal.libsignal.internal.CompletableFuture;
 import org.signal.libsignal.internal.Native;
 import org.signal.libsignal.internal.NativeHandleGuard;
@@ -19,6 +20,18 @@ class TokioAsyncContext extends NativeHandleGuard.SimpleOwner {
     return (CompletableFuture<Class<Object>>) Native.AsyncLoadClass(this, className);
   }
 
+  /**
+   * Cancels all outstanding tasks and shuts down the runtime, waiting up to {@code timeout} for
+   * them to finish.
+   *
+   * <p>Any async calls made using this context afterwards will fail with {@link
+   * IllegalStateException}.
+   */
+  void shutdown(Duration timeout) {
+    final int timeoutMillis = (int) Math.min(timeout.toMillis(), Integer.MAX_VALUE);
+    guardedRun(nativeHandle -> Native.TokioAsyncContext_shutdown(nativeHandle, timeoutMillis));
+  }
+
   @Override
   protected void release(final long nativeHandle) {
     Native.TokioAsyncContext_Destroy(nativeHandle);
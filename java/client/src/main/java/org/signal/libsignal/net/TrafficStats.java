@@ -0,0 +1,78 @@
+//
+// Copyright 2024 Signal Messenger, LLC.
+// SPDX-License-Identifier: AGPL-3.0-only
+//
+
+package org.signal.libsignal.net;
+
+import java.nio.ByteBuffer;
+import org.signal.libsignal.internal.Native;
+
+/**
+ * The bytes sent and received by each kind of connection libsignal makes, for the whole process.
+ *
+ * <p>The counts are taken on top of TLS, so they include HTTP and WebSocket framing but not TLS
+ * records or TCP/IP headers.
+ */
+public final class TrafficStats {
+  /** Bytes sent and received over one kind of connection. */
+  public static final class Counts {
+    public final long sent;
+    public final long received;
+
+    public Counts(long sent, long received) {
+      this.sent = sent;
+      this.received = received;
+    }
+
+    @Override
+    public boolean equals(Object other) {
+      if (!(other instanceof Counts)) {
+        return false;
+      }
+      Counts that = (Counts) other;
+      return this.sent == that.sent && this.received == that.received;
+    }
+
+    @Override
+    public int hashCode() {
+      return Long.hashCode(sent) * 31 + Long.hashCode(received);
+    }
+
+    @Override
+    public String toString() {
+      return "Counts{sent=" + sent + ", received=" + received + "}";
+    }
+  }
+
+  public final Counts chat;
+  public final Counts cdsi;
+  public final Counts svr;
+  public final Counts cdn;
+
+  /**
+   * Reads the counts for each kind in the order chat, CDSI, SVR, CDN, each as two big-endian
+   * 64-bit integers (sent, then received).
+   */
+  TrafficStats(byte[] serialized) {
+    ByteBuffer buffer = ByteBuffer.wrap(serialized);
+    this.chat = new Counts(buffer.getLong(), buffer.getLong());
+    this.cdsi = new Counts(buffer.getLong(), buffer.getLong());
+    this.svr = new Counts(buffer.getLong(), buffer.getLong());
+    this.cdn = new Counts(buffer.getLong(), buffer.getLong());
+  }
+
+  /** The totals since the process started or {@link #reset} was last called. */
+  public static TrafficStats snapshot() {
+    return new TrafficStats(Native.TrafficStats_snapshot());
+  }
+
+  /**
+   * Starts all the totals over from zero, returning what they were.
+   *
+   * <p>Bytes counted while this runs end up in exactly one of the result and the new totals.
+   */
+  public static TrafficStats reset() {
+    return new TrafficStats(Native.TrafficStats_reset());
+  }
+}
@@ -7,12 +7,18 @@ package org.signal.libsignal.messagebackup;
 
 import static org.junit.Assert.assertArrayEquals;
 import static org.junit.Assert.assertEquals;
+import static org.junit.Assert.assertFalse;
 import static org.junit.Assert.assertThrows;
 import static org.junit.Assert.assertTrue;
 
 import java.io.ByteArrayInputStream;
+import java.io.ByteArrayOutputStream;
 import java.io.IOException;
 import java.io.InputStream;
+import java.io.OutputStream;
+import java.util.ArrayList;
+import java.util.Arrays;
+import java.util.List;
 import java.util.UUID;
 import java.util.function.Supplier;
 import org.junit.Test;
@@ -31,6 +37,7 @@ public class MessageBackupValidationTest {
   }
 
   static final String VALID_BACKUP_RESOURCE_NAME = "encryptedbackup.binproto.encrypted";
+  static final String UNENCRYPTED_BACKUP_RESOURCE_NAME = "canonical-backup.binproto";
   static final MessageBackup.Purpose BACKUP_PURPOSE = MessageBackup.Purpose.REMOTE_BACKUP;
 
   @Test
@@ -47,6 +54,34 @@ public class MessageBackupValidationTest {
     MessageBackup.ValidationResult result =
         MessageBackup.validate(key, BACKUP_PURPOSE, factory, length);
     assertArrayEquals(result.unknownFieldMessages, new String[0]);
+    assertEquals("[]", result.unknownFieldsJson);
+  }
+
+  @Test
+  public void validBackupFileReportsProgress() throws IOException, ValidationError {
+    Supplier<InputStream> factory =
+        () -> {
+          return MessageBackupValidationTest.class.getResourceAsStream(VALID_BACKUP_RESOURCE_NAME);
+        };
+    final long length;
+    try (InputStream input = factory.get()) {
+      length = ResourceReader.readAll(input).length;
+    }
+    MessageBackupKey key = makeMessageBackupKey();
+    List<long[]> reports = new ArrayList<>();
+    MessageBackup.validate(
+        key,
+        BACKUP_PURPOSE,
+        factory,
+        length,
+        (framesRead, bytesRead, totalBytes) ->
+            reports.add(new long[] {framesRead, bytesRead, totalBytes}));
+
+    assertFalse(reports.isEmpty());
+    long[] last = reports.get(reports.size() - 1);
+    assertTrue(last[0] > 0);
+    assertEquals(length, last[1]);
+    assertEquals(length, last[2]);
   }
 
   @Test
@@ -64,6 +99,31 @@ public class MessageBackupValidationTest {
               MessageBackup.validate(key, BACKUP_PURPOSE, factory, 0);
             });
     assertEquals(error.getMessage(), "not enough bytes for an HMAC");
+    assertEquals(ValidationError.Kind.TOO_SHORT, error.kind);
+  }
+
+  @Test
+  public void wrongKeyReportsHmacMismatch() throws IOException {
+    Supplier<InputStream> factory =
+        () -> {
+          return MessageBackupValidationTest.class.getResourceAsStream(VALID_BACKUP_RESOURCE_NAME);
+        };
+    final long length;
+    try (InputStream input = factory.get()) {
+      length = ResourceReader.readAll(input).length;
+    }
+    byte[] wrongMasterKey = new byte[32];
+    Arrays.fill(wrongMasterKey, (byte) 'W');
+    Aci aci = new Aci(new UUID(0x1111111111111111L, 0x1111111111111111L));
+    MessageBackupKey key = new MessageBackupKey(wrongMasterKey, aci);
+
+    ValidationError error =
+        assertThrows(
+            ValidationError.class,
+            () -> {
+              MessageBackup.validate(key, BACKUP_PURPOSE, factory, length);
+            });
+    assertEquals(ValidationError.Kind.HMAC_MISMATCH, error.kind);
   }
 
   @Test
@@ -94,6 +154,35 @@ public class MessageBackupValidationTest {
             });
     assertEquals(thrown.getMessage(), ThrowingInputStream.MESSAGE);
   }
+
+  @Test
+  public void writeEncryptedProducesValidBackup() throws IOException, ValidationError {
+    MessageBackupKey key = makeMessageBackupKey();
+    ByteArrayOutputStream output = new ByteArrayOutputStream();
+    try (InputStream frames =
+        MessageBackupValidationTest.class.getResourceAsStream(UNENCRYPTED_BACKUP_RESOURCE_NAME)) {
+      MessageBackup.writeEncrypted(key, frames, output);
+    }
+
+    byte[] encrypted = output.toByteArray();
+    MessageBackup.ValidationResult result =
+        MessageBackup.validate(
+            key, BACKUP_PURPOSE, () -> new ByteArrayInputStream(encrypted), encrypted.length);
+    assertArrayEquals(result.unknownFieldMessages, new String[0]);
+  }
+
+  @Test
+  public void throwingOutputStreamThrowsIoException() throws IOException {
+    MessageBackupKey key = makeMessageBackupKey();
+    try (InputStream frames =
+        MessageBackupValidationTest.class.getResourceAsStream(UNENCRYPTED_BACKUP_RESOURCE_NAME)) {
+      IOException thrown =
+          assertThrows(
+              IOException.class,
+              () -> MessageBackup.writeEncrypted(key, frames, new ThrowingOutputStream()));
+      assertEquals(ThrowingOutputStream.MESSAGE, thrown.getMessage());
+    }
+  }
 }
 
 /** Input stream that throws an exception after producing some number of bytes. */
@@ -135,3 +224,13 @@ class ThrowingInputStream extends InputStream {
   private InputStream inner;
   private long bytesToReadBeforeThrowing;
 }
+
+/** Output stream that refuses every write. */
+class ThrowingOutputStream extends OutputStream {
+  public static String MESSAGE = "no space left";
+
+  @Override
+  public void write(int b) throws IOException {
+    throw new IOException(ThrowingOutputStream.MESSAGE);
+  }
+}
@@ -9,6 +9,7 @@ import static org.junit.Assert.*;
 
 import java.io.IOException;
 import java.time.Duration;
+import java.util.List;
 import java.util.Map;
 import java.util.UUID;
 import java.util.concurrent.ExecutionException;
@@ -52,6 +53,33 @@ public class CdsiLookupResponseTest {
     assertEquals(expected, actual);
   }
 
+  @Test
+  public void cdsiLookupEntriesConvert()
+      throws ServiceId.InvalidServiceIdException, ExecutionException, InterruptedException {
+    ServiceId.Aci aci = new ServiceId.Aci(UUID.fromString(aciUuid));
+    ServiceId.Pni pni = new ServiceId.Pni(UUID.fromString(pniUuid));
+
+    CdsiLookupEntries expected =
+        new CdsiLookupEntries(
+            List.of(
+                new CdsiLookupEntries.Entry(this.e164Both, aci, pni),
+                new CdsiLookupEntries.Entry(this.e164Pni, null, pni)),
+            this.debugPermitsUsed);
+
+    TokioAsyncContext context = new TokioAsyncContext();
+    Future<Object> response;
+
+    try (NativeHandleGuard guard = new NativeHandleGuard(context)) {
+      response = NativeTesting.TESTING_CdsiLookupResponseEntriesConvert(guard.nativeHandle());
+    }
+
+    CdsiLookupEntries actual = (CdsiLookupEntries) response.get();
+    assertEquals(expected, actual);
+    // The PNI-only entry comes through with a null ACI, not a nil UUID.
+    assertNull(actual.entries().get(1).aci);
+    assertEquals(List.of(expected.entries().get(0)), actual.entriesWithAci());
+  }
+
   @Test
   public void cdsiLookupErrorConvert() {
     assertLookupErrorIs(
@@ -55,6 +55,9 @@ public class ChatServiceTest {
     assertEquals(IpType.IPv4, debugInfo.ipType());
     assertEquals(200, debugInfo.durationMs());
     assertEquals("connection_info", debugInfo.connectionInfo());
+    assertEquals(RouteType.TLS_PROXY, debugInfo.routeType());
+    assertEquals("192.0.2.1", debugInfo.remoteIp());
+    assertEquals(443, debugInfo.remotePort());
   }
 
   @Test
@@ -83,8 +86,13 @@ public class ChatServiceTest {
     assertChatServiceErrorIs("UnexpectedFrameReceived", ChatServiceException.class);
     assertChatServiceErrorIs("ServerRequestMissingId", ChatServiceException.class);
     assertChatServiceErrorIs("IncomingDataInvalid", ChatServiceException.class);
-    assertChatServiceErrorIs("Timeout", ChatServiceException.class);
+    final ChatServiceException timeout =
+        assertChatServiceErrorIs("Timeout", ChatServiceException.class);
+    assertTrue(timeout.getMessage(), timeout.getMessage().contains("000000000000002a"));
     assertChatServiceErrorIs("TimeoutEstablishingConnection", ChatServiceException.class);
+    final ChatServiceException channelClosed =
+        assertChatServiceErrorIs("RequestChannelClosed", ChatServiceException.class);
+    assertTrue(channelClosed.getMessage(), channelClosed.getMessage().contains("000000000000002a"));
 
     // These two are more of internal errors, but they should never happen anyway.
     assertChatServiceErrorIs("FailedToPassMessageToIncomingChannel", ChatServiceException.class);
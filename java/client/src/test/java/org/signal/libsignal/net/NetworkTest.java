@@ -16,4 +16,14 @@ public class NetworkTest {
     var net = new Network(Network.Environment.STAGING, USER_AGENT);
     net.onNetworkChange();
   }
+
+  @Test
+  public void chatNetworkHint() {
+    // Similarly, this only affects future connections, so just check that every value is accepted.
+    var net = new Network(Network.Environment.STAGING, USER_AGENT);
+    for (var transport : Network.NetworkTransport.values()) {
+      net.setChatNetworkHint(transport, false);
+      net.setChatNetworkHint(transport, true);
+    }
+  }
 }
@@ -0,0 +1,49 @@
+//
+// Copyright 2024 Signal Messenger, LLC.
+// SPDX-License-Identifier: AGPL-3.0-only
+//
+
+package org.signal.libsignal.net;
+
+import static org.junit.Assert.assertEquals;
+import static org.junit.Assert.assertTrue;
+
+import org.junit.Test;
+
+public class TrafficStatsTest {
+  @Test
+  public void layout() {
+    byte[] serialized = new byte[64];
+    for (int i = 0; i < 8; i++) {
+      // Each count is big-endian, with its index in the last byte and 1 in the first.
+      serialized[8 * i] = 1;
+      serialized[8 * i + 7] = (byte) i;
+    }
+    long base = 1L << 56;
+
+    var stats = new TrafficStats(serialized);
+    assertEquals(new TrafficStats.Counts(base, base + 1), stats.chat);
+    assertEquals(new TrafficStats.Counts(base + 2, base + 3), stats.cdsi);
+    assertEquals(new TrafficStats.Counts(base + 4, base + 5), stats.svr);
+    assertEquals(new TrafficStats.Counts(base + 6, base + 7), stats.cdn);
+  }
+
+  @Test
+  public void snapshotAndReset() {
+    TrafficStats.reset();
+    var snapshot = TrafficStats.snapshot();
+    // Counts only go up until they're reset, so the reset sees at least what the snapshot did.
+    var reset = TrafficStats.reset();
+
+    TrafficStats.Counts[][] pairs = {
+      {snapshot.chat, reset.chat},
+      {snapshot.cdsi, reset.cdsi},
+      {snapshot.svr, reset.svr},
+      {snapshot.cdn, reset.cdn},
+    };
+    for (var pair : pairs) {
+      assertTrue(pair[0].sent <= pair[1].sent);
+      assertTrue(pair[0].received <= pair[1].received);
+    }
+  }
+}
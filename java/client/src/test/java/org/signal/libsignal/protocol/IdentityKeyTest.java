@@ -6,6 +6,8 @@
 package org.signal.libsignal.protocol;
 
 import junit.framework.TestCase;
+import org.signal.libsignal.protocol.ecc.ECPrivateKey;
+import org.signal.libsignal.protocol.util.Hex;
 
 public class IdentityKeyTest extends TestCase {
   public void testSignAlternateKey() {
@@ -14,4 +16,50 @@ public class IdentityKeyTest extends TestCase {
     byte[] signature = secondary.signAlternateIdentity(primary.getPublicKey());
     assertTrue(secondary.getPublicKey().verifyAlternateIdentity(primary.getPublicKey(), signature));
   }
+
+  // Shared with the Rust, Swift, and TypeScript tests.
+  private static final String PNI_TEST_ACI_PRIVATE =
+      "5882e1f3662ee3dd54b5d0b80f7d3f67277ee39a67d47697f78974879cd93266";
+  private static final String PNI_TEST_ACI_PUBLIC =
+      "0505c86d71f5ee1af068207fb1b50ba69d60fd70ffa78375eaa9f60f100ca65871";
+  private static final String PNI_TEST_PNI_PRIVATE =
+      "1823511d57e3ca8aafbe50a6d0a3f0ed4076eae74c5e0e2351d84eda70dc7d6f";
+  private static final String PNI_TEST_PNI_PUBLIC =
+      "0564a82fb9ceae27d3f741f728b3a3bc10a6446e4d702f3184b302a0fb5c93a424";
+  private static final String PNI_TEST_SIGNATURE =
+      "17e3c41803c64aad8effe9a4c516cc1452e1c1a5a1fafea6cd1cc55aa8f869b2"
+          + "83f821cfd6eec07b43190acbd62274704c24928da000e322095e6ec690b9db0b";
+
+  private static IdentityKeyPair pniTestKeyPair(String privateHex, String publicHex)
+      throws Exception {
+    IdentityKeyPair pair =
+        new IdentityKeyPair(
+            new IdentityKey(Hex.fromStringCondensedAssert(publicHex)),
+            new ECPrivateKey(Hex.fromStringCondensedAssert(privateHex)));
+    assertEquals(pair.getPublicKey().getPublicKey(), pair.getPrivateKey().publicKey());
+    return pair;
+  }
+
+  public void testPniSignatureVector() throws Exception {
+    IdentityKeyPair aci = pniTestKeyPair(PNI_TEST_ACI_PRIVATE, PNI_TEST_ACI_PUBLIC);
+    IdentityKeyPair pni = pniTestKeyPair(PNI_TEST_PNI_PRIVATE, PNI_TEST_PNI_PUBLIC);
+    byte[] signature = Hex.fromStringCondensedAssert(PNI_TEST_SIGNATURE);
+
+    assertTrue(aci.getPublicKey().verifyPniSignature(pni.getPublicKey(), signature));
+    assertFalse(pni.getPublicKey().verifyPniSignature(aci.getPublicKey(), signature));
+
+    byte[] fresh = pni.createPniSignature(aci.getPublicKey());
+    assertTrue(aci.getPublicKey().verifyPniSignature(pni.getPublicKey(), fresh));
+  }
+
+  public void testPniSignatureWrongLength() throws Exception {
+    IdentityKeyPair aci = pniTestKeyPair(PNI_TEST_ACI_PRIVATE, PNI_TEST_ACI_PUBLIC);
+    IdentityKeyPair pni = pniTestKeyPair(PNI_TEST_PNI_PRIVATE, PNI_TEST_PNI_PUBLIC);
+    try {
+      aci.getPublicKey().verifyPniSignature(pni.getPublicKey(), new byte[63]);
+      fail("should have thrown");
+    } catch (IllegalArgumentException e) {
+      // Expected.
+    }
+  }
 }
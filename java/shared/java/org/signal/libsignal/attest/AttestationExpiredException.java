@@ -0,0 +1,17 @@
+//
+// Copyright 2024 Signal Messenger, LLC.
+// SPDX-License-Identifier: AGPL-3.0-only
+//
+
+package org.signal.libsignal.attest;
+
+/**
+ * An enclave's attestation is not valid at the current time.
+ *
+ * <p>This can happen if the device's clock is wrong.
+ */
+public class AttestationExpiredException extends AttestationFailedException {
+  public AttestationExpiredException(String msg) {
+    super(msg);
+  }
+}
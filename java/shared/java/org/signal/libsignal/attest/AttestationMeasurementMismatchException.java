@@ -0,0 +1,17 @@
+//
+// Copyright 2024 Signal Messenger, LLC.
+// SPDX-License-Identifier: AGPL-3.0-only
+//
+
+package org.signal.libsignal.attest;
+
+/**
+ * An enclave is not running the code this version of the app expects.
+ *
+ * <p>This usually means the app needs to be updated.
+ */
+public class AttestationMeasurementMismatchException extends AttestationFailedException {
+  public AttestationMeasurementMismatchException(String msg) {
+    super(msg);
+  }
+}
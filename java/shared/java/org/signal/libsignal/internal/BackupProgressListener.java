@@ -0,0 +1,17 @@
+//
+// Copyright 2024 Signal Messenger, LLC.
+// SPDX-License-Identifier: AGPL-3.0-only
+//
+
+package org.signal.libsignal.internal;
+
+/**
+ * Receives progress updates while a message backup is read.
+ *
+ * <p>Called synchronously on the thread doing the reading.
+ */
+@CalledFromNative
+public interface BackupProgressListener {
+  @CalledFromNative
+  void onProgress(long framesRead, long bytesRead, long totalBytes);
+}
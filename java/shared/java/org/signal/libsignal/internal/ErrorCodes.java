@@ -0,0 +1,47 @@
+//
+// Copyright 2024 Signal Messenger, LLC.
+// SPDX-License-Identifier: AGPL-3.0-only
+//
+
+package org.signal.libsignal.internal;
+
+import java.util.Collections;
+import java.util.Map;
+import java.util.OptionalInt;
+import java.util.WeakHashMap;
+
+/**
+ * Stable numeric codes for exceptions thrown by libsignal.
+ *
+ * <p>Every exception created by the Rust library is tagged with a code identifying the most
+ * specific cause of the failure, even when the exception's class is more general. For example, a
+ * DNS failure while connecting to CDSI is thrown as an {@link java.io.IOException}, but its code
+ * still identifies it as a DNS failure. Codes are grouped by subsystem (networking in the 1000s,
+ * message backups in the 2000s, and so on); see {@code rust/bridge/shared/types/src/error_code.rs}
+ * for the full list.
+ *
+ * <p>A code keeps its number and meaning once released, and numbers are never reused, so codes
+ * are safe to persist or report to a server. New codes may be added at any time, including as a
+ * more specific replacement for the code a particular failure used to have. Exception messages, by
+ * contrast, are not stable.
+ *
+ * <p>Exceptions thrown by app code during a callback are rethrown as-is and have no code.
+ */
+@CalledFromNative
+public final class ErrorCodes {
+  private ErrorCodes() {}
+
+  private static final Map<Throwable, Integer> codes =
+      Collections.synchronizedMap(new WeakHashMap<>());
+
+  /** Returns the code for {@code throwable}, if it was created by libsignal. */
+  public static OptionalInt of(Throwable throwable) {
+    final Integer code = codes.get(throwable);
+    return code == null ? OptionalInt.empty() : OptionalInt.of(code);
+  }
+
+  @CalledFromNative
+  static void attach(Throwable throwable, int code) {
+    codes.put(throwable, code);
+  }
+}
@@ -117,6 +117,8 @@ public final class Native {
 
   public static native String AccountEntropyPool_Generate();
 
+  public static native void AddSupplementalRootCertificate(byte[] der) throws Exception;
+
   public static native void Aes256Ctr32_Destroy(long handle);
   public static native long Aes256Ctr32_New(byte[] key, byte[] nonce, int initialCtr) throws Exception;
   public static native void Aes256Ctr32_Process(long ctr, byte[] data, int offset, int length);
@@ -141,6 +143,7 @@ public final class Native {
   public static native void AuthChat_Destroy(long handle);
 
   public static native void AuthCredentialPresentation_CheckValidContents(byte[] presentationBytes) throws Exception;
+  public static native String AuthCredentialPresentation_GetHttpAuthorization(byte[] presentationBytes, byte[] groupPublicParams);
   public static native byte[] AuthCredentialPresentation_GetPniCiphertext(byte[] presentationBytes);
   public static native long AuthCredentialPresentation_GetRedemptionTime(byte[] presentationBytes);
   public static native byte[] AuthCredentialPresentation_GetUuidCiphertext(byte[] presentationBytes);
@@ -148,6 +151,7 @@ public final class Native {
   public static native void AuthCredentialWithPniResponse_CheckValidContents(byte[] bytes) throws Exception;
 
   public static native void AuthCredentialWithPni_CheckValidContents(byte[] bytes) throws Exception;
+  public static native long AuthCredentialWithPni_GetRefreshDeadlineOrZero(byte[] bytes);
 
   public static native void BackupAuthCredentialPresentation_CheckValidContents(byte[] presentationBytes) throws Exception;
   public static native byte[] BackupAuthCredentialPresentation_GetBackupId(byte[] presentationBytes);
@@ -169,6 +173,11 @@ public final class Native {
   public static native int BackupAuthCredential_GetBackupLevel(byte[] credentialBytes);
   public static native byte[] BackupAuthCredential_PresentDeterministic(byte[] credentialBytes, byte[] serverParamsBytes, byte[] randomness) throws Exception;
 
+  public static native void BackupKeyMaterial_Destroy(long handle);
+  public static native byte[] BackupKeyMaterial_GetBackupId(long material);
+  public static native byte[] BackupKeyMaterial_GetBackupKey(long material);
+  public static native long BackupKeyMaterial_New(byte[] masterKey, byte[] aci);
+
   public static native void CallLinkAuthCredentialPresentation_CheckValidContents(byte[] presentationBytes) throws Exception;
   public static native byte[] CallLinkAuthCredentialPresentation_GetUserId(byte[] presentationBytes);
   public static native void CallLinkAuthCredentialPresentation_Verify(byte[] presentationBytes, long now, byte[] serverParamsBytes, byte[] callLinkParamsBytes) throws Exception;
@@ -187,31 +196,48 @@ public final class Native {
   public static native byte[] CallLinkSecretParams_DeriveFromRootKey(byte[] rootKey);
   public static native byte[] CallLinkSecretParams_GetPublicParams(byte[] paramsBytes);
 
+  public static native CompletableFuture<byte[]> Cdn_DownloadAttachment(long asyncRuntime, long connectionManager, int cdnNumber, String cdnKey, byte[] expectedDigest);
+
   public static native long Cds2ClientState_New(byte[] mrenclave, byte[] attestationMsg, long currentTimestamp) throws Exception;
 
   public static native Map Cds2Metrics_extract(byte[] attestationMsg) throws Exception;
 
   public static native void CdsiLookup_Destroy(long handle);
   public static native CompletableFuture<Object> CdsiLookup_complete(long asyncRuntime, long lookup);
-  public static native CompletableFuture<Long> CdsiLookup_new(long asyncRuntime, long connectionManager, String username, String password, long request);
+  public static native CompletableFuture<Object> CdsiLookup_completeEntries(long asyncRuntime, long lookup);
+  public static native CompletableFuture<Long> CdsiLookup_new(long asyncRuntime, long connectionManager, String username, String password, long request, int timeoutMillis);
   public static native byte[] CdsiLookup_token(long lookup);
+  public static native CompletableFuture<byte[]> Cdsi_CaptureAttestationEvidence(long asyncRuntime, long connectionManager, String username, String password, int timeoutMillis);
 
   public static native CompletableFuture<Object> ChatService_auth_send(long asyncRuntime, long chat, long httpRequest, int timeoutMillis);
   public static native CompletableFuture<Object> ChatService_auth_send_and_debug(long asyncRuntime, long chat, long httpRequest, int timeoutMillis);
+  public static native CompletableFuture<Void> ChatService_auth_submit_captcha_challenge(long asyncRuntime, long chat, String token, String captcha, int timeoutMillis);
+  public static native CompletableFuture<Void> ChatService_auth_submit_push_challenge(long asyncRuntime, long chat, String challenge, int timeoutMillis);
   public static native CompletableFuture<Object> ChatService_connect_auth(long asyncRuntime, long chat);
   public static native CompletableFuture<Object> ChatService_connect_unauth(long asyncRuntime, long chat);
   public static native CompletableFuture ChatService_disconnect_auth(long asyncRuntime, long chat);
   public static native CompletableFuture ChatService_disconnect_unauth(long asyncRuntime, long chat);
   public static native long ChatService_new_auth(long connectionManager, String username, String password, boolean receiveStories);
   public static native long ChatService_new_unauth(long connectionManager);
+  public static native int ChatService_rtt_estimate_millis_auth(long chat);
+  public static native int ChatService_rtt_estimate_millis_unauth(long chat);
   public static native CompletableFuture<Object> ChatService_unauth_send(long asyncRuntime, long chat, long httpRequest, int timeoutMillis);
   public static native CompletableFuture<Object> ChatService_unauth_send_and_debug(long asyncRuntime, long chat, long httpRequest, int timeoutMillis);
 
+  public static native void ClearGlobalProxy();
+
   public static native void ConnectionManager_Destroy(long handle);
   public static native void ConnectionManager_clear_proxy(long connectionManager);
+  public static native byte[] ConnectionManager_export_cooldown_state(long connectionManager);
+  public static native void ConnectionManager_import_cooldown_state(long connectionManager, byte[] state);
   public static native long ConnectionManager_new(int environment, String userAgent);
+  public static native long ConnectionManager_new_custom(int environment, String userAgent, String chatHost, int chatPort, String cdsiHost, int cdsiPort, byte[] cdsiMrEnclave, byte[] rootCertificateDer) throws Exception;
   public static native void ConnectionManager_on_network_change(long connectionManager);
+  public static native CompletableFuture<byte[]> ConnectionManager_run_connectivity_report(long asyncRuntime, long connectionManager, int timeoutMillis);
+  public static native void ConnectionManager_set_chat_network_hint(long connectionManager, int transport, boolean constrained);
   public static native void ConnectionManager_set_proxy(long connectionManager, String host, int port) throws Exception;
+  public static native void ConnectionManager_set_static_dns_entry(long connectionManager, String hostname, String ipAddresses) throws Exception;
+  public static native void ConnectionManager_set_svr3_retry_config(long connectionManager, int maxAttempts, int failureThreshold);
 
   public static native void CreateCallLinkCredentialPresentation_CheckValidContents(byte[] presentationBytes) throws Exception;
   public static native void CreateCallLinkCredentialPresentation_Verify(byte[] presentationBytes, byte[] roomId, long now, byte[] serverParamsBytes, byte[] callLinkParamsBytes) throws Exception;
@@ -347,6 +373,11 @@ public final class Native {
 
   public static native boolean IdentityKey_VerifyAlternateIdentity(long publicKey, long otherIdentity, byte[] signature) throws Exception;
 
+  public static native void IncrementalMacAndDigest_Destroy(long handle);
+  public static native byte[] IncrementalMacAndDigest_Finalize(long mac);
+  public static native long IncrementalMacAndDigest_Initialize(byte[] key, int chunkSize);
+  public static native byte[] IncrementalMacAndDigest_Update(long mac, byte[] bytes, int offset, int length);
+
   public static native int IncrementalMac_CalculateChunkSize(int dataSize);
   public static native void IncrementalMac_Destroy(long handle);
   public static native byte[] IncrementalMac_Finalize(long mac);
@@ -386,27 +417,50 @@ public final class Native {
   public static native void LookupRequest_addE164(long request, String e164);
   public static native void LookupRequest_addPreviousE164(long request, String e164);
   public static native long LookupRequest_new();
+  public static native int LookupRequest_permitCostEstimate(long request);
   public static native void LookupRequest_setReturnAcisWithoutUaks(long request, boolean returnAcisWithoutUaks);
   public static native void LookupRequest_setToken(long request, byte[] token);
 
   public static native void MessageBackupKey_Destroy(long handle);
+  public static native long MessageBackupKey_FromKeyMaterial(long material);
   public static native long MessageBackupKey_New(byte[] masterKey, byte[] aci);
 
   public static native Object MessageBackupValidator_Validate(long key, InputStream firstStream, InputStream secondStream, long len, int purpose) throws Exception;
+  public static native Object MessageBackupValidator_ValidateWithProgress(long key, InputStream firstStream, InputStream secondStream, long len, int purpose, BackupProgressListener progressListener) throws Exception;
+
+  public static native void MessageBackup_WriteEncrypted(InputStream framesSource, long key, OutputStream outputStream) throws Exception;
 
   public static native long Mp4Sanitizer_Sanitize(InputStream input, long len) throws Exception;
 
+  public static native int MultiRecipientMismatchedDevices_Count(long mismatched);
+  public static native void MultiRecipientMismatchedDevices_Destroy(long handle);
+  public static native byte[] MultiRecipientMismatchedDevices_GetExtraDevices(long mismatched, int index) throws Exception;
+  public static native byte[] MultiRecipientMismatchedDevices_GetMissingDevices(long mismatched, int index) throws Exception;
+  public static native byte[] MultiRecipientMismatchedDevices_GetServiceId(long mismatched, int index) throws Exception;
+  public static native byte[] MultiRecipientMismatchedDevices_GetStaleDevices(long mismatched, int index) throws Exception;
+  public static native long MultiRecipientMismatchedDevices_Parse(byte[] body) throws Exception;
+
+  public static native byte[][] MultiRecipientSendResponse_ParseUnregistered(byte[] body) throws Exception;
+
   public static native void NumericFingerprintGenerator_Destroy(long handle);
   public static native String NumericFingerprintGenerator_GetDisplayString(long obj) throws Exception;
   public static native byte[] NumericFingerprintGenerator_GetScannableEncoding(long obj) throws Exception;
   public static native long NumericFingerprintGenerator_New(int iterations, int version, byte[] localIdentifier, byte[] localKey, byte[] remoteIdentifier, byte[] remoteKey) throws Exception;
 
+  public static native String ParseProxyUrl(String url) throws Exception;
+
   public static native byte[] PinHash_AccessKey(long ph);
   public static native void PinHash_Destroy(long handle);
   public static native byte[] PinHash_EncryptionKey(long ph);
   public static native long PinHash_FromSalt(byte[] pin, byte[] salt) throws Exception;
   public static native long PinHash_FromUsernameMrenclave(byte[] pin, String username, byte[] mrenclave) throws Exception;
 
+  public static native void PinMigrationOutcome_Destroy(long handle);
+  public static native String PinMigrationOutcome_GetErrorMessage(long outcome);
+  public static native byte[] PinMigrationOutcome_GetSecret(long outcome);
+  public static native byte[] PinMigrationOutcome_GetShareSet(long outcome);
+  public static native boolean PinMigrationOutcome_IsComplete(long outcome);
+
   public static native String Pin_LocalHash(byte[] pin) throws Exception;
   public static native boolean Pin_VerifyLocalHash(String encodedHash, byte[] pin) throws Exception;
 
@@ -417,6 +471,9 @@ public final class Native {
   public static native byte[] PlaintextContent_GetBody(long obj) throws Exception;
   public static native byte[] PlaintextContent_GetSerialized(long obj) throws Exception;
 
+  public static native byte[] PniSignature_Create(long pniPublicKey, long pniPrivateKey, long aciIdentity) throws Exception;
+  public static native boolean PniSignature_Verify(long aciIdentity, long pniIdentity, byte[] signature) throws Exception;
+
   public static native void PreKeyBundle_Destroy(long handle);
   public static native int PreKeyBundle_GetDeviceId(long obj) throws Exception;
   public static native long PreKeyBundle_GetIdentityKey(long p) throws Exception;
@@ -491,6 +548,12 @@ public final class Native {
   public static native long ReceiptCredential_GetReceiptExpirationTime(byte[] receiptCredential);
   public static native long ReceiptCredential_GetReceiptLevel(byte[] receiptCredential);
 
+  public static native void RegistrationLockFailure_Destroy(long handle);
+  public static native String RegistrationLockFailure_GetSvr2Password(long failure);
+  public static native String RegistrationLockFailure_GetSvr2Username(long failure);
+  public static native long RegistrationLockFailure_GetTimeRemainingMillis(long failure);
+  public static native long RegistrationLockFailure_Parse(byte[] body) throws Exception;
+
   public static native void SanitizedMetadata_Destroy(long handle);
   public static native long SanitizedMetadata_GetDataLen(long sanitized);
   public static native long SanitizedMetadata_GetDataOffset(long sanitized);
@@ -612,6 +675,10 @@ public final class Native {
   public static native long SessionRecord_NewFresh();
   public static native byte[] SessionRecord_Serialize(long obj) throws Exception;
 
+  public static native void SetGlobalProxy(String host, int port) throws Exception;
+
+  public static native void SetGlobalProxyFromUrl(String url) throws Exception;
+
   public static native void SgxClientState_CompleteHandshake(long cli, byte[] handshakeReceived) throws Exception;
   public static native void SgxClientState_Destroy(long handle);
   public static native byte[] SgxClientState_EstablishedRecv(long cli, byte[] receivedCiphertext) throws Exception;
@@ -642,11 +709,17 @@ public final class Native {
 
   public static native long Svr2Client_New(byte[] mrenclave, byte[] attestationMsg, long currentTimestamp) throws Exception;
 
+  public static native CompletableFuture<Long> Svr2MigrateToSvr3(long asyncRuntime, long connectionManager, String pin, int maxTries, String svr2Username, String svr2Password, String svr3Username, String svr3Password);
+
+  public static native CompletableFuture<Long> Svr2ResumeMigrationToSvr3(long asyncRuntime, long connectionManager, long previousOutcome, String pin, int maxTries, String svr2Username, String svr2Password, String svr3Username, String svr3Password);
+
   public static native CompletableFuture<byte[]> Svr3Backup(long asyncRuntime, long connectionManager, byte[] secret, String password, int maxTries, String username, String enclavePassword);
 
   public static native CompletableFuture<byte[]> Svr3Migrate(long asyncRuntime, long connectionManager, byte[] secret, String password, int maxTries, String username, String enclavePassword);
 
-  public static native CompletableFuture<Void> Svr3Remove(long asyncRuntime, long connectionManager, String username, String enclavePassword);
+  public static native CompletableFuture<byte[]> Svr3Query(long asyncRuntime, long connectionManager, String username, String enclavePassword);
+
+  public static native CompletableFuture<byte[]> Svr3Remove(long asyncRuntime, long connectionManager, String username, String enclavePassword);
 
   public static native CompletableFuture<byte[]> Svr3Restore(long asyncRuntime, long connectionManager, String password, byte[] shareSet, String username, String enclavePassword);
 
@@ -655,9 +728,20 @@ public final class Native {
   public static native void TokioAsyncContext_Destroy(long handle);
   public static native void TokioAsyncContext_cancel(long context, long rawCancellationId);
   public static native long TokioAsyncContext_new();
+  public static native void TokioAsyncContext_shutdown(long context, int timeoutMillis);
+
+  public static native byte[] TrafficStats_reset();
+  public static native byte[] TrafficStats_snapshot();
+
+  public static native byte[] TrustedRootCertificateFingerprints();
 
   public static native void UnauthChat_Destroy(long handle);
 
+  public static native void UnauthChatPool_Destroy(long handle);
+  public static native long UnauthChatPool_new(long asyncRuntime, long connectionManager);
+  public static native CompletableFuture<Object> UnauthChatPool_send(long asyncRuntime, long pool, long httpRequest, int timeoutMillis);
+  public static native void UnauthChatPool_shutdown(long pool);
+
   public static native long UnidentifiedSenderMessageContent_Deserialize(byte[] data) throws Exception;
   public static native void UnidentifiedSenderMessageContent_Destroy(long handle);
   public static native int UnidentifiedSenderMessageContent_GetContentHint(long m) throws Exception;
@@ -54,6 +54,7 @@ public final class NativeTesting {
 
   public static native void TESTING_CdsiLookupErrorConvert(String errorDescription) throws Exception;
   public static native CompletableFuture<Object> TESTING_CdsiLookupResponseConvert(long asyncRuntime);
+  public static native CompletableFuture<Object> TESTING_CdsiLookupResponseEntriesConvert(long asyncRuntime);
   public static native byte[] TESTING_ChatRequestGetBody(long request);
   public static native String TESTING_ChatRequestGetHeaderValue(long request, String headerName);
   public static native String TESTING_ChatRequestGetMethod(long request);
@@ -63,8 +64,10 @@ public final class NativeTesting {
   public static native Object TESTING_ChatServiceResponseAndDebugInfoConvert() throws Exception;
   public static native Object TESTING_ChatServiceResponseConvert(boolean bodyPresent) throws Exception;
   public static native void TESTING_ChatService_InjectConnectionInterrupted(long chat);
+  public static native void TESTING_ChatService_InjectIncomingMessages(long chat, int count);
   public static native void TESTING_ChatService_InjectIntentionalDisconnect(long chat);
   public static native void TESTING_ChatService_InjectRawServerRequest(long chat, byte[] bytes);
+  public static native CompletableFuture TESTING_ConnectionManager_PrewarmDns(long asyncRuntime, long connectionManager, String hostname);
   public static native void TESTING_ErrorOnBorrowAsync(Object input);
   public static native CompletableFuture TESTING_ErrorOnBorrowIo(long asyncRuntime, Object input);
   public static native void TESTING_ErrorOnBorrowSync(Object input);
@@ -77,6 +80,10 @@ public final class NativeTesting {
   public static native CompletableFuture<Integer> TESTING_FutureSuccess(long asyncRuntime, int input);
   public static native CompletableFuture<Void> TESTING_FutureThrowsCustomErrorType(long asyncRuntime);
   public static native byte[] TESTING_InputStreamReadIntoZeroLengthSlice(InputStream capsAlphabetInput);
+  public static native void TESTING_Net_AdvanceTime(long asyncRuntime, int millis);
+  public static native int TESTING_Net_LiveConnectionCount(String kind);
+  public static native long TESTING_Net_NewTokioAsyncContextWithPausedTime();
+  public static native void TESTING_Net_SetTimeAutoAdvance(long asyncRuntime, boolean autoAdvance);
   public static native void TESTING_NonSuspendingBackgroundThreadRuntime_Destroy(long handle);
   public static native CompletableFuture TESTING_OnlyCompletesByCancellation(long asyncRuntime);
   public static native String TESTING_OtherTestingHandleType_getValue(long handle);
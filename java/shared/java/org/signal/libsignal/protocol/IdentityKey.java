@@ -60,6 +60,21 @@ public class IdentityKey {
     }
   }
 
+  /**
+   * Verifies a signature from {@link IdentityKeyPair#createPniSignature}, which claims that {@code
+   * pniIdentity} belongs to the same account as this ACI identity key.
+   *
+   * @throws IllegalArgumentException if the signature has the wrong length
+   */
+  public boolean verifyPniSignature(IdentityKey pniIdentity, byte[] signature) {
+    try (NativeHandleGuard guard = new NativeHandleGuard(this.publicKey);
+        NativeHandleGuard pniGuard = new NativeHandleGuard(pniIdentity.publicKey); ) {
+      return filterExceptions(
+          () ->
+              Native.PniSignature_Verify(guard.nativeHandle(), pniGuard.nativeHandle(), signature));
+    }
+  }
+
   @Override
   public boolean equals(Object other) {
     if (other == null) return false;
@@ -71,4 +71,21 @@ public class IdentityKeyPair {
                   publicKey.nativeHandle(), privateKey.nativeHandle(), otherPublic.nativeHandle()));
     }
   }
+
+  /**
+   * Signs {@code aciIdentity} with this PNI identity key pair, as sent in a {@code
+   * PniSignatureMessage}.
+   *
+   * <p>The result can be checked with {@link IdentityKey#verifyPniSignature}.
+   */
+  public byte[] createPniSignature(IdentityKey aciIdentity) {
+    try (NativeHandleGuard publicKey = new NativeHandleGuard(this.publicKey.getPublicKey());
+        NativeHandleGuard privateKey = new NativeHandleGuard(this.privateKey);
+        NativeHandleGuard aciPublic = new NativeHandleGuard(aciIdentity.getPublicKey()); ) {
+      return filterExceptions(
+          () ->
+              Native.PniSignature_Create(
+                  publicKey.nativeHandle(), privateKey.nativeHandle(), aciPublic.nativeHandle()));
+    }
+  }
 }
@@ -10,6 +10,7 @@ import static org.signal.libsignal.internal.FilterExceptions.filterExceptions;
 import java.time.Instant;
 import org.signal.libsignal.internal.Native;
 import org.signal.libsignal.zkgroup.InvalidInputException;
+import org.signal.libsignal.zkgroup.groups.GroupPublicParams;
 import org.signal.libsignal.zkgroup.groups.UuidCiphertext;
 import org.signal.libsignal.zkgroup.internal.ByteArray;
 
@@ -58,6 +59,15 @@ public final class AuthCredentialPresentation extends ByteArray {
     return Instant.ofEpochSecond(Native.AuthCredentialPresentation_GetRedemptionTime(contents));
   }
 
+  /**
+   * Returns the value of the {@code Authorization} header for a request to the group storage
+   * service for the group with the given public params.
+   */
+  public String getHttpAuthorization(GroupPublicParams groupPublicParams) {
+    return Native.AuthCredentialPresentation_GetHttpAuthorization(
+        contents, groupPublicParams.getInternalContentsForJNI());
+  }
+
   public Version getVersion() {
     byte version = this.contents[0];
     final Version[] values = Version.values();
@@ -7,6 +7,7 @@ package org.signal.libsignal.zkgroup.auth;
 
 import static org.signal.libsignal.internal.FilterExceptions.filterExceptions;
 
+import java.time.Instant;
 import org.signal.libsignal.internal.Native;
 import org.signal.libsignal.zkgroup.InvalidInputException;
 import org.signal.libsignal.zkgroup.internal.ByteArray;
@@ -18,4 +19,16 @@ public final class AuthCredentialWithPni extends ByteArray {
         InvalidInputException.class,
         () -> Native.AuthCredentialWithPni_CheckValidContents(contents));
   }
+
+  /**
+   * Returns when this credential stops being accepted, if it's in an old format that has to be
+   * replaced by fetching a new credential from the server.
+   *
+   * @return the deadline for fetching a new credential, or {@code null} if this one is already in
+   *     the current format
+   */
+  public Instant getRefreshDeadline() {
+    long deadline = Native.AuthCredentialWithPni_GetRefreshDeadlineOrZero(contents);
+    return deadline != 0 ? Instant.ofEpochSecond(deadline) : null;
+  }
 }
@@ -0,0 +1,34 @@
+//
+// Copyright 2024 Signal Messenger, LLC.
+// SPDX-License-Identifier: AGPL-3.0-only
+//
+
+package org.signal.libsignal.internal;
+
+import static org.junit.Assert.*;
+
+import java.util.OptionalInt;
+import org.junit.Test;
+
+public class ErrorCodesTest {
+  // Values from rust/bridge/shared/types/src/error_code.rs.
+  private static final int INVALID_ARGUMENT = 2;
+  private static final int UNEXPECTED_PANIC = 9;
+
+  @Test
+  public void testErrorsFromRustHaveCodes() {
+    IllegalArgumentException badArgument =
+        assertThrows(
+            IllegalArgumentException.class, () -> NativeTesting.TESTING_ErrorOnBorrowSync(null));
+    assertEquals(OptionalInt.of(INVALID_ARGUMENT), ErrorCodes.of(badArgument));
+
+    AssertionError panic =
+        assertThrows(AssertionError.class, () -> NativeTesting.TESTING_PanicOnBorrowSync(null));
+    assertEquals(OptionalInt.of(UNEXPECTED_PANIC), ErrorCodes.of(panic));
+  }
+
+  @Test
+  public void testOtherExceptionsHaveNoCode() {
+    assertEquals(OptionalInt.empty(), ErrorCodes.of(new IllegalArgumentException()));
+  }
+}
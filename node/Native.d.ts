@@ -21,6 +21,17 @@ interface LookupResponseEntry {
   readonly pni: string | undefined;
 }
 
+interface LookupResponseEntries {
+  entries: LookupEntry[];
+  debugPermitsUsed: number;
+}
+
+interface LookupEntry {
+  readonly e164: string;
+  readonly aci: Buffer | null;
+  readonly pni: Buffer | null;
+}
+
 interface ChatResponse {
   status: number;
   message: string | undefined;
@@ -32,6 +43,9 @@ interface ChatServiceDebugInfo {
   ipType: number;
   durationMillis: number;
   connectionInfo: string;
+  routeType: number;
+  remoteIp?: string;
+  remotePort?: number;
 }
 
 interface ResponseAndDebugInfo {
@@ -113,6 +127,15 @@ export abstract class InputStream {
 
 export abstract class SyncInputStream extends Buffer {}
 
+export abstract class OutputStream {
+  _write(buf: Buffer): Promise<void>;
+  _flush(): Promise<void>;
+}
+
+export abstract class BackupProgressListener {
+  _on_progress(framesRead: number, bytesRead: number, totalBytes: number): void;
+}
+
 export abstract class ChatListener {
   _incoming_message(
     envelope: Buffer,
@@ -133,8 +156,10 @@ type Wrapper<T> = Readonly<{
 }>;
 
 interface MessageBackupValidationOutcome {
+  errorKind: number | null;
   errorMessage: string | null;
   unknownFieldMessages: Array<string>;
+  unknownFieldsJson: string;
 }
 
 // eslint-disable-next-line @typescript-eslint/no-unused-vars
@@ -144,15 +169,18 @@ export function registerErrors(errorsModule: Record<string, unknown>): void;
 
 export const enum LogLevel { Error = 1, Warn, Info, Debug, Trace }
 export function AccountEntropyPool_Generate(): string;
+export function AddSupplementalRootCertificate(der: Buffer): void;
 export function Aes256GcmSiv_Decrypt(aesGcmSiv: Wrapper<Aes256GcmSiv>, ctext: Buffer, nonce: Buffer, associatedData: Buffer): Buffer;
 export function Aes256GcmSiv_Encrypt(aesGcmSivObj: Wrapper<Aes256GcmSiv>, ptext: Buffer, nonce: Buffer, associatedData: Buffer): Buffer;
 export function Aes256GcmSiv_New(key: Buffer): Aes256GcmSiv;
 export function AuthCredentialPresentation_CheckValidContents(presentationBytes: Buffer): void;
+export function AuthCredentialPresentation_GetHttpAuthorization(presentationBytes: Buffer, groupPublicParams: Serialized<GroupPublicParams>): string;
 export function AuthCredentialPresentation_GetPniCiphertext(presentationBytes: Buffer): Buffer | null;
 export function AuthCredentialPresentation_GetRedemptionTime(presentationBytes: Buffer): Timestamp;
 export function AuthCredentialPresentation_GetUuidCiphertext(presentationBytes: Buffer): Serialized<UuidCiphertext>;
 export function AuthCredentialWithPniResponse_CheckValidContents(bytes: Buffer): void;
 export function AuthCredentialWithPni_CheckValidContents(bytes: Buffer): void;
+export function AuthCredentialWithPni_GetRefreshDeadlineOrZero(bytes: Buffer): Timestamp;
 export function BackupAuthCredentialPresentation_CheckValidContents(presentationBytes: Buffer): void;
 export function BackupAuthCredentialPresentation_GetBackupId(presentationBytes: Buffer): Buffer;
 export function BackupAuthCredentialPresentation_GetBackupLevel(presentationBytes: Buffer): number;
@@ -168,6 +196,9 @@ export function BackupAuthCredential_CheckValidContents(paramsBytes: Buffer): vo
 export function BackupAuthCredential_GetBackupId(credentialBytes: Buffer): Buffer;
 export function BackupAuthCredential_GetBackupLevel(credentialBytes: Buffer): number;
 export function BackupAuthCredential_PresentDeterministic(credentialBytes: Buffer, serverParamsBytes: Buffer, randomness: Buffer): Buffer;
+export function BackupKeyMaterial_GetBackupId(material: Wrapper<BackupKeyMaterial>): Buffer;
+export function BackupKeyMaterial_GetBackupKey(material: Wrapper<BackupKeyMaterial>): Buffer;
+export function BackupKeyMaterial_New(masterKey: Buffer, aci: Buffer): BackupKeyMaterial;
 export function CallLinkAuthCredentialPresentation_CheckValidContents(presentationBytes: Buffer): void;
 export function CallLinkAuthCredentialPresentation_GetUserId(presentationBytes: Buffer): Serialized<UuidCiphertext>;
 export function CallLinkAuthCredentialPresentation_Verify(presentationBytes: Buffer, now: Timestamp, serverParamsBytes: Buffer, callLinkParamsBytes: Buffer): void;
@@ -181,33 +212,47 @@ export function CallLinkSecretParams_CheckValidContents(paramsBytes: Buffer): vo
 export function CallLinkSecretParams_DecryptUserId(paramsBytes: Buffer, userId: Serialized<UuidCiphertext>): Buffer;
 export function CallLinkSecretParams_DeriveFromRootKey(rootKey: Buffer): Buffer;
 export function CallLinkSecretParams_GetPublicParams(paramsBytes: Buffer): Buffer;
+export function Cdn_DownloadAttachment(asyncRuntime: Wrapper<TokioAsyncContext>, connectionManager: Wrapper<ConnectionManager>, cdnNumber: number, cdnKey: string, expectedDigest: Buffer): Promise<Buffer>;
 export function Cds2ClientState_New(mrenclave: Buffer, attestationMsg: Buffer, currentTimestamp: Timestamp): SgxClientState;
 export function CdsiLookup_complete(asyncRuntime: Wrapper<TokioAsyncContext>, lookup: Wrapper<CdsiLookup>): Promise<LookupResponse>;
-export function CdsiLookup_new(asyncRuntime: Wrapper<TokioAsyncContext>, connectionManager: Wrapper<ConnectionManager>, username: string, password: string, request: Wrapper<LookupRequest>): Promise<CdsiLookup>;
+export function CdsiLookup_completeEntries(asyncRuntime: Wrapper<TokioAsyncContext>, lookup: Wrapper<CdsiLookup>): Promise<LookupResponseEntries>;
+export function CdsiLookup_new(asyncRuntime: Wrapper<TokioAsyncContext>, connectionManager: Wrapper<ConnectionManager>, username: string, password: string, request: Wrapper<LookupRequest>, timeoutMillis: number): Promise<CdsiLookup>;
 export function CdsiLookup_token(lookup: Wrapper<CdsiLookup>): Buffer;
+export function Cdsi_CaptureAttestationEvidence(asyncRuntime: Wrapper<TokioAsyncContext>, connectionManager: Wrapper<ConnectionManager>, username: string, password: string, timeoutMillis: number): Promise<Buffer>;
 export function ChatService_SetListenerAuth(runtime: Wrapper<TokioAsyncContext>, chat: Wrapper<AuthChat>, makeListener: MakeChatListener | null): void;
 export function ChatService_SetListenerUnauth(runtime: Wrapper<TokioAsyncContext>, chat: Wrapper<UnauthChat>, makeListener: MakeChatListener | null): void;
 export function ChatService_auth_send(asyncRuntime: Wrapper<TokioAsyncContext>, chat: Wrapper<AuthChat>, httpRequest: Wrapper<HttpRequest>, timeoutMillis: number): Promise<ChatResponse>;
 export function ChatService_auth_send_and_debug(asyncRuntime: Wrapper<TokioAsyncContext>, chat: Wrapper<AuthChat>, httpRequest: Wrapper<HttpRequest>, timeoutMillis: number): Promise<ResponseAndDebugInfo>;
+export function ChatService_auth_submit_captcha_challenge(asyncRuntime: Wrapper<TokioAsyncContext>, chat: Wrapper<AuthChat>, token: string, captcha: string, timeoutMillis: number): Promise<void>;
+export function ChatService_auth_submit_push_challenge(asyncRuntime: Wrapper<TokioAsyncContext>, chat: Wrapper<AuthChat>, challenge: string, timeoutMillis: number): Promise<void>;
 export function ChatService_connect_auth(asyncRuntime: Wrapper<TokioAsyncContext>, chat: Wrapper<AuthChat>): Promise<ChatServiceDebugInfo>;
 export function ChatService_connect_unauth(asyncRuntime: Wrapper<TokioAsyncContext>, chat: Wrapper<UnauthChat>): Promise<ChatServiceDebugInfo>;
 export function ChatService_disconnect_auth(asyncRuntime: Wrapper<TokioAsyncContext>, chat: Wrapper<AuthChat>): Promise<void>;
 export function ChatService_disconnect_unauth(asyncRuntime: Wrapper<TokioAsyncContext>, chat: Wrapper<UnauthChat>): Promise<void>;
 export function ChatService_new_auth(connectionManager: Wrapper<ConnectionManager>, username: string, password: string, receiveStories: boolean): AuthChat;
 export function ChatService_new_unauth(connectionManager: Wrapper<ConnectionManager>): UnauthChat;
+export function ChatService_rtt_estimate_millis_auth(chat: Wrapper<AuthChat>): number | null;
+export function ChatService_rtt_estimate_millis_unauth(chat: Wrapper<UnauthChat>): number | null;
 export function ChatService_unauth_send(asyncRuntime: Wrapper<TokioAsyncContext>, chat: Wrapper<UnauthChat>, httpRequest: Wrapper<HttpRequest>, timeoutMillis: number): Promise<ChatResponse>;
 export function ChatService_unauth_send_and_debug(asyncRuntime: Wrapper<TokioAsyncContext>, chat: Wrapper<UnauthChat>, httpRequest: Wrapper<HttpRequest>, timeoutMillis: number): Promise<ResponseAndDebugInfo>;
 export function CiphertextMessage_FromPlaintextContent(m: Wrapper<PlaintextContent>): CiphertextMessage;
 export function CiphertextMessage_Serialize(obj: Wrapper<CiphertextMessage>): Buffer;
 export function CiphertextMessage_Type(msg: Wrapper<CiphertextMessage>): number;
+export function ClearGlobalProxy(): void;
 export function ComparableBackup_GetComparableString(backup: Wrapper<ComparableBackup>): string;
 export function ComparableBackup_GetUnknownFields(backup: Wrapper<ComparableBackup>): string[];
 export function ComparableBackup_ReadUnencrypted(stream: InputStream, len: bigint, purpose: number): Promise<ComparableBackup>;
 export function ConnectionManager_clear_proxy(connectionManager: Wrapper<ConnectionManager>): void;
+export function ConnectionManager_export_cooldown_state(connectionManager: Wrapper<ConnectionManager>): Buffer;
+export function ConnectionManager_import_cooldown_state(connectionManager: Wrapper<ConnectionManager>, state: Buffer): void;
 export function ConnectionManager_new(environment: number, userAgent: string): ConnectionManager;
+export function ConnectionManager_new_custom(environment: number, userAgent: string, chatHost: string, chatPort: number, cdsiHost: string, cdsiPort: number, cdsiMrEnclave: Buffer, rootCertificateDer: Buffer): ConnectionManager;
 export function ConnectionManager_on_network_change(connectionManager: Wrapper<ConnectionManager>): void;
+export function ConnectionManager_run_connectivity_report(asyncRuntime: Wrapper<TokioAsyncContext>, connectionManager: Wrapper<ConnectionManager>, timeoutMillis: number): Promise<Buffer>;
 export function ConnectionManager_set_ipv6_enabled(connectionManager: Wrapper<ConnectionManager>, ipv6Enabled: boolean): void;
 export function ConnectionManager_set_proxy(connectionManager: Wrapper<ConnectionManager>, host: string, port: number): void;
+export function ConnectionManager_set_static_dns_entry(connectionManager: Wrapper<ConnectionManager>, hostname: string, ipAddresses: string): void;
+export function ConnectionManager_set_svr3_retry_config(connectionManager: Wrapper<ConnectionManager>, maxAttempts: number, failureThreshold: number): void;
 export function CreateCallLinkCredentialPresentation_CheckValidContents(presentationBytes: Buffer): void;
 export function CreateCallLinkCredentialPresentation_Verify(presentationBytes: Buffer, roomId: Buffer, now: Timestamp, serverParamsBytes: Buffer, callLinkParamsBytes: Buffer): void;
 export function CreateCallLinkCredentialRequestContext_CheckValidContents(contextBytes: Buffer): void;
@@ -282,6 +327,9 @@ export function IdentityKeyPair_Deserialize(buffer: Buffer): {publicKey:PublicKe
 export function IdentityKeyPair_Serialize(publicKey: Wrapper<PublicKey>, privateKey: Wrapper<PrivateKey>): Buffer;
 export function IdentityKeyPair_SignAlternateIdentity(publicKey: Wrapper<PublicKey>, privateKey: Wrapper<PrivateKey>, otherIdentity: Wrapper<PublicKey>): Buffer;
 export function IdentityKey_VerifyAlternateIdentity(publicKey: Wrapper<PublicKey>, otherIdentity: Wrapper<PublicKey>, signature: Buffer): boolean;
+export function IncrementalMacAndDigest_Finalize(mac: Wrapper<IncrementalMacAndDigest>): Buffer;
+export function IncrementalMacAndDigest_Initialize(key: Buffer, chunkSize: number): IncrementalMacAndDigest;
+export function IncrementalMacAndDigest_Update(mac: Wrapper<IncrementalMacAndDigest>, bytes: Buffer, offset: number, length: number): Buffer;
 export function IncrementalMac_CalculateChunkSize(dataSize: number): number;
 export function IncrementalMac_Finalize(mac: Wrapper<IncrementalMac>): Buffer;
 export function IncrementalMac_Initialize(key: Buffer, chunkSize: number): IncrementalMac;
@@ -307,16 +355,34 @@ export function LookupRequest_addAciAndAccessKey(request: Wrapper<LookupRequest>
 export function LookupRequest_addE164(request: Wrapper<LookupRequest>, e164: string): void;
 export function LookupRequest_addPreviousE164(request: Wrapper<LookupRequest>, e164: string): void;
 export function LookupRequest_new(): LookupRequest;
+export function LookupRequest_permitCostEstimate(request: Wrapper<LookupRequest>): number;
 export function LookupRequest_setReturnAcisWithoutUaks(request: Wrapper<LookupRequest>, returnAcisWithoutUaks: boolean): void;
 export function LookupRequest_setToken(request: Wrapper<LookupRequest>, token: Buffer): void;
+export function MessageBackupKey_FromKeyMaterial(material: Wrapper<BackupKeyMaterial>): MessageBackupKey;
 export function MessageBackupKey_New(masterKey: Buffer, aci: Buffer): MessageBackupKey;
 export function MessageBackupValidator_Validate(key: Wrapper<MessageBackupKey>, firstStream: InputStream, secondStream: InputStream, len: bigint, purpose: number): Promise<MessageBackupValidationOutcome>;
+export function MessageBackupValidator_ValidateWithProgress(key: Wrapper<MessageBackupKey>, firstStream: InputStream, secondStream: InputStream, len: bigint, purpose: number, progressListener: BackupProgressListener): Promise<MessageBackupValidationOutcome>;
+export function MessageBackup_WriteEncrypted(framesSource: InputStream, key: Wrapper<MessageBackupKey>, outputStream: OutputStream): Promise<void>;
 export function MinidumpToJSONString(buffer: Buffer): string;
 export function Mp4Sanitizer_Sanitize(input: InputStream, len: bigint): Promise<SanitizedMetadata>;
+export function MultiRecipientMismatchedDevices_Count(mismatched: Wrapper<MultiRecipientMismatchedDevices>): number;
+export function MultiRecipientMismatchedDevices_GetExtraDevices(mismatched: Wrapper<MultiRecipientMismatchedDevices>, index: number): Buffer;
+export function MultiRecipientMismatchedDevices_GetMissingDevices(mismatched: Wrapper<MultiRecipientMismatchedDevices>, index: number): Buffer;
+export function MultiRecipientMismatchedDevices_GetServiceId(mismatched: Wrapper<MultiRecipientMismatchedDevices>, index: number): Buffer;
+export function MultiRecipientMismatchedDevices_GetStaleDevices(mismatched: Wrapper<MultiRecipientMismatchedDevices>, index: number): Buffer;
+export function MultiRecipientMismatchedDevices_Parse(body: Buffer): MultiRecipientMismatchedDevices;
+export function MultiRecipientSendResponse_ParseUnregistered(body: Buffer): Buffer[];
+export function ParseProxyUrl(url: string): string;
+export function PinMigrationOutcome_GetErrorMessage(outcome: Wrapper<PinMigrationOutcome>): string | null;
+export function PinMigrationOutcome_GetSecret(outcome: Wrapper<PinMigrationOutcome>): Buffer;
+export function PinMigrationOutcome_GetShareSet(outcome: Wrapper<PinMigrationOutcome>): Buffer;
+export function PinMigrationOutcome_IsComplete(outcome: Wrapper<PinMigrationOutcome>): boolean;
 export function PlaintextContent_Deserialize(data: Buffer): PlaintextContent;
 export function PlaintextContent_FromDecryptionErrorMessage(m: Wrapper<DecryptionErrorMessage>): PlaintextContent;
 export function PlaintextContent_GetBody(obj: Wrapper<PlaintextContent>): Buffer;
 export function PlaintextContent_Serialize(obj: Wrapper<PlaintextContent>): Buffer;
+export function PniSignature_Create(pniPublicKey: Wrapper<PublicKey>, pniPrivateKey: Wrapper<PrivateKey>, aciIdentity: Wrapper<PublicKey>): Buffer;
+export function PniSignature_Verify(aciIdentity: Wrapper<PublicKey>, pniIdentity: Wrapper<PublicKey>, signature: Buffer): boolean;
 export function PreKeyBundle_GetDeviceId(obj: Wrapper<PreKeyBundle>): number;
 export function PreKeyBundle_GetIdentityKey(p: Wrapper<PreKeyBundle>): PublicKey;
 export function PreKeyBundle_GetKyberPreKeyId(obj: Wrapper<PreKeyBundle>): number | null;
@@ -380,6 +446,10 @@ export function ReceiptCredentialResponse_CheckValidContents(buffer: Buffer): vo
 export function ReceiptCredential_CheckValidContents(buffer: Buffer): void;
 export function ReceiptCredential_GetReceiptExpirationTime(receiptCredential: Serialized<ReceiptCredential>): Timestamp;
 export function ReceiptCredential_GetReceiptLevel(receiptCredential: Serialized<ReceiptCredential>): bigint;
+export function RegistrationLockFailure_GetSvr2Password(failure: Wrapper<RegistrationLockFailure>): string | null;
+export function RegistrationLockFailure_GetSvr2Username(failure: Wrapper<RegistrationLockFailure>): string | null;
+export function RegistrationLockFailure_GetTimeRemainingMillis(failure: Wrapper<RegistrationLockFailure>): bigint;
+export function RegistrationLockFailure_Parse(body: Buffer): RegistrationLockFailure;
 export function SanitizedMetadata_GetDataLen(sanitized: Wrapper<SanitizedMetadata>): bigint;
 export function SanitizedMetadata_GetDataOffset(sanitized: Wrapper<SanitizedMetadata>): bigint;
 export function SanitizedMetadata_GetMetadata(sanitized: Wrapper<SanitizedMetadata>): Buffer;
@@ -472,6 +542,8 @@ export function SessionRecord_GetLocalRegistrationId(obj: Wrapper<SessionRecord>
 export function SessionRecord_GetRemoteRegistrationId(obj: Wrapper<SessionRecord>): number;
 export function SessionRecord_HasUsableSenderChain(s: Wrapper<SessionRecord>, now: Timestamp): boolean;
 export function SessionRecord_Serialize(obj: Wrapper<SessionRecord>): Buffer;
+export function SetGlobalProxy(host: string, port: number): void;
+export function SetGlobalProxyFromUrl(url: string): void;
 export function SgxClientState_CompleteHandshake(cli: Wrapper<SgxClientState>, handshakeReceived: Buffer): void;
 export function SgxClientState_EstablishedRecv(cli: Wrapper<SgxClientState>, receivedCiphertext: Buffer): Buffer;
 export function SgxClientState_EstablishedSend(cli: Wrapper<SgxClientState>, plaintextToSend: Buffer): Buffer;
@@ -492,13 +564,17 @@ export function SignedPreKeyRecord_GetSignature(obj: Wrapper<SignedPreKeyRecord>
 export function SignedPreKeyRecord_GetTimestamp(obj: Wrapper<SignedPreKeyRecord>): Timestamp;
 export function SignedPreKeyRecord_New(id: number, timestamp: Timestamp, pubKey: Wrapper<PublicKey>, privKey: Wrapper<PrivateKey>, signature: Buffer): SignedPreKeyRecord;
 export function SignedPreKeyRecord_Serialize(obj: Wrapper<SignedPreKeyRecord>): Buffer;
+export function Svr2MigrateToSvr3(asyncRuntime: Wrapper<TokioAsyncContext>, connectionManager: Wrapper<ConnectionManager>, pin: string, maxTries: number, svr2Username: string, svr2Password: string, svr3Username: string, svr3Password: string): Promise<PinMigrationOutcome>;
+export function Svr2ResumeMigrationToSvr3(asyncRuntime: Wrapper<TokioAsyncContext>, connectionManager: Wrapper<ConnectionManager>, previousOutcome: Wrapper<PinMigrationOutcome>, pin: string, maxTries: number, svr2Username: string, svr2Password: string, svr3Username: string, svr3Password: string): Promise<PinMigrationOutcome>;
 export function Svr3Backup(asyncRuntime: Wrapper<TokioAsyncContext>, connectionManager: Wrapper<ConnectionManager>, secret: Buffer, password: string, maxTries: number, username: string, enclavePassword: string): Promise<Buffer>;
-export function Svr3Remove(asyncRuntime: Wrapper<TokioAsyncContext>, connectionManager: Wrapper<ConnectionManager>, username: string, enclavePassword: string): Promise<void>;
+export function Svr3Query(asyncRuntime: Wrapper<TokioAsyncContext>, connectionManager: Wrapper<ConnectionManager>, username: string, enclavePassword: string): Promise<Buffer>;
+export function Svr3Remove(asyncRuntime: Wrapper<TokioAsyncContext>, connectionManager: Wrapper<ConnectionManager>, username: string, enclavePassword: string): Promise<Buffer>;
 export function Svr3Restore(asyncRuntime: Wrapper<TokioAsyncContext>, connectionManager: Wrapper<ConnectionManager>, password: string, shareSet: Buffer, username: string, enclavePassword: string): Promise<Buffer>;
 export function TESTING_CdsiLookupErrorConvert(errorDescription: string): void;
 export function TESTING_CdsiLookupResponseConvert(asyncRuntime: Wrapper<TokioAsyncContext>): Promise<LookupResponse>;
+export function TESTING_CdsiLookupResponseEntriesConvert(asyncRuntime: Wrapper<TokioAsyncContext>): Promise<LookupResponseEntries>;
 export function TESTING_ChatRequestGetBody(request: Wrapper<HttpRequest>): Buffer;
-export function TESTING_ChatRequestGetHeaderValue(request: Wrapper<HttpRequest>, headerName: string): string;
+export function TESTING_ChatRequestGetHeaderValue(request: Wrapper<HttpRequest>, headerName: string): string | null;
 export function TESTING_ChatRequestGetMethod(request: Wrapper<HttpRequest>): string;
 export function TESTING_ChatRequestGetPath(request: Wrapper<HttpRequest>): string;
 export function TESTING_ChatServiceDebugInfoConvert(): ChatServiceDebugInfo;
@@ -506,8 +582,10 @@ export function TESTING_ChatServiceErrorConvert(errorDescription: string): void;
 export function TESTING_ChatServiceResponseAndDebugInfoConvert(): ResponseAndDebugInfo;
 export function TESTING_ChatServiceResponseConvert(bodyPresent: boolean): ChatResponse;
 export function TESTING_ChatService_InjectConnectionInterrupted(chat: Wrapper<AuthChat>): void;
+export function TESTING_ChatService_InjectIncomingMessages(chat: Wrapper<AuthChat>, count: number): void;
 export function TESTING_ChatService_InjectIntentionalDisconnect(chat: Wrapper<AuthChat>): void;
 export function TESTING_ChatService_InjectRawServerRequest(chat: Wrapper<AuthChat>, bytes: Buffer): void;
+export function TESTING_ConnectionManager_PrewarmDns(asyncRuntime: Wrapper<TokioAsyncContext>, connectionManager: Wrapper<ConnectionManager>, hostname: string): Promise<void>;
 export function TESTING_ErrorOnBorrowAsync(_input: null): Promise<void>;
 export function TESTING_ErrorOnBorrowIo(asyncRuntime: Wrapper<NonSuspendingBackgroundThreadRuntime>, _input: null): Promise<void>;
 export function TESTING_ErrorOnBorrowSync(_input: null): void;
@@ -519,6 +597,10 @@ export function TESTING_FutureProducesOtherPointerType(asyncRuntime: Wrapper<Non
 export function TESTING_FutureProducesPointerType(asyncRuntime: Wrapper<NonSuspendingBackgroundThreadRuntime>, input: number): Promise<TestingHandleType>;
 export function TESTING_FutureSuccess(asyncRuntime: Wrapper<NonSuspendingBackgroundThreadRuntime>, input: number): Promise<number>;
 export function TESTING_InputStreamReadIntoZeroLengthSlice(capsAlphabetInput: InputStream): Promise<Buffer>;
+export function TESTING_Net_AdvanceTime(asyncRuntime: Wrapper<TokioAsyncContext>, millis: number): void;
+export function TESTING_Net_LiveConnectionCount(kind: string): number;
+export function TESTING_Net_NewTokioAsyncContextWithPausedTime(): TokioAsyncContext;
+export function TESTING_Net_SetTimeAutoAdvance(asyncRuntime: Wrapper<TokioAsyncContext>, autoAdvance: boolean): void;
 export function TESTING_NonSuspendingBackgroundThreadRuntime_New(): NonSuspendingBackgroundThreadRuntime;
 export function TESTING_OnlyCompletesByCancellation(asyncRuntime: Wrapper<TokioAsyncContext>): Promise<void>;
 export function TESTING_OtherTestingHandleType_getValue(handle: Wrapper<OtherTestingHandleType>): string;
@@ -540,6 +622,13 @@ export function TESTING_ServerMessageAck_Create(): ServerMessageAck;
 export function TESTING_TestingHandleType_getValue(handle: Wrapper<TestingHandleType>): number;
 export function TokioAsyncContext_cancel(context: Wrapper<TokioAsyncContext>, rawCancellationId: bigint): void;
 export function TokioAsyncContext_new(): TokioAsyncContext;
+export function TokioAsyncContext_shutdown(context: Wrapper<TokioAsyncContext>, timeoutMillis: number): void;
+export function TrafficStats_reset(): Buffer;
+export function TrafficStats_snapshot(): Buffer;
+export function TrustedRootCertificateFingerprints(): Buffer;
+export function UnauthChatPool_new(asyncRuntime: Wrapper<TokioAsyncContext>, connectionManager: Wrapper<ConnectionManager>): UnauthChatPool;
+export function UnauthChatPool_send(asyncRuntime: Wrapper<TokioAsyncContext>, pool: Wrapper<UnauthChatPool>, httpRequest: Wrapper<HttpRequest>, timeoutMillis: number): Promise<ChatResponse>;
+export function UnauthChatPool_shutdown(pool: Wrapper<UnauthChatPool>): void;
 export function UnidentifiedSenderMessageContent_Deserialize(data: Buffer): UnidentifiedSenderMessageContent;
 export function UnidentifiedSenderMessageContent_GetContentHint(m: Wrapper<UnidentifiedSenderMessageContent>): number;
 export function UnidentifiedSenderMessageContent_GetContents(obj: Wrapper<UnidentifiedSenderMessageContent>): Buffer;
@@ -564,6 +653,7 @@ export function initLogger(maxLevel: LogLevel, callback: (level: LogLevel, targe
 export function test_only_fn_returns_123(): number;
 interface Aes256GcmSiv { readonly __type: unique symbol; }
 interface AuthChat { readonly __type: unique symbol; }
+interface BackupKeyMaterial { readonly __type: unique symbol; }
 interface CdsiLookup { readonly __type: unique symbol; }
 interface CiphertextMessage { readonly __type: unique symbol; }
 interface ComparableBackup { readonly __type: unique symbol; }
@@ -579,14 +669,17 @@ interface GroupSecretParams { readonly __type: unique symbol; }
 interface HsmEnclaveClient { readonly __type: unique symbol; }
 interface HttpRequest { readonly __type: unique symbol; }
 interface IncrementalMac { readonly __type: unique symbol; }
+interface IncrementalMacAndDigest { readonly __type: unique symbol; }
 interface KyberKeyPair { readonly __type: unique symbol; }
 interface KyberPreKeyRecord { readonly __type: unique symbol; }
 interface KyberPublicKey { readonly __type: unique symbol; }
 interface KyberSecretKey { readonly __type: unique symbol; }
 interface LookupRequest { readonly __type: unique symbol; }
 interface MessageBackupKey { readonly __type: unique symbol; }
+interface MultiRecipientMismatchedDevices { readonly __type: unique symbol; }
 interface NonSuspendingBackgroundThreadRuntime { readonly __type: unique symbol; }
 interface OtherTestingHandleType { readonly __type: unique symbol; }
+interface PinMigrationOutcome { readonly __type: unique symbol; }
 interface PlaintextContent { readonly __type: unique symbol; }
 interface PreKeyBundle { readonly __type: unique symbol; }
 interface PreKeyRecord { readonly __type: unique symbol; }
@@ -604,6 +697,7 @@ interface ReceiptCredentialPresentation { readonly __type: unique symbol; }
 interface ReceiptCredentialRequest { readonly __type: unique symbol; }
 interface ReceiptCredentialRequestContext { readonly __type: unique symbol; }
 interface ReceiptCredentialResponse { readonly __type: unique symbol; }
+interface RegistrationLockFailure { readonly __type: unique symbol; }
 interface SanitizedMetadata { readonly __type: unique symbol; }
 interface SealedSenderDecryptionResult { readonly __type: unique symbol; }
 interface SenderCertificate { readonly __type: unique symbol; }
@@ -621,6 +715,7 @@ interface SignedPreKeyRecord { readonly __type: unique symbol; }
 interface TestingHandleType { readonly __type: unique symbol; }
 interface TokioAsyncContext { readonly __type: unique symbol; }
 interface UnauthChat { readonly __type: unique symbol; }
+interface UnauthChatPool { readonly __type: unique symbol; }
 interface UnidentifiedSenderMessageContent { readonly __type: unique symbol; }
 interface UuidCiphertext { readonly __type: unique symbol; }
 interface ValidatingMac { readonly __type: unique symbol; }
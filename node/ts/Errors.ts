@@ -47,6 +47,9 @@ export enum ErrorCode {
   SvrRequestFailed,
   SvrRestoreFailed,
 
+  AttestationExpired,
+  AttestationMeasurementMismatch,
+
   ChatServiceInactive,
   AppExpired,
   DeviceDelinked,
@@ -58,6 +61,15 @@ export enum ErrorCode {
 
 export class LibSignalErrorBase extends Error {
   public readonly code: ErrorCode;
+  /**
+   * A number identifying the most specific cause of this error, even when {@link code} is more
+   * general.
+   *
+   * Stable codes keep their number and meaning once released, and numbers are never reused, but
+   * new codes may be added at any time. See `rust/bridge/shared/types/src/error_code.rs` for the
+   * full list. Undefined for errors that don't come from the Rust library.
+   */
+  public readonly stableCode?: number;
   public readonly operation: string;
   readonly _addr?: string | Native.ProtocolAddress;
 
@@ -65,7 +77,8 @@ export class LibSignalErrorBase extends Error {
     message: string,
     name: keyof typeof ErrorCode | undefined,
     operation: string,
-    extraProps?: Record<string, unknown>
+    extraProps?: Record<string, unknown>,
+    stableCode?: number
   ) {
     super(message);
     // Include the dynamic check for `name in ErrorCode` in case there's a bug in the Rust code.
@@ -77,6 +90,7 @@ export class LibSignalErrorBase extends Error {
       this.code = ErrorCode.Generic;
     }
     this.operation = operation;
+    this.stableCode = stableCode;
     if (extraProps !== undefined) {
       Object.assign(this, extraProps);
     }
@@ -265,6 +279,14 @@ export type SvrRestoreFailedError = LibSignalErrorCommon & {
   readonly triesRemaining: number;
 };
 
+export type AttestationExpiredError = LibSignalErrorCommon & {
+  code: ErrorCode.AttestationExpired;
+};
+
+export type AttestationMeasurementMismatchError = LibSignalErrorCommon & {
+  code: ErrorCode.AttestationMeasurementMismatch;
+};
+
 export type BackupValidationError = LibSignalErrorCommon & {
   code: ErrorCode.BackupValidation;
   readonly unknownFields: ReadonlyArray<string>;
@@ -305,6 +327,8 @@ export type LibSignalError =
   | SvrDataMissingError
   | SvrRestoreFailedError
   | SvrRequestFailedError
+  | AttestationExpiredError
+  | AttestationMeasurementMismatchError
   | UnsupportedMediaInputError
   | ChatServiceInactive
   | AppExpiredError
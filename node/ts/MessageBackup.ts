@@ -9,16 +9,44 @@
  * @module MessageBackup
  */
 
+import * as fs from 'node:fs/promises';
 import * as Native from '../Native';
 import { Aci } from './Address';
-import { InputStream } from './io';
+import { InputStream, OutputStream } from './io';
 
 export type InputStreamFactory = () => InputStream;
 
+// This must match the Rust version of the enum.
+export enum ValidationErrorKind {
+  /** The backup couldn't be authenticated, usually because the wrong key was used. */
+  HmacMismatch = 0,
+  /** The input is too short to be an encrypted backup. */
+  TooShort = 1,
+  /** The decrypted contents couldn't be parsed as backup frames. */
+  Corrupted = 2,
+  /** The backup doesn't contain any frames. */
+  NoFrames = 3,
+  /** A frame's contents are invalid. */
+  InvalidContents = 4,
+  /** The backup is missing required frames. */
+  Incomplete = 5,
+  /**
+   * The backup uses a version of the format that isn't supported.
+   *
+   * If the backup is newer than the library, updating the app may allow it to be read.
+   */
+  UnsupportedVersion = 6,
+}
+
 /**
  * Result of validating a message backup bundle.
  */
 export class ValidationOutcome {
+  /**
+   * The kind of error encountered during validation, if any.
+   */
+  public errorKind: ValidationErrorKind | null;
+
   /**
    * A developer-facing message about the error encountered during validation,
    * if any.
@@ -30,6 +58,12 @@ export class ValidationOutcome {
    */
   public unknownFieldMessages: string[];
 
+  /**
+   * The same unknown fields as {@link unknownFieldMessages}, as a JSON array of
+   * objects with `frameIndex`, `path`, and `value` keys.
+   */
+  public unknownFieldsJson: string;
+
   /**
    * `true` if the backup is valid, `false` otherwise.
    *
@@ -40,9 +74,16 @@ export class ValidationOutcome {
   }
 
   constructor(outcome: Native.MessageBackupValidationOutcome) {
-    const { errorMessage, unknownFieldMessages } = outcome;
+    const {
+      errorKind,
+      errorMessage,
+      unknownFieldMessages,
+      unknownFieldsJson,
+    } = outcome;
+    this.errorKind = errorKind;
     this.errorMessage = errorMessage;
     this.unknownFieldMessages = unknownFieldMessages;
+    this.unknownFieldsJson = unknownFieldsJson;
   }
 }
 
@@ -65,41 +106,277 @@ export class MessageBackupKey {
   }
 }
 
+/**
+ * The keys used for message backups, derived from an account's master key.
+ *
+ * Each key is only derived once, the first time it's needed.
+ */
+export class BackupKeyMaterial {
+  readonly _nativeHandle: Native.BackupKeyMaterial;
+
+  /**
+   * Create key material from the given master key and ACI.
+   *
+   * `masterKeyBytes` should contain exactly 32 bytes.
+   */
+  public constructor(masterKeyBytes: Buffer, aci: Aci) {
+    this._nativeHandle = Native.BackupKeyMaterial_New(
+      masterKeyBytes,
+      aci.getServiceIdFixedWidthBinary()
+    );
+  }
+
+  /** The 32-byte backup key. */
+  public get backupKey(): Buffer {
+    return Native.BackupKeyMaterial_GetBackupKey(this);
+  }
+
+  /** The 16-byte backup ID. */
+  public get backupId(): Buffer {
+    return Native.BackupKeyMaterial_GetBackupId(this);
+  }
+}
+
 // This must match the Rust version of the enum.
 export enum Purpose {
   DeviceTransfer = 0,
   RemoteBackup = 1,
 }
 
+/**
+ * Progress reported while validating a backup.
+ */
+export type ValidationProgress = {
+  /** The number of frames read so far. */
+  framesRead: number;
+  /** The number of (encrypted) input bytes read so far. */
+  bytesRead: number;
+  /** The total number of input bytes. */
+  totalBytes: number;
+};
+
+/**
+ * Optional behavior for {@link validate} and {@link validateFile}.
+ */
+export type ValidationOptions = {
+  /**
+   * Called periodically as the input is read, and once more when all of it has
+   * been consumed.
+   */
+  onProgress?: (progress: ValidationProgress) => void;
+  /**
+   * Stops validation when aborted.
+   *
+   * Any pending read of the input is abandoned, and validation rejects with
+   * the signal's `reason`.
+   */
+  abortSignal?: AbortSignal;
+};
+
 /**
  * Validate a backup file
  *
- * @param backupKey The key to use to decrypt the backup contents.
+ * @param backupKey The key to use to decrypt the backup contents, or the key
+ *   material to derive it from.
  * @param purpose Whether the backup is intended for device-to-device transfer or remote storage.
  * @param inputFactory A function that returns new input streams that read the backup contents.
  * @param length The exact length of the input stream.
+ * @param options Either {@link ValidationOptions}, or just the `onProgress`
+ *   callback.
  * @returns The outcome of validation, including any errors and warnings.
  * @throws IoError If an IO error on the input occurs.
  */
 export async function validate(
-  backupKey: MessageBackupKey,
+  backupKey: MessageBackupKey | BackupKeyMaterial,
   purpose: Purpose,
   inputFactory: InputStreamFactory,
-  length: bigint
+  length: bigint,
+  options?: ValidationOptions | ((progress: ValidationProgress) => void)
 ): Promise<ValidationOutcome> {
-  const firstStream = inputFactory();
-  const secondStream = inputFactory();
+  const { onProgress, abortSignal } =
+    typeof options === 'function' ? { onProgress: options } : options ?? {};
+  abortSignal?.throwIfAborted();
+
+  const key =
+    backupKey instanceof BackupKeyMaterial
+      ? { _nativeHandle: Native.MessageBackupKey_FromKeyMaterial(backupKey) }
+      : backupKey;
+
+  const makeStream =
+    abortSignal === undefined
+      ? inputFactory
+      : () => new AbortableInputStream(inputFactory(), abortSignal);
+  const firstStream = makeStream();
+  const secondStream = makeStream();
+  if (onProgress === undefined) {
+    return new ValidationOutcome(
+      await Native.MessageBackupValidator_Validate(
+        key,
+        firstStream,
+        secondStream,
+        length,
+        purpose
+      )
+    );
+  }
+  const progressListener = {
+    _on_progress(framesRead: number, bytesRead: number, totalBytes: number) {
+      onProgress({ framesRead, bytesRead, totalBytes });
+    },
+  };
   return new ValidationOutcome(
-    await Native.MessageBackupValidator_Validate(
-      backupKey,
+    await Native.MessageBackupValidator_ValidateWithProgress(
+      key,
       firstStream,
       secondStream,
       length,
-      purpose
+      purpose,
+      progressListener
     )
   );
 }
 
+/**
+ * Validate a backup file on disk.
+ *
+ * Like {@link validate}, but reads the backup from the file at `filePath`.
+ *
+ * @param backupKey The key to use to decrypt the backup contents, or the key
+ *   material to derive it from.
+ * @param purpose Whether the backup is intended for device-to-device transfer or remote storage.
+ * @param filePath The path of the backup file.
+ * @param options Progress reporting and cancellation.
+ * @returns The outcome of validation, including any errors and warnings.
+ * @throws If the file can't be read.
+ */
+export async function validateFile(
+  backupKey: MessageBackupKey | BackupKeyMaterial,
+  purpose: Purpose,
+  filePath: string,
+  options?: ValidationOptions
+): Promise<ValidationOutcome> {
+  const { size } = await fs.stat(filePath);
+  const streams: FileInputStream[] = [];
+  try {
+    return await validate(
+      backupKey,
+      purpose,
+      () => {
+        const stream = new FileInputStream(filePath, size);
+        streams.push(stream);
+        return stream;
+      },
+      BigInt(size),
+      options
+    );
+  } finally {
+    await Promise.all(streams.map((stream) => stream.close()));
+  }
+}
+
+/**
+ * Encrypt a backup, writing the result to an output stream.
+ *
+ * The frames are compressed, padded, and encrypted into the same format that
+ * {@link validate} reads.
+ *
+ * @param backupKey The key to use to encrypt the backup contents, or the key
+ *   material to derive it from.
+ * @param frames The serialized frames of the backup, starting with the backup
+ *   info, each prefixed with its varint-encoded length.
+ * @param output Where to write the encrypted backup. It is flushed at the end.
+ * @throws IoError If an IO error on the input or output occurs.
+ */
+export async function writeEncrypted(
+  backupKey: MessageBackupKey | BackupKeyMaterial,
+  frames: InputStream,
+  output: OutputStream
+): Promise<void> {
+  const key =
+    backupKey instanceof BackupKeyMaterial
+      ? { _nativeHandle: Native.MessageBackupKey_FromKeyMaterial(backupKey) }
+      : backupKey;
+  await Native.MessageBackup_WriteEncrypted(frames, key, output);
+}
+
+/**
+ * Wraps another stream so that reading fails as soon as `abortSignal` is
+ * aborted, even if a read is already in progress.
+ */
+class AbortableInputStream extends InputStream {
+  constructor(
+    private readonly inner: InputStream,
+    private readonly abortSignal: AbortSignal
+  ) {
+    super();
+  }
+
+  read(amount: number): Promise<Buffer> {
+    return this.abortable(() => this.inner.read(amount));
+  }
+
+  skip(amount: number): Promise<void> {
+    return this.abortable(() => this.inner.skip(amount));
+  }
+
+  private abortable<T>(operation: () => Promise<T>): Promise<T> {
+    const abortSignal = this.abortSignal;
+    if (abortSignal.aborted) {
+      return Promise.reject(abortSignal.reason);
+    }
+    return new Promise((resolve, reject) => {
+      const onAbort = () => reject(abortSignal.reason);
+      abortSignal.addEventListener('abort', onAbort, { once: true });
+      operation()
+        .then(resolve, reject)
+        .finally(() => abortSignal.removeEventListener('abort', onAbort));
+    });
+  }
+}
+
+/**
+ * Reads a file of known size, opening it on first use.
+ */
+class FileInputStream extends InputStream {
+  private handle: Promise<fs.FileHandle> | undefined;
+  private position = 0;
+
+  constructor(
+    private readonly filePath: string,
+    private readonly size: number
+  ) {
+    super();
+  }
+
+  async read(amount: number): Promise<Buffer> {
+    const handle = await this.open();
+    const buffer = Buffer.alloc(amount);
+    const { bytesRead } = await handle.read(buffer, 0, amount, this.position);
+    this.position += bytesRead;
+    return buffer.subarray(0, bytesRead);
+  }
+
+  skip(amount: number): Promise<void> {
+    if (this.position + amount > this.size) {
+      return Promise.reject(new Error('skipped past end of file'));
+    }
+    this.position += amount;
+    return Promise.resolve();
+  }
+
+  async close(): Promise<void> {
+    // If opening failed, the error was already reported by the read that tried.
+    const handle = await this.handle?.catch(() => undefined);
+    this.handle = undefined;
+    await handle?.close();
+  }
+
+  private open(): Promise<fs.FileHandle> {
+    this.handle ??= fs.open(this.filePath, 'r');
+    return this.handle;
+  }
+}
+
 /**
  * An in-memory representation of a backup file used to compare contents.
  *
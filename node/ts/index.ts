@@ -208,6 +208,16 @@ export class PublicKey {
   verifyAlternateIdentity(other: PublicKey, signature: Buffer): boolean {
     return Native.IdentityKey_VerifyAlternateIdentity(this, other, signature);
   }
+
+  /**
+   * Verifies a signature from {@link IdentityKeyPair#createPniSignature}, which claims that
+   * `pniIdentity` belongs to the same account as this ACI identity key.
+   *
+   * Throws if the signature has the wrong length.
+   */
+  verifyPniSignature(pniIdentity: PublicKey, signature: Buffer): boolean {
+    return Native.PniSignature_Verify(this, pniIdentity, signature);
+  }
 }
 
 export class PrivateKey {
@@ -348,6 +358,17 @@ export class IdentityKeyPair {
       other
     );
   }
+
+  /**
+   * Signs `aciIdentity` with this PNI identity key pair, as sent in a `PniSignatureMessage`.
+   */
+  createPniSignature(aciIdentity: PublicKey): Buffer {
+    return Native.PniSignature_Create(
+      this.publicKey,
+      this.privateKey,
+      aciIdentity
+    );
+  }
 }
 
 export class PreKeyBundle {
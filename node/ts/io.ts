@@ -42,3 +42,33 @@ export abstract class InputStream implements Native.InputStream {
    */
   abstract skip(amount: number): Promise<void>;
 }
+
+/**
+ * An abstract class representing an output stream of bytes.
+ */
+export abstract class OutputStream implements Native.OutputStream {
+  _write(buf: Buffer): Promise<void> {
+    return this.write(buf);
+  }
+
+  _flush(): Promise<void> {
+    return this.flush();
+  }
+
+  /**
+   * Write all of `buf` to the output stream.
+   *
+   * @param buf The bytes to write.
+   * @returns A promise which is resolved once the bytes have been written.
+   * @throws {IoError} If an I/O error occurred while writing to the output.
+   */
+  abstract write(buf: Buffer): Promise<void>;
+
+  /**
+   * Make sure everything written so far has reached its destination.
+   *
+   * @returns A promise which is resolved once the output has been flushed.
+   * @throws {IoError} If an I/O error occurred while flushing the output.
+   */
+  abstract flush(): Promise<void>;
+}
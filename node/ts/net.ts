@@ -5,7 +5,7 @@
 
 import type { ReadonlyDeep } from 'type-fest';
 import * as Native from '../Native';
-import { Aci } from './Address';
+import { Aci, Pni } from './Address';
 import {
   AppExpiredError,
   ChatServiceInactive,
@@ -20,6 +20,7 @@ import { ServerMessageAck, Wrapper } from '../Native';
 import { Buffer } from 'node:buffer';
 
 const DEFAULT_CHAT_REQUEST_TIMEOUT_MILLIS = 5000;
+const DEFAULT_CDSI_CONNECT_TIMEOUT_MILLIS = 3 * 60 * 1000;
 
 // This must match the libsignal-bridge Rust enum of the same name.
 export enum Environment {
@@ -37,6 +38,8 @@ export type CDSRequestOptionsType = {
   acisAndAccessKeys: Array<{ aci: string; accessKey: string }>;
   returnAcisWithoutUaks: boolean;
   abortSignal?: AbortSignal;
+  /** How long to keep trying to connect before giving up. */
+  connectTimeoutMillis?: number;
 };
 
 export type CDSResponseEntryType<Aci, Pni> = {
@@ -54,6 +57,45 @@ export interface CDSResponseType<Aci, Pni> {
   debugPermitsUsed: number;
 }
 
+export type CDSLookupEntry = {
+  e164: string;
+  aci: Aci | null;
+  pni: Pni | null;
+};
+
+/**
+ * The result of a CDSI lookup, as a list of entries in the order the server
+ * sent them.
+ *
+ * Unlike {@link CDSResponseType}, each entry carries its own phone number, and
+ * a missing ACI or PNI is always `null`.
+ */
+export class CDSLookupEntries {
+  constructor(
+    readonly entries: ReadonlyArray<CDSLookupEntry>,
+    readonly debugPermitsUsed: number
+  ) {}
+
+  /** @internal */
+  static _fromNative(native: Native.LookupResponseEntries): CDSLookupEntries {
+    return new CDSLookupEntries(
+      native.entries.map(({ e164, aci, pni }) => ({
+        e164,
+        aci: aci ? Aci.parseFromServiceIdFixedWidthBinary(aci) : null,
+        pni: pni ? Pni.parseFromServiceIdFixedWidthBinary(pni) : null,
+      })),
+      native.debugPermitsUsed
+    );
+  }
+
+  /** Returns only the entries that have an ACI, in order. */
+  entriesWithAci(): Array<CDSLookupEntry & { aci: Aci }> {
+    return this.entries.filter(
+      (entry): entry is CDSLookupEntry & { aci: Aci } => entry.aci !== null
+    );
+  }
+}
+
 export type ChatRequest = Readonly<{
   verb: string;
   path: string;
@@ -100,6 +142,16 @@ export class TokioAsyncContext {
     }
     return promise;
   }
+
+  /**
+   * Cancels all outstanding tasks and shuts down the runtime, waiting up to `timeoutMillis` for
+   * them to finish.
+   *
+   * Any async calls made using this context afterwards will throw.
+   */
+  shutdown(timeoutMillis: number): void {
+    Native.TokioAsyncContext_shutdown(this, timeoutMillis);
+  }
 }
 
 export class ChatServerMessageAck {
@@ -213,6 +265,15 @@ export type ChatService = {
     chatRequest: ChatRequest,
     options?: { abortSignal?: AbortSignal }
   ): Promise<Native.ChatResponse>;
+
+  /**
+   * Returns a smoothed estimate of the round-trip time to the Chat Service in milliseconds,
+   * measured passively over the current connection.
+   *
+   * Returns `null` if there's no connection, or if the estimate isn't available yet. The estimate
+   * starts over whenever the service reconnects.
+   */
+  getRoundTripTimeEstimate(): number | null;
 };
 
 /**
@@ -270,6 +331,10 @@ export class AuthenticatedChatService implements ChatService {
     );
   }
 
+  getRoundTripTimeEstimate(): number | null {
+    return Native.ChatService_rtt_estimate_millis_auth(this.chatService);
+  }
+
   connect(options?: {
     abortSignal?: AbortSignal;
   }): Promise<Native.ChatServiceDebugInfo> {
@@ -353,6 +418,10 @@ export class UnauthenticatedChatService implements ChatService {
     );
   }
 
+  getRoundTripTimeEstimate(): number | null {
+    return Native.ChatService_rtt_estimate_millis_unauth(this.chatService);
+  }
+
   connect(options?: {
     abortSignal?: AbortSignal;
   }): Promise<Native.ChatServiceDebugInfo> {
@@ -508,14 +577,42 @@ export class Net {
   }
 
   async cdsiLookup(
+    auth: Readonly<ServiceAuth>,
+    options: ReadonlyDeep<CDSRequestOptionsType>
+  ): Promise<CDSResponseType<string, string>> {
+    const lookup = await this.startCdsiLookup(auth, options);
+    return await this.asyncContext.makeCancellable(
+      options.abortSignal,
+      Native.CdsiLookup_complete(this.asyncContext, lookup)
+    );
+  }
+
+  /**
+   * Like {@link #cdsiLookup}, but returns the results as a list of entries
+   * with explicitly optional ACIs and PNIs.
+   */
+  async cdsiLookupEntries(
+    auth: Readonly<ServiceAuth>,
+    options: ReadonlyDeep<CDSRequestOptionsType>
+  ): Promise<CDSLookupEntries> {
+    const lookup = await this.startCdsiLookup(auth, options);
+    const entries = await this.asyncContext.makeCancellable(
+      options.abortSignal,
+      Native.CdsiLookup_completeEntries(this.asyncContext, lookup)
+    );
+    return CDSLookupEntries._fromNative(entries);
+  }
+
+  private async startCdsiLookup(
     { username, password }: Readonly<ServiceAuth>,
     {
       e164s,
       acisAndAccessKeys,
       returnAcisWithoutUaks,
       abortSignal,
+      connectTimeoutMillis,
     }: ReadonlyDeep<CDSRequestOptionsType>
-  ): Promise<CDSResponseType<string, string>> {
+  ): Promise<Wrapper<Native.CdsiLookup>> {
     const request = newNativeHandle(Native.LookupRequest_new());
     e164s.forEach((e164) => {
       Native.LookupRequest_addE164(request, e164);
@@ -541,13 +638,11 @@ export class Net {
         this.connectionManager,
         username,
         password,
-        request
+        request,
+        connectTimeoutMillis ?? DEFAULT_CDSI_CONNECT_TIMEOUT_MILLIS
       )
     );
-    return await this.asyncContext.makeCancellable(
-      abortSignal,
-      Native.CdsiLookup_complete(this.asyncContext, newNativeHandle(lookup))
-    );
+    return newNativeHandle(lookup);
   }
 }
 
@@ -658,7 +753,7 @@ export interface Svr3Client {
    * Remove a value stored in SVR3.
    *
    * This method will succeed even if the data has never been backed up in the
-   * first place.
+   * first place, or if it could be removed from only some of the enclaves.
    *
    * Error messages are log-safe and do not contain any sensitive data.
    *
@@ -683,6 +778,54 @@ export interface Svr3Client {
     auth: Readonly<ServiceAuth>,
     options?: { abortSignal?: AbortSignal }
   ): Promise<void>;
+
+  /**
+   * Move a pin-protected secret from SVR2 to SVR3.
+   *
+   * The secret is restored from SVR2 using `pin`, backed up to SVR3 with `pin`
+   * as the password, and finally deleted from SVR2. Transient failures while
+   * backing up or deleting are retried a few times before giving up.
+   *
+   * If restoring from SVR2 fails, the returned `Promise` is rejected (for
+   * example with {@link SvrRestoreFailedError} for a wrong pin), and there is
+   * nothing to resume. Any later failure instead produces an incomplete
+   * {@link PinMigrationOutcome}, which holds the restored secret and can be
+   * passed to {@link Svr3Client#resumeMigrationFromSvr2} to continue from the
+   * step that failed.
+   *
+   * Error messages are log-safe and do not contain any sensitive data.
+   *
+   * @param pin - The user's pin, used both to restore from SVR2 and as the
+   * SVR3 password.
+   * @param maxTries - Number of times the secret will be allowed to be guessed
+   * in SVR3. Must be positive.
+   * @param svr2Auth - Credentials for SVR2 obtained from the Chat Server.
+   * @param svr3Auth - Credentials for SVR3 obtained from the Chat Server.
+   */
+  migrateFromSvr2(
+    pin: string,
+    maxTries: number,
+    svr2Auth: Readonly<ServiceAuth>,
+    svr3Auth: Readonly<ServiceAuth>,
+    options?: { abortSignal?: AbortSignal }
+  ): Promise<PinMigrationOutcome>;
+
+  /**
+   * Continue a migration started by {@link Svr3Client#migrateFromSvr2} from
+   * the step that failed.
+   *
+   * If `previousOutcome` is already complete, an equivalent outcome is returned
+   * without contacting either service. The credentials do not have to be the
+   * ones used originally.
+   */
+  resumeMigrationFromSvr2(
+    previousOutcome: PinMigrationOutcome,
+    pin: string,
+    maxTries: number,
+    svr2Auth: Readonly<ServiceAuth>,
+    svr3Auth: Readonly<ServiceAuth>,
+    options?: { abortSignal?: AbortSignal }
+  ): Promise<PinMigrationOutcome>;
 }
 
 /**
@@ -699,6 +842,102 @@ export class RestoredSecret {
   }
 }
 
+/** Bytes sent and received over one kind of connection. */
+export type TrafficCounts = {
+  sent: number;
+  received: number;
+};
+
+/**
+ * The bytes sent and received by each kind of connection libsignal makes, for
+ * the whole process.
+ *
+ * The counts are taken on top of TLS, so they include HTTP and WebSocket
+ * framing but not TLS records or TCP/IP headers.
+ */
+export class TrafficStats {
+  readonly chat: TrafficCounts;
+  readonly cdsi: TrafficCounts;
+  readonly svr: TrafficCounts;
+  readonly cdn: TrafficCounts;
+
+  /**
+   * Reads the counts for each kind in the order chat, CDSI, SVR, CDN, each as
+   * two big-endian 64-bit integers (sent, then received).
+   */
+  constructor(serialized: Buffer) {
+    const counts = (index: number): TrafficCounts => ({
+      sent: Number(serialized.readBigUInt64BE(16 * index)),
+      received: Number(serialized.readBigUInt64BE(16 * index + 8)),
+    });
+    this.chat = counts(0);
+    this.cdsi = counts(1);
+    this.svr = counts(2);
+    this.cdn = counts(3);
+  }
+
+  /** The totals since the process started or {@link reset} was last called. */
+  static snapshot(): TrafficStats {
+    return new TrafficStats(Native.TrafficStats_snapshot());
+  }
+
+  /**
+   * Starts all the totals over from zero, returning what they were.
+   *
+   * Bytes counted while this runs end up in exactly one of the result and the
+   * new totals.
+   */
+  static reset(): TrafficStats {
+    return new TrafficStats(Native.TrafficStats_reset());
+  }
+}
+
+/**
+ * The result of moving a pin-protected secret from SVR2 to SVR3.
+ *
+ * Once the secret has been restored from SVR2 it is always available, even if
+ * a later step of the migration failed.
+ */
+export class PinMigrationOutcome {
+  readonly _nativeHandle: Native.PinMigrationOutcome;
+
+  constructor(handle: Native.PinMigrationOutcome) {
+    this._nativeHandle = handle;
+  }
+
+  /**
+   * Whether the secret has been backed up to SVR3 and deleted from SVR2.
+   *
+   * If not, {@link PinMigrationOutcome#errorMessage} describes what went
+   * wrong.
+   */
+  get isComplete(): boolean {
+    return Native.PinMigrationOutcome_IsComplete(this);
+  }
+
+  /** The 32-byte secret restored from SVR2. */
+  get secret(): Buffer {
+    return Native.PinMigrationOutcome_GetSecret(this);
+  }
+
+  /**
+   * The serialized masked share set produced by backing up to SVR3, or `null`
+   * if the backup step has not succeeded yet.
+   */
+  get shareSet(): Buffer | null {
+    const shareSet = Native.PinMigrationOutcome_GetShareSet(this);
+    return shareSet.length == 0 ? null : shareSet;
+  }
+
+  /**
+   * A log-safe description of the error that stopped the migration, or `null`
+   * if it is complete.
+   */
+  get errorMessage(): string | null {
+    return Native.PinMigrationOutcome_GetErrorMessage(this);
+  }
+}
+
 class Svr3ClientImpl implements Svr3Client {
   constructor(
     private readonly asyncContext: TokioAsyncContext,
@@ -749,7 +988,7 @@ class Svr3ClientImpl implements Svr3Client {
     auth: Readonly<ServiceAuth>,
     options?: { abortSignal?: AbortSignal }
   ): Promise<void> {
-    return this.asyncContext.makeCancellable(
+    await this.asyncContext.makeCancellable(
       options?.abortSignal,
       Native.Svr3Remove(
         this.asyncContext,
@@ -759,4 +998,52 @@ class Svr3ClientImpl implements Svr3Client {
       )
     );
   }
+
+  async migrateFromSvr2(
+    pin: string,
+    maxTries: number,
+    svr2Auth: Readonly<ServiceAuth>,
+    svr3Auth: Readonly<ServiceAuth>,
+    options?: { abortSignal?: AbortSignal }
+  ): Promise<PinMigrationOutcome> {
+    const outcome = await this.asyncContext.makeCancellable(
+      options?.abortSignal,
+      Native.Svr2MigrateToSvr3(
+        this.asyncContext,
+        this.connectionManager,
+        pin,
+        maxTries,
+        svr2Auth.username,
+        svr2Auth.password,
+        svr3Auth.username,
+        svr3Auth.password
+      )
+    );
+    return new PinMigrationOutcome(outcome);
+  }
+
+  async resumeMigrationFromSvr2(
+    previousOutcome: PinMigrationOutcome,
+    pin: string,
+    maxTries: number,
+    svr2Auth: Readonly<ServiceAuth>,
+    svr3Auth: Readonly<ServiceAuth>,
+    options?: { abortSignal?: AbortSignal }
+  ): Promise<PinMigrationOutcome> {
+    const outcome = await this.asyncContext.makeCancellable(
+      options?.abortSignal,
+      Native.Svr2ResumeMigrationToSvr3(
+        this.asyncContext,
+        this.connectionManager,
+        previousOutcome,
+        pin,
+        maxTries,
+        svr2Auth.username,
+        svr2Auth.password,
+        svr3Auth.username,
+        svr3Auth.password
+      )
+    );
+    return new PinMigrationOutcome(outcome);
+  }
 }
@@ -34,6 +34,8 @@ describe('Async runtime not on the Node executor', () => {
       assert.instanceOf(e, LibSignalErrorBase);
       const err = e as LibSignalError;
       assert.equal(err.code, ErrorCode.Generic);
+      // SignalErrorCode::InvalidArgument
+      assert.equal(err.stableCode, 2);
     }
   });
 });
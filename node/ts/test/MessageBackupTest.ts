@@ -7,7 +7,13 @@ import { assert } from 'chai';
 import * as MessageBackup from '../MessageBackup';
 import * as util from './util';
 import { Aci } from '../Address';
-import { Uint8ArrayInputStream, ErrorInputStream } from './ioutil';
+import {
+  Uint8ArrayInputStream,
+  Uint8ArrayOutputStream,
+  ErrorInputStream,
+  ErrorOutputStream,
+  PendingInputStream,
+} from './ioutil';
 import * as fs from 'node:fs';
 import * as path from 'node:path';
 import { LogLevel } from '..';
@@ -33,6 +39,50 @@ describe('MessageBackup', () => {
         BigInt(input.length)
       );
       assert.equal(outcome.errorMessage, null);
+      assert.equal(outcome.errorKind, null);
+      assert.deepEqual(JSON.parse(outcome.unknownFieldsJson), []);
+    });
+
+    it('reports a mismatched HMAC for the wrong key', async () => {
+      const input = fs.readFileSync(
+        path.join(__dirname, '../../ts/test/new_account.binproto.encrypted')
+      );
+      const wrongMasterKey = Buffer.from(
+        new Uint8Array(32).fill('W'.charCodeAt(0))
+      );
+      const wrongKey = new MessageBackup.MessageBackupKey(wrongMasterKey, aci);
+
+      const outcome = await MessageBackup.validate(
+        wrongKey,
+        purpose,
+        () => new Uint8ArrayInputStream(input),
+        BigInt(input.length)
+      );
+      assert.equal(
+        outcome.errorKind,
+        MessageBackup.ValidationErrorKind.HmacMismatch
+      );
+    });
+
+    it('reports progress while validating', async () => {
+      const input = fs.readFileSync(
+        path.join(__dirname, '../../ts/test/new_account.binproto.encrypted')
+      );
+
+      const reports: MessageBackup.ValidationProgress[] = [];
+      const outcome = await MessageBackup.validate(
+        testKey,
+        purpose,
+        () => new Uint8ArrayInputStream(input),
+        BigInt(input.length),
+        (progress) => reports.push(progress)
+      );
+      assert.equal(outcome.errorMessage, null);
+
+      const last = reports[reports.length - 1];
+      assert.isAbove(last.framesRead, 0);
+      assert.equal(last.bytesRead, input.length);
+      assert.equal(last.totalBytes, input.length);
     });
 
     it('produces an error message on empty input', async () => {
@@ -43,6 +93,77 @@ describe('MessageBackup', () => {
         0n
       );
       assert.equal(outcome.errorMessage, 'not enough bytes for an HMAC');
+      assert.equal(
+        outcome.errorKind,
+        MessageBackup.ValidationErrorKind.TooShort
+      );
+    });
+
+    it('accepts key material in place of a key', async () => {
+      const input = fs.readFileSync(
+        path.join(__dirname, '../../ts/test/new_account.binproto.encrypted')
+      );
+      const keyMaterial = new MessageBackup.BackupKeyMaterial(masterKey, aci);
+      assert.lengthOf(keyMaterial.backupKey, 32);
+      assert.lengthOf(keyMaterial.backupId, 16);
+
+      const outcome = await MessageBackup.validate(
+        keyMaterial,
+        purpose,
+        () => new Uint8ArrayInputStream(input),
+        BigInt(input.length)
+      );
+      assert.equal(outcome.errorMessage, null);
+    });
+
+    it('validates a backup file by path', async () => {
+      const reports: MessageBackup.ValidationProgress[] = [];
+      const outcome = await MessageBackup.validateFile(
+        new MessageBackup.BackupKeyMaterial(masterKey, aci),
+        purpose,
+        path.join(__dirname, '../../ts/test/new_account.binproto.encrypted'),
+        { onProgress: (progress) => reports.push(progress) }
+      );
+      assert.equal(outcome.errorMessage, null);
+      assert.isNotEmpty(reports);
+    });
+
+    it('can be cancelled while waiting for input', async () => {
+      const stream = new PendingInputStream();
+      const abortController = new AbortController();
+      const validation = MessageBackup.validate(
+        testKey,
+        purpose,
+        () => stream,
+        1000n,
+        { abortSignal: abortController.signal }
+      );
+      await stream.readStarted;
+      abortController.abort();
+
+      try {
+        await validation;
+        assert.fail('did not throw');
+      } catch (e) {
+        assert.instanceOf(e, Error);
+        assert.equal((e as Error).name, 'AbortError');
+      }
+    });
+
+    it('does not start if already cancelled', async () => {
+      try {
+        await MessageBackup.validate(
+          testKey,
+          purpose,
+          () => new ErrorInputStream(),
+          234n,
+          { abortSignal: AbortSignal.abort() }
+        );
+        assert.fail('did not throw');
+      } catch (e) {
+        assert.instanceOf(e, Error);
+        assert.equal((e as Error).name, 'AbortError');
+      }
     });
 
     it('throws a raised IO error', async () => {
@@ -59,6 +180,44 @@ describe('MessageBackup', () => {
       }
     });
   });
+
+  describe('writeEncrypted', () => {
+    const frames = fs.readFileSync(
+      path.join(__dirname, '../../ts/test/canonical-backup.binproto')
+    );
+
+    it('produces a backup that validates', async () => {
+      const output = new Uint8ArrayOutputStream();
+      await MessageBackup.writeEncrypted(
+        testKey,
+        new Uint8ArrayInputStream(frames),
+        output
+      );
+      assert(output.flushed);
+
+      const encrypted = output.data;
+      const outcome = await MessageBackup.validate(
+        testKey,
+        purpose,
+        () => new Uint8ArrayInputStream(encrypted),
+        BigInt(encrypted.length)
+      );
+      assert.equal(outcome.errorMessage, null);
+    });
+
+    it('throws a raised IO error from the output', async () => {
+      try {
+        await MessageBackup.writeEncrypted(
+          testKey,
+          new Uint8ArrayInputStream(frames),
+          new ErrorOutputStream()
+        );
+        assert.fail('did not throw');
+      } catch (e) {
+        assert.instanceOf(e, ErrorOutputStream.Error);
+      }
+    });
+  });
 });
 
 describe('ComparableBackup', () => {
@@ -13,12 +13,14 @@ import * as Native from '../../Native';
 import { ErrorCode, LibSignalErrorBase } from '../Errors';
 import {
   buildHttpRequest,
+  CDSLookupEntries,
   ChatServerMessageAck,
   ChatServiceListener,
   Environment,
   Net,
   newNativeHandle,
   ServiceAuth,
+  TrafficStats,
 } from '../net';
 import { randomBytes } from 'crypto';
 import { ChatResponse } from '../../Native';
@@ -41,6 +43,33 @@ describe('Net class', () => {
   });
 });
 
+describe('TrafficStats', () => {
+  it('reads each count in order', () => {
+    const serialized = Buffer.alloc(64);
+    for (let i = 0; i < 8; i++) {
+      // Each count is big-endian, with its index in the last byte and 1 in the one before.
+      serialized[8 * i + 6] = 1;
+      serialized[8 * i + 7] = i;
+    }
+    const stats = new TrafficStats(serialized);
+    assert.deepEqual(stats.chat, { sent: 256, received: 257 });
+    assert.deepEqual(stats.cdsi, { sent: 258, received: 259 });
+    assert.deepEqual(stats.svr, { sent: 260, received: 261 });
+    assert.deepEqual(stats.cdn, { sent: 262, received: 263 });
+  });
+
+  it('can be snapshotted and reset', () => {
+    TrafficStats.reset();
+    const snapshot = TrafficStats.snapshot();
+    // Counts only go up until they're reset, so the reset sees at least what the snapshot did.
+    const reset = TrafficStats.reset();
+    for (const kind of ['chat', 'cdsi', 'svr', 'cdn'] as const) {
+      assert.isAtMost(snapshot[kind].sent, reset[kind].sent);
+      assert.isAtMost(snapshot[kind].received, reset[kind].received);
+    }
+  });
+});
+
 describe('chat service api', () => {
   it('converts errors to native', () => {
     const cases: Array<[string, ErrorCode]> = [
@@ -54,6 +83,7 @@ describe('chat service api', () => {
       ['IncomingDataInvalid', ErrorCode.IoError],
       ['Timeout', ErrorCode.IoError],
       ['TimeoutEstablishingConnection', ErrorCode.IoError],
+      ['RequestChannelClosed', ErrorCode.IoError],
 
       // These two are more of internal errors, but they should never happen anyway.
       ['FailedToPassMessageToIncomingChannel', ErrorCode.IoError],
@@ -69,6 +99,14 @@ describe('chat service api', () => {
     });
   });
 
+  it('includes the trace ID in per-request errors', () => {
+    for (const name of ['Timeout', 'RequestChannelClosed']) {
+      expect(() => Native.TESTING_ChatServiceErrorConvert(name)).throws(
+        /000000000000002a/
+      );
+    }
+  });
+
   it('converts Response object to native', () => {
     const status = 200;
     const headers: ReadonlyArray<[string, string]> = [
@@ -100,6 +138,9 @@ describe('chat service api', () => {
       ipType: 1,
       durationMillis: 200,
       connectionInfo: 'connection_info',
+      routeType: 4,
+      remoteIp: '192.0.2.1',
+      remotePort: 443,
     };
     expect(Native.TESTING_ChatServiceDebugInfoConvert()).deep.equals(expected);
   });
@@ -349,6 +390,42 @@ describe('chat service api', () => {
     });
   });
 
+  it('messages arrive in order even when the listener is slow', async () => {
+    const net = new Net(Environment.Staging, userAgent);
+    const completable = new CompletablePromise();
+    const count = 50;
+    const timestampsReceived: number[] = [];
+    const listener: ChatServiceListener = {
+      onIncomingMessage(
+        envelope: Buffer,
+        timestamp: number,
+        _ack: ChatServerMessageAck
+      ): void {
+        expect(envelope.toString('utf8')).to.eql(timestamp.toString());
+        // Hold up the callback for a little while without yielding to the event loop.
+        const until = Date.now() + Math.random() * 10;
+        while (Date.now() < until) {
+          // busy-wait
+        }
+        timestampsReceived.push(timestamp);
+        if (timestampsReceived.length == count) {
+          completable.complete();
+        }
+      },
+      onQueueEmpty(): void {
+        fail('unexpected call');
+      },
+      onConnectionInterrupted(_cause: object | null): void {
+        fail('unexpected call');
+      },
+    };
+    const chat = net.newAuthenticatedChatService('', '', false, listener);
+    Native.TESTING_ChatService_InjectIncomingMessages(chat.chatService, count);
+    await completable.done();
+
+    expect(timestampsReceived).to.eql([...Array(count).keys()]);
+  });
+
   it('listener gets null cause for intentional disconnect', async () => {
     const net = new Net(Environment.Staging, userAgent);
     const completable = new CompletablePromise();
@@ -444,6 +521,25 @@ describe('cdsi lookup', () => {
       expect(result).deep.equals(expected);
     });
 
+    it('converts entries to native', async () => {
+      const asyncContext = Native.TokioAsyncContext_new();
+      const result = CDSLookupEntries._fromNative(
+        await Native.TESTING_CdsiLookupResponseEntriesConvert({
+          _nativeHandle: asyncContext,
+        })
+      );
+
+      const expectedAci = Aci.fromUuid(aciUuid);
+      const expectedPni = Pni.fromUuid(pniUuid);
+      expect(result.debugPermitsUsed).equals(debugPermitsUsed);
+      expect(result.entries).deep.equals([
+        { e164: e164Both, aci: expectedAci, pni: expectedPni },
+        // The PNI-only entry comes through with a null ACI, not a nil UUID.
+        { e164: e164Pni, aci: null, pni: expectedPni },
+      ]);
+      expect(result.entriesWithAci()).deep.equals([result.entries[0]]);
+    });
+
     it('converts errors to native', () => {
       const cases: Array<[string, ErrorCode, string]> = [
         [
@@ -2225,6 +2225,55 @@ describe('SignalClient', () => {
     );
   });
 
+  it('can verify PNI signatures from shared test vectors', () => {
+    // Shared with the Rust, Java, and Swift tests.
+    const aci = new SignalClient.IdentityKeyPair(
+      SignalClient.PublicKey.deserialize(
+        Buffer.from(
+          '0505c86d71f5ee1af068207fb1b50ba69d60fd70ffa78375eaa9f60f100ca65871',
+          'hex'
+        )
+      ),
+      SignalClient.PrivateKey.deserialize(
+        Buffer.from(
+          '5882e1f3662ee3dd54b5d0b80f7d3f67277ee39a67d47697f78974879cd93266',
+          'hex'
+        )
+      )
+    );
+    const pni = new SignalClient.IdentityKeyPair(
+      SignalClient.PublicKey.deserialize(
+        Buffer.from(
+          '0564a82fb9ceae27d3f741f728b3a3bc10a6446e4d702f3184b302a0fb5c93a424',
+          'hex'
+        )
+      ),
+      SignalClient.PrivateKey.deserialize(
+        Buffer.from(
+          '1823511d57e3ca8aafbe50a6d0a3f0ed4076eae74c5e0e2351d84eda70dc7d6f',
+          'hex'
+        )
+      )
+    );
+    assert.equal(aci.privateKey.getPublicKey().compare(aci.publicKey), 0);
+    assert.equal(pni.privateKey.getPublicKey().compare(pni.publicKey), 0);
+    const signature = Buffer.from(
+      '17e3c41803c64aad8effe9a4c516cc1452e1c1a5a1fafea6cd1cc55aa8f869b2' +
+        '83f821cfd6eec07b43190acbd62274704c24928da000e322095e6ec690b9db0b',
+      'hex'
+    );
+
+    assert(aci.publicKey.verifyPniSignature(pni.publicKey, signature));
+    assert(!pni.publicKey.verifyPniSignature(aci.publicKey, signature));
+
+    const fresh = pni.createPniSignature(aci.publicKey);
+    assert(aci.publicKey.verifyPniSignature(pni.publicKey, fresh));
+
+    assert.throws(() =>
+      aci.publicKey.verifyPniSignature(pni.publicKey, signature.subarray(1))
+    );
+  });
+
   it('includes all error codes in LibSignalError', () => {
     // This is a compilation test only.
     type MissingCodes = Exclude<
@@ -351,6 +351,15 @@ describe('ZKGroup', () => {
       presentation,
       new Date(1000 * redemptionTime)
     );
+
+    // The Authorization header for group storage requests
+    const expectedCredentials = `${groupPublicParams
+      .serialize()
+      .toString('hex')}:${presentation.serialize().toString('hex')}`;
+    assert.equal(
+      presentation.getHttpAuthorization(groupPublicParams),
+      `Basic ${Buffer.from(expectedCredentials).toString('base64')}`
+    );
   });
 
   it('testExpiringProfileKeyIntegration', () => {
@@ -3,7 +3,7 @@
 // SPDX-License-Identifier: AGPL-3.0-only
 //
 
-import { InputStream } from '../io';
+import { InputStream, OutputStream } from '../io';
 
 export class ErrorInputStream extends InputStream {
   public static Error = class extends Error {};
@@ -16,6 +16,29 @@ export class ErrorInputStream extends InputStream {
   }
 }
 
+/** A stream whose reads never complete. */
+export class PendingInputStream extends InputStream {
+  /** Resolved when the first read starts. */
+  readStarted: Promise<void>;
+  private onReadStarted: () => void = () => {};
+
+  constructor() {
+    super();
+    this.readStarted = new Promise((resolve) => {
+      this.onReadStarted = resolve;
+    });
+  }
+
+  read(_amount: number): Promise<Buffer> {
+    this.onReadStarted();
+    return new Promise(() => {});
+  }
+  skip(_amount: number): Promise<void> {
+    this.onReadStarted();
+    return new Promise(() => {});
+  }
+}
+
 export class Uint8ArrayInputStream extends InputStream {
   data: Uint8Array;
 
@@ -39,3 +62,34 @@ export class Uint8ArrayInputStream extends InputStream {
     return Promise.resolve();
   }
 }
+
+export class Uint8ArrayOutputStream extends OutputStream {
+  chunks: Buffer[] = [];
+  flushed = false;
+
+  get data(): Buffer {
+    return Buffer.concat(this.chunks);
+  }
+
+  write(buf: Buffer): Promise<void> {
+    this.chunks.push(Buffer.from(buf));
+    this.flushed = false;
+    return Promise.resolve();
+  }
+
+  flush(): Promise<void> {
+    this.flushed = true;
+    return Promise.resolve();
+  }
+}
+
+export class ErrorOutputStream extends OutputStream {
+  public static Error = class extends Error {};
+
+  write(_buf: Buffer): Promise<void> {
+    throw new ErrorOutputStream.Error();
+  }
+  flush(): Promise<void> {
+    throw new ErrorOutputStream.Error();
+  }
+}
@@ -5,6 +5,7 @@
 
 import ByteArray from '../internal/ByteArray';
 import * as Native from '../../../Native';
+import GroupPublicParams from '../groups/GroupPublicParams';
 import UuidCiphertext from '../groups/UuidCiphertext';
 
 export default class AuthCredentialPresentation extends ByteArray {
@@ -30,6 +31,17 @@ export default class AuthCredentialPresentation extends ByteArray {
     return new UuidCiphertext(ciphertextBytes);
   }
 
+  /**
+   * Returns the value of the `Authorization` header for a request to the group storage service
+   * for the group with the given public params.
+   */
+  getHttpAuthorization(groupPublicParams: GroupPublicParams): string {
+    return Native.AuthCredentialPresentation_GetHttpAuthorization(
+      this.contents,
+      groupPublicParams.getContents()
+    );
+  }
+
   getRedemptionTime(): Date {
     return new Date(
       1000 * Native.AuthCredentialPresentation_GetRedemptionTime(this.contents)
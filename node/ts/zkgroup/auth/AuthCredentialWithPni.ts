@@ -12,4 +12,17 @@ export default class AuthCredentialWithPni extends ByteArray {
   constructor(contents: Buffer) {
     super(contents, Native.AuthCredentialWithPni_CheckValidContents);
   }
+
+  /**
+   * Returns when this credential stops being accepted, if it's in an old format that has to be
+   * replaced by fetching a new credential from the server.
+   *
+   * Returns `null` if the credential is already in the current format.
+   */
+  getRefreshDeadline(): Date | null {
+    const deadline = Native.AuthCredentialWithPni_GetRefreshDeadlineOrZero(
+      this.contents
+    );
+    return deadline !== 0 ? new Date(1000 * deadline) : null;
+  }
 }
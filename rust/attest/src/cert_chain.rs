@@ -13,7 +13,7 @@ use boring_signal::x509::store::X509StoreRef;
 use boring_signal::x509::{X509StoreContext, X509};
 
 use crate::error::ContextError;
-use crate::expireable::Expireable;
+use crate::expireable::{self, Expireable};
 
 pub(crate) struct CertChainErrorDomain;
 pub(crate) type Error = ContextError<CertChainErrorDomain>;
@@ -157,6 +157,14 @@ impl Expireable for CertChain {
                     .unwrap_or(false)
         })
     }
+
+    fn valid_until(&self) -> Option<SystemTime> {
+        expireable::earliest(
+            self.certs
+                .iter()
+                .map(|cert| crate::util::asn1_time_to_system_time(cert.not_after())),
+        )
+    }
 }
 
 #[cfg(test)]
@@ -46,7 +46,7 @@ use crate::dcap::evidence::Evidence;
 pub use crate::dcap::sgx_report_body::MREnclave;
 use crate::dcap::sgx_report_body::SgxFlags;
 use crate::dcap::sgx_x509::SgxPckExtension;
-use crate::enclave::AttestationError;
+use crate::enclave::{AttestationError, AttestationFailureKind};
 use crate::error::{Context, ContextError};
 use crate::expireable::Expireable;
 
@@ -108,12 +108,14 @@ pub fn verify_remote_attestation(
 
     // 5. Verify the enclave measurements in the Quote reflect an enclave identity expected.
     if expected_mrenclave != &attestation.mrenclave {
-        return Err(Error::new(format!(
-            "expected mrenclave {}, was {}",
-            expected_mrenclave.encode_hex::<String>(),
-            attestation.mrenclave.encode_hex::<String>(),
-        ))
-        .into());
+        return Err(AttestationError::new(
+            format!(
+                "expected mrenclave {}, was {}",
+                expected_mrenclave.encode_hex::<String>(),
+                attestation.mrenclave.encode_hex::<String>(),
+            ),
+            AttestationFailureKind::MeasurementMismatch,
+        ));
     }
 
     Ok(attestation.claims)
@@ -216,7 +218,7 @@ fn attest(
     evidence_bytes: &[u8],
     endorsement_bytes: &[u8],
     current_time: SystemTime,
-) -> Result<Attestation> {
+) -> std::result::Result<Attestation, AttestationError> {
     let evidence = evidence::Evidence::try_from(evidence_bytes).context("evidence")?;
     let endorsements =
         endorsements::SgxEndorsements::try_from(endorsement_bytes).context("endorsements")?;
@@ -228,12 +230,12 @@ fn attest_impl(
     endorsements: SgxEndorsements,
     trusted_root_pkey: &PKeyRef<Public>,
     current_time: SystemTime,
-) -> Result<Attestation> {
+) -> std::result::Result<Attestation, AttestationError> {
     // 1. Verify the integrity of the signature chain from the Quote to the Intel-issued PCK certificate.
     // 2. Verify no keys in the chain have been revoked.
     // verify the time parameter falls within “not before” and “not after” metadata
-    verify_expiration(current_time, &evidence).context("evidence")?;
-    verify_expiration(current_time, &endorsements).context("endorsements")?;
+    verify_expiration(current_time, &evidence, "evidence")?;
+    verify_expiration(current_time, &endorsements, "endorsements")?;
     verify_certificates(trusted_root_pkey, &evidence, &endorsements, current_time)?;
 
     // 3. Verify the Quoting Enclave is from a suitable source and is up to date
@@ -253,7 +255,7 @@ fn attest_impl(
     // enclave is not running in debug mode
     let report = &evidence.quote.quote_body.report_body;
     if report.has_flag(SgxFlags::DEBUG) {
-        return Err(Error::new("Application enclave in debug mode"));
+        return Err(Error::new("Application enclave in debug mode").into());
     }
 
     Ok(Attestation {
@@ -388,15 +390,24 @@ pub(crate) fn from_trusted(
     build().map_err(|e| Error::from(e).context("building trusted certificate store"))
 }
 
-fn verify_expiration(timestamp: SystemTime, expireable: &dyn Expireable) -> Result<()> {
+fn verify_expiration(
+    timestamp: SystemTime,
+    expireable: &dyn Expireable,
+    context: &str,
+) -> std::result::Result<(), AttestationError> {
     if !expireable.valid_at(timestamp) {
         let epoch_duration = timestamp
             .duration_since(SystemTime::UNIX_EPOCH)
             .map_err(|_| Error::new("invalid timestamp provided for expiration check"))?;
-        return Err(Error::new(format!(
-            "attestation is not valid for {}",
-            epoch_duration.as_secs(),
-        )));
+        return Err(AttestationError::new(
+            format!(
+                "({context}) attestation is not valid for {}",
+                epoch_duration.as_secs(),
+            ),
+            AttestationFailureKind::Expired {
+                valid_until: expireable.valid_until(),
+            },
+        ));
     }
 
     Ok(())
@@ -650,6 +661,7 @@ mod test {
 
     use std::time::{Duration, SystemTime};
 
+    use assert_matches::assert_matches;
     use boring_signal::bn::BigNum;
     use hex_literal::hex;
 
@@ -778,6 +790,52 @@ mod test {
         .is_err());
     }
 
+    #[test]
+    fn test_verify_remote_attestation_reports_validity_window() {
+        let valid_time: SystemTime = SystemTime::UNIX_EPOCH + Duration::from_millis(1674105089000);
+        let far_future = valid_time + Duration::from_secs(10 * 365 * 24 * 60 * 60);
+
+        let err = verify_remote_attestation(
+            include_bytes!("../tests/data/dcap.evidence"),
+            include_bytes!("../tests/data/dcap.endorsements"),
+            &EXPECTED_MRENCLAVE,
+            ACCEPTED_SW_ADVISORIES,
+            far_future,
+        )
+        .expect_err("expired");
+
+        assert_matches!(
+            crate::enclave::Error::from(err),
+            crate::enclave::Error::AttestationExpired {
+                valid_until: Some(valid_until)
+            } => {
+                assert!(valid_time <= valid_until && valid_until < far_future);
+            }
+        );
+    }
+
+    #[test]
+    fn test_verify_remote_attestation_mrenclave_mismatch() {
+        let current_time: SystemTime =
+            SystemTime::UNIX_EPOCH + Duration::from_millis(1674105089000);
+        let mut other_mrenclave = EXPECTED_MRENCLAVE;
+        other_mrenclave[0] ^= 1;
+
+        let err = verify_remote_attestation(
+            include_bytes!("../tests/data/dcap.evidence"),
+            include_bytes!("../tests/data/dcap.endorsements"),
+            &other_mrenclave,
+            ACCEPTED_SW_ADVISORIES,
+            current_time,
+        )
+        .expect_err("wrong mrenclave");
+
+        assert_matches!(
+            crate::enclave::Error::from(err),
+            crate::enclave::Error::AttestationMeasurementMismatch
+        );
+    }
+
     #[test]
     fn debug_flag() {
         let mut builder = FakeAttestation::builder();
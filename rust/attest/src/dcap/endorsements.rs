@@ -24,7 +24,7 @@ use crate::dcap::revocation_list::RevocationList;
 use crate::dcap::{Error, Expireable, Result};
 use crate::endian::UInt32LE;
 use crate::error::Context;
-use crate::util;
+use crate::{expireable, util};
 
 // Inline header file references are paths from the root of the repository tree.
 // https://github.com/openenclave/openenclave/tree/v0.17.7
@@ -202,6 +202,18 @@ impl Expireable for SgxEndorsements {
             && self.pck_issuer_crl.valid_at(timestamp)
             && self.root_crl.valid_at(timestamp)
     }
+
+    fn valid_until(&self) -> Option<SystemTime> {
+        expireable::earliest([
+            self.qe_id_issuer_chain.valid_until(),
+            self.pck_issuer_crl_chain.valid_until(),
+            self.tcb_issuer_chain.valid_until(),
+            self.tcb_info.valid_until(),
+            self.qe_id_info.valid_until(),
+            self.pck_issuer_crl.valid_until(),
+            self.root_crl.valid_until(),
+        ])
+    }
 }
 
 fn validate_offsets(offsets: &[usize], data: &[u8]) -> Result<()> {
@@ -448,6 +460,10 @@ impl Expireable for TcbInfo {
         //    want to fail requests because of clock skew
         timestamp <= self.next_update.into()
     }
+
+    fn valid_until(&self) -> Option<SystemTime> {
+        Some(self.next_update.into())
+    }
 }
 
 #[derive(Deserialize, Debug)]
@@ -687,6 +703,10 @@ impl Expireable for EnclaveIdentity {
         //    want to fail requests because of clock skew
         timestamp <= self.next_update.into()
     }
+
+    fn valid_until(&self) -> Option<SystemTime> {
+        Some(self.next_update.into())
+    }
 }
 
 #[derive(Debug, PartialEq, Eq, Deserialize)]
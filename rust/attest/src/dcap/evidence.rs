@@ -45,6 +45,10 @@ impl Expireable for Evidence<'_> {
     fn valid_at(&self, timestamp: std::time::SystemTime) -> bool {
         self.quote.valid_at(timestamp)
     }
+
+    fn valid_until(&self) -> Option<std::time::SystemTime> {
+        self.quote.valid_until()
+    }
 }
 
 /// Version of oe_custom_claims_header_t/oe_custom_claims_entry_t
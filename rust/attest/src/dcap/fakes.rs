@@ -30,6 +30,7 @@ use crate::dcap::endorsements::SgxEndorsements;
 use crate::dcap::evidence::Evidence;
 use crate::dcap::revocation_list::RevocationList;
 use crate::dcap::{attest_impl, Attestation};
+use crate::enclave::AttestationError;
 
 const EVIDENCE_BYTES: &[u8] = include_bytes!("../../tests/data/dcap.evidence");
 const ENDORSEMENT_BYTES: &[u8] = include_bytes!("../../tests/data/dcap.endorsements");
@@ -143,7 +144,7 @@ impl FakeAttestation {
         }
     }
 
-    pub fn attest(self) -> Result<Attestation, super::Error> {
+    pub fn attest(self) -> Result<Attestation, AttestationError> {
         attest_impl(
             self.evidence,
             self.endorsements,
@@ -25,6 +25,10 @@ impl Expireable for RevocationList {
             .map(|order| order.is_lt())
             .unwrap_or(false)
     }
+
+    fn valid_until(&self) -> Option<SystemTime> {
+        crate::util::asn1_time_to_system_time(self.crl.next_update()?)
+    }
 }
 
 impl RevocationList {
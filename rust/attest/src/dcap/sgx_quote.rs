@@ -163,6 +163,10 @@ impl<'a> Expireable for SgxQuote<'a> {
         // quote_body is not expireable
         self.support.valid_at(timestamp)
     }
+
+    fn valid_until(&self) -> Option<SystemTime> {
+        self.support.valid_until()
+    }
 }
 
 #[derive(Debug, PartialEq)]
@@ -308,6 +312,10 @@ impl<'a> Expireable for SgxQuoteSupport<'a> {
     fn valid_at(&self, timestamp: SystemTime) -> bool {
         self.pck_cert_chain.valid_at(timestamp)
     }
+
+    fn valid_until(&self) -> Option<SystemTime> {
+        self.pck_cert_chain.valid_until()
+    }
 }
 
 #[derive(Debug, zerocopy::FromBytes, zerocopy::FromZeroes)]
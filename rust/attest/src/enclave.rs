@@ -4,6 +4,7 @@
 //
 
 use std::collections::HashMap;
+use std::time::SystemTime;
 
 use displaydoc::Display;
 use prost::Message;
@@ -20,21 +21,44 @@ pub type Result<T> = std::result::Result<T, Error>;
 #[error("{message}")]
 pub struct AttestationError {
     message: String,
+    kind: AttestationFailureKind,
 }
 
-impl From<dcap::Error> for AttestationError {
-    fn from(e: dcap::Error) -> Self {
+/// The failures that callers might want to handle differently from the rest.
+///
+/// [`Error`] has a separate variant for each of these besides `Other`, so that an app can
+/// (for example) ask to be updated only when the enclave is running code it doesn't expect.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub(crate) enum AttestationFailureKind {
+    Other,
+    Expired { valid_until: Option<SystemTime> },
+    MeasurementMismatch,
+}
+
+impl AttestationError {
+    pub(crate) fn new(message: impl Into<String>, kind: AttestationFailureKind) -> Self {
         Self {
-            message: e.to_string(),
+            message: message.into(),
+            kind,
         }
     }
 }
 
+impl From<dcap::Error> for AttestationError {
+    fn from(e: dcap::Error) -> Self {
+        Self::new(e.to_string(), AttestationFailureKind::Other)
+    }
+}
+
 /// Error types for an enclave noise session.
 #[derive(Display, Debug, thiserror::Error)]
 pub enum Error {
     /// failure to attest remote enclave: {0:?}
-    AttestationError(#[from] AttestationError),
+    AttestationError(#[source] AttestationError),
+    /// attestation is not valid at the current time (valid until {valid_until:?})
+    AttestationExpired { valid_until: Option<SystemTime> },
+    /// remote enclave is not running the expected code
+    AttestationMeasurementMismatch,
     /// failure to communicate on established Noise channel to the enclave: {0}
     NoiseError(#[from] client_connection::Error),
     /// failure to complete Noise handshake to the enclave: {0}
@@ -45,6 +69,18 @@ pub enum Error {
     InvalidBridgeStateError,
 }
 
+impl From<AttestationError> for Error {
+    fn from(err: AttestationError) -> Self {
+        match err.kind {
+            AttestationFailureKind::Other => Self::AttestationError(err),
+            AttestationFailureKind::Expired { valid_until } => {
+                Self::AttestationExpired { valid_until }
+            }
+            AttestationFailureKind::MeasurementMismatch => Self::AttestationMeasurementMismatch,
+        }
+    }
+}
+
 impl From<prost::DecodeError> for Error {
     fn from(err: prost::DecodeError) -> Self {
         Error::AttestationDataError {
@@ -55,29 +91,33 @@ impl From<prost::DecodeError> for Error {
 
 impl From<nitro::NitroError> for AttestationError {
     fn from(err: nitro::NitroError) -> Self {
-        AttestationError {
-            message: err.to_string(),
-        }
+        let kind = match err {
+            nitro::NitroError::InvalidPcrs => AttestationFailureKind::MeasurementMismatch,
+            _ => AttestationFailureKind::Other,
+        };
+        AttestationError::new(err.to_string(), kind)
     }
 }
 
 impl From<nitro::NitroError> for Error {
     fn from(err: nitro::NitroError) -> Self {
-        Self::AttestationError(err.into())
+        AttestationError::from(err).into()
     }
 }
 
 impl From<Tpm2Error> for AttestationError {
     fn from(err: Tpm2Error) -> Self {
-        AttestationError {
-            message: err.to_string(),
-        }
+        let kind = match err {
+            Tpm2Error::PcrMismatch => AttestationFailureKind::MeasurementMismatch,
+            _ => AttestationFailureKind::Other,
+        };
+        AttestationError::new(err.to_string(), kind)
     }
 }
 
 impl From<Tpm2Error> for Error {
     fn from(err: Tpm2Error) -> Self {
-        Self::AttestationError(err.into())
+        AttestationError::from(err).into()
     }
 }
 
@@ -7,4 +7,13 @@ use std::time::SystemTime;
 
 pub(crate) trait Expireable {
     fn valid_at(&self, timestamp: SystemTime) -> bool;
+
+    /// The last time at which this is valid, if it's known.
+    fn valid_until(&self) -> Option<SystemTime>;
+}
+
+/// The earliest of `times`, or `None` if any of them is unknown.
+pub(crate) fn earliest(times: impl IntoIterator<Item = Option<SystemTime>>) -> Option<SystemTime> {
+    // `None` sorts before any `Some`, so an unknown time wins.
+    times.into_iter().min().flatten()
 }
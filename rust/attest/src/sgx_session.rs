@@ -94,10 +94,16 @@ pub mod testutil {
 
     pub fn handshake_from_tests_data() -> Result<Handshake> {
         // Read test data files, de-hex-stringing as necessary.
-        let mrenclave_bytes = mrenclave_bytes();
+        handshake_from_tests_data_for_mrenclave(&mrenclave_bytes())
+    }
+
+    /// Like [`handshake_from_tests_data`], but checks the evidence against `mrenclave`.
+    ///
+    /// Only succeeds if `mrenclave` is the one from [`mrenclave_bytes`].
+    pub fn handshake_from_tests_data_for_mrenclave(mrenclave: &[u8]) -> Result<Handshake> {
         let current_time = SystemTime::UNIX_EPOCH + Duration::from_millis(1655857680000);
         Ok(Handshake::for_sgx(
-            &mrenclave_bytes,
+            mrenclave,
             EVIDENCE_BYTES,
             ENDORSEMENT_BYTES,
             &[],
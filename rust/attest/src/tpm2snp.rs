@@ -120,8 +120,8 @@ fn verify_ak_cert(
     };
     chain.validate_chain(&store, &[])?;
     if !chain.valid_at(now) {
-        return Err(Error::AttestationDataError {
-            reason: "Expired certificate".to_string(),
+        return Err(Error::AttestationExpired {
+            valid_until: chain.valid_until(),
         });
     }
     akcert
@@ -171,8 +171,8 @@ fn verify_vcek_cert(
     if chain.valid_at(now) {
         Ok(vcek_cert.public_key()?)
     } else {
-        Err(Error::AttestationDataError {
-            reason: "Expired certificate".to_string(),
+        Err(Error::AttestationExpired {
+            valid_until: chain.valid_until(),
         })
     }
 }
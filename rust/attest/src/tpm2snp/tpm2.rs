@@ -32,6 +32,8 @@ pub enum Error {
     InvalidReport,
     /// Invalid PCRs
     InvalidPcrs,
+    /// PCRs don't match the expected values
+    PcrMismatch,
     /// Verification failed
     VerificationFailed,
 }
@@ -287,7 +289,7 @@ impl<'a> Pcrs<'a> {
         if is_match.into() {
             Ok(())
         } else {
-            Err(Error::InvalidPcrs)
+            Err(Error::PcrMismatch)
         }
     }
 }
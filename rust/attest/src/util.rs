@@ -5,7 +5,7 @@
 
 use std::time::SystemTime;
 
-use boring_signal::asn1::Asn1Time;
+use boring_signal::asn1::{Asn1Time, Asn1TimeRef};
 use libc::time_t;
 
 /// A replacement for [`std::collections::HashMap`] that performs linear lookups.
@@ -95,6 +95,18 @@ pub(crate) fn system_time_to_asn1_time(
     Asn1Time::from_unix(t).map_err(|_| FailedToConvertToAsn1Time)
 }
 
+pub(crate) fn asn1_time_to_system_time(timestamp: &Asn1TimeRef) -> Option<SystemTime> {
+    const DAY_SECS: u64 = 24 * 60 * 60;
+
+    let diff = Asn1Time::from_unix(0)
+        .expect("0 is valid unix time")
+        .diff(timestamp)
+        .ok()?;
+    let days = u64::try_from(diff.days).ok()?;
+    let secs = u64::try_from(diff.secs).ok()?;
+    SystemTime::UNIX_EPOCH.checked_add(std::time::Duration::from_secs(days * DAY_SECS + secs))
+}
+
 #[cfg(test)]
 mod test {
     use super::*;
@@ -10,6 +10,7 @@ use std::ffi::{c_char, c_uchar, c_uint, CString};
 use std::panic::AssertUnwindSafe;
 
 use futures_util::FutureExt;
+use libsignal_bridge::error_code::ErrorCode as _;
 use libsignal_bridge::ffi::*;
 #[cfg(feature = "libsignal-bridge-testing")]
 #[allow(unused_imports)]
@@ -58,6 +59,13 @@ pub unsafe extern "C" fn signal_free_lookup_response_entry_list(
     drop(buffer.into_box())
 }
 
+#[no_mangle]
+pub unsafe extern "C" fn signal_free_lookup_entry_list(
+    buffer: OwnedBufferOf<crate::FfiCdsiLookupEntry>,
+) {
+    drop(buffer.into_box())
+}
+
 #[no_mangle]
 pub unsafe extern "C" fn signal_free_bytestring_array(array: BytestringArray) {
     drop(array.into_boxed_parts())
@@ -117,6 +125,16 @@ pub unsafe extern "C" fn signal_error_get_type(err: *const SignalFfiError) -> u3
     }
 }
 
+/// Returns the [stable error code](libsignal_bridge::error_code) for `err`, or 0 if `err` is
+/// null.
+#[no_mangle]
+pub unsafe extern "C" fn signal_error_get_stable_code(err: *const SignalFfiError) -> u32 {
+    match err.as_ref() {
+        Some(err) => err.error_code().into(),
+        None => 0,
+    }
+}
+
 #[no_mangle]
 pub unsafe extern "C" fn signal_error_get_retry_after_seconds(
     err: *const SignalFfiError,
@@ -225,10 +243,11 @@ pub unsafe extern "C" fn signal_sealed_session_cipher_decrypt(
         let local_e164 = Option::convert_from(local_e164)?;
         let local_uuid = Option::convert_from(local_uuid)?.ok_or(NullPointerError)?;
 
-        let decrypted = sealed_sender_decrypt(
+        let decrypted = sealed_sender_decrypt_with_validation_cache(
             ctext,
             trust_root,
             Timestamp::from_epoch_millis(timestamp),
+            libsignal_bridge::protocol::sender_certificate_validation_cache(),
             local_e164,
             local_uuid,
             local_device_id.into(),
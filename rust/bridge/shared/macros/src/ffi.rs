@@ -181,8 +181,7 @@ fn bridge_io_body(
                     }));
                     ffi::FutureResultReporter::new(__future.await)
                 }
-            );
-            Ok(())
+            )
         })
     }
 }
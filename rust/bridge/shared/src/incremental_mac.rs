@@ -5,7 +5,7 @@
 
 use crypto_common::KeyInit;
 use hmac::digest::typenum::Unsigned;
-use hmac::digest::{crypto_common, OutputSizeUser};
+use hmac::digest::{crypto_common, Digest as _, OutputSizeUser};
 use hmac::Hmac;
 use libsignal_bridge_macros::*;
 use libsignal_bridge_types::incremental_mac::*;
@@ -57,6 +57,49 @@ pub fn IncrementalMac_Finalize(mac: &mut IncrementalMac) -> Vec<u8> {
         .to_vec()
 }
 
+bridge_handle_fns!(IncrementalMacAndDigest, clone = false);
+
+#[bridge_fn]
+pub fn IncrementalMacAndDigest_Initialize(key: &[u8], chunk_size: u32) -> IncrementalMacAndDigest {
+    let hmac =
+        Hmac::<Digest>::new_from_slice(key).expect("Should be able to create a new HMAC instance");
+    IncrementalMacAndDigest(Some((
+        Incremental::new(hmac, chunk_size as usize),
+        Digest::new(),
+    )))
+}
+
+/// Like [`IncrementalMac_Update`], but also feeds the bytes to the digest.
+#[bridge_fn]
+pub fn IncrementalMacAndDigest_Update(
+    mac: &mut IncrementalMacAndDigest,
+    bytes: &[u8],
+    offset: u32,
+    length: u32,
+) -> Vec<u8> {
+    let offset = offset as usize;
+    let length = length as usize;
+    let bytes = &bytes[offset..offset + length];
+    let (incremental, digest) = mac.0.as_mut().expect("MAC used after finalize");
+    digest.update(bytes);
+    incremental
+        .update(bytes)
+        .flat_map(|out| -> [u8; 32] { out.into() })
+        .collect()
+}
+
+/// Returns the final MAC (as from [`IncrementalMac_Finalize`]) followed by the digest of all the
+/// input.
+#[bridge_fn]
+pub fn IncrementalMacAndDigest_Finalize(mac: &mut IncrementalMacAndDigest) -> Vec<u8> {
+    let (incremental, digest) = mac.0.take().expect("MAC used after finalize");
+    [
+        incremental.finalize().as_slice(),
+        digest.finalize().as_slice(),
+    ]
+    .concat()
+}
+
 bridge_handle_fns!(ValidatingMac, clone = false);
 
 #[bridge_fn]
@@ -99,6 +142,8 @@ pub fn ValidatingMac_Finalize(mac: &mut ValidatingMac) -> i32 {
 
 #[cfg(test)]
 mod test {
+    use test_case::test_case;
+
     use super::*;
 
     fn find_drop_log<'a>(
@@ -118,6 +163,16 @@ mod test {
         })
     }
 
+    #[test]
+    fn mac_and_digest_drop_without_finalize() {
+        testing_logger::setup();
+        let incremental = IncrementalMacAndDigest_Initialize(&[], 32);
+        std::mem::drop(incremental);
+        testing_logger::validate(|captured_logs| {
+            assert!(find_drop_log(captured_logs).is_some());
+        })
+    }
+
     #[test]
     fn drop_with_finalize() {
         testing_logger::setup();
@@ -128,4 +183,36 @@ mod test {
             assert!(find_drop_log(captured_logs).is_none());
         })
     }
+
+    #[test_case(100, 32; "partial final chunk")]
+    #[test_case(96, 32; "chunk-aligned input")]
+    #[test_case(32, 32; "single chunk")]
+    #[test_case(0, 32; "empty input")]
+    fn mac_and_digest_matches_separate_computations(input_len: usize, chunk_size: u32) {
+        const KEY: &[u8] = b"an incremental MAC key";
+        let input: Vec<u8> = (0..input_len).map(|i| i as u8).collect();
+
+        let mut expected_mac = IncrementalMac_Initialize(KEY, chunk_size);
+        let mut mac_and_digest = IncrementalMacAndDigest_Initialize(KEY, chunk_size);
+        let mut expected_macs = vec![];
+        let mut macs = vec![];
+        // Feed the input in uneven pieces so updates don't line up with chunk boundaries.
+        for piece in input.chunks(7) {
+            let len = piece.len() as u32;
+            expected_macs.extend(IncrementalMac_Update(&mut expected_mac, piece, 0, len));
+            macs.extend(IncrementalMacAndDigest_Update(
+                &mut mac_and_digest,
+                piece,
+                0,
+                len,
+            ));
+        }
+        expected_macs.extend(IncrementalMac_Finalize(&mut expected_mac));
+        let finalized = IncrementalMacAndDigest_Finalize(&mut mac_and_digest);
+        let (final_mac, digest) = finalized.split_at(finalized.len() - 32);
+        macs.extend(final_mac);
+
+        assert_eq!(macs, expected_macs);
+        assert_eq!(digest, Digest::digest(&input).as_slice());
+    }
 }
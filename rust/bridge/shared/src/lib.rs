@@ -11,7 +11,7 @@ compile_error!("Feature \"ffi\", \"jni\", or \"node\" must be enabled for this c
 
 pub use libsignal_bridge_types::{
     bridge_as_handle, bridge_deserialize, bridge_fixed_length_serializable_fns, bridge_get,
-    bridge_handle_fns, bridge_serializable_handle_fns, describe_panic, io, support,
+    bridge_handle_fns, bridge_serializable_handle_fns, describe_panic, error_code, io, support,
 };
 #[cfg(feature = "ffi")]
 pub use libsignal_bridge_types::{ffi, ffi_arg_type, ffi_result_type};
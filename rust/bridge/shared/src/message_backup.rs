@@ -6,15 +6,18 @@
 use libsignal_bridge_macros::*;
 use libsignal_bridge_types::message_backup::*;
 use libsignal_message_backup::backup::Purpose;
-use libsignal_message_backup::frame::LimitedReaderFactory;
-use libsignal_message_backup::{BackupReader, ReadResult};
+use libsignal_message_backup::frame::{write_encrypted, CompressionConfig, LimitedReaderFactory};
+use libsignal_message_backup::{BackupProgress, BackupReader, ReadResult};
 use libsignal_protocol::Aci;
+use rand::rngs::OsRng;
+use rand::Rng as _;
 
-use crate::io::{AsyncInput, InputStream};
+use crate::io::{AsyncInput, InputStream, InputStreamRead, OutputStream};
 use crate::support::*;
 use crate::*;
 
 bridge_handle_fns!(MessageBackupKey, clone = false);
+bridge_handle_fns!(BackupKeyMaterial, clone = false);
 bridge_handle_fns!(
     MessageBackupValidationOutcome,
     clone = false,
@@ -27,11 +30,39 @@ fn MessageBackupKey_New(master_key: &[u8; 32], aci: Aci) -> MessageBackupKey {
     MessageBackupKey::new(master_key, aci)
 }
 
+#[bridge_fn]
+fn MessageBackupKey_FromKeyMaterial(material: &BackupKeyMaterial) -> MessageBackupKey {
+    material.into()
+}
+
+#[bridge_fn]
+fn BackupKeyMaterial_New(master_key: &[u8; 32], aci: Aci) -> BackupKeyMaterial {
+    BackupKeyMaterial::new(master_key, aci)
+}
+
+#[bridge_fn]
+fn BackupKeyMaterial_GetBackupKey(material: &BackupKeyMaterial) -> [u8; 32] {
+    *material.backup_key().as_bytes()
+}
+
+#[bridge_fn]
+fn BackupKeyMaterial_GetBackupId(material: &BackupKeyMaterial) -> [u8; 16] {
+    *material.backup_id().as_bytes()
+}
+
 #[bridge_fn(jni = false, node = false)]
 fn MessageBackupValidationOutcome_getErrorMessage(
     outcome: &MessageBackupValidationOutcome,
 ) -> Option<&str> {
-    outcome.error_message.as_deref()
+    outcome.error.as_ref().map(|e| e.message.as_str())
+}
+
+/// Returns a [`MessageBackupValidationErrorKind`] value.
+///
+/// Only meaningful if the outcome has an error message.
+#[bridge_fn(jni = false, node = false)]
+fn MessageBackupValidationOutcome_getErrorKind(outcome: &MessageBackupValidationOutcome) -> u8 {
+    outcome.error.as_ref().map_or(0, |e| e.kind as u8)
 }
 
 #[bridge_fn(jni = false, node = false)]
@@ -52,6 +83,86 @@ async fn MessageBackupValidator_Validate(
     second_stream: &mut dyn InputStream,
     len: u64,
     purpose: AsType<Purpose, u8>,
+) -> Result<MessageBackupValidationOutcome, std::io::Error> {
+    validate(key, first_stream, second_stream, len, purpose, |_| ()).await
+}
+
+#[bridge_fn]
+async fn MessageBackupValidator_ValidateWithProgress(
+    key: &MessageBackupKey,
+    first_stream: &mut dyn InputStream,
+    second_stream: &mut dyn InputStream,
+    len: u64,
+    purpose: AsType<Purpose, u8>,
+    progress_listener: &mut dyn BackupProgressListener,
+) -> Result<MessageBackupValidationOutcome, std::io::Error> {
+    let progress_listener = &*progress_listener;
+    validate(key, first_stream, second_stream, len, purpose, |progress| {
+        progress_listener.on_progress(
+            progress.frames_read,
+            progress.bytes_read,
+            // The bridged reader is always reading an encrypted backup of known length.
+            progress.total_bytes.unwrap_or(len),
+        )
+    })
+    .await
+}
+
+/// How many bytes are requested from or handed to a platform stream at a time.
+const STREAM_CHUNK_SIZE: usize = 64 * 1024;
+
+/// Compresses, pads, and encrypts the frames read from `frames_source`, writing the result to
+/// `output_stream`.
+///
+/// The frames must be serialized the way [`BackupReader`] reads them: the backup info followed by
+/// each frame, every one prefixed with its varint-encoded length. The output is the same format
+/// that [`MessageBackupValidator_Validate`] checks.
+#[bridge_fn]
+async fn MessageBackup_WriteEncrypted(
+    frames_source: &mut dyn InputStream,
+    key: &MessageBackupKey,
+    output_stream: &mut dyn OutputStream,
+) -> Result<(), std::io::Error> {
+    let frames = read_to_end(frames_source).await?;
+
+    // The encryption itself happens in memory; only the input and output are streamed.
+    let iv: [u8; 16] = OsRng.gen();
+    let encrypted = write_encrypted(&key.0, &iv, &frames, CompressionConfig::default(), true).await;
+
+    for chunk in encrypted.chunks(STREAM_CHUNK_SIZE) {
+        output_stream.write(chunk).await?;
+    }
+    output_stream.flush().await
+}
+
+async fn read_to_end(stream: &dyn InputStream) -> std::io::Result<Vec<u8>> {
+    let mut contents = Vec::new();
+    let mut buf = vec![0; STREAM_CHUNK_SIZE];
+    loop {
+        let amount_read = match stream.read(&mut buf)? {
+            InputStreamRead::Ready { amount_read } => {
+                contents.extend_from_slice(&buf[..amount_read]);
+                amount_read
+            }
+            InputStreamRead::Pending(read) => {
+                let data = read.await?;
+                contents.extend_from_slice(&data);
+                data.len()
+            }
+        };
+        if amount_read == 0 {
+            return Ok(contents);
+        }
+    }
+}
+
+async fn validate(
+    key: &MessageBackupKey,
+    first_stream: &mut dyn InputStream,
+    second_stream: &mut dyn InputStream,
+    len: u64,
+    purpose: AsType<Purpose, u8>,
+    on_progress: impl Fn(BackupProgress),
 ) -> Result<MessageBackupValidationOutcome, std::io::Error> {
     let streams = [
         AsyncInput::new(first_stream, len),
@@ -66,21 +177,27 @@ async fn MessageBackupValidator_Validate(
                 let ReadResult {
                     result,
                     found_unknown_fields,
-                } = reader.validate_all().await;
+                    found_oversized_frames: _,
+                    found_duplicate_chat_items: _,
+                    found_expire_timer_version_conflicts: _,
+                    backup_time_warning: _,
+                    policy_flags: _,
+                    backup_version: _,
+                } = reader.with_progress(on_progress).validate_all().await;
 
                 (result.err().map(Into::into), found_unknown_fields)
             }
         };
 
-    let error_message = error
-        .map(|m| match m {
+    let error = error
+        .map(|e| match e {
             MessageBackupValidationError::Io(io) => Err(io),
-            MessageBackupValidationError::String(msg) => Ok(msg),
+            MessageBackupValidationError::Invalid(failure) => Ok(failure),
         })
         .transpose()?;
 
     Ok(MessageBackupValidationOutcome {
-        error_message,
+        error,
         found_unknown_fields,
     })
 }
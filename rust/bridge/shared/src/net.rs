@@ -4,13 +4,27 @@
 //
 
 use std::convert::TryInto as _;
+use std::net::IpAddr;
 use std::num::{NonZeroU16, NonZeroU32};
+use std::time::Duration;
 
 use base64::prelude::{Engine, BASE64_STANDARD};
 use libsignal_bridge_macros::{bridge_fn, bridge_io};
-use libsignal_bridge_types::net::Svr3Clients;
+use libsignal_bridge_types::net::svr3_retry::Svr3RetryConfig;
 pub use libsignal_bridge_types::net::{ConnectionManager, Environment, TokioAsyncContext};
+use libsignal_bridge_types::net::{PinMigrationOutcome, Svr2Connector, Svr3Clients};
 use libsignal_net::auth::Auth;
+use libsignal_net::certs::{add_supplemental_root_der, trusted_root_fingerprints};
+use libsignal_net::env::EnvBuilder;
+use libsignal_net::infra::host::Host;
+use libsignal_net::infra::traffic_stats::{
+    TrafficCounts, TrafficKind, TrafficSnapshot, TrafficStats,
+};
+use libsignal_net::network_hint::{NetworkHint, NetworkTransport};
+use libsignal_net::proxy::{
+    parse_proxy_url, set_global_proxy, set_invalid_global_proxy, ProxyConfig,
+};
+use libsignal_net::svr::{migrate_pin_to_svr3, resume_pin_migration};
 use libsignal_net::svr3::traits::*;
 use libsignal_net::svr3::{self, migrate_backup, restore_with_fallback, OpaqueMaskedShareSet};
 use rand::rngs::OsRng;
@@ -18,11 +32,13 @@ use rand::rngs::OsRng;
 use crate::support::*;
 use crate::*;
 
+pub(crate) mod cdn;
 pub(crate) mod cdsi;
 pub(crate) mod chat;
 mod tokio;
 
 bridge_handle_fns!(ConnectionManager, clone = false);
+bridge_handle_fns!(PinMigrationOutcome, clone = false);
 
 #[bridge_fn]
 fn ConnectionManager_new(
@@ -32,6 +48,40 @@ fn ConnectionManager_new(
     ConnectionManager::new(environment.into_inner(), user_agent)
 }
 
+/// Creates a ConnectionManager that talks to the given chat and CDSI servers instead of
+/// `environment`'s.
+///
+/// All other services are taken from `environment`. If `root_certificate_der` is empty, Signal's
+/// root certificates are trusted for the given servers.
+#[bridge_fn]
+fn ConnectionManager_new_custom(
+    environment: AsType<Environment, u8>,
+    user_agent: String,
+    chat_host: String,
+    chat_port: i32,
+    cdsi_host: String,
+    cdsi_port: i32,
+    cdsi_mr_enclave: &[u8],
+    root_certificate_der: &[u8],
+) -> Result<ConnectionManager, std::io::Error> {
+    let port = |port: i32| {
+        u16::try_from(port)
+            .ok()
+            .and_then(NonZeroU16::new)
+            .ok_or(std::io::ErrorKind::InvalidInput)
+    };
+    let mut builder = EnvBuilder::with_base(environment.into_inner().env())
+        .chat(chat_host, port(chat_port)?)
+        .cdsi(cdsi_host, port(cdsi_port)?, cdsi_mr_enclave);
+    if !root_certificate_der.is_empty() {
+        builder = builder.root_certificate_der(root_certificate_der);
+    }
+    let env = builder
+        .build()
+        .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidInput, e.to_string()))?;
+    Ok(ConnectionManager::new_with_env(&env, user_agent))
+}
+
 #[bridge_fn]
 fn ConnectionManager_set_proxy(
     connection_manager: &ConnectionManager,
@@ -54,11 +104,193 @@ fn ConnectionManager_set_ipv6_enabled(connection_manager: &ConnectionManager, ip
     connection_manager.set_ipv6_enabled(ipv6_enabled)
 }
 
+/// Tunes subsequent chat connections for the kind of network the device is on.
+///
+/// `transport` is 0 for unknown, 1 for Wi-Fi, and 2 for cellular; `constrained` is whether the
+/// network is metered or has background data restricted.
+///
+/// Only bridged for Java so far.
+#[bridge_fn(ffi = false, node = false)]
+fn ConnectionManager_set_chat_network_hint(
+    connection_manager: &ConnectionManager,
+    transport: AsType<NetworkTransport, u8>,
+    constrained: bool,
+) {
+    connection_manager.set_chat_network_hint(NetworkHint {
+        transport: transport.into_inner(),
+        constrained,
+    })
+}
+
+/// Makes `hostname` resolve to the given comma-separated IP addresses instead of being looked up.
+///
+/// An empty list removes the override for `hostname`.
+#[bridge_fn]
+fn ConnectionManager_set_static_dns_entry(
+    connection_manager: &ConnectionManager,
+    hostname: String,
+    ip_addresses: String,
+) -> Result<(), std::io::Error> {
+    let addresses = ip_addresses
+        .split(',')
+        .map(str::trim)
+        .filter(|address| !address.is_empty())
+        .map(|address| address.parse::<IpAddr>())
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|_| std::io::ErrorKind::InvalidInput)?;
+    connection_manager.set_static_dns_entry(&hostname, addresses);
+    Ok(())
+}
+
+/// Sets how many times SVR3 operations try connecting to each enclave, and after how many
+/// consecutive failures connections to that kind of enclave stop being attempted for a while.
+#[bridge_fn]
+fn ConnectionManager_set_svr3_retry_config(
+    connection_manager: &ConnectionManager,
+    max_attempts: AsType<NonZeroU32, u32>,
+    failure_threshold: AsType<NonZeroU32, u32>,
+) {
+    connection_manager.set_svr3_retry_config(Svr3RetryConfig {
+        max_attempts: max_attempts.into_inner(),
+        failure_threshold: failure_threshold.into_inner(),
+        ..Default::default()
+    })
+}
+
 #[bridge_fn]
 fn ConnectionManager_on_network_change(connection_manager: &ConnectionManager) {
     connection_manager.on_network_change()
 }
 
+/// Returns the route cooldowns of `connection_manager`, for the app to save until its next launch.
+#[bridge_fn]
+fn ConnectionManager_export_cooldown_state(connection_manager: &ConnectionManager) -> Vec<u8> {
+    libsignal_net::connection_manager_state::serialize(&connection_manager.export_cooldown_state())
+}
+
+/// Restores route cooldowns saved by `ConnectionManager_export_cooldown_state`.
+///
+/// Saved state that can't be read is ignored, since it's only an optimization.
+#[bridge_fn]
+fn ConnectionManager_import_cooldown_state(connection_manager: &ConnectionManager, state: &[u8]) {
+    match libsignal_net::connection_manager_state::deserialize(state) {
+        Ok(state) => connection_manager.import_cooldown_state(&state),
+        Err(e) => log::warn!("ignoring invalid connection manager state: {e}"),
+    }
+}
+
+/// Checks whether chat, CDSI, and the CDNs can be reached, without using any credentials.
+///
+/// Returns the report as JSON.
+#[bridge_io(TokioAsyncContext)]
+async fn ConnectionManager_run_connectivity_report(
+    connection_manager: &ConnectionManager,
+    timeout_millis: u32,
+) -> Vec<u8> {
+    let report = connection_manager
+        .connectivity_report(Duration::from_millis(timeout_millis.into()))
+        .await;
+    serde_json::to_vec(&report).expect("can serialize the report")
+}
+
+/// Routes all new connections, from every ConnectionManager, through the given TLS proxy.
+///
+/// Existing connections are treated as if the network changed, so they will be re-established
+/// through the proxy.
+#[bridge_fn]
+fn SetGlobalProxy(host: String, port: i32) -> Result<(), std::io::Error> {
+    // See ConnectionManager_set_proxy for why this is an i32.
+    let Some(port) = u16::try_from(port).ok().and_then(NonZeroU16::new) else {
+        // Fail closed: connections shouldn't silently bypass a proxy the user asked for.
+        set_invalid_global_proxy();
+        return Err(std::io::ErrorKind::InvalidInput.into());
+    };
+    set_global_proxy(Some(ProxyConfig {
+        host: Host::parse_as_ip_or_domain(&host),
+        port,
+    }));
+    Ok(())
+}
+
+/// Parses a Signal proxy link (`https://signal.tube/#<host>` or `sgnl://proxy?host=<host>`).
+///
+/// Returns the proxy address as `host:port`, with the host normalized the way it will be used to
+/// connect.
+#[bridge_fn]
+fn ParseProxyUrl(url: String) -> Result<String, std::io::Error> {
+    let ProxyConfig { host, port } = parse_proxy_url(&url)
+        .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidInput, e.to_string()))?;
+    Ok(format!("{host}:{port}"))
+}
+
+/// Like SetGlobalProxy, but takes a Signal proxy link, as accepted by ParseProxyUrl.
+#[bridge_fn]
+fn SetGlobalProxyFromUrl(url: String) -> Result<(), std::io::Error> {
+    match parse_proxy_url(&url) {
+        Ok(proxy) => {
+            set_global_proxy(Some(proxy));
+            Ok(())
+        }
+        Err(e) => {
+            // Fail closed, as in SetGlobalProxy.
+            set_invalid_global_proxy();
+            Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidInput,
+                e.to_string(),
+            ))
+        }
+    }
+}
+
+#[bridge_fn]
+fn ClearGlobalProxy() {
+    set_global_proxy(None)
+}
+
+/// Trusts an additional root certificate (DER-encoded) for all new connections to Signal's
+/// servers, in case the servers have moved to a root this build doesn't know about.
+#[bridge_fn]
+fn AddSupplementalRootCertificate(der: &[u8]) -> Result<(), std::io::Error> {
+    add_supplemental_root_der(der)
+        .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidInput, e))
+}
+
+/// Returns the SHA-256 fingerprints of all trusted roots, concatenated.
+#[bridge_fn]
+fn TrustedRootCertificateFingerprints() -> Vec<u8> {
+    trusted_root_fingerprints().concat()
+}
+
+/// Returns the bytes sent and received by each kind of connection since the process started or
+/// TrafficStats_reset was last called.
+///
+/// The result is the counts for each kind in the order chat, CDSI, SVR, CDN. Each kind gets 16
+/// bytes: the bytes sent, then the bytes received, as big-endian u64s.
+#[bridge_fn]
+fn TrafficStats_snapshot() -> [u8; 64] {
+    traffic_snapshot_to_bytes(TrafficStats::global().snapshot())
+}
+
+/// Starts the counts reported by TrafficStats_snapshot over from zero, returning what they were in
+/// the same form.
+#[bridge_fn]
+fn TrafficStats_reset() -> [u8; 64] {
+    traffic_snapshot_to_bytes(TrafficStats::global().reset())
+}
+
+fn traffic_snapshot_to_bytes(snapshot: TrafficSnapshot) -> [u8; 64] {
+    // A new kind changes the layout, which each client has to be updated for.
+    const _: () = assert!(TrafficKind::ALL.len() * 16 == 64);
+    let mut bytes = [0; 64];
+    for (chunk, (_kind, TrafficCounts { sent, received })) in
+        bytes.chunks_exact_mut(16).zip(snapshot.iter())
+    {
+        chunk[..8].copy_from_slice(&sent.to_be_bytes());
+        chunk[8..].copy_from_slice(&received.to_be_bytes());
+    }
+    bytes
+}
+
 #[bridge_fn]
 fn CreateOTP(username: String, secret: &[u8]) -> String {
     Auth::otp(&username, secret, std::time::SystemTime::now())
@@ -145,17 +377,37 @@ async fn Svr3Restore(
     Ok(restored_secret.serialize())
 }
 
+/// Removes the user's backup from the current set of SVR3 enclaves.
+///
+/// The result has one byte per enclave, `1` if the data is gone from that enclave and `0` if it
+/// could not be removed. Fails only if it could not be removed from any of them.
 #[bridge_io(TokioAsyncContext)]
 async fn Svr3Remove(
     connection_manager: &ConnectionManager,
     username: String,         // hex-encoded uid
     enclave_password: String, // timestamp:otp(...)
-) -> Result<(), svr3::Error> {
+) -> Result<Vec<u8>, svr3::Error> {
     // Removal assumes that any migration that needed to happen already happened,
     // and, just like with `backup`, it is always performed on the current set
     // of SVR3 enclaves.
     let client = Svr3Clients::new(connection_manager, username, enclave_password).current;
-    client.remove().await
+    let result = client.remove().await?;
+    Ok(result.removed.into_iter().map(u8::from).collect())
+}
+
+/// Checks whether the current set of SVR3 enclaves has a backup for the user, without consuming a
+/// restore attempt.
+///
+/// The result is a flags byte (`0x1` if every enclave has a share, `0x2` if only some do),
+/// followed by the big-endian tries remaining if any enclave has a share.
+#[bridge_io(TokioAsyncContext)]
+async fn Svr3Query(
+    connection_manager: &ConnectionManager,
+    username: String,         // hex-encoded uid
+    enclave_password: String, // timestamp:otp(...)
+) -> Result<Vec<u8>, svr3::Error> {
+    let client = Svr3Clients::new(connection_manager, username, enclave_password).current;
+    Ok(client.query().await?.serialize())
 }
 
 #[bridge_io(TokioAsyncContext, node = false)]
@@ -174,6 +426,80 @@ async fn Svr3Rotate(
     client.rotate(share_set, &mut rng).await
 }
 
+/// Moves the user's secret from SVR2 to SVR3, deleting it from SVR2 once it has been backed up.
+///
+/// `pin` is used both to restore from SVR2 and as the password for the SVR3 backup. Failure to
+/// restore from SVR2 is reported as an error; after that point, the returned outcome says
+/// whether the migration completed, and can be passed to `Svr2ResumeMigrationToSvr3` if not.
+#[bridge_io(TokioAsyncContext)]
+async fn Svr2MigrateToSvr3(
+    connection_manager: &ConnectionManager,
+    pin: String,
+    max_tries: AsType<NonZeroU32, u32>,
+    svr2_username: String,
+    svr2_password: String,
+    svr3_username: String,
+    svr3_password: String,
+) -> Result<PinMigrationOutcome, svr3::Error> {
+    let mut rng = OsRng;
+    let svr2 = Svr2Connector::new(connection_manager, svr2_username, svr2_password);
+    let svr3 = Svr3Clients::new(connection_manager, svr3_username, svr3_password).current;
+    let result = migrate_pin_to_svr3(&svr2, &svr3, &pin, max_tries.into_inner(), &mut rng).await;
+    PinMigrationOutcome::new(result)
+}
+
+/// Continues a migration that previously stopped partway through.
+///
+/// The arguments must match the original call to `Svr2MigrateToSvr3`.
+#[bridge_io(TokioAsyncContext)]
+async fn Svr2ResumeMigrationToSvr3(
+    connection_manager: &ConnectionManager,
+    previous_outcome: &PinMigrationOutcome,
+    pin: String,
+    max_tries: AsType<NonZeroU32, u32>,
+    svr2_username: String,
+    svr2_password: String,
+    svr3_username: String,
+    svr3_password: String,
+) -> Result<PinMigrationOutcome, svr3::Error> {
+    let Some(step) = previous_outcome.resume_from() else {
+        // Already complete; there is nothing left to do.
+        return Ok(previous_outcome.clone());
+    };
+    let mut rng = OsRng;
+    let svr2 = Svr2Connector::new(connection_manager, svr2_username, svr2_password);
+    let svr3 = Svr3Clients::new(connection_manager, svr3_username, svr3_password).current;
+    let result =
+        resume_pin_migration(step, &svr2, &svr3, &pin, max_tries.into_inner(), &mut rng).await;
+    PinMigrationOutcome::new(result)
+}
+
+#[bridge_fn]
+fn PinMigrationOutcome_IsComplete(outcome: &PinMigrationOutcome) -> bool {
+    outcome.resume_from().is_none()
+}
+
+#[bridge_fn]
+fn PinMigrationOutcome_GetSecret(outcome: &PinMigrationOutcome) -> [u8; 32] {
+    outcome.secret()
+}
+
+/// Returns an empty buffer if the secret hasn't been backed up to SVR3 yet.
+///
+/// A serialized share set is never empty.
+#[bridge_fn]
+fn PinMigrationOutcome_GetShareSet(outcome: &PinMigrationOutcome) -> Vec<u8> {
+    outcome
+        .share_set()
+        .map(|share_set| share_set.serialize().expect("can serialize the share set"))
+        .unwrap_or_default()
+}
+
+#[bridge_fn]
+fn PinMigrationOutcome_GetErrorMessage(outcome: &PinMigrationOutcome) -> Option<String> {
+    outcome.error_message().map(str::to_owned)
+}
+
 #[cfg(test)]
 mod test {
     use test_case::test_case;
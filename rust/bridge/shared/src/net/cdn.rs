@@ -0,0 +1,40 @@
+//
+// Copyright 2024 Signal Messenger, LLC.
+// SPDX-License-Identifier: AGPL-3.0-only
+//
+
+use libsignal_bridge_macros::bridge_io;
+use libsignal_bridge_types::net::{ConnectionManager, TokioAsyncContext};
+use libsignal_net::cdn::{DownloadError, DownloadRequest};
+
+use crate::support::*;
+use crate::*;
+
+/// Downloads a whole attachment into memory, checking it against its SHA-256 digest.
+///
+/// Transient failures are retried internally, resuming from where the previous attempt left off.
+#[bridge_io(TokioAsyncContext)]
+async fn Cdn_DownloadAttachment(
+    connection_manager: &ConnectionManager,
+    cdn_number: u32,
+    cdn_key: String,
+    expected_digest: Box<[u8]>,
+) -> Result<Vec<u8>, DownloadError> {
+    let expected_digest = <[u8; 32]>::try_from(&*expected_digest)
+        .map_err(|_| DownloadError::InvalidArgument("expected digest must be 32 bytes"))?;
+    let mut attachment: Vec<u8> = vec![];
+    connection_manager
+        .attachment_downloader()
+        .download(
+            DownloadRequest {
+                cdn_number,
+                cdn_key: &cdn_key,
+                resume_offset: 0,
+                expected_digest: Some(expected_digest),
+            },
+            &mut attachment,
+            |_| {},
+        )
+        .await?;
+    Ok(attachment)
+}
@@ -4,14 +4,18 @@
 //
 
 use std::convert::TryInto as _;
+use std::time::Duration;
 
 use libsignal_bridge_macros::{bridge_fn, bridge_io};
-use libsignal_bridge_types::net::cdsi::{CdsiLookup, LookupRequest};
+use libsignal_bridge_types::net::cdsi::{
+    capture_attestation_evidence, CdsiLookup, LookupRequest, LookupResponseEntries,
+};
 use libsignal_bridge_types::net::{ConnectionManager, TokioAsyncContext};
 use libsignal_core::E164;
 use libsignal_net::auth::Auth;
 use libsignal_net::cdsi::{self, AciAndAccessKey, LookupResponse};
 use libsignal_protocol::{Aci, SignalProtocolError};
+use tokio::time::Instant;
 
 use crate::support::*;
 use crate::*;
@@ -61,6 +65,11 @@ fn LookupRequest_setReturnAcisWithoutUaks(request: &LookupRequest, return_acis_w
     request.lock().return_acis_without_uaks = return_acis_without_uaks;
 }
 
+#[bridge_fn]
+fn LookupRequest_permitCostEstimate(request: &LookupRequest) -> u32 {
+    request.lock().permit_cost_estimate()
+}
+
 bridge_handle_fns!(CdsiLookup, clone = false);
 
 #[bridge_io(TokioAsyncContext)]
@@ -69,11 +78,29 @@ async fn CdsiLookup_new(
     username: String,
     password: String,
     request: &LookupRequest,
+    timeout_millis: u32,
 ) -> Result<CdsiLookup, cdsi::LookupError> {
     let request = std::mem::take(&mut *request.lock());
     let auth = Auth { username, password };
+    let deadline = Instant::now() + Duration::from_millis(timeout_millis.into());
 
-    CdsiLookup::new(connection_manager, auth, request).await
+    CdsiLookup::new(connection_manager, auth, request, deadline).await
+}
+
+/// Connects to CDSI, returns the raw attestation evidence it presented, and disconnects.
+///
+/// This is for archiving the evidence for each enclave version; no lookup is made.
+#[bridge_io(TokioAsyncContext)]
+async fn Cdsi_CaptureAttestationEvidence(
+    connection_manager: &ConnectionManager,
+    username: String,
+    password: String,
+    timeout_millis: u32,
+) -> Result<Vec<u8>, cdsi::LookupError> {
+    let auth = Auth { username, password };
+    let deadline = Instant::now() + Duration::from_millis(timeout_millis.into());
+
+    capture_attestation_evidence(connection_manager, auth, deadline).await
 }
 
 #[bridge_fn]
@@ -89,3 +116,16 @@ async fn CdsiLookup_complete(lookup: &CdsiLookup) -> Result<LookupResponse, cdsi
         .collect()
         .await
 }
+
+/// Like `CdsiLookup_complete`, but returns the records as a list with explicitly optional IDs.
+#[bridge_io(TokioAsyncContext)]
+async fn CdsiLookup_completeEntries(
+    lookup: &CdsiLookup,
+) -> Result<LookupResponseEntries, cdsi::LookupError> {
+    let response = lookup
+        .take_remaining()
+        .expect("not completed yet")
+        .collect()
+        .await?;
+    Ok(response.into())
+}
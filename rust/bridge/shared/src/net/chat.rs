@@ -12,9 +12,14 @@ use libsignal_bridge_types::net::chat::*;
 use libsignal_bridge_types::net::{ConnectionManager, TokioAsyncContext};
 use libsignal_bridge_types::support::AsType;
 use libsignal_net::auth::Auth;
+use libsignal_net::chat::challenge::{
+    parse_rate_limit_challenge_response, RateLimitChallenge, RateLimitChallengeError,
+};
+use libsignal_net::chat::multi_recipient::{MismatchedDevices, MultiRecipientSendResponse};
 use libsignal_net::chat::{
     self, ChatServiceError, DebugInfo as ChatServiceDebugInfo, Request, Response as ChatResponse,
 };
+use libsignal_protocol::{DeviceId, ServiceId, SignalProtocolError};
 
 use crate::support::*;
 use crate::*;
@@ -22,6 +27,9 @@ use crate::*;
 bridge_handle_fns!(AuthChat, clone = false);
 bridge_handle_fns!(UnauthChat, clone = false);
 bridge_handle_fns!(HttpRequest, clone = false);
+bridge_handle_fns!(MultiRecipientMismatchedDevices, clone = false);
+bridge_handle_fns!(RegistrationLockFailure, clone = false);
+bridge_handle_fns!(UnauthChatPool, clone = false);
 
 #[bridge_fn(ffi = false)]
 fn HttpRequest_new(
@@ -77,6 +85,21 @@ fn ChatService_new_auth(
     )
 }
 
+/// Only bridged for Swift so far; Java and Node apps still pass fixed credentials to
+/// [`ChatService_new_auth`].
+#[bridge_fn(jni = false, node = false)]
+fn ChatService_new_auth_with_provider(
+    connection_manager: &ConnectionManager,
+    auth_provider: &mut dyn MakeChatAuthProvider,
+    receive_stories: bool,
+) -> AuthChat {
+    Chat::new_auth(
+        connection_manager,
+        auth_provider.make_auth_provider(),
+        receive_stories,
+    )
+}
+
 #[bridge_io(TokioAsyncContext)]
 async fn ChatService_disconnect_unauth(chat: &UnauthChat) {
     chat.service.0.disconnect().await
@@ -87,6 +110,22 @@ async fn ChatService_disconnect_auth(chat: &AuthChat) {
     chat.service.0.disconnect().await
 }
 
+/// Returns `None` when there's no estimate; see [`chat::ChatService::rtt_estimate`].
+fn rtt_estimate_millis(rtt: Option<Duration>) -> Option<u32> {
+    // u32::MAX is how None is passed to Swift and Java.
+    rtt.map(|rtt| rtt.as_millis().try_into().unwrap_or(u32::MAX - 1))
+}
+
+#[bridge_fn]
+fn ChatService_rtt_estimate_millis_unauth(chat: &UnauthChat) -> Option<u32> {
+    rtt_estimate_millis(chat.service.0.rtt_estimate_unauthenticated())
+}
+
+#[bridge_fn]
+fn ChatService_rtt_estimate_millis_auth(chat: &AuthChat) -> Option<u32> {
+    rtt_estimate_millis(chat.service.0.rtt_estimate_authenticated())
+}
+
 #[bridge_io(TokioAsyncContext)]
 async fn ChatService_connect_unauth(
     chat: &UnauthChat,
@@ -189,6 +228,37 @@ async fn ChatService_auth_send_and_debug(
     })
 }
 
+#[bridge_fn]
+fn UnauthChatPool_new(
+    async_runtime: &TokioAsyncContext,
+    connection_manager: &ConnectionManager,
+) -> UnauthChatPool {
+    UnauthChatPool::new(connection_manager, async_runtime)
+}
+
+#[bridge_io(TokioAsyncContext)]
+async fn UnauthChatPool_send(
+    pool: &UnauthChatPool,
+    http_request: &HttpRequest,
+    timeout_millis: u32,
+) -> Result<ChatResponse, ChatServiceError> {
+    let headers = http_request.headers.lock().expect("not poisoned").clone();
+    let request = chat::Request {
+        method: http_request.method.clone(),
+        path: http_request.path.clone(),
+        headers,
+        body: http_request.body.clone(),
+    };
+    pool.0
+        .send_and_release(request, Duration::from_millis(timeout_millis.into()))
+        .await
+}
+
+#[bridge_fn]
+fn UnauthChatPool_shutdown(pool: &UnauthChatPool) {
+    pool.0.shutdown()
+}
+
 #[bridge_fn(jni = false)]
 fn ChatService_SetListenerAuth(
     runtime: &TokioAsyncContext,
@@ -221,6 +291,168 @@ fn ChatService_SetListenerUnauth(
     chat.set_listener(listener, runtime)
 }
 
+#[bridge_fn]
+fn MultiRecipientSendResponse_ParseUnregistered(
+    body: &[u8],
+) -> Result<Box<[Vec<u8>]>, SignalProtocolError> {
+    let MultiRecipientSendResponse { unregistered } =
+        MultiRecipientSendResponse::from_body(body)
+            .map_err(|e| SignalProtocolError::InvalidArgument(e.to_string()))?;
+    Ok(unregistered
+        .into_iter()
+        .map(|id| id.service_id_fixed_width_binary().to_vec())
+        .collect())
+}
+
+#[bridge_fn]
+fn MultiRecipientMismatchedDevices_Parse(
+    body: &[u8],
+) -> Result<MultiRecipientMismatchedDevices, SignalProtocolError> {
+    MismatchedDevices::list_from_body(body)
+        .map(MultiRecipientMismatchedDevices)
+        .map_err(|e| SignalProtocolError::InvalidArgument(e.to_string()))
+}
+
+#[bridge_fn]
+fn MultiRecipientMismatchedDevices_Count(mismatched: &MultiRecipientMismatchedDevices) -> u32 {
+    mismatched
+        .0
+        .len()
+        .try_into()
+        .expect("bounded by the response size")
+}
+
+fn mismatched_devices_entry(
+    mismatched: &MultiRecipientMismatchedDevices,
+    index: u32,
+) -> Result<&MismatchedDevices, SignalProtocolError> {
+    usize::try_from(index)
+        .ok()
+        .and_then(|index| mismatched.0.get(index))
+        .ok_or_else(|| SignalProtocolError::InvalidArgument(format!("no entry at index {index}")))
+}
+
+/// Device IDs are returned as bytes; the server never assigns IDs above 127.
+fn device_ids_as_bytes(ids: &[DeviceId]) -> Result<Vec<u8>, SignalProtocolError> {
+    ids.iter()
+        .map(|&id| {
+            u8::try_from(u32::from(id)).map_err(|_| {
+                SignalProtocolError::InvalidArgument(format!("invalid device ID {id}"))
+            })
+        })
+        .collect()
+}
+
+#[bridge_fn]
+fn MultiRecipientMismatchedDevices_GetServiceId(
+    mismatched: &MultiRecipientMismatchedDevices,
+    index: u32,
+) -> Result<ServiceId, SignalProtocolError> {
+    Ok(mismatched_devices_entry(mismatched, index)?.account)
+}
+
+#[bridge_fn]
+fn MultiRecipientMismatchedDevices_GetMissingDevices(
+    mismatched: &MultiRecipientMismatchedDevices,
+    index: u32,
+) -> Result<Vec<u8>, SignalProtocolError> {
+    device_ids_as_bytes(&mismatched_devices_entry(mismatched, index)?.missing_devices)
+}
+
+#[bridge_fn]
+fn MultiRecipientMismatchedDevices_GetExtraDevices(
+    mismatched: &MultiRecipientMismatchedDevices,
+    index: u32,
+) -> Result<Vec<u8>, SignalProtocolError> {
+    device_ids_as_bytes(&mismatched_devices_entry(mismatched, index)?.extra_devices)
+}
+
+#[bridge_fn]
+fn MultiRecipientMismatchedDevices_GetStaleDevices(
+    mismatched: &MultiRecipientMismatchedDevices,
+    index: u32,
+) -> Result<Vec<u8>, SignalProtocolError> {
+    device_ids_as_bytes(&mismatched_devices_entry(mismatched, index)?.stale_devices)
+}
+
+#[bridge_fn]
+fn RegistrationLockFailure_Parse(
+    body: &[u8],
+) -> Result<RegistrationLockFailure, SignalProtocolError> {
+    RegistrationLockFailure::from_body(body)
+        .map_err(|e| SignalProtocolError::InvalidArgument(e.to_string()))
+}
+
+#[bridge_fn]
+fn RegistrationLockFailure_GetTimeRemainingMillis(failure: &RegistrationLockFailure) -> u64 {
+    failure
+        .time_remaining
+        .as_millis()
+        .try_into()
+        .expect("parsed from a u64")
+}
+
+#[bridge_fn]
+fn RegistrationLockFailure_GetSvr2Username(failure: &RegistrationLockFailure) -> Option<String> {
+    failure
+        .svr2_credentials
+        .as_ref()
+        .map(|auth| auth.username.clone())
+}
+
+#[bridge_fn]
+fn RegistrationLockFailure_GetSvr2Password(failure: &RegistrationLockFailure) -> Option<String> {
+    failure
+        .svr2_credentials
+        .as_ref()
+        .map(|auth| auth.password.clone())
+}
+
+async fn submit_rate_limit_challenge(
+    chat: &AuthChat,
+    challenge: RateLimitChallenge,
+    timeout_millis: u32,
+) -> Result<(), RateLimitChallengeError> {
+    let response = chat
+        .service
+        .0
+        .send_authenticated(
+            challenge.to_request(),
+            Duration::from_millis(timeout_millis.into()),
+        )
+        .await?;
+    parse_rate_limit_challenge_response(&response)
+}
+
+#[bridge_io(TokioAsyncContext)]
+async fn ChatService_auth_submit_captcha_challenge(
+    chat: &AuthChat,
+    token: String,
+    captcha: String,
+    timeout_millis: u32,
+) -> Result<(), RateLimitChallengeError> {
+    submit_rate_limit_challenge(
+        chat,
+        RateLimitChallenge::Captcha { token, captcha },
+        timeout_millis,
+    )
+    .await
+}
+
+#[bridge_io(TokioAsyncContext)]
+async fn ChatService_auth_submit_push_challenge(
+    chat: &AuthChat,
+    challenge: String,
+    timeout_millis: u32,
+) -> Result<(), RateLimitChallengeError> {
+    submit_rate_limit_challenge(
+        chat,
+        RateLimitChallenge::PushToken(challenge),
+        timeout_millis,
+    )
+    .await
+}
+
 bridge_handle_fns!(ServerMessageAck, clone = false);
 
 #[bridge_io(TokioAsyncContext, node = false)]
@@ -3,6 +3,8 @@
 // SPDX-License-Identifier: AGPL-3.0-only
 //
 
+use std::time::Duration;
+
 use libsignal_bridge_macros::bridge_fn;
 use libsignal_bridge_types::net::tokio::TokioAsyncContext;
 
@@ -20,3 +22,8 @@ fn TokioAsyncContext_new() -> TokioAsyncContext {
 fn TokioAsyncContext_cancel(context: &TokioAsyncContext, raw_cancellation_id: u64) {
     context.cancel(raw_cancellation_id.into())
 }
+
+#[bridge_fn]
+fn TokioAsyncContext_shutdown(context: &TokioAsyncContext, timeout_millis: u32) {
+    context.shutdown(Duration::from_millis(timeout_millis.into()))
+}
@@ -3,6 +3,8 @@
 // SPDX-License-Identifier: AGPL-3.0-only
 //
 
+use std::sync::OnceLock;
+
 // Will be unused when building for Node only.
 #[allow(unused_imports)]
 use futures_util::FutureExt;
@@ -279,6 +281,30 @@ fn IdentityKey_VerifyAlternateIdentity(
     identity.verify_alternate_identity(&other_identity, signature)
 }
 
+#[bridge_fn]
+fn PniSignature_Create(
+    pni_public_key: &PublicKey,
+    pni_private_key: &PrivateKey,
+    aci_identity: &PublicKey,
+) -> Result<Vec<u8>> {
+    let mut rng = rand::rngs::OsRng;
+    let pni_identity = IdentityKeyPair::new(IdentityKey::new(*pni_public_key), *pni_private_key);
+    Ok(create_pni_signature(&pni_identity, &IdentityKey::new(*aci_identity), &mut rng)?.into_vec())
+}
+
+#[bridge_fn]
+fn PniSignature_Verify(
+    aci_identity: &PublicKey,
+    pni_identity: &PublicKey,
+    signature: &[u8],
+) -> Result<bool> {
+    verify_pni_signature(
+        &IdentityKey::new(*aci_identity),
+        &IdentityKey::new(*pni_identity),
+        signature,
+    )
+}
+
 #[bridge_fn(jni = false)]
 fn Fingerprint_New(
     iterations: u32,
@@ -736,13 +762,22 @@ bridge_get!(SenderCertificate::expiration -> Timestamp);
 bridge_get!(SenderCertificate::sender_device_id as GetDeviceId -> u32);
 bridge_get!(SenderCertificate::key -> PublicKey);
 
+/// The cache used for all sender certificate validation in this process.
+///
+/// Shared so that a certificate seen once, whether through an explicit validation or as part of
+/// decrypting a sealed sender message, doesn't have its signatures checked again.
+pub fn sender_certificate_validation_cache() -> &'static SenderCertificateValidationCache {
+    static CACHE: OnceLock<SenderCertificateValidationCache> = OnceLock::new();
+    CACHE.get_or_init(|| SenderCertificateValidationCache::new(true))
+}
+
 #[bridge_fn]
 fn SenderCertificate_Validate(
     cert: &SenderCertificate,
     key: &PublicKey,
     time: Timestamp,
 ) -> Result<bool> {
-    cert.validate(key, time)
+    cert.validate_with_cache(key, time, sender_certificate_validation_cache())
 }
 
 #[bridge_fn]
@@ -1238,10 +1273,11 @@ async fn SealedSender_DecryptMessage(
     signed_prekey_store: &mut dyn SignedPreKeyStore,
     kyber_prekey_store: &mut dyn KyberPreKeyStore,
 ) -> Result<SealedSenderDecryptionResult> {
-    sealed_sender_decrypt(
+    sealed_sender_decrypt_with_validation_cache(
         message,
         trust_root,
         timestamp,
+        sender_certificate_validation_cache(),
         local_e164,
         local_uuid,
         local_device_id.into(),
@@ -4,6 +4,7 @@
 //
 
 use ::zkgroup;
+use base64::prelude::{Engine as _, BASE64_STANDARD};
 use libsignal_bridge_macros::*;
 use libsignal_bridge_types::zkgroup::validate_serialization;
 use libsignal_protocol::{Aci, Pni, ServiceId};
@@ -24,21 +25,21 @@ use zkgroup::*;
 use crate::support::*;
 use crate::*;
 
-bridge_fixed_length_serializable_fns!(ExpiringProfileKeyCredential);
-bridge_fixed_length_serializable_fns!(ExpiringProfileKeyCredentialResponse);
+bridge_fixed_length_serializable_fns!(ExpiringProfileKeyCredential, versioned);
+bridge_fixed_length_serializable_fns!(ExpiringProfileKeyCredentialResponse, versioned);
 bridge_fixed_length_serializable_fns!(GroupMasterKey);
 bridge_fixed_length_serializable_fns!(GroupPublicParams);
 bridge_fixed_length_serializable_fns!(GroupSecretParams);
 bridge_fixed_length_serializable_fns!(ProfileKey);
 bridge_fixed_length_serializable_fns!(ProfileKeyCiphertext);
 bridge_fixed_length_serializable_fns!(ProfileKeyCommitment);
-bridge_fixed_length_serializable_fns!(ProfileKeyCredentialRequest);
-bridge_fixed_length_serializable_fns!(ProfileKeyCredentialRequestContext);
-bridge_fixed_length_serializable_fns!(ReceiptCredential);
-bridge_fixed_length_serializable_fns!(ReceiptCredentialPresentation);
-bridge_fixed_length_serializable_fns!(ReceiptCredentialRequest);
-bridge_fixed_length_serializable_fns!(ReceiptCredentialRequestContext);
-bridge_fixed_length_serializable_fns!(ReceiptCredentialResponse);
+bridge_fixed_length_serializable_fns!(ProfileKeyCredentialRequest, versioned);
+bridge_fixed_length_serializable_fns!(ProfileKeyCredentialRequestContext, versioned);
+bridge_fixed_length_serializable_fns!(ReceiptCredential, versioned);
+bridge_fixed_length_serializable_fns!(ReceiptCredentialPresentation, versioned);
+bridge_fixed_length_serializable_fns!(ReceiptCredentialRequest, versioned);
+bridge_fixed_length_serializable_fns!(ReceiptCredentialRequestContext, versioned);
+bridge_fixed_length_serializable_fns!(ReceiptCredentialResponse, versioned);
 bridge_fixed_length_serializable_fns!(UuidCiphertext);
 
 bridge_serializable_handle_fns!(ServerPublicParams);
@@ -335,6 +336,20 @@ fn AuthCredentialWithPni_CheckValidContents(
     AuthCredentialWithPni::new(bytes).map(|_| ())
 }
 
+/// Returns the time by which a new credential has to be fetched, or 0 if this one is already in the
+/// current format.
+///
+/// A credential that doesn't need a refresh is left as is, so there's nothing else to return.
+#[bridge_fn]
+fn AuthCredentialWithPni_GetRefreshDeadlineOrZero(bytes: &[u8]) -> Timestamp {
+    let outcome =
+        AuthCredentialWithPni::upgrade_from_v0(bytes).expect("should have been parsed previously");
+    match outcome {
+        UpgradeOutcome::Converted(_) => Timestamp::from_epoch_seconds(0),
+        UpgradeOutcome::RefreshRequired { expiration } => expiration,
+    }
+}
+
 #[bridge_fn]
 fn AuthCredentialWithPniResponse_CheckValidContents(
     bytes: &[u8],
@@ -480,6 +495,21 @@ fn AuthCredentialPresentation_GetRedemptionTime(presentation_bytes: &[u8]) -> Ti
     presentation.get_redemption_time()
 }
 
+/// Returns the value of the `Authorization` header for a group storage request.
+#[bridge_fn]
+fn AuthCredentialPresentation_GetHttpAuthorization(
+    presentation_bytes: &[u8],
+    group_public_params: Serialized<GroupPublicParams>,
+) -> String {
+    let presentation = AnyAuthCredentialPresentation::new(presentation_bytes)
+        .expect("should have been parsed previously");
+    let (username, password) = presentation_to_http_auth(&presentation, &group_public_params);
+    format!(
+        "Basic {}",
+        BASE64_STANDARD.encode(format!("{username}:{password}"))
+    )
+}
+
 // FIXME: bridge_get
 #[bridge_fn]
 fn ProfileKeyCredentialRequestContext_GetRequest(
@@ -901,6 +931,7 @@ fn BackupAuthCredentialRequestContext_New(backup_key: &[u8; 32], uuid: Uuid) ->
 fn BackupAuthCredentialRequestContext_CheckValidContents(
     context_bytes: &[u8],
 ) -> Result<(), ZkGroupDeserializationFailure> {
+    zkgroup::check_version::<BackupAuthCredentialRequestContext>(context_bytes)?;
     validate_serialization::<BackupAuthCredentialRequestContext>(context_bytes)
 }
 
@@ -917,6 +948,7 @@ fn BackupAuthCredentialRequestContext_GetRequest(context_bytes: &[u8]) -> Vec<u8
 fn BackupAuthCredentialRequest_CheckValidContents(
     request_bytes: &[u8],
 ) -> Result<(), ZkGroupDeserializationFailure> {
+    zkgroup::check_version::<BackupAuthCredentialRequest>(request_bytes)?;
     validate_serialization::<BackupAuthCredentialRequest>(request_bytes)
 }
 
@@ -946,6 +978,7 @@ fn BackupAuthCredentialRequest_IssueDeterministic(
 fn BackupAuthCredentialResponse_CheckValidContents(
     response_bytes: &[u8],
 ) -> Result<(), ZkGroupDeserializationFailure> {
+    zkgroup::check_version::<BackupAuthCredentialResponse>(response_bytes)?;
     validate_serialization::<BackupAuthCredentialResponse>(response_bytes)
 }
 
@@ -971,6 +1004,7 @@ fn BackupAuthCredentialRequestContext_ReceiveResponse(
 fn BackupAuthCredential_CheckValidContents(
     params_bytes: &[u8],
 ) -> Result<(), ZkGroupDeserializationFailure> {
+    zkgroup::check_version::<BackupAuthCredential>(params_bytes)?;
     validate_serialization::<BackupAuthCredential>(params_bytes)
 }
 
@@ -1007,6 +1041,7 @@ fn BackupAuthCredential_PresentDeterministic(
 fn BackupAuthCredentialPresentation_CheckValidContents(
     presentation_bytes: &[u8],
 ) -> Result<(), ZkGroupDeserializationFailure> {
+    zkgroup::check_version::<BackupAuthCredentialPresentation>(presentation_bytes)?;
     validate_serialization::<BackupAuthCredentialPresentation>(presentation_bytes)
 }
 
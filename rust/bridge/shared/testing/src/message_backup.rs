@@ -29,6 +29,12 @@ async fn ComparableBackup_ReadUnencrypted(
     let ReadResult {
         result,
         found_unknown_fields,
+        found_oversized_frames: _,
+        found_duplicate_chat_items: _,
+        found_expire_timer_version_conflicts: _,
+        backup_time_warning: _,
+        policy_flags: _,
+        backup_version: _,
     } = reader.read_all().await;
 
     match result {
@@ -45,7 +51,7 @@ async fn ComparableBackup_ReadUnencrypted(
 
 #[bridge_fn]
 fn ComparableBackup_GetComparableString(backup: &ComparableBackup) -> String {
-    backup.backup.to_string_pretty()
+    backup.backup.to_canonical_string()
 }
 
 #[bridge_fn]
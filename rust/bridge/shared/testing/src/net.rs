@@ -3,30 +3,32 @@
 // SPDX-License-Identifier: AGPL-3.0-only
 //
 
+use std::net::SocketAddr;
 use std::str::FromStr;
 use std::time::Duration;
 
-use http::{HeaderMap, HeaderName, HeaderValue, StatusCode};
+use http::{HeaderMap, HeaderValue, StatusCode};
 use libsignal_bridge_macros::*;
+use libsignal_bridge_types::net::cdsi::LookupResponseEntries;
 use libsignal_bridge_types::net::chat::{
     AuthChat, HttpRequest, ResponseAndDebugInfo, ServerMessageAck,
 };
-use libsignal_bridge_types::net::TokioAsyncContext;
+use libsignal_bridge_types::net::{ConnectionManager, TokioAsyncContext};
 use libsignal_core::E164;
 use libsignal_net::cdsi::{LookupError, LookupResponse, LookupResponseEntry};
 use libsignal_net::chat::{
-    self, ChatServiceError, DebugInfo as ChatServiceDebugInfo, Response as ChatResponse,
+    self, ChatServiceError, DebugInfo as ChatServiceDebugInfo, Response as ChatResponse, TraceId,
 };
+use libsignal_net::infra::live_connections::live_connection_counts;
 use libsignal_net::infra::ws::WebSocketServiceError;
-use libsignal_net::infra::IpType;
+use libsignal_net::infra::{IpType, RouteType};
 use libsignal_protocol::{Aci, Pni};
 use nonzero_ext::nonzero;
 use uuid::Uuid;
 
 use crate::*;
 
-#[bridge_io(TokioAsyncContext)]
-async fn TESTING_CdsiLookupResponseConvert() -> LookupResponse {
+fn test_lookup_response() -> LookupResponse {
     const E164_BOTH: E164 = E164::new(nonzero!(18005551011u64));
     const E164_PNI: E164 = E164::new(nonzero!(18005551012u64));
     const ACI_UUID: &str = "9d0652a3-dcc3-4d11-975f-74d61598733f";
@@ -53,11 +55,56 @@ async fn TESTING_CdsiLookupResponseConvert() -> LookupResponse {
     }
 }
 
+#[bridge_io(TokioAsyncContext)]
+async fn TESTING_CdsiLookupResponseConvert() -> LookupResponse {
+    test_lookup_response()
+}
+
+#[bridge_io(TokioAsyncContext)]
+async fn TESTING_CdsiLookupResponseEntriesConvert() -> LookupResponseEntries {
+    test_lookup_response().into()
+}
+
 #[bridge_io(TokioAsyncContext)]
 async fn TESTING_OnlyCompletesByCancellation() {
     std::future::pending::<()>().await
 }
 
+#[bridge_io(TokioAsyncContext)]
+async fn TESTING_ConnectionManager_PrewarmDns(
+    connection_manager: &ConnectionManager,
+    hostname: String,
+) {
+    connection_manager.prewarm_dns(&[&hostname]).await
+}
+
+/// Creates a [`TokioAsyncContext`] whose clock only moves when the runtime is idle or when
+/// [`TESTING_Net_AdvanceTime`] is called, so that timeouts can be tested without waiting for them.
+#[bridge_fn]
+fn TESTING_Net_NewTokioAsyncContextWithPausedTime() -> TokioAsyncContext {
+    TokioAsyncContext::new_with_paused_time()
+}
+
+#[bridge_fn]
+fn TESTING_Net_SetTimeAutoAdvance(async_runtime: &TokioAsyncContext, auto_advance: bool) {
+    async_runtime.set_time_auto_advance(auto_advance)
+}
+
+#[bridge_fn]
+fn TESTING_Net_AdvanceTime(async_runtime: &TokioAsyncContext, millis: u32) {
+    async_runtime.advance_time(Duration::from_millis(millis.into()))
+}
+
+/// The number of connections of `kind` ("chat", "cdsi", "svr", or "cdn") that are open right now.
+///
+/// Connections are only counted in debug builds; in release builds this is always 0.
+#[bridge_fn]
+fn TESTING_Net_LiveConnectionCount(kind: String) -> u32 {
+    live_connection_counts()
+        .get(kind.as_str())
+        .map_or(0, |&count| count.try_into().unwrap_or(u32::MAX))
+}
+
 macro_rules! make_error_testing_enum {
     (enum $name:ident for $orig:ident {
         $($orig_case:ident => $case:ident,)*
@@ -153,6 +200,8 @@ make_error_testing_enum! {
         ServiceInactive => ServiceInactive,
         ServiceUnavailable => ServiceUnavailable,
         ServiceIntentionallyDisconnected => ServiceIntentionallyDisconnected,
+        ServerRequestedReconnect => ServerRequestedReconnect,
+        RequestChannelClosed => RequestChannelClosed,
     }
 }
 
@@ -178,7 +227,10 @@ fn TESTING_ChatServiceErrorConvert(
         TestingChatServiceError::RequestHasInvalidHeader => {
             ChatServiceError::RequestHasInvalidHeader
         }
-        TestingChatServiceError::Timeout => ChatServiceError::Timeout,
+        TestingChatServiceError::Timeout => ChatServiceError::Timeout {
+            trace_id: TraceId::new(0x2a),
+            elapsed: Duration::from_secs(42),
+        },
         TestingChatServiceError::TimeoutEstablishingConnection => {
             ChatServiceError::TimeoutEstablishingConnection { attempts: 42 }
         }
@@ -190,6 +242,12 @@ fn TESTING_ChatServiceErrorConvert(
         TestingChatServiceError::ServiceIntentionallyDisconnected => {
             ChatServiceError::ServiceIntentionallyDisconnected
         }
+        TestingChatServiceError::ServerRequestedReconnect => {
+            ChatServiceError::ServerRequestedReconnect
+        }
+        TestingChatServiceError::RequestChannelClosed => ChatServiceError::RequestChannelClosed {
+            trace_id: TraceId::new(0x2a),
+        },
     })
 }
 
@@ -220,6 +278,8 @@ fn TESTING_ChatServiceDebugInfoConvert() -> Result<ChatServiceDebugInfo, ChatSer
     Ok(ChatServiceDebugInfo {
         ip_type: IpType::V4,
         duration: Duration::from_millis(200),
+        route_type: Some(RouteType::TlsProxy),
+        remote_address: Some(SocketAddr::from(([192, 0, 2, 1], 443))),
         connection_info: "connection_info".to_string(),
     })
 }
@@ -244,16 +304,8 @@ fn TESTING_ChatRequestGetPath(request: &HttpRequest) -> String {
 }
 
 #[bridge_fn]
-fn TESTING_ChatRequestGetHeaderValue(request: &HttpRequest, header_name: String) -> String {
-    request
-        .headers
-        .lock()
-        .expect("not poisoned")
-        .get(HeaderName::try_from(header_name).expect("valid header name"))
-        .expect("header value present")
-        .to_str()
-        .expect("value is a string")
-        .to_string()
+fn TESTING_ChatRequestGetHeaderValue(request: &HttpRequest, header_name: String) -> Option<String> {
+    request.get_header(&header_name)
 }
 
 #[bridge_fn]
@@ -274,6 +326,30 @@ fn TESTING_ChatService_InjectRawServerRequest(chat: &AuthChat, bytes: &[u8]) {
         .expect("not closed");
 }
 
+/// Injects `count` incoming messages, with server delivery timestamps counting up from 0 and
+/// envelopes containing the same number as a decimal string.
+///
+/// The messages are sent from a separate thread, so the caller can go back to handling listener
+/// callbacks while they're delivered.
+#[bridge_fn]
+fn TESTING_ChatService_InjectIncomingMessages(chat: &AuthChat, count: u32) {
+    let synthetic_request_tx = chat.synthetic_request_tx.clone();
+    std::thread::spawn(move || {
+        for i in 0..count {
+            let request_proto = chat::RequestProto {
+                verb: Some(http::Method::PUT.to_string()),
+                path: Some("/api/v1/message".to_owned()),
+                body: Some(i.to_string().into_bytes()),
+                headers: vec![format!("x-signal-timestamp:{i}")],
+                id: Some(i.into()),
+            };
+            synthetic_request_tx
+                .blocking_send(chat::ws::ServerEvent::fake(request_proto))
+                .expect("not closed");
+        }
+    });
+}
+
 #[bridge_fn]
 fn TESTING_ChatService_InjectConnectionInterrupted(chat: &AuthChat) {
     chat.synthetic_request_tx
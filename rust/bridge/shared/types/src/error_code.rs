@@ -0,0 +1,812 @@
+//
+// Copyright 2024 Signal Messenger, LLC.
+// SPDX-License-Identifier: AGPL-3.0-only
+//
+
+//! Stable numeric codes for every error that can cross the bridge.
+//!
+//! The app-language error types are organized around what a caller can do about an error, so
+//! plenty of distinct failures end up looking the same once they get there (for example, every
+//! transport failure during a CDSI lookup is just an I/O error). A [`SignalErrorCode`] keeps the
+//! most specific cause instead: errors that wrap other errors report the code of the wrapped
+//! error, so a DNS failure while connecting to CDSI is
+//! [`SignalErrorCode::TransportDnsError`] rather than a generic network failure.
+//!
+//! Codes are grouped by subsystem:
+//!
+//! | Range     | Subsystem                                      |
+//! |-----------|------------------------------------------------|
+//! | 1–999     | the bridge itself, and errors without a home   |
+//! | 1000–1999 | networking (transport, websockets, chat, CDSI, SVR, CDN) |
+//! | 2000–2999 | message backups                                |
+//! | 3000–3999 | zkgroup                                        |
+//! | 4000–4999 | the Signal protocol                            |
+//! | 5000–5999 | enclave attestation                            |
+//! | 6000–6999 | usernames                                      |
+//! | 7000–7999 | cryptographic primitives, PINs, device transfer |
+//! | 8000–8999 | media sanitizers                               |
+//!
+//! # Stability
+//!
+//! Once released, a code keeps its number and its meaning. New codes can be added, and an
+//! existing error can start reporting a newer, more specific code, so clients should be prepared
+//! to see codes they don't know about. Numbers are never reused, even if the error they
+//! described goes away. The human-readable message that accompanies a code is *not* stable.
+
+use std::io::Error as IoError;
+
+use attest::enclave::Error as EnclaveError;
+use attest::hsm_enclave::Error as HsmEnclaveError;
+use device_transfer::Error as DeviceTransferError;
+use libsignal_message_backup::backup::{CompletionError, ValidationError};
+use libsignal_message_backup::parse::ParseError as BackupParseError;
+use libsignal_net::cdn::DownloadError;
+use libsignal_net::cdsi::LookupError;
+use libsignal_net::chat::challenge::RateLimitChallengeError;
+use libsignal_net::chat::ChatServiceError;
+use libsignal_net::infra::errors::TransportConnectError;
+use libsignal_net::infra::ws::{WebSocketConnectError, WebSocketServiceError};
+use libsignal_net::svr3::Error as Svr3Error;
+use libsignal_protocol::SignalProtocolError;
+use signal_crypto::Error as SignalCryptoError;
+use signal_pin::Error as PinError;
+use usernames::{UsernameError, UsernameLinkError};
+use zkgroup::{ZkGroupDeserializationFailure, ZkGroupVerificationFailure};
+
+use crate::support::RuntimeClosed;
+
+/// A stable identifier for the cause of an error; see the [module documentation](self).
+#[derive(
+    Copy,
+    Clone,
+    Debug,
+    PartialEq,
+    Eq,
+    Hash,
+    num_enum::IntoPrimitive,
+    num_enum::TryFromPrimitive,
+    strum::EnumIter,
+)]
+#[repr(u32)]
+pub enum SignalErrorCode {
+    // The bridge, and errors that don't belong to any one subsystem.
+    InternalError = 1,
+    InvalidArgument = 2,
+    InvalidState = 3,
+    NullParameter = 4,
+    InvalidUtf8String = 5,
+    Cancelled = 6,
+    CallbackError = 7,
+    IoError = 8,
+    UnexpectedPanic = 9,
+    RuntimeClosed = 10,
+    InvalidUri = 11,
+
+    // Networking.
+    NetConnectionTimedOut = 1000,
+    NetRateLimited = 1001,
+    NetProtocol = 1002,
+
+    TransportInvalidConfiguration = 1100,
+    TransportTcpConnectionFailed = 1101,
+    TransportDnsError = 1102,
+    TransportSslError = 1103,
+    TransportCertError = 1104,
+    TransportSslHandshakeFailed = 1105,
+    TransportUntrustedRoot = 1106,
+    TransportProxyProtocol = 1107,
+
+    WebSocketChannelClosed = 1200,
+    WebSocketChannelIdleTooLong = 1201,
+    WebSocketIo = 1202,
+    WebSocketProtocol = 1203,
+    WebSocketCapacity = 1204,
+    WebSocketHttpStatus = 1205,
+    WebSocketHttpFormat = 1206,
+    WebSocketUrl = 1207,
+    WebSocketOther = 1208,
+    WebSocketSubProtocol = 1209,
+    WebSocketConnectFailed = 1210,
+
+    ChatServiceInactive = 1300,
+    ChatServiceIntentionallyDisconnected = 1301,
+    ChatAppExpired = 1302,
+    ChatDeviceDeregistered = 1303,
+    ChatServerRequestedReconnect = 1304,
+    ChatRequestTimedOut = 1305,
+    ChatConnectTimedOut = 1306,
+    ChatAllConnectionRoutesFailed = 1307,
+    ChatServiceUnavailable = 1308,
+    ChatUnexpectedFrame = 1309,
+    ChatServerRequestMissingId = 1310,
+    ChatIncomingDataInvalid = 1311,
+    ChatRequestChannelClosed = 1312,
+    ChatRequestHasInvalidHeader = 1313,
+    ChatIncomingChannelFailed = 1314,
+    ChatChallengeRejected = 1320,
+    ChatChallengeUnexpectedStatus = 1321,
+
+    CdsiInvalidToken = 1400,
+    CdsiInvalidResponse = 1401,
+    CdsiParseError = 1402,
+    CdsiServerError = 1403,
+    CdsiInvalidRequest = 1404,
+
+    SvrDataMissing = 1500,
+    SvrRestoreFailed = 1501,
+    SvrRotationMachineTooManySteps = 1502,
+    SvrRequestFailed = 1503,
+    SvrCircuitOpen = 1504,
+
+    CdnUnknown = 1600,
+    CdnConnectionFailed = 1601,
+    CdnServerError = 1602,
+    CdnNotFound = 1603,
+    CdnUnexpectedStatus = 1604,
+    CdnInvalidResponse = 1605,
+    CdnDigestMismatch = 1606,
+
+    // Message backups.
+    BackupNoFrames = 2000,
+    BackupInvalidProtobuf = 2001,
+    BackupHmacMismatch = 2002,
+    BackupPolicyViolation = 2003,
+    BackupUnsupportedVersion = 2004,
+    BackupVersionTooOld = 2005,
+
+    BackupMissingAccountData = 2100,
+    BackupNoSelfRecipient = 2101,
+
+    BackupEmptyFrame = 2200,
+    BackupMultipleAccountData = 2201,
+    BackupChatItemBeforeSelfRecipient = 2202,
+    BackupInvalidAccountData = 2203,
+    BackupInvalidRecipient = 2204,
+    BackupInvalidChat = 2205,
+    BackupInvalidCall = 2206,
+    BackupInvalidStickerPack = 2207,
+    BackupInvalidBackupTime = 2208,
+
+    // zkgroup.
+    ZkGroupVerificationFailure = 3000,
+    ZkGroupDeserializationFailure = 3001,
+
+    // The Signal protocol.
+    ProtocolInvalidMessage = 4000,
+    ProtocolInvalidSealedSenderMessage = 4001,
+    ProtocolInvalidProtobuf = 4002,
+    ProtocolLegacyCiphertextVersion = 4003,
+    ProtocolUnknownCiphertextVersion = 4004,
+    ProtocolUnrecognizedMessageVersion = 4005,
+    ProtocolFingerprintVersionMismatch = 4006,
+    ProtocolFingerprintParsingError = 4007,
+    ProtocolInvalidKey = 4008,
+    ProtocolInvalidSignature = 4009,
+    ProtocolUntrustedIdentity = 4010,
+    ProtocolInvalidKeyIdentifier = 4011,
+    ProtocolSessionNotFound = 4012,
+    ProtocolNoSenderKeyState = 4013,
+    ProtocolInvalidSession = 4014,
+    ProtocolInvalidSenderKeySession = 4015,
+    ProtocolInvalidRegistrationId = 4016,
+    ProtocolDuplicatedMessage = 4017,
+    ProtocolSealedSenderSelfSend = 4018,
+
+    // Enclave attestation.
+    AttestationFailed = 5000,
+    AttestationExpired = 5001,
+    AttestationMeasurementMismatch = 5002,
+    AttestationDataInvalid = 5003,
+    EnclaveNoiseError = 5004,
+    EnclaveNoiseHandshakeFailed = 5005,
+
+    HsmCommunicationError = 5100,
+    HsmHandshakeFailed = 5101,
+    HsmUntrustedCode = 5102,
+    HsmInvalidPublicKey = 5103,
+    HsmInvalidCodeHash = 5104,
+
+    // Usernames.
+    UsernameCannotBeEmpty = 6000,
+    UsernameCannotStartWithDigit = 6001,
+    UsernameMissingSeparator = 6002,
+    UsernameBadNicknameCharacter = 6003,
+    UsernameTooShort = 6004,
+    UsernameTooLong = 6005,
+    UsernameDiscriminatorCannotBeEmpty = 6010,
+    UsernameDiscriminatorCannotBeZero = 6011,
+    UsernameDiscriminatorCannotBeSingleDigit = 6012,
+    UsernameDiscriminatorCannotHaveLeadingZeros = 6013,
+    UsernameBadDiscriminatorCharacter = 6014,
+    UsernameDiscriminatorTooLarge = 6015,
+    UsernameProofVerificationFailure = 6020,
+
+    UsernameLinkInputDataTooLong = 6100,
+    UsernameLinkInvalidEntropyDataLength = 6101,
+    UsernameLinkDataTooShort = 6102,
+    UsernameLinkHmacMismatch = 6103,
+    UsernameLinkBadCiphertext = 6104,
+    UsernameLinkInvalidDecryptedData = 6105,
+
+    // Cryptographic primitives, PINs, and device transfer.
+    CryptoUnknownAlgorithm = 7000,
+    CryptoInvalidKeySize = 7001,
+    CryptoInvalidNonceSize = 7002,
+    CryptoInvalidInputSize = 7003,
+    CryptoInvalidTag = 7004,
+
+    PinArgon2Error = 7100,
+    PinDecodingError = 7101,
+    PinMrenclaveLookupError = 7102,
+
+    DeviceTransferKeyDecodingFailed = 7200,
+    DeviceTransferInternalError = 7201,
+
+    // Media sanitizers.
+    MediaInvalidInput = 8000,
+    MediaUnsupportedInput = 8001,
+}
+
+/// An error that has a [`SignalErrorCode`].
+///
+/// Implementations should match every variant explicitly (no `_` arms), so that adding a variant
+/// is a compile error until it has been given a code.
+pub trait ErrorCode {
+    fn error_code(&self) -> SignalErrorCode;
+}
+
+impl ErrorCode for SignalProtocolError {
+    fn error_code(&self) -> SignalErrorCode {
+        match self {
+            Self::InvalidArgument(_) => SignalErrorCode::InvalidArgument,
+            Self::InvalidState(_, _) => SignalErrorCode::InvalidState,
+            Self::InvalidProtobufEncoding => SignalErrorCode::ProtocolInvalidProtobuf,
+            Self::CiphertextMessageTooShort(_)
+            | Self::InvalidMessage(_, _)
+            | Self::BadKEMCiphertextLength(_, _) => SignalErrorCode::ProtocolInvalidMessage,
+            Self::InvalidSealedSenderMessage(_) => {
+                SignalErrorCode::ProtocolInvalidSealedSenderMessage
+            }
+            Self::LegacyCiphertextVersion(_) => SignalErrorCode::ProtocolLegacyCiphertextVersion,
+            Self::UnrecognizedCiphertextVersion(_) => {
+                SignalErrorCode::ProtocolUnknownCiphertextVersion
+            }
+            Self::UnrecognizedMessageVersion(_) | Self::UnknownSealedSenderVersion(_) => {
+                SignalErrorCode::ProtocolUnrecognizedMessageVersion
+            }
+            Self::FingerprintVersionMismatch(_, _) => {
+                SignalErrorCode::ProtocolFingerprintVersionMismatch
+            }
+            Self::FingerprintParsingError => SignalErrorCode::ProtocolFingerprintParsingError,
+            Self::NoKeyTypeIdentifier
+            | Self::BadKeyType(_)
+            | Self::BadKeyLength(_, _)
+            | Self::InvalidMacKeyLength(_)
+            | Self::BadKEMKeyType(_)
+            | Self::WrongKEMKeyType(_, _)
+            | Self::BadKEMKeyLength(_, _) => SignalErrorCode::ProtocolInvalidKey,
+            Self::SignatureValidationFailed => SignalErrorCode::ProtocolInvalidSignature,
+            Self::UntrustedIdentity(_) => SignalErrorCode::ProtocolUntrustedIdentity,
+            Self::InvalidPreKeyId | Self::InvalidSignedPreKeyId | Self::InvalidKyberPreKeyId => {
+                SignalErrorCode::ProtocolInvalidKeyIdentifier
+            }
+            Self::SessionNotFound(_) => SignalErrorCode::ProtocolSessionNotFound,
+            Self::NoSenderKeyState { .. } => SignalErrorCode::ProtocolNoSenderKeyState,
+            Self::InvalidSessionStructure(_) => SignalErrorCode::ProtocolInvalidSession,
+            Self::InvalidSenderKeySession { .. } => {
+                SignalErrorCode::ProtocolInvalidSenderKeySession
+            }
+            Self::InvalidRegistrationId(_, _) => SignalErrorCode::ProtocolInvalidRegistrationId,
+            Self::DuplicatedMessage(_, _) => SignalErrorCode::ProtocolDuplicatedMessage,
+            Self::SealedSenderSelfSend => SignalErrorCode::ProtocolSealedSenderSelfSend,
+            Self::FfiBindingError(_) => SignalErrorCode::InternalError,
+            Self::ApplicationCallbackError(_, _) => SignalErrorCode::CallbackError,
+        }
+    }
+}
+
+impl ErrorCode for IoError {
+    fn error_code(&self) -> SignalErrorCode {
+        SignalErrorCode::IoError
+    }
+}
+
+impl ErrorCode for http::uri::InvalidUri {
+    fn error_code(&self) -> SignalErrorCode {
+        SignalErrorCode::InvalidUri
+    }
+}
+
+impl ErrorCode for RuntimeClosed {
+    fn error_code(&self) -> SignalErrorCode {
+        SignalErrorCode::RuntimeClosed
+    }
+}
+
+impl ErrorCode for TransportConnectError {
+    fn error_code(&self) -> SignalErrorCode {
+        match self {
+            Self::InvalidConfiguration => SignalErrorCode::TransportInvalidConfiguration,
+            Self::TcpConnectionFailed => SignalErrorCode::TransportTcpConnectionFailed,
+            Self::DnsError => SignalErrorCode::TransportDnsError,
+            Self::SslError(_) => SignalErrorCode::TransportSslError,
+            Self::CertError => SignalErrorCode::TransportCertError,
+            Self::SslFailedHandshake(_) => SignalErrorCode::TransportSslHandshakeFailed,
+            Self::UntrustedRoot => SignalErrorCode::TransportUntrustedRoot,
+            Self::ProxyProtocol => SignalErrorCode::TransportProxyProtocol,
+        }
+    }
+}
+
+impl ErrorCode for WebSocketConnectError {
+    fn error_code(&self) -> SignalErrorCode {
+        match self {
+            Self::Transport(e) => e.error_code(),
+            Self::Timeout => SignalErrorCode::NetConnectionTimedOut,
+            Self::WebSocketError(_) => SignalErrorCode::WebSocketConnectFailed,
+            Self::RejectedByServer { .. } => SignalErrorCode::WebSocketHttpStatus,
+            Self::SubProtocol(_) => SignalErrorCode::WebSocketSubProtocol,
+        }
+    }
+}
+
+impl ErrorCode for WebSocketServiceError {
+    fn error_code(&self) -> SignalErrorCode {
+        match self {
+            Self::ChannelClosed => SignalErrorCode::WebSocketChannelClosed,
+            Self::ChannelIdleTooLong => SignalErrorCode::WebSocketChannelIdleTooLong,
+            Self::Io(_) => SignalErrorCode::WebSocketIo,
+            Self::Protocol(_) => SignalErrorCode::WebSocketProtocol,
+            Self::Capacity(_) => SignalErrorCode::WebSocketCapacity,
+            Self::Http(_) => SignalErrorCode::WebSocketHttpStatus,
+            Self::HttpFormat(_) => SignalErrorCode::WebSocketHttpFormat,
+            Self::Url(_) => SignalErrorCode::WebSocketUrl,
+            Self::Other(_) => SignalErrorCode::WebSocketOther,
+        }
+    }
+}
+
+impl ErrorCode for ChatServiceError {
+    fn error_code(&self) -> SignalErrorCode {
+        match self {
+            Self::WebSocket(e) => e.error_code(),
+            Self::AppExpired => SignalErrorCode::ChatAppExpired,
+            Self::DeviceDeregistered => SignalErrorCode::ChatDeviceDeregistered,
+            Self::UnexpectedFrameReceived => SignalErrorCode::ChatUnexpectedFrame,
+            Self::ServerRequestMissingId => SignalErrorCode::ChatServerRequestMissingId,
+            Self::FailedToPassMessageToIncomingChannel => {
+                SignalErrorCode::ChatIncomingChannelFailed
+            }
+            Self::IncomingDataInvalid => SignalErrorCode::ChatIncomingDataInvalid,
+            Self::RequestHasInvalidHeader => SignalErrorCode::ChatRequestHasInvalidHeader,
+            Self::Timeout { .. } => SignalErrorCode::ChatRequestTimedOut,
+            Self::TimeoutEstablishingConnection { .. } => SignalErrorCode::ChatConnectTimedOut,
+            Self::AllConnectionRoutesFailed { .. } => {
+                SignalErrorCode::ChatAllConnectionRoutesFailed
+            }
+            Self::ServiceInactive => SignalErrorCode::ChatServiceInactive,
+            Self::ServiceUnavailable => SignalErrorCode::ChatServiceUnavailable,
+            Self::ServiceIntentionallyDisconnected => {
+                SignalErrorCode::ChatServiceIntentionallyDisconnected
+            }
+            Self::ServerRequestedReconnect => SignalErrorCode::ChatServerRequestedReconnect,
+            Self::RequestChannelClosed { .. } => SignalErrorCode::ChatRequestChannelClosed,
+        }
+    }
+}
+
+impl ErrorCode for RateLimitChallengeError {
+    fn error_code(&self) -> SignalErrorCode {
+        match self {
+            Self::Chat(e) => e.error_code(),
+            Self::Rejected => SignalErrorCode::ChatChallengeRejected,
+            Self::RateLimited { .. } => SignalErrorCode::NetRateLimited,
+            Self::UnexpectedStatus(_) => SignalErrorCode::ChatChallengeUnexpectedStatus,
+        }
+    }
+}
+
+impl ErrorCode for LookupError {
+    fn error_code(&self) -> SignalErrorCode {
+        match self {
+            Self::Protocol => SignalErrorCode::NetProtocol,
+            Self::AttestationError(e) => e.error_code(),
+            Self::InvalidResponse => SignalErrorCode::CdsiInvalidResponse,
+            Self::RateLimited { .. } => SignalErrorCode::NetRateLimited,
+            Self::InvalidToken => SignalErrorCode::CdsiInvalidToken,
+            Self::ParseError => SignalErrorCode::CdsiParseError,
+            Self::ConnectTransport(e) => e.error_code(),
+            Self::WebSocket(e) => e.error_code(),
+            Self::ConnectionTimedOut => SignalErrorCode::NetConnectionTimedOut,
+            Self::InvalidArgument { .. } | Self::InvalidPrebuiltRequest(_) => {
+                SignalErrorCode::CdsiInvalidRequest
+            }
+            Self::Server { .. } => SignalErrorCode::CdsiServerError,
+        }
+    }
+}
+
+impl ErrorCode for Svr3Error {
+    fn error_code(&self) -> SignalErrorCode {
+        match self {
+            Self::Connect(e) => e.error_code(),
+            Self::Service(e) => e.error_code(),
+            Self::Protocol(_) => SignalErrorCode::NetProtocol,
+            Self::AttestationError(e) => e.error_code(),
+            Self::RequestFailed(_) => SignalErrorCode::SvrRequestFailed,
+            Self::RestoreFailed(_) => SignalErrorCode::SvrRestoreFailed,
+            Self::DataMissing => SignalErrorCode::SvrDataMissing,
+            Self::ConnectionTimedOut => SignalErrorCode::NetConnectionTimedOut,
+            Self::RotationMachineTooManySteps => SignalErrorCode::SvrRotationMachineTooManySteps,
+            Self::CircuitOpen => SignalErrorCode::SvrCircuitOpen,
+            Self::PasswordRotationIncomplete { cause, .. } => cause.error_code(),
+        }
+    }
+}
+
+impl ErrorCode for DownloadError {
+    fn error_code(&self) -> SignalErrorCode {
+        match self {
+            Self::UnknownCdn(_) => SignalErrorCode::CdnUnknown,
+            Self::InvalidArgument(_) => SignalErrorCode::InvalidArgument,
+            Self::Connection(_) => SignalErrorCode::CdnConnectionFailed,
+            Self::RateLimited { .. } => SignalErrorCode::NetRateLimited,
+            Self::ServerError(_) => SignalErrorCode::CdnServerError,
+            Self::NotFound => SignalErrorCode::CdnNotFound,
+            Self::UnexpectedStatus(_) => SignalErrorCode::CdnUnexpectedStatus,
+            Self::InvalidResponse(_) => SignalErrorCode::CdnInvalidResponse,
+            Self::DigestMismatch => SignalErrorCode::CdnDigestMismatch,
+            Self::Sink(e) => e.error_code(),
+        }
+    }
+}
+
+impl ErrorCode for libsignal_message_backup::ReadError {
+    fn error_code(&self) -> SignalErrorCode {
+        self.error.error_code()
+    }
+}
+
+impl ErrorCode for libsignal_message_backup::Error {
+    fn error_code(&self) -> SignalErrorCode {
+        match self {
+            Self::BackupValidation(failure) => failure.error.error_code(),
+            Self::BackupCompletion(e) => e.error_code(),
+            Self::Parse(BackupParseError::Io(e)) => e.error_code(),
+            Self::Parse(BackupParseError::Decode(_)) | Self::InvalidProtobuf(_) => {
+                SignalErrorCode::BackupInvalidProtobuf
+            }
+            Self::NoFrames => SignalErrorCode::BackupNoFrames,
+            Self::HmacMismatch(_) => SignalErrorCode::BackupHmacMismatch,
+            Self::PolicyViolation { .. } => SignalErrorCode::BackupPolicyViolation,
+            Self::UnsupportedVersion { .. } => SignalErrorCode::BackupUnsupportedVersion,
+            Self::VersionTooOld { .. } => SignalErrorCode::BackupVersionTooOld,
+        }
+    }
+}
+
+impl ErrorCode for CompletionError {
+    fn error_code(&self) -> SignalErrorCode {
+        match self {
+            Self::MissingAccountData => SignalErrorCode::BackupMissingAccountData,
+            Self::NoSelfRecipient => SignalErrorCode::BackupNoSelfRecipient,
+        }
+    }
+}
+
+impl ErrorCode for ValidationError {
+    fn error_code(&self) -> SignalErrorCode {
+        match self {
+            Self::EmptyFrame => SignalErrorCode::BackupEmptyFrame,
+            Self::MultipleAccountData => SignalErrorCode::BackupMultipleAccountData,
+            Self::ChatItemBeforeSelfRecipient(_) => {
+                SignalErrorCode::BackupChatItemBeforeSelfRecipient
+            }
+            Self::AccountData(_) => SignalErrorCode::BackupInvalidAccountData,
+            Self::RecipientError(_) => SignalErrorCode::BackupInvalidRecipient,
+            Self::ChatError(_) => SignalErrorCode::BackupInvalidChat,
+            Self::CallError(_) => SignalErrorCode::BackupInvalidCall,
+            Self::StickerError(_) => SignalErrorCode::BackupInvalidStickerPack,
+            Self::BackupTime(_) => SignalErrorCode::BackupInvalidBackupTime,
+        }
+    }
+}
+
+impl ErrorCode for ZkGroupVerificationFailure {
+    fn error_code(&self) -> SignalErrorCode {
+        SignalErrorCode::ZkGroupVerificationFailure
+    }
+}
+
+impl ErrorCode for ZkGroupDeserializationFailure {
+    fn error_code(&self) -> SignalErrorCode {
+        SignalErrorCode::ZkGroupDeserializationFailure
+    }
+}
+
+impl ErrorCode for EnclaveError {
+    fn error_code(&self) -> SignalErrorCode {
+        match self {
+            Self::AttestationError(_) => SignalErrorCode::AttestationFailed,
+            Self::AttestationExpired { .. } => SignalErrorCode::AttestationExpired,
+            Self::AttestationMeasurementMismatch => SignalErrorCode::AttestationMeasurementMismatch,
+            Self::NoiseError(_) => SignalErrorCode::EnclaveNoiseError,
+            Self::NoiseHandshakeError(_) => SignalErrorCode::EnclaveNoiseHandshakeFailed,
+            Self::AttestationDataError { .. } => SignalErrorCode::AttestationDataInvalid,
+            Self::InvalidBridgeStateError => SignalErrorCode::InvalidState,
+        }
+    }
+}
+
+impl ErrorCode for HsmEnclaveError {
+    fn error_code(&self) -> SignalErrorCode {
+        match self {
+            Self::HSMCommunicationError(_) => SignalErrorCode::HsmCommunicationError,
+            Self::HSMHandshakeError(_) => SignalErrorCode::HsmHandshakeFailed,
+            Self::TrustedCodeError => SignalErrorCode::HsmUntrustedCode,
+            Self::InvalidPublicKeyError => SignalErrorCode::HsmInvalidPublicKey,
+            Self::InvalidCodeHashError => SignalErrorCode::HsmInvalidCodeHash,
+            Self::InvalidBridgeStateError => SignalErrorCode::InvalidState,
+        }
+    }
+}
+
+impl ErrorCode for UsernameError {
+    fn error_code(&self) -> SignalErrorCode {
+        match self {
+            Self::NicknameCannotBeEmpty => SignalErrorCode::UsernameCannotBeEmpty,
+            Self::NicknameCannotStartWithDigit => SignalErrorCode::UsernameCannotStartWithDigit,
+            Self::MissingSeparator => SignalErrorCode::UsernameMissingSeparator,
+            Self::BadNicknameCharacter => SignalErrorCode::UsernameBadNicknameCharacter,
+            Self::NicknameTooShort => SignalErrorCode::UsernameTooShort,
+            Self::NicknameTooLong => SignalErrorCode::UsernameTooLong,
+            Self::DiscriminatorCannotBeEmpty => SignalErrorCode::UsernameDiscriminatorCannotBeEmpty,
+            Self::DiscriminatorCannotBeZero => SignalErrorCode::UsernameDiscriminatorCannotBeZero,
+            Self::DiscriminatorCannotBeSingleDigit => {
+                SignalErrorCode::UsernameDiscriminatorCannotBeSingleDigit
+            }
+            Self::DiscriminatorCannotHaveLeadingZeros => {
+                SignalErrorCode::UsernameDiscriminatorCannotHaveLeadingZeros
+            }
+            Self::BadDiscriminatorCharacter => SignalErrorCode::UsernameBadDiscriminatorCharacter,
+            Self::DiscriminatorTooLarge => SignalErrorCode::UsernameDiscriminatorTooLarge,
+        }
+    }
+}
+
+impl ErrorCode for usernames::ProofVerificationFailure {
+    fn error_code(&self) -> SignalErrorCode {
+        SignalErrorCode::UsernameProofVerificationFailure
+    }
+}
+
+impl ErrorCode for UsernameLinkError {
+    fn error_code(&self) -> SignalErrorCode {
+        match self {
+            Self::InputDataTooLong => SignalErrorCode::UsernameLinkInputDataTooLong,
+            Self::InvalidEntropyDataLength => SignalErrorCode::UsernameLinkInvalidEntropyDataLength,
+            Self::UsernameLinkDataTooShort => SignalErrorCode::UsernameLinkDataTooShort,
+            Self::HmacMismatch => SignalErrorCode::UsernameLinkHmacMismatch,
+            Self::BadCiphertext => SignalErrorCode::UsernameLinkBadCiphertext,
+            Self::InvalidDecryptedDataStructure => {
+                SignalErrorCode::UsernameLinkInvalidDecryptedData
+            }
+        }
+    }
+}
+
+impl ErrorCode for SignalCryptoError {
+    fn error_code(&self) -> SignalErrorCode {
+        match self {
+            Self::UnknownAlgorithm(_, _) => SignalErrorCode::CryptoUnknownAlgorithm,
+            Self::InvalidKeySize => SignalErrorCode::CryptoInvalidKeySize,
+            Self::InvalidNonceSize => SignalErrorCode::CryptoInvalidNonceSize,
+            Self::InvalidInputSize => SignalErrorCode::CryptoInvalidInputSize,
+            Self::InvalidTag => SignalErrorCode::CryptoInvalidTag,
+        }
+    }
+}
+
+impl ErrorCode for PinError {
+    fn error_code(&self) -> SignalErrorCode {
+        match self {
+            Self::Argon2Error(_) => SignalErrorCode::PinArgon2Error,
+            Self::DecodingError(_) => SignalErrorCode::PinDecodingError,
+            Self::MrenclaveLookupError => SignalErrorCode::PinMrenclaveLookupError,
+        }
+    }
+}
+
+impl ErrorCode for DeviceTransferError {
+    fn error_code(&self) -> SignalErrorCode {
+        match self {
+            Self::KeyDecodingFailed => SignalErrorCode::DeviceTransferKeyDecodingFailed,
+            Self::InternalError(_) => SignalErrorCode::DeviceTransferInternalError,
+        }
+    }
+}
+
+#[cfg(feature = "signal-media")]
+mod media {
+    use signal_media::sanitize::{mp4, webp};
+
+    use super::*;
+
+    impl ErrorCode for mp4::Error {
+        fn error_code(&self) -> SignalErrorCode {
+            match self {
+                Self::Io(e) => e.error_code(),
+                Self::Parse(e) => e.error_code(),
+            }
+        }
+    }
+
+    impl ErrorCode for mp4::ParseErrorReport {
+        fn error_code(&self) -> SignalErrorCode {
+            match self.kind {
+                mp4::ParseError::InvalidBoxLayout { .. }
+                | mp4::ParseError::InvalidInput { .. }
+                | mp4::ParseError::MissingRequiredBox { .. }
+                | mp4::ParseError::TruncatedBox => SignalErrorCode::MediaInvalidInput,
+
+                mp4::ParseError::UnsupportedBoxLayout { .. }
+                | mp4::ParseError::UnsupportedBox { .. }
+                | mp4::ParseError::UnsupportedFormat { .. } => {
+                    SignalErrorCode::MediaUnsupportedInput
+                }
+            }
+        }
+    }
+
+    impl ErrorCode for webp::Error {
+        fn error_code(&self) -> SignalErrorCode {
+            match self {
+                Self::Io(e) => e.error_code(),
+                Self::Parse(e) => e.error_code(),
+            }
+        }
+    }
+
+    impl ErrorCode for webp::ParseErrorReport {
+        fn error_code(&self) -> SignalErrorCode {
+            match self.kind {
+                webp::ParseError::InvalidChunkLayout { .. }
+                | webp::ParseError::InvalidInput { .. }
+                | webp::ParseError::InvalidVp8lPrefixCode { .. }
+                | webp::ParseError::MissingRequiredChunk { .. }
+                | webp::ParseError::TruncatedChunk => SignalErrorCode::MediaInvalidInput,
+
+                webp::ParseError::UnsupportedChunk { .. }
+                | webp::ParseError::UnsupportedVp8lVersion { .. } => {
+                    SignalErrorCode::MediaUnsupportedInput
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::collections::HashSet;
+
+    use strum::IntoEnumIterator as _;
+    use test_case::test_case;
+
+    use super::*;
+
+    #[test]
+    fn codes_are_unique_and_round_trip() {
+        // Duplicate discriminants are already a compile error; this makes sure the numbers
+        // survive the trip to the app languages and back.
+        let mut seen = HashSet::new();
+        for code in SignalErrorCode::iter() {
+            let value = u32::from(code);
+            assert!(seen.insert(value), "{code:?} reuses {value}");
+            assert_eq!(SignalErrorCode::try_from(value).ok(), Some(code));
+        }
+        assert!(SignalErrorCode::try_from(0).is_err());
+    }
+
+    #[test]
+    fn codes_are_in_their_subsystem_range() {
+        const SUBSYSTEMS: &[(&[&str], std::ops::Range<u32>)] = &[
+            (
+                &[
+                    "Net",
+                    "Transport",
+                    "WebSocket",
+                    "Chat",
+                    "Cdsi",
+                    "Svr",
+                    "Cdn",
+                ],
+                1000..2000,
+            ),
+            (&["Backup"], 2000..3000),
+            (&["ZkGroup"], 3000..4000),
+            (&["Protocol"], 4000..5000),
+            (&["Attestation", "Enclave", "Hsm"], 5000..6000),
+            (&["Username"], 6000..7000),
+            (&["Crypto", "Pin", "DeviceTransfer"], 7000..8000),
+            (&["Media"], 8000..9000),
+        ];
+
+        for code in SignalErrorCode::iter() {
+            let name = format!("{code:?}");
+            let range = SUBSYSTEMS
+                .iter()
+                .find(|(prefixes, _)| prefixes.iter().any(|p| name.starts_with(p)))
+                .map_or(1..1000, |(_, range)| range.clone());
+            let value = u32::from(code);
+            assert!(
+                range.contains(&value),
+                "{name} = {value} is outside {range:?}"
+            );
+        }
+    }
+
+    #[test_case(
+        LookupError::ConnectTransport(TransportConnectError::DnsError),
+        SignalErrorCode::TransportDnsError,
+        1102;
+        "CDSI DNS failure"
+    )]
+    #[test_case(
+        LookupError::WebSocket(WebSocketServiceError::ChannelIdleTooLong),
+        SignalErrorCode::WebSocketChannelIdleTooLong,
+        1201;
+        "CDSI websocket idle"
+    )]
+    #[test_case(
+        LookupError::AttestationError(EnclaveError::AttestationMeasurementMismatch),
+        SignalErrorCode::AttestationMeasurementMismatch,
+        5002;
+        "CDSI attestation"
+    )]
+    #[test_case(
+        LookupError::RateLimited { retry_after_seconds: 30 },
+        SignalErrorCode::NetRateLimited,
+        1001;
+        "CDSI rate limited"
+    )]
+    fn lookup_error_codes(error: LookupError, expected: SignalErrorCode, value: u32) {
+        assert_eq!(error.error_code(), expected);
+        assert_eq!(u32::from(expected), value);
+    }
+
+    #[test]
+    fn nested_error_codes() {
+        assert_eq!(
+            Svr3Error::Connect(WebSocketConnectError::Transport(
+                TransportConnectError::UntrustedRoot
+            ))
+            .error_code(),
+            SignalErrorCode::TransportUntrustedRoot
+        );
+        assert_eq!(
+            Svr3Error::Connect(WebSocketConnectError::Timeout).error_code(),
+            SignalErrorCode::NetConnectionTimedOut
+        );
+        assert_eq!(
+            RateLimitChallengeError::Chat(ChatServiceError::AppExpired).error_code(),
+            SignalErrorCode::ChatAppExpired
+        );
+        assert_eq!(
+            ChatServiceError::WebSocket(WebSocketServiceError::ChannelClosed).error_code(),
+            SignalErrorCode::WebSocketChannelClosed
+        );
+        assert_eq!(
+            DownloadError::Sink(IoError::new(std::io::ErrorKind::Other, "disk full")).error_code(),
+            SignalErrorCode::IoError
+        );
+        assert_eq!(
+            libsignal_message_backup::ReadError {
+                error: CompletionError::NoSelfRecipient.into(),
+                found_unknown_fields: vec![],
+            }
+            .error_code(),
+            SignalErrorCode::BackupNoSelfRecipient
+        );
+        assert_eq!(u32::from(SignalErrorCode::BackupNoSelfRecipient), 2101);
+    }
+}
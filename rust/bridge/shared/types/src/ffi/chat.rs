@@ -3,12 +3,13 @@
 // SPDX-License-Identifier: AGPL-3.0-only
 //
 
-use std::ffi::{c_uchar, c_void};
+use std::ffi::{c_char, c_uchar, c_void, CStr};
 
+use libsignal_net::auth::{Auth, AuthProvider};
 use libsignal_net::chat::ChatServiceError;
 
 use super::*;
-use crate::net::chat::{ChatListener, MakeChatListener, ServerMessageAck};
+use crate::net::chat::{ChatListener, MakeChatAuthProvider, MakeChatListener, ServerMessageAck};
 
 type ReceivedIncomingMessage = extern "C" fn(
     ctx: *mut c_void,
@@ -88,3 +89,94 @@ impl ChatListener for ChatListenerStruct {
         )
     }
 }
+
+type CurrentAuth =
+    extern "C" fn(ctx: *mut c_void, username: *mut *const c_char, password: *mut *const c_char);
+type RefreshAuth = extern "C" fn(
+    ctx: *mut c_void,
+    username: *mut *const c_char,
+    password: *mut *const c_char,
+) -> bool;
+type DestroyChatAuthProvider = extern "C" fn(ctx: *mut c_void);
+
+/// Callbacks for [`AuthProvider`].
+///
+/// Both callbacks write credentials to `username` and `password` as NUL-terminated UTF-8 strings.
+/// These are copied as soon as the callback returns, so they only need to stay valid until the
+/// next callback is made. `refresh` returns `false` (and doesn't have to write anything) if there
+/// are no new credentials to try.
+///
+/// Callbacks will not be made concurrently, but may not always happen on the same thread. They're
+/// made while connecting, so they should return promptly.
+#[repr(C)]
+#[derive(Copy, Clone)]
+pub struct FfiChatAuthProviderStruct {
+    ctx: *mut c_void,
+    current: CurrentAuth,
+    refresh: RefreshAuth,
+    destroy: DestroyChatAuthProvider,
+}
+
+pub type FfiMakeChatAuthProviderStruct = FfiChatAuthProviderStruct;
+
+// SAFETY: Auth providers are used from multiple threads. It's up to the creator of the C struct to
+// make sure `ctx` is appropriate for this.
+unsafe impl Send for FfiChatAuthProviderStruct {}
+unsafe impl Sync for FfiChatAuthProviderStruct {}
+
+impl MakeChatAuthProvider for &FfiChatAuthProviderStruct {
+    fn make_auth_provider(&self) -> Box<dyn AuthProvider> {
+        Box::new(ChatAuthProviderStruct(**self))
+    }
+}
+
+struct ChatAuthProviderStruct(FfiChatAuthProviderStruct);
+
+impl Drop for ChatAuthProviderStruct {
+    fn drop(&mut self) {
+        (self.0.destroy)(self.0.ctx);
+    }
+}
+
+/// Copies credentials written by one of the [`FfiChatAuthProviderStruct`] callbacks.
+fn copy_auth(username: *const c_char, password: *const c_char) -> Option<Auth> {
+    if username.is_null() || password.is_null() {
+        log::error!("chat auth provider did not provide credentials");
+        return None;
+    }
+    // SAFETY: the callbacks promise NUL-terminated strings that are valid until the next callback.
+    let (username, password) = unsafe { (CStr::from_ptr(username), CStr::from_ptr(password)) };
+    match (username.to_str(), password.to_str()) {
+        (Ok(username), Ok(password)) => Some(Auth {
+            username: username.to_owned(),
+            password: password.to_owned(),
+        }),
+        _ => {
+            log::error!("chat auth provider returned credentials that weren't valid UTF-8");
+            None
+        }
+    }
+}
+
+impl AuthProvider for ChatAuthProviderStruct {
+    fn current(&self) -> Auth {
+        let mut username = std::ptr::null();
+        let mut password = std::ptr::null();
+        (self.0.current)(self.0.ctx, &mut username, &mut password);
+        // Connecting with empty credentials will be rejected, which gives `refresh` a chance to do
+        // better.
+        copy_auth(username, password).unwrap_or_else(|| Auth {
+            username: String::new(),
+            password: String::new(),
+        })
+    }
+
+    fn refresh(&self) -> Option<Auth> {
+        let mut username = std::ptr::null();
+        let mut password = std::ptr::null();
+        if !(self.0.refresh)(self.0.ctx, &mut username, &mut password) {
+            return None;
+        }
+        copy_auth(username, password)
+    }
+}
@@ -13,8 +13,9 @@ use paste::paste;
 use uuid::Uuid;
 
 use super::*;
-use crate::io::{InputStream, SyncInputStream};
-use crate::net::chat::MakeChatListener;
+use crate::io::{InputStream, OutputStream, SyncInputStream};
+use crate::message_backup::BackupProgressListener;
+use crate::net::chat::{MakeChatAuthProvider, MakeChatListener};
 use crate::support::{extend_lifetime, AsType, FixedLengthBincodeSerializable, Serialized};
 
 /// Converts arguments from their FFI form to their Rust form.
@@ -385,7 +386,10 @@ bridge_trait!(SignedPreKeyStore);
 bridge_trait!(KyberPreKeyStore);
 bridge_trait!(InputStream);
 bridge_trait!(SyncInputStream);
+bridge_trait!(OutputStream);
+bridge_trait!(BackupProgressListener);
 bridge_trait!(MakeChatListener);
+bridge_trait!(MakeChatAuthProvider);
 
 impl<T: ResultTypeInfo, E> ResultTypeInfo for Result<T, E>
 where
@@ -663,6 +667,38 @@ impl ResultTypeInfo for libsignal_net::cdsi::LookupResponse {
     }
 }
 
+impl ResultTypeInfo for crate::net::cdsi::LookupResponseEntries {
+    type ResultType = FfiCdsiLookupEntries;
+    fn convert_into(self) -> SignalFfiResult<Self::ResultType> {
+        let Self {
+            entries,
+            debug_permits_used,
+        } = self;
+
+        let entries = entries
+            .into_iter()
+            .map(|e| FfiCdsiLookupEntry {
+                e164: NonZeroU64::from(e.e164).into(),
+                aci: e.aci.map_or([0; 17], |aci| {
+                    ServiceId::from(aci).service_id_fixed_width_binary()
+                }),
+                has_aci: e.aci.is_some(),
+                pni: e.pni.map_or([0; 17], |pni| {
+                    ServiceId::from(pni).service_id_fixed_width_binary()
+                }),
+                has_pni: e.pni.is_some(),
+            })
+            .collect::<Vec<_>>()
+            .into_boxed_slice()
+            .into();
+
+        Ok(FfiCdsiLookupEntries {
+            entries,
+            debug_permits_used,
+        })
+    }
+}
+
 impl ResultTypeInfo for libsignal_net::chat::Response {
     type ResultType = FfiChatResponse;
 
@@ -703,6 +739,8 @@ impl ResultTypeInfo for libsignal_net::chat::DebugInfo {
         let Self {
             ip_type,
             duration,
+            route_type,
+            remote_address,
             connection_info,
         } = self;
 
@@ -710,6 +748,11 @@ impl ResultTypeInfo for libsignal_net::chat::DebugInfo {
             raw_ip_type: ip_type as u8,
             duration_secs: duration.as_secs_f64(),
             connection_info: connection_info.convert_into()?,
+            raw_route_type: crate::net::chat::route_type_code(route_type),
+            remote_ip: remote_address
+                .map(|address| address.ip().to_string())
+                .convert_into()?,
+            remote_port: remote_address.map_or(0, |address| address.port()),
         })
     }
 }
@@ -902,6 +945,7 @@ macro_rules! ffi_result_type {
     (Box<[Vec<u8>]>) => (ffi::BytestringArray);
 
     (LookupResponse) => (ffi::FfiCdsiLookupResponse);
+    (LookupResponseEntries) => (ffi::FfiCdsiLookupEntries);
     (ChatResponse) => (ffi::FfiChatResponse);
     (ChatServiceDebugInfo) => (ffi::FfiChatServiceDebugInfo);
     (ResponseAndDebugInfo) => (ffi::FfiResponseAndDebugInfo);
@@ -9,6 +9,8 @@ use std::io::{Error as IoError, ErrorKind as IoErrorKind};
 use attest::enclave::Error as EnclaveError;
 use attest::hsm_enclave::Error as HsmEnclaveError;
 use device_transfer::Error as DeviceTransferError;
+use libsignal_net::cdn::DownloadError;
+use libsignal_net::chat::challenge::RateLimitChallengeError;
 use libsignal_net::chat::ChatServiceError;
 use libsignal_net::infra::ws::WebSocketConnectError;
 use libsignal_net::svr3::Error as Svr3Error;
@@ -19,7 +21,8 @@ use usernames::{UsernameError, UsernameLinkError};
 use zkgroup::{ZkGroupDeserializationFailure, ZkGroupVerificationFailure};
 
 use super::{FutureCancelled, NullPointerError, UnexpectedPanic};
-use crate::support::describe_panic;
+use crate::error_code::{self, ErrorCode};
+use crate::support::{describe_panic, RuntimeClosed};
 
 #[derive(Debug)]
 #[repr(C)]
@@ -46,6 +49,8 @@ pub enum SignalErrorCode {
     InvalidKey = 40,
     InvalidSignature = 41,
     InvalidAttestationData = 42,
+    AttestationExpired = 43,
+    AttestationMeasurementMismatch = 44,
 
     FingerprintVersionMismatch = 51,
     FingerprintParsingError = 52,
@@ -118,7 +123,7 @@ impl<T: std::any::Any> UpcastAsAny for T {
 /// Error returned when asking for an attribute of an error that doesn't support that attribute.
 pub struct WrongErrorKind;
 
-pub trait FfiError: UpcastAsAny + fmt::Debug + Send + 'static {
+pub trait FfiError: UpcastAsAny + ErrorCode + fmt::Debug + Send + 'static {
     fn describe(&self) -> String;
     fn code(&self) -> SignalErrorCode;
 
@@ -304,6 +309,8 @@ impl FfiError for EnclaveError {
                 SignalErrorCode::InvalidMessage
             }
             Self::AttestationDataError { .. } => SignalErrorCode::InvalidAttestationData,
+            Self::AttestationExpired { .. } => SignalErrorCode::AttestationExpired,
+            Self::AttestationMeasurementMismatch => SignalErrorCode::AttestationMeasurementMismatch,
             Self::InvalidBridgeStateError => SignalErrorCode::InvalidState,
         }
     }
@@ -447,7 +454,9 @@ impl FfiError for libsignal_net::cdsi::LookupError {
             Self::ConnectTransport(e) => format!("IO error: {e}"),
             Self::WebSocket(e) => format!("WebSocket error: {e}"),
             Self::ConnectionTimedOut => "Connect timed out".to_owned(),
-            Self::InvalidArgument { .. } => format!("invalid argument: {self}"),
+            Self::InvalidArgument { .. } | Self::InvalidPrebuiltRequest(_) => {
+                format!("invalid argument: {self}")
+            }
         }
     }
 
@@ -462,7 +471,9 @@ impl FfiError for libsignal_net::cdsi::LookupError {
             Self::ConnectTransport(_) => SignalErrorCode::IoError,
             Self::WebSocket(_) => SignalErrorCode::WebSocket,
             Self::ConnectionTimedOut => SignalErrorCode::ConnectionTimedOut,
-            Self::InvalidArgument { .. } => SignalErrorCode::InvalidArgument,
+            Self::InvalidArgument { .. } | Self::InvalidPrebuiltRequest(_) => {
+                SignalErrorCode::InvalidArgument
+            }
         }
     }
 
@@ -476,6 +487,92 @@ impl FfiError for libsignal_net::cdsi::LookupError {
     }
 }
 
+impl FfiError for DownloadError {
+    fn describe(&self) -> String {
+        match self {
+            Self::UnknownCdn(_) | Self::InvalidArgument(_) => format!("invalid argument: {self}"),
+            Self::Connection(_) | Self::Sink(_) => format!("IO error: {self}"),
+            Self::RateLimited {
+                retry_after_seconds: Some(retry_after_seconds),
+            } => format!("Rate limited; try again after {retry_after_seconds}s"),
+            Self::RateLimited {
+                retry_after_seconds: None,
+            }
+            | Self::ServerError(_)
+            | Self::NotFound
+            | Self::UnexpectedStatus(_)
+            | Self::InvalidResponse(_) => format!("Protocol error: {self}"),
+            Self::DigestMismatch => self.to_string(),
+        }
+    }
+
+    fn code(&self) -> SignalErrorCode {
+        match self {
+            Self::UnknownCdn(_) | Self::InvalidArgument(_) => SignalErrorCode::InvalidArgument,
+            Self::Connection(_) | Self::Sink(_) => SignalErrorCode::IoError,
+            Self::RateLimited {
+                retry_after_seconds: Some(_),
+            } => SignalErrorCode::RateLimited,
+            Self::RateLimited {
+                retry_after_seconds: None,
+            }
+            | Self::ServerError(_)
+            | Self::NotFound
+            | Self::UnexpectedStatus(_)
+            | Self::InvalidResponse(_) => SignalErrorCode::NetworkProtocol,
+            Self::DigestMismatch => SignalErrorCode::VerificationFailure,
+        }
+    }
+
+    fn provide_retry_after_seconds(&self) -> Result<u32, WrongErrorKind> {
+        match self {
+            Self::RateLimited {
+                retry_after_seconds: Some(retry_after_seconds),
+            } => Ok(*retry_after_seconds),
+            _ => Err(WrongErrorKind),
+        }
+    }
+}
+
+impl FfiError for RateLimitChallengeError {
+    fn describe(&self) -> String {
+        match self {
+            Self::Chat(e) => e.describe(),
+            Self::RateLimited {
+                retry_after_seconds: Some(retry_after_seconds),
+            } => format!("Rate limited; try again after {retry_after_seconds}s"),
+            Self::Rejected => format!("Challenge failed: {self}"),
+            Self::RateLimited {
+                retry_after_seconds: None,
+            }
+            | Self::UnexpectedStatus(_) => format!("Protocol error: {self}"),
+        }
+    }
+
+    fn code(&self) -> SignalErrorCode {
+        match self {
+            Self::Chat(e) => e.code(),
+            Self::RateLimited {
+                retry_after_seconds: Some(_),
+            } => SignalErrorCode::RateLimited,
+            Self::Rejected => SignalErrorCode::VerificationFailure,
+            Self::RateLimited {
+                retry_after_seconds: None,
+            }
+            | Self::UnexpectedStatus(_) => SignalErrorCode::NetworkProtocol,
+        }
+    }
+
+    fn provide_retry_after_seconds(&self) -> Result<u32, WrongErrorKind> {
+        match self {
+            Self::RateLimited {
+                retry_after_seconds: Some(retry_after_seconds),
+            } => Ok(*retry_after_seconds),
+            _ => Err(WrongErrorKind),
+        }
+    }
+}
+
 impl FfiError for Svr3Error {
     fn describe(&self) -> String {
         match self {
@@ -485,7 +582,8 @@ impl FfiError for Svr3Error {
             Self::Connect(WebSocketConnectError::Transport(e)) => format!("IO error: {e}"),
             Self::Connect(
                 e @ (WebSocketConnectError::WebSocketError(_)
-                | WebSocketConnectError::RejectedByServer { .. }),
+                | WebSocketConnectError::RejectedByServer { .. }
+                | WebSocketConnectError::SubProtocol(_)),
             ) => {
                 format!("WebSocket error: {e}")
             }
@@ -495,7 +593,9 @@ impl FfiError for Svr3Error {
             Self::RequestFailed(_)
             | Self::RestoreFailed(_)
             | Self::DataMissing
-            | Self::RotationMachineTooManySteps => {
+            | Self::RotationMachineTooManySteps
+            | Self::CircuitOpen
+            | Self::PasswordRotationIncomplete { .. } => {
                 format!("SVR error: {self}")
             }
         }
@@ -507,16 +607,18 @@ impl FfiError for Svr3Error {
                 WebSocketConnectError::Transport(_) => SignalErrorCode::IoError,
                 WebSocketConnectError::Timeout => SignalErrorCode::ConnectionTimedOut,
                 WebSocketConnectError::WebSocketError(_)
-                | WebSocketConnectError::RejectedByServer { .. } => SignalErrorCode::WebSocket,
+                | WebSocketConnectError::RejectedByServer { .. }
+                | WebSocketConnectError::SubProtocol(_) => SignalErrorCode::WebSocket,
             },
             Self::Service(_) => SignalErrorCode::WebSocket,
             Self::ConnectionTimedOut => SignalErrorCode::ConnectionTimedOut,
             Self::AttestationError(inner) => inner.code(),
             Self::Protocol(_) => SignalErrorCode::NetworkProtocol,
-            Self::RequestFailed(_) => SignalErrorCode::UnknownError,
+            Self::RequestFailed(_) | Self::CircuitOpen => SignalErrorCode::UnknownError,
             Self::RestoreFailed(_) => SignalErrorCode::SvrRestoreFailed,
             Self::DataMissing => SignalErrorCode::SvrDataMissing,
             Self::RotationMachineTooManySteps => SignalErrorCode::SvrRotationMachineTooManySteps,
+            Self::PasswordRotationIncomplete { cause, .. } => cause.code(),
         }
     }
 
@@ -541,8 +643,10 @@ impl FfiError for ChatServiceError {
             Self::FailedToPassMessageToIncomingChannel | Self::RequestHasInvalidHeader => {
                 format!("internal error: {self}")
             }
-            Self::Timeout | Self::TimeoutEstablishingConnection { .. } => {
-                "Connect timed out".to_owned()
+            Self::Timeout { trace_id, .. } => format!("Request timed out (trace ID {trace_id})"),
+            Self::TimeoutEstablishingConnection { .. } => "Connect timed out".to_owned(),
+            Self::RequestChannelClosed { trace_id } => {
+                format!("WebSocket error: channel closed (trace ID {trace_id})")
             }
             Self::ServiceInactive => "Chat service inactive".to_owned(),
             Self::AppExpired => "App expired".to_owned(),
@@ -550,12 +654,15 @@ impl FfiError for ChatServiceError {
             Self::ServiceIntentionallyDisconnected => {
                 "Chat service explicitly disconnected".to_owned()
             }
+            Self::ServerRequestedReconnect => "Chat server requested a reconnect".to_owned(),
         }
     }
 
     fn code(&self) -> SignalErrorCode {
         match self {
-            Self::WebSocket(_) => SignalErrorCode::WebSocket,
+            Self::WebSocket(_)
+            | Self::RequestChannelClosed { .. }
+            | Self::ServerRequestedReconnect => SignalErrorCode::WebSocket,
             Self::AllConnectionRoutesFailed { .. } | Self::ServiceUnavailable => {
                 SignalErrorCode::ConnectionFailed
             }
@@ -565,7 +672,7 @@ impl FfiError for ChatServiceError {
             Self::FailedToPassMessageToIncomingChannel | Self::RequestHasInvalidHeader => {
                 SignalErrorCode::InternalError
             }
-            Self::Timeout | Self::TimeoutEstablishingConnection { .. } => {
+            Self::Timeout { .. } | Self::TimeoutEstablishingConnection { .. } => {
                 SignalErrorCode::ConnectionTimedOut
             }
             Self::ServiceInactive => SignalErrorCode::ChatServiceInactive,
@@ -671,6 +778,12 @@ impl FfiError for NullPointerError {
     }
 }
 
+impl ErrorCode for NullPointerError {
+    fn error_code(&self) -> error_code::SignalErrorCode {
+        error_code::SignalErrorCode::NullParameter
+    }
+}
+
 impl FfiError for UnexpectedPanic {
     fn describe(&self) -> String {
         format!("unexpected panic: {}", describe_panic(&self.0))
@@ -681,6 +794,12 @@ impl FfiError for UnexpectedPanic {
     }
 }
 
+impl ErrorCode for UnexpectedPanic {
+    fn error_code(&self) -> error_code::SignalErrorCode {
+        error_code::SignalErrorCode::UnexpectedPanic
+    }
+}
+
 impl FfiError for std::str::Utf8Error {
     fn describe(&self) -> String {
         "invalid UTF8 string".to_owned()
@@ -691,6 +810,22 @@ impl FfiError for std::str::Utf8Error {
     }
 }
 
+impl ErrorCode for std::str::Utf8Error {
+    fn error_code(&self) -> error_code::SignalErrorCode {
+        error_code::SignalErrorCode::InvalidUtf8String
+    }
+}
+
+impl FfiError for RuntimeClosed {
+    fn describe(&self) -> String {
+        self.to_string()
+    }
+
+    fn code(&self) -> SignalErrorCode {
+        SignalErrorCode::InvalidState
+    }
+}
+
 impl FfiError for FutureCancelled {
     fn describe(&self) -> String {
         "cancelled".to_owned()
@@ -701,6 +836,12 @@ impl FfiError for FutureCancelled {
     }
 }
 
+impl ErrorCode for FutureCancelled {
+    fn error_code(&self) -> error_code::SignalErrorCode {
+        error_code::SignalErrorCode::Cancelled
+    }
+}
+
 pub type SignalFfiResult<T> = Result<T, SignalFfiError>;
 
 /// Represents an error returned by a callback, following the C conventions that 0 means "success".
@@ -8,7 +8,7 @@ use std::future::Future;
 use futures_util::{FutureExt, TryFutureExt};
 
 use super::*;
-use crate::support::{AsyncRuntime, ResultReporter};
+use crate::support::{AsyncRuntime, ResultReporter, RuntimeClosed};
 
 #[derive(Debug)]
 pub struct FutureCancelled;
@@ -102,33 +102,40 @@ impl<T: ResultTypeInfo + std::panic::UnwindSafe> ResultReporter for FutureResult
 ///
 /// `promise_context` is passed through unchanged.
 ///
+/// Fails with [`RuntimeClosed`] without starting anything if `runtime` has been shut down, in
+/// which case `promise` will never be completed.
+///
 /// ## Example
 ///
 /// ```no_run
 /// # use libsignal_bridge_types::ffi::*;
 /// # use libsignal_bridge_types::{AsyncRuntime, ResultReporter};
 /// # use libsignal_bridge_types::support::NoOpAsyncRuntime;
-/// # fn test(promise: &mut CPromise<i32>, async_runtime: &NoOpAsyncRuntime) {
+/// # fn test(promise: &mut CPromise<i32>, async_runtime: &NoOpAsyncRuntime) -> SignalFfiResult<()> {
 /// run_future_on_runtime(async_runtime, promise, |_cancel| async {
 ///     let result: i32 = 1 + 2;
 ///     // Do some complicated awaiting here.
 ///     FutureResultReporter::new(Ok(result))
-/// });
+/// })?;
+/// # Ok(())
 /// # }
 #[inline]
 pub fn run_future_on_runtime<R, F, O>(
     runtime: &R,
     promise: &mut CPromise<O::ResultType>,
     future: impl FnOnce(R::Cancellation) -> F,
-) where
+) -> SignalFfiResult<()>
+where
     R: AsyncRuntime<F>,
     F: Future + std::panic::UnwindSafe + 'static,
     F::Output: ResultReporter<Receiver = PromiseCompleter<O>>,
     O: ResultTypeInfo + 'static,
 {
+    runtime.check_open()?;
     let completion = PromiseCompleter { promise: *promise };
     let cancellation_id = runtime.run_future(future, completion);
     promise.cancellation_id = cancellation_id.into();
+    Ok(())
 }
 
 /// Catches panics that occur in `future` and converts them to [`SignalFfiError::UnexpectedPanic`].
@@ -10,11 +10,13 @@ use async_trait::async_trait;
 use libsignal_protocol::SignalProtocolError;
 
 use super::CallbackError;
-use crate::io::{InputStream, InputStreamRead, SyncInputStream};
+use crate::io::{InputStream, InputStreamRead, OutputStream, SyncInputStream};
 
 type Read =
     extern "C" fn(ctx: *mut c_void, buf: *mut u8, buf_len: usize, amount_read: *mut usize) -> c_int;
 type Skip = extern "C" fn(ctx: *mut c_void, amount: u64) -> c_int;
+type Write = extern "C" fn(ctx: *mut c_void, buf: *const u8, buf_len: usize) -> c_int;
+type Flush = extern "C" fn(ctx: *mut c_void) -> c_int;
 
 #[repr(C)]
 #[derive(Copy, Clone)]
@@ -26,6 +28,14 @@ pub struct FfiInputStreamStruct {
 
 pub type FfiSyncInputStreamStruct = FfiInputStreamStruct;
 
+#[repr(C)]
+#[derive(Copy, Clone)]
+pub struct FfiOutputStreamStruct {
+    ctx: *mut c_void,
+    write: Write,
+    flush: Flush,
+}
+
 impl FfiInputStreamStruct {
     fn do_read(&self, buf: &mut [u8]) -> io::Result<usize> {
         let mut amount_read = 0;
@@ -67,3 +77,22 @@ impl SyncInputStream for &FfiInputStreamStruct {
         self.do_skip(amount)
     }
 }
+
+#[async_trait(?Send)]
+impl OutputStream for &FfiOutputStreamStruct {
+    async fn write(&self, buf: &[u8]) -> io::Result<()> {
+        let result = (self.write)(self.ctx, buf.as_ptr(), buf.len());
+        CallbackError::check(result).map_err(|e| {
+            let err = SignalProtocolError::for_application_callback("write")(e);
+            io::Error::new(io::ErrorKind::Other, err)
+        })
+    }
+
+    async fn flush(&self) -> io::Result<()> {
+        let result = (self.flush)(self.ctx);
+        CallbackError::check(result).map_err(|e| {
+            let err = SignalProtocolError::for_application_callback("flush")(e);
+            io::Error::new(io::ErrorKind::Other, err)
+        })
+    }
+}
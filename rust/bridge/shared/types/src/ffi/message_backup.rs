@@ -0,0 +1,27 @@
+//
+// Copyright 2024 Signal Messenger, LLC.
+// SPDX-License-Identifier: AGPL-3.0-only
+//
+
+use std::ffi::c_void;
+
+use crate::message_backup::BackupProgressListener;
+
+type OnBackupProgress =
+    extern "C" fn(ctx: *mut c_void, frames_read: u64, bytes_read: u64, total_bytes: u64);
+
+/// Callback for [`BackupProgressListener`].
+///
+/// Called synchronously on the thread doing the validation.
+#[repr(C)]
+#[derive(Copy, Clone)]
+pub struct FfiBackupProgressListenerStruct {
+    ctx: *mut c_void,
+    on_progress: OnBackupProgress,
+}
+
+impl BackupProgressListener for &FfiBackupProgressListenerStruct {
+    fn on_progress(&self, frames_read: u64, bytes_read: u64, total_bytes: u64) {
+        (self.on_progress)(self.ctx, frames_read, bytes_read, total_bytes)
+    }
+}
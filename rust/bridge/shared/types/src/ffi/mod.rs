@@ -24,6 +24,9 @@ pub use futures::*;
 mod io;
 pub use io::*;
 
+mod message_backup;
+pub use message_backup::*;
+
 mod storage;
 pub use storage::*;
 
@@ -163,6 +166,26 @@ pub struct FfiCdsiLookupResponse {
     debug_permits_used: i32,
 }
 
+#[repr(C)]
+#[derive(Debug)]
+pub struct FfiCdsiLookupEntry {
+    /// Telephone number, as an unformatted e164.
+    pub e164: u64,
+    /// Only meaningful if `has_aci` is set.
+    pub aci: ServiceIdFixedWidthBinaryBytes,
+    pub has_aci: bool,
+    /// Only meaningful if `has_pni` is set.
+    pub pni: ServiceIdFixedWidthBinaryBytes,
+    pub has_pni: bool,
+}
+
+#[repr(C)]
+#[derive(Debug)]
+pub struct FfiCdsiLookupEntries {
+    entries: OwnedBufferOf<FfiCdsiLookupEntry>,
+    debug_permits_used: i32,
+}
+
 /// A type alias to be used with [`OwnedBufferOf`], so that `OwnedBufferOf<c_char>` and
 /// `OwnedBufferOf<*const c_char>` get distinct names.
 pub type CStringPtr = *const std::ffi::c_char;
@@ -182,6 +205,11 @@ pub struct FfiChatServiceDebugInfo {
     raw_ip_type: u8,
     duration_secs: f64,
     connection_info: *const std::ffi::c_char,
+    raw_route_type: u8,
+    /// `NULL` if the connection wasn't made to a known IP address.
+    remote_ip: *const std::ffi::c_char,
+    /// 0 if the connection wasn't made to a known IP address.
+    remote_port: u16,
 }
 
 #[repr(C)]
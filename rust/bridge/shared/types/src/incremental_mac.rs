@@ -15,6 +15,14 @@ pub struct IncrementalMac(pub Option<Incremental<Hmac<Digest>>>);
 
 bridge_as_handle!(IncrementalMac, mut = true);
 
+/// An [`IncrementalMac`] that also computes a plain digest of the same input.
+///
+/// This lets an attachment's incremental MAC and its overall digest be produced in one pass.
+#[derive(Clone)]
+pub struct IncrementalMacAndDigest(pub Option<(Incremental<Hmac<Digest>>, Digest)>);
+
+bridge_as_handle!(IncrementalMacAndDigest, mut = true);
+
 #[derive(Clone)]
 pub struct ValidatingMac(pub Option<Validating<Hmac<Digest>>>);
 
@@ -28,4 +36,12 @@ impl Drop for IncrementalMac {
     }
 }
 
+impl Drop for IncrementalMacAndDigest {
+    fn drop(&mut self) {
+        if self.0.is_some() {
+            log::warn!("{}", UNEXPECTED_DROP_MESSAGE);
+        }
+    }
+}
+
 pub static UNEXPECTED_DROP_MESSAGE: &str = "MAC is dropped without calling finalize";
@@ -83,6 +83,25 @@ pub trait SyncInputStream {
     fn skip(&self, amount: u64) -> io::Result<()>;
 }
 
+/// An output stream of bytes.
+#[async_trait(?Send)]
+pub trait OutputStream {
+    /// Write all of `buf` to the output stream.
+    ///
+    /// # Errors
+    ///
+    /// If an I/O error occurred while writing to the output, an [`io::Error`] is returned. Some of `buf` may have been
+    /// written anyway.
+    async fn write(&self, buf: &[u8]) -> io::Result<()>;
+
+    /// Make sure everything written so far has reached its destination.
+    ///
+    /// # Errors
+    ///
+    /// If an I/O error occurred while flushing the output, an [`io::Error`] is returned.
+    async fn flush(&self) -> io::Result<()>;
+}
+
 pub struct SyncInput<'a> {
     stream: &'a dyn SyncInputStream,
     pos: u64,
@@ -14,8 +14,10 @@ use libsignal_protocol::*;
 use paste::paste;
 
 use super::*;
-use crate::io::{InputStream, SyncInputStream};
-use crate::message_backup::MessageBackupValidationOutcome;
+use crate::io::{InputStream, OutputStream, SyncInputStream};
+use crate::message_backup::{
+    BackupProgressListener, MessageBackupValidationFailure, MessageBackupValidationOutcome,
+};
 use crate::net::chat::ResponseAndDebugInfo;
 use crate::support::{Array, AsType, FixedLengthBincodeSerializable, Serialized};
 
@@ -455,6 +457,8 @@ bridge_trait!(SignedPreKeyStore);
 bridge_trait!(KyberPreKeyStore);
 bridge_trait!(InputStream);
 bridge_trait!(SyncInputStream);
+bridge_trait!(OutputStream);
+bridge_trait!(BackupProgressListener);
 
 /// A translation from a Java interface where the implementing class wraps the Rust handle.
 impl<'a> SimpleArgTypeInfo<'a> for CiphertextMessageRef<'a> {
@@ -1048,6 +1052,81 @@ impl<'a> ResultTypeInfo<'a> for libsignal_net::cdsi::LookupResponse {
     }
 }
 
+impl<'a> ResultTypeInfo<'a> for crate::net::cdsi::LookupResponseEntries {
+    type ResultType = JObject<'a>;
+
+    fn convert_into(self, env: &mut JNIEnv<'a>) -> Result<Self::ResultType, BridgeLayerError> {
+        let Self {
+            entries,
+            debug_permits_used,
+        } = self;
+
+        const ENTRY_CLASS: ClassName =
+            ClassName("org.signal.libsignal.net.CdsiLookupEntries$Entry");
+        let entry_class = find_class(env, ENTRY_CLASS)?;
+
+        let len = entries.len();
+        let entries_array = env
+            .new_object_array(
+                len.try_into().map_err(|_| {
+                    BridgeLayerError::IntegerOverflow(format!("{len}_usize to i32"))
+                })?,
+                &entry_class,
+                JObject::null(),
+            )
+            .check_exceptions(env, "LookupResponseEntries::convert_into")?;
+
+        for (index, entry) in entries.into_iter().enumerate() {
+            let LookupResponseEntry { aci, e164, pni } = entry;
+            let e164 = AutoLocal::new(
+                JObject::from(
+                    env.new_string(e164.to_string())
+                        .check_exceptions(env, "LookupResponseEntries::convert_into")?,
+                ),
+                env,
+            );
+            // Missing IDs are passed as null.
+            let aci = AutoLocal::new(
+                aci.map(|aci| aci.convert_into(env))
+                    .transpose()?
+                    .unwrap_or_default(),
+                env,
+            );
+            let pni = AutoLocal::new(
+                pni.map(|pni| pni.convert_into(env))
+                    .transpose()?
+                    .unwrap_or_default(),
+                env,
+            );
+
+            let entry = AutoLocal::new(
+                new_object(
+                    env,
+                    &entry_class,
+                    jni_args!((e164 => java.lang.String, aci => [byte], pni => [byte]) -> void),
+                )
+                .check_exceptions(env, ENTRY_CLASS.0)?,
+                env,
+            );
+            env.set_object_array_element(
+                &entries_array,
+                index.try_into().expect("max size validated above"),
+                entry,
+            )
+            .check_exceptions(env, "LookupResponseEntries::convert_into")?;
+        }
+
+        new_instance(
+            env,
+            ClassName("org.signal.libsignal.net.CdsiLookupEntries"),
+            jni_args!((
+                entries_array => [org.signal.libsignal.net.CdsiLookupEntries::Entry],
+                debug_permits_used => int,
+            ) -> void),
+        )
+    }
+}
+
 impl<'a> ResultTypeInfo<'a> for libsignal_net::chat::Response {
     type ResultType = JObject<'a>;
 
@@ -1106,6 +1185,8 @@ impl<'a> ResultTypeInfo<'a> for libsignal_net::chat::DebugInfo {
         let Self {
             ip_type,
             duration,
+            route_type,
+            remote_address,
             connection_info,
         } = self;
 
@@ -1120,6 +1201,15 @@ impl<'a> ResultTypeInfo<'a> for libsignal_net::chat::DebugInfo {
             .new_string(connection_info)
             .check_exceptions(env, "DebugInfo::convert_into")?;
 
+        // route type as code
+        let route_type_byte = crate::net::chat::route_type_code(route_type) as i8;
+
+        // remote address as a (nullable) IP string and a port, 0 if unknown
+        let remote_ip_string = remote_address
+            .map(|address| address.ip().to_string())
+            .convert_into(env)?;
+        let remote_port: i32 = remote_address.map_or(0, |address| address.port()).into();
+
         new_instance(
             env,
             ClassName("org.signal.libsignal.net.ChatService$DebugInfo"),
@@ -1127,6 +1217,9 @@ impl<'a> ResultTypeInfo<'a> for libsignal_net::chat::DebugInfo {
                 ip_type_byte => byte,
                 duration_ms => int,
                 connection_info_string => java.lang.String,
+                route_type_byte => byte,
+                remote_ip_string => java.lang.String,
+                remote_port => int,
             ) -> void),
         )
     }
@@ -1215,21 +1308,47 @@ impl<'a> ResultTypeInfo<'a> for MessageBackupValidationOutcome {
 
     fn convert_into(self, env: &mut JNIEnv<'a>) -> Result<Self::ResultType, BridgeLayerError> {
         let Self {
-            error_message,
+            error,
             found_unknown_fields,
         } = self;
 
+        let unknown_fields_json =
+            libsignal_message_backup::FoundUnknownField::to_json_array(&found_unknown_fields)
+                .convert_into(env)?;
         let unknown_fields = make_object_array(
             env,
             jni_class_name!(java.lang.String),
             found_unknown_fields.into_iter().map(|f| f.to_string()),
         )?;
-        let error_message = error_message.convert_into(env)?;
+        // Pair<Integer, String>, or null if there was no error
+        let error = match error {
+            None => JObject::null(),
+            Some(MessageBackupValidationFailure { kind, message }) => {
+                let kind = jint::from(kind as u8);
+                let kind = new_instance(
+                    env,
+                    ClassName("java.lang.Integer"),
+                    jni_args!((kind => int) -> void),
+                )?;
+                let message = message.convert_into(env)?;
+                new_instance(
+                    env,
+                    ClassName("org.signal.libsignal.protocol.util.Pair"),
+                    jni_args!((kind => java.lang.Object, message => java.lang.Object) -> void),
+                )?
+            }
+        };
 
+        // Pair<Pair<Integer, String>, Pair<String[], String>>
+        let unknown_fields = new_instance(
+            env,
+            ClassName("org.signal.libsignal.protocol.util.Pair"),
+            jni_args!((unknown_fields => java.lang.Object, unknown_fields_json => java.lang.Object) -> void),
+        )?;
         new_instance(
             env,
             ClassName("org.signal.libsignal.protocol.util.Pair"),
-            jni_args!((error_message => java.lang.Object, unknown_fields => java.lang.Object) -> void),
+            jni_args!((error => java.lang.Object, unknown_fields => java.lang.Object) -> void),
         )
     }
 }
@@ -1506,6 +1625,9 @@ macro_rules! jni_result_type {
     (LookupResponse) => {
         ::jni::objects::JObject<'local>
     };
+    (LookupResponseEntries) => {
+        ::jni::objects::JObject<'local>
+    };
     (ChatResponse) => {
         ::jni::objects::JObject<'local>
     };
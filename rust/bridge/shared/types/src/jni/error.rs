@@ -20,8 +20,9 @@ use usernames::{UsernameError, UsernameLinkError};
 use zkgroup::{ZkGroupDeserializationFailure, ZkGroupVerificationFailure};
 
 use super::*;
+use crate::error_code::{ErrorCode, SignalErrorCode};
 use crate::net::cdsi::CdsiError;
-use crate::support::describe_panic;
+use crate::support::{describe_panic, RuntimeClosed};
 
 /// The top-level error type for when something goes wrong.
 #[derive(Debug, thiserror::Error)]
@@ -49,6 +50,7 @@ pub enum SignalJniError {
     InvalidUri(InvalidUri),
     ConnectTimedOut,
     BackupValidation(#[from] libsignal_message_backup::ReadError),
+    RuntimeClosed(#[from] RuntimeClosed),
     Bridge(BridgeLayerError),
     TestingError {
         exception_class: ClassName<'static>,
@@ -97,6 +99,7 @@ impl fmt::Display for SignalJniError {
             SignalJniError::WebSocket(e) => write!(f, "{e}"),
             SignalJniError::ConnectTimedOut => write!(f, "connect timed out"),
             SignalJniError::BackupValidation(e) => write!(f, "{}", e),
+            SignalJniError::RuntimeClosed(e) => write!(f, "{}", e),
             SignalJniError::Svr3(e) => write!(f, "{}", e),
             SignalJniError::Bridge(e) => write!(f, "{}", e),
             SignalJniError::TestingError { exception_class } => {
@@ -253,7 +256,8 @@ impl From<libsignal_net::cdsi::LookupError> for SignalJniError {
             LookupError::AttestationError(e) => return e.into(),
             LookupError::ConnectTransport(e) => return IoError::from(e).into(),
             LookupError::WebSocket(e) => return e.into(),
-            LookupError::InvalidArgument { server_reason: _ } => {
+            LookupError::InvalidArgument { server_reason: _ }
+            | LookupError::InvalidPrebuiltRequest(_) => {
                 return SignalJniError::Protocol(SignalProtocolError::InvalidArgument(
                     e.to_string(),
                 ))
@@ -272,6 +276,57 @@ impl From<libsignal_net::cdsi::LookupError> for SignalJniError {
     }
 }
 
+impl From<libsignal_net::cdn::DownloadError> for SignalJniError {
+    fn from(e: libsignal_net::cdn::DownloadError) -> SignalJniError {
+        use libsignal_net::cdn::DownloadError;
+        match e {
+            DownloadError::UnknownCdn(_) | DownloadError::InvalidArgument(_) => {
+                SignalJniError::Protocol(SignalProtocolError::InvalidArgument(e.to_string()))
+            }
+            // Surfaces as a RetryLaterException, which isn't specific to CDSI.
+            DownloadError::RateLimited {
+                retry_after_seconds: Some(retry_after_seconds),
+            } => SignalJniError::Cdsi(CdsiError::RateLimited {
+                retry_after: Duration::from_secs(retry_after_seconds.into()),
+            }),
+            DownloadError::Sink(e) => SignalJniError::Io(e),
+            DownloadError::RateLimited {
+                retry_after_seconds: None,
+            }
+            | DownloadError::Connection(_)
+            | DownloadError::ServerError(_)
+            | DownloadError::NotFound
+            | DownloadError::UnexpectedStatus(_)
+            | DownloadError::InvalidResponse(_)
+            | DownloadError::DigestMismatch => {
+                SignalJniError::Io(IoError::new(IoErrorKind::Other, e.to_string()))
+            }
+        }
+    }
+}
+
+impl From<libsignal_net::chat::challenge::RateLimitChallengeError> for SignalJniError {
+    fn from(e: libsignal_net::chat::challenge::RateLimitChallengeError) -> SignalJniError {
+        use libsignal_net::chat::challenge::RateLimitChallengeError;
+        match e {
+            RateLimitChallengeError::Chat(e) => e.into(),
+            // Surfaces as a RetryLaterException, which isn't specific to CDSI.
+            RateLimitChallengeError::RateLimited {
+                retry_after_seconds: Some(retry_after_seconds),
+            } => SignalJniError::Cdsi(CdsiError::RateLimited {
+                retry_after: Duration::from_secs(retry_after_seconds.into()),
+            }),
+            RateLimitChallengeError::Rejected
+            | RateLimitChallengeError::RateLimited {
+                retry_after_seconds: None,
+            }
+            | RateLimitChallengeError::UnexpectedStatus(_) => {
+                SignalJniError::Io(IoError::new(IoErrorKind::Other, e.to_string()))
+            }
+        }
+    }
+}
+
 impl From<BridgeLayerError> for SignalJniError {
     fn from(e: BridgeLayerError) -> SignalJniError {
         SignalJniError::Bridge(e)
@@ -285,6 +340,7 @@ impl From<Svr3Error> for SignalJniError {
                 WebSocketConnectError::Timeout => SignalJniError::ConnectTimedOut,
                 WebSocketConnectError::Transport(e) => SignalJniError::Io(e.into()),
                 WebSocketConnectError::WebSocketError(e) => WebSocketServiceError::from(e).into(),
+                WebSocketConnectError::SubProtocol(e) => WebSocketServiceError::from(e).into(),
                 WebSocketConnectError::RejectedByServer {
                     response,
                     received_at: _,
@@ -297,7 +353,9 @@ impl From<Svr3Error> for SignalJniError {
             | Svr3Error::RequestFailed(_)
             | Svr3Error::RestoreFailed(_)
             | Svr3Error::DataMissing
-            | Svr3Error::RotationMachineTooManySteps => SignalJniError::Svr3(err),
+            | Svr3Error::RotationMachineTooManySteps
+            | Svr3Error::CircuitOpen
+            | Svr3Error::PasswordRotationIncomplete { .. } => SignalJniError::Svr3(err),
         }
     }
 }
@@ -330,6 +388,68 @@ impl From<SignalJniError> for IoError {
     }
 }
 
+impl ErrorCode for SignalJniError {
+    fn error_code(&self) -> SignalErrorCode {
+        match self {
+            SignalJniError::Protocol(e) => e.error_code(),
+            SignalJniError::DeviceTransfer(e) => e.error_code(),
+            SignalJniError::SignalCrypto(e) => e.error_code(),
+            SignalJniError::HsmEnclave(e) => e.error_code(),
+            SignalJniError::Enclave(e) => e.error_code(),
+            SignalJniError::Pin(e) => e.error_code(),
+            SignalJniError::ZkGroupDeserializationFailure(e) => e.error_code(),
+            SignalJniError::ZkGroupVerificationFailure(e) => e.error_code(),
+            SignalJniError::UsernameError(e) => e.error_code(),
+            SignalJniError::UsernameProofError(e) => e.error_code(),
+            SignalJniError::UsernameLinkError(e) => e.error_code(),
+            SignalJniError::Io(e) => e.error_code(),
+            #[cfg(feature = "signal-media")]
+            SignalJniError::Mp4SanitizeParse(e) => e.error_code(),
+            #[cfg(feature = "signal-media")]
+            SignalJniError::WebpSanitizeParse(e) => e.error_code(),
+            SignalJniError::Cdsi(e) => e.error_code(),
+            SignalJniError::Svr3(e) => e.error_code(),
+            SignalJniError::WebSocket(e) => e.error_code(),
+            SignalJniError::ChatService(e) => e.error_code(),
+            SignalJniError::InvalidUri(e) => e.error_code(),
+            SignalJniError::ConnectTimedOut => SignalErrorCode::NetConnectionTimedOut,
+            SignalJniError::BackupValidation(e) => e.error_code(),
+            SignalJniError::RuntimeClosed(e) => e.error_code(),
+            SignalJniError::Bridge(e) => e.error_code(),
+            SignalJniError::TestingError { .. } => SignalErrorCode::InternalError,
+        }
+    }
+}
+
+impl ErrorCode for BridgeLayerError {
+    fn error_code(&self) -> SignalErrorCode {
+        match self {
+            Self::BadArgument(_) | Self::IntegerOverflow(_) | Self::IncorrectArrayLength { .. } => {
+                SignalErrorCode::InvalidArgument
+            }
+            Self::NullPointer(_) => SignalErrorCode::NullParameter,
+            Self::Jni(_) | Self::BadJniParameter(_) | Self::UnexpectedJniResultType(_, _) => {
+                SignalErrorCode::InternalError
+            }
+            Self::CallbackException(_, _) => SignalErrorCode::CallbackError,
+            Self::UnexpectedPanic(_) => SignalErrorCode::UnexpectedPanic,
+        }
+    }
+}
+
+impl ErrorCode for CdsiError {
+    fn error_code(&self) -> SignalErrorCode {
+        match self {
+            CdsiError::Protocol => SignalErrorCode::NetProtocol,
+            CdsiError::InvalidResponse => SignalErrorCode::CdsiInvalidResponse,
+            CdsiError::RateLimited { .. } => SignalErrorCode::NetRateLimited,
+            CdsiError::ParseError => SignalErrorCode::CdsiParseError,
+            CdsiError::InvalidToken => SignalErrorCode::CdsiInvalidToken,
+            CdsiError::Server { .. } => SignalErrorCode::CdsiServerError,
+        }
+    }
+}
+
 pub type SignalJniResult<T> = Result<T, SignalJniError>;
 
 /// A lifetime-less reference to a thrown Java exception that can be used as an [`Error`].
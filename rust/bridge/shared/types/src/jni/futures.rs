@@ -162,6 +162,7 @@ where
     O: for<'a> ResultTypeInfo<'a> + std::panic::UnwindSafe + 'static,
     F::Output: ResultReporter<Receiver = FutureCompleter<O>>,
 {
+    runtime.check_open()?;
     let java_future = new_instance(
         env,
         ClassName("org.signal.libsignal.internal.CompletableFuture"),
@@ -9,10 +9,11 @@ use std::io;
 use async_trait::async_trait;
 
 use super::*;
-use crate::io::{InputStream, InputStreamRead, SyncInputStream};
+use crate::io::{InputStream, InputStreamRead, OutputStream, SyncInputStream};
 
 pub type JavaInputStream<'a> = JObject<'a>;
 pub type JavaSyncInputStream<'a> = JObject<'a>;
+pub type JavaOutputStream<'a> = JObject<'a>;
 
 /// Implementation of [`InputStream`] for an argument to a bridge function.
 pub struct JniInputStream<'a> {
@@ -110,3 +111,55 @@ impl SyncInputStream for JniInputStream<'_> {
         Ok(self.do_skip(amount)?)
     }
 }
+
+/// Implementation of [`OutputStream`] for an argument to a bridge function.
+pub struct JniOutputStream<'a> {
+    env: RefCell<EnvHandle<'a>>,
+    stream: &'a JObject<'a>,
+}
+
+impl<'a> JniOutputStream<'a> {
+    pub fn new<'context: 'a>(
+        env: &mut JNIEnv<'context>,
+        stream: &'a JObject<'a>,
+    ) -> Result<Self, BridgeLayerError> {
+        check_jobject_type(env, stream, ClassName("java.io.OutputStream"))?;
+        Ok(Self {
+            env: EnvHandle::new(env).into(),
+            stream,
+        })
+    }
+
+    fn do_write(&self, buf: &[u8]) -> SignalJniResult<()> {
+        self.env.borrow_mut().with_local_frame(8, "write", |env| {
+            let java_buf = env
+                .byte_array_from_slice(buf)
+                .check_exceptions(env, "write")?;
+            call_method_checked(
+                env,
+                self.stream,
+                "write",
+                jni_args!((java_buf => [byte]) -> void),
+            )?;
+            Ok(())
+        })
+    }
+
+    fn do_flush(&self) -> SignalJniResult<()> {
+        self.env.borrow_mut().with_local_frame(8, "flush", |env| {
+            call_method_checked(env, self.stream, "flush", jni_args!(() -> void))?;
+            Ok(())
+        })
+    }
+}
+
+#[async_trait(?Send)]
+impl OutputStream for JniOutputStream<'_> {
+    async fn write(&self, buf: &[u8]) -> io::Result<()> {
+        Ok(self.do_write(buf)?)
+    }
+
+    async fn flush(&self) -> io::Result<()> {
+        Ok(self.do_flush()?)
+    }
+}
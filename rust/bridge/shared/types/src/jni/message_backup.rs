@@ -0,0 +1,63 @@
+//
+// Copyright 2024 Signal Messenger, LLC.
+// SPDX-License-Identifier: AGPL-3.0-only
+//
+
+use std::cell::RefCell;
+
+use super::*;
+use crate::message_backup::BackupProgressListener;
+
+pub type JavaBackupProgressListener<'a> = JObject<'a>;
+
+/// Implementation of [`BackupProgressListener`] for an argument to a bridge function.
+pub struct JniBackupProgressListener<'a> {
+    env: RefCell<EnvHandle<'a>>,
+    listener: &'a JObject<'a>,
+}
+
+impl<'a> JniBackupProgressListener<'a> {
+    pub fn new<'context: 'a>(
+        env: &mut JNIEnv<'context>,
+        listener: &'a JObject<'a>,
+    ) -> Result<Self, BridgeLayerError> {
+        check_jobject_type(
+            env,
+            listener,
+            ClassName("org.signal.libsignal.internal.BackupProgressListener"),
+        )?;
+        Ok(Self {
+            env: EnvHandle::new(env).into(),
+            listener,
+        })
+    }
+
+    fn do_on_progress(
+        &self,
+        frames_read: u64,
+        bytes_read: u64,
+        total_bytes: u64,
+    ) -> SignalJniResult<()> {
+        self.env
+            .borrow_mut()
+            .with_local_frame(8, "onProgress", |env| {
+                // Reinterpret the bits as Java longs, as for other u64 results.
+                let callback_args = jni_args!((
+                    frames_read as jlong => long,
+                    bytes_read as jlong => long,
+                    total_bytes as jlong => long
+                ) -> void);
+                call_method_checked(env, self.listener, "onProgress", callback_args)?;
+                Ok(())
+            })
+    }
+}
+
+impl BackupProgressListener for JniBackupProgressListener<'_> {
+    fn on_progress(&self, frames_read: u64, bytes_read: u64, total_bytes: u64) {
+        // Progress is informational; a failure to report it shouldn't fail validation.
+        if let Err(e) = self.do_on_progress(frames_read, bytes_read, total_bytes) {
+            log::warn!("failed to report backup progress: {e}");
+        }
+    }
+}
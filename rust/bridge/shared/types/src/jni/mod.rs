@@ -23,6 +23,7 @@ use signal_crypto::Error as SignalCryptoError;
 use signal_pin::Error as PinError;
 use usernames::{UsernameError, UsernameLinkError};
 
+use crate::error_code::ErrorCode as _;
 use crate::net::cdsi::CdsiError;
 
 #[macro_use]
@@ -46,6 +47,9 @@ mod io;
 pub use io::*;
 use libsignal_net::chat::ChatServiceError;
 
+mod message_backup;
+pub use message_backup::*;
+
 mod storage;
 pub use storage::*;
 
@@ -100,9 +104,35 @@ where
     // `F`. That's expensive in terms of code size, so we break out the
     // invariant part into a separate function.
     let ConsumableException { throwable, error } = ConsumableException::new(env, error);
+    if let (Ok(throwable), ConsumableExceptionError::JniError(error)) = (&throwable, &error) {
+        attach_error_code(env, throwable, error);
+    }
     consume(env, throwable, error)
 }
 
+/// Records the [stable code](crate::error_code) for `error` so that Java code can look it up with
+/// `ErrorCodes.of(throwable)`.
+///
+/// Failing to do so isn't fatal; the exception is still thrown, just without a code.
+fn attach_error_code(env: &mut JNIEnv, throwable: &JThrowable, error: &SignalJniError) {
+    let code: jint = u32::from(error.error_code())
+        .try_into()
+        .expect("error codes fit in an int");
+    let result = find_class(env, ClassName("org.signal.libsignal.internal.ErrorCodes")).and_then(
+        |error_codes_class| {
+            call_static_method_checked(
+                env,
+                error_codes_class,
+                "attach",
+                jni_args!((throwable => java.lang.Throwable, code => int) -> void),
+            )
+        },
+    );
+    if let Err(failure) = result {
+        log::warn!("failed to attach error code to exception for {error}: {failure}");
+    }
+}
+
 struct ConsumableException<'a> {
     throwable: Result<JThrowable<'a>, BridgeLayerError>,
     error: ConsumableExceptionError,
@@ -468,6 +498,14 @@ impl<'env> ConsumableException<'env> {
                 ClassName("org.signal.libsignal.attest.AttestationFailedException"),
                 error,
             ),
+            SignalJniError::Enclave(EnclaveError::AttestationExpired { .. }) => (
+                ClassName("org.signal.libsignal.attest.AttestationExpiredException"),
+                error,
+            ),
+            SignalJniError::Enclave(EnclaveError::AttestationMeasurementMismatch) => (
+                ClassName("org.signal.libsignal.attest.AttestationMeasurementMismatchException"),
+                error,
+            ),
             SignalJniError::Enclave(EnclaveError::AttestationDataError { .. }) => (
                 ClassName("org.signal.libsignal.attest.AttestationDataException"),
                 error,
@@ -657,6 +695,10 @@ impl<'env> ConsumableException<'env> {
                 error,
             ),
 
+            SignalJniError::RuntimeClosed(_) => {
+                (ClassName("java.lang.IllegalStateException"), error)
+            }
+
             SignalJniError::TestingError { exception_class } => (exception_class, error),
         };
 
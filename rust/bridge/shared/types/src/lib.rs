@@ -25,6 +25,7 @@ pub use support::{describe_panic, AsyncRuntime, ResultReporter};
 
 pub mod cds2;
 pub mod crypto;
+pub mod error_code;
 pub mod hsm_enclave;
 pub mod net;
 pub mod protocol;
@@ -3,8 +3,12 @@
 // SPDX-License-Identifier: AGPL-3.0-only
 //
 
+use std::sync::OnceLock;
+
 use libsignal_message_backup::frame::ValidationError as FrameValidationError;
-use libsignal_message_backup::key::{BackupKey, MessageBackupKey as MessageBackupKeyInner};
+use libsignal_message_backup::key::{
+    BackupId, BackupKey, MessageBackupKey as MessageBackupKeyInner,
+};
 use libsignal_message_backup::parse::ParseError;
 use libsignal_message_backup::{Error, FoundUnknownField};
 use libsignal_protocol::Aci;
@@ -21,41 +25,154 @@ impl MessageBackupKey {
     }
 }
 
+impl From<&BackupKeyMaterial> for MessageBackupKey {
+    fn from(material: &BackupKeyMaterial) -> Self {
+        Self(material.message_backup_key().clone())
+    }
+}
+
 bridge_as_handle!(MessageBackupKey);
 
+/// The backup keys for an account, each derived the first time it's needed and then kept.
+///
+/// All of the keys are zeroized when this is dropped.
+pub struct BackupKeyMaterial {
+    aci: Aci,
+    backup_key: BackupKey,
+    backup_id: OnceLock<BackupId>,
+    message_backup_key: OnceLock<MessageBackupKeyInner>,
+    #[cfg(test)]
+    derivations: std::sync::atomic::AtomicUsize,
+}
+
+impl BackupKeyMaterial {
+    pub fn new(master_key: &[u8; 32], aci: Aci) -> Self {
+        let material = Self {
+            aci,
+            backup_key: BackupKey::derive_from_master_key(master_key),
+            backup_id: OnceLock::new(),
+            message_backup_key: OnceLock::new(),
+            #[cfg(test)]
+            derivations: Default::default(),
+        };
+        material.count_derivation();
+        material
+    }
+
+    pub fn backup_key(&self) -> &BackupKey {
+        &self.backup_key
+    }
+
+    pub fn backup_id(&self) -> &BackupId {
+        self.backup_id.get_or_init(|| {
+            self.count_derivation();
+            self.backup_key.derive_backup_id(&self.aci)
+        })
+    }
+
+    pub fn message_backup_key(&self) -> &MessageBackupKeyInner {
+        self.message_backup_key.get_or_init(|| {
+            let backup_id = self.backup_id();
+            self.count_derivation();
+            MessageBackupKeyInner::derive(&self.backup_key, backup_id)
+        })
+    }
+
+    fn count_derivation(&self) {
+        #[cfg(test)]
+        self.derivations
+            .fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+    }
+}
+
+bridge_as_handle!(BackupKeyMaterial);
+
 #[derive(Debug)]
 pub enum MessageBackupValidationError {
     Io(std::io::Error),
-    String(String),
+    Invalid(MessageBackupValidationFailure),
+}
+
+/// Why a backup failed validation.
+///
+/// The numeric values are bridged, so the app-language enums must be kept in sync.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[repr(u8)]
+pub enum MessageBackupValidationErrorKind {
+    /// The backup couldn't be authenticated, usually because the wrong key was used.
+    HmacMismatch = 0,
+    /// The input is too short to be an encrypted backup.
+    TooShort = 1,
+    /// The decrypted contents couldn't be parsed as backup frames.
+    Corrupted = 2,
+    /// The backup doesn't contain any frames.
+    NoFrames = 3,
+    /// A frame's contents are invalid.
+    InvalidContents = 4,
+    /// The backup is missing required frames.
+    Incomplete = 5,
+    /// The backup uses a version of the format that isn't supported.
+    UnsupportedVersion = 6,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct MessageBackupValidationFailure {
+    pub kind: MessageBackupValidationErrorKind,
+    /// A developer-facing description of the error.
+    pub message: String,
+}
+
+impl MessageBackupValidationError {
+    fn invalid(kind: MessageBackupValidationErrorKind, error: impl std::fmt::Display) -> Self {
+        Self::Invalid(MessageBackupValidationFailure {
+            kind,
+            message: error.to_string(),
+        })
+    }
 }
 
 impl From<Error> for MessageBackupValidationError {
     fn from(value: Error) -> Self {
+        use MessageBackupValidationErrorKind as Kind;
         match value {
-            Error::BackupValidation(e) => Self::String(e.to_string()),
-            Error::BackupCompletion(e) => Self::String(e.to_string()),
+            Error::BackupValidation(e) => Self::invalid(Kind::InvalidContents, e),
+            e @ Error::PolicyViolation { .. } => Self::invalid(Kind::InvalidContents, e),
+            Error::BackupCompletion(e) => Self::invalid(Kind::Incomplete, e),
             Error::Parse(ParseError::Io(e)) => Self::Io(e),
-            e @ Error::NoFrames
-            | e @ Error::InvalidProtobuf(_)
-            | e @ Error::HmacMismatch(_)
-            | e @ Error::Parse(ParseError::Decode(_)) => Self::String(e.to_string()),
+            e @ (Error::InvalidProtobuf(_) | Error::Parse(ParseError::Decode(_))) => {
+                Self::invalid(Kind::Corrupted, e)
+            }
+            e @ Error::NoFrames => Self::invalid(Kind::NoFrames, e),
+            e @ Error::HmacMismatch(_) => Self::invalid(Kind::HmacMismatch, e),
+            e @ (Error::UnsupportedVersion { .. } | Error::VersionTooOld { .. }) => {
+                Self::invalid(Kind::UnsupportedVersion, e)
+            }
         }
     }
 }
 
 impl From<FrameValidationError> for MessageBackupValidationError {
     fn from(value: FrameValidationError) -> Self {
+        use MessageBackupValidationErrorKind as Kind;
         match value {
             FrameValidationError::Io(e) => Self::Io(e),
-            e @ (FrameValidationError::TooShort | FrameValidationError::InvalidHmac(_)) => {
-                Self::String(e.to_string())
-            }
+            e @ FrameValidationError::TooShort => Self::invalid(Kind::TooShort, e),
+            e @ FrameValidationError::InvalidHmac(_) => Self::invalid(Kind::HmacMismatch, e),
         }
     }
 }
 
+/// Receives progress updates while a backup is being validated.
+///
+/// See [`libsignal_message_backup::BackupReader::with_progress`] for how often this is called.
+pub trait BackupProgressListener {
+    /// `total_bytes` is the length of the encrypted input; `bytes_read` counts encrypted bytes.
+    fn on_progress(&self, frames_read: u64, bytes_read: u64, total_bytes: u64);
+}
+
 pub struct MessageBackupValidationOutcome {
-    pub error_message: Option<String>,
+    /// Why validation failed, or `None` if the backup is valid.
+    pub error: Option<MessageBackupValidationFailure>,
     pub found_unknown_fields: Vec<FoundUnknownField>,
 }
 bridge_as_handle!(MessageBackupValidationOutcome, jni = false, node = false);
@@ -66,3 +183,80 @@ pub struct ComparableBackup {
 }
 
 bridge_as_handle!(ComparableBackup);
+
+#[cfg(test)]
+mod test {
+    use assert_matches::assert_matches;
+    use libsignal_message_backup::backup::CompletionError;
+    use test_case::test_case;
+
+    use super::*;
+
+    #[test_case(Error::NoFrames, MessageBackupValidationErrorKind::NoFrames)]
+    #[test_case(
+        Error::BackupCompletion(CompletionError::MissingAccountData),
+        MessageBackupValidationErrorKind::Incomplete
+    )]
+    #[test_case(
+        Error::UnsupportedVersion { found: 2, supported_max: 1 },
+        MessageBackupValidationErrorKind::UnsupportedVersion
+    )]
+    #[test_case(
+        FrameValidationError::TooShort,
+        MessageBackupValidationErrorKind::TooShort
+    )]
+    fn error_kind(
+        error: impl Into<MessageBackupValidationError>,
+        expected: MessageBackupValidationErrorKind,
+    ) {
+        let error: MessageBackupValidationError = error.into();
+        let failure =
+            assert_matches!(error, MessageBackupValidationError::Invalid(failure) => failure);
+        assert_eq!(failure.kind, expected);
+        assert!(!failure.message.is_empty());
+    }
+
+    #[test]
+    fn key_material_matches_direct_derivation() {
+        const MASTER_KEY: [u8; 32] = [0x5a; 32];
+        let aci = Aci::from_uuid_bytes([0x11; 16]);
+
+        let backup_key = BackupKey::derive_from_master_key(&MASTER_KEY);
+        let backup_id = backup_key.derive_backup_id(&aci);
+        let message_backup_key = MessageBackupKey::new(&MASTER_KEY, aci).0;
+
+        let material = BackupKeyMaterial::new(&MASTER_KEY, aci);
+        assert_eq!(material.backup_key().as_bytes(), backup_key.as_bytes());
+        assert_eq!(material.backup_id().as_bytes(), backup_id.as_bytes());
+        let from_material = MessageBackupKey::from(&material).0;
+        assert_eq!(from_material.hmac_key, message_backup_key.hmac_key);
+        assert_eq!(from_material.aes_key, message_backup_key.aes_key);
+    }
+
+    #[test]
+    fn key_material_derives_each_key_once() {
+        let material = BackupKeyMaterial::new(&[0x5a; 32], Aci::from_uuid_bytes([0x11; 16]));
+        let derivations = || {
+            material
+                .derivations
+                .load(std::sync::atomic::Ordering::Relaxed)
+        };
+        assert_eq!(derivations(), 1);
+
+        for _ in 0..3 {
+            _ = material.message_backup_key();
+            _ = material.backup_id();
+            _ = material.backup_key();
+        }
+        assert_eq!(derivations(), 3);
+    }
+
+    #[test]
+    fn io_errors_are_not_validation_failures() {
+        let error = FrameValidationError::Io(std::io::ErrorKind::UnexpectedEof.into());
+        assert_matches!(
+            MessageBackupValidationError::from(error),
+            MessageBackupValidationError::Io(_)
+        );
+    }
+}
@@ -4,18 +4,28 @@
 //
 
 use std::marker::PhantomData;
+use std::net::IpAddr;
 use std::num::{NonZeroU16, NonZeroU32};
 use std::panic::RefUnwindSafe;
+use std::sync::Arc;
+use std::time::Duration;
 
 use aes_gcm_siv::aead::rand_core::CryptoRngCore;
 use async_trait::async_trait;
 use futures_util::future::join3;
 use libsignal_net::auth::Auth;
+use libsignal_net::cdn::{AttachmentDownloader, DownloadConfig};
+use libsignal_net::diagnostics::{
+    run_connectivity_report, ConnectivityReport, ConnectivityTargets,
+};
 use libsignal_net::enclave::{
-    Cdsi, EnclaveEndpoint, EnclaveEndpointConnection, EnclaveKind, Nitro, PpssSetup, Sgx, Tpm2Snp,
+    self, Cdsi, EnclaveEndpoint, EnclaveEndpointConnection, EnclaveKind, Nitro, PpssSetup, Sgx,
+    SgxPreQuantum, Tpm2Snp,
 };
 use libsignal_net::env::{add_user_agent_header, Env, Svr3Env};
-use libsignal_net::infra::connection_manager::MultiRouteConnectionManager;
+use libsignal_net::infra::connection_manager::{
+    ConnectionManagerState, MultiRouteConnectionManager,
+};
 use libsignal_net::infra::dns::DnsResolver;
 use libsignal_net::infra::host::Host;
 use libsignal_net::infra::tcp_ssl::proxy::tls::TlsProxyConnector as TcpSslProxyConnector;
@@ -23,31 +33,39 @@ use libsignal_net::infra::tcp_ssl::{
     DirectConnector as TcpSslDirectConnector, TcpSslConnector, TcpSslConnectorStream,
 };
 use libsignal_net::infra::timeouts::ONE_ROUTE_CONNECTION_TIMEOUT;
-use libsignal_net::infra::utils::ObservableEvent;
-use libsignal_net::infra::EndpointConnection;
-use libsignal_net::svr::SvrConnection;
+use libsignal_net::infra::traffic_stats::{CountingConnector, CountingStream, TrafficKind};
+use libsignal_net::infra::utils::{EventSubscription, ObservableEvent};
+use libsignal_net::infra::ws::WebSocketConfig;
+use libsignal_net::infra::{ConnectionParams, EndpointConnection};
+use libsignal_net::network_hint::NetworkHint;
+use libsignal_net::proxy::{apply_global_proxy, global_proxy_changed_event, GlobalProxyPolicy};
+use libsignal_net::svr::{PinMigrated, PinMigrationError, PinMigrationStep, SvrConnection};
+use libsignal_net::svr2::Svr2Connect;
 use libsignal_net::svr3::traits::*;
-use libsignal_net::svr3::{Error, OpaqueMaskedShareSet};
-use libsignal_svr3::EvaluationResult;
+use libsignal_net::svr3::{Error, OpaqueMaskedShareSet, RemoveResult};
+use libsignal_svr3::{EvaluationResult, QueryResult};
+use signal_pin::PinHash;
 
+use crate::net::svr3_retry::{connect_with_retry, Svr3RetryConfig, SVR3_CIRCUIT_BREAKERS};
 use crate::*;
 
 pub mod cdsi;
 pub mod chat;
+pub mod svr3_retry;
 pub mod tokio;
 
 pub use tokio::TokioAsyncContext;
 
 #[derive(num_enum::TryFromPrimitive)]
 #[repr(u8)]
-#[derive(Clone, Copy, strum::Display)]
+#[derive(Clone, Copy, PartialEq, Eq, strum::Display)]
 pub enum Environment {
     Staging = 0,
     Prod = 1,
 }
 
 impl Environment {
-    fn env<'a>(self) -> Env<'a, Svr3Env<'a>> {
+    pub fn env<'a>(self) -> Env<'a, Svr3Env<'a>> {
         match self {
             Self::Staging => libsignal_net::env::STAGING,
             Self::Prod => libsignal_net::env::PROD,
@@ -58,13 +76,19 @@ impl Environment {
 pub struct ConnectionManager {
     chat: EndpointConnection<MultiRouteConnectionManager>,
     cdsi: EnclaveEndpointConnection<Cdsi, MultiRouteConnectionManager>,
+    svr2: EnclaveEndpointConnection<SgxPreQuantum, MultiRouteConnectionManager>,
     svr3: (
         EnclaveEndpointConnection<Sgx, MultiRouteConnectionManager>,
         EnclaveEndpointConnection<Nitro, MultiRouteConnectionManager>,
         EnclaveEndpointConnection<Tpm2Snp, MultiRouteConnectionManager>,
     ),
+    cdns: Vec<(u32, ConnectionParams)>,
     transport_connector: std::sync::Mutex<TcpSslConnector>,
-    network_change_event: ObservableEvent,
+    connectivity_targets: ConnectivityTargets,
+    network_hint: std::sync::Mutex<NetworkHint>,
+    svr3_retry_config: std::sync::Mutex<Svr3RetryConfig>,
+    network_change_event: Arc<ObservableEvent>,
+    _global_proxy_subscription: EventSubscription,
 }
 
 impl RefUnwindSafe for ConnectionManager {}
@@ -72,47 +96,97 @@ impl RefUnwindSafe for ConnectionManager {}
 impl ConnectionManager {
     pub fn new(environment: Environment, user_agent: String) -> Self {
         log::info!("Initializing connection manager for {}...", &environment);
-        let network_change_event = ObservableEvent::new();
-        let dns_resolver = DnsResolver::new_with_static_fallback(
-            environment.env().static_fallback(),
+        Self::new_with_env(&environment.env(), user_agent)
+    }
+
+    /// Creates a connection manager for an environment that isn't one of Signal's own, such as
+    /// one produced by [`EnvBuilder`](libsignal_net::env::EnvBuilder).
+    pub fn new_with_env(env: &Env<'static, Svr3Env<'static>>, user_agent: String) -> Self {
+        let network_change_event = Arc::new(ObservableEvent::new());
+        // A change to the global proxy invalidates existing connections just like a network change
+        // would.
+        let global_proxy_subscription = {
+            let network_change_event = Arc::downgrade(&network_change_event);
+            global_proxy_changed_event().subscribe(Box::new(move || {
+                if let Some(event) = network_change_event.upgrade() {
+                    event.fire()
+                }
+            }))
+        };
+        let dns_resolver =
+            DnsResolver::new_with_static_fallback(env.static_fallback(), &network_change_event);
+        let mut chat = libsignal_net::chat::endpoint_connection(
+            &env.chat_domain_config.connect,
+            &user_agent,
             &network_change_event,
         );
+        // Let chat connections skip routes whose hosts the resolver has recently seen fail.
+        if let Some(negative_cache) = dns_resolver.negative_cache() {
+            chat.manager = chat.manager.with_negative_cache(negative_cache.clone());
+        }
         let transport_connector =
             std::sync::Mutex::new(TcpSslDirectConnector::new(dns_resolver).into());
-        let chat = libsignal_net::chat::endpoint_connection(
-            &environment.env().chat_domain_config.connect,
-            &user_agent,
-            &network_change_event,
-        );
+        let (cdn_numbers, cdn_params): (Vec<_>, Vec<_>) = env.cdn_connection_params().unzip();
+        let cdns = cdn_numbers
+            .into_iter()
+            .zip(add_user_agent_header(cdn_params, &user_agent))
+            .collect();
         Self {
             chat,
-            cdsi: Self::endpoint_connection(
-                &environment.env().cdsi,
-                &user_agent,
-                &network_change_event,
-            ),
+            cdsi: Self::endpoint_connection(&env.cdsi, &user_agent, &network_change_event),
+            svr2: Self::endpoint_connection(&env.svr2, &user_agent, &network_change_event),
             svr3: (
-                Self::endpoint_connection(
-                    environment.env().svr3.sgx(),
-                    &user_agent,
-                    &network_change_event,
-                ),
-                Self::endpoint_connection(
-                    environment.env().svr3.nitro(),
-                    &user_agent,
-                    &network_change_event,
-                ),
-                Self::endpoint_connection(
-                    environment.env().svr3.tpm2snp(),
-                    &user_agent,
-                    &network_change_event,
-                ),
+                Self::endpoint_connection(env.svr3.sgx(), &user_agent, &network_change_event),
+                Self::endpoint_connection(env.svr3.nitro(), &user_agent, &network_change_event),
+                Self::endpoint_connection(env.svr3.tpm2snp(), &user_agent, &network_change_event),
             ),
+            cdns,
             transport_connector,
+            connectivity_targets: ConnectivityTargets::for_env(env),
+            network_hint: Default::default(),
+            svr3_retry_config: Default::default(),
             network_change_event,
+            _global_proxy_subscription: global_proxy_subscription,
         }
     }
 
+    /// Returns a connector for a new connection, taking the global proxy setting into account.
+    pub fn transport_connector(&self) -> TcpSslConnector {
+        self.transport_connector_with_policy(GlobalProxyPolicy::Respect)
+    }
+
+    /// Like [`Self::transport_connector`], but lets traffic needed to reach the proxy itself
+    /// opt out of the global proxy with [`GlobalProxyPolicy::Bypass`].
+    pub fn transport_connector_with_policy(&self, policy: GlobalProxyPolicy) -> TcpSslConnector {
+        let connector = self
+            .transport_connector
+            .lock()
+            .expect("not poisoned")
+            .clone();
+        apply_global_proxy(connector, policy)
+    }
+
+    /// Like [`Self::transport_connector`], but the connection's traffic is counted as `kind` in
+    /// the global [`TrafficStats`](libsignal_net::infra::traffic_stats::TrafficStats).
+    pub fn counting_transport_connector(
+        &self,
+        kind: TrafficKind,
+    ) -> CountingConnector<TcpSslConnector> {
+        CountingConnector::new(self.transport_connector(), kind)
+    }
+
+    /// Returns a downloader for attachments on the CDNs, taking the global proxy setting into
+    /// account.
+    pub fn attachment_downloader(
+        &self,
+    ) -> AttachmentDownloader<CountingConnector<TcpSslConnector>> {
+        AttachmentDownloader::new(
+            self.cdns.iter().cloned(),
+            self.counting_transport_connector(TrafficKind::Cdn),
+            DownloadConfig::default(),
+        )
+    }
+
     pub fn set_proxy(&self, host: &str, port: Option<NonZeroU16>) -> Result<(), std::io::Error> {
         let host = Host::parse_as_ip_or_domain(host);
 
@@ -160,6 +234,57 @@ impl ConnectionManager {
         guard.set_ipv6_enabled(ipv6_enabled);
     }
 
+    /// Adjusts subsequent chat connection attempts for the network described by `hint`.
+    ///
+    /// The connect timeout and keep-alive interval only apply to chat. The IPv6 preference is
+    /// applied to the shared DNS resolver, so it affects every kind of connection. Existing
+    /// connections are unaffected.
+    pub fn set_chat_network_hint(&self, hint: NetworkHint) {
+        let mut guard = self.network_hint.lock().expect("not poisoned");
+        self.dns_resolver()
+            .set_ipv6_preferred(hint.tuning().prefer_ipv6);
+        *guard = hint;
+    }
+
+    /// Changes how subsequent SVR3 operations retry connecting to each enclave.
+    pub fn set_svr3_retry_config(&self, config: Svr3RetryConfig) {
+        *self.svr3_retry_config.lock().expect("not poisoned") = config;
+    }
+
+    /// The chat endpoint, with its WebSocket config adjusted for the current [`NetworkHint`].
+    pub(crate) fn chat_endpoint(&self) -> EndpointConnection<MultiRouteConnectionManager> {
+        let tuning = self.network_hint.lock().expect("not poisoned").tuning();
+        let EndpointConnection { manager, config } = &self.chat;
+        EndpointConnection {
+            manager: manager.clone(),
+            config: WebSocketConfig {
+                max_connection_time: tuning.connect_timeout,
+                keep_alive_interval: tuning.keep_alive_interval,
+                ..config.clone()
+            },
+        }
+    }
+
+    /// Makes `hostname` resolve to `addresses` for all connections, or removes the override if
+    /// `addresses` is empty.
+    pub fn set_static_dns_entry(&self, hostname: &str, addresses: Vec<IpAddr>) {
+        self.dns_resolver().set_static_entry(hostname, addresses)
+    }
+
+    /// Looks up `hostnames` ahead of time; see [`DnsResolver::prewarm`].
+    pub async fn prewarm_dns(&self, hostnames: &[&str]) {
+        self.dns_resolver().prewarm(hostnames).await
+    }
+
+    /// The resolver shared by every connector this manager hands out, whether proxied or not.
+    fn dns_resolver(&self) -> DnsResolver {
+        self.transport_connector
+            .lock()
+            .expect("not poisoned")
+            .dns_resolver()
+            .clone()
+    }
+
     fn endpoint_connection<E: EnclaveKind>(
         endpoint: &EnclaveEndpoint<'static, E>,
         user_agent: &str,
@@ -178,9 +303,50 @@ impl ConnectionManager {
         )
     }
 
+    /// Checks which services in this manager's environment are currently reachable.
+    pub async fn connectivity_report(&self, timeout: Duration) -> ConnectivityReport {
+        run_connectivity_report(
+            &self.connectivity_targets,
+            &self.transport_connector(),
+            timeout,
+        )
+        .await
+    }
+
     pub fn on_network_change(&self) {
         self.network_change_event.fire()
     }
+
+    /// The route cooldowns of every service, to be saved until the app is next started.
+    pub fn export_cooldown_state(&self) -> ConnectionManagerState {
+        let (svr3_sgx, svr3_nitro, svr3_tpm2snp) = &self.svr3;
+        ConnectionManagerState {
+            routes: [
+                self.chat.manager.export_state(),
+                self.cdsi.manager().export_state(),
+                self.svr2.manager().export_state(),
+                svr3_sgx.manager().export_state(),
+                svr3_nitro.manager().export_state(),
+                svr3_tpm2snp.manager().export_state(),
+            ]
+            .into_iter()
+            .flat_map(|state| state.routes)
+            .collect(),
+        }
+    }
+
+    /// Restores route cooldowns saved by [`Self::export_cooldown_state`].
+    ///
+    /// This should be done before any connections are made.
+    pub fn import_cooldown_state(&self, state: &ConnectionManagerState) {
+        let (svr3_sgx, svr3_nitro, svr3_tpm2snp) = &self.svr3;
+        self.chat.manager.import_state(state);
+        self.cdsi.manager().import_state(state);
+        self.svr2.manager().import_state(state);
+        svr3_sgx.manager().import_state(state);
+        svr3_nitro.manager().import_state(state);
+        svr3_tpm2snp.manager().import_state(state);
+    }
 }
 
 bridge_as_handle!(ConnectionManager);
@@ -225,20 +391,34 @@ impl<'a> Svr3Clients<'a> {
 
 #[async_trait]
 impl<'a> Svr3Connect for Svr3Client<'a, CurrentVersion> {
-    type Stream = TcpSslConnectorStream;
+    type Stream = CountingStream<TcpSslConnectorStream>;
     type Env = Svr3Env<'static>;
 
     async fn connect(&self) -> <Self::Env as PpssSetup<Self::Stream>>::ConnectionResults {
         let ConnectionManager {
             svr3: (sgx, nitro, tpm2snp),
-            transport_connector,
             ..
         } = &self.connection_manager;
-        let transport_connector = transport_connector.lock().expect("not poisoned").clone();
+        let transport_connector = self
+            .connection_manager
+            .counting_transport_connector(TrafficKind::Svr);
+        let config = self
+            .connection_manager
+            .svr3_retry_config
+            .lock()
+            .expect("not poisoned")
+            .clone();
+        let breakers = &*SVR3_CIRCUIT_BREAKERS;
         let (sgx, nitro, tpm2snp) = join3(
-            SvrConnection::connect(self.auth.clone(), sgx, transport_connector.clone()),
-            SvrConnection::connect(self.auth.clone(), nitro, transport_connector.clone()),
-            SvrConnection::connect(self.auth.clone(), tpm2snp, transport_connector),
+            connect_with_retry::<Sgx, _, _>(&config, breakers, || {
+                SvrConnection::connect(self.auth.clone(), sgx, transport_connector.clone())
+            }),
+            connect_with_retry::<Nitro, _, _>(&config, breakers, || {
+                SvrConnection::connect(self.auth.clone(), nitro, transport_connector.clone())
+            }),
+            connect_with_retry::<Tpm2Snp, _, _>(&config, breakers, || {
+                SvrConnection::connect(self.auth.clone(), tpm2snp, transport_connector.clone())
+            }),
         )
         .await;
         (sgx, nitro, tpm2snp)
@@ -272,14 +452,14 @@ impl<'a> Restore for Svr3Client<'a, PreviousVersion> {
 
 #[async_trait]
 impl<'a> Remove for Svr3Client<'a, PreviousVersion> {
-    async fn remove(&self) -> Result<(), Error> {
+    async fn remove(&self) -> Result<RemoveResult, Error> {
         empty_env::remove().await
     }
 }
 
 #[async_trait]
 impl<'a> Query for Svr3Client<'a, PreviousVersion> {
-    async fn query(&self) -> Result<u32, Error> {
+    async fn query(&self) -> Result<QueryResult, Error> {
         empty_env::query().await
     }
 }
@@ -295,6 +475,116 @@ impl<'a> Rotate for Svr3Client<'a, PreviousVersion> {
     }
 }
 
+pub struct Svr2Connector<'a> {
+    connection_manager: &'a ConnectionManager,
+    auth: Auth,
+}
+
+impl<'a> Svr2Connector<'a> {
+    pub fn new(
+        connection_manager: &'a ConnectionManager,
+        username: String,
+        password: String,
+    ) -> Self {
+        Self {
+            connection_manager,
+            auth: Auth { username, password },
+        }
+    }
+}
+
+#[async_trait]
+impl<'a> Svr2Connect for Svr2Connector<'a> {
+    type Stream = CountingStream<TcpSslConnectorStream>;
+
+    fn pin_salt(&self) -> [u8; 32] {
+        let group_id = self.connection_manager.svr2.params().raft_config.group_id;
+        PinHash::make_salt(&self.auth.username, group_id)
+    }
+
+    async fn connect(&self) -> Result<SvrConnection<SgxPreQuantum, Self::Stream>, enclave::Error> {
+        SvrConnection::connect(
+            self.auth.clone(),
+            &self.connection_manager.svr2,
+            self.connection_manager
+                .counting_transport_connector(TrafficKind::Svr),
+        )
+        .await
+    }
+}
+
+/// The result of moving a user from SVR2 to SVR3, once their secret has been restored from SVR2.
+///
+/// Failures before that point are reported as errors instead, since there is nothing to resume.
+#[derive(Clone)]
+pub struct PinMigrationOutcome {
+    secret: [u8; 32],
+    /// Present once the secret has been backed up to SVR3.
+    share_set: Option<OpaqueMaskedShareSet>,
+    /// Present if the migration stopped partway through.
+    error_message: Option<String>,
+}
+
+impl PinMigrationOutcome {
+    /// Converts the result of a migration, treating failure to restore from SVR2 as an error.
+    pub fn new(result: Result<PinMigrated, PinMigrationError>) -> Result<Self, Error> {
+        let PinMigrationError { resume_from, error } = match result {
+            Ok(PinMigrated { secret, share_set }) => {
+                return Ok(Self {
+                    secret,
+                    share_set: Some(share_set),
+                    error_message: None,
+                })
+            }
+            Err(e) => e,
+        };
+        let error_message = Some(error.to_string());
+        match resume_from {
+            PinMigrationStep::RestoreFromSvr2 => Err(error),
+            PinMigrationStep::BackupToSvr3 { secret } => Ok(Self {
+                secret,
+                share_set: None,
+                error_message,
+            }),
+            PinMigrationStep::DeleteFromSvr2 { secret, share_set } => Ok(Self {
+                secret,
+                share_set: Some(share_set),
+                error_message,
+            }),
+        }
+    }
+
+    /// The step to continue the migration from, or `None` if it's complete.
+    pub fn resume_from(&self) -> Option<PinMigrationStep> {
+        self.error_message.as_ref()?;
+        let secret = self.secret;
+        Some(match &self.share_set {
+            None => PinMigrationStep::BackupToSvr3 { secret },
+            Some(share_set) => PinMigrationStep::DeleteFromSvr2 {
+                secret,
+                share_set: share_set.clone(),
+            },
+        })
+    }
+
+    /// The secret restored from SVR2.
+    pub fn secret(&self) -> [u8; 32] {
+        self.secret
+    }
+
+    /// The share set for the SVR3 backup, if it has been made.
+    pub fn share_set(&self) -> Option<&OpaqueMaskedShareSet> {
+        self.share_set.as_ref()
+    }
+
+    /// Why the migration stopped, if it did not complete.
+    pub fn error_message(&self) -> Option<&str> {
+        self.error_message.as_deref()
+    }
+}
+
+bridge_as_handle!(PinMigrationOutcome);
+
 // These functions define the behavior of the empty `PreviousVersion`
 // when there is no migration going on.
 // When there _is_ migration both current and previous clients should instead
@@ -322,12 +612,17 @@ mod empty_env {
         Err(Error::DataMissing)
     }
 
-    pub async fn remove() -> Result<(), Error> {
-        Ok(())
+    pub async fn remove() -> Result<RemoveResult, Error> {
+        // There are no enclaves to remove anything from.
+        Ok(RemoveResult { removed: vec![] })
     }
 
-    pub async fn query() -> Result<u32, Error> {
-        Err(Error::DataMissing)
+    pub async fn query() -> Result<QueryResult, Error> {
+        Ok(QueryResult {
+            tries_remaining: None,
+            present: false,
+            degraded: false,
+        })
     }
 
     pub async fn rotate() -> Result<(), Error> {
@@ -356,4 +651,171 @@ mod test {
         let transport_connector = manager.transport_connector.lock().expect("not poisoned");
         assert_matches!(&*transport_connector, TcpSslConnector::Invalid(_))
     }
+
+    #[test]
+    fn network_hint_applies_to_new_chat_connections() {
+        use libsignal_net::network_hint::NetworkTransport;
+
+        let manager = ConnectionManager::new(Environment::Staging, "test-user-agent".to_owned());
+        let hint = NetworkHint {
+            transport: NetworkTransport::Cellular,
+            constrained: true,
+        };
+        assert_ne!(hint.tuning(), NetworkHint::default().tuning());
+
+        manager.set_chat_network_hint(hint);
+        let config = manager.chat_endpoint().config;
+        assert_eq!(config.max_connection_time, hint.tuning().connect_timeout);
+        assert_eq!(
+            config.keep_alive_interval,
+            hint.tuning().keep_alive_interval
+        );
+    }
+
+    /// Serializes the tests that change the process-wide proxy setting.
+    ///
+    /// Any test that depends on the setting (for example, by checking which connector
+    /// [`ConnectionManager::transport_connector`] hands out) must hold this too.
+    static GLOBAL_PROXY_LOCK: ::tokio::sync::Mutex<()> = ::tokio::sync::Mutex::const_new(());
+
+    /// Holds [`GLOBAL_PROXY_LOCK`], and puts back the global proxy that was set when it was
+    /// acquired when dropped, even if the test panicked.
+    struct GlobalProxyGuard {
+        previous: Option<libsignal_net::proxy::ProxyConfig>,
+        _lock: ::tokio::sync::MutexGuard<'static, ()>,
+    }
+
+    impl GlobalProxyGuard {
+        async fn acquire() -> Self {
+            let _lock = GLOBAL_PROXY_LOCK.lock().await;
+            Self {
+                previous: libsignal_net::proxy::global_proxy(),
+                _lock,
+            }
+        }
+    }
+
+    impl Drop for GlobalProxyGuard {
+        fn drop(&mut self) {
+            // Runs before the lock is released.
+            libsignal_net::proxy::set_global_proxy(self.previous.take());
+        }
+    }
+
+    #[tokio::test]
+    async fn global_proxy_applies_to_chat_and_cdsi() {
+        use std::net::Ipv4Addr;
+        use std::sync::atomic::{AtomicUsize, Ordering};
+
+        use libsignal_net::proxy::{set_global_proxy, ProxyConfig};
+
+        let _guard = GlobalProxyGuard::acquire().await;
+
+        // Stands in for a proxy; we only care that connections show up here.
+        let proxy_stub = ::tokio::net::TcpListener::bind((Ipv4Addr::LOCALHOST, 0))
+            .await
+            .expect("can bind");
+        let proxy_port = proxy_stub.local_addr().expect("bound").port();
+
+        let manager = ConnectionManager::new(Environment::Staging, "test-user-agent".to_owned());
+        let network_changes = Arc::new(AtomicUsize::new(0));
+        let _subscription = {
+            let network_changes = network_changes.clone();
+            manager.network_change_event.subscribe(Box::new(move || {
+                network_changes.fetch_add(1, Ordering::Relaxed);
+            }))
+        };
+
+        set_global_proxy(Some(ProxyConfig {
+            host: Host::Domain(format!("UNENCRYPTED_FOR_TESTING@{}", Ipv4Addr::LOCALHOST).into()),
+            port: NonZeroU16::new(proxy_port).expect("nonzero"),
+        }));
+        assert_eq!(network_changes.load(Ordering::Relaxed), 1);
+        assert_matches!(manager.transport_connector(), TcpSslConnector::Proxied(_));
+
+        let auth = Auth {
+            username: "username".to_owned(),
+            password: "password".to_owned(),
+        };
+        ::tokio::select! {
+            _ = cdsi::CdsiLookup::new(
+                &manager,
+                auth,
+                Default::default(),
+                ::tokio::time::Instant::now() + ONE_ROUTE_CONNECTION_TIMEOUT,
+            ) => {
+                panic!("CDSI lookup finished without going through the proxy")
+            }
+            accepted = proxy_stub.accept() => {
+                accepted.expect("CDSI connected to the proxy");
+            }
+        }
+
+        let chat = chat::UnauthChat::new_unauth(&manager);
+        ::tokio::select! {
+            _ = chat.service.0.connect_unauthenticated() => {
+                panic!("chat connect finished without going through the proxy")
+            }
+            accepted = proxy_stub.accept() => {
+                accepted.expect("chat connected to the proxy");
+            }
+        }
+
+        set_global_proxy(None);
+        assert_eq!(network_changes.load(Ordering::Relaxed), 2);
+        assert_matches!(manager.transport_connector(), TcpSslConnector::Direct(_));
+    }
+
+    #[tokio::test]
+    async fn global_proxy_can_be_bypassed() {
+        use libsignal_net::proxy::{set_global_proxy, ProxyConfig};
+
+        let _guard = GlobalProxyGuard::acquire().await;
+
+        let manager = ConnectionManager::new(Environment::Staging, "test-user-agent".to_owned());
+        set_global_proxy(Some(ProxyConfig {
+            host: Host::Domain("proxy.example".into()),
+            port: NonZeroU16::new(443).expect("nonzero"),
+        }));
+
+        assert_matches!(
+            manager.transport_connector_with_policy(GlobalProxyPolicy::Respect),
+            TcpSslConnector::Proxied(_)
+        );
+        assert_matches!(
+            manager.transport_connector_with_policy(GlobalProxyPolicy::Bypass),
+            TcpSslConnector::Direct(_)
+        );
+    }
+
+    #[::tokio::test]
+    async fn static_dns_entries_apply_to_handed_out_connectors() {
+        use std::net::Ipv4Addr;
+
+        const HOSTNAME: &str = "chat.staging.signal.org";
+
+        let _guard = GlobalProxyGuard::acquire().await;
+
+        let manager = ConnectionManager::new(Environment::Staging, "test-user-agent".to_owned());
+        manager.set_static_dns_entry(HOSTNAME, vec![Ipv4Addr::LOCALHOST.into()]);
+
+        // Chat, CDSI, and SVR3 all connect using connectors from transport_connector(), so
+        // checking its resolver covers them, with or without a proxy in the way.
+        let check_resolver = |connector: TcpSslConnector| async move {
+            let result = connector
+                .dns_resolver()
+                .lookup_ip(HOSTNAME)
+                .await
+                .expect("overridden");
+            assert_eq!(
+                result.into_iter().collect::<Vec<_>>(),
+                [IpAddr::from(Ipv4Addr::LOCALHOST)]
+            );
+        };
+        check_resolver(manager.transport_connector()).await;
+        manager
+            .set_proxy("proxy.example", NonZeroU16::new(443))
+            .expect("valid");
+        check_resolver(manager.transport_connector()).await;
+    }
 }
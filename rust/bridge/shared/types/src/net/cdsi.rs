@@ -6,6 +6,8 @@
 use libsignal_net::auth::Auth;
 use libsignal_net::cdsi::{self, CdsiConnection, ClientResponseCollector, Token};
 use libsignal_net::infra::tcp_ssl::TcpSslConnectorStream;
+use libsignal_net::infra::traffic_stats::{CountingStream, TrafficKind};
+use tokio::time::Instant;
 
 use crate::net::ConnectionManager;
 use crate::*;
@@ -43,22 +45,27 @@ bridge_as_handle!(LookupRequest);
 
 pub struct CdsiLookup {
     pub token: Token,
-    remaining: std::sync::Mutex<Option<ClientResponseCollector<TcpSslConnectorStream>>>,
+    remaining:
+        std::sync::Mutex<Option<ClientResponseCollector<CountingStream<TcpSslConnectorStream>>>>,
 }
 
 impl CdsiLookup {
+    /// Connects to CDSI and sends `request`, giving up on connecting at `deadline`.
     pub async fn new(
         connection_manager: &ConnectionManager,
         auth: Auth,
         request: cdsi::LookupRequest,
+        deadline: Instant,
     ) -> Result<Self, cdsi::LookupError> {
-        let transport_connector = connection_manager
-            .transport_connector
-            .lock()
-            .expect("not poisoned")
-            .clone();
-        let connected =
-            CdsiConnection::connect(&connection_manager.cdsi, transport_connector, auth).await?;
+        let transport_connector =
+            connection_manager.counting_transport_connector(TrafficKind::Cdsi);
+        let connected = CdsiConnection::connect_with_deadline(
+            &connection_manager.cdsi,
+            transport_connector,
+            auth,
+            deadline,
+        )
+        .await?;
         let (token, remaining_response) = connected.send_request(request).await?;
 
         Ok(CdsiLookup {
@@ -67,9 +74,58 @@ impl CdsiLookup {
         })
     }
 
-    pub fn take_remaining(&self) -> Option<ClientResponseCollector<TcpSslConnectorStream>> {
+    pub fn take_remaining(
+        &self,
+    ) -> Option<ClientResponseCollector<CountingStream<TcpSslConnectorStream>>> {
         self.remaining.lock().expect("not poisoned").take()
     }
 }
 
 bridge_as_handle!(CdsiLookup);
+
+/// A [`cdsi::LookupResponse`] bridged as a list of entries, each with an explicitly optional ACI
+/// and PNI.
+///
+/// The bridged [`cdsi::LookupResponse`] is a map keyed by phone number, and over FFI a missing
+/// ID is sent as the nil UUID. This keeps the records in order and never needs a placeholder.
+#[derive(Debug)]
+pub struct LookupResponseEntries {
+    pub entries: Vec<cdsi::LookupResponseEntry>,
+    pub debug_permits_used: i32,
+}
+
+impl From<cdsi::LookupResponse> for LookupResponseEntries {
+    fn from(response: cdsi::LookupResponse) -> Self {
+        let cdsi::LookupResponse {
+            records,
+            debug_permits_used,
+        } = response;
+        Self {
+            entries: records,
+            debug_permits_used,
+        }
+    }
+}
+
+/// Connects to CDSI just long enough to see the attestation evidence it presents, and returns
+/// the raw attestation message.
+///
+/// No lookup is made; the connection is closed as soon as the handshake completes.
+pub async fn capture_attestation_evidence(
+    connection_manager: &ConnectionManager,
+    auth: Auth,
+    deadline: Instant,
+) -> Result<Vec<u8>, cdsi::LookupError> {
+    let connected = CdsiConnection::connect_capturing_evidence(
+        &connection_manager.cdsi,
+        connection_manager.counting_transport_connector(TrafficKind::Cdsi),
+        auth,
+        deadline,
+    )
+    .await?;
+    Ok(connected
+        .attestation_info()
+        .raw_evidence
+        .clone()
+        .expect("requested when connecting"))
+}
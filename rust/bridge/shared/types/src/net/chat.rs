@@ -7,6 +7,7 @@ use std::future::Future;
 use std::panic::{self, RefUnwindSafe};
 use std::str::FromStr;
 use std::sync::Arc;
+use std::time::Instant;
 
 use atomic_take::AtomicTake;
 use futures_util::stream::BoxStream;
@@ -14,10 +15,13 @@ use futures_util::StreamExt as _;
 use http::status::InvalidStatusCode;
 use http::uri::{InvalidUri, PathAndQuery};
 use http::{HeaderMap, HeaderName, HeaderValue};
-use libsignal_net::auth::Auth;
+use libsignal_net::auth::{Auth, AuthProvider};
 use libsignal_net::chat::{
     self, ChatServiceError, DebugInfo as ChatServiceDebugInfo, Response as ChatResponse,
 };
+use libsignal_net::infra::tcp_ssl::TcpSslConnectorStream;
+use libsignal_net::infra::traffic_stats::{CountingStream, TrafficKind};
+use libsignal_net::infra::RouteType;
 use libsignal_protocol::Timestamp;
 use tokio::sync::{mpsc, oneshot};
 
@@ -58,12 +62,11 @@ pub struct Chat<T> {
     pub service: T,
     listener: std::sync::Mutex<ChatListenerState>,
     pub synthetic_request_tx:
-        mpsc::Sender<chat::ws::ServerEvent<libsignal_net::infra::tcp_ssl::TcpSslConnectorStream>>,
+        mpsc::Sender<chat::ws::ServerEvent<CountingStream<TcpSslConnectorStream>>>,
 }
 
 type MpscPair<T> = (mpsc::Sender<T>, mpsc::Receiver<T>);
-type ServerEventStreamPair =
-    MpscPair<chat::ws::ServerEvent<libsignal_net::infra::tcp_ssl::TcpSslConnectorStream>>;
+type ServerEventStreamPair = MpscPair<chat::ws::ServerEvent<CountingStream<TcpSslConnectorStream>>>;
 
 // These two types are the same for now, but might not be in the future.
 pub struct AuthChatService(
@@ -120,7 +123,7 @@ impl<T> Chat<T> {
         // starting a run-loop. We *do* want that run-loop to be async so it goes to sleep when
         // there are no messages.
         let handle = runtime
-            .rt
+            .handle
             .spawn(listener.start_listening(request_stream_future, cancel_rx));
 
         *guard = ChatListenerState::Active {
@@ -138,7 +141,7 @@ impl<T> Chat<T> {
 impl Chat<AuthChatService> {
     pub fn new_auth(
         connection_manager: &ConnectionManager,
-        auth: Auth,
+        auth: impl AuthProvider + 'static,
         receive_stories: bool,
     ) -> Self {
         let (incoming_auth_tx, incoming_auth_rx) = mpsc::channel(1);
@@ -147,12 +150,8 @@ impl Chat<AuthChatService> {
         let (incoming_unauth_tx, _incoming_unauth_rx) = mpsc::channel(1);
 
         let service = chat::chat_service(
-            &connection_manager.chat,
-            connection_manager
-                .transport_connector
-                .lock()
-                .expect("not poisoned")
-                .clone(),
+            &connection_manager.chat_endpoint(),
+            connection_manager.counting_transport_connector(TrafficKind::Chat),
             incoming_auth_tx,
             incoming_unauth_tx,
             auth,
@@ -174,12 +173,8 @@ impl Chat<UnauthChatService> {
         let synthetic_request_tx = incoming_unauth_tx.clone();
 
         let service = chat::chat_service(
-            &connection_manager.chat,
-            connection_manager
-                .transport_connector
-                .lock()
-                .expect("not poisoned")
-                .clone(),
+            &connection_manager.chat_endpoint(),
+            connection_manager.counting_transport_connector(TrafficKind::Chat),
             incoming_auth_tx,
             incoming_unauth_tx,
             // These will be unused because the auth service won't ever be connected.
@@ -198,6 +193,25 @@ impl Chat<UnauthChatService> {
     }
 }
 
+/// A pool of unauthenticated connections for one-shot requests.
+pub struct UnauthChatPool(pub chat::pool::UnauthChatPool);
+
+// The pool's connect function isn't marked RefUnwindSafe, but the pool's state is only modified
+// while holding a lock, so it's never left inconsistent by a panic.
+impl RefUnwindSafe for UnauthChatPool {}
+
+impl UnauthChatPool {
+    pub fn new(connection_manager: &ConnectionManager, runtime: &TokioAsyncContext) -> Self {
+        // The pool closes expired connections from a task on the runtime.
+        let _guard = runtime.handle.enter();
+        Self(chat::unauth_chat_pool(
+            &connection_manager.chat_endpoint(),
+            connection_manager.counting_transport_connector(TrafficKind::Chat),
+            Default::default(),
+        ))
+    }
+}
+
 pub type UnauthChat = Chat<UnauthChatService>;
 pub type AuthChat = Chat<AuthChatService>;
 
@@ -208,14 +222,39 @@ pub struct HttpRequest {
     pub headers: std::sync::Mutex<HeaderMap>,
 }
 
+/// The per-recipient device mismatches from a rejected multi-recipient send.
+pub struct MultiRecipientMismatchedDevices(pub Vec<chat::multi_recipient::MismatchedDevices>);
+
+pub use chat::challenge::RegistrationLockFailure;
+
 pub struct ResponseAndDebugInfo {
     pub response: ChatResponse,
     pub debug_info: ChatServiceDebugInfo,
 }
 
+/// The code used for a [`ChatServiceDebugInfo`]'s route type on the app side.
+///
+/// 0 means there was no connection. These must be kept in sync with the app-side enums.
+pub fn route_type_code(route_type: Option<RouteType>) -> u8 {
+    match route_type {
+        None => 0,
+        Some(RouteType::Direct) => 1,
+        Some(RouteType::ProxyF) => 2,
+        Some(RouteType::ProxyG) => 3,
+        Some(RouteType::TlsProxy) => 4,
+        Some(RouteType::SocksProxy) => 5,
+        // Only present when libsignal-net-infra is built for testing.
+        #[allow(unreachable_patterns)]
+        Some(_) => 0,
+    }
+}
+
 bridge_as_handle!(UnauthChat);
 bridge_as_handle!(AuthChat);
 bridge_as_handle!(HttpRequest);
+bridge_as_handle!(MultiRecipientMismatchedDevices);
+bridge_as_handle!(RegistrationLockFailure);
+bridge_as_handle!(UnauthChatPool);
 
 /// Newtype wrapper for implementing [`TryFrom`]`
 pub struct HttpMethod(http::Method);
@@ -259,15 +298,56 @@ impl HttpRequest {
         })
     }
 
+    /// Adds a header, keeping any existing values for the same name.
+    ///
+    /// Names are case-insensitive. Invalid names and values are rejected when they're converted to
+    /// [`HeaderName`] and [`HeaderValue`], so any header that makes it here can be sent as is.
     pub fn add_header(&self, name: HeaderName, value: HeaderValue) {
         let mut guard = self.headers.lock().expect("not poisoned");
         guard.append(name, value);
     }
+
+    /// Returns the first value for the header `name`, matched case-insensitively.
+    ///
+    /// Returns `None` if there's no such header, including if `name` isn't a valid header name.
+    pub fn get_header(&self, name: &str) -> Option<String> {
+        let guard = self.headers.lock().expect("not poisoned");
+        guard.get(name).map(header_value_to_string)
+    }
+
+    /// Returns all values for the header `name`, combined into one as described in [RFC 9110
+    /// §5.3](https://www.rfc-editor.org/rfc/rfc9110#section-5.3).
+    ///
+    /// Like [`Self::get_header`], `name` is matched case-insensitively.
+    pub fn get_combined_header(&self, name: &str) -> Option<String> {
+        let guard = self.headers.lock().expect("not poisoned");
+        let mut values = guard
+            .get_all(name)
+            .iter()
+            .map(header_value_to_string)
+            .peekable();
+        values.peek()?;
+        Some(values.collect::<Vec<_>>().join(", "))
+    }
+
+    /// Lists the names of the headers that have been added, in lowercase and without duplicates.
+    pub fn header_names(&self) -> Vec<String> {
+        let guard = self.headers.lock().expect("not poisoned");
+        guard.keys().map(|name| name.as_str().to_owned()).collect()
+    }
+}
+
+fn header_value_to_string(value: &HeaderValue) -> String {
+    // Header values are only ever constructed from strings, so they should always be valid UTF-8.
+    String::from_utf8_lossy(value.as_bytes()).into_owned()
 }
 
 /// A trait of callbacks for different kinds of [`chat::server_requests::ServerMessage`].
 ///
 /// Done as multiple functions so we can adjust the types to be more suitable for bridging.
+///
+/// Callbacks for a single connection are made one at a time, in the order the server sent the
+/// events; the next callback isn't made until the previous one has returned.
 pub trait ChatListener: Send {
     fn received_incoming_message(
         &mut self,
@@ -294,6 +374,20 @@ impl dyn ChatListener {
                 server_delivery_timestamp,
                 ServerMessageAck::new(send_ack),
             ),
+            // Apps don't have a separate callback for receipts yet, so they still get the
+            // original envelope.
+            chat::server_requests::ServerEvent::Receipt {
+                request_id: _,
+                sender: _,
+                timestamps: _,
+                envelope,
+                server_delivery_timestamp,
+                send_ack,
+            } => self.received_incoming_message(
+                envelope,
+                server_delivery_timestamp,
+                ServerMessageAck::new(send_ack),
+            ),
             chat::server_requests::ServerEvent::QueueEmpty => self.received_queue_empty(),
             chat::server_requests::ServerEvent::Stopped(error) => {
                 self.connection_interrupted(error)
@@ -304,7 +398,9 @@ impl dyn ChatListener {
     /// Starts a run loop to read from a stream of requests.
     ///
     /// Awaits `request_stream_future`, then loops until the stream is drained or `cancel_rx` fires.
-    /// Each item in the stream is processed using [`Self::received_server_request`].
+    /// Each item in the stream is processed using [`Self::received_server_request`], and the next
+    /// item isn't read until that returns. A warning is logged if that takes longer than
+    /// [`chat::ws::LONG_REQUEST_PROCESSING_THRESHOLD`].
     ///
     /// Consumes `self`. Returns the remaining request stream, in case another listener will be set
     /// later.
@@ -351,7 +447,29 @@ impl dyn ChatListener {
                 listener_for_blocking_task.received_server_request(next);
                 listener_for_blocking_task
             });
-            listener = match blocking_task.await {
+            let mut blocking_task = std::pin::pin!(blocking_task);
+            let callback_start = Instant::now();
+            let result = match ::tokio::time::timeout(
+                chat::ws::LONG_REQUEST_PROCESSING_THRESHOLD,
+                &mut blocking_task,
+            )
+            .await
+            {
+                Ok(result) => result,
+                Err(_elapsed) => {
+                    log::warn!(
+                        "chat listener callback has been running for more than {:?}; later events are waiting for it",
+                        chat::ws::LONG_REQUEST_PROCESSING_THRESHOLD,
+                    );
+                    let result = blocking_task.await;
+                    log::warn!(
+                        "chat listener callback finished after {:?}",
+                        callback_start.elapsed()
+                    );
+                    result
+                }
+            };
+            listener = match result {
                 Ok(listener) => Some(listener),
                 Err(e) => {
                     log::error!(
@@ -375,6 +493,14 @@ pub trait MakeChatListener {
     fn make_listener(&self) -> Box<dyn ChatListener>;
 }
 
+/// Produces the [`AuthProvider`] an authenticated chat service gets its credentials from.
+///
+/// Like [`MakeChatListener`], this keeps the allocation explicit in `bridge_fn` signatures. Only the
+/// FFI bridge implements it for now.
+pub trait MakeChatAuthProvider {
+    fn make_auth_provider(&self) -> Box<dyn AuthProvider>;
+}
+
 /// Wraps a named type and a single-use guard around [`chat::server_requests::AckEnvelopeFuture`].
 pub struct ServerMessageAck {
     inner: AtomicTake<chat::server_requests::ResponseEnvelopeSender>,
@@ -398,3 +524,142 @@ bridge_as_handle!(ServerMessageAck);
 // makes it `!RefUnwindSafe`. We're putting that back; because we only manipulate the `AtomicTake`
 // using its atomic operations, it can never be in an invalid state.
 impl std::panic::RefUnwindSafe for ServerMessageAck {}
+
+#[cfg(test)]
+mod test {
+    use std::sync::atomic::{AtomicBool, Ordering};
+    use std::sync::Mutex;
+    use std::time::Duration;
+
+    use rand::Rng as _;
+
+    use super::*;
+
+    fn request_with_headers(headers: &[(&str, &str)]) -> HttpRequest {
+        let request = HttpRequest::new(HttpMethod(http::Method::GET), "/v1/test".to_owned(), None)
+            .expect("valid");
+        for (name, value) in headers {
+            request.add_header(
+                HeaderName::try_from(*name).expect("valid name"),
+                HeaderValue::try_from(*value).expect("valid value"),
+            );
+        }
+        request
+    }
+
+    #[test]
+    fn header_lookup_is_case_insensitive() {
+        let request = request_with_headers(&[("Content-Type", "application/json")]);
+        for name in ["content-type", "Content-Type", "CONTENT-TYPE"] {
+            assert_eq!(
+                request.get_header(name).as_deref(),
+                Some("application/json"),
+                "{name}"
+            );
+        }
+        assert_eq!(request.header_names(), ["content-type"]);
+    }
+
+    #[test]
+    fn missing_or_invalid_header_name_is_none() {
+        let request = request_with_headers(&[("Content-Type", "application/json")]);
+        assert_eq!(request.get_header("accept"), None);
+        assert_eq!(request.get_combined_header("accept"), None);
+        assert_eq!(request.get_header("not a header"), None);
+    }
+
+    #[test]
+    fn duplicate_headers() {
+        let request = request_with_headers(&[
+            ("Accept", "text/plain"),
+            ("X-Other", "value"),
+            ("accept", "application/json"),
+        ]);
+        assert_eq!(request.get_header("accept").as_deref(), Some("text/plain"));
+        assert_eq!(
+            request.get_combined_header("ACCEPT").as_deref(),
+            Some("text/plain, application/json")
+        );
+        assert_eq!(
+            request.get_combined_header("x-other").as_deref(),
+            Some("value")
+        );
+
+        let mut names = request.header_names();
+        names.sort();
+        assert_eq!(names, ["accept", "x-other"]);
+    }
+
+    #[test]
+    fn invalid_header_names_are_rejected_before_insertion() {
+        // The bridge converts names with TryFrom<String>, so a bad name is an error from
+        // HttpRequest_add_header rather than a header that can never be looked up.
+        assert!(HeaderName::try_from("bad name".to_owned()).is_err());
+        assert!(HeaderName::try_from("bad\nname".to_owned()).is_err());
+        assert!(HeaderName::try_from(String::new()).is_err());
+    }
+
+    /// Records the timestamps of incoming messages, taking a random amount of time for each.
+    struct SlowListener {
+        received: Arc<Mutex<Vec<u64>>>,
+        in_callback: Arc<AtomicBool>,
+    }
+
+    impl ChatListener for SlowListener {
+        fn received_incoming_message(
+            &mut self,
+            _envelope: Vec<u8>,
+            timestamp: Timestamp,
+            _ack: ServerMessageAck,
+        ) {
+            assert!(
+                !self.in_callback.swap(true, Ordering::SeqCst),
+                "callbacks overlapped"
+            );
+            std::thread::sleep(Duration::from_millis(rand::thread_rng().gen_range(0..10)));
+            self.received
+                .lock()
+                .expect("not poisoned")
+                .push(timestamp.epoch_millis());
+            self.in_callback.store(false, Ordering::SeqCst);
+        }
+
+        fn received_queue_empty(&mut self) {
+            unreachable!("not sent")
+        }
+
+        fn connection_interrupted(&mut self, _disconnect_cause: ChatServiceError) {
+            unreachable!("not sent")
+        }
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn listener_sees_events_in_order_despite_slow_callbacks() {
+        const COUNT: u64 = 50;
+
+        let events = (0..COUNT).map(|i| chat::server_requests::ServerEvent::IncomingMessage {
+            request_id: i,
+            envelope: vec![],
+            server_delivery_timestamp: Timestamp::from_epoch_millis(i),
+            send_ack: Box::new(|_| Box::pin(std::future::ready(Ok(())))),
+        });
+        let received = Arc::new(Mutex::new(vec![]));
+        let listener: Box<dyn ChatListener> = Box::new(SlowListener {
+            received: received.clone(),
+            in_callback: Default::default(),
+        });
+
+        let (_cancel_tx, cancel_rx) = oneshot::channel();
+        let _remaining = listener
+            .start_listening(
+                std::future::ready(Ok(futures_util::stream::iter(events).boxed())),
+                cancel_rx,
+            )
+            .await;
+
+        assert_eq!(
+            *received.lock().expect("not poisoned"),
+            (0..COUNT).collect::<Vec<_>>()
+        );
+    }
+}
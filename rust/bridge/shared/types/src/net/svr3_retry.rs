@@ -0,0 +1,318 @@
+//
+// Copyright 2024 Signal Messenger, LLC.
+// SPDX-License-Identifier: AGPL-3.0-only
+//
+
+//! Retries for connecting to SVR3 enclaves, and a circuit breaker to stop retrying altogether
+//! when an enclave is clearly down.
+//!
+//! Without these, transient failures go straight to the app, whose own retries have been known to
+//! hammer the enclaves during an outage.
+
+use std::collections::HashMap;
+use std::future::Future;
+use std::num::NonZeroU32;
+use std::time::Duration;
+
+use libsignal_net::enclave;
+use once_cell::sync::Lazy;
+use rand::Rng;
+use tokio::time::Instant;
+
+/// How SVR3 enclave connections are retried.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Svr3RetryConfig {
+    /// The total number of attempts to connect to each enclave, including the first.
+    pub max_attempts: NonZeroU32,
+    /// The upper bound on the delay before the first retry, which doubles for each one after.
+    pub base_delay: Duration,
+    /// The upper bound on the delay before any retry.
+    pub max_delay: Duration,
+    /// The number of consecutive failures to connect to one kind of enclave after which further
+    /// attempts fail immediately with [`enclave::Error::CircuitOpen`].
+    pub failure_threshold: NonZeroU32,
+    /// How long attempts keep failing immediately once the threshold is reached.
+    pub open_duration: Duration,
+}
+
+impl Default for Svr3RetryConfig {
+    fn default() -> Self {
+        Self {
+            max_attempts: NonZeroU32::new(3).expect("non-zero"),
+            base_delay: Duration::from_millis(250),
+            max_delay: Duration::from_secs(4),
+            failure_threshold: NonZeroU32::new(5).expect("non-zero"),
+            open_duration: Duration::from_secs(30),
+        }
+    }
+}
+
+impl Svr3RetryConfig {
+    /// Picks the delay before retry number `retry` (starting from 1), with "full jitter": anywhere
+    /// from zero up to the exponentially growing bound.
+    fn backoff(&self, retry: u32, rng: &mut impl Rng) -> Duration {
+        let bound = self
+            .base_delay
+            .saturating_mul(1 << (retry - 1).min(16))
+            .min(self.max_delay);
+        rng.gen_range(Duration::ZERO..=bound)
+    }
+}
+
+/// Tracks consecutive connection failures for each kind of enclave.
+#[derive(Debug, Default)]
+pub struct CircuitBreakers {
+    states: std::sync::Mutex<HashMap<&'static str, BreakerState>>,
+}
+
+#[derive(Debug, Default)]
+struct BreakerState {
+    consecutive_failures: u32,
+    open_until: Option<Instant>,
+}
+
+/// Shared by every connection manager in the process, since they all talk to the same enclaves.
+pub static SVR3_CIRCUIT_BREAKERS: Lazy<CircuitBreakers> = Lazy::new(CircuitBreakers::default);
+
+impl CircuitBreakers {
+    fn check(&self, kind: &'static str) -> Result<(), enclave::Error> {
+        let states = self.states.lock().expect("not poisoned");
+        match states.get(kind).and_then(|state| state.open_until) {
+            Some(open_until) if Instant::now() < open_until => Err(enclave::Error::CircuitOpen),
+            _ => Ok(()),
+        }
+    }
+
+    fn record_success(&self, kind: &'static str) {
+        self.states.lock().expect("not poisoned").remove(kind);
+    }
+
+    fn record_failure(&self, kind: &'static str, config: &Svr3RetryConfig) {
+        let mut states = self.states.lock().expect("not poisoned");
+        let state = states.entry(kind).or_default();
+        state.consecutive_failures += 1;
+        if state.consecutive_failures >= config.failure_threshold.get() {
+            log::warn!(
+                "{kind} failed {} times in a row; not connecting for {:?}",
+                state.consecutive_failures,
+                config.open_duration
+            );
+            state.open_until = Some(Instant::now() + config.open_duration);
+        }
+    }
+}
+
+/// Only failures that might go away on their own are retried; attestation and protocol errors
+/// won't.
+fn is_retryable(error: &enclave::Error) -> bool {
+    match error {
+        enclave::Error::ConnectionTimedOut | enclave::Error::WebSocket(_) => true,
+        enclave::Error::WebSocketConnect(_)
+        | enclave::Error::Protocol
+        | enclave::Error::AttestationError(_)
+        | enclave::Error::CircuitOpen => false,
+    }
+}
+
+/// Calls `connect` until it succeeds, fails with an error that isn't worth retrying, or has been
+/// tried [`Svr3RetryConfig::max_attempts`] times.
+///
+/// Retryable failures count towards opening the circuit breaker for `E`, and a success closes it.
+pub async fn connect_with_retry<E, T, Fut>(
+    config: &Svr3RetryConfig,
+    breakers: &CircuitBreakers,
+    mut connect: impl FnMut() -> Fut,
+) -> Result<T, enclave::Error>
+where
+    Fut: Future<Output = Result<T, enclave::Error>>,
+{
+    let kind = std::any::type_name::<E>();
+    let mut attempts = 0;
+    loop {
+        breakers.check(kind)?;
+        attempts += 1;
+        match connect().await {
+            Ok(connection) => {
+                breakers.record_success(kind);
+                return Ok(connection);
+            }
+            Err(e) if is_retryable(&e) => {
+                breakers.record_failure(kind, config);
+                if attempts >= config.max_attempts.get() {
+                    return Err(e);
+                }
+                let delay = config.backoff(attempts, &mut rand::thread_rng());
+                log::info!("connecting to {kind} failed ({e}); retrying in {delay:?}");
+                tokio::time::sleep(delay).await;
+            }
+            Err(e) => return Err(e),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::sync::atomic::{AtomicU32, Ordering};
+
+    use assert_matches::assert_matches;
+    use libsignal_net::enclave::{Nitro, Sgx};
+    use test_case::test_case;
+
+    use super::*;
+
+    /// Stands in for an enclave, failing the given attempts (numbered from 1) and accepting the
+    /// rest.
+    struct FakeEnclave<F> {
+        attempts: AtomicU32,
+        fails: F,
+    }
+
+    impl<F: Fn(u32) -> Option<enclave::Error>> FakeEnclave<F> {
+        fn new(fails: F) -> Self {
+            Self {
+                attempts: AtomicU32::new(0),
+                fails,
+            }
+        }
+
+        async fn connect(&self) -> Result<(), enclave::Error> {
+            let attempt = self.attempts.fetch_add(1, Ordering::Relaxed) + 1;
+            (self.fails)(attempt).map_or(Ok(()), Err)
+        }
+
+        fn attempts(&self) -> u32 {
+            self.attempts.load(Ordering::Relaxed)
+        }
+    }
+
+    fn timed_out(_attempt: u32) -> Option<enclave::Error> {
+        Some(enclave::Error::ConnectionTimedOut)
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn retries_until_success() {
+        let config = Svr3RetryConfig::default();
+        let breakers = CircuitBreakers::default();
+        let enclave = FakeEnclave::new(|attempt| {
+            if attempt < 3 {
+                timed_out(attempt)
+            } else {
+                None
+            }
+        });
+
+        let start = Instant::now();
+        connect_with_retry::<Sgx, _, _>(&config, &breakers, || enclave.connect())
+            .await
+            .expect("succeeds on the last attempt");
+        assert_eq!(enclave.attempts(), 3);
+        let max_total_delay = config.base_delay + config.base_delay * 2;
+        assert!(start.elapsed() <= max_total_delay, "{:?}", start.elapsed());
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn gives_up_after_max_attempts() {
+        let config = Svr3RetryConfig::default();
+        let breakers = CircuitBreakers::default();
+        let enclave = FakeEnclave::new(timed_out);
+
+        let result =
+            connect_with_retry::<Sgx, _, _>(&config, &breakers, || enclave.connect()).await;
+        assert_matches!(result, Err(enclave::Error::ConnectionTimedOut));
+        assert_eq!(enclave.attempts(), config.max_attempts.get());
+    }
+
+    #[test_case(enclave::Error::Protocol; "protocol")]
+    #[test_case(enclave::Error::AttestationError(attest::enclave::Error::AttestationDataError {
+        reason: "test".to_owned()
+    }); "attestation")]
+    #[tokio::test(start_paused = true)]
+    async fn does_not_retry_other_errors(error: enclave::Error) {
+        let config = Svr3RetryConfig::default();
+        let breakers = CircuitBreakers::default();
+        let error = std::sync::Mutex::new(Some(error));
+        let enclave = FakeEnclave::new(|_| error.lock().expect("not poisoned").take());
+
+        let result =
+            connect_with_retry::<Sgx, _, _>(&config, &breakers, || enclave.connect()).await;
+        assert_matches!(result, Err(_));
+        assert_eq!(enclave.attempts(), 1);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn breaker_opens_after_consecutive_failures() {
+        let config = Svr3RetryConfig {
+            max_attempts: NonZeroU32::new(1).expect("non-zero"),
+            ..Default::default()
+        };
+        let threshold = config.failure_threshold.get();
+        let breakers = CircuitBreakers::default();
+        let enclave = FakeEnclave::new(timed_out);
+
+        for _ in 0..threshold {
+            let result =
+                connect_with_retry::<Sgx, _, _>(&config, &breakers, || enclave.connect()).await;
+            assert_matches!(result, Err(enclave::Error::ConnectionTimedOut));
+        }
+        assert_eq!(enclave.attempts(), threshold);
+
+        // Now the breaker is open, and the enclave isn't contacted at all.
+        let result =
+            connect_with_retry::<Sgx, _, _>(&config, &breakers, || enclave.connect()).await;
+        assert_matches!(result, Err(enclave::Error::CircuitOpen));
+        assert_eq!(enclave.attempts(), threshold);
+
+        // Other kinds of enclave are unaffected.
+        let other = FakeEnclave::new(|_| None);
+        connect_with_retry::<Nitro, _, _>(&config, &breakers, || other.connect())
+            .await
+            .expect("different breaker");
+
+        // Once the breaker's time is up, the enclave is tried again...
+        tokio::time::advance(config.open_duration).await;
+        let result =
+            connect_with_retry::<Sgx, _, _>(&config, &breakers, || enclave.connect()).await;
+        assert_matches!(result, Err(enclave::Error::ConnectionTimedOut));
+        assert_eq!(enclave.attempts(), threshold + 1);
+
+        // ...but a single failure reopens it.
+        let result =
+            connect_with_retry::<Sgx, _, _>(&config, &breakers, || enclave.connect()).await;
+        assert_matches!(result, Err(enclave::Error::CircuitOpen));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn success_resets_breaker() {
+        let config = Svr3RetryConfig::default();
+        let threshold = config.failure_threshold.get();
+        let breakers = CircuitBreakers::default();
+        // Fail all but every third attempt, so each call succeeds on its last try, after more
+        // failures in total than the threshold.
+        let enclave = FakeEnclave::new(|attempt| {
+            if attempt % 3 != 0 {
+                timed_out(attempt)
+            } else {
+                None
+            }
+        });
+
+        for _ in 0..threshold {
+            connect_with_retry::<Sgx, _, _>(&config, &breakers, || enclave.connect())
+                .await
+                .expect("intermittent failures are retried");
+        }
+        assert_eq!(enclave.attempts(), threshold * 3);
+    }
+
+    #[test]
+    fn backoff_is_bounded() {
+        let config = Svr3RetryConfig::default();
+        let mut rng = rand::thread_rng();
+        for retry in 1..40 {
+            let bound = (config.base_delay * 2u32.pow((retry - 1).min(16))).min(config.max_delay);
+            for _ in 0..10 {
+                assert!(config.backoff(retry, &mut rng) <= bound);
+            }
+        }
+    }
+}
@@ -6,7 +6,8 @@
 use std::collections::HashMap;
 use std::future::Future;
 use std::sync::atomic::AtomicU64;
-use std::sync::{Arc, Mutex};
+use std::sync::{Arc, Condvar, Mutex};
+use std::time::{Duration, Instant};
 
 use futures_util::future::BoxFuture;
 use futures_util::FutureExt as _;
@@ -14,24 +15,243 @@ use futures_util::FutureExt as _;
 use crate::support::*;
 use crate::*;
 pub struct TokioAsyncContext {
-    pub(crate) rt: tokio::runtime::Runtime,
-    tasks: Arc<Mutex<HashMap<CancellationId, tokio::sync::oneshot::Sender<()>>>>,
+    /// The runtime itself, until [`Self::shutdown`] drops it.
+    rt: Mutex<Option<RuntimeOwner>>,
+    /// A handle to `rt`, which stays usable (if inert) after shutdown.
+    pub(crate) handle: tokio::runtime::Handle,
+    tasks: Arc<TaskTracker>,
     next_raw_cancellation_id: AtomicU64,
+    /// Keeps a paused clock from advancing on its own while set.
+    ///
+    /// Dropping the sender lets the blocking task holding back the clock finish.
+    #[cfg(feature = "testing-fns")]
+    auto_advance_inhibitor: Mutex<Option<std::sync::mpsc::Sender<()>>>,
+}
+
+enum RuntimeOwner {
+    /// A runtime that runs on its own worker threads.
+    Direct(tokio::runtime::Runtime),
+    /// A current-thread runtime being driven by a dedicated thread.
+    ///
+    /// Sending a timeout makes the thread shut the runtime down, waiting up to that long.
+    #[cfg(feature = "testing-fns")]
+    DedicatedThread {
+        stop: tokio::sync::oneshot::Sender<Duration>,
+        thread: std::thread::JoinHandle<()>,
+    },
+}
+
+impl RuntimeOwner {
+    fn shutdown_timeout(self, timeout: Duration) {
+        match self {
+            RuntimeOwner::Direct(rt) => rt.shutdown_timeout(timeout),
+            #[cfg(feature = "testing-fns")]
+            RuntimeOwner::DedicatedThread { stop, thread } => {
+                // If the thread has already exited, there's nothing left to stop.
+                let _ = stop.send(timeout);
+                if thread.join().is_err() {
+                    log::warn!("async runtime thread panicked");
+                }
+            }
+        }
+    }
+}
+
+/// Keeps track of the tasks started by [`TokioAsyncContext::run_future`].
+#[derive(Default)]
+struct TaskTracker {
+    state: Mutex<TaskState>,
+    /// Notified whenever a task finishes.
+    finished: Condvar,
+}
+
+#[derive(Default)]
+struct TaskState {
+    /// Cancellation senders for tasks that are still running and haven't been cancelled.
+    cancellations: HashMap<CancellationId, tokio::sync::oneshot::Sender<()>>,
+    /// The number of tasks that haven't finished (including reporting their results), whether or
+    /// not they've been cancelled.
+    running: usize,
+    /// Set once [`TokioAsyncContext::shutdown`] is called.
+    closed: bool,
+}
+
+impl TaskTracker {
+    fn lock(&self) -> std::sync::MutexGuard<'_, TaskState> {
+        self.state.lock().expect("task map isn't poisoned")
+    }
+
+    fn finish(&self, cancellation_id: CancellationId) {
+        let mut state = self.lock();
+        state.cancellations.remove(&cancellation_id);
+        state.running -= 1;
+        drop(state);
+        self.finished.notify_all();
+    }
+
+    /// Waits for all running tasks to finish, returning `false` if `deadline` passes first.
+    fn wait_until_idle(&self, deadline: Instant) -> bool {
+        let mut state = self.lock();
+        while state.running != 0 {
+            let Some(remaining) = deadline.checked_duration_since(Instant::now()) else {
+                return false;
+            };
+            state = self
+                .finished
+                .wait_timeout(state, remaining)
+                .expect("task map isn't poisoned")
+                .0;
+        }
+        true
+    }
 }
 
 impl TokioAsyncContext {
     // This is an expensive operation, so we don't want to just use Default.
     #[allow(clippy::new_without_default)]
     pub fn new() -> Self {
-        Self {
-            rt: tokio::runtime::Builder::new_multi_thread()
+        Self::with_runtime(
+            tokio::runtime::Builder::new_multi_thread()
                 .enable_io()
                 .enable_time()
                 .thread_name("libsignal-tokio-worker")
                 .build()
                 .expect("failed to create runtime"),
+        )
+    }
+
+    fn with_runtime(rt: tokio::runtime::Runtime) -> Self {
+        Self::with_owner(rt.handle().clone(), RuntimeOwner::Direct(rt))
+    }
+
+    fn with_owner(handle: tokio::runtime::Handle, owner: RuntimeOwner) -> Self {
+        Self {
+            handle,
+            rt: Mutex::new(Some(owner)),
             tasks: Default::default(),
             next_raw_cancellation_id: AtomicU64::new(1),
+            #[cfg(feature = "testing-fns")]
+            auto_advance_inhibitor: Mutex::new(None),
+        }
+    }
+
+    /// Creates a context whose clock is paused, for testing timeouts without waiting for them.
+    ///
+    /// The clock only moves when the runtime has nothing else to do, at which point it skips ahead
+    /// to the next timer, or when [`Self::advance_time`] is called. The former can be turned off
+    /// with [`Self::set_time_auto_advance`].
+    ///
+    /// Tokio only supports paused time on a single-threaded runtime, so the runtime is driven by a
+    /// dedicated thread rather than a pool of workers.
+    #[cfg(feature = "testing-fns")]
+    pub fn new_with_paused_time() -> Self {
+        let rt = tokio::runtime::Builder::new_current_thread()
+            .enable_io()
+            .enable_time()
+            .start_paused(true)
+            .build()
+            .expect("failed to create runtime");
+        let handle = rt.handle().clone();
+        let (stop, stop_rx) = tokio::sync::oneshot::channel();
+        let thread = std::thread::Builder::new()
+            .name("libsignal-tokio-paused".to_owned())
+            .spawn(move || {
+                // If the context is dropped without being shut down, don't wait for anything.
+                let timeout = rt.block_on(stop_rx).unwrap_or_default();
+                rt.shutdown_timeout(timeout);
+            })
+            .expect("failed to start runtime thread");
+        Self::with_owner(handle, RuntimeOwner::DedicatedThread { stop, thread })
+    }
+
+    /// Controls whether a paused clock skips ahead to the next timer whenever the runtime is idle.
+    ///
+    /// This is on by default. With it off, time only moves on calls to [`Self::advance_time`].
+    ///
+    /// Only meaningful for a context created by [`Self::new_with_paused_time`].
+    #[cfg(feature = "testing-fns")]
+    pub fn set_time_auto_advance(&self, auto_advance: bool) {
+        let mut inhibitor = self.auto_advance_inhibitor.lock().expect("not poisoned");
+        match (auto_advance, inhibitor.is_some()) {
+            (true, _) => {
+                // Dropping the sender ends the blocking task.
+                *inhibitor = None;
+            }
+            (false, true) => {}
+            (false, false) => {
+                // Tokio doesn't auto-advance the clock while a blocking task is running.
+                let (tx, rx) = std::sync::mpsc::channel::<()>();
+                let _: tokio::task::JoinHandle<()> = self.handle.spawn_blocking(move || {
+                    // Returns an error once the sender is dropped.
+                    let _ = rx.recv();
+                });
+                *inhibitor = Some(tx);
+            }
+        }
+    }
+
+    /// Moves a paused clock forward by `duration`, firing any timers that come due.
+    ///
+    /// Blocks until the clock has been advanced.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the context wasn't created by [`Self::new_with_paused_time`], or has been shut
+    /// down.
+    #[cfg(feature = "testing-fns")]
+    pub fn advance_time(&self, duration: Duration) {
+        let (tx, rx) = std::sync::mpsc::channel();
+        let _: tokio::task::JoinHandle<()> = self.handle.spawn(async move {
+            tokio::time::advance(duration).await;
+            // The caller is blocked waiting for this.
+            let _ = tx.send(());
+        });
+        rx.recv()
+            .expect("time can only be advanced on a running context with paused time");
+    }
+
+    /// Stops accepting new work and shuts down the runtime, giving outstanding tasks up to
+    /// `timeout` to finish.
+    ///
+    /// Every outstanding task is cancelled as if by [`AsyncRuntimeBase::cancel`], so that it can
+    /// clean up (closing its connections, for example) and report its result. Whatever is still
+    /// running once `timeout` has elapsed is dropped along with the runtime. Work submitted after
+    /// this is called is rejected with [`RuntimeClosed`].
+    ///
+    /// Calling this more than once is safe; only the first call waits for anything.
+    ///
+    /// This blocks, so it must not be called from a task on this runtime.
+    pub fn shutdown(&self, timeout: Duration) {
+        let deadline = Instant::now() + timeout;
+
+        let cancellations = {
+            let mut state = self.tasks.lock();
+            if state.closed {
+                log::debug!("async runtime is already shut down");
+                return;
+            }
+            state.closed = true;
+            std::mem::take(&mut state.cancellations)
+        };
+        if !cancellations.is_empty() {
+            log::info!(
+                "shutting down async runtime; cancelling {} tasks",
+                cancellations.len()
+            );
+        }
+        // Dropping the senders cancels the tasks.
+        drop(cancellations);
+        // Let timers fire on their own again, so nothing is stuck waiting for time to be advanced.
+        #[cfg(feature = "testing-fns")]
+        self.set_time_auto_advance(true);
+
+        if !self.tasks.wait_until_idle(deadline) {
+            log::warn!("async runtime shutdown timed out with tasks still running");
+        }
+
+        let rt = self.rt.lock().expect("not poisoned").take();
+        if let Some(rt) = rt {
+            rt.shutdown_timeout(deadline.saturating_duration_since(Instant::now()));
         }
     }
 }
@@ -72,11 +292,7 @@ impl AsyncRuntimeBase for TokioAsyncContext {
             log::warn!("ignoring invalid cancellation ID");
             return;
         }
-        let maybe_cancel_tx = self
-            .tasks
-            .lock()
-            .expect("task map isn't poisoned")
-            .remove(&cancellation_token);
+        let maybe_cancel_tx = self.tasks.lock().cancellations.remove(&cancellation_token);
         // Either there's an active task and this will Drop its cancellation Sender,
         // or there's no matching task and this will do nothing.
         // (The explicit drop is to make it clear that this doesn't happen inside the lock.)
@@ -87,6 +303,13 @@ impl AsyncRuntimeBase for TokioAsyncContext {
         }
         drop(maybe_cancel_tx);
     }
+
+    fn check_open(&self) -> Result<(), RuntimeClosed> {
+        if self.tasks.lock().closed {
+            return Err(RuntimeClosed);
+        }
+        Ok(())
+    }
 }
 
 impl<F> AsyncRuntime<F> for TokioAsyncContext
@@ -140,35 +363,43 @@ impl TokioAsyncContext {
                 .fetch_add(1, std::sync::atomic::Ordering::Relaxed),
         );
         debug_assert_ne!(cancellation_id, CancellationId::NotSupported);
-        let previous_cancel_tx = self
-            .tasks
-            .lock()
-            .expect("task map isn't poisoned")
-            .insert(cancellation_id, cancel_tx);
-        debug_assert!(
-            previous_cancel_tx.is_none(),
-            "shouldn't reuse cancellation IDs"
-        );
+        {
+            let mut state = self.tasks.lock();
+            state.running += 1;
+            if state.closed {
+                // Callers are expected to check for this up front with `check_open`, but shutdown
+                // might have started since then. Start the task already cancelled; if the runtime
+                // hasn't been dropped yet, it will still get to report its result.
+                log::warn!("starting task with {cancellation_id:?} after shutdown");
+                drop(cancel_tx);
+            } else {
+                let previous_cancel_tx = state.cancellations.insert(cancellation_id, cancel_tx);
+                debug_assert!(
+                    previous_cancel_tx.is_none(),
+                    "shouldn't reuse cancellation IDs"
+                );
+            }
+        }
 
         let future = make_future(TokioContextCancellation(cancel_rx));
 
-        let handle = self.rt.handle().clone();
+        let handle = self.handle.clone();
         let task_map_weak = Arc::downgrade(&self.tasks);
 
         #[allow(clippy::let_underscore_future)]
-        let _: tokio::task::JoinHandle<()> = self.rt.spawn(async move {
+        let _: tokio::task::JoinHandle<()> = self.handle.spawn(async move {
             let report_fn = future.await;
-            let _: tokio::task::JoinHandle<()> = handle.spawn_blocking(report_fn);
-            // What happens if we don't get here? We leak an entry in the task map. Also, we
-            // probably have bigger problems, because in practice all the `bridge_io` futures are
-            // supposed to be catching panics.
-            if let Some(task_map) = task_map_weak.upgrade() {
-                task_map
-                    .lock()
-                    .expect("task map isn't poisoned")
-                    .remove(&cancellation_id);
-            }
-            log::trace!("completed task with {cancellation_id:?}");
+            let _: tokio::task::JoinHandle<()> = handle.spawn_blocking(move || {
+                report_fn();
+                // What happens if we don't get here? We leak an entry in the task map, and
+                // shutdown will wait for the full timeout. Also, we probably have bigger
+                // problems, because in practice all the `bridge_io` futures are supposed to be
+                // catching panics.
+                if let Some(task_map) = task_map_weak.upgrade() {
+                    task_map.finish(cancellation_id);
+                }
+                log::trace!("completed task with {cancellation_id:?}");
+            });
         });
 
         log::trace!("started task with {cancellation_id:?}");
@@ -249,11 +480,7 @@ mod test {
         let (sum_tx, mut sum_rx, sum_future) = sum_task();
         runtime.spawn(sum_future);
 
-        let async_context = TokioAsyncContext {
-            rt: runtime,
-            tasks: Default::default(),
-            next_raw_cancellation_id: AtomicU64::new(1),
-        };
+        let async_context = TokioAsyncContext::with_runtime(runtime);
 
         let (send_to_task, task_output, when_reporting) = {
             let (sender, receiver) = oneshot::channel();
@@ -313,11 +540,7 @@ mod test {
         runtime_builder.worker_threads(1);
         let runtime = runtime_builder.build().expect("valid runtime");
 
-        let async_context = TokioAsyncContext {
-            rt: runtime,
-            tasks: Default::default(),
-            next_raw_cancellation_id: AtomicU64::new(1),
-        };
+        let async_context = TokioAsyncContext::with_runtime(runtime);
 
         let (on_start_reporting1, mut when_reporting1) = oneshot::channel();
         let cancellation_id1 = async_context.run_future(
@@ -362,4 +585,122 @@ mod test {
         async_context.cancel(cancellation_id1);
         when_reporting1.blocking_recv().expect("completed");
     }
+
+    #[test]
+    fn shutdown_cancels_outstanding_tasks() {
+        let async_context = TokioAsyncContext::new();
+
+        let (on_start_reporting, mut when_reporting) = oneshot::channel();
+        let _cancellation_id = async_context.run_future(
+            |cancel| async move {
+                cancel.await;
+                NotifyingReporter {
+                    on_start_reporting,
+                    reporter: DiscardingReporter,
+                }
+            },
+            (),
+        );
+        assert_matches!(async_context.check_open(), Ok(()));
+
+        const TIMEOUT: Duration = Duration::from_secs(60);
+        let start = Instant::now();
+        async_context.shutdown(TIMEOUT);
+        assert!(start.elapsed() < TIMEOUT);
+
+        // The task observed the cancellation and got to report its result.
+        when_reporting.try_recv().expect("completed");
+        assert_matches!(async_context.check_open(), Err(RuntimeClosed));
+
+        // Shutting down again doesn't wait for anything.
+        async_context.shutdown(TIMEOUT);
+    }
+
+    /// Runs a task that gives up waiting after `timeout`, the way a request to an unresponsive
+    /// server would.
+    ///
+    /// Returns a receiver that's notified when the task reports its result, and where that result
+    /// (whether the task timed out) ends up.
+    #[cfg(feature = "testing-fns")]
+    fn start_task_with_timeout(
+        async_context: &TokioAsyncContext,
+        timeout: Duration,
+    ) -> (oneshot::Receiver<()>, Arc<Mutex<Option<bool>>>) {
+        let (on_start_reporting, when_reporting) = oneshot::channel();
+        let output = Arc::new(Mutex::new(None));
+        let task_output = output.clone();
+        let _cancellation_id = async_context.run_future(
+            |_cancel| async move {
+                let result = tokio::time::timeout(timeout, std::future::pending::<()>()).await;
+                NotifyingReporter {
+                    on_start_reporting,
+                    reporter: (result.is_err(), task_output),
+                }
+            },
+            (),
+        );
+        (when_reporting, output)
+    }
+
+    #[cfg(feature = "testing-fns")]
+    #[test]
+    fn paused_time_advances_only_when_asked() {
+        const SEND_TIMEOUT: Duration = Duration::from_secs(30);
+
+        let async_context = TokioAsyncContext::new_with_paused_time();
+        async_context.set_time_auto_advance(false);
+        let start = Instant::now();
+        let (mut when_reporting, output) = start_task_with_timeout(&async_context, SEND_TIMEOUT);
+
+        async_context.advance_time(SEND_TIMEOUT - Duration::from_secs(1));
+        assert_matches!(
+            when_reporting.try_recv(),
+            Err(oneshot::error::TryRecvError::Empty)
+        );
+
+        async_context.advance_time(Duration::from_secs(1));
+        when_reporting.blocking_recv().expect("completed");
+        assert!(start.elapsed() < SEND_TIMEOUT, "{:?}", start.elapsed());
+
+        async_context.shutdown(Duration::from_secs(1));
+        assert_eq!(*output.lock().expect("not poisoned"), Some(true));
+    }
+
+    #[cfg(feature = "testing-fns")]
+    #[test]
+    fn paused_time_auto_advances_by_default() {
+        const SEND_TIMEOUT: Duration = Duration::from_secs(30);
+
+        let async_context = TokioAsyncContext::new_with_paused_time();
+        let start = Instant::now();
+        let (when_reporting, output) = start_task_with_timeout(&async_context, SEND_TIMEOUT);
+
+        when_reporting.blocking_recv().expect("completed");
+        assert!(start.elapsed() < SEND_TIMEOUT, "{:?}", start.elapsed());
+
+        async_context.shutdown(Duration::from_secs(1));
+        assert_eq!(*output.lock().expect("not poisoned"), Some(true));
+    }
+
+    #[test]
+    fn shutdown_gives_up_after_timeout() {
+        let async_context = TokioAsyncContext::new();
+
+        let (_never_sent, never_received) = oneshot::channel::<()>();
+        let _cancellation_id = async_context.run_future(
+            |_cancel| async move {
+                // Ignores cancellation.
+                let _ = never_received.await;
+                DiscardingReporter
+            },
+            (),
+        );
+
+        const TIMEOUT: Duration = Duration::from_millis(100);
+        let start = Instant::now();
+        async_context.shutdown(TIMEOUT);
+        let elapsed = start.elapsed();
+        assert!(elapsed >= TIMEOUT, "{elapsed:?}");
+        assert!(elapsed < Duration::from_secs(10), "{elapsed:?}");
+    }
 }
@@ -17,6 +17,11 @@ use signal_neon_futures::call_method;
 use crate::net::chat::{ChatListener, MakeChatListener, ServerMessageAck};
 use crate::node::{ResultTypeInfo, SignalNodeError as _};
 
+/// Forwards chat events to a JavaScript object.
+///
+/// Each callback is queued on the JS event loop rather than waited for, since the JS thread may
+/// itself be blocked waiting to hand an event to the chat. The event loop runs queued callbacks
+/// one at a time in the order they were queued, so they're still seen in order.
 #[derive(Clone)]
 pub struct NodeChatListener {
     js_channel: Channel,
@@ -16,8 +16,8 @@ use neon::types::JsBigInt;
 use paste::paste;
 
 use super::*;
-use crate::io::{InputStream, SyncInputStream};
-use crate::message_backup::MessageBackupValidationOutcome;
+use crate::io::{InputStream, OutputStream, SyncInputStream};
+use crate::message_backup::{BackupProgressListener, MessageBackupValidationOutcome};
 use crate::net::chat::{MakeChatListener, ResponseAndDebugInfo};
 use crate::node::chat::NodeMakeChatListener;
 use crate::support::{extend_lifetime, Array, AsType, FixedLengthBincodeSerializable, Serialized};
@@ -649,6 +649,8 @@ bridge_trait!(SessionStore);
 bridge_trait!(SignedPreKeyStore);
 bridge_trait!(KyberPreKeyStore);
 bridge_trait!(InputStream);
+bridge_trait!(OutputStream);
+bridge_trait!(BackupProgressListener);
 
 impl<'storage, 'context: 'storage> ArgTypeInfo<'storage, 'context>
     for &'storage dyn MakeChatListener
@@ -894,15 +896,22 @@ impl<'a> ResultTypeInfo<'a> for MessageBackupValidationOutcome {
 
     fn convert_into(self, cx: &mut impl Context<'a>) -> JsResult<'a, Self::ResultType> {
         let Self {
-            error_message,
+            error,
             found_unknown_fields,
         } = self;
+        let (error_kind, error_message) = error.map(|e| (e.kind as u8, e.message)).unzip();
+        let error_kind = error_kind.convert_into(cx)?;
         let error_message = error_message.convert_into(cx)?;
         let unknown_field_messages = found_unknown_fields.as_slice().convert_into(cx)?;
+        let unknown_fields_json = cx.string(
+            libsignal_message_backup::FoundUnknownField::to_json_array(&found_unknown_fields),
+        );
 
         let obj = JsObject::new(cx);
+        obj.set(cx, "errorKind", error_kind)?;
         obj.set(cx, "errorMessage", error_message)?;
         obj.set(cx, "unknownFieldMessages", unknown_field_messages)?;
+        obj.set(cx, "unknownFieldsJson", unknown_fields_json)?;
 
         Ok(obj)
     }
@@ -980,6 +989,8 @@ impl<'a> ResultTypeInfo<'a> for libsignal_net::chat::DebugInfo {
         let Self {
             ip_type,
             duration,
+            route_type,
+            remote_address,
             connection_info,
         } = self;
         let obj = JsObject::new(cx);
@@ -987,10 +998,18 @@ impl<'a> ResultTypeInfo<'a> for libsignal_net::chat::DebugInfo {
         let ip_type = cx.number(ip_type as u8);
         let duration = cx.number(duration.as_millis().try_into().unwrap_or(u32::MAX));
         let connection_info = cx.string(connection_info);
+        let route_type = cx.number(crate::net::chat::route_type_code(route_type));
 
         obj.set(cx, "ipType", ip_type)?;
         obj.set(cx, "durationMillis", duration)?;
         obj.set(cx, "connectionInfo", connection_info)?;
+        obj.set(cx, "routeType", route_type)?;
+        if let Some(remote_address) = remote_address {
+            let remote_ip = cx.string(remote_address.ip().to_string());
+            let remote_port = cx.number(remote_address.port());
+            obj.set(cx, "remoteIp", remote_ip)?;
+            obj.set(cx, "remotePort", remote_port)?;
+        }
 
         Ok(obj)
     }
@@ -1067,6 +1086,36 @@ impl<'a> ResultTypeInfo<'a> for libsignal_net::cdsi::LookupResponse {
     }
 }
 
+impl<'a> ResultTypeInfo<'a> for crate::net::cdsi::LookupResponseEntries {
+    type ResultType = JsObject;
+    fn convert_into(self, cx: &mut impl Context<'a>) -> JsResult<'a, Self::ResultType> {
+        let Self {
+            entries,
+            debug_permits_used,
+        } = self;
+
+        let entries_array = JsArray::new(cx, entries.len());
+        for (entry, i) in entries.into_iter().zip(0..) {
+            let libsignal_net::cdsi::LookupResponseEntry { e164, aci, pni } = entry;
+            let value = cx.empty_object();
+            let e164 = cx.string(e164.to_string());
+            // Missing IDs are passed as null.
+            let aci = aci.convert_into(cx)?;
+            let pni = pni.convert_into(cx)?;
+            value.set(cx, "e164", e164)?;
+            value.set(cx, "aci", aci)?;
+            value.set(cx, "pni", pni)?;
+            entries_array.set(cx, i, value)?;
+        }
+        let debug_permits_used = JsNumber::new(cx, debug_permits_used);
+
+        let output = JsObject::new(cx);
+        output.set(cx, "entries", entries_array)?;
+        output.set(cx, "debugPermitsUsed", debug_permits_used)?;
+        Ok(output)
+    }
+}
+
 macro_rules! full_range_integer {
     ($typ:ty) => {
         #[doc = "Converts all valid integer values for the type."]
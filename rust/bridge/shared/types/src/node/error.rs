@@ -11,6 +11,7 @@ use signal_media::sanitize::mp4::{Error as Mp4Error, ParseError as Mp4ParseError
 use signal_media::sanitize::webp::{Error as WebpError, ParseError as WebpParseError};
 
 use super::*;
+use crate::error_code::{ErrorCode, SignalErrorCode};
 
 const ERRORS_PROPERTY_NAME: &str = "Errors";
 const ERROR_CLASS_NAME: &str = "LibSignalErrorBase";
@@ -41,6 +42,7 @@ fn new_js_error<'a, C: Context<'a>>(
     cx: &mut C,
     module: Handle<'a, JsObject>,
     name: Option<&str>,
+    code: SignalErrorCode,
     message: &str,
     operation: &str,
     make_extra_props: impl FnOnce(&mut C) -> JsResult<'a, JsValue>,
@@ -59,6 +61,7 @@ fn new_js_error<'a, C: Context<'a>>(
             name_arg,
             cx.string(operation),
             extra_props_arg,
+            cx.number(u32::from(code)),
         );
         error_class.construct_with(cx).args(args).apply(cx)
     });
@@ -121,18 +124,20 @@ impl std::fmt::Display for ThrownException {
 
 impl std::error::Error for ThrownException {}
 
-pub trait SignalNodeError: Sized + fmt::Display {
+pub trait SignalNodeError: ErrorCode + Sized + fmt::Display {
     fn into_throwable<'a, C: Context<'a>>(
         self,
         cx: &mut C,
         module: Handle<'a, JsObject>,
         operation_name: &str,
     ) -> Handle<'a, JsError> {
+        let code = self.error_code();
         let message = self.to_string();
         new_js_error(
             cx,
             module,
             None,
+            code,
             &message,
             operation_name,
             no_extra_properties,
@@ -140,6 +145,8 @@ pub trait SignalNodeError: Sized + fmt::Display {
     }
 }
 
+const ATTESTATION_EXPIRED: &str = "AttestationExpired";
+const ATTESTATION_MEASUREMENT_MISMATCH: &str = "AttestationMeasurementMismatch";
 const INVALID_MEDIA_INPUT: &str = "InvalidMediaInput";
 const IO_ERROR: &str = "IoError";
 const RATE_LIMITED_ERROR: &str = "RateLimitedError";
@@ -156,12 +163,14 @@ impl SignalNodeError for SignalProtocolError {
         module: Handle<'a, JsObject>,
         operation_name: &str,
     ) -> Handle<'a, JsError> {
+        let code = self.error_code();
         let message = self.to_string();
         match self {
             SignalProtocolError::DuplicatedMessage(..) => new_js_error(
                 cx,
                 module,
                 Some("DuplicatedMessage"),
+                code,
                 &message,
                 operation_name,
                 no_extra_properties,
@@ -170,6 +179,7 @@ impl SignalNodeError for SignalProtocolError {
                 cx,
                 module,
                 Some("SealedSenderSelfSend"),
+                code,
                 &message,
                 operation_name,
                 no_extra_properties,
@@ -185,6 +195,7 @@ impl SignalNodeError for SignalProtocolError {
                     cx,
                     module,
                     Some("UntrustedIdentity"),
+                    code,
                     &message,
                     operation_name,
                     make_extra_props,
@@ -201,6 +212,7 @@ impl SignalNodeError for SignalProtocolError {
                     cx,
                     module,
                     Some("InvalidRegistrationId"),
+                    code,
                     &message,
                     operation_name,
                     make_extra_props,
@@ -210,6 +222,7 @@ impl SignalNodeError for SignalProtocolError {
                 cx,
                 module,
                 Some("InvalidSession"),
+                code,
                 &message,
                 operation_name,
                 no_extra_properties,
@@ -226,6 +239,7 @@ impl SignalNodeError for SignalProtocolError {
                     cx,
                     module,
                     Some("InvalidSenderKeySession"),
+                    code,
                     &message,
                     operation_name,
                     make_extra_props,
@@ -235,6 +249,7 @@ impl SignalNodeError for SignalProtocolError {
                 cx,
                 module,
                 None,
+                code,
                 &message,
                 operation_name,
                 no_extra_properties,
@@ -247,7 +262,35 @@ impl SignalNodeError for device_transfer::Error {}
 
 impl SignalNodeError for attest::hsm_enclave::Error {}
 
-impl SignalNodeError for attest::enclave::Error {}
+impl SignalNodeError for attest::enclave::Error {
+    fn into_throwable<'a, C: Context<'a>>(
+        self,
+        cx: &mut C,
+        module: Handle<'a, JsObject>,
+        operation_name: &str,
+    ) -> Handle<'a, JsError> {
+        let name = match self {
+            Self::AttestationExpired { .. } => Some(ATTESTATION_EXPIRED),
+            Self::AttestationMeasurementMismatch => Some(ATTESTATION_MEASUREMENT_MISMATCH),
+            Self::AttestationError(_)
+            | Self::NoiseError(_)
+            | Self::NoiseHandshakeError(_)
+            | Self::AttestationDataError { .. }
+            | Self::InvalidBridgeStateError => None,
+        };
+        let code = self.error_code();
+        let message = self.to_string();
+        new_js_error(
+            cx,
+            module,
+            name,
+            code,
+            &message,
+            operation_name,
+            no_extra_properties,
+        )
+    }
+}
 
 impl SignalNodeError for signal_crypto::Error {}
 
@@ -276,11 +319,13 @@ impl SignalNodeError for usernames::UsernameError {
             Self::BadDiscriminatorCharacter => "BadDiscriminatorCharacter",
             Self::DiscriminatorTooLarge => "DiscriminatorTooLarge",
         };
+        let code = self.error_code();
         let message = self.to_string();
         new_js_error(
             cx,
             module,
             Some(name),
+            code,
             &message,
             operation_name,
             no_extra_properties,
@@ -305,11 +350,13 @@ impl SignalNodeError for usernames::UsernameLinkError {
             | Self::BadCiphertext
             | Self::InvalidDecryptedDataStructure => Some("InvalidUsernameLinkEncryptedData"),
         };
+        let code = self.error_code();
         let message = self.to_string();
         new_js_error(
             cx,
             module,
             name,
+            code,
             &message,
             operation_name,
             no_extra_properties,
@@ -336,11 +383,13 @@ impl SignalNodeError for Mp4Error {
                 | Mp4ParseError::UnsupportedFormat(_) => UNSUPPORTED_MEDIA_INPUT,
             },
         };
+        let code = self.error_code();
         let message = self.to_string();
         new_js_error(
             cx,
             module,
             Some(name),
+            code,
             &message,
             operation_name,
             no_extra_properties,
@@ -368,11 +417,13 @@ impl SignalNodeError for WebpError {
                 }
             },
         };
+        let code = self.error_code();
         let message = self.to_string();
         new_js_error(
             cx,
             module,
             Some(name),
+            code,
             &message,
             operation_name,
             no_extra_properties,
@@ -418,11 +469,13 @@ impl SignalNodeError for libsignal_net::chat::ChatServiceError {
             // TODO: Distinguish retryable errors from proper failures?
             _ => Some(IO_ERROR),
         };
+        let code = self.error_code();
         let message = self.to_string();
         new_js_error(
             cx,
             module,
             name,
+            code,
             &message,
             operation_name,
             no_extra_properties,
@@ -438,11 +491,13 @@ impl SignalNodeError for http::uri::InvalidUri {
         operation_name: &str,
     ) -> Handle<'a, JsError> {
         let name = Some("InvalidUri");
+        let code = self.error_code();
         let message = self.to_string();
         new_js_error(
             cx,
             module,
             name,
+            code,
             &message,
             operation_name,
             no_extra_properties,
@@ -470,7 +525,9 @@ impl SignalNodeError for libsignal_net::cdsi::LookupError {
                 }),
             ),
             Self::AttestationError(e) => return e.into_throwable(cx, module, operation_name),
-            Self::InvalidArgument { server_reason: _ } => (None, None),
+            Self::InvalidArgument { server_reason: _ } | Self::InvalidPrebuiltRequest(_) => {
+                (None, None)
+            }
             Self::InvalidToken => (Some("CdsiInvalidToken"), None),
             Self::ConnectionTimedOut
             | Self::ConnectTransport(_)
@@ -480,11 +537,98 @@ impl SignalNodeError for libsignal_net::cdsi::LookupError {
             | Self::ParseError
             | Self::Server { reason: _ } => (Some(IO_ERROR), None),
         };
+        let code = self.error_code();
+        let message = self.to_string();
+        new_js_error(
+            cx,
+            module,
+            name,
+            code,
+            &message,
+            operation_name,
+            optional_extra_properties(make_extra_props),
+        )
+    }
+}
+
+impl SignalNodeError for libsignal_net::cdn::DownloadError {
+    fn into_throwable<'a, C: Context<'a>>(
+        self,
+        cx: &mut C,
+        module: Handle<'a, JsObject>,
+        operation_name: &str,
+    ) -> Handle<'a, JsError> {
+        let (name, make_extra_props) = match self {
+            Self::RateLimited {
+                retry_after_seconds: Some(retry_after_seconds),
+            } => (
+                Some(RATE_LIMITED_ERROR),
+                Some(move |cx: &mut C| {
+                    let props = cx.empty_object();
+                    let retry_after = retry_after_seconds.convert_into(cx)?;
+                    props.set(cx, "retryAfterSecs", retry_after)?;
+                    Ok(props.upcast())
+                }),
+            ),
+            Self::UnknownCdn(_) | Self::InvalidArgument(_) => (None, None),
+            Self::DigestMismatch => (Some("VerificationFailed"), None),
+            Self::RateLimited {
+                retry_after_seconds: None,
+            }
+            | Self::Connection(_)
+            | Self::ServerError(_)
+            | Self::NotFound
+            | Self::UnexpectedStatus(_)
+            | Self::InvalidResponse(_)
+            | Self::Sink(_) => (Some(IO_ERROR), None),
+        };
+        let code = self.error_code();
+        let message = self.to_string();
+        new_js_error(
+            cx,
+            module,
+            name,
+            code,
+            &message,
+            operation_name,
+            optional_extra_properties(make_extra_props),
+        )
+    }
+}
+
+impl SignalNodeError for libsignal_net::chat::challenge::RateLimitChallengeError {
+    fn into_throwable<'a, C: Context<'a>>(
+        self,
+        cx: &mut C,
+        module: Handle<'a, JsObject>,
+        operation_name: &str,
+    ) -> Handle<'a, JsError> {
+        let (name, make_extra_props) = match self {
+            Self::Chat(e) => return e.into_throwable(cx, module, operation_name),
+            Self::RateLimited {
+                retry_after_seconds: Some(retry_after_seconds),
+            } => (
+                Some(RATE_LIMITED_ERROR),
+                Some(move |cx: &mut C| {
+                    let props = cx.empty_object();
+                    let retry_after = retry_after_seconds.convert_into(cx)?;
+                    props.set(cx, "retryAfterSecs", retry_after)?;
+                    Ok(props.upcast())
+                }),
+            ),
+            Self::Rejected => (Some("VerificationFailed"), None),
+            Self::RateLimited {
+                retry_after_seconds: None,
+            }
+            | Self::UnexpectedStatus(_) => (Some(IO_ERROR), None),
+        };
+        let code = self.error_code();
         let message = self.to_string();
         new_js_error(
             cx,
             module,
             name,
+            code,
             &message,
             operation_name,
             optional_extra_properties(make_extra_props),
@@ -517,15 +661,19 @@ impl SignalNodeError for libsignal_net::svr3::Error {
                 }),
             ),
             Svr3Error::DataMissing => (Some(SVR3_DATA_MISSING), None),
-            Svr3Error::Protocol(_) => (None, None),
+            Svr3Error::Protocol(_)
+            | Svr3Error::CircuitOpen
+            | Svr3Error::PasswordRotationIncomplete { .. } => (None, None),
             Svr3Error::RotationMachineTooManySteps => (Some(SVR3_ROTATION_MACHINE_STEPS), None),
         };
 
+        let code = self.error_code();
         let message = self.to_string();
         new_js_error(
             cx,
             module,
             name,
+            code,
             &message,
             operation_name,
             optional_extra_properties(make_props),
@@ -533,6 +681,12 @@ impl SignalNodeError for libsignal_net::svr3::Error {
     }
 }
 
+impl ErrorCode for CancellationError {
+    fn error_code(&self) -> SignalErrorCode {
+        SignalErrorCode::Cancelled
+    }
+}
+
 impl SignalNodeError for CancellationError {
     fn into_throwable<'a, C: Context<'a>>(
         self,
@@ -540,11 +694,13 @@ impl SignalNodeError for CancellationError {
         module: Handle<'a, JsObject>,
         operation_name: &str,
     ) -> Handle<'a, JsError> {
+        let code = self.error_code();
         let message = self.to_string();
         new_js_error(
             cx,
             module,
             Some("Cancelled"),
+            code,
             &message,
             operation_name,
             no_extra_properties,
@@ -552,6 +708,8 @@ impl SignalNodeError for CancellationError {
     }
 }
 
+impl SignalNodeError for crate::support::RuntimeClosed {}
+
 impl SignalNodeError for libsignal_message_backup::ReadError {
     fn into_throwable<'a, C: Context<'a>>(
         self,
@@ -559,6 +717,7 @@ impl SignalNodeError for libsignal_message_backup::ReadError {
         module: Handle<'a, JsObject>,
         operation_name: &str,
     ) -> Handle<'a, JsError> {
+        let code = self.error_code();
         let libsignal_message_backup::ReadError {
             error,
             found_unknown_fields,
@@ -574,6 +733,7 @@ impl SignalNodeError for libsignal_message_backup::ReadError {
             cx,
             module,
             Some("BackupValidation"),
+            code,
             &message,
             operation_name,
             make_props,
@@ -169,6 +169,9 @@ where
 /// # impl std::fmt::Display for MyError {
 /// #   fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result { unimplemented!() }
 /// # }
+/// # impl libsignal_bridge_types::error_code::ErrorCode for MyError {
+/// #   fn error_code(&self) -> libsignal_bridge_types::error_code::SignalErrorCode { unimplemented!() }
+/// # }
 /// # impl SignalNodeError for MyError {}
 /// # fn test(cx: &mut FunctionContext, async_runtime: &NoOpAsyncRuntime) -> NeonResult<()> {
 /// let js_promise = run_future_on_runtime(cx, async_runtime, "example", |_cancel| async {
@@ -194,6 +197,13 @@ where
     O: for<'a> ResultTypeInfo<'a> + Send + std::panic::UnwindSafe + 'static,
     E: SignalNodeError + Send + 'static,
 {
+    if let Err(closed) = runtime.check_open() {
+        let module = cx
+            .this::<JsObject>()
+            .expect("'this' is the module containing errors, which is a valid object");
+        let throwable = closed.into_throwable(cx, module, node_function_name);
+        return cx.throw(throwable);
+    }
     let (deferred, promise) = cx.promise();
     let completer = PromiseSettler::new(cx, deferred, node_function_name);
     let cancellation_token = runtime.run_future(future, completer);
@@ -12,13 +12,18 @@ use futures_util::TryFutureExt;
 use signal_neon_futures::*;
 
 use super::*;
-use crate::io::{InputStream, InputStreamRead, SyncInputStream};
+use crate::io::{InputStream, InputStreamRead, OutputStream, SyncInputStream};
 
 pub struct NodeInputStream {
     js_channel: Channel,
     stream_object: Arc<Root<JsObject>>,
 }
 
+pub struct NodeOutputStream {
+    js_channel: Channel,
+    stream_object: Arc<Root<JsObject>>,
+}
+
 pub struct NodeSyncInputStream<'a> {
     buffer: AssumedImmutableBuffer<'a>,
     pos: Cell<usize>,
@@ -102,6 +107,76 @@ impl InputStream for NodeInputStream {
     }
 }
 
+impl NodeOutputStream {
+    pub(crate) fn new(cx: &mut FunctionContext, stream: Handle<JsObject>) -> Self {
+        Self {
+            js_channel: cx.channel(),
+            stream_object: Arc::new(stream.root(cx)),
+        }
+    }
+
+    async fn do_write(&self, buf: Vec<u8>) -> Result<(), ThrownException> {
+        let stream_object_shared = self.stream_object.clone();
+        JsFuture::get_promise(&self.js_channel, move |cx| {
+            let stream_object = stream_object_shared.to_inner(cx);
+            let js_buf = buf.as_slice().convert_into(cx)?;
+            let result = call_method(cx, stream_object, "_write", [js_buf.upcast()])?;
+            let result = result.downcast_or_throw(cx)?;
+            stream_object_shared.finalize(cx);
+            Ok(result)
+        })
+        .then(|cx, result| match result {
+            Ok(value) => match value.downcast::<JsUndefined, _>(cx) {
+                Ok(_) => Ok(()),
+                Err(_) => Err("unexpected result from _write".into()),
+            },
+            Err(error) => Err(ThrownException::from_value(cx, error)),
+        })
+        .await
+    }
+
+    async fn do_flush(&self) -> Result<(), ThrownException> {
+        let stream_object_shared = self.stream_object.clone();
+        JsFuture::get_promise(&self.js_channel, move |cx| {
+            let stream_object = stream_object_shared.to_inner(cx);
+            let no_args: [Handle<JsValue>; 0] = [];
+            let result = call_method(cx, stream_object, "_flush", no_args)?;
+            let result = result.downcast_or_throw(cx)?;
+            stream_object_shared.finalize(cx);
+            Ok(result)
+        })
+        .then(|cx, result| match result {
+            Ok(value) => match value.downcast::<JsUndefined, _>(cx) {
+                Ok(_) => Ok(()),
+                Err(_) => Err("unexpected result from _flush".into()),
+            },
+            Err(error) => Err(ThrownException::from_value(cx, error)),
+        })
+        .await
+    }
+}
+
+impl Finalize for NodeOutputStream {
+    fn finalize<'a, C: neon::prelude::Context<'a>>(self, cx: &mut C) {
+        self.stream_object.finalize(cx)
+    }
+}
+
+#[async_trait(?Send)]
+impl OutputStream for NodeOutputStream {
+    async fn write(&self, buf: &[u8]) -> IoResult<()> {
+        self.do_write(buf.to_vec())
+            .await
+            .map_err(|err| IoError::new(IoErrorKind::Other, err))
+    }
+
+    async fn flush(&self) -> IoResult<()> {
+        self.do_flush()
+            .await
+            .map_err(|err| IoError::new(IoErrorKind::Other, err))
+    }
+}
+
 impl<'a> NodeSyncInputStream<'a> {
     pub(crate) fn new(buffer: AssumedImmutableBuffer<'a>) -> Self {
         Self {
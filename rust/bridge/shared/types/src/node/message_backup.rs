@@ -0,0 +1,46 @@
+//
+// Copyright 2024 Signal Messenger, LLC.
+// SPDX-License-Identifier: AGPL-3.0-only
+//
+
+use std::sync::Arc;
+
+use signal_neon_futures::*;
+
+use super::*;
+use crate::message_backup::BackupProgressListener;
+
+pub struct NodeBackupProgressListener {
+    js_channel: Channel,
+    callback_object: Arc<Root<JsObject>>,
+}
+
+impl NodeBackupProgressListener {
+    pub(crate) fn new(cx: &mut FunctionContext, callback_object: Handle<JsObject>) -> Self {
+        Self {
+            js_channel: cx.channel(),
+            callback_object: Arc::new(callback_object.root(cx)),
+        }
+    }
+}
+
+impl Finalize for NodeBackupProgressListener {
+    fn finalize<'a, C: Context<'a>>(self, cx: &mut C) {
+        self.callback_object.finalize(cx)
+    }
+}
+
+impl BackupProgressListener for NodeBackupProgressListener {
+    fn on_progress(&self, frames_read: u64, bytes_read: u64, total_bytes: u64) {
+        // Progress is informational, so there's no need to wait for the callback to run. Calls are
+        // still delivered in order.
+        let callback_object_shared = self.callback_object.clone();
+        self.js_channel.send(move |mut cx| {
+            let callback = callback_object_shared.to_inner(&mut cx);
+            let args = [frames_read, bytes_read, total_bytes].map(|n| cx.number(n as f64).upcast());
+            let _result = call_method(&mut cx, callback, "_on_progress", args)?;
+            callback_object_shared.finalize(&mut cx);
+            Ok(())
+        });
+    }
+}
@@ -41,6 +41,9 @@ pub use futures::*;
 mod io;
 pub use io::*;
 
+mod message_backup;
+pub use message_backup::*;
+
 mod chat;
 mod storage;
 
@@ -272,8 +272,20 @@ pub trait AsyncRuntimeBase {
             log::warn!("this runtime does not support cancellation ({cancellation_token:?})");
         }
     }
+
+    /// Checks whether the runtime is still accepting new work.
+    ///
+    /// Bridges check this before calling [`AsyncRuntime::run_future`], so that they can report
+    /// the failure as an error rather than starting a task that will never run.
+    fn check_open(&self) -> Result<(), RuntimeClosed> {
+        Ok(())
+    }
 }
 
+#[derive(Debug, displaydoc::Display, thiserror::Error)]
+/// async runtime has been shut down
+pub struct RuntimeClosed;
+
 /// Abstracts over executing a future with type `F`.
 ///
 /// Putting the future type in the trait signature allows runtimes to impose additional
@@ -46,14 +46,19 @@ macro_rules! bridge_as_fixed_length_serializable {
 ///
 /// `
 /// `#[bridge_fn] fn FooBar_CheckValidContents`, which checks that the type can be deserialized.
+///
+/// `bridge_fixed_length_serializable_fns!(FooBar, versioned)` additionally checks the leading byte
+/// against [`zkgroup::VersionedSerialization`] first, so that data from a newer version of zkgroup
+/// is reported as such rather than as corrupted.
 #[macro_export]
 macro_rules! bridge_fixed_length_serializable_fns {
-    ($typ:ident) => {
+    ($typ:ident $(, $versioned:ident)?) => {
         ::paste::paste! {
             #[bridge_fn]
             fn [<$typ _CheckValidContents>](
                 buffer: &[u8]
             ) -> Result<(), ZkGroupDeserializationFailure> {
+                $($crate::bridge_fixed_length_serializable_fns!(@$versioned $typ, buffer);)?
                 if buffer.len() != <$typ as FixedLengthBincodeSerializable>::Array::LEN {
                     return Err(ZkGroupDeserializationFailure::new::<$typ>())
                 }
@@ -61,6 +66,9 @@ macro_rules! bridge_fixed_length_serializable_fns {
             }
         }
     };
+    (@versioned $typ:ident, $buffer:ident) => {
+        zkgroup::check_version::<$typ>($buffer)?
+    };
 }
 
 /// Bridges a ZKGroup serializable type via [`FixedLengthBincodeSerializable`].
@@ -7,6 +7,7 @@
 
 //! Types for identifying an individual Signal client instance.
 
+use std::cmp::Ordering;
 use std::fmt;
 
 use uuid::Uuid;
@@ -161,7 +162,12 @@ pub type ServiceIdFixedWidthBinaryBytes = [u8; 17];
 ///
 /// Conceptually this is a UUID in a particular "namespace" representing a particular way to reach a
 /// user on the Signal service.
-#[derive(Clone, Copy, Hash, PartialEq, Eq, PartialOrd, Ord)]
+///
+/// Service IDs are ordered by their [fixed-width binary representation][fixed-width], which is the
+/// canonical order shared with the server (see [`sort_service_ids_canonical`]).
+///
+/// [fixed-width]: ServiceId::service_id_fixed_width_binary
+#[derive(Clone, Copy, Hash, PartialEq, Eq)]
 pub enum ServiceId {
     /// An ACI
     Aci(Aci),
@@ -279,6 +285,36 @@ impl fmt::Debug for ServiceId {
     }
 }
 
+/// Compares the [fixed-width binary representations][fixed-width]: first by kind, with all ACIs
+/// before all PNIs, then by the bytes of the UUID.
+///
+/// This is *not* the order of the [string representations][string], which put PNIs in the middle
+/// of the ACIs.
+///
+/// [fixed-width]: ServiceId::service_id_fixed_width_binary
+/// [string]: ServiceId::service_id_string
+impl Ord for ServiceId {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.service_id_fixed_width_binary()
+            .cmp(&other.service_id_fixed_width_binary())
+    }
+}
+
+impl PartialOrd for ServiceId {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+/// Sorts `ids` into the canonical order for service IDs, as defined by the [`Ord`] implementation
+/// for [`ServiceId`].
+///
+/// Anything that must agree with the server on the order of a list of service IDs, such as group
+/// membership hashing, should sort with this.
+pub fn sort_service_ids_canonical(ids: &mut [ServiceId]) {
+    ids.sort_unstable();
+}
+
 impl From<Aci> for ServiceId {
     #[inline]
     fn from(aci: Aci) -> Self {
@@ -626,6 +662,53 @@ mod service_id_tests {
         assert_eq!(original, ids);
     }
 
+    #[test]
+    fn aci_orders_before_pni_with_same_uuid() {
+        let uuid = uuid::uuid!("8c78cd2a-16ff-427d-83dc-1a5e36ce713d");
+        let aci = ServiceId::from(Aci::from_uuid(uuid));
+        let pni = ServiceId::from(Pni::from_uuid(uuid));
+        assert_eq!(aci.cmp(&pni), Ordering::Less);
+        assert_eq!(pni.cmp(&aci), Ordering::Greater);
+    }
+
+    #[test]
+    fn canonical_sort_of_mixed_list() {
+        let low = uuid::uuid!("0d0c7a3c-0e8a-4a6e-9d1f-4c9b1d3f2a10");
+        let mid = uuid::uuid!("8c78cd2a-16ff-427d-83dc-1a5e36ce713d");
+        let high = uuid::uuid!("ffffffff-0000-4000-8000-000000000000");
+
+        let mut ids: Vec<ServiceId> = vec![
+            Pni::from_uuid(mid).into(),
+            Aci::from_uuid(high).into(),
+            Pni::from_uuid(low).into(),
+            Aci::from_uuid(mid).into(),
+            Aci::from_uuid(low).into(),
+            Pni::from_uuid(high).into(),
+        ];
+        sort_service_ids_canonical(&mut ids);
+
+        let sorted_strings = ids
+            .iter()
+            .map(|id| id.service_id_string())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            sorted_strings,
+            [
+                "0d0c7a3c-0e8a-4a6e-9d1f-4c9b1d3f2a10",
+                "8c78cd2a-16ff-427d-83dc-1a5e36ce713d",
+                "ffffffff-0000-4000-8000-000000000000",
+                "PNI:0d0c7a3c-0e8a-4a6e-9d1f-4c9b1d3f2a10",
+                "PNI:8c78cd2a-16ff-427d-83dc-1a5e36ce713d",
+                "PNI:ffffffff-0000-4000-8000-000000000000",
+            ]
+        );
+
+        // Sorting the strings instead would put the last ACI after the PNIs.
+        let mut by_string = sorted_strings.clone();
+        by_string.sort();
+        assert_ne!(by_string, sorted_strings);
+    }
+
     #[test]
     fn ordering_consistency() {
         proptest!(|(
@@ -8,8 +8,8 @@ mod e164;
 mod version;
 
 pub use address::{
-    Aci, DeviceId, Pni, ProtocolAddress, ServiceId, ServiceIdFixedWidthBinaryBytes, ServiceIdKind,
-    WrongKindOfServiceIdError,
+    sort_service_ids_canonical, Aci, DeviceId, Pni, ProtocolAddress, ServiceId,
+    ServiceIdFixedWidthBinaryBytes, ServiceIdKind, WrongKindOfServiceIdError,
 };
 pub use e164::E164;
 pub use version::VERSION;
@@ -0,0 +1,93 @@
+//
+// Copyright 2024 Signal Messenger, LLC.
+// SPDX-License-Identifier: AGPL-3.0-only
+//
+
+use criterion::{criterion_group, criterion_main, Criterion, Throughput};
+use futures::executor::block_on;
+use futures::io::Cursor;
+use futures::AsyncReadExt as _;
+use libsignal_message_backup::backup::Purpose;
+use libsignal_message_backup::frame::{
+    write_encrypted, CompressionConfig, FileReaderFactory, FramesReader, MmapReaderFactory,
+    ReaderFactory,
+};
+use libsignal_message_backup::key::{BackupKey, MessageBackupKey};
+use libsignal_message_backup::testutil::SyntheticBackupBuilder;
+use libsignal_message_backup::BackupReader;
+
+const PLAINTEXT_LEN: usize = 64 << 20;
+
+fn read_all<R>(key: &MessageBackupKey, factory: impl ReaderFactory<Reader = R>) -> usize
+where
+    R: futures::AsyncRead + mediasan_common::AsyncSkip + Unpin,
+{
+    block_on(async {
+        let mut reader = FramesReader::new(key, factory).await.expect("valid HMAC");
+        let mut contents = Vec::with_capacity(PLAINTEXT_LEN);
+        reader.read_to_end(&mut contents).await.expect("can read")
+    })
+}
+
+pub fn read_backup(c: &mut Criterion) {
+    let backup_key = BackupKey::derive_from_master_key(&[0x42; BackupKey::MASTER_KEY_LEN]);
+    let key = MessageBackupKey::derive(
+        &backup_key,
+        &backup_key.derive_backup_id(&libsignal_core::Aci::from_uuid_bytes([0x11; 16])),
+    );
+
+    // The contents don't need to be valid frames; only the decryption layer is measured.
+    let plaintext = vec![0xAB; PLAINTEXT_LEN];
+    let encrypted = block_on(write_encrypted(
+        &key,
+        &[0; 16],
+        &plaintext,
+        CompressionConfig::None,
+        false,
+    ));
+    let file = tempfile::NamedTempFile::new().expect("can create");
+    std::fs::write(file.path(), &encrypted).expect("can write");
+
+    let mut group = c.benchmark_group("read_backup");
+    group.sample_size(10);
+    group.throughput(Throughput::Bytes(encrypted.len() as u64));
+
+    group.bench_function("file", |b| {
+        b.iter(|| read_all(&key, FileReaderFactory { path: file.path() }))
+    });
+    group.bench_function("mmap", |b| {
+        b.iter(|| {
+            // SAFETY: nothing else touches the temporary file.
+            let factory = unsafe { MmapReaderFactory::open(file.path()) }.expect("can map");
+            read_all(&key, &factory)
+        })
+    });
+    group.finish();
+}
+
+pub fn validate_backup(c: &mut Criterion) {
+    // 100,000 chat items, spread across 1,000 chats.
+    let plaintext = SyntheticBackupBuilder::default()
+        .with_contacts(900)
+        .with_groups(100)
+        .with_chats(1000)
+        .with_items_per_chat(100)
+        .to_plaintext();
+
+    let mut group = c.benchmark_group("validate_backup");
+    group.sample_size(10);
+    group.throughput(Throughput::Bytes(plaintext.len() as u64));
+
+    group.bench_function("read_all", |b| {
+        b.iter(|| {
+            let reader =
+                BackupReader::new_unencrypted(Cursor::new(&plaintext), Purpose::RemoteBackup);
+            block_on(reader.read_all()).result.expect("valid backup")
+        })
+    });
+    group.finish();
+}
+
+criterion_group!(benches, read_backup, validate_backup);
+
+criterion_main!(benches);
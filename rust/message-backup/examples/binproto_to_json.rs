@@ -6,24 +6,28 @@
 use clap::Parser;
 use clap_stdin::FileOrStdin;
 use futures::io::AllowStdIo;
+use libsignal_message_backup::backup::Purpose;
 
 #[derive(Parser)]
-/// Compresses and encrypts an unencrypted backup file.
+/// Validates an unencrypted backup file and prints it as JSON.
 struct CliArgs {
     /// the file to read from, or '-' to read from stdin
     filename: FileOrStdin,
+
+    /// the purpose the backup is intended for
+    #[arg(long, default_value_t=Purpose::RemoteBackup)]
+    purpose: Purpose,
 }
 
 fn main() {
-    let CliArgs { filename } = CliArgs::parse();
+    let CliArgs { filename, purpose } = CliArgs::parse();
 
     eprintln!("reading from {:?}", filename.source);
 
-    let json_array =
-        futures::executor::block_on(libsignal_message_backup::backup::convert_to_json(
-            AllowStdIo::new(filename.into_reader().expect("failed to open")),
-        ))
-        .expect("failed to convert");
-
-    print!("{:#}", serde_json::Value::Array(json_array));
+    futures::executor::block_on(libsignal_message_backup::backup::convert_to_json_streaming(
+        AllowStdIo::new(filename.into_reader().expect("failed to open")),
+        purpose,
+        std::io::stdout().lock(),
+    ))
+    .expect("failed to convert");
 }
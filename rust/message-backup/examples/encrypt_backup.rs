@@ -6,21 +6,14 @@
 use std::fmt::Display;
 use std::io::{stdout, Read as _, Write};
 
-use aes::cipher::block_padding::Pkcs7;
 use aes::cipher::crypto_common::rand_core::{OsRng, RngCore};
-use aes::cipher::{BlockEncryptMut, KeyIvInit};
-use aes::Aes256;
-use async_compression::futures::bufread::GzipEncoder;
 use clap::builder::TypedValueParser;
 use clap::{ArgAction, Parser};
 use clap_stdin::FileOrStdin;
-use futures::io::Cursor;
-use futures::AsyncReadExt;
-use hmac::Mac;
 use libsignal_core::Aci;
 use libsignal_message_backup::args::{parse_aci, parse_hex_bytes};
+use libsignal_message_backup::frame::{write_encrypted, CompressionConfig};
 use libsignal_message_backup::key::{BackupKey, MessageBackupKey};
-use sha2::Sha256;
 
 const DEFAULT_ACI: Aci = Aci::from_uuid_bytes([0x11; 16]);
 const DEFAULT_MASTER_KEY: [u8; 32] = [b'M'; 32];
@@ -53,6 +46,19 @@ struct CliArgs {
     /// pad the compressed output to a bucket boundary before encrypting
     #[arg(long, default_value_t = true, action=ArgAction::Set)]
     pad_bucketed: bool,
+
+    /// gzip compression level, from 1 (fastest) to 9 (smallest)
+    #[arg(
+        long,
+        default_value_t = CompressionConfig::DEFAULT_GZIP_LEVEL,
+        value_parser = clap::value_parser!(u32).range(1..=9),
+        conflicts_with = "no_compression"
+    )]
+    gzip_level: u32,
+
+    /// store the contents without compressing them
+    #[arg(long)]
+    no_compression: bool,
 }
 
 fn main() {
@@ -62,6 +68,8 @@ fn main() {
         aci: WrapCliArg(aci),
         iv,
         pad_bucketed,
+        gzip_level,
+        no_compression,
     } = CliArgs::parse();
 
     let backup_key = BackupKey::derive_from_master_key(&master_key);
@@ -78,25 +86,21 @@ fn main() {
     let contents = read_file(filename);
     eprintln!("read {} bytes", contents.len());
 
-    let mut compressed_contents = gzip_compress(contents);
-    eprintln!("compressed to {} bytes", compressed_contents.len());
-
-    if pad_bucketed {
-        pad_gzipped_bucketed(&mut compressed_contents);
-        eprintln!("padded to {} bytes", compressed_contents.len());
-    }
-
-    let MessageBackupKey { hmac_key, aes_key } = &key;
-
-    write_bytes("IV", iv);
-
-    let encrypted_contents = aes_cbc_encrypt(aes_key, &iv, compressed_contents);
-    eprintln!("encrypted to {} bytes", encrypted_contents.len());
+    let compression = if no_compression {
+        CompressionConfig::None
+    } else {
+        CompressionConfig::Gzip { level: gzip_level }
+    };
 
-    let hmac = hmac_checksum(hmac_key, &iv, &encrypted_contents);
-    write_bytes("encrypted", encrypted_contents);
+    let encrypted = futures::executor::block_on(write_encrypted(
+        &key,
+        &iv,
+        &contents,
+        compression,
+        pad_bucketed,
+    ));
 
-    write_bytes("HMAC", hmac);
+    write_bytes("IV, encrypted, and HMAC", encrypted);
 }
 
 fn read_file(filename: FileOrStdin) -> Vec<u8> {
@@ -110,39 +114,6 @@ fn read_file(filename: FileOrStdin) -> Vec<u8> {
     contents
 }
 
-fn aes_cbc_encrypt(aes_key: &[u8; 32], iv: &[u8; 16], compressed_contents: Vec<u8>) -> Vec<u8> {
-    let encryptor = cbc::Encryptor::<Aes256>::new(aes_key.into(), iv.into());
-
-    encryptor.encrypt_padded_vec_mut::<Pkcs7>(&compressed_contents)
-}
-fn hmac_checksum(hmac_key: &[u8; 32], iv: &[u8], encrypted_contents: &[u8]) -> [u8; 32] {
-    let mut hmac = hmac::Hmac::<Sha256>::new_from_slice(hmac_key).expect("correct key size");
-    hmac.update(iv);
-    hmac.update(encrypted_contents);
-    hmac.finalize().into_bytes().into()
-}
-
-fn gzip_compress(contents: Vec<u8>) -> Vec<u8> {
-    let mut compressed_contents = Vec::new();
-    futures::executor::block_on(
-        GzipEncoder::new(Cursor::new(contents)).read_to_end(&mut compressed_contents),
-    )
-    .expect("failed to compress");
-
-    compressed_contents
-}
-
-fn pad_gzipped_bucketed(out: &mut Vec<u8>) {
-    const BASE: f64 = 1.05;
-    let len = u32::try_from(out.len()).expect("backup < 4GB");
-    let padded_len = {
-        let exp = f64::log(len.into(), BASE).ceil();
-        u32::max(541, BASE.powf(exp).floor() as u32)
-    };
-
-    out.resize(padded_len.try_into().unwrap(), 0);
-}
-
 fn write_bytes(label: &'static str, bytes: impl AsRef<[u8]>) {
     let bytes = bytes.as_ref();
     stdout().write_all(bytes).expect("failed to write");
@@ -3,34 +3,44 @@
 // SPDX-License-Identifier: AGPL-3.0-only
 //
 
-use std::collections::{hash_map, HashMap};
+use std::collections::{hash_map, HashMap, HashSet};
 use std::fmt::Debug;
 use std::sync::Arc;
+use std::time::SystemTime;
 
 use derive_where::derive_where;
 use libsignal_core::Aci;
 
 pub(crate) use crate::backup::account_data::{AccountData, AccountDataError};
-use crate::backup::call::{AdHocCall, CallError};
+use crate::backup::call::{AdHocCall, CallError, CallId};
 use crate::backup::chat::chat_style::{CustomChatColor, CustomColorId};
-use crate::backup::chat::{ChatData, ChatError, ChatItemData, ChatItemError, PinOrder};
+use crate::backup::chat::{
+    ChatData, ChatError, ChatItemData, ChatItemError, ChatItemFingerprint, Direction, PinOrder,
+};
 use crate::backup::frame::{ChatId, RecipientId};
 use crate::backup::method::{Lookup, LookupPair, Method, Store, ValidateOnly};
+use crate::backup::policy::{AllowAll, FramePolicy, FramePolicyFor, PolicyDecision};
 use crate::backup::recipient::{
-    DestinationKind, FullRecipientData, MinimalRecipientData, RecipientError,
+    Destination, DestinationKind, FullRecipientData, MinimalRecipientData, RecipientError,
 };
 use crate::backup::serialize::SerializeOrder;
 use crate::backup::sticker::{PackId as StickerPackId, StickerPack, StickerPackError};
-use crate::backup::time::Timestamp;
+pub use crate::backup::time::Timestamp;
 use crate::proto::backup as proto;
 use crate::proto::backup::frame::Item as FrameItem;
 
 mod account_data;
+pub mod cache;
 mod call;
 mod chat;
+#[cfg(feature = "json")]
+pub mod export;
 mod file;
+pub mod filter;
 mod frame;
 pub(crate) mod method;
+pub mod plan;
+pub mod policy;
 mod recipient;
 pub mod serialize;
 mod sticker;
@@ -81,9 +91,41 @@ pub struct PartialBackup<M: Method + ReferencedTypes> {
     meta: BackupMeta,
     account_data: Option<AccountData<M>>,
     recipients: HashMap<RecipientId, M::RecipientData>,
+    /// The ID of the (unique) [`DestinationKind::Self_`] recipient, once seen.
+    ///
+    /// The Self recipient must precede all chat items, since so many of them
+    /// are only valid relative to it.
+    self_recipient: Option<RecipientId>,
+    /// The ID of the (unique) [`DestinationKind::ReleaseNotes`] recipient, once seen.
+    release_notes_recipient: Option<RecipientId>,
     chats: ChatsData<M>,
     ad_hoc_calls: M::List<AdHocCall<M::RecipientReference>>,
+    call_ids: CallIds,
     sticker_packs: HashMap<StickerPackId, StickerPack<M>>,
+    /// The unknown fields of each frame that had any, to write back out with it.
+    ///
+    /// Only the fields of the frame and of its item are kept; see
+    /// [`CompletedBackup::to_frames`].
+    unknown_fields: M::List<(FrameKey, UnknownFrameFields)>,
+    /// The number of ad hoc calls added so far, to tell them apart in [`Self::unknown_fields`].
+    ad_hoc_calls_count: usize,
+    /// Describes the frame being added, for validation that depends on it.
+    current_frame: FrameMeta,
+    /// Set when the frame being added held a chat item that was too big; see
+    /// [`Self::take_oversized_chat_item`].
+    oversized_chat_item: Option<usize>,
+    /// Every chat item seen so far, to detect items that were exported twice.
+    chat_item_fingerprints: HashSet<(ChatId, ChatItemFingerprint)>,
+    /// If set, a likely duplicate chat item is an error rather than a warning.
+    reject_duplicate_chat_items: bool,
+    /// Set when the frame being added held a likely duplicate chat item; see
+    /// [`Self::take_duplicate_chat_item`].
+    duplicate_chat_item: bool,
+    /// Consulted for each recipient, chat item, and ad-hoc call; see [`Self::with_policy`].
+    policy: Box<dyn FramePolicyFor<M> + Send>,
+    /// What [`Self::policy`] decided about the frame being added; see
+    /// [`Self::take_policy_decision`].
+    policy_decision: PolicyDecision,
 }
 
 #[derive_where(Debug)]
@@ -94,6 +136,7 @@ pub struct CompletedBackup<M: Method + ReferencedTypes> {
     chats: ChatsData<M>,
     ad_hoc_calls: M::List<AdHocCall<M::RecipientReference>>,
     sticker_packs: HashMap<StickerPackId, StickerPack<M>>,
+    unknown_fields: M::List<(FrameKey, UnknownFrameFields)>,
 }
 
 pub type Backup = CompletedBackup<Store>;
@@ -106,6 +149,149 @@ struct ChatsData<M: Method + ReferencedTypes> {
     pub chat_items_count: usize,
 }
 
+/// Identifies the validated data a frame was turned into.
+#[derive(Copy, Clone, Debug, Eq, Hash, PartialEq)]
+enum FrameKey {
+    AccountData,
+    Recipient(RecipientId),
+    Chat(ChatId),
+    /// By [`ChatItemData::total_chat_item_order_index`].
+    ChatItem(usize),
+    StickerPack(StickerPackId),
+    /// By position among the ad hoc calls.
+    AdHocCall(usize),
+}
+
+/// The unknown fields of a [`proto::Frame`] and of its item.
+#[derive(Debug)]
+struct UnknownFrameFields {
+    frame: protobuf::UnknownFields,
+    item: protobuf::UnknownFields,
+}
+
+impl UnknownFrameFields {
+    /// Takes the unknown fields out of `frame` and `item`.
+    fn take(frame: &mut protobuf::SpecialFields, item: &mut FrameItem) -> Self {
+        let item = match item {
+            FrameItem::Account(item) => item.special_fields.mut_unknown_fields(),
+            FrameItem::Recipient(item) => item.special_fields.mut_unknown_fields(),
+            FrameItem::Chat(item) => item.special_fields.mut_unknown_fields(),
+            FrameItem::ChatItem(item) => item.special_fields.mut_unknown_fields(),
+            FrameItem::StickerPack(item) => item.special_fields.mut_unknown_fields(),
+            FrameItem::AdHocCall(item) => item.special_fields.mut_unknown_fields(),
+        };
+        Self {
+            frame: std::mem::take(frame.mut_unknown_fields()),
+            item: std::mem::take(item),
+        }
+    }
+
+    fn is_empty(&self) -> bool {
+        self.frame.iter().next().is_none() && self.item.iter().next().is_none()
+    }
+
+    /// Puts the fields back into a frame rebuilt from validated data.
+    fn restore(&self, frame: &mut proto::Frame) {
+        let Self {
+            frame: frame_unknown,
+            item,
+        } = self;
+        *frame.special_fields.mut_unknown_fields() = frame_unknown.clone();
+        let item_special_fields = match frame.item.as_mut().expect("rebuilt frames have items") {
+            FrameItem::Account(item) => &mut item.special_fields,
+            FrameItem::Recipient(item) => &mut item.special_fields,
+            FrameItem::Chat(item) => &mut item.special_fields,
+            FrameItem::ChatItem(item) => &mut item.special_fields,
+            FrameItem::StickerPack(item) => &mut item.special_fields,
+            FrameItem::AdHocCall(item) => &mut item.special_fields,
+        };
+        *item_special_fields.mut_unknown_fields() = item.clone();
+    }
+}
+
+/// The validated data for one frame of a [`CompletedBackup<Store>`].
+enum FrameSource<'a> {
+    AccountData(&'a AccountData<Store>),
+    Recipient(RecipientId, &'a FullRecipientData),
+    Chat(ChatId, &'a ChatData<Store>),
+    ChatItem(ChatId, &'a ChatItemData<Store>),
+    StickerPack(StickerPackId, &'a StickerPack<Store>),
+    AdHocCall(usize, &'a AdHocCall<FullRecipientData>),
+}
+
+impl FrameSource<'_> {
+    fn to_proto(&self, context: &ProtoContext) -> (FrameKey, FrameItem) {
+        match *self {
+            FrameSource::AccountData(account_data) => (
+                FrameKey::AccountData,
+                FrameItem::Account(account_data.to_proto(context)),
+            ),
+            FrameSource::Recipient(id, data) => (
+                FrameKey::Recipient(id),
+                FrameItem::Recipient(proto::Recipient {
+                    id: id.0,
+                    destination: Some(data.to_proto(context)),
+                    special_fields: Default::default(),
+                }),
+            ),
+            FrameSource::Chat(id, chat) => (
+                FrameKey::Chat(id),
+                FrameItem::Chat(chat.to_proto(id, context)),
+            ),
+            FrameSource::ChatItem(chat_id, item) => (
+                FrameKey::ChatItem(item.total_chat_item_order_index),
+                FrameItem::ChatItem(item.to_proto(chat_id, context)),
+            ),
+            FrameSource::StickerPack(id, pack) => (
+                FrameKey::StickerPack(id),
+                FrameItem::StickerPack(pack.to_proto(&id)),
+            ),
+            FrameSource::AdHocCall(index, call) => (
+                FrameKey::AdHocCall(index),
+                FrameItem::AdHocCall(call.to_proto(context)),
+            ),
+        }
+    }
+}
+
+/// Maps the shared data behind references back to the IDs it was read with.
+///
+/// Validated data refers to recipients and custom chat colors directly rather
+/// than by ID, so this is needed to rebuild protos from it.
+pub(crate) struct ProtoContext {
+    recipient_ids: HashMap<*const Destination<Store>, u64>,
+    custom_color_ids: HashMap<*const CustomChatColor, u64>,
+}
+
+impl ProtoContext {
+    pub(crate) fn recipient_id(&self, recipient: &FullRecipientData) -> u64 {
+        *self
+            .recipient_ids
+            .get(&recipient.as_ptr())
+            .expect("all recipients in the backup are known")
+    }
+
+    pub(crate) fn custom_color_id(&self, color: &Arc<CustomChatColor>) -> u64 {
+        *self
+            .custom_color_ids
+            .get(&Arc::as_ptr(color))
+            .expect("all custom colors in the backup are known")
+    }
+}
+
+/// The [`CallId`]s seen so far, used to reject calls that appear more than once.
+#[derive(Debug, Default)]
+struct CallIds {
+    /// Group calls by the chat they were recorded in.
+    ///
+    /// The same group call legitimately appears in every chat its participants
+    /// share, so only repeats within one chat are rejected.
+    group_calls: HashSet<(ChatId, CallId)>,
+    /// Every call ID in use, and whether it was used by an ad-hoc call (as
+    /// opposed to a group call).
+    is_ad_hoc: HashMap<CallId, bool>,
+}
+
 #[derive(Debug, serde::Serialize)]
 pub struct BackupMeta {
     /// The version of the backup format being parsed.
@@ -117,6 +303,137 @@ pub struct BackupMeta {
     pub backup_time: Timestamp,
     /// What purpose the backup was intended for.
     pub purpose: Purpose,
+    /// Fields in the source [`proto::BackupInfo`] that this version of the
+    /// format doesn't know about.
+    #[serde(skip)]
+    pub unknown_fields: protobuf::UnknownFields,
+    /// Size thresholds for frames holding chat items.
+    #[serde(skip)]
+    pub frame_size_limits: FrameSizeLimits,
+    /// When the backup is being validated.
+    ///
+    /// This is the reference point for [`BackupTimePolicy`].
+    #[serde(skip)]
+    pub validation_time: Timestamp,
+    /// How far [`Self::backup_time`] can be from [`Self::validation_time`].
+    #[serde(skip)]
+    pub backup_time_policy: BackupTimePolicy,
+    /// How much of each chat item revision is kept.
+    ///
+    /// Included in the canonical backup string, since it changes how revisions
+    /// are represented there.
+    pub revision_storage: RevisionStorage,
+}
+
+/// Thresholds for the serialized size of frames holding chat items.
+///
+/// Some transports can't handle frames above a certain size, so chat items
+/// read from frames larger than `warn_above` are reported, and for
+/// [`Purpose::RemoteBackup`] those larger than `max` are rejected.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct FrameSizeLimits {
+    pub warn_above: usize,
+    pub max: usize,
+}
+
+impl Default for FrameSizeLimits {
+    fn default() -> Self {
+        Self {
+            warn_above: 1 << 20,
+            max: 16 << 20,
+        }
+    }
+}
+
+/// How much of each chat item revision is kept once it's been validated.
+///
+/// Every revision is validated in full either way. Messages that were edited
+/// many times can have hundreds of revisions, though, and most consumers only
+/// care about the latest version, so by default only a small summary of each
+/// revision is kept.
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq, serde::Serialize)]
+pub enum RevisionStorage {
+    /// Keep a [`RevisionSummary`](chat::RevisionSummary) for each revision.
+    #[default]
+    Summary,
+    /// Keep each revision as a full [`ChatItemData`](chat::ChatItemData).
+    Full,
+}
+
+/// Bounds on how far [`BackupMeta::backup_time`] can be from
+/// [`BackupMeta::validation_time`].
+///
+/// A backup older than the server would have kept, or one from the future,
+/// indicates a clock problem on one device or the other and confuses
+/// expiration math. For [`Purpose::RemoteBackup`] a backup outside these
+/// bounds is rejected; for [`Purpose::DeviceTransfer`] it's only reported.
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq)]
+pub struct BackupTimePolicy {
+    /// How many days before the validation time a backup can have been made,
+    /// or `None` for no limit.
+    pub max_age_days: Option<u32>,
+}
+
+impl BackupTimePolicy {
+    /// How far after the validation time a backup can have been made, to
+    /// allow for clock skew between devices.
+    pub const MAX_FUTURE: std::time::Duration = std::time::Duration::from_secs(24 * 60 * 60);
+}
+
+#[derive(Clone, Debug, displaydoc::Display, thiserror::Error)]
+pub enum BackupTimeError {
+    /// backup is {age_days} days old, more than the limit of {max_age_days}
+    TooOld { age_days: u64, max_age_days: u32 },
+    /// backup was made {hours_ahead} hours after the validation time
+    InFuture { hours_ahead: u64 },
+}
+
+impl BackupMeta {
+    /// Checks [`Self::backup_time`] against [`Self::backup_time_policy`].
+    pub fn check_backup_time(&self) -> Result<(), BackupTimeError> {
+        const SECONDS_PER_HOUR: u64 = 60 * 60;
+        const SECONDS_PER_DAY: u64 = 24 * SECONDS_PER_HOUR;
+
+        let backup_time = self.backup_time.into_inner();
+        let validation_time = self.validation_time.into_inner();
+        let BackupTimePolicy { max_age_days } = self.backup_time_policy;
+
+        match validation_time.duration_since(backup_time) {
+            Ok(age) => match max_age_days {
+                Some(max_age_days)
+                    if age
+                        > std::time::Duration::from_secs(
+                            u64::from(max_age_days) * SECONDS_PER_DAY,
+                        ) =>
+                {
+                    Err(BackupTimeError::TooOld {
+                        age_days: age.as_secs() / SECONDS_PER_DAY,
+                        max_age_days,
+                    })
+                }
+                _ => Ok(()),
+            },
+            Err(e) => {
+                let ahead = e.duration();
+                if ahead > BackupTimePolicy::MAX_FUTURE {
+                    Err(BackupTimeError::InFuture {
+                        hours_ahead: ahead.as_secs() / SECONDS_PER_HOUR,
+                    })
+                } else {
+                    Ok(())
+                }
+            }
+        }
+    }
+}
+
+/// Information about the frame currently being validated.
+#[derive(Copy, Clone, Debug, Default)]
+pub struct FrameMeta {
+    /// The size of the frame as read from the input, before protobuf decoding.
+    ///
+    /// `None` if the frame wasn't read from serialized input.
+    pub serialized_size: Option<usize>,
 }
 
 #[repr(u8)]
@@ -152,6 +469,8 @@ pub enum Purpose {
 pub enum CompletionError {
     /// no AccountData frames found
     MissingAccountData,
+    /// no Self recipient found
+    NoSelfRecipient,
 }
 
 impl<M: Method + ReferencedTypes> TryFrom<PartialBackup<M>> for CompletedBackup<M> {
@@ -162,12 +481,27 @@ impl<M: Method + ReferencedTypes> TryFrom<PartialBackup<M>> for CompletedBackup<
             meta,
             account_data,
             recipients,
+            self_recipient,
+            release_notes_recipient: _,
             chats,
             ad_hoc_calls,
+            call_ids: _,
             sticker_packs,
+            unknown_fields,
+            ad_hoc_calls_count: _,
+            current_frame: _,
+            oversized_chat_item: _,
+            chat_item_fingerprints: _,
+            reject_duplicate_chat_items: _,
+            duplicate_chat_item: _,
+            policy: _,
+            policy_decision: _,
         } = value;
 
         let account_data = account_data.ok_or(CompletionError::MissingAccountData)?;
+        if self_recipient.is_none() {
+            return Err(CompletionError::NoSelfRecipient);
+        }
 
         Ok(CompletedBackup {
             meta,
@@ -176,16 +510,138 @@ impl<M: Method + ReferencedTypes> TryFrom<PartialBackup<M>> for CompletedBackup<
             chats,
             ad_hoc_calls,
             sticker_packs,
+            unknown_fields,
         })
     }
 }
 
+impl CompletedBackup<Store> {
+    /// Produces a [`proto::BackupInfo`] equivalent to the one this backup was
+    /// read from.
+    pub fn to_backup_info(&self) -> proto::BackupInfo {
+        let BackupMeta {
+            version,
+            backup_time,
+            purpose: _,
+            unknown_fields,
+            frame_size_limits: _,
+            validation_time: _,
+            backup_time_policy: _,
+            revision_storage: _,
+        } = &self.meta;
+
+        let mut info = proto::BackupInfo {
+            version: *version,
+            backupTimeMs: backup_time.as_millis(),
+            special_fields: Default::default(),
+        };
+        *info.special_fields.mut_unknown_fields() = unknown_fields.clone();
+        info
+    }
+
+    /// Rebuilds the frames for this backup from its validated data.
+    ///
+    /// Along with [`Self::to_backup_info`], this is enough to write the backup
+    /// back out, including any changes made to it since it was read. Reading
+    /// the result produces an equivalent backup.
+    ///
+    /// The frames are produced in an order that validates, not necessarily
+    /// the one they were read in: account data, then recipients (with
+    /// distribution lists last, since they refer to other recipients), chats,
+    /// chat items in the order they were read, sticker packs, and ad hoc
+    /// calls. Unknown fields on each frame and on its item are written back
+    /// out; those nested any deeper, and unknown enum values, aren't kept past
+    /// validation.
+    pub fn to_frames(&self) -> impl Iterator<Item = proto::Frame> + '_ {
+        let Self {
+            meta: _,
+            account_data,
+            recipients,
+            chats,
+            ad_hoc_calls,
+            sticker_packs,
+            unknown_fields,
+        } = self;
+
+        let context = ProtoContext {
+            recipient_ids: recipients
+                .iter()
+                .map(|(id, data)| (data.as_ptr(), id.0))
+                .collect(),
+            custom_color_ids: account_data
+                .account_settings
+                .custom_chat_colors
+                .iter()
+                .map(|(id, color)| (Arc::as_ptr(color), id.0))
+                .collect(),
+        };
+        let unknown_fields: HashMap<_, _> = unknown_fields
+            .iter()
+            .map(|(key, fields)| (*key, fields))
+            .collect();
+
+        let mut recipients = recipients.iter().collect::<Vec<_>>();
+        recipients
+            .sort_by_key(|(id, data)| (matches!(***data, Destination::DistributionList(_)), id.0));
+        let mut chats = chats.items.iter().collect::<Vec<_>>();
+        chats.sort_by_key(|(id, _)| id.0);
+        let mut chat_items = chats
+            .iter()
+            .flat_map(|&(&id, chat)| chat.items.iter().map(move |item| (id, item)))
+            .collect::<Vec<_>>();
+        chat_items.sort_by_key(|(_, item)| item.total_chat_item_order_index);
+        let mut sticker_packs = sticker_packs.iter().collect::<Vec<_>>();
+        sticker_packs.sort_by_key(|&(&id, _)| id);
+
+        std::iter::once(FrameSource::AccountData(account_data))
+            .chain(
+                recipients
+                    .into_iter()
+                    .map(|(&id, data)| FrameSource::Recipient(id, data)),
+            )
+            .chain(
+                chats
+                    .into_iter()
+                    .map(|(&id, chat)| FrameSource::Chat(id, chat)),
+            )
+            .chain(
+                chat_items
+                    .into_iter()
+                    .map(|(id, item)| FrameSource::ChatItem(id, item)),
+            )
+            .chain(
+                sticker_packs
+                    .into_iter()
+                    .map(|(&id, pack)| FrameSource::StickerPack(id, pack)),
+            )
+            .chain(
+                ad_hoc_calls
+                    .iter()
+                    .enumerate()
+                    .map(|(index, call)| FrameSource::AdHocCall(index, call)),
+            )
+            .map(move |source| {
+                let (key, item) = source.to_proto(&context);
+                let mut frame = proto::Frame {
+                    item: Some(item),
+                    special_fields: Default::default(),
+                };
+                if let Some(fields) = unknown_fields.get(&key) {
+                    fields.restore(&mut frame);
+                }
+                frame
+            })
+    }
+}
+
 #[derive(Debug, displaydoc::Display, thiserror::Error)]
 pub enum ValidationError {
     /// Frame.item is a oneof but has no value
     EmptyFrame,
     /// multiple AccountData frames found
     MultipleAccountData,
+    /// chat item in {0:?} appeared before the Self recipient
+    ChatItemBeforeSelfRecipient(ChatId),
     /// AccountData error: {0}
     AccountData(#[from] AccountDataError),
     /// {0}
@@ -196,6 +652,59 @@ pub enum ValidationError {
     CallError(#[from] CallFrameError),
     /// {0}
     StickerError(#[from] StickerError),
+    /// BackupInfo.backupTimeMs: {0}
+    BackupTime(#[from] BackupTimeError),
+}
+
+/// A [`ValidationError`] along with where in the backup it was found.
+#[derive(Debug, thiserror::Error)]
+pub struct ValidationFailure {
+    /// The index of the frame that failed to validate, counting the BackupInfo as frame 0.
+    pub frame_index: usize,
+    /// The chat the frame is for, if it held a chat or a chat item.
+    pub chat_id: Option<u64>,
+    /// When the frame's chat item was sent, if it held one.
+    pub chat_item_sent_at: Option<Timestamp>,
+    #[source]
+    pub error: ValidationError,
+}
+
+impl ValidationFailure {
+    /// Returns a function that attaches the position of `frame` to an error found while
+    /// validating it.
+    pub(crate) fn locate(
+        frame_index: usize,
+        frame: &proto::Frame,
+    ) -> impl FnOnce(ValidationError) -> Self {
+        let (chat_id, chat_item_sent_at) = match &frame.item {
+            Some(FrameItem::Chat(chat)) => (Some(chat.id), None),
+            Some(FrameItem::ChatItem(item)) => (
+                Some(item.chatId),
+                Some(Timestamp::from_millis(item.dateSent, "ChatItem.dateSent")),
+            ),
+            _ => (None, None),
+        };
+        move |error| Self {
+            frame_index,
+            chat_id,
+            chat_item_sent_at,
+            error,
+        }
+    }
+}
+
+impl std::fmt::Display for ValidationFailure {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "frame {}", self.frame_index)?;
+        match (self.chat_id, self.chat_item_sent_at) {
+            (Some(chat_id), Some(sent_at)) => {
+                write!(f, " (chat {chat_id}, item sent at {})", sent_at.as_millis())?
+            }
+            (Some(chat_id), None) => write!(f, " (chat {chat_id})")?,
+            (None, _) => {}
+        }
+        write!(f, ": {}", self.error)
+    }
 }
 
 #[derive(Debug, displaydoc::Display, thiserror::Error)]
@@ -343,51 +852,220 @@ impl<M: Method + ReferencedTypes> PartialBackup<M> {
         let proto::BackupInfo {
             version,
             backupTimeMs,
-            special_fields: _,
+            mut special_fields,
         } = value;
 
         let meta = BackupMeta {
             version,
             backup_time: Timestamp::from_millis(backupTimeMs, "BackupInfo.backupTimeMs"),
             purpose,
+            unknown_fields: std::mem::take(special_fields.mut_unknown_fields()),
+            frame_size_limits: FrameSizeLimits::default(),
+            validation_time: Timestamp::now(),
+            backup_time_policy: BackupTimePolicy::default(),
+            revision_storage: RevisionStorage::default(),
         };
 
         Self {
             meta,
             account_data: None,
             recipients: Default::default(),
+            self_recipient: None,
+            release_notes_recipient: None,
             chats: Default::default(),
             ad_hoc_calls: Default::default(),
+            call_ids: Default::default(),
             sticker_packs: HashMap::new(),
+            unknown_fields: Default::default(),
+            ad_hoc_calls_count: 0,
+            current_frame: FrameMeta::default(),
+            oversized_chat_item: None,
+            chat_item_fingerprints: HashSet::new(),
+            reject_duplicate_chat_items: false,
+            duplicate_chat_item: false,
+            policy: Box::new(AllowAll),
+            policy_decision: PolicyDecision::Allow,
+        }
+    }
+
+    pub fn with_frame_size_limits(mut self, frame_size_limits: FrameSizeLimits) -> Self {
+        self.meta.frame_size_limits = frame_size_limits;
+        self
+    }
+
+    /// Overrides the time [`BackupMeta::backup_time`] is checked against,
+    /// which is otherwise the time this was created.
+    pub fn with_validation_time(mut self, validation_time: SystemTime) -> Self {
+        self.meta.validation_time = Timestamp::from_system_time(validation_time);
+        self
+    }
+
+    pub fn with_backup_time_policy(mut self, backup_time_policy: BackupTimePolicy) -> Self {
+        self.meta.backup_time_policy = backup_time_policy;
+        self
+    }
+
+    pub fn with_revision_storage(mut self, revision_storage: RevisionStorage) -> Self {
+        self.meta.revision_storage = revision_storage;
+        self
+    }
+
+    /// Makes a chat item with the same author, timestamp, and kind of message as an earlier
+    /// item in its chat an error.
+    ///
+    /// Otherwise such items are only reported, through [`Self::take_duplicate_chat_item`], since
+    /// the same message really can be sent twice within a millisecond.
+    pub fn with_duplicate_chat_items_rejected(mut self, reject: bool) -> Self {
+        self.reject_duplicate_chat_items = reject;
+        self
+    }
+
+    /// Applies `policy` to each recipient, chat item, and ad-hoc call once it's been validated.
+    pub fn with_policy(mut self, policy: impl FramePolicy + Send + 'static) -> Self {
+        self.policy = Box::new(policy);
+        self
+    }
+
+    /// Checks the backup time against the [`BackupTimePolicy`].
+    ///
+    /// A problem is an error for [`Purpose::RemoteBackup`], but for
+    /// [`Purpose::DeviceTransfer`] it's returned as `Ok(Some(_))` so that it
+    /// can be reported instead.
+    pub fn check_backup_time(&self) -> Result<Option<BackupTimeError>, ValidationError> {
+        match (self.meta.check_backup_time(), self.meta.purpose) {
+            (Ok(()), _) => Ok(None),
+            (Err(e), Purpose::RemoteBackup) => Err(e.into()),
+            (Err(e), Purpose::DeviceTransfer) => Ok(Some(e)),
         }
     }
 
     pub fn add_frame(&mut self, frame: proto::Frame) -> Result<(), ValidationError> {
-        self.add_frame_item(frame.item.ok_or(ValidationError::EmptyFrame)?)
+        self.add_frame_with_meta(frame, FrameMeta::default())
+    }
+
+    /// Like [`Self::add_frame`], but with information about how `frame` was read.
+    pub fn add_frame_with_meta(
+        &mut self,
+        frame: proto::Frame,
+        meta: FrameMeta,
+    ) -> Result<(), ValidationError> {
+        self.start_frame(meta);
+        let proto::Frame {
+            item,
+            mut special_fields,
+        } = frame;
+        let mut item = item.ok_or(ValidationError::EmptyFrame)?;
+        let unknown_fields = UnknownFrameFields::take(&mut special_fields, &mut item);
+
+        let key = self.add_frame_item(item)?;
+        if !unknown_fields.is_empty() {
+            self.unknown_fields.extend([(key, unknown_fields)]);
+        }
+        Ok(())
+    }
+
+    /// Resets the state that describes the frame being added.
+    fn start_frame(&mut self, meta: FrameMeta) {
+        self.current_frame = meta;
+        self.oversized_chat_item = None;
+        self.duplicate_chat_item = false;
+        self.policy_decision = PolicyDecision::Allow;
     }
 
-    fn add_frame_item(&mut self, item: FrameItem) -> Result<(), ValidationError> {
+    /// Returns the serialized size of the most recently added frame if it held
+    /// a chat item and was larger than [`FrameSizeLimits::warn_above`].
+    pub fn take_oversized_chat_item(&mut self) -> Option<usize> {
+        self.oversized_chat_item.take()
+    }
+
+    /// Returns whether the most recently added frame held a chat item that looks like a copy of
+    /// an earlier one.
+    ///
+    /// Such items are only reported here if they aren't rejected; see
+    /// [`Self::with_duplicate_chat_items_rejected`].
+    pub fn take_duplicate_chat_item(&mut self) -> bool {
+        std::mem::take(&mut self.duplicate_chat_item)
+    }
+
+    /// Returns what the [`FramePolicy`] decided about the most recently added
+    /// frame, or [`PolicyDecision::Allow`] if it wasn't consulted.
+    pub fn take_policy_decision(&mut self) -> PolicyDecision {
+        std::mem::take(&mut self.policy_decision)
+    }
+
+    /// Returns the chats whose expiration timer updates so far imply a higher expireTimerVersion
+    /// than the chat declared, in order of chat ID.
+    pub fn expire_timer_version_conflicts(&self) -> Vec<crate::FoundExpireTimerVersionConflict> {
+        let mut conflicts = self
+            .chats
+            .items
+            .iter()
+            .filter(|(_, chat)| chat.has_expire_timer_version_conflict())
+            .map(|(id, chat)| {
+                let history = &chat.expire_timer_history;
+                let (latest_change_sent_at, _) = history
+                    .latest
+                    .expect("conflicts require at least one change");
+                crate::FoundExpireTimerVersionConflict {
+                    chat_id: id.0,
+                    expire_timer_version: chat.expiration_timer_version,
+                    timer_changes: history.changes,
+                    latest_change_sent_at,
+                }
+            })
+            .collect::<Vec<_>>();
+        conflicts.sort_by_key(|conflict| conflict.chat_id);
+        conflicts
+    }
+
+    /// Validates and adds `item`, returning what it was added as.
+    fn add_frame_item(&mut self, item: FrameItem) -> Result<FrameKey, ValidationError> {
         match item {
-            FrameItem::Account(account_data) => self.add_account_data(account_data),
-            FrameItem::Recipient(recipient) => self.add_recipient(recipient).map_err(Into::into),
-            FrameItem::Chat(chat) => self.add_chat(chat).map_err(Into::into),
-            FrameItem::ChatItem(chat_item) => self.add_chat_item(chat_item).map_err(Into::into),
+            FrameItem::Account(account_data) => self
+                .add_account_data(account_data)
+                .map(|()| FrameKey::AccountData),
+            FrameItem::Recipient(recipient) => {
+                let id = recipient.id();
+                self.add_recipient(recipient)?;
+                Ok(FrameKey::Recipient(id))
+            }
+            FrameItem::Chat(chat) => {
+                let id = chat.id();
+                self.add_chat(chat)?;
+                Ok(FrameKey::Chat(id))
+            }
+            FrameItem::ChatItem(chat_item) => {
+                let index = self.chats.chat_items_count;
+                self.add_chat_item(chat_item)?;
+                Ok(FrameKey::ChatItem(index))
+            }
             FrameItem::StickerPack(sticker_pack) => {
-                self.add_sticker_pack(sticker_pack).map_err(Into::into)
+                let id = self.add_sticker_pack(sticker_pack)?;
+                Ok(FrameKey::StickerPack(id))
+            }
+            FrameItem::AdHocCall(call) => {
+                let index = self.ad_hoc_calls_count;
+                self.add_ad_hoc_call(call)?;
+                Ok(FrameKey::AdHocCall(index))
             }
-            FrameItem::AdHocCall(call) => self.add_ad_hoc_call(call).map_err(Into::into),
         }
     }
 
     fn add_ad_hoc_call(&mut self, call: proto::AdHocCall) -> Result<(), CallFrameError> {
         let recipient_id = call.recipientId;
         let call_id = call.callId;
-        let call = call.try_into_with(self).map_err(|error| CallFrameError {
+        let err_with_ids = |error| CallFrameError {
             recipient_id,
             call_id,
             error,
-        })?;
+        };
+        let call: AdHocCall<_> = call.try_into_with(self).map_err(err_with_ids)?;
+        self.call_ids
+            .add_ad_hoc_call(call.id)
+            .map_err(err_with_ids)?;
+        self.policy_decision = self.policy.check_ad_hoc_call(&call);
         self.ad_hoc_calls.extend(Some(call));
+        self.ad_hoc_calls_count += 1;
         Ok(())
     }
 
@@ -407,9 +1085,31 @@ impl<M: Method + ReferencedTypes> PartialBackup<M> {
         let id = recipient.id();
         let err_with_id = |e| RecipientFrameError(id, e);
         let recipient = M::try_convert_recipient(recipient, self).map_err(err_with_id)?;
+        let kind = *recipient.as_ref();
         match self.recipients.entry(id) {
             hash_map::Entry::Occupied(_) => Err(err_with_id(RecipientError::DuplicateRecipient)),
             hash_map::Entry::Vacant(v) => {
+                match kind {
+                    DestinationKind::Self_ => {
+                        if let Some(first) = self.self_recipient {
+                            return Err(err_with_id(RecipientError::MultipleSelf(first, id)));
+                        }
+                        self.self_recipient = Some(id);
+                    }
+                    DestinationKind::ReleaseNotes => {
+                        if let Some(first) = self.release_notes_recipient {
+                            return Err(err_with_id(RecipientError::MultipleReleaseNotes(
+                                first, id,
+                            )));
+                        }
+                        self.release_notes_recipient = Some(id);
+                    }
+                    DestinationKind::Contact
+                    | DestinationKind::Group
+                    | DestinationKind::DistributionList
+                    | DestinationKind::CallLink => {}
+                }
+                self.policy_decision = self.policy.check_recipient(recipient.as_ref());
                 let _ = v.insert(recipient);
                 Ok(())
             }
@@ -430,14 +1130,39 @@ impl<M: Method + ReferencedTypes> PartialBackup<M> {
     fn add_chat_item(&mut self, chat_item: proto::ChatItem) -> Result<(), ValidationError> {
         let chat_id = ChatId(chat_item.chatId);
 
-        let chat_item_data = chat_item
+        if self.self_recipient.is_none() {
+            return Err(ValidationError::ChatItemBeforeSelfRecipient(chat_id));
+        }
+
+        let fingerprint = ChatItemFingerprint::of(&chat_item);
+        let chat_item_data: ChatItemData<M> = chat_item
             .try_into_with(self)
             .map_err(|e: ChatItemError| ChatFrameError(chat_id, e.into()))?;
 
-        Ok(self.chats.add_chat_item(chat_id, chat_item_data)?)
+        self.oversized_chat_item = chat_item_data.oversized_frame_size;
+
+        if let Some(call_id) = chat_item_data.group_call_id() {
+            self.call_ids
+                .add_group_call(chat_id, call_id)
+                .map_err(|e| ChatFrameError(chat_id, ChatItemError::from(e).into()))?;
+        }
+
+        let decision = self.policy.check_chat_item(&chat_item_data);
+        self.chats.add_chat_item(chat_id, chat_item_data)?;
+        if !self.chat_item_fingerprints.insert((chat_id, fingerprint)) {
+            if self.reject_duplicate_chat_items {
+                return Err(ChatFrameError(chat_id, ChatItemError::DuplicateMessage.into()).into());
+            }
+            self.duplicate_chat_item = true;
+        }
+        self.policy_decision = decision;
+        Ok(())
     }
 
-    fn add_sticker_pack(&mut self, sticker_pack: proto::StickerPack) -> Result<(), StickerError> {
+    fn add_sticker_pack(
+        &mut self,
+        sticker_pack: proto::StickerPack,
+    ) -> Result<StickerPackId, StickerError> {
         let id = sticker_pack
             .packId
             .as_slice()
@@ -450,7 +1175,7 @@ impl<M: Method + ReferencedTypes> PartialBackup<M> {
             hash_map::Entry::Occupied(_) => Err(StickerError::DuplicateId(id)),
             hash_map::Entry::Vacant(v) => {
                 v.insert(pack);
-                Ok(())
+                Ok(id)
             }
         }
     }
@@ -491,7 +1216,16 @@ impl<M: Method + ReferencedTypes> ChatsData<M> {
             .get_mut(&chat_id)
             .ok_or(ChatFrameError(chat_id, ChatItemError::NoChatForItem.into()))?;
 
+        if chat_data.is_release_notes && matches!(item.direction, Direction::Outgoing(_)) {
+            return Err(ChatFrameError(
+                chat_id,
+                ChatError::OutgoingMessageInReleaseNotes,
+            ));
+        }
+
         item.total_chat_item_order_index = *chat_items_count;
+        chat_data.media_summary.add_item(&item);
+        chat_data.expire_timer_history.add_item(&item);
 
         chat_data.items.extend([item]);
 
@@ -501,6 +1235,32 @@ impl<M: Method + ReferencedTypes> ChatsData<M> {
     }
 }
 
+impl CallIds {
+    fn add_group_call(&mut self, chat_id: ChatId, call_id: CallId) -> Result<(), CallError> {
+        if self.is_ad_hoc.get(&call_id) == Some(&true) {
+            return Err(CallError::AdHocCallIdCollision(call_id));
+        }
+        if !self.group_calls.insert((chat_id, call_id)) {
+            return Err(CallError::DuplicateCallId(call_id));
+        }
+        self.is_ad_hoc.insert(call_id, false);
+        Ok(())
+    }
+
+    fn add_ad_hoc_call(&mut self, call_id: CallId) -> Result<(), CallError> {
+        match self.is_ad_hoc.entry(call_id) {
+            hash_map::Entry::Occupied(e) if !*e.get() => {
+                Err(CallError::AdHocCallIdCollision(call_id))
+            }
+            hash_map::Entry::Occupied(_) => Ok(()),
+            hash_map::Entry::Vacant(v) => {
+                v.insert(true);
+                Ok(())
+            }
+        }
+    }
+}
+
 impl<M: Method + ReferencedTypes> Lookup<RecipientId, M::RecipientReference> for PartialBackup<M> {
     fn lookup<'a>(&'a self, key: &'a RecipientId) -> Option<&'a M::RecipientReference> {
         self.recipients
@@ -550,6 +1310,12 @@ impl<M: Method + ReferencedTypes> AsRef<BackupMeta> for PartialBackup<M> {
     }
 }
 
+impl<M: Method + ReferencedTypes> AsRef<FrameMeta> for PartialBackup<M> {
+    fn as_ref(&self) -> &FrameMeta {
+        &self.current_frame
+    }
+}
+
 #[cfg(feature = "json")]
 #[derive(Debug, thiserror::Error, displaydoc::Display)]
 pub enum ConvertJsonError {
@@ -565,6 +1331,8 @@ pub enum ConvertJsonError {
     ProtoEncode(#[from] protobuf::Error),
     /// input/output error: {0}
     Io(#[from] std::io::Error),
+    /// invalid backup: {0}
+    Invalid(#[from] crate::Error),
 }
 
 #[cfg(feature = "json")]
@@ -597,6 +1365,14 @@ pub fn convert_from_json(json: Vec<serde_json::Value>) -> Result<Box<[u8]>, Conv
     Ok(serialized.into_boxed_slice())
 }
 
+#[cfg(feature = "json")]
+fn proto_to_json(
+    proto: &impl protobuf::MessageFull,
+) -> Result<serde_json::Value, ConvertJsonError> {
+    let json_proto = protobuf_json_mapping::print_to_string(proto)?;
+    Ok(serde_json::from_str(&json_proto)?)
+}
+
 #[cfg(feature = "json")]
 pub async fn convert_to_json(
     length_delimited_binproto: impl futures::AsyncRead + Unpin,
@@ -604,9 +1380,7 @@ pub async fn convert_to_json(
     fn binary_proto_to_json<M: protobuf::MessageFull>(
         binary: &[u8],
     ) -> Result<serde_json::Value, ConvertJsonError> {
-        let proto = M::parse_from_bytes(binary)?;
-        let json_proto = protobuf_json_mapping::print_to_string(&proto)?;
-        Ok(serde_json::from_str(&json_proto)?)
+        proto_to_json(&M::parse_from_bytes(binary)?)
     }
 
     let mut reader = crate::VarintDelimitedReader::new(length_delimited_binproto);
@@ -624,6 +1398,113 @@ pub async fn convert_to_json(
     Ok(array)
 }
 
+/// Like [`convert_to_json`], but validates the backup and writes each frame to `out` as soon as
+/// it's been validated.
+///
+/// Only what's needed to validate later frames is kept in memory, not the JSON, so this works
+/// for backups too large for [`convert_to_json`]. The output is byte-for-byte the same as
+/// formatting the result of [`convert_to_json`] as a [`serde_json::Value::Array`] with `{:#}`.
+///
+/// If the backup is invalid, the frames before the one that failed will already have been
+/// written, and the array is left unterminated.
+#[cfg(feature = "json")]
+pub async fn convert_to_json_streaming(
+    length_delimited_binproto: impl futures::AsyncRead + Unpin,
+    purpose: Purpose,
+    out: impl std::io::Write,
+) -> Result<(), ConvertJsonError> {
+    use protobuf::Message as _;
+
+    let mut reader = crate::VarintDelimitedReader::new(length_delimited_binproto);
+    let mut array = PrettyJsonArrayWriter::new(out);
+
+    let backup_info = reader
+        .read_next()
+        .await?
+        .ok_or(ConvertJsonError::EmptyArray)?;
+    let backup_info = proto::BackupInfo::parse_from_bytes(&backup_info)?;
+    let backup_info_json = proto_to_json(&backup_info)?;
+
+    crate::check_version(backup_info.version)?;
+    let mut backup = PartialBackup::new_validator(backup_info, purpose);
+    backup
+        .check_backup_time()
+        .map_err(|error| ValidationFailure {
+            frame_index: 0,
+            chat_id: None,
+            chat_item_sent_at: None,
+            error,
+        })
+        .map_err(crate::Error::from)?;
+    array.push(&backup_info_json)?;
+
+    let mut frame_index = 1;
+    while let Some(serialized) = reader.read_next().await? {
+        let frame = proto::Frame::parse_from_bytes(&serialized)?;
+        let frame_json = proto_to_json(&frame)?;
+
+        let locate_failure = ValidationFailure::locate(frame_index, &frame);
+        let meta = FrameMeta {
+            serialized_size: Some(serialized.len()),
+        };
+        backup
+            .add_frame_with_meta(frame, meta)
+            .map_err(locate_failure)
+            .map_err(crate::Error::from)?;
+        array.push(&frame_json)?;
+        frame_index += 1;
+    }
+
+    let _: CompletedBackup<ValidateOnly> = backup.try_into().map_err(crate::Error::from)?;
+    Ok(array.finish()?)
+}
+
+/// Writes a JSON array one element at a time, formatted the way `{:#}` formats a whole
+/// [`serde_json::Value::Array`].
+#[cfg(feature = "json")]
+struct PrettyJsonArrayWriter<W> {
+    out: W,
+    is_empty: bool,
+    /// Scratch space for formatting each element before it's indented.
+    element: Vec<u8>,
+}
+
+#[cfg(feature = "json")]
+impl<W: std::io::Write> PrettyJsonArrayWriter<W> {
+    const INDENT: &'static [u8] = b"  ";
+
+    fn new(out: W) -> Self {
+        Self {
+            out,
+            is_empty: true,
+            element: Vec::new(),
+        }
+    }
+
+    fn push(&mut self, value: &serde_json::Value) -> Result<(), ConvertJsonError> {
+        self.element.clear();
+        serde_json::to_writer_pretty(&mut self.element, value)?;
+
+        self.out
+            .write_all(if self.is_empty { b"[\n" } else { b",\n" })?;
+        self.is_empty = false;
+        // A newline can't appear inside a JSON string, so every one of them starts a new line
+        // that needs another level of indentation.
+        for line in self.element.split_inclusive(|b| *b == b'\n') {
+            self.out.write_all(Self::INDENT)?;
+            self.out.write_all(line)?;
+        }
+        self.out.flush()?;
+        Ok(())
+    }
+
+    fn finish(mut self) -> std::io::Result<()> {
+        self.out
+            .write_all(if self.is_empty { b"[]" } else { b"\n]" })?;
+        self.out.flush()
+    }
+}
+
 struct InvalidAci;
 
 fn uuid_bytes_to_aci(bytes: Vec<u8>) -> Result<Aci, InvalidAci> {
@@ -636,6 +1517,7 @@ fn uuid_bytes_to_aci(bytes: Vec<u8>) -> Result<Aci, InvalidAci> {
 #[cfg(test)]
 mod test {
     use assert_matches::assert_matches;
+    use protobuf::Message as _;
     use test_case::{test_case, test_matrix};
 
     use super::*;
@@ -658,6 +1540,29 @@ mod test {
                 ..Self::test_data()
             }
         }
+
+        fn test_data_group_call(chat_id: u64, call_id: u64) -> Self {
+            Self {
+                chatId: chat_id,
+                authorId: proto::Recipient::TEST_ID,
+                item: Some(proto::chat_item::Item::UpdateMessage(
+                    proto::ChatUpdateMessage {
+                        update: Some(proto::chat_update_message::Update::GroupCall(
+                            proto::GroupCall {
+                                callId: Some(call_id),
+                                ..proto::GroupCall::test_data()
+                            },
+                        )),
+                        ..Default::default()
+                    },
+                )),
+                directionalDetails: Some(proto::chat_item::DirectionalDetails::Directionless(
+                    Default::default(),
+                )),
+                dateSent: proto::ChatItem::test_data().dateSent,
+                ..Default::default()
+            }
+        }
     }
 
     trait TestPartialBackupMethod: Method + ReferencedTypes + Sized {
@@ -737,6 +1642,378 @@ mod test {
         );
     }
 
+    #[test_case(ValidateOnly::fake())]
+    #[test_case(Store::fake())]
+    fn rejects_multiple_self_recipients<M: Method + ReferencedTypes>(
+        mut partial: PartialBackup<M>,
+    ) {
+        let second_self = proto::Recipient {
+            id: proto::Recipient::TEST_ID + 1,
+            ..proto::Recipient::test_data()
+        };
+        assert_matches!(
+            partial.add_recipient(second_self),
+            Err(RecipientFrameError(id, RecipientError::MultipleSelf(first, second)))
+            if id.0 == proto::Recipient::TEST_ID + 1
+                && first.0 == proto::Recipient::TEST_ID
+                && second == id
+        );
+    }
+
+    fn release_notes_recipient(id: u64) -> proto::Recipient {
+        proto::Recipient {
+            id,
+            destination: Some(proto::recipient::Destination::ReleaseNotes(
+                Default::default(),
+            )),
+            ..Default::default()
+        }
+    }
+
+    const TEST_RELEASE_NOTES_ID: u64 = 0x4E7E5;
+
+    #[test_case(ValidateOnly::fake())]
+    #[test_case(Store::fake())]
+    fn rejects_multiple_release_notes_recipients<M: Method + ReferencedTypes>(
+        mut partial: PartialBackup<M>,
+    ) {
+        partial
+            .add_recipient(release_notes_recipient(TEST_RELEASE_NOTES_ID))
+            .expect("accepts first");
+        assert_matches!(
+            partial.add_recipient(release_notes_recipient(TEST_RELEASE_NOTES_ID + 1)),
+            Err(RecipientFrameError(id, RecipientError::MultipleReleaseNotes(first, second)))
+            if id.0 == TEST_RELEASE_NOTES_ID + 1
+                && first.0 == TEST_RELEASE_NOTES_ID
+                && second == id
+        );
+    }
+
+    #[test_case(ValidateOnly::fake())]
+    #[test_case(Store::fake())]
+    fn rejects_outgoing_chat_item_in_release_notes_chat<M: Method + ReferencedTypes>(
+        mut partial: PartialBackup<M>,
+    ) {
+        const CHAT_ID: u64 = proto::Chat::TEST_ID + 1;
+        partial
+            .add_recipient(release_notes_recipient(TEST_RELEASE_NOTES_ID))
+            .expect("valid recipient");
+        partial
+            .add_chat(proto::Chat {
+                id: CHAT_ID,
+                recipientId: TEST_RELEASE_NOTES_ID,
+                ..proto::Chat::test_data()
+            })
+            .expect("valid chat");
+
+        let incoming = proto::ChatItem {
+            chatId: CHAT_ID,
+            authorId: TEST_RELEASE_NOTES_ID,
+            ..proto::ChatItem::test_data()
+        };
+        partial
+            .add_chat_item(incoming.clone())
+            .expect("incoming messages are allowed");
+
+        let outgoing = proto::ChatItem {
+            authorId: proto::Recipient::TEST_ID,
+            directionalDetails: Some(proto::chat_item::OutgoingMessageDetails::default().into()),
+            dateSent: incoming.dateSent + 1,
+            ..incoming
+        };
+        assert_matches!(
+            partial.add_chat_item(outgoing),
+            Err(ValidationError::ChatError(ChatFrameError(
+                ChatId(CHAT_ID),
+                ChatError::OutgoingMessageInReleaseNotes
+            )))
+        );
+    }
+
+    #[test_case(ValidateOnly::empty())]
+    #[test_case(Store::empty())]
+    fn rejects_chat_item_before_self_recipient<M: Method + ReferencedTypes>(
+        mut partial: PartialBackup<M>,
+    ) {
+        partial
+            .add_recipient(proto::Recipient::test_data_contact())
+            .expect("valid recipient");
+        partial
+            .add_chat(proto::Chat {
+                recipientId: proto::Recipient::test_data_contact().id,
+                ..proto::Chat::test_data()
+            })
+            .expect("valid chat");
+
+        assert_matches!(
+            partial.add_chat_item(proto::ChatItem::test_data()),
+            Err(ValidationError::ChatItemBeforeSelfRecipient(id))
+            if id == ChatId(proto::Chat::TEST_ID)
+        );
+    }
+
+    const TEST_CALL_ID: u64 = 2468;
+
+    /// Adds a self recipient, a call link, and two chats with IDs 1 and 2.
+    fn add_two_chats<M: Method + ReferencedTypes>(partial: &mut PartialBackup<M>) {
+        let call_link_recipient = proto::Recipient {
+            id: crate::backup::call::test::TEST_CALL_LINK_RECIPIENT_ID.0,
+            destination: Some(proto::recipient::Destination::CallLink(
+                proto::CallLink::test_data(),
+            )),
+            ..Default::default()
+        };
+        let frames: [FrameItem; 4] = [
+            proto::Recipient::test_data().into(),
+            call_link_recipient.into(),
+            proto::Chat {
+                id: 1,
+                ..proto::Chat::test_data()
+            }
+            .into(),
+            proto::Chat {
+                id: 2,
+                ..proto::Chat::test_data()
+            }
+            .into(),
+        ];
+        for frame in frames {
+            partial.add_frame_item(frame).expect("can add one");
+        }
+    }
+
+    #[test_case(ValidateOnly::empty())]
+    #[test_case(Store::empty())]
+    fn allows_same_group_call_id_in_different_chats<M: Method + ReferencedTypes>(
+        mut partial: PartialBackup<M>,
+    ) {
+        add_two_chats(&mut partial);
+        for chat_id in [1, 2] {
+            partial
+                .add_chat_item(proto::ChatItem::test_data_group_call(chat_id, TEST_CALL_ID))
+                .expect("valid group call");
+        }
+        partial
+            .add_chat_item(proto::ChatItem::test_data_group_call(1, TEST_CALL_ID + 1))
+            .expect("different call ID");
+    }
+
+    #[test_case(ValidateOnly::empty())]
+    #[test_case(Store::empty())]
+    fn rejects_duplicate_group_call_id_in_chat<M: Method + ReferencedTypes>(
+        mut partial: PartialBackup<M>,
+    ) {
+        add_two_chats(&mut partial);
+        partial
+            .add_chat_item(proto::ChatItem::test_data_group_call(1, TEST_CALL_ID))
+            .expect("valid group call");
+
+        assert_matches!(
+            partial.add_chat_item(proto::ChatItem::test_data_group_call(1, TEST_CALL_ID)),
+            Err(ValidationError::ChatError(ChatFrameError(
+                ChatId(1),
+                ChatError::ChatItem(ChatItemError::Call(CallError::DuplicateCallId(_)))
+            )))
+        );
+    }
+
+    #[test_case(true; "group call first")]
+    #[test_case(false; "ad hoc call first")]
+    fn rejects_ad_hoc_call_id_used_by_group_call(group_call_first: bool) {
+        let mut partial = ValidateOnly::empty();
+        add_two_chats(&mut partial);
+        let ad_hoc_call = proto::AdHocCall {
+            callId: TEST_CALL_ID,
+            ..proto::AdHocCall::test_data()
+        };
+        let group_call = proto::ChatItem::test_data_group_call(1, TEST_CALL_ID);
+
+        let err = if group_call_first {
+            partial.add_chat_item(group_call).expect("valid group call");
+            partial
+                .add_ad_hoc_call(ad_hoc_call)
+                .map_err(|e| e.error)
+                .unwrap_err()
+        } else {
+            partial
+                .add_ad_hoc_call(ad_hoc_call)
+                .expect("valid ad hoc call");
+            assert_matches!(
+                partial.add_chat_item(group_call),
+                Err(ValidationError::ChatError(ChatFrameError(
+                    _,
+                    ChatError::ChatItem(ChatItemError::Call(e))
+                ))) => e
+            )
+        };
+        assert_matches!(err, CallError::AdHocCallIdCollision(_));
+    }
+
+    #[test_case(ValidateOnly::empty())]
+    #[test_case(Store::empty())]
+    fn completion_requires_self_recipient<M: Method + ReferencedTypes>(
+        mut partial: PartialBackup<M>,
+    ) {
+        partial
+            .add_account_data(proto::AccountData::test_data())
+            .expect("valid account data");
+
+        assert_matches!(
+            CompletedBackup::try_from(partial),
+            Err(CompletionError::NoSelfRecipient)
+        );
+    }
+
+    const UNKNOWN_FIELD_NUMBER: u32 = 9999;
+
+    /// A backup that contains one of each kind of frame.
+    fn one_of_each_frame() -> Vec<proto::Frame> {
+        let call_link_recipient = proto::Recipient {
+            id: crate::backup::call::test::TEST_CALL_LINK_RECIPIENT_ID.0,
+            destination: Some(proto::recipient::Destination::CallLink(
+                proto::CallLink::test_data(),
+            )),
+            ..Default::default()
+        };
+        [
+            FrameItem::from(proto::AccountData::test_data()),
+            proto::Recipient::test_data().into(),
+            proto::Recipient::test_data_contact().into(),
+            call_link_recipient.into(),
+            proto::Chat::test_data().into(),
+            proto::ChatItem::test_data().into(),
+            proto::StickerPack::test_data().into(),
+            proto::AdHocCall::test_data().into(),
+        ]
+        .into_iter()
+        .map(|item| proto::Frame {
+            item: Some(item),
+            ..Default::default()
+        })
+        .collect()
+    }
+
+    fn add_unknown_field(item: &mut FrameItem) {
+        let unknown_fields = match item {
+            FrameItem::Account(item) => item.special_fields.mut_unknown_fields(),
+            FrameItem::Recipient(item) => item.special_fields.mut_unknown_fields(),
+            FrameItem::Chat(item) => item.special_fields.mut_unknown_fields(),
+            FrameItem::ChatItem(item) => item.special_fields.mut_unknown_fields(),
+            FrameItem::StickerPack(item) => item.special_fields.mut_unknown_fields(),
+            FrameItem::AdHocCall(item) => item.special_fields.mut_unknown_fields(),
+        };
+        unknown_fields.add_varint(UNKNOWN_FIELD_NUMBER, 1);
+    }
+
+    fn store_all(frames: impl IntoIterator<Item = proto::Frame>) -> Backup {
+        let mut partial = Store::empty();
+        for frame in frames {
+            partial.add_frame(frame).expect("valid frame");
+        }
+        partial.try_into().expect("valid completed backup")
+    }
+
+    #[test_case(0; "account data")]
+    #[test_case(1; "self recipient")]
+    #[test_case(2; "contact recipient")]
+    #[test_case(3; "call link recipient")]
+    #[test_case(4; "chat")]
+    #[test_case(5; "chat item")]
+    #[test_case(6; "sticker pack")]
+    #[test_case(7; "ad hoc call")]
+    fn to_frames_round_trips_unknown_fields(index: usize) {
+        use crate::unknown::VisitUnknownFieldsExt as _;
+
+        let mut frames = one_of_each_frame();
+        add_unknown_field(frames[index].item.as_mut().expect("has item"));
+        frames[index]
+            .special_fields
+            .mut_unknown_fields()
+            .add_varint(UNKNOWN_FIELD_NUMBER, 2);
+
+        let written = store_all(frames.clone()).to_frames().collect::<Vec<_>>();
+        assert_eq!(written.len(), frames.len());
+
+        // The frames are rebuilt in their own order, so look for the one with unknown fields.
+        let found = written
+            .iter()
+            .map(|frame| frame.collect_unknown_fields())
+            .filter(|found| !found.is_empty())
+            .collect::<Vec<_>>();
+        assert_eq!(found.len(), 1, "{found:?}");
+        assert_eq!(found[0].len(), 2, "{found:?}");
+        assert_eq!(found[0], frames[index].collect_unknown_fields());
+
+        let reread = store_all(written.clone());
+        assert_eq!(
+            serde_json::to_string(&serialize::Backup::from(reread)).expect("can serialize"),
+            serde_json::to_string(&serialize::Backup::from(store_all(frames)))
+                .expect("can serialize"),
+        );
+    }
+
+    #[test]
+    fn to_frames_reflects_changes_to_validated_data() {
+        let mut backup = store_all(one_of_each_frame());
+        backup.account_data.given_name = "Changed".to_owned();
+        for chat in backup.chats.items.values_mut() {
+            assert!(!chat.archived);
+            chat.archived = true;
+        }
+
+        let written = backup.to_frames().collect::<Vec<_>>();
+        let account_data = written
+            .iter()
+            .find_map(|frame| match &frame.item {
+                Some(FrameItem::Account(account_data)) => Some(account_data),
+                _ => None,
+            })
+            .expect("has account data");
+        assert_eq!(account_data.givenName, "Changed");
+        let chats = written
+            .iter()
+            .filter_map(|frame| match &frame.item {
+                Some(FrameItem::Chat(chat)) => Some(chat),
+                _ => None,
+            })
+            .collect::<Vec<_>>();
+        assert!(!chats.is_empty());
+        assert!(chats.iter().all(|chat| chat.archived));
+
+        let reread = store_all(written);
+        assert_eq!(reread.account_data.given_name, "Changed");
+        assert!(reread.chats.items.values().all(|chat| chat.archived));
+    }
+
+    #[test]
+    fn to_frames_is_stable() {
+        let written = store_all(one_of_each_frame())
+            .to_frames()
+            .collect::<Vec<_>>();
+        let rewritten = store_all(written.clone()).to_frames().collect::<Vec<_>>();
+        assert_eq!(rewritten, written);
+    }
+
+    #[test]
+    fn to_backup_info_round_trips_unknown_fields() {
+        let mut info = proto::BackupInfo {
+            version: 1,
+            backupTimeMs: 1715636551000,
+            ..Default::default()
+        };
+        info.special_fields
+            .mut_unknown_fields()
+            .add_varint(UNKNOWN_FIELD_NUMBER, 1);
+
+        let mut partial = PartialBackup::new_store(info.clone(), Purpose::RemoteBackup);
+        for frame in one_of_each_frame() {
+            partial.add_frame(frame).expect("valid frame");
+        }
+        let backup = CompletedBackup::try_from(partial).expect("valid completed backup");
+
+        assert_eq!(backup.to_backup_info(), info);
+    }
+
     #[test]
     fn chat_item_order() {
         let mut partial = Store::empty();
@@ -795,4 +2072,404 @@ mod test {
             HashMap::from([(ChatId(1), vec![0, 2, 4]), (ChatId(2), vec![1, 3, 5])])
         );
     }
+
+    #[test]
+    fn media_summary() {
+        use crate::backup::chat::MediaSummary;
+        use crate::backup::time::testutil::MillisecondsSinceEpoch;
+
+        fn attachment(content_type: &str, size: u32) -> proto::MessageAttachment {
+            proto::MessageAttachment {
+                pointer: Some(proto::FilePointer {
+                    contentType: Some(content_type.into()),
+                    locator: Some(proto::file_pointer::Locator::AttachmentLocator(
+                        proto::file_pointer::AttachmentLocator {
+                            cdnKey: "ABCDEFG".into(),
+                            cdnNumber: 3,
+                            uploadTimestamp: MillisecondsSinceEpoch::TEST_VALUE.0,
+                            key: vec![0x12, 0x34],
+                            digest: vec![0x56, 0x78],
+                            size,
+                            special_fields: Default::default(),
+                        },
+                    )),
+                    ..proto::FilePointer::minimal_test_data()
+                })
+                .into(),
+                ..proto::MessageAttachment::test_data()
+            }
+        }
+
+        let mut partial = Store::empty();
+        partial
+            .add_account_data(proto::AccountData::test_data())
+            .expect("valid account data");
+        partial
+            .add_recipient(proto::Recipient::test_data())
+            .expect("valid recipient");
+        partial
+            .add_recipient(proto::Recipient::test_data_contact())
+            .expect("valid recipient");
+        partial
+            .add_chat(proto::Chat::test_data())
+            .expect("valid chat");
+
+        // Includes a quote with a thumbnail, which isn't counted.
+        let with_attachments = proto::StandardMessage {
+            attachments: vec![
+                attachment("image/jpeg", 1_000),
+                attachment("video/mp4", 20_000),
+                attachment("application/pdf", 300),
+            ],
+            ..proto::StandardMessage::test_data()
+        };
+        // Previous versions of the message aren't counted either.
+        let revision = proto::ChatItem {
+            item: Some(proto::chat_item::Item::StandardMessage(
+                with_attachments.clone(),
+            )),
+            ..proto::ChatItem::test_data()
+        };
+        let voice_message = proto::StandardMessage {
+            attachments: vec![proto::MessageAttachment {
+                flag: proto::message_attachment::Flag::VOICE_MESSAGE.into(),
+                ..attachment("audio/aac", 4_000)
+            }],
+            ..proto::StandardMessage::test_voice_message_data()
+        };
+        let view_once = proto::ViewOnceMessage {
+            attachment: Some(attachment("image/jpeg", 50_000)).into(),
+            ..Default::default()
+        };
+
+        for (item, revisions) in [
+            (
+                proto::chat_item::Item::StandardMessage(with_attachments),
+                vec![revision],
+            ),
+            (
+                proto::chat_item::Item::StandardMessage(voice_message),
+                vec![],
+            ),
+            (proto::chat_item::Item::ViewOnceMessage(view_once), vec![]),
+        ] {
+            partial
+                .add_chat_item(proto::ChatItem {
+                    item: Some(item),
+                    revisions,
+                    ..proto::ChatItem::test_data()
+                })
+                .expect("valid chat item");
+        }
+
+        let mut chats = CompletedBackup::try_from(partial)
+            .expect("valid completed backup")
+            .chats
+            .items;
+        let chat = chats
+            .remove(&ChatId(proto::Chat::TEST_ID))
+            .expect("chat exists");
+
+        assert_eq!(
+            chat.media_summary,
+            MediaSummary {
+                images: 1,
+                videos: 1,
+                audio: 1,
+                files: 1,
+                view_once: 1,
+                total_size: 75_300,
+            }
+        );
+    }
+
+    fn chat_item_frame_with_text_len(text_len: usize) -> proto::Frame {
+        let mut item = proto::ChatItem::test_data();
+        let Some(proto::chat_item::Item::StandardMessage(message)) = &mut item.item else {
+            unreachable!("test data is a standard message");
+        };
+        message.text.mut_or_insert_default().body = "x".repeat(text_len);
+        proto::Frame {
+            item: Some(item.into()),
+            ..Default::default()
+        }
+    }
+
+    #[test_case(Purpose::RemoteBackup, 100 => Ok(false); "small")]
+    #[test_case(Purpose::RemoteBackup, 2 << 20 => Ok(true); "above warning threshold")]
+    #[test_case(Purpose::RemoteBackup, 17 << 20 => Err(()); "above max")]
+    #[test_case(Purpose::DeviceTransfer, 17 << 20 => Ok(true); "above max for transfer")]
+    fn chat_item_frame_size(purpose: Purpose, text_len: usize) -> Result<bool, ()> {
+        let mut partial = PartialBackup::<Store>::new(proto::BackupInfo::new(), purpose);
+        for item in [
+            proto::Recipient::test_data().into(),
+            proto::Chat::test_data().into(),
+            proto::Recipient::test_data_contact().into(),
+        ] {
+            partial.add_frame_item(item).expect("valid frame");
+        }
+
+        let frame = chat_item_frame_with_text_len(text_len);
+        let size = frame.compute_size().try_into().expect("fits in usize");
+        let meta = FrameMeta {
+            serialized_size: Some(size),
+        };
+
+        match partial.add_frame_with_meta(frame, meta) {
+            Ok(()) => {
+                let oversized = partial.take_oversized_chat_item();
+                if let Some(oversized) = oversized {
+                    assert_eq!(oversized, size);
+                }
+                assert_eq!(partial.take_oversized_chat_item(), None);
+                Ok(oversized.is_some())
+            }
+            Err(e) => {
+                assert_matches!(
+                    e,
+                    ValidationError::ChatError(ChatFrameError(
+                        _,
+                        ChatError::ChatItem(ChatItemError::FrameTooLarge(actual, max))
+                    )) if actual == size && max == FrameSizeLimits::default().max
+                );
+                Err(())
+            }
+        }
+    }
+
+    #[test]
+    fn chat_item_frame_size_custom_limits() {
+        let mut partial = Store::fake().with_frame_size_limits(FrameSizeLimits {
+            warn_above: 10,
+            max: 20,
+        });
+
+        let meta = |size| FrameMeta {
+            serialized_size: Some(size),
+        };
+        let frame = || proto::Frame {
+            item: Some(proto::ChatItem::test_data().into()),
+            ..Default::default()
+        };
+
+        partial
+            .add_frame_with_meta(frame(), meta(10))
+            .expect("valid");
+        assert_eq!(partial.take_oversized_chat_item(), None);
+
+        partial
+            .add_frame_with_meta(frame(), meta(11))
+            .expect("valid");
+        assert_eq!(partial.take_oversized_chat_item(), Some(11));
+
+        assert_matches!(
+            partial.add_frame_with_meta(frame(), meta(21)),
+            Err(ValidationError::ChatError(ChatFrameError(
+                _,
+                ChatError::ChatItem(ChatItemError::FrameTooLarge(21, 20))
+            )))
+        );
+
+        // Frames that don't hold chat items aren't subject to the limits.
+        let chat = proto::Chat {
+            id: proto::Chat::TEST_ID + 1,
+            ..proto::Chat::test_data()
+        };
+        partial
+            .add_frame_with_meta(
+                proto::Frame {
+                    item: Some(chat.into()),
+                    ..Default::default()
+                },
+                meta(21),
+            )
+            .expect("valid");
+        assert_eq!(partial.take_oversized_chat_item(), None);
+    }
+
+    #[test]
+    fn duplicate_chat_item_is_a_warning_by_default() {
+        let mut partial = Store::fake();
+        let frame = || chat_item_frame_with_text_len(10);
+
+        partial
+            .add_frame_with_meta(frame(), FrameMeta::default())
+            .expect("valid");
+        assert!(!partial.take_duplicate_chat_item());
+
+        // The same text with a different length isn't flagged.
+        partial
+            .add_frame_with_meta(chat_item_frame_with_text_len(11), FrameMeta::default())
+            .expect("valid");
+        assert!(!partial.take_duplicate_chat_item());
+
+        partial
+            .add_frame_with_meta(frame(), FrameMeta::default())
+            .expect("valid");
+        assert!(partial.take_duplicate_chat_item());
+        assert!(!partial.take_duplicate_chat_item());
+    }
+
+    #[test]
+    fn duplicate_chat_item_can_be_rejected() {
+        let mut partial = Store::fake().with_duplicate_chat_items_rejected(true);
+        let frame = || chat_item_frame_with_text_len(10);
+
+        partial
+            .add_frame_with_meta(frame(), FrameMeta::default())
+            .expect("valid");
+        assert_matches!(
+            partial.add_frame_with_meta(frame(), FrameMeta::default()),
+            Err(ValidationError::ChatError(ChatFrameError(
+                _,
+                ChatError::ChatItem(ChatItemError::DuplicateMessage)
+            )))
+        );
+    }
+
+    fn expiration_timer_update_frame(sent_at: u64, expires_in_ms: u64) -> proto::Frame {
+        let mut item = proto::ChatItem {
+            directionalDetails: Some(
+                proto::chat_item::DirectionlessMessageDetails::default().into(),
+            ),
+            dateSent: sent_at,
+            ..proto::ChatItem::test_data()
+        };
+        item.set_updateMessage(proto::ChatUpdateMessage {
+            update: Some(proto::chat_update_message::Update::ExpirationTimerChange(
+                proto::ExpirationTimerChatUpdate {
+                    expiresInMs: expires_in_ms,
+                    ..Default::default()
+                },
+            )),
+            ..Default::default()
+        });
+        proto::Frame {
+            item: Some(item.into()),
+            ..Default::default()
+        }
+    }
+
+    fn partial_with_expire_timer_version(version: u32) -> PartialBackup<Store> {
+        Store::fake_with([
+            proto::Recipient::test_data().into(),
+            proto::Chat {
+                expireTimerVersion: version,
+                ..proto::Chat::test_data()
+            }
+            .into(),
+            proto::Recipient::test_data_contact().into(),
+        ])
+    }
+
+    #[test]
+    fn expire_timer_updates_within_version() {
+        let mut partial = partial_with_expire_timer_version(2);
+        for (sent_at, expires_in_ms) in [(2, 0), (1, 1000)] {
+            partial
+                .add_frame_with_meta(
+                    expiration_timer_update_frame(sent_at, expires_in_ms),
+                    FrameMeta::default(),
+                )
+                .expect("valid");
+        }
+        assert_eq!(partial.expire_timer_version_conflicts(), vec![]);
+    }
+
+    #[test]
+    fn expire_timer_updates_beyond_version_are_reported() {
+        let mut partial = partial_with_expire_timer_version(1);
+        for (sent_at, expires_in_ms) in [(3, 1000), (1, 0), (2, 2000)] {
+            partial
+                .add_frame_with_meta(
+                    expiration_timer_update_frame(sent_at, expires_in_ms),
+                    FrameMeta::default(),
+                )
+                .expect("valid");
+        }
+        assert_eq!(
+            partial.expire_timer_version_conflicts(),
+            vec![crate::FoundExpireTimerVersionConflict {
+                chat_id: proto::Chat::TEST_ID,
+                expire_timer_version: 1,
+                timer_changes: 3,
+                latest_change_sent_at: Timestamp::from_millis(3, "test"),
+            }]
+        );
+    }
+
+    #[test_case(0; "turned off")]
+    #[test_case(1000; "set")]
+    fn expire_timer_updates_without_version_are_accepted(expires_in_ms: u64) {
+        let mut partial = partial_with_expire_timer_version(0);
+        partial
+            .add_frame_with_meta(
+                expiration_timer_update_frame(1, expires_in_ms),
+                FrameMeta::default(),
+            )
+            .expect("valid");
+        // Exports from before versions were tracked have nothing to check the updates against.
+        assert_eq!(partial.expire_timer_version_conflicts(), vec![]);
+    }
+
+    const BACKUP_TIME_MS: u64 = 1_700_000_000_000;
+    const MS_PER_DAY: u64 = 24 * 60 * 60 * 1000;
+
+    fn partial_with_backup_time(purpose: Purpose) -> PartialBackup<ValidateOnly> {
+        PartialBackup::new(
+            proto::BackupInfo {
+                backupTimeMs: BACKUP_TIME_MS,
+                ..Default::default()
+            },
+            purpose,
+        )
+    }
+
+    #[test_case(Purpose::RemoteBackup, BACKUP_TIME_MS => Ok(None); "same time")]
+    #[test_case(Purpose::RemoteBackup, BACKUP_TIME_MS + 30 * MS_PER_DAY => Ok(None); "at max age")]
+    #[test_case(
+        Purpose::RemoteBackup, BACKUP_TIME_MS + 30 * MS_PER_DAY + 1
+        => Err("BackupInfo.backupTimeMs: backup is 30 days old, more than the limit of 30".into());
+        "past max age"
+    )]
+    #[test_case(
+        Purpose::DeviceTransfer, BACKUP_TIME_MS + 30 * MS_PER_DAY + 1
+        => Ok(Some("backup is 30 days old, more than the limit of 30".into()));
+        "past max age for transfer"
+    )]
+    #[test_case(Purpose::RemoteBackup, BACKUP_TIME_MS - MS_PER_DAY => Ok(None); "at max future")]
+    #[test_case(
+        Purpose::RemoteBackup, BACKUP_TIME_MS - MS_PER_DAY - 1
+        => Err("BackupInfo.backupTimeMs: backup was made 24 hours after the validation time".into());
+        "past max future"
+    )]
+    #[test_case(
+        Purpose::DeviceTransfer, BACKUP_TIME_MS - 3 * MS_PER_DAY
+        => Ok(Some("backup was made 72 hours after the validation time".into()));
+        "past max future for transfer"
+    )]
+    fn backup_time_policy(
+        purpose: Purpose,
+        validation_time_ms: u64,
+    ) -> Result<Option<String>, String> {
+        partial_with_backup_time(purpose)
+            .with_backup_time_policy(BackupTimePolicy {
+                max_age_days: Some(30),
+            })
+            .with_validation_time(
+                SystemTime::UNIX_EPOCH + std::time::Duration::from_millis(validation_time_ms),
+            )
+            .check_backup_time()
+            .map(|warning| warning.map(|w| w.to_string()))
+            .map_err(|e| e.to_string())
+    }
+
+    #[test]
+    fn backup_time_policy_has_no_max_age_by_default() {
+        let partial = partial_with_backup_time(Purpose::RemoteBackup).with_validation_time(
+            SystemTime::UNIX_EPOCH
+                + std::time::Duration::from_millis(BACKUP_TIME_MS + 10_000 * MS_PER_DAY),
+        );
+        assert_matches!(partial.check_backup_time(), Ok(None));
+    }
 }
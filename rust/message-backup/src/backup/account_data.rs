@@ -13,9 +13,9 @@ use uuid::Uuid;
 use zkgroup::ProfileKeyBytes;
 
 use crate::backup::chat::chat_style::{ChatStyle, ChatStyleError, CustomColorMap};
-use crate::backup::method::Method;
+use crate::backup::method::{Method, Store};
 use crate::backup::time::Duration;
-use crate::backup::{serialize, ReferencedTypes, TryIntoWith as _};
+use crate::backup::{serialize, ProtoContext, ReferencedTypes, TryIntoWith as _};
 use crate::proto::backup as proto;
 
 #[derive_where(Debug)]
@@ -220,9 +220,8 @@ impl TryFrom<proto::account_data::SubscriberData> for Subscription {
             .try_into()
             .map_err(|id: Vec<u8>| SubscriptionError::InvalidSubscriberId(id.len()))?;
 
-        if currencyCode.is_empty() {
-            return Err(SubscriptionError::EmptyCurrency);
-        }
+        check_currency_code(&currencyCode)
+            .map_err(|EmptyCurrency| SubscriptionError::EmptyCurrency)?;
         let currency_code = currencyCode;
 
         Ok(Subscription {
@@ -233,6 +232,20 @@ impl TryFrom<proto::account_data::SubscriberData> for Subscription {
     }
 }
 
+/// A currency code was required but not present.
+#[derive(Copy, Clone, Debug)]
+pub(crate) struct EmptyCurrency;
+
+/// The check every currency code in a backup has to pass.
+///
+/// Codes only have to be present here; callers may be stricter about their format.
+pub(crate) fn check_currency_code(code: &str) -> Result<(), EmptyCurrency> {
+    if code.is_empty() {
+        return Err(EmptyCurrency);
+    }
+    Ok(())
+}
+
 impl TryFrom<(String, Option<proto::account_data::UsernameLink>)> for UsernameData {
     type Error = AccountDataError;
 
@@ -344,6 +357,146 @@ impl<M: Method + ReferencedTypes> TryFrom<proto::account_data::AccountSettings>
     }
 }
 
+impl AccountData<Store> {
+    pub(crate) fn to_proto(&self, context: &ProtoContext) -> proto::AccountData {
+        let Self {
+            profile_key,
+            username,
+            given_name,
+            family_name,
+            account_settings,
+            avatar_url_path,
+            donation_subscription,
+            backup_subscription,
+        } = self;
+
+        let (username, username_link) = match username {
+            None => (None, None),
+            Some(UsernameData { username, link }) => (
+                Some(username.to_string()),
+                link.as_ref().map(UsernameLink::to_proto),
+            ),
+        };
+
+        proto::AccountData {
+            profileKey: profile_key.to_vec(),
+            username,
+            givenName: given_name.clone(),
+            familyName: family_name.clone(),
+            accountSettings: Some(account_settings.to_proto(context)).into(),
+            usernameLink: username_link.into(),
+            avatarUrlPath: avatar_url_path.clone(),
+            donationSubscriberData: donation_subscription
+                .as_ref()
+                .map(Subscription::to_proto)
+                .into(),
+            backupsSubscriberData: backup_subscription
+                .as_ref()
+                .map(Subscription::to_proto)
+                .into(),
+            special_fields: Default::default(),
+        }
+    }
+}
+
+impl Subscription {
+    fn to_proto(&self) -> proto::account_data::SubscriberData {
+        let Self {
+            subscriber_id,
+            currency_code,
+            manually_canceled,
+        } = self;
+
+        proto::account_data::SubscriberData {
+            subscriberId: subscriber_id.to_vec(),
+            currencyCode: currency_code.clone(),
+            manuallyCancelled: *manually_canceled,
+            special_fields: Default::default(),
+        }
+    }
+}
+
+impl UsernameLink {
+    fn to_proto(&self) -> proto::account_data::UsernameLink {
+        let Self {
+            color,
+            entropy,
+            server_id,
+        } = self;
+
+        proto::account_data::UsernameLink {
+            color: (*color).into(),
+            entropy: entropy.to_vec(),
+            serverId: server_id.as_bytes().to_vec(),
+            special_fields: Default::default(),
+        }
+    }
+}
+
+impl AccountSettings<Store> {
+    fn to_proto(&self, context: &ProtoContext) -> proto::account_data::AccountSettings {
+        let Self {
+            phone_number_sharing,
+            read_receipts,
+            sealed_sender_indicators,
+            typing_indicators,
+            link_previews,
+            not_discoverable_by_phone_number,
+            prefer_contact_avatars,
+            display_badges_on_profile,
+            keep_muted_chats_archived,
+            has_set_my_stories_privacy,
+            has_viewed_onboarding_story,
+            stories_disabled,
+            story_view_receipts_enabled,
+            has_seen_group_story_education_sheet,
+            has_completed_username_onboarding,
+            universal_expire_timer,
+            preferred_reaction_emoji,
+            default_chat_style,
+            custom_chat_colors,
+        } = self;
+
+        use proto::account_data::PhoneNumberSharingMode;
+        let phone_number_sharing_mode = match phone_number_sharing {
+            PhoneSharing::WithEverybody => PhoneNumberSharingMode::EVERYBODY,
+            PhoneSharing::WithNobody => PhoneNumberSharingMode::NOBODY,
+        };
+
+        let universal_expire_timer_seconds = universal_expire_timer.map_or(0, |timer| {
+            (timer.as_millis() / 1000)
+                .try_into()
+                .expect("read from u32 seconds")
+        });
+
+        proto::account_data::AccountSettings {
+            phoneNumberSharingMode: phone_number_sharing_mode.into(),
+            readReceipts: *read_receipts,
+            sealedSenderIndicators: *sealed_sender_indicators,
+            typingIndicators: *typing_indicators,
+            linkPreviews: *link_previews,
+            notDiscoverableByPhoneNumber: *not_discoverable_by_phone_number,
+            preferContactAvatars: *prefer_contact_avatars,
+            displayBadgesOnProfile: *display_badges_on_profile,
+            keepMutedChatsArchived: *keep_muted_chats_archived,
+            hasSetMyStoriesPrivacy: *has_set_my_stories_privacy,
+            hasViewedOnboardingStory: *has_viewed_onboarding_story,
+            storiesDisabled: *stories_disabled,
+            storyViewReceiptsEnabled: *story_view_receipts_enabled,
+            hasSeenGroupStoryEducationSheet: *has_seen_group_story_education_sheet,
+            hasCompletedUsernameOnboarding: *has_completed_username_onboarding,
+            universalExpireTimerSeconds: universal_expire_timer_seconds,
+            preferredReactionEmoji: preferred_reaction_emoji.clone(),
+            defaultChatStyle: default_chat_style
+                .as_ref()
+                .map(|style| style.to_proto(context))
+                .into(),
+            customChatColors: custom_chat_colors.to_proto(),
+            special_fields: Default::default(),
+        }
+    }
+}
+
 #[cfg(test)]
 mod test {
     use std::sync::Arc;
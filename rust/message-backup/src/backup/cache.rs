@@ -0,0 +1,349 @@
+//
+// Copyright (C) 2024 Signal Messenger, LLC.
+// SPDX-License-Identifier: AGPL-3.0-only
+//
+
+//! Skipping work when validating a backup that has only grown since the last time.
+//!
+//! While a backup is being created, it may be validated again and again as frames are added to
+//! it. A [`ValidationCache`] remembers each frame from the previous run, so that a frame that's
+//! identical to the one in the same position last time (with all the frames before it identical
+//! too) can have its effect on the validation context replayed instead of being converted again.
+//!
+//! For now only recipients and chats are replayed. Every other kind of frame, and every frame
+//! from the first one that differs from last time, is validated in full. So are the checks that
+//! happen once all the frames have been read.
+
+use sha2::{Digest as _, Sha256};
+
+use crate::backup::chat::ChatData;
+use crate::backup::frame::{ChatId, RecipientId};
+use crate::backup::method::{Method, ValidateOnly};
+use crate::backup::recipient::{DestinationKind, MinimalRecipientData};
+use crate::backup::{FrameMeta, PartialBackup, ReferencedTypes, ValidationError, WithId as _};
+use crate::proto::backup as proto;
+use crate::proto::backup::frame::Item as FrameItem;
+
+/// Remembers the frames of a backup between validations; see the [module
+/// documentation](self).
+///
+/// Pass the same cache to each call to
+/// [`BackupReader::validate_all_with_cache`](crate::BackupReader::validate_all_with_cache).
+#[derive(Debug, Default)]
+pub struct ValidationCache {
+    /// One entry per frame after the backup info, up to the last frame that
+    /// was added successfully.
+    frames: Vec<CachedFrame>,
+    /// The index into `frames` of the next frame to be added.
+    next_index: usize,
+    /// Whether every frame added so far in this run matched its entry.
+    matching: bool,
+    stats: CacheStats,
+}
+
+/// How much work a [`ValidationCache`] saved in the most recent validation.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub struct CacheStats {
+    /// Frames whose effects were replayed from the cache.
+    pub replayed: usize,
+    /// Frames that were converted and validated in full.
+    pub converted: usize,
+}
+
+#[derive(Debug)]
+struct CachedFrame {
+    digest: [u8; 32],
+    /// `None` for frames that have to be converted every time.
+    replay: Option<Replay>,
+}
+
+/// What a replayable frame added to the validation context.
+#[derive(Debug)]
+enum Replay {
+    Recipient(RecipientId, MinimalRecipientData),
+    Chat(ChatId, ChatData<ValidateOnly>),
+}
+
+/// Where to find what a frame added to the validation context.
+#[derive(Copy, Clone)]
+enum Registration {
+    Recipient(RecipientId),
+    Chat(ChatId),
+}
+
+impl ValidationCache {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Statistics for the most recent validation that used this cache.
+    pub fn last_run(&self) -> CacheStats {
+        self.stats
+    }
+
+    pub(crate) fn start_run(&mut self) {
+        self.next_index = 0;
+        self.matching = true;
+        self.stats = CacheStats::default();
+    }
+}
+
+/// How frames read from the input get added to a [`PartialBackup`].
+pub(crate) trait AddFrame<M: Method + ReferencedTypes> {
+    /// Adds `frame`, which was parsed from `serialized`.
+    fn add_frame(
+        &mut self,
+        backup: &mut PartialBackup<M>,
+        serialized: &[u8],
+        frame: proto::Frame,
+        meta: FrameMeta,
+    ) -> Result<(), ValidationError>;
+}
+
+/// Validates every frame in full.
+pub(crate) struct Uncached;
+
+impl<M: Method + ReferencedTypes> AddFrame<M> for Uncached {
+    fn add_frame(
+        &mut self,
+        backup: &mut PartialBackup<M>,
+        _serialized: &[u8],
+        frame: proto::Frame,
+        meta: FrameMeta,
+    ) -> Result<(), ValidationError> {
+        backup.add_frame_with_meta(frame, meta)
+    }
+}
+
+impl AddFrame<ValidateOnly> for &mut ValidationCache {
+    fn add_frame(
+        &mut self,
+        backup: &mut PartialBackup<ValidateOnly>,
+        serialized: &[u8],
+        frame: proto::Frame,
+        meta: FrameMeta,
+    ) -> Result<(), ValidationError> {
+        let index = self.next_index;
+        self.next_index += 1;
+        let digest: [u8; 32] = Sha256::digest(serialized).into();
+
+        self.matching = self.matching
+            && self
+                .frames
+                .get(index)
+                .is_some_and(|cached| cached.digest == digest);
+        if !self.matching {
+            // Nothing recorded from here on can be trusted.
+            self.frames.truncate(index);
+        }
+
+        if let Some(CachedFrame {
+            replay: Some(replay),
+            ..
+        }) = self.frames.get(index)
+        {
+            self.stats.replayed += 1;
+            return backup.replay(replay, meta);
+        }
+
+        let registration = Registration::of(&frame);
+        self.stats.converted += 1;
+        if let Err(e) = backup.add_frame_with_meta(frame, meta) {
+            self.frames.truncate(index);
+            self.matching = false;
+            return Err(e);
+        }
+        if !self.matching {
+            self.frames.push(CachedFrame {
+                digest,
+                replay: registration.and_then(|registration| backup.recorded(registration)),
+            });
+        }
+        Ok(())
+    }
+}
+
+impl Registration {
+    fn of(frame: &proto::Frame) -> Option<Self> {
+        match frame.item.as_ref()? {
+            FrameItem::Recipient(recipient) => Some(Self::Recipient(recipient.id())),
+            FrameItem::Chat(chat) => Some(Self::Chat(chat.id())),
+            FrameItem::Account(_)
+            | FrameItem::ChatItem(_)
+            | FrameItem::StickerPack(_)
+            | FrameItem::AdHocCall(_) => None,
+        }
+    }
+}
+
+impl PartialBackup<ValidateOnly> {
+    /// Copies out what was just added for `registration`.
+    fn recorded(&self, registration: Registration) -> Option<Replay> {
+        match registration {
+            Registration::Recipient(id) => self
+                .recipients
+                .get(&id)
+                .map(|data| Replay::Recipient(id, data.clone())),
+            Registration::Chat(id) => self
+                .chats
+                .items
+                .get(&id)
+                .map(|data| Replay::Chat(id, data.clone())),
+        }
+    }
+
+    /// Has the same effect as adding the frame `replay` was recorded from.
+    fn replay(&mut self, replay: &Replay, meta: FrameMeta) -> Result<(), ValidationError> {
+        self.start_frame(meta);
+        match replay {
+            Replay::Recipient(id, data) => {
+                match data.as_ref() {
+                    DestinationKind::Self_ => self.self_recipient = Some(*id),
+                    DestinationKind::ReleaseNotes => self.release_notes_recipient = Some(*id),
+                    _ => {}
+                }
+                self.policy_decision = self.policy.check_recipient(data.as_ref());
+                self.recipients.insert(*id, data.clone());
+            }
+            Replay::Chat(id, data) => self.chats.add_chat(*id, data.clone())?,
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use assert_matches::assert_matches;
+
+    use super::*;
+    use crate::backup::{convert_from_json, Purpose};
+    use crate::{BackupReader, Error, ReadResult};
+
+    const FIXTURE: &str =
+        include_str!("../../tests/res/test-cases/valid/simple-chat-update-message.jsonproto");
+
+    /// The fixture's frames, starting with the backup info.
+    fn fixture() -> Vec<serde_json::Value> {
+        json5::from_str(FIXTURE).expect("valid JSON")
+    }
+
+    fn count(json: &[serde_json::Value], kinds: &[&str]) -> usize {
+        json.iter()
+            .filter(|frame| kinds.iter().any(|kind| frame.get(kind).is_some()))
+            .count()
+    }
+
+    fn validate(json: &[serde_json::Value], cache: Option<&mut ValidationCache>) -> ReadResult<()> {
+        let serialized = convert_from_json(json.to_vec()).expect("valid frames");
+        let reader = BackupReader::new_unencrypted(
+            futures::io::Cursor::new(serialized),
+            Purpose::RemoteBackup,
+        );
+        match cache {
+            Some(cache) => futures::executor::block_on(reader.validate_all_with_cache(cache)),
+            None => futures::executor::block_on(reader.validate_all()),
+        }
+    }
+
+    fn assert_same_outcome(cached: &ReadResult<()>, uncached: &ReadResult<()>) {
+        assert_eq!(
+            cached.result.as_ref().map_err(ToString::to_string),
+            uncached.result.as_ref().map_err(ToString::to_string)
+        );
+        assert_eq!(
+            cached.found_unknown_fields.len(),
+            uncached.found_unknown_fields.len()
+        );
+        assert_eq!(
+            cached.found_duplicate_chat_items.len(),
+            uncached.found_duplicate_chat_items.len()
+        );
+        assert_eq!(cached.policy_flags.len(), uncached.policy_flags.len());
+    }
+
+    #[test]
+    fn appended_frames_reuse_the_unchanged_prefix() {
+        let full = fixture();
+        let prefix = &full[..full.len() - 5];
+        let frame_count = full.len() - 1;
+
+        let mut cache = ValidationCache::new();
+        let first = validate(prefix, Some(&mut cache));
+        assert_same_outcome(&first, &validate(prefix, None));
+        assert_eq!(
+            cache.last_run(),
+            CacheStats {
+                replayed: 0,
+                converted: prefix.len() - 1,
+            }
+        );
+
+        let second = validate(&full, Some(&mut cache));
+        assert_matches!(second.result, Ok(()));
+        assert_same_outcome(&second, &validate(&full, None));
+        let replayed = count(prefix, &["recipient", "chat"]);
+        assert_ne!(replayed, 0);
+        assert_eq!(
+            cache.last_run(),
+            CacheStats {
+                replayed,
+                converted: frame_count - replayed,
+            }
+        );
+    }
+
+    #[test]
+    fn frames_after_a_change_are_converted() {
+        let mut full = fixture();
+        let mut cache = ValidationCache::new();
+        assert_matches!(validate(&full, Some(&mut cache)).result, Ok(()));
+
+        let changed_index = full
+            .iter()
+            .position(|frame| frame.get("chat").is_some())
+            .expect("has a chat");
+        full[changed_index]["chat"]["markedUnread"] = true.into();
+
+        let cached = validate(&full, Some(&mut cache));
+        assert_matches!(cached.result, Ok(()));
+        assert_same_outcome(&cached, &validate(&full, None));
+        let replayed = count(&full[..changed_index], &["recipient", "chat"]);
+        assert_eq!(
+            cache.last_run(),
+            CacheStats {
+                replayed,
+                converted: full.len() - 1 - replayed,
+            }
+        );
+    }
+
+    #[test]
+    fn cross_frame_checks_still_see_replayed_frames() {
+        let mut full = fixture();
+        let mut cache = ValidationCache::new();
+        assert_matches!(validate(&full, Some(&mut cache)).result, Ok(()));
+
+        // A second chat with an ID that's already in use is only invalid because of a frame
+        // that's now replayed.
+        let duplicate_chat = full
+            .iter()
+            .find(|frame| frame.get("chat").is_some())
+            .expect("has a chat")
+            .clone();
+        full.push(duplicate_chat);
+
+        let cached = validate(&full, Some(&mut cache));
+        assert_matches!(cached.result, Err(Error::BackupValidation(_)));
+        assert_same_outcome(&cached, &validate(&full, None));
+        assert_ne!(cache.last_run().replayed, 0);
+
+        // The failed frame wasn't recorded, so the next run still replays everything before it.
+        full.pop();
+        let cached = validate(&full, Some(&mut cache));
+        assert_matches!(cached.result, Ok(()));
+        assert_eq!(
+            cache.last_run().replayed,
+            count(&full, &["recipient", "chat"])
+        );
+    }
+}
@@ -7,9 +7,9 @@ use std::fmt::Debug;
 
 use crate::backup::frame::RecipientId;
 use crate::backup::method::LookupPair;
-use crate::backup::recipient::DestinationKind;
+use crate::backup::recipient::{DestinationKind, FullRecipientData};
 use crate::backup::time::Timestamp;
-use crate::backup::{serialize, TryFromWith};
+use crate::backup::{serialize, ProtoContext, TryFromWith};
 use crate::proto::backup as proto;
 
 /// Validated version of [`proto::AdHocCall`].
@@ -50,7 +50,7 @@ pub struct GroupCall<Recipient> {
 ///
 /// This is not referenced as a foreign key from elsewhere in a backup, but
 /// corresponds to shared state across conversation members for a given call.
-#[derive(Copy, Clone, Debug, Eq, PartialEq, PartialOrd, Ord, serde::Serialize)]
+#[derive(Copy, Clone, Debug, Eq, Hash, PartialEq, PartialOrd, Ord, serde::Serialize)]
 pub struct CallId(u64);
 
 #[derive(Debug, displaydoc::Display, thiserror::Error)]
@@ -74,6 +74,10 @@ pub enum CallError {
     UnknownState,
     /// call direction is UNKNOWN_DIRECTION
     UnknownDirection,
+    /// {0:?} already appeared as a group call in this chat
+    DuplicateCallId(CallId),
+    /// {0:?} is used by both an ad-hoc call and a group call
+    AdHocCallIdCollision(CallId),
 }
 
 #[derive(Debug, displaydoc::Display, thiserror::Error)]
@@ -366,6 +370,149 @@ impl TryFrom<proto::CallLink> for CallLink {
     }
 }
 
+impl IndividualCall {
+    pub(crate) fn to_proto(&self) -> proto::IndividualCall {
+        let Self {
+            id,
+            call_type,
+            state,
+            outgoing,
+            started_at,
+            read,
+        } = self;
+
+        let direction = {
+            use proto::individual_call::Direction;
+            if *outgoing {
+                Direction::OUTGOING
+            } else {
+                Direction::INCOMING
+            }
+        };
+
+        let type_ = {
+            use proto::individual_call::Type;
+            match call_type {
+                CallType::Audio => Type::AUDIO_CALL,
+                CallType::Video => Type::VIDEO_CALL,
+            }
+        };
+
+        let state = {
+            use proto::individual_call::State;
+            match state {
+                IndividualCallState::Accepted => State::ACCEPTED,
+                IndividualCallState::Missed => State::MISSED,
+                IndividualCallState::NotAccepted => State::NOT_ACCEPTED,
+                IndividualCallState::MissedByNotificationProfile => {
+                    State::MISSED_NOTIFICATION_PROFILE
+                }
+            }
+        };
+
+        proto::IndividualCall {
+            callId: id.map(|id| id.0),
+            type_: type_.into(),
+            state: state.into(),
+            direction: direction.into(),
+            startedCallTimestamp: started_at.as_millis(),
+            read: *read,
+            special_fields: Default::default(),
+        }
+    }
+}
+
+impl GroupCall<FullRecipientData> {
+    pub(crate) fn to_proto(&self, context: &ProtoContext) -> proto::GroupCall {
+        let Self {
+            id,
+            state,
+            started_call_recipient,
+            ringer_recipient,
+            started_at,
+            ended_at,
+            read,
+        } = self;
+
+        let state = {
+            use proto::group_call::State;
+            match state {
+                GroupCallState::Missed => State::MISSED,
+                GroupCallState::Generic => State::GENERIC,
+                GroupCallState::Joined => State::JOINED,
+                GroupCallState::Ringing => State::RINGING,
+                GroupCallState::Accepted => State::ACCEPTED,
+                GroupCallState::Declined => State::DECLINED,
+                GroupCallState::MissedByNotificationProfile => State::MISSED_NOTIFICATION_PROFILE,
+                GroupCallState::OutgoingRing => State::OUTGOING_RING,
+            }
+        };
+
+        proto::GroupCall {
+            callId: id.map(|id| id.0),
+            state: state.into(),
+            startedCallRecipientId: started_call_recipient
+                .as_ref()
+                .map(|recipient| context.recipient_id(recipient)),
+            ringerRecipientId: ringer_recipient
+                .as_ref()
+                .map(|recipient| context.recipient_id(recipient)),
+            startedCallTimestamp: started_at.as_millis(),
+            endedCallTimestamp: ended_at.as_millis(),
+            read: *read,
+            special_fields: Default::default(),
+        }
+    }
+}
+
+impl AdHocCall<FullRecipientData> {
+    pub(crate) fn to_proto(&self, context: &ProtoContext) -> proto::AdHocCall {
+        let Self {
+            id,
+            timestamp,
+            recipient,
+        } = self;
+
+        proto::AdHocCall {
+            callId: id.0,
+            recipientId: context.recipient_id(recipient),
+            state: proto::ad_hoc_call::State::GENERIC.into(),
+            callTimestamp: timestamp.as_millis(),
+            special_fields: Default::default(),
+        }
+    }
+}
+
+impl CallLink {
+    pub(crate) fn to_proto(&self) -> proto::CallLink {
+        let Self {
+            admin_approval,
+            root_key,
+            admin_key,
+            expiration,
+            name,
+        } = self;
+
+        let restrictions = {
+            use proto::call_link::Restrictions;
+            if *admin_approval {
+                Restrictions::ADMIN_APPROVAL
+            } else {
+                Restrictions::NONE
+            }
+        };
+
+        proto::CallLink {
+            rootKey: root_key.to_vec(),
+            adminKey: admin_key.clone(),
+            name: name.clone(),
+            restrictions: restrictions.into(),
+            expirationMs: expiration.as_millis(),
+            special_fields: Default::default(),
+        }
+    }
+}
+
 #[cfg(test)]
 pub(crate) mod test {
     use protobuf::EnumOrUnknown;
@@ -412,7 +559,7 @@ pub(crate) mod test {
     impl proto::AdHocCall {
         const TEST_ID: u64 = 888888;
 
-        fn test_data() -> Self {
+        pub(crate) fn test_data() -> Self {
             Self {
                 callId: Self::TEST_ID,
                 recipientId: TEST_CALL_LINK_RECIPIENT_ID.0,
@@ -426,7 +573,7 @@ pub(crate) mod test {
     const TEST_CALL_LINK_ROOT_KEY: CallLinkRootKey = [b'R'; 16];
     const TEST_CALL_LINK_ADMIN_KEY: &[u8] = b"A";
     impl proto::CallLink {
-        fn test_data() -> Self {
+        pub(crate) fn test_data() -> Self {
             Self {
                 rootKey: TEST_CALL_LINK_ROOT_KEY.to_vec(),
                 adminKey: Some(TEST_CALL_LINK_ADMIN_KEY.to_vec()),
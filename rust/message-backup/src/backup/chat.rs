@@ -14,15 +14,19 @@ use std::num::{NonZeroU32, NonZeroU64};
 
 use derive_where::derive_where;
 
+use crate::backup::call::CallId;
 use crate::backup::chat::chat_style::{ChatStyle, ChatStyleError, CustomColorId};
 use crate::backup::file::{FilePointerError, MessageAttachmentError};
-use crate::backup::frame::RecipientId;
-use crate::backup::method::{Lookup, LookupPair, Method};
-use crate::backup::recipient::DestinationKind;
+use crate::backup::frame::{ChatId, RecipientId};
+use crate::backup::method::{Lookup, LookupPair, Method, Store};
+use crate::backup::recipient::{DestinationKind, FullRecipientData};
 use crate::backup::serialize::{SerializeOrder, UnorderedList};
 use crate::backup::sticker::MessageStickerError;
 use crate::backup::time::{Duration, Timestamp};
-use crate::backup::{BackupMeta, CallError, ReferencedTypes, TryFromWith, TryIntoWith as _};
+use crate::backup::{
+    BackupMeta, CallError, FrameMeta, FrameSizeLimits, ProtoContext, ReferencedTypes,
+    RevisionStorage, TryFromWith, TryIntoWith as _,
+};
 use crate::proto::backup as proto;
 
 mod contact_message;
@@ -30,6 +34,9 @@ use contact_message::*;
 
 pub(crate) mod chat_style;
 
+mod expire_timer_history;
+pub(crate) use expire_timer_history::ExpireTimerHistory;
+
 mod gift_badge;
 use gift_badge::*;
 
@@ -39,13 +46,16 @@ use group::*;
 mod link;
 use link::*;
 
+mod media_summary;
+pub use media_summary::MediaSummary;
+
 mod payment;
 use payment::*;
 
-mod quote;
+pub(crate) mod quote;
 use quote::*;
 
-mod reactions;
+pub(crate) mod reactions;
 use reactions::*;
 
 mod standard_message;
@@ -77,6 +87,10 @@ pub enum ChatError {
     InvalidRecipient(RecipientId, DestinationKind),
     /// chat with {0:?} has an expirationTimerMs but no expireTimerVersion
     MissingExpireTimerVersion(RecipientId),
+    /// chat with the Release Notes recipient {0:?} has an expiration timer
+    ExpirationTimerOnReleaseNotes(RecipientId),
+    /// outgoing message in the Release Notes chat
+    OutgoingMessageInReleaseNotes,
     /// chat item: {0}
     ChatItem(#[from] ChatItemError),
     /// {0:?} already appeared
@@ -160,6 +174,12 @@ pub enum ChatItemError {
     LearnedProfileIsEmpty,
     /// invalid e164
     InvalidE164,
+    /// payment activation: {0}
+    PaymentActivation(#[from] PaymentActivationError),
+    /// chat item frame is {0} bytes, more than the limit of {1}
+    FrameTooLarge(usize, usize),
+    /// same author, timestamp, and kind of message as an earlier item in the chat
+    DuplicateMessage,
 }
 
 #[derive(Debug, thiserror::Error)]
@@ -171,6 +191,7 @@ pub struct InvalidExpiration {
 
 /// Validated version of [`proto::Chat`].
 #[derive_where(Debug)]
+#[derive_where(Clone; M::List<ChatItemData<M>>: Clone, ChatStyle<M>: Clone)]
 #[derive(serde::Serialize)]
 #[cfg_attr(test, derive_where(PartialEq;
     M::List<ChatItemData<M>>: PartialEq,
@@ -194,6 +215,24 @@ pub struct ChatData<M: Method + ReferencedTypes> {
     pub dont_notify_for_mentions_if_muted: bool,
     pub marked_unread: bool,
     pub archived: bool,
+    /// Accumulated as items are added to the chat.
+    pub media_summary: MediaSummary,
+    /// Whether this is the chat with the [`DestinationKind::ReleaseNotes`]
+    /// recipient, which can't have outgoing messages.
+    #[serde(skip)]
+    pub(crate) is_release_notes: bool,
+    /// Accumulated as items are added to the chat.
+    #[serde(skip)]
+    pub(crate) expire_timer_history: ExpireTimerHistory,
+}
+
+impl<M: Method + ReferencedTypes> ChatData<M> {
+    /// Whether [`Self::expire_timer_history`] has more updates than
+    /// [`Self::expiration_timer_version`] allows for.
+    pub(super) fn has_expire_timer_version_conflict(&self) -> bool {
+        self.expire_timer_history
+            .conflicts_with(self.expiration_timer_version)
+    }
 }
 
 #[derive(Copy, Clone, Debug, Eq, Hash, PartialEq, PartialOrd, Ord, serde::Serialize)]
@@ -210,8 +249,7 @@ pub struct ChatItemData<M: Method + ReferencedTypes> {
     pub author: M::RecipientReference,
     #[serde(bound(serialize = "ChatItemMessage<M>: serde::Serialize"))]
     pub message: ChatItemMessage<M>,
-    // This could be Self: Serialize but that just confuses the compiler.
-    pub revisions: Vec<ChatItemData<M>>,
+    pub revisions: Revisions<M>,
     pub direction: Direction<M::RecipientReference>,
     pub expire_start: Option<Timestamp>,
     pub expires_in: Option<Duration>,
@@ -220,14 +258,115 @@ pub struct ChatItemData<M: Method + ReferencedTypes> {
     /// The position of this chat item among all chat items (across chats) in
     /// the source stream.
     pub total_chat_item_order_index: usize,
+    /// The size of the frame this item was read from, if that was more than
+    /// [`FrameSizeLimits::warn_above`].
+    #[serde(skip)]
+    pub oversized_frame_size: Option<usize>,
     _limit_construction_to_module: (),
 }
 
+impl<M: Method + ReferencedTypes> ChatItemData<M> {
+    /// The ID of the group call this item records, if it has one.
+    pub(super) fn group_call_id(&self) -> Option<CallId> {
+        match &self.message {
+            ChatItemMessage::Update(UpdateMessage::GroupCall(call)) => call.id,
+            _ => None,
+        }
+    }
+}
+
+/// The earlier versions of a [`ChatItemData`], kept according to the backup's [`RevisionStorage`].
+#[derive_where(Debug)]
+#[derive(serde::Serialize)]
+#[serde(
+    untagged,
+    bound(
+        serialize = "M::RecipientReference: serde::Serialize, ChatItemMessage<M>: serde::Serialize"
+    )
+)]
+#[cfg_attr(test, derive_where(PartialEq;
+    ChatItemMessage<M>: PartialEq,
+    M::RecipientReference: PartialEq
+))]
+pub enum Revisions<M: Method + ReferencedTypes> {
+    Summaries(Vec<RevisionSummary<M::RecipientReference>>),
+    Full(Vec<ChatItemData<M>>),
+}
+
+impl<M: Method + ReferencedTypes> Revisions<M> {
+    pub fn len(&self) -> usize {
+        match self {
+            Self::Summaries(summaries) => summaries.len(),
+            Self::Full(items) => items.len(),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+/// What's kept of a revision under [`RevisionStorage::Summary`].
+///
+/// The revision has been validated in full by the time this is made; this is
+/// just enough to tell the revisions of an item apart.
+#[derive(Debug, serde::Serialize)]
+#[cfg_attr(test, derive(PartialEq))]
+pub struct RevisionSummary<Recipient> {
+    #[serde(bound(serialize = "Recipient: serde::Serialize"))]
+    pub author: Recipient,
+    pub sent_at: Timestamp,
+    pub direction: DirectionDiscriminants,
+    pub message: ChatItemMessageDiscriminants,
+}
+
+impl<M: Method + ReferencedTypes> ChatItemData<M> {
+    fn into_revision_summary(self) -> RevisionSummary<M::RecipientReference> {
+        RevisionSummary {
+            direction: (&self.direction).into(),
+            message: (&self.message).into(),
+            author: self.author,
+            sent_at: self.sent_at,
+        }
+    }
+}
+
+/// Enough of a [`proto::ChatItem`] to tell when it's probably a copy of another one.
+///
+/// Two items with the same fingerprint aren't necessarily the same message; someone may really
+/// have sent the same text twice within a millisecond. This is only a cheap way to notice
+/// backups that were exported with every message twice.
+#[derive(Debug, Eq, Hash, PartialEq)]
+pub(super) struct ChatItemFingerprint {
+    author: u64,
+    sent_at: u64,
+    kind: Option<std::mem::Discriminant<proto::chat_item::Item>>,
+    text_len: usize,
+}
+
+impl ChatItemFingerprint {
+    pub(super) fn of(item: &proto::ChatItem) -> Self {
+        let text_len = match &item.item {
+            Some(proto::chat_item::Item::StandardMessage(message)) => {
+                message.text.as_ref().map_or(0, |text| text.body.len())
+            }
+            _ => 0,
+        };
+        Self {
+            author: item.authorId,
+            sent_at: item.dateSent,
+            kind: item.item.as_ref().map(std::mem::discriminant),
+            text_len,
+        }
+    }
+}
+
 const MAX_REMOTE_BACKUP_DISAPPEARING_MESSAGE_TIME: Duration = Duration::from_hours(24);
 
 /// Validated version of [`proto::chat_item::Item`].
 #[derive_where(Debug)]
-#[derive(serde::Serialize)]
+#[derive(serde::Serialize, strum::EnumDiscriminants)]
+#[strum_discriminants(derive(serde::Serialize))]
 #[cfg_attr(test, derive_where(PartialEq;
     M::BoxedValue<GiftBadge>: PartialEq,
     M::RecipientReference: PartialEq
@@ -245,6 +384,7 @@ pub enum ChatItemMessage<M: Method + ReferencedTypes> {
 }
 
 #[derive(Debug, serde::Serialize, strum::EnumDiscriminants)]
+#[strum_discriminants(derive(serde::Serialize))]
 #[cfg_attr(test, derive(PartialEq))]
 pub enum Direction<Recipient> {
     Incoming {
@@ -378,6 +518,10 @@ impl<
         if expiration_timer.is_some() && expireTimerVersion == 0 {
             return Err(ChatError::MissingExpireTimerVersion(recipient_id));
         }
+        let is_release_notes = kind == DestinationKind::ReleaseNotes;
+        if is_release_notes && expiration_timer.is_some() {
+            return Err(ChatError::ExpirationTimerOnReleaseNotes(recipient_id));
+        }
         let expiration_timer_version = expireTimerVersion;
 
         Ok(Self {
@@ -391,18 +535,27 @@ impl<
             archived,
             marked_unread: markedUnread,
             dont_notify_for_mentions_if_muted: dontNotifyForMentionsIfMuted,
+            media_summary: Default::default(),
+            is_release_notes,
+            expire_timer_history: Default::default(),
         })
     }
 }
 
 impl<
-        C: LookupPair<RecipientId, DestinationKind, M::RecipientReference> + AsRef<BackupMeta>,
+        C: LookupPair<RecipientId, DestinationKind, M::RecipientReference>
+            + AsRef<BackupMeta>
+            + AsRef<FrameMeta>,
         M: Method + ReferencedTypes,
     > TryFromWith<proto::ChatItem, C> for ChatItemData<M>
 {
     type Error = ChatItemError;
 
     fn try_from_with(value: proto::ChatItem, context: &C) -> Result<Self, ChatItemError> {
+        let meta: &BackupMeta = context.as_ref();
+        let frame: &FrameMeta = context.as_ref();
+        let oversized_frame_size = check_frame_size(frame, meta)?;
+
         let proto::ChatItem {
             chatId: _,
             authorId,
@@ -461,58 +614,19 @@ impl<
             (_, _) => Ok(()),
         }?;
 
-        let revisions: Vec<_> = revisions
+        // Each revision is dropped as soon as it's been validated, unless all of
+        // it is being kept.
+        let revisions = revisions
             .into_iter()
-            .map(|rev| {
-                // We have to test this on the raw IDs because RecipientReference isn't necessarily
-                // comparable.
-                if author_id.0 != rev.authorId {
-                    return Err(ChatItemError::RevisionWithMismatchedAuthor(
-                        author_id,
-                        RecipientId(rev.authorId),
-                    ));
-                }
-
-                let item: ChatItemData<M> = rev.try_into_with(context)?;
-                if DirectionDiscriminants::from(&direction)
-                    != DirectionDiscriminants::from(&item.direction)
-                {
-                    return Err(ChatItemError::RevisionWithMismatchedDirection(
-                        DirectionDiscriminants::from(&direction),
-                        DirectionDiscriminants::from(&item.direction),
-                    ));
-                }
-                match &item.message {
-                    ChatItemMessage::Update(update) => match update {
-                        UpdateMessage::GroupCall(_) | UpdateMessage::IndividualCall(_) => {
-                            return Err(ChatItemError::RevisionContainsCall)
-                        }
-                        UpdateMessage::Simple(_)
-                        | UpdateMessage::GroupChange { updates: _ }
-                        | UpdateMessage::ExpirationTimerChange { expires_in: _ }
-                        | UpdateMessage::ProfileChange {
-                            previous: _,
-                            new: _,
-                        }
-                        | UpdateMessage::ThreadMerge { previous_e164: _ }
-                        | UpdateMessage::SessionSwitchover { e164: _ }
-                        | UpdateMessage::LearnedProfileUpdate(_) => (),
-                    },
-                    ChatItemMessage::Standard(_)
-                    | ChatItemMessage::Contact(_)
-                    | ChatItemMessage::Voice(_)
-                    | ChatItemMessage::PaymentNotification(_)
-                    | ChatItemMessage::Sticker(_)
-                    | ChatItemMessage::GiftBadge(_)
-                    | ChatItemMessage::RemoteDeleted
-                    | ChatItemMessage::ViewOnce(_) => (),
-                }
-                if !item.revisions.is_empty() {
-                    return Err(ChatItemError::RevisionContainsRevisions);
-                }
-                Ok(item)
-            })
-            .collect::<Result<_, _>>()?;
+            .map(|rev| validate_revision::<_, M>(rev, author_id, &direction, context));
+        let revisions = match meta.revision_storage {
+            RevisionStorage::Summary => Revisions::Summaries(
+                revisions
+                    .map(|item| item.map(ChatItemData::into_revision_summary))
+                    .collect::<Result<_, _>>()?,
+            ),
+            RevisionStorage::Full => Revisions::Full(revisions.collect::<Result<_, _>>()?),
+        };
 
         let sent_at = Timestamp::from_millis(dateSent, "ChatItem.dateSent");
         let expire_start = NonZeroU64::new(expireStartDate)
@@ -532,9 +646,9 @@ impl<
             (Some(expire_start), Some(expires_in)) => {
                 let expires_at = expire_start + expires_in;
                 // Ensure that ephemeral content that's due to expire soon isn't backed up.
-                let backup_time = context.as_ref().backup_time;
+                let backup_time = meta.backup_time;
                 let allowed_expire_at = backup_time
-                    + match context.as_ref().purpose {
+                    + match meta.purpose {
                         crate::backup::Purpose::DeviceTransfer => Duration::ZERO,
                         crate::backup::Purpose::RemoteBackup => {
                             MAX_REMOTE_BACKUP_DISAPPEARING_MESSAGE_TIME
@@ -561,11 +675,286 @@ impl<
             expires_in,
             sms,
             total_chat_item_order_index: Default::default(),
+            oversized_frame_size,
             _limit_construction_to_module: (),
         })
     }
 }
 
+impl ChatData<Store> {
+    pub(crate) fn to_proto(&self, id: ChatId, context: &ProtoContext) -> proto::Chat {
+        let Self {
+            recipient,
+            items: _,
+            expiration_timer,
+            expiration_timer_version,
+            mute_until,
+            style,
+            pinned_order,
+            dont_notify_for_mentions_if_muted,
+            marked_unread,
+            archived,
+            media_summary: _,
+            is_release_notes: _,
+            expire_timer_history: _,
+        } = self;
+
+        proto::Chat {
+            id: id.0,
+            recipientId: context.recipient_id(recipient),
+            expirationTimerMs: expiration_timer.map_or(0, |timer| timer.as_millis()),
+            expireTimerVersion: *expiration_timer_version,
+            muteUntilMs: mute_until.map_or(0, |t| t.as_millis()),
+            pinnedOrder: pinned_order.map_or(0, |order| order.0.get()),
+            archived: *archived,
+            markedUnread: *marked_unread,
+            dontNotifyForMentionsIfMuted: *dont_notify_for_mentions_if_muted,
+            style: style.as_ref().map(|style| style.to_proto(context)).into(),
+            special_fields: Default::default(),
+        }
+    }
+}
+
+impl ChatItemData<Store> {
+    /// Rebuilds the item as it would appear in the chat with ID `chat_id`.
+    ///
+    /// Revisions are only written if they were kept with [`RevisionStorage::Full`]; summaries
+    /// don't have enough left of the item to rebuild it.
+    pub(crate) fn to_proto(&self, chat_id: ChatId, context: &ProtoContext) -> proto::ChatItem {
+        let Self {
+            author,
+            message,
+            revisions,
+            direction,
+            expire_start,
+            expires_in,
+            sent_at,
+            sms,
+            total_chat_item_order_index: _,
+            oversized_frame_size: _,
+            _limit_construction_to_module: _,
+        } = self;
+
+        let revisions = match revisions {
+            Revisions::Summaries(_) => vec![],
+            Revisions::Full(items) => items
+                .iter()
+                .map(|item| item.to_proto(chat_id, context))
+                .collect(),
+        };
+
+        proto::ChatItem {
+            chatId: chat_id.0,
+            authorId: context.recipient_id(author),
+            item: Some(message.to_proto(context)),
+            directionalDetails: Some(direction.to_proto(context)),
+            revisions,
+            expireStartDate: expire_start.map_or(0, |t| t.as_millis()),
+            expiresInMs: expires_in.map_or(0, |d| d.as_millis()),
+            dateSent: sent_at.as_millis(),
+            sms: *sms,
+            special_fields: Default::default(),
+        }
+    }
+}
+
+impl ChatItemMessage<Store> {
+    fn to_proto(&self, context: &ProtoContext) -> proto::chat_item::Item {
+        use proto::chat_item::Item;
+        match self {
+            ChatItemMessage::Standard(message) => Item::StandardMessage(message.to_proto(context)),
+            ChatItemMessage::Contact(message) => Item::ContactMessage(message.to_proto(context)),
+            ChatItemMessage::Voice(message) => Item::StandardMessage(message.to_proto(context)),
+            ChatItemMessage::Sticker(message) => Item::StickerMessage(message.to_proto(context)),
+            ChatItemMessage::RemoteDeleted => {
+                Item::RemoteDeletedMessage(proto::RemoteDeletedMessage::default())
+            }
+            ChatItemMessage::Update(message) => Item::UpdateMessage(message.to_proto(context)),
+            ChatItemMessage::PaymentNotification(message) => {
+                Item::PaymentNotification(message.to_proto())
+            }
+            ChatItemMessage::GiftBadge(badge) => Item::GiftBadge(badge.to_proto()),
+            ChatItemMessage::ViewOnce(message) => Item::ViewOnceMessage(message.to_proto(context)),
+        }
+    }
+}
+
+impl Direction<FullRecipientData> {
+    fn to_proto(&self, context: &ProtoContext) -> proto::chat_item::DirectionalDetails {
+        use proto::chat_item::*;
+        match self {
+            Direction::Incoming {
+                sent,
+                received,
+                read,
+                sealed_sender,
+            } => DirectionalDetails::Incoming(IncomingMessageDetails {
+                dateReceived: received.as_millis(),
+                dateServerSent: sent.as_millis(),
+                read: *read,
+                sealedSender: *sealed_sender,
+                special_fields: Default::default(),
+            }),
+            Direction::Outgoing(sends) => DirectionalDetails::Outgoing(OutgoingMessageDetails {
+                sendStatus: sends.0.iter().map(|send| send.to_proto(context)).collect(),
+                special_fields: Default::default(),
+            }),
+            Direction::Directionless => {
+                DirectionalDetails::Directionless(DirectionlessMessageDetails::default())
+            }
+        }
+    }
+}
+
+impl OutgoingSend<FullRecipientData> {
+    fn to_proto(&self, context: &ProtoContext) -> proto::SendStatus {
+        use proto::send_status;
+        let Self {
+            recipient,
+            status,
+            last_status_update,
+        } = self;
+
+        let status = match status {
+            DeliveryStatus::Failed(reason) => {
+                send_status::DeliveryStatus::Failed(send_status::Failed {
+                    reason: match reason {
+                        DeliveryFailureReason::Unknown => {
+                            send_status::failed::FailureReason::UNKNOWN
+                        }
+                        DeliveryFailureReason::Network => {
+                            send_status::failed::FailureReason::NETWORK
+                        }
+                        DeliveryFailureReason::IdentityKeyMismatch => {
+                            send_status::failed::FailureReason::IDENTITY_KEY_MISMATCH
+                        }
+                    }
+                    .into(),
+                    special_fields: Default::default(),
+                })
+            }
+            DeliveryStatus::Pending => {
+                send_status::DeliveryStatus::Pending(send_status::Pending::default())
+            }
+            DeliveryStatus::Sent { sealed_sender } => {
+                send_status::DeliveryStatus::Sent(send_status::Sent {
+                    sealedSender: *sealed_sender,
+                    special_fields: Default::default(),
+                })
+            }
+            DeliveryStatus::Delivered { sealed_sender } => {
+                send_status::DeliveryStatus::Delivered(send_status::Delivered {
+                    sealedSender: *sealed_sender,
+                    special_fields: Default::default(),
+                })
+            }
+            DeliveryStatus::Read { sealed_sender } => {
+                send_status::DeliveryStatus::Read(send_status::Read {
+                    sealedSender: *sealed_sender,
+                    special_fields: Default::default(),
+                })
+            }
+            DeliveryStatus::Viewed { sealed_sender } => {
+                send_status::DeliveryStatus::Viewed(send_status::Viewed {
+                    sealedSender: *sealed_sender,
+                    special_fields: Default::default(),
+                })
+            }
+            DeliveryStatus::Skipped => {
+                send_status::DeliveryStatus::Skipped(send_status::Skipped::default())
+            }
+        };
+
+        proto::SendStatus {
+            recipientId: context.recipient_id(recipient),
+            timestamp: last_status_update.as_millis(),
+            deliveryStatus: Some(status),
+            special_fields: Default::default(),
+        }
+    }
+}
+
+/// Validates `rev` as a revision of a chat item from `author_id` with the given `direction`.
+fn validate_revision<C, M>(
+    rev: proto::ChatItem,
+    author_id: RecipientId,
+    direction: &Direction<M::RecipientReference>,
+    context: &C,
+) -> Result<ChatItemData<M>, ChatItemError>
+where
+    C: LookupPair<RecipientId, DestinationKind, M::RecipientReference>
+        + AsRef<BackupMeta>
+        + AsRef<FrameMeta>,
+    M: Method + ReferencedTypes,
+{
+    // We have to test this on the raw IDs because RecipientReference isn't necessarily
+    // comparable.
+    if author_id.0 != rev.authorId {
+        return Err(ChatItemError::RevisionWithMismatchedAuthor(
+            author_id,
+            RecipientId(rev.authorId),
+        ));
+    }
+
+    let item: ChatItemData<M> = rev.try_into_with(context)?;
+    if DirectionDiscriminants::from(direction) != DirectionDiscriminants::from(&item.direction) {
+        return Err(ChatItemError::RevisionWithMismatchedDirection(
+            DirectionDiscriminants::from(direction),
+            DirectionDiscriminants::from(&item.direction),
+        ));
+    }
+    match &item.message {
+        ChatItemMessage::Update(update) => match update {
+            UpdateMessage::GroupCall(_) | UpdateMessage::IndividualCall(_) => {
+                return Err(ChatItemError::RevisionContainsCall)
+            }
+            UpdateMessage::Simple(_)
+            | UpdateMessage::GroupChange { updates: _ }
+            | UpdateMessage::ExpirationTimerChange { expires_in: _ }
+            | UpdateMessage::ProfileChange {
+                previous: _,
+                new: _,
+            }
+            | UpdateMessage::ThreadMerge { previous_e164: _ }
+            | UpdateMessage::SessionSwitchover { e164: _ }
+            | UpdateMessage::LearnedProfileUpdate(_)
+            | UpdateMessage::PaymentActivation {
+                kind: _,
+                amount: _,
+                currency_code: _,
+            } => (),
+        },
+        ChatItemMessage::Standard(_)
+        | ChatItemMessage::Contact(_)
+        | ChatItemMessage::Voice(_)
+        | ChatItemMessage::PaymentNotification(_)
+        | ChatItemMessage::Sticker(_)
+        | ChatItemMessage::GiftBadge(_)
+        | ChatItemMessage::RemoteDeleted
+        | ChatItemMessage::ViewOnce(_) => (),
+    }
+    if !item.revisions.is_empty() {
+        return Err(ChatItemError::RevisionContainsRevisions);
+    }
+    Ok(item)
+}
+
+/// Checks the size of the frame a chat item was read from against the backup's
+/// [`FrameSizeLimits`].
+///
+/// Returns the frame's size if it should be reported as oversized.
+fn check_frame_size(frame: &FrameMeta, meta: &BackupMeta) -> Result<Option<usize>, ChatItemError> {
+    let Some(size) = frame.serialized_size else {
+        return Ok(None);
+    };
+    let FrameSizeLimits { warn_above, max } = meta.frame_size_limits;
+
+    if size > max && meta.purpose == crate::backup::Purpose::RemoteBackup {
+        return Err(ChatItemError::FrameTooLarge(size, max));
+    }
+    Ok((size > warn_above).then_some(size))
+}
+
 impl<R: Clone, C: LookupPair<RecipientId, DestinationKind, R>>
     TryFromWith<proto::chat_item::DirectionalDetails, C> for Direction<R>
 {
@@ -750,7 +1139,6 @@ mod test {
     use test_case::test_case;
 
     use super::*;
-    use crate::backup::method::Store;
     use crate::backup::testutil::TestContext;
     use crate::backup::time::testutil::MillisecondsSinceEpoch;
     use crate::backup::Purpose;
@@ -814,6 +1202,9 @@ mod test {
                 archived: false,
                 marked_unread: false,
                 dont_notify_for_mentions_if_muted: false,
+                media_summary: MediaSummary::default(),
+                is_release_notes: false,
+                expire_timer_history: ExpireTimerHistory::default(),
             })
         );
     }
@@ -836,6 +1227,14 @@ mod test {
     #[test_case(|x| {
         x.recipientId = 0;
     } => Err(ChatError::NoRecipient(RecipientId(0))); "unknown recipient")]
+    #[test_case(|x| {
+        x.recipientId = TestContext::RELEASE_NOTES_ID.0;
+    } => Ok(()); "release notes chat")]
+    #[test_case(|x| {
+        x.recipientId = TestContext::RELEASE_NOTES_ID.0;
+        x.expirationTimerMs = 123456;
+        x.expireTimerVersion = 3;
+    } => Err(ChatError::ExpirationTimerOnReleaseNotes(TestContext::RELEASE_NOTES_ID)); "release notes chat with expiration timer")]
     fn chat(modifier: fn(&mut proto::Chat)) -> Result<(), ChatError> {
         let mut chat = proto::Chat::test_data();
         modifier(&mut chat);
@@ -850,7 +1249,7 @@ mod test {
             Ok(ChatItemData::<Store> {
                 author: TestContext::contact_recipient().clone(),
                 message: ChatItemMessage::Standard(StandardMessage::from_proto_test_data()),
-                revisions: vec![],
+                revisions: Revisions::Summaries(vec![]),
                 direction: Direction::Incoming {
                     received: Timestamp::test_value(),
                     sent: Timestamp::test_value(),
@@ -862,6 +1261,7 @@ mod test {
                 sent_at: Timestamp::test_value(),
                 sms: false,
                 total_chat_item_order_index: 0,
+                oversized_frame_size: None,
                 _limit_construction_to_module: (),
             })
         )
@@ -975,6 +1375,95 @@ mod test {
             .map(|_: ChatItemData<Store>| ())
     }
 
+    fn context_with_revision_storage(revision_storage: RevisionStorage) -> TestContext {
+        TestContext(BackupMeta {
+            revision_storage,
+            ..TestContext::default().0
+        })
+    }
+
+    fn chat_item_with_revisions(count: usize, text_len: usize) -> proto::ChatItem {
+        let mut revision = proto::ChatItem::test_data();
+        let Some(proto::chat_item::Item::StandardMessage(message)) = &mut revision.item else {
+            unreachable!("test data is a standard message");
+        };
+        message.text.mut_or_insert_default().body = "x".repeat(text_len);
+        proto::ChatItem {
+            revisions: vec![revision; count],
+            ..proto::ChatItem::test_data()
+        }
+    }
+
+    #[test]
+    fn revisions_are_summarized_by_default() {
+        let item: ChatItemData<Store> = chat_item_with_revisions(2, 10)
+            .try_into_with(&TestContext::default())
+            .expect("valid");
+        let summary = || RevisionSummary {
+            author: TestContext::contact_recipient().clone(),
+            sent_at: Timestamp::test_value(),
+            direction: DirectionDiscriminants::Incoming,
+            message: ChatItemMessageDiscriminants::Standard,
+        };
+        assert_eq!(
+            item.revisions,
+            Revisions::Summaries(vec![summary(), summary()])
+        );
+    }
+
+    #[test]
+    fn full_revisions_can_be_kept() {
+        let item: ChatItemData<Store> = chat_item_with_revisions(2, 10)
+            .try_into_with(&context_with_revision_storage(RevisionStorage::Full))
+            .expect("valid");
+        let revisions = assert_matches!(item.revisions, Revisions::Full(revisions) => revisions);
+        assert_eq!(revisions.len(), 2);
+        for revision in revisions {
+            assert_matches!(
+                revision.message,
+                ChatItemMessage::Standard(StandardMessage { text: Some(text), .. })
+                    if text.text.len() == 10
+            );
+        }
+    }
+
+    #[test]
+    fn summarized_revisions_dont_grow_with_revision_size() {
+        const REVISION_COUNT: usize = 1_000;
+        const TEXT_LEN: usize = 1_000;
+        let item = chat_item_with_revisions(REVISION_COUNT, TEXT_LEN);
+
+        let full: ChatItemData<Store> = item
+            .clone()
+            .try_into_with(&context_with_revision_storage(RevisionStorage::Full))
+            .expect("valid");
+        let full_text_len: usize =
+            assert_matches!(&full.revisions, Revisions::Full(revisions) => revisions)
+                .iter()
+                .map(|revision| match &revision.message {
+                    ChatItemMessage::Standard(message) => {
+                        message.text.as_ref().map_or(0, |text| text.text.len())
+                    }
+                    _ => 0,
+                })
+                .sum();
+        assert_eq!(full_text_len, REVISION_COUNT * TEXT_LEN);
+
+        let summarized: ChatItemData<Store> =
+            item.try_into_with(&TestContext::default()).expect("valid");
+        let summaries =
+            assert_matches!(summarized.revisions, Revisions::Summaries(summaries) => summaries);
+        assert_eq!(summaries.len(), REVISION_COUNT);
+        // A summary owns nothing on the heap (the author is shared), so the whole
+        // list costs a small, fixed amount per revision no matter how large each
+        // revision was.
+        assert!(
+            std::mem::size_of_val(summaries.as_slice()) < REVISION_COUNT * TEXT_LEN / 10,
+            "{} bytes of summaries",
+            std::mem::size_of_val(summaries.as_slice())
+        );
+    }
+
     #[test_case(Purpose::DeviceTransfer, 3600, Ok(()))]
     #[test_case(Purpose::RemoteBackup, 86400, Ok(()))]
     #[test_case(
@@ -1004,6 +1493,11 @@ mod test {
             backup_time,
             purpose: backup_purpose,
             version: 0,
+            unknown_fields: Default::default(),
+            frame_size_limits: Default::default(),
+            validation_time: backup_time,
+            backup_time_policy: Default::default(),
+            revision_storage: Default::default(),
         };
 
         let mut item = proto::ChatItem::test_data();
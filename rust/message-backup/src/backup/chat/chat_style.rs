@@ -2,20 +2,21 @@
 // SPDX-License-Identifier: AGPL-3.0-only
 //
 
-use std::collections::BTreeMap;
 use std::fmt::Debug;
+use std::sync::Arc;
 
 use derive_where::derive_where;
 use itertools::Itertools as _;
 
 use crate::backup::file::{FilePointer, FilePointerError};
-use crate::backup::method::{Lookup, Method};
+use crate::backup::method::{Lookup, Method, Store};
 use crate::backup::serialize::{SerializeOrder, UnorderedList};
-use crate::backup::{serialize, ReferencedTypes, TryFromWith, TryIntoWith as _};
+use crate::backup::{serialize, ProtoContext, ReferencedTypes, TryFromWith, TryIntoWith as _};
 use crate::proto::backup as proto;
 
 #[derive(serde::Serialize)]
 #[derive_where(Debug)]
+#[derive_where(Clone; Wallpaper<M>: Clone)]
 #[cfg_attr(test, derive_where(PartialEq; M::CustomColorReference: PartialEq, Wallpaper<M>: PartialEq))]
 pub struct ChatStyle<M: Method + ReferencedTypes> {
     #[serde(bound(serialize = "Wallpaper<M>: serde::Serialize"))]
@@ -26,6 +27,7 @@ pub struct ChatStyle<M: Method + ReferencedTypes> {
 
 #[derive(serde::Serialize)]
 #[derive_where(Debug)]
+#[derive_where(Clone; M::BoxedValue<FilePointer>: Clone)]
 #[cfg_attr(test, derive_where(PartialEq; M::BoxedValue<FilePointer>: PartialEq))]
 pub enum Wallpaper<M: Method> {
     Preset(WallpaperPreset),
@@ -42,13 +44,13 @@ impl TryFrom<u32> for Color {
 
     fn try_from(value: u32) -> Result<Self, Self::Error> {
         if value >> 24 != 0xFF {
-            return Err(ChatStyleError::ChatColorNotOpaque(value));
+            return Err(ChatStyleError::UnexpectedAlpha(value));
         }
         Ok(Self(value))
     }
 }
 
-#[derive(Debug, serde::Serialize)]
+#[derive(Clone, Debug, serde::Serialize)]
 #[cfg_attr(test, derive(PartialEq))]
 pub struct WallpaperPreset {
     /// Guaranteed to not be [`proto::chat_style::WallpaperPreset::UNKNOWN_WALLPAPER_PRESET`].
@@ -56,7 +58,7 @@ pub struct WallpaperPreset {
     enum_value: proto::chat_style::WallpaperPreset,
 }
 
-#[derive(Debug, serde::Serialize)]
+#[derive(Clone, Debug, serde::Serialize)]
 #[cfg_attr(test, derive(PartialEq))]
 pub enum BubbleColor<CustomColor> {
     Preset(BubbleColorPreset),
@@ -69,6 +71,9 @@ pub enum BubbleColor<CustomColor> {
 pub struct BubbleGradientColor {
     pub color: Color,
     /// guaranteed to be in the range `[0, 1]`
+    ///
+    /// Serialized as a string since floats don't have a canonical representation.
+    #[serde(serialize_with = "serialize::to_string")]
     position: f32,
 }
 
@@ -80,11 +85,10 @@ impl SerializeOrder for BubbleGradientColor {
     }
 }
 
-#[derive(Debug, serde::Serialize)]
+#[derive(Clone, Debug, serde::Serialize)]
 #[cfg_attr(test, derive(PartialEq))]
 pub struct BubbleColorPreset {
     /// Guaranteed to not be [`proto::chat_style::BubbleColorPreset::UNKNOWN_BUBBLE_COLOR_PRESET`].
-    #[allow(unused)]
     #[serde(serialize_with = "serialize::enum_as_string")]
     enum_value: proto::chat_style::BubbleColorPreset,
 }
@@ -96,6 +100,7 @@ pub struct CustomColorId(pub(crate) u64);
 #[cfg_attr(test, derive(PartialEq))]
 pub enum CustomChatColor {
     Gradient {
+        /// guaranteed to be in the range `[0, 360]`
         angle: u32,
         colors: UnorderedList<BubbleGradientColor>,
     },
@@ -129,7 +134,7 @@ impl<M: ReferencedTypes> serde::Serialize for CustomColorMap<M> {
     where
         S: serde::Serializer,
     {
-        BTreeMap::from_iter(self.0.iter().map(|(id, data)| (*id, data))).serialize(serializer)
+        serialize::map_as_sorted_entries(self.0.iter().map(|(id, data)| (id, data)), serializer)
     }
 }
 
@@ -153,15 +158,19 @@ pub enum ChatStyleError {
     UnknownPresetBubbleColor,
     /// found 0 colors and 0 positions in gradient
     GradientEmpty,
-    /// chat color was not opaque (ARGB 0x{0:08X})
-    ChatColorNotOpaque(u32),
+    /// found {0} colors in gradient, but only 2 to 4 are permitted
+    UnsupportedGradientLength(usize),
+    /// gradient angle {0} is not in the range 0 to 360
+    GradientAngleOutOfRange(u32),
+    /// chat color had an alpha channel other than 0xFF (ARGB 0x{0:08X})
+    UnexpectedAlpha(u32),
     /// bubble gradient position is invalid: {0}
     InvalidBubbleGradientPosition(f32),
-    /// only simple gradients from 0.0 to 1.0 are permitted
-    UnsupportedGradient,
+    /// bubble gradient positions are not strictly increasing
+    GradientPositionsNotIncreasing,
     /// referenced unknown custom color ID {0:?}
     UnknownCustomColorId(u64),
-    /// duplicate custom color ID {0}
+    /// custom color ID {0} is defined more than once with different contents
     DuplicateCustomChatColorId(u64),
 }
 
@@ -169,7 +178,30 @@ impl<M: ReferencedTypes> TryFrom<Vec<proto::chat_style::CustomChatColor>> for Cu
     type Error = ChatStyleError;
 
     fn try_from(value: Vec<proto::chat_style::CustomChatColor>) -> Result<Self, Self::Error> {
-        value
+        // Compare the protos themselves, before any conversion, so that only exact copies count as
+        // duplicates.
+        let mut unique_colors =
+            Vec::<proto::chat_style::CustomChatColor>::with_capacity(value.len());
+        for custom_color in value {
+            match unique_colors
+                .iter()
+                .find(|existing| existing.id == custom_color.id)
+            {
+                None => unique_colors.push(custom_color),
+                Some(existing) if *existing == custom_color => {
+                    log::warn!(
+                        "custom chat color {} is defined more than once with identical contents; \
+                         the exporter should only include it once",
+                        custom_color.id
+                    );
+                }
+                Some(_) => {
+                    return Err(ChatStyleError::DuplicateCustomChatColorId(custom_color.id));
+                }
+            }
+        }
+
+        unique_colors
             .into_iter()
             .try_fold(Self::default(), |mut colors, custom_color| {
                 let (id, custom_color) = TryFrom::try_from(custom_color)?;
@@ -292,30 +324,41 @@ impl TryFrom<proto::chat_style::custom_chat_color::Color> for CustomChatColor {
                     special_fields: _,
                 } = gradient;
 
+                if angle > 360 {
+                    return Err(ChatStyleError::GradientAngleOutOfRange(angle));
+                }
+
                 let color_count = colors.len();
                 let position_count = positions.len();
-                let colors = match color_count {
+                match color_count {
                     _ if color_count != position_count => {
                         return Err(ChatStyleError::GradientLengthMismatch {
                             color_count,
                             position_count,
                         });
                     }
-                    0 => {
-                        return Err(ChatStyleError::GradientEmpty);
-                    }
-                    2 if positions == [0.0, 1.0] || positions == [1.0, 0.0] => colors
-                        .into_iter()
-                        .zip(positions)
-                        .map(|(color, position)| BubbleGradientColor::new(color, position))
-                        .try_collect()?,
-                    _ => {
-                        // For now, only allow simple gradients with exactly two colors, at 0.0 and 1.0.
-                        return Err(ChatStyleError::UnsupportedGradient);
-                    }
-                };
-
-                CustomChatColor::Gradient { angle, colors }
+                    0 => return Err(ChatStyleError::GradientEmpty),
+                    2..=4 => {}
+                    _ => return Err(ChatStyleError::UnsupportedGradientLength(color_count)),
+                }
+
+                let colors: Vec<_> = colors
+                    .into_iter()
+                    .zip(positions)
+                    .map(|(color, position)| BubbleGradientColor::new(color, position))
+                    .try_collect()?;
+                if !colors
+                    .iter()
+                    .tuple_windows()
+                    .all(|(a, b)| a.position < b.position)
+                {
+                    return Err(ChatStyleError::GradientPositionsNotIncreasing);
+                }
+
+                CustomChatColor::Gradient {
+                    angle,
+                    colors: colors.into(),
+                }
             }
             ColorProto::Solid(color) => CustomChatColor::Solid {
                 color: Color::try_from(color)?,
@@ -399,15 +442,88 @@ impl BubbleGradientColor {
     }
 }
 
+impl ChatStyle<Store> {
+    pub(crate) fn to_proto(&self, context: &ProtoContext) -> proto::ChatStyle {
+        let Self {
+            wallpaper,
+            bubble_color,
+            dim_wallpaper_in_dark_mode,
+        } = self;
+
+        let wallpaper = wallpaper.as_ref().map(|wallpaper| match wallpaper {
+            Wallpaper::Preset(preset) => {
+                proto::chat_style::Wallpaper::WallpaperPreset(preset.enum_value.into())
+            }
+            Wallpaper::Photo(photo) => {
+                proto::chat_style::Wallpaper::WallpaperPhoto(photo.to_proto())
+            }
+        });
+
+        let bubble_color = {
+            use proto::chat_style::BubbleColor as BubbleColorProto;
+            match bubble_color {
+                BubbleColor::Preset(preset) => {
+                    BubbleColorProto::BubbleColorPreset(preset.enum_value.into())
+                }
+                BubbleColor::Custom(color) => {
+                    BubbleColorProto::CustomColorId(context.custom_color_id(color))
+                }
+                BubbleColor::Auto => BubbleColorProto::AutoBubbleColor(Default::default()),
+            }
+        };
+
+        proto::ChatStyle {
+            wallpaper,
+            bubbleColor: Some(bubble_color),
+            dimWallpaperInDarkMode: *dim_wallpaper_in_dark_mode,
+            special_fields: Default::default(),
+        }
+    }
+}
+
+impl CustomColorMap<Store> {
+    /// The colors in the order they were read.
+    pub(crate) fn iter(&self) -> impl Iterator<Item = (CustomColorId, &Arc<CustomChatColor>)> {
+        self.0.iter().map(|(id, color)| (*id, color))
+    }
+
+    pub(crate) fn to_proto(&self) -> Vec<proto::chat_style::CustomChatColor> {
+        self.iter()
+            .map(|(id, color)| proto::chat_style::CustomChatColor {
+                id: id.0,
+                color: Some(color.to_proto()),
+                special_fields: Default::default(),
+            })
+            .collect()
+    }
+}
+
+impl CustomChatColor {
+    fn to_proto(&self) -> proto::chat_style::custom_chat_color::Color {
+        use proto::chat_style::custom_chat_color::Color as ColorProto;
+
+        match self {
+            CustomChatColor::Gradient { angle, colors } => {
+                ColorProto::Gradient(proto::chat_style::Gradient {
+                    angle: *angle,
+                    colors: colors.0.iter().map(|color| color.color.0).collect(),
+                    positions: colors.0.iter().map(|color| color.position).collect(),
+                    special_fields: Default::default(),
+                })
+            }
+            CustomChatColor::Solid { color } => ColorProto::Solid(color.0),
+        }
+    }
+}
+
 #[cfg(test)]
 mod test {
-    use std::sync::Arc;
-
+    use assert_matches::assert_matches;
     use test_case::test_case;
 
     use super::*;
     use crate::backup::chat::chat_style::Color;
-    use crate::backup::method::Store;
+    use crate::backup::file::{AttachmentLocatorError, MessageAttachment};
     use crate::backup::testutil::TestContext;
 
     impl proto::ChatStyle {
@@ -481,7 +597,7 @@ mod test {
             proto::chat_style::custom_chat_color::Color::Gradient(proto::chat_style::Gradient {
                 angle: 123,
                 colors: vec![0xFF005555, 0xFF009999],
-                positions: vec![1.0, 0.0],
+                positions: vec![0.0, 1.0],
                 special_fields: Default::default(),
             })
             .try_into(),
@@ -490,11 +606,11 @@ mod test {
                 colors: vec![
                     BubbleGradientColor {
                         color: Color(0xFF005555),
-                        position: 1.0,
+                        position: 0.0,
                     },
                     BubbleGradientColor {
                         color: Color(0xFF009999),
-                        position: 0.0,
+                        position: 1.0,
                     },
                 ]
                 .into(),
@@ -502,6 +618,21 @@ mod test {
         );
     }
 
+    #[test_case(&[0.0, 1.0], 0; "two colors")]
+    #[test_case(&[0.0, 0.5, 1.0], 180; "three colors")]
+    #[test_case(&[0.1, 0.2, 0.3, 0.9], 360; "four colors, not at the ends")]
+    fn valid_multi_color_gradient(positions: &[f32], angle: u32) {
+        let result = CustomChatColor::try_from(
+            proto::chat_style::custom_chat_color::Color::Gradient(proto::chat_style::Gradient {
+                angle,
+                colors: vec![0xFF005555; positions.len()],
+                positions: positions.to_vec(),
+                special_fields: Default::default(),
+            }),
+        );
+        assert_matches!(result, Ok(CustomChatColor::Gradient { colors, .. }) if colors.0.len() == positions.len());
+    }
+
     #[test]
     fn valid_custom_chat_color() {
         assert_eq!(
@@ -530,22 +661,70 @@ mod test {
             ..Default::default()
         });
     }
-    fn complex_gradient(proto: &mut proto::chat_style::CustomChatColor) {
+    fn single_color_gradient(proto: &mut proto::chat_style::CustomChatColor) {
+        proto.set_gradient(proto::chat_style::Gradient {
+            colors: vec![0xFFFFFFFF],
+            positions: vec![0.0],
+            ..Default::default()
+        });
+    }
+    fn five_color_gradient(proto: &mut proto::chat_style::CustomChatColor) {
+        proto.set_gradient(proto::chat_style::Gradient {
+            colors: vec![0xFFFFFFFF; 5],
+            positions: vec![0.0, 0.25, 0.5, 0.75, 1.0],
+            ..Default::default()
+        });
+    }
+    fn reversed_gradient(proto: &mut proto::chat_style::CustomChatColor) {
+        proto.set_gradient(proto::chat_style::Gradient {
+            colors: vec![0xFFFFFFFF, 0xFF000000],
+            positions: vec![1.0, 0.0],
+            ..Default::default()
+        });
+    }
+    fn repeated_gradient_position(proto: &mut proto::chat_style::CustomChatColor) {
         proto.set_gradient(proto::chat_style::Gradient {
             colors: vec![0xFFFFFFFF, 0xFF000000, 0xFFFFFFFF],
-            positions: vec![0.0, 0.5, 1.0],
+            positions: vec![0.0, 0.5, 0.5],
+            ..Default::default()
+        });
+    }
+    fn gradient_angle_too_large(proto: &mut proto::chat_style::CustomChatColor) {
+        proto.set_gradient(proto::chat_style::Gradient {
+            angle: 361,
+            colors: vec![0xFFFFFFFF, 0xFF000000],
+            positions: vec![0.0, 1.0],
+            ..Default::default()
+        });
+    }
+    fn non_opaque_gradient_color(proto: &mut proto::chat_style::CustomChatColor) {
+        proto.set_gradient(proto::chat_style::Gradient {
+            colors: vec![0xFFFFFFFF, 0x80000000],
+            positions: vec![0.0, 1.0],
             ..Default::default()
         });
     }
     fn non_opaque_color(proto: &mut proto::chat_style::CustomChatColor) {
         proto.set_solid(0);
     }
+    fn translucent_color(proto: &mut proto::chat_style::CustomChatColor) {
+        proto.set_solid(0xFE123456);
+    }
 
     #[test_case(uneven_gradient, ChatStyleError::GradientLengthMismatch {color_count: 3, position_count: 2})]
     #[test_case(empty_gradient, ChatStyleError::GradientEmpty)]
-    #[test_case(complex_gradient, ChatStyleError::UnsupportedGradient)]
-    #[test_case(invalid_gradient_position, ChatStyleError::UnsupportedGradient)]
-    #[test_case(non_opaque_color, ChatStyleError::ChatColorNotOpaque(0))]
+    #[test_case(single_color_gradient, ChatStyleError::UnsupportedGradientLength(1))]
+    #[test_case(five_color_gradient, ChatStyleError::UnsupportedGradientLength(5))]
+    #[test_case(invalid_gradient_position, ChatStyleError::InvalidBubbleGradientPosition(-1.0))]
+    #[test_case(reversed_gradient, ChatStyleError::GradientPositionsNotIncreasing)]
+    #[test_case(
+        repeated_gradient_position,
+        ChatStyleError::GradientPositionsNotIncreasing
+    )]
+    #[test_case(gradient_angle_too_large, ChatStyleError::GradientAngleOutOfRange(361))]
+    #[test_case(non_opaque_gradient_color, ChatStyleError::UnexpectedAlpha(0x80000000))]
+    #[test_case(non_opaque_color, ChatStyleError::UnexpectedAlpha(0))]
+    #[test_case(translucent_color, ChatStyleError::UnexpectedAlpha(0xFE123456))]
     fn custom_color(
         modifier: fn(&mut proto::chat_style::CustomChatColor),
         expected_err: ChatStyleError,
@@ -577,6 +756,28 @@ mod test {
         Err(ChatStyleError::WallpaperPhoto(FilePointerError::NoLocator));
         "invalid wallpaper photo"
     )]
+    #[test_case(
+        |x| x.set_wallpaperPhoto(proto::FilePointer {
+            locator: Some(proto::file_pointer::Locator::AttachmentLocator(
+                proto::file_pointer::AttachmentLocator::test_data(),
+            )),
+            ..proto::FilePointer::test_data()
+        }) => Ok(());
+        "wallpaper photo with transit locator"
+    )]
+    #[test_case(
+        |x| x.set_wallpaperPhoto(proto::FilePointer {
+            locator: Some(proto::file_pointer::Locator::BackupLocator(
+                proto::file_pointer::BackupLocator {
+                    key: vec![],
+                    ..proto::file_pointer::BackupLocator::test_data()
+                },
+            )),
+            ..proto::FilePointer::test_data()
+        }) =>
+        Err(ChatStyleError::WallpaperPhoto(FilePointerError::Locator(AttachmentLocatorError::MissingKey)));
+        "wallpaper photo with invalid locator"
+    )]
     #[test_case(
         |x| x.set_bubbleColorPreset(proto::chat_style::BubbleColorPreset::UNKNOWN_BUBBLE_COLOR_PRESET) =>
         Err(ChatStyleError::UnknownPresetBubbleColor);
@@ -590,6 +791,71 @@ mod test {
             .map(|_: ChatStyle<Store>| ())
     }
 
+    #[test]
+    fn wallpaper_photo_serializes_like_message_attachment() {
+        let pointer = || proto::FilePointer {
+            locator: Some(proto::file_pointer::Locator::BackupLocator(
+                proto::file_pointer::BackupLocator::test_data(),
+            )),
+            ..proto::FilePointer::test_data()
+        };
+
+        let wallpaper =
+            Wallpaper::<Store>::try_from(proto::chat_style::Wallpaper::WallpaperPhoto(pointer()))
+                .expect("valid");
+        let attachment = MessageAttachment::try_from(proto::MessageAttachment {
+            pointer: Some(pointer()).into(),
+            ..Default::default()
+        })
+        .expect("valid");
+
+        assert_eq!(
+            serde_json::to_value(wallpaper).expect("valid"),
+            serde_json::json!({
+                "Photo": serde_json::to_value(attachment.pointer).expect("valid"),
+            }),
+        );
+    }
+
+    #[test]
+    fn custom_color_map_drops_identical_duplicates() {
+        let colors = vec![
+            proto::chat_style::CustomChatColor::test_data(),
+            proto::chat_style::CustomChatColor {
+                id: 12345,
+                ..proto::chat_style::CustomChatColor::test_data()
+            },
+            proto::chat_style::CustomChatColor::test_data(),
+        ];
+        let map = CustomColorMap::<Store>::try_from(colors).expect("valid");
+        assert_eq!(
+            map.0.iter().map(|(id, _)| *id).collect::<Vec<_>>(),
+            [
+                proto::chat_style::CustomChatColor::TEST_ID,
+                CustomColorId(12345)
+            ]
+        );
+    }
+
+    #[test]
+    fn custom_color_map_rejects_conflicting_duplicates() {
+        let colors = vec![
+            proto::chat_style::CustomChatColor::test_data(),
+            proto::chat_style::CustomChatColor {
+                color: Some(proto::chat_style::custom_chat_color::Color::Solid(
+                    0xFF654321,
+                )),
+                ..proto::chat_style::CustomChatColor::test_data()
+            },
+        ];
+        assert_eq!(
+            CustomColorMap::<Store>::try_from(colors).map(|_| ()),
+            Err(ChatStyleError::DuplicateCustomChatColorId(
+                proto::chat_style::CustomChatColor::TEST_ID.0
+            ))
+        );
+    }
+
     #[test]
     fn custom_color_map_sorts_when_serializing() {
         let color1 = Arc::new(CustomChatColor::Solid { color: Color(100) });
@@ -10,9 +10,9 @@ use crate::backup::chat::{ChatItemError, ReactionSet};
 use crate::backup::file::{FilePointer, FilePointerError};
 use crate::backup::frame::RecipientId;
 use crate::backup::method::LookupPair;
-use crate::backup::recipient::DestinationKind;
+use crate::backup::recipient::{DestinationKind, FullRecipientData};
 use crate::backup::serialize::SerializeOrder;
-use crate::backup::{TryFromWith, TryIntoWith as _};
+use crate::backup::{ProtoContext, TryFromWith, TryIntoWith as _};
 use crate::proto::backup as proto;
 
 /// Validated version of [`proto::ContactMessage`].
@@ -168,13 +168,52 @@ impl TryFrom<proto::ContactAttachment> for ContactAttachment {
     }
 }
 
+impl ContactMessage<FullRecipientData> {
+    pub(crate) fn to_proto(&self, context: &ProtoContext) -> proto::ContactMessage {
+        let Self {
+            contacts,
+            reactions,
+            _limit_construction_to_module: _,
+        } = self;
+
+        proto::ContactMessage {
+            contact: contacts.iter().map(ContactAttachment::to_proto).collect(),
+            reactions: reactions.to_proto(context),
+            special_fields: Default::default(),
+        }
+    }
+}
+
+impl ContactAttachment {
+    fn to_proto(&self) -> proto::ContactAttachment {
+        let Self {
+            name,
+            number,
+            email,
+            address,
+            organization,
+            avatar,
+            _limit_construction_to_module: _,
+        } = self;
+
+        proto::ContactAttachment {
+            name: name.clone().into(),
+            number: number.clone(),
+            email: email.clone(),
+            address: address.clone(),
+            organization: organization.clone(),
+            avatar: avatar.as_ref().map(FilePointer::to_proto).into(),
+            special_fields: Default::default(),
+        }
+    }
+}
+
 #[cfg(test)]
 mod test {
     use test_case::test_case;
 
     use super::*;
     use crate::backup::chat::{Reaction, ReactionError};
-    use crate::backup::recipient::FullRecipientData;
     use crate::backup::testutil::TestContext;
 
     impl proto::ContactMessage {
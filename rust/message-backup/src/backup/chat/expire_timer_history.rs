@@ -0,0 +1,119 @@
+//
+// Copyright (C) 2024 Signal Messenger, LLC.
+// SPDX-License-Identifier: AGPL-3.0-only
+//
+
+use crate::backup::chat::{ChatItemData, ChatItemMessage, UpdateMessage};
+use crate::backup::method::Method;
+use crate::backup::time::{Duration, Timestamp};
+use crate::backup::ReferencedTypes;
+
+/// The expiration timer updates in a chat, to check against its expireTimerVersion.
+///
+/// Clients bump a chat's timer version every time its timer changes, so a chat can't have had more
+/// changes than its version. (It can have fewer, since the updates recording older changes may
+/// have been deleted.)
+#[derive(Clone, Debug, Default)]
+#[cfg_attr(test, derive(PartialEq))]
+pub(crate) struct ExpireTimerHistory {
+    /// The number of expiration timer updates seen.
+    pub changes: u32,
+    /// The most recently sent of those updates, and the timer it set.
+    pub latest: Option<(Timestamp, Duration)>,
+}
+
+impl ExpireTimerHistory {
+    /// Records `item` if it's an expiration timer update.
+    pub(super) fn add_item<M: Method + ReferencedTypes>(&mut self, item: &ChatItemData<M>) {
+        // Revisions aren't looked at: updates can't be edited.
+        let ChatItemMessage::Update(UpdateMessage::ExpirationTimerChange { expires_in }) =
+            item.message
+        else {
+            return;
+        };
+
+        self.changes = self.changes.saturating_add(1);
+        // Items aren't necessarily exported in the order they were sent, so the latest update is
+        // the one with the greatest timestamp, not the last one added.
+        if self
+            .latest
+            .map_or(true, |(latest_sent_at, _)| latest_sent_at <= item.sent_at)
+        {
+            self.latest = Some((item.sent_at, expires_in));
+        }
+    }
+
+    /// Whether the updates imply a higher version than `expire_timer_version`.
+    ///
+    /// A version of 0 means the exporting client didn't track versions, so it never conflicts.
+    pub(super) fn conflicts_with(&self, expire_timer_version: u32) -> bool {
+        expire_timer_version != 0 && self.changes > expire_timer_version
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use test_case::test_case;
+
+    use super::*;
+    use crate::backup::method::Store;
+    use crate::backup::testutil::TestContext;
+    use crate::backup::TryIntoWith as _;
+    use crate::proto::backup as proto;
+
+    fn timer_update(sent_at: u64, expires_in_ms: u64) -> ChatItemData<Store> {
+        let mut item = proto::ChatItem {
+            directionalDetails: Some(
+                proto::chat_item::DirectionlessMessageDetails::default().into(),
+            ),
+            dateSent: sent_at,
+            ..proto::ChatItem::test_data()
+        };
+        item.set_updateMessage(proto::ChatUpdateMessage {
+            update: Some(proto::chat_update_message::Update::ExpirationTimerChange(
+                proto::ExpirationTimerChatUpdate {
+                    expiresInMs: expires_in_ms,
+                    ..Default::default()
+                },
+            )),
+            ..Default::default()
+        });
+        item.try_into_with(&TestContext::default()).expect("valid")
+    }
+
+    #[test]
+    fn ignores_other_items() {
+        let mut history = ExpireTimerHistory::default();
+        let item: ChatItemData<Store> = proto::ChatItem::test_data()
+            .try_into_with(&TestContext::default())
+            .expect("valid");
+        history.add_item(&item);
+        assert_eq!(history, ExpireTimerHistory::default());
+    }
+
+    #[test_case(&[(1, 1000), (2, 0)] => Some((2, Duration::ZERO)); "in order")]
+    #[test_case(&[(2, 0), (1, 1000)] => Some((2, Duration::ZERO)); "out of order")]
+    #[test_case(&[(1, 1000), (1, 0)] => Some((1, Duration::ZERO)); "same timestamp")]
+    fn latest_is_by_sent_at(updates: &[(u64, u64)]) -> Option<(u64, Duration)> {
+        let mut history = ExpireTimerHistory::default();
+        for &(sent_at, expires_in_ms) in updates {
+            history.add_item(&timer_update(sent_at, expires_in_ms));
+        }
+        assert_eq!(history.changes, 2);
+        history
+            .latest
+            .map(|(sent_at, expires_in)| (sent_at.as_millis(), expires_in))
+    }
+
+    #[test_case(0, 0 => false)]
+    #[test_case(1, 0 => false)]
+    #[test_case(2, 2 => false)]
+    #[test_case(3, 2 => true)]
+    fn conflicts_with(changes: u32, expire_timer_version: u32) -> bool {
+        ExpireTimerHistory {
+            changes,
+            latest: None,
+        }
+        .conflicts_with(expire_timer_version)
+    }
+}
@@ -113,6 +113,32 @@ impl TryFrom<proto::GiftBadge> for GiftBadge {
     }
 }
 
+impl GiftBadge {
+    pub(crate) fn to_proto(&self) -> proto::GiftBadge {
+        use proto::gift_badge::State;
+        let (receipt_credential_presentation, state) = match self {
+            Self::Valid {
+                receipt_credential_presentation,
+                state,
+            } => (
+                zkgroup::serialize(receipt_credential_presentation),
+                match state {
+                    GiftBadgeState::Unopened => State::UNOPENED,
+                    GiftBadgeState::Opened => State::OPENED,
+                    GiftBadgeState::Redeemed => State::REDEEMED,
+                },
+            ),
+            Self::Failed => (vec![], State::FAILED),
+        };
+
+        proto::GiftBadge {
+            receiptCredentialPresentation: receipt_credential_presentation,
+            state: state.into(),
+            special_fields: Default::default(),
+        }
+    }
+}
+
 #[cfg(test)]
 mod test {
     use test_case::test_case;
@@ -13,6 +13,7 @@ use itertools::Itertools as _;
 use libsignal_core::{Aci, Pni, ServiceId};
 use macro_rules_attribute::macro_rules_derive;
 use protobuf::{EnumOrUnknown, Message};
+use uuid::Uuid;
 
 use crate::backup::serialize::UnorderedList;
 use crate::backup::time::Duration;
@@ -33,7 +34,7 @@ use crate::proto::backup::{
     GroupV2MigrationUpdate, SelfInvitedOtherUserToGroupUpdate, SelfInvitedToGroupUpdate,
 };
 
-/// Implements `TryFrom<$MESSAGE>` for [`GroupChatUpdate`].
+/// Implements `TryFrom<$MESSAGE>` for [`GroupChatUpdate`], and the conversion back.
 ///
 /// This is a custom derive macro (applied via [`macro_rules_derive`]) that is
 /// applied to the enum type `GroupChatUpdate`. It assumes each variant has
@@ -42,6 +43,10 @@ use crate::proto::backup::{
 /// that calls [`ValidateFrom::validate_from`] for each field in sequence. If
 /// they all succeed, the appropriate variant of `GroupChatUpdate` is produced.
 /// Otherwise the first error is returned.
+///
+/// It also generates `GroupChatUpdate::to_proto`, which calls
+/// [`ToProtoField::to_proto_field`] for each field of a variant to rebuild its
+/// proto message.
 macro_rules! TryFromProto {
     ($( #[$attrs:meta] )*
     pub enum GroupChatUpdate { $(
@@ -54,6 +59,21 @@ macro_rules! TryFromProto {
     )* }) => {
         // Expand using the next match for each enum variant.
         $(TryFromProto!($VariantName, $($($field),*)? );)*
+
+        impl GroupChatUpdate {
+            #[allow(non_snake_case)]
+            pub(crate) fn to_proto(&self) -> proto::group_change_chat_update::update::Update {
+                use proto::group_change_chat_update::update::Update;
+                match self { $(
+                    GroupChatUpdate::$VariantName $({ $($field),* })? => {
+                        Update::$VariantName($VariantName {
+                            $($($field: ToProtoField::to_proto_field($field),)*)?
+                            special_fields: Default::default(),
+                        })
+                    }
+                )* }
+            }
+        }
     };
     ($MESSAGE:ident, $($field:ident),* ) => {
         impl TryFrom<$MESSAGE> for GroupChatUpdate {
@@ -384,6 +404,81 @@ impl ValidateFrom<u64> for Duration {
     }
 }
 
+/// Module-private inverse of [`ValidateFrom`], producing the proto field a value
+/// was validated from.
+trait ToProtoField {
+    type Proto;
+    fn to_proto_field(&self) -> Self::Proto;
+}
+
+impl ToProtoField for Option<Aci> {
+    type Proto = Option<Vec<u8>>;
+    fn to_proto_field(&self) -> Self::Proto {
+        self.as_ref().map(Aci::to_proto_field)
+    }
+}
+
+impl ToProtoField for Aci {
+    type Proto = Vec<u8>;
+    fn to_proto_field(&self) -> Self::Proto {
+        Uuid::from(*self).into_bytes().to_vec()
+    }
+}
+
+impl ToProtoField for ServiceId {
+    type Proto = Vec<u8>;
+    fn to_proto_field(&self) -> Self::Proto {
+        self.service_id_binary()
+    }
+}
+
+impl<T: Clone> ToProtoField for NoValidation<T> {
+    type Proto = T;
+    fn to_proto_field(&self) -> Self::Proto {
+        self.0.clone()
+    }
+}
+
+impl ToProtoField for AccessLevel {
+    type Proto = EnumOrUnknown<proto::GroupV2AccessLevel>;
+    fn to_proto_field(&self) -> Self::Proto {
+        match self {
+            AccessLevel::Any => proto::GroupV2AccessLevel::ANY,
+            AccessLevel::Member => proto::GroupV2AccessLevel::MEMBER,
+            AccessLevel::Administrator => proto::GroupV2AccessLevel::ADMINISTRATOR,
+        }
+        .into()
+    }
+}
+
+impl ToProtoField for UnorderedList<Invitee> {
+    type Proto = Vec<proto::group_invitation_revoked_update::Invitee>;
+    fn to_proto_field(&self) -> Self::Proto {
+        self.0
+            .iter()
+            .map(
+                |Invitee {
+                     inviter,
+                     invitee_aci,
+                     invitee_pni,
+                 }| group_invitation_revoked_update::Invitee {
+                    inviterAci: inviter.to_proto_field(),
+                    inviteeAci: invitee_aci.to_proto_field(),
+                    inviteePni: invitee_pni.map(|pni| Uuid::from(pni).into_bytes().to_vec()),
+                    special_fields: Default::default(),
+                },
+            )
+            .collect()
+    }
+}
+
+impl ToProtoField for Duration {
+    type Proto = u64;
+    fn to_proto_field(&self) -> Self::Proto {
+        self.as_millis()
+    }
+}
+
 impl TryFrom<proto::group_invitation_revoked_update::Invitee> for Invitee {
     type Error = InviteeError;
 
@@ -55,6 +55,27 @@ impl TryFrom<proto::LinkPreview> for LinkPreview {
     }
 }
 
+impl LinkPreview {
+    pub(crate) fn to_proto(&self) -> proto::LinkPreview {
+        let Self {
+            url,
+            title,
+            image,
+            description,
+            date,
+        } = self;
+
+        proto::LinkPreview {
+            url: url.clone(),
+            title: title.clone(),
+            image: image.as_ref().map(FilePointer::to_proto).into(),
+            description: description.clone(),
+            date: date.map(|date| date.as_millis()),
+            special_fields: Default::default(),
+        }
+    }
+}
+
 #[cfg(test)]
 mod test {
     use test_case::test_case;
@@ -0,0 +1,117 @@
+// Copyright (C) 2024 Signal Messenger, LLC.
+// SPDX-License-Identifier: AGPL-3.0-only
+//
+
+use crate::backup::chat::{ChatItemData, ChatItemMessage};
+use crate::backup::file::{AttachmentLocator, FilePointer};
+use crate::backup::method::Method;
+use crate::backup::ReferencedTypes;
+
+/// Totals for the media sent in a chat, as shown in a conversation's details.
+///
+/// Only attachments sent as messages in their own right are counted; stickers, link preview
+/// images, contact avatars, and quote thumbnails are not.
+#[derive(Clone, Debug, Default, serde::Serialize)]
+#[cfg_attr(test, derive(PartialEq))]
+pub struct MediaSummary {
+    pub images: u64,
+    pub videos: u64,
+    pub audio: u64,
+    pub files: u64,
+    /// View-once messages, whose attachments aren't counted as any of the above.
+    pub view_once: u64,
+    /// The sum of the sizes declared by the counted attachments' locators, in bytes.
+    pub total_size: u64,
+}
+
+impl MediaSummary {
+    pub(super) fn add_item<M: Method + ReferencedTypes>(&mut self, item: &ChatItemData<M>) {
+        // Revisions aren't looked at: they're earlier versions of the same message, and carry the
+        // same attachments.
+        match &item.message {
+            ChatItemMessage::Standard(message) => {
+                for attachment in &message.attachments {
+                    self.add_attachment(&attachment.pointer);
+                }
+            }
+            ChatItemMessage::Voice(message) => {
+                self.audio += 1;
+                self.total_size += declared_size(&message.attachment.pointer);
+            }
+            ChatItemMessage::ViewOnce(message) => {
+                self.view_once += 1;
+                if let Some(attachment) = &message.attachment {
+                    self.total_size += declared_size(&attachment.pointer);
+                }
+            }
+            ChatItemMessage::Contact(_)
+            | ChatItemMessage::Sticker(_)
+            | ChatItemMessage::RemoteDeleted
+            | ChatItemMessage::Update(_)
+            | ChatItemMessage::PaymentNotification(_)
+            | ChatItemMessage::GiftBadge(_) => (),
+        }
+    }
+
+    fn add_attachment(&mut self, pointer: &FilePointer) {
+        let top_level_type = pointer
+            .content_type
+            .as_deref()
+            .and_then(|content_type| content_type.split_once('/'))
+            .map(|(top_level_type, _subtype)| top_level_type.to_ascii_lowercase());
+        let count = match top_level_type.as_deref() {
+            Some("image") => &mut self.images,
+            Some("video") => &mut self.videos,
+            Some("audio") => &mut self.audio,
+            _ => &mut self.files,
+        };
+        *count += 1;
+        self.total_size += declared_size(pointer);
+    }
+}
+
+fn declared_size(pointer: &FilePointer) -> u64 {
+    match pointer.locator {
+        AttachmentLocator::Backup { size, .. } | AttachmentLocator::Transit { size, .. } => {
+            size.into()
+        }
+        AttachmentLocator::Invalid => 0,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use test_case::test_case;
+
+    use super::*;
+    use crate::proto::backup as proto;
+
+    #[test_case(Some("image/jpeg") => (1, 0, 0, 0))]
+    #[test_case(Some("IMAGE/GIF") => (1, 0, 0, 0))]
+    #[test_case(Some("video/mp4") => (0, 1, 0, 0))]
+    #[test_case(Some("audio/aac") => (0, 0, 1, 0))]
+    #[test_case(Some("application/pdf") => (0, 0, 0, 1))]
+    #[test_case(Some("image") => (0, 0, 0, 1); "no subtype")]
+    #[test_case(Some("") => (0, 0, 0, 1); "empty")]
+    #[test_case(None => (0, 0, 0, 1); "missing")]
+    fn attachment_class(content_type: Option<&str>) -> (u64, u64, u64, u64) {
+        let mut summary = MediaSummary::default();
+        summary.add_attachment(
+            &FilePointer::try_from(proto::FilePointer {
+                contentType: content_type.map(Into::into),
+                ..proto::FilePointer::minimal_test_data()
+            })
+            .expect("valid"),
+        );
+        let MediaSummary {
+            images,
+            videos,
+            audio,
+            files,
+            view_once,
+            total_size,
+        } = summary;
+        assert_eq!((view_once, total_size), (0, 0));
+        (images, videos, audio, files)
+    }
+}
@@ -49,9 +49,9 @@ pub struct MobAmount(String);
 #[derive(Clone, Debug, serde::Serialize)]
 #[cfg_attr(test, derive(PartialEq))]
 pub enum Identification {
-    #[serde(serialize_with = "serialize::list_of_hex")]
+    #[serde(serialize_with = "serialize::unordered_list_of_hex")]
     Sent { key_images: Vec<Vec<u8>> },
-    #[serde(serialize_with = "serialize::list_of_hex")]
+    #[serde(serialize_with = "serialize::unordered_list_of_hex")]
     Received { public_keys: Vec<Vec<u8>> },
 }
 
@@ -228,6 +228,89 @@ impl TryFrom<proto::payment_notification::transaction_details::FailedTransaction
     }
 }
 
+impl PaymentNotification {
+    pub(crate) fn to_proto(&self) -> proto::PaymentNotification {
+        let Self {
+            amount,
+            fee,
+            note,
+            details,
+        } = self;
+
+        let transaction_details = details.as_ref().map(|details| {
+            use proto::payment_notification::transaction_details::Payment;
+            let payment = match details {
+                TransactionDetails::Transaction(transaction) => {
+                    Payment::Transaction(transaction.to_proto())
+                }
+                TransactionDetails::FailedTransaction(FailedTransaction { reason }) => {
+                    Payment::FailedTransaction(
+                        proto::payment_notification::transaction_details::FailedTransaction {
+                            reason: (*reason).into(),
+                            special_fields: Default::default(),
+                        },
+                    )
+                }
+            };
+            proto::payment_notification::TransactionDetails {
+                payment: Some(payment),
+                special_fields: Default::default(),
+            }
+        });
+
+        proto::PaymentNotification {
+            amountMob: amount.as_ref().map(|amount| amount.0.clone()),
+            feeMob: fee.as_ref().map(|fee| fee.0.clone()),
+            note: note.clone(),
+            transactionDetails: transaction_details.into(),
+            special_fields: Default::default(),
+        }
+    }
+}
+
+impl Transaction {
+    fn to_proto(&self) -> proto::payment_notification::transaction_details::Transaction {
+        use proto::payment_notification::transaction_details::{
+            MobileCoinTxoIdentification, Transaction as TransactionProto,
+        };
+
+        let Self {
+            status,
+            identification,
+            timestamp,
+            block_timestamp,
+            block_index,
+            transaction,
+            receipt,
+        } = self;
+
+        let mobile_coin_identification =
+            identification
+                .as_ref()
+                .map(|identification| match identification {
+                    Identification::Sent { key_images } => MobileCoinTxoIdentification {
+                        keyImages: key_images.clone(),
+                        ..Default::default()
+                    },
+                    Identification::Received { public_keys } => MobileCoinTxoIdentification {
+                        publicKey: public_keys.clone(),
+                        ..Default::default()
+                    },
+                });
+
+        TransactionProto {
+            status: (*status).into(),
+            mobileCoinIdentification: mobile_coin_identification.into(),
+            timestamp: timestamp.map(|t| t.as_millis()),
+            blockIndex: *block_index,
+            blockTimestamp: block_timestamp.map(|t| t.as_millis()),
+            transaction: transaction.clone(),
+            receipt: receipt.clone(),
+            special_fields: Default::default(),
+        }
+    }
+}
+
 #[derive(Copy, Clone, Debug)]
 #[cfg_attr(test, derive(PartialEq))]
 pub struct ParseError;
@@ -7,9 +7,9 @@ use crate::backup::chat::text::{MessageText, TextError};
 use crate::backup::file::{MessageAttachment, MessageAttachmentError};
 use crate::backup::frame::RecipientId;
 use crate::backup::method::LookupPair;
-use crate::backup::recipient::DestinationKind;
+use crate::backup::recipient::{DestinationKind, FullRecipientData};
 use crate::backup::time::Timestamp;
-use crate::backup::TryFromWith;
+use crate::backup::{BackupMeta, ProtoContext, TryFromWith, TryIntoWith as _};
 use crate::proto::backup as proto;
 
 /// Validated version of [`proto::Quote`]
@@ -60,8 +60,8 @@ pub enum QuoteError {
     AttachmentThumbnailWrongFlag(proto::message_attachment::Flag),
 }
 
-impl<R: Clone, C: LookupPair<RecipientId, DestinationKind, R>> TryFromWith<proto::Quote, C>
-    for Quote<R>
+impl<R: Clone, C: LookupPair<RecipientId, DestinationKind, R> + AsRef<BackupMeta>>
+    TryFromWith<proto::Quote, C> for Quote<R>
 {
     type Error = QuoteError;
 
@@ -100,7 +100,10 @@ impl<R: Clone, C: LookupPair<RecipientId, DestinationKind, R>> TryFromWith<proto
             proto::quote::Type::GIFTBADGE => QuoteType::GiftBadge,
         };
 
-        let text = text.into_option().map(|text| text.try_into()).transpose()?;
+        let text = text
+            .into_option()
+            .map(|text| text.try_into_with(context))
+            .transpose()?;
 
         let attachments = attachments
             .into_iter()
@@ -154,12 +157,56 @@ impl TryFrom<proto::quote::QuotedAttachment> for QuotedAttachment {
     }
 }
 
+impl Quote<FullRecipientData> {
+    pub(crate) fn to_proto(&self, context: &ProtoContext) -> proto::Quote {
+        let Self {
+            author,
+            quote_type,
+            target_sent_timestamp,
+            attachments,
+            text,
+            _limit_construction_to_module: _,
+        } = self;
+
+        let type_ = match quote_type {
+            QuoteType::Normal => proto::quote::Type::NORMAL,
+            QuoteType::GiftBadge => proto::quote::Type::GIFTBADGE,
+        };
+
+        proto::Quote {
+            authorId: context.recipient_id(author),
+            type_: type_.into(),
+            targetSentTimestamp: target_sent_timestamp.map(|timestamp| timestamp.as_millis()),
+            text: text.as_ref().map(MessageText::to_proto).into(),
+            attachments: attachments.iter().map(QuotedAttachment::to_proto).collect(),
+            special_fields: Default::default(),
+        }
+    }
+}
+
+impl QuotedAttachment {
+    fn to_proto(&self) -> proto::quote::QuotedAttachment {
+        let Self {
+            content_type,
+            file_name,
+            thumbnail,
+            _limit_construction_to_module: _,
+        } = self;
+
+        proto::quote::QuotedAttachment {
+            contentType: content_type.clone(),
+            fileName: file_name.clone(),
+            thumbnail: thumbnail.as_ref().map(MessageAttachment::to_proto).into(),
+            special_fields: Default::default(),
+        }
+    }
+}
+
 #[cfg(test)]
 mod test {
     use test_case::test_case;
 
     use super::*;
-    use crate::backup::recipient::FullRecipientData;
     use crate::backup::testutil::TestContext;
     use crate::backup::time::testutil::MillisecondsSinceEpoch;
 
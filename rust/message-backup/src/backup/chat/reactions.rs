@@ -3,16 +3,16 @@
 // SPDX-License-Identifier: AGPL-3.0-only
 //
 
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 
 use itertools::Itertools;
 
 use crate::backup::frame::RecipientId;
 use crate::backup::method::LookupPair;
-use crate::backup::recipient::DestinationKind;
+use crate::backup::recipient::{DestinationKind, FullRecipientData};
 use crate::backup::serialize::SerializeOrder;
 use crate::backup::time::Timestamp;
-use crate::backup::{TryFromWith, TryIntoWith};
+use crate::backup::{ProtoContext, TryFromWith, TryIntoWith};
 use crate::proto::backup as proto;
 
 /// Validated version of [`proto::Reaction`].
@@ -49,8 +49,18 @@ pub enum ReactionError {
     MultipleReactions(RecipientId),
     /// "emoji" is an empty string
     EmptyEmoji,
+    /// "emoji" is {0} bytes long, more than any single emoji
+    EmojiTooLong(usize),
+    /// multiple reactions with sortOrder {0}
+    DuplicateSortOrder(u64),
 }
 
+/// The longest "emoji" accepted, in UTF-8 bytes.
+///
+/// This isn't meant to recognize emoji, only to reject text that clearly isn't one. Also applies
+/// to the emoji associated with a sticker.
+pub(crate) const MAX_EMOJI_BYTES: usize = 16;
+
 impl<R: Clone, C: LookupPair<RecipientId, DestinationKind, R>> TryFromWith<proto::Reaction, C>
     for Reaction<R>
 {
@@ -68,6 +78,9 @@ impl<R: Clone, C: LookupPair<RecipientId, DestinationKind, R>> TryFromWith<proto
         if emoji.is_empty() {
             return Err(ReactionError::EmptyEmoji);
         }
+        if emoji.len() > MAX_EMOJI_BYTES {
+            return Err(ReactionError::EmojiTooLong(emoji.len()));
+        }
 
         let author_id = RecipientId(authorId);
         let Some((&author_kind, author)) = context.lookup_pair(&author_id) else {
@@ -102,13 +115,18 @@ impl<R: Clone, C: LookupPair<RecipientId, DestinationKind, R>> TryFromWith<Vec<p
 
     fn try_from_with(items: Vec<proto::Reaction>, context: &C) -> Result<Self, Self::Error> {
         let mut reactions = HashMap::with_capacity(items.len());
+        let mut sort_orders = HashSet::with_capacity(items.len());
 
         for item in items {
             let author_id = RecipientId(item.authorId);
-            let reaction = item.try_into_with(context)?;
+            let reaction: Reaction<R> = item.try_into_with(context)?;
+            let sort_order = reaction.sort_order;
             if reactions.insert(author_id, reaction).is_some() {
                 return Err(ReactionError::MultipleReactions(author_id));
             }
+            if !sort_orders.insert(sort_order) {
+                return Err(ReactionError::DuplicateSortOrder(sort_order));
+            }
         }
 
         Ok(Self { reactions })
@@ -124,10 +142,16 @@ where
     where
         S: serde::Serializer,
     {
+        serializer.collect_seq(self.in_order())
+    }
+}
+
+impl<R: SerializeOrder> ReactionSet<R> {
+    /// The reactions in the order they're serialized in.
+    pub(crate) fn in_order(&self) -> Vec<&Reaction<R>> {
         let mut items = self.reactions.values().collect_vec();
         items.sort_by(|l, r| l.serialize_cmp(r));
-
-        serializer.collect_seq(items)
+        items
     }
 }
 
@@ -139,6 +163,35 @@ impl<R> FromIterator<(RecipientId, Reaction<R>)> for ReactionSet<R> {
     }
 }
 
+impl Reaction<FullRecipientData> {
+    fn to_proto(&self, context: &ProtoContext) -> proto::Reaction {
+        let Self {
+            emoji,
+            sort_order,
+            author,
+            sent_timestamp,
+            _limit_construction_to_module: _,
+        } = self;
+
+        proto::Reaction {
+            authorId: context.recipient_id(author),
+            sentTimestamp: sent_timestamp.as_millis(),
+            emoji: emoji.clone(),
+            sortOrder: *sort_order,
+            special_fields: Default::default(),
+        }
+    }
+}
+
+impl ReactionSet<FullRecipientData> {
+    pub(crate) fn to_proto(&self, context: &ProtoContext) -> Vec<proto::Reaction> {
+        self.in_order()
+            .into_iter()
+            .map(|reaction| reaction.to_proto(context))
+            .collect()
+    }
+}
+
 /// Custom implementation of PartialEq to avoid comparing the keys; RecipientIds are not stable
 /// across backups.
 #[cfg(test)]
@@ -168,7 +221,6 @@ mod test {
 
     use super::*;
     use crate::backup::chat::StandardMessage;
-    use crate::backup::recipient::FullRecipientData;
     use crate::backup::testutil::TestContext;
     use crate::backup::time::testutil::MillisecondsSinceEpoch;
     use crate::backup::time::Duration;
@@ -213,6 +265,11 @@ mod test {
         |x| x.authorId = TestContext::GROUP_ID.0 => Err(ReactionError::InvalidAuthor(TestContext::GROUP_ID, DestinationKind::Group));
         "invalid author id"
     )]
+    #[test_case(|x| x.emoji = "".into() => Err(ReactionError::EmptyEmoji); "empty emoji")]
+    #[test_case(|x| x.emoji = "👍🏽👍🏽".into() => Ok(()); "emoji at max length")]
+    #[test_case(|x| x.emoji = "👍🏽👍🏽!".into() => Err(ReactionError::EmojiTooLong(17)); "emoji too long")]
+    #[test_case(|x| x.emoji = "looks good to me".into() => Ok(()); "text at max length")]
+    #[test_case(|x| x.emoji = "looks great to me".into() => Err(ReactionError::EmojiTooLong(17)); "text too long")]
     fn reaction(modifier: fn(&mut proto::Reaction)) -> Result<(), ReactionError> {
         let mut reaction = proto::Reaction::test_data();
         modifier(&mut reaction);
@@ -232,14 +289,14 @@ mod test {
             Err(ReactionError::MultipleReactions(TestContext::SELF_ID))
         );
 
-        // Note that having the same sort order is okay. Some clients use timestamps as sort order
-        // and it's possible for those to be identical.
+        // Different authors are fine...
         assert_matches!(
             ReactionSet::try_from_with(
                 vec![
                     proto::Reaction::test_data(),
                     proto::Reaction {
                         authorId: TestContext::CONTACT_ID.0,
+                        sortOrder: proto::Reaction::test_data().sortOrder + 1,
                         ..proto::Reaction::test_data()
                     }
                 ],
@@ -247,6 +304,21 @@ mod test {
             ),
             Ok(_)
         );
+
+        // ...but not with the same sort order, which would leave the order ambiguous.
+        assert_matches!(
+            ReactionSet::try_from_with(
+                vec![
+                    proto::Reaction::test_data(),
+                    proto::Reaction {
+                        authorId: TestContext::CONTACT_ID.0,
+                        ..proto::Reaction::test_data()
+                    }
+                ],
+                &TestContext::default(),
+            ),
+            Err(ReactionError::DuplicateSortOrder(3))
+        );
     }
 
     #[test]
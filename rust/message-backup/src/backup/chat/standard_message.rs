@@ -12,9 +12,9 @@ use crate::backup::chat::{ChatItemError, ReactionSet};
 use crate::backup::file::{FilePointer, MessageAttachment};
 use crate::backup::frame::RecipientId;
 use crate::backup::method::LookupPair;
-use crate::backup::recipient::DestinationKind;
+use crate::backup::recipient::{DestinationKind, FullRecipientData};
 use crate::backup::serialize::SerializeOrder;
-use crate::backup::{TryFromWith, TryIntoWith as _};
+use crate::backup::{BackupMeta, ProtoContext, TryFromWith, TryIntoWith as _};
 use crate::proto::backup as proto;
 
 /// Validated version of [`proto::StandardMessage`].
@@ -31,7 +31,7 @@ pub struct StandardMessage<Recipient> {
     _limit_construction_to_module: (),
 }
 
-impl<R: Clone, C: LookupPair<RecipientId, DestinationKind, R>>
+impl<R: Clone, C: LookupPair<RecipientId, DestinationKind, R> + AsRef<BackupMeta>>
     TryFromWith<proto::StandardMessage, C> for StandardMessage<R>
 {
     type Error = ChatItemError;
@@ -54,7 +54,10 @@ impl<R: Clone, C: LookupPair<RecipientId, DestinationKind, R>>
             .map(|q| q.try_into_with(context))
             .transpose()?;
 
-        let text = text.into_option().map(MessageText::try_from).transpose()?;
+        let text = text
+            .into_option()
+            .map(|text| text.try_into_with(context))
+            .transpose()?;
 
         let link_previews = linkPreview
             .into_iter()
@@ -84,11 +87,37 @@ impl<R: Clone, C: LookupPair<RecipientId, DestinationKind, R>>
     }
 }
 
+impl StandardMessage<FullRecipientData> {
+    pub(crate) fn to_proto(&self, context: &ProtoContext) -> proto::StandardMessage {
+        let Self {
+            text,
+            quote,
+            attachments,
+            reactions,
+            link_previews,
+            long_text,
+            _limit_construction_to_module: _,
+        } = self;
+
+        proto::StandardMessage {
+            text: text.as_ref().map(MessageText::to_proto).into(),
+            quote: quote.as_ref().map(|quote| quote.to_proto(context)).into(),
+            attachments: attachments
+                .iter()
+                .map(MessageAttachment::to_proto)
+                .collect(),
+            reactions: reactions.to_proto(context),
+            linkPreview: link_previews.iter().map(LinkPreview::to_proto).collect(),
+            longText: long_text.as_ref().map(FilePointer::to_proto).into(),
+            special_fields: Default::default(),
+        }
+    }
+}
+
 #[cfg(test)]
 mod test {
     use super::*;
     use crate::backup::chat::Reaction;
-    use crate::backup::recipient::FullRecipientData;
     use crate::backup::testutil::TestContext;
 
     impl proto::StandardMessage {
@@ -8,10 +8,10 @@ use derive_where::derive_where;
 use crate::backup::chat::{ChatItemError, ReactionSet};
 use crate::backup::frame::RecipientId;
 use crate::backup::method::LookupPair;
-use crate::backup::recipient::DestinationKind;
+use crate::backup::recipient::{DestinationKind, FullRecipientData};
 use crate::backup::serialize::SerializeOrder;
 use crate::backup::sticker::MessageSticker;
-use crate::backup::{TryFromWith, TryIntoWith as _};
+use crate::backup::{ProtoContext, TryFromWith, TryIntoWith as _};
 use crate::proto::backup as proto;
 
 /// Validated version of [`proto::StickerMessage`].
@@ -51,13 +51,28 @@ impl<R: Clone, C: LookupPair<RecipientId, DestinationKind, R>> TryFromWith<proto
     }
 }
 
+impl StickerMessage<FullRecipientData> {
+    pub(crate) fn to_proto(&self, context: &ProtoContext) -> proto::StickerMessage {
+        let Self {
+            reactions,
+            sticker,
+            _limit_construction_to_module: _,
+        } = self;
+
+        proto::StickerMessage {
+            reactions: reactions.to_proto(context),
+            sticker: Some(sticker.to_proto()).into(),
+            special_fields: Default::default(),
+        }
+    }
+}
+
 #[cfg(test)]
 mod test {
     use test_case::test_case;
 
     use super::*;
     use crate::backup::chat::ReactionError;
-    use crate::backup::recipient::FullRecipientData;
     use crate::backup::testutil::TestContext;
 
     impl proto::StickerMessage {
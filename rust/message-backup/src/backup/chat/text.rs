@@ -4,11 +4,15 @@
 //
 
 use libsignal_core::Aci;
+use uuid::Uuid;
 
 use crate::backup::serialize::{self, UnorderedList};
-use crate::backup::uuid_bytes_to_aci;
+use crate::backup::{uuid_bytes_to_aci, BackupMeta, Purpose, TryFromWith};
 use crate::proto::backup as proto;
 
+/// The longest message body, in UTF-8 bytes, allowed in a [`Purpose::RemoteBackup`].
+pub const MAX_BODY_LENGTH_BYTES: usize = 128 * 1024;
+
 /// Validated version of [`proto::Text`].
 #[derive(Debug, serde::Serialize)]
 #[cfg_attr(test, derive(PartialEq))]
@@ -17,6 +21,10 @@ pub struct MessageText {
     pub ranges: UnorderedList<TextRange>,
 }
 
+/// A styled or mentioned span of a [`MessageText`].
+///
+/// Like the mobile clients, `start` and `length` count UTF-16 code units, not bytes or `char`s, and
+/// a missing value means 0. The range is validated to be non-empty and within the text.
 #[derive(Debug, serde::Serialize)]
 #[cfg_attr(test, derive(PartialEq, Clone))]
 pub struct TextRange {
@@ -39,18 +47,35 @@ pub enum TextError {
     MentionInvalidAci,
     /// BodyRange.associatedValue is a oneof but has no value
     NoAssociatedValueForBodyRange,
+    /// body is {0} bytes, over the limit of 128 KiB
+    BodyTooLong(usize),
+    /// range at {0} is empty
+    EmptyRange(u32),
+    /// range at {start} with length {length} exceeds body length of {body_len} UTF-16 units
+    RangeOutOfBounds {
+        start: u32,
+        length: u32,
+        body_len: usize,
+    },
 }
 
-impl TryFrom<proto::Text> for MessageText {
+impl<C: AsRef<BackupMeta>> TryFromWith<proto::Text, C> for MessageText {
     type Error = TextError;
 
-    fn try_from(value: proto::Text) -> Result<Self, Self::Error> {
+    fn try_from_with(value: proto::Text, context: &C) -> Result<Self, Self::Error> {
         let proto::Text {
             body,
             bodyRanges,
             special_fields: _,
         } = value;
 
+        if body.len() > MAX_BODY_LENGTH_BYTES && context.as_ref().purpose == Purpose::RemoteBackup {
+            return Err(TextError::BodyTooLong(body.len()));
+        }
+
+        // Clients index into the body by UTF-16 code unit, so ranges are checked the same way.
+        let body_len = body.encode_utf16().count();
+
         let ranges = bodyRanges
             .into_iter()
             .map(|range| {
@@ -60,6 +85,8 @@ impl TryFrom<proto::Text> for MessageText {
                     associatedValue,
                     special_fields: _,
                 } = range;
+                check_range_bounds(start, length, body_len)?;
+
                 use proto::body_range::AssociatedValue;
                 let effect =
                     match associatedValue.ok_or(TextError::NoAssociatedValueForBodyRange)? {
@@ -82,10 +109,71 @@ impl TryFrom<proto::Text> for MessageText {
     }
 }
 
+fn check_range_bounds(
+    start: Option<u32>,
+    length: Option<u32>,
+    body_len: usize,
+) -> Result<(), TextError> {
+    let start = start.unwrap_or_default();
+    let length = length.unwrap_or_default();
+    if length == 0 {
+        return Err(TextError::EmptyRange(start));
+    }
+    let end = u64::from(start) + u64::from(length);
+    if end > body_len as u64 {
+        return Err(TextError::RangeOutOfBounds {
+            start,
+            length,
+            body_len,
+        });
+    }
+    Ok(())
+}
+
+impl MessageText {
+    pub(crate) fn to_proto(&self) -> proto::Text {
+        let Self { text, ranges } = self;
+
+        proto::Text {
+            body: text.clone(),
+            bodyRanges: ranges.0.iter().map(TextRange::to_proto).collect(),
+            special_fields: Default::default(),
+        }
+    }
+}
+
+impl TextRange {
+    fn to_proto(&self) -> proto::BodyRange {
+        let Self {
+            start,
+            length,
+            effect,
+        } = self;
+
+        use proto::body_range::AssociatedValue;
+        let associated_value = match effect {
+            TextEffect::MentionAci(aci) => {
+                AssociatedValue::MentionAci(Uuid::from(*aci).into_bytes().to_vec())
+            }
+            TextEffect::Style(style) => AssociatedValue::Style((*style).into()),
+        };
+
+        proto::BodyRange {
+            start: *start,
+            length: *length,
+            associatedValue: Some(associated_value),
+            special_fields: Default::default(),
+        }
+    }
+}
+
 #[cfg(test)]
 mod test {
+    use test_case::test_case;
+
     use super::*;
-    use crate::backup::testutil::TEST_MESSAGE_TEXT;
+    use crate::backup::testutil::{TestContext, TEST_MESSAGE_TEXT};
+    use crate::backup::TryIntoWith as _;
 
     impl proto::Text {
         pub(crate) fn test_data() -> Self {
@@ -121,11 +209,61 @@ mod test {
     #[test]
     fn valid_text() {
         assert_eq!(
-            proto::Text::test_data().try_into(),
+            proto::Text::test_data().try_into_with(&TestContext::default()),
             Ok(MessageText::from_proto_test_data())
         );
     }
 
+    fn style_range(start: Option<u32>, length: Option<u32>) -> proto::BodyRange {
+        proto::BodyRange {
+            start,
+            length,
+            associatedValue: Some(proto::body_range::AssociatedValue::Style(
+                proto::body_range::Style::BOLD.into(),
+            )),
+            special_fields: Default::default(),
+        }
+    }
+
+    // "a👍b" is 3 chars, 6 UTF-8 bytes, and 4 UTF-16 code units.
+    const EMOJI_BODY: &str = "a\u{1F44D}b";
+
+    #[test_case(Some(0), Some(4) => Ok(()); "whole body")]
+    #[test_case(Some(1), Some(2) => Ok(()); "just the emoji")]
+    #[test_case(Some(3), Some(1) => Ok(()); "after the emoji")]
+    #[test_case(None, Some(1) => Ok(()); "missing start")]
+    #[test_case(Some(3), Some(2) => Err(TextError::RangeOutOfBounds { start: 3, length: 2, body_len: 4 }); "past the end")]
+    #[test_case(Some(0), Some(6) => Err(TextError::RangeOutOfBounds { start: 0, length: 6, body_len: 4 }); "counting bytes")]
+    #[test_case(Some(4), Some(1) => Err(TextError::RangeOutOfBounds { start: 4, length: 1, body_len: 4 }); "starts at end")]
+    #[test_case(Some(u32::MAX), Some(u32::MAX) => Err(TextError::RangeOutOfBounds { start: u32::MAX, length: u32::MAX, body_len: 4 }); "overflow")]
+    #[test_case(Some(1), Some(0) => Err(TextError::EmptyRange(1)); "empty")]
+    #[test_case(Some(1), None => Err(TextError::EmptyRange(1)); "missing length")]
+    fn range_bounds(start: Option<u32>, length: Option<u32>) -> Result<(), TextError> {
+        proto::Text {
+            body: EMOJI_BODY.into(),
+            bodyRanges: vec![style_range(start, length)],
+            special_fields: Default::default(),
+        }
+        .try_into_with(&TestContext::default())
+        .map(|_: MessageText| ())
+    }
+
+    #[test_case(Purpose::RemoteBackup, MAX_BODY_LENGTH_BYTES => Ok(()))]
+    #[test_case(Purpose::RemoteBackup, MAX_BODY_LENGTH_BYTES + 1 => Err(TextError::BodyTooLong(MAX_BODY_LENGTH_BYTES + 1)))]
+    #[test_case(Purpose::DeviceTransfer, MAX_BODY_LENGTH_BYTES + 1 => Ok(()))]
+    fn body_length(purpose: Purpose, len: usize) -> Result<(), TextError> {
+        let context = TestContext(BackupMeta {
+            purpose,
+            ..TestContext::default().0
+        });
+        proto::Text {
+            body: "x".repeat(len),
+            ..Default::default()
+        }
+        .try_into_with(&context)
+        .map(|_: MessageText| ())
+    }
+
     #[test]
     fn ranges_are_sorted_when_serialized() {
         let range1 = TextRange {
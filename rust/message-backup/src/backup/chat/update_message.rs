@@ -2,14 +2,18 @@
 // SPDX-License-Identifier: AGPL-3.0-only
 //
 
+use serde::ser::SerializeTupleVariant as _;
+
+use crate::backup::account_data::{check_currency_code, EmptyCurrency};
 use crate::backup::call::{GroupCall, IndividualCall};
 use crate::backup::chat::group::GroupChatUpdate;
+use crate::backup::chat::payment::MobAmount;
 use crate::backup::chat::ChatItemError;
 use crate::backup::frame::RecipientId;
 use crate::backup::method::LookupPair;
-use crate::backup::recipient::{DestinationKind, E164};
+use crate::backup::recipient::{DestinationKind, FullRecipientData, InvalidE164, E164};
 use crate::backup::time::Duration;
-use crate::backup::{TryFromWith, TryIntoWith as _};
+use crate::backup::{BackupMeta, ProtoContext, Purpose, TryFromWith, TryIntoWith as _};
 use crate::proto::backup as proto;
 
 /// Validated version of [`proto::chat_update_message::Update`].
@@ -17,14 +21,85 @@ use crate::proto::backup as proto;
 #[cfg_attr(test, derive(PartialEq))]
 pub enum UpdateMessage<Recipient> {
     Simple(SimpleChatUpdate),
-    GroupChange { updates: Vec<GroupChatUpdate> },
-    ExpirationTimerChange { expires_in: Duration },
-    ProfileChange { previous: String, new: String },
-    ThreadMerge { previous_e164: E164 },
-    SessionSwitchover { e164: E164 },
+    GroupChange {
+        updates: Vec<GroupChatUpdate>,
+    },
+    ExpirationTimerChange {
+        expires_in: Duration,
+    },
+    ProfileChange {
+        previous: String,
+        new: String,
+    },
+    ThreadMerge {
+        previous_e164: E164,
+    },
+    SessionSwitchover {
+        e164: E164,
+    },
     IndividualCall(IndividualCall),
     GroupCall(GroupCall<Recipient>),
-    LearnedProfileUpdate(proto::learned_profile_chat_update::PreviousName),
+    LearnedProfileUpdate(LearnedProfilePreviousName),
+    PaymentActivation {
+        kind: PaymentActivationKind,
+        amount: MobAmount,
+        currency_code: String,
+    },
+}
+
+/// Validated version of [`proto::learned_profile_chat_update::PreviousName`].
+///
+/// Some exporters wrote a previous e164 into the username field as `+<digits>`; that's accepted and
+/// canonicalized as an e164. Since usernames can't start with a `+`, any other string starting with
+/// one is rejected as an invalid e164.
+#[derive(Debug)]
+#[cfg_attr(test, derive(PartialEq))]
+pub enum LearnedProfilePreviousName {
+    E164(E164),
+    Username(String),
+}
+
+/// Serialized the same way as the proto type this replaced, to keep canonical output stable.
+impl serde::Serialize for LearnedProfilePreviousName {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        match self {
+            LearnedProfilePreviousName::E164(e164) => {
+                let mut tv = serializer.serialize_tuple_variant("PreviousName", 0, "E164", 1)?;
+                tv.serialize_field(e164)?;
+                tv.end()
+            }
+            LearnedProfilePreviousName::Username(username) => {
+                let mut tv =
+                    serializer.serialize_tuple_variant("PreviousName", 1, "Username", 1)?;
+                tv.serialize_field(username)?;
+                tv.end()
+            }
+        }
+    }
+}
+
+/// Validated version of [`proto::payment_activation_chat_update::Type`].
+#[derive(Debug, serde::Serialize)]
+#[cfg_attr(test, derive(PartialEq))]
+pub enum PaymentActivationKind {
+    ActivationRequest,
+    Activated,
+}
+
+#[derive(Debug, displaydoc::Display, thiserror::Error)]
+#[cfg_attr(test, derive(PartialEq))]
+pub enum PaymentActivationError {
+    /// payment activation type is UNKNOWN
+    UnknownType,
+    /// amount was not parsable
+    InvalidAmount,
+    /// currency code was empty
+    EmptyCurrency,
+    /// unknown currency code {0:?}
+    UnknownCurrency(String),
 }
 
 /// Validated version of [`proto::simple_chat_update::Type`].
@@ -49,7 +124,7 @@ pub enum SimpleChatUpdate {
     MessageRequestAccepted,
 }
 
-impl<C: LookupPair<RecipientId, DestinationKind, R>, R: Clone>
+impl<C: LookupPair<RecipientId, DestinationKind, R> + AsRef<BackupMeta>, R: Clone>
     TryFromWith<proto::ChatUpdateMessage, C> for UpdateMessage<R>
 {
     type Error = ChatItemError;
@@ -138,16 +213,14 @@ impl<C: LookupPair<RecipientId, DestinationKind, R>, R: Clone>
                 previousE164,
                 special_fields: _,
             }) => {
-                let previous_e164 = previousE164
-                    .try_into()
-                    .map_err(|_| ChatItemError::InvalidE164)?;
+                let previous_e164 = e164_from_u64(previousE164)?;
                 UpdateMessage::ThreadMerge { previous_e164 }
             }
             Update::SessionSwitchover(proto::SessionSwitchoverChatUpdate {
                 e164,
                 special_fields: _,
             }) => {
-                let e164 = e164.try_into().map_err(|_| ChatItemError::InvalidE164)?;
+                let e164 = e164_from_u64(e164)?;
                 UpdateMessage::SessionSwitchover { e164 }
             }
             Update::IndividualCall(call) => UpdateMessage::IndividualCall(call.try_into()?),
@@ -156,12 +229,189 @@ impl<C: LookupPair<RecipientId, DestinationKind, R>, R: Clone>
                 previousName,
                 special_fields: _,
             }) => UpdateMessage::LearnedProfileUpdate(
-                previousName.ok_or(ChatItemError::LearnedProfileIsEmpty)?,
+                previousName
+                    .ok_or(ChatItemError::LearnedProfileIsEmpty)?
+                    .try_into()?,
             ),
+            Update::PaymentActivation(update) => {
+                payment_activation(update, context.as_ref().purpose)?
+            }
         })
     }
 }
 
+impl UpdateMessage<FullRecipientData> {
+    pub(crate) fn to_proto(&self, context: &ProtoContext) -> proto::ChatUpdateMessage {
+        use proto::chat_update_message::Update;
+        let update = match self {
+            UpdateMessage::Simple(simple) => Update::SimpleUpdate(proto::SimpleChatUpdate {
+                type_: simple.to_proto().into(),
+                special_fields: Default::default(),
+            }),
+            UpdateMessage::GroupChange { updates } => {
+                Update::GroupChange(proto::GroupChangeChatUpdate {
+                    updates: updates
+                        .iter()
+                        .map(|update| proto::group_change_chat_update::Update {
+                            update: Some(update.to_proto()),
+                            special_fields: Default::default(),
+                        })
+                        .collect(),
+                    special_fields: Default::default(),
+                })
+            }
+            UpdateMessage::ExpirationTimerChange { expires_in } => {
+                Update::ExpirationTimerChange(proto::ExpirationTimerChatUpdate {
+                    expiresInMs: expires_in.as_millis(),
+                    special_fields: Default::default(),
+                })
+            }
+            UpdateMessage::ProfileChange { previous, new } => {
+                Update::ProfileChange(proto::ProfileChangeChatUpdate {
+                    previousName: previous.clone(),
+                    newName: new.clone(),
+                    special_fields: Default::default(),
+                })
+            }
+            UpdateMessage::ThreadMerge { previous_e164 } => {
+                Update::ThreadMerge(proto::ThreadMergeChatUpdate {
+                    previousE164: previous_e164.into(),
+                    special_fields: Default::default(),
+                })
+            }
+            UpdateMessage::SessionSwitchover { e164 } => {
+                Update::SessionSwitchover(proto::SessionSwitchoverChatUpdate {
+                    e164: e164.into(),
+                    special_fields: Default::default(),
+                })
+            }
+            UpdateMessage::IndividualCall(call) => Update::IndividualCall(call.to_proto()),
+            UpdateMessage::GroupCall(call) => Update::GroupCall(call.to_proto(context)),
+            UpdateMessage::LearnedProfileUpdate(previous_name) => {
+                use proto::learned_profile_chat_update::PreviousName;
+                Update::LearnedProfileChange(proto::LearnedProfileChatUpdate {
+                    previousName: Some(match previous_name {
+                        LearnedProfilePreviousName::E164(e164) => PreviousName::E164(e164.into()),
+                        LearnedProfilePreviousName::Username(username) => {
+                            PreviousName::Username(username.clone())
+                        }
+                    }),
+                    special_fields: Default::default(),
+                })
+            }
+            UpdateMessage::PaymentActivation {
+                kind,
+                amount,
+                currency_code,
+            } => {
+                use proto::payment_activation_chat_update::Type;
+                Update::PaymentActivation(proto::PaymentActivationChatUpdate {
+                    type_: match kind {
+                        PaymentActivationKind::ActivationRequest => Type::ACTIVATION_REQUEST,
+                        PaymentActivationKind::Activated => Type::ACTIVATED,
+                    }
+                    .into(),
+                    amount: amount.to_string(),
+                    currencyCode: currency_code.clone(),
+                    special_fields: Default::default(),
+                })
+            }
+        };
+        proto::ChatUpdateMessage {
+            update: Some(update),
+            special_fields: Default::default(),
+        }
+    }
+}
+
+impl SimpleChatUpdate {
+    fn to_proto(&self) -> proto::simple_chat_update::Type {
+        use proto::simple_chat_update::Type;
+        match self {
+            SimpleChatUpdate::JoinedSignal => Type::JOINED_SIGNAL,
+            SimpleChatUpdate::IdentityUpdate => Type::IDENTITY_UPDATE,
+            SimpleChatUpdate::IdentityVerified => Type::IDENTITY_VERIFIED,
+            SimpleChatUpdate::IdentityDefault => Type::IDENTITY_DEFAULT,
+            SimpleChatUpdate::ChangeNumber => Type::CHANGE_NUMBER,
+            SimpleChatUpdate::EndSession => Type::END_SESSION,
+            SimpleChatUpdate::ChatSessionRefresh => Type::CHAT_SESSION_REFRESH,
+            SimpleChatUpdate::BadDecrypt => Type::BAD_DECRYPT,
+            SimpleChatUpdate::PaymentsActivated => Type::PAYMENTS_ACTIVATED,
+            SimpleChatUpdate::PaymentActivationRequest => Type::PAYMENT_ACTIVATION_REQUEST,
+            SimpleChatUpdate::UnsupportedProtocolMessage => Type::UNSUPPORTED_PROTOCOL_MESSAGE,
+            SimpleChatUpdate::ReleaseChannelDonationRequest => {
+                Type::RELEASE_CHANNEL_DONATION_REQUEST
+            }
+            SimpleChatUpdate::ReportedSpam => Type::REPORTED_SPAM,
+            SimpleChatUpdate::Blocked => Type::BLOCKED,
+            SimpleChatUpdate::Unblocked => Type::UNBLOCKED,
+            SimpleChatUpdate::MessageRequestAccepted => Type::MESSAGE_REQUEST_ACCEPTED,
+        }
+    }
+}
+
+impl TryFrom<proto::learned_profile_chat_update::PreviousName> for LearnedProfilePreviousName {
+    type Error = ChatItemError;
+
+    fn try_from(
+        value: proto::learned_profile_chat_update::PreviousName,
+    ) -> Result<Self, Self::Error> {
+        use proto::learned_profile_chat_update::PreviousName;
+        Ok(match value {
+            PreviousName::E164(e164) => Self::E164(e164_from_u64(e164)?),
+            PreviousName::Username(username) if username.starts_with('+') => Self::E164(
+                username
+                    .parse()
+                    .map_err(|InvalidE164| ChatItemError::InvalidE164)?,
+            ),
+            PreviousName::Username(username) => Self::Username(username),
+        })
+    }
+}
+
+fn e164_from_u64(value: u64) -> Result<E164, ChatItemError> {
+    value.try_into().map_err(|_| ChatItemError::InvalidE164)
+}
+
+fn payment_activation<R>(
+    update: proto::PaymentActivationChatUpdate,
+    purpose: Purpose,
+) -> Result<UpdateMessage<R>, PaymentActivationError> {
+    let proto::PaymentActivationChatUpdate {
+        type_,
+        amount,
+        currencyCode,
+        special_fields: _,
+    } = update;
+
+    use proto::payment_activation_chat_update::Type;
+    let kind = match type_.enum_value_or_default() {
+        Type::UNKNOWN => return Err(PaymentActivationError::UnknownType),
+        Type::ACTIVATION_REQUEST => PaymentActivationKind::ActivationRequest,
+        Type::ACTIVATED => PaymentActivationKind::Activated,
+    };
+
+    let amount = MobAmount::try_from(amount).map_err(|_| PaymentActivationError::InvalidAmount)?;
+
+    check_currency_code(&currencyCode)
+        .map_err(|EmptyCurrency| PaymentActivationError::EmptyCurrency)?;
+    // A device transfer is between two clients that agree on which currencies exist, but a remote
+    // backup may be restored by any client, so it's held to the ISO 4217 format.
+    let is_iso_4217 =
+        currencyCode.len() == 3 && currencyCode.bytes().all(|b| b.is_ascii_uppercase());
+    match purpose {
+        Purpose::DeviceTransfer => (),
+        Purpose::RemoteBackup if is_iso_4217 => (),
+        Purpose::RemoteBackup => return Err(PaymentActivationError::UnknownCurrency(currencyCode)),
+    }
+
+    Ok(UpdateMessage::PaymentActivation {
+        kind,
+        amount,
+        currency_code: currencyCode,
+    })
+}
+
 #[cfg(test)]
 mod test {
     use assert_matches::assert_matches;
@@ -260,4 +510,163 @@ mod test {
 
         assert_eq!(result, expected)
     }
+
+    fn e164(value: u64) -> E164 {
+        value.try_into().expect("nonzero")
+    }
+
+    #[test_case(
+        proto::learned_profile_chat_update::PreviousName::E164(17735550199),
+        Ok(LearnedProfilePreviousName::E164(e164(17735550199)));
+        "e164"
+    )]
+    #[test_case(
+        proto::learned_profile_chat_update::PreviousName::E164(0),
+        Err(ChatItemError::InvalidE164);
+        "zero e164"
+    )]
+    #[test_case(
+        proto::learned_profile_chat_update::PreviousName::Username("boba_fett.99".to_owned()),
+        Ok(LearnedProfilePreviousName::Username("boba_fett.99".to_owned()));
+        "username"
+    )]
+    #[test_case(
+        proto::learned_profile_chat_update::PreviousName::Username("+17735550199".to_owned()),
+        Ok(LearnedProfilePreviousName::E164(e164(17735550199)));
+        "plus prefixed e164 string"
+    )]
+    #[test_case(
+        proto::learned_profile_chat_update::PreviousName::Username("+1773555019912345".to_owned()),
+        Err(ChatItemError::InvalidE164);
+        "overlong e164 string"
+    )]
+    #[test_case(
+        proto::learned_profile_chat_update::PreviousName::Username("+1 773 555 0199".to_owned()),
+        Err(ChatItemError::InvalidE164);
+        "e164 string with spaces"
+    )]
+    #[test_case(
+        proto::learned_profile_chat_update::PreviousName::Username("+1773555O199".to_owned()),
+        Err(ChatItemError::InvalidE164);
+        "e164 string with letters"
+    )]
+    #[test_case(
+        proto::learned_profile_chat_update::PreviousName::Username("+".to_owned()),
+        Err(ChatItemError::InvalidE164);
+        "bare plus"
+    )]
+    fn learned_profile_previous_name(
+        previous_name: proto::learned_profile_chat_update::PreviousName,
+        expected: Result<LearnedProfilePreviousName, ChatItemError>,
+    ) {
+        let result: Result<UpdateMessage<FullRecipientData>, _> = proto::ChatUpdateMessage {
+            update: Some(
+                proto::LearnedProfileChatUpdate {
+                    previousName: Some(previous_name),
+                    ..Default::default()
+                }
+                .into(),
+            ),
+            ..Default::default()
+        }
+        .try_into_with(&TestContext::default());
+
+        assert_eq!(result, expected.map(UpdateMessage::LearnedProfileUpdate));
+    }
+
+    impl proto::PaymentActivationChatUpdate {
+        fn test_data() -> Self {
+            Self {
+                type_: proto::payment_activation_chat_update::Type::ACTIVATED.into(),
+                amount: "1.50".to_owned(),
+                currencyCode: "USD".to_owned(),
+                ..Default::default()
+            }
+        }
+    }
+
+    fn payment_activation_result(
+        update: proto::PaymentActivationChatUpdate,
+        purpose: Purpose,
+    ) -> Result<UpdateMessage<FullRecipientData>, ChatItemError> {
+        let context = TestContext(BackupMeta {
+            purpose,
+            ..TestContext::default().0
+        });
+        proto::ChatUpdateMessage {
+            update: Some(update.into()),
+            ..Default::default()
+        }
+        .try_into_with(&context)
+    }
+
+    #[test]
+    fn payment_activation() {
+        assert_eq!(
+            payment_activation_result(
+                proto::PaymentActivationChatUpdate::test_data(),
+                Purpose::RemoteBackup
+            ),
+            Ok(UpdateMessage::PaymentActivation {
+                kind: PaymentActivationKind::Activated,
+                amount: MobAmount::try_from("1.50".to_owned()).expect("valid"),
+                currency_code: "USD".to_owned(),
+            })
+        );
+    }
+
+    #[test_case(|x| x.type_ = Default::default() => Err(PaymentActivationError::UnknownType); "unknown type")]
+    #[test_case(|x| x.amount = "1.5.0".to_owned() => Err(PaymentActivationError::InvalidAmount); "invalid amount")]
+    #[test_case(|x| x.amount = "".to_owned() => Err(PaymentActivationError::InvalidAmount); "empty amount")]
+    #[test_case(|x| x.currencyCode = "".to_owned() => Err(PaymentActivationError::EmptyCurrency); "empty currency")]
+    #[test_case(|x| x.currencyCode = "usd".to_owned() => Err(PaymentActivationError::UnknownCurrency("usd".to_owned())); "lowercase currency")]
+    #[test_case(|x| x.currencyCode = "DOLLARS".to_owned() => Err(PaymentActivationError::UnknownCurrency("DOLLARS".to_owned())); "unknown currency")]
+    #[test_case(|x| x.currencyCode = "MOB".to_owned() => Ok(()); "mobilecoin")]
+    fn payment_activation_remote_backup(
+        modifier: fn(&mut proto::PaymentActivationChatUpdate),
+    ) -> Result<(), PaymentActivationError> {
+        let mut update = proto::PaymentActivationChatUpdate::test_data();
+        modifier(&mut update);
+        payment_activation_result(update, Purpose::RemoteBackup)
+            .map(|_| ())
+            .map_err(|e| assert_matches!(e, ChatItemError::PaymentActivation(e) => e))
+    }
+
+    #[test]
+    fn payment_activation_device_transfer_allows_unknown_currency() {
+        let update = proto::PaymentActivationChatUpdate {
+            currencyCode: "DOLLARS".to_owned(),
+            ..proto::PaymentActivationChatUpdate::test_data()
+        };
+        assert_matches!(
+            payment_activation_result(update, Purpose::DeviceTransfer),
+            Ok(UpdateMessage::PaymentActivation { currency_code, .. }) if currency_code == "DOLLARS"
+        );
+    }
+
+    #[test_case(
+        proto::simple_chat_update::Type::PAYMENTS_ACTIVATED,
+        SimpleChatUpdate::PaymentsActivated
+    )]
+    #[test_case(
+        proto::simple_chat_update::Type::PAYMENT_ACTIVATION_REQUEST,
+        SimpleChatUpdate::PaymentActivationRequest
+    )]
+    fn payment_activation_simple_update_still_accepted(
+        type_: proto::simple_chat_update::Type,
+        expected: SimpleChatUpdate,
+    ) {
+        let update = proto::SimpleChatUpdate {
+            type_: type_.into(),
+            ..Default::default()
+        };
+        assert_eq!(
+            proto::ChatUpdateMessage {
+                update: Some(update.into()),
+                ..Default::default()
+            }
+            .try_into_with(&TestContext::default()),
+            Ok(UpdateMessage::<FullRecipientData>::Simple(expected))
+        );
+    }
 }
@@ -6,12 +6,12 @@
 use derive_where::derive_where;
 
 use crate::backup::chat::{ReactionError, ReactionSet};
-use crate::backup::file::{MessageAttachment, MessageAttachmentError};
+use crate::backup::file::{AttachmentLocator, MessageAttachment, MessageAttachmentError};
 use crate::backup::frame::RecipientId;
 use crate::backup::method::LookupPair;
-use crate::backup::recipient::DestinationKind;
+use crate::backup::recipient::{DestinationKind, FullRecipientData};
 use crate::backup::serialize::SerializeOrder;
-use crate::backup::{TryFromWith, TryIntoWith as _};
+use crate::backup::{BackupMeta, ProtoContext, Purpose, TryFromWith, TryIntoWith as _};
 use crate::proto::backup as proto;
 
 /// Validated version of a view-once message [`proto::ViewOnceMessage`].
@@ -29,11 +29,13 @@ pub struct ViewOnceMessage<Recipient> {
 pub enum ViewOnceMessageError {
     /// attachment: {0}
     Attachment(#[from] MessageAttachmentError),
+    /// attachment has a locator, but remote backups only keep a tombstone for view-once media
+    ContentNotAllowedInRemoteBackup,
     /// invalid reaction: {0}
     Reaction(#[from] ReactionError),
 }
 
-impl<R: Clone, C: LookupPair<RecipientId, DestinationKind, R>>
+impl<R: Clone, C: LookupPair<RecipientId, DestinationKind, R> + AsRef<BackupMeta>>
     TryFromWith<proto::ViewOnceMessage, C> for ViewOnceMessage<R>
 {
     type Error = ViewOnceMessageError;
@@ -50,6 +52,13 @@ impl<R: Clone, C: LookupPair<RecipientId, DestinationKind, R>>
             .map(MessageAttachment::try_from)
             .transpose()?;
 
+        if let Some(attachment) = &attachment {
+            let has_content = !matches!(attachment.pointer.locator, AttachmentLocator::Invalid);
+            if has_content && context.as_ref().purpose == Purpose::RemoteBackup {
+                return Err(ViewOnceMessageError::ContentNotAllowedInRemoteBackup);
+            }
+        }
+
         let reactions = reactions.try_into_with(context)?;
 
         Ok(Self {
@@ -60,13 +69,28 @@ impl<R: Clone, C: LookupPair<RecipientId, DestinationKind, R>>
     }
 }
 
+impl ViewOnceMessage<FullRecipientData> {
+    pub(crate) fn to_proto(&self, context: &ProtoContext) -> proto::ViewOnceMessage {
+        let Self {
+            attachment,
+            reactions,
+            _limit_construction_to_module: _,
+        } = self;
+
+        proto::ViewOnceMessage {
+            attachment: attachment.as_ref().map(MessageAttachment::to_proto).into(),
+            reactions: reactions.to_proto(context),
+            special_fields: Default::default(),
+        }
+    }
+}
+
 #[cfg(test)]
 mod test {
     use test_case::test_case;
 
     use super::*;
     use crate::backup::chat::Reaction;
-    use crate::backup::recipient::FullRecipientData;
     use crate::backup::testutil::TestContext;
 
     impl proto::ViewOnceMessage {
@@ -94,6 +118,19 @@ mod test {
         )
     }
 
+    fn with_backup_locator(message: &mut proto::ViewOnceMessage) {
+        message
+            .attachment
+            .as_mut()
+            .expect("has attachment")
+            .pointer
+            .as_mut()
+            .expect("has pointer")
+            .locator = Some(proto::file_pointer::Locator::BackupLocator(
+            proto::file_pointer::BackupLocator::test_data(),
+        ));
+    }
+
     #[test_case(|x| x.reactions.clear() => Ok(()); "no reactions")]
     #[test_case(|x| x.reactions.push(proto::Reaction::default()) => Err(ViewOnceMessageError::Reaction(ReactionError::EmptyEmoji)); "invalid reaction")]
     #[test_case(|x| x.attachment = None.into() => Ok(()); "already viewed")]
@@ -108,4 +145,26 @@ mod test {
             .try_into_with(&TestContext::default())
             .map(|_: ViewOnceMessage<FullRecipientData>| ())
     }
+
+    #[test_case(Purpose::RemoteBackup, |_| {} => Ok(()); "remote backup tombstone")]
+    #[test_case(Purpose::DeviceTransfer, |_| {} => Ok(()); "device transfer tombstone")]
+    #[test_case(Purpose::RemoteBackup, |x| x.attachment = None.into() => Ok(()); "remote backup already viewed")]
+    #[test_case(Purpose::DeviceTransfer, |x| x.attachment = None.into() => Ok(()); "device transfer already viewed")]
+    #[test_case(Purpose::RemoteBackup, with_backup_locator => Err(ViewOnceMessageError::ContentNotAllowedInRemoteBackup); "remote backup with content")]
+    #[test_case(Purpose::DeviceTransfer, with_backup_locator => Ok(()); "device transfer with content")]
+    fn view_once_message_purpose(
+        purpose: Purpose,
+        modifier: fn(&mut proto::ViewOnceMessage),
+    ) -> Result<(), ViewOnceMessageError> {
+        let mut message = proto::ViewOnceMessage::test_data();
+        modifier(&mut message);
+
+        let context = TestContext(BackupMeta {
+            purpose,
+            ..TestContext::default().0
+        });
+        message
+            .try_into_with(&context)
+            .map(|_: ViewOnceMessage<FullRecipientData>| ())
+    }
 }
@@ -10,9 +10,9 @@ use crate::backup::chat::{ReactionError, ReactionSet};
 use crate::backup::file::{MessageAttachment, MessageAttachmentError};
 use crate::backup::frame::RecipientId;
 use crate::backup::method::LookupPair;
-use crate::backup::recipient::DestinationKind;
+use crate::backup::recipient::{DestinationKind, FullRecipientData};
 use crate::backup::serialize::SerializeOrder;
-use crate::backup::{TryFromWith, TryIntoWith as _};
+use crate::backup::{BackupMeta, ProtoContext, TryFromWith, TryIntoWith as _};
 use crate::proto::backup as proto;
 
 /// Validated version of a voice message [`proto::StandardMessage`].
@@ -43,7 +43,7 @@ pub enum VoiceMessageError {
     Reaction(#[from] ReactionError),
 }
 
-impl<R: Clone, C: LookupPair<RecipientId, DestinationKind, R>>
+impl<R: Clone, C: LookupPair<RecipientId, DestinationKind, R> + AsRef<BackupMeta>>
     TryFromWith<proto::StandardMessage, C> for VoiceMessage<R>
 {
     type Error = VoiceMessageError;
@@ -92,14 +92,32 @@ impl<R: Clone, C: LookupPair<RecipientId, DestinationKind, R>>
     }
 }
 
+impl VoiceMessage<FullRecipientData> {
+    pub(crate) fn to_proto(&self, context: &ProtoContext) -> proto::StandardMessage {
+        let Self {
+            quote,
+            reactions,
+            attachment,
+            _limit_construction_to_module: _,
+        } = self;
+
+        proto::StandardMessage {
+            quote: quote.as_ref().map(|quote| quote.to_proto(context)).into(),
+            reactions: reactions.to_proto(context),
+            attachments: vec![attachment.to_proto()],
+            ..Default::default()
+        }
+    }
+}
+
 #[cfg(test)]
 mod test {
     use test_case::test_case;
 
     use super::*;
     use crate::backup::chat::Reaction;
-    use crate::backup::recipient::FullRecipientData;
     use crate::backup::testutil::TestContext;
+    use crate::backup::Purpose;
 
     #[test]
     fn valid_voice_message() {
@@ -131,4 +149,37 @@ mod test {
             .try_into_with(&TestContext::default())
             .map(|_: VoiceMessage<FullRecipientData>| ())
     }
+
+    fn with_backup_locator(message: &mut proto::StandardMessage) {
+        message.attachments[0]
+            .pointer
+            .as_mut()
+            .expect("has pointer")
+            .locator = Some(proto::file_pointer::Locator::BackupLocator(
+            proto::file_pointer::BackupLocator::test_data(),
+        ));
+    }
+
+    // Unlike view-once messages, voice messages keep their content in every kind of backup.
+    #[test_case(Purpose::RemoteBackup, |_| {} => Ok(()); "remote backup tombstone")]
+    #[test_case(Purpose::DeviceTransfer, |_| {} => Ok(()); "device transfer tombstone")]
+    #[test_case(Purpose::RemoteBackup, with_backup_locator => Ok(()); "remote backup with content")]
+    #[test_case(Purpose::DeviceTransfer, with_backup_locator => Ok(()); "device transfer with content")]
+    #[test_case(Purpose::RemoteBackup, |x| x.attachments.clear() => Err(VoiceMessageError::WrongAttachmentsCount(0)); "remote backup no attachments")]
+    #[test_case(Purpose::DeviceTransfer, |x| x.attachments.clear() => Err(VoiceMessageError::WrongAttachmentsCount(0)); "device transfer no attachments")]
+    fn voice_message_purpose(
+        purpose: Purpose,
+        modifier: fn(&mut proto::StandardMessage),
+    ) -> Result<(), VoiceMessageError> {
+        let mut message = proto::StandardMessage::test_voice_message_data();
+        modifier(&mut message);
+
+        let context = TestContext(BackupMeta {
+            purpose,
+            ..TestContext::default().0
+        });
+        message
+            .try_into_with(&context)
+            .map(|_: VoiceMessage<FullRecipientData>| ())
+    }
 }
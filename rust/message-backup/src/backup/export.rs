@@ -0,0 +1,310 @@
+//
+// Copyright (C) 2024 Signal Messenger, LLC.
+// SPDX-License-Identifier: AGPL-3.0-only
+//
+
+//! Writes a validated backup out as a directory of canonical JSON files.
+//!
+//! This lets a device transfer be imported incrementally. The layout is
+//!
+//! ```text
+//! <dir>/
+//!   manifest.json
+//!   recipients.json
+//!   attachments.json
+//!   chats/
+//!     chat-<chat id>.json
+//! ```
+//!
+//! Every file other than the manifest is listed in `manifest.json` along with its size and
+//! SHA-256 digest. The output for a given backup is deterministic, and files that already have
+//! the expected contents aren't rewritten, so an interrupted export can be resumed by running it
+//! again.
+
+use std::path::{Path, PathBuf};
+
+use itertools::Itertools as _;
+use serde::Serialize;
+use sha2::{Digest as _, Sha256};
+
+use crate::backup::chat::{ChatItemData, ChatItemMessage};
+use crate::backup::file::FilePointer;
+use crate::backup::frame::ChatId;
+use crate::backup::method::Store;
+use crate::backup::serialize::{self, UnorderedList, CANONICAL_VERSION};
+use crate::backup::{Backup, ChatsData, CompletedBackup};
+
+mod conversation;
+pub use conversation::{
+    export_chat, ChatExportError, ChatExportFormat, ChatRecipient, InvalidChatRecipient,
+};
+
+pub const MANIFEST_FILE_NAME: &str = "manifest.json";
+pub const RECIPIENTS_FILE_NAME: &str = "recipients.json";
+pub const ATTACHMENTS_FILE_NAME: &str = "attachments.json";
+pub const CHATS_DIR_NAME: &str = "chats";
+
+#[derive(Debug, displaydoc::Display, thiserror::Error)]
+pub enum ExportError {
+    /// failed to read {0:?}: {1}
+    Read(PathBuf, #[source] std::io::Error),
+    /// failed to write {0:?}: {1}
+    Write(PathBuf, #[source] std::io::Error),
+}
+
+/// What [`to_directory`] exported.
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct ExportSummary {
+    pub chats: usize,
+    pub recipients: usize,
+    pub attachments: usize,
+    /// The number of files that were created or overwritten, including the manifest.
+    pub files_written: usize,
+    /// The number of files that already had the expected contents.
+    pub files_unchanged: usize,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct Manifest {
+    canonical_version: u32,
+    chat_count: usize,
+    recipient_count: usize,
+    attachment_count: usize,
+    files: Vec<ManifestFile>,
+}
+
+#[derive(Serialize)]
+struct ManifestFile {
+    /// Relative to the export directory, always with `/` as the separator.
+    path: String,
+    size: usize,
+    #[serde(with = "hex")]
+    sha256: [u8; 32],
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct AttachmentEntry<'a> {
+    chat_id: ChatId,
+    pointer: &'a FilePointer,
+}
+
+/// Exports `backup` into `dir`, which is created if it doesn't exist.
+///
+/// See the [module documentation](self) for the layout.
+pub fn to_directory(backup: &Backup, dir: &Path) -> Result<ExportSummary, ExportError> {
+    let CompletedBackup {
+        recipients,
+        chats: ChatsData { items: chats, .. },
+        ..
+    } = backup;
+
+    let chats_dir = dir.join(CHATS_DIR_NAME);
+    std::fs::create_dir_all(&chats_dir).map_err(|e| ExportError::Write(chats_dir, e))?;
+
+    let mut writer = Writer {
+        dir,
+        summary: ExportSummary::default(),
+    };
+    let mut files = Vec::new();
+
+    let mut attachments = Vec::new();
+    for (&chat_id, chat) in chats.iter().sorted_by_key(|(id, _)| id.0) {
+        let path = chat_file_path(chat_id);
+        files.push(writer.write(path, &to_canonical_json(chat))?);
+
+        attachments.extend(
+            chat.items
+                .iter()
+                .flat_map(attachment_pointers)
+                .map(|pointer| AttachmentEntry { chat_id, pointer }),
+        );
+    }
+
+    let recipient_list = UnorderedList::from_iter(recipients.values().cloned());
+    files.push(writer.write(
+        RECIPIENTS_FILE_NAME.to_owned(),
+        &to_canonical_json(&recipient_list),
+    )?);
+    files.push(writer.write(
+        ATTACHMENTS_FILE_NAME.to_owned(),
+        &to_canonical_json(&attachments),
+    )?);
+
+    let manifest = Manifest {
+        canonical_version: CANONICAL_VERSION,
+        chat_count: chats.len(),
+        recipient_count: recipients.len(),
+        attachment_count: attachments.len(),
+        files,
+    };
+    // The manifest is written last so that its presence indicates a complete export.
+    writer.write(MANIFEST_FILE_NAME.to_owned(), &to_canonical_json(&manifest))?;
+
+    Ok(ExportSummary {
+        chats: manifest.chat_count,
+        recipients: manifest.recipient_count,
+        attachments: manifest.attachment_count,
+        ..writer.summary
+    })
+}
+
+/// Produces a file name for a chat that's safe on any platform.
+///
+/// Chat IDs are plain integers, so unlike recipient names or identifiers, they can't contain path
+/// separators or characters that some filesystems reject.
+fn chat_file_path(chat_id: ChatId) -> String {
+    format!("{CHATS_DIR_NAME}/chat-{}.json", chat_id.0)
+}
+
+/// The attachments counted by [`MediaSummary`](crate::backup::chat::MediaSummary), in order.
+fn attachment_pointers(item: &ChatItemData<Store>) -> Vec<&FilePointer> {
+    match &item.message {
+        ChatItemMessage::Standard(message) => message
+            .attachments
+            .iter()
+            .map(|attachment| &attachment.pointer)
+            .collect(),
+        ChatItemMessage::Voice(message) => vec![&message.attachment.pointer],
+        ChatItemMessage::ViewOnce(message) => message
+            .attachment
+            .iter()
+            .map(|attachment| &attachment.pointer)
+            .collect(),
+        ChatItemMessage::Contact(_)
+        | ChatItemMessage::Sticker(_)
+        | ChatItemMessage::RemoteDeleted
+        | ChatItemMessage::Update(_)
+        | ChatItemMessage::PaymentNotification(_)
+        | ChatItemMessage::GiftBadge(_) => vec![],
+    }
+}
+
+fn to_canonical_json(value: &impl Serialize) -> String {
+    let value = serde_json::to_value(value).expect("can't fail serialization");
+    serde_json::to_string_pretty(&serialize::canonicalize(value)).expect("can't fail serialization")
+}
+
+/// Writes files into the export directory, skipping those that are already up to date.
+struct Writer<'a> {
+    dir: &'a Path,
+    summary: ExportSummary,
+}
+
+impl Writer<'_> {
+    fn write(&mut self, path: String, contents: &str) -> Result<ManifestFile, ExportError> {
+        let full_path = path.split('/').fold(self.dir.to_owned(), |p, c| p.join(c));
+        let sha256: [u8; 32] = Sha256::digest(contents).into();
+
+        let existing = match std::fs::read(&full_path) {
+            Ok(existing) => Some(existing),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => None,
+            Err(e) => return Err(ExportError::Read(full_path, e)),
+        };
+
+        if existing.is_some_and(|existing| <[u8; 32]>::from(Sha256::digest(existing)) == sha256) {
+            self.summary.files_unchanged += 1;
+        } else {
+            std::fs::write(&full_path, contents).map_err(|e| ExportError::Write(full_path, e))?;
+            self.summary.files_written += 1;
+        }
+
+        Ok(ManifestFile {
+            path,
+            size: contents.len(),
+            sha256,
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::io::Cursor;
+
+    use super::*;
+    use crate::backup::Purpose;
+    use crate::BackupReader;
+
+    fn read_canonical_backup() -> Backup {
+        let binproto = include_bytes!("../../tests/res/canonical-backup.binproto");
+        let reader = BackupReader::new_unencrypted(Cursor::new(binproto), Purpose::RemoteBackup);
+        futures::executor::block_on(reader.read_all())
+            .result
+            .expect("valid backup")
+    }
+
+    #[test]
+    fn export_matches_manifest() {
+        let backup = read_canonical_backup();
+        let dir = tempfile::tempdir().expect("can create temp dir");
+
+        let summary = to_directory(&backup, dir.path()).expect("can export");
+        assert_eq!(summary.chats, backup.chats.items.len());
+        assert_eq!(summary.recipients, backup.recipients.len());
+        assert_eq!(summary.files_unchanged, 0);
+
+        let manifest: serde_json::Value = serde_json::from_slice(
+            &std::fs::read(dir.path().join(MANIFEST_FILE_NAME)).expect("manifest exists"),
+        )
+        .expect("valid JSON");
+        assert_eq!(manifest["canonicalVersion"], CANONICAL_VERSION);
+
+        let files = manifest["files"].as_array().expect("has files");
+        // One per chat, plus recipients and attachments.
+        assert_eq!(files.len(), summary.chats + 2);
+        // Everything but the manifest itself is listed.
+        assert_eq!(summary.files_written, files.len() + 1);
+
+        for file in files {
+            let path = file["path"].as_str().expect("has path");
+            let contents = std::fs::read(dir.path().join(path)).expect("file exists");
+            assert_eq!(file["size"], contents.len(), "{path}");
+            assert_eq!(
+                file["sha256"],
+                hex::encode(Sha256::digest(&contents)),
+                "{path}"
+            );
+        }
+    }
+
+    #[test]
+    fn export_is_idempotent() {
+        let backup = read_canonical_backup();
+        let dir = tempfile::tempdir().expect("can create temp dir");
+
+        let first = to_directory(&backup, dir.path()).expect("can export");
+        let manifest_path = dir.path().join(MANIFEST_FILE_NAME);
+        let first_manifest = std::fs::read(&manifest_path).expect("manifest exists");
+
+        let second = to_directory(&backup, dir.path()).expect("can export again");
+        assert_eq!(
+            second,
+            ExportSummary {
+                files_written: 0,
+                files_unchanged: first.files_written,
+                ..first
+            }
+        );
+        assert_eq!(
+            std::fs::read(&manifest_path).expect("manifest exists"),
+            first_manifest
+        );
+    }
+
+    #[test]
+    fn export_resumes_after_interruption() {
+        let backup = read_canonical_backup();
+        let dir = tempfile::tempdir().expect("can create temp dir");
+
+        let first = to_directory(&backup, dir.path()).expect("can export");
+
+        // Simulate an export that was cut off partway through writing.
+        std::fs::remove_file(dir.path().join(MANIFEST_FILE_NAME)).expect("can remove");
+        std::fs::write(dir.path().join(RECIPIENTS_FILE_NAME), "{").expect("can truncate");
+
+        let resumed = to_directory(&backup, dir.path()).expect("can resume");
+        assert_eq!(resumed.files_written, 2);
+        assert_eq!(resumed.files_unchanged, first.files_written - 2);
+    }
+}
@@ -0,0 +1,709 @@
+//
+// Copyright (C) 2024 Signal Messenger, LLC.
+// SPDX-License-Identifier: AGPL-3.0-only
+//
+
+//! Exporting a single conversation as a document that can be read without the rest of the backup.
+//!
+//! Unlike [`to_directory`](super::to_directory), the output is meant for people rather than for
+//! importing: authors are shown by name, quotes point at the message they quote, and attachments
+//! are listed by name and size without any of the information needed to download them.
+
+use std::collections::HashMap;
+use std::io::Write;
+
+use libsignal_core::ServiceId;
+use serde::Serialize;
+
+use crate::backup::chat::quote::Quote;
+use crate::backup::chat::reactions::ReactionSet;
+use crate::backup::chat::text::MessageText;
+use crate::backup::chat::{ChatItemData, ChatItemMessage, Direction};
+use crate::backup::file::{AttachmentLocator, FilePointer};
+use crate::backup::frame::RecipientId;
+use crate::backup::method::Store;
+use crate::backup::recipient::{Destination, FullRecipientData};
+use crate::backup::{Backup, CompletedBackup};
+
+/// Identifies the conversation to export by who it's with.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ChatRecipient {
+    /// The ID of a recipient frame in the backup.
+    RecipientId(u64),
+    /// The ACI or PNI of a contact.
+    ServiceId(ServiceId),
+}
+
+/// not a recipient ID or service ID
+#[derive(Debug, displaydoc::Display, thiserror::Error)]
+pub struct InvalidChatRecipient;
+
+impl std::str::FromStr for ChatRecipient {
+    type Err = InvalidChatRecipient;
+
+    /// Parses a plain number as a recipient ID, and anything else as a service ID string.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if let Ok(id) = s.parse() {
+            return Ok(Self::RecipientId(id));
+        }
+        ServiceId::parse_from_service_id_string(s)
+            .map(Self::ServiceId)
+            .ok_or(InvalidChatRecipient)
+    }
+}
+
+impl std::fmt::Display for ChatRecipient {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::RecipientId(id) => write!(f, "recipient {id}"),
+            Self::ServiceId(service_id) => f.write_str(&service_id.service_id_string()),
+        }
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, strum::EnumString, strum::Display)]
+#[strum(serialize_all = "lowercase")]
+pub enum ChatExportFormat {
+    Json,
+    Html,
+}
+
+#[derive(Debug, displaydoc::Display, thiserror::Error)]
+pub enum ChatExportError {
+    /// no recipient matches {0}
+    UnknownRecipient(ChatRecipient),
+    /// there is no chat with {0}
+    NoChat(ChatRecipient),
+    /// failed to write the export: {0}
+    Write(#[from] std::io::Error),
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct ExportedChat {
+    with: Participant,
+    messages: Vec<ExportedMessage>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct Participant {
+    recipient_id: Option<RecipientId>,
+    name: String,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct ExportedMessage {
+    sent_at: u64,
+    author: Participant,
+    direction: &'static str,
+    kind: &'static str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    text: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    quote: Option<ExportedQuote>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    attachments: Vec<ExportedAttachment>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    reactions: Vec<ExportedReaction>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct ExportedQuote {
+    author: Participant,
+    /// The index in `messages` of the quoted message, if it's in the export.
+    message_index: Option<usize>,
+    /// The quoted text as it was when it was quoted.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    text: Option<String>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct ExportedAttachment {
+    file_name: Option<String>,
+    content_type: Option<String>,
+    size: Option<u32>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct ExportedReaction {
+    emoji: String,
+    author: Participant,
+    sent_at: u64,
+}
+
+/// Writes the conversation with `recipient` from `backup` to `out` as a self-contained document.
+///
+/// Messages are written in the order they appear in the backup, along with their reactions.
+/// Only the latest revision of an edited message is included.
+pub fn export_chat(
+    backup: &Backup,
+    recipient: &ChatRecipient,
+    format: ChatExportFormat,
+    mut out: impl Write,
+) -> Result<(), ChatExportError> {
+    let chat = ExportedChat::new(backup, recipient)?;
+    match format {
+        ChatExportFormat::Json => {
+            serde_json::to_writer_pretty(&mut out, &chat).map_err(std::io::Error::from)?;
+            writeln!(out)?;
+        }
+        ChatExportFormat::Html => chat.write_html(&mut out)?,
+    }
+    Ok(())
+}
+
+impl ExportedChat {
+    fn new(backup: &Backup, selector: &ChatRecipient) -> Result<Self, ChatExportError> {
+        let CompletedBackup {
+            account_data,
+            recipients,
+            chats,
+            ..
+        } = backup;
+
+        let names = Names {
+            ids: recipients
+                .iter()
+                .map(|(&id, data)| (Names::key(data), id))
+                .collect(),
+            self_name: [&account_data.given_name, &account_data.family_name]
+                .into_iter()
+                .filter(|name| !name.is_empty())
+                .cloned()
+                .collect::<Vec<_>>()
+                .join(" "),
+        };
+
+        let recipient = match selector {
+            ChatRecipient::RecipientId(id) => recipients.get(&RecipientId(*id)),
+            ChatRecipient::ServiceId(service_id) => {
+                recipients.values().find(|data| match &***data {
+                    Destination::Contact(contact) => {
+                        contact.aci.map(ServiceId::from) == Some(*service_id)
+                            || contact.pni.map(ServiceId::from) == Some(*service_id)
+                    }
+                    _ => false,
+                })
+            }
+        }
+        .ok_or_else(|| ChatExportError::UnknownRecipient(selector.clone()))?;
+
+        let chat = chats
+            .items
+            .values()
+            .find(|chat| chat.recipient == *recipient)
+            .ok_or_else(|| ChatExportError::NoChat(selector.clone()))?;
+
+        let mut message_indices = HashMap::new();
+        let mut messages = Vec::with_capacity(chat.items.len());
+        for item in &chat.items {
+            let message = ExportedMessage::new(item, &names, &message_indices);
+            message_indices
+                .entry((Names::key(&item.author), message.sent_at))
+                .or_insert(messages.len());
+            messages.push(message);
+        }
+
+        Ok(Self {
+            with: names.participant(recipient),
+            messages,
+        })
+    }
+}
+
+impl ExportedMessage {
+    fn new(
+        item: &ChatItemData<Store>,
+        names: &Names,
+        message_indices: &HashMap<(RecipientKey, u64), usize>,
+    ) -> Self {
+        let direction = match item.direction {
+            Direction::Incoming { .. } => "incoming",
+            Direction::Outgoing(_) => "outgoing",
+            Direction::Directionless => "none",
+        };
+
+        let mut text = None;
+        let mut quote = None;
+        let mut attachments = vec![];
+        let mut reactions = None;
+        let kind = match &item.message {
+            ChatItemMessage::Standard(message) => {
+                text = message.text.as_ref();
+                quote = message.quote.as_ref();
+                attachments.extend(message.attachments.iter().map(|a| &a.pointer));
+                attachments.extend(&message.long_text);
+                reactions = Some(&message.reactions);
+                "standard"
+            }
+            ChatItemMessage::Contact(message) => {
+                reactions = Some(&message.reactions);
+                "contact"
+            }
+            ChatItemMessage::Voice(message) => {
+                quote = message.quote.as_ref();
+                attachments.push(&message.attachment.pointer);
+                reactions = Some(&message.reactions);
+                "voice"
+            }
+            ChatItemMessage::Sticker(message) => {
+                reactions = Some(&message.reactions);
+                "sticker"
+            }
+            ChatItemMessage::RemoteDeleted => "deleted",
+            ChatItemMessage::Update(_) => "update",
+            ChatItemMessage::PaymentNotification(_) => "payment",
+            ChatItemMessage::GiftBadge(_) => "giftBadge",
+            ChatItemMessage::ViewOnce(message) => {
+                // The contents of a view-once message aren't for keeping.
+                reactions = Some(&message.reactions);
+                "viewOnce"
+            }
+        };
+
+        Self {
+            sent_at: item.sent_at.as_millis(),
+            author: names.participant(&item.author),
+            direction,
+            kind,
+            text: text.map(|text: &MessageText| text.text.clone()),
+            quote: quote.map(|quote| ExportedQuote::new(quote, names, message_indices)),
+            attachments: attachments
+                .into_iter()
+                .map(ExportedAttachment::from)
+                .collect(),
+            reactions: reactions
+                .map(|reactions| ExportedReaction::all(reactions, names))
+                .unwrap_or_default(),
+        }
+    }
+}
+
+impl ExportedQuote {
+    fn new(
+        quote: &Quote<FullRecipientData>,
+        names: &Names,
+        message_indices: &HashMap<(RecipientKey, u64), usize>,
+    ) -> Self {
+        let message_index = quote.target_sent_timestamp.and_then(|sent_at| {
+            message_indices
+                .get(&(Names::key(&quote.author), sent_at.as_millis()))
+                .copied()
+        });
+        Self {
+            author: names.participant(&quote.author),
+            message_index,
+            text: quote.text.as_ref().map(|text| text.text.clone()),
+        }
+    }
+}
+
+impl From<&FilePointer> for ExportedAttachment {
+    fn from(pointer: &FilePointer) -> Self {
+        let size = match pointer.locator {
+            AttachmentLocator::Backup { size, .. } | AttachmentLocator::Transit { size, .. } => {
+                Some(size)
+            }
+            AttachmentLocator::Invalid => None,
+        };
+        Self {
+            file_name: pointer.file_name.clone(),
+            content_type: pointer.content_type.clone(),
+            size,
+        }
+    }
+}
+
+impl ExportedReaction {
+    fn all(reactions: &ReactionSet<FullRecipientData>, names: &Names) -> Vec<Self> {
+        reactions
+            .in_order()
+            .into_iter()
+            .map(|reaction| Self {
+                emoji: reaction.emoji.clone(),
+                author: names.participant(&reaction.author),
+                sent_at: reaction.sent_timestamp.as_millis(),
+            })
+            .collect()
+    }
+}
+
+/// Identifies a recipient by the address of its shared data.
+type RecipientKey = *const Destination<Store>;
+
+/// Resolves recipients to how they're shown in the export.
+struct Names {
+    ids: HashMap<RecipientKey, RecipientId>,
+    /// The account's own profile name.
+    self_name: String,
+}
+
+impl Names {
+    fn key(recipient: &FullRecipientData) -> RecipientKey {
+        &**recipient
+    }
+
+    fn participant(&self, recipient: &FullRecipientData) -> Participant {
+        Participant {
+            recipient_id: self.ids.get(&Self::key(recipient)).copied(),
+            name: self.name(recipient),
+        }
+    }
+
+    fn name(&self, recipient: &FullRecipientData) -> String {
+        match &**recipient {
+            Destination::Contact(contact) => {
+                let profile_name = [&contact.profile_given_name, &contact.profile_family_name]
+                    .into_iter()
+                    .flatten()
+                    .filter(|name| !name.is_empty())
+                    .cloned()
+                    .collect::<Vec<_>>()
+                    .join(" ");
+                if !profile_name.is_empty() {
+                    profile_name
+                } else if let Some(e164) = &contact.e164 {
+                    e164.to_string()
+                } else if let Some(username) = &contact.username {
+                    username.clone()
+                } else if let Some(aci) = contact.aci {
+                    aci.service_id_string()
+                } else {
+                    "Unknown contact".to_owned()
+                }
+            }
+            Destination::Self_ if !self.self_name.is_empty() => self.self_name.clone(),
+            Destination::Self_ => "You".to_owned(),
+            Destination::Group(group) => group
+                .snapshot
+                .title
+                .clone()
+                .unwrap_or_else(|| "Unnamed group".to_owned()),
+            Destination::DistributionList(_) => "Story".to_owned(),
+            Destination::ReleaseNotes => "Signal".to_owned(),
+            Destination::CallLink(call_link) => call_link.name.clone(),
+        }
+    }
+}
+
+impl ExportedChat {
+    fn write_html(&self, out: &mut impl Write) -> std::io::Result<()> {
+        let title = escape_html(&self.with.name);
+        write!(
+            out,
+            "<!DOCTYPE html>\n\
+             <html>\n\
+             <head>\n\
+             <meta charset=\"utf-8\">\n\
+             <title>{title}</title>\n\
+             <style>\n\
+             .message {{ margin-bottom: 1em; }}\n\
+             .outgoing {{ text-align: right; }}\n\
+             .meta {{ color: gray; font-size: smaller; }}\n\
+             .text {{ white-space: pre-wrap; }}\n\
+             </style>\n\
+             </head>\n\
+             <body>\n\
+             <h1>{title}</h1>\n\
+             <ol class=\"messages\">\n"
+        )?;
+
+        for (index, message) in self.messages.iter().enumerate() {
+            writeln!(
+                out,
+                "<li id=\"message-{index}\" class=\"message {} {}\">",
+                message.direction, message.kind
+            )?;
+            writeln!(
+                out,
+                "<div class=\"meta\"><span class=\"author\">{}</span> <time>{}</time></div>",
+                escape_html(&message.author.name),
+                format_millis(message.sent_at),
+            )?;
+            if let Some(quote) = &message.quote {
+                let author = escape_html(&quote.author.name);
+                let text = escape_html(quote.text.as_deref().unwrap_or_default());
+                match quote.message_index {
+                    Some(quoted) => writeln!(
+                        out,
+                        "<blockquote><a href=\"#message-{quoted}\">{author}</a>: {text}</blockquote>"
+                    )?,
+                    None => writeln!(out, "<blockquote>{author}: {text}</blockquote>")?,
+                }
+            }
+            if let Some(text) = &message.text {
+                writeln!(out, "<p class=\"text\">{}</p>", escape_html(text))?;
+            }
+            if !message.attachments.is_empty() {
+                writeln!(out, "<ul class=\"attachments\">")?;
+                for attachment in &message.attachments {
+                    let name = attachment.file_name.as_deref().unwrap_or("attachment");
+                    let details = [
+                        attachment.content_type.clone(),
+                        attachment.size.map(|size| format!("{size} bytes")),
+                    ]
+                    .into_iter()
+                    .flatten()
+                    .collect::<Vec<_>>()
+                    .join(", ");
+                    writeln!(
+                        out,
+                        "<li>{} ({})</li>",
+                        escape_html(name),
+                        escape_html(&details)
+                    )?;
+                }
+                writeln!(out, "</ul>")?;
+            }
+            if !message.reactions.is_empty() {
+                writeln!(out, "<ul class=\"reactions\">")?;
+                for reaction in &message.reactions {
+                    writeln!(
+                        out,
+                        "<li>{} {}</li>",
+                        escape_html(&reaction.emoji),
+                        escape_html(&reaction.author.name)
+                    )?;
+                }
+                writeln!(out, "</ul>")?;
+            }
+            writeln!(out, "</li>")?;
+        }
+
+        writeln!(out, "</ol>\n</body>\n</html>")
+    }
+}
+
+/// Escapes `text` for use in HTML element content or a quoted attribute value.
+fn escape_html(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// Formats a timestamp as `YYYY-MM-DD HH:MM:SS UTC`.
+fn format_millis(millis: u64) -> String {
+    const SECS_PER_DAY: u64 = 24 * 60 * 60;
+    let secs = millis / 1000;
+    let (days, secs_of_day) = (secs / SECS_PER_DAY, secs % SECS_PER_DAY);
+
+    // Converts days since 1970-01-01 to a proleptic Gregorian date; see
+    // <https://howardhinnant.github.io/date_algorithms.html#civil_from_days>.
+    let z = days + 719_468;
+    let era = z / 146_097;
+    let day_of_era = z % 146_097;
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let shifted_month = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * shifted_month + 2) / 5 + 1;
+    let month = if shifted_month < 10 {
+        shifted_month + 3
+    } else {
+        shifted_month - 9
+    };
+    let year = year_of_era + era * 400 + u64::from(month <= 2);
+
+    format!(
+        "{year:04}-{month:02}-{day:02} {:02}:{:02}:{:02} UTC",
+        secs_of_day / 3600,
+        secs_of_day / 60 % 60,
+        secs_of_day % 60,
+    )
+}
+
+#[cfg(test)]
+mod test {
+    use assert_matches::assert_matches;
+    use serde_json::json;
+    use test_case::test_case;
+
+    use super::*;
+    use crate::backup::{convert_from_json, Purpose};
+    use crate::BackupReader;
+
+    const FIXTURE: &str =
+        include_str!("../../../tests/res/test-cases/valid/incoming-message-with-edits.jsonproto");
+
+    /// Han Solo's ACI in the fixture.
+    const HAN_ACI: &str = "5f8c568d-0119-47bd-81aa-bb87c9b71995";
+
+    /// The fixture's group chat with a reply from Chewie that quotes Han's message.
+    fn backup() -> Backup {
+        let mut json: Vec<serde_json::Value> = json5::from_str(FIXTURE).expect("valid JSON");
+        json.push(json!({
+            "chatItem": {
+                "authorId": 5,
+                "chatId": 1,
+                "dateSent": 86_400_000 + 4000,
+                "incoming": {
+                    "dateReceived": 86_400_000 + 4002,
+                    "dateServerSent": 86_400_000 + 4001,
+                    "read": true,
+                    "sealedSender": true,
+                },
+                "standardMessage": {
+                    "text": { "body": "<script>alert('rrargh')</script> & more" },
+                    "quote": {
+                        "targetSentTimestamp": 3000,
+                        "authorId": 4,
+                        "text": { "body": "Latest revision" },
+                        "type": "NORMAL",
+                    },
+                    "attachments": [{
+                        "pointer": {
+                            "attachmentLocator": {
+                                "cdnKey": "spanner",
+                                "cdnNumber": 2,
+                                "uploadTimestamp": 1,
+                                "key": "AAAA",
+                                "digest": "AAAA",
+                                "size": 1234,
+                            },
+                            "contentType": "image/png",
+                            "fileName": "plasma spanner.png",
+                        },
+                        "flag": "NONE",
+                    }],
+                },
+            }
+        }));
+
+        let reader = BackupReader::new_unencrypted(
+            futures::io::Cursor::new(convert_from_json(json).expect("valid frames")),
+            Purpose::RemoteBackup,
+        );
+        futures::executor::block_on(reader.read_all())
+            .result
+            .expect("valid backup")
+    }
+
+    fn export(recipient: &str, format: ChatExportFormat) -> Result<Vec<u8>, ChatExportError> {
+        let mut out = vec![];
+        export_chat(
+            &backup(),
+            &recipient.parse().expect("valid recipient"),
+            format,
+            &mut out,
+        )?;
+        Ok(out)
+    }
+
+    #[test]
+    fn json_export() {
+        let exported: serde_json::Value =
+            serde_json::from_slice(&export("6", ChatExportFormat::Json).expect("can export"))
+                .expect("valid JSON");
+
+        assert_eq!(
+            exported["with"],
+            json!({ "recipientId": 6, "name": "Millenium Falcon Mechanics" })
+        );
+        let messages = exported["messages"].as_array().expect("has messages");
+        assert_eq!(messages.len(), 2);
+
+        assert_eq!(
+            messages[0]["author"],
+            json!({ "recipientId": 4, "name": "Han Solo" })
+        );
+        assert_eq!(messages[0]["sentAt"], 3000);
+        assert_eq!(messages[0]["direction"], "incoming");
+        assert_eq!(messages[0]["text"], "Latest revision: \u{fffc}");
+        assert_eq!(
+            messages[0]["reactions"],
+            json!([
+                {
+                    "emoji": "👀",
+                    "author": { "recipientId": 1, "name": "Boba Fett" },
+                    "sentAt": 101,
+                },
+                {
+                    "emoji": "🥂",
+                    "author": { "recipientId": 4, "name": "Han Solo" },
+                    "sentAt": 102,
+                },
+            ])
+        );
+
+        assert_eq!(messages[1]["author"]["name"], "Chew Bacca");
+        assert_eq!(
+            messages[1]["quote"],
+            json!({
+                "author": { "recipientId": 4, "name": "Han Solo" },
+                "messageIndex": 0,
+                "text": "Latest revision",
+            })
+        );
+        assert_eq!(
+            messages[1]["attachments"],
+            json!([{
+                "fileName": "plasma spanner.png",
+                "contentType": "image/png",
+                "size": 1234,
+            }])
+        );
+    }
+
+    #[test]
+    fn html_export_escapes_text() {
+        let html =
+            String::from_utf8(export("6", ChatExportFormat::Html).expect("can export")).unwrap();
+
+        assert!(!html.contains("<script>"), "{html}");
+        assert!(
+            html.contains("&lt;script&gt;alert(&#39;rrargh&#39;)&lt;/script&gt; &amp; more"),
+            "{html}"
+        );
+        assert!(
+            html.contains("<a href=\"#message-0\">Han Solo</a>"),
+            "{html}"
+        );
+        assert!(html.contains("1970-01-02 00:00:04 UTC"), "{html}");
+    }
+
+    #[test_case("4"; "recipient ID")]
+    #[test_case(HAN_ACI; "service ID")]
+    fn contact_without_chat(recipient: &str) {
+        assert_matches!(
+            export(recipient, ChatExportFormat::Json),
+            Err(ChatExportError::NoChat(_))
+        );
+    }
+
+    #[test]
+    fn unknown_recipient() {
+        assert_matches!(
+            export("99", ChatExportFormat::Json),
+            Err(ChatExportError::UnknownRecipient(
+                ChatRecipient::RecipientId(99)
+            ))
+        );
+    }
+
+    #[test]
+    fn parse_chat_recipient() {
+        assert_eq!(
+            "12".parse::<ChatRecipient>().ok(),
+            Some(ChatRecipient::RecipientId(12))
+        );
+        assert_matches!(
+            HAN_ACI.parse::<ChatRecipient>(),
+            Ok(ChatRecipient::ServiceId(ServiceId::Aci(_)))
+        );
+        assert_matches!("han".parse::<ChatRecipient>(), Err(InvalidChatRecipient));
+    }
+}
@@ -276,6 +276,108 @@ impl TryFrom<proto::MessageAttachment> for MessageAttachment {
     }
 }
 
+impl AttachmentLocator {
+    fn to_proto(&self) -> proto::file_pointer::Locator {
+        match self {
+            Self::Backup {
+                cdn_number,
+                key,
+                digest,
+                is_thumbnail,
+                size,
+                transit_cdn_key,
+                transit_cdn_number,
+            } => {
+                let mut media_name = digest.encode_hex::<String>();
+                if *is_thumbnail {
+                    media_name.push_str("_thumbnail");
+                }
+                proto::file_pointer::Locator::BackupLocator(proto::file_pointer::BackupLocator {
+                    mediaName: media_name,
+                    cdnNumber: *cdn_number,
+                    key: key.clone(),
+                    digest: digest.clone(),
+                    size: *size,
+                    transitCdnKey: transit_cdn_key.clone(),
+                    transitCdnNumber: *transit_cdn_number,
+                    special_fields: Default::default(),
+                })
+            }
+            Self::Transit {
+                cdn_key,
+                cdn_number,
+                upload_timestamp,
+                key,
+                digest,
+                size,
+            } => proto::file_pointer::Locator::AttachmentLocator(
+                proto::file_pointer::AttachmentLocator {
+                    cdnKey: cdn_key.clone(),
+                    cdnNumber: *cdn_number,
+                    uploadTimestamp: upload_timestamp.as_millis(),
+                    key: key.clone(),
+                    digest: digest.clone(),
+                    size: *size,
+                    special_fields: Default::default(),
+                },
+            ),
+            Self::Invalid => {
+                proto::file_pointer::Locator::InvalidAttachmentLocator(Default::default())
+            }
+        }
+    }
+}
+
+impl FilePointer {
+    pub(crate) fn to_proto(&self) -> proto::FilePointer {
+        let Self {
+            locator,
+            content_type,
+            incremental_mac,
+            incremental_mac_chunk_size,
+            file_name,
+            width,
+            height,
+            caption,
+            blur_hash,
+            _limit_construction_to_module: _,
+        } = self;
+
+        proto::FilePointer {
+            locator: Some(locator.to_proto()),
+            contentType: content_type.clone(),
+            incrementalMac: incremental_mac.clone(),
+            incrementalMacChunkSize: *incremental_mac_chunk_size,
+            fileName: file_name.clone(),
+            width: *width,
+            height: *height,
+            caption: caption.clone(),
+            blurHash: blur_hash.clone(),
+            special_fields: Default::default(),
+        }
+    }
+}
+
+impl MessageAttachment {
+    pub(crate) fn to_proto(&self) -> proto::MessageAttachment {
+        let Self {
+            pointer,
+            flag,
+            client_uuid,
+            _limit_construction_to_module: _,
+        } = self;
+
+        proto::MessageAttachment {
+            pointer: Some(pointer.to_proto()).into(),
+            flag: (*flag).into(),
+            clientUuid: client_uuid.map(|uuid| uuid.into_bytes().to_vec()),
+            // Not kept once validated.
+            wasDownloaded: false,
+            special_fields: Default::default(),
+        }
+    }
+}
+
 #[cfg(test)]
 mod test {
     use hex_literal::hex;
@@ -285,7 +387,7 @@ mod test {
     use crate::backup::time::testutil::MillisecondsSinceEpoch;
 
     impl proto::file_pointer::BackupLocator {
-        fn test_data() -> Self {
+        pub(crate) fn test_data() -> Self {
             Self {
                 mediaName: "5678".into(),
                 cdnNumber: Some(3),
@@ -343,7 +445,7 @@ mod test {
     }
 
     impl proto::file_pointer::AttachmentLocator {
-        fn test_data() -> Self {
+        pub(crate) fn test_data() -> Self {
             Self {
                 cdnKey: "ABCDEFG".into(),
                 cdnNumber: 3,
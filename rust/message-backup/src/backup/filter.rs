@@ -0,0 +1,165 @@
+//
+// Copyright (C) 2024 Signal Messenger, LLC.
+// SPDX-License-Identifier: AGPL-3.0-only
+//
+
+//! Reducing a backup to its recent history.
+//!
+//! A newly linked device doesn't need a primary device's entire message history to be useful.
+//! [`recent_subset`] keeps everything a chat item might refer to, but only the chat items sent
+//! since a cutoff, so that the result is still a valid backup.
+
+use std::collections::HashSet;
+
+use crate::backup::time::Timestamp;
+use crate::backup::Backup;
+use crate::proto::backup as proto;
+use crate::proto::backup::frame::Item as FrameItem;
+
+/// Produces the frames of `backup` with only the chat items sent at or after `cutoff`.
+///
+/// Account data, recipients, sticker packs, and ad hoc calls are always kept, since chat items
+/// (and each other) can refer to them. Archived chats are dropped along with all their items
+/// unless `include_archived` is set. Chats that have no items left are kept, so that their
+/// settings carry over.
+///
+/// The frames are produced in the order [`Backup::to_frames`] produces them, and can be written
+/// out after [`Backup::to_backup_info`].
+pub fn recent_subset(
+    backup: &Backup,
+    cutoff: Timestamp,
+    include_archived: bool,
+) -> Vec<proto::Frame> {
+    let cutoff = cutoff.as_millis();
+    let mut dropped_chats = HashSet::new();
+
+    backup
+        .to_frames()
+        .filter(|frame| match &frame.item {
+            Some(FrameItem::Chat(chat)) => {
+                let keep = include_archived || !chat.archived;
+                if !keep {
+                    dropped_chats.insert(chat.id);
+                }
+                keep
+            }
+            // Chats always come before their items, since all the chats are written first.
+            Some(FrameItem::ChatItem(item)) => {
+                !dropped_chats.contains(&item.chatId) && item.dateSent >= cutoff
+            }
+            _ => true,
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod test {
+    use protobuf::Message as _;
+
+    use super::*;
+    use crate::backup::{convert_from_json, Purpose};
+    use crate::BackupReader;
+
+    const FIXTURE: &str =
+        include_str!("../../tests/res/test-cases/valid/simple-chat-update-message.jsonproto");
+
+    /// Falls partway through the fixture's chat items, whose timestamps run from 1 to 20.
+    const CUTOFF_MILLIS: u64 = 10;
+    const RECENT_ITEMS_IN_CHAT_1: usize = 10;
+    const RECENT_ITEMS_IN_CHAT_2: usize = 2;
+
+    fn read(serialized: &[u8]) -> Backup {
+        let reader = BackupReader::new_unencrypted(
+            futures::io::Cursor::new(serialized),
+            Purpose::DeviceTransfer,
+        );
+        futures::executor::block_on(reader.read_all())
+            .result
+            .expect("valid backup")
+    }
+
+    fn fixture(archive_chat_2: bool) -> Backup {
+        let mut json: Vec<serde_json::Value> = json5::from_str(FIXTURE).expect("valid JSON");
+        if archive_chat_2 {
+            let chat = json
+                .iter_mut()
+                .filter_map(|frame| frame.get_mut("chat"))
+                .find(|chat| chat["id"] == 2)
+                .expect("has chat 2");
+            chat["archived"] = true.into();
+        }
+        read(&convert_from_json(json).expect("valid frames"))
+    }
+
+    fn write(backup: &Backup, frames: &[proto::Frame]) -> Vec<u8> {
+        let mut serialized = Vec::new();
+        backup
+            .to_backup_info()
+            .write_length_delimited_to_vec(&mut serialized)
+            .expect("can serialize");
+        for frame in frames {
+            frame
+                .write_length_delimited_to_vec(&mut serialized)
+                .expect("can serialize");
+        }
+        serialized
+    }
+
+    fn count(frames: &[proto::Frame], is_kind: impl Fn(&FrameItem) -> bool) -> usize {
+        frames
+            .iter()
+            .filter(|frame| frame.item.as_ref().is_some_and(&is_kind))
+            .count()
+    }
+
+    fn chat_items_in(frames: &[proto::Frame], chat_id: u64) -> usize {
+        count(
+            frames,
+            |item| matches!(item, FrameItem::ChatItem(item) if item.chatId == chat_id),
+        )
+    }
+
+    #[test]
+    fn keeps_recent_chat_items_and_everything_they_refer_to() {
+        let backup = fixture(false);
+        let all_frames = backup.to_frames().collect::<Vec<_>>();
+
+        let frames = recent_subset(
+            &backup,
+            Timestamp::from_millis(CUTOFF_MILLIS, "test"),
+            false,
+        );
+
+        assert_eq!(chat_items_in(&frames, 1), RECENT_ITEMS_IN_CHAT_1);
+        assert_eq!(chat_items_in(&frames, 2), RECENT_ITEMS_IN_CHAT_2);
+        let kept_kinds: [fn(&FrameItem) -> bool; 3] = [
+            |item| matches!(item, FrameItem::Account(_)),
+            |item| matches!(item, FrameItem::Recipient(_)),
+            |item| matches!(item, FrameItem::Chat(_)),
+        ];
+        for is_kind in kept_kinds {
+            assert_eq!(count(&frames, is_kind), count(&all_frames, is_kind));
+        }
+
+        let reread = read(&write(&backup, &frames));
+        assert_eq!(reread.to_frames().collect::<Vec<_>>(), frames);
+    }
+
+    #[test]
+    fn archived_chats_are_dropped_unless_requested() {
+        let backup = fixture(true);
+        let cutoff = Timestamp::from_millis(CUTOFF_MILLIS, "test");
+        let is_chat = |item: &FrameItem| matches!(item, FrameItem::Chat(_));
+
+        let frames = recent_subset(&backup, cutoff, false);
+        assert_eq!(count(&frames, is_chat), 1);
+        assert_eq!(chat_items_in(&frames, 1), RECENT_ITEMS_IN_CHAT_1);
+        assert_eq!(chat_items_in(&frames, 2), 0);
+        read(&write(&backup, &frames));
+
+        let frames = recent_subset(&backup, cutoff, true);
+        assert_eq!(count(&frames, is_chat), 2);
+        assert_eq!(chat_items_in(&frames, 2), RECENT_ITEMS_IN_CHAT_2);
+        read(&write(&backup, &frames));
+    }
+}
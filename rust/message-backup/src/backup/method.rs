@@ -44,7 +44,7 @@ pub trait Method: serde::Serialize {
 #[derive(serde::Serialize)]
 pub enum ValidateOnly {}
 
-#[derive(Default, Debug)]
+#[derive(Clone, Default, Debug)]
 pub struct ValidateOnlyList;
 
 impl<T> Extend<T> for ValidateOnlyList {
@@ -0,0 +1,446 @@
+//
+// Copyright (C) 2024 Signal Messenger, LLC.
+// SPDX-License-Identifier: AGPL-3.0-only
+//
+
+//! Ordering a backup's frames for import.
+//!
+//! A client importing a backup into its own database wants to insert everything a row refers to
+//! before the row itself, and to do so in transactions of bounded size. [`plan`] splits a
+//! validated [`Backup`] into [`ImportBatch`]es that satisfy both: each batch holds frames of a
+//! single kind, and every reference made by a frame in a batch is to something provided by a
+//! frame in an earlier batch.
+
+use std::collections::HashSet;
+use std::num::NonZeroUsize;
+
+use strum::IntoEnumIterator as _;
+
+use crate::backup::Backup;
+use crate::proto::backup as proto;
+use crate::proto::backup::frame::Item as FrameItem;
+
+/// What the frames in an [`ImportBatch`] contain.
+///
+/// Batches of each kind are emitted in the order listed here, as long as their references allow.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash, strum::EnumIter)]
+pub enum ImportBatchKind {
+    /// The account data, including the custom chat colors that chat styles can refer to.
+    AccountData,
+    /// Recipients other than distribution lists.
+    Recipients,
+    /// Distribution list recipients, which refer to their member recipients.
+    DistributionLists,
+    Chats,
+    ChatItems,
+    StickerPacks,
+    AdHocCalls,
+}
+
+/// Frames that can be imported together.
+#[derive(Debug, PartialEq)]
+pub struct ImportBatch {
+    pub kind: ImportBatchKind,
+    /// The frames in this batch, in the order they appear in the backup.
+    pub frames: Vec<proto::Frame>,
+}
+
+/// Something a frame can provide that other frames can refer to.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum ImportKey {
+    CustomColor(u64),
+    Recipient(u64),
+    Chat(u64),
+}
+
+/// {kind:?} frame refers to {reference:?}, which is not provided by any frame
+#[derive(Debug, displaydoc::Display, thiserror::Error)]
+pub struct UnresolvedReferenceError {
+    pub kind: ImportBatchKind,
+    pub reference: ImportKey,
+}
+
+/// Splits a validated backup into batches of at most `max_batch_len` frames, in an order that's
+/// safe to import.
+///
+/// The frames are the ones produced by [`Backup::to_frames`]; see [`plan_frames`] for the
+/// details.
+pub fn plan(backup: &Backup, max_batch_len: NonZeroUsize) -> Vec<ImportBatch> {
+    plan_frames(backup.to_frames(), max_batch_len)
+        .expect("validation rejects references to frames that aren't present")
+}
+
+/// Splits `frames` into batches of at most `max_batch_len` frames, in an order that's safe to
+/// import.
+///
+/// Every batch only contains frames of one [kind](ImportBatchKind), and every reference made by a
+/// frame in a batch is to something provided by a frame in an earlier batch. Subject to that,
+/// frames of the same kind keep their relative order from `frames`, and as few batches are used
+/// as possible.
+///
+/// Unlike [`plan`], this doesn't require that `frames` has been validated, so references may
+/// come before the frames they refer to, or never be resolved at all.
+pub fn plan_frames(
+    frames: impl IntoIterator<Item = proto::Frame>,
+    max_batch_len: NonZeroUsize,
+) -> Result<Vec<ImportBatch>, UnresolvedReferenceError> {
+    // Frames without an item can't be in a validated backup, and there's nothing to import for
+    // them anyway.
+    let mut pending = frames
+        .into_iter()
+        .filter_map(PlannedFrame::new)
+        .collect::<Vec<_>>();
+    let mut provided = HashSet::new();
+    let mut batches = Vec::new();
+
+    // Each round emits every frame whose references have all been provided, in kind order. In a
+    // validated backup everything gets emitted in the first round; later rounds are only needed
+    // when frames refer to others of the same kind, or (for unvalidated frames) to something of a
+    // later kind.
+    while !pending.is_empty() {
+        let pending_before_round = pending.len();
+
+        for kind in ImportBatchKind::iter() {
+            let (ready, not_ready) = pending.into_iter().partition::<Vec<_>, _>(|frame| {
+                frame.kind == kind
+                    && frame
+                        .references
+                        .iter()
+                        .all(|reference| provided.contains(reference))
+            });
+            pending = not_ready;
+
+            provided.extend(
+                ready
+                    .iter()
+                    .flat_map(|frame| frame.provides.iter().copied()),
+            );
+            let mut ready = ready.into_iter().map(|frame| frame.frame).peekable();
+            while ready.peek().is_some() {
+                batches.push(ImportBatch {
+                    kind,
+                    frames: ready.by_ref().take(max_batch_len.get()).collect(),
+                });
+            }
+        }
+
+        if pending.len() == pending_before_round {
+            // Nothing could be emitted this round, so nothing ever will be. Report the first
+            // reference that's holding things up.
+            let (kind, reference) = pending
+                .iter()
+                .find_map(|frame| {
+                    frame
+                        .references
+                        .iter()
+                        .find(|reference| !provided.contains(reference))
+                        .map(|reference| (frame.kind, *reference))
+                })
+                .expect("a frame can only be pending if it has an unresolved reference");
+            return Err(UnresolvedReferenceError { kind, reference });
+        }
+    }
+
+    Ok(batches)
+}
+
+/// A frame along with what it provides and refers to.
+struct PlannedFrame {
+    frame: proto::Frame,
+    kind: ImportBatchKind,
+    provides: Vec<ImportKey>,
+    references: Vec<ImportKey>,
+}
+
+impl PlannedFrame {
+    fn new(frame: proto::Frame) -> Option<Self> {
+        let (kind, provides, references) = match frame.item.as_ref()? {
+            FrameItem::Account(account_data) => {
+                let settings = account_data.accountSettings.as_ref();
+                let provides = settings
+                    .map(|settings| {
+                        settings
+                            .customChatColors
+                            .iter()
+                            .map(|color| ImportKey::CustomColor(color.id))
+                            .collect()
+                    })
+                    .unwrap_or_default();
+                // The default chat style can only refer to colors from the same frame.
+                (ImportBatchKind::AccountData, provides, vec![])
+            }
+            FrameItem::Recipient(recipient) => {
+                let provides = vec![ImportKey::Recipient(recipient.id)];
+                match &recipient.destination {
+                    Some(proto::recipient::Destination::DistributionList(item)) => {
+                        let members = match &item.item {
+                            Some(proto::distribution_list_item::Item::DistributionList(list)) => {
+                                list.memberRecipientIds
+                                    .iter()
+                                    .map(|id| ImportKey::Recipient(*id))
+                                    .collect()
+                            }
+                            Some(proto::distribution_list_item::Item::DeletionTimestamp(_))
+                            | None => vec![],
+                        };
+                        (ImportBatchKind::DistributionLists, provides, members)
+                    }
+                    _ => (ImportBatchKind::Recipients, provides, vec![]),
+                }
+            }
+            FrameItem::Chat(chat) => {
+                let mut references = vec![ImportKey::Recipient(chat.recipientId)];
+                references.extend(chat.style.as_ref().and_then(custom_color_reference));
+                (
+                    ImportBatchKind::Chats,
+                    vec![ImportKey::Chat(chat.id)],
+                    references,
+                )
+            }
+            // Chat items can refer to other recipients in their contents (quotes, reactions, and
+            // so on), but all recipients that don't refer to anything themselves are emitted
+            // before any chat items, so only the references that decide placement are tracked.
+            FrameItem::ChatItem(item) => (
+                ImportBatchKind::ChatItems,
+                vec![],
+                vec![
+                    ImportKey::Chat(item.chatId),
+                    ImportKey::Recipient(item.authorId),
+                ],
+            ),
+            FrameItem::StickerPack(_) => (ImportBatchKind::StickerPacks, vec![], vec![]),
+            FrameItem::AdHocCall(call) => (
+                ImportBatchKind::AdHocCalls,
+                vec![],
+                vec![ImportKey::Recipient(call.recipientId)],
+            ),
+        };
+        Some(Self {
+            frame,
+            kind,
+            provides,
+            references,
+        })
+    }
+}
+
+fn custom_color_reference(style: &proto::ChatStyle) -> Option<ImportKey> {
+    match style.bubbleColor {
+        Some(proto::chat_style::BubbleColor::CustomColorId(id)) => Some(ImportKey::CustomColor(id)),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::collections::HashMap;
+
+    use assert_matches::assert_matches;
+    use nonzero_ext::nonzero;
+
+    use super::*;
+
+    const CONTACT_ID: u64 = 1;
+    const DISTRIBUTION_LIST_ID: u64 = 2;
+    const SELF_ID: u64 = 3;
+    const CUSTOM_COLOR_ID: u64 = 50;
+
+    fn frame(item: impl Into<FrameItem>) -> proto::Frame {
+        proto::Frame {
+            item: Some(item.into()),
+            ..Default::default()
+        }
+    }
+
+    fn contact(id: u64) -> proto::Frame {
+        frame(proto::Recipient {
+            id,
+            destination: Some(proto::recipient::Destination::Contact(Default::default())),
+            ..Default::default()
+        })
+    }
+
+    fn distribution_list(id: u64, members: Vec<u64>) -> proto::Frame {
+        frame(proto::Recipient {
+            id,
+            destination: Some(proto::recipient::Destination::DistributionList(
+                proto::DistributionListItem {
+                    item: Some(proto::distribution_list_item::Item::DistributionList(
+                        proto::DistributionList {
+                            memberRecipientIds: members,
+                            ..Default::default()
+                        },
+                    )),
+                    ..Default::default()
+                },
+            )),
+            ..Default::default()
+        })
+    }
+
+    fn chat(id: u64, recipient_id: u64, custom_color_id: Option<u64>) -> proto::Frame {
+        frame(proto::Chat {
+            id,
+            recipientId: recipient_id,
+            style: custom_color_id
+                .map(|id| proto::ChatStyle {
+                    bubbleColor: Some(proto::chat_style::BubbleColor::CustomColorId(id)),
+                    ..Default::default()
+                })
+                .into(),
+            ..Default::default()
+        })
+    }
+
+    fn chat_item(chat_id: u64, author_id: u64) -> proto::Frame {
+        frame(proto::ChatItem {
+            chatId: chat_id,
+            authorId: author_id,
+            ..Default::default()
+        })
+    }
+
+    fn account_data() -> proto::Frame {
+        frame(proto::AccountData {
+            accountSettings: Some(proto::account_data::AccountSettings {
+                customChatColors: vec![proto::chat_style::CustomChatColor {
+                    id: CUSTOM_COLOR_ID,
+                    ..Default::default()
+                }],
+                ..Default::default()
+            })
+            .into(),
+            ..Default::default()
+        })
+    }
+
+    /// Frames that refer to other frames that come after them.
+    fn forward_referencing_frames() -> Vec<proto::Frame> {
+        vec![
+            chat_item(10, SELF_ID),
+            chat(10, CONTACT_ID, Some(CUSTOM_COLOR_ID)),
+            chat_item(10, CONTACT_ID),
+            distribution_list(DISTRIBUTION_LIST_ID, vec![CONTACT_ID]),
+            chat(11, SELF_ID, None),
+            chat_item(11, SELF_ID),
+            contact(CONTACT_ID),
+            frame(proto::StickerPack::default()),
+            contact(SELF_ID),
+            account_data(),
+        ]
+    }
+
+    /// Checks that every reference made in a batch is to something provided by an earlier one,
+    /// and that each kind's frames are in their original order.
+    fn assert_dependency_safe(frames: &[proto::Frame], batches: &[ImportBatch]) {
+        let mut provided = HashSet::new();
+        for batch in batches {
+            let planned = batch
+                .frames
+                .iter()
+                .map(|frame| PlannedFrame::new(frame.clone()).expect("has item"))
+                .collect::<Vec<_>>();
+            for frame in &planned {
+                assert_eq!(frame.kind, batch.kind);
+                for reference in &frame.references {
+                    assert!(
+                        provided.contains(reference),
+                        "{:?} refers to {reference:?} before it's provided",
+                        batch.kind
+                    );
+                }
+            }
+            provided.extend(
+                planned
+                    .iter()
+                    .flat_map(|frame| frame.provides.iter().copied()),
+            );
+        }
+
+        let position_in_input = |frame: &proto::Frame| {
+            frames
+                .iter()
+                .position(|f| f == frame)
+                .expect("planned frames come from the input")
+        };
+        let mut last_position_by_kind = HashMap::new();
+        let mut planned_count = 0;
+        for batch in batches {
+            for frame in &batch.frames {
+                let position = position_in_input(frame);
+                if let Some(last) = last_position_by_kind.insert(batch.kind, position) {
+                    assert!(last < position, "{:?} frames out of order", batch.kind);
+                }
+                planned_count += 1;
+            }
+        }
+        assert_eq!(planned_count, frames.len());
+    }
+
+    #[test]
+    fn forward_references_are_resolved() {
+        let frames = forward_referencing_frames();
+        let batches = plan_frames(frames.clone(), nonzero!(100usize)).expect("can plan");
+        assert_dependency_safe(&frames, &batches);
+
+        assert_eq!(
+            batches.iter().map(|batch| batch.kind).collect::<Vec<_>>(),
+            [
+                ImportBatchKind::AccountData,
+                ImportBatchKind::Recipients,
+                ImportBatchKind::DistributionLists,
+                ImportBatchKind::Chats,
+                ImportBatchKind::ChatItems,
+                ImportBatchKind::StickerPacks,
+            ]
+        );
+    }
+
+    #[test]
+    fn batches_are_limited_in_size() {
+        let frames = forward_referencing_frames();
+        let batches = plan_frames(frames.clone(), nonzero!(2usize)).expect("can plan");
+        assert_dependency_safe(&frames, &batches);
+
+        assert!(batches.iter().all(|batch| batch.frames.len() <= 2));
+        assert_eq!(
+            batches
+                .iter()
+                .map(|batch| (batch.kind, batch.frames.len()))
+                .collect::<Vec<_>>(),
+            [
+                (ImportBatchKind::AccountData, 1),
+                (ImportBatchKind::Recipients, 2),
+                (ImportBatchKind::DistributionLists, 1),
+                (ImportBatchKind::Chats, 2),
+                (ImportBatchKind::ChatItems, 2),
+                (ImportBatchKind::ChatItems, 1),
+                (ImportBatchKind::StickerPacks, 1),
+            ]
+        );
+    }
+
+    #[test]
+    fn chat_with_unknown_custom_color_is_unresolved() {
+        let frames = [contact(CONTACT_ID), chat(10, CONTACT_ID, Some(9999))];
+        assert_matches!(
+            plan_frames(frames, nonzero!(100usize)),
+            Err(UnresolvedReferenceError {
+                kind: ImportBatchKind::Chats,
+                reference: ImportKey::CustomColor(9999),
+            })
+        );
+    }
+
+    #[test]
+    fn distribution_list_with_unknown_member_is_unresolved() {
+        let frames = [distribution_list(DISTRIBUTION_LIST_ID, vec![CONTACT_ID])];
+        assert_matches!(
+            plan_frames(frames, nonzero!(100usize)),
+            Err(UnresolvedReferenceError {
+                kind: ImportBatchKind::DistributionLists,
+                reference: ImportKey::Recipient(CONTACT_ID),
+            })
+        );
+    }
+}
@@ -0,0 +1,76 @@
+//
+// Copyright (C) 2024 Signal Messenger, LLC.
+// SPDX-License-Identifier: AGPL-3.0-only
+//
+
+//! Hooks for applying custom rules to the contents of a backup as it's read.
+//!
+//! A [`FramePolicy`] is consulted after each recipient, chat item, and ad-hoc call has been
+//! validated, and can reject the backup or flag the frame for the caller to report.
+
+pub use crate::backup::call::AdHocCall;
+pub use crate::backup::chat::{ChatItemData, ChatItemMessage};
+pub use crate::backup::method::Method;
+pub use crate::backup::recipient::DestinationKind;
+pub use crate::backup::ReferencedTypes;
+
+/// What a [`FramePolicy`] decided about one frame.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub enum PolicyDecision {
+    /// The frame is acceptable.
+    #[default]
+    Allow,
+    /// The frame is acceptable, but is reported in
+    /// [`ReadResult::policy_flags`](crate::ReadResult::policy_flags).
+    Flag(String),
+    /// The frame is not acceptable, and reading stops with
+    /// [`Error::PolicyViolation`](crate::Error::PolicyViolation).
+    Reject(String),
+}
+
+/// Custom rules for the contents of a backup, on top of the usual validation.
+///
+/// Every check allows everything unless overridden.
+pub trait FramePolicy {
+    fn check_recipient(&self, _recipient: &DestinationKind) -> PolicyDecision {
+        PolicyDecision::Allow
+    }
+
+    fn check_chat_item<M: Method + ReferencedTypes>(
+        &self,
+        _item: &ChatItemData<M>,
+    ) -> PolicyDecision {
+        PolicyDecision::Allow
+    }
+
+    fn check_ad_hoc_call<Recipient>(&self, _call: &AdHocCall<Recipient>) -> PolicyDecision {
+        PolicyDecision::Allow
+    }
+}
+
+/// The [`FramePolicy`] used unless another is provided, which allows everything.
+#[derive(Copy, Clone, Debug, Default)]
+pub struct AllowAll;
+
+impl FramePolicy for AllowAll {}
+
+/// Object-safe form of [`FramePolicy`] for a single [`Method`].
+pub(super) trait FramePolicyFor<M: Method + ReferencedTypes> {
+    fn check_recipient(&self, recipient: &DestinationKind) -> PolicyDecision;
+    fn check_chat_item(&self, item: &ChatItemData<M>) -> PolicyDecision;
+    fn check_ad_hoc_call(&self, call: &AdHocCall<M::RecipientReference>) -> PolicyDecision;
+}
+
+impl<M: Method + ReferencedTypes, P: FramePolicy> FramePolicyFor<M> for P {
+    fn check_recipient(&self, recipient: &DestinationKind) -> PolicyDecision {
+        FramePolicy::check_recipient(self, recipient)
+    }
+
+    fn check_chat_item(&self, item: &ChatItemData<M>) -> PolicyDecision {
+        FramePolicy::check_chat_item(self, item)
+    }
+
+    fn check_ad_hoc_call(&self, call: &AdHocCall<M::RecipientReference>) -> PolicyDecision {
+        FramePolicy::check_ad_hoc_call(self, call)
+    }
+}
@@ -18,7 +18,7 @@ use crate::backup::frame::RecipientId;
 use crate::backup::method::{LookupPair, Method, Store, ValidateOnly};
 use crate::backup::serialize::{self, SerializeOrder, UnorderedList};
 use crate::backup::time::Timestamp;
-use crate::backup::{ReferencedTypes, TryFromWith, TryIntoWith};
+use crate::backup::{ProtoContext, ReferencedTypes, TryFromWith, TryIntoWith};
 use crate::proto::backup as proto;
 use crate::proto::backup::recipient::Destination as RecipientDestination;
 
@@ -30,6 +30,10 @@ use group::*;
 pub enum RecipientError {
     /// multiple frames with the same ID
     DuplicateRecipient,
+    /// multiple Self recipients: {0:?} and {1:?}
+    MultipleSelf(RecipientId, RecipientId),
+    /// multiple Release Notes recipients: {0:?} and {1:?}
+    MultipleReleaseNotes(RecipientId, RecipientId),
     /// Recipient.destination is a oneof but is empty
     MissingDestination,
     /// invalid {0}
@@ -72,7 +76,7 @@ pub enum RecipientError {
 ///
 /// This is intentionally the minimal amount of data required to validate later
 /// frames.
-#[derive(Debug)]
+#[derive(Clone, Debug)]
 #[cfg_attr(test, derive(PartialEq))]
 pub struct MinimalRecipientData(DestinationKind);
 
@@ -128,10 +132,15 @@ impl AsRef<DestinationKind> for DestinationKind {
 /// The ordering should be considered arbitrary; a proper ordering of E164s would use a
 /// lexicographic ordering of the decimal digits, but that costs more in CPU. Use the string
 /// representation as a sort key if sorting for human consumption.
-#[derive(Debug, serde::Serialize, PartialEq, Eq, PartialOrd, Ord)]
-#[serde(transparent)]
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord)]
 pub struct E164(NonZeroU64);
 
+impl serde::Serialize for E164 {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serialize::e164(self.0.get(), serializer)
+    }
+}
+
 impl TryFrom<u64> for E164 {
     type Error = <NonZeroU64 as TryFrom<u64>>::Error;
 
@@ -146,12 +155,43 @@ impl From<E164> for u64 {
     }
 }
 
+impl From<&E164> for u64 {
+    fn from(value: &E164) -> Self {
+        value.0.into()
+    }
+}
+
 impl std::fmt::Display for E164 {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         write!(f, "+{}", self.0)
     }
 }
 
+/// invalid e164
+#[derive(Debug, displaydoc::Display, thiserror::Error)]
+#[cfg_attr(test, derive(PartialEq))]
+pub struct InvalidE164;
+
+impl std::str::FromStr for E164 {
+    type Err = InvalidE164;
+
+    /// Parses the `+<digits>` form produced by [`Display`](std::fmt::Display).
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        /// The longest number E.164 allows, not counting the `+`.
+        const MAX_DIGITS: usize = 15;
+
+        let digits = s.strip_prefix('+').ok_or(InvalidE164)?;
+        if digits.is_empty()
+            || digits.len() > MAX_DIGITS
+            || !digits.bytes().all(|b| b.is_ascii_digit())
+        {
+            return Err(InvalidE164);
+        }
+        let value: u64 = digits.parse().expect("at most 15 digits fits in u64");
+        value.try_into().map_err(|_| InvalidE164)
+    }
+}
+
 #[derive(Debug, serde::Serialize)]
 #[cfg_attr(test, derive(PartialEq))]
 pub struct ContactData {
@@ -232,6 +272,11 @@ impl FullRecipientData {
     pub(crate) fn new(data: Destination<Store>) -> Self {
         Self(Arc::new(data))
     }
+
+    /// Identifies the shared data, which every reference to the same recipient points to.
+    pub(crate) fn as_ptr(&self) -> *const Destination<Store> {
+        Arc::as_ptr(&self.0)
+    }
 }
 
 impl AsRef<DestinationKind> for FullRecipientData {
@@ -507,6 +552,124 @@ impl<R: Clone, C: LookupPair<RecipientId, DestinationKind, R>>
     }
 }
 
+impl Destination<Store> {
+    pub(crate) fn to_proto(&self, context: &ProtoContext) -> RecipientDestination {
+        match self {
+            Destination::Contact(contact) => RecipientDestination::Contact(contact.to_proto()),
+            Destination::Group(group) => RecipientDestination::Group(group.to_proto()),
+            Destination::DistributionList(list) => {
+                RecipientDestination::DistributionList(list.to_proto(context))
+            }
+            Destination::Self_ => RecipientDestination::Self_(Default::default()),
+            Destination::ReleaseNotes => RecipientDestination::ReleaseNotes(Default::default()),
+            Destination::CallLink(call_link) => {
+                RecipientDestination::CallLink(call_link.to_proto())
+            }
+        }
+    }
+}
+
+impl ContactData {
+    fn to_proto(&self) -> proto::Contact {
+        let Self {
+            aci,
+            pni,
+            profile_key,
+            username,
+            registration,
+            e164,
+            blocked,
+            visibility,
+            profile_sharing,
+            profile_given_name,
+            profile_family_name,
+            hide_story,
+        } = self;
+
+        let registration = match registration {
+            Registration::NotRegistered { unregistered_at } => {
+                proto::contact::Registration::NotRegistered(proto::contact::NotRegistered {
+                    unregisteredTimestamp: unregistered_at.map_or(0, |at| at.as_millis()),
+                    special_fields: Default::default(),
+                })
+            }
+            Registration::Registered => {
+                proto::contact::Registration::Registered(Default::default())
+            }
+        };
+
+        proto::Contact {
+            aci: aci.map(|aci| Uuid::from(aci).into_bytes().to_vec()),
+            pni: pni.map(|pni| Uuid::from(pni).into_bytes().to_vec()),
+            profileKey: profile_key.map(|key| key.to_vec()),
+            username: username.clone(),
+            e164: e164.as_ref().map(|e164| e164.0.get()),
+            blocked: *blocked,
+            visibility: (*visibility).into(),
+            registration: Some(registration),
+            profileSharing: *profile_sharing,
+            profileGivenName: profile_given_name.clone(),
+            profileFamilyName: profile_family_name.clone(),
+            hideStory: *hide_story,
+            special_fields: Default::default(),
+        }
+    }
+}
+
+impl DistributionListItem<FullRecipientData> {
+    fn to_proto(&self, context: &ProtoContext) -> proto::DistributionListItem {
+        let (distribution_id, item) = match self {
+            Self::Deleted {
+                distribution_id,
+                at,
+            } => (
+                distribution_id,
+                proto::distribution_list_item::Item::DeletionTimestamp(at.as_millis()),
+            ),
+            Self::List {
+                distribution_id,
+                name,
+                allow_replies,
+                privacy_mode,
+            } => {
+                let (privacy_mode, members) = match privacy_mode {
+                    PrivacyMode::OnlyWith(members) => (
+                        proto::distribution_list::PrivacyMode::ONLY_WITH,
+                        Some(members),
+                    ),
+                    PrivacyMode::AllExcept(members) => (
+                        proto::distribution_list::PrivacyMode::ALL_EXCEPT,
+                        Some(members),
+                    ),
+                    PrivacyMode::All => (proto::distribution_list::PrivacyMode::ALL, None),
+                };
+                (
+                    distribution_id,
+                    proto::distribution_list_item::Item::DistributionList(
+                        proto::DistributionList {
+                            name: name.clone(),
+                            allowReplies: *allow_replies,
+                            privacyMode: privacy_mode.into(),
+                            memberRecipientIds: members
+                                .into_iter()
+                                .flat_map(|members| &members.0)
+                                .map(|member| context.recipient_id(member))
+                                .collect(),
+                            special_fields: Default::default(),
+                        },
+                    ),
+                )
+            }
+        };
+
+        proto::DistributionListItem {
+            distributionId: distribution_id.as_bytes().to_vec(),
+            item: Some(item),
+            special_fields: Default::default(),
+        }
+    }
+}
+
 #[cfg(test)]
 mod test {
     use assert_matches::assert_matches;
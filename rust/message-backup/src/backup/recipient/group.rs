@@ -8,8 +8,10 @@
 // crate, but we want intra-crate privacy.
 #![allow(clippy::manual_non_exhaustive)]
 
+use std::collections::HashSet;
+
 use itertools::Itertools as _;
-use libsignal_core::ServiceIdKind;
+use libsignal_core::{Aci, ServiceId, ServiceIdKind};
 use zkgroup::GroupMasterKeyBytes;
 
 use crate::backup::serialize::{self, UnorderedList};
@@ -79,6 +81,12 @@ pub enum GroupError {
     MemberPendingProfileKeyHasProfileKey,
     /// MemberPendingProfileKey's userId and addedByUserId are the same
     MemberPendingProfileKeyWasInvitedBySelf,
+    /// {0:?} appears more than once across the member lists
+    MemberInMultipleLists(ServiceId),
+    /// group has members but none of them are administrators
+    NoAdministrator,
+    /// MemberPendingProfileKey was added by {0:?}, who is not a member
+    MemberPendingProfileKeyInviterNotMember(Aci),
 }
 
 impl proto::group::group_attribute_blob::Content {
@@ -257,6 +265,13 @@ impl TryFrom<proto::group::GroupSnapshot> for GroupSnapshot {
             .map(GroupMemberBanned::try_from)
             .try_collect()?;
 
+        check_member_lists(
+            &members,
+            &members_pending_profile_key,
+            &members_pending_admin_approval,
+            &members_banned,
+        )?;
+
         Ok(Self {
             title,
             description,
@@ -277,6 +292,132 @@ impl TryFrom<proto::group::GroupSnapshot> for GroupSnapshot {
     }
 }
 
+impl GroupSnapshot {
+    fn to_proto(&self) -> proto::group::GroupSnapshot {
+        use proto::group::group_attribute_blob::Content;
+
+        let Self {
+            title,
+            description,
+            avatar_url,
+            disappearing_messages_timer,
+            access_control_attributes,
+            access_control_members,
+            access_control_add_from_invite_link,
+            version,
+            members,
+            members_pending_profile_key,
+            members_pending_admin_approval,
+            invite_link_password,
+            announcements_only,
+            members_banned,
+            _limit_construction_to_module: _,
+        } = self;
+
+        let blob = |content| proto::group::GroupAttributeBlob {
+            content: Some(content),
+            special_fields: Default::default(),
+        };
+
+        proto::group::GroupSnapshot {
+            title: title
+                .clone()
+                .map(|title| blob(Content::Title(title)))
+                .into(),
+            description: description
+                .clone()
+                .map(|description| blob(Content::DescriptionText(description)))
+                .into(),
+            avatarUrl: avatar_url.clone(),
+            disappearingMessagesTimer: disappearing_messages_timer
+                .map(|timer| {
+                    blob(Content::DisappearingMessagesDuration(
+                        timer
+                            .as_millis()
+                            .try_into()
+                            .expect("read from u32 milliseconds"),
+                    ))
+                })
+                .into(),
+            accessControl: Some(proto::group::AccessControl {
+                attributes: (*access_control_attributes).into(),
+                members: (*access_control_members).into(),
+                addFromInviteLink: (*access_control_add_from_invite_link).into(),
+                special_fields: Default::default(),
+            })
+            .into(),
+            version: *version,
+            members: members.0.iter().map(GroupMember::to_proto).collect(),
+            membersPendingProfileKey: members_pending_profile_key
+                .0
+                .iter()
+                .map(GroupMemberPendingProfileKey::to_proto)
+                .collect(),
+            membersPendingAdminApproval: members_pending_admin_approval
+                .0
+                .iter()
+                .map(GroupMemberPendingAdminApproval::to_proto)
+                .collect(),
+            inviteLinkPassword: invite_link_password.clone(),
+            announcements_only: *announcements_only,
+            members_banned: members_banned
+                .0
+                .iter()
+                .map(GroupMemberBanned::to_proto)
+                .collect(),
+            special_fields: Default::default(),
+        }
+    }
+}
+
+/// Checks the constraints that involve more than one of a snapshot's member lists.
+fn check_member_lists(
+    members: &UnorderedList<GroupMember>,
+    members_pending_profile_key: &UnorderedList<GroupMemberPendingProfileKey>,
+    members_pending_admin_approval: &UnorderedList<GroupMemberPendingAdminApproval>,
+    members_banned: &UnorderedList<GroupMemberBanned>,
+) -> Result<(), GroupError> {
+    let all_user_ids = itertools::chain!(
+        members.0.iter().map(|m| ServiceId::from(m.user_id)),
+        members_pending_profile_key.0.iter().map(|m| m.user_id),
+        members_pending_admin_approval
+            .0
+            .iter()
+            .map(|m| ServiceId::from(m.user_id)),
+        members_banned.0.iter().map(|m| m.user_id),
+    );
+    let mut seen = HashSet::new();
+    for user_id in all_user_ids {
+        if !seen.insert(user_id) {
+            return Err(GroupError::MemberInMultipleLists(user_id));
+        }
+    }
+
+    // A snapshot with no full members is for a group the user is still pending in (or has left),
+    // so there's nothing to check roles or inviters against.
+    if members.is_empty() {
+        return Ok(());
+    }
+
+    if !members.0.iter().any(|m| m.role == Role::Administrator) {
+        return Err(GroupError::NoAdministrator);
+    }
+
+    for invited in &members_pending_profile_key.0 {
+        if !members
+            .0
+            .iter()
+            .any(|m| m.user_id == invited.added_by_user_id)
+        {
+            return Err(GroupError::MemberPendingProfileKeyInviterNotMember(
+                invited.added_by_user_id,
+            ));
+        }
+    }
+
+    Ok(())
+}
+
 #[derive(Debug, serde::Serialize)]
 #[cfg_attr(test, derive(PartialEq))]
 pub struct GroupData {
@@ -328,6 +469,28 @@ impl TryFrom<proto::Group> for GroupData {
     }
 }
 
+impl GroupData {
+    pub(crate) fn to_proto(&self) -> proto::Group {
+        let Self {
+            master_key,
+            whitelisted,
+            hide_story,
+            story_send_mode,
+            snapshot,
+            _limit_construction_to_module: _,
+        } = self;
+
+        proto::Group {
+            masterKey: master_key.to_vec(),
+            whitelisted: *whitelisted,
+            hideStory: *hide_story,
+            storySendMode: (*story_send_mode).into(),
+            snapshot: Some(snapshot.to_proto()).into(),
+            special_fields: Default::default(),
+        }
+    }
+}
+
 #[cfg(test)]
 mod test {
     use libsignal_core::Aci;
@@ -336,6 +499,10 @@ mod test {
     use super::*;
     use crate::proto::backup::group::access_control::AccessRequired;
 
+    const INVITED_ACI: [u8; 16] = [0xc1; 16];
+    const REQUESTING_ACI: [u8; 16] = [0xc2; 16];
+    const BANNED_ACI: [u8; 16] = [0xc3; 16];
+
     impl proto::Group {
         pub(crate) const TEST_MASTER_KEY: GroupMasterKeyBytes = [0x33; 32];
 
@@ -378,16 +545,29 @@ mod test {
                     })
                     .into(),
                     version: 5,
-                    members: vec![proto::group::Member::test_data()],
-                    membersPendingProfileKey: vec![
-                        proto::group::MemberPendingProfileKey::test_data(),
-                    ],
-                    membersPendingAdminApproval: vec![
-                        proto::group::MemberPendingAdminApproval::test_data(),
-                    ],
+                    members: vec![proto::group::Member {
+                        role: proto::group::member::Role::ADMINISTRATOR.into(),
+                        ..proto::group::Member::test_data()
+                    }],
+                    membersPendingProfileKey: vec![proto::group::MemberPendingProfileKey {
+                        member: Some(proto::group::Member {
+                            userId: INVITED_ACI.to_vec(),
+                            ..proto::group::Member::test_data()
+                        })
+                        .into(),
+                        addedByUserId: proto::Contact::TEST_ACI.to_vec(),
+                        ..proto::group::MemberPendingProfileKey::test_data()
+                    }],
+                    membersPendingAdminApproval: vec![proto::group::MemberPendingAdminApproval {
+                        userId: REQUESTING_ACI.to_vec(),
+                        ..proto::group::MemberPendingAdminApproval::test_data()
+                    }],
                     inviteLinkPassword: vec![0x05; 5],
                     announcements_only: true,
-                    members_banned: vec![proto::group::MemberBanned::test_data()],
+                    members_banned: vec![proto::group::MemberBanned {
+                        userId: BANNED_ACI.to_vec(),
+                        ..proto::group::MemberBanned::test_data()
+                    }],
                     ..Default::default()
                 })
                 .into(),
@@ -413,18 +593,29 @@ mod test {
                     access_control_members: AccessRequired::MEMBER,
                     access_control_add_from_invite_link: AccessRequired::ANY,
                     version: 5,
-                    members: vec![GroupMember::from_proto_test_data()].into(),
-                    members_pending_profile_key: vec![
-                        GroupMemberPendingProfileKey::from_proto_test_data(),
-                    ]
+                    members: vec![GroupMember {
+                        role: Role::Administrator,
+                        ..GroupMember::from_proto_test_data()
+                    }]
+                    .into(),
+                    members_pending_profile_key: vec![GroupMemberPendingProfileKey {
+                        user_id: Aci::from_uuid_bytes(INVITED_ACI).into(),
+                        added_by_user_id: Aci::from_uuid_bytes(proto::Contact::TEST_ACI),
+                        ..GroupMemberPendingProfileKey::from_proto_test_data()
+                    }]
                     .into(),
-                    members_pending_admin_approval: vec![
-                        GroupMemberPendingAdminApproval::from_proto_test_data(),
-                    ]
+                    members_pending_admin_approval: vec![GroupMemberPendingAdminApproval {
+                        user_id: Aci::from_uuid_bytes(REQUESTING_ACI),
+                        ..GroupMemberPendingAdminApproval::from_proto_test_data()
+                    }]
                     .into(),
                     invite_link_password: vec![0x05; 5],
                     announcements_only: true,
-                    members_banned: vec![GroupMemberBanned::from_proto_test_data()].into(),
+                    members_banned: vec![GroupMemberBanned {
+                        user_id: Aci::from_uuid_bytes(BANNED_ACI).into(),
+                        ..GroupMemberBanned::from_proto_test_data()
+                    }]
+                    .into(),
                     _limit_construction_to_module: (),
                 },
                 _limit_construction_to_module: (),
@@ -448,6 +639,8 @@ mod test {
         GroupData::try_from(group).map(|_| ())
     }
 
+    const TEST_MEMBER: Aci = Aci::from_uuid_bytes(proto::Contact::TEST_ACI);
+
     #[test_case(|x| x.title = None.into() => Ok(()); "missing title")]
     #[test_case(|x| x.title = Some(Default::default()).into() => Err(GroupError::BlobMissingContent { which: "title" }); "empty title blob")]
     #[test_case(|x| x.title = x.disappearingMessagesTimer.clone() => Err(GroupError::BlobWrongContent { which: "title", unexpected: "disappearingMessagesDuration" }); "wrong title blob")]
@@ -463,6 +656,15 @@ mod test {
     #[test_case(|x| x.accessControl.as_mut().unwrap().addFromInviteLink = AccessRequired::MEMBER.into() => Err(GroupError::InvalidAccess { which: "addFromInviteLink", access: AccessRequired::MEMBER }); "bad addFromInviteLink AccessRequired")]
     #[test_case(|x| x.inviteLinkPassword = vec![] => Ok(()); "empty invite link password")]
     #[test_case(|x| x.members[0].userId = vec![] => Err(GroupError::MemberInvalidServiceId { which: "member" }); "bad member")]
+    #[test_case(|x| x.members.push(x.members[0].clone()) => Err(GroupError::MemberInMultipleLists(TEST_MEMBER.into())); "repeated member")]
+    #[test_case(|x| x.membersPendingProfileKey[0].member.as_mut().unwrap().userId = REQUESTING_ACI.to_vec() => Err(GroupError::MemberInMultipleLists(Aci::from_uuid_bytes(REQUESTING_ACI).into())); "invited also requesting")]
+    #[test_case(|x| x.membersPendingAdminApproval[0].userId = x.members[0].userId.clone() => Err(GroupError::MemberInMultipleLists(TEST_MEMBER.into())); "member also requesting")]
+    #[test_case(|x| x.members_banned[0].userId = x.members[0].userId.clone() => Err(GroupError::MemberInMultipleLists(TEST_MEMBER.into())); "member also banned")]
+    #[test_case(|x| x.members_banned[0].userId = INVITED_ACI.to_vec() => Err(GroupError::MemberInMultipleLists(Aci::from_uuid_bytes(INVITED_ACI).into())); "invited also banned")]
+    #[test_case(|x| x.members[0].role = proto::group::member::Role::DEFAULT.into() => Err(GroupError::NoAdministrator); "no administrator")]
+    #[test_case(|x| x.members.clear() => Ok(()); "pending with no members")]
+    #[test_case(|x| x.membersPendingProfileKey[0].addedByUserId = BANNED_ACI.to_vec() => Err(GroupError::MemberPendingProfileKeyInviterNotMember(Aci::from_uuid_bytes(BANNED_ACI))); "inviter not a member")]
+
     fn group_snapshot(
         modifier: impl FnOnce(&mut proto::group::GroupSnapshot),
     ) -> Result<(), GroupError> {
@@ -26,6 +26,15 @@ pub struct GroupMember {
     pub(super) _limit_construction_to_module: (),
 }
 
+impl Role {
+    fn to_proto(self) -> proto::group::member::Role {
+        match self {
+            Role::Default => proto::group::member::Role::DEFAULT,
+            Role::Administrator => proto::group::member::Role::ADMINISTRATOR,
+        }
+    }
+}
+
 impl SerializeOrder for GroupMember {
     fn serialize_cmp(&self, other: &Self) -> std::cmp::Ordering {
         self.user_id.cmp(&other.user_id)
@@ -68,6 +77,24 @@ impl TryFrom<proto::group::Member> for GroupMember {
     }
 }
 
+impl GroupMember {
+    pub(super) fn to_proto(&self) -> proto::group::Member {
+        let Self {
+            user_id,
+            role,
+            joined_at_version,
+            _limit_construction_to_module: _,
+        } = self;
+
+        proto::group::Member {
+            userId: user_id.service_id_binary(),
+            role: role.to_proto().into(),
+            joinedAtVersion: *joined_at_version,
+            special_fields: Default::default(),
+        }
+    }
+}
+
 #[derive(Debug, serde::Serialize)]
 #[cfg_attr(test, derive(PartialEq, Clone))]
 pub struct GroupMemberPendingProfileKey {
@@ -146,6 +173,32 @@ impl TryFrom<proto::group::MemberPendingProfileKey> for GroupMemberPendingProfil
     }
 }
 
+impl GroupMemberPendingProfileKey {
+    pub(super) fn to_proto(&self) -> proto::group::MemberPendingProfileKey {
+        let Self {
+            user_id,
+            role,
+            joined_at_version,
+            added_by_user_id,
+            timestamp,
+            _limit_construction_to_module: _,
+        } = self;
+
+        proto::group::MemberPendingProfileKey {
+            member: Some(proto::group::Member {
+                userId: user_id.service_id_binary(),
+                role: role.to_proto().into(),
+                joinedAtVersion: *joined_at_version,
+                special_fields: Default::default(),
+            })
+            .into(),
+            addedByUserId: added_by_user_id.service_id_binary(),
+            timestamp: timestamp.as_millis(),
+            special_fields: Default::default(),
+        }
+    }
+}
+
 #[derive(Debug, serde::Serialize)]
 #[cfg_attr(test, derive(PartialEq, Clone))]
 pub struct GroupMemberPendingAdminApproval {
@@ -192,6 +245,22 @@ impl TryFrom<proto::group::MemberPendingAdminApproval> for GroupMemberPendingAdm
     }
 }
 
+impl GroupMemberPendingAdminApproval {
+    pub(super) fn to_proto(&self) -> proto::group::MemberPendingAdminApproval {
+        let Self {
+            user_id,
+            timestamp,
+            _limit_construction_to_module: _,
+        } = self;
+
+        proto::group::MemberPendingAdminApproval {
+            userId: user_id.service_id_binary(),
+            timestamp: timestamp.as_millis(),
+            special_fields: Default::default(),
+        }
+    }
+}
+
 #[derive(Debug, serde::Serialize)]
 #[cfg_attr(test, derive(PartialEq, Clone))]
 pub struct GroupMemberBanned {
@@ -232,6 +301,22 @@ impl TryFrom<proto::group::MemberBanned> for GroupMemberBanned {
     }
 }
 
+impl GroupMemberBanned {
+    pub(super) fn to_proto(&self) -> proto::group::MemberBanned {
+        let Self {
+            user_id,
+            timestamp,
+            _limit_construction_to_module: _,
+        } = self;
+
+        proto::group::MemberBanned {
+            userId: user_id.service_id_binary(),
+            timestamp: timestamp.as_millis(),
+            special_fields: Default::default(),
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use libsignal_core::{Pni, ServiceIdKind};
@@ -3,9 +3,10 @@
 // SPDX-License-Identifier: AGPL-3.0-only
 //
 
+use itertools::Itertools as _;
 use libsignal_core::ServiceId;
 use protobuf::Enum as _;
-use serde::ser::{SerializeStruct as _, SerializeTupleVariant as _};
+use serde::ser::SerializeStruct as _;
 use serde::{Serialize, Serializer};
 use uuid::Uuid;
 
@@ -21,6 +22,9 @@ use crate::backup::sticker::{PackId as StickerPackId, StickerPack};
 use crate::backup::{BackupMeta, ChatsData, CompletedBackup};
 use crate::proto::backup as proto;
 
+mod redact;
+pub use redact::RedactionPolicy;
+
 mod unordered_list;
 pub use unordered_list::UnorderedList;
 
@@ -36,11 +40,67 @@ pub struct Backup {
     sticker_packs: UnorderedList<(StickerPackId, StickerPack<Store>)>,
 }
 
+/// Version tag for the output of [`Backup::to_canonical_string`].
+///
+/// Bump this whenever a change to the validated types changes the canonical output for an
+/// existing backup, so that stored comparison strings can't be mistaken for current ones.
+pub const CANONICAL_VERSION: u32 = 2;
+
 impl Backup {
     #[cfg(feature = "json")]
     pub fn to_string_pretty(&self) -> String {
         serde_json::to_string_pretty(self).expect("can't fail serialization")
     }
+
+    /// Serializes to a JSON string that is stable across library versions.
+    ///
+    /// Unlike [`Self::to_string_pretty`], object keys are sorted rather than being emitted in
+    /// declaration order, and the result is wrapped in an object that records the
+    /// [`CANONICAL_VERSION`]. Maps and unordered collections are already serialized as sorted
+    /// lists by the types in this crate.
+    ///
+    /// # Panics
+    ///
+    /// Panics if any value serializes as a floating-point number, since those don't have a
+    /// stable textual representation.
+    #[cfg(feature = "json")]
+    pub fn to_canonical_string(&self) -> String {
+        let backup = serde_json::to_value(self).expect("can't fail serialization");
+        let versioned = serde_json::json!({
+            "version": CANONICAL_VERSION,
+            "backup": canonicalize(backup),
+        });
+        serde_json::to_string_pretty(&versioned).expect("can't fail serialization")
+    }
+
+    /// Like [`Self::to_canonical_string`], but with account identifiers redacted according to
+    /// `policy`.
+    #[cfg(feature = "json")]
+    pub fn to_redacted_canonical_string(&self, policy: RedactionPolicy) -> String {
+        policy.apply(|| self.to_canonical_string())
+    }
+}
+
+/// Recursively sorts object keys and checks that no floats are present.
+#[cfg(feature = "json")]
+pub(crate) fn canonicalize(value: serde_json::Value) -> serde_json::Value {
+    use serde_json::Value;
+    match value {
+        Value::Null | Value::Bool(_) | Value::String(_) => value,
+        Value::Number(n) => {
+            assert!(!n.is_f64(), "floats are not allowed in canonical output");
+            Value::Number(n)
+        }
+        Value::Array(items) => items.into_iter().map(canonicalize).collect(),
+        Value::Object(fields) => {
+            let mut fields = Vec::from_iter(fields);
+            fields.sort_by(|(lhs, _), (rhs, _)| lhs.cmp(rhs));
+            fields
+                .into_iter()
+                .map(|(key, value)| (key, canonicalize(value)))
+                .collect()
+        }
+    }
 }
 
 impl From<CompletedBackup<Store>> for Backup {
@@ -57,6 +117,7 @@ impl From<CompletedBackup<Store>> for Backup {
                 },
             ad_hoc_calls,
             sticker_packs,
+            unknown_fields: _,
         } = value;
         Self {
             meta,
@@ -75,24 +136,37 @@ pub(crate) fn to_string<S: Serializer>(t: &impl ToString, s: S) -> Result<S::Ok,
     t.to_string().serialize(s)
 }
 
-/// Serializes using [`ServiceId::service_id_string`].
+/// Serializes using [`ServiceId::service_id_string`], subject to the current
+/// [`RedactionPolicy`].
 pub(crate) fn service_id_as_string<S: Serializer>(
     id: &(impl Copy + Into<ServiceId>),
     serializer: S,
 ) -> Result<S::Ok, S::Error> {
-    (*id).into().service_id_string().serialize(serializer)
+    RedactionPolicy::current()
+        .service_id((*id).into())
+        .serialize(serializer)
 }
 
-/// Serializes using [`ServiceId::service_id_string`].
+/// Serializes using [`ServiceId::service_id_string`], subject to the current
+/// [`RedactionPolicy`].
 pub(crate) fn optional_service_id_as_string<S: Serializer>(
     id: &Option<(impl Copy + Into<ServiceId>)>,
     serializer: S,
 ) -> Result<S::Ok, S::Error> {
     (*id)
-        .map(|id| id.into().service_id_string())
+        .map(|id| RedactionPolicy::current().service_id(id.into()))
         .serialize(serializer)
 }
 
+/// Serializes a phone number as an integer, or as a string if the current [`RedactionPolicy`]
+/// redacts it.
+pub(crate) fn e164<S: Serializer>(e164: u64, serializer: S) -> Result<S::Ok, S::Error> {
+    match RedactionPolicy::current().e164(e164) {
+        None => e164.serialize(serializer),
+        Some(redacted) => redacted.serialize(serializer),
+    }
+}
+
 /// Serializes [`protobuf::Enum`] types as strings.
 pub(crate) fn enum_as_string<S: Serializer>(
     source: &impl protobuf::Enum,
@@ -109,14 +183,41 @@ pub(crate) fn optional_hex<S: Serializer>(
     value.as_ref().map(hex::encode).serialize(serializer)
 }
 
-/// Serializes an optional bytestring as hex.
-pub(crate) fn list_of_hex<S: Serializer>(
+/// Serializes an unordered list of bytestrings as sorted hex strings.
+pub(crate) fn unordered_list_of_hex<S: Serializer>(
     value: &[impl AsRef<[u8]>],
     serializer: S,
 ) -> Result<S::Ok, S::Error> {
-    // From the implementation of Serialize for [T],
-    // https://docs.rs/serde/1.0.210/src/serde/ser/impls.rs.html#175-186
-    serializer.collect_seq(value.iter().map(hex::encode))
+    let mut items = value.iter().map(hex::encode).collect_vec();
+    items.sort();
+    serializer.collect_seq(items)
+}
+
+/// Serializes map entries as a list of `{"key": key, "value": value}` objects sorted by key.
+///
+/// JSON only allows string keys, and map iteration order isn't stable, so this is used
+/// instead of serializing as a map.
+pub(crate) fn map_as_sorted_entries<'a, K, V, S>(
+    entries: impl IntoIterator<Item = (&'a K, &'a V)>,
+    serializer: S,
+) -> Result<S::Ok, S::Error>
+where
+    K: Ord + Serialize + 'a,
+    V: Serialize + 'a,
+    S: Serializer,
+{
+    #[derive(serde::Serialize)]
+    struct Entry<'a, K, V> {
+        key: &'a K,
+        value: &'a V,
+    }
+
+    let mut entries = entries
+        .into_iter()
+        .map(|(key, value)| Entry { key, value })
+        .collect_vec();
+    entries.sort_by(|lhs, rhs| lhs.key.cmp(rhs.key));
+    serializer.collect_seq(entries)
 }
 
 /// Serialization helper for [`UnorderedList`].
@@ -347,27 +448,6 @@ impl serde::Serialize for proto::contact_attachment::PostalAddress {
     }
 }
 
-impl serde::Serialize for proto::learned_profile_chat_update::PreviousName {
-    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
-    where
-        S: Serializer,
-    {
-        match self {
-            proto::learned_profile_chat_update::PreviousName::E164(e164) => {
-                let mut tv = serializer.serialize_tuple_variant("PreviousName", 0, "E164", 1)?;
-                tv.serialize_field(e164)?;
-                tv.end()
-            }
-            proto::learned_profile_chat_update::PreviousName::Username(username) => {
-                let mut tv =
-                    serializer.serialize_tuple_variant("PreviousName", 1, "Username", 1)?;
-                tv.serialize_field(username)?;
-                tv.end()
-            }
-        }
-    }
-}
-
 #[cfg(test)]
 mod test {
 
@@ -406,6 +486,11 @@ mod test {
                 version: 1,
                 backup_time: Timestamp::test_value(),
                 purpose: crate::backup::Purpose::RemoteBackup,
+                unknown_fields: Default::default(),
+                frame_size_limits: Default::default(),
+                validation_time: Timestamp::test_value(),
+                backup_time_policy: Default::default(),
+                revision_storage: Default::default(),
             },
             account_data: AccountData::from_proto_test_data(),
             recipients: UnorderedList::default(),
@@ -423,6 +508,21 @@ mod test {
         );
     }
 
+    #[test]
+    #[should_panic(expected = "floats are not allowed")]
+    fn canonicalize_rejects_floats() {
+        canonicalize(serde_json::json!({ "nested": [{ "position": 0.5 }] }));
+    }
+
+    #[test]
+    fn canonicalize_sorts_keys() {
+        let value = canonicalize(serde_json::json!({ "b": { "d": 1, "c": 2 }, "a": [] }));
+        assert_eq!(
+            serde_json::to_string(&value).unwrap(),
+            r#"{"a":[],"b":{"c":2,"d":1}}"#
+        );
+    }
+
     trait Renumbered {
         fn renumbered(self) -> Self;
     }
@@ -500,6 +600,13 @@ mod test {
         }
     }
 
+    fn make_self_recipient() -> proto::Frame {
+        make_recipient(
+            SELF_ID,
+            &proto::recipient::Destination::Self_(Default::default()),
+        )
+    }
+
     fn standard_message(text: &str) -> proto::StandardMessage {
         proto::StandardMessage {
             text: Some(proto::Text {
@@ -515,16 +622,20 @@ mod test {
     const SECOND_CONTACT_CHAT_ID: ChatId = ChatId(2);
     const GROUP_CHAT_ID: ChatId = ChatId(3);
 
+    const SELF_ID: RecipientId = RecipientId(99);
     const FIRST_CONTACT_ID: RecipientId = RecipientId(100);
     const SECOND_CONTACT_ID: RecipientId = RecipientId(101);
     const GROUP_ID: RecipientId = RecipientId(102);
 
     #[test]
     fn shuffled_chats_and_recipient_ids() {
-        let base = vec![crate::proto::backup::Frame {
-            item: Some(proto::AccountData::test_data().into()),
-            special_fields: Default::default(),
-        }];
+        let base = vec![
+            crate::proto::backup::Frame {
+                item: Some(proto::AccountData::test_data().into()),
+                special_fields: Default::default(),
+            },
+            make_self_recipient(),
+        ];
 
         let first_contact = make_contact("first", 1);
         let second_contact = make_contact("second", 2);
@@ -605,6 +716,7 @@ mod test {
                 item: Some(proto::AccountData::test_data().into()),
                 special_fields: Default::default(),
             },
+            make_self_recipient(),
             make_recipient(FIRST_CONTACT_ID, &first_contact),
             make_chat(FIRST_CONTACT_CHAT_ID, FIRST_CONTACT_ID),
             make_recipient(SECOND_CONTACT_ID, &second_contact),
@@ -0,0 +1,151 @@
+//
+// Copyright (C) 2024 Signal Messenger, LLC.
+// SPDX-License-Identifier: AGPL-3.0-only
+//
+
+use std::cell::Cell;
+
+use libsignal_core::ServiceId;
+
+/// How much of each account identifier to keep when serializing a backup.
+///
+/// This applies to service IDs (ACIs and PNIs) and phone numbers; everything else is serialized
+/// as usual, so the structure of the output doesn't change.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, strum::EnumString, strum::Display)]
+#[strum(serialize_all = "lowercase")]
+pub enum RedactionPolicy {
+    /// Identifiers are serialized in full.
+    #[default]
+    None,
+    /// Service IDs keep only their kind and the first 3 characters of their UUID; phone numbers
+    /// keep only their country code and last 2 digits.
+    Partial,
+    /// Identifiers are replaced entirely.
+    Full,
+}
+
+thread_local! {
+    static CURRENT_POLICY: Cell<RedactionPolicy> = const { Cell::new(RedactionPolicy::None) };
+}
+
+const REDACTED: &str = "[redacted]";
+
+impl RedactionPolicy {
+    /// Runs `f` with this policy applied to any serialization it does on the current thread.
+    pub fn apply<T>(self, f: impl FnOnce() -> T) -> T {
+        struct Restore(RedactionPolicy);
+        impl Drop for Restore {
+            fn drop(&mut self) {
+                CURRENT_POLICY.set(self.0);
+            }
+        }
+
+        let _restore = Restore(CURRENT_POLICY.replace(self));
+        f()
+    }
+
+    pub(super) fn current() -> Self {
+        CURRENT_POLICY.get()
+    }
+
+    pub(super) fn service_id(self, id: ServiceId) -> String {
+        let full = id.service_id_string();
+        match self {
+            Self::None => full,
+            Self::Partial => {
+                // Keep the "PNI:" prefix, if any, so the kind of ID is still visible.
+                let uuid_start = full.len() - uuid::fmt::Hyphenated::LENGTH;
+                format!("{}...", &full[..uuid_start + 3])
+            }
+            Self::Full => REDACTED.to_owned(),
+        }
+    }
+
+    /// Returns the redacted form of `e164`, or `None` if it shouldn't be redacted.
+    pub(super) fn e164(self, e164: u64) -> Option<String> {
+        match self {
+            Self::None => None,
+            Self::Partial => {
+                let digits = e164.to_string();
+                let country_code_len = country_code_len(&digits);
+                let Some(hidden_len) = digits.len().checked_sub(country_code_len + 2) else {
+                    // Too short to be a real phone number; don't try to keep any of it.
+                    return Some(REDACTED.to_owned());
+                };
+                Some(format!(
+                    "+{}{}{}",
+                    &digits[..country_code_len],
+                    "*".repeat(hidden_len),
+                    &digits[country_code_len + hidden_len..]
+                ))
+            }
+            Self::Full => Some(REDACTED.to_owned()),
+        }
+    }
+}
+
+/// Returns the number of leading digits of `digits` that make up its country calling code.
+///
+/// Country codes are prefix-free, so this only needs to know which ones are shorter than the
+/// usual three digits.
+fn country_code_len(digits: &str) -> usize {
+    const TWO_DIGIT_CODES: &[&str] = &[
+        "20", "27", "30", "31", "32", "33", "34", "36", "39", "40", "41", "43", "44", "45", "46",
+        "47", "48", "49", "51", "52", "53", "54", "55", "56", "57", "58", "60", "61", "62", "63",
+        "64", "65", "66", "81", "82", "84", "86", "90", "91", "92", "93", "94", "95", "98",
+    ];
+    if digits.starts_with(['1', '7']) {
+        1
+    } else if TWO_DIGIT_CODES.iter().any(|code| digits.starts_with(code)) {
+        2
+    } else {
+        3
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use libsignal_core::{Aci, Pni};
+    use test_case::test_case;
+
+    use super::*;
+
+    const ACI: Aci = Aci::from_uuid_bytes([0xab; 16]);
+    const PNI: Pni = Pni::from_uuid_bytes([0xcd; 16]);
+
+    #[test_case(RedactionPolicy::None => "abababab-abab-abab-abab-abababababab")]
+    #[test_case(RedactionPolicy::Partial => "aba...")]
+    #[test_case(RedactionPolicy::Full => "[redacted]")]
+    fn aci(policy: RedactionPolicy) -> String {
+        policy.service_id(ACI.into())
+    }
+
+    #[test_case(RedactionPolicy::None => "PNI:cdcdcdcd-cdcd-cdcd-cdcd-cdcdcdcdcdcd")]
+    #[test_case(RedactionPolicy::Partial => "PNI:cdc...")]
+    #[test_case(RedactionPolicy::Full => "[redacted]")]
+    fn pni(policy: RedactionPolicy) -> String {
+        policy.service_id(PNI.into())
+    }
+
+    #[test_case(RedactionPolicy::None, 16505550101 => None)]
+    #[test_case(RedactionPolicy::Partial, 16505550101 => Some("+1********01".to_owned()))]
+    #[test_case(RedactionPolicy::Partial, 447700900123 => Some("+44********23".to_owned()))]
+    #[test_case(RedactionPolicy::Partial, 3548001234 => Some("+354*****34".to_owned()))]
+    #[test_case(RedactionPolicy::Partial, 441 => Some("[redacted]".to_owned()))]
+    #[test_case(RedactionPolicy::Full, 16505550101 => Some("[redacted]".to_owned()))]
+    fn e164(policy: RedactionPolicy, e164: u64) -> Option<String> {
+        policy.e164(e164)
+    }
+
+    #[test]
+    fn apply_restores_previous_policy() {
+        RedactionPolicy::Partial.apply(|| {
+            assert_eq!(RedactionPolicy::current(), RedactionPolicy::Partial);
+            RedactionPolicy::Full.apply(|| {
+                assert_eq!(RedactionPolicy::current(), RedactionPolicy::Full);
+            });
+            assert_eq!(RedactionPolicy::current(), RedactionPolicy::Partial);
+        });
+        assert_eq!(RedactionPolicy::current(), RedactionPolicy::None);
+    }
+}
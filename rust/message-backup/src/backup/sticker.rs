@@ -10,11 +10,16 @@
 
 use derive_where::derive_where;
 
+use crate::backup::chat::reactions::MAX_EMOJI_BYTES;
 use crate::backup::file::{FilePointer, FilePointerError};
-use crate::backup::method::Method;
+use crate::backup::method::{Method, Store};
 use crate::proto::backup as proto;
 
 /// Validated version of [`proto::StickerPack`].
+///
+/// A backed-up pack is only its ID (checked when the frame is added, since that's what packs are
+/// keyed by) and key. The stickers in it, and their emoji, aren't part of the backup; they're
+/// downloaded again using the key.
 #[derive_where(Debug)]
 #[derive(serde::Serialize)]
 #[cfg_attr(test, derive_where(PartialEq;
@@ -70,6 +75,10 @@ pub enum MessageStickerError {
     InvalidPackId,
     /// pack key is invalid
     InvalidPackKey,
+    /// "emoji" is an empty string
+    EmptyEmoji,
+    /// "emoji" is {0} bytes long, more than any single emoji
+    EmojiTooLong(usize),
     /// missing data pointer
     MissingDataPointer,
     /// data pointer: {0}
@@ -118,6 +127,16 @@ impl TryFrom<proto::Sticker> for MessageSticker {
             .try_into()
             .map_err(|_| MessageStickerError::InvalidPackKey)?;
 
+        // Held to the same limits as a reaction, except that it's optional.
+        if let Some(emoji) = &emoji {
+            if emoji.is_empty() {
+                return Err(MessageStickerError::EmptyEmoji);
+            }
+            if emoji.len() > MAX_EMOJI_BYTES {
+                return Err(MessageStickerError::EmojiTooLong(emoji.len()));
+            }
+        }
+
         let data = data
             .into_option()
             .ok_or(MessageStickerError::MissingDataPointer)?
@@ -134,12 +153,48 @@ impl TryFrom<proto::Sticker> for MessageSticker {
     }
 }
 
+impl StickerPack<Store> {
+    pub(crate) fn to_proto(&self, id: &PackId) -> proto::StickerPack {
+        let Self {
+            key,
+            _limit_construction_to_module: _,
+        } = self;
+
+        proto::StickerPack {
+            packId: id.0.to_vec(),
+            packKey: key.0.to_vec(),
+            special_fields: Default::default(),
+        }
+    }
+}
+
+impl MessageSticker {
+    pub(crate) fn to_proto(&self) -> proto::Sticker {
+        let Self {
+            pack_id,
+            pack_key,
+            sticker_id,
+            emoji,
+            data,
+            _limit_construction_to_module: _,
+        } = self;
+
+        proto::Sticker {
+            packId: pack_id.0.to_vec(),
+            packKey: pack_key.0.to_vec(),
+            stickerId: *sticker_id,
+            emoji: emoji.clone(),
+            data: Some(data.to_proto()).into(),
+            special_fields: Default::default(),
+        }
+    }
+}
+
 #[cfg(test)]
 mod test {
     use test_case::test_case;
 
     use super::*;
-    use crate::backup::method::Store;
 
     impl proto::StickerPack {
         pub(crate) const TEST_ID: PackId = PackId(Self::TEST_ID_BYTES);
@@ -147,7 +202,7 @@ mod test {
         const TEST_ID_BYTES: [u8; 16] = [0x22; 16];
         const TEST_KEY: [u8; 32] = [0x11; 32];
 
-        fn test_data() -> Self {
+        pub(crate) fn test_data() -> Self {
             Self {
                 packId: Self::TEST_ID_BYTES.into(),
                 packKey: Self::TEST_KEY.into(),
@@ -210,6 +265,10 @@ mod test {
     #[test_case(|x| x.packId = vec![123; 3] => Err(MessageStickerError::InvalidPackId); "invalid pack ID")]
     #[test_case(|x| x.stickerId = 555555 => Ok(()); "unknown sticker ID")]
     #[test_case(|x| x.packId = vec![0xff; 16] => Ok(()); "unknown pack ID")]
+    #[test_case(|x| x.emoji = Some("🦦".into()) => Ok(()); "emoji")]
+    #[test_case(|x| x.emoji = Some("".into()) => Err(MessageStickerError::EmptyEmoji); "empty emoji")]
+    #[test_case(|x| x.emoji = Some("👍🏽👍🏽".into()) => Ok(()); "emoji at max length")]
+    #[test_case(|x| x.emoji = Some("👍🏽👍🏽!".into()) => Err(MessageStickerError::EmojiTooLong(17)); "emoji too long")]
     #[test_case(|x| x.data = None.into() => Err(MessageStickerError::MissingDataPointer); "no data")]
     #[test_case(
         |x| x.data = Some(proto::FilePointer::default()).into() =>
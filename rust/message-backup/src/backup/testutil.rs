@@ -16,7 +16,7 @@ use crate::backup::method::{Lookup, LookupPair};
 use crate::backup::recipient::group::GroupData;
 use crate::backup::recipient::{ContactData, Destination, DestinationKind, FullRecipientData};
 use crate::backup::time::Timestamp;
-use crate::backup::{BackupMeta, Purpose};
+use crate::backup::{BackupMeta, FrameMeta, Purpose};
 
 pub(super) struct TestContext(pub(super) BackupMeta);
 
@@ -32,6 +32,11 @@ impl BackupMeta {
             backup_time: Timestamp::test_value(),
             purpose: Purpose::RemoteBackup,
             version: 0,
+            unknown_fields: Default::default(),
+            frame_size_limits: Default::default(),
+            validation_time: Timestamp::test_value(),
+            backup_time_policy: Default::default(),
+            revision_storage: Default::default(),
         }
     }
 }
@@ -43,12 +48,15 @@ static GROUP_RECIPIENT: Lazy<FullRecipientData> =
     Lazy::new(|| FullRecipientData::new(Destination::Group(GroupData::from_proto_test_data())));
 static CALL_LINK_RECIPIENT: Lazy<FullRecipientData> =
     Lazy::new(|| FullRecipientData::new(Destination::CallLink(CallLink::from_proto_test_data())));
+static RELEASE_NOTES_RECIPIENT: Lazy<FullRecipientData> =
+    Lazy::new(|| FullRecipientData::new(Destination::ReleaseNotes));
 
 impl TestContext {
     pub(super) const CONTACT_ID: RecipientId = RecipientId(123456789);
     pub(super) const SELF_ID: RecipientId = RecipientId(1111111111);
     pub(super) const GROUP_ID: RecipientId = RecipientId(7000000);
     pub(super) const CALL_LINK_ID: RecipientId = RecipientId(0xCA77);
+    pub(super) const RELEASE_NOTES_ID: RecipientId = RecipientId(0x4E7E5);
 }
 
 impl LookupPair<RecipientId, DestinationKind, FullRecipientData> for TestContext {
@@ -61,6 +69,9 @@ impl LookupPair<RecipientId, DestinationKind, FullRecipientData> for TestContext
             Self::SELF_ID => Some((&DestinationKind::Self_, &SELF_RECIPIENT)),
             Self::GROUP_ID => Some((&DestinationKind::Group, &GROUP_RECIPIENT)),
             Self::CALL_LINK_ID => Some((&DestinationKind::CallLink, &CALL_LINK_RECIPIENT)),
+            Self::RELEASE_NOTES_ID => {
+                Some((&DestinationKind::ReleaseNotes, &RELEASE_NOTES_RECIPIENT))
+            }
             _ => None,
         }
     }
@@ -78,6 +89,14 @@ impl AsRef<BackupMeta> for TestContext {
     }
 }
 
+impl AsRef<FrameMeta> for TestContext {
+    fn as_ref(&self) -> &FrameMeta {
+        &FrameMeta {
+            serialized_size: None,
+        }
+    }
+}
+
 impl Lookup<CustomColorId, Arc<CustomChatColor>> for TestContext {
     fn lookup<'a>(&'a self, key: &'a CustomColorId) -> Option<&'a Arc<CustomChatColor>> {
         (*key == Self::CUSTOM_CHAT_COLOR_ID).then(|| &*TEST_CUSTOM_COLOR)
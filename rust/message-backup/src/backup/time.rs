@@ -29,9 +29,36 @@ impl Timestamp {
         Self(UNIX_EPOCH + std::time::Duration::from_millis(since_epoch))
     }
 
+    /// The current time.
+    ///
+    /// wasm32 has no system clock, so there the JavaScript host is asked instead.
+    pub(super) fn now() -> Self {
+        #[cfg(not(all(target_arch = "wasm32", feature = "wasm")))]
+        let now = SystemTime::now();
+        #[cfg(all(target_arch = "wasm32", feature = "wasm"))]
+        let now = UNIX_EPOCH + std::time::Duration::from_millis(js_sys::Date::now() as u64);
+        Self::from_system_time(now)
+    }
+
+    /// Wraps a time that didn't come from the backup, like the current time.
+    ///
+    /// Times before [`UNIX_EPOCH`] are clamped to it.
+    pub(super) fn from_system_time(time: SystemTime) -> Self {
+        Self(time.max(UNIX_EPOCH))
+    }
+
     pub(super) fn into_inner(self) -> SystemTime {
         self.0
     }
+
+    pub fn as_millis(&self) -> u64 {
+        self.0
+            .duration_since(UNIX_EPOCH)
+            .expect("should not be possible to construct a Timestamp older than UNIX_EPOCH")
+            .as_millis()
+            .try_into()
+            .expect("constructed from u64 milliseconds")
+    }
 }
 
 impl serde::Serialize for Timestamp {
@@ -58,6 +85,13 @@ impl Duration {
         // std::time::Duration::from_hours isn't stable yet, but it's the same as this.
         Self(std::time::Duration::from_secs(60 * 60 * hours))
     }
+
+    pub(super) fn as_millis(&self) -> u64 {
+        self.0
+            .as_millis()
+            .try_into()
+            .expect("constructed from u64 milliseconds")
+    }
 }
 
 impl serde::Serialize for Duration {
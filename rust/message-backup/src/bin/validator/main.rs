@@ -10,13 +10,19 @@ use futures::io::AllowStdIo;
 use futures::AsyncRead;
 use libsignal_core::Aci;
 use libsignal_message_backup::args::{parse_aci, parse_hex_bytes};
-use libsignal_message_backup::backup::Purpose;
+#[cfg(feature = "json")]
+use libsignal_message_backup::backup::export::{ChatExportFormat, ChatRecipient};
+use libsignal_message_backup::backup::serialize::RedactionPolicy;
+use libsignal_message_backup::backup::{BackupTimeError, BackupTimePolicy, Purpose};
 use libsignal_message_backup::frame::{
-    CursorFactory, FileReaderFactory, FramesReader, ReaderFactory, UnvalidatedHmacReader,
-    VerifyHmac,
+    CursorFactory, FileReaderFactory, FramesReader, MmapReaderFactory, ReadProgress, ReaderFactory,
+    UnvalidatedHmacReader, VerifyHmac,
 };
 use libsignal_message_backup::key::{BackupKey, MessageBackupKey};
-use libsignal_message_backup::{BackupReader, Error, FoundUnknownField, ReadResult};
+use libsignal_message_backup::{
+    BackupReader, Error, FoundDuplicateChatItem, FoundExpireTimerVersionConflict,
+    FoundOversizedFrame, FoundUnknownField, ReadResult,
+};
 use mediasan_common::SeekSkipAdapter;
 
 use crate::args::ParseVerbosity;
@@ -27,8 +33,11 @@ mod args;
 ///
 /// Backups can be read from a file or from stdin. If no keys are provided, the
 /// backup is assumed to be a sequence of varint-delimited protos. Otherwise,
-/// the backup file is assumed to be an encrypted gzip-compressed sequence of
-/// followed by an HMAC of the contents.
+/// the backup file is assumed to be an encrypted sequence of varint-delimited
+/// protos, optionally gzip-compressed, followed by an HMAC of the contents.
+///
+/// Large backup files are memory-mapped, and must not be modified while they're
+/// being validated.
 #[derive(Debug, Parser)]
 struct Cli {
     /// filename to read the backup from, or - for stdin
@@ -43,10 +52,34 @@ struct Cli {
     #[arg(long)]
     print: bool,
 
+    /// with --print, prints the contents as canonical JSON instead of debug output (requires the "json" feature)
+    #[arg(long, requires = "print")]
+    json: bool,
+
+    /// with --print, masks ACIs, PNIs, and phone numbers ("partial" or "full"); implies --json
+    #[arg(long, requires = "print", conflicts_with = "verbose")]
+    redact: Option<RedactionPolicy>,
+
+    /// prints the chat with this recipient (a recipient ID, ACI, or PNI) to stdout as a standalone document (requires the "json" feature)
+    #[arg(long, conflicts_with = "print")]
+    export_chat: Option<String>,
+
+    /// with --export-chat, the format of the document ("json" or "html"; defaults to "json")
+    #[arg(long, requires = "export_chat")]
+    export_format: Option<String>,
+
     /// the purpose the backup is intended for
     #[arg(long, default_value_t=Purpose::RemoteBackup)]
     purpose: Purpose,
 
+    /// rejects remote backups made more than this many days ago (device transfers only get a warning)
+    #[arg(long)]
+    max_backup_age_days: Option<u32>,
+
+    /// always read the file with buffered reads, even if it's large enough to be memory-mapped
+    #[arg(long)]
+    no_mmap: bool,
+
     // TODO once https://github.com/clap-rs/clap/issues/5092 is resolved, make
     // `derive_key` and `key_parts` Optional at the top level.
     #[command(flatten)]
@@ -91,14 +124,38 @@ async fn async_main() {
         key_parts,
 
         purpose,
+        max_backup_age_days,
+        no_mmap,
         print,
+        json,
+        redact,
+        export_chat,
+        export_format,
         verbose,
     } = Cli::parse();
     env_logger::init();
 
-    let print = PrintOutput(print);
+    // Debug output can't be redacted, so redacting always prints JSON.
+    let redact = redact.unwrap_or_default();
+    let print = if let Some(recipient) = export_chat {
+        PrintOutput::chat(recipient, export_format)
+    } else {
+        match (print, json || redact != RedactionPolicy::None) {
+            (false, _) => PrintOutput::None,
+            (true, false) => PrintOutput::Debug,
+            (true, true) => {
+                if !cfg!(feature = "json") {
+                    panic!("--json requires building with the \"json\" feature");
+                }
+                PrintOutput::CanonicalJson(redact)
+            }
+        }
+    };
 
     let verbosity = verbose.into();
+    let backup_time_policy = BackupTimePolicy {
+        max_age_days: max_backup_age_days,
+    };
 
     let derive_key = {
         let DeriveKey { master_key, aci } = derive_key;
@@ -122,7 +179,7 @@ async fn async_main() {
         }
     };
 
-    let contents = FilenameOrContents::from(file_or_stdin);
+    let contents = FilenameOrContents::new(file_or_stdin, !no_mmap);
     let mut factory = AsyncReaderFactory::from(&contents);
 
     let reader = if let Some(key) = key {
@@ -139,19 +196,23 @@ async fn async_main() {
     };
 
     reader
-        .execute(print, verbosity)
+        .execute(print, verbosity, backup_time_policy)
         .await
         .unwrap_or_else(|e| panic!("backup error: {e:#}"));
 }
 
-/// Filename or in-memory buffer of contents.
+/// Regular files at least this big are memory-mapped unless `--no-mmap` is passed.
+const MMAP_THRESHOLD_BYTES: u64 = 16 << 20;
+
+/// Filename, memory-mapped file, or in-memory buffer of contents.
 enum FilenameOrContents {
     Filename(String),
+    Mapped(MmapReaderFactory),
     Contents(Box<[u8]>),
 }
 
-impl From<clap_stdin::FileOrStdin> for FilenameOrContents {
-    fn from(arg: clap_stdin::FileOrStdin) -> Self {
+impl FilenameOrContents {
+    fn new(arg: clap_stdin::FileOrStdin, allow_mmap: bool) -> Self {
         match arg.source {
             clap_stdin::Source::Stdin => {
                 let mut buffer = vec![];
@@ -161,7 +222,22 @@ impl From<clap_stdin::FileOrStdin> for FilenameOrContents {
                     .expect("failed to read from stdin");
                 Self::Contents(buffer.into_boxed_slice())
             }
-            clap_stdin::Source::Arg(path) => Self::Filename(path),
+            clap_stdin::Source::Arg(path) => {
+                let is_large_file = std::fs::metadata(&path)
+                    .is_ok_and(|m| m.is_file() && m.len() >= MMAP_THRESHOLD_BYTES);
+                if !(allow_mmap && is_large_file) {
+                    return Self::Filename(path);
+                }
+                // SAFETY: the validator only reads the file, and documents that it mustn't be
+                // modified while it's being validated.
+                match unsafe { MmapReaderFactory::open(&path) } {
+                    Ok(mapped) => Self::Mapped(mapped),
+                    Err(e) => {
+                        log::warn!("failed to map {path}, falling back to buffered reads: {e}");
+                        Self::Filename(path)
+                    }
+                }
+            }
         }
     }
 }
@@ -174,6 +250,7 @@ enum AsyncReaderFactory<'a> {
     // If that changes, this should be changed to an async-aware type, like
     // something from the `tokio` or `async-std` crates.
     File(FileReaderFactory<&'a str>),
+    Mapped(&'a MmapReaderFactory),
     Cursor(CursorFactory<&'a [u8]>),
 }
 
@@ -181,6 +258,7 @@ impl<'a> From<&'a FilenameOrContents> for AsyncReaderFactory<'a> {
     fn from(value: &'a FilenameOrContents) -> Self {
         match value {
             FilenameOrContents::Filename(path) => Self::File(FileReaderFactory { path }),
+            FilenameOrContents::Mapped(mapped) => Self::Mapped(mapped),
             FilenameOrContents::Contents(contents) => Self::Cursor(CursorFactory::new(contents)),
         }
     }
@@ -199,6 +277,7 @@ impl<'a> ReaderFactory for AsyncReaderFactory<'a> {
             AsyncReaderFactory::File(f) => f.make_reader().map(|SeekSkipAdapter(f)| {
                 futures::future::Either::Left(futures::io::BufReader::new(f))
             }),
+            AsyncReaderFactory::Mapped(m) => m.make_reader().map(futures::future::Either::Right),
             AsyncReaderFactory::Cursor(c) => c.make_reader().map(futures::future::Either::Right),
         }
         .map(SeekSkipAdapter)
@@ -210,35 +289,106 @@ enum MaybeEncryptedBackupReader<R: AsyncRead + Unpin> {
     PlaintextBinproto(BackupReader<UnvalidatedHmacReader<R>>),
 }
 
-struct PrintOutput(bool);
+enum PrintOutput {
+    None,
+    Debug,
+    CanonicalJson(RedactionPolicy),
+    #[cfg(feature = "json")]
+    Chat(ChatRecipient, ChatExportFormat),
+}
+
+impl PrintOutput {
+    #[cfg(feature = "json")]
+    fn chat(recipient: String, format: Option<String>) -> Self {
+        let recipient = recipient
+            .parse()
+            .unwrap_or_else(|e| panic!("invalid --export-chat {recipient:?}: {e}"));
+        let format = format
+            .map(|format| {
+                format
+                    .parse()
+                    .unwrap_or_else(|_| panic!("unknown --export-format {format:?}"))
+            })
+            .unwrap_or(ChatExportFormat::Json);
+        Self::Chat(recipient, format)
+    }
+
+    #[cfg(not(feature = "json"))]
+    fn chat(_recipient: String, _format: Option<String>) -> Self {
+        panic!("--export-chat requires building with the \"json\" feature");
+    }
+}
 
 impl<R: AsyncRead + Unpin> MaybeEncryptedBackupReader<R> {
-    async fn execute(self, print: PrintOutput, verbosity: ParseVerbosity) -> Result<(), Error> {
+    async fn execute(
+        self,
+        print: PrintOutput,
+        verbosity: ParseVerbosity,
+        backup_time_policy: BackupTimePolicy,
+    ) -> Result<(), Error> {
         async fn validate(
-            mut backup_reader: BackupReader<impl AsyncRead + Unpin + VerifyHmac>,
-            PrintOutput(print): PrintOutput,
+            mut backup_reader: BackupReader<impl AsyncRead + Unpin + VerifyHmac + ReadProgress>,
+            print: PrintOutput,
             verbosity: ParseVerbosity,
+            backup_time_policy: BackupTimePolicy,
         ) -> Result<(), Error> {
             if let Some(visitor) = verbosity.into_visitor() {
                 backup_reader.visitor = visitor;
             }
             let ReadResult {
                 found_unknown_fields,
+                found_oversized_frames,
+                found_duplicate_chat_items,
+                found_expire_timer_version_conflicts,
+                backup_time_warning,
+                policy_flags: _,
+                backup_version: _,
                 result,
-            } = backup_reader.read_all().await;
+            } = backup_reader
+                .with_backup_time_policy(backup_time_policy)
+                .read_all()
+                .await;
 
             print_unknown_fields(found_unknown_fields);
+            print_oversized_frames(found_oversized_frames);
+            print_duplicate_chat_items(found_duplicate_chat_items);
+            print_expire_timer_version_conflicts(found_expire_timer_version_conflicts);
+            print_backup_time_warning(backup_time_warning);
+            print_unsupported_version_hint(&result);
             let backup = result?;
 
-            if print {
-                println!("{backup:#?}");
+            match print {
+                PrintOutput::None => {}
+                PrintOutput::Debug => println!("{backup:#?}"),
+                #[cfg(feature = "json")]
+                PrintOutput::CanonicalJson(redact) => println!(
+                    "{}",
+                    libsignal_message_backup::backup::serialize::Backup::from(backup)
+                        .to_redacted_canonical_string(redact)
+                ),
+                #[cfg(feature = "json")]
+                PrintOutput::Chat(recipient, format) => {
+                    libsignal_message_backup::backup::export::export_chat(
+                        &backup,
+                        &recipient,
+                        format,
+                        std::io::stdout().lock(),
+                    )
+                    .unwrap_or_else(|e| panic!("failed to export chat: {e}"))
+                }
+                #[cfg(not(feature = "json"))]
+                PrintOutput::CanonicalJson(_) => unreachable!("checked when parsing arguments"),
             }
             Ok(())
         }
 
         match self {
-            Self::EncryptedCompressed(reader) => validate(*reader, print, verbosity).await,
-            Self::PlaintextBinproto(reader) => validate(reader, print, verbosity).await,
+            Self::EncryptedCompressed(reader) => {
+                validate(*reader, print, verbosity, backup_time_policy).await
+            }
+            Self::PlaintextBinproto(reader) => {
+                validate(reader, print, verbosity, backup_time_policy).await
+            }
         }
     }
 }
@@ -254,6 +404,56 @@ fn print_unknown_fields(found_unknown_fields: Vec<FoundUnknownField>) {
     }
 }
 
+fn print_oversized_frames(found_oversized_frames: Vec<FoundOversizedFrame>) {
+    if found_oversized_frames.is_empty() {
+        return;
+    }
+
+    eprintln!("some chat items are larger than recommended:");
+    for frame in found_oversized_frames {
+        eprintln!("{frame}");
+    }
+}
+
+fn print_duplicate_chat_items(found_duplicate_chat_items: Vec<FoundDuplicateChatItem>) {
+    if found_duplicate_chat_items.is_empty() {
+        return;
+    }
+
+    eprintln!("some chat items may have been exported twice:");
+    for item in found_duplicate_chat_items {
+        eprintln!("{item}");
+    }
+}
+
+fn print_expire_timer_version_conflicts(
+    found_expire_timer_version_conflicts: Vec<FoundExpireTimerVersionConflict>,
+) {
+    if found_expire_timer_version_conflicts.is_empty() {
+        return;
+    }
+
+    eprintln!("some chats' expiration timer versions went backwards:");
+    for conflict in found_expire_timer_version_conflicts {
+        eprintln!("{conflict}");
+    }
+}
+
+fn print_backup_time_warning(backup_time_warning: Option<BackupTimeError>) {
+    if let Some(warning) = backup_time_warning {
+        eprintln!("backup time is out of range: {warning}");
+    }
+}
+
+fn print_unsupported_version_hint<T>(result: &Result<T, Error>) {
+    if let Err(Error::UnsupportedVersion { .. }) = result {
+        eprintln!(
+            "this backup uses a newer format than this validator understands; \
+            update the validator to check it"
+        );
+    }
+}
+
 #[cfg(test)]
 mod test {
     use assert_matches::assert_matches;
@@ -284,13 +484,63 @@ mod test {
                 },
             verbose: 0,
             print: false,
+            json: false,
+            redact: None,
+            export_chat: None,
+            export_format: None,
             purpose: Purpose::RemoteBackup,
+            max_backup_age_days: None,
+            no_mmap: false,
             derive_key: DeriveKey { master_key: None, aci: None},
             key_parts: KeyParts { hmac_key: None, aes_key: None },
         }) =>  file_source);
         assert_eq!(file_source, "filename");
     }
 
+    #[test]
+    fn cli_parse_json_requires_print() {
+        let e = assert_matches!(Cli::try_parse_from([EXECUTABLE_NAME, "filename", "--json"]), Err(e) => e);
+        assert_eq!(e.kind(), clap::error::ErrorKind::MissingRequiredArgument);
+
+        let cli =
+            Cli::try_parse_from([EXECUTABLE_NAME, "filename", "--print", "--json"]).expect("valid");
+        assert!(cli.print && cli.json);
+    }
+
+    #[test]
+    fn cli_parse_redact() {
+        let e = assert_matches!(Cli::try_parse_from([EXECUTABLE_NAME, "filename", "--redact", "partial"]), Err(e) => e);
+        assert_eq!(e.kind(), clap::error::ErrorKind::MissingRequiredArgument);
+
+        let e = assert_matches!(Cli::try_parse_from([EXECUTABLE_NAME, "filename", "--print", "--redact", "partial", "-v"]), Err(e) => e);
+        assert_eq!(e.kind(), clap::error::ErrorKind::ArgumentConflict);
+
+        let cli = Cli::try_parse_from([EXECUTABLE_NAME, "filename", "--print", "--redact", "full"])
+            .expect("valid");
+        assert_eq!(cli.redact, Some(RedactionPolicy::Full));
+    }
+
+    #[test]
+    fn cli_parse_export_chat() {
+        let e = assert_matches!(Cli::try_parse_from([EXECUTABLE_NAME, "filename", "--export-format", "html"]), Err(e) => e);
+        assert_eq!(e.kind(), clap::error::ErrorKind::MissingRequiredArgument);
+
+        let e = assert_matches!(Cli::try_parse_from([EXECUTABLE_NAME, "filename", "--print", "--export-chat", "3"]), Err(e) => e);
+        assert_eq!(e.kind(), clap::error::ErrorKind::ArgumentConflict);
+
+        let cli = Cli::try_parse_from([
+            EXECUTABLE_NAME,
+            "filename",
+            "--export-chat",
+            "3",
+            "--export-format",
+            "html",
+        ])
+        .expect("valid");
+        assert_eq!(cli.export_chat.as_deref(), Some("3"));
+        assert_eq!(cli.export_format.as_deref(), Some("html"));
+    }
+
     #[test]
     fn cli_parse_derive_keys() {
         const INPUT: &[&str] = &[
@@ -310,7 +560,13 @@ mod test {
                 },
             verbose: 0,
             print: false,
+            json: false,
+            redact: None,
+            export_chat: None,
+            export_format: None,
             purpose: Purpose::RemoteBackup,
+            max_backup_age_days: None,
+            no_mmap: false,
             derive_key,
             key_parts: KeyParts { hmac_key: None, aes_key: None },
         }) => (file_source, derive_key));
@@ -343,7 +599,13 @@ mod test {
                 },
             verbose: 0,
             print: false,
+            json: false,
+            redact: None,
+            export_chat: None,
+            export_format: None,
             purpose: Purpose::RemoteBackup,
+            max_backup_age_days: None,
+            no_mmap: false,
             derive_key: DeriveKey { master_key: None, aci: None},
             key_parts,
         }) => (file_source, key_parts));
@@ -426,4 +688,20 @@ mod test {
         let cli = Cli::try_parse_from(input).expect("parse failed");
         assert_eq!(cli.purpose, expected_purpose);
     }
+
+    #[test]
+    fn cli_parse_max_backup_age_days() {
+        let input = [EXECUTABLE_NAME, "filename", "--max-backup-age-days", "30"];
+        let cli = Cli::try_parse_from(input).expect("parse failed");
+        assert_eq!(cli.max_backup_age_days, Some(30));
+
+        let input = [
+            EXECUTABLE_NAME,
+            "filename",
+            "--max-backup-age-days",
+            "forever",
+        ];
+        let e = assert_matches!(Cli::try_parse_from(input), Err(e) => e);
+        assert_eq!(e.kind(), clap::error::ErrorKind::ValueValidation);
+    }
 }
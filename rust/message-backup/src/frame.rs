@@ -4,11 +4,12 @@
 //
 
 use std::borrow::BorrowMut;
+use std::cell::Cell;
+use std::rc::Rc;
 
 use aes::cipher::Unsigned;
-use async_compression::futures::bufread::GzipDecoder;
 use async_trait::async_trait;
-use futures::io::{BufReader, Take};
+use futures::io::Take;
 use futures::{AsyncRead, AsyncReadExt};
 use hmac::digest::OutputSizeUser;
 use hmac::{Hmac, Mac as _};
@@ -17,24 +18,34 @@ use sha2::Sha256;
 use subtle::ConstantTimeEq as _;
 
 use crate::frame::aes_read::{Aes256CbcReader, AES_IV_SIZE};
+use crate::frame::compress::Decompressor;
+use crate::frame::count_read::CountingReader;
 use crate::frame::mac_read::MacReader;
 use crate::key::MessageBackupKey;
 
 mod aes_read;
 mod block_stream;
 mod cbc;
+mod compress;
+mod count_read;
 mod mac_read;
 mod reader_factory;
 mod unpad;
 
-pub use reader_factory::{CursorFactory, FileReaderFactory, LimitedReaderFactory, ReaderFactory};
+pub use compress::CompressionConfig;
+pub use reader_factory::{CursorFactory, LimitedReaderFactory, ReaderFactory};
+#[cfg(not(target_arch = "wasm32"))]
+pub use reader_factory::{FileReaderFactory, MmapReaderFactory};
 
 const HMAC_LEN: usize = <<Hmac<Sha256> as OutputSizeUser>::OutputSize as Unsigned>::USIZE;
 
 #[derive(Debug)]
 pub struct FramesReader<R: AsyncRead + Unpin> {
-    reader: GzipDecoder<BufReader<Aes256CbcReader<HmacSha256Reader<Take<R>>>>>,
+    reader: Decompressor<Aes256CbcReader<HmacSha256Reader<Take<CountingReader<R>>>>>,
     expected_hmac: [u8; HMAC_LEN],
+    /// Ciphertext bytes read so far, shared with the [`CountingReader`] at the bottom of `reader`.
+    bytes_read: Rc<Cell<u64>>,
+    total_bytes: u64,
 }
 
 /// Reader that computes a SHA256 HMAC of the yielded bytes.
@@ -60,7 +71,10 @@ pub struct HmacMismatchError {
 /// Reader that doesn't check the HMAC of the yielded contents.
 ///
 /// Implements [`VerifyHmac`] by always returning success from `verify_hmac`.
-pub struct UnvalidatedHmacReader<R>(R);
+pub struct UnvalidatedHmacReader<R> {
+    reader: R,
+    bytes_read: u64,
+}
 
 #[derive(Debug, thiserror::Error, displaydoc::Display)]
 pub enum VerifyHmacError {
@@ -73,7 +87,19 @@ pub enum VerifyHmacError {
 #[async_trait(?Send)]
 pub trait VerifyHmac: Sized {
     /// Checks that the input that was received has a valid HMAC.
-    async fn verify_hmac(self) -> Result<(), VerifyHmacError>;
+    ///
+    /// Any input that hasn't been read yet is consumed to do so. Returns the total number of input
+    /// bytes read, as [`ReadProgress::bytes_read`] would count them.
+    async fn verify_hmac(self) -> Result<u64, VerifyHmacError>;
+}
+
+/// Reader that knows how much of its input has been consumed.
+pub trait ReadProgress {
+    /// The number of input bytes consumed so far.
+    fn bytes_read(&self) -> u64;
+
+    /// The total number of input bytes, if known.
+    fn total_bytes(&self) -> Option<u64>;
 }
 
 impl<R: AsyncRead + AsyncSkip + Unpin> FramesReader<R> {
@@ -81,13 +107,13 @@ impl<R: AsyncRead + AsyncSkip + Unpin> FramesReader<R> {
         key: &MessageBackupKey,
         mut reader_factory: impl ReaderFactory<Reader = R>,
     ) -> Result<FramesReader<R>, ValidationError> {
+        let total_bytes;
         let content_len;
         let expected_hmac;
         {
             let mut reader = reader_factory.make_reader()?;
-            content_len = reader
-                .stream_len()
-                .await?
+            total_bytes = reader.stream_len().await?;
+            content_len = total_bytes
                 .checked_sub(HMAC_LEN as u64)
                 .ok_or(ValidationError::TooShort)?;
             log::debug!("found {content_len} bytes with a {HMAC_LEN}-byte HMAC");
@@ -109,8 +135,10 @@ impl<R: AsyncRead + AsyncSkip + Unpin> FramesReader<R> {
             }
         };
 
+        let bytes_read = Rc::new(Cell::new(0));
         let mut content = MacReader::new_sha256(
-            reader_factory.make_reader()?.take(content_len),
+            CountingReader::new(reader_factory.make_reader()?, bytes_read.clone())
+                .take(content_len),
             &key.hmac_key,
         );
 
@@ -118,15 +146,31 @@ impl<R: AsyncRead + AsyncSkip + Unpin> FramesReader<R> {
         content.read_exact(&mut iv).await?;
 
         let decrypted = Aes256CbcReader::new(&key.aes_key, &iv, content);
-        let decompressed = GzipDecoder::new(BufReader::new(decrypted));
+        let decompressed = Decompressor::new(decrypted);
 
         Ok(Self {
             reader: decompressed,
             expected_hmac,
+            bytes_read,
+            total_bytes,
         })
     }
 }
 
+/// Reports progress in terms of the encrypted input, since the size of the decrypted and
+/// decompressed contents isn't known up front.
+///
+/// The HMAC at the end of the input isn't counted until [`VerifyHmac::verify_hmac`] checks it.
+impl<R: AsyncRead + Unpin> ReadProgress for FramesReader<R> {
+    fn bytes_read(&self) -> u64 {
+        self.bytes_read.get()
+    }
+
+    fn total_bytes(&self) -> Option<u64> {
+        Some(self.total_bytes)
+    }
+}
+
 impl<R: AsyncRead + Unpin> AsyncRead for FramesReader<R> {
     fn poll_read(
         self: std::pin::Pin<&mut Self>,
@@ -149,7 +193,10 @@ impl<R> MacReader<R, Hmac<Sha256>> {
 
 impl<R> UnvalidatedHmacReader<R> {
     pub(crate) fn new(reader: R) -> Self {
-        Self(reader)
+        Self {
+            reader,
+            bytes_read: 0,
+        }
     }
 }
 
@@ -159,14 +206,28 @@ impl<R: AsyncRead + Unpin> AsyncRead for UnvalidatedHmacReader<R> {
         cx: &mut std::task::Context<'_>,
         buf: &mut [u8],
     ) -> std::task::Poll<std::io::Result<usize>> {
-        std::pin::Pin::new(&mut self.get_mut().0).poll_read(cx, buf)
+        let Self { reader, bytes_read } = self.get_mut();
+        let num_read = futures::ready!(std::pin::Pin::new(reader).poll_read(cx, buf))?;
+        *bytes_read += num_read as u64;
+        std::task::Poll::Ready(Ok(num_read))
+    }
+}
+
+/// The total size isn't known, since the input is an arbitrary stream.
+impl<R> ReadProgress for UnvalidatedHmacReader<R> {
+    fn bytes_read(&self) -> u64 {
+        self.bytes_read
+    }
+
+    fn total_bytes(&self) -> Option<u64> {
+        None
     }
 }
 
 #[async_trait(?Send)]
 impl<R> VerifyHmac for UnvalidatedHmacReader<R> {
-    async fn verify_hmac(self) -> Result<(), VerifyHmacError> {
-        Ok(())
+    async fn verify_hmac(self) -> Result<u64, VerifyHmacError> {
+        Ok(self.bytes_read)
     }
 }
 
@@ -186,28 +247,73 @@ impl std::fmt::Display for HmacMismatchError {
 
 #[async_trait(?Send)]
 impl<R: AsyncRead + Unpin> VerifyHmac for FramesReader<R> {
-    async fn verify_hmac(self) -> Result<(), VerifyHmacError> {
+    async fn verify_hmac(self) -> Result<u64, VerifyHmacError> {
         let Self {
             expected_hmac: expected,
             reader,
+            bytes_read,
+            total_bytes: _,
         } = self;
         // It's possible that the outer reader didn't read all the way to the
         // end. This can happen when the GZIPped data has trailing padding after
         // the compressed contents. Make sure all the bytes from the inner
         // stream get read through the MacReader input bytes before doing the
         // comparison.
-        let mut reader: MacReader<_, _> = reader.into_inner().into_inner().into_inner();
+        let mut reader: MacReader<_, _> = reader.into_inner().into_inner();
         futures::io::copy(&mut reader, &mut futures::io::sink()).await?;
 
         let found: [u8; HMAC_LEN] = reader.finalize().into();
         if expected.ct_eq(&found).into() {
-            Ok(())
+            // The HMAC itself was read before any of the contents, in `FramesReader::new`.
+            Ok(bytes_read.get() + HMAC_LEN as u64)
         } else {
             Err(HmacMismatchError { expected, found }.into())
         }
     }
 }
 
+/// Compresses, pads, and encrypts `frames` into the format read by [`FramesReader`].
+///
+/// If `pad_bucketed` is set, the compressed contents are padded with zeros to one of a set of
+/// exponentially-spaced sizes before being encrypted, so that the size of the output reveals less
+/// about the size of the backup.
+pub async fn write_encrypted(
+    key: &MessageBackupKey,
+    iv: &[u8; AES_IV_SIZE],
+    frames: &[u8],
+    compression: CompressionConfig,
+    pad_bucketed: bool,
+) -> Vec<u8> {
+    let mut compressed = compress::compress(compression, frames).await;
+    if pad_bucketed {
+        pad_to_bucket(&mut compressed);
+    }
+
+    let ciphertext = signal_crypto::aes_256_cbc_encrypt(&compressed, &key.aes_key, iv)
+        .expect("key and IV have the right sizes");
+    let mut output = iv.to_vec();
+    output.extend_from_slice(&ciphertext);
+
+    let hmac = Hmac::<Sha256>::new_from_slice(&key.hmac_key)
+        .expect("HMAC-SHA256 should accept any size key")
+        .chain_update(&output)
+        .finalize();
+    output.extend_from_slice(&hmac.into_bytes());
+    output
+}
+
+fn pad_to_bucket(contents: &mut Vec<u8>) {
+    const BASE: f64 = 1.05;
+    const MIN_LEN: u32 = 541;
+    let len = u32::try_from(contents.len()).expect("backup < 4GB");
+    let padded_len = {
+        let exp = f64::log(len.into(), BASE).ceil();
+        u32::max(MIN_LEN, BASE.powf(exp).floor() as u32)
+    };
+
+    contents.resize(padded_len.try_into().expect("u32 fits in usize"), 0);
+}
+
 async fn hmac_sha256(
     hmac_key: &[u8],
     reader: impl AsyncRead + Unpin,
@@ -343,6 +449,12 @@ mod test {
         block_on(AsyncReadExt::read_to_end(&mut reader, &mut buf)).expect("can read");
 
         assert_eq!(buf, FRAME_DATA,);
+
+        // Any padding left after the compressed contents is read while checking the HMAC.
+        assert_eq!(
+            block_on(reader.verify_hmac()).expect("valid HMAC"),
+            encoded_frame.len() as u64
+        );
     }
 
     #[test_case(Pad)]
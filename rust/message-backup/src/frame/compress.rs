@@ -0,0 +1,310 @@
+//
+// Copyright (C) 2024 Signal Messenger, LLC.
+// SPDX-License-Identifier: AGPL-3.0-only
+//
+
+use std::pin::Pin;
+use std::task::Poll;
+
+use arrayvec::ArrayVec;
+use async_compression::futures::bufread::{GzipDecoder, GzipEncoder};
+use async_compression::Level;
+use futures::io::{BufReader, Cursor};
+use futures::{ready, AsyncBufRead, AsyncRead, AsyncReadExt as _};
+
+/// The first bytes of every gzip stream.
+const GZIP_MAGIC: [u8; 2] = [0x1f, 0x8b];
+
+/// The maximum length of a varint-encoded frame length.
+const VARINT_MAX_LENGTH: usize = 10;
+
+/// How the frames of a backup are compressed before being encrypted.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum CompressionConfig {
+    /// Store the frames as-is.
+    ///
+    /// This trades size for speed, e.g. for a transfer over a fast local link.
+    None,
+    /// Compress the frames with gzip at the given level, from 1 (fastest) to 9 (smallest).
+    ///
+    /// Levels outside that range are clamped to it.
+    Gzip { level: u32 },
+}
+
+impl CompressionConfig {
+    pub const DEFAULT_GZIP_LEVEL: u32 = 6;
+}
+
+impl Default for CompressionConfig {
+    fn default() -> Self {
+        Self::Gzip {
+            level: Self::DEFAULT_GZIP_LEVEL,
+        }
+    }
+}
+
+pub(crate) async fn compress(config: CompressionConfig, plaintext: &[u8]) -> Vec<u8> {
+    match config {
+        CompressionConfig::None => plaintext.to_vec(),
+        CompressionConfig::Gzip { level } => {
+            let level = Level::Precise(level.try_into().unwrap_or(i32::MAX));
+            let mut compressed = Vec::new();
+            GzipEncoder::with_quality(Cursor::new(plaintext), level)
+                .read_to_end(&mut compressed)
+                .await
+                .expect("reading from in-memory cursor can't fail");
+            compressed
+        }
+    }
+}
+
+/// [`AsyncRead`]er for the decrypted contents of a backup, compressed or not.
+///
+/// Which one is detected from the first bytes of the contents: gzip streams have a fixed header,
+/// which can't be confused with the length of the first frame.
+#[derive(Debug)]
+pub(crate) enum Decompressor<R> {
+    /// No contents have been read yet.
+    ///
+    /// This is only `None` while switching to one of the other states.
+    Detecting(Option<BufReader<R>>),
+    Gzip(GzipDecoder<BufReader<R>>),
+    Stored(StoredFramesReader<BufReader<R>>),
+}
+
+impl<R: AsyncRead> Decompressor<R> {
+    pub(crate) fn new(reader: R) -> Self {
+        Self::Detecting(Some(BufReader::new(reader)))
+    }
+
+    pub(crate) fn into_inner(self) -> R {
+        match self {
+            Self::Detecting(reader) => reader.expect("not switching").into_inner(),
+            Self::Gzip(reader) => reader.into_inner().into_inner(),
+            Self::Stored(reader) => reader.reader.into_inner(),
+        }
+    }
+}
+
+impl<R: AsyncRead + Unpin> AsyncRead for Decompressor<R> {
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut std::task::Context<'_>,
+        buf: &mut [u8],
+    ) -> Poll<futures::io::Result<usize>> {
+        let this = self.get_mut();
+        if let Self::Detecting(maybe_reader) = this {
+            let reader = maybe_reader.as_mut().expect("not switching");
+            let is_gzip = ready!(Pin::new(reader).poll_fill_buf(cx))?.starts_with(&GZIP_MAGIC);
+            let reader = maybe_reader.take().expect("not switching");
+            log::debug!(
+                "backup contents are {}",
+                if is_gzip { "gzip-compressed" } else { "stored" }
+            );
+            *this = if is_gzip {
+                Self::Gzip(GzipDecoder::new(reader))
+            } else {
+                Self::Stored(StoredFramesReader::new(reader))
+            };
+        }
+
+        match this {
+            Self::Detecting(_) => unreachable!("detected above"),
+            Self::Gzip(reader) => Pin::new(reader).poll_read(cx, buf),
+            Self::Stored(reader) => Pin::new(reader).poll_read(cx, buf),
+        }
+    }
+}
+
+/// [`AsyncRead`]er for varint-delimited frames that weren't compressed.
+///
+/// Yields the frames as-is, stopping at the padding that follows them. Padding is all zeros, which
+/// would otherwise be read as a sequence of empty frames; since a valid frame is never empty, the
+/// first zero length marks the start of the padding. Anything other than zeros after that point is
+/// an error.
+#[derive(Debug)]
+pub(crate) struct StoredFramesReader<R> {
+    reader: R,
+    state: StoredState,
+}
+
+#[derive(Debug)]
+enum StoredState {
+    /// Reading the length of the next frame.
+    Length(ArrayVec<u8, VARINT_MAX_LENGTH>),
+    /// Yielding the length that was read, then `remaining` bytes of the frame.
+    Frame {
+        length: ArrayVec<u8, VARINT_MAX_LENGTH>,
+        remaining: u64,
+    },
+    /// Skipping the padding.
+    Padding,
+}
+
+impl<R> StoredFramesReader<R> {
+    fn new(reader: R) -> Self {
+        Self {
+            reader,
+            state: StoredState::Length(ArrayVec::new()),
+        }
+    }
+}
+
+impl<R: AsyncBufRead + Unpin> AsyncRead for StoredFramesReader<R> {
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut std::task::Context<'_>,
+        buf: &mut [u8],
+    ) -> Poll<futures::io::Result<usize>> {
+        let Self { reader, state } = self.get_mut();
+        if buf.is_empty() {
+            return Poll::Ready(Ok(0));
+        }
+
+        loop {
+            match state {
+                StoredState::Length(length) => {
+                    let available = ready!(Pin::new(&mut *reader).poll_fill_buf(cx))?;
+                    let Some(&byte) = available.first() else {
+                        if length.is_empty() {
+                            return Poll::Ready(Ok(0));
+                        }
+                        // Yield the partial length and let the frame parser report the error.
+                        *state = StoredState::Frame {
+                            length: std::mem::take(length),
+                            remaining: 0,
+                        };
+                        continue;
+                    };
+                    Pin::new(&mut *reader).consume(1);
+
+                    length.try_push(byte).map_err(|_| {
+                        futures::io::Error::from(futures::io::ErrorKind::InvalidData)
+                    })?;
+                    if byte & 0x80 != 0 {
+                        continue;
+                    }
+
+                    if length[..] == [0] {
+                        *state = StoredState::Padding;
+                        continue;
+                    }
+                    let remaining = length
+                        .iter()
+                        .rev()
+                        .fold(0u64, |acc, b| (acc << 7) | u64::from(b & 0x7f));
+                    *state = StoredState::Frame {
+                        length: std::mem::take(length),
+                        remaining,
+                    };
+                }
+                StoredState::Frame { length, remaining } => {
+                    if !length.is_empty() {
+                        let count = length.len().min(buf.len());
+                        buf[..count].copy_from_slice(&length[..count]);
+                        length.drain(..count);
+                        return Poll::Ready(Ok(count));
+                    }
+                    if *remaining == 0 {
+                        *state = StoredState::Length(ArrayVec::new());
+                        continue;
+                    }
+
+                    let max_count = usize::try_from(*remaining)
+                        .unwrap_or(usize::MAX)
+                        .min(buf.len());
+                    let count =
+                        ready!(Pin::new(&mut *reader).poll_read(cx, &mut buf[..max_count]))?;
+                    *remaining -= count as u64;
+                    return Poll::Ready(Ok(count));
+                }
+                StoredState::Padding => {
+                    let available = ready!(Pin::new(&mut *reader).poll_fill_buf(cx))?;
+                    if available.is_empty() {
+                        return Poll::Ready(Ok(0));
+                    }
+                    if available.iter().any(|b| *b != 0) {
+                        return Poll::Ready(Err(futures::io::Error::new(
+                            futures::io::ErrorKind::InvalidData,
+                            "non-zero padding after frames",
+                        )));
+                    }
+                    let count = available.len();
+                    Pin::new(&mut *reader).consume(count);
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use assert_matches::assert_matches;
+    use futures::executor::block_on;
+    use futures::AsyncReadExt;
+    use test_case::test_case;
+
+    use super::*;
+
+    /// Two frames, of lengths 3 and 200.
+    fn frames() -> Vec<u8> {
+        [&[3, b'a', b'b', b'c'][..], &[0xc8, 0x01], &[b'x'; 200]].concat()
+    }
+
+    fn read_stored(contents: &[u8]) -> futures::io::Result<Vec<u8>> {
+        let mut reader = Decompressor::new(Cursor::new(contents));
+        let mut buf = Vec::new();
+        block_on(AsyncReadExt::read_to_end(&mut reader, &mut buf))?;
+        assert_matches!(reader, Decompressor::Stored(_));
+        Ok(buf)
+    }
+
+    #[test_case(0; "unpadded")]
+    #[test_case(1; "one byte of padding")]
+    #[test_case(1000; "lots of padding")]
+    fn stored_frames_stop_at_padding(padding: usize) {
+        let mut contents = frames();
+        contents.resize(contents.len() + padding, 0);
+        assert_eq!(read_stored(&contents).expect("valid"), frames());
+    }
+
+    #[test]
+    fn stored_frames_reject_data_after_padding() {
+        let mut contents = frames();
+        contents.extend_from_slice(&[0, 0, 1]);
+        assert_eq!(
+            read_stored(&contents).expect_err("invalid").kind(),
+            futures::io::ErrorKind::InvalidData
+        );
+    }
+
+    #[test]
+    fn stored_frames_pass_through_truncation() {
+        let contents = frames();
+        let truncated = &contents[..contents.len() - 1];
+        // The frame parser is responsible for noticing the frame is short.
+        assert_eq!(read_stored(truncated).expect("valid"), truncated);
+    }
+
+    #[test_case(CompressionConfig::Gzip { level: 1 }; "fastest")]
+    #[test_case(CompressionConfig::default(); "default")]
+    #[test_case(CompressionConfig::Gzip { level: 9 }; "smallest")]
+    fn gzip_round_trip(config: CompressionConfig) {
+        let compressed = block_on(compress(config, &frames()));
+        assert!(compressed.starts_with(&GZIP_MAGIC));
+
+        let mut reader = Decompressor::new(Cursor::new(compressed));
+        let mut buf = Vec::new();
+        block_on(AsyncReadExt::read_to_end(&mut reader, &mut buf)).expect("valid");
+        assert_eq!(buf, frames());
+        assert_matches!(reader, Decompressor::Gzip(_));
+    }
+
+    #[test]
+    fn no_compression_is_identity() {
+        assert_eq!(
+            block_on(compress(CompressionConfig::None, &frames())),
+            frames()
+        );
+    }
+}
@@ -0,0 +1,67 @@
+//
+// Copyright (C) 2024 Signal Messenger, LLC.
+// SPDX-License-Identifier: AGPL-3.0-only
+//
+
+use std::cell::Cell;
+use std::pin::Pin;
+use std::rc::Rc;
+
+use futures::{ready, AsyncRead};
+
+/// [`AsyncRead`]er that keeps a running count of the bytes produced.
+///
+/// The count is shared so it can still be observed after the reader has been wrapped by others.
+#[derive(Debug)]
+pub(crate) struct CountingReader<R> {
+    reader: R,
+    count: Rc<Cell<u64>>,
+}
+
+impl<R> CountingReader<R> {
+    pub(crate) fn new(reader: R, count: Rc<Cell<u64>>) -> Self {
+        Self { reader, count }
+    }
+}
+
+impl<R: AsyncRead + Unpin> AsyncRead for CountingReader<R> {
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut std::task::Context<'_>,
+        buf: &mut [u8],
+    ) -> std::task::Poll<futures::io::Result<usize>> {
+        let Self { reader, count } = self.get_mut();
+        let num_read = ready!(Pin::new(reader).poll_read(cx, buf))?;
+
+        count.set(count.get() + num_read as u64);
+
+        std::task::Poll::Ready(Ok(num_read))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use futures::io::Cursor;
+    use futures::FutureExt as _;
+
+    use super::*;
+
+    #[test]
+    fn counts_bytes_read() {
+        let count = Rc::new(Cell::new(0));
+        let mut reader = CountingReader::new(Cursor::new([0; 100]), count.clone());
+
+        let mut buf = [0; 30];
+        futures::AsyncReadExt::read_exact(&mut reader, &mut buf)
+            .now_or_never()
+            .expect("future finished")
+            .expect("success");
+        assert_eq!(count.get(), 30);
+
+        futures::io::copy(&mut reader, &mut futures::io::sink())
+            .now_or_never()
+            .expect("future finished")
+            .expect("success");
+        assert_eq!(count.get(), 100);
+    }
+}
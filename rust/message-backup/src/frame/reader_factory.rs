@@ -3,12 +3,23 @@
 // SPDX-License-Identifier: AGPL-3.0-only
 //
 
+//! Sources of the readers for [`FramesReader`](super::FramesReader).
+//!
+//! There's no file system on wasm32, so the readers for files aren't available there.
+
+#[cfg(not(target_arch = "wasm32"))]
 use std::fs::File;
+#[cfg(not(target_arch = "wasm32"))]
+use std::path::Path;
 
 use arrayvec::ArrayVec;
-use futures::io::{AllowStdIo, Cursor};
+#[cfg(not(target_arch = "wasm32"))]
+use futures::io::AllowStdIo;
+use futures::io::Cursor;
 use futures::AsyncRead;
-use mediasan_common::{AsyncSkip, SeekSkipAdapter};
+use mediasan_common::AsyncSkip;
+#[cfg(not(target_arch = "wasm32"))]
+use mediasan_common::SeekSkipAdapter;
 
 /// Provider of an [`AsyncRead`] and [`AsyncSkip`] reader.
 pub trait ReaderFactory {
@@ -20,6 +31,7 @@ pub trait ReaderFactory {
 }
 
 /// Implementation of [`ReaderFactory`] that opens the same file path.
+#[cfg(not(target_arch = "wasm32"))]
 #[derive(Debug)]
 pub struct FileReaderFactory<P> {
     pub path: P,
@@ -35,6 +47,63 @@ impl<B> CursorFactory<B> {
     }
 }
 
+/// Implementation of [`ReaderFactory`] that reads from a memory-mapped file.
+///
+/// This avoids a read syscall for every buffer's worth of a large file. Readers are made from a
+/// reference to the factory, as cursors into the mapping (see [`CursorFactory`]).
+///
+/// # Platform caveats
+///
+/// A mapping reflects changes made to the file after it was mapped. On Unix platforms, reading
+/// past the end of a file that was truncated after mapping raises `SIGBUS`; on Windows, the file
+/// can't be truncated while it's mapped. The file's length is checked against the mapping when
+/// it's opened and again each time a reader is made, which catches a file that shrank in
+/// between, but not one that shrinks while a reader is in use.
+#[cfg(not(target_arch = "wasm32"))]
+#[derive(Debug)]
+pub struct MmapReaderFactory {
+    file: File,
+    map: memmap2::Mmap,
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl MmapReaderFactory {
+    /// Maps the file at `path` into memory.
+    ///
+    /// # Safety
+    ///
+    /// The file must not be modified or truncated by this or any other process while the
+    /// factory or any of its readers are alive; see the platform caveats above.
+    pub unsafe fn open(path: impl AsRef<Path>) -> std::io::Result<Self> {
+        let file = File::open(path)?;
+        let map = memmap2::Mmap::map(&file)?;
+        let factory = Self { file, map };
+        factory.check_len()?;
+        Ok(factory)
+    }
+
+    /// The number of bytes mapped.
+    pub fn len(&self) -> usize {
+        self.map.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.map.is_empty()
+    }
+
+    fn check_len(&self) -> std::io::Result<()> {
+        let file_len = self.file.metadata()?.len();
+        let mapped_len = u64::try_from(self.map.len()).expect("usize fits in u64");
+        if file_len < mapped_len {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::UnexpectedEof,
+                format!("file shrank to {file_len} bytes after {mapped_len} bytes were mapped"),
+            ));
+        }
+        Ok(())
+    }
+}
+
 /// Implementation of [`ReaderFactory`] with a pre-allocated set of readers.
 pub struct LimitedReaderFactory<R, const N: usize>(ArrayVec<R, N>);
 
@@ -46,6 +115,7 @@ impl<R, const N: usize> LimitedReaderFactory<R, N> {
     }
 }
 
+#[cfg(not(target_arch = "wasm32"))]
 impl<P: AsRef<std::path::Path>> ReaderFactory for FileReaderFactory<P> {
     type Reader = SeekSkipAdapter<AllowStdIo<File>>;
 
@@ -64,6 +134,17 @@ impl<'a, B: AsRef<[u8]> + ?Sized> ReaderFactory for CursorFactory<&'a B> {
     }
 }
 
+#[cfg(not(target_arch = "wasm32"))]
+impl<'a> ReaderFactory for &'a MmapReaderFactory {
+    type Reader = Cursor<&'a [u8]>;
+
+    fn make_reader(&mut self) -> futures::io::Result<Self::Reader> {
+        let factory: &'a MmapReaderFactory = *self;
+        factory.check_len()?;
+        CursorFactory::new(&factory.map[..]).make_reader()
+    }
+}
+
 impl<R: AsyncRead + AsyncSkip, const N: usize> ReaderFactory for LimitedReaderFactory<R, N> {
     type Reader = R;
 
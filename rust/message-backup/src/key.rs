@@ -13,9 +13,10 @@
 use hkdf::Hkdf;
 use libsignal_core::Aci;
 use sha2::Sha256;
+use zeroize::{Zeroize, ZeroizeOnDrop};
 
 /// Primary key for backups that is used to derive other keys.
-#[derive(Debug)]
+#[derive(Debug, Zeroize, ZeroizeOnDrop)]
 #[cfg_attr(test, derive(Eq, PartialEq))]
 pub struct BackupKey([u8; BackupKey::LEN]);
 
@@ -39,6 +40,10 @@ impl BackupKey {
         Self(key)
     }
 
+    pub fn as_bytes(&self) -> &[u8; Self::LEN] {
+        &self.0
+    }
+
     /// Derive the [`BackupId`] from a user's `BackupKey` and [`Aci`].
     pub fn derive_backup_id(&self, aci: &Aci) -> BackupId {
         const INFO: &[u8] = b"20231003_Signal_Backups_GenerateBackupId";
@@ -55,15 +60,19 @@ impl BackupKey {
 /// The per-account key used to store backups.
 ///
 /// This is derived from a user's [`BackupId`] along with their [`Aci`].
-#[derive(Debug)]
+#[derive(Debug, Zeroize, ZeroizeOnDrop)]
 #[cfg_attr(test, derive(Eq, PartialEq))]
 pub struct BackupId([u8; BackupId::LEN]);
 
 impl BackupId {
     pub const LEN: usize = 16;
+
+    pub fn as_bytes(&self) -> &[u8; Self::LEN] {
+        &self.0
+    }
 }
 
-#[derive(Debug)]
+#[derive(Clone, Debug, Zeroize, ZeroizeOnDrop)]
 #[cfg_attr(test, derive(Eq, PartialEq))]
 pub struct MessageBackupKey {
     pub hmac_key: [u8; MessageBackupKey::HMAC_KEY_LEN],
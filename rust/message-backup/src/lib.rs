@@ -7,14 +7,23 @@
 //!
 //! Contains code to read and validate message backup files.
 
+use std::ops::RangeInclusive;
+use std::time::SystemTime;
+
 use futures::AsyncRead;
 use mediasan_common::AsyncSkip;
 use protobuf::Message as _;
 
+use crate::backup::cache::{AddFrame, Uncached, ValidationCache};
 use crate::backup::method::{Store, ValidateOnly};
-use crate::backup::{CompletedBackup, Purpose};
+use crate::backup::policy::{AllowAll, FramePolicy, PolicyDecision};
+use crate::backup::{
+    BackupTimeError, BackupTimePolicy, CompletedBackup, FrameMeta, FrameSizeLimits, Purpose,
+    RevisionStorage, Timestamp,
+};
 use crate::frame::{
-    HmacMismatchError, ReaderFactory, UnvalidatedHmacReader, VerifyHmac, VerifyHmacError,
+    HmacMismatchError, ReadProgress, ReaderFactory, UnvalidatedHmacReader, VerifyHmac,
+    VerifyHmacError,
 };
 use crate::key::MessageBackupKey;
 use crate::parse::VarintDelimitedReader;
@@ -25,20 +34,77 @@ pub mod backup;
 pub mod frame;
 pub mod key;
 pub mod parse;
+#[cfg(feature = "test-util")]
+pub mod testutil;
 pub mod unknown;
+#[cfg(feature = "wasm")]
+pub mod wasm;
 
-pub(crate) mod proto;
+pub mod proto;
 
-pub struct BackupReader<R> {
-    purpose: Purpose,
+pub struct BackupReader<R, P = fn(BackupProgress), F = AllowAll> {
     reader: VarintDelimitedReader<R>,
     pub visitor: fn(&dyn std::fmt::Debug),
+    on_progress: P,
+    policy: F,
+    options: ValidationOptions,
+}
+
+/// Settings from a [`BackupReader`] that are applied to the backup being read.
+#[derive(Copy, Clone, Debug)]
+struct ValidationOptions {
+    purpose: Purpose,
+    frame_size_limits: FrameSizeLimits,
+    backup_time_policy: BackupTimePolicy,
+    /// If `None`, the time reading starts.
+    validation_time: Option<SystemTime>,
+    reject_duplicate_chat_items: bool,
+    revision_storage: RevisionStorage,
+}
+
+impl ValidationOptions {
+    fn new(purpose: Purpose) -> Self {
+        Self {
+            purpose,
+            frame_size_limits: FrameSizeLimits::default(),
+            backup_time_policy: BackupTimePolicy::default(),
+            validation_time: None,
+            reject_duplicate_chat_items: false,
+            revision_storage: RevisionStorage::default(),
+        }
+    }
+}
+
+/// How far a [`BackupReader`] has gotten through its input.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub struct BackupProgress {
+    /// The number of frames read so far, including the leading backup info.
+    pub frames_read: u64,
+    /// The number of input bytes consumed so far.
+    ///
+    /// For encrypted backups this counts encrypted bytes, since the size of the decrypted
+    /// contents isn't known up front.
+    pub bytes_read: u64,
+    /// The total size of the input, if known.
+    pub total_bytes: Option<u64>,
 }
 
+/// The values of [`BackupInfo.version`](proto::backup::BackupInfo::version) this crate can read.
+///
+/// A backup with any other version is rejected as soon as its `BackupInfo` has been read, before
+/// any of its frames are looked at: [`Error::UnsupportedVersion`] for one made with a newer format,
+/// and [`Error::VersionTooOld`] for one made with a format that's no longer supported.
+pub const SUPPORTED_VERSION_RANGE: RangeInclusive<u64> = 1..=1;
+
+/// Progress is reported at least once per this many frames...
+const PROGRESS_FRAME_INTERVAL: u64 = 100;
+/// ...or this many bytes of input, whichever comes first.
+const PROGRESS_BYTE_INTERVAL: u64 = 1 << 20;
+
 #[derive(Debug, thiserror::Error, displaydoc::Display)]
 pub enum Error {
     /// {0}
-    BackupValidation(#[from] backup::ValidationError),
+    BackupValidation(#[from] backup::ValidationFailure),
     /// {0}
     BackupCompletion(#[from] backup::CompletionError),
     /// {0}
@@ -49,12 +115,35 @@ pub enum Error {
     InvalidProtobuf(#[from] protobuf::Error),
     /// mismatched HMAC: {0}
     HmacMismatch(#[from] HmacMismatchError),
+    /// frame {frame_index} rejected by policy: {reason}
+    PolicyViolation { frame_index: usize, reason: String },
+    /// backup version {found} is newer than the latest supported version, {supported_max}
+    UnsupportedVersion { found: u64, supported_max: u64 },
+    /// backup version {found} is older than the earliest supported version, {supported_min}
+    VersionTooOld { found: u64, supported_min: u64 },
 }
 
 #[must_use]
 pub struct ReadResult<B> {
     pub result: Result<B, Error>,
     pub found_unknown_fields: Vec<FoundUnknownField>,
+    pub found_oversized_frames: Vec<FoundOversizedFrame>,
+    /// Chat items that look like copies of earlier items in the same chat.
+    pub found_duplicate_chat_items: Vec<FoundDuplicateChatItem>,
+    /// Chats whose expiration timer updates imply a higher expireTimerVersion than they declare.
+    ///
+    /// Chats without an expireTimerVersion aren't checked. Only reported if every frame was read and validated.
+    pub found_expire_timer_version_conflicts: Vec<FoundExpireTimerVersionConflict>,
+    /// Set if the backup time was outside the [`BackupTimePolicy`] but the
+    /// backup's purpose means that isn't an error.
+    pub backup_time_warning: Option<BackupTimeError>,
+    /// Frames the [`FramePolicy`] flagged without rejecting them.
+    pub policy_flags: Vec<FoundPolicyFlag>,
+    /// The version in the backup's `BackupInfo`, or `None` if it couldn't be read.
+    ///
+    /// This is set even if reading failed later, so the version of a backup that was rejected by
+    /// [`SUPPORTED_VERSION_RANGE`] (or for any other reason) can still be reported.
+    pub backup_version: Option<u64>,
 }
 
 #[derive(Debug, thiserror::Error)]
@@ -81,7 +170,12 @@ impl std::fmt::Display for ReadError {
     }
 }
 
-#[derive(Clone, Debug, Eq, PartialEq)]
+/// An unknown field or enum value found while reading a backup.
+///
+/// Besides the human-readable [`Display`](std::fmt::Display) form, this can be serialized; see
+/// [`FoundUnknownField::to_json_array`] for the schema.
+#[derive(Clone, Debug, Eq, PartialEq, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
 pub struct FoundUnknownField {
     pub frame_index: usize,
     pub path: Vec<PathPart>,
@@ -104,20 +198,241 @@ impl std::fmt::Display for FoundUnknownField {
     }
 }
 
+/// A frame holding a chat item that was larger than
+/// [`FrameSizeLimits::warn_above`].
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct FoundOversizedFrame {
+    pub frame_index: usize,
+    pub serialized_size: usize,
+}
+
+impl std::fmt::Display for FoundOversizedFrame {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let Self {
+            frame_index,
+            serialized_size,
+        } = self;
+        write!(
+            f,
+            "frame {frame_index} holds a chat item and is {serialized_size} bytes"
+        )
+    }
+}
+
+/// A frame holding a chat item with the same author, timestamp, and kind of message as an
+/// earlier item in the same chat.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct FoundDuplicateChatItem {
+    pub frame_index: usize,
+}
+
+impl std::fmt::Display for FoundDuplicateChatItem {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let Self { frame_index } = self;
+        write!(
+            f,
+            "frame {frame_index} holds a chat item that looks like a duplicate"
+        )
+    }
+}
+
+/// A chat with more expiration timer updates than its expireTimerVersion allows for.
+///
+/// Every change to a chat's timer bumps its version, so the version must have gone backwards at
+/// some point. Clients use the version to resolve concurrent timer changes, and may pick the wrong
+/// timer after restoring such a backup.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct FoundExpireTimerVersionConflict {
+    pub chat_id: u64,
+    pub expire_timer_version: u32,
+    pub timer_changes: u32,
+    /// When the most recent of the updates was sent.
+    pub latest_change_sent_at: Timestamp,
+}
+
+impl std::fmt::Display for FoundExpireTimerVersionConflict {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let Self {
+            chat_id,
+            expire_timer_version,
+            timer_changes,
+            latest_change_sent_at,
+        } = self;
+        write!(
+            f,
+            "chat {chat_id} has {timer_changes} expiration timer updates (the latest sent at {}) \
+            but expireTimerVersion {expire_timer_version}",
+            latest_change_sent_at.as_millis()
+        )
+    }
+}
+
+/// A frame that a [`FramePolicy`] flagged with [`PolicyDecision::Flag`].
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct FoundPolicyFlag {
+    pub frame_index: usize,
+    pub reason: String,
+}
+
+impl std::fmt::Display for FoundPolicyFlag {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let Self {
+            frame_index,
+            reason,
+        } = self;
+        write!(f, "frame {frame_index} flagged by policy: {reason}")
+    }
+}
+
+impl FoundUnknownField {
+    /// Serializes `found` as a JSON array, for integrators that want structured data.
+    ///
+    /// Each element has the form
+    ///
+    /// ```json
+    /// {
+    ///   "frameIndex": 3,
+    ///   "path": [
+    ///     { "kind": "field", "fieldName": "chatItem" },
+    ///     { "kind": "repeated", "fieldName": "revisions", "index": 0 },
+    ///     { "kind": "mapValue", "fieldName": "map", "key": "\"key\"" }
+    ///   ],
+    ///   "value": { "kind": "field", "tag": 900 }
+    /// }
+    /// ```
+    ///
+    /// where `value` is instead `{ "kind": "enumValue", "number": 7 }` for an unknown enum value.
+    /// Map keys are formatted as in the [`Display`](std::fmt::Display) output, with strings
+    /// quoted.
+    #[cfg(feature = "json")]
+    pub fn to_json_array(found: &[Self]) -> String {
+        serde_json::to_string(found).expect("can't fail serialization")
+    }
+}
+
 impl<R> ReadResult<R> {
+    /// Serializes [`Self::found_unknown_fields`]; see [`FoundUnknownField::to_json_array`].
+    #[cfg(feature = "json")]
+    pub fn unknown_fields_json(&self) -> String {
+        FoundUnknownField::to_json_array(&self.found_unknown_fields)
+    }
+
     fn and_then<T>(self, f: impl FnOnce(R) -> Result<T, Error>) -> ReadResult<T> {
         let Self {
             result,
             found_unknown_fields,
+            found_oversized_frames,
+            found_duplicate_chat_items,
+            found_expire_timer_version_conflicts,
+            backup_time_warning,
+            policy_flags,
+            backup_version,
         } = self;
         ReadResult {
             found_unknown_fields,
+            found_oversized_frames,
+            found_duplicate_chat_items,
+            found_expire_timer_version_conflicts,
+            backup_time_warning,
+            policy_flags,
+            backup_version,
             result: result.and_then(f),
         }
     }
 }
 
-impl<R: AsyncRead + Unpin + VerifyHmac> BackupReader<R> {
+impl<R, P, F> BackupReader<R, P, F> {
+    /// Reports progress to `on_progress` while reading.
+    ///
+    /// Progress is reported every 100 frames or 1 MiB of input, whichever comes first, and once
+    /// more after the input has been fully read and its HMAC checked. That final report counts all
+    /// the input that was read, including the HMAC at the end of an encrypted backup.
+    pub fn with_progress<P2: Fn(BackupProgress)>(self, on_progress: P2) -> BackupReader<R, P2, F> {
+        let Self {
+            reader,
+            visitor,
+            on_progress: _,
+            policy,
+            options,
+        } = self;
+        BackupReader {
+            reader,
+            visitor,
+            on_progress,
+            policy,
+            options,
+        }
+    }
+
+    /// Applies `policy` to each recipient, chat item, and ad-hoc call after it's been validated.
+    ///
+    /// A [`PolicyDecision::Reject`] stops reading with [`Error::PolicyViolation`], and a
+    /// [`PolicyDecision::Flag`] is reported in [`ReadResult::policy_flags`].
+    pub fn with_policy<F2: FramePolicy>(self, policy: F2) -> BackupReader<R, P, F2> {
+        let Self {
+            reader,
+            visitor,
+            on_progress,
+            policy: _,
+            options,
+        } = self;
+        BackupReader {
+            reader,
+            visitor,
+            on_progress,
+            policy,
+            options,
+        }
+    }
+
+    /// Overrides the default [`FrameSizeLimits`] for frames holding chat items.
+    ///
+    /// Frames above the warning threshold are reported in
+    /// [`ReadResult::found_oversized_frames`].
+    pub fn with_frame_size_limits(mut self, frame_size_limits: FrameSizeLimits) -> Self {
+        self.options.frame_size_limits = frame_size_limits;
+        self
+    }
+
+    /// Overrides the default [`BackupTimePolicy`], which puts no limit on a backup's age.
+    ///
+    /// A backup outside the policy fails validation for [`Purpose::RemoteBackup`], and is
+    /// reported in [`ReadResult::backup_time_warning`] otherwise.
+    pub fn with_backup_time_policy(mut self, backup_time_policy: BackupTimePolicy) -> Self {
+        self.options.backup_time_policy = backup_time_policy;
+        self
+    }
+
+    /// Makes a likely duplicate chat item an error, instead of reporting it in
+    /// [`ReadResult::found_duplicate_chat_items`].
+    ///
+    /// Identical messages can legitimately be sent within the same millisecond, so this can
+    /// reject valid backups.
+    pub fn with_duplicate_chat_items_rejected(mut self) -> Self {
+        self.options.reject_duplicate_chat_items = true;
+        self
+    }
+
+    /// Overrides the default [`RevisionStorage`], which keeps only a summary of each chat item
+    /// revision once it's been validated.
+    pub fn with_revision_storage(mut self, revision_storage: RevisionStorage) -> Self {
+        self.options.revision_storage = revision_storage;
+        self
+    }
+
+    /// Checks the backup time against `validation_time` instead of the time reading starts.
+    pub fn with_validation_time(mut self, validation_time: SystemTime) -> Self {
+        self.options.validation_time = Some(validation_time);
+        self
+    }
+}
+
+impl<
+        R: AsyncRead + Unpin + VerifyHmac + ReadProgress,
+        P: Fn(BackupProgress),
+        F: FramePolicy + Send + 'static,
+    > BackupReader<R, P, F>
+{
     pub async fn read_all(self) -> ReadResult<backup::CompletedBackup<Store>> {
         self.collect_all()
             .await
@@ -131,19 +446,68 @@ impl<R: AsyncRead + Unpin + VerifyHmac> BackupReader<R> {
         })
     }
 
+    /// Like [`Self::validate_all`], but skips converting frames again that were already
+    /// validated the last time `cache` was used.
+    ///
+    /// The outcome is the same as without the cache; see [`ValidationCache`] for how much is
+    /// skipped.
+    pub async fn validate_all_with_cache(self, cache: &mut ValidationCache) -> ReadResult<()> {
+        cache.start_run();
+        self.collect_all_with(cache).await.and_then(|partial| {
+            let _: CompletedBackup<ValidateOnly> = partial.try_into()?;
+            Ok(())
+        })
+    }
+
     pub async fn collect_all<M: backup::method::Method + backup::ReferencedTypes>(
         self,
+    ) -> ReadResult<backup::PartialBackup<M>> {
+        self.collect_all_with(Uncached).await
+    }
+
+    async fn collect_all_with<M: backup::method::Method + backup::ReferencedTypes>(
+        self,
+        add_frame: impl AddFrame<M>,
     ) -> ReadResult<backup::PartialBackup<M>> {
         let Self {
             reader,
             visitor,
-            purpose,
+            on_progress,
+            policy,
+            options,
         } = self;
 
         let mut found_unknown_fields = Vec::new();
-        let result = read_all_frames(purpose, reader, visitor, &mut found_unknown_fields).await;
+        let mut found_oversized_frames = Vec::new();
+        let mut found_duplicate_chat_items = Vec::new();
+        let mut found_expire_timer_version_conflicts = Vec::new();
+        let mut backup_time_warning = None;
+        let mut policy_flags = Vec::new();
+        let mut backup_version = None;
+        let result = read_all_frames(
+            options,
+            reader,
+            visitor,
+            ProgressReporter::new(on_progress),
+            policy,
+            add_frame,
+            &mut found_unknown_fields,
+            &mut found_oversized_frames,
+            &mut found_duplicate_chat_items,
+            &mut found_expire_timer_version_conflicts,
+            &mut backup_time_warning,
+            &mut policy_flags,
+            &mut backup_version,
+        )
+        .await;
         ReadResult {
             found_unknown_fields,
+            found_oversized_frames,
+            found_duplicate_chat_items,
+            found_expire_timer_version_conflicts,
+            backup_time_warning,
+            policy_flags,
+            backup_version,
             result,
         }
     }
@@ -154,8 +518,10 @@ impl<R: AsyncRead + Unpin> BackupReader<UnvalidatedHmacReader<R>> {
         let reader = VarintDelimitedReader::new(UnvalidatedHmacReader::new(reader));
         Self {
             reader,
-            purpose,
             visitor: |_| (),
+            on_progress: |_| (),
+            policy: AllowAll,
+            options: ValidationOptions::new(purpose),
         }
     }
 }
@@ -169,18 +535,60 @@ impl<R: AsyncRead + AsyncSkip + Unpin> BackupReader<frame::FramesReader<R>> {
         let reader = frame::FramesReader::new(key, factory).await?;
         Ok(Self {
             reader: VarintDelimitedReader::new(reader),
-            purpose,
             visitor: |_| (),
+            on_progress: |_| (),
+            policy: AllowAll,
+            options: ValidationOptions::new(purpose),
         })
     }
 }
 
+/// Rate-limits calls to a progress callback.
+struct ProgressReporter<P> {
+    on_progress: P,
+    last_reported: BackupProgress,
+}
+
+impl<P: Fn(BackupProgress)> ProgressReporter<P> {
+    fn new(on_progress: P) -> Self {
+        Self {
+            on_progress,
+            last_reported: BackupProgress::default(),
+        }
+    }
+
+    fn update(&mut self, progress: BackupProgress) {
+        if progress.frames_read - self.last_reported.frames_read >= PROGRESS_FRAME_INTERVAL
+            || progress.bytes_read - self.last_reported.bytes_read >= PROGRESS_BYTE_INTERVAL
+        {
+            self.report(progress);
+        }
+    }
+
+    fn report(&mut self, progress: BackupProgress) {
+        self.last_reported = progress;
+        (self.on_progress)(progress)
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
 async fn read_all_frames<M: backup::method::Method + backup::ReferencedTypes>(
-    purpose: Purpose,
-    mut reader: VarintDelimitedReader<impl AsyncRead + Unpin + VerifyHmac>,
+    options: ValidationOptions,
+    mut reader: VarintDelimitedReader<impl AsyncRead + Unpin + VerifyHmac + ReadProgress>,
     mut visitor: impl FnMut(&dyn std::fmt::Debug),
+    mut progress: ProgressReporter<impl Fn(BackupProgress)>,
+    policy: impl FramePolicy + Send + 'static,
+    mut add_frame: impl AddFrame<M>,
     unknown_fields: &mut impl Extend<FoundUnknownField>,
+    oversized_frames: &mut impl Extend<FoundOversizedFrame>,
+    duplicate_chat_items: &mut impl Extend<FoundDuplicateChatItem>,
+    expire_timer_version_conflicts: &mut impl Extend<FoundExpireTimerVersionConflict>,
+    backup_time_warning: &mut Option<BackupTimeError>,
+    policy_flags: &mut impl Extend<FoundPolicyFlag>,
+    backup_version: &mut Option<u64>,
 ) -> Result<backup::PartialBackup<M>, Error> {
+    let total_bytes = reader.get_ref().total_bytes();
+
     let mut add_found_unknown = |found_unknown: Vec<_>, index| {
         let iter = found_unknown
             .into_iter()
@@ -198,25 +606,115 @@ async fn read_all_frames<M: backup::method::Method + backup::ReferencedTypes>(
     visitor(&backup_info);
     add_found_unknown(backup_info.collect_unknown_fields(), 0);
 
-    let mut backup = backup::PartialBackup::new(backup_info, purpose);
+    *backup_version = Some(backup_info.version);
+    check_version(backup_info.version)?;
+
+    let ValidationOptions {
+        purpose,
+        frame_size_limits,
+        backup_time_policy,
+        validation_time,
+        reject_duplicate_chat_items,
+        revision_storage,
+    } = options;
+    let mut backup = backup::PartialBackup::new(backup_info, purpose)
+        .with_frame_size_limits(frame_size_limits)
+        .with_backup_time_policy(backup_time_policy)
+        .with_duplicate_chat_items_rejected(reject_duplicate_chat_items)
+        .with_revision_storage(revision_storage)
+        .with_policy(policy);
+    if let Some(validation_time) = validation_time {
+        backup = backup.with_validation_time(validation_time);
+    }
+    *backup_time_warning =
+        backup
+            .check_backup_time()
+            .map_err(|error| backup::ValidationFailure {
+                frame_index: 0,
+                chat_id: None,
+                chat_item_sent_at: None,
+                error,
+            })?;
     let mut frame_index = 1;
 
     while let Some(frame) = reader.read_next().await? {
         let frame_proto = proto::backup::Frame::parse_from_bytes(&frame)?;
         visitor(&frame_proto);
         add_found_unknown(frame_proto.collect_unknown_fields(), frame_index);
+
+        let frame_meta = FrameMeta {
+            serialized_size: Some(frame.len()),
+        };
+        let locate_failure = backup::ValidationFailure::locate(frame_index, &frame_proto);
+        add_frame
+            .add_frame(&mut backup, &frame, frame_proto, frame_meta)
+            .map_err(locate_failure)?;
+        if let Some(serialized_size) = backup.take_oversized_chat_item() {
+            oversized_frames.extend([FoundOversizedFrame {
+                frame_index,
+                serialized_size,
+            }]);
+        }
+        if backup.take_duplicate_chat_item() {
+            duplicate_chat_items.extend([FoundDuplicateChatItem { frame_index }]);
+        }
+        match backup.take_policy_decision() {
+            PolicyDecision::Allow => {}
+            PolicyDecision::Flag(reason) => policy_flags.extend([FoundPolicyFlag {
+                frame_index,
+                reason,
+            }]),
+            PolicyDecision::Reject(reason) => {
+                return Err(Error::PolicyViolation {
+                    frame_index,
+                    reason,
+                })
+            }
+        }
         frame_index += 1;
 
-        backup.add_frame(frame_proto)?
+        progress.update(BackupProgress {
+            frames_read: frame_index as u64,
+            bytes_read: reader.get_ref().bytes_read(),
+            total_bytes,
+        });
     }
 
     // Before reporting success, check that the HMAC still matches. This
     // prevents TOC/TOU issues.
-    reader.into_inner().verify_hmac().await?;
+    let bytes_read = reader.into_inner().verify_hmac().await?;
+
+    progress.report(BackupProgress {
+        frames_read: frame_index as u64,
+        bytes_read,
+        total_bytes,
+    });
+
+    // Chats can have items anywhere after them, so this can only be checked once every frame has
+    // been read.
+    expire_timer_version_conflicts.extend(backup.expire_timer_version_conflicts());
 
     Ok(backup)
 }
 
+/// Checks `version` against [`SUPPORTED_VERSION_RANGE`].
+pub(crate) fn check_version(version: u64) -> Result<(), Error> {
+    let (supported_min, supported_max) = SUPPORTED_VERSION_RANGE.into_inner();
+    if version > supported_max {
+        return Err(Error::UnsupportedVersion {
+            found: version,
+            supported_max,
+        });
+    }
+    if version < supported_min {
+        return Err(Error::VersionTooOld {
+            found: version,
+            supported_min,
+        });
+    }
+    Ok(())
+}
+
 impl From<VerifyHmacError> for Error {
     fn from(value: VerifyHmacError) -> Self {
         match value {
@@ -54,6 +54,11 @@ impl<R: AsyncRead + Unpin> VarintDelimitedReader<R> {
         Ok(Some(buf.into_boxed_slice()))
     }
 
+    /// Returns a reference to the inner [`AsyncRead`]er.
+    pub(crate) fn get_ref(&self) -> &R {
+        &self.reader
+    }
+
     /// Consumes self, returning the inner [`AsyncRead`]er.
     pub(crate) fn into_inner(self) -> R {
         self.reader
@@ -78,3 +78,8 @@ impl_from_oneof!(
     LearnedProfileChatUpdate,
     LearnedProfileChange
 );
+impl_from_oneof!(
+    chat_update_message::Update,
+    PaymentActivationChatUpdate,
+    PaymentActivation
+);
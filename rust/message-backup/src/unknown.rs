@@ -32,7 +32,15 @@ impl std::fmt::Display for FormatPath<&[PathPart]> {
 }
 
 /// Protobuf message path component.
-#[derive(Clone, Debug, Eq, PartialEq, displaydoc::Display)]
+///
+/// Serializes as an object with a `kind` of `"repeated"`, `"field"`, or `"mapValue"` alongside
+/// the variant's fields in camelCase.
+#[derive(Clone, Debug, Eq, PartialEq, displaydoc::Display, serde::Serialize)]
+#[serde(
+    tag = "kind",
+    rename_all = "camelCase",
+    rename_all_fields = "camelCase"
+)]
 pub enum PathPart {
     /// {field_name}[{index}]
     Repeated { field_name: String, index: usize },
@@ -42,7 +50,10 @@ pub enum PathPart {
     MapValue { field_name: String, key: String },
 }
 
-#[derive(Copy, Clone, Debug, Eq, PartialEq, displaydoc::Display)]
+/// Serializes as an object with a `kind` of `"enumValue"` or `"field"` alongside the variant's
+/// fields.
+#[derive(Copy, Clone, Debug, Eq, PartialEq, displaydoc::Display, serde::Serialize)]
+#[serde(tag = "kind", rename_all = "camelCase")]
 pub enum UnknownValue {
     /// enum value {number}
     EnumValue { number: i32 },
@@ -427,4 +438,61 @@ mod test {
             HashMap::from(EXPECTED_UNKNOWN)
         );
     }
+
+    #[test]
+    fn found_unknown_fields_json() {
+        let found = [
+            crate::FoundUnknownField {
+                frame_index: 2,
+                path: vec![
+                    PathPart::Field {
+                        field_name: "nested_message".into(),
+                    },
+                    PathPart::Repeated {
+                        field_name: "repeated_message".into(),
+                        index: 1,
+                    },
+                ],
+                value: UnknownValue::Field { tag: 711 },
+            },
+            crate::FoundUnknownField {
+                frame_index: 3,
+                path: vec![
+                    PathPart::MapValue {
+                        field_name: "map".into(),
+                        key: MapKey::String("map_key").to_string(),
+                    },
+                    PathPart::Field {
+                        field_name: "enum".into(),
+                    },
+                ],
+                value: UnknownValue::EnumValue { number: 3 },
+            },
+        ];
+
+        let json: serde_json::Value =
+            serde_json::from_str(&crate::FoundUnknownField::to_json_array(&found))
+                .expect("valid JSON");
+        assert_eq!(
+            json,
+            serde_json::json!([
+                {
+                    "frameIndex": 2,
+                    "path": [
+                        { "kind": "field", "fieldName": "nested_message" },
+                        { "kind": "repeated", "fieldName": "repeated_message", "index": 1 },
+                    ],
+                    "value": { "kind": "field", "tag": 711 },
+                },
+                {
+                    "frameIndex": 3,
+                    "path": [
+                        { "kind": "mapValue", "fieldName": "map", "key": "\"map_key\"" },
+                        { "kind": "field", "fieldName": "enum" },
+                    ],
+                    "value": { "kind": "enumValue", "number": 3 },
+                },
+            ])
+        );
+    }
 }
@@ -0,0 +1,103 @@
+//
+// Copyright 2024 Signal Messenger, LLC.
+// SPDX-License-Identifier: AGPL-3.0-only
+//
+
+//! Validation of plaintext backups from JavaScript, for tools that run in a browser.
+
+use std::str::FromStr as _;
+
+use wasm_bindgen::prelude::*;
+
+use crate::backup::{Purpose, ValidationFailure};
+use crate::{BackupReader, Error, FoundUnknownField, ReadResult};
+
+/// The result of [`validate_bytes`], as seen from JavaScript.
+#[derive(Debug, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+struct ValidationOutcome {
+    ok: bool,
+    found_unknown_fields: Vec<FoundUnknownField>,
+    error: Option<ValidationOutcomeError>,
+}
+
+#[derive(Debug, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+struct ValidationOutcomeError {
+    message: String,
+    /// Where the error was found, if it was in a particular frame.
+    frame_index: Option<usize>,
+    chat_id: Option<u64>,
+    chat_item_sent_at: Option<u64>,
+}
+
+impl From<Error> for ValidationOutcomeError {
+    fn from(error: Error) -> Self {
+        let message = error.to_string();
+        match error {
+            Error::BackupValidation(ValidationFailure {
+                frame_index,
+                chat_id,
+                chat_item_sent_at,
+                error: _,
+            }) => Self {
+                message,
+                frame_index: Some(frame_index),
+                chat_id,
+                chat_item_sent_at: chat_item_sent_at.map(|sent_at| sent_at.as_millis()),
+            },
+            Error::PolicyViolation {
+                frame_index,
+                reason: _,
+            } => Self {
+                message,
+                frame_index: Some(frame_index),
+                chat_id: None,
+                chat_item_sent_at: None,
+            },
+            Error::BackupCompletion(_)
+            | Error::Parse(_)
+            | Error::NoFrames
+            | Error::InvalidProtobuf(_)
+            | Error::HmacMismatch(_)
+            | Error::UnsupportedVersion { .. }
+            | Error::VersionTooOld { .. } => Self {
+                message,
+                frame_index: None,
+                chat_id: None,
+                chat_item_sent_at: None,
+            },
+        }
+    }
+}
+
+/// Validates a backup made of unencrypted, varint-delimited protos.
+///
+/// `purpose` is parsed like the validator's `--purpose` argument. Returns an object with `ok`,
+/// `foundUnknownFields`, and (if not `ok`) `error`, which has a `message` and, when known, the
+/// `frameIndex`, `chatId`, and `chatItemSentAt` of the frame that failed to validate.
+#[wasm_bindgen(js_name = validateBytes)]
+pub fn validate_bytes(bytes: &[u8], purpose: &str) -> Result<JsValue, JsError> {
+    let purpose = Purpose::from_str(purpose)
+        .map_err(|_| JsError::new(&format!("unknown backup purpose {purpose:?}")))?;
+
+    let reader = BackupReader::new_unencrypted(futures::io::Cursor::new(bytes), purpose);
+    // Everything is already in memory, so nothing here ever waits.
+    let ReadResult {
+        result,
+        found_unknown_fields,
+        found_oversized_frames: _,
+        found_duplicate_chat_items: _,
+        found_expire_timer_version_conflicts: _,
+        backup_time_warning: _,
+        policy_flags: _,
+        backup_version: _,
+    } = futures::executor::block_on(reader.validate_all());
+
+    let outcome = ValidationOutcome {
+        ok: result.is_ok(),
+        found_unknown_fields,
+        error: result.err().map(Into::into),
+    };
+    serde_wasm_bindgen::to_value(&outcome).map_err(|e| JsError::new(&e.to_string()))
+}
@@ -0,0 +1,122 @@
+//
+// Copyright 2024 Signal Messenger, LLC.
+// SPDX-License-Identifier: AGPL-3.0-only
+//
+
+//! Checks that streaming JSON conversion doesn't hold on to the JSON it's written.
+//!
+//! This counts every allocation in the process, so it's kept apart from other tests that could
+//! be allocating at the same time.
+
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use assert_matches::assert_matches;
+use futures::io::Cursor;
+use libsignal_message_backup::backup::Purpose;
+
+/// Tracks the most memory in use at once since the last [`CountingAllocator::reset_peak`].
+struct CountingAllocator {
+    current: AtomicUsize,
+    peak: AtomicUsize,
+}
+
+impl CountingAllocator {
+    /// Returns the peak number of bytes allocated on top of what was in use at the start.
+    fn peak_during(&self, f: impl FnOnce()) -> usize {
+        let start = self.current.load(Ordering::SeqCst);
+        self.peak.store(start, Ordering::SeqCst);
+        f();
+        self.peak.load(Ordering::SeqCst).saturating_sub(start)
+    }
+}
+
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        let ptr = System.alloc(layout);
+        if !ptr.is_null() {
+            let current = self.current.fetch_add(layout.size(), Ordering::SeqCst) + layout.size();
+            self.peak.fetch_max(current, Ordering::SeqCst);
+        }
+        ptr
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout);
+        self.current.fetch_sub(layout.size(), Ordering::SeqCst);
+    }
+}
+
+#[global_allocator]
+static ALLOCATOR: CountingAllocator = CountingAllocator {
+    current: AtomicUsize::new(0),
+    peak: AtomicUsize::new(0),
+};
+
+/// Counts the bytes written without keeping them.
+#[derive(Default)]
+struct CountingSink(usize);
+
+impl std::io::Write for CountingSink {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.0 += buf.len();
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+#[test]
+fn streaming_json_memory_is_bounded() {
+    const CHAT_ITEMS: u64 = 2000;
+    const BODY_LEN: usize = 2000;
+
+    let json_contents = json5::from_str(include_str!(
+        "res/test-cases/valid/simple-chat-update-message.jsonproto"
+    ))
+    .expect("invalid JSON");
+    let mut json_array =
+        assert_matches!(json_contents, serde_json::Value::Array(contents) => contents);
+    // Pad the backup out with long messages, which take far more space as JSON than they do in
+    // the state kept for validation.
+    json_array.extend((0..CHAT_ITEMS).map(|i| {
+        serde_json::json!({
+            "chatItem": {
+                "authorId": 1,
+                "chatId": 1,
+                "dateSent": 100 + i,
+                "outgoing": {
+                    "sendStatus": [{
+                        "recipientId": 4,
+                        "timestamp": 100 + i,
+                        "sent": { "sealedSender": true }
+                    }]
+                },
+                "standardMessage": {
+                    "text": { "body": "x".repeat(BODY_LEN) }
+                }
+            }
+        })
+    }));
+    let binproto =
+        libsignal_message_backup::backup::convert_from_json(json_array).expect("failed to convert");
+
+    let mut output = CountingSink::default();
+    let peak = ALLOCATOR.peak_during(|| {
+        futures::executor::block_on(libsignal_message_backup::backup::convert_to_json_streaming(
+            Cursor::new(&*binproto),
+            Purpose::RemoteBackup,
+            &mut output,
+        ))
+        .expect("valid backup")
+    });
+
+    let output_len = output.0;
+    assert!(output_len > CHAT_ITEMS as usize * BODY_LEN);
+    assert!(
+        peak < output_len / 4,
+        "peak allocation of {peak} bytes for {output_len} bytes of output"
+    );
+}
@@ -3,6 +3,7 @@
 // SPDX-License-Identifier: AGPL-3.0-only
 //
 
+use std::cell::RefCell;
 use std::path::PathBuf;
 
 use assert_cmd::Command;
@@ -11,10 +12,22 @@ use dir_test::{dir_test, Fixture};
 use futures::io::Cursor;
 use futures::AsyncRead;
 use libsignal_core::Aci;
-use libsignal_message_backup::backup::Purpose;
-use libsignal_message_backup::frame::{FileReaderFactory, VerifyHmac};
+use libsignal_message_backup::backup::policy::{
+    ChatItemData, ChatItemMessage, FramePolicy, Method, PolicyDecision, ReferencedTypes,
+};
+use libsignal_message_backup::backup::serialize::RedactionPolicy;
+use libsignal_message_backup::backup::{Purpose, RevisionStorage, Timestamp, ValidationError};
+use libsignal_message_backup::frame::{
+    write_encrypted, CompressionConfig, CursorFactory, FileReaderFactory, MmapReaderFactory,
+    ReadProgress, ReaderFactory as _, VerifyHmac,
+};
 use libsignal_message_backup::key::{BackupKey, MessageBackupKey};
-use libsignal_message_backup::{BackupReader, ReadResult};
+use libsignal_message_backup::{
+    BackupProgress, BackupReader, FoundDuplicateChatItem, FoundExpireTimerVersionConflict,
+    FoundPolicyFlag, ReadResult,
+};
+use protobuf::Message as _;
+use test_case::test_case;
 
 const BACKUP_PURPOSE: Purpose = Purpose::RemoteBackup;
 
@@ -53,13 +66,129 @@ fn can_serialize_json_proto(input: Fixture<&str>) {
     let result = futures::executor::block_on(reader.read_all())
         .result
         .expect("valid backup");
-    // This should not crash.
+    // This should not crash, including the check that no floats are serialized.
     println!(
         "{}",
-        libsignal_message_backup::backup::serialize::Backup::from(result).to_string_pretty()
+        libsignal_message_backup::backup::serialize::Backup::from(result).to_canonical_string()
     )
 }
 
+#[dir_test(
+        dir: "$CARGO_MANIFEST_DIR/tests/res/test-cases",
+        glob: "valid/*.jsonproto",
+        postfix: "round_trip"
+    )]
+fn can_write_back_json_proto(input: Fixture<&str>) {
+    let json_contents = input.into_content();
+    let json_contents = json5::from_str(json_contents).expect("invalid JSON");
+    let json_array = assert_matches!(json_contents, serde_json::Value::Array(contents) => contents);
+    let binproto =
+        libsignal_message_backup::backup::convert_from_json(json_array).expect("failed to convert");
+
+    // Revisions are only written back out if they were kept in full.
+    let read = |binproto: &[u8]| {
+        let reader = BackupReader::new_unencrypted(Cursor::new(binproto), BACKUP_PURPOSE)
+            .with_revision_storage(RevisionStorage::Full);
+        let ReadResult {
+            result,
+            found_unknown_fields,
+            found_oversized_frames: _,
+            found_duplicate_chat_items: _,
+            found_expire_timer_version_conflicts: _,
+            backup_time_warning: _,
+            policy_flags: _,
+            backup_version: _,
+        } = futures::executor::block_on(reader.read_all());
+        (result.expect("valid backup"), found_unknown_fields)
+    };
+
+    let (backup, found_unknown_fields) = read(&binproto);
+
+    let mut rewritten = Vec::new();
+    backup
+        .to_backup_info()
+        .write_length_delimited_to_vec(&mut rewritten)
+        .expect("can write");
+    for frame in backup.to_frames() {
+        frame
+            .write_length_delimited_to_vec(&mut rewritten)
+            .expect("can write");
+    }
+
+    let (reread, reread_unknown_fields) = read(&rewritten);
+    assert_eq!(reread_unknown_fields, found_unknown_fields);
+    pretty_assertions::assert_str_eq!(
+        libsignal_message_backup::backup::serialize::Backup::from(reread).to_string_pretty(),
+        libsignal_message_backup::backup::serialize::Backup::from(backup).to_string_pretty()
+    );
+}
+
+#[dir_test(
+        dir: "$CARGO_MANIFEST_DIR/tests/res/test-cases",
+        glob: "valid/*.jsonproto",
+        postfix: "redacted"
+    )]
+fn redacted_serialization_hides_identifiers(input: Fixture<&str>) {
+    let json_contents = input.into_content();
+    let json_contents = json5::from_str(json_contents).expect("invalid JSON");
+    let mut identifiers = Vec::new();
+    collect_identifiers(&json_contents, &mut identifiers);
+    let json_array = assert_matches!(json_contents, serde_json::Value::Array(contents) => contents);
+    let binproto =
+        libsignal_message_backup::backup::convert_from_json(json_array).expect("failed to convert");
+
+    let input = Cursor::new(&binproto);
+    let reader = BackupReader::new_unencrypted(input, BACKUP_PURPOSE);
+    let result = futures::executor::block_on(reader.read_all())
+        .result
+        .expect("valid backup");
+    let backup = libsignal_message_backup::backup::serialize::Backup::from(result);
+
+    let unredacted = backup.to_canonical_string();
+    for identifier in &identifiers {
+        assert!(unredacted.contains(identifier), "{identifier} missing");
+    }
+    for policy in [RedactionPolicy::Partial, RedactionPolicy::Full] {
+        let redacted = backup.to_redacted_canonical_string(policy);
+        for identifier in &identifiers {
+            assert!(
+                !redacted.contains(identifier),
+                "{identifier} present with {policy} redaction"
+            );
+        }
+    }
+}
+
+/// Collects the service IDs (as UUID strings) and phone numbers in a JSON-formatted backup.
+fn collect_identifiers(value: &serde_json::Value, found: &mut Vec<String>) {
+    use base64::prelude::{Engine as _, BASE64_STANDARD};
+    use serde_json::Value;
+
+    match value {
+        Value::Object(fields) => {
+            for (key, value) in fields {
+                match (key.as_str(), value) {
+                    ("aci" | "pni" | "mentionAci", Value::String(encoded)) => {
+                        let bytes = BASE64_STANDARD.decode(encoded).expect("valid base64");
+                        found.push(uuid::Uuid::from_slice(&bytes).expect("UUID").to_string());
+                    }
+                    ("e164" | "previousE164", Value::String(number)) => found.push(number.clone()),
+                    ("e164" | "previousE164", Value::Number(number)) => {
+                        found.push(number.to_string())
+                    }
+                    _ => collect_identifiers(value, found),
+                }
+            }
+        }
+        Value::Array(items) => {
+            for item in items {
+                collect_identifiers(item, found)
+            }
+        }
+        Value::Null | Value::Bool(_) | Value::Number(_) | Value::String(_) => {}
+    }
+}
+
 #[test]
 fn serialized_account_settings_is_valid() {
     let binproto = include_bytes!("res/canonical-backup.binproto");
@@ -71,10 +200,76 @@ fn serialized_account_settings_is_valid() {
         .result
         .expect("valid backup");
     let canonical_repr =
-        libsignal_message_backup::backup::serialize::Backup::from(result).to_string_pretty();
+        libsignal_message_backup::backup::serialize::Backup::from(result).to_canonical_string();
     pretty_assertions::assert_str_eq!(canonical_repr, expected_canonical_str)
 }
 
+/// Checks that [`convert_to_json_streaming`] matches the whole-array formatting of
+/// [`convert_to_json`].
+///
+/// [`convert_to_json_streaming`]: libsignal_message_backup::backup::convert_to_json_streaming
+/// [`convert_to_json`]: libsignal_message_backup::backup::convert_to_json
+fn assert_streaming_json_matches(binproto: &[u8]) {
+    let array = futures::executor::block_on(libsignal_message_backup::backup::convert_to_json(
+        Cursor::new(binproto),
+    ))
+    .expect("can convert");
+    let expected = format!("{:#}", serde_json::Value::Array(array));
+
+    let mut streamed = Vec::new();
+    futures::executor::block_on(libsignal_message_backup::backup::convert_to_json_streaming(
+        Cursor::new(binproto),
+        BACKUP_PURPOSE,
+        &mut streamed,
+    ))
+    .expect("valid backup");
+
+    pretty_assertions::assert_str_eq!(String::from_utf8(streamed).expect("UTF-8"), expected);
+}
+
+#[test]
+fn streaming_json_matches_canonical_backup() {
+    assert_streaming_json_matches(include_bytes!("res/canonical-backup.binproto"));
+}
+
+#[dir_test(
+        dir: "$CARGO_MANIFEST_DIR/tests/res/test-cases",
+        glob: "valid/*.jsonproto",
+        postfix: "streaming_json"
+    )]
+fn streaming_json_matches(input: Fixture<&str>) {
+    let json_contents = input.into_content();
+    let json_contents = json5::from_str(json_contents).expect("invalid JSON");
+    let json_array = assert_matches!(json_contents, serde_json::Value::Array(contents) => contents);
+    let binproto =
+        libsignal_message_backup::backup::convert_from_json(json_array).expect("failed to convert");
+    assert_streaming_json_matches(&binproto);
+}
+
+#[test]
+fn streaming_json_rejects_invalid_backup() {
+    let json_contents = json5::from_str(include_str!(
+        "res/test-cases/invalid/missing-account-data.jsonproto"
+    ))
+    .expect("invalid JSON");
+    let json_array = assert_matches!(json_contents, serde_json::Value::Array(contents) => contents);
+    let binproto =
+        libsignal_message_backup::backup::convert_from_json(json_array).expect("failed to convert");
+
+    let result =
+        futures::executor::block_on(libsignal_message_backup::backup::convert_to_json_streaming(
+            Cursor::new(&*binproto),
+            BACKUP_PURPOSE,
+            std::io::sink(),
+        ));
+    assert_matches!(
+        result,
+        Err(libsignal_message_backup::backup::ConvertJsonError::Invalid(
+            libsignal_message_backup::Error::BackupCompletion(_)
+        ))
+    );
+}
+
 const ENCRYPTED_SOURCE_SUFFIX: &str = ".source.jsonproto";
 #[dir_test(
         dir: "$CARGO_MANIFEST_DIR/tests/res/test-cases",
@@ -167,6 +362,371 @@ fn is_valid_encrypted_proto(input: Fixture<PathBuf>) {
         .expect("command failed");
 }
 
+#[dir_test(
+        dir: "$CARGO_MANIFEST_DIR/tests/res/test-cases",
+        glob: "valid-encrypted/*.binproto.encrypted",
+        loader: PathBuf::from,
+        postfix: "mmap"
+    )]
+fn mmap_reader_matches_file_reader(input: Fixture<PathBuf>) {
+    let path = input.content();
+
+    let backup_key = BackupKey::derive_from_master_key(&MASTER_KEY);
+    let key = MessageBackupKey::derive(&backup_key, &backup_key.derive_backup_id(&ACI));
+
+    let buffered = futures::executor::block_on(BackupReader::new_encrypted_compressed(
+        &key,
+        FileReaderFactory { path },
+        BACKUP_PURPOSE,
+    ))
+    .expect("valid HMAC");
+
+    // SAFETY: test files aren't modified while the tests run.
+    let mapped = unsafe { MmapReaderFactory::open(path) }.expect("can map");
+    assert_eq!(
+        mapped.len(),
+        usize::try_from(std::fs::metadata(path).expect("exists").len()).expect("fits")
+    );
+    let mapped_reader = futures::executor::block_on(BackupReader::new_encrypted_compressed(
+        &key,
+        &mapped,
+        BACKUP_PURPOSE,
+    ))
+    .expect("valid HMAC");
+
+    pretty_assertions::assert_str_eq!(canonical_json(mapped_reader), canonical_json(buffered));
+}
+
+#[test]
+fn mmap_reader_notices_truncated_file() {
+    let file = tempfile::NamedTempFile::new().expect("can create");
+    std::fs::write(file.path(), [0; 100]).expect("can write");
+
+    // SAFETY: the file is only truncated before any reader is made.
+    let mapped = unsafe { MmapReaderFactory::open(file.path()) }.expect("can map");
+    assert_eq!(mapped.len(), 100);
+    (&mapped).make_reader().expect("not truncated yet");
+
+    file.as_file().set_len(10).expect("can truncate");
+    let err = (&mapped).make_reader().expect_err("truncated");
+    assert_eq!(err.kind(), std::io::ErrorKind::UnexpectedEof);
+}
+
+#[dir_test(
+        dir: "$CARGO_MANIFEST_DIR/tests/res/test-cases",
+        glob: "valid-encrypted/*.binproto.encrypted",
+        loader: PathBuf::from,
+        postfix: "progress"
+    )]
+fn encrypted_progress_counts_encrypted_bytes(input: Fixture<PathBuf>) {
+    let path = input.into_content();
+    let source_path = format!("{}{ENCRYPTED_SOURCE_SUFFIX}", path.to_str().unwrap());
+    let source_json = json5::from_str(&std::fs::read_to_string(source_path).expect("can read"))
+        .expect("invalid JSON");
+    let frame_count =
+        assert_matches!(source_json, serde_json::Value::Array(frames) => frames.len());
+    let file_len = std::fs::metadata(&path).expect("can stat").len();
+
+    let backup_key = BackupKey::derive_from_master_key(&MASTER_KEY);
+    let key = MessageBackupKey::derive(&backup_key, &backup_key.derive_backup_id(&ACI));
+
+    let reports = RefCell::new(Vec::new());
+    let reader = futures::executor::block_on(BackupReader::new_encrypted_compressed(
+        &key,
+        FileReaderFactory { path },
+        BACKUP_PURPOSE,
+    ))
+    .expect("valid encrypted backup")
+    .with_progress(|progress| reports.borrow_mut().push(progress));
+    futures::executor::block_on(reader.validate_all())
+        .result
+        .expect("valid backup");
+
+    assert_eq!(
+        reports.into_inner().last(),
+        Some(&BackupProgress {
+            frames_read: frame_count as u64,
+            bytes_read: file_len,
+            total_bytes: Some(file_len),
+        })
+    );
+}
+
+#[test]
+fn progress_is_reported_periodically() {
+    const EXTRA_CHAT_ITEMS: u64 = 250;
+
+    let json_contents = json5::from_str(include_str!(
+        "res/test-cases/valid/simple-chat-update-message.jsonproto"
+    ))
+    .expect("invalid JSON");
+    let mut json_array =
+        assert_matches!(json_contents, serde_json::Value::Array(contents) => contents);
+    // Pad the backup out with enough chat items to cross the frame reporting interval.
+    json_array.extend((0..EXTRA_CHAT_ITEMS).map(|i| {
+        serde_json::json!({
+            "chatItem": {
+                "authorId": 1,
+                "chatId": 1,
+                "dateSent": 100 + i,
+                "directionless": {},
+                "updateMessage": {
+                    "simpleUpdate": {
+                        "type": "MESSAGE_REQUEST_ACCEPTED"
+                    }
+                }
+            }
+        })
+    }));
+    let frame_count = json_array.len() as u64;
+    let binproto =
+        libsignal_message_backup::backup::convert_from_json(json_array).expect("failed to convert");
+
+    let reports = RefCell::new(Vec::new());
+    let reader = BackupReader::new_unencrypted(Cursor::new(&binproto), BACKUP_PURPOSE)
+        .with_progress(|progress| reports.borrow_mut().push(progress));
+    futures::executor::block_on(reader.validate_all())
+        .result
+        .expect("valid backup");
+    let reports = reports.into_inner();
+
+    assert!(reports.len() > 2, "too few reports: {reports:?}");
+    for pair in reports.windows(2) {
+        assert!(
+            pair[0].frames_read <= pair[1].frames_read && pair[0].bytes_read <= pair[1].bytes_read,
+            "progress went backwards: {pair:?}"
+        );
+    }
+    assert_eq!(
+        reports.last(),
+        Some(&BackupProgress {
+            frames_read: frame_count,
+            bytes_read: binproto.len() as u64,
+            total_bytes: None,
+        })
+    );
+}
+
+const CANONICAL_BACKUP: &[u8] = include_bytes!("res/canonical-backup.binproto");
+
+fn canonical_json(
+    reader: BackupReader<impl AsyncRead + Unpin + VerifyHmac + ReadProgress>,
+) -> String {
+    let backup = futures::executor::block_on(reader.read_all())
+        .result
+        .expect("valid backup");
+    libsignal_message_backup::backup::serialize::Backup::from(backup).to_canonical_string()
+}
+
+fn encrypt_canonical_backup(compression: CompressionConfig) -> Vec<u8> {
+    let backup_key = BackupKey::derive_from_master_key(&MASTER_KEY);
+    let key = MessageBackupKey::derive(&backup_key, &backup_key.derive_backup_id(&ACI));
+    futures::executor::block_on(write_encrypted(
+        &key,
+        &IV,
+        CANONICAL_BACKUP,
+        compression,
+        true,
+    ))
+}
+
+#[test_case(CompressionConfig::Gzip { level: 1 }; "gzip level 1")]
+#[test_case(CompressionConfig::Gzip { level: 9 }; "gzip level 9")]
+#[test_case(CompressionConfig::None; "no compression")]
+fn compression_round_trip(compression: CompressionConfig) {
+    let expected = canonical_json(BackupReader::new_unencrypted(
+        Cursor::new(CANONICAL_BACKUP),
+        BACKUP_PURPOSE,
+    ));
+
+    let encrypted = encrypt_canonical_backup(compression);
+    let backup_key = BackupKey::derive_from_master_key(&MASTER_KEY);
+    let key = MessageBackupKey::derive(&backup_key, &backup_key.derive_backup_id(&ACI));
+    let reader = futures::executor::block_on(BackupReader::new_encrypted_compressed(
+        &key,
+        CursorFactory::new(&encrypted),
+        BACKUP_PURPOSE,
+    ))
+    .expect("valid HMAC");
+
+    pretty_assertions::assert_str_eq!(canonical_json(reader), expected);
+}
+
+#[test]
+fn no_compression_is_larger() {
+    let stored = encrypt_canonical_backup(CompressionConfig::None);
+    for level in [1, 9] {
+        let compressed = encrypt_canonical_backup(CompressionConfig::Gzip { level });
+        assert!(
+            stored.len() > compressed.len(),
+            "level {level}: {} <= {}",
+            stored.len(),
+            compressed.len()
+        );
+    }
+}
+
+/// Rejects (or flags) gift badges, and allows everything else.
+struct NoGiftBadges {
+    reject: bool,
+}
+
+impl FramePolicy for NoGiftBadges {
+    fn check_chat_item<M: Method + ReferencedTypes>(
+        &self,
+        item: &ChatItemData<M>,
+    ) -> PolicyDecision {
+        match (&item.message, self.reject) {
+            (ChatItemMessage::GiftBadge(_), true) => PolicyDecision::Reject("gift badge".into()),
+            (ChatItemMessage::GiftBadge(_), false) => PolicyDecision::Flag("gift badge".into()),
+            _ => PolicyDecision::Allow,
+        }
+    }
+}
+
+fn read_with_policy(json: &str, policy: NoGiftBadges) -> ReadResult<()> {
+    let json_contents = json5::from_str(json).expect("invalid JSON");
+    let json_array = assert_matches!(json_contents, serde_json::Value::Array(contents) => contents);
+    let binproto =
+        libsignal_message_backup::backup::convert_from_json(json_array).expect("failed to convert");
+
+    let reader =
+        BackupReader::new_unencrypted(Cursor::new(binproto), BACKUP_PURPOSE).with_policy(policy);
+    futures::executor::block_on(reader.validate_all())
+}
+
+const GIFT_BADGE_FRAME_INDEX: usize = 5;
+
+#[test]
+fn policy_can_reject_frame() {
+    let ReadResult {
+        result,
+        policy_flags,
+        ..
+    } = read_with_policy(
+        include_str!("res/test-cases/valid/gift-badge.jsonproto"),
+        NoGiftBadges { reject: true },
+    );
+    assert_matches!(
+        result,
+        Err(libsignal_message_backup::Error::PolicyViolation { frame_index, reason })
+            if frame_index == GIFT_BADGE_FRAME_INDEX && reason == "gift badge"
+    );
+    assert_eq!(policy_flags, Vec::new());
+}
+
+#[test]
+fn policy_can_flag_frame() {
+    let ReadResult {
+        result,
+        policy_flags,
+        ..
+    } = read_with_policy(
+        include_str!("res/test-cases/valid/gift-badge.jsonproto"),
+        NoGiftBadges { reject: false },
+    );
+    result.expect("valid backup");
+    assert_eq!(
+        policy_flags,
+        vec![FoundPolicyFlag {
+            frame_index: GIFT_BADGE_FRAME_INDEX,
+            reason: "gift badge".into(),
+        }]
+    );
+}
+
+#[test]
+fn policy_allows_other_frames() {
+    let ReadResult {
+        result,
+        policy_flags,
+        ..
+    } = read_with_policy(
+        include_str!("res/test-cases/valid/simple-chat-update-message.jsonproto"),
+        NoGiftBadges { reject: true },
+    );
+    result.expect("valid backup");
+    assert_eq!(policy_flags, Vec::new());
+}
+
+/// Reads the gift badge test case with its chat item exported a second time.
+fn read_with_duplicated_chat_item(reject_duplicates: bool) -> ReadResult<()> {
+    let json_contents = json5::from_str(include_str!("res/test-cases/valid/gift-badge.jsonproto"))
+        .expect("invalid JSON");
+    let mut json_array =
+        assert_matches!(json_contents, serde_json::Value::Array(contents) => contents);
+    json_array.push(json_array[GIFT_BADGE_FRAME_INDEX].clone());
+    let binproto =
+        libsignal_message_backup::backup::convert_from_json(json_array).expect("failed to convert");
+
+    let mut reader = BackupReader::new_unencrypted(Cursor::new(binproto), BACKUP_PURPOSE);
+    if reject_duplicates {
+        reader = reader.with_duplicate_chat_items_rejected();
+    }
+    futures::executor::block_on(reader.validate_all())
+}
+
+#[test]
+fn duplicate_chat_item_is_reported() {
+    let ReadResult {
+        result,
+        found_duplicate_chat_items,
+        ..
+    } = read_with_duplicated_chat_item(false);
+    result.expect("valid backup");
+    assert_eq!(
+        found_duplicate_chat_items,
+        vec![FoundDuplicateChatItem {
+            frame_index: GIFT_BADGE_FRAME_INDEX + 1,
+        }]
+    );
+}
+
+#[test]
+fn duplicate_chat_item_can_be_rejected() {
+    let ReadResult {
+        result,
+        found_duplicate_chat_items,
+        ..
+    } = read_with_duplicated_chat_item(true);
+    let err = result.expect_err("duplicate").to_string();
+    assert!(err.contains("same author, timestamp"), "error was {err}");
+    assert_eq!(found_duplicate_chat_items, Vec::new());
+}
+
+const EXPIRATION_TIMER_CHAT_FRAME_INDEX: usize = 6;
+
+#[test]
+fn expire_timer_version_conflict_is_reported() {
+    let json_contents = json5::from_str(include_str!(
+        "res/test-cases/valid/expiration-timer-chat-update-message.jsonproto"
+    ))
+    .expect("invalid JSON");
+    let mut json_array =
+        assert_matches!(json_contents, serde_json::Value::Array(contents) => contents);
+    // The chat has two expiration timer updates, so it should be at version 2 or later.
+    json_array[EXPIRATION_TIMER_CHAT_FRAME_INDEX]["chat"]["expireTimerVersion"] = 1.into();
+    let binproto =
+        libsignal_message_backup::backup::convert_from_json(json_array).expect("failed to convert");
+
+    let reader = BackupReader::new_unencrypted(Cursor::new(binproto), BACKUP_PURPOSE);
+    let ReadResult {
+        result,
+        found_expire_timer_version_conflicts,
+        ..
+    } = futures::executor::block_on(reader.validate_all());
+    result.expect("valid backup");
+    assert_eq!(
+        found_expire_timer_version_conflicts,
+        vec![FoundExpireTimerVersionConflict {
+            chat_id: 1,
+            expire_timer_version: 1,
+            timer_changes: 2,
+            latest_change_sent_at: Timestamp::from_millis(2, "test"),
+        }]
+    );
+}
+
 const EXPECTED_SUFFIX: &str = "jsonproto.expected";
 #[dir_test(
     dir: "$CARGO_MANIFEST_DIR/tests/res/test-cases",
@@ -189,6 +749,12 @@ fn invalid_jsonproto(input: Fixture<PathBuf>) {
     let ReadResult {
         result,
         found_unknown_fields: _,
+        found_oversized_frames: _,
+        found_duplicate_chat_items: _,
+        found_expire_timer_version_conflicts: _,
+        backup_time_warning: _,
+        policy_flags: _,
+        backup_version: _,
     } = futures::executor::block_on(reader.read_all());
 
     let text = result.expect_err("unexpectedly valid").to_string();
@@ -205,6 +771,102 @@ fn invalid_jsonproto(input: Fixture<PathBuf>) {
     assert_eq!(text, expected_text);
 }
 
+#[test]
+fn validation_failure_reports_position() {
+    const INVALID_JSON: &str =
+        include_str!("res/test-cases/invalid/self-recipient-after-chat-item.jsonproto");
+
+    let json_contents = json5::from_str(INVALID_JSON).expect("invalid JSON");
+    let json_array = assert_matches!(json_contents, serde_json::Value::Array(contents) => contents);
+    let binproto =
+        libsignal_message_backup::backup::convert_from_json(json_array).expect("failed to convert");
+
+    let reader = BackupReader::new_unencrypted(Cursor::new(&*binproto), Purpose::RemoteBackup);
+    let error = futures::executor::block_on(reader.read_all())
+        .result
+        .expect_err("unexpectedly valid");
+    let failure = assert_matches!(error, libsignal_message_backup::Error::BackupValidation(failure) => failure);
+
+    assert_eq!(failure.frame_index, 3);
+    assert_eq!(failure.chat_id, Some(1));
+    assert_eq!(
+        failure.chat_item_sent_at,
+        Some(Timestamp::from_millis(1, "test"))
+    );
+    assert_matches!(
+        failure.error,
+        ValidationError::ChatItemBeforeSelfRecipient(_)
+    );
+}
+
+fn read_version_fixture(json: &str) -> (Box<[u8]>, ReadResult<()>) {
+    let json_contents = json5::from_str(json).expect("invalid JSON");
+    let json_array = assert_matches!(json_contents, serde_json::Value::Array(contents) => contents);
+    let binproto =
+        libsignal_message_backup::backup::convert_from_json(json_array).expect("failed to convert");
+
+    let reader = BackupReader::new_unencrypted(Cursor::new(&*binproto), Purpose::RemoteBackup);
+    let result = futures::executor::block_on(reader.validate_all());
+    (binproto, result)
+}
+
+#[test]
+fn newer_version_is_rejected_with_version() {
+    let (
+        binproto,
+        ReadResult {
+            result,
+            backup_version,
+            ..
+        },
+    ) = read_version_fixture(include_str!(
+        "res/test-cases/invalid/backup-version-too-new.jsonproto"
+    ));
+    assert_matches!(
+        result,
+        Err(libsignal_message_backup::Error::UnsupportedVersion {
+            found: 2,
+            supported_max: 1
+        })
+    );
+    assert_eq!(backup_version, Some(2));
+
+    // The CLI tool should suggest updating.
+    let output = validator_command()
+        .arg("-")
+        .write_stdin(binproto)
+        .output()
+        .expect("can run");
+    assert!(!output.status.success());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(
+        stderr.contains("update the validator"),
+        "stderr was {stderr}"
+    );
+}
+
+#[test]
+fn older_version_is_rejected_with_version() {
+    let (
+        _,
+        ReadResult {
+            result,
+            backup_version,
+            ..
+        },
+    ) = read_version_fixture(include_str!(
+        "res/test-cases/invalid/backup-version-too-old.jsonproto"
+    ));
+    assert_matches!(
+        result,
+        Err(libsignal_message_backup::Error::VersionTooOld {
+            found: 0,
+            supported_min: 1
+        })
+    );
+    assert_eq!(backup_version, Some(0));
+}
+
 fn write_expected_output() -> bool {
     std::env::var_os("OVERWRITE_EXPECTED_OUTPUT").is_some()
 }
@@ -224,14 +886,24 @@ fn validate_proto(binproto: &[u8]) {
         .expect("command failed");
 }
 
-fn validate(mut reader: BackupReader<impl AsyncRead + Unpin + VerifyHmac>) {
+fn validate(mut reader: BackupReader<impl AsyncRead + Unpin + VerifyHmac + ReadProgress>) {
     reader.visitor = |msg| println!("{msg:#?}");
 
     let ReadResult {
         result,
         found_unknown_fields,
+        found_oversized_frames,
+        found_duplicate_chat_items: _,
+        found_expire_timer_version_conflicts,
+        backup_time_warning,
+        policy_flags,
+        backup_version: _,
     } = futures::executor::block_on(reader.read_all());
     assert_eq!(found_unknown_fields, Vec::new());
+    assert_eq!(found_oversized_frames, Vec::new());
+    assert_eq!(found_expire_timer_version_conflicts, Vec::new());
+    assert!(backup_time_warning.is_none(), "{backup_time_warning:?}");
+    assert_eq!(policy_flags, Vec::new());
 
     let backup = result.expect("invalid backup");
     println!("got backup:\n{backup:#?}");
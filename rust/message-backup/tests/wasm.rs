@@ -0,0 +1,61 @@
+//
+// Copyright 2024 Signal Messenger, LLC.
+// SPDX-License-Identifier: AGPL-3.0-only
+//
+
+//! Run with wasm-bindgen-test-runner as the runner for wasm32-unknown-unknown, e.g.
+//!
+//! ```sh
+//! CARGO_TARGET_WASM32_UNKNOWN_UNKNOWN_RUNNER=wasm-bindgen-test-runner \
+//!     cargo test -p libsignal-message-backup --target wasm32-unknown-unknown \
+//!     --no-default-features --features wasm --test wasm
+//! ```
+
+#![cfg(all(target_arch = "wasm32", feature = "wasm"))]
+
+use libsignal_message_backup::wasm::validate_bytes;
+use serde_json::json;
+use wasm_bindgen_test::wasm_bindgen_test;
+
+fn validate(bytes: &[u8]) -> serde_json::Value {
+    let outcome =
+        validate_bytes(bytes, "remote_backup").unwrap_or_else(|_| panic!("valid purpose"));
+    serde_wasm_bindgen::from_value(outcome).expect("plain object")
+}
+
+#[wasm_bindgen_test]
+fn valid_backup() {
+    let outcome = validate(include_bytes!("res/canonical-backup.binproto"));
+    assert_eq!(outcome["ok"], json!(true), "{outcome:#}");
+    assert_eq!(outcome["foundUnknownFields"], json!([]));
+}
+
+#[wasm_bindgen_test]
+fn invalid_backup_reports_position() {
+    let json_contents = json5::from_str(include_str!(
+        "res/test-cases/invalid/missing-recipient.jsonproto"
+    ))
+    .expect("valid JSON");
+    let serde_json::Value::Array(json_array) = json_contents else {
+        panic!("not an array");
+    };
+    let binproto =
+        libsignal_message_backup::backup::convert_from_json(json_array).expect("can convert");
+
+    let outcome = validate(&binproto);
+    assert_eq!(outcome["ok"], json!(false));
+    let error = &outcome["error"];
+    assert_eq!(
+        error["message"],
+        json!(include_str!(
+            "res/test-cases/invalid/missing-recipient.jsonproto.expected"
+        ))
+    );
+    assert_eq!(error["frameIndex"], json!(2));
+    assert_eq!(error["chatId"], json!(1));
+}
+
+#[wasm_bindgen_test]
+fn unknown_purpose_is_rejected() {
+    assert!(validate_bytes(&[], "carrier_pigeon").is_err());
+}
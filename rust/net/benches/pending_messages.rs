@@ -0,0 +1,99 @@
+//
+// Copyright 2024 Signal Messenger, LLC.
+// SPDX-License-Identifier: AGPL-3.0-only
+//
+
+use std::collections::HashMap;
+
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion};
+use libsignal_net::chat::ws::{NoMoreRequests, PendingMessagesMap, RequestId};
+use libsignal_net::chat::ResponseProto;
+use tokio::sync::oneshot;
+
+/// The operations being compared, so the same workloads can run against both implementations.
+trait Pending: Default + Sync {
+    type Id;
+    fn insert(&self, responder: oneshot::Sender<ResponseProto>) -> Self::Id;
+    fn remove(&self, id: &Self::Id) -> Option<oneshot::Sender<ResponseProto>>;
+}
+
+impl Pending for PendingMessagesMap {
+    type Id = RequestId;
+
+    fn insert(&self, responder: oneshot::Sender<ResponseProto>) -> RequestId {
+        PendingMessagesMap::insert(self, responder)
+            .unwrap_or_else(|NoMoreRequests| panic!("not cancelled"))
+    }
+
+    fn remove(&self, id: &RequestId) -> Option<oneshot::Sender<ResponseProto>> {
+        PendingMessagesMap::remove(self, id)
+    }
+}
+
+/// The map as it was before being sharded: a `HashMap` behind a single async lock.
+#[derive(Default)]
+struct HashMapBaseline(tokio::sync::Mutex<(HashMap<u64, oneshot::Sender<ResponseProto>>, u64)>);
+
+impl Pending for HashMapBaseline {
+    type Id = u64;
+
+    fn insert(&self, responder: oneshot::Sender<ResponseProto>) -> u64 {
+        let (pending, next_id) = &mut *self.0.blocking_lock();
+        let id = *next_id;
+        pending.insert(id, responder);
+        *next_id += 1;
+        id
+    }
+
+    fn remove(&self, id: &u64) -> Option<oneshot::Sender<ResponseProto>> {
+        self.0.blocking_lock().0.remove(id)
+    }
+}
+
+/// Keeps `in_flight` requests pending at a time, completing `rounds` batches of them.
+fn run<P: Pending>(map: &P, rounds: usize, in_flight: usize, ids: &mut Vec<P::Id>) {
+    for _ in 0..rounds {
+        ids.extend((0..in_flight).map(|_| map.insert(oneshot::channel().0)));
+        for id in ids.drain(..) {
+            map.remove(&id).expect("pending");
+        }
+    }
+}
+
+fn bench_impl<P: Pending>(c: &mut Criterion, name: &str) {
+    const ROUNDS: usize = 100;
+    const IN_FLIGHT: usize = 64;
+
+    let mut group = c.benchmark_group("pending_messages");
+
+    let map = P::default();
+    let mut ids = Vec::with_capacity(IN_FLIGHT);
+    group.bench_function(BenchmarkId::new(name, "single_thread"), |b| {
+        b.iter(|| run(&map, ROUNDS, IN_FLIGHT, &mut ids))
+    });
+
+    for threads in [4, 16] {
+        let map = P::default();
+        group.bench_function(BenchmarkId::new(name, format!("{threads}_threads")), |b| {
+            b.iter(|| {
+                std::thread::scope(|scope| {
+                    for _ in 0..threads {
+                        scope.spawn(|| {
+                            run(&map, ROUNDS, IN_FLIGHT, &mut Vec::with_capacity(IN_FLIGHT))
+                        });
+                    }
+                })
+            })
+        });
+    }
+
+    group.finish();
+}
+
+pub fn pending_messages(c: &mut Criterion) {
+    bench_impl::<HashMapBaseline>(c, "hash_map");
+    bench_impl::<PendingMessagesMap>(c, "sharded_slab");
+}
+
+criterion_group!(benches, pending_messages);
+criterion_main!(benches);
@@ -0,0 +1,299 @@
+//
+// Copyright 2024 Signal Messenger, LLC.
+// SPDX-License-Identifier: AGPL-3.0-only
+//
+
+//! Checks that each of the services libsignal-net talks to can be reached, using only public APIs.
+//!
+//! Runs three checks against the chosen environment:
+//!
+//! - `chat`: connects to the chat server without authentication and sends a keepalive request.
+//! - `cdsi`: connects to CDSI and makes a lookup for no numbers at all, which is just enough to
+//!   complete attestation. Needs `LIBSIGNAL_TESTING_CDSI_ENCLAVE_SECRET`.
+//! - `svr3`: queries every SVR3 enclave for a random user. Needs
+//!   `LIBSIGNAL_TESTING_ENCLAVE_SECRET`.
+//!
+//! The secrets are base64-encoded, as for the integration tests. A check whose secret isn't set
+//! is skipped. Each check prints one line of the form
+//!
+//! ```text
+//! component=chat result=PASS latency_ms=123 detail="200 OK"
+//! ```
+//!
+//! and the program exits with a failure status if any check failed.
+
+use std::fmt::Display;
+use std::process::ExitCode;
+use std::time::Duration;
+
+use async_trait::async_trait;
+use base64::prelude::{Engine as _, BASE64_STANDARD};
+use clap::{Parser, ValueEnum};
+use http::Method;
+use libsignal_net::auth::Auth;
+use libsignal_net::cdsi::{CdsiConnection, LookupRequest};
+use libsignal_net::chat::test_support::simple_chat_service;
+use libsignal_net::chat::Request;
+use libsignal_net::enclave::{EnclaveEndpointConnection, PpssSetup};
+use libsignal_net::env::{Env, Svr3Env};
+use libsignal_net::infra::dns::DnsResolver;
+use libsignal_net::infra::tcp_ssl::DirectConnector;
+use libsignal_net::infra::utils::ObservableEvent;
+use libsignal_net::infra::TransportConnector;
+use libsignal_net::svr3::traits::{Query as _, Svr3Connect};
+use rand_core::{OsRng, RngCore};
+use tokio::time::Instant;
+
+const CDSI_SECRET_VAR: &str = "LIBSIGNAL_TESTING_CDSI_ENCLAVE_SECRET";
+const SVR3_SECRET_VAR: &str = "LIBSIGNAL_TESTING_ENCLAVE_SECRET";
+const TIMEOUT: Duration = Duration::from_secs(10);
+
+#[derive(Parser, Debug)]
+struct Config {
+    #[arg(long, value_enum, default_value_t = Environment::Staging)]
+    env: Environment,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+enum Environment {
+    Staging,
+    #[value(alias("production"))]
+    Prod,
+}
+
+impl Environment {
+    fn env(self) -> Env<'static, Svr3Env<'static>> {
+        match self {
+            Environment::Staging => libsignal_net::env::STAGING,
+            Environment::Prod => libsignal_net::env::PROD,
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Eq)]
+enum Outcome {
+    Pass(String),
+    Fail(String),
+    Skip(String),
+}
+
+/// The result of checking one component, printed as a single line.
+#[derive(Debug)]
+struct Report {
+    component: &'static str,
+    outcome: Outcome,
+    latency: Duration,
+}
+
+impl Display for Report {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let (result, detail) = match &self.outcome {
+            Outcome::Pass(detail) => ("PASS", detail),
+            Outcome::Fail(detail) => ("FAIL", detail),
+            Outcome::Skip(detail) => ("SKIP", detail),
+        };
+        write!(
+            f,
+            "component={} result={result} latency_ms={} detail={detail:?}",
+            self.component,
+            self.latency.as_millis(),
+        )
+    }
+}
+
+impl Report {
+    async fn run(
+        component: &'static str,
+        check: impl std::future::Future<Output = Outcome>,
+    ) -> Self {
+        let start = Instant::now();
+        let outcome = check.await;
+        Self {
+            component,
+            outcome,
+            latency: start.elapsed(),
+        }
+    }
+}
+
+#[tokio::main]
+async fn main() -> ExitCode {
+    env_logger::builder()
+        .filter_module(module_path!(), log::LevelFilter::Info)
+        .parse_default_env()
+        .init();
+
+    let config = Config::parse();
+    let env = config.env.env();
+
+    let reports = [
+        Report::run("chat", check_chat(&env)).await,
+        Report::run("cdsi", check_cdsi(&env)).await,
+        Report::run("svr3", check_svr3(config.env.env().svr3)).await,
+    ];
+
+    let mut any_failures = false;
+    for report in &reports {
+        println!("{report}");
+        any_failures |= matches!(report.outcome, Outcome::Fail(_));
+    }
+
+    if any_failures {
+        ExitCode::FAILURE
+    } else {
+        ExitCode::SUCCESS
+    }
+}
+
+async fn check_chat(env: &Env<'static, Svr3Env<'static>>) -> Outcome {
+    let connection_params = env
+        .chat_domain_config
+        .connect
+        .connection_params_with_fallback();
+    let chat = simple_chat_service(env, Auth::default(), connection_params);
+
+    if let Err(e) = chat.connect_unauthenticated().await {
+        return Outcome::Fail(format!("connect: {e}"));
+    }
+    let request = Request {
+        method: Method::GET,
+        path: http::uri::PathAndQuery::from_static("/v1/keepalive"),
+        headers: Default::default(),
+        body: None,
+    };
+    let result = chat.send_unauthenticated(request, TIMEOUT).await;
+    chat.disconnect().await;
+
+    match result {
+        Ok(response) if response.status.is_success() => Outcome::Pass(response.status.to_string()),
+        Ok(response) => Outcome::Fail(format!("keepalive: {}", response.status)),
+        Err(e) => Outcome::Fail(format!("keepalive: {e}")),
+    }
+}
+
+async fn check_cdsi(env: &Env<'static, Svr3Env<'static>>) -> Outcome {
+    let auth = match auth_from_env(CDSI_SECRET_VAR) {
+        Ok(auth) => auth,
+        Err(outcome) => return outcome,
+    };
+
+    let network_change_event = ObservableEvent::default();
+    let endpoint_connection =
+        EnclaveEndpointConnection::new(&env.cdsi, TIMEOUT, &network_change_event);
+    let transport = DirectConnector::new(DnsResolver::new(&network_change_event));
+
+    let connection = match CdsiConnection::connect(&endpoint_connection, transport, auth).await {
+        Ok(connection) => connection,
+        Err(e) => return Outcome::Fail(format!("connect: {e}")),
+    };
+    let lookup = async {
+        let (_token, remaining_response) =
+            connection.send_request(LookupRequest::default()).await?;
+        remaining_response.collect().await
+    };
+    match tokio::time::timeout(TIMEOUT, lookup).await {
+        Ok(Ok(response)) => Outcome::Pass(format!("{} records", response.records.len())),
+        Ok(Err(e)) => Outcome::Fail(format!("lookup: {e}")),
+        Err(_) => Outcome::Fail("lookup: timed out".to_owned()),
+    }
+}
+
+struct Svr3Client {
+    env: Svr3Env<'static>,
+    auth: Auth,
+}
+
+type Stream = <DirectConnector as TransportConnector>::Stream;
+
+#[async_trait]
+impl Svr3Connect for Svr3Client {
+    type Stream = Stream;
+    type Env = Svr3Env<'static>;
+
+    async fn connect(&self) -> <Svr3Env as PpssSetup<Stream>>::ConnectionResults {
+        self.env.connect_directly(&self.auth).await
+    }
+}
+
+async fn check_svr3(env: Svr3Env<'static>) -> Outcome {
+    let auth = match auth_from_env(SVR3_SECRET_VAR) {
+        Ok(auth) => auth,
+        Err(outcome) => return outcome,
+    };
+
+    let client = Svr3Client { env, auth };
+    match client.query().await {
+        Ok(result) => Outcome::Pass(format!("{result:?}")),
+        Err(e) => Outcome::Fail(format!("query: {e}")),
+    }
+}
+
+/// Makes credentials for a random user from the enclave secret in `var`.
+///
+/// If there is no usable secret, returns the outcome to report instead.
+fn auth_from_env(var: &str) -> Result<Auth, Outcome> {
+    let Ok(b64) = std::env::var(var) else {
+        return Err(Outcome::Skip(format!("{var} is not set")));
+    };
+    let secret = parse_secret(&b64).map_err(Outcome::Fail)?;
+
+    let mut uid = [0u8; 16];
+    OsRng.fill_bytes(&mut uid);
+    Ok(Auth::from_uid_and_secret(uid, secret))
+}
+
+fn parse_secret(b64: &str) -> Result<[u8; 32], String> {
+    let bytes = BASE64_STANDARD
+        .decode(b64)
+        .map_err(|e| format!("secret is not valid base64: {e}"))?;
+    bytes
+        .try_into()
+        .map_err(|bytes: Vec<u8>| format!("secret is {} bytes, not 32", bytes.len()))
+}
+
+#[cfg(test)]
+mod test {
+    use test_case::test_case;
+
+    use super::*;
+
+    #[test_case(&[] => Environment::Staging; "default")]
+    #[test_case(&["--env", "staging"] => Environment::Staging; "staging")]
+    #[test_case(&["--env", "prod"] => Environment::Prod; "prod")]
+    #[test_case(&["--env", "production"] => Environment::Prod; "production")]
+    fn parses_env(args: &[&str]) -> Environment {
+        Config::try_parse_from(std::iter::once("net_smoketest").chain(args.iter().copied()))
+            .expect("valid")
+            .env
+    }
+
+    #[test_case(&["--env", "dev"]; "unknown env")]
+    #[test_case(&["--env"]; "missing env")]
+    #[test_case(&["staging"]; "positional")]
+    fn rejects_bad_args(args: &[&str]) {
+        Config::try_parse_from(std::iter::once("net_smoketest").chain(args.iter().copied()))
+            .expect_err("invalid");
+    }
+
+    #[test_case(Outcome::Pass("200 OK".into()) => r#"component=chat result=PASS latency_ms=1500 detail="200 OK""#; "pass")]
+    #[test_case(Outcome::Fail("connect: \"quoted\"".into()) => r#"component=chat result=FAIL latency_ms=1500 detail="connect: \"quoted\"""#; "fail")]
+    #[test_case(Outcome::Skip("".into()) => r#"component=chat result=SKIP latency_ms=1500 detail="""#; "skip")]
+    fn formats_report(outcome: Outcome) -> String {
+        Report {
+            component: "chat",
+            outcome,
+            latency: Duration::from_micros(1_500_900),
+        }
+        .to_string()
+    }
+
+    #[test]
+    fn parses_secret() {
+        assert_eq!(parse_secret(&BASE64_STANDARD.encode([7; 32])), Ok([7; 32]));
+        assert_eq!(
+            parse_secret(&BASE64_STANDARD.encode([7; 16])),
+            Err("secret is 16 bytes, not 32".to_owned())
+        );
+        assert!(parse_secret("not base64!").is_err());
+    }
+}
@@ -10,6 +10,7 @@
 //! actual stored secret data needs to be exactly 32 bytes long, it is generated randomly
 //! at each invocation instead of being passed via the command line.
 
+use std::borrow::Cow;
 use std::time::Duration;
 
 use async_trait::async_trait;
@@ -29,6 +30,7 @@ use libsignal_net::infra::certs::RootCertificates;
 use libsignal_net::infra::dns::DnsResolver;
 use libsignal_net::infra::tcp_ssl::DirectConnector as TcpSslTransportConnector;
 use libsignal_net::infra::utils::ObservableEvent;
+use libsignal_net::infra::ws::WebSocketMessageLimits;
 use libsignal_net::infra::TransportConnector;
 use libsignal_net::svr::SvrConnection;
 use libsignal_net::svr3::traits::*;
@@ -48,20 +50,21 @@ const TEST_SERVER_DOMAIN_CONFIG: DomainConfig = DomainConfig {
     ip_v4: &[],
     ip_v6: &[],
     connect: ConnectionConfig {
-        hostname: "backend1.svr3.test.signal.org",
+        hostname: Cow::Borrowed("backend1.svr3.test.signal.org"),
         port: nonzero!(443_u16),
         cert: TEST_SERVER_CERT,
         confirmation_header_name: None,
-        proxy: ConnectionProxyConfig {
+        websocket_limits: WebSocketMessageLimits::DEFAULT,
+        proxy: Some(ConnectionProxyConfig {
             path_prefix: "/svr3-test",
             configs: [PROXY_CONFIG_F_STAGING, PROXY_CONFIG_G],
-        },
+        }),
     },
 };
 const TEST_SERVER_ENDPOINT_PARAMS: EndpointParams<'static, Sgx> = EndpointParams {
-    mr_enclave: MrEnclave::new(&hex!(
+    mr_enclave: MrEnclave::new(Cow::Borrowed(&hex!(
         "acb1973aa0bbbd14b3b4e06f145497d948fd4a98efc500fcce363b3b743ec482"
-    )),
+    ))),
     raft_config: TEST_SERVER_RAFT_CONFIG,
 };
 
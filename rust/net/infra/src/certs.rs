@@ -4,6 +4,7 @@
 //
 
 use std::borrow::Cow;
+use std::sync::RwLock;
 
 use boring_signal::error::ErrorStack;
 use boring_signal::ssl::{SslAlert, SslConnectorBuilder, SslVerifyError, SslVerifyMode};
@@ -27,6 +28,34 @@ impl From<ErrorStack> for Error {
     }
 }
 
+/// invalid DER-encoded certificate
+#[derive(thiserror::Error, Debug, displaydoc::Display)]
+pub struct CertParseError;
+
+/// Roots trusted in addition to every pinned set of [`RootCertificates`].
+static SUPPLEMENTAL_ROOT_DERS: RwLock<Vec<Box<[u8]>>> = RwLock::new(Vec::new());
+
+/// Trusts `der` as a root for all TLS connections made after this call that use pinned
+/// certificates (that is, anything but [`RootCertificates::Native`]).
+///
+/// This lets apps keep working when the server certificates are rotated to a root that wasn't
+/// known when they were built. Adding a root that's already been added has no effect.
+pub fn add_supplemental_root_der(der: &[u8]) -> Result<(), CertParseError> {
+    X509::from_der(der).map_err(|_| CertParseError)?;
+
+    let mut roots = SUPPLEMENTAL_ROOT_DERS.write().expect("not poisoned");
+    if !roots.iter().any(|existing| **existing == *der) {
+        log::info!("adding supplemental root certificate");
+        roots.push(der.into());
+    }
+    Ok(())
+}
+
+/// The roots added with [`add_supplemental_root_der`], in the order they were added.
+pub fn supplemental_root_ders() -> Vec<Box<[u8]>> {
+    SUPPLEMENTAL_ROOT_DERS.read().expect("not poisoned").clone()
+}
+
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub enum RootCertificates {
     Native,
@@ -69,6 +98,9 @@ impl RootCertificates {
         for der in ders {
             store_builder.add_cert(X509::from_der(der)?)?;
         }
+        for der in SUPPLEMENTAL_ROOT_DERS.read().expect("not poisoned").iter() {
+            store_builder.add_cert(X509::from_der(der)?)?;
+        }
         connector.set_verify_cert_store(store_builder.build())?;
         Ok(())
     }
@@ -191,6 +223,34 @@ mod test {
         localhost_http_server, make_http_request_response_over, SERVER_CERTIFICATE, SERVER_HOSTNAME,
     };
 
+    #[test]
+    fn supplemental_root_must_parse() {
+        assert_matches!(
+            add_supplemental_root_der(b"not a certificate"),
+            Err(CertParseError)
+        );
+        assert!(!supplemental_root_ders()
+            .iter()
+            .any(|der| &**der == b"not a certificate"));
+    }
+
+    #[test]
+    fn supplemental_root_added_once() {
+        let cert = rcgen::generate_simple_self_signed(["supplemental.test".to_string()])
+            .expect("can generate");
+        let der = cert.cert.der();
+
+        add_supplemental_root_der(der).expect("valid");
+        add_supplemental_root_der(der).expect("valid");
+        assert_eq!(
+            supplemental_root_ders()
+                .iter()
+                .filter(|existing| ***existing == **der)
+                .count(),
+            1
+        );
+    }
+
     #[tokio::test]
     async fn verify_certificate_via_rustls() {
         let (addr, server) = localhost_http_server();
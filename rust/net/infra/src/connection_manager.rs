@@ -6,20 +6,22 @@
 use std::cmp::{max, min};
 use std::fmt::Debug;
 use std::future::Future;
+use std::num::NonZeroU16;
 use std::ops::Add;
 use std::panic::RefUnwindSafe;
-use std::sync::Arc;
-use std::time::Duration;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
 
 use async_trait::async_trait;
 use itertools::Itertools;
-use tokio::sync::Mutex;
 use tokio::time::{timeout_at, Instant};
 
 use crate::errors::LogSafeDisplay;
+use crate::host::Host;
+use crate::negative_cache::NegativeCache;
 use crate::timeouts::{CONNECTION_ROUTE_COOLDOWN_INTERVALS, CONNECTION_ROUTE_MAX_COOLDOWN};
 use crate::utils::{EventSubscription, ObservableEvent};
-use crate::ConnectionParams;
+use crate::{ConnectionParams, TransportConnectionParams};
 
 /// Represents the outcome of the connection attempt
 #[derive(Debug)]
@@ -52,7 +54,35 @@ pub trait ConnectionManager: Clone + Send + Sync {
         Fun: Fn(&'a ConnectionParams) -> Fut + Send + Sync,
         Fut: Future<Output = Result<T, E>> + Send;
 
+    /// Keeps trying to connect until an attempt succeeds, fails fatally, or `deadline` passes.
+    ///
+    /// Each attempt is limited by both its route's own timeout and the time remaining before
+    /// `deadline`, and no new attempts are started once `deadline` has passed. Non-fatal errors
+    /// are retried on the same route until it goes into cooldown.
+    async fn connect_with_deadline<'a, T, E, Fun, Fut>(
+        &'a self,
+        deadline: Instant,
+        connection_fn: Fun,
+    ) -> Result<T, DeadlineConnectError<E>>
+    where
+        T: Send,
+        E: Send + Debug + LogSafeDisplay + ErrorClassifier,
+        Fun: Fn(&'a ConnectionParams) -> Fut + Send + Sync,
+        Fut: Future<Output = Result<T, E>> + Send,
+    {
+        connect_routes_with_deadline(std::slice::from_ref(self), None, deadline, connection_fn)
+            .await
+    }
+
     fn describe_for_logging(&self) -> String;
+
+    /// The host and port connections are made to, if this manager only ever uses one route.
+    ///
+    /// This lets a [`MultiRouteConnectionManager`] consult its [`NegativeCache`] to skip routes
+    /// that are known to fail.
+    fn tcp_destination(&self) -> Option<(Host<&str>, NonZeroU16)> {
+        None
+    }
 }
 
 #[async_trait]
@@ -73,9 +103,27 @@ where
         (*self).connect_or_wait(connection_fn).await
     }
 
+    async fn connect_with_deadline<'a, T, E, Fun, Fut>(
+        &'a self,
+        deadline: Instant,
+        connection_fn: Fun,
+    ) -> Result<T, DeadlineConnectError<E>>
+    where
+        T: Send,
+        E: Send + Debug + LogSafeDisplay + ErrorClassifier,
+        Fun: Fn(&'a ConnectionParams) -> Fut + Send + Sync,
+        Fut: Future<Output = Result<T, E>> + Send,
+    {
+        (*self).connect_with_deadline(deadline, connection_fn).await
+    }
+
     fn describe_for_logging(&self) -> String {
         (*self).describe_for_logging()
     }
+
+    fn tcp_destination(&self) -> Option<(Host<&str>, NonZeroU16)> {
+        (*self).tcp_destination()
+    }
 }
 
 #[derive(Clone, Debug)]
@@ -169,6 +217,65 @@ impl ThrottlingConnectionManagerState {
         // attempt as a single failure.
         *self = self.clone().after_attempt(false, latest_attempt);
     }
+
+    /// Records the cooldown in terms of wall-clock time, which (unlike [`Instant`]) means
+    /// something to a later run of the app.
+    fn save(&self, route: String, now: Instant, wall_now: SystemTime) -> RouteCooldownState {
+        let cooldown_until = wall_now + self.next_attempt.saturating_duration_since(now);
+        let cooldown_until_unix_millis = cooldown_until
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_nanos()
+            // Round up, so that a restored cooldown never ends early.
+            .div_ceil(1_000_000)
+            .try_into()
+            .unwrap_or(u64::MAX);
+        RouteCooldownState {
+            route,
+            consecutive_failures: self.consecutive_fails,
+            cooldown_until_unix_millis,
+        }
+    }
+
+    /// The inverse of [`Self::save`].
+    ///
+    /// The wall clock may have been changed since the state was saved, so a cooldown is never
+    /// restored as longer than [`CONNECTION_ROUTE_MAX_COOLDOWN`].
+    fn restore(saved: &RouteCooldownState, now: Instant, wall_now: SystemTime) -> Self {
+        let cooldown_until = UNIX_EPOCH + Duration::from_millis(saved.cooldown_until_unix_millis);
+        let remaining = cooldown_until
+            .duration_since(wall_now)
+            .unwrap_or_default()
+            .min(CONNECTION_ROUTE_MAX_COOLDOWN);
+        let max_consecutive_fails = (CONNECTION_ROUTE_COOLDOWN_INTERVALS.len() - 1)
+            .try_into()
+            .unwrap();
+        Self {
+            consecutive_fails: min(saved.consecutive_failures, max_consecutive_fails),
+            next_attempt: now + remaining,
+            ..Self::new(now)
+        }
+    }
+}
+
+/// The cooldown of a single route, in a form that can be saved across restarts of the app.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct RouteCooldownState {
+    /// Identifies the route, so that a saved cooldown is only ever applied to the same one.
+    pub route: String,
+    /// The number of failed attempts in a row, which determines how long the next cooldown is.
+    pub consecutive_failures: u16,
+    /// When the route may be tried again, in milliseconds since the Unix epoch.
+    pub cooldown_until_unix_millis: u64,
+}
+
+/// Route cooldowns exported from a connection manager, to be restored when the app restarts.
+///
+/// Without this, an app that keeps crashing would try a failing route again every time it starts.
+/// Only routes that have been failing are included.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct ConnectionManagerState {
+    pub routes: Vec<RouteCooldownState>,
 }
 
 /// A connection manager that only attempts one route (i.e. one [ConnectionParams]).
@@ -188,14 +295,54 @@ pub struct SingleRouteThrottlingConnectionManager<C = ConnectionParams> {
 /// It iterates over them until it can find one that results in a successful connection attempt.
 /// If none did, it will return [ConnectionAttemptOutcome::WaitUntil] with the minimum possible
 /// cooldown time (based on cooldown times returned by all throttling connection managers).
+///
+/// If given a [`NegativeCache`], routes whose host recently failed to resolve or refused a
+/// connection are skipped as if they were in cooldown, until the failure expires.
 #[derive(Clone)]
 pub struct MultiRouteConnectionManager<M = SingleRouteThrottlingConnectionManager> {
     route_managers: Vec<M>,
+    negative_cache: Option<NegativeCache>,
 }
 
 impl<M> MultiRouteConnectionManager<M> {
     pub fn new(route_managers: Vec<M>) -> Self {
-        Self { route_managers }
+        Self {
+            route_managers,
+            negative_cache: None,
+        }
+    }
+
+    /// Skips routes that `negative_cache` knows to be failing.
+    ///
+    /// This is most useful with the cache of the [`DnsResolver`](crate::dns::DnsResolver) used
+    /// for the connections, which is where failures are recorded.
+    pub fn with_negative_cache(self, negative_cache: NegativeCache) -> Self {
+        Self {
+            negative_cache: Some(negative_cache),
+            ..self
+        }
+    }
+}
+
+impl MultiRouteConnectionManager {
+    /// Collects the cooldowns of all the routes, for [`Self::import_state`].
+    pub fn export_state(&self) -> ConnectionManagerState {
+        ConnectionManagerState {
+            routes: self
+                .route_managers
+                .iter()
+                .flat_map(|manager| manager.export_state().routes)
+                .collect(),
+        }
+    }
+
+    /// Restores the cooldowns of any of the routes found in `state`.
+    ///
+    /// See [`SingleRouteThrottlingConnectionManager::import_state`].
+    pub fn import_state(&self, state: &ConnectionManagerState) {
+        for manager in &self.route_managers {
+            manager.import_state(state);
+        }
     }
 }
 
@@ -224,7 +371,13 @@ where
     {
         let mut wait_until = None;
         for route_manager in self.route_managers.iter() {
-            match retry_connect_until_cooldown(route_manager, &connection_fn).await {
+            match retry_connect_until_cooldown(
+                route_manager,
+                self.negative_cache.as_ref(),
+                &connection_fn,
+            )
+            .await
+            {
                 Ok(t) => return ConnectionAttemptOutcome::Attempted(Ok(t)),
                 Err(RetryError::WaitUntil(i)) => {
                     wait_until = Some(
@@ -240,6 +393,27 @@ where
         )
     }
 
+    /// Tries each route in order, as in [`Self::connect_or_wait`], until `deadline` passes.
+    async fn connect_with_deadline<'a, T, E, Fun, Fut>(
+        &'a self,
+        deadline: Instant,
+        connection_fn: Fun,
+    ) -> Result<T, DeadlineConnectError<E>>
+    where
+        T: Send,
+        E: Send + Debug + LogSafeDisplay + ErrorClassifier,
+        Fun: Fn(&'a ConnectionParams) -> Fut + Send + Sync,
+        Fut: Future<Output = Result<T, E>> + Send,
+    {
+        connect_routes_with_deadline(
+            &self.route_managers,
+            self.negative_cache.as_ref(),
+            deadline,
+            connection_fn,
+        )
+        .await
+    }
+
     fn describe_for_logging(&self) -> String {
         format!(
             "multi-route: [{}]",
@@ -251,6 +425,117 @@ where
     }
 }
 
+/// The connection attempts made by [`ConnectionManager::connect_with_deadline`] before it gave up.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub struct AttemptBreakdown {
+    /// Attempts that failed with a non-fatal error.
+    pub failed: u16,
+    /// Attempts that ran out of time, whether the route's own timeout or the overall deadline.
+    pub timed_out: u16,
+    /// Routes that weren't tried (any more) because they were in cooldown or known to be failing.
+    pub skipped_in_cooldown: u16,
+}
+
+impl std::fmt::Display for AttemptBreakdown {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let Self {
+            failed,
+            timed_out,
+            skipped_in_cooldown,
+        } = self;
+        write!(
+            f,
+            "{failed} failed, {timed_out} timed out, {skipped_in_cooldown} skipped in cooldown"
+        )
+    }
+}
+
+impl LogSafeDisplay for AttemptBreakdown {}
+
+#[derive(Debug, displaydoc::Display)]
+pub enum DeadlineConnectError<E: LogSafeDisplay> {
+    /// {0}
+    Fatal(E),
+    /// Connection timed out ({0})
+    ConnectionTimedOut(AttemptBreakdown),
+}
+
+impl<E: LogSafeDisplay> LogSafeDisplay for DeadlineConnectError<E> {}
+
+async fn connect_routes_with_deadline<'a, M, T, E, Fun, Fut>(
+    route_managers: &'a [M],
+    negative_cache: Option<&NegativeCache>,
+    deadline: Instant,
+    connection_fn: Fun,
+) -> Result<T, DeadlineConnectError<E>>
+where
+    M: ConnectionManager,
+    T: Send,
+    E: Send + Debug + LogSafeDisplay + ErrorClassifier,
+    Fun: Fn(&'a ConnectionParams) -> Fut + Send + Sync,
+    Fut: Future<Output = Result<T, E>> + Send,
+{
+    let mut attempts = AttemptBreakdown::default();
+    for route_manager in route_managers {
+        loop {
+            if Instant::now() >= deadline {
+                return Err(DeadlineConnectError::ConnectionTimedOut(attempts));
+            }
+            if known_to_fail_until(negative_cache, route_manager).is_some() {
+                attempts.skipped_in_cooldown += 1;
+                break;
+            }
+            // If the deadline cuts an attempt short, the route manager doesn't get to record the
+            // outcome. That's deliberate: running out of the caller's time says nothing about
+            // whether the route works.
+            let Ok(outcome) =
+                timeout_at(deadline, route_manager.connect_or_wait(&connection_fn)).await
+            else {
+                log::info!(
+                    "Connection attempt cut short by deadline ({})",
+                    route_manager.describe_for_logging()
+                );
+                attempts.timed_out += 1;
+                return Err(DeadlineConnectError::ConnectionTimedOut(attempts));
+            };
+
+            match outcome {
+                ConnectionAttemptOutcome::Attempted(Ok(t)) => return Ok(t),
+                ConnectionAttemptOutcome::Attempted(Err(e)) => {
+                    log::info!(
+                        "Connection attempt failed with an error: {} ({})",
+                        e,
+                        route_manager.describe_for_logging(),
+                    );
+                    match e.classify() {
+                        ErrorClass::Fatal => return Err(DeadlineConnectError::Fatal(e)),
+                        ErrorClass::Intermittent => attempts.failed += 1,
+                        ErrorClass::RetryAt(when) => {
+                            attempts.failed += 1;
+                            if when >= deadline {
+                                return Err(DeadlineConnectError::ConnectionTimedOut(attempts));
+                            }
+                            tokio::time::sleep_until(when).await;
+                        }
+                    }
+                }
+                ConnectionAttemptOutcome::TimedOut => {
+                    log::info!(
+                        "Connection attempt timed out ({})",
+                        route_manager.describe_for_logging()
+                    );
+                    attempts.timed_out += 1;
+                }
+                ConnectionAttemptOutcome::WaitUntil(_) => {
+                    attempts.skipped_in_cooldown += 1;
+                    break;
+                }
+            }
+        }
+    }
+    Err(DeadlineConnectError::ConnectionTimedOut(attempts))
+}
+
 pub enum RetryError<E> {
     /// Connection can be attempted again at a given Instant
     WaitUntil(Instant),
@@ -279,6 +564,7 @@ pub trait ErrorClassifier {
 
 async fn retry_connect_until_cooldown<'a, T, E, Fun, Fut>(
     route_manager: &'a impl ConnectionManager,
+    negative_cache: Option<&NegativeCache>,
     connection_fn: &Fun,
 ) -> Result<T, RetryError<E>>
 where
@@ -288,6 +574,9 @@ where
     Fut: Future<Output = Result<T, E>> + Send,
 {
     loop {
+        if let Some(failed_until) = known_to_fail_until(negative_cache, route_manager) {
+            return Err(RetryError::WaitUntil(failed_until));
+        }
         let result = route_manager.connect_or_wait(connection_fn).await;
         match result {
             ConnectionAttemptOutcome::Attempted(Ok(r)) => {
@@ -332,6 +621,22 @@ where
     }
 }
 
+/// Checks `negative_cache` for a recent failure of the route used by `route_manager`.
+fn known_to_fail_until(
+    negative_cache: Option<&NegativeCache>,
+    route_manager: &impl ConnectionManager,
+) -> Option<Instant> {
+    let negative_cache = negative_cache?;
+    let (host, port) = route_manager.tcp_destination()?;
+    let failed_until = negative_cache.check_route(host, port)?;
+    log::info!(
+        "Skipping route known to be failing for {:?} ({})",
+        failed_until.saturating_duration_since(Instant::now()),
+        route_manager.describe_for_logging(),
+    );
+    Some(failed_until)
+}
+
 impl<C> SingleRouteThrottlingConnectionManager<C> {
     pub fn new(
         connection_params: C,
@@ -352,17 +657,10 @@ impl<C> SingleRouteThrottlingConnectionManager<C> {
             let Some(state) = state_for_network_changed.upgrade() else {
                 return;
             };
-            let time_of_event = Instant::now();
-            // We'd like to reset the cooldowns synchronously, but tokio won't let us block on an
-            // async-aware mutex if we're currently within an async runtime. Spawn a task to do the
-            // reset ASAP instead.
-            if let Ok(tokio_runtime) = tokio::runtime::Handle::try_current() {
-                tokio_runtime.spawn(async move {
-                    state.lock().await.network_changed(time_of_event);
-                });
-            } else {
-                state.blocking_lock().network_changed(time_of_event);
-            }
+            state
+                .lock()
+                .expect("not poisoned")
+                .network_changed(Instant::now());
         }));
 
         Self {
@@ -383,7 +681,7 @@ impl<C> SingleRouteThrottlingConnectionManager<C> {
         Fun: Fn(&'a C) -> Fut + Send + Sync,
         Fut: Future<Output = Result<T, E>> + Send,
     {
-        let state = self.state.lock().await.clone();
+        let state = self.state.lock().expect("not poisoned").clone();
         let attempt_start_time = Instant::now();
         if attempt_start_time < state.next_attempt {
             return ConnectionAttemptOutcome::WaitUntil(state.next_attempt);
@@ -394,7 +692,7 @@ impl<C> SingleRouteThrottlingConnectionManager<C> {
         )
         .await;
 
-        let mut s = self.state.lock().await;
+        let mut s = self.state.lock().expect("not poisoned");
 
         // Ensure unwind safety by atomically updating the locked state with
         // respect to panics.
@@ -410,6 +708,60 @@ impl<C> SingleRouteThrottlingConnectionManager<C> {
     }
 }
 
+impl SingleRouteThrottlingConnectionManager {
+    /// Like [`Self::new`], but starting with any cooldown for this route recorded in `state`.
+    pub fn new_with_state(
+        connection_params: ConnectionParams,
+        connection_timeout: Duration,
+        network_changed_event: &ObservableEvent,
+        state: &ConnectionManagerState,
+    ) -> Self {
+        let manager = Self::new(connection_params, connection_timeout, network_changed_event);
+        manager.import_state(state);
+        manager
+    }
+
+    /// Exports the route's cooldown, if it has been failing.
+    pub fn export_state(&self) -> ConnectionManagerState {
+        let state = self.state.lock().expect("not poisoned");
+        if state.consecutive_fails == 0 {
+            return ConnectionManagerState::default();
+        }
+        ConnectionManagerState {
+            routes: vec![state.save(self.route_key(), Instant::now(), SystemTime::now())],
+        }
+    }
+
+    /// Replaces the route's cooldown with the one recorded for it in `state`, if any.
+    ///
+    /// This is meant for a manager that hasn't been used yet, such as one just created after the
+    /// app restarted.
+    pub fn import_state(&self, state: &ConnectionManagerState) {
+        let route = self.route_key();
+        let Some(saved) = state.routes.iter().find(|saved| saved.route == route) else {
+            return;
+        };
+        *self.state.lock().expect("not poisoned") =
+            ThrottlingConnectionManagerState::restore(saved, Instant::now(), SystemTime::now());
+    }
+
+    /// Distinguishes this route from the others in an exported [`ConnectionManagerState`].
+    ///
+    /// The route type alone isn't enough, since the same kind of route is used for many services.
+    fn route_key(&self) -> String {
+        let ConnectionParams {
+            route_type,
+            http_host,
+            transport,
+            ..
+        } = &self.connection_params;
+        format!(
+            "{route_type} {http_host} via {}:{}",
+            transport.tcp_host, transport.port
+        )
+    }
+}
+
 /// Declare &SingleRouteThrottlingConnectionManager unwind-safe.
 ///
 /// This is guaranteed by the impl blocks, which only update locked state
@@ -434,6 +786,11 @@ impl ConnectionManager for SingleRouteThrottlingConnectionManager {
     fn describe_for_logging(&self) -> String {
         self.connection_params.route_type.to_string()
     }
+
+    fn tcp_destination(&self) -> Option<(Host<&str>, NonZeroU16)> {
+        let TransportConnectionParams { tcp_host, port, .. } = &self.connection_params.transport;
+        Some((tcp_host.as_deref(), *port))
+    }
 }
 
 #[cfg(test)]
@@ -452,6 +809,7 @@ mod test {
         ClassifiableTestError, TestError, FEW_ATTEMPTS, LONG_CONNECTION_TIME, MANY_ATTEMPTS,
         TIMEOUT_DURATION, TIME_ADVANCE_VALUE,
     };
+    use crate::timeouts::NEGATIVE_CACHE_DNS_FAILURE_TTL;
     use crate::{HttpRequestDecoratorSeq, RouteType, TransportConnectionParams};
 
     const ROUTE_THAT_TIMES_OUT: &str = "timeout.signal.org";
@@ -581,11 +939,7 @@ mod test {
         // Wait a bit, but not long enough that the cooldown should have elapsed.
         time::advance(TIME_ADVANCE_VALUE).await;
         network_changed_event.fire();
-        // At this point the cooldown reset task has been spawned, but not run.
-        // Yielding does not guarantee that tokio will execute it, but it does make it very likely,
-        // especially on the current_thread runtime.
-        tokio::task::yield_now().await;
-        assert_eq!(manager.state.lock().await.reset_counter, 1);
+        assert_eq!(manager.state.lock().expect("not poisoned").reset_counter, 1);
 
         let attempt_outcome: ConnectionAttemptOutcome<(), TestError> =
             manager.connect_or_wait(|_| future::ready(Ok(()))).await;
@@ -615,11 +969,7 @@ mod test {
         // Wait a bit, but not long enough that the cooldown should have elapsed.
         time::advance(TIME_ADVANCE_VALUE).await;
         network_changed_event.fire();
-        // At this point the cooldown reset task has been spawned, but not run.
-        // Yielding does not guarantee that tokio will execute it, but it does make it very likely,
-        // especially on the current_thread runtime.
-        tokio::task::yield_now().await;
-        assert_eq!(manager.state.lock().await.reset_counter, 1);
+        assert_eq!(manager.state.lock().expect("not poisoned").reset_counter, 1);
 
         // first attempt after network change
         let time_over_timeout = TIMEOUT_DURATION * 2;
@@ -747,6 +1097,76 @@ mod test {
         validate_expected_route(&multi_route_manager, true, ROUTE_1).await;
     }
 
+    #[tokio::test(flavor = "current_thread", start_paused = true)]
+    async fn multi_route_manager_skips_routes_known_to_fail() {
+        let network_change_event = ObservableEvent::default();
+        let negative_cache = NegativeCache::new(Default::default(), &network_change_event);
+        let multi_route_manager = MultiRouteConnectionManager::new(vec![
+            SingleRouteThrottlingConnectionManager::new(
+                example_connection_params(ROUTE_1),
+                TIMEOUT_DURATION,
+                &network_change_event,
+            ),
+            SingleRouteThrottlingConnectionManager::new(
+                example_connection_params(ROUTE_2),
+                TIMEOUT_DURATION,
+                &network_change_event,
+            ),
+        ])
+        .with_negative_cache(negative_cache.clone());
+
+        // route1 is healthy, but its host just failed to resolve.
+        negative_cache.record_lookup_failure(ROUTE_1, negative_cache.generation());
+        validate_expected_route(&multi_route_manager, true, ROUTE_2).await;
+
+        // Once the failure expires, route1 is tried again.
+        time::advance(NEGATIVE_CACHE_DNS_FAILURE_TTL).await;
+        validate_expected_route(&multi_route_manager, true, ROUTE_1).await;
+
+        // A network change makes it worth trying right away.
+        negative_cache.record_lookup_failure(ROUTE_1, negative_cache.generation());
+        validate_expected_route(&multi_route_manager, true, ROUTE_2).await;
+        network_change_event.fire();
+        validate_expected_route(&multi_route_manager, true, ROUTE_1).await;
+
+        let stats = negative_cache.stats();
+        assert_eq!(stats.hits, 2);
+    }
+
+    #[tokio::test(flavor = "current_thread", start_paused = true)]
+    async fn connect_with_deadline_skips_routes_known_to_fail() {
+        let network_change_event = ObservableEvent::default();
+        let negative_cache = NegativeCache::new(Default::default(), &network_change_event);
+        let multi_route_manager =
+            MultiRouteConnectionManager::new(vec![SingleRouteThrottlingConnectionManager::new(
+                example_connection_params(ROUTE_1),
+                TIMEOUT_DURATION,
+                &network_change_event,
+            )])
+            .with_negative_cache(negative_cache.clone());
+
+        negative_cache.record_connect_refused(
+            Host::Domain(ROUTE_1),
+            nonzero!(443u16),
+            negative_cache.generation(),
+        );
+        let attempts_made = AtomicU16::new(0);
+        let result: Result<(), DeadlineConnectError<TestError>> = multi_route_manager
+            .connect_with_deadline(Instant::now() + TIMEOUT_DURATION, |_| {
+                attempts_made.fetch_add(1, Ordering::Relaxed);
+                future::ready(Ok(()))
+            })
+            .await;
+        assert_matches!(
+            result,
+            Err(DeadlineConnectError::ConnectionTimedOut(AttemptBreakdown {
+                skipped_in_cooldown: 1,
+                ..
+            }))
+        );
+        assert_eq!(attempts_made.load(Ordering::Relaxed), 0);
+    }
+
     #[derive(Clone, Debug)]
     struct CooldownAfterSomeAttempts {
         attempts_until_cooldown: u16,
@@ -940,6 +1360,81 @@ mod test {
         assert_eq!(wait_until, now + SHORT_DELAY);
     }
 
+    #[tokio::test(flavor = "current_thread", start_paused = true)]
+    async fn connect_with_deadline_stops_when_deadline_passes() {
+        let slow_route = |host| {
+            SingleRouteThrottlingConnectionManager::new(
+                example_connection_params(host),
+                TIMEOUT_DURATION,
+                &ObservableEvent::default(),
+            )
+        };
+        let multi_route_manager = MultiRouteConnectionManager::new(vec![
+            slow_route(ROUTE_1),
+            slow_route(ROUTE_2),
+            slow_route(ROUTE_THAT_TIMES_OUT),
+        ]);
+
+        // Each route gets two attempts before going into cooldown, so the third route's first
+        // attempt gets cut short.
+        let start = Instant::now();
+        let deadline = start + TIMEOUT_DURATION * 5;
+        let res: Result<(), DeadlineConnectError<TestError>> = multi_route_manager
+            .connect_with_deadline(deadline, |_| async {
+                tokio::time::sleep(LONG_CONNECTION_TIME).await;
+                Ok(())
+            })
+            .await;
+
+        let attempts = assert_matches!(res, Err(DeadlineConnectError::ConnectionTimedOut(a)) => a);
+        assert_eq!(
+            attempts,
+            AttemptBreakdown {
+                failed: 0,
+                timed_out: 5,
+                skipped_in_cooldown: 2,
+            }
+        );
+        assert_eq!(Instant::now(), deadline);
+    }
+
+    #[tokio::test(flavor = "current_thread", start_paused = true)]
+    async fn connect_with_deadline_does_not_start_after_deadline() {
+        let multi_route_manager = MultiRouteConnectionManager::new(vec![FailingSingle(
+            example_connection_params(ROUTE_1),
+        )]);
+        let res: Result<(), DeadlineConnectError<TestError>> = multi_route_manager
+            .connect_with_deadline(Instant::now(), |_| future::pending())
+            .await;
+        assert_matches!(
+            res,
+            Err(DeadlineConnectError::ConnectionTimedOut(a)) if a == AttemptBreakdown::default()
+        );
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn connect_with_deadline_gives_up_if_retry_after_is_past_deadline() {
+        let multi_route_manager = MultiRouteConnectionManager::new(vec![FailingSingle(
+            example_connection_params(ROUTE_1),
+        )]);
+        let start = Instant::now();
+        let res: Result<(), DeadlineConnectError<ClassifiableTestError>> = multi_route_manager
+            .connect_with_deadline(start + Duration::from_secs(10), |_| {
+                future::ready(Err(ClassifiableTestError(ErrorClass::RetryAt(
+                    start + Duration::from_secs(42),
+                ))))
+            })
+            .await;
+        assert_matches!(
+            res,
+            Err(DeadlineConnectError::ConnectionTimedOut(AttemptBreakdown {
+                failed: 1,
+                ..
+            }))
+        );
+        assert_eq!(Instant::now(), start);
+    }
+
     async fn validate_expected_route(
         multi_route_manager: &MultiRouteConnectionManager,
         route1_healthy: bool,
@@ -987,6 +1482,160 @@ mod test {
         }
     }
 
+    #[tokio::test(flavor = "current_thread", start_paused = true)]
+    async fn cooldown_survives_export_and_import() {
+        let manager = SingleRouteThrottlingConnectionManager::new(
+            example_connection_params(ROUTE_1),
+            TIMEOUT_DURATION,
+            &ObservableEvent::default(),
+        );
+        assert_eq!(manager.export_state(), ConnectionManagerState::default());
+
+        for _ in 0..MANY_ATTEMPTS {
+            time::advance(TIME_ADVANCE_VALUE).await;
+            let _attempt_outcome: ConnectionAttemptOutcome<(), TestError> = manager
+                .connect_or_wait(|_| future::ready(Err(TestError::Expected)))
+                .await;
+        }
+        let state = manager.export_state();
+        assert_matches!(&state.routes[..], [route] if route.consecutive_failures > 0);
+
+        // A manager for some other route is unaffected.
+        let other = SingleRouteThrottlingConnectionManager::new_with_state(
+            example_connection_params(ROUTE_2),
+            TIMEOUT_DURATION,
+            &ObservableEvent::default(),
+            &state,
+        );
+        let attempt_outcome: ConnectionAttemptOutcome<(), TestError> =
+            other.connect_or_wait(|_| future::ready(Ok(()))).await;
+        assert_matches!(attempt_outcome, ConnectionAttemptOutcome::Attempted(Ok(())));
+
+        let restored = SingleRouteThrottlingConnectionManager::new_with_state(
+            example_connection_params(ROUTE_1),
+            TIMEOUT_DURATION,
+            &ObservableEvent::default(),
+            &state,
+        );
+        let attempt_outcome: ConnectionAttemptOutcome<(), TestError> =
+            restored.connect_or_wait(|_| future::ready(Ok(()))).await;
+        assert_matches!(
+            attempt_outcome,
+            ConnectionAttemptOutcome::WaitUntil(later)
+            if later > Instant::now() && later <= Instant::now() + CONNECTION_ROUTE_MAX_COOLDOWN
+        );
+
+        time::advance(CONNECTION_ROUTE_MAX_COOLDOWN).await;
+        let attempt_outcome: ConnectionAttemptOutcome<(), TestError> =
+            restored.connect_or_wait(|_| future::ready(Ok(()))).await;
+        assert_matches!(attempt_outcome, ConnectionAttemptOutcome::Attempted(Ok(())));
+        assert_eq!(restored.export_state(), ConnectionManagerState::default());
+    }
+
+    #[tokio::test(flavor = "current_thread", start_paused = true)]
+    async fn restored_cooldown_uses_wall_clock_time() {
+        let now = Instant::now();
+        let wall_now = UNIX_EPOCH + Duration::from_secs(1_700_000_000);
+        let mut state = ThrottlingConnectionManagerState::new(now);
+        for _ in 0..3 {
+            state = state.after_attempt(false, now);
+        }
+        let remaining = state.next_attempt - now;
+        let saved = state.save("route".to_owned(), now, wall_now);
+        assert_eq!(
+            saved,
+            RouteCooldownState {
+                route: "route".to_owned(),
+                consecutive_failures: 3,
+                cooldown_until_unix_millis: 1_700_000_000_000
+                    + u64::try_from(remaining.as_millis()).unwrap(),
+            }
+        );
+
+        // Some of the cooldown passed while the app wasn't running.
+        let later = now + Duration::from_secs(100);
+        let restored = ThrottlingConnectionManagerState::restore(
+            &saved,
+            later,
+            wall_now + Duration::from_secs(1),
+        );
+        assert_eq!(restored.consecutive_fails, 3);
+        assert_eq!(
+            restored.next_attempt,
+            later + remaining - Duration::from_secs(1)
+        );
+
+        // All of it passed.
+        let restored =
+            ThrottlingConnectionManagerState::restore(&saved, later, wall_now + remaining * 2);
+        assert_eq!(restored.next_attempt, later);
+    }
+
+    #[tokio::test(flavor = "current_thread", start_paused = true)]
+    async fn restored_cooldown_is_clamped() {
+        let now = Instant::now();
+        let wall_now = SystemTime::now();
+        let saved = RouteCooldownState {
+            route: "route".to_owned(),
+            consecutive_failures: u16::MAX,
+            cooldown_until_unix_millis: u64::MAX,
+        };
+        let restored = ThrottlingConnectionManagerState::restore(&saved, now, wall_now);
+        assert_eq!(
+            usize::from(restored.consecutive_fails),
+            CONNECTION_ROUTE_COOLDOWN_INTERVALS.len() - 1
+        );
+        assert_eq!(restored.next_attempt, now + CONNECTION_ROUTE_MAX_COOLDOWN);
+    }
+
+    #[tokio::test(flavor = "current_thread", start_paused = true)]
+    async fn multi_route_manager_exports_all_failing_routes() {
+        let make_multi = |state: &ConnectionManagerState| {
+            MultiRouteConnectionManager::new(
+                [ROUTE_1, ROUTE_2, ROUTE_THAT_TIMES_OUT]
+                    .map(|route| {
+                        SingleRouteThrottlingConnectionManager::new_with_state(
+                            example_connection_params(route),
+                            TIMEOUT_DURATION,
+                            &ObservableEvent::default(),
+                            state,
+                        )
+                    })
+                    .into(),
+            )
+        };
+        let multi = make_multi(&ConnectionManagerState::default());
+        for _ in 0..MANY_ATTEMPTS {
+            time::advance(TIME_ADVANCE_VALUE).await;
+            let _attempt_outcome: ConnectionAttemptOutcome<(), TestError> = multi
+                .connect_or_wait(|params| {
+                    future::ready(if &*params.http_host == ROUTE_THAT_TIMES_OUT {
+                        Ok(())
+                    } else {
+                        Err(TestError::Expected)
+                    })
+                })
+                .await;
+        }
+
+        let state = multi.export_state();
+        let failing_routes = state
+            .routes
+            .iter()
+            .map(|route| route.route.as_str())
+            .collect_vec();
+        assert_eq!(
+            failing_routes,
+            [
+                format!("test {ROUTE_1} via {ROUTE_1}:443"),
+                format!("test {ROUTE_2} via {ROUTE_2}:443"),
+            ]
+        );
+
+        let restored = make_multi(&state);
+        assert_eq!(restored.export_state().routes.len(), 2);
+    }
+
     fn example_connection_params(host: &str) -> ConnectionParams {
         let host = host.into();
         ConnectionParams {
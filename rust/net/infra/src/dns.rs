@@ -3,12 +3,14 @@
 // SPDX-License-Identifier: AGPL-3.0-only
 //
 
+use std::borrow::Cow;
 use std::collections::HashMap;
-use std::net::Ipv6Addr;
+use std::net::{IpAddr, Ipv6Addr};
 use std::str::FromStr as _;
 use std::sync::{Arc, Mutex};
 use std::time::Duration;
 
+use itertools::{Either, Itertools as _};
 use nonzero_ext::nonzero;
 use oneshot_broadcast::Sender;
 use tokio::time::Instant;
@@ -24,9 +26,12 @@ use crate::dns::dns_utils::oneshot_broadcast::Receiver;
 use crate::dns::dns_utils::{log_safe_domain, oneshot_broadcast};
 use crate::dns::lookup_result::LookupResult;
 use crate::host::Host;
-use crate::timeouts::{DNS_FALLBACK_LOOKUP_TIMEOUTS, DNS_SYSTEM_LOOKUP_TIMEOUT};
+use crate::negative_cache::{NegativeCache, NegativeCacheConfig};
+use crate::timeouts::{
+    DNS_FALLBACK_LOOKUP_TIMEOUTS, DNS_PREWARMED_RESULT_LIFETIME, DNS_SYSTEM_LOOKUP_TIMEOUT,
+};
 use crate::utils::{self, ObservableEvent};
-use crate::{ConnectionParams, HttpRequestDecoratorSeq, RouteType};
+use crate::{ConnectionParams, DnsSource, HttpRequestDecoratorSeq, RouteType};
 
 pub mod custom_resolver;
 mod dns_errors;
@@ -44,6 +49,12 @@ pub type Result<T> = std::result::Result<T, Error>;
 struct DnsResolverState {
     /// Controls if lookup results will contain IPv6 entries.
     ipv6_enabled: bool,
+    /// Controls whether connections try IPv6 addresses before IPv4 ones.
+    ipv6_preferred: bool,
+    /// Fixed results that take precedence over any lookup.
+    static_overrides: HashMap<String, LookupResult>,
+    /// Results of lookups performed ahead of time, along with when they stop being usable.
+    prewarmed: HashMap<String, (LookupResult, Instant)>,
     in_flight_lookups: HashMap<String, Receiver<Result<LookupResult>>>,
 }
 
@@ -51,6 +62,9 @@ impl std::fmt::Debug for DnsResolverState {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         f.debug_struct("DnsResolverState")
             .field("ipv6_enabled", &self.ipv6_enabled)
+            .field("ipv6_preferred", &self.ipv6_preferred)
+            .field("static_overrides", &self.static_overrides.keys())
+            .field("prewarmed", &self.prewarmed.keys())
             .field("in_flight_lookups", &self.in_flight_lookups.keys())
             .finish()
     }
@@ -60,6 +74,9 @@ impl Default for DnsResolverState {
     fn default() -> Self {
         Self {
             ipv6_enabled: true,
+            ipv6_preferred: true,
+            static_overrides: Default::default(),
+            prewarmed: Default::default(),
             in_flight_lookups: Default::default(),
         }
     }
@@ -69,6 +86,10 @@ impl Default for DnsResolverState {
 pub struct DnsResolver {
     lookup_options: Arc<[LookupOption]>,
     state: Arc<Mutex<DnsResolverState>>,
+    negative_cache: Option<NegativeCache>,
+    /// Clears [`DnsResolverState::prewarmed`] on network changes; see
+    /// [`Self::clearing_prewarmed_on`].
+    _network_change_subscription: Option<Arc<utils::EventSubscription>>,
 }
 
 /// A single DNS resolution strategy that can be tried.
@@ -93,6 +114,8 @@ impl DnsResolver {
         DnsResolver {
             lookup_options,
             state: Default::default(),
+            negative_cache: None,
+            _network_change_subscription: None,
         }
     }
 
@@ -105,17 +128,24 @@ impl DnsResolver {
     pub(crate) fn new_from_static_map(static_map: HashMap<&'static str, LookupResult>) -> Self {
         DnsResolver {
             lookup_options: Arc::new([LookupOption {
-                lookup: Box::new(StaticDnsMap(static_map)),
+                lookup: Box::new(StaticDnsMap(
+                    static_map
+                        .into_iter()
+                        .map(|(hostname, result)| (hostname.into(), result))
+                        .collect(),
+                )),
                 timeout_after: Duration::from_millis(1),
             }]),
             state: Default::default(),
+            negative_cache: None,
+            _network_change_subscription: None,
         }
     }
 
     /// Creates a DNS resolver with a default resolution strategy
     /// to be used for most of the external use cases
     pub fn new_with_static_fallback(
-        static_map: HashMap<&'static str, LookupResult>,
+        static_map: HashMap<Cow<'static, str>, LookupResult>,
         network_change_event: &ObservableEvent,
     ) -> Self {
         let host = CLOUDFLARE_NS.into();
@@ -157,17 +187,133 @@ impl DnsResolver {
         DnsResolver {
             lookup_options,
             state: Default::default(),
+            negative_cache: Some(NegativeCache::new(
+                NegativeCacheConfig::default(),
+                network_change_event,
+            )),
+            _network_change_subscription: None,
         }
+        .clearing_prewarmed_on(network_change_event)
+    }
+
+    /// Forgets prewarmed results whenever `network_change_event` fires.
+    ///
+    /// Some networks intercept DNS requests and return addresses that only work within that
+    /// network, so results looked up before a change can't be trusted after it.
+    pub(crate) fn clearing_prewarmed_on(self, network_change_event: &ObservableEvent) -> Self {
+        // The subscription alone shouldn't keep the state alive.
+        let state = Arc::downgrade(&self.state);
+        let subscription = network_change_event.subscribe(Box::new(move || {
+            let Some(state) = state.upgrade() else {
+                return;
+            };
+            state.lock().expect("not poisoned").prewarmed.clear();
+        }));
+        Self {
+            _network_change_subscription: Some(Arc::new(subscription)),
+            ..self
+        }
+    }
+
+    /// Replaces the cache of recent failures consulted before each lookup, or disables it.
+    ///
+    /// Resolvers created with [`Self::new`] use a cache with the default TTLs. The same cache is
+    /// also used for TCP connections made through this resolver, and can be shared with a
+    /// [`MultiRouteConnectionManager`](crate::connection_manager::MultiRouteConnectionManager) so
+    /// that it skips routes that are known to fail.
+    pub fn with_negative_cache(self, negative_cache: Option<NegativeCache>) -> Self {
+        Self {
+            negative_cache,
+            ..self
+        }
+    }
+
+    pub fn negative_cache(&self) -> Option<&NegativeCache> {
+        self.negative_cache.as_ref()
     }
 
     pub fn set_ipv6_enabled(&self, ipv6_enabled: bool) {
         let mut guard = self.state.lock().expect("not poisoned");
         if guard.ipv6_enabled != ipv6_enabled {
             guard.ipv6_enabled = ipv6_enabled;
+            guard.prewarmed.clear();
             guard.in_flight_lookups.clear();
         }
     }
 
+    /// Sets whether connections should try a resolved IPv6 address before an IPv4 one.
+    ///
+    /// Unlike [`Self::set_ipv6_enabled`], this doesn't affect the results of lookups, only the
+    /// order they're used in, so cached results remain valid.
+    pub fn set_ipv6_preferred(&self, ipv6_preferred: bool) {
+        self.state.lock().expect("not poisoned").ipv6_preferred = ipv6_preferred;
+    }
+
+    pub(crate) fn ipv6_preferred(&self) -> bool {
+        self.state.lock().expect("not poisoned").ipv6_preferred
+    }
+
+    /// Replaces the set of hostnames that resolve to fixed addresses without any lookup.
+    ///
+    /// This is meant for test labs and captive environments that need to reach servers by IP
+    /// without touching the system's DNS configuration. Passing an empty map removes all
+    /// overrides.
+    pub fn set_static_entries<K: Into<String>>(&self, entries: HashMap<K, Vec<IpAddr>>) {
+        let static_overrides = entries
+            .into_iter()
+            .filter(|(_, addresses)| !addresses.is_empty())
+            .map(|(hostname, addresses)| (hostname.into(), static_lookup_result(addresses)))
+            .collect();
+        let mut guard = self.state.lock().expect("not poisoned");
+        guard.static_overrides = static_overrides;
+    }
+
+    /// Sets or, if `addresses` is empty, removes the fixed addresses for a single hostname.
+    ///
+    /// See [`Self::set_static_entries`].
+    pub fn set_static_entry(&self, hostname: &str, addresses: Vec<IpAddr>) {
+        let mut guard = self.state.lock().expect("not poisoned");
+        if addresses.is_empty() {
+            guard.static_overrides.remove(hostname);
+        } else {
+            guard
+                .static_overrides
+                .insert(hostname.to_owned(), static_lookup_result(addresses));
+        }
+    }
+
+    /// Looks up each of `hostnames` ahead of time, so that connections made shortly afterwards
+    /// don't have to wait on DNS.
+    ///
+    /// Results are kept for [`DNS_PREWARMED_RESULT_LIFETIME`], until IPv6 is enabled or disabled,
+    /// or (for resolvers created with [`Self::new`]) until the network changes. Failed lookups are
+    /// logged and otherwise ignored.
+    pub async fn prewarm(&self, hostnames: &[&str]) {
+        let lookups = hostnames.iter().map(|hostname| async move {
+            let result = match self.start_or_join_lookup(hostname).val().await {
+                Ok(r) => r,
+                Err(_) => Err(Error::LookupFailed),
+            };
+            match result {
+                Ok(result) => {
+                    let mut guard = self.state.lock().expect("not poisoned");
+                    guard.prewarmed.insert(
+                        hostname.to_string(),
+                        (result, Instant::now() + DNS_PREWARMED_RESULT_LIFETIME),
+                    );
+                }
+                Err(e) => {
+                    log::info!(
+                        "Failed to prewarm domain [{}]: {}",
+                        log_safe_domain(hostname),
+                        e
+                    );
+                }
+            }
+        });
+        futures_util::future::join_all(lookups).await;
+    }
+
     pub async fn lookup_ip(&self, hostname: &str) -> Result<LookupResult> {
         let parse_as_ip_addr = hostname.parse().ok().or_else(|| {
             let hostname = hostname.strip_prefix('[')?;
@@ -185,6 +331,21 @@ impl DnsResolver {
                 ipv6,
             });
         }
+        if let Some(result) = self.lookup_local(hostname) {
+            return result;
+        }
+        if let Some(failed_until) = self
+            .negative_cache
+            .as_ref()
+            .and_then(|cache| cache.check_lookup(hostname))
+        {
+            log::info!(
+                "Not looking up domain [{}] again until {:?} after a recent failure",
+                log_safe_domain(hostname),
+                failed_until.saturating_duration_since(Instant::now()),
+            );
+            return Err(Error::Cooldown);
+        }
         match self.start_or_join_lookup(hostname).val().await {
             Ok(r) => r,
             Err(_) => {
@@ -194,6 +355,25 @@ impl DnsResolver {
         }
     }
 
+    /// Checks for a static override or an unexpired prewarmed result for `hostname`.
+    fn lookup_local(&self, hostname: &str) -> Option<Result<LookupResult>> {
+        let mut guard = self.state.lock().expect("not poisoned");
+        if let Some(result) = guard.static_overrides.get(hostname) {
+            return Some(filter_by_ip_type(result.clone(), guard.ipv6_enabled));
+        }
+        match guard.prewarmed.get(hostname) {
+            Some((result, expires_at)) if Instant::now() < *expires_at => Some(Ok(LookupResult {
+                source: DnsSource::Cache,
+                ..result.clone()
+            })),
+            Some(_) => {
+                guard.prewarmed.remove(hostname);
+                None
+            }
+            None => None,
+        }
+    }
+
     fn start_or_join_lookup(&self, hostname: &str) -> Receiver<Result<LookupResult>> {
         let mut guard = self.state.lock().expect("not poisoned");
         match guard.in_flight_lookups.get(hostname) {
@@ -216,6 +396,7 @@ impl DnsResolver {
         ipv6_enabled: bool,
     ) {
         let self_clone = self.clone();
+        let generation = self.negative_cache.as_ref().map(NegativeCache::generation);
         tokio::spawn(async move {
             let request = DnsLookupRequest {
                 hostname: Arc::from(hostname.as_str()),
@@ -234,14 +415,13 @@ impl DnsResolver {
                 Err(Error::LookupFailed)
             };
 
-            let result = perform_lookups.await.and_then(|res| match ipv6_enabled {
-                true => Ok(res),
-                false if res.ipv4.is_empty() => Err(Error::RequestedIpTypeNotFound),
-                false => Ok(LookupResult {
-                    ipv6: vec![],
-                    ..res
-                }),
-            });
+            let result = perform_lookups.await;
+            if let (Err(_), Some(cache), Some(generation)) =
+                (&result, &self_clone.negative_cache, generation)
+            {
+                cache.record_lookup_failure(&hostname, generation);
+            }
+            let result = result.and_then(|res| filter_by_ip_type(res, ipv6_enabled));
 
             self_clone.clear_in_flight_map(hostname.as_str());
             if result_sender.send(result).is_err() {
@@ -259,6 +439,27 @@ impl DnsResolver {
     }
 }
 
+fn filter_by_ip_type(result: LookupResult, ipv6_enabled: bool) -> Result<LookupResult> {
+    match ipv6_enabled {
+        true => Ok(result),
+        false if result.ipv4.is_empty() => Err(Error::RequestedIpTypeNotFound),
+        false => Ok(LookupResult {
+            ipv6: vec![],
+            ..result
+        }),
+    }
+}
+
+fn static_lookup_result(addresses: Vec<IpAddr>) -> LookupResult {
+    let (ipv4, ipv6) = addresses
+        .into_iter()
+        .partition_map(|address| match address {
+            IpAddr::V4(v4) => Either::Left(v4),
+            IpAddr::V6(v6) => Either::Right(v6),
+        });
+    LookupResult::new(DnsSource::Static, ipv4, ipv6)
+}
+
 impl LookupOption {
     async fn attempt(&self, request: DnsLookupRequest) -> Result<LookupResult> {
         let Self {
@@ -305,6 +506,7 @@ mod test {
     use super::*;
     use crate::dns::dns_lookup::DnsLookupRequest;
     use crate::dns::{DnsLookup, DnsResolver, Error, LookupResult, StaticDnsMap};
+    use crate::timeouts::NEGATIVE_CACHE_DNS_FAILURE_TTL;
     use crate::utils::sleep_and_catch_up;
     use crate::DnsSource;
 
@@ -442,8 +644,8 @@ mod test {
     #[tokio::test(start_paused = true)]
     async fn test_dns_lookup_fallback() {
         let static_dns_map = StaticDnsMap(HashMap::from([
-            (FALLBACK_ONLY_DOMAIN, (IPV4, IPV6).into()),
-            (TIMING_OUT_DOMAIN, (IPV4, IPV6).into()),
+            (FALLBACK_ONLY_DOMAIN.into(), (IPV4, IPV6).into()),
+            (TIMING_OUT_DOMAIN.into(), (IPV4, IPV6).into()),
         ]));
         let dns_resolver = DnsResolver::new_custom(vec![
             (
@@ -470,8 +672,10 @@ mod test {
 
     #[tokio::test]
     async fn test_dns_lookup_ipv6_disabled() {
-        let static_dns_map =
-            StaticDnsMap(HashMap::from([(FALLBACK_ONLY_DOMAIN, (IPV4, IPV6).into())]));
+        let static_dns_map = StaticDnsMap(HashMap::from([(
+            FALLBACK_ONLY_DOMAIN.into(),
+            (IPV4, IPV6).into(),
+        )]));
         let dns_resolver = DnsResolver::new_custom(vec![
             (
                 TestLookup::standard_responses(Duration::ZERO),
@@ -750,4 +954,127 @@ mod test {
         // making sure that the `test_lookup` have only seen one request
         assert_matches!(test_lookup.logged_requests().as_slice(), [_, _]);
     }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_static_entries_take_precedence() {
+        let test_lookup = TestLookup::standard_responses(Duration::ZERO);
+        let dns_resolver = DnsResolver::new_custom(vec![(test_lookup.clone(), ATTEMPT_TIMEOUT)]);
+
+        dns_resolver.set_static_entries(HashMap::from([(IPV4_ONLY_DOMAIN, vec![IPV6.into()])]));
+        let result = dns_resolver
+            .lookup_ip(IPV4_ONLY_DOMAIN)
+            .await
+            .expect("success");
+        assert_eq!(result.source(), DnsSource::Static);
+        assert_empty!(result.ipv4);
+        assert_eq!(&[IPV6], result.ipv6.as_slice());
+        assert_empty!(test_lookup.logged_requests());
+
+        dns_resolver.set_ipv6_enabled(false);
+        assert_matches!(
+            dns_resolver.lookup_ip(IPV4_ONLY_DOMAIN).await,
+            Err(Error::RequestedIpTypeNotFound)
+        );
+        dns_resolver.set_ipv6_enabled(true);
+
+        dns_resolver.set_static_entry(IPV4_ONLY_DOMAIN, vec![]);
+        let result = dns_resolver
+            .lookup_ip(IPV4_ONLY_DOMAIN)
+            .await
+            .expect("success");
+        assert_eq!(&[IPV4], result.ipv4.as_slice());
+        assert_matches!(test_lookup.logged_requests().as_slice(), [_]);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_prewarmed_results_are_dropped_when_stale_or_on_network_change() {
+        let test_lookup = TestLookup::standard_responses(Duration::ZERO);
+        let network_change_event = ObservableEvent::new();
+        let dns_resolver = DnsResolver::new_custom(vec![(test_lookup.clone(), ATTEMPT_TIMEOUT)])
+            .clearing_prewarmed_on(&network_change_event);
+        let lookup_source = || async {
+            dns_resolver
+                .lookup_ip(IPV4_ONLY_DOMAIN)
+                .await
+                .expect("success")
+                .source()
+        };
+
+        dns_resolver
+            .prewarm(&[IPV4_ONLY_DOMAIN, FALLBACK_ONLY_DOMAIN])
+            .await;
+        assert_eq!(test_lookup.logged_requests().len(), 2);
+
+        assert_eq!(lookup_source().await, DnsSource::Cache);
+        assert_eq!(test_lookup.logged_requests().len(), 2);
+
+        // Failed lookups aren't remembered.
+        assert_matches!(
+            dns_resolver.lookup_ip(FALLBACK_ONLY_DOMAIN).await,
+            Err(Error::LookupFailed)
+        );
+        assert_eq!(test_lookup.logged_requests().len(), 3);
+
+        // A network change drops the prewarmed result, however fresh.
+        network_change_event.fire();
+        assert_eq!(lookup_source().await, DnsSource::Test);
+        assert_eq!(test_lookup.logged_requests().len(), 4);
+
+        // So does time.
+        dns_resolver.prewarm(&[IPV4_ONLY_DOMAIN]).await;
+        assert_eq!(lookup_source().await, DnsSource::Cache);
+        tokio::time::advance(DNS_PREWARMED_RESULT_LIFETIME).await;
+        assert_eq!(lookup_source().await, DnsSource::Test);
+        assert_eq!(test_lookup.logged_requests().len(), 6);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_failed_lookups_are_remembered_until_network_change() {
+        let test_lookup = TestLookup::standard_responses(Duration::ZERO);
+        let network_change_event = ObservableEvent::new();
+        let dns_resolver = DnsResolver::new_custom(vec![(test_lookup.clone(), ATTEMPT_TIMEOUT)])
+            .with_negative_cache(Some(NegativeCache::new(
+                NegativeCacheConfig::default(),
+                &network_change_event,
+            )));
+
+        assert_matches!(
+            dns_resolver.lookup_ip(FALLBACK_ONLY_DOMAIN).await,
+            Err(Error::LookupFailed)
+        );
+        assert_eq!(test_lookup.logged_requests().len(), 1);
+
+        // Within the TTL, the lookup isn't even attempted.
+        assert_matches!(
+            dns_resolver.lookup_ip(FALLBACK_ONLY_DOMAIN).await,
+            Err(Error::Cooldown)
+        );
+        assert_eq!(test_lookup.logged_requests().len(), 1);
+
+        // Other domains aren't affected.
+        dns_resolver
+            .lookup_ip(IPV4_ONLY_DOMAIN)
+            .await
+            .expect("success");
+        assert_eq!(test_lookup.logged_requests().len(), 2);
+
+        tokio::time::advance(NEGATIVE_CACHE_DNS_FAILURE_TTL).await;
+        assert_matches!(
+            dns_resolver.lookup_ip(FALLBACK_ONLY_DOMAIN).await,
+            Err(Error::LookupFailed)
+        );
+        assert_eq!(test_lookup.logged_requests().len(), 3);
+
+        // A network change forgets the failure immediately.
+        network_change_event.fire();
+        assert_matches!(
+            dns_resolver.lookup_ip(FALLBACK_ONLY_DOMAIN).await,
+            Err(Error::LookupFailed)
+        );
+        assert_eq!(test_lookup.logged_requests().len(), 4);
+
+        let stats = dns_resolver.negative_cache().expect("set").stats();
+        assert_eq!(stats.hits, 1);
+        assert_eq!(stats.misses, 4);
+    }
 }
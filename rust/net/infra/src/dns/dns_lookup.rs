@@ -3,6 +3,7 @@
 // SPDX-License-Identifier: AGPL-3.0-only
 //
 
+use std::borrow::Cow;
 use std::collections::HashMap;
 use std::fmt::Debug;
 use std::net::SocketAddr;
@@ -34,7 +35,7 @@ pub struct SystemDnsLookup;
 
 /// Performs DNS lookup in a map of statically configured, non-expiring entries
 #[derive(Debug, Default)]
-pub struct StaticDnsMap(pub HashMap<&'static str, LookupResult>);
+pub struct StaticDnsMap(pub HashMap<Cow<'static, str>, LookupResult>);
 
 #[async_trait]
 impl DnsLookup for SystemDnsLookup {
@@ -45,6 +45,22 @@ impl LookupResult {
     pub(crate) fn is_empty(&self) -> bool {
         self.ipv4.is_empty() && self.ipv6.is_empty()
     }
+
+    /// Like [`IntoIterator::into_iter`], but starting with an IPv4 address instead if
+    /// `prefer_ipv6` is `false`.
+    pub(crate) fn into_iter_with_preference(
+        self,
+        prefer_ipv6: bool,
+    ) -> impl Iterator<Item = IpAddr> {
+        if prefer_ipv6 {
+            itertools::Either::Left(self.into_iter())
+        } else {
+            itertools::Either::Right(itertools::interleave(
+                self.ipv4.into_iter().map(IpAddr::V4),
+                self.ipv6.into_iter().map(IpAddr::V6),
+            ))
+        }
+    }
 }
 
 #[cfg(test)]
@@ -118,6 +134,30 @@ mod test {
         );
     }
 
+    #[test]
+    fn lookup_result_can_prefer_ipv4() {
+        let ipv4_1 = ip_addr!(v4, "1.1.1.1");
+        let ipv4_2 = ip_addr!(v4, "2.2.2.2");
+        let ipv6_1 = ip_addr!(v6, "::1");
+
+        let lookup_result =
+            LookupResult::new(DnsSource::Static, vec![ipv4_1, ipv4_2], vec![ipv6_1]);
+        assert_eq!(
+            lookup_result
+                .clone()
+                .into_iter_with_preference(false)
+                .collect::<Vec<_>>(),
+            vec![IpAddr::V4(ipv4_1), IpAddr::V6(ipv6_1), IpAddr::V4(ipv4_2)],
+        );
+        assert_eq!(
+            lookup_result
+                .clone()
+                .into_iter_with_preference(true)
+                .collect::<Vec<_>>(),
+            lookup_result.into_iter().collect::<Vec<_>>(),
+        );
+    }
+
     fn validate_expected_order(ipv4s: Vec<Ipv4Addr>, ipv6s: Vec<Ipv6Addr>, expected: Vec<IpAddr>) {
         let lookup_result = LookupResult::new(DnsSource::Static, ipv4s, ipv6s);
         let actual: Vec<IpAddr> = lookup_result.into_iter().collect();
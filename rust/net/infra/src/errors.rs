@@ -5,6 +5,7 @@
 
 use std::fmt::Display;
 
+use boring_signal::x509::X509VerifyError;
 use tokio_boring_signal::HandshakeError;
 
 use crate::certs;
@@ -26,6 +27,8 @@ pub enum TransportConnectError {
     CertError,
     /// Failed to establish SSL connection: {0}
     SslFailedHandshake(FailedHandshakeReason),
+    /// Server certificate is not signed by a trusted root
+    UntrustedRoot,
     /// Proxy handshake failed
     ProxyProtocol,
 }
@@ -93,6 +96,23 @@ impl From<certs::Error> for TransportConnectError {
 
 impl<S> From<HandshakeError<S>> for TransportConnectError {
     fn from(error: HandshakeError<S>) -> Self {
+        // Called out separately so that apps can tell when certificate pinning is what's
+        // preventing them from connecting.
+        const UNTRUSTED_ROOT_ERRORS: [X509VerifyError; 4] = [
+            X509VerifyError::DEPTH_ZERO_SELF_SIGNED_CERT,
+            X509VerifyError::SELF_SIGNED_CERT_IN_CHAIN,
+            X509VerifyError::UNABLE_TO_GET_ISSUER_CERT,
+            X509VerifyError::UNABLE_TO_GET_ISSUER_CERT_LOCALLY,
+        ];
+        let untrusted_root = error.ssl().is_some_and(|ssl| match ssl.verify_result() {
+            Ok(()) => false,
+            Err(e) => UNTRUSTED_ROOT_ERRORS.contains(&e),
+        });
+        if untrusted_root {
+            log::debug!("handshake error: {error}");
+            return Self::UntrustedRoot;
+        }
+
         Self::SslFailedHandshake(FailedHandshakeReason::from(error))
     }
 }
@@ -104,6 +124,7 @@ impl From<TransportConnectError> for std::io::Error {
             TransportConnectError::InvalidConfiguration => ErrorKind::InvalidInput,
             TransportConnectError::TcpConnectionFailed => ErrorKind::ConnectionRefused,
             TransportConnectError::SslFailedHandshake(_)
+            | TransportConnectError::UntrustedRoot
             | TransportConnectError::SslError(_)
             | TransportConnectError::CertError
             | TransportConnectError::ProxyProtocol => ErrorKind::InvalidData,
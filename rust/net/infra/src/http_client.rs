@@ -4,19 +4,23 @@
 //
 
 use bytes::Bytes;
+use futures_util::stream::BoxStream;
+use futures_util::{StreamExt as _, TryStreamExt as _};
 use http::response::Parts;
 use http::uri::PathAndQuery;
 use http::HeaderMap;
-use http_body_util::{BodyExt, Full, Limited};
+use http_body_util::{BodyDataStream, BodyExt, Full, Limited};
+use hyper::body::Incoming;
 use hyper::client::conn::http2;
 use hyper_util::rt::{TokioExecutor, TokioIo};
 
+use crate::errors::TransportConnectError;
 use crate::{Alpn, ConnectionParams, StreamAndInfo, TransportConnector};
 
 #[derive(displaydoc::Display, Debug)]
 pub enum HttpError {
-    /// SSL handshake failed
-    SslHandshakeFailed,
+    /// SSL handshake failed: {0}
+    SslHandshakeFailed(TransportConnectError),
     /// HTTP2 handshake failed
     Http2HandshakeFailed,
     /// Failed to create HTTP request
@@ -48,37 +52,9 @@ impl AggregatingHttp2Client {
         headers: HeaderMap,
         body: Bytes,
     ) -> Result<(Parts, Bytes), HttpError> {
-        let uri = format!(
-            "https://{}:{}{}",
-            self.connection_params.http_host, self.connection_params.transport.port, path_and_query
-        );
-        let mut request_builder = http::Request::builder()
-            .method(method)
-            .uri(uri)
-            .version(http::Version::HTTP_2);
-
-        request_builder
-            .headers_mut()
-            // This can fail if the builder is invalid.
-            .ok_or(HttpError::FailedToCreateRequest)?
-            .extend(headers);
-        let request_builder = self
-            .connection_params
-            .http_request_decorator
-            .decorate_request(request_builder);
-
-        let content_length = body.len();
-        let request = request_builder
-            .header(http::header::CONTENT_LENGTH, content_length)
-            .body(Full::new(body))
-            .map_err(|_| HttpError::FailedToCreateRequest)?;
-
         let res = self
-            .service
-            .clone()
-            .send_request(request)
-            .await
-            .map_err(|_| HttpError::SendRequestError)?;
+            .send_request(path_and_query, method, headers, body)
+            .await?;
 
         let (parts, body) = res.into_parts();
 
@@ -110,9 +86,71 @@ impl AggregatingHttp2Client {
 
         Ok((parts, content))
     }
+
+    /// Like [`Self::send_request_aggregate_response`], but returns the body as it arrives rather
+    /// than collecting it.
+    ///
+    /// The body isn't limited to the client's maximum response size. If the connection fails
+    /// partway through, the stream yields [`HttpError::FailedToReadContentOfUnknownSize`].
+    pub async fn send_request_streaming_response(
+        &self,
+        path_and_query: PathAndQuery,
+        method: http::Method,
+        headers: HeaderMap,
+        body: Bytes,
+    ) -> Result<(Parts, BoxStream<'static, Result<Bytes, HttpError>>), HttpError> {
+        let res = self
+            .send_request(path_and_query, method, headers, body)
+            .await?;
+        let (parts, body) = res.into_parts();
+        let body = BodyDataStream::new(body)
+            .map_err(|_| HttpError::FailedToReadContentOfUnknownSize)
+            .boxed();
+        Ok((parts, body))
+    }
+
+    async fn send_request(
+        &self,
+        path_and_query: PathAndQuery,
+        method: http::Method,
+        headers: HeaderMap,
+        body: Bytes,
+    ) -> Result<http::Response<Incoming>, HttpError> {
+        let uri = format!(
+            "https://{}:{}{}",
+            self.connection_params.http_host, self.connection_params.transport.port, path_and_query
+        );
+        let mut request_builder = http::Request::builder()
+            .method(method)
+            .uri(uri)
+            .version(http::Version::HTTP_2);
+
+        request_builder
+            .headers_mut()
+            // This can fail if the builder is invalid.
+            .ok_or(HttpError::FailedToCreateRequest)?
+            .extend(headers);
+        let request_builder = self
+            .connection_params
+            .http_request_decorator
+            .decorate_request(request_builder);
+
+        let content_length = body.len();
+        let request = request_builder
+            .header(http::header::CONTENT_LENGTH, content_length)
+            .body(Full::new(body))
+            .map_err(|_| HttpError::FailedToCreateRequest)?;
+
+        self.service
+            .clone()
+            .send_request(request)
+            .await
+            .map_err(|_| HttpError::SendRequestError)
+    }
 }
 
-pub(crate) async fn http2_client<C: TransportConnector>(
+/// Connects to `connection_params` and performs an HTTP/2 handshake.
+pub async fn http2_client<C: TransportConnector>(
     transport_connector: &C,
     connection_params: ConnectionParams,
     max_response_size: usize,
@@ -122,7 +160,7 @@ pub(crate) async fn http2_client<C: TransportConnector>(
         .await
         .map_err(|e| {
             log::error!("error: {}", e);
-            HttpError::SslHandshakeFailed
+            HttpError::SslHandshakeFailed(e)
         })?;
     let io = TokioIo::new(ssl_stream);
     let (sender, connection) = http2::handshake::<_, _, Full<Bytes>>(TokioExecutor::new(), io)
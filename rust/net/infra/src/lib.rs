@@ -24,7 +24,7 @@ use crate::errors::TransportConnectError;
 use crate::host::Host;
 use crate::timeouts::{WS_KEEP_ALIVE_INTERVAL, WS_MAX_IDLE_INTERVAL};
 use crate::utils::{basic_authorization, ObservableEvent};
-use crate::ws::WebSocketConfig;
+use crate::ws::{WebSocketConfig, WebSocketMessageLimits};
 
 pub mod certs;
 pub mod connection_manager;
@@ -32,11 +32,15 @@ pub mod dns;
 pub mod errors;
 pub mod host;
 pub mod http_client;
+pub mod live_connections;
+pub mod logging;
+pub mod negative_cache;
 pub mod noise;
 pub mod route;
 pub mod service;
 pub mod tcp_ssl;
 pub mod timeouts;
+pub mod traffic_stats;
 pub mod utils;
 pub mod ws;
 pub mod ws2;
@@ -158,6 +162,15 @@ pub struct ConnectionInfo {
     /// If IP information is available, it's recommended to use [Host::Ip] and
     /// only use [Host::Domain] as a fallback.
     pub address: Host<Arc<str>>,
+
+    /// Port that was used to establish the connection
+    pub port: NonZeroU16,
+
+    /// The websocket sub-protocol selected by the server, if any
+    ///
+    /// Always one of the values offered in [`WebSocketConfig::sub_protocols`]. Only set once a
+    /// websocket connection has been established on top of the transport.
+    pub websocket_sub_protocol: Option<&'static str>,
 }
 
 /// Source for the result of a hostname lookup.
@@ -202,12 +215,17 @@ pub enum RouteType {
 
 impl ConnectionInfo {
     pub fn description(&self) -> String {
-        format!(
+        let mut description = format!(
             "route={};dns_source={};ip_type={:?}",
             self.route_type,
             self.dns_source,
             IpType::from_host(&self.address)
-        )
+        );
+        if let Some(sub_protocol) = self.websocket_sub_protocol {
+            description.push_str(";sub_protocol=");
+            description.push_str(sub_protocol);
+        }
+        description
     }
 }
 
@@ -335,11 +353,13 @@ pub fn make_ws_config(
     connect_timeout: Duration,
 ) -> WebSocketConfig {
     WebSocketConfig {
-        ws_config: tungstenite::protocol::WebSocketConfig::default(),
+        ws_config: WebSocketMessageLimits::DEFAULT.ws_config(),
         endpoint: websocket_endpoint,
         max_connection_time: connect_timeout,
         keep_alive_interval: WS_KEEP_ALIVE_INTERVAL,
         max_idle_time: WS_MAX_IDLE_INTERVAL,
+        sub_protocols: &[],
+        draining_close_code: None,
     }
 }
 
@@ -477,6 +497,8 @@ pub mod testutil {
                     route_type: RouteType::Test,
                     dns_source: DnsSource::Test,
                     address: connection_params.tcp_host.clone(),
+                    port: connection_params.port,
+                    websocket_sub_protocol: None,
                 },
             ))
         }
@@ -596,6 +618,7 @@ pub mod testutil {
 #[cfg(test)]
 pub(crate) mod test {
     use http::Request;
+    use nonzero_ext::nonzero;
 
     use crate::host::Host;
     use crate::utils::basic_authorization;
@@ -605,14 +628,25 @@ pub(crate) mod test {
     fn connection_info_description() {
         let connection_info = ConnectionInfo {
             address: Host::Domain("test.signal.org".into()),
+            port: nonzero!(443u16),
             dns_source: DnsSource::SystemLookup,
             route_type: RouteType::Test,
+            websocket_sub_protocol: None,
         };
 
         assert_eq!(
             connection_info.description(),
             "route=test;dns_source=systemlookup;ip_type=Unknown"
         );
+
+        let connection_info = ConnectionInfo {
+            websocket_sub_protocol: Some("test.v1"),
+            ..connection_info
+        };
+        assert_eq!(
+            connection_info.description(),
+            "route=test;dns_source=systemlookup;ip_type=Unknown;sub_protocol=test.v1"
+        );
     }
 
     #[test]
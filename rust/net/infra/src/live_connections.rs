@@ -0,0 +1,165 @@
+//
+// Copyright 2024 Signal Messenger, LLC.
+// SPDX-License-Identifier: AGPL-3.0-only
+//
+
+//! Accounting for open connections, to catch leaks in debug builds.
+//!
+//! Each connection owns a [`LiveConnection`] for as long as its transport stream is open. In debug
+//! builds these are counted by kind, and the counts are available from [`live_connection_counts`].
+//! A connection that is dropped before it was explicitly closed (or before the server closed it)
+//! logs a warning, since that usually means someone lost track of it. In release builds only the
+//! warning remains; nothing is counted.
+
+use std::collections::HashMap;
+
+/// Connections to the chat server.
+pub const CHAT: &str = "chat";
+/// Connections to the contact discovery enclave.
+pub const CDSI: &str = "cdsi";
+/// Connections to the secure value recovery enclaves.
+pub const SVR: &str = "svr";
+/// Connections to the attachment CDNs.
+pub const CDN: &str = "cdn";
+
+/// Marks a connection as open until dropped; see the [module documentation](self).
+#[derive(Debug)]
+pub struct LiveConnection {
+    kind: &'static str,
+    closed: bool,
+}
+
+impl LiveConnection {
+    pub fn new(kind: &'static str) -> Self {
+        #[cfg(debug_assertions)]
+        registry::opened(kind);
+        Self {
+            kind,
+            closed: false,
+        }
+    }
+
+    pub fn kind(&self) -> &'static str {
+        self.kind
+    }
+
+    /// Records that the connection was shut down deliberately, by either side.
+    ///
+    /// Dropping the connection after this won't be reported as a leak.
+    pub fn mark_closed(&mut self) {
+        self.closed = true;
+    }
+}
+
+impl Drop for LiveConnection {
+    fn drop(&mut self) {
+        #[cfg(debug_assertions)]
+        registry::dropped(self.kind, self.closed);
+        if !self.closed {
+            crate::logging::warn!("{} connection was dropped without being closed", self.kind);
+        }
+    }
+}
+
+/// The number of connections of each kind that are currently open.
+///
+/// Kinds with no open connections are omitted. Always empty in release builds.
+#[cfg(debug_assertions)]
+pub fn live_connection_counts() -> HashMap<&'static str, usize> {
+    registry::live_counts()
+}
+
+/// The number of connections of each kind that are currently open.
+///
+/// Kinds with no open connections are omitted. Always empty in release builds.
+#[cfg(not(debug_assertions))]
+pub fn live_connection_counts() -> HashMap<&'static str, usize> {
+    HashMap::new()
+}
+
+#[cfg(debug_assertions)]
+mod registry {
+    use std::collections::{BTreeMap, HashMap};
+    use std::sync::Mutex;
+
+    #[derive(Default)]
+    struct Counts {
+        live: usize,
+        #[cfg_attr(not(test), allow(dead_code))]
+        abandoned: usize,
+    }
+
+    static COUNTS: Mutex<BTreeMap<&'static str, Counts>> = Mutex::new(BTreeMap::new());
+
+    fn with_counts<T>(f: impl FnOnce(&mut BTreeMap<&'static str, Counts>) -> T) -> T {
+        // The counts are only for diagnostics, so a panic elsewhere shouldn't stop them.
+        let mut counts = COUNTS.lock().unwrap_or_else(|e| e.into_inner());
+        f(&mut counts)
+    }
+
+    pub(super) fn opened(kind: &'static str) {
+        with_counts(|counts| counts.entry(kind).or_default().live += 1)
+    }
+
+    pub(super) fn dropped(kind: &'static str, closed: bool) {
+        with_counts(|counts| {
+            let entry = counts.entry(kind).or_default();
+            entry.live = entry.live.saturating_sub(1);
+            if !closed {
+                entry.abandoned += 1;
+            }
+        })
+    }
+
+    pub(super) fn live_counts() -> HashMap<&'static str, usize> {
+        with_counts(|counts| {
+            counts
+                .iter()
+                .filter(|(_, counts)| counts.live != 0)
+                .map(|(kind, counts)| (*kind, counts.live))
+                .collect()
+        })
+    }
+
+    #[cfg(test)]
+    pub(crate) fn abandoned_count(kind: &'static str) -> usize {
+        with_counts(|counts| counts.get(kind).map_or(0, |counts| counts.abandoned))
+    }
+}
+
+#[cfg(all(test, debug_assertions))]
+pub(crate) use registry::abandoned_count;
+
+#[cfg(all(test, debug_assertions))]
+mod test {
+    use super::*;
+
+    // Each test uses its own kind, since the counts are shared by every test in the process.
+
+    #[test]
+    fn counts_follow_connection_lifetimes() {
+        const KIND: &str = "test-lifetimes";
+        assert_eq!(live_connection_counts().get(KIND), None);
+
+        let mut first = LiveConnection::new(KIND);
+        let mut second = LiveConnection::new(KIND);
+        assert_eq!(live_connection_counts().get(KIND), Some(&2));
+
+        first.mark_closed();
+        drop(first);
+        assert_eq!(live_connection_counts().get(KIND), Some(&1));
+
+        second.mark_closed();
+        drop(second);
+        assert_eq!(live_connection_counts().get(KIND), None);
+        assert_eq!(abandoned_count(KIND), 0);
+    }
+
+    #[test]
+    fn dropping_without_closing_is_reported() {
+        const KIND: &str = "test-abandoned";
+        drop(LiveConnection::new(KIND));
+        assert_eq!(live_connection_counts().get(KIND), None);
+        assert_eq!(abandoned_count(KIND), 1);
+    }
+}
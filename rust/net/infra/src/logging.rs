@@ -0,0 +1,15 @@
+//
+// Copyright 2024 Signal Messenger, LLC.
+// SPDX-License-Identifier: AGPL-3.0-only
+//
+
+//! Logging macros for connection lifecycle events.
+//!
+//! With the `tracing` feature enabled, these emit [`tracing`] events, which are attached to any
+//! active spans (and still forwarded to [`log`] if no subscriber is installed). Otherwise they are
+//! the plain [`log`] macros.
+
+#[cfg(not(feature = "tracing"))]
+pub use log::{debug, error, info, trace, warn};
+#[cfg(feature = "tracing")]
+pub use tracing::{debug, error, info, trace, warn};
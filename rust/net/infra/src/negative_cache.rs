@@ -0,0 +1,304 @@
+//
+// Copyright 2024 Signal Messenger, LLC.
+// SPDX-License-Identifier: AGPL-3.0-only
+//
+
+//! Short-lived memory of DNS lookups and TCP connections that just failed.
+//!
+//! When several connection attempts are made in a burst, there's no point in re-resolving a
+//! hostname that just failed to resolve, or reconnecting to a port that just refused the
+//! connection. Failures are only remembered for a few seconds, and only for the current network:
+//! a network change event starts a new *generation* and forgets everything. Failures of attempts
+//! that started in an earlier generation are never recorded.
+
+use std::collections::HashMap;
+use std::num::NonZeroU16;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use tokio::time::Instant;
+
+use crate::host::Host;
+use crate::timeouts::{NEGATIVE_CACHE_CONNECT_REFUSED_TTL, NEGATIVE_CACHE_DNS_FAILURE_TTL};
+use crate::utils::{EventSubscription, ObservableEvent};
+
+/// How long failures are remembered by a [`NegativeCache`].
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct NegativeCacheConfig {
+    /// For a DNS lookup that failed or timed out.
+    pub dns_failure_ttl: Duration,
+    /// For a TCP connection that was refused by every resolved address.
+    pub connect_refused_ttl: Duration,
+}
+
+impl Default for NegativeCacheConfig {
+    fn default() -> Self {
+        Self {
+            dns_failure_ttl: NEGATIVE_CACHE_DNS_FAILURE_TTL,
+            connect_refused_ttl: NEGATIVE_CACHE_CONNECT_REFUSED_TTL,
+        }
+    }
+}
+
+/// The number of times a [`NegativeCache`] was consulted, for diagnostics.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub struct NegativeCacheStats {
+    /// Checks that found a recent failure, so that the attempt could be skipped.
+    pub hits: u64,
+    /// Checks that found nothing, so that the attempt had to be made.
+    pub misses: u64,
+}
+
+/// Identifies the network generation an attempt was started in.
+///
+/// See [`NegativeCache::generation`].
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct Generation(u64);
+
+#[derive(Clone, Debug, Hash, PartialEq, Eq)]
+enum FailureKey {
+    Lookup(String),
+    Connect(Host<String>, NonZeroU16),
+}
+
+#[derive(Debug, Default)]
+struct NegativeCacheState {
+    generation: u64,
+    failures: HashMap<FailureKey, Instant>,
+}
+
+#[derive(Debug)]
+struct NegativeCacheInner {
+    config: NegativeCacheConfig,
+    state: Mutex<NegativeCacheState>,
+    hits: AtomicU64,
+    misses: AtomicU64,
+}
+
+/// Remembers recent DNS and TCP connection failures until they expire or the network changes.
+///
+/// Clones share the same cache.
+#[derive(Clone, Debug)]
+pub struct NegativeCache {
+    inner: Arc<NegativeCacheInner>,
+    _network_changed_subscription: Arc<EventSubscription>,
+}
+
+impl NegativeCache {
+    pub fn new(config: NegativeCacheConfig, network_changed_event: &ObservableEvent) -> Self {
+        let inner = Arc::new(NegativeCacheInner {
+            config,
+            state: Default::default(),
+            hits: Default::default(),
+            misses: Default::default(),
+        });
+
+        // As with the connection managers, the subscription alone shouldn't keep the cache alive.
+        let inner_for_network_changed = Arc::downgrade(&inner);
+        let network_changed_subscription = network_changed_event.subscribe(Box::new(move || {
+            let Some(inner) = inner_for_network_changed.upgrade() else {
+                return;
+            };
+            let mut state = inner.state.lock().expect("not poisoned");
+            state.generation += 1;
+            state.failures.clear();
+        }));
+
+        Self {
+            inner,
+            _network_changed_subscription: Arc::new(network_changed_subscription),
+        }
+    }
+
+    /// The current network generation.
+    ///
+    /// This should be read when an attempt *starts*, and passed back when recording its failure,
+    /// so that a failure on a network that's no longer in use isn't remembered.
+    pub fn generation(&self) -> Generation {
+        Generation(self.inner.state.lock().expect("not poisoned").generation)
+    }
+
+    /// Returns when a recent failure to look up `hostname` expires, if there was one.
+    pub fn check_lookup(&self, hostname: &str) -> Option<Instant> {
+        let failed_until = self.failed_until(&FailureKey::Lookup(hostname.to_owned()));
+        self.count(failed_until)
+    }
+
+    /// Returns when a recent refusal to connect to `host` on `port` expires, if there was one.
+    pub fn check_connect(&self, host: Host<&str>, port: NonZeroU16) -> Option<Instant> {
+        let failed_until = self.failed_until(&connect_key(host, port));
+        self.count(failed_until)
+    }
+
+    /// Returns how long a connection to `host` on `port` is known to be hopeless, whether because
+    /// the hostname couldn't be resolved or because the connection was refused.
+    ///
+    /// This only counts as a single hit or miss.
+    pub fn check_route(&self, host: Host<&str>, port: NonZeroU16) -> Option<Instant> {
+        let lookup_failed_until = match host {
+            Host::Domain(domain) => self.failed_until(&FailureKey::Lookup(domain.to_owned())),
+            Host::Ip(_) => None,
+        };
+        let connect_failed_until = self.failed_until(&connect_key(host, port));
+        self.count(Option::max(lookup_failed_until, connect_failed_until))
+    }
+
+    /// Records that looking up `hostname` failed, for an attempt started in `generation`.
+    pub fn record_lookup_failure(&self, hostname: &str, generation: Generation) {
+        self.record(
+            FailureKey::Lookup(hostname.to_owned()),
+            self.inner.config.dns_failure_ttl,
+            generation,
+        )
+    }
+
+    /// Records that connecting to `host` on `port` was refused, for an attempt started in
+    /// `generation`.
+    pub fn record_connect_refused(
+        &self,
+        host: Host<&str>,
+        port: NonZeroU16,
+        generation: Generation,
+    ) {
+        self.record(
+            connect_key(host, port),
+            self.inner.config.connect_refused_ttl,
+            generation,
+        )
+    }
+
+    pub fn stats(&self) -> NegativeCacheStats {
+        NegativeCacheStats {
+            hits: self.inner.hits.load(Ordering::Relaxed),
+            misses: self.inner.misses.load(Ordering::Relaxed),
+        }
+    }
+
+    fn failed_until(&self, key: &FailureKey) -> Option<Instant> {
+        let mut state = self.inner.state.lock().expect("not poisoned");
+        match state.failures.get(key) {
+            Some(expires_at) if Instant::now() < *expires_at => Some(*expires_at),
+            Some(_) => {
+                state.failures.remove(key);
+                None
+            }
+            None => None,
+        }
+    }
+
+    fn record(&self, key: FailureKey, ttl: Duration, generation: Generation) {
+        let mut state = self.inner.state.lock().expect("not poisoned");
+        if Generation(state.generation) != generation {
+            return;
+        }
+        state.failures.insert(key, Instant::now() + ttl);
+    }
+
+    fn count(&self, failed_until: Option<Instant>) -> Option<Instant> {
+        let counter = match failed_until {
+            Some(_) => &self.inner.hits,
+            None => &self.inner.misses,
+        };
+        counter.fetch_add(1, Ordering::Relaxed);
+        failed_until
+    }
+}
+
+fn connect_key(host: Host<&str>, port: NonZeroU16) -> FailureKey {
+    let host = match host {
+        Host::Ip(ip) => Host::Ip(ip),
+        Host::Domain(domain) => Host::Domain(domain.to_owned()),
+    };
+    FailureKey::Connect(host, port)
+}
+
+#[cfg(test)]
+mod test {
+    use std::net::Ipv4Addr;
+
+    use nonzero_ext::nonzero;
+
+    use super::*;
+
+    const HOST: &str = "chat.signal.org";
+    const PORT: NonZeroU16 = nonzero!(443u16);
+
+    #[tokio::test(start_paused = true)]
+    async fn failures_expire_after_their_ttl() {
+        let event = ObservableEvent::new();
+        let cache = NegativeCache::new(NegativeCacheConfig::default(), &event);
+
+        assert_eq!(cache.check_lookup(HOST), None);
+        cache.record_lookup_failure(HOST, cache.generation());
+        cache.record_connect_refused(
+            Host::Ip(Ipv4Addr::LOCALHOST.into()),
+            PORT,
+            cache.generation(),
+        );
+
+        let lookup_expiry = Instant::now() + NEGATIVE_CACHE_DNS_FAILURE_TTL;
+        let connect_expiry = Instant::now() + NEGATIVE_CACHE_CONNECT_REFUSED_TTL;
+        assert_eq!(cache.check_lookup(HOST), Some(lookup_expiry));
+        assert_eq!(
+            cache.check_connect(Host::Ip(Ipv4Addr::LOCALHOST.into()), PORT),
+            Some(connect_expiry)
+        );
+        assert_eq!(
+            cache.check_route(Host::Domain(HOST), PORT),
+            Some(lookup_expiry)
+        );
+        assert_eq!(cache.check_lookup("other.signal.org"), None);
+
+        tokio::time::sleep(NEGATIVE_CACHE_CONNECT_REFUSED_TTL).await;
+        assert_eq!(
+            cache.check_connect(Host::Ip(Ipv4Addr::LOCALHOST.into()), PORT),
+            None
+        );
+        assert_eq!(cache.check_lookup(HOST), Some(lookup_expiry));
+
+        tokio::time::sleep_until(lookup_expiry).await;
+        assert_eq!(cache.check_lookup(HOST), None);
+
+        assert_eq!(cache.stats(), NegativeCacheStats { hits: 4, misses: 4 });
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn ttls_are_configurable() {
+        let event = ObservableEvent::new();
+        let config = NegativeCacheConfig {
+            dns_failure_ttl: Duration::from_secs(30),
+            connect_refused_ttl: Duration::ZERO,
+        };
+        let cache = NegativeCache::new(config, &event);
+
+        cache.record_lookup_failure(HOST, cache.generation());
+        cache.record_connect_refused(Host::Domain(HOST), PORT, cache.generation());
+        assert_eq!(
+            cache.check_lookup(HOST),
+            Some(Instant::now() + Duration::from_secs(30))
+        );
+        assert_eq!(cache.check_connect(Host::Domain(HOST), PORT), None);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn network_change_forgets_failures() {
+        let event = ObservableEvent::new();
+        let cache = NegativeCache::new(NegativeCacheConfig::default(), &event);
+
+        let old_generation = cache.generation();
+        cache.record_lookup_failure(HOST, old_generation);
+        assert!(cache.check_lookup(HOST).is_some());
+
+        event.fire();
+        assert_ne!(cache.generation(), old_generation);
+        assert_eq!(cache.check_lookup(HOST), None);
+
+        // An attempt that started before the change finishing afterwards isn't remembered either.
+        cache.record_lookup_failure(HOST, old_generation);
+        assert_eq!(cache.check_lookup(HOST), None);
+
+        cache.record_lookup_failure(HOST, cache.generation());
+        assert!(cache.check_lookup(HOST).is_some());
+    }
+}
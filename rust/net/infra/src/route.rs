@@ -101,6 +101,7 @@ mod test {
             fragment: WebSocketRouteFragment {
                 ws_config: WebSocketConfig::default(),
                 endpoint: WS_ENDPOINT.clone(),
+                sub_protocols: &[],
             },
             inner: HttpsProvider {
                 direct_host_header: "http-host".into(),
@@ -130,6 +131,7 @@ mod test {
                 fragment: WebSocketRouteFragment {
                     ws_config: WebSocketConfig::default(),
                     endpoint: WS_ENDPOINT.clone(),
+                    sub_protocols: &[],
                 },
                 inner: HttpsTlsRoute {
                     fragment: HttpRouteFragment {
@@ -152,6 +154,7 @@ mod test {
                 fragment: WebSocketRouteFragment {
                     ws_config: WebSocketConfig::default(),
                     endpoint: WS_ENDPOINT.clone(),
+                    sub_protocols: &[],
                 },
                 inner: HttpsTlsRoute {
                     fragment: HttpRouteFragment {
@@ -174,6 +177,7 @@ mod test {
                 fragment: WebSocketRouteFragment {
                     ws_config: WebSocketConfig::default(),
                     endpoint: WS_ENDPOINT.clone(),
+                    sub_protocols: &[],
                 },
                 inner: HttpsTlsRoute {
                     fragment: HttpRouteFragment {
@@ -14,6 +14,10 @@ pub struct WebSocketRouteFragment {
     pub ws_config: WebSocketConfig,
     /// The HTTP path to use when establishing the websocket connection.
     pub endpoint: PathAndQuery,
+    /// The sub-protocols to offer, most preferred first.
+    ///
+    /// See [`crate::ws::WebSocketConfig::sub_protocols`].
+    pub sub_protocols: &'static [&'static str],
 }
 
 pub type WebSocketRoute<H> = SimpleRoute<WebSocketRouteFragment, H>;
@@ -51,7 +55,9 @@ impl<R: ReplaceFragment<S>, S> ReplaceFragment<S> for WebSocketRoute<R> {
 /// implement [`PartialEq`].
 impl PartialEq for WebSocketRouteFragment {
     fn eq(&self, other: &Self) -> bool {
-        self.endpoint == other.endpoint && ws_config_eq(self.ws_config, other.ws_config)
+        self.endpoint == other.endpoint
+            && self.sub_protocols == other.sub_protocols
+            && ws_config_eq(self.ws_config, other.ws_config)
     }
 }
 
@@ -13,7 +13,7 @@ use tokio::sync::Mutex;
 use tokio::time::{timeout_at, Instant};
 
 use crate::connection_manager::{
-    ConnectionAttemptOutcome, ConnectionManager, ErrorClass, ErrorClassifier,
+    ConnectionAttemptOutcome, ConnectionManager, DeadlineConnectError, ErrorClass, ErrorClassifier,
 };
 use crate::errors::LogSafeDisplay;
 use crate::{ConnectionInfo, ConnectionParams, HttpRequestDecorator};
@@ -48,6 +48,9 @@ pub enum CancellationReason {
     ExplicitDisconnect,
     ServiceError,
     RemoteClose,
+    /// The server closed the connection because it is shutting down, not because anything went
+    /// wrong; it's fine to reconnect immediately.
+    ServerDraining,
     ProtocolError,
 }
 
@@ -177,14 +180,7 @@ where
         log::debug!("attempting a connection");
         let connection_attempt_result = self
             .connection_manager
-            .connect_or_wait(|connection_params| {
-                log::debug!(
-                    "trying to connect to {}:{}",
-                    connection_params.transport.tcp_host,
-                    connection_params.transport.port
-                );
-                self.service_connector.connect_channel(connection_params)
-            })
+            .connect_or_wait(|connection_params| self.connect_channel(connection_params))
             .await;
 
         match connection_attempt_result {
@@ -210,6 +206,40 @@ where
             }
         }
     }
+
+    /// Keeps trying to connect until a connection is made, an attempt fails fatally, or
+    /// `deadline` passes.
+    ///
+    /// See [`ConnectionManager::connect_with_deadline`].
+    pub async fn connect_with_deadline(
+        &self,
+        deadline: Instant,
+    ) -> Result<(C::Service, CancellationToken), DeadlineConnectError<C::ConnectError>> {
+        log::debug!("attempting a connection with a deadline");
+        let channel = self
+            .connection_manager
+            .connect_with_deadline(deadline, |connection_params| {
+                self.connect_channel(connection_params)
+            })
+            .await
+            .inspect_err(|e| log::debug!("connection attempt failed: {e}"))?;
+        log::debug!("connection attempt succeeded");
+        Ok(self.service_connector.start_service(channel))
+    }
+
+    async fn connect_channel(
+        &self,
+        connection_params: &ConnectionParams,
+    ) -> Result<C::Channel, C::ConnectError> {
+        log::debug!(
+            "trying to connect to {}:{}",
+            connection_params.transport.tcp_host,
+            connection_params.transport.port
+        );
+        self.service_connector
+            .connect_channel(connection_params)
+            .await
+    }
 }
 
 pub(crate) struct ServiceInner<C: ServiceConnector, M> {
@@ -304,13 +334,23 @@ where
     }
 
     pub async fn connect(&self) -> Result<(), ConnectError<C::ConnectError>> {
-        self.do_connect().await
+        self.do_connect(Instant::now() + self.data.connection_timeout)
+            .await
+    }
+
+    /// Like [`Self::connect`], but gives up at `deadline` if that comes before the service's own
+    /// connection timeout.
+    pub async fn connect_with_deadline(
+        &self,
+        deadline: Instant,
+    ) -> Result<(), ConnectError<C::ConnectError>> {
+        let own_deadline = Instant::now() + self.data.connection_timeout;
+        self.do_connect(deadline.min(own_deadline)).await
     }
 
-    async fn do_connect(&self) -> Result<(), ConnectError<C::ConnectError>> {
+    async fn do_connect(&self, deadline: Instant) -> Result<(), ConnectError<C::ConnectError>> {
         let mut attempts: u16 = 0;
         let start_of_connection_process = Instant::now();
-        let deadline = start_of_connection_process + self.data.connection_timeout;
         let deadline_for_starting = deadline - MINIMUM_CONNECTION_TIME;
 
         let mut guard = match timeout_at(deadline, self.data.state.lock()).await {
@@ -418,6 +458,18 @@ where
     pub async fn service(&self) -> Result<C::Service, StateError> {
         self.map_service(|service| service.clone()).await
     }
+
+    /// Like [`Self::service`], but returns `None` instead of waiting if the state is locked, as it
+    /// is while connecting.
+    pub fn try_service(&self) -> Option<C::Service> {
+        let guard = self.data.state.try_lock().ok()?;
+        match &*guard {
+            ServiceState::Active(service, status) if !status.is_cancelled() => {
+                Some(service.clone())
+            }
+            _ => None,
+        }
+    }
 }
 
 #[cfg(test)]
@@ -4,6 +4,7 @@
 //
 
 use std::num::NonZeroU16;
+use std::sync::atomic::{AtomicUsize, Ordering};
 use std::sync::Arc;
 
 use async_trait::async_trait;
@@ -18,6 +19,7 @@ use crate::certs::RootCertificates;
 use crate::dns::DnsResolver;
 use crate::errors::TransportConnectError;
 use crate::host::Host;
+use crate::negative_cache::NegativeCache;
 use crate::tcp_ssl::proxy::tls::TlsProxyConnector;
 use crate::timeouts::TCP_CONNECTION_ATTEMPT_DELAY;
 use crate::utils::first_ok;
@@ -45,6 +47,15 @@ impl TcpSslConnector {
         };
         dns_resolver.set_ipv6_enabled(ipv6_enabled);
     }
+
+    /// The resolver used to look up the destination (or proxy) host.
+    pub fn dns_resolver(&self) -> &DnsResolver {
+        match self {
+            TcpSslConnector::Direct(c) => &c.dns_resolver,
+            TcpSslConnector::Proxied(c) => &c.dns_resolver,
+            TcpSslConnector::Invalid(resolver) => resolver,
+        }
+    }
 }
 
 pub struct TcpSslConnectorStream(
@@ -126,6 +137,16 @@ async fn connect_tcp(
     host: Host<&str>,
     port: NonZeroU16,
 ) -> Result<StreamAndInfo<TcpStream>, TransportConnectError> {
+    let negative_cache = dns_resolver.negative_cache();
+    if let Some(failed_until) = negative_cache.and_then(|cache| cache.check_connect(host, port)) {
+        log::info!(
+            "Not connecting again until {:?} after the connection was recently refused",
+            failed_until.saturating_duration_since(tokio::time::Instant::now()),
+        );
+        return Err(TransportConnectError::TcpConnectionFailed);
+    }
+    let generation = negative_cache.map(NegativeCache::generation);
+
     let dns_lookup = match host {
         Host::Ip(ip) => {
             let (ipv4, ipv6) = match ip {
@@ -149,6 +170,11 @@ async fn connect_tcp(
     }
 
     let dns_source = dns_lookup.source();
+    let prefer_ipv6 = dns_resolver.ipv6_preferred();
+    let candidate_count = dns_lookup.ipv4.len() + dns_lookup.ipv6.len();
+    // Counts addresses that refused the connection, as opposed to failing some other way.
+    let refused_count = AtomicUsize::new(0);
+    let refused_count = &refused_count;
 
     // The idea is to go through the list of candidate IP addresses
     // and to attempt a connection to each of them, giving each one a `CONNECTION_ATTEMPT_DELAY` headstart
@@ -158,34 +184,47 @@ async fn connect_tcp(
     // First, for each resolved IP address, constructing a future
     // that incorporates the delay based on its position in the list.
     // This way we can start all futures at once and simply wait for the first one to complete successfully.
-    let staggered_futures = dns_lookup.into_iter().enumerate().map(|(idx, ip)| {
-        let delay = TCP_CONNECTION_ATTEMPT_DELAY * idx.try_into().unwrap();
-        async move {
-            if !delay.is_zero() {
-                tokio::time::sleep(delay).await;
+    let staggered_futures = dns_lookup
+        .into_iter_with_preference(prefer_ipv6)
+        .enumerate()
+        .map(|(idx, ip)| {
+            let delay = TCP_CONNECTION_ATTEMPT_DELAY * idx.try_into().unwrap();
+            async move {
+                if !delay.is_zero() {
+                    tokio::time::sleep(delay).await;
+                }
+                TcpStream::connect((ip, port.into()))
+                    .inspect_err(|e| {
+                        log::debug!("failed to connect to IP [{ip}] with an error: {e:?}");
+                        if e.kind() == std::io::ErrorKind::ConnectionRefused {
+                            refused_count.fetch_add(1, Ordering::Relaxed);
+                        }
+                    })
+                    .await
+                    .map(|r| {
+                        log::debug!("successfully connected to IP [{ip}]");
+                        StreamAndInfo(
+                            r,
+                            ConnectionInfo {
+                                route_type,
+                                dns_source,
+                                address: ip.into(),
+                                port,
+                                websocket_sub_protocol: None,
+                            },
+                        )
+                    })
             }
-            TcpStream::connect((ip, port.into()))
-                .inspect_err(|e| {
-                    log::debug!("failed to connect to IP [{ip}] with an error: {e:?}");
-                })
-                .await
-                .map(|r| {
-                    log::debug!("successfully connected to IP [{ip}]");
-                    StreamAndInfo(
-                        r,
-                        ConnectionInfo {
-                            route_type,
-                            dns_source,
-                            address: ip.into(),
-                        },
-                    )
-                })
-        }
-    });
+        });
 
-    first_ok(staggered_futures)
-        .await
-        .ok_or(TransportConnectError::TcpConnectionFailed)
+    let result = first_ok(staggered_futures).await;
+    if result.is_none() && refused_count.load(Ordering::Relaxed) == candidate_count {
+        // Every address actively refused the connection, so trying again right away is pointless.
+        if let (Some(cache), Some(generation)) = (negative_cache, generation) {
+            cache.record_connect_refused(host, port, generation);
+        }
+    }
+    result.ok_or(TransportConnectError::TcpConnectionFailed)
 }
 
 impl AsyncRead for TcpSslConnectorStream {
@@ -282,11 +321,19 @@ pub(crate) mod testutil {
     ///
     /// Returns the address of the server and a [`Future`] that runs it.
     pub(crate) fn localhost_http_server() -> (SocketAddr, impl Future<Output = ()>) {
+        localhost_http_server_with_identity(
+            SERVER_CERTIFICATE.cert.pem(),
+            SERVER_CERTIFICATE.key_pair.serialize_pem(),
+        )
+    }
+
+    /// Like [`localhost_http_server`], but presents the given PEM-encoded certificate chain.
+    pub(crate) fn localhost_http_server_with_identity(
+        cert_pem: String,
+        key_pem: String,
+    ) -> (SocketAddr, impl Future<Output = ()>) {
         let filter = warp::any().map(|| FAKE_RESPONSE);
-        let server = warp::serve(filter)
-            .tls()
-            .cert(SERVER_CERTIFICATE.cert.pem())
-            .key(SERVER_CERTIFICATE.key_pair.serialize_pem());
+        let server = warp::serve(filter).tls().cert(cert_pem).key(key_pem);
 
         server.bind_ephemeral((Ipv6Addr::LOCALHOST, 0))
     }
@@ -321,7 +368,7 @@ pub(crate) mod testutil {
 mod test {
     use std::borrow::Cow;
     use std::collections::HashMap;
-    use std::net::Ipv6Addr;
+    use std::net::{Ipv4Addr, Ipv6Addr};
 
     use assert_matches::assert_matches;
     use test_case::test_case;
@@ -330,6 +377,8 @@ mod test {
     use super::*;
     use crate::dns::lookup_result::LookupResult;
     use crate::host::Host;
+    use crate::negative_cache::{NegativeCacheConfig, NegativeCacheStats};
+    use crate::utils::ObservableEvent;
 
     #[test_case(true; "resolved hostname")]
     #[test_case(false; "by IP")]
@@ -361,14 +410,169 @@ mod test {
             info,
             ConnectionInfo {
                 address: Host::Ip(Ipv6Addr::LOCALHOST.into()),
+                port: addr.port().try_into().expect("bound port"),
                 dns_source: crate::DnsSource::Static,
                 route_type: RouteType::Direct,
+                websocket_sub_protocol: None,
             }
         );
 
         make_http_request_response_over(stream).await
     }
 
+    #[tokio::test]
+    async fn connect_with_static_dns_override() {
+        let (addr, server) = localhost_http_server();
+        let _server_handle = tokio::spawn(server);
+
+        // No lookups will succeed, so the override is the only way to find the server.
+        let dns_resolver = DnsResolver::new_from_static_map(HashMap::new());
+        dns_resolver.set_static_entries(HashMap::from([(SERVER_HOSTNAME, vec![addr.ip()])]));
+
+        let connector = DirectConnector::new(dns_resolver);
+        let connection_params = TransportConnectionParams {
+            sni: SERVER_HOSTNAME.into(),
+            tcp_host: Host::Domain(SERVER_HOSTNAME.into()),
+            port: addr.port().try_into().expect("bound port"),
+            certs: RootCertificates::FromDer(Cow::Borrowed(SERVER_CERTIFICATE.cert.der())),
+        };
+
+        let StreamAndInfo(stream, info) = connector
+            .connect(&connection_params, Alpn::Http1_1)
+            .await
+            .expect("can connect");
+        assert_eq!(info.dns_source, crate::DnsSource::Static);
+
+        make_http_request_response_over(stream).await
+    }
+
+    #[tokio::test]
+    async fn connect_with_prewarmed_dns_until_network_change() {
+        let (addr, server) = localhost_http_server();
+        let _server_handle = tokio::spawn(server);
+
+        let network_change_event = ObservableEvent::new();
+        let dns_resolver = DnsResolver::new_from_static_map(HashMap::from([(
+            SERVER_HOSTNAME,
+            LookupResult::localhost(),
+        )]))
+        .clearing_prewarmed_on(&network_change_event);
+        dns_resolver.prewarm(&[SERVER_HOSTNAME]).await;
+
+        let connector = DirectConnector::new(dns_resolver);
+        let connection_params = TransportConnectionParams {
+            sni: SERVER_HOSTNAME.into(),
+            tcp_host: Host::Domain(SERVER_HOSTNAME.into()),
+            port: addr.port().try_into().expect("bound port"),
+            certs: RootCertificates::FromDer(Cow::Borrowed(SERVER_CERTIFICATE.cert.der())),
+        };
+
+        let StreamAndInfo(stream, info) = connector
+            .connect(&connection_params, Alpn::Http1_1)
+            .await
+            .expect("can connect");
+        assert_eq!(info.dns_source, crate::DnsSource::Cache);
+        make_http_request_response_over(stream).await;
+
+        // After a network change, the hostname has to be looked up again.
+        network_change_event.fire();
+        let StreamAndInfo(stream, info) = connector
+            .connect(&connection_params, Alpn::Http1_1)
+            .await
+            .expect("can connect");
+        assert_eq!(info.dns_source, crate::DnsSource::Static);
+        make_http_request_response_over(stream).await
+    }
+
+    #[tokio::test]
+    async fn refused_connections_are_remembered_until_network_change() {
+        // Bind and immediately close a listener to find a port nothing is listening on.
+        let addr = std::net::TcpListener::bind((Ipv4Addr::LOCALHOST, 0))
+            .expect("can bind")
+            .local_addr()
+            .expect("bound");
+        let host = Host::Ip(addr.ip());
+        let port = addr.port().try_into().expect("bound port");
+
+        let network_change_event = ObservableEvent::new();
+        let negative_cache =
+            NegativeCache::new(NegativeCacheConfig::default(), &network_change_event);
+        let dns_resolver = DnsResolver::new_from_static_map(HashMap::new())
+            .with_negative_cache(Some(negative_cache.clone()));
+
+        let connect = || connect_tcp(&dns_resolver, RouteType::Direct, host, port);
+        assert_matches!(
+            connect().await,
+            Err(TransportConnectError::TcpConnectionFailed)
+        );
+        assert!(negative_cache.check_connect(host, port).is_some());
+
+        // The next attempt is skipped without connecting...
+        assert_matches!(
+            connect().await,
+            Err(TransportConnectError::TcpConnectionFailed)
+        );
+        assert_eq!(
+            negative_cache.stats(),
+            NegativeCacheStats { hits: 2, misses: 1 }
+        );
+
+        // ...until the network changes.
+        network_change_event.fire();
+        assert_eq!(negative_cache.check_connect(host, port), None);
+    }
+
+    #[tokio::test]
+    async fn connect_with_supplemental_root() {
+        // A fresh CA, so that no other test can have trusted it already.
+        let ca_key = rcgen::KeyPair::generate().expect("can generate");
+        let ca_cert = {
+            let mut params = rcgen::CertificateParams::new(Vec::<String>::new()).expect("valid");
+            params.is_ca = rcgen::IsCa::Ca(rcgen::BasicConstraints::Unconstrained);
+            params.self_signed(&ca_key).expect("can sign")
+        };
+        let server_key = rcgen::KeyPair::generate().expect("can generate");
+        let server_cert = rcgen::CertificateParams::new([SERVER_HOSTNAME.to_string()])
+            .expect("valid")
+            .signed_by(&server_key, &ca_cert, &ca_key)
+            .expect("can sign");
+
+        let (addr, server) =
+            localhost_http_server_with_identity(server_cert.pem(), server_key.serialize_pem());
+        let _server_handle = tokio::spawn(server);
+
+        let connector = DirectConnector::new(DnsResolver::new_from_static_map(HashMap::from([(
+            SERVER_HOSTNAME,
+            LookupResult::localhost(),
+        )])));
+        let connection_params = TransportConnectionParams {
+            sni: SERVER_HOSTNAME.into(),
+            tcp_host: Host::Domain(SERVER_HOSTNAME.into()),
+            port: addr.port().try_into().expect("bound port"),
+            // Pinned to some other root.
+            certs: RootCertificates::FromDer(Cow::Borrowed(SERVER_CERTIFICATE.cert.der())),
+        };
+
+        match connector.connect(&connection_params, Alpn::Http1_1).await {
+            Ok(_) => {
+                // We can't use expect_err() or assert_matches! because the success case isn't Debug.
+                panic!("should have failed");
+            }
+            Err(e) => {
+                assert_matches!(e, TransportConnectError::UntrustedRoot);
+            }
+        }
+
+        crate::certs::add_supplemental_root_der(ca_cert.der()).expect("valid");
+
+        let StreamAndInfo(stream, _info) = connector
+            .connect(&connection_params, Alpn::Http1_1)
+            .await
+            .expect("can connect");
+
+        make_http_request_response_over(stream).await
+    }
+
     #[tokio::test]
     async fn connect_through_invalid() {
         let (addr, server) = localhost_http_server();
@@ -139,6 +139,8 @@ impl TransportConnector for SocksConnector {
                 route_type: RouteType::SocksProxy,
                 dns_source,
                 address: remote_address.address,
+                port: remote_address.port,
+                websocket_sub_protocol: None,
             },
         ))
     }
@@ -381,9 +383,10 @@ mod test {
             (IpTarget, _) => DnsSource::Static,
         };
 
+        let proxy_port = NonZeroU16::new(proxy.0.local_addr().unwrap().port()).unwrap();
         let connector = SocksConnector {
             proxy_host: Host::Domain(PROXY_HOSTNAME.into()),
-            proxy_port: NonZeroU16::new(proxy.0.local_addr().unwrap().port()).unwrap(),
+            proxy_port,
             protocol: Protocol::Socks5 {
                 username_password: proxy_credentials.clone(),
             },
@@ -454,7 +457,9 @@ mod test {
             ConnectionInfo {
                 route_type: RouteType::SocksProxy,
                 dns_source: expected_dns_source,
-                address: Host::Ip(tls_server.tcp.listen_addr.ip())
+                address: Host::Ip(tls_server.tcp.listen_addr.ip()),
+                port: proxy_port,
+                websocket_sub_protocol: None,
             }
         );
 
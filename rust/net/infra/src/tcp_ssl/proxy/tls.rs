@@ -200,8 +200,10 @@ mod test {
             info,
             ConnectionInfo {
                 address: Host::Ip(Ipv6Addr::LOCALHOST.into()),
+                port: proxy_addr.port().try_into().unwrap(),
                 dns_source: crate::DnsSource::Static,
                 route_type: RouteType::TlsProxy,
+                websocket_sub_protocol: None,
             }
         );
 
@@ -243,8 +245,10 @@ mod test {
             info,
             ConnectionInfo {
                 address: Host::Ip(Ipv6Addr::LOCALHOST.into()),
+                port: proxy_addr.port().try_into().unwrap(),
                 dns_source: crate::DnsSource::Static,
-                route_type: RouteType::TlsProxy
+                route_type: RouteType::TlsProxy,
+                websocket_sub_protocol: None,
             }
         );
 
@@ -19,6 +19,14 @@ pub const DNS_RESOLUTION_DELAY: Duration = Duration::from_millis(50);
 /// Regardless of the caller's behavior, DNS resolver will wait this time interval
 /// for results to arrive to cache them for the future lookups.
 pub const DNS_CALL_BACKGROUND_TIMEOUT: Duration = Duration::from_secs(30);
+/// How long the result of a lookup made ahead of time (see [`crate::dns::DnsResolver::prewarm`])
+/// can be used before it's considered stale.
+pub const DNS_PREWARMED_RESULT_LIFETIME: Duration = Duration::from_secs(5 * 60);
+/// How long a failed DNS lookup is remembered (see [`crate::negative_cache::NegativeCache`]).
+pub const NEGATIVE_CACHE_DNS_FAILURE_TTL: Duration = Duration::from_secs(5);
+/// How long a refused TCP connection is remembered (see
+/// [`crate::negative_cache::NegativeCache`]).
+pub const NEGATIVE_CACHE_CONNECT_REFUSED_TTL: Duration = Duration::from_secs(2);
 
 /// Frequency of the WebSocket `PING` requests
 pub const WS_KEEP_ALIVE_INTERVAL: Duration = Duration::from_secs(15);
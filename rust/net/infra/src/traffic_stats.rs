@@ -0,0 +1,290 @@
+//
+// Copyright 2024 Signal Messenger, LLC.
+// SPDX-License-Identifier: AGPL-3.0-only
+//
+
+//! Accounting for the bytes sent and received by each kind of connection, to show users how much
+//! data they've used.
+//!
+//! A connection is counted by making it with a [`CountingConnector`], whose streams add to the
+//! process-wide [`TrafficStats`] as they are read and written. The counts are taken on top of
+//! TLS, so they include HTTP and WebSocket framing but not TLS records or TCP/IP headers.
+
+use std::io::IoSlice;
+use std::pin::Pin;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::task::{Context, Poll};
+
+use async_trait::async_trait;
+use tokio::io::{AsyncRead, AsyncWrite, ReadBuf};
+
+use crate::errors::TransportConnectError;
+use crate::{live_connections, Alpn, StreamAndInfo, TransportConnectionParams, TransportConnector};
+
+/// What a connection is for.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum TrafficKind {
+    Chat,
+    Cdsi,
+    Svr,
+    Cdn,
+}
+
+impl TrafficKind {
+    pub const ALL: [Self; 4] = [Self::Chat, Self::Cdsi, Self::Svr, Self::Cdn];
+
+    /// The same names used by [`live_connections`].
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Chat => live_connections::CHAT,
+            Self::Cdsi => live_connections::CDSI,
+            Self::Svr => live_connections::SVR,
+            Self::Cdn => live_connections::CDN,
+        }
+    }
+}
+
+/// Bytes sent and received.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub struct TrafficCounts {
+    pub sent: u64,
+    pub received: u64,
+}
+
+/// The [`TrafficCounts`] for every [`TrafficKind`] at one point in time.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub struct TrafficSnapshot([TrafficCounts; TrafficKind::ALL.len()]);
+
+impl TrafficSnapshot {
+    pub fn get(&self, kind: TrafficKind) -> TrafficCounts {
+        self.0[kind as usize]
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (TrafficKind, TrafficCounts)> + '_ {
+        TrafficKind::ALL.into_iter().zip(self.0.iter().copied())
+    }
+}
+
+#[derive(Debug)]
+struct Counters {
+    sent: AtomicU64,
+    received: AtomicU64,
+}
+
+/// Running totals of bytes sent and received, by [`TrafficKind`].
+///
+/// Use [`TrafficStats::global`] for the totals kept by [`CountingConnector::new`].
+#[derive(Debug)]
+pub struct TrafficStats([Counters; TrafficKind::ALL.len()]);
+
+static GLOBAL: TrafficStats = TrafficStats::new();
+
+impl TrafficStats {
+    /// Totals that start at zero, separate from [`Self::global`].
+    ///
+    /// Useful for counting only some connections, such as those made by a single test.
+    pub const fn new() -> Self {
+        // Only used to initialize the array below.
+        #[allow(clippy::declare_interior_mutable_const)]
+        const ZERO: Counters = Counters {
+            sent: AtomicU64::new(0),
+            received: AtomicU64::new(0),
+        };
+        Self([ZERO; TrafficKind::ALL.len()])
+    }
+
+    /// The totals for the whole process.
+    pub fn global() -> &'static Self {
+        &GLOBAL
+    }
+
+    fn counters(&self, kind: TrafficKind) -> &Counters {
+        &self.0[kind as usize]
+    }
+
+    /// The totals since the process started or [`Self::reset`] was last called.
+    ///
+    /// Connections may be in use while this runs, so the counts for different kinds aren't
+    /// necessarily taken at exactly the same moment.
+    pub fn snapshot(&self) -> TrafficSnapshot {
+        TrafficSnapshot(TrafficKind::ALL.map(|kind| {
+            let counters = self.counters(kind);
+            TrafficCounts {
+                sent: counters.sent.load(Ordering::Relaxed),
+                received: counters.received.load(Ordering::Relaxed),
+            }
+        }))
+    }
+
+    /// Starts all the totals over from zero, returning what they were.
+    ///
+    /// Bytes counted while this runs end up in exactly one of the returned snapshot and the new
+    /// totals.
+    pub fn reset(&self) -> TrafficSnapshot {
+        TrafficSnapshot(TrafficKind::ALL.map(|kind| {
+            let counters = self.counters(kind);
+            TrafficCounts {
+                sent: counters.sent.swap(0, Ordering::Relaxed),
+                received: counters.received.swap(0, Ordering::Relaxed),
+            }
+        }))
+    }
+}
+
+impl Default for TrafficStats {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// A [`TransportConnector`] whose streams are counted in [`TrafficStats`].
+#[derive(Clone, Debug)]
+pub struct CountingConnector<T> {
+    inner: T,
+    counters: &'static Counters,
+}
+
+impl<T> CountingConnector<T> {
+    pub fn new(inner: T, kind: TrafficKind) -> Self {
+        Self::with_stats(inner, kind, TrafficStats::global())
+    }
+
+    /// Like [`Self::new`], but counts the traffic in `stats` instead of the global totals.
+    pub fn with_stats(inner: T, kind: TrafficKind, stats: &'static TrafficStats) -> Self {
+        Self {
+            inner,
+            counters: stats.counters(kind),
+        }
+    }
+}
+
+#[async_trait]
+impl<T: TransportConnector> TransportConnector for CountingConnector<T> {
+    type Stream = CountingStream<T::Stream>;
+
+    async fn connect(
+        &self,
+        connection_params: &TransportConnectionParams,
+        alpn: Alpn,
+    ) -> Result<StreamAndInfo<Self::Stream>, TransportConnectError> {
+        let counters = self.counters;
+        self.inner
+            .connect(connection_params, alpn)
+            .await
+            .map(|s| s.map_stream(|inner| CountingStream { inner, counters }))
+    }
+}
+
+/// A stream made by a [`CountingConnector`].
+#[derive(Debug)]
+pub struct CountingStream<S> {
+    inner: S,
+    counters: &'static Counters,
+}
+
+impl<S: AsyncRead + Unpin> AsyncRead for CountingStream<S> {
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<std::io::Result<()>> {
+        let this = self.get_mut();
+        let before = buf.filled().len();
+        let result = Pin::new(&mut this.inner).poll_read(cx, buf);
+        let read = buf.filled().len() - before;
+        if read != 0 {
+            this.counters
+                .received
+                .fetch_add(read as u64, Ordering::Relaxed);
+        }
+        result
+    }
+}
+
+impl<S: AsyncWrite + Unpin> AsyncWrite for CountingStream<S> {
+    fn poll_write(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<Result<usize, std::io::Error>> {
+        let this = self.get_mut();
+        let result = Pin::new(&mut this.inner).poll_write(cx, buf);
+        this.count_written(&result);
+        result
+    }
+
+    fn poll_write_vectored(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        bufs: &[IoSlice<'_>],
+    ) -> Poll<Result<usize, std::io::Error>> {
+        let this = self.get_mut();
+        let result = Pin::new(&mut this.inner).poll_write_vectored(cx, bufs);
+        this.count_written(&result);
+        result
+    }
+
+    fn is_write_vectored(&self) -> bool {
+        self.inner.is_write_vectored()
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), std::io::Error>> {
+        Pin::new(&mut self.get_mut().inner).poll_flush(cx)
+    }
+
+    fn poll_shutdown(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Result<(), std::io::Error>> {
+        Pin::new(&mut self.get_mut().inner).poll_shutdown(cx)
+    }
+}
+
+impl<S> CountingStream<S> {
+    fn count_written(&self, result: &Poll<Result<usize, std::io::Error>>) {
+        if let Poll::Ready(Ok(written @ 1..)) = result {
+            self.counters
+                .sent
+                .fetch_add(*written as u64, Ordering::Relaxed);
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use tokio::io::{AsyncReadExt as _, AsyncWriteExt as _};
+
+    use super::*;
+
+    #[tokio::test]
+    async fn counts_bytes_in_each_direction() {
+        // Separate from the global stats, which other tests may be adding to.
+        static STATS: TrafficStats = TrafficStats::new();
+
+        let (client, mut server) = tokio::io::duplex(1024);
+        let mut stream = CountingStream {
+            inner: client,
+            counters: STATS.counters(TrafficKind::Cdsi),
+        };
+
+        stream.write_all(b"hello").await.unwrap();
+        server.write_all(b"hi there").await.unwrap();
+        let mut received = [0; 8];
+        stream.read_exact(&mut received).await.unwrap();
+
+        let snapshot = STATS.snapshot();
+        assert_eq!(
+            snapshot.get(TrafficKind::Cdsi),
+            TrafficCounts {
+                sent: 5,
+                received: 8
+            }
+        );
+        for kind in [TrafficKind::Chat, TrafficKind::Svr, TrafficKind::Cdn] {
+            assert_eq!(snapshot.get(kind), TrafficCounts::default());
+        }
+
+        assert_eq!(STATS.reset(), snapshot);
+        assert_eq!(STATS.snapshot(), TrafficSnapshot::default());
+    }
+}
@@ -16,20 +16,25 @@ use derive_where::derive_where;
 use futures_util::stream::{SplitSink, SplitStream};
 use futures_util::{SinkExt as _, StreamExt, TryFutureExt};
 use http::uri::PathAndQuery;
+use tokio::io::{AsyncRead, AsyncReadExt as _};
 use tokio::sync::Mutex;
 use tokio::time::Instant;
 use tokio_tungstenite::WebSocketStream;
 use tungstenite::handshake::client::generate_key;
+use tungstenite::protocol::frame::coding::{Data, OpCode};
+use tungstenite::protocol::frame::Frame;
 use tungstenite::protocol::CloseFrame;
 use tungstenite::{http, Message};
 
 use crate::errors::LogSafeDisplay;
 use crate::host::Host;
+use crate::live_connections::LiveConnection;
 use crate::service::{CancellationReason, CancellationToken, ServiceConnector};
 use crate::utils::timeout;
 use crate::ws::error::{HttpFormatError, ProtocolError, SpaceError};
 use crate::{
-    Alpn, AsyncDuplexStream, ConnectionInfo, ConnectionParams, StreamAndInfo, TransportConnector,
+    logging, Alpn, AsyncDuplexStream, ConnectionInfo, ConnectionParams, RouteType, StreamAndInfo,
+    TransportConnector,
 };
 
 pub mod error;
@@ -38,6 +43,9 @@ pub use error::{Error, WebSocketConnectError};
 mod noise;
 pub use noise::WebSocketTransport;
 
+mod rtt;
+pub use rtt::{RttEstimator, RTT_SAMPLE_COUNT};
+
 /// Configuration for a websocket connection.
 #[derive(Debug, Clone)]
 pub struct WebSocketConfig {
@@ -61,6 +69,59 @@ pub struct WebSocketConfig {
     /// How long to allow the connection to be idle before the server is assumed
     /// to have become unavailable.
     pub max_idle_time: Duration,
+    /// The sub-protocols to offer in the [`Sec-WebSocket-Protocol`] header, most preferred first.
+    ///
+    /// If empty, the header isn't sent and the server must not select a sub-protocol.
+    ///
+    /// [`Sec-WebSocket-Protocol`]: http::header::SEC_WEBSOCKET_PROTOCOL
+    pub sub_protocols: &'static [&'static str],
+    /// The [close code] the server uses to announce that it's shutting down gracefully.
+    ///
+    /// A close frame with this code cancels the service with
+    /// [`CancellationReason::ServerDraining`] instead of [`CancellationReason::RemoteClose`], so the
+    /// client knows it can reconnect right away. If `None`, every close is treated the same.
+    ///
+    /// [close code]: tungstenite::protocol::frame::coding::CloseCode
+    pub draining_close_code: Option<u16>,
+}
+
+/// Limits on the size of data received over a websocket.
+///
+/// tungstenite's defaults allow messages of up to 64 MiB, far more than the Signal services ever
+/// send. Tighter limits reduce how much memory a misbehaving server (or proxy) can make a client
+/// allocate. Exceeding either limit fails the connection with
+/// [`SpaceError::Capacity`].
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct WebSocketMessageLimits {
+    /// The largest message to accept, after reassembling fragmented frames.
+    pub max_message_size: usize,
+    /// The largest single frame to accept.
+    pub max_frame_size: usize,
+}
+
+impl WebSocketMessageLimits {
+    pub const DEFAULT: Self = Self {
+        max_message_size: 8 << 20,
+        max_frame_size: 8 << 20,
+    };
+
+    /// Produces a protocol-level configuration that enforces these limits.
+    pub fn ws_config(self) -> tungstenite::protocol::WebSocketConfig {
+        let Self {
+            max_message_size,
+            max_frame_size,
+        } = self;
+        let mut config = tungstenite::protocol::WebSocketConfig::default();
+        config.max_message_size = Some(max_message_size);
+        config.max_frame_size = Some(max_frame_size);
+        config
+    }
+}
+
+impl Default for WebSocketMessageLimits {
+    fn default() -> Self {
+        Self::DEFAULT
+    }
 }
 
 /// [`ServiceConnector`] for services that wrap a websocket connection.
@@ -135,6 +196,12 @@ impl From<tungstenite::Error> for WebSocketServiceError {
     }
 }
 
+impl From<tungstenite::error::SubProtocolError> for WebSocketServiceError {
+    fn from(value: tungstenite::error::SubProtocolError) -> Self {
+        Self::Protocol(tungstenite::error::ProtocolError::SecWebSocketSubProtocolError(value))
+    }
+}
+
 #[async_trait]
 impl<T, E> ServiceConnector for WebSocketClientConnector<T, E>
 where
@@ -146,6 +213,17 @@ where
     type Channel = (WebSocketStream<T::Stream>, ConnectionInfo);
     type ConnectError = WebSocketConnectError;
 
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(
+            name = "ws.connect_channel",
+            skip_all,
+            fields(
+                route = %connection_params.route_type,
+                host = %connection_params.transport.sni,
+            ),
+        )
+    )]
     async fn connect_channel(
         &self,
         connection_params: &ConnectionParams,
@@ -154,6 +232,7 @@ where
             connection_params,
             self.cfg.endpoint.clone(),
             self.cfg.ws_config,
+            self.cfg.sub_protocols,
             &self.transport_connector,
         );
         timeout(
@@ -171,6 +250,7 @@ where
             channel.1,
             self.cfg.keep_alive_interval,
             self.cfg.max_idle_time,
+            self.cfg.draining_close_code,
         )
     }
 }
@@ -180,6 +260,7 @@ fn start_ws_service<S: AsyncDuplexStream, E>(
     connection_info: ConnectionInfo,
     keep_alive_interval: Duration,
     max_idle_time: Duration,
+    draining_close_code: Option<u16>,
 ) -> (WebSocketClient<S, E>, CancellationToken) {
     let service_cancellation = CancellationToken::new();
     let (ws_sink, ws_stream) = channel.split();
@@ -192,10 +273,12 @@ fn start_ws_service<S: AsyncDuplexStream, E>(
         ws_stream,
         keep_alive_interval,
         max_idle_time,
+        draining_close_code,
         ws_writer: ws_client_writer.clone(),
         service_cancellation: service_cancellation.clone(),
         last_frame_received: Instant::now(),
         last_keepalive_sent: Instant::now(),
+        rtt_estimator: RttEstimator::default(),
     };
     (
         WebSocketClient {
@@ -231,8 +314,50 @@ where
         })
         .await
     }
+
+    /// Sends a binary message of exactly `len` bytes read from `reader`.
+    ///
+    /// The message is split into frames of at most [`MAX_STREAMED_FRAME_LEN`] bytes, and only one
+    /// frame is held in memory at a time. Nothing else can be sent until the whole message has
+    /// been written. If `reader` fails or ends early, the message can't be completed, so the
+    /// connection is closed.
+    pub async fn send_binary_stream(
+        &self,
+        reader: impl AsyncRead + Unpin,
+        len: usize,
+    ) -> Result<(), E> {
+        run_and_update_status(&self.service_cancellation, || {
+            async {
+                let mut guard = self.ws_sink.lock().await;
+                let mut reader = reader;
+                let mut remaining = len;
+                let mut opcode = OpCode::Data(Data::Binary);
+                loop {
+                    let mut frame = vec![0; remaining.min(MAX_STREAMED_FRAME_LEN)];
+                    reader
+                        .read_exact(&mut frame)
+                        .await
+                        .map_err(WebSocketServiceError::Io)?;
+                    remaining -= frame.len();
+                    let is_final = remaining == 0;
+                    guard
+                        .send(Message::Frame(Frame::message(frame, opcode, is_final)))
+                        .await?;
+                    if is_final {
+                        return Ok(());
+                    }
+                    opcode = OpCode::Data(Data::Continue);
+                }
+            }
+            .map_err(|e: WebSocketServiceError| e.into())
+        })
+        .await
+    }
 }
 
+/// The largest frame payload sent by [`WebSocketClientWriter::send_binary_stream`].
+pub const MAX_STREAMED_FRAME_LEN: usize = 64 * 1024;
+
 #[derive(Debug)]
 pub struct WebSocketClientReader<S, E> {
     ws_stream: SplitStream<WebSocketStream<S>>,
@@ -240,8 +365,17 @@ pub struct WebSocketClientReader<S, E> {
     service_cancellation: CancellationToken,
     keep_alive_interval: Duration,
     max_idle_time: Duration,
+    draining_close_code: Option<u16>,
     last_frame_received: Instant,
     last_keepalive_sent: Instant,
+    rtt_estimator: RttEstimator,
+}
+
+impl<S, E> WebSocketClientReader<S, E> {
+    /// Round-trip times measured from the keepalive pings sent on this connection.
+    pub fn rtt_estimator(&self) -> &RttEstimator {
+        &self.rtt_estimator
+    }
 }
 
 impl<S: AsyncDuplexStream, E> WebSocketClientReader<S, E>
@@ -267,28 +401,29 @@ where
                     _ = self.service_cancellation.cancelled() => Event::StopService,
                 } {
                     Event::SendKeepAlive => {
-                        self.ws_writer.send(Message::Ping(vec![])).await?;
+                        let payload = self.rtt_estimator.start_ping(Instant::now());
+                        self.ws_writer.send(Message::Ping(payload)).await?;
                         self.last_keepalive_sent = Instant::now();
                         continue;
                     }
                     Event::Message(maybe_message) => maybe_message,
                     Event::StopService => {
-                        log::info!("service was stopped");
+                        logging::info!("service was stopped");
                         return Err(WebSocketServiceError::ChannelClosed.into());
                     }
                     Event::IdleTimeout => {
-                        log::warn!("channel was idle for {}s", self.max_idle_time.as_secs());
+                        logging::warn!("channel was idle for {}s", self.max_idle_time.as_secs());
                         return Err(WebSocketServiceError::ChannelIdleTooLong.into());
                     }
                 };
                 // now checking if whatever we've read from the stream is a message
                 let message = match maybe_message {
                     None | Some(Err(tungstenite::Error::ConnectionClosed)) => {
-                        log::warn!("websocket connection was unexpectedly closed");
+                        logging::warn!("websocket connection was unexpectedly closed");
                         return Ok(NextOrClose::Close(None));
                     }
                     Some(Err(e)) => {
-                        log::trace!("websocket error: {e}");
+                        logging::trace!("websocket error: {e}");
                         return Err(WebSocketServiceError::from(e).into());
                     }
                     Some(Ok(message)) => message,
@@ -298,10 +433,22 @@ where
                 match message {
                     Message::Text(t) => return Ok(NextOrClose::Next(t.into())),
                     Message::Binary(b) => return Ok(NextOrClose::Next(b.into())),
-                    Message::Ping(_) | Message::Pong(_) => continue,
+                    Message::Ping(_) => continue,
+                    Message::Pong(payload) => {
+                        self.rtt_estimator.pong_received(&payload, Instant::now());
+                        continue;
+                    }
                     Message::Close(close_frame) => {
-                        self.service_cancellation
-                            .cancel(CancellationReason::RemoteClose);
+                        let is_draining = self.draining_close_code.is_some_and(|draining| {
+                            close_frame
+                                .as_ref()
+                                .is_some_and(|frame| u16::from(frame.code) == draining)
+                        });
+                        self.service_cancellation.cancel(if is_draining {
+                            CancellationReason::ServerDraining
+                        } else {
+                            CancellationReason::RemoteClose
+                        });
                         return Ok(NextOrClose::Close(close_frame));
                     }
                     Message::Frame(_) => unreachable!("only for sending"),
@@ -335,11 +482,17 @@ async fn connect_websocket<T: TransportConnector>(
     connection_params: &ConnectionParams,
     endpoint: PathAndQuery,
     ws_config: tungstenite::protocol::WebSocketConfig,
+    sub_protocols: &'static [&'static str],
     transport_connector: &T,
 ) -> Result<(WebSocketStream<T::Stream>, ConnectionInfo), WebSocketConnectError> {
-    let StreamAndInfo(ssl_stream, remote_address) = transport_connector
+    let StreamAndInfo(ssl_stream, mut remote_address) = transport_connector
         .connect(&connection_params.transport, Alpn::Http1_1)
         .await?;
+    if remote_address.route_type == RouteType::Direct {
+        // The transport only knows it connected directly to some host; the connection params know
+        // whether that host is fronting for the real one.
+        remote_address.route_type = connection_params.route_type;
+    }
 
     // we need to explicitly create upgrade request
     // because request decorators require a request `Builder`
@@ -362,12 +515,21 @@ async fn connect_websocket<T: TransportConnector>(
                 .build()
                 .unwrap(),
         );
+    let request_builder = if sub_protocols.is_empty() {
+        request_builder
+    } else {
+        request_builder.header(
+            http::header::SEC_WEBSOCKET_PROTOCOL,
+            http::HeaderValue::from_str(&sub_protocols.join(", "))
+                .expect("valid `Sec-WebSocket-Protocol` header value"),
+        )
+    };
 
     let request_builder = connection_params
         .http_request_decorator
         .decorate_request(request_builder);
 
-    let (ws_stream, _response) = tokio_tungstenite::client_async_with_config(
+    let (ws_stream, response) = tokio_tungstenite::client_async_with_config(
         request_builder.body(()).expect("can get request body"),
         ssl_stream,
         Some(ws_config),
@@ -380,9 +542,44 @@ async fn connect_websocket<T: TransportConnector>(
         handle_ws_error(connection_params, e, Instant::now())
     })?;
 
+    remote_address.websocket_sub_protocol = selected_sub_protocol(&response, sub_protocols)?;
+
     Ok((ws_stream, remote_address))
 }
 
+/// Checks the server's choice of sub-protocol against the ones that were offered.
+///
+/// tungstenite performs the same check during the handshake; doing it again here means the
+/// negotiated protocol can be reported as one of the `'static` values from the config.
+fn selected_sub_protocol<B>(
+    response: &http::Response<B>,
+    offered: &'static [&'static str],
+) -> Result<Option<&'static str>, WebSocketConnectError> {
+    use tungstenite::error::SubProtocolError;
+
+    let Some(selected) = response.headers().get(http::header::SEC_WEBSOCKET_PROTOCOL) else {
+        if offered.is_empty() {
+            return Ok(None);
+        }
+        return Err(WebSocketConnectError::SubProtocol(
+            SubProtocolError::NoSubProtocol,
+        ));
+    };
+    if offered.is_empty() {
+        return Err(WebSocketConnectError::SubProtocol(
+            SubProtocolError::ServerSentSubProtocolNoneRequested,
+        ));
+    }
+    offered
+        .iter()
+        .copied()
+        .find(|offered| selected.as_bytes() == offered.as_bytes())
+        .map(Some)
+        .ok_or(WebSocketConnectError::SubProtocol(
+            SubProtocolError::InvalidSubProtocol,
+        ))
+}
+
 fn handle_ws_error(
     connection_params: &ConnectionParams,
     error: tungstenite::Error,
@@ -404,6 +601,9 @@ fn handle_ws_error(
                 received_at,
             }
         }
+        tungstenite::Error::Protocol(
+            tungstenite::error::ProtocolError::SecWebSocketSubProtocolError(e),
+        ) => WebSocketConnectError::SubProtocol(e),
         e => WebSocketConnectError::WebSocketError(e),
     }
 }
@@ -455,7 +655,6 @@ where
         self.ws_client_writer.send(item).await
     }
 
-    #[cfg(any(test, feature = "test-util"))]
     pub(crate) async fn close(self, close: Option<CloseFrame<'static>>) -> Result<(), E> {
         self.ws_client_writer.send(Message::Close(close)).await
     }
@@ -502,6 +701,19 @@ pub type DefaultStream = tokio_boring_signal::SslStream<tokio::net::TcpStream>;
 pub struct AttestedConnection<S> {
     websocket: WebSocketClient<S, WebSocketServiceError>,
     client_connection: ClientConnection,
+    attestation_info: AttestationInfo,
+    live: LiveConnection,
+}
+
+/// What the host presented to prove it's running in an enclave.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct AttestationInfo {
+    /// The attestation message exactly as the host sent it, containing its evidence and
+    /// endorsements.
+    ///
+    /// Only kept if requested when connecting; see
+    /// [`AttestedConnection::connect_capturing_evidence`].
+    pub raw_evidence: Option<Vec<u8>>,
 }
 
 impl<S> AttestedConnection<S> {
@@ -512,6 +724,10 @@ impl<S> AttestedConnection<S> {
     pub fn handshake_hash(&self) -> &[u8] {
         &self.client_connection.handshake_hash
     }
+
+    pub fn attestation_info(&self) -> &AttestationInfo {
+        &self.attestation_info
+    }
 }
 
 impl<S> AsMut<AttestedConnection<S>> for AttestedConnection<S> {
@@ -564,18 +780,55 @@ where
     S: AsyncDuplexStream,
 {
     /// Connect to remote host and verify remote attestation.
+    ///
+    /// `kind` is how the connection is counted by [`live_connections`](crate::live_connections).
     pub async fn connect(
+        websocket: WebSocketClient<S, WebSocketServiceError>,
+        kind: &'static str,
+        new_handshake: impl FnOnce(&[u8]) -> enclave::Result<enclave::Handshake>,
+    ) -> Result<Self, AttestedConnectionError> {
+        Self::connect_capturing_evidence(websocket, kind, new_handshake, false).await
+    }
+
+    /// Like [`Self::connect`], but if `capture_evidence` is set, also keeps a copy of the
+    /// attestation message in [`AttestationInfo::raw_evidence`].
+    ///
+    /// The handshake itself is the same either way.
+    pub async fn connect_capturing_evidence(
         mut websocket: WebSocketClient<S, WebSocketServiceError>,
+        kind: &'static str,
         new_handshake: impl FnOnce(&[u8]) -> enclave::Result<enclave::Handshake>,
+        capture_evidence: bool,
     ) -> Result<Self, AttestedConnectionError> {
-        let client_connection = authenticate(&mut websocket, new_handshake).await?;
+        let mut live = LiveConnection::new(kind);
+        let (client_connection, attestation_info) =
+            match authenticate(&mut websocket, new_handshake, capture_evidence).await {
+                Ok(authenticated) => authenticated,
+                Err(e) => {
+                    // The connection is being given up on, not abandoned.
+                    live.mark_closed();
+                    return Err(e);
+                }
+            };
 
         Ok(Self {
             websocket,
             client_connection,
+            attestation_info,
+            live,
         })
     }
 
+    /// Closes the connection from the client side.
+    ///
+    /// The server may have gone away already, so failing to send the close frame isn't an error.
+    pub async fn close(mut self) {
+        self.live.mark_closed();
+        if let Err(e) = self.websocket.close(None).await {
+            logging::debug!("failed to close {} connection: {e}", self.live.kind());
+        }
+    }
+
     pub async fn send(
         &mut self,
         request: impl prost::Message,
@@ -610,7 +863,10 @@ where
     pub async fn receive_bytes(&mut self) -> Result<NextOrClose<Vec<u8>>, AttestedConnectionError> {
         let received = self.websocket.receive().await?;
         let received = match received {
-            NextOrClose::Close(frame) => return Ok(NextOrClose::Close(frame)),
+            NextOrClose::Close(frame) => {
+                self.live.mark_closed();
+                return Ok(NextOrClose::Close(frame));
+            }
             NextOrClose::Next(t) => t.try_into_binary()?,
         };
         self.client_connection
@@ -632,12 +888,16 @@ impl TextOrBinary {
 async fn authenticate<S: AsyncDuplexStream>(
     websocket: &mut WebSocketClient<S, WebSocketServiceError>,
     new_handshake: impl FnOnce(&[u8]) -> enclave::Result<enclave::Handshake>,
-) -> Result<ClientConnection, AttestedConnectionError> {
+    capture_evidence: bool,
+) -> Result<(ClientConnection, AttestationInfo), AttestedConnectionError> {
     let attestation_msg = websocket
         .receive()
         .await?
         .next_or(WebSocketServiceError::ChannelClosed)?
         .try_into_binary()?;
+    let attestation_info = AttestationInfo {
+        raw_evidence: capture_evidence.then(|| attestation_msg.clone()),
+    };
     let handshake = new_handshake(attestation_msg.as_ref())?;
 
     websocket
@@ -650,7 +910,7 @@ async fn authenticate<S: AsyncDuplexStream>(
         .next_or(WebSocketServiceError::ChannelClosed)?
         .try_into_binary()?;
 
-    Ok(handshake.complete(&initial_response)?)
+    Ok((handshake.complete(&initial_response)?, attestation_info))
 }
 
 /// Test utilities related to websockets.
@@ -680,6 +940,8 @@ pub mod testutil {
             route_type: RouteType::Test,
             dns_source: DnsSource::Test,
             address: Host::Domain("localhost".into()),
+            port: nonzero_ext::nonzero!(443u16),
+            websocket_sub_protocol: None,
         }
     }
 
@@ -691,6 +953,7 @@ pub mod testutil {
             mock_connection_info(),
             WS_KEEP_ALIVE_INTERVAL,
             WS_MAX_IDLE_INTERVAL,
+            None,
         )
         .0
     }
@@ -703,6 +966,7 @@ pub mod testutil {
                 connection_info,
                 VERY_LARGE_TIMEOUT,
                 VERY_LARGE_TIMEOUT,
+                None,
             );
             client
         }
@@ -713,17 +977,17 @@ pub mod testutil {
 
     /// Response to an incoming frame.
     ///
-    /// Zero or one frames to reply with followed by an optional close.
+    /// Zero or more frames to reply with followed by an optional close.
     #[derive(Default)]
     pub struct AttestedServerOutput {
-        pub message: Option<Vec<u8>>,
+        pub messages: Vec<Vec<u8>>,
         pub close_after: Option<Option<CloseFrame<'static>>>,
     }
 
     impl AttestedServerOutput {
         pub fn message(contents: Vec<u8>) -> Self {
             Self {
-                message: Some(contents),
+                messages: vec![contents],
                 ..Default::default()
             }
         }
@@ -811,10 +1075,10 @@ pub mod testutil {
 
             let AttestedServerOutput {
                 close_after,
-                message,
+                messages,
             } = on_message(received);
 
-            if let Some(payload) = message {
+            for payload in messages {
                 let mut outgoing = vec![0; payload.len() + 16 /* snow tag len */];
                 let written = server_transport
                     .write_message(&payload, &mut outgoing)
@@ -836,12 +1100,16 @@ mod test {
     use assert_matches::assert_matches;
     use futures_util::{pin_mut, poll};
     use nonzero_ext::nonzero;
-    use test_case::test_matrix;
+    use test_case::{test_case, test_matrix};
+    use tungstenite::error::SubProtocolError;
+    use warp::{Filter as _, Reply as _};
 
     use super::testutil::*;
     use super::*;
     use crate::certs::RootCertificates;
-    use crate::{HttpRequestDecoratorSeq, RouteType, TransportConnectionParams};
+    use crate::connection_manager::{ErrorClass, ErrorClassifier as _};
+    use crate::testutil::InMemoryWarpConnector;
+    use crate::{make_ws_config, HttpRequestDecoratorSeq, RouteType, TransportConnectionParams};
 
     const MESSAGE_TEXT: &str = "text";
 
@@ -944,17 +1212,51 @@ mod test {
             attest::sgx_session::testutil::private_key(),
         ));
 
-        let mut connection =
-            AttestedConnection::connect(websocket_test_client(client), |fake_attestation| {
+        let mut connection = AttestedConnection::connect(
+            websocket_test_client(client),
+            "test",
+            |fake_attestation| {
                 assert_eq!(fake_attestation, FAKE_ATTESTATION);
                 attest::sgx_session::testutil::handshake_from_tests_data()
-            })
-            .await
-            .unwrap();
+            },
+        )
+        .await
+        .unwrap();
 
         connection.send(Vec::from(ECHO_BYTES)).await.unwrap();
         let response: Vec<u8> = connection.receive().await.unwrap().unwrap_next();
         assert_eq!(&response, ECHO_BYTES);
+        assert_eq!(connection.attestation_info().raw_evidence, None);
+    }
+
+    #[tokio::test]
+    async fn attested_connection_captures_evidence() {
+        let (server, client) = fake_websocket().await;
+        tokio::task::spawn(run_attested_echo_server(
+            server,
+            attest::sgx_session::testutil::private_key(),
+        ));
+
+        let mut connection = AttestedConnection::connect_capturing_evidence(
+            websocket_test_client(client),
+            "test",
+            |fake_attestation| {
+                assert_eq!(fake_attestation, FAKE_ATTESTATION);
+                attest::sgx_session::testutil::handshake_from_tests_data()
+            },
+            true,
+        )
+        .await
+        .unwrap();
+        assert_eq!(
+            connection.attestation_info().raw_evidence.as_deref(),
+            Some(FAKE_ATTESTATION)
+        );
+
+        // The connection works the same as ever.
+        connection.send(Vec::from(ECHO_BYTES)).await.unwrap();
+        let response: Vec<u8> = connection.receive().await.unwrap().unwrap_next();
+        assert_eq!(&response, ECHO_BYTES);
     }
 
     #[tokio::test]
@@ -973,7 +1275,8 @@ mod test {
         }
 
         assert_matches!(
-            AttestedConnection::connect(websocket_test_client(client), fail_to_handshake).await,
+            AttestedConnection::connect(websocket_test_client(client), "test", fail_to_handshake)
+                .await,
             Err(_)
         );
     }
@@ -987,13 +1290,16 @@ mod test {
             attest::sgx_session::testutil::private_key(),
         ));
 
-        let mut connection =
-            AttestedConnection::connect(websocket_test_client(client), |fake_attestation| {
+        let mut connection = AttestedConnection::connect(
+            websocket_test_client(client),
+            "test",
+            |fake_attestation| {
                 assert_eq!(fake_attestation, FAKE_ATTESTATION);
                 attest::sgx_session::testutil::handshake_from_tests_data()
-            })
-            .await
-            .unwrap();
+            },
+        )
+        .await
+        .unwrap();
 
         connection.send(Vec::from(ECHO_BYTES)).await.unwrap();
         // Decoding a vec as a 32-bit float shouldn't work.
@@ -1003,6 +1309,61 @@ mod test {
         );
     }
 
+    #[cfg(debug_assertions)]
+    #[tokio::test]
+    async fn attested_connection_counted_until_closed() {
+        use crate::live_connections::{abandoned_count, live_connection_counts};
+
+        // Unique to this test, since the counts are shared by every test in the process.
+        const KIND: &str = "test-attested-closed";
+
+        let (server, client) = fake_websocket().await;
+        tokio::task::spawn(run_attested_echo_server(
+            server,
+            attest::sgx_session::testutil::private_key(),
+        ));
+
+        let mut connection =
+            AttestedConnection::connect(websocket_test_client(client), KIND, |_| {
+                attest::sgx_session::testutil::handshake_from_tests_data()
+            })
+            .await
+            .unwrap();
+        assert_eq!(live_connection_counts().get(KIND), Some(&1));
+
+        connection.send(Vec::from(ECHO_BYTES)).await.unwrap();
+        let _: Vec<u8> = connection.receive().await.unwrap().unwrap_next();
+        connection.close().await;
+
+        assert_eq!(live_connection_counts().get(KIND), None);
+        assert_eq!(abandoned_count(KIND), 0);
+    }
+
+    #[cfg(debug_assertions)]
+    #[tokio::test]
+    async fn attested_connection_abandoned() {
+        use crate::live_connections::{abandoned_count, live_connection_counts};
+
+        const KIND: &str = "test-attested-abandoned";
+
+        let (server, client) = fake_websocket().await;
+        tokio::task::spawn(run_attested_echo_server(
+            server,
+            attest::sgx_session::testutil::private_key(),
+        ));
+
+        let connection = AttestedConnection::connect(websocket_test_client(client), KIND, |_| {
+            attest::sgx_session::testutil::handshake_from_tests_data()
+        })
+        .await
+        .unwrap();
+        assert_eq!(live_connection_counts().get(KIND), Some(&1));
+
+        drop(connection);
+        assert_eq!(live_connection_counts().get(KIND), None);
+        assert_eq!(abandoned_count(KIND), 1);
+    }
+
     fn example_connection_params(hostname: &str) -> ConnectionParams {
         let hostname = hostname.into();
         ConnectionParams {
@@ -1075,4 +1436,87 @@ mod test {
             );
         }
     }
+
+    #[test_case(&["test.v2", "test.v1"], Some("test.v1") => matches Ok(Some("test.v1")); "accepted")]
+    #[test_case(&[], None => matches Ok(None); "none offered")]
+    #[test_case(&["test.v1"], None => matches Err(SubProtocolError::NoSubProtocol); "none selected")]
+    #[test_case(&["test.v1"], Some("test.v3") => matches Err(SubProtocolError::InvalidSubProtocol); "unknown selected")]
+    #[test_case(&[], Some("test.v1") => matches Err(SubProtocolError::ServerSentSubProtocolNoneRequested); "unrequested")]
+    #[tokio::test]
+    async fn sub_protocol_negotiation(
+        offered: &'static [&'static str],
+        selected: Option<&'static str>,
+    ) -> Result<Option<&'static str>, SubProtocolError> {
+        let filter = warp::ws().map(move |ws: warp::ws::Ws| {
+            let reply = ws.on_upgrade(|_websocket| std::future::ready(()));
+            match selected {
+                Some(selected) => {
+                    warp::reply::with_header(reply, "sec-websocket-protocol", selected)
+                        .into_response()
+                }
+                None => reply.into_response(),
+            }
+        });
+        let connector = WebSocketClientConnector::<_, WebSocketServiceError>::new(
+            InMemoryWarpConnector::new(filter),
+            WebSocketConfig {
+                sub_protocols: offered,
+                ..make_ws_config(PathAndQuery::from_static("/test"), Duration::from_secs(1))
+            },
+        );
+
+        match connector
+            .connect_channel(&example_connection_params("example.signal.org"))
+            .await
+        {
+            Ok((_stream, connection_info)) => Ok(connection_info.websocket_sub_protocol),
+            Err(e) => {
+                assert_matches!(e.classify(), ErrorClass::Fatal);
+                let WebSocketConnectError::SubProtocol(e) = e else {
+                    panic!("unexpected error: {e:?}");
+                };
+                Err(e)
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn message_over_size_limit_fails_connection() {
+        const LIMITS: WebSocketMessageLimits = WebSocketMessageLimits {
+            max_message_size: 1024,
+            max_frame_size: 1024,
+        };
+
+        let filter = warp::ws().map(|ws: warp::ws::Ws| {
+            ws.on_upgrade(|mut websocket| async move {
+                websocket
+                    .send(warp::ws::Message::binary(vec![
+                        0;
+                        LIMITS.max_message_size + 1
+                    ]))
+                    .await
+                    .expect("can send");
+                // Keep the connection open until the client gives up on it.
+                while let Some(Ok(_)) = websocket.next().await {}
+            })
+        });
+        let connector = WebSocketClientConnector::<_, WebSocketServiceError>::new(
+            InMemoryWarpConnector::new(filter),
+            WebSocketConfig {
+                ws_config: LIMITS.ws_config(),
+                ..make_ws_config(PathAndQuery::from_static("/test"), Duration::from_secs(1))
+            },
+        );
+
+        let channel = connector
+            .connect_channel(&example_connection_params("example.signal.org"))
+            .await
+            .expect("can connect");
+        let (mut client, _cancellation) = connector.start_service(channel);
+
+        assert_matches!(
+            client.next().await,
+            Err(WebSocketServiceError::Capacity(SpaceError::Capacity(_)))
+        );
+    }
 }
@@ -30,6 +30,10 @@ pub enum WebSocketConnectError {
         response: http::Response<Option<Vec<u8>>>,
         received_at: Instant,
     },
+    /// The server's choice of sub-protocol wasn't one of the ones offered.
+    ///
+    /// See [`WebSocketConfig::sub_protocols`](crate::ws::WebSocketConfig::sub_protocols).
+    SubProtocol(tungstenite::error::SubProtocolError),
 }
 
 impl std::fmt::Display for WebSocketConnectError {
@@ -50,6 +54,8 @@ impl std::fmt::Display for WebSocketConnectError {
                     response.status()
                 )
             }
+            // tungstenite's messages for these are fixed strings.
+            WebSocketConnectError::SubProtocol(e) => write!(f, "websocket sub-protocol: {e}"),
         }
     }
 }
@@ -58,13 +64,17 @@ impl LogSafeDisplay for WebSocketConnectError {}
 
 impl ErrorClassifier for WebSocketConnectError {
     fn classify(&self) -> ErrorClass {
-        let WebSocketConnectError::RejectedByServer {
-            response,
-            received_at,
-        } = self
-        else {
+        let (response, received_at) = match self {
+            WebSocketConnectError::RejectedByServer {
+                response,
+                received_at,
+            } => (response, received_at),
+            // The server doesn't speak any protocol we do; asking again won't change that.
+            WebSocketConnectError::SubProtocol(_) => return ErrorClass::Fatal,
             // If we didn't make it to the server, we should retry.
-            return ErrorClass::Intermittent;
+            WebSocketConnectError::Transport(_)
+            | WebSocketConnectError::Timeout
+            | WebSocketConnectError::WebSocketError(_) => return ErrorClass::Intermittent,
         };
 
         // Retry-After takes precedence over everything else.
@@ -0,0 +1,119 @@
+//
+// Copyright 2024 Signal Messenger, LLC.
+// SPDX-License-Identifier: AGPL-3.0-only
+//
+
+//! Passive round-trip time estimates from keepalive pings.
+//!
+//! Each keepalive [`Ping`] carries a sequence number, and the [`Pong`] that echoes it back gives
+//! one sample. Pongs that don't echo the ping that's currently outstanding (because the server
+//! sent them unsolicited, or because a newer ping has been sent since) are ignored.
+//!
+//! [`Ping`]: tungstenite::Message::Ping
+//! [`Pong`]: tungstenite::Message::Pong
+
+use std::collections::VecDeque;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use tokio::time::Instant;
+
+/// The number of most recent samples averaged by [`RttEstimator::estimate`].
+pub const RTT_SAMPLE_COUNT: usize = 5;
+
+/// Round-trip times measured on a single websocket connection.
+///
+/// Clones share the same measurements.
+#[derive(Clone, Debug, Default)]
+pub struct RttEstimator(Arc<Mutex<RttState>>);
+
+#[derive(Debug, Default)]
+struct RttState {
+    next_ping_id: u64,
+    outstanding_ping: Option<(u64, Instant)>,
+    samples: VecDeque<Duration>,
+}
+
+impl RttEstimator {
+    /// The mean of the last [`RTT_SAMPLE_COUNT`] round-trip times, or `None` if no ping has been
+    /// answered yet.
+    pub fn estimate(&self) -> Option<Duration> {
+        let state = self.0.lock().expect("not poisoned");
+        let count = u32::try_from(state.samples.len()).expect("bounded by RTT_SAMPLE_COUNT");
+        if count == 0 {
+            return None;
+        }
+        Some(state.samples.iter().sum::<Duration>() / count)
+    }
+
+    /// Records that a ping is being sent at `now`, and returns the payload to send with it.
+    ///
+    /// Any ping that's still outstanding will no longer be matched.
+    pub(crate) fn start_ping(&self, now: Instant) -> Vec<u8> {
+        let mut state = self.0.lock().expect("not poisoned");
+        let id = state.next_ping_id;
+        state.next_ping_id = id.wrapping_add(1);
+        state.outstanding_ping = Some((id, now));
+        id.to_be_bytes().to_vec()
+    }
+
+    /// Records that a pong with `payload` was received at `now`.
+    pub(crate) fn pong_received(&self, payload: &[u8], now: Instant) {
+        let mut state = self.0.lock().expect("not poisoned");
+        let Some((id, sent_at)) = state.outstanding_ping else {
+            return;
+        };
+        if payload != id.to_be_bytes() {
+            return;
+        }
+        state.outstanding_ping = None;
+        if state.samples.len() == RTT_SAMPLE_COUNT {
+            state.samples.pop_front();
+        }
+        state
+            .samples
+            .push_back(now.saturating_duration_since(sent_at));
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[tokio::test(start_paused = true)]
+    async fn averages_recent_samples() {
+        let rtt = RttEstimator::default();
+        assert_eq!(rtt.estimate(), None);
+
+        let payload = rtt.start_ping(Instant::now());
+        rtt.pong_received(&payload, Instant::now() + Duration::from_millis(600));
+        assert_eq!(rtt.estimate(), Some(Duration::from_millis(600)));
+
+        for _ in 0..RTT_SAMPLE_COUNT {
+            let payload = rtt.start_ping(Instant::now());
+            rtt.pong_received(&payload, Instant::now() + Duration::from_millis(100));
+        }
+        assert_eq!(rtt.estimate(), Some(Duration::from_millis(100)));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn ignores_unmatched_pongs() {
+        let rtt = RttEstimator::default();
+
+        // Unsolicited, with nothing outstanding.
+        rtt.pong_received(&[], Instant::now());
+        rtt.pong_received(&0u64.to_be_bytes(), Instant::now());
+        assert_eq!(rtt.estimate(), None);
+
+        // Superseded by a later ping.
+        let first = rtt.start_ping(Instant::now());
+        let second = rtt.start_ping(Instant::now());
+        rtt.pong_received(&first, Instant::now() + Duration::from_secs(1));
+        assert_eq!(rtt.estimate(), None);
+
+        // Answered twice.
+        rtt.pong_received(&second, Instant::now() + Duration::from_millis(200));
+        rtt.pong_received(&second, Instant::now() + Duration::from_secs(1));
+        assert_eq!(rtt.estimate(), Some(Duration::from_millis(200)));
+    }
+}
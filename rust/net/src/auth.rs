@@ -53,3 +53,43 @@ impl HttpBasicAuth for Auth {
         &self.password
     }
 }
+
+/// Supplies credentials for the authenticated chat connection.
+///
+/// [`current`](Self::current) is consulted every time a connection is made, so credentials can
+/// change between connections without recreating the chat service. If the server rejects them,
+/// [`refresh`](Self::refresh) gets one chance to produce new ones before the connection attempt
+/// fails.
+///
+/// Both methods are called from the task making the connection, so they should return promptly.
+/// The chat service never calls them concurrently, even when connecting on several routes at once.
+pub trait AuthProvider: Send + Sync {
+    /// The credentials to use for the next connection attempt.
+    fn current(&self) -> Auth;
+
+    /// Called when the server rejects the credentials from [`current`](Self::current).
+    ///
+    /// Returns the credentials to retry with, or `None` if there's nothing else to try.
+    fn refresh(&self) -> Option<Auth>;
+}
+
+/// Fixed credentials, which can't be refreshed.
+impl AuthProvider for Auth {
+    fn current(&self) -> Auth {
+        self.clone()
+    }
+
+    fn refresh(&self) -> Option<Auth> {
+        None
+    }
+}
+
+impl<T: AuthProvider + ?Sized> AuthProvider for Box<T> {
+    fn current(&self) -> Auth {
+        (**self).current()
+    }
+
+    fn refresh(&self) -> Option<Auth> {
+        (**self).refresh()
+    }
+}
@@ -0,0 +1,740 @@
+//
+// Copyright 2024 Signal Messenger, LLC.
+// SPDX-License-Identifier: AGPL-3.0-only
+//
+
+//! Downloading attachments from the CDNs.
+//!
+//! Attachments are fetched with plain HTTP/2 GETs. A download that fails partway through is
+//! picked up again with a ranged request starting from the last byte written to the sink, so
+//! nothing is fetched twice and the digest can be computed incrementally as the data arrives.
+
+use std::collections::HashMap;
+use std::num::NonZeroU32;
+use std::time::Duration;
+
+use bytes::Bytes;
+use futures_util::StreamExt as _;
+use http::uri::PathAndQuery;
+use http::{HeaderMap, HeaderValue, Method, StatusCode};
+use libsignal_net_infra::http_client::{http2_client, AggregatingHttp2Client, HttpError};
+use libsignal_net_infra::live_connections::{self, LiveConnection};
+use libsignal_net_infra::{extract_retry_after_seconds, ConnectionParams, TransportConnector};
+use sha2::{Digest as _, Sha256};
+use thiserror::Error;
+use tokio::io::{AsyncWrite, AsyncWriteExt as _};
+
+/// Responses are only ever read as streams, so there's no need to buffer any of them.
+const MAX_AGGREGATE_RESPONSE_SIZE: usize = 0;
+
+#[derive(Clone, Debug)]
+pub struct DownloadConfig {
+    /// How many times to try before giving up.
+    ///
+    /// An attempt that makes progress before failing isn't counted.
+    pub max_attempts: NonZeroU32,
+    /// How long to wait before the first retry; this doubles for each retry after that.
+    pub retry_delay: Duration,
+    /// The longest to wait before a retry, including when the server asks for longer.
+    pub max_retry_delay: Duration,
+    pub connect_timeout: Duration,
+}
+
+impl Default for DownloadConfig {
+    fn default() -> Self {
+        Self {
+            max_attempts: NonZeroU32::new(5).expect("nonzero"),
+            retry_delay: Duration::from_secs(1),
+            max_retry_delay: Duration::from_secs(60),
+            connect_timeout: Duration::from_secs(10),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct DownloadRequest<'a> {
+    pub cdn_number: u32,
+    pub cdn_key: &'a str,
+    /// How much of the attachment the sink already has, from an earlier download.
+    pub resume_offset: u64,
+    /// The SHA-256 digest of the whole attachment, if it should be checked.
+    ///
+    /// This can only be checked when downloading from the start.
+    pub expected_digest: Option<[u8; 32]>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct DownloadProgress {
+    /// The number of bytes the sink has, including any from before the resume offset.
+    pub downloaded: u64,
+    /// The size of the attachment, once the server has said what it is.
+    pub total: Option<u64>,
+}
+
+#[derive(Debug, Error, displaydoc::Display)]
+pub enum DownloadError {
+    /// no connection parameters for CDN {0}
+    UnknownCdn(u32),
+    /// invalid argument: {0}
+    InvalidArgument(&'static str),
+    /// failed to connect: {0}
+    Connection(String),
+    /// retry later
+    RateLimited { retry_after_seconds: Option<u32> },
+    /// server error: {0}
+    ServerError(u16),
+    /// attachment not found
+    NotFound,
+    /// unexpected response status: {0}
+    UnexpectedStatus(u16),
+    /// invalid response: {0}
+    InvalidResponse(&'static str),
+    /// attachment does not match the expected digest
+    DigestMismatch,
+    /// failed to write the attachment: {0}
+    Sink(std::io::Error),
+}
+
+/// Downloads attachments from the CDNs, resuming after transient failures.
+pub struct AttachmentDownloader<T> {
+    cdns: HashMap<u32, ConnectionParams>,
+    transport_connector: T,
+    config: DownloadConfig,
+}
+
+impl<T: TransportConnector> AttachmentDownloader<T> {
+    pub fn new(
+        cdns: impl IntoIterator<Item = (u32, ConnectionParams)>,
+        transport_connector: T,
+        config: DownloadConfig,
+    ) -> Self {
+        Self {
+            cdns: cdns.into_iter().collect(),
+            transport_connector,
+            config,
+        }
+    }
+
+    /// Downloads an attachment into `sink`, starting from `request.resume_offset`.
+    ///
+    /// `on_progress` is called each time more of the attachment has been written. Returns the
+    /// size of the attachment, once all of it has been written and the sink flushed.
+    pub async fn download(
+        &self,
+        request: DownloadRequest<'_>,
+        mut sink: impl AsyncWrite + Unpin,
+        mut on_progress: impl FnMut(DownloadProgress),
+    ) -> Result<u64, DownloadError> {
+        let DownloadRequest {
+            cdn_number,
+            cdn_key,
+            resume_offset,
+            expected_digest,
+        } = request;
+        let connection_params = self
+            .cdns
+            .get(&cdn_number)
+            .ok_or(DownloadError::UnknownCdn(cdn_number))?;
+        let path = attachment_path(cdn_key)?;
+        if expected_digest.is_some() && resume_offset != 0 {
+            return Err(DownloadError::InvalidArgument(
+                "the digest can only be checked when downloading from the start",
+            ));
+        }
+
+        let mut state = DownloadState {
+            progress: DownloadProgress {
+                downloaded: resume_offset,
+                total: None,
+            },
+            hasher: expected_digest.map(|_| Sha256::new()),
+        };
+        let mut attempts_left = self.config.max_attempts.get();
+        let mut retry_delay = self.config.retry_delay;
+
+        loop {
+            let offset_before = state.progress.downloaded;
+            let result = self
+                .attempt(
+                    connection_params,
+                    &path,
+                    &mut state,
+                    &mut sink,
+                    &mut on_progress,
+                )
+                .await;
+            let (error, retry_after) = match result {
+                Ok(()) => break,
+                Err(AttemptError::Fatal(error)) => return Err(error),
+                Err(AttemptError::Transient { error, retry_after }) => (error, retry_after),
+            };
+
+            if state.progress.downloaded > offset_before {
+                attempts_left = self.config.max_attempts.get();
+                retry_delay = self.config.retry_delay;
+            }
+            attempts_left -= 1;
+            if attempts_left == 0 {
+                return Err(error);
+            }
+
+            let delay = retry_after
+                .unwrap_or(retry_delay)
+                .min(self.config.max_retry_delay);
+            log::info!(
+                "attachment download failed at offset {} ({error}); retrying in {delay:?}",
+                state.progress.downloaded
+            );
+            tokio::time::sleep(delay).await;
+            retry_delay = (retry_delay * 2).min(self.config.max_retry_delay);
+        }
+
+        if let (Some(expected), Some(hasher)) = (expected_digest, state.hasher) {
+            if hasher.finalize().as_slice() != expected {
+                return Err(DownloadError::DigestMismatch);
+            }
+        }
+        sink.flush().await.map_err(DownloadError::Sink)?;
+        Ok(state.progress.downloaded)
+    }
+
+    /// Makes one request, writing whatever arrives to `sink`.
+    ///
+    /// On failure, `state` says how far the download got.
+    async fn attempt(
+        &self,
+        connection_params: &ConnectionParams,
+        path: &PathAndQuery,
+        state: &mut DownloadState,
+        sink: &mut (impl AsyncWrite + Unpin),
+        on_progress: &mut impl FnMut(DownloadProgress),
+    ) -> Result<(), AttemptError> {
+        let client = tokio::time::timeout(
+            self.config.connect_timeout,
+            http2_client(
+                &self.transport_connector,
+                connection_params.clone(),
+                MAX_AGGREGATE_RESPONSE_SIZE,
+            ),
+        )
+        .await
+        .map_err(|_| AttemptError::transient(DownloadError::Connection("timed out".to_owned())))?
+        .map_err(AttemptError::from_http)?;
+
+        // The connection is closed when `client` is dropped at the end of the attempt, however it
+        // ends; it's only abandoned if the download is cancelled partway through.
+        let mut live = LiveConnection::new(live_connections::CDN);
+        let result = fetch(&client, path, state, sink, on_progress).await;
+        live.mark_closed();
+        result
+    }
+}
+
+/// Requests the attachment at `path` on an open connection, writing whatever arrives to `sink`.
+///
+/// On failure, `state` says how far the download got.
+async fn fetch(
+    client: &AggregatingHttp2Client,
+    path: &PathAndQuery,
+    state: &mut DownloadState,
+    sink: &mut (impl AsyncWrite + Unpin),
+    on_progress: &mut impl FnMut(DownloadProgress),
+) -> Result<(), AttemptError> {
+    let offset = state.progress.downloaded;
+    let mut headers = HeaderMap::new();
+    if offset != 0 {
+        headers.insert(
+            http::header::RANGE,
+            HeaderValue::from_str(&format!("bytes={offset}-")).expect("valid header value"),
+        );
+    }
+    let (parts, mut body) = client
+        .send_request_streaming_response(path.clone(), Method::GET, headers, Bytes::new())
+        .await
+        .map_err(AttemptError::from_http)?;
+
+    // The number of bytes at the start of the body that the sink already has.
+    let mut to_skip = 0;
+    match parts.status {
+        StatusCode::OK => {
+            to_skip = offset;
+            state.progress.total = content_length(&parts.headers);
+        }
+        StatusCode::PARTIAL_CONTENT => {
+            let (start, total) = content_range(&parts.headers).ok_or(AttemptError::Fatal(
+                DownloadError::InvalidResponse("missing or invalid Content-Range"),
+            ))?;
+            if start != offset {
+                return Err(AttemptError::Fatal(DownloadError::InvalidResponse(
+                    "Content-Range does not start at the requested offset",
+                )));
+            }
+            state.progress.total = total;
+        }
+        StatusCode::RANGE_NOT_SATISFIABLE
+            if offset != 0 && unsatisfied_range_size(&parts.headers) == Some(offset) =>
+        {
+            // The sink already has all of it.
+            state.progress.total = Some(offset);
+            return Ok(());
+        }
+        StatusCode::NOT_FOUND => return Err(AttemptError::Fatal(DownloadError::NotFound)),
+        StatusCode::TOO_MANY_REQUESTS => {
+            let retry_after_seconds = extract_retry_after_seconds(&parts.headers);
+            return Err(AttemptError::Transient {
+                error: DownloadError::RateLimited {
+                    retry_after_seconds,
+                },
+                retry_after: retry_after_seconds.map(|s| Duration::from_secs(s.into())),
+            });
+        }
+        status if status.is_server_error() => {
+            let retry_after_seconds = (status == StatusCode::SERVICE_UNAVAILABLE)
+                .then(|| extract_retry_after_seconds(&parts.headers))
+                .flatten();
+            return Err(AttemptError::Transient {
+                error: DownloadError::ServerError(status.as_u16()),
+                retry_after: retry_after_seconds.map(|s| Duration::from_secs(s.into())),
+            });
+        }
+        status => {
+            return Err(AttemptError::Fatal(DownloadError::UnexpectedStatus(
+                status.as_u16(),
+            )))
+        }
+    }
+
+    while let Some(chunk) = body.next().await {
+        let chunk = chunk.map_err(AttemptError::from_http)?;
+        let skipped = usize::try_from(to_skip)
+            .unwrap_or(usize::MAX)
+            .min(chunk.len());
+        to_skip -= skipped as u64;
+        let chunk = &chunk[skipped..];
+        if chunk.is_empty() {
+            continue;
+        }
+
+        sink.write_all(chunk)
+            .await
+            .map_err(|e| AttemptError::Fatal(DownloadError::Sink(e)))?;
+        if let Some(hasher) = &mut state.hasher {
+            hasher.update(chunk);
+        }
+        state.progress.downloaded += chunk.len() as u64;
+        on_progress(state.progress);
+    }
+
+    if to_skip != 0 {
+        return Err(AttemptError::Fatal(DownloadError::InvalidResponse(
+            "attachment is shorter than the resume offset",
+        )));
+    }
+    match state.progress.total {
+        Some(total) if state.progress.downloaded < total => Err(AttemptError::transient(
+            DownloadError::Connection("response ended early".to_owned()),
+        )),
+        Some(total) if state.progress.downloaded > total => Err(AttemptError::Fatal(
+            DownloadError::InvalidResponse("attachment is longer than advertised"),
+        )),
+        _ => Ok(()),
+    }
+}
+
+struct DownloadState {
+    progress: DownloadProgress,
+    /// Hashes everything written to the sink, if the digest is being checked.
+    hasher: Option<Sha256>,
+}
+
+enum AttemptError {
+    Transient {
+        error: DownloadError,
+        retry_after: Option<Duration>,
+    },
+    Fatal(DownloadError),
+}
+
+impl AttemptError {
+    fn transient(error: DownloadError) -> Self {
+        Self::Transient {
+            error,
+            retry_after: None,
+        }
+    }
+
+    fn from_http(error: HttpError) -> Self {
+        Self::transient(DownloadError::Connection(error.to_string()))
+    }
+}
+
+fn attachment_path(cdn_key: &str) -> Result<PathAndQuery, DownloadError> {
+    if cdn_key.is_empty() || cdn_key.contains(['/', '?', '#']) {
+        return Err(DownloadError::InvalidArgument("invalid CDN key"));
+    }
+    PathAndQuery::try_from(format!("/attachments/{cdn_key}"))
+        .map_err(|_| DownloadError::InvalidArgument("invalid CDN key"))
+}
+
+fn content_length(headers: &HeaderMap) -> Option<u64> {
+    headers
+        .get(http::header::CONTENT_LENGTH)?
+        .to_str()
+        .ok()?
+        .parse()
+        .ok()
+}
+
+/// Parses `Content-Range: bytes <start>-<end>/<size>` into the start and, if known, the size.
+fn content_range(headers: &HeaderMap) -> Option<(u64, Option<u64>)> {
+    let value = headers.get(http::header::CONTENT_RANGE)?.to_str().ok()?;
+    let (range, size) = value.strip_prefix("bytes ")?.split_once('/')?;
+    let (start, _end) = range.split_once('-')?;
+    let size = match size {
+        "*" => None,
+        size => Some(size.parse().ok()?),
+    };
+    Some((start.parse().ok()?, size))
+}
+
+/// Parses `Content-Range: bytes */<size>`, as sent with a 416 response.
+fn unsatisfied_range_size(headers: &HeaderMap) -> Option<u64> {
+    headers
+        .get(http::header::CONTENT_RANGE)?
+        .to_str()
+        .ok()?
+        .strip_prefix("bytes */")?
+        .parse()
+        .ok()
+}
+
+#[cfg(test)]
+mod test {
+    use std::sync::{Arc, Mutex};
+
+    use assert_matches::assert_matches;
+    use libsignal_net_infra::certs::RootCertificates;
+    use libsignal_net_infra::host::Host;
+    use libsignal_net_infra::testutil::InMemoryWarpConnector;
+    use libsignal_net_infra::{HttpRequestDecoratorSeq, RouteType, TransportConnectionParams};
+    use nonzero_ext::nonzero;
+    use test_case::test_case;
+    use warp::Filter;
+
+    use super::*;
+
+    const CDN_NUMBER: u32 = 3;
+    const CDN_KEY: &str = "abcdef";
+    const ATTACHMENT_LEN: usize = 10_000;
+    const DROP_AFTER: usize = 4_000;
+
+    fn attachment() -> Vec<u8> {
+        (0..ATTACHMENT_LEN).map(|i| (i % 251) as u8).collect()
+    }
+
+    fn digest(data: &[u8]) -> [u8; 32] {
+        Sha256::digest(data).into()
+    }
+
+    fn connection_params() -> ConnectionParams {
+        let hostname = "cdn3.test".into();
+        ConnectionParams {
+            route_type: RouteType::Test,
+            transport: TransportConnectionParams {
+                sni: Arc::clone(&hostname),
+                tcp_host: Host::Domain(Arc::clone(&hostname)),
+                port: nonzero!(443u16),
+                certs: RootCertificates::Native,
+            },
+            http_host: hostname,
+            http_request_decorator: HttpRequestDecoratorSeq::default(),
+            connection_confirmation_header: None,
+        }
+    }
+
+    fn downloader<F>(server: F) -> AttachmentDownloader<InMemoryWarpConnector<F>>
+    where
+        F: warp::Filter + Clone + Send + Sync + 'static,
+        F::Extract: warp::Reply,
+    {
+        AttachmentDownloader::new(
+            [(CDN_NUMBER, connection_params())],
+            InMemoryWarpConnector::new(server),
+            DownloadConfig::default(),
+        )
+    }
+
+    fn request(expected_digest: Option<[u8; 32]>) -> DownloadRequest<'static> {
+        DownloadRequest {
+            cdn_number: CDN_NUMBER,
+            cdn_key: CDN_KEY,
+            resume_offset: 0,
+            expected_digest,
+        }
+    }
+
+    type Response = warp::http::Response<warp::hyper::Body>;
+
+    fn response(status: StatusCode) -> warp::http::response::Builder {
+        warp::http::Response::builder().status(status.as_u16())
+    }
+
+    /// Serves `/attachments/CDN_KEY`, recording the `Range` header of each request.
+    fn server(
+        ranges: Arc<Mutex<Vec<Option<String>>>>,
+        respond: impl Fn(usize, Option<&str>) -> Response + Clone + Send + Sync + 'static,
+    ) -> impl Filter<Extract = (Response,)> + Clone + Send + Sync + 'static {
+        warp::get()
+            .and(warp::path!("attachments" / String))
+            .and(warp::header::optional::<String>("range"))
+            .map(move |key: String, range: Option<String>| {
+                assert_eq!(key, CDN_KEY);
+                let mut ranges = ranges.lock().expect("not poisoned");
+                ranges.push(range.clone());
+                respond(ranges.len(), range.as_deref())
+            })
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn resumes_after_connection_drops_mid_body() {
+        let data = attachment();
+        let ranges = Arc::new(Mutex::new(vec![]));
+        let server = server(Arc::clone(&ranges), {
+            let data = data.clone();
+            move |request_number, range| match request_number {
+                1 => {
+                    // Send the first part of the body, then fail the stream.
+                    let chunks: Vec<Result<Bytes, std::io::Error>> = vec![
+                        Ok(Bytes::copy_from_slice(&data[..DROP_AFTER])),
+                        Err(std::io::ErrorKind::ConnectionReset.into()),
+                    ];
+                    response(StatusCode::OK)
+                        .header("content-length", data.len())
+                        .body(warp::hyper::Body::wrap_stream(futures_util::stream::iter(
+                            chunks,
+                        )))
+                        .expect("valid")
+                }
+                _ => {
+                    let start: usize = range
+                        .and_then(|r| r.strip_prefix("bytes="))
+                        .and_then(|r| r.strip_suffix('-'))
+                        .and_then(|r| r.parse().ok())
+                        .expect("ranged request");
+                    response(StatusCode::PARTIAL_CONTENT)
+                        .header(
+                            "content-range",
+                            format!("bytes {start}-{}/{}", data.len() - 1, data.len()),
+                        )
+                        .body(data[start..].to_vec().into())
+                        .expect("valid")
+                }
+            }
+        });
+
+        let mut sink: Vec<u8> = vec![];
+        let mut progress = vec![];
+        let size = downloader(server)
+            .download(request(Some(digest(&data))), &mut sink, |p| {
+                progress.push(p)
+            })
+            .await
+            .expect("downloaded");
+
+        assert_eq!(size, ATTACHMENT_LEN as u64);
+        assert_eq!(sink, data);
+        assert_eq!(
+            *ranges.lock().expect("not poisoned"),
+            [None, Some(format!("bytes={DROP_AFTER}-"))]
+        );
+        assert!(
+            progress
+                .windows(2)
+                .all(|w| w[0].downloaded < w[1].downloaded),
+            "{progress:?}"
+        );
+        assert_eq!(
+            progress.last(),
+            Some(&DownloadProgress {
+                downloaded: ATTACHMENT_LEN as u64,
+                total: Some(ATTACHMENT_LEN as u64),
+            })
+        );
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn resume_offset_skips_what_the_sink_has() {
+        let data = attachment();
+        let ranges = Arc::new(Mutex::new(vec![]));
+        // A server that ignores ranges and always sends the whole thing.
+        let server = server(Arc::clone(&ranges), {
+            let data = data.clone();
+            move |_, _| {
+                response(StatusCode::OK)
+                    .body(data.clone().into())
+                    .expect("valid")
+            }
+        });
+
+        let mut sink = data[..DROP_AFTER].to_vec();
+        let size = downloader(server)
+            .download(
+                DownloadRequest {
+                    resume_offset: DROP_AFTER as u64,
+                    ..request(None)
+                },
+                &mut sink,
+                |_| {},
+            )
+            .await
+            .expect("downloaded");
+
+        assert_eq!(size, ATTACHMENT_LEN as u64);
+        assert_eq!(sink, data);
+        assert_eq!(
+            *ranges.lock().expect("not poisoned"),
+            [Some(format!("bytes={DROP_AFTER}-"))]
+        );
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn honors_retry_after() {
+        let data = attachment();
+        let ranges = Arc::new(Mutex::new(vec![]));
+        let server = server(Arc::clone(&ranges), {
+            let data = data.clone();
+            move |request_number, _| match request_number {
+                1 => response(StatusCode::TOO_MANY_REQUESTS)
+                    .header("retry-after", "30")
+                    .body(Default::default())
+                    .expect("valid"),
+                _ => response(StatusCode::OK)
+                    .body(data.clone().into())
+                    .expect("valid"),
+            }
+        });
+
+        let start = tokio::time::Instant::now();
+        let mut sink: Vec<u8> = vec![];
+        downloader(server)
+            .download(request(Some(digest(&data))), &mut sink, |_| {})
+            .await
+            .expect("downloaded");
+
+        assert!(start.elapsed() >= Duration::from_secs(30));
+        assert_eq!(sink, data);
+        assert_eq!(ranges.lock().expect("not poisoned").len(), 2);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn gives_up_when_rate_limited_too_often() {
+        let server = server(Default::default(), |_, _| {
+            response(StatusCode::TOO_MANY_REQUESTS)
+                .header("retry-after", "5")
+                .body(Default::default())
+                .expect("valid")
+        });
+
+        let result = downloader(server)
+            .download(request(None), &mut tokio::io::sink(), |_| {})
+            .await;
+        assert_matches!(
+            result,
+            Err(DownloadError::RateLimited {
+                retry_after_seconds: Some(5)
+            })
+        );
+    }
+
+    #[test_case(StatusCode::NOT_FOUND => matches DownloadError::NotFound)]
+    #[test_case(StatusCode::FORBIDDEN => matches DownloadError::UnexpectedStatus(403))]
+    #[tokio::test(start_paused = true)]
+    async fn fails_without_retrying(status: StatusCode) -> DownloadError {
+        let ranges = Arc::new(Mutex::new(vec![]));
+        let server = server(Arc::clone(&ranges), move |_, _| {
+            response(status).body(Default::default()).expect("valid")
+        });
+
+        let error = downloader(server)
+            .download(request(None), &mut tokio::io::sink(), |_| {})
+            .await
+            .expect_err("failed");
+        assert_eq!(ranges.lock().expect("not poisoned").len(), 1);
+        error
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn detects_digest_mismatch() {
+        let data = attachment();
+        let server = server(Default::default(), {
+            let data = data.clone();
+            move |_, _| {
+                response(StatusCode::OK)
+                    .body(data.clone().into())
+                    .expect("valid")
+            }
+        });
+
+        let result = downloader(server)
+            .download(request(Some([0; 32])), &mut tokio::io::sink(), |_| {})
+            .await;
+        assert_matches!(result, Err(DownloadError::DigestMismatch));
+    }
+
+    #[tokio::test]
+    async fn rejects_bad_requests() {
+        let downloader = downloader(warp::any().map(warp::reply));
+
+        assert_matches!(
+            downloader
+                .download(
+                    DownloadRequest {
+                        cdn_number: 1,
+                        ..request(None)
+                    },
+                    &mut tokio::io::sink(),
+                    |_| {}
+                )
+                .await,
+            Err(DownloadError::UnknownCdn(1))
+        );
+        assert_matches!(
+            downloader
+                .download(
+                    DownloadRequest {
+                        cdn_key: "../profiles",
+                        ..request(None)
+                    },
+                    &mut tokio::io::sink(),
+                    |_| {}
+                )
+                .await,
+            Err(DownloadError::InvalidArgument(_))
+        );
+        assert_matches!(
+            downloader
+                .download(
+                    DownloadRequest {
+                        resume_offset: 10,
+                        ..request(Some([0; 32]))
+                    },
+                    &mut tokio::io::sink(),
+                    |_| {}
+                )
+                .await,
+            Err(DownloadError::InvalidArgument(_))
+        );
+    }
+
+    #[test_case("bytes 0-99/100" => Some((0, Some(100))))]
+    #[test_case("bytes 40-99/*" => Some((40, None)))]
+    #[test_case("bytes */100" => None)]
+    #[test_case("items 0-99/100" => None)]
+    fn parses_content_range(value: &str) -> Option<(u64, Option<u64>)> {
+        let headers = HeaderMap::from_iter([(
+            http::header::CONTENT_RANGE,
+            HeaderValue::from_str(value).expect("valid"),
+        )]);
+        content_range(&headers)
+    }
+}
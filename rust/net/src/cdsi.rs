@@ -3,22 +3,25 @@
 // SPDX-License-Identifier: AGPL-3.0-only
 //
 
+use std::collections::HashMap;
 use std::default::Default;
+use std::future::Future;
 
 use http::StatusCode;
 use libsignal_core::{Aci, Pni, E164};
 use libsignal_net_infra::connection_manager::ConnectionManager;
 use libsignal_net_infra::errors::TransportConnectError;
 use libsignal_net_infra::ws::{
-    AttestedConnection, AttestedConnectionError, NextOrClose, WebSocketConnectError,
-    WebSocketServiceError,
+    AttestationInfo, AttestedConnection, AttestedConnectionError, NextOrClose,
+    WebSocketConnectError, WebSocketServiceError,
 };
 use libsignal_net_infra::{
-    extract_retry_after_seconds, AsyncDuplexStream, HttpBasicAuth, TransportConnector,
+    extract_retry_after_seconds, logging, AsyncDuplexStream, HttpBasicAuth, TransportConnector,
 };
 use prost::Message as _;
 use thiserror::Error;
 use tokio::net::TcpStream;
+use tokio::time::Instant;
 use tokio_boring_signal::SslStream;
 use tungstenite::protocol::frame::coding::CloseCode;
 use tungstenite::protocol::CloseFrame;
@@ -27,6 +30,9 @@ use uuid::Uuid;
 use crate::enclave::{Cdsi, EnclaveEndpointConnection};
 use crate::proto::cds2::{ClientRequest, ClientResponse};
 
+mod delta;
+pub use delta::MappingDelta;
+
 trait FixedLengthSerializable {
     const SERIALIZED_LEN: usize;
 
@@ -81,6 +87,20 @@ impl FixedLengthSerializable for AciAndAccessKey {
     }
 }
 
+// Best-effort model of how the server charges permits for a lookup, used by
+// [`LookupRequest::permit_cost_estimate`]. These follow the server's documented behavior at the
+// time of writing, but the server is free to change its rate limiting without notice.
+
+/// Permits charged for each E164 that wasn't part of a previous lookup.
+const PERMIT_COST_PER_NEW_E164: u32 = 1;
+/// Permits charged for each previously-looked-up E164 when there's no token.
+///
+/// With a token, the server already knows about these and doesn't charge for them again; without
+/// one, they're indistinguishable from new E164s.
+const PERMIT_COST_PER_PREV_E164_WITHOUT_TOKEN: u32 = 1;
+/// Permits charged for each ACI and access key pair, independently of the E164s.
+const PERMIT_COST_PER_ACI_AND_ACCESS_KEY: u32 = 1;
+
 #[derive(Default)]
 pub struct LookupRequest {
     pub new_e164s: Vec<E164>,
@@ -91,6 +111,151 @@ pub struct LookupRequest {
 }
 
 impl LookupRequest {
+    /// Estimates the number of bytes this request will take up when sent to the server.
+    ///
+    /// Only the variable-length fields are counted; the protobuf framing adds a handful of bytes
+    /// on top of this.
+    pub fn estimated_size(&self) -> usize {
+        let Self {
+            new_e164s,
+            prev_e164s,
+            acis_and_access_keys,
+            return_acis_without_uaks: _,
+            token,
+        } = self;
+
+        (new_e164s.len() + prev_e164s.len()) * E164::SERIALIZED_LEN
+            + acis_and_access_keys.len() * AciAndAccessKey::SERIALIZED_LEN
+            + token.len()
+    }
+
+    /// Estimates the number of rate-limit permits the server will charge for this request.
+    ///
+    /// This is only a best-effort estimate, suitable for warning the user before a large lookup;
+    /// the server doesn't promise to keep charging the same way. A non-empty token is assumed to
+    /// be valid.
+    pub fn permit_cost_estimate(&self) -> u32 {
+        let Self {
+            new_e164s,
+            prev_e164s,
+            acis_and_access_keys,
+            return_acis_without_uaks: _,
+            token,
+        } = self;
+
+        let cost = |count: usize, permits_each: u32| {
+            u32::try_from(count)
+                .unwrap_or(u32::MAX)
+                .saturating_mul(permits_each)
+        };
+        let prev_e164s_cost = if token.is_empty() {
+            cost(prev_e164s.len(), PERMIT_COST_PER_PREV_E164_WITHOUT_TOKEN)
+        } else {
+            0
+        };
+
+        cost(new_e164s.len(), PERMIT_COST_PER_NEW_E164)
+            .saturating_add(prev_e164s_cost)
+            .saturating_add(cost(
+                acis_and_access_keys.len(),
+                PERMIT_COST_PER_ACI_AND_ACCESS_KEY,
+            ))
+    }
+
+    /// Splits this request into requests whose [estimated size](Self::estimated_size) is at most
+    /// `max_request_bytes`.
+    ///
+    /// All of `prev_e164s` stays in the first request, followed by as many of the ACI and access
+    /// key pairs and then `new_e164s` as will fit; the rest are spread across the following
+    /// requests. Every request is given a copy of this request's token, which stands in for the
+    /// token that will be returned by the previous request when using [`lookup_chunked`].
+    ///
+    /// A token only discounts the numbers in `prev_e164s`, so each request after the first lists
+    /// every number sent before it there, and those count towards its size. A request is never
+    /// left empty, so a single entry (or the `prev_e164s` list) that is larger than
+    /// `max_request_bytes` ends up in a request of its own that is over the limit. In particular,
+    /// once the numbers already sent fill `max_request_bytes` by themselves, every following
+    /// request carries a single new entry.
+    pub fn chunked(self, max_request_bytes: usize) -> Vec<LookupRequest> {
+        let Self {
+            new_e164s,
+            prev_e164s,
+            acis_and_access_keys,
+            return_acis_without_uaks,
+            token,
+        } = self;
+
+        let next_chunk = |previous: &Self| Self {
+            prev_e164s: previous
+                .prev_e164s
+                .iter()
+                .chain(&previous.new_e164s)
+                .copied()
+                .collect(),
+            return_acis_without_uaks,
+            token: token.clone(),
+            ..Default::default()
+        };
+
+        /// Returns the last chunk if `added_len` more bytes fit, or a new chunk otherwise.
+        fn chunk_with_room(
+            chunks: &mut Vec<LookupRequest>,
+            added_len: usize,
+            max_request_bytes: usize,
+            next_chunk: impl FnOnce(&LookupRequest) -> LookupRequest,
+        ) -> &mut LookupRequest {
+            let is_first = chunks.len() == 1;
+            let last = chunks.last().expect("always at least one chunk");
+            // Only the first chunk's prev_e164s are entries of its own; later chunks just repeat
+            // the numbers sent before them.
+            let last_has_entries = !(last.new_e164s.is_empty()
+                && (!is_first || last.prev_e164s.is_empty())
+                && last.acis_and_access_keys.is_empty());
+            if last_has_entries && last.estimated_size() + added_len > max_request_bytes {
+                let next = next_chunk(last);
+                chunks.push(next);
+            }
+            chunks.last_mut().expect("always at least one chunk")
+        }
+
+        let mut chunks = vec![Self {
+            prev_e164s,
+            return_acis_without_uaks,
+            token: token.clone(),
+            ..Default::default()
+        }];
+        for pair in acis_and_access_keys {
+            chunk_with_room(
+                &mut chunks,
+                AciAndAccessKey::SERIALIZED_LEN,
+                max_request_bytes,
+                next_chunk,
+            )
+            .acis_and_access_keys
+            .push(pair);
+        }
+        for e164 in new_e164s {
+            chunk_with_room(
+                &mut chunks,
+                E164::SERIALIZED_LEN,
+                max_request_bytes,
+                next_chunk,
+            )
+            .new_e164s
+            .push(e164);
+        }
+        chunks
+    }
+
+    /// Serializes the request exactly as [`CdsiConnection::send_request`] would send it.
+    ///
+    /// This allows building the request ahead of time, then sending it with
+    /// [`CdsiConnection::send_prebuilt_request`] once connected. The length of the result is also
+    /// the exact size of the request, unlike [`Self::estimated_size`].
+    pub fn into_client_request_bytes(self) -> Vec<u8> {
+        self.into_client_request().encode_to_vec()
+    }
+
     fn into_client_request(self) -> ClientRequest {
         let Self {
             new_e164s,
@@ -128,6 +293,66 @@ pub struct LookupResponse {
     pub debug_permits_used: i32,
 }
 
+/// Everything in a [`LookupResponse`] except its records.
+///
+/// Returned by [`ClientResponseCollector::collect_streaming`], which hands over the records as
+/// they arrive.
+#[derive(Debug)]
+#[cfg_attr(test, derive(PartialEq))]
+pub struct LookupResponseSummary {
+    pub debug_permits_used: i32,
+}
+
+impl LookupResponse {
+    /// Indexes the records by E164, for comparing against later lookups with
+    /// [`MappingDelta::compute`].
+    ///
+    /// If an E164 appears more than once, the last record for it is kept.
+    pub fn into_map(self) -> HashMap<E164, LookupResponseEntry> {
+        self.records
+            .into_iter()
+            .map(|entry| (entry.e164, entry))
+            .collect()
+    }
+}
+
+/// Keeps a running total of the permits the server reports using for lookups.
+///
+/// The server's count is only meant for debugging, and the total only covers the lookups that
+/// were [recorded](Self::record). Apps that want to track usage across launches can persist
+/// [`Self::total_permits_used`] and restore it with [`Self::with_total`].
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub struct PermitTracker {
+    total_permits_used: u64,
+    last_permits_used: Option<u32>,
+}
+
+impl PermitTracker {
+    pub fn with_total(total_permits_used: u64) -> Self {
+        Self {
+            total_permits_used,
+            last_permits_used: None,
+        }
+    }
+
+    /// Adds the permits used by `response` to the total.
+    pub fn record(&mut self, response: &LookupResponse) {
+        // The server should never report a negative count; treat one as nothing used.
+        let used = u32::try_from(response.debug_permits_used).unwrap_or(0);
+        self.last_permits_used = Some(used);
+        self.total_permits_used = self.total_permits_used.saturating_add(used.into());
+    }
+
+    /// The permits used by the most recently recorded lookup, if any.
+    pub fn last_permits_used(&self) -> Option<u32> {
+        self.last_permits_used
+    }
+
+    pub fn total_permits_used(&self) -> u64 {
+        self.total_permits_used
+    }
+}
+
 #[derive(Clone, Debug)]
 #[cfg_attr(test, derive(PartialEq))]
 pub struct LookupResponseEntry {
@@ -159,20 +384,9 @@ impl TryFrom<ClientResponse> for LookupResponse {
             debug_permits_used,
         } = response;
 
-        if e164_pni_aci_triples.len() % LookupResponseEntry::SERIALIZED_LEN != 0 {
-            return Err(LookupResponseParseError::InvalidNumberOfBytes {
-                actual_length: e164_pni_aci_triples.len(),
-            });
-        }
-
-        let records = e164_pni_aci_triples
-            .chunks(LookupResponseEntry::SERIALIZED_LEN)
-            .flat_map(|record| {
-                LookupResponseEntry::try_parse_from(
-                    record.try_into().expect("chunk size is correct"),
-                )
-            })
-            .collect();
+        let mut triples = TripleDecoder::default();
+        let records = triples.push(&e164_pni_aci_triples);
+        triples.finish()?;
 
         Ok(Self {
             records,
@@ -181,6 +395,57 @@ impl TryFrom<ClientResponse> for LookupResponse {
     }
 }
 
+/// Decodes `e164_pni_aci_triples` that arrive in pieces of arbitrary size.
+///
+/// Triples that are split between pieces are held until the rest of their bytes arrive.
+#[derive(Debug, Default)]
+struct TripleDecoder {
+    partial: Vec<u8>,
+    total_len: usize,
+}
+
+impl TripleDecoder {
+    /// Returns the records completed by `bytes`.
+    ///
+    /// As with a complete response, records without a valid E164 are skipped.
+    fn push(&mut self, mut bytes: &[u8]) -> Vec<LookupResponseEntry> {
+        const LEN: usize = LookupResponseEntry::SERIALIZED_LEN;
+        self.total_len += bytes.len();
+
+        let mut records = Vec::with_capacity((self.partial.len() + bytes.len()) / LEN);
+        if !self.partial.is_empty() {
+            let (rest_of_triple, remaining) =
+                bytes.split_at(bytes.len().min(LEN - self.partial.len()));
+            self.partial.extend_from_slice(rest_of_triple);
+            bytes = remaining;
+            if self.partial.len() < LEN {
+                return records;
+            }
+            records.extend(LookupResponseEntry::try_parse_from(
+                self.partial.as_slice().try_into().expect("complete triple"),
+            ));
+            self.partial.clear();
+        }
+
+        let mut triples = bytes.chunks_exact(LEN);
+        records.extend(triples.by_ref().flat_map(|record| {
+            LookupResponseEntry::try_parse_from(record.try_into().expect("chunk size is correct"))
+        }));
+        self.partial.extend_from_slice(triples.remainder());
+        records
+    }
+
+    /// Checks that no partial triple was left over.
+    fn finish(self) -> Result<(), LookupResponseParseError> {
+        if !self.partial.is_empty() {
+            return Err(LookupResponseParseError::InvalidNumberOfBytes {
+                actual_length: self.total_len,
+            });
+        }
+        Ok(())
+    }
+}
+
 impl LookupResponseEntry {
     fn try_parse_from(record: &[u8; Self::SERIALIZED_LEN]) -> Option<Self> {
         fn non_nil_uuid<T: From<Uuid>>(bytes: &uuid::Bytes) -> Option<T> {
@@ -254,6 +519,8 @@ pub enum LookupError {
     ConnectionTimedOut,
     /// request was invalid: {server_reason}
     InvalidArgument { server_reason: String },
+    /// prebuilt request was invalid: {0}
+    InvalidPrebuiltRequest(&'static str),
     /// server error: {reason}
     Server { reason: &'static str },
 }
@@ -292,11 +559,13 @@ impl From<crate::enclave::Error> for LookupError {
                     Self::WebSocket(WebSocketServiceError::Http(response))
                 }
                 WebSocketConnectError::WebSocketError(e) => Self::WebSocket(e.into()),
+                WebSocketConnectError::SubProtocol(e) => Self::WebSocket(e.into()),
             },
             Error::AttestationError(err) => Self::AttestationError(err),
             Error::WebSocket(err) => Self::WebSocket(err),
             Error::Protocol => Self::Protocol,
-            Error::ConnectionTimedOut => Self::ConnectionTimedOut,
+            // CDSI connections don't go through a circuit breaker, but this is the closest match.
+            Error::ConnectionTimedOut | Error::CircuitOpen => Self::ConnectionTimedOut,
         }
     }
 }
@@ -331,11 +600,79 @@ impl<S: AsyncDuplexStream> CdsiConnection<S> {
         Ok(Self(connection))
     }
 
+    /// Like [`Self::connect`], but keeps trying the endpoint's routes until `deadline`.
+    pub async fn connect_with_deadline<C, T>(
+        endpoint: &EnclaveEndpointConnection<Cdsi, C>,
+        transport_connector: T,
+        auth: impl HttpBasicAuth,
+        deadline: Instant,
+    ) -> Result<Self, LookupError>
+    where
+        C: ConnectionManager,
+        T: TransportConnector<Stream = S>,
+    {
+        let connection = endpoint
+            .connect_with_deadline(auth, transport_connector, deadline)
+            .await?;
+        Ok(Self(connection))
+    }
+
+    /// Like [`Self::connect_with_deadline`], but also keeps the attestation evidence the server
+    /// presented, for [`Self::attestation_info`].
+    pub async fn connect_capturing_evidence<C, T>(
+        endpoint: &EnclaveEndpointConnection<Cdsi, C>,
+        transport_connector: T,
+        auth: impl HttpBasicAuth,
+        deadline: Instant,
+    ) -> Result<Self, LookupError>
+    where
+        C: ConnectionManager,
+        T: TransportConnector<Stream = S>,
+    {
+        let connection = endpoint
+            .connect_capturing_evidence(auth, transport_connector, deadline)
+            .await?;
+        Ok(Self(connection))
+    }
+
+    pub fn attestation_info(&self) -> &AttestationInfo {
+        self.0.attestation_info()
+    }
+
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(name = "cdsi.send_request", skip_all)
+    )]
     pub async fn send_request(
-        mut self,
+        self,
         request: LookupRequest,
     ) -> Result<(Token, ClientResponseCollector<S>), LookupError> {
-        self.0.send(request.into_client_request()).await?;
+        self.send_request_bytes(request.into_client_request_bytes())
+            .await
+    }
+
+    /// Like [`Self::send_request`], but with a request already serialized using
+    /// [`LookupRequest::into_client_request_bytes`].
+    ///
+    /// The bytes are checked before anything is sent, and rejected with
+    /// [`LookupError::InvalidPrebuiltRequest`] if they couldn't have come from a [`LookupRequest`].
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(name = "cdsi.send_prebuilt_request", skip_all)
+    )]
+    pub async fn send_prebuilt_request(
+        self,
+        request: Vec<u8>,
+    ) -> Result<(Token, ClientResponseCollector<S>), LookupError> {
+        validate_prebuilt_request(&request)?;
+        self.send_request_bytes(request).await
+    }
+
+    async fn send_request_bytes(
+        mut self,
+        request: Vec<u8>,
+    ) -> Result<(Token, ClientResponseCollector<S>), LookupError> {
+        self.0.send_bytes(request).await?;
         let token_response: ClientResponse = self.0.receive().await?.next_or_else(|close| {
             close
                 .and_then(err_for_close)
@@ -353,8 +690,67 @@ impl<S: AsyncDuplexStream> CdsiConnection<S> {
     }
 }
 
+/// Checks that `request` is something [`LookupRequest::into_client_request_bytes`] could have
+/// produced.
+fn validate_prebuilt_request(request: &[u8]) -> Result<(), LookupError> {
+    let ClientRequest {
+        aci_uak_pairs,
+        prev_e164s,
+        new_e164s,
+        discard_e164s,
+        token: _,
+        token_ack,
+        return_acis_without_uaks: _,
+    } = ClientRequest::decode(request)
+        .map_err(|_| LookupError::InvalidPrebuiltRequest("not a ClientRequest"))?;
+
+    // The ack is sent separately, once the server has responded with a token.
+    if token_ack {
+        return Err(LookupError::InvalidPrebuiltRequest("token_ack is set"));
+    }
+    if !discard_e164s.is_empty() {
+        return Err(LookupError::InvalidPrebuiltRequest(
+            "discard_e164s is not supported",
+        ));
+    }
+    if aci_uak_pairs.len() % AciAndAccessKey::SERIALIZED_LEN != 0 {
+        return Err(LookupError::InvalidPrebuiltRequest(
+            "aci_uak_pairs has a partial entry",
+        ));
+    }
+    if prev_e164s.len() % E164::SERIALIZED_LEN != 0 || new_e164s.len() % E164::SERIALIZED_LEN != 0 {
+        return Err(LookupError::InvalidPrebuiltRequest(
+            "e164s have a partial entry",
+        ));
+    }
+    Ok(())
+}
+
 impl<S: AsyncDuplexStream> ClientResponseCollector<S> {
     pub async fn collect(self) -> Result<LookupResponse, LookupError> {
+        let mut records = Vec::new();
+        let LookupResponseSummary { debug_permits_used } = self
+            .collect_streaming(|batch| records.extend(batch))
+            .await?;
+        Ok(LookupResponse {
+            records,
+            debug_permits_used,
+        })
+    }
+
+    /// Like [`Self::collect`], but hands over the records as they arrive.
+    ///
+    /// The server may split a large response across several websocket messages. `on_batch` is
+    /// called once for each of them with the records that message completes; a record whose bytes
+    /// are split between two messages is part of the second message's batch.
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(name = "cdsi.collect", skip_all)
+    )]
+    pub async fn collect_streaming(
+        self,
+        mut on_batch: impl FnMut(Vec<LookupResponseEntry>),
+    ) -> Result<LookupResponseSummary, LookupError> {
         let Self(mut connection) = self;
 
         let token_ack = ClientRequest {
@@ -368,13 +764,24 @@ impl<S: AsyncDuplexStream> ClientResponseCollector<S> {
                 .and_then(err_for_close)
                 .unwrap_or(LookupError::Protocol)
         })?;
+
+        let mut triples = TripleDecoder::default();
+        let mut debug_permits_used = 0;
         loop {
-            match connection.0.receive_bytes().await? {
-                NextOrClose::Next(decoded) => {
-                    response
-                        .merge(decoded.as_ref())
-                        .map_err(LookupError::from)?;
-                }
+            let ClientResponse {
+                e164_pni_aci_triples,
+                token: _,
+                debug_permits_used: permits_in_message,
+            } = response;
+            // As when merging protobuf messages, a later message only replaces the count if it
+            // has one.
+            if permits_in_message != 0 {
+                debug_permits_used = permits_in_message;
+            }
+            on_batch(triples.push(&e164_pni_aci_triples));
+
+            response = match connection.0.receive_bytes().await? {
+                NextOrClose::Next(message) => ClientResponse::decode(message.as_slice())?,
                 NextOrClose::Close(
                     None
                     | Some(CloseFrame {
@@ -385,10 +792,54 @@ impl<S: AsyncDuplexStream> ClientResponseCollector<S> {
                 NextOrClose::Close(Some(close)) => {
                     return Err(err_for_close(close).unwrap_or(LookupError::Protocol))
                 }
-            }
+            };
+        }
+        triples.finish()?;
+
+        Ok(LookupResponseSummary { debug_permits_used })
+    }
+}
+
+/// Performs a lookup that might be too large for a single request.
+///
+/// The request is split using [`LookupRequest::chunked`], and each chunk is sent over a new
+/// connection produced by `connect`, one after another. Every chunk after the first is sent with
+/// the token returned for the previous one, which covers the numbers the chunk repeats in
+/// `prev_e164s`. The returned token is the one from the last chunk, and the response contains the
+/// records from all chunks.
+pub async fn lookup_chunked<S, F, Fut>(
+    mut connect: F,
+    request: LookupRequest,
+    max_request_bytes: usize,
+) -> Result<(Token, LookupResponse), LookupError>
+where
+    S: AsyncDuplexStream,
+    F: FnMut() -> Fut,
+    Fut: Future<Output = Result<CdsiConnection<S>, LookupError>>,
+{
+    let mut token = None;
+    let mut merged = LookupResponse {
+        records: Vec::new(),
+        debug_permits_used: 0,
+    };
+
+    for mut chunk in request.chunked(max_request_bytes) {
+        if let Some(Token(previous_token)) = token.take() {
+            chunk.token = previous_token;
         }
-        Ok(response.try_into()?)
+
+        let (chunk_token, collector) = connect().await?.send_request(chunk).await?;
+        let LookupResponse {
+            records,
+            debug_permits_used,
+        } = collector.collect().await?;
+
+        merged.records.extend(records);
+        merged.debug_permits_used += debug_permits_used;
+        token = Some(chunk_token);
     }
+
+    Ok((token.expect("always at least one chunk"), merged))
 }
 
 /// Numeric code set by the server on the websocket close frame.
@@ -408,7 +859,7 @@ enum CdsiCloseCode {
 /// provided close frame. Otherwise returns `None`.
 fn err_for_close(CloseFrame { code, reason }: CloseFrame<'_>) -> Option<LookupError> {
     let Ok(code) = CdsiCloseCode::try_from(u16::from(code)) else {
-        log::warn!("got unexpected websocket error code: {code}",);
+        logging::warn!("got unexpected websocket error code: {code}",);
         return None;
     };
 
@@ -436,10 +887,12 @@ fn err_for_close(CloseFrame { code, reason }: CloseFrame<'_>) -> Option<LookupEr
 #[cfg(test)]
 mod test {
     use std::num::NonZeroU64;
+    use std::sync::{Arc, Mutex};
     use std::time::Duration;
 
     use assert_matches::assert_matches;
     use hex_literal::hex;
+    use libsignal_net_infra::live_connections;
     use libsignal_net_infra::testutil::InMemoryWarpConnector;
     use libsignal_net_infra::utils::ObservableEvent;
     use libsignal_net_infra::ws::testutil::{
@@ -448,6 +901,7 @@ mod test {
     };
     use libsignal_net_infra::ws::WebSocketClient;
     use nonzero_ext::nonzero;
+    use test_case::test_case;
     use tungstenite::protocol::frame::coding::CloseCode;
     use tungstenite::protocol::CloseFrame;
     use uuid::Uuid;
@@ -497,6 +951,67 @@ mod test {
         );
     }
 
+    fn numbered_records(count: u8) -> Vec<LookupResponseEntry> {
+        (1..=count)
+            .map(|i| LookupResponseEntry {
+                e164: E164::new(NonZeroU64::new(18005550100 + u64::from(i)).expect("nonzero")),
+                aci: Some(Aci::from_uuid_bytes([i; 16])),
+                pni: (i % 2 == 0).then(|| Pni::from_uuid_bytes([0x80 | i; 16])),
+            })
+            .collect()
+    }
+
+    /// Splits `bytes` at each of `offsets`, which must be in increasing order.
+    fn split_at_offsets<'a>(bytes: &'a [u8], offsets: &[usize]) -> Vec<&'a [u8]> {
+        let mut pieces = Vec::with_capacity(offsets.len() + 1);
+        let mut start = 0;
+        for &offset in offsets {
+            pieces.push(&bytes[start..offset]);
+            start = offset;
+        }
+        pieces.push(&bytes[start..]);
+        pieces
+    }
+
+    #[test_case(&[]; "whole")]
+    #[test_case(&[1]; "after the first byte")]
+    #[test_case(&[39, 41]; "around a triple boundary")]
+    #[test_case(&[40, 80]; "on triple boundaries")]
+    #[test_case(&[0, 17, 17, 63, 119]; "with empty and tiny pieces")]
+    #[test_case(&[5, 10, 15, 20, 25, 30, 35]; "one triple in many pieces")]
+    fn triple_decoder_reassembles_split_triples(offsets: &[usize]) {
+        let records = numbered_records(3);
+        let bytes = records.iter().cloned().collect_serialized();
+
+        let mut decoder = TripleDecoder::default();
+        let mut decoded = Vec::new();
+        let mut bytes_so_far = 0;
+        for piece in split_at_offsets(&bytes, offsets) {
+            decoded.extend(decoder.push(piece));
+            bytes_so_far += piece.len();
+            // Each record is produced as soon as its last byte arrives.
+            assert_eq!(
+                decoded.len(),
+                bytes_so_far / LookupResponseEntry::SERIALIZED_LEN
+            );
+        }
+        decoder.finish().expect("no partial triple");
+        assert_eq!(decoded, records);
+    }
+
+    #[test]
+    fn triple_decoder_rejects_trailing_partial_triple() {
+        let bytes = numbered_records(2).into_iter().collect_serialized();
+
+        let mut decoder = TripleDecoder::default();
+        assert_eq!(decoder.push(&bytes[..50]).len(), 1);
+        assert!(decoder.push(&bytes[50..79]).is_empty());
+        assert_eq!(
+            decoder.finish(),
+            Err(LookupResponseParseError::InvalidNumberOfBytes { actual_length: 79 })
+        );
+    }
+
     #[test]
     fn serialize_e164s() {
         let e164s: Vec<E164> = (18005551001..)
@@ -537,6 +1052,170 @@ mod test {
         );
     }
 
+    fn e164s(range: std::ops::Range<u64>) -> Vec<E164> {
+        range
+            .map(|n| E164::new(NonZeroU64::new(18005550000 + n).unwrap()))
+            .collect()
+    }
+
+    fn acis_and_access_keys(range: std::ops::Range<u8>) -> Vec<AciAndAccessKey> {
+        range
+            .map(|i| AciAndAccessKey {
+                access_key: [i; 16],
+                aci: Aci::from_uuid_bytes([i | 0x80; 16]),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn estimated_size_matches_serialized_fields() {
+        let request = LookupRequest {
+            new_e164s: e164s(0..5),
+            prev_e164s: e164s(5..8),
+            acis_and_access_keys: acis_and_access_keys(0..2),
+            return_acis_without_uaks: true,
+            token: b"some token".as_slice().into(),
+        };
+        let estimated_size = request.estimated_size();
+        assert_eq!(estimated_size, 5 * 8 + 3 * 8 + 2 * 32 + 10);
+
+        let ClientRequest {
+            aci_uak_pairs,
+            prev_e164s,
+            new_e164s,
+            token,
+            ..
+        } = request.into_client_request();
+        assert_eq!(
+            estimated_size,
+            aci_uak_pairs.len() + prev_e164s.len() + new_e164s.len() + token.len()
+        );
+    }
+
+    #[test_case(0, 0, 0, false => 0; "empty")]
+    #[test_case(5, 0, 0, true => 5; "new only")]
+    #[test_case(5, 3, 0, true => 5; "previous with token")]
+    #[test_case(5, 3, 0, false => 8; "previous without token")]
+    #[test_case(0, 0, 2, false => 2; "acis only")]
+    #[test_case(5, 3, 2, true => 7; "everything with token")]
+    #[test_case(5, 3, 2, false => 10; "everything without token")]
+    fn permit_cost_estimate(new: u64, prev: u64, acis: u8, with_token: bool) -> u32 {
+        LookupRequest {
+            new_e164s: e164s(0..new),
+            prev_e164s: e164s(new..new + prev),
+            acis_and_access_keys: acis_and_access_keys(0..acis),
+            return_acis_without_uaks: false,
+            token: if with_token {
+                b"some token".as_slice().into()
+            } else {
+                Default::default()
+            },
+        }
+        .permit_cost_estimate()
+    }
+
+    #[test]
+    fn permit_tracker_accumulates() {
+        let mut tracker = PermitTracker::with_total(100);
+        assert_eq!(tracker.last_permits_used(), None);
+
+        for (debug_permits_used, expected_total) in [(7, 107), (3, 110)] {
+            tracker.record(&LookupResponse {
+                records: vec![],
+                debug_permits_used,
+            });
+            assert_eq!(tracker.last_permits_used(), Some(debug_permits_used as u32));
+            assert_eq!(tracker.total_permits_used(), expected_total);
+        }
+    }
+
+    #[test]
+    fn chunked_request_boundaries() {
+        // Room for the token plus two ACI and access key pairs and two numbers, or ten numbers.
+        const MAX_REQUEST_BYTES: usize = 10 + 2 * 32 + 2 * 8;
+        let request = LookupRequest {
+            new_e164s: e164s(0..8),
+            prev_e164s: e164s(10..12),
+            acis_and_access_keys: acis_and_access_keys(0..4),
+            return_acis_without_uaks: true,
+            token: b"some token".as_slice().into(),
+        };
+
+        let chunks = request.chunked(MAX_REQUEST_BYTES);
+        assert_eq!(
+            chunks
+                .iter()
+                .map(|chunk| (
+                    chunk.prev_e164s.len(),
+                    chunk.acis_and_access_keys.len(),
+                    chunk.new_e164s.len()
+                ))
+                .collect::<Vec<_>>(),
+            [(2, 2, 0), (2, 2, 0), (2, 0, 8)]
+        );
+        for chunk in &chunks {
+            assert!(chunk.estimated_size() <= MAX_REQUEST_BYTES);
+            assert!(chunk.return_acis_without_uaks);
+            assert_eq!(&*chunk.token, b"some token");
+        }
+
+        assert_eq!(chunks[0].prev_e164s, e164s(10..12));
+        for pair in chunks.windows(2) {
+            let [previous, next] = pair else {
+                unreachable!("windows of two")
+            };
+            assert_eq!(
+                next.prev_e164s,
+                [&previous.prev_e164s[..], &previous.new_e164s[..]].concat()
+            );
+        }
+        assert_eq!(
+            chunks
+                .iter()
+                .flat_map(|chunk| &chunk.new_e164s)
+                .copied()
+                .collect::<Vec<_>>(),
+            e164s(0..8)
+        );
+    }
+
+    #[test]
+    fn chunked_request_fits_in_one() {
+        let request = LookupRequest {
+            new_e164s: e164s(0..10),
+            prev_e164s: e164s(10..12),
+            ..Default::default()
+        };
+        let size = request.estimated_size();
+
+        let chunks = request.chunked(size);
+        assert_eq!(chunks.len(), 1);
+        assert_eq!(chunks[0].estimated_size(), size);
+    }
+
+    #[test]
+    fn chunked_request_never_empty() {
+        let chunks = LookupRequest::default().chunked(0);
+        assert_eq!(chunks.len(), 1);
+
+        // Entries larger than the limit each get a chunk of their own, on top of the numbers
+        // already sent.
+        let chunks = LookupRequest {
+            new_e164s: e164s(0..3),
+            prev_e164s: e164s(3..5),
+            ..Default::default()
+        }
+        .chunked(1);
+        assert_eq!(
+            chunks
+                .iter()
+                .map(|chunk| (chunk.prev_e164s.len(), chunk.new_e164s.len()))
+                .collect::<Vec<_>>(),
+            [(2, 0), (2, 1), (3, 1), (4, 1)]
+        );
+        assert_eq!(chunks[3].prev_e164s, [e164s(3..5), e164s(0..2)].concat());
+    }
+
     /// Server-side state relative to a remote request.
     #[derive(Debug, Default, PartialEq)]
     enum FakeServerState {
@@ -581,14 +1260,12 @@ mod test {
                     let mut triples_bytes = [0; LookupResponseEntry::SERIALIZED_LEN];
                     Self::RESPONSE_RECORD.serialize_into(&mut triples_bytes);
                     AttestedServerOutput {
-                        message: Some(
-                            ClientResponse {
-                                debug_permits_used: 1,
-                                e164_pni_aci_triples: triples_bytes.to_vec(),
-                                ..Default::default()
-                            }
-                            .encode_to_vec(),
-                        ),
+                        messages: vec![ClientResponse {
+                            debug_permits_used: 1,
+                            e164_pni_aci_triples: triples_bytes.to_vec(),
+                            ..Default::default()
+                        }
+                        .encode_to_vec()],
                         close_after: Some(None),
                     }
                 }
@@ -628,6 +1305,44 @@ mod test {
         }
     }
 
+    #[tokio::test]
+    async fn captured_evidence_does_not_affect_lookup() {
+        let (server, client) = fake_websocket().await;
+
+        let fake_server = FakeServerState::default().into_handler();
+        tokio::spawn(run_attested_server(
+            server,
+            attest::sgx_session::testutil::private_key(),
+            fake_server,
+        ));
+
+        let ws_client = WebSocketClient::new_fake(client, mock_connection_info());
+        let cdsi_connection = CdsiConnection(
+            AttestedConnection::connect_capturing_evidence(
+                ws_client,
+                live_connections::CDSI,
+                |_| attest::sgx_session::testutil::handshake_from_tests_data(),
+                true,
+            )
+            .await
+            .expect("handshake failed"),
+        );
+        assert_eq!(
+            cdsi_connection.attestation_info().raw_evidence.as_deref(),
+            Some(FAKE_ATTESTATION)
+        );
+
+        let (_token, collector) = cdsi_connection
+            .send_request(LookupRequest {
+                token: b"valid but ignored token".as_slice().into(),
+                ..Default::default()
+            })
+            .await
+            .expect("request accepted");
+        let response = collector.collect().await.expect("successful request");
+        assert_eq!(response.records, vec![FakeServerState::RESPONSE_RECORD]);
+    }
+
     #[tokio::test]
     async fn lookup_success() {
         let (server, client) = fake_websocket().await;
@@ -641,7 +1356,7 @@ mod test {
 
         let ws_client = WebSocketClient::new_fake(client, mock_connection_info());
         let cdsi_connection = CdsiConnection(
-            AttestedConnection::connect(ws_client, |fake_attestation| {
+            AttestedConnection::connect(ws_client, live_connections::CDSI, |fake_attestation| {
                 assert_eq!(fake_attestation, FAKE_ATTESTATION);
                 attest::sgx_session::testutil::handshake_from_tests_data()
             })
@@ -670,6 +1385,354 @@ mod test {
         );
     }
 
+    #[test_case(&[]; "in one message")]
+    #[test_case(&[1, 39]; "at awkward offsets")]
+    #[test_case(&[0, 40, 41, 119]; "with empty and single-byte messages")]
+    #[tokio::test]
+    async fn lookup_response_split_across_messages(offsets: &'static [usize]) {
+        let records = numbered_records(3);
+        let triples = records.iter().cloned().collect_serialized();
+        let pieces = split_at_offsets(&triples, offsets);
+        let last_index = pieces.len() - 1;
+        let mut messages = Some(
+            pieces
+                .into_iter()
+                .enumerate()
+                .map(|(index, piece)| {
+                    ClientResponse {
+                        e164_pni_aci_triples: piece.to_vec(),
+                        debug_permits_used: if index == last_index { 3 } else { 0 },
+                        ..Default::default()
+                    }
+                    .encode_to_vec()
+                })
+                .collect::<Vec<_>>(),
+        );
+
+        let (server, client) = fake_websocket().await;
+
+        let mut fake_server = FakeServerState::default().into_handler();
+        tokio::spawn(run_attested_server(
+            server,
+            attest::sgx_session::testutil::private_key(),
+            move |frame| {
+                let mut output = fake_server(frame);
+                // Replace the canned final response with the split one.
+                if output.close_after.is_some() {
+                    output.messages = messages.take().expect("only one final response");
+                }
+                output
+            },
+        ));
+
+        let ws_client = WebSocketClient::new_fake(client, mock_connection_info());
+        let cdsi_connection = CdsiConnection(
+            AttestedConnection::connect(ws_client, live_connections::CDSI, |_| {
+                attest::sgx_session::testutil::handshake_from_tests_data()
+            })
+            .await
+            .expect("handshake failed"),
+        );
+
+        let (_token, collector) = cdsi_connection
+            .send_request(LookupRequest {
+                token: b"valid but ignored token".as_slice().into(),
+                ..Default::default()
+            })
+            .await
+            .expect("request accepted");
+
+        let mut batches = Vec::new();
+        let summary = collector
+            .collect_streaming(|batch| batches.push(batch))
+            .await
+            .expect("successful request");
+
+        assert_eq!(
+            summary,
+            LookupResponseSummary {
+                debug_permits_used: 3
+            }
+        );
+        assert_eq!(batches.len(), offsets.len() + 1);
+        assert_eq!(batches.concat(), records);
+    }
+
+    #[cfg(debug_assertions)]
+    #[tokio::test]
+    async fn lookup_connection_is_counted_until_server_closes() {
+        // Unique to this test, since the counts are shared by every test in the process.
+        const KIND: &str = "test-cdsi-lookup";
+
+        let (server, client) = fake_websocket().await;
+
+        let fake_server = FakeServerState::default().into_handler();
+        tokio::spawn(run_attested_server(
+            server,
+            attest::sgx_session::testutil::private_key(),
+            fake_server,
+        ));
+
+        let ws_client = WebSocketClient::new_fake(client, mock_connection_info());
+        let cdsi_connection = CdsiConnection(
+            AttestedConnection::connect(ws_client, KIND, |_| {
+                attest::sgx_session::testutil::handshake_from_tests_data()
+            })
+            .await
+            .expect("handshake failed"),
+        );
+        assert_eq!(
+            live_connections::live_connection_counts().get(KIND),
+            Some(&1)
+        );
+
+        let (_token, collector) = cdsi_connection
+            .send_request(LookupRequest {
+                token: b"valid but ignored token".as_slice().into(),
+                ..Default::default()
+            })
+            .await
+            .expect("request accepted");
+        let _response = collector.collect().await.expect("successful request");
+
+        assert_eq!(live_connections::live_connection_counts().get(KIND), None);
+    }
+
+    #[cfg(feature = "tracing")]
+    #[tokio::test]
+    async fn lookup_emits_tracing_spans() {
+        use tracing_subscriber::layer::SubscriberExt as _;
+
+        /// Records the name of every span that is created.
+        #[derive(Clone, Default)]
+        struct SpanNames(Arc<Mutex<Vec<&'static str>>>);
+
+        impl<S: tracing::Subscriber> tracing_subscriber::Layer<S> for SpanNames {
+            fn on_new_span(
+                &self,
+                attrs: &tracing::span::Attributes<'_>,
+                _id: &tracing::span::Id,
+                _ctx: tracing_subscriber::layer::Context<'_, S>,
+            ) {
+                self.0
+                    .lock()
+                    .expect("not poisoned")
+                    .push(attrs.metadata().name());
+            }
+        }
+
+        let span_names = SpanNames::default();
+        let subscriber = tracing_subscriber::registry()
+            .with(tracing_subscriber::fmt::layer().with_test_writer())
+            .with(span_names.clone());
+        let _guard = tracing::subscriber::set_default(subscriber);
+
+        let (server, client) = fake_websocket().await;
+
+        let fake_server = FakeServerState::default().into_handler();
+        tokio::spawn(run_attested_server(
+            server,
+            attest::sgx_session::testutil::private_key(),
+            fake_server,
+        ));
+
+        let ws_client = WebSocketClient::new_fake(client, mock_connection_info());
+        let cdsi_connection = CdsiConnection(
+            AttestedConnection::connect(ws_client, live_connections::CDSI, |_| {
+                attest::sgx_session::testutil::handshake_from_tests_data()
+            })
+            .await
+            .expect("handshake failed"),
+        );
+
+        let (_token, collector) = cdsi_connection
+            .send_request(LookupRequest {
+                token: b"valid but ignored token".as_slice().into(),
+                ..Default::default()
+            })
+            .await
+            .expect("request accepted");
+        let _response = collector.collect().await.expect("successful request");
+
+        assert_eq!(
+            *span_names.0.lock().expect("not poisoned"),
+            ["cdsi.send_request", "cdsi.collect"]
+        );
+    }
+
+    #[tokio::test]
+    async fn prebuilt_request_is_sent_as_is() {
+        let received_requests = Arc::new(Mutex::new(Vec::new()));
+        let connect = || {
+            let received_requests = Arc::clone(&received_requests);
+            async move {
+                let (server, client) = fake_websocket().await;
+
+                let mut fake_server = FakeServerState::default().into_handler();
+                let mut is_first_frame = true;
+                tokio::spawn(run_attested_server(
+                    server,
+                    attest::sgx_session::testutil::private_key(),
+                    move |frame| {
+                        if let (true, NextOrClose::Next(frame)) = (is_first_frame, &frame) {
+                            received_requests.lock().unwrap().push(frame.clone());
+                        }
+                        is_first_frame = false;
+                        fake_server(frame)
+                    },
+                ));
+
+                let ws_client = WebSocketClient::new_fake(client, mock_connection_info());
+                CdsiConnection(
+                    AttestedConnection::connect(ws_client, live_connections::CDSI, |_| {
+                        attest::sgx_session::testutil::handshake_from_tests_data()
+                    })
+                    .await
+                    .expect("handshake failed"),
+                )
+            }
+        };
+        let request = || LookupRequest {
+            new_e164s: e164s(0..3),
+            prev_e164s: e164s(3..5),
+            acis_and_access_keys: acis_and_access_keys(0..2),
+            return_acis_without_uaks: true,
+            token: b"some token".as_slice().into(),
+        };
+
+        let (_token, collector) = connect()
+            .await
+            .send_request(request())
+            .await
+            .expect("request accepted");
+        let response = collector.collect().await.expect("successful request");
+
+        let prebuilt = request().into_client_request_bytes();
+        let (_token, collector) = connect()
+            .await
+            .send_prebuilt_request(prebuilt.clone())
+            .await
+            .expect("request accepted");
+        assert_eq!(
+            collector.collect().await.expect("successful request"),
+            response
+        );
+
+        let received_requests = received_requests.lock().unwrap();
+        assert_eq!(*received_requests, [prebuilt.clone(), prebuilt]);
+    }
+
+    #[test_case(LookupRequest::default().into_client_request_bytes() => matches Ok(()); "empty")]
+    #[test_case(b"\xff\xff".to_vec() => matches Err(LookupError::InvalidPrebuiltRequest(_)); "garbage")]
+    #[test_case(ClientRequest { token_ack: true, ..Default::default() }.encode_to_vec() => matches Err(LookupError::InvalidPrebuiltRequest("token_ack is set")); "token ack")]
+    #[test_case(ClientRequest { discard_e164s: vec![0; 8], ..Default::default() }.encode_to_vec() => matches Err(LookupError::InvalidPrebuiltRequest(_)); "discard")]
+    #[test_case(ClientRequest { new_e164s: vec![0; 7], ..Default::default() }.encode_to_vec() => matches Err(LookupError::InvalidPrebuiltRequest(_)); "partial e164")]
+    #[test_case(ClientRequest { aci_uak_pairs: vec![0; 48], ..Default::default() }.encode_to_vec() => matches Err(LookupError::InvalidPrebuiltRequest(_)); "partial aci")]
+    fn prebuilt_request_validation(request: Vec<u8>) -> Result<(), LookupError> {
+        validate_prebuilt_request(&request)
+    }
+
+    #[tokio::test]
+    async fn lookup_chunked_over_multiple_connections() {
+        const ORIGINAL_TOKEN: &[u8] = b"original token";
+        let received_requests = Arc::new(Mutex::new(Vec::new()));
+
+        let connect = || {
+            let received_requests = Arc::clone(&received_requests);
+            async move {
+                let (server, client) = fake_websocket().await;
+
+                let mut fake_server = FakeServerState::default().into_handler();
+                let mut is_first_frame = true;
+                tokio::spawn(run_attested_server(
+                    server,
+                    attest::sgx_session::testutil::private_key(),
+                    move |frame| {
+                        let request = match (std::mem::take(&mut is_first_frame), &frame) {
+                            (true, NextOrClose::Next(frame)) => {
+                                ClientRequest::decode(frame.as_slice()).expect("can decode")
+                            }
+                            _ => return fake_server(frame),
+                        };
+                        received_requests.lock().unwrap().push(request.clone());
+
+                        // This server's tokens are just the numbers they cover, so like a real
+                        // server it can reject a token that doesn't match prev_e164s.
+                        if request.token != ORIGINAL_TOKEN && request.token != request.prev_e164s {
+                            return AttestedServerOutput::close(None);
+                        }
+                        let mut output = fake_server(frame);
+                        output.messages = vec![ClientResponse {
+                            token: [request.prev_e164s, request.new_e164s].concat(),
+                            ..Default::default()
+                        }
+                        .encode_to_vec()];
+                        output
+                    },
+                ));
+
+                let ws_client = WebSocketClient::new_fake(client, mock_connection_info());
+                Ok::<_, LookupError>(CdsiConnection(
+                    AttestedConnection::connect(
+                        ws_client,
+                        live_connections::CDSI,
+                        |fake_attestation| {
+                            assert_eq!(fake_attestation, FAKE_ATTESTATION);
+                            attest::sgx_session::testutil::handshake_from_tests_data()
+                        },
+                    )
+                    .await
+                    .expect("handshake failed"),
+                ))
+            }
+        };
+
+        let request = LookupRequest {
+            new_e164s: e164s(0..4),
+            prev_e164s: e164s(10..12),
+            token: ORIGINAL_TOKEN.into(),
+            ..Default::default()
+        };
+        // Room for the token and four numbers.
+        let max_request_bytes = ORIGINAL_TOKEN.len() + 4 * E164::SERIALIZED_LEN;
+
+        let (token, response) = lookup_chunked(connect, request, max_request_bytes)
+            .await
+            .expect("lookup succeeds");
+
+        // The last token covers every number looked up.
+        assert_eq!(
+            token.0.into_vec(),
+            [e164s(10..12), e164s(0..4)]
+                .concat()
+                .into_iter()
+                .collect_serialized()
+        );
+        assert_eq!(
+            response,
+            LookupResponse {
+                debug_permits_used: 3,
+                records: vec![FakeServerState::RESPONSE_RECORD; 3],
+            }
+        );
+
+        let received_requests = received_requests.lock().unwrap();
+        assert_eq!(
+            received_requests
+                .iter()
+                .map(|request| (
+                    request.prev_e164s.len() / E164::SERIALIZED_LEN,
+                    request.new_e164s.len() / E164::SERIALIZED_LEN,
+                ))
+                .collect::<Vec<_>>(),
+            [(2, 2), (4, 1), (5, 1)]
+        );
+        assert_eq!(received_requests[0].token, ORIGINAL_TOKEN);
+        for request in &received_requests[1..] {
+            assert_eq!(request.token, request.prev_e164s);
+        }
+    }
+
     const RETRY_AFTER_SECS: u32 = 12345;
 
     #[tokio::test]
@@ -696,7 +1759,7 @@ mod test {
 
         let ws_client = WebSocketClient::new_fake(client, mock_connection_info());
         let cdsi_connection = CdsiConnection(
-            AttestedConnection::connect(ws_client, |fake_attestation| {
+            AttestedConnection::connect(ws_client, live_connections::CDSI, |fake_attestation| {
                 assert_eq!(fake_attestation, FAKE_ATTESTATION);
                 attest::sgx_session::testutil::handshake_from_tests_data()
             })
@@ -743,7 +1806,7 @@ mod test {
 
         let ws_client = WebSocketClient::new_fake(client, mock_connection_info());
         let cdsi_connection = CdsiConnection(
-            AttestedConnection::connect(ws_client, |fake_attestation| {
+            AttestedConnection::connect(ws_client, live_connections::CDSI, |fake_attestation| {
                 assert_eq!(fake_attestation, FAKE_ATTESTATION);
                 attest::sgx_session::testutil::handshake_from_tests_data()
             })
@@ -820,7 +1883,7 @@ mod test {
 
         let ws_client = WebSocketClient::new_fake(client, mock_connection_info());
         let cdsi_connection = CdsiConnection(
-            AttestedConnection::connect(ws_client, |fake_attestation| {
+            AttestedConnection::connect(ws_client, live_connections::CDSI, |fake_attestation| {
                 assert_eq!(fake_attestation, FAKE_ATTESTATION);
                 attest::sgx_session::testutil::handshake_from_tests_data()
             })
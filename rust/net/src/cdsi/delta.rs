@@ -0,0 +1,230 @@
+//
+// Copyright 2024 Signal Messenger, LLC.
+// SPDX-License-Identifier: AGPL-3.0-only
+//
+
+use std::collections::{HashMap, HashSet};
+
+use libsignal_core::E164;
+
+use crate::cdsi::{LookupResponse, LookupResponseEntry};
+
+/// How the results of a CDSI lookup differ from those of an earlier one.
+///
+/// Every E164 in the results has an entry, even if it has neither an ACI nor a PNI, so an E164 is
+/// only *added* or *removed* when its entry appears or disappears. For an E164 in both sets of
+/// results, an ACI or PNI appearing, disappearing, or being replaced counts as a change to that ID.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct MappingDelta {
+    /// E164s in the current results but not the previous ones.
+    pub added: HashSet<E164>,
+    /// E164s in the previous results but not the current ones.
+    pub removed: HashSet<E164>,
+    /// E164s in both results whose ACI differs.
+    pub aci_changed: HashSet<E164>,
+    /// E164s in both results whose PNI differs.
+    pub pni_changed: HashSet<E164>,
+}
+
+impl MappingDelta {
+    /// Compares `current` against the `previous` results, as produced by
+    /// [`LookupResponse::into_map`].
+    pub fn compute(
+        previous: &HashMap<E164, LookupResponseEntry>,
+        current: &LookupResponse,
+    ) -> Self {
+        let mut delta = Self::default();
+        let mut seen = HashSet::with_capacity(current.records.len());
+
+        for entry in &current.records {
+            let LookupResponseEntry { e164, aci, pni } = entry;
+            seen.insert(*e164);
+
+            let Some(previous) = previous.get(e164) else {
+                delta.added.insert(*e164);
+                continue;
+            };
+            if previous.aci != *aci {
+                delta.aci_changed.insert(*e164);
+            }
+            if previous.pni != *pni {
+                delta.pni_changed.insert(*e164);
+            }
+        }
+
+        delta.removed.extend(
+            previous
+                .keys()
+                .filter(|e164| !seen.contains(*e164))
+                .copied(),
+        );
+
+        delta
+    }
+
+    pub fn is_empty(&self) -> bool {
+        let Self {
+            added,
+            removed,
+            aci_changed,
+            pni_changed,
+        } = self;
+        added.is_empty() && removed.is_empty() && aci_changed.is_empty() && pni_changed.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use libsignal_core::{Aci, Pni};
+    use test_case::{test_case, test_matrix};
+    use uuid::Uuid;
+
+    use super::*;
+
+    const E164_A: E164 = E164::new(nonzero_ext::nonzero!(18005550101u64));
+    const E164_B: E164 = E164::new(nonzero_ext::nonzero!(18005550102u64));
+
+    /// The states an ID can be in, for building the transition matrix.
+    #[derive(Copy, Clone, Debug)]
+    enum Id {
+        Absent,
+        First,
+        Second,
+    }
+
+    impl Id {
+        fn to_id<T: From<Uuid>>(self) -> Option<T> {
+            match self {
+                Id::Absent => None,
+                Id::First => Some(Uuid::from_u128(0x1111).into()),
+                Id::Second => Some(Uuid::from_u128(0x2222).into()),
+            }
+        }
+    }
+
+    fn entry(e164: E164, aci: Id, pni: Id) -> LookupResponseEntry {
+        LookupResponseEntry {
+            e164,
+            aci: aci.to_id::<Aci>(),
+            pni: pni.to_id::<Pni>(),
+        }
+    }
+
+    fn response(records: Vec<LookupResponseEntry>) -> LookupResponse {
+        LookupResponse {
+            records,
+            debug_permits_used: 0,
+        }
+    }
+
+    fn is_same(before: Id, after: Id) -> bool {
+        matches!(
+            (before, after),
+            (Id::Absent, Id::Absent) | (Id::First, Id::First) | (Id::Second, Id::Second)
+        )
+    }
+
+    #[test_matrix(
+        [Id::Absent, Id::First, Id::Second],
+        [Id::Absent, Id::First, Id::Second]
+    )]
+    fn aci_transition(before: Id, after: Id) {
+        let previous = response(vec![entry(E164_A, before, Id::First)]).into_map();
+        let current = response(vec![entry(E164_A, after, Id::First)]);
+
+        let delta = MappingDelta::compute(&previous, &current);
+        let expected_changed = if is_same(before, after) {
+            HashSet::new()
+        } else {
+            HashSet::from([E164_A])
+        };
+        assert_eq!(
+            delta,
+            MappingDelta {
+                aci_changed: expected_changed,
+                ..Default::default()
+            }
+        );
+    }
+
+    #[test_matrix(
+        [Id::Absent, Id::First, Id::Second],
+        [Id::Absent, Id::First, Id::Second]
+    )]
+    fn pni_transition(before: Id, after: Id) {
+        let previous = response(vec![entry(E164_A, Id::First, before)]).into_map();
+        let current = response(vec![entry(E164_A, Id::First, after)]);
+
+        let delta = MappingDelta::compute(&previous, &current);
+        let expected_changed = if is_same(before, after) {
+            HashSet::new()
+        } else {
+            HashSet::from([E164_A])
+        };
+        assert_eq!(
+            delta,
+            MappingDelta {
+                pni_changed: expected_changed,
+                ..Default::default()
+            }
+        );
+    }
+
+    #[test_case(Id::Absent, Id::Absent; "no IDs")]
+    #[test_case(Id::First, Id::Absent; "ACI only")]
+    #[test_case(Id::Absent, Id::First; "PNI only")]
+    #[test_case(Id::First, Id::Second; "both")]
+    fn entry_appearing_and_disappearing(aci: Id, pni: Id) {
+        let empty = response(vec![]);
+        let with_entry = response(vec![entry(E164_A, aci, pni)]);
+
+        assert_eq!(
+            MappingDelta::compute(&empty.into_map(), &with_entry),
+            MappingDelta {
+                added: HashSet::from([E164_A]),
+                ..Default::default()
+            }
+        );
+
+        assert_eq!(
+            MappingDelta::compute(&with_entry.into_map(), &response(vec![])),
+            MappingDelta {
+                removed: HashSet::from([E164_A]),
+                ..Default::default()
+            }
+        );
+    }
+
+    #[test]
+    fn multiple_entries() {
+        let previous = response(vec![
+            entry(E164_A, Id::First, Id::Absent),
+            entry(E164_B, Id::First, Id::First),
+        ])
+        .into_map();
+        let current = response(vec![entry(E164_A, Id::Second, Id::First)]);
+
+        let delta = MappingDelta::compute(&previous, &current);
+        assert_eq!(
+            delta,
+            MappingDelta {
+                added: HashSet::new(),
+                removed: HashSet::from([E164_B]),
+                aci_changed: HashSet::from([E164_A]),
+                pni_changed: HashSet::from([E164_A]),
+            }
+        );
+        assert!(!delta.is_empty());
+    }
+
+    #[test]
+    fn unchanged_results_have_empty_delta() {
+        let records = vec![
+            entry(E164_A, Id::First, Id::Second),
+            entry(E164_B, Id::Absent, Id::First),
+        ];
+        let previous = response(records.clone()).into_map();
+        let delta = MappingDelta::compute(&previous, &response(records));
+        assert!(delta.is_empty(), "{delta:?}");
+    }
+}
@@ -3,10 +3,15 @@
 // SPDX-License-Identifier: AGPL-3.0-only
 //
 
-pub use libsignal_net_infra::certs::RootCertificates;
+pub use libsignal_net_infra::certs::{
+    add_supplemental_root_der, supplemental_root_ders, CertParseError, RootCertificates,
+};
+use sha2::{Digest as _, Sha256};
+
+const SIGNAL_ROOT_CERTIFICATE_DERS: &[&[u8]] = &[include_bytes!("../res/signal.cer")];
 
 pub const SIGNAL_ROOT_CERTIFICATES: RootCertificates =
-    RootCertificates::FromStaticDers(&[include_bytes!("../res/signal.cer")]);
+    RootCertificates::FromStaticDers(SIGNAL_ROOT_CERTIFICATE_DERS);
 
 // GIAG2 cert plus root certs from pki.goog
 pub const PROXY_G_ROOT_CERTIFICATES: RootCertificates = RootCertificates::FromStaticDers(&[
@@ -18,3 +23,36 @@ pub const PROXY_G_ROOT_CERTIFICATES: RootCertificates = RootCertificates::FromSt
     include_bytes!("../res/GTSR3.cer"),
     include_bytes!("../res/GTSR4.cer"),
 ]);
+
+/// The SHA-256 fingerprints of the roots currently trusted for connections to Signal's servers.
+///
+/// These are the roots in [`SIGNAL_ROOT_CERTIFICATES`], followed by any added with
+/// [`add_supplemental_root_der`].
+pub fn trusted_root_fingerprints() -> Vec<[u8; 32]> {
+    SIGNAL_ROOT_CERTIFICATE_DERS
+        .iter()
+        .copied()
+        .chain(supplemental_root_ders().iter().map(|der| &**der))
+        .map(|der| Sha256::digest(der).into())
+        .collect()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn trusted_root_fingerprints_include_supplemental_roots() {
+        let signal_root_fingerprint: [u8; 32] =
+            Sha256::digest(include_bytes!("../res/signal.cer")).into();
+        let fingerprints = trusted_root_fingerprints();
+        assert_eq!(fingerprints.first(), Some(&signal_root_fingerprint));
+
+        const SUPPLEMENTAL_ROOT: &[u8] = include_bytes!("../res/GTSR1.cer");
+        add_supplemental_root_der(SUPPLEMENTAL_ROOT).expect("valid");
+
+        let fingerprints = trusted_root_fingerprints();
+        assert_eq!(fingerprints.first(), Some(&signal_root_fingerprint));
+        assert!(fingerprints.contains(&Sha256::digest(SUPPLEMENTAL_ROOT).into()));
+    }
+}
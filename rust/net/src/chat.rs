@@ -2,6 +2,7 @@
 // Copyright 2023 Signal Messenger, LLC.
 // SPDX-License-Identifier: AGPL-3.0-only
 //
+use std::net::SocketAddr;
 use std::sync::Arc;
 use std::time::Duration;
 
@@ -10,23 +11,30 @@ use ::http::{HeaderMap, HeaderName, HeaderValue, StatusCode};
 use async_trait::async_trait;
 use futures_util::future::BoxFuture;
 use libsignal_net_infra::connection_manager::MultiRouteConnectionManager;
+use libsignal_net_infra::host::Host;
 use libsignal_net_infra::service::{Service, ServiceConnectorWithDecorator};
 use libsignal_net_infra::timeouts::{MULTI_ROUTE_CONNECTION_TIMEOUT, ONE_ROUTE_CONNECTION_TIMEOUT};
-use libsignal_net_infra::utils::{basic_authorization, ObservableEvent};
-use libsignal_net_infra::ws::WebSocketClientConnector;
+use libsignal_net_infra::utils::ObservableEvent;
+use libsignal_net_infra::ws::{WebSocketClientConnector, WebSocketConfig};
 use libsignal_net_infra::{
-    make_ws_config, EndpointConnection, HttpRequestDecorator, IpType, TransportConnector,
+    make_ws_config, ConnectionInfo, EndpointConnection, HttpRequestDecorator, IpType, RouteType,
+    TransportConnector,
 };
+use tokio::io::AsyncRead;
 
-use crate::auth::Auth;
-use crate::chat::ws::{ChatOverWebSocketServiceConnector, ServerEvent};
+use crate::auth::AuthProvider;
+use crate::chat::pool::{UnauthChatPool, UnauthChatPoolConfig};
+use crate::chat::ws::{ChatOverWebSocketServiceConnector, ServerEvent, ServiceConnectorWithAuth};
 use crate::env::{add_user_agent_header, ConnectionConfig, RECEIVE_STORIES_HEADER_NAME};
 use crate::proto;
 
 mod error;
 pub use error::ChatServiceError;
 
+pub mod challenge;
+pub mod multi_recipient;
 pub mod noise;
+pub mod pool;
 pub mod server_requests;
 pub mod service;
 pub mod ws;
@@ -44,6 +52,14 @@ pub trait ChatService {
     /// or HTTP) capable of sending [Request] objects.
     async fn send(&self, msg: Request, timeout: Duration) -> Result<Response, ChatServiceError>;
 
+    /// Like [`ChatService::send`], but reads the body of the request as it is sent instead of
+    /// requiring it to be in memory.
+    async fn send_streaming(
+        &self,
+        msg: RequestWithBodyStream,
+        timeout: Duration,
+    ) -> Result<Response, ChatServiceError>;
+
     /// Establish a connection without sending a request.
     async fn connect(&self) -> Result<(), ChatServiceError>;
 
@@ -52,6 +68,12 @@ pub trait ChatService {
     /// Depending on the implementing logic, the connection may be re-established later
     /// with a call to [ChatService::send].
     async fn disconnect(&self);
+
+    /// A smoothed estimate of the round-trip time to the server over the current connection.
+    ///
+    /// Returns `None` if there's no connection (including while one is being established), or if
+    /// none of its keepalive pings have been answered yet.
+    fn rtt_estimate(&self) -> Option<Duration>;
 }
 
 #[async_trait]
@@ -73,10 +95,69 @@ pub struct DebugInfo {
     pub ip_type: IpType,
     /// Time it took to complete the request.
     pub duration: Duration,
+    /// How the connection was established, or `None` if there was no connection.
+    pub route_type: Option<RouteType>,
+    /// The remote address the connection was established to, if it was resolved to an IP.
+    ///
+    /// For proxied connections, this is the address of the proxy.
+    pub remote_address: Option<SocketAddr>,
     /// Connection information summary.
+    ///
+    /// Superseded by the structured fields above, and kept for one more release for
+    /// compatibility.
     pub connection_info: String,
 }
 
+impl DebugInfo {
+    pub(crate) fn new(connection_info: Option<&ConnectionInfo>, duration: Duration) -> Self {
+        let Some(connection_info) = connection_info else {
+            return Self {
+                ip_type: IpType::Unknown,
+                duration,
+                route_type: None,
+                remote_address: None,
+                connection_info: String::new(),
+            };
+        };
+        let remote_address = match connection_info.address {
+            Host::Ip(ip) => Some(SocketAddr::new(ip, connection_info.port.get())),
+            Host::Domain(_) => None,
+        };
+        Self {
+            ip_type: IpType::from_host(&connection_info.address),
+            duration,
+            route_type: Some(connection_info.route_type),
+            remote_address,
+            connection_info: connection_info.description(),
+        }
+    }
+}
+
+/// Identifies a single request sent over a chat connection.
+///
+/// The ID is sent to the server in the [`TRACE_ID_HEADER_NAME`] header and is included in errors
+/// about the request, so client and server logs can be correlated.
+///
+/// [`TRACE_ID_HEADER_NAME`]: crate::env::TRACE_ID_HEADER_NAME
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct TraceId(u64);
+
+impl TraceId {
+    pub const fn new(id: u64) -> Self {
+        Self(id)
+    }
+
+    pub fn random() -> Self {
+        Self(rand::random())
+    }
+}
+
+impl std::fmt::Display for TraceId {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{:016x}", self.0)
+    }
+}
+
 #[derive(Clone, Debug)]
 pub struct Request {
     pub method: ::http::Method,
@@ -85,6 +166,30 @@ pub struct Request {
     pub path: PathAndQuery,
 }
 
+/// A [`Request`] whose body is read from a stream while the request is being sent.
+///
+/// The length of the body has to be known up front, because the body is nested inside the
+/// length-prefixed request message.
+pub struct RequestWithBodyStream {
+    pub method: ::http::Method,
+    pub body: Box<dyn AsyncRead + Send + Unpin>,
+    /// The exact number of bytes that will be read from `body`.
+    pub body_len: usize,
+    pub headers: HeaderMap,
+    pub path: PathAndQuery,
+}
+
+impl std::fmt::Debug for RequestWithBodyStream {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("RequestWithBodyStream")
+            .field("method", &self.method)
+            .field("body_len", &self.body_len)
+            .field("headers", &self.headers)
+            .field("path", &self.path)
+            .finish_non_exhaustive()
+    }
+}
+
 #[derive(Clone, Debug)]
 pub struct Response {
     pub status: StatusCode,
@@ -164,6 +269,22 @@ where
         self.unauth_service.send(msg, timeout).await
     }
 
+    pub async fn send_authenticated_streaming(
+        &self,
+        msg: RequestWithBodyStream,
+        timeout: Duration,
+    ) -> Result<Response, ChatServiceError> {
+        self.auth_service.send_streaming(msg, timeout).await
+    }
+
+    pub async fn send_unauthenticated_streaming(
+        &self,
+        msg: RequestWithBodyStream,
+        timeout: Duration,
+    ) -> Result<Response, ChatServiceError> {
+        self.unauth_service.send_streaming(msg, timeout).await
+    }
+
     pub async fn send_authenticated_and_debug(
         &self,
         msg: Request,
@@ -193,6 +314,14 @@ where
         self.auth_service.disconnect().await;
     }
 
+    pub fn rtt_estimate_authenticated(&self) -> Option<Duration> {
+        self.auth_service.rtt_estimate()
+    }
+
+    pub fn rtt_estimate_unauthenticated(&self) -> Option<Duration> {
+        self.unauth_service.rtt_estimate()
+    }
+
     pub fn into_dyn(
         self,
     ) -> Chat<
@@ -242,6 +371,18 @@ impl<D: DelegatingChatService> ChatService for D {
         self.inner().send(msg, timeout)
     }
 
+    fn send_streaming<'life0, 'async_trait>(
+        &'life0 self,
+        msg: RequestWithBodyStream,
+        timeout: Duration,
+    ) -> BoxFuture<'async_trait, Result<Response, ChatServiceError>>
+    where
+        'life0: 'async_trait,
+        Self: 'async_trait,
+    {
+        self.inner().send_streaming(msg, timeout)
+    }
+
     fn connect<'life0, 'async_trait>(
         &'life0 self,
     ) -> BoxFuture<'life0, Result<(), ChatServiceError>>
@@ -259,6 +400,10 @@ impl<D: DelegatingChatService> ChatService for D {
     {
         self.inner().disconnect()
     }
+
+    fn rtt_estimate(&self) -> Option<Duration> {
+        self.inner().rtt_estimate()
+    }
 }
 
 impl<D: DelegatingChatService> ChatServiceWithDebugInfo for D
@@ -387,23 +532,17 @@ impl DelegatingChatService for Arc<dyn ChatServiceWithDebugInfo + Send + Sync> {
 fn build_authorized_chat_service(
     connection_manager_ws: &MultiRouteConnectionManager,
     service_connector_ws: &ChatOverWebSocketServiceConnector<impl TransportConnector + 'static>,
-    auth: Auth,
+    auth: Arc<dyn AuthProvider>,
     receive_stories: bool,
 ) -> AuthorizedChatService<impl ChatServiceWithDebugInfo> {
-    let header_map = HeaderMap::from_iter([
-        (
-            http::header::AUTHORIZATION,
-            basic_authorization(&auth.username, &auth.password),
-        ),
-        (
-            HeaderName::from_static(RECEIVE_STORIES_HEADER_NAME),
-            HeaderValue::from_static(if receive_stories { "true" } else { "false" }),
-        ),
-    ]);
+    let header_map = HeaderMap::from_iter([(
+        HeaderName::from_static(RECEIVE_STORIES_HEADER_NAME),
+        HeaderValue::from_static(if receive_stories { "true" } else { "false" }),
+    )]);
     // ws authorized
     let chat_over_ws_auth = Service::new(
         ServiceConnectorWithDecorator::new(
-            service_connector_ws.clone(),
+            ServiceConnectorWithAuth::new(service_connector_ws.clone(), auth),
             HttpRequestDecorator::Headers(header_map),
         ),
         connection_manager_ws.clone(),
@@ -440,7 +579,7 @@ pub fn chat_service<T: TransportConnector + 'static>(
     transport_connector: T,
     incoming_auth_tx: tokio::sync::mpsc::Sender<ServerEvent<T::Stream>>,
     incoming_unauth_tx: tokio::sync::mpsc::Sender<ServerEvent<T::Stream>>,
-    auth: Auth,
+    auth: impl AuthProvider + 'static,
     receive_stories: bool,
 ) -> Chat<impl ChatServiceWithDebugInfo, impl ChatServiceWithDebugInfo> {
     // Cannot reuse the same connector, since they lock on `incoming_tx` internally.
@@ -456,7 +595,7 @@ pub fn chat_service<T: TransportConnector + 'static>(
         let auth_service = build_authorized_chat_service(
             &endpoint.manager,
             &auth_ws_connector,
-            auth,
+            Arc::new(auth),
             receive_stories,
         );
         let unauth_service = build_anonymous_chat_service(&endpoint.manager, &unauth_ws_connector);
@@ -467,6 +606,19 @@ pub fn chat_service<T: TransportConnector + 'static>(
     }
 }
 
+/// Creates a pool of unauthenticated connections to the chat server for one-shot requests.
+pub fn unauth_chat_pool<T: TransportConnector + 'static>(
+    endpoint: &EndpointConnection<MultiRouteConnectionManager>,
+    transport_connector: T,
+    config: UnauthChatPoolConfig,
+) -> UnauthChatPool {
+    UnauthChatPool::new(
+        WebSocketClientConnector::new(transport_connector, endpoint.config.clone()),
+        endpoint.manager.clone(),
+        config,
+    )
+}
+
 pub fn endpoint_connection(
     connection_config: &ConnectionConfig,
     user_agent: &str,
@@ -475,7 +627,12 @@ pub fn endpoint_connection(
     let chat_endpoint = PathAndQuery::from_static(crate::env::constants::WEB_SOCKET_PATH);
     let chat_connection_params = connection_config.connection_params_with_fallback();
     let chat_connection_params = add_user_agent_header(chat_connection_params, user_agent);
-    let chat_ws_config = make_ws_config(chat_endpoint, ONE_ROUTE_CONNECTION_TIMEOUT);
+    let chat_ws_config = WebSocketConfig {
+        ws_config: connection_config.websocket_limits.ws_config(),
+        sub_protocols: crate::env::constants::CHAT_WEB_SOCKET_SUB_PROTOCOLS,
+        draining_close_code: Some(crate::env::constants::CHAT_SERVER_DRAINING_CLOSE_CODE),
+        ..make_ws_config(chat_endpoint, ONE_ROUTE_CONNECTION_TIMEOUT)
+    };
     EndpointConnection::new_multi(
         chat_connection_params,
         ONE_ROUTE_CONNECTION_TIMEOUT,
@@ -498,7 +655,9 @@ pub mod test_support {
     use super::*;
     use crate::auth::Auth;
     use crate::chat::{Chat, ChatServiceWithDebugInfo};
-    use crate::env::constants::WEB_SOCKET_PATH;
+    use crate::env::constants::{
+        CHAT_SERVER_DRAINING_CLOSE_CODE, CHAT_WEB_SOCKET_SUB_PROTOCOLS, WEB_SOCKET_PATH,
+    };
     use crate::env::{Env, Svr3Env};
 
     pub type AnyChat = Chat<
@@ -517,7 +676,12 @@ pub mod test_support {
             DnsResolver::new_with_static_fallback(env.static_fallback(), &network_change_event);
         let transport_connector = DirectConnector::new(dns_resolver);
         let chat_endpoint = PathAndQuery::from_static(WEB_SOCKET_PATH);
-        let chat_ws_config = make_ws_config(chat_endpoint, one_route_connect_timeout);
+        let chat_ws_config = WebSocketConfig {
+            ws_config: env.chat_domain_config.connect.websocket_limits.ws_config(),
+            sub_protocols: CHAT_WEB_SOCKET_SUB_PROTOCOLS,
+            draining_close_code: Some(CHAT_SERVER_DRAINING_CLOSE_CODE),
+            ..make_ws_config(chat_endpoint, one_route_connect_timeout)
+        };
         let connection = EndpointConnection::new_multi(
             connection_params,
             one_route_connect_timeout,
@@ -541,9 +705,14 @@ pub mod test_support {
 
 #[cfg(test)]
 pub(crate) mod test {
+    use std::net::Ipv4Addr;
+
     use assert_matches::assert_matches;
     use http::{HeaderName, HeaderValue};
+    use libsignal_net_infra::DnsSource;
+    use nonzero_ext::nonzero;
 
+    use super::*;
     use crate::chat::{Response, ResponseProto, ResponseProtoInvalidError};
 
     pub(crate) mod shared {
@@ -563,7 +732,9 @@ pub(crate) mod test {
         use nonzero_ext::nonzero;
 
         use crate::certs::SIGNAL_ROOT_CERTIFICATES;
-        use crate::chat::{ChatService, ChatServiceError, Request, Response};
+        use crate::chat::{
+            ChatService, ChatServiceError, Request, RequestWithBodyStream, Response,
+        };
 
         #[async_trait]
         impl<C> ChatService for NoReconnectService<C>
@@ -586,6 +757,19 @@ pub(crate) mod test {
                 }
             }
 
+            async fn send_streaming(
+                &self,
+                msg: RequestWithBodyStream,
+                timeout: Duration,
+            ) -> Result<Response, ChatServiceError> {
+                match &*self.inner {
+                    ServiceState::Active(service, status) if !status.is_cancelled() => {
+                        service.clone().send_streaming(msg, timeout).await
+                    }
+                    _ => Err(ChatServiceError::AllConnectionRoutesFailed { attempts: 1 }),
+                }
+            }
+
             async fn connect(&self) -> Result<(), ChatServiceError> {
                 Ok(())
             }
@@ -595,6 +779,15 @@ pub(crate) mod test {
                     status.cancel(CancellationReason::ExplicitDisconnect)
                 }
             }
+
+            fn rtt_estimate(&self) -> Option<Duration> {
+                match &*self.inner {
+                    ServiceState::Active(service, status) if !status.is_cancelled() => {
+                        service.rtt_estimate()
+                    }
+                    _ => None,
+                }
+            }
         }
 
         pub fn test_request(method: Method, endpoint: &str) -> Request {
@@ -631,6 +824,58 @@ pub(crate) mod test {
         }
     }
 
+    #[test]
+    fn debug_info_for_direct_connection() {
+        let connection_info = ConnectionInfo {
+            route_type: RouteType::Direct,
+            dns_source: DnsSource::Cache,
+            address: Host::Ip(Ipv4Addr::new(192, 0, 2, 1).into()),
+            port: nonzero!(443u16),
+            websocket_sub_protocol: None,
+        };
+        let debug_info = DebugInfo::new(Some(&connection_info), Duration::from_secs(1));
+
+        assert_matches!(debug_info.ip_type, IpType::V4);
+        assert_eq!(debug_info.route_type, Some(RouteType::Direct));
+        assert_eq!(
+            debug_info.remote_address,
+            Some("192.0.2.1:443".parse().unwrap())
+        );
+        assert_eq!(
+            debug_info.connection_info,
+            "route=direct;dns_source=cache;ip_type=V4"
+        );
+    }
+
+    #[test]
+    fn debug_info_for_proxied_connection() {
+        let connection_info = ConnectionInfo {
+            route_type: RouteType::SocksProxy,
+            dns_source: DnsSource::Delegated,
+            address: Host::Ip("2001:db8::1".parse().unwrap()),
+            port: nonzero!(1080u16),
+            websocket_sub_protocol: None,
+        };
+        let debug_info = DebugInfo::new(Some(&connection_info), Duration::from_secs(1));
+
+        assert_matches!(debug_info.ip_type, IpType::V6);
+        assert_eq!(debug_info.route_type, Some(RouteType::SocksProxy));
+        assert_eq!(
+            debug_info.remote_address,
+            Some("[2001:db8::1]:1080".parse().unwrap())
+        );
+    }
+
+    #[test]
+    fn debug_info_without_connection() {
+        let debug_info = DebugInfo::new(None, Duration::from_secs(1));
+
+        assert_matches!(debug_info.ip_type, IpType::Unknown);
+        assert_eq!(debug_info.route_type, None);
+        assert_eq!(debug_info.remote_address, None);
+        assert_eq!(debug_info.connection_info, "");
+    }
+
     #[test]
     fn proto_into_response_works_with_valid_data() {
         let expected_body = b"content";
@@ -0,0 +1,294 @@
+//
+// Copyright 2024 Signal Messenger, LLC.
+// SPDX-License-Identifier: AGPL-3.0-only
+//
+
+//! Typed requests and responses for the challenges the chat server puts in front of a client.
+//!
+//! Registering a number that has registration lock enabled fails with 423 Locked, with a body
+//! saying how long the lock will last and, if the account has a PIN backed up to SVR2, the
+//! credentials needed to restore it. Requests that trip the server's rate limits fail with 428
+//! until the client answers a captcha or push challenge using [`submit_rate_limit_challenge`].
+
+use std::time::Duration;
+
+use http::{HeaderMap, HeaderValue, Method, StatusCode};
+use libsignal_net_infra::extract_retry_after_seconds;
+
+use crate::auth::Auth;
+use crate::chat::{ChatService, ChatServiceError, Request, Response};
+
+const CHALLENGE_PATH: &str = "/v1/challenge";
+
+/// The body of a 423 response to a registration request for a number with registration lock.
+#[derive(Clone)]
+pub struct RegistrationLockFailure {
+    /// How long until the lock expires if the account isn't used.
+    pub time_remaining: Duration,
+    /// Credentials for restoring the account's PIN from SVR2, if it has one backed up there.
+    pub svr2_credentials: Option<Auth>,
+}
+
+impl std::fmt::Debug for RegistrationLockFailure {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("RegistrationLockFailure")
+            .field("time_remaining", &self.time_remaining)
+            .field(
+                "svr2_credentials",
+                &self.svr2_credentials.as_ref().map(|_| "_"),
+            )
+            .finish()
+    }
+}
+
+impl RegistrationLockFailure {
+    /// Parses the JSON body of a 423 response.
+    pub fn from_body(body: &[u8]) -> Result<Self, serde_json::Error> {
+        #[derive(serde::Deserialize)]
+        #[serde(rename_all = "camelCase")]
+        struct Raw {
+            /// In milliseconds.
+            time_remaining: u64,
+            #[serde(default)]
+            svr2_credentials: Option<RawCredentials>,
+        }
+
+        #[derive(serde::Deserialize)]
+        struct RawCredentials {
+            username: String,
+            password: String,
+        }
+
+        let Raw {
+            time_remaining,
+            svr2_credentials,
+        } = serde_json::from_slice(body)?;
+        Ok(Self {
+            time_remaining: Duration::from_millis(time_remaining),
+            svr2_credentials: svr2_credentials
+                .map(|RawCredentials { username, password }| Auth { username, password }),
+        })
+    }
+}
+
+/// An answer to a rate limit challenge.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum RateLimitChallenge {
+    /// A solved captcha, along with the token from the 428 response that asked for it.
+    Captcha { token: String, captcha: String },
+    /// The challenge delivered to the device as a push notification.
+    PushToken(String),
+}
+
+#[derive(Debug, thiserror::Error, displaydoc::Display)]
+pub enum RateLimitChallengeError {
+    /// the server did not accept the challenge answer
+    Rejected,
+    /// rate limited; retry after {retry_after_seconds:?} seconds
+    RateLimited { retry_after_seconds: Option<u32> },
+    /// unexpected response status {0}
+    UnexpectedStatus(StatusCode),
+    /// {0}
+    Chat(#[from] ChatServiceError),
+}
+
+impl RateLimitChallenge {
+    /// The request that submits this answer to the chat server.
+    ///
+    /// The request must be sent over an authenticated connection.
+    pub fn to_request(&self) -> Request {
+        let body = match self {
+            Self::Captcha { token, captcha } => serde_json::json!({
+                "type": "captcha",
+                "token": token,
+                "captcha": captcha,
+            }),
+            Self::PushToken(challenge) => serde_json::json!({
+                "type": "rateLimitPushChallenge",
+                "challenge": challenge,
+            }),
+        };
+        Request {
+            method: Method::PUT,
+            path: http::uri::PathAndQuery::from_static(CHALLENGE_PATH),
+            headers: HeaderMap::from_iter([(
+                http::header::CONTENT_TYPE,
+                HeaderValue::from_static("application/json"),
+            )]),
+            body: Some(body.to_string().into_bytes().into_boxed_slice()),
+        }
+    }
+}
+
+/// Interprets the server's response to a submitted challenge answer.
+pub fn parse_rate_limit_challenge_response(
+    response: &Response,
+) -> Result<(), RateLimitChallengeError> {
+    match response.status {
+        status if status.is_success() => Ok(()),
+        StatusCode::PRECONDITION_REQUIRED => Err(RateLimitChallengeError::Rejected),
+        StatusCode::TOO_MANY_REQUESTS => Err(RateLimitChallengeError::RateLimited {
+            retry_after_seconds: extract_retry_after_seconds(&response.headers),
+        }),
+        status => Err(RateLimitChallengeError::UnexpectedStatus(status)),
+    }
+}
+
+/// Submits an answer to a rate limit challenge over an authenticated chat connection.
+pub async fn submit_rate_limit_challenge(
+    chat: &(impl ChatService + ?Sized),
+    challenge: &RateLimitChallenge,
+    timeout: Duration,
+) -> Result<(), RateLimitChallengeError> {
+    let response = chat.send(challenge.to_request(), timeout).await?;
+    parse_rate_limit_challenge_response(&response)
+}
+
+#[cfg(test)]
+mod test {
+    use assert_matches::assert_matches;
+
+    use super::*;
+
+    fn server_response(status: StatusCode, headers: &[(&'static str, &'static str)]) -> Response {
+        Response {
+            status,
+            message: None,
+            body: None,
+            headers: headers
+                .iter()
+                .map(|&(name, value)| {
+                    (
+                        http::HeaderName::from_static(name),
+                        HeaderValue::from_static(value),
+                    )
+                })
+                .collect(),
+        }
+    }
+
+    #[test]
+    fn registration_lock_with_credentials() {
+        let failure = RegistrationLockFailure::from_body(
+            br#"{
+                "timeRemaining": 604800000,
+                "svr2Credentials": {
+                    "username": "2d1c6a0c3c3f4e0b",
+                    "password": "1717171717:0123456789abcdef0123"
+                },
+                "backupCredentials": null
+            }"#,
+        )
+        .expect("valid");
+        assert_eq!(
+            failure.time_remaining,
+            Duration::from_secs(7 * 24 * 60 * 60)
+        );
+        let Auth { username, password } = failure.svr2_credentials.expect("has credentials");
+        assert_eq!(username, "2d1c6a0c3c3f4e0b");
+        assert_eq!(password, "1717171717:0123456789abcdef0123");
+    }
+
+    #[test]
+    fn registration_lock_without_credentials() {
+        for body in [
+            r#"{"timeRemaining": 1000}"#,
+            r#"{"timeRemaining": 1000, "svr2Credentials": null}"#,
+        ] {
+            let failure = RegistrationLockFailure::from_body(body.as_bytes()).expect("valid");
+            assert_eq!(failure.time_remaining, Duration::from_secs(1));
+            assert!(failure.svr2_credentials.is_none());
+        }
+    }
+
+    #[test]
+    fn registration_lock_with_malformed_credentials() {
+        assert_matches!(
+            RegistrationLockFailure::from_body(
+                br#"{"timeRemaining": 1000, "svr2Credentials": {"username": "2d1c6a0c3c3f4e0b"}}"#
+            ),
+            Err(_)
+        );
+        assert_matches!(
+            RegistrationLockFailure::from_body(
+                br#"{"timeRemaining": 1000, "svr2Credentials": "2d1c6a0c3c3f4e0b"}"#
+            ),
+            Err(_)
+        );
+    }
+
+    #[test]
+    fn challenge_requests() {
+        let request = RateLimitChallenge::Captcha {
+            token: "challenge-token".to_owned(),
+            captcha: "signal-hcaptcha.5fad97ac.challenge.solution".to_owned(),
+        }
+        .to_request();
+        assert_eq!(request.method, Method::PUT);
+        assert_eq!(request.path, CHALLENGE_PATH);
+        assert_eq!(
+            request.headers.get(http::header::CONTENT_TYPE),
+            Some(&HeaderValue::from_static("application/json"))
+        );
+        let body: serde_json::Value =
+            serde_json::from_slice(&request.body.expect("has body")).expect("JSON");
+        assert_eq!(
+            body,
+            serde_json::json!({
+                "type": "captcha",
+                "token": "challenge-token",
+                "captcha": "signal-hcaptcha.5fad97ac.challenge.solution",
+            })
+        );
+
+        let request = RateLimitChallenge::PushToken("push-challenge".to_owned()).to_request();
+        let body: serde_json::Value =
+            serde_json::from_slice(&request.body.expect("has body")).expect("JSON");
+        assert_eq!(
+            body,
+            serde_json::json!({
+                "type": "rateLimitPushChallenge",
+                "challenge": "push-challenge",
+            })
+        );
+    }
+
+    #[test]
+    fn challenge_responses() {
+        assert_matches!(
+            parse_rate_limit_challenge_response(&server_response(StatusCode::OK, &[])),
+            Ok(())
+        );
+        assert_matches!(
+            parse_rate_limit_challenge_response(&server_response(
+                StatusCode::PRECONDITION_REQUIRED,
+                &[]
+            )),
+            Err(RateLimitChallengeError::Rejected)
+        );
+        assert_matches!(
+            parse_rate_limit_challenge_response(&server_response(
+                StatusCode::TOO_MANY_REQUESTS,
+                &[("retry-after", "30")]
+            )),
+            Err(RateLimitChallengeError::RateLimited {
+                retry_after_seconds: Some(30)
+            })
+        );
+        assert_matches!(
+            parse_rate_limit_challenge_response(&server_response(
+                StatusCode::TOO_MANY_REQUESTS,
+                &[]
+            )),
+            Err(RateLimitChallengeError::RateLimited {
+                retry_after_seconds: None
+            })
+        );
+        assert_matches!(
+            parse_rate_limit_challenge_response(&server_response(StatusCode::BAD_REQUEST, &[])),
+            Err(RateLimitChallengeError::UnexpectedStatus(
+                StatusCode::BAD_REQUEST
+            ))
+        );
+    }
+}
@@ -3,11 +3,17 @@
 // SPDX-License-Identifier: AGPL-3.0-only
 //
 
-use libsignal_net_infra::connection_manager::{ErrorClass, ErrorClassifier};
+use std::time::Duration;
+
+use libsignal_net_infra::connection_manager::{
+    AttemptBreakdown, DeadlineConnectError, ErrorClass, ErrorClassifier,
+};
 use libsignal_net_infra::errors::{LogSafeDisplay, TransportConnectError};
 use libsignal_net_infra::service;
 use libsignal_net_infra::ws::{WebSocketConnectError, WebSocketServiceError};
 
+use crate::chat::TraceId;
+
 #[derive(Debug, thiserror::Error, displaydoc::Display)]
 pub enum ChatServiceError {
     /// websocket error: {0}
@@ -26,8 +32,11 @@ pub enum ChatServiceError {
     IncomingDataInvalid,
     /// Request object must contain only ASCII text as header names and values.
     RequestHasInvalidHeader,
-    /// Timeout
-    Timeout,
+    /// Chat request {trace_id} timed out after {elapsed:?}
+    Timeout {
+        trace_id: TraceId,
+        elapsed: Duration,
+    },
     /// Timed out while establishing connection after {attempts} attempts
     TimeoutEstablishingConnection { attempts: u16 },
     /// All connection routes failed or timed out, {attempts} attempts made
@@ -38,6 +47,22 @@ pub enum ChatServiceError {
     ServiceUnavailable,
     /// Service was disconnected by an intentional local call
     ServiceIntentionallyDisconnected,
+    /// Server is shutting down and asked the client to reconnect
+    ServerRequestedReconnect,
+    /// Connection closed before chat request {trace_id} received a response
+    RequestChannelClosed { trace_id: TraceId },
+}
+
+impl ChatServiceError {
+    /// The trace ID of the request that failed, if the error is specific to one request.
+    pub fn trace_id(&self) -> Option<TraceId> {
+        match self {
+            Self::Timeout { trace_id, .. } | Self::RequestChannelClosed { trace_id } => {
+                Some(*trace_id)
+            }
+            _ => None,
+        }
+    }
 }
 
 impl LogSafeDisplay for ChatServiceError {}
@@ -68,6 +93,9 @@ impl From<WebSocketConnectError> for ChatServiceError {
                 | TransportConnectError::SslFailedHandshake(_) => {
                     WebSocketServiceError::Other("TLS failure")
                 }
+                TransportConnectError::UntrustedRoot => {
+                    WebSocketServiceError::Other("untrusted server certificate")
+                }
                 TransportConnectError::CertError => {
                     WebSocketServiceError::Other("failed to load certificates")
                 }
@@ -76,8 +104,11 @@ impl From<WebSocketConnectError> for ChatServiceError {
                 }
             }
             .into(),
-            WebSocketConnectError::Timeout => Self::Timeout,
+            // There's no request to attribute this to, so treat it like any other connect
+            // timeout.
+            WebSocketConnectError::Timeout => Self::TimeoutEstablishingConnection { attempts: 1 },
             WebSocketConnectError::WebSocketError(e) => Self::WebSocket(e.into()),
+            WebSocketConnectError::SubProtocol(e) => Self::WebSocket(e.into()),
             WebSocketConnectError::RejectedByServer {
                 response,
                 received_at: _,
@@ -114,6 +145,23 @@ impl<E: LogSafeDisplay + Into<ChatServiceError>> From<service::ConnectError<E>>
     }
 }
 
+impl<E: LogSafeDisplay + Into<ChatServiceError>> From<DeadlineConnectError<E>>
+    for ChatServiceError
+{
+    fn from(e: DeadlineConnectError<E>) -> Self {
+        match e {
+            DeadlineConnectError::Fatal(e) => e.into(),
+            DeadlineConnectError::ConnectionTimedOut(AttemptBreakdown {
+                failed,
+                timed_out,
+                skipped_in_cooldown: _,
+            }) => Self::TimeoutEstablishingConnection {
+                attempts: failed.saturating_add(timed_out),
+            },
+        }
+    }
+}
+
 impl From<service::StateError> for ChatServiceError {
     fn from(e: service::StateError) -> Self {
         match e {
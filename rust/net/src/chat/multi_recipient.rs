@@ -0,0 +1,290 @@
+//
+// Copyright 2024 Signal Messenger, LLC.
+// SPDX-License-Identifier: AGPL-3.0-only
+//
+
+//! Typed parsing of the chat server's responses to multi-recipient (sealed sender v2) sends.
+//!
+//! A successful send returns a list of recipients that turned out to be unregistered. A send that
+//! didn't match the server's view of the recipients' devices is rejected with 409 (devices were
+//! added or removed) or 410 (devices were re-registered), with a body describing the mismatch for
+//! each affected recipient.
+
+use http::StatusCode;
+use libsignal_core::{DeviceId, ServiceId};
+
+use crate::chat::Response;
+
+/// The body of a successful multi-recipient send.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct MultiRecipientSendResponse {
+    /// Recipients that were skipped because they're no longer registered.
+    pub unregistered: Vec<ServiceId>,
+}
+
+/// How the devices a message was sent to differ from one recipient's registered devices.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct MismatchedDevices {
+    pub account: ServiceId,
+    /// Devices that were added since the sender last fetched the recipient's devices.
+    pub missing_devices: Vec<DeviceId>,
+    /// Devices that were removed since the sender last fetched the recipient's devices.
+    pub extra_devices: Vec<DeviceId>,
+    /// Devices that were re-registered, so the sender's sessions with them are out of date.
+    pub stale_devices: Vec<DeviceId>,
+}
+
+#[derive(Debug, thiserror::Error, displaydoc::Display)]
+pub enum ResponseBodyError {
+    /// response has no body
+    MissingBody,
+    /// response body does not have the expected structure: {0}
+    Json(#[from] serde_json::Error),
+    /// entry {index} has an invalid service ID
+    InvalidServiceId { index: usize },
+}
+
+#[derive(Debug, thiserror::Error, displaydoc::Display)]
+pub enum MultiRecipientSendError {
+    /// some recipients' devices have changed
+    MismatchedDevices(Vec<MismatchedDevices>),
+    /// unexpected response status {0}
+    UnexpectedStatus(StatusCode),
+    /// invalid response: {0}
+    InvalidResponse(#[from] ResponseBodyError),
+}
+
+impl MultiRecipientSendResponse {
+    /// Parses the JSON body of a 200 response.
+    pub fn from_body(body: &[u8]) -> Result<Self, ResponseBodyError> {
+        #[derive(serde::Deserialize)]
+        struct Raw {
+            uuids404: Vec<String>,
+        }
+
+        let Raw { uuids404 } = serde_json::from_slice(body)?;
+        let unregistered = uuids404
+            .iter()
+            .enumerate()
+            .map(|(index, id)| parse_service_id(index, id))
+            .collect::<Result<_, _>>()?;
+        Ok(Self { unregistered })
+    }
+}
+
+impl MismatchedDevices {
+    /// Parses the JSON body of a 409 or 410 response.
+    ///
+    /// Both have the same structure, but each only fills in the lists relevant to it.
+    pub fn list_from_body(body: &[u8]) -> Result<Vec<Self>, ResponseBodyError> {
+        #[derive(serde::Deserialize)]
+        struct RawEntry {
+            uuid: String,
+            devices: RawDevices,
+        }
+
+        #[derive(serde::Deserialize)]
+        #[serde(rename_all = "camelCase")]
+        struct RawDevices {
+            #[serde(default)]
+            missing_devices: Vec<u32>,
+            #[serde(default)]
+            extra_devices: Vec<u32>,
+            #[serde(default)]
+            stale_devices: Vec<u32>,
+        }
+
+        let entries: Vec<RawEntry> = serde_json::from_slice(body)?;
+        entries
+            .into_iter()
+            .enumerate()
+            .map(|(index, RawEntry { uuid, devices })| {
+                let RawDevices {
+                    missing_devices,
+                    extra_devices,
+                    stale_devices,
+                } = devices;
+                let to_device_ids = |ids: Vec<u32>| ids.into_iter().map(DeviceId::from).collect();
+                Ok(Self {
+                    account: parse_service_id(index, &uuid)?,
+                    missing_devices: to_device_ids(missing_devices),
+                    extra_devices: to_device_ids(extra_devices),
+                    stale_devices: to_device_ids(stale_devices),
+                })
+            })
+            .collect()
+    }
+}
+
+/// Interprets the server's response to a multi-recipient send.
+pub fn parse_multi_recipient_send_response(
+    response: &Response,
+) -> Result<MultiRecipientSendResponse, MultiRecipientSendError> {
+    let body = || {
+        response
+            .body
+            .as_deref()
+            .ok_or(ResponseBodyError::MissingBody)
+    };
+    match response.status {
+        StatusCode::OK => Ok(MultiRecipientSendResponse::from_body(body()?)?),
+        StatusCode::CONFLICT | StatusCode::GONE => Err(MultiRecipientSendError::MismatchedDevices(
+            MismatchedDevices::list_from_body(body()?)?,
+        )),
+        status => Err(MultiRecipientSendError::UnexpectedStatus(status)),
+    }
+}
+
+fn parse_service_id(index: usize, id: &str) -> Result<ServiceId, ResponseBodyError> {
+    ServiceId::parse_from_service_id_string(id).ok_or(ResponseBodyError::InvalidServiceId { index })
+}
+
+#[cfg(test)]
+mod test {
+    use assert_matches::assert_matches;
+    use http::HeaderMap;
+    use libsignal_core::{Aci, Pni};
+    use uuid::uuid;
+
+    use super::*;
+
+    const ACI: Aci =
+        Aci::from_uuid_bytes(uuid!("9d0652a3-dcc3-4d11-975f-74d61598733f").into_bytes());
+    const PNI: Pni =
+        Pni::from_uuid_bytes(uuid!("b36f2ad5-4f45-4cd4-9ec2-5d2e8c63a6d4").into_bytes());
+
+    fn server_response(status: StatusCode, body: &str) -> Response {
+        Response {
+            status,
+            message: None,
+            body: Some(body.as_bytes().into()),
+            headers: HeaderMap::new(),
+        }
+    }
+
+    #[test]
+    fn success_with_unregistered() {
+        let response = server_response(
+            StatusCode::OK,
+            r#"{"uuids404":["9d0652a3-dcc3-4d11-975f-74d61598733f","PNI:b36f2ad5-4f45-4cd4-9ec2-5d2e8c63a6d4"]}"#,
+        );
+        assert_eq!(
+            parse_multi_recipient_send_response(&response).expect("valid"),
+            MultiRecipientSendResponse {
+                unregistered: vec![ACI.into(), PNI.into()],
+            }
+        );
+    }
+
+    #[test]
+    fn success_with_no_unregistered() {
+        let response = server_response(StatusCode::OK, r#"{"uuids404":[]}"#);
+        assert_eq!(
+            parse_multi_recipient_send_response(&response).expect("valid"),
+            MultiRecipientSendResponse::default()
+        );
+    }
+
+    #[test]
+    fn mismatched_devices() {
+        let response = server_response(
+            StatusCode::CONFLICT,
+            r#"[
+                {"uuid":"9d0652a3-dcc3-4d11-975f-74d61598733f","devices":{"missingDevices":[3],"extraDevices":[2,4]}},
+                {"uuid":"PNI:b36f2ad5-4f45-4cd4-9ec2-5d2e8c63a6d4","devices":{"missingDevices":[1],"extraDevices":[]}}
+            ]"#,
+        );
+        let mismatched = assert_matches!(
+            parse_multi_recipient_send_response(&response),
+            Err(MultiRecipientSendError::MismatchedDevices(m)) => m
+        );
+        assert_eq!(
+            mismatched,
+            [
+                MismatchedDevices {
+                    account: ACI.into(),
+                    missing_devices: vec![3.into()],
+                    extra_devices: vec![2.into(), 4.into()],
+                    stale_devices: vec![],
+                },
+                MismatchedDevices {
+                    account: PNI.into(),
+                    missing_devices: vec![1.into()],
+                    extra_devices: vec![],
+                    stale_devices: vec![],
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn stale_devices() {
+        let response = server_response(
+            StatusCode::GONE,
+            r#"[{"uuid":"9d0652a3-dcc3-4d11-975f-74d61598733f","devices":{"staleDevices":[1,2]}}]"#,
+        );
+        let mismatched = assert_matches!(
+            parse_multi_recipient_send_response(&response),
+            Err(MultiRecipientSendError::MismatchedDevices(m)) => m
+        );
+        assert_eq!(
+            mismatched,
+            [MismatchedDevices {
+                account: ACI.into(),
+                missing_devices: vec![],
+                extra_devices: vec![],
+                stale_devices: vec![1.into(), 2.into()],
+            }]
+        );
+    }
+
+    #[test]
+    fn rejects_malformed_service_ids() {
+        let response = server_response(
+            StatusCode::OK,
+            r#"{"uuids404":["9d0652a3-dcc3-4d11-975f-74d61598733f","9d0652a3-dcc3-4d11"]}"#,
+        );
+        assert_matches!(
+            parse_multi_recipient_send_response(&response),
+            Err(MultiRecipientSendError::InvalidResponse(
+                ResponseBodyError::InvalidServiceId { index: 1 }
+            ))
+        );
+
+        let response = server_response(
+            StatusCode::GONE,
+            r#"[{"uuid":"ACI:9d0652a3-dcc3-4d11-975f-74d61598733f","devices":{"staleDevices":[1]}}]"#,
+        );
+        assert_matches!(
+            parse_multi_recipient_send_response(&response),
+            Err(MultiRecipientSendError::InvalidResponse(
+                ResponseBodyError::InvalidServiceId { index: 0 }
+            ))
+        );
+    }
+
+    #[test]
+    fn rejects_unexpected_responses() {
+        assert_matches!(
+            parse_multi_recipient_send_response(&server_response(StatusCode::OK, "[]")),
+            Err(MultiRecipientSendError::InvalidResponse(
+                ResponseBodyError::Json(_)
+            ))
+        );
+        assert_matches!(
+            parse_multi_recipient_send_response(&Response {
+                body: None,
+                ..server_response(StatusCode::CONFLICT, "")
+            }),
+            Err(MultiRecipientSendError::InvalidResponse(
+                ResponseBodyError::MissingBody
+            ))
+        );
+        assert_matches!(
+            parse_multi_recipient_send_response(&server_response(StatusCode::UNAUTHORIZED, "")),
+            Err(MultiRecipientSendError::UnexpectedStatus(
+                StatusCode::UNAUTHORIZED
+            ))
+        );
+    }
+}
@@ -0,0 +1,384 @@
+//
+// Copyright 2024 Signal Messenger, LLC.
+// SPDX-License-Identifier: AGPL-3.0-only
+//
+
+//! Reusing unauthenticated chat connections across one-shot requests.
+//!
+//! Requests like profile fetches tend to come in bursts, and don't need a long-lived connection.
+//! Rather than connecting and disconnecting for each one, an [`UnauthChatPool`] keeps a few
+//! recently-used connections open for a little while in case another request comes along.
+
+use std::collections::VecDeque;
+use std::sync::{Arc, Mutex, Weak};
+use std::time::Duration;
+
+use futures_util::future::BoxFuture;
+use futures_util::FutureExt as _;
+use libsignal_net_infra::connection_manager::ConnectionManager;
+use libsignal_net_infra::service::{CancellationReason, CancellationToken, ServiceInitializer};
+use libsignal_net_infra::timeouts::MULTI_ROUTE_CONNECTION_TIMEOUT;
+use libsignal_net_infra::ws::WebSocketClientConnector;
+use libsignal_net_infra::TransportConnector;
+use tokio::sync::mpsc;
+use tokio::time::Instant;
+
+use crate::chat::ws::ChatOverWebSocketServiceConnector;
+use crate::chat::{ChatService, ChatServiceError, Request, Response};
+
+/// How many connections an [`UnauthChatPool`] keeps, and for how long.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct UnauthChatPoolConfig {
+    /// The most connections to keep open while they're not being used.
+    pub max_idle_connections: usize,
+    /// How long an unused connection is kept open.
+    pub idle_ttl: Duration,
+    /// How long to spend trying to connect when there's no idle connection to use.
+    pub connection_timeout: Duration,
+}
+
+impl Default for UnauthChatPoolConfig {
+    fn default() -> Self {
+        Self {
+            max_idle_connections: 2,
+            idle_ttl: Duration::from_secs(30),
+            connection_timeout: MULTI_ROUTE_CONNECTION_TIMEOUT,
+        }
+    }
+}
+
+/// Expired connections are looked for every half TTL, but no more often than this.
+const MIN_REAP_INTERVAL: Duration = Duration::from_secs(1);
+
+type PooledService = Arc<dyn ChatService + Send + Sync>;
+
+type ConnectFn = Box<
+    dyn Fn(Instant) -> BoxFuture<'static, Result<PooledConnection, ChatServiceError>> + Send + Sync,
+>;
+
+/// Unauthenticated chat connections that can be reused for one-shot requests.
+///
+/// Connections are made as needed by [`Self::send_and_release`], and kept around afterwards for
+/// up to [`UnauthChatPoolConfig::idle_ttl`]. A background task closes connections that have
+/// expired, so a pool must be created from within a Tokio runtime.
+///
+/// Pooled connections are only meant for requests; if the server sends a request of its own on
+/// one, the connection is closed.
+pub struct UnauthChatPool {
+    connect: ConnectFn,
+    config: UnauthChatPoolConfig,
+    state: Arc<Mutex<PoolState>>,
+}
+
+struct PooledConnection {
+    service: PooledService,
+    status: CancellationToken,
+}
+
+struct IdleConnection {
+    connection: PooledConnection,
+    idle_since: Instant,
+}
+
+#[derive(Default)]
+struct PoolState {
+    /// Idle connections, least recently used first.
+    idle: VecDeque<IdleConnection>,
+    is_shut_down: bool,
+}
+
+impl UnauthChatPool {
+    pub fn new<T, M>(
+        ws_client_connector: WebSocketClientConnector<T, ChatServiceError>,
+        connection_manager: M,
+        config: UnauthChatPoolConfig,
+    ) -> Self
+    where
+        T: TransportConnector + 'static,
+        M: ConnectionManager + 'static,
+    {
+        let connect = move |deadline| {
+            // Each connection needs its own connector, since a connector only lets one of its
+            // connections read at a time. Nothing is listening for requests from the server.
+            let (incoming_tx, _incoming_rx) = mpsc::channel(1);
+            let initializer = ServiceInitializer::new(
+                ChatOverWebSocketServiceConnector::new(ws_client_connector.clone(), incoming_tx),
+                connection_manager.clone(),
+            );
+            async move {
+                let (service, status) = initializer.connect_with_deadline(deadline).await?;
+                Ok(PooledConnection {
+                    service: Arc::new(service),
+                    status,
+                })
+            }
+            .boxed()
+        };
+
+        let state = Arc::new(Mutex::new(PoolState::default()));
+        tokio::spawn(reap_expired(Arc::downgrade(&state), config.idle_ttl));
+
+        Self {
+            connect: Box::new(connect),
+            config,
+            state,
+        }
+    }
+
+    /// Sends `request` on an idle connection, or a new one if there are none, then returns the
+    /// connection to the pool.
+    pub async fn send_and_release(
+        &self,
+        request: Request,
+        timeout: Duration,
+    ) -> Result<Response, ChatServiceError> {
+        let connection = match self.checkout()? {
+            Some(connection) => connection,
+            None => {
+                log::debug!("no idle unauth chat connection, connecting");
+                (self.connect)(Instant::now() + self.config.connection_timeout).await?
+            }
+        };
+        let result = connection.service.send(request, timeout).await;
+        self.release(connection);
+        result
+    }
+
+    /// Closes all idle connections, and stops accepting requests.
+    ///
+    /// Requests that are already in progress are allowed to finish, after which their
+    /// connections are closed too.
+    pub fn shutdown(&self) {
+        let mut state = self.state.lock().expect("not poisoned");
+        state.is_shut_down = true;
+        for IdleConnection { connection, .. } in state.idle.drain(..) {
+            connection.disconnect();
+        }
+    }
+
+    /// Takes the most recently used idle connection that's still usable, if any.
+    fn checkout(&self) -> Result<Option<PooledConnection>, ChatServiceError> {
+        let mut state = self.state.lock().expect("not poisoned");
+        if state.is_shut_down {
+            return Err(ChatServiceError::ServiceIntentionallyDisconnected);
+        }
+        state.reap(Instant::now(), self.config.idle_ttl);
+        Ok(state
+            .idle
+            .pop_back()
+            .map(|IdleConnection { connection, .. }| connection))
+    }
+
+    fn release(&self, connection: PooledConnection) {
+        let mut state = self.state.lock().expect("not poisoned");
+        if state.is_shut_down || connection.status.is_cancelled() {
+            connection.disconnect();
+            return;
+        }
+        state.idle.push_back(IdleConnection {
+            connection,
+            idle_since: Instant::now(),
+        });
+        while state.idle.len() > self.config.max_idle_connections {
+            let IdleConnection { connection, .. } = state.idle.pop_front().expect("more than zero");
+            connection.disconnect();
+        }
+    }
+}
+
+impl Drop for UnauthChatPool {
+    fn drop(&mut self) {
+        self.shutdown()
+    }
+}
+
+impl PooledConnection {
+    fn disconnect(self) {
+        self.status.cancel(CancellationReason::ExplicitDisconnect)
+    }
+}
+
+impl PoolState {
+    /// Closes and removes idle connections that have expired or been closed by the server.
+    fn reap(&mut self, now: Instant, idle_ttl: Duration) {
+        for IdleConnection {
+            connection,
+            idle_since,
+        } in std::mem::take(&mut self.idle)
+        {
+            if connection.status.is_cancelled() {
+                log::debug!("discarding closed unauth chat connection");
+            } else if now.saturating_duration_since(idle_since) >= idle_ttl {
+                connection.disconnect();
+            } else {
+                self.idle.push_back(IdleConnection {
+                    connection,
+                    idle_since,
+                });
+            }
+        }
+    }
+}
+
+async fn reap_expired(state: Weak<Mutex<PoolState>>, idle_ttl: Duration) {
+    let mut interval = tokio::time::interval((idle_ttl / 2).max(MIN_REAP_INTERVAL));
+    loop {
+        interval.tick().await;
+        let Some(state) = state.upgrade() else {
+            return;
+        };
+        let mut state = state.lock().expect("not poisoned");
+        if state.is_shut_down {
+            return;
+        }
+        state.reap(Instant::now(), idle_ttl);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    use assert_matches::assert_matches;
+    use futures_util::{SinkExt as _, StreamExt as _};
+    use http::uri::PathAndQuery;
+    use http::{Method, StatusCode};
+    use libsignal_net_infra::testutil::{InMemoryWarpConnector, TIMEOUT_DURATION};
+    use libsignal_net_infra::ws::WebSocketConfig;
+    use prost::Message as _;
+    use warp::Filter as _;
+
+    use super::*;
+    use crate::chat::test::shared::{connection_manager, test_request};
+    use crate::chat::{ChatMessageType, MessageProto, ResponseProto};
+
+    const IDLE_TTL: Duration = Duration::from_secs(10);
+
+    fn test_ws_config() -> WebSocketConfig {
+        WebSocketConfig {
+            ws_config: tungstenite::protocol::WebSocketConfig::default(),
+            endpoint: PathAndQuery::from_static("/test"),
+            max_connection_time: Duration::from_secs(1),
+            keep_alive_interval: Duration::from_secs(5),
+            max_idle_time: Duration::from_secs(15),
+            sub_protocols: &[],
+            draining_close_code: None,
+        }
+    }
+
+    /// Creates a pool connected to a fake server that responds to every request with 200, along
+    /// with a count of the connections made to that server.
+    fn pool_with_fake_server() -> (UnauthChatPool, Arc<AtomicUsize>) {
+        let connection_count = Arc::new(AtomicUsize::new(0));
+        let server = {
+            let connection_count = connection_count.clone();
+            warp::any().and(warp::ws()).map(move |ws: warp::ws::Ws| {
+                connection_count.fetch_add(1, Ordering::SeqCst);
+                ws.on_upgrade(|websocket| async move {
+                    let (mut tx, mut rx) = websocket.split();
+                    while let Some(Ok(message)) = rx.next().await {
+                        if !message.is_binary() {
+                            continue;
+                        }
+                        let request = MessageProto::decode(message.as_bytes())
+                            .expect("valid message")
+                            .request
+                            .expect("is a request");
+                        let response = MessageProto {
+                            r#type: Some(ChatMessageType::Response.into()),
+                            request: None,
+                            response: Some(ResponseProto {
+                                id: request.id,
+                                status: Some(StatusCode::OK.as_u16().into()),
+                                message: None,
+                                headers: vec![],
+                                body: None,
+                            }),
+                        };
+                        tx.send(warp::ws::Message::binary(response.encode_to_vec()))
+                            .await
+                            .expect("can send");
+                    }
+                })
+            })
+        };
+
+        let pool = UnauthChatPool::new(
+            WebSocketClientConnector::new(InMemoryWarpConnector::new(server), test_ws_config()),
+            connection_manager(),
+            UnauthChatPoolConfig {
+                idle_ttl: IDLE_TTL,
+                ..Default::default()
+            },
+        );
+        (pool, connection_count)
+    }
+
+    async fn send(pool: &UnauthChatPool) -> Result<Response, ChatServiceError> {
+        pool.send_and_release(test_request(Method::GET, "/"), TIMEOUT_DURATION)
+            .await
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn second_request_reuses_connection() {
+        let (pool, connection_count) = pool_with_fake_server();
+
+        assert_eq!(send(&pool).await.expect("success").status, StatusCode::OK);
+        assert_eq!(send(&pool).await.expect("success").status, StatusCode::OK);
+        assert_eq!(connection_count.load(Ordering::SeqCst), 1);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn expired_connection_is_replaced() {
+        let (pool, connection_count) = pool_with_fake_server();
+
+        send(&pool).await.expect("success");
+        // The reaper runs every half TTL, so by now it's had a chance to close the connection.
+        tokio::time::sleep(IDLE_TTL * 2).await;
+        assert!(pool.state.lock().expect("not poisoned").idle.is_empty());
+
+        send(&pool).await.expect("success");
+        assert_eq!(connection_count.load(Ordering::SeqCst), 2);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn closed_connection_is_replaced() {
+        let (pool, connection_count) = pool_with_fake_server();
+
+        send(&pool).await.expect("success");
+        {
+            let state = pool.state.lock().expect("not poisoned");
+            let idle = state.idle.back().expect("released");
+            idle.connection
+                .status
+                .cancel(CancellationReason::RemoteClose);
+        }
+
+        send(&pool).await.expect("success");
+        assert_eq!(connection_count.load(Ordering::SeqCst), 2);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn shutdown_closes_idle_connections() {
+        let (pool, _connection_count) = pool_with_fake_server();
+
+        send(&pool).await.expect("success");
+        let status = pool
+            .state
+            .lock()
+            .expect("not poisoned")
+            .idle
+            .back()
+            .expect("released")
+            .connection
+            .status
+            .clone();
+
+        pool.shutdown();
+        assert!(status.is_cancelled());
+        assert_matches!(
+            send(&pool).await,
+            Err(ChatServiceError::ServiceIntentionallyDisconnected)
+        );
+    }
+}
@@ -5,8 +5,10 @@
 
 use futures_util::future::BoxFuture;
 use futures_util::Stream;
+use libsignal_core::ServiceId;
 use libsignal_net_infra::AsyncDuplexStream;
 use libsignal_protocol::Timestamp;
+use prost::Message as _;
 use tokio::sync::mpsc;
 use tokio_stream::wrappers::ReceiverStream;
 use tokio_stream::StreamExt as _;
@@ -14,6 +16,7 @@ use tokio_stream::StreamExt as _;
 use crate::chat::ws::ServerEvent as WsServerEvent;
 use crate::chat::ChatServiceError;
 use crate::env::TIMESTAMP_HEADER_NAME;
+use crate::proto::envelope as proto;
 
 pub type ResponseEnvelopeSender = Box<
     dyn FnOnce(http::StatusCode) -> BoxFuture<'static, Result<(), ChatServiceError>> + Send + Sync,
@@ -27,6 +30,19 @@ pub enum ServerEvent {
         server_delivery_timestamp: Timestamp,
         send_ack: ResponseEnvelopeSender,
     },
+    /// A delivery receipt the server generated for messages sent by this account.
+    ///
+    /// Recognized from the envelope's unencrypted fields alone; anything that doesn't look exactly
+    /// like a server receipt is delivered as [`ServerEvent::IncomingMessage`] instead.
+    Receipt {
+        request_id: u64,
+        sender: ServiceId,
+        timestamps: Vec<Timestamp>,
+        /// The envelope the receipt was parsed from, for consumers that process it as-is.
+        envelope: Vec<u8>,
+        server_delivery_timestamp: Timestamp,
+        send_ack: ResponseEnvelopeSender,
+    },
     Stopped(ChatServiceError),
 }
 
@@ -45,6 +61,21 @@ impl std::fmt::Debug for ServerEvent {
                 .field("envelope", &format_args!("{} bytes", envelope.len()))
                 .field("server_delivery_timestamp", server_delivery_timestamp)
                 .finish(),
+            Self::Receipt {
+                request_id,
+                sender,
+                timestamps,
+                envelope,
+                server_delivery_timestamp,
+                send_ack: _,
+            } => f
+                .debug_struct("Receipt")
+                .field("request_id", request_id)
+                .field("sender", &sender.service_id_string())
+                .field("timestamps", timestamps)
+                .field("envelope", &format_args!("{} bytes", envelope.len()))
+                .field("server_delivery_timestamp", server_delivery_timestamp)
+                .finish(),
             Self::Stopped(error) => f
                 .debug_struct("ConnectionInterrupted")
                 .field("reason", error)
@@ -94,15 +125,28 @@ pub fn stream_incoming_messages(
                     // We don't check whether the body is missing here. The consumer still needs to ack
                     // malformed envelopes, or they'd be delivered over and over, and an empty envelope
                     // is just a special case of a malformed envelope.
-                    ServerEvent::IncomingMessage {
-                        request_id: request_proto.id(),
-                        envelope: request_proto.body.unwrap_or_default(),
-                        server_delivery_timestamp: Timestamp::from_epoch_millis(
-                            raw_timestamp.unwrap_or_default(),
-                        ),
-                        send_ack: Box::new(|status| {
-                            Box::pin(response_sender.send_response(status))
-                        }),
+                    let request_id = request_proto.id();
+                    let envelope = request_proto.body.unwrap_or_default();
+                    let server_delivery_timestamp =
+                        Timestamp::from_epoch_millis(raw_timestamp.unwrap_or_default());
+                    let send_ack: ResponseEnvelopeSender =
+                        Box::new(|status| Box::pin(response_sender.send_response(status)));
+
+                    match parse_server_delivery_receipt(&envelope) {
+                        Some((sender, timestamps)) => ServerEvent::Receipt {
+                            request_id,
+                            sender,
+                            timestamps,
+                            envelope,
+                            server_delivery_timestamp,
+                            send_ack,
+                        },
+                        None => ServerEvent::IncomingMessage {
+                            request_id,
+                            envelope,
+                            server_delivery_timestamp,
+                            send_ack,
+                        },
                     }
                 }
                 "" => {
@@ -118,3 +162,136 @@ pub fn stream_incoming_messages(
         }
     })
 }
+
+/// Extracts the sender and receipted timestamps from `envelope` if it's a server delivery receipt.
+///
+/// Only the envelope's unencrypted fields are looked at. Returns `None` for any other kind of
+/// envelope, as well as for receipts that can't be parsed.
+fn parse_server_delivery_receipt(envelope: &[u8]) -> Option<(ServiceId, Vec<Timestamp>)> {
+    let proto::Envelope {
+        r#type,
+        timestamp,
+        source_service_id,
+    } = proto::Envelope::decode(envelope).ok()?;
+
+    if r#type != Some(proto::envelope::Type::ServerDeliveryReceipt.into()) {
+        return None;
+    }
+    let sender = ServiceId::parse_from_service_id_string(source_service_id.as_deref()?)?;
+    // The server sends a separate receipt for each message, identified by its sent timestamp.
+    let timestamp = Timestamp::from_epoch_millis(timestamp?);
+    Some((sender, vec![timestamp]))
+}
+
+#[cfg(test)]
+mod test {
+    use assert_matches::assert_matches;
+    use libsignal_core::Aci;
+    use tokio::io::DuplexStream;
+    use tokio_stream::StreamExt as _;
+    use uuid::uuid;
+
+    use super::*;
+    use crate::chat::RequestProto;
+
+    const SENDER: Aci =
+        Aci::from_uuid_bytes(uuid!("9d0652a3-dcc3-4d11-975f-74d61598733f").into_bytes());
+    const SENT_TIMESTAMP: u64 = 1_700_000_000_000;
+    const DELIVERY_TIMESTAMP: u64 = 1_700_000_001_234;
+
+    fn envelope(r#type: Option<proto::envelope::Type>, sender: Option<&str>) -> Vec<u8> {
+        proto::Envelope {
+            r#type: r#type.map(Into::into),
+            timestamp: Some(SENT_TIMESTAMP),
+            source_service_id: sender.map(Into::into),
+        }
+        .encode_to_vec()
+    }
+
+    async fn classify(envelope: Vec<u8>) -> ServerEvent {
+        let (tx, rx) = mpsc::channel(1);
+        tx.send(WsServerEvent::<DuplexStream>::fake(RequestProto {
+            verb: Some(http::Method::PUT.to_string()),
+            path: Some("/api/v1/message".to_owned()),
+            body: Some(envelope),
+            headers: vec![format!("{TIMESTAMP_HEADER_NAME}: {DELIVERY_TIMESTAMP}")],
+            id: Some(5),
+        }))
+        .await
+        .expect("not closed");
+        drop(tx);
+
+        let mut events = std::pin::pin!(stream_incoming_messages(rx));
+        let event = events.next().await.expect("one event");
+        assert_matches!(events.next().await, None);
+        event
+    }
+
+    #[tokio::test]
+    async fn server_delivery_receipt() {
+        let envelope = envelope(
+            Some(proto::envelope::Type::ServerDeliveryReceipt),
+            Some(&SENDER.service_id_string()),
+        );
+        let (sender, timestamps, receipt_envelope, server_delivery_timestamp) = assert_matches!(
+            classify(envelope.clone()).await,
+            ServerEvent::Receipt {
+                request_id: 5,
+                sender,
+                timestamps,
+                envelope,
+                server_delivery_timestamp,
+                send_ack: _,
+            } => (sender, timestamps, envelope, server_delivery_timestamp)
+        );
+        assert_eq!(sender, SENDER.into());
+        assert_eq!(timestamps, [Timestamp::from_epoch_millis(SENT_TIMESTAMP)]);
+        assert_eq!(receipt_envelope, envelope);
+        assert_eq!(
+            server_delivery_timestamp,
+            Timestamp::from_epoch_millis(DELIVERY_TIMESTAMP)
+        );
+    }
+
+    #[tokio::test]
+    async fn client_messages_are_not_receipts() {
+        for r#type in [
+            proto::envelope::Type::Ciphertext,
+            proto::envelope::Type::PrekeyBundle,
+            proto::envelope::Type::UnidentifiedSender,
+            proto::envelope::Type::PlaintextContent,
+        ] {
+            let envelope = envelope(Some(r#type), Some(&SENDER.service_id_string()));
+            let incoming_envelope = assert_matches!(
+                classify(envelope.clone()).await,
+                ServerEvent::IncomingMessage { request_id: 5, envelope, .. } => envelope
+            );
+            assert_eq!(incoming_envelope, envelope, "{type:?}");
+        }
+    }
+
+    #[tokio::test]
+    async fn malformed_receipts_fall_back_to_incoming_message() {
+        for envelope in [
+            envelope(Some(proto::envelope::Type::ServerDeliveryReceipt), None),
+            envelope(
+                Some(proto::envelope::Type::ServerDeliveryReceipt),
+                Some("not a service ID"),
+            ),
+            envelope(None, Some(&SENDER.service_id_string())),
+            proto::Envelope {
+                r#type: Some(proto::envelope::Type::ServerDeliveryReceipt.into()),
+                timestamp: None,
+                source_service_id: Some(SENDER.service_id_string()),
+            }
+            .encode_to_vec(),
+            b"not a protobuf".to_vec(),
+            vec![],
+        ] {
+            assert_matches!(
+                classify(envelope).await,
+                ServerEvent::IncomingMessage { .. }
+            );
+        }
+    }
+}
@@ -13,7 +13,8 @@ use libsignal_net_infra::service::{RemoteAddressInfo, Service, ServiceConnector}
 use tokio::time::Instant;
 
 use crate::chat::{
-    ChatService, ChatServiceError, ChatServiceWithDebugInfo, DebugInfo, IpType, Request, Response,
+    ChatService, ChatServiceError, ChatServiceWithDebugInfo, DebugInfo, Request,
+    RequestWithBodyStream, Response,
 };
 
 #[async_trait]
@@ -30,6 +31,14 @@ where
         self.service().await?.send(msg, timeout).await
     }
 
+    async fn send_streaming(
+        &self,
+        msg: RequestWithBodyStream,
+        timeout: Duration,
+    ) -> Result<Response, ChatServiceError> {
+        self.service().await?.send_streaming(msg, timeout).await
+    }
+
     async fn connect(&self) -> Result<(), ChatServiceError> {
         Ok(self.connect().await?)
     }
@@ -37,6 +46,10 @@ where
     async fn disconnect(&self) {
         self.disconnect().await;
     }
+
+    fn rtt_estimate(&self) -> Option<Duration> {
+        self.try_service()?.rtt_estimate()
+    }
 }
 
 #[async_trait]
@@ -57,7 +70,7 @@ where
         let start = Instant::now();
         let deadline = start + timeout;
         let service = self.service().await;
-        let (response, ip_type, connection_info) = match service {
+        let (response, connection_info) = match service {
             Ok(s) => {
                 let method_for_log = msg.method.clone();
                 let path_for_log_without_query = msg.path.path().to_owned();
@@ -76,23 +89,12 @@ where
                     );
                 }
 
-                (
-                    result,
-                    IpType::from_host(&s.connection_info().address),
-                    s.connection_info().description(),
-                )
+                (result, Some(s.connection_info()))
             }
-            Err(e) => (Err(e.into()), IpType::Unknown, "".to_string()),
+            Err(e) => (Err(e.into()), None),
         };
         let duration = start.elapsed();
-        (
-            response,
-            DebugInfo {
-                ip_type,
-                duration,
-                connection_info,
-            },
-        )
+        (response, DebugInfo::new(connection_info.as_ref(), duration))
     }
 
     async fn connect_and_debug(&self) -> Result<DebugInfo, ChatServiceError> {
@@ -101,13 +103,7 @@ where
         self.connect().await?;
 
         let connection_info = self.connection_info().await?;
-        let ip_type = IpType::from_host(&connection_info.address);
-        let connection_info = connection_info.description();
         let duration = start.elapsed();
-        Ok(DebugInfo {
-            ip_type,
-            duration,
-            connection_info,
-        })
+        Ok(DebugInfo::new(Some(&connection_info), duration))
     }
 }
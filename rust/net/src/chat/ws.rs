@@ -3,46 +3,60 @@
 // SPDX-License-Identifier: AGPL-3.0-only
 //
 
-use std::collections::{HashMap, VecDeque};
+use std::collections::VecDeque;
 use std::sync::Arc;
 use std::time::Duration;
 
 use async_trait::async_trait;
 use derive_where::derive_where;
-use futures_util::FutureExt;
-use http::header::ToStrError;
+use http::header::{HeaderMap, HeaderValue, ToStrError};
 use http::status::StatusCode;
+use libsignal_net_infra::live_connections::{self, LiveConnection};
 use libsignal_net_infra::service::{
     CancellationReason, CancellationToken, RemoteAddressInfo, ServiceConnector,
 };
 use libsignal_net_infra::ws::{
-    NextOrClose, TextOrBinary, WebSocketClient, WebSocketClientConnector, WebSocketClientReader,
-    WebSocketClientWriter, WebSocketConnectError, WebSocketServiceError,
+    NextOrClose, RttEstimator, TextOrBinary, WebSocketClient, WebSocketClientConnector,
+    WebSocketClientReader, WebSocketClientWriter, WebSocketConnectError, WebSocketServiceError,
 };
 use libsignal_net_infra::{
-    AsyncDuplexStream, ConnectionInfo, ConnectionParams, TransportConnector,
+    logging, AsyncDuplexStream, ConnectionInfo, ConnectionParams, TransportConnector,
 };
 use prost::Message;
+use tokio::io::AsyncReadExt as _;
 use tokio::sync::{mpsc, oneshot, Mutex};
 use tokio::time::Instant;
 use tokio_tungstenite::WebSocketStream;
 
+use crate::auth::{Auth, AuthProvider};
 use crate::chat::{
-    ChatMessageType, ChatService, ChatServiceError, MessageProto, Request, RequestProto, Response,
-    ResponseProto,
+    ChatMessageType, ChatService, ChatServiceError, MessageProto, Request, RequestProto,
+    RequestWithBodyStream, Response, ResponseProto, TraceId,
 };
+use crate::env::TRACE_ID_HEADER_NAME;
 use crate::proto::chat_websocket::web_socket_message::Type;
 
-#[derive(Debug, Default, Eq, Hash, PartialEq, Clone, Copy)]
-struct RequestId {
-    id: u64,
-}
+mod health;
+use health::RequestLatencies;
+pub use health::{ConnectionHealth, LATENCY_WINDOW_SIZE, MIN_LATENCY_SAMPLES};
 
-impl RequestId {
-    const fn new(id: u64) -> Self {
-        Self { id }
-    }
-}
+mod pacing;
+pub use pacing::BatchPacing;
+
+mod pending;
+#[cfg(feature = "test-util")]
+pub use pending::{NoMoreRequests, PendingMessagesMap, RequestId};
+#[cfg(not(feature = "test-util"))]
+use pending::{PendingMessagesMap, RequestId};
+
+mod state;
+use state::ConnectionLifecycle;
+pub use state::{ConnectionState, StopReason};
+
+/// How long handling a single server request can take before it's worth warning about.
+///
+/// Requests are handled one at a time, so a slow handler holds up everything after it.
+pub const LONG_REQUEST_PROCESSING_THRESHOLD: Duration = Duration::from_millis(500);
 
 enum ChatMessage {
     Request(RequestProto),
@@ -105,45 +119,6 @@ impl<S: AsyncDuplexStream> ServerEvent<S> {
     }
 }
 
-#[derive(Debug, Default)]
-struct PendingMessagesMap {
-    pending: HashMap<RequestId, oneshot::Sender<ResponseProto>>,
-    next_id: u64,
-}
-
-struct NoMoreRequests;
-
-impl PendingMessagesMap {
-    const CANCELLED: u64 = u64::MAX;
-
-    fn insert(
-        &mut self,
-        responder: oneshot::Sender<ResponseProto>,
-    ) -> Result<RequestId, NoMoreRequests> {
-        if self.next_id == Self::CANCELLED {
-            return Err(NoMoreRequests);
-        }
-
-        let id = RequestId::new(self.next_id);
-        let prev = self.pending.insert(id, responder);
-        assert!(
-            prev.is_none(),
-            "IDs are picked uniquely and shouldn't wrap around in a reasonable amount of time"
-        );
-        self.next_id += 1;
-        Ok(id)
-    }
-
-    fn remove(&mut self, id: &RequestId) -> Option<oneshot::Sender<ResponseProto>> {
-        self.pending.remove(id)
-    }
-
-    fn cancel_all(&mut self) {
-        self.next_id = Self::CANCELLED;
-        self.pending.clear();
-    }
-}
-
 #[derive_where(Clone)]
 pub(super) struct ChatOverWebSocketServiceConnector<T: TransportConnector> {
     ws_client_connector: WebSocketClientConnector<T, ChatServiceError>,
@@ -168,6 +143,14 @@ impl<T: TransportConnector> ServiceConnector for ChatOverWebSocketServiceConnect
     type Channel = (WebSocketStream<T::Stream>, ConnectionInfo);
     type ConnectError = WebSocketConnectError;
 
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(
+            name = "chat.connect_channel",
+            skip_all,
+            fields(route = %connection_params.route_type),
+        )
+    )]
     async fn connect_channel(
         &self,
         connection_params: &ConnectionParams,
@@ -184,39 +167,48 @@ impl<T: TransportConnector> ServiceConnector for ChatOverWebSocketServiceConnect
             ws_client_reader,
             connection_info,
         } = ws_client;
-        let pending_messages: Arc<Mutex<PendingMessagesMap>> = Default::default();
+        let pending_messages: Arc<PendingMessagesMap> = Default::default();
+        let lifecycle = Arc::new(ConnectionLifecycle::new(service_status.clone()));
+        let rtt_estimator = ws_client_reader.rtt_estimator().clone();
         tokio::spawn(reader_task(
+            LiveConnection::new(live_connections::CHAT),
             ws_client_reader,
             ws_client_writer.clone(),
             self.incoming_tx.clone(),
             pending_messages.clone(),
-            service_status.clone(),
+            lifecycle.clone(),
         ));
         (
             ChatOverWebSocket {
                 ws_client_writer,
-                service_cancellation: service_status.clone(),
+                lifecycle,
                 pending_messages,
                 connection_info,
+                rtt_estimator,
+                latencies: RequestLatencies::default(),
             },
             service_status,
         )
     }
 }
 
+#[cfg_attr(
+    feature = "tracing",
+    tracing::instrument(name = "chat.reader_task", skip_all)
+)]
 async fn reader_task<S: AsyncDuplexStream + 'static>(
+    mut live: LiveConnection,
     mut ws_client_reader: WebSocketClientReader<S, ChatServiceError>,
     ws_client_writer: WebSocketClientWriter<S, ChatServiceError>,
     incoming_tx: Arc<Mutex<mpsc::Sender<ServerEvent<S>>>>,
-    pending_messages: Arc<Mutex<PendingMessagesMap>>,
-    service_cancellation: CancellationToken,
+    pending_messages: Arc<PendingMessagesMap>,
+    lifecycle: Arc<ConnectionLifecycle>,
 ) {
-    const LONG_REQUEST_PROCESSING_THRESHOLD: Duration = Duration::from_millis(500);
-
     // Hold the ServerEvent Sender exclusively while the reader task is alive. This prevents two
     // reader tasks from being active at once, interleaving their events. Note that ServerEvents
     // that don't come from ChatOverWebSocketServiceConnector still won't be synchronized.
     let incoming_tx = incoming_tx.lock().await;
+    lifecycle.set_active();
 
     let mut previous_request_paths_for_logging =
         VecDeque::with_capacity(incoming_tx.max_capacity());
@@ -225,28 +217,31 @@ async fn reader_task<S: AsyncDuplexStream + 'static>(
         let data = match ws_client_reader.next().await {
             Ok(NextOrClose::Next(TextOrBinary::Binary(data))) => data,
             Ok(NextOrClose::Next(TextOrBinary::Text(_))) => {
-                log::info!("Text frame received on chat websocket");
-                service_cancellation.cancel(CancellationReason::ProtocolError);
+                logging::info!("Text frame received on chat websocket");
+                lifecycle.stop(CancellationReason::ProtocolError);
                 break ChatServiceError::UnexpectedFrameReceived;
             }
             Ok(NextOrClose::Close(_)) => {
-                if service_cancellation.cancelled().now_or_never()
-                    == Some(CancellationReason::ExplicitDisconnect)
-                {
-                    break ChatServiceError::ServiceIntentionallyDisconnected;
+                // The websocket reader recognizes the draining close code and records it as the
+                // reason the service stopped.
+                if lifecycle.cancellation_reason() == Some(CancellationReason::ServerDraining) {
+                    logging::info!("chat server is draining connections");
+                    lifecycle.set_draining();
+                    break ChatServiceError::ServerRequestedReconnect;
+                }
+                match lifecycle.stop(CancellationReason::RemoteClose) {
+                    CancellationReason::ExplicitDisconnect => {
+                        break ChatServiceError::ServiceIntentionallyDisconnected;
+                    }
+                    _ => break WebSocketServiceError::ChannelClosed.into(),
                 }
-                service_cancellation.cancel(CancellationReason::RemoteClose);
-                break WebSocketServiceError::ChannelClosed.into();
             }
-            Err(e) => {
-                if service_cancellation.cancelled().now_or_never()
-                    == Some(CancellationReason::ExplicitDisconnect)
-                {
+            Err(e) => match lifecycle.stop(CancellationReason::ServiceError) {
+                CancellationReason::ExplicitDisconnect => {
                     break ChatServiceError::ServiceIntentionallyDisconnected;
                 }
-                service_cancellation.cancel(CancellationReason::ServiceError);
-                break e;
-            }
+                _ => break e,
+            },
         };
 
         // binary data received
@@ -256,7 +251,7 @@ async fn reader_task<S: AsyncDuplexStream + 'static>(
                 let server_request = match ServerEvent::new(req, ws_client_writer.clone()) {
                     Ok(server_request) => server_request,
                     Err(e) => {
-                        service_cancellation.cancel(CancellationReason::ProtocolError);
+                        lifecycle.stop(CancellationReason::ProtocolError);
                         break e;
                     }
                 };
@@ -266,14 +261,14 @@ async fn reader_task<S: AsyncDuplexStream + 'static>(
                 let request_send_elapsed = request_send_start.elapsed();
 
                 if delivery_result.is_err() {
-                    service_cancellation.cancel(CancellationReason::RemoteClose);
+                    lifecycle.stop(CancellationReason::RemoteClose);
                     break ChatServiceError::FailedToPassMessageToIncomingChannel;
                 }
 
                 if previous_request_paths_for_logging.len() == incoming_tx.max_capacity() {
                     let previous_request_path = previous_request_paths_for_logging.pop_front();
                     if request_send_elapsed > LONG_REQUEST_PROCESSING_THRESHOLD {
-                        log::warn!(
+                        logging::warn!(
                             concat!(
                                 "processing for previous request {} ({} request(s) ago) took {:?}{}; ",
                                 "this could cause problems for the authenticated connection to the ",
@@ -293,37 +288,149 @@ async fn reader_task<S: AsyncDuplexStream + 'static>(
                 previous_request_paths_for_logging.push_back(request_path);
             }
             Ok(ChatMessage::Response(id, res)) => {
-                let map = &mut pending_messages.lock().await;
-                if let Some(sender) = map.remove(&id) {
+                if let Some(sender) = pending_messages.remove(&id) {
                     // this doesn't have to be successful,
                     // e.g. request might have timed out
                     let _ignore_failed_send = sender.send(res);
                 }
             }
             Err(e) => {
-                service_cancellation.cancel(CancellationReason::ProtocolError);
+                lifecycle.stop(CancellationReason::ProtocolError);
                 break e;
             }
         }
     };
 
+    // However the loop ended, the connection is done with; only a task that's dropped partway
+    // through counts as abandoned.
+    live.mark_closed();
+
     _ = incoming_tx.send(ServerEvent::Stopped(error)).await;
 
     // before terminating the task, marking channel as inactive
-    service_cancellation.cancel(CancellationReason::RemoteClose);
+    lifecycle.stop(CancellationReason::RemoteClose);
 
     // Clear the pending messages map. These requests don't wait on the service status just in case
     // a response comes in late; dropping the response senders is how we cancel them.
-    pending_messages.lock().await.cancel_all();
+    pending_messages.cancel_all();
+}
+
+/// [`ServiceConnector`] that authenticates chat connections with credentials from an
+/// [`AuthProvider`].
+///
+/// If the server rejects the credentials with a 401 or 403 during the websocket upgrade, the
+/// provider gets one chance to refresh them, and the connection is retried with the new
+/// credentials before the error is returned.
+///
+/// Calls to the provider are never made concurrently, even when several connection attempts are
+/// in progress at once.
+#[derive(Clone)]
+pub(super) struct ServiceConnectorWithAuth<C> {
+    inner: C,
+    auth: Arc<dyn AuthProvider>,
+    /// Held for the duration of each call to `auth`, and shared between clones.
+    auth_lock: Arc<std::sync::Mutex<()>>,
+}
+
+impl<C> ServiceConnectorWithAuth<C> {
+    pub fn new(inner: C, auth: Arc<dyn AuthProvider>) -> Self {
+        Self {
+            inner,
+            auth,
+            auth_lock: Default::default(),
+        }
+    }
+
+    fn current_auth(&self) -> Auth {
+        let _guard = self.auth_lock.lock().expect("not poisoned");
+        self.auth.current()
+    }
+
+    fn refresh_auth(&self) -> Option<Auth> {
+        let _guard = self.auth_lock.lock().expect("not poisoned");
+        self.auth.refresh()
+    }
+}
+
+impl<C> ServiceConnectorWithAuth<C>
+where
+    C: ServiceConnector<ConnectError = WebSocketConnectError> + Send + Sync,
+{
+    async fn connect_channel_with(
+        &self,
+        connection_params: &ConnectionParams,
+        auth: Auth,
+    ) -> Result<C::Channel, WebSocketConnectError> {
+        let decorated = connection_params.clone().with_decorator(auth.into());
+        self.inner.connect_channel(&decorated).await
+    }
+}
+
+#[async_trait]
+impl<C> ServiceConnector for ServiceConnectorWithAuth<C>
+where
+    C: ServiceConnector<ConnectError = WebSocketConnectError> + Send + Sync,
+{
+    type Service = C::Service;
+    type Channel = C::Channel;
+    type ConnectError = WebSocketConnectError;
+
+    async fn connect_channel(
+        &self,
+        connection_params: &ConnectionParams,
+    ) -> Result<Self::Channel, Self::ConnectError> {
+        let auth = self.current_auth();
+        match self.connect_channel_with(connection_params, auth).await {
+            Err(WebSocketConnectError::RejectedByServer {
+                response,
+                received_at,
+            }) if matches!(
+                response.status(),
+                StatusCode::UNAUTHORIZED | StatusCode::FORBIDDEN
+            ) =>
+            {
+                let Some(refreshed) = self.refresh_auth() else {
+                    return Err(WebSocketConnectError::RejectedByServer {
+                        response,
+                        received_at,
+                    });
+                };
+                logging::info!(
+                    "chat server rejected credentials ({}); retrying with refreshed credentials",
+                    response.status()
+                );
+                self.connect_channel_with(connection_params, refreshed)
+                    .await
+            }
+            result => result,
+        }
+    }
+
+    fn start_service(&self, channel: Self::Channel) -> (Self::Service, CancellationToken) {
+        self.inner.start_service(channel)
+    }
 }
 
 #[derive_where(Clone)]
 #[derive(Debug)]
 pub struct ChatOverWebSocket<S> {
     ws_client_writer: WebSocketClientWriter<S, ChatServiceError>,
-    service_cancellation: CancellationToken,
-    pending_messages: Arc<Mutex<PendingMessagesMap>>,
+    lifecycle: Arc<ConnectionLifecycle>,
+    pending_messages: Arc<PendingMessagesMap>,
     connection_info: ConnectionInfo,
+    rtt_estimator: RttEstimator,
+    latencies: RequestLatencies,
+}
+
+impl<S> ChatOverWebSocket<S> {
+    pub fn state(&self) -> ConnectionState {
+        self.lifecycle.state()
+    }
+
+    /// Waits until the connection has stopped, then returns why.
+    pub async fn wait_for_stopped(&self) -> StopReason {
+        self.lifecycle.wait_for_stopped().await
+    }
 }
 
 impl<S> RemoteAddressInfo for ChatOverWebSocket<S> {
@@ -332,43 +439,146 @@ impl<S> RemoteAddressInfo for ChatOverWebSocket<S> {
     }
 }
 
-#[async_trait]
-impl<S> ChatService for ChatOverWebSocket<S>
-where
-    S: AsyncDuplexStream,
-{
-    async fn send(&self, msg: Request, timeout: Duration) -> Result<Response, ChatServiceError> {
-        // checking if channel has been closed
-        if self.service_cancellation.is_cancelled() {
-            return Err(ChatServiceError::ServiceIntentionallyDisconnected);
-        }
+/// A request that has been assigned an ID but not yet sent.
+struct PendingRequest {
+    id: RequestId,
+    trace_id: TraceId,
+    started_at: Instant,
+    response_rx: oneshot::Receiver<ResponseProto>,
+}
 
+impl<S> ChatOverWebSocket<S> {
+    /// Registers a new request, adding its trace ID to `headers`.
+    async fn start_request(
+        &self,
+        headers: &mut HeaderMap,
+    ) -> Result<PendingRequest, ChatServiceError> {
+        let trace_id = TraceId::random();
+        let started_at = Instant::now();
         let (response_tx, response_rx) = oneshot::channel::<ResponseProto>();
 
-        // defining a scope here to release the lock ASAP
-        let id = {
-            let map = &mut self.pending_messages.lock().await;
-            // It's possible that the service has been stopped between the check above and the
-            // insert below. This accounts for that.
-            map.insert(response_tx)
-                .map_err(|_| WebSocketServiceError::ChannelClosed)?
-        };
+        // Once registered, the request will get either a response or a RequestChannelClosed error
+        // when the reader task stops.
+        let id = self
+            .lifecycle
+            .register_if_accepting(|| self.pending_messages.insert(response_tx))
+            .map_err(|_state| ChatServiceError::ServiceIntentionallyDisconnected)?
+            .map_err(|_| ChatServiceError::RequestChannelClosed { trace_id })?;
+
+        headers.insert(
+            TRACE_ID_HEADER_NAME,
+            HeaderValue::from_str(&trace_id.to_string()).expect("hex digits are a valid header"),
+        );
 
-        let msg = request_to_websocket_proto(msg, id)
-            .map_err(|_| ChatServiceError::RequestHasInvalidHeader)?;
+        Ok(PendingRequest {
+            id,
+            trace_id,
+            started_at,
+            response_rx,
+        })
+    }
 
-        self.ws_client_writer.send(msg.encode_to_vec()).await?;
+    /// Waits for the response to a request that has been sent.
+    async fn finish_request(
+        &self,
+        request: PendingRequest,
+        timeout: Duration,
+    ) -> Result<Response, ChatServiceError> {
+        let PendingRequest {
+            id,
+            trace_id,
+            started_at,
+            response_rx,
+        } = request;
 
         tokio::select! {
-            result = response_rx => result.map_err(|_| WebSocketServiceError::ChannelClosed.into()),
+            result = response_rx => {
+                let response_proto =
+                    result.map_err(|_| ChatServiceError::RequestChannelClosed { trace_id })?;
+                let latency = started_at.elapsed();
+                logging::debug!("[{trace_id}] chat response received after {latency:?}");
+                self.latencies.record(latency);
+                Ok(response_proto)
+            }
             _ = tokio::time::sleep(timeout) => {
-                let map = &mut self.pending_messages.lock().await;
-                map.remove(&id);
-                Err(ChatServiceError::Timeout)
+                self.pending_messages.remove(&id);
+                Err(ChatServiceError::Timeout { trace_id, elapsed: started_at.elapsed() })
             },
         }
         .and_then(|response_proto| Ok(response_proto.try_into()?))
     }
+}
+
+#[async_trait]
+impl<S> ChatService for ChatOverWebSocket<S>
+where
+    S: AsyncDuplexStream,
+{
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(
+            name = "chat.send",
+            skip_all,
+            fields(
+                route = %self.connection_info.route_type,
+                method = %msg.method,
+                request_id = tracing::field::Empty,
+                trace_id = tracing::field::Empty,
+            ),
+        )
+    )]
+    async fn send(
+        &self,
+        mut msg: Request,
+        timeout: Duration,
+    ) -> Result<Response, ChatServiceError> {
+        let request = self.start_request(&mut msg.headers).await?;
+        #[cfg(feature = "tracing")]
+        tracing::Span::current()
+            .record("request_id", request.id.id)
+            .record("trace_id", tracing::field::display(request.trace_id));
+
+        let msg = request_to_websocket_proto(msg, request.id)
+            .map_err(|_| ChatServiceError::RequestHasInvalidHeader)?;
+
+        self.ws_client_writer.send(msg.encode_to_vec()).await?;
+
+        self.finish_request(request, timeout).await
+    }
+
+    async fn send_streaming(
+        &self,
+        mut msg: RequestWithBodyStream,
+        timeout: Duration,
+    ) -> Result<Response, ChatServiceError> {
+        let request = self.start_request(&mut msg.headers).await?;
+
+        let RequestWithBodyStream {
+            method,
+            body,
+            body_len,
+            headers,
+            path,
+        } = msg;
+        let prefix = streaming_request_prefix(
+            Request {
+                method,
+                body: None,
+                headers,
+                path,
+            },
+            request.id,
+            body_len,
+        )
+        .map_err(|_| ChatServiceError::RequestHasInvalidHeader)?;
+
+        let message_len = prefix.len() + body_len;
+        self.ws_client_writer
+            .send_binary_stream(std::io::Cursor::new(prefix).chain(body), message_len)
+            .await?;
+
+        self.finish_request(request, timeout).await
+    }
 
     async fn connect(&self) -> Result<(), ChatServiceError> {
         // ChatServiceOverWebsocket is created connected
@@ -376,8 +586,11 @@ where
     }
 
     async fn disconnect(&self) {
-        self.service_cancellation
-            .cancel(CancellationReason::ExplicitDisconnect)
+        self.lifecycle.stop(CancellationReason::ExplicitDisconnect);
+    }
+
+    fn rtt_estimate(&self) -> Option<Duration> {
+        self.rtt_estimator.estimate()
     }
 }
 
@@ -437,37 +650,108 @@ fn request_to_websocket_proto(msg: Request, id: RequestId) -> Result<MessageProt
     })
 }
 
+/// Encodes everything in the message for `msg` up to the contents of its body, which must be
+/// `body_len` bytes long.
+///
+/// Protobuf allows fields to appear in any order, so the body is encoded after all the other
+/// fields of the request. That way the encoded body can be appended to the result to get a
+/// complete message.
+fn streaming_request_prefix(
+    msg: Request,
+    id: RequestId,
+    body_len: usize,
+) -> Result<Vec<u8>, ToStrError> {
+    use prost::encoding::{encode_key, encode_varint, encoded_len_varint, key_len, WireType};
+
+    // Field numbers from chat_websocket.proto.
+    const MESSAGE_REQUEST_TAG: u32 = 2;
+    const REQUEST_BODY_TAG: u32 = 3;
+
+    let MessageProto {
+        r#type,
+        request,
+        response: _,
+    } = request_to_websocket_proto(msg, id)?;
+    let request = request.expect("request_to_websocket_proto always sets the request");
+    let message_without_request = MessageProto {
+        r#type,
+        request: None,
+        response: None,
+    };
+
+    let request_len = request.encoded_len()
+        + key_len(REQUEST_BODY_TAG)
+        + encoded_len_varint(body_len as u64)
+        + body_len;
+
+    let mut prefix = Vec::with_capacity(
+        message_without_request.encoded_len()
+            + key_len(MESSAGE_REQUEST_TAG)
+            + encoded_len_varint(request_len as u64)
+            + request_len
+            - body_len,
+    );
+    message_without_request
+        .encode(&mut prefix)
+        .expect("vec has enough capacity");
+    encode_key(MESSAGE_REQUEST_TAG, WireType::LengthDelimited, &mut prefix);
+    encode_varint(request_len as u64, &mut prefix);
+    request
+        .encode(&mut prefix)
+        .expect("vec has enough capacity");
+    encode_key(REQUEST_BODY_TAG, WireType::LengthDelimited, &mut prefix);
+    encode_varint(body_len as u64, &mut prefix);
+    Ok(prefix)
+}
+
 #[cfg(test)]
 mod test {
+    use std::collections::VecDeque;
     use std::fmt::Debug;
     use std::future::Future;
+    use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
     use std::sync::Arc;
     use std::time::Duration;
 
     use assert_matches::assert_matches;
-    use futures_util::{SinkExt, StreamExt};
+    use futures_util::{FutureExt as _, SinkExt, StreamExt};
     use http::uri::PathAndQuery;
     use http::{Method, StatusCode};
-    use libsignal_net_infra::service::CancellationReason;
+    use libsignal_net_infra::service::{CancellationReason, Service, ServiceState};
     use libsignal_net_infra::testutil::{
         InMemoryWarpConnector, NoReconnectService, TestError, TIMEOUT_DURATION,
     };
+    use libsignal_net_infra::traffic_stats::{CountingConnector, TrafficKind, TrafficStats};
+    use libsignal_net_infra::utils::basic_authorization;
+    use libsignal_net_infra::ws::error::SpaceError;
     use libsignal_net_infra::ws::{
-        WebSocketClientConnector, WebSocketConfig, WebSocketServiceError,
+        WebSocketClientConnector, WebSocketConfig, WebSocketConnectError, WebSocketMessageLimits,
+        WebSocketServiceError, RTT_SAMPLE_COUNT,
     };
     use prost::Message;
-    use tokio::io::DuplexStream;
+    use test_case::test_case;
+    use tokio::io::{AsyncWriteExt as _, DuplexStream};
     use tokio::sync::mpsc::Receiver;
     use tokio::sync::{mpsc, Mutex};
     use tokio::time::Instant;
     use warp::{Filter, Reply};
 
+    use crate::auth::{Auth, AuthProvider};
+    use crate::chat::challenge::{
+        submit_rate_limit_challenge, RateLimitChallenge, RateLimitChallengeError,
+    };
     use crate::chat::test::shared::{connection_manager, test_request};
     use crate::chat::ws::{
-        decode_and_validate, request_to_websocket_proto, ChatMessage,
-        ChatOverWebSocketServiceConnector, ChatServiceError, RequestId, ServerEvent,
+        decode_and_validate, request_to_websocket_proto, streaming_request_prefix, BatchPacing,
+        ChatMessage, ChatOverWebSocket, ChatOverWebSocketServiceConnector, ChatServiceError,
+        ConnectionState, RequestId, ServerEvent, ServiceConnectorWithAuth,
+    };
+    use crate::chat::{
+        ChatMessageType, ChatService, MessageProto, Request, RequestProto, RequestWithBodyStream,
+        ResponseProto,
     };
-    use crate::chat::{ChatMessageType, ChatService, MessageProto, ResponseProto};
+    use crate::env::constants::CHAT_SERVER_DRAINING_CLOSE_CODE;
+    use crate::env::TRACE_ID_HEADER_NAME;
     use crate::proto::chat_websocket::WebSocketMessage;
 
     fn test_ws_config() -> WebSocketConfig {
@@ -477,6 +761,8 @@ mod test {
             max_connection_time: Duration::from_secs(1),
             keep_alive_interval: Duration::from_secs(5),
             max_idle_time: Duration::from_secs(15),
+            sub_protocols: &[],
+            draining_close_code: Some(CHAT_SERVER_DRAINING_CLOSE_CODE),
         }
     }
 
@@ -501,6 +787,47 @@ mod test {
         assert!(!ws_chat.service_status().unwrap().is_cancelled());
     }
 
+    #[tokio::test(flavor = "current_thread", start_paused = true)]
+    async fn ws_service_estimates_rtt_from_keepalive_pongs() {
+        let first_delay = Duration::from_millis(1000);
+        let later_delay = Duration::from_millis(200);
+
+        // The server answers a PING the next time it reads from the connection, so pausing before
+        // that read delays the PONG.
+        let (ws_server, _) = ws_warp_filter(move |websocket| async move {
+            let (_, mut rx) = websocket.split();
+            let mut delays = std::iter::once(first_delay).chain(std::iter::repeat(later_delay));
+            loop {
+                let message: warp::ws::Message =
+                    rx.next().await.expect("is some").expect("not an error");
+                if message.is_ping() {
+                    tokio::time::sleep(delays.next().expect("unending")).await;
+                }
+            }
+        });
+
+        let ws_config = test_ws_config();
+        let keep_alive_interval = ws_config.keep_alive_interval;
+        let (ws_chat, _) = create_ws_chat_service(ws_config.clone(), ws_server.clone()).await;
+        let chat = active_service(&ws_chat);
+        assert_eq!(chat.rtt_estimate(), None);
+
+        // Just after the first PONG.
+        tokio::time::sleep(keep_alive_interval + first_delay + Duration::from_millis(1)).await;
+        assert_eq!(chat.rtt_estimate(), Some(first_delay));
+
+        tokio::time::sleep(keep_alive_interval).await;
+        assert_eq!(chat.rtt_estimate(), Some((first_delay + later_delay) / 2));
+
+        // Once the first sample has dropped out, the estimate settles on the later delay.
+        tokio::time::sleep(keep_alive_interval * RTT_SAMPLE_COUNT as u32).await;
+        assert_eq!(chat.rtt_estimate(), Some(later_delay));
+
+        // A new connection starts over.
+        let (ws_chat, _) = create_ws_chat_service(ws_config, ws_server).await;
+        assert_eq!(active_service(&ws_chat).rtt_estimate(), None);
+    }
+
     #[tokio::test(flavor = "current_thread", start_paused = true)]
     async fn ws_service_connects_and_closes_after_not_receiving_pongs() {
         let ws_config = test_ws_config();
@@ -548,6 +875,186 @@ mod test {
         validate_server_stopped_successfully(server_res_rx).await;
     }
 
+    #[test_case(CHAT_SERVER_DRAINING_CLOSE_CODE => matches (
+        CancellationReason::ServerDraining,
+        ChatServiceError::ServerRequestedReconnect
+    ); "draining")]
+    #[test_case(1000 => matches (
+        CancellationReason::RemoteClose,
+        ChatServiceError::WebSocket(WebSocketServiceError::ChannelClosed)
+    ); "normal")]
+    #[test_case(4000 => matches (
+        CancellationReason::RemoteClose,
+        ChatServiceError::WebSocket(WebSocketServiceError::ChannelClosed)
+    ); "other private code")]
+    #[tokio::test(flavor = "current_thread", start_paused = true)]
+    async fn ws_service_reports_close_code_from_server(
+        close_code: u16,
+    ) -> (CancellationReason, ChatServiceError) {
+        let (ws_server, server_res_rx) = ws_warp_filter(move |websocket| async move {
+            let (mut tx, mut rx) = websocket.split();
+            tokio::spawn(async move { while (rx.next().await).is_some() {} });
+            tx.send(warp::ws::Message::close_with(close_code, "bye"))
+                .await
+                .expect("can send")
+        });
+
+        let (ws_chat, mut incoming_rx) = create_ws_chat_service(test_ws_config(), ws_server).await;
+        let error = assert_matches!(
+            incoming_rx.recv().await,
+            Some(ServerEvent::Stopped(error)) => error
+        );
+        validate_server_stopped_successfully(server_res_rx).await;
+
+        let reason = ws_chat
+            .service_status()
+            .unwrap()
+            .cancelled()
+            .now_or_never()
+            .expect("cancelled");
+        (reason, error)
+    }
+
+    #[tokio::test(flavor = "current_thread", start_paused = true)]
+    async fn ws_service_reconnects_immediately_after_server_drains() {
+        let connections = Arc::new(AtomicUsize::new(0));
+        let (ws_server, _server_res_rx) = ws_warp_filter({
+            let connections = Arc::clone(&connections);
+            move |websocket| {
+                let connections = Arc::clone(&connections);
+                async move {
+                    let (mut tx, mut rx) = websocket.split();
+                    let is_first = connections.fetch_add(1, Ordering::SeqCst) == 0;
+                    if is_first {
+                        tx.send(warp::ws::Message::close_with(
+                            CHAT_SERVER_DRAINING_CLOSE_CODE,
+                            "draining",
+                        ))
+                        .await
+                        .expect("can send");
+                    }
+                    while (rx.next().await).is_some() {}
+                }
+            }
+        });
+
+        let (incoming_tx, mut incoming_rx) = mpsc::channel::<ServerEvent<DuplexStream>>(512);
+        let ws_connector = ChatOverWebSocketServiceConnector::new(
+            WebSocketClientConnector::new(InMemoryWarpConnector::new(ws_server), test_ws_config()),
+            incoming_tx,
+        );
+        let service = Service::new(ws_connector, connection_manager(), TIMEOUT_DURATION);
+
+        service.connect().await.expect("can connect");
+        assert_matches!(
+            incoming_rx.recv().await,
+            Some(ServerEvent::Stopped(
+                ChatServiceError::ServerRequestedReconnect
+            ))
+        );
+
+        // Draining isn't a connection failure, so there's no cooldown before the next attempt.
+        let reconnect_start = Instant::now();
+        service.connect().await.expect("can reconnect");
+        let reconnect_time = reconnect_start.elapsed();
+        assert!(
+            reconnect_time < Duration::from_secs(1),
+            "took {reconnect_time:?} to reconnect"
+        );
+        assert_eq!(connections.load(Ordering::SeqCst), 2);
+        service.service().await.expect("active");
+    }
+
+    #[tokio::test(flavor = "current_thread", start_paused = true)]
+    async fn ws_service_state_follows_connection_lifecycle() {
+        let (ws_server, _server_res_rx) = ws_warp_filter(move |websocket| async move {
+            let (_tx, mut rx) = websocket.split();
+            while (rx.next().await).is_some() {}
+        });
+        let (ws_chat, _incoming_rx) = create_ws_chat_service(test_ws_config(), ws_server).await;
+        let chat = active_service(&ws_chat);
+
+        // Give the reader task a chance to start.
+        tokio::time::sleep(Duration::from_millis(1)).await;
+        assert_eq!(chat.state(), ConnectionState::Active);
+
+        chat.disconnect().await;
+        assert_eq!(
+            chat.state(),
+            ConnectionState::Stopped(CancellationReason::ExplicitDisconnect)
+        );
+        assert_eq!(
+            chat.wait_for_stopped().await,
+            CancellationReason::ExplicitDisconnect
+        );
+        assert_eq!(
+            ws_chat.service_status().unwrap().cancelled().now_or_never(),
+            Some(CancellationReason::ExplicitDisconnect)
+        );
+    }
+
+    #[tokio::test(flavor = "current_thread", start_paused = true)]
+    async fn ws_service_state_reports_remote_close() {
+        let (ws_server, _server_res_rx) = ws_warp_filter(move |websocket| async move {
+            let (mut tx, _rx) = websocket.split();
+            tx.send(warp::ws::Message::close()).await.expect("can send");
+        });
+        let (ws_chat, _incoming_rx) = create_ws_chat_service(test_ws_config(), ws_server).await;
+        let chat = active_service(&ws_chat);
+
+        assert_eq!(
+            chat.wait_for_stopped().await,
+            CancellationReason::RemoteClose
+        );
+        assert_matches!(
+            chat.send(test_request(Method::GET, "/"), TIMEOUT_DURATION)
+                .await,
+            Err(ChatServiceError::ServiceIntentionallyDisconnected)
+        );
+    }
+
+    #[test_case(true; "send first")]
+    #[test_case(false; "disconnect first")]
+    #[tokio::test(flavor = "current_thread", start_paused = true)]
+    async fn ws_service_send_racing_disconnect_fails_promptly(send_first: bool) {
+        // The server reads requests but never responds.
+        let (ws_server, _server_res_rx) = ws_warp_filter(move |websocket| async move {
+            let (_tx, mut rx) = websocket.split();
+            while (rx.next().await).is_some() {}
+        });
+        let (ws_chat, _incoming_rx) = create_ws_chat_service(test_ws_config(), ws_server).await;
+        let chat = active_service(&ws_chat);
+
+        let start = Instant::now();
+        let send = chat.send(test_request(Method::GET, "/"), TIMEOUT_DURATION);
+        let disconnect = chat.disconnect();
+        let response = if send_first {
+            tokio::join!(send, disconnect).0
+        } else {
+            tokio::join!(disconnect, send).1
+        };
+
+        // Whichever happens first, the request fails as soon as the connection stops instead of
+        // waiting for a response that will never come.
+        assert!(start.elapsed() < TIMEOUT_DURATION);
+        if send_first {
+            assert_matches!(
+                response,
+                Err(ChatServiceError::RequestChannelClosed { .. }
+                    | ChatServiceError::WebSocket(WebSocketServiceError::ChannelClosed))
+            );
+        } else {
+            assert_matches!(
+                response,
+                Err(ChatServiceError::ServiceIntentionallyDisconnected)
+            );
+        }
+        assert_eq!(
+            chat.wait_for_stopped().await,
+            CancellationReason::ExplicitDisconnect
+        );
+    }
+
     #[tokio::test(flavor = "current_thread", start_paused = true)]
     async fn ws_service_stops_on_unexpected_frame_from_server() {
         let ws_config = test_ws_config();
@@ -622,6 +1129,109 @@ mod test {
         );
     }
 
+    #[tokio::test(flavor = "current_thread", start_paused = true)]
+    async fn ws_service_counts_chat_traffic() {
+        // creating a server that reports the size of each request and response
+        let (sizes_tx, mut sizes_rx) = mpsc::unbounded_channel();
+        let (ws_server, _) = ws_warp_filter(move |websocket| {
+            let sizes_tx = sizes_tx.clone();
+            async move {
+                let (mut tx, mut rx) = websocket.split();
+                while let Some(Ok(msg)) = rx.next().await {
+                    if !msg.is_binary() {
+                        continue;
+                    }
+                    let request = decode_and_validate(msg.as_bytes()).expect("chat message");
+                    let response = response_for_request(&request, StatusCode::OK)
+                        .expect("is valid request")
+                        .encode_to_vec();
+                    sizes_tx
+                        .send((msg.as_bytes().len(), response.len()))
+                        .expect("test is listening");
+                    tx.send(warp::ws::Message::binary(response))
+                        .await
+                        .expect("can send response")
+                }
+            }
+        });
+
+        // Separate from the global stats, which other tests may be adding to.
+        static STATS: TrafficStats = TrafficStats::new();
+
+        let (incoming_tx, _incoming_rx) = mpsc::channel(1);
+        let ws_connector = ChatOverWebSocketServiceConnector::new(
+            WebSocketClientConnector::new(
+                CountingConnector::with_stats(
+                    InMemoryWarpConnector::new(ws_server),
+                    TrafficKind::Chat,
+                    &STATS,
+                ),
+                test_ws_config(),
+            ),
+            incoming_tx,
+        );
+        let ws_chat = NoReconnectService::start(ws_connector, connection_manager()).await;
+
+        // Leave out the WebSocket handshake, so the change is just the request and its response.
+        let before = STATS.snapshot();
+        ws_chat
+            .send(test_request(Method::GET, "/"), TIMEOUT_DURATION)
+            .await
+            .expect("response");
+        let after = STATS.snapshot();
+
+        let (request_len, response_len) = sizes_rx.recv().await.expect("request was seen");
+        let sent = after.get(TrafficKind::Chat).sent - before.get(TrafficKind::Chat).sent;
+        let received =
+            after.get(TrafficKind::Chat).received - before.get(TrafficKind::Chat).received;
+        // A WebSocket frame header is at most 14 bytes.
+        assert!(
+            (request_len..=request_len + 14).contains(&(sent as usize)),
+            "sent {sent} bytes for a {request_len}-byte request"
+        );
+        assert!(
+            (response_len..=response_len + 14).contains(&(received as usize)),
+            "received {received} bytes for a {response_len}-byte response"
+        );
+        assert_eq!(after.get(TrafficKind::Cdsi), before.get(TrafficKind::Cdsi));
+    }
+
+    #[tokio::test(flavor = "current_thread", start_paused = true)]
+    async fn ws_service_submits_rate_limit_challenge() {
+        // creating a server that rejects every challenge answer
+        let (ws_server, server_res_rx) = ws_warp_filter(move |websocket| async move {
+            let (mut tx, mut rx) = websocket.split();
+            loop {
+                let msg = rx.next().await.expect("not closed").expect("not an error");
+                let request = decode_and_validate(msg.as_bytes()).expect("chat message");
+                let request_proto = assert_matches!(&request, ChatMessage::Request(r) => r);
+                assert_eq!(request_proto.verb.as_deref(), Some("PUT"));
+                assert_eq!(request_proto.path.as_deref(), Some("/v1/challenge"));
+                let body: serde_json::Value =
+                    serde_json::from_slice(request_proto.body()).expect("JSON body");
+                assert_eq!(body["type"], "rateLimitPushChallenge");
+                assert_eq!(body["challenge"], "push-challenge");
+
+                let message_proto =
+                    response_for_request(&request, StatusCode::PRECONDITION_REQUIRED)
+                        .expect("is valid request");
+                tx.send(warp::ws::Message::binary(message_proto.encode_to_vec()))
+                    .await
+                    .expect("can send response")
+            }
+        });
+
+        let (ws_chat, _) = create_ws_chat_service(test_ws_config(), ws_server).await;
+        let result = submit_rate_limit_challenge(
+            &ws_chat,
+            &RateLimitChallenge::PushToken("push-challenge".to_owned()),
+            TIMEOUT_DURATION,
+        )
+        .await;
+        assert_matches!(result, Err(RateLimitChallengeError::Rejected));
+        validate_server_running(server_res_rx).await;
+    }
+
     #[tokio::test(flavor = "current_thread", start_paused = true)]
     async fn ws_service_times_out_on_late_response_from_server() {
         // creating a server that responds to requests with 200
@@ -646,8 +1256,289 @@ mod test {
         let response = ws_chat
             .send(test_request(Method::GET, "/"), TIMEOUT_DURATION)
             .await;
-        assert_matches!(response, Err(ChatServiceError::Timeout));
+        assert_matches!(
+            response,
+            Err(ChatServiceError::Timeout { elapsed, .. }) if elapsed == TIMEOUT_DURATION
+        );
+        validate_server_running(server_res_rx).await;
+    }
+
+    #[tokio::test(flavor = "current_thread", start_paused = true)]
+    async fn ws_service_adapts_timeout_to_observed_latency() {
+        // creating a server that waits as long as each request's path says before responding
+        let (ws_server, server_res_rx) = ws_warp_filter(move |websocket| async move {
+            let (mut tx, mut rx) = websocket.split();
+            loop {
+                let msg = rx.next().await.expect("not closed").expect("not an error");
+                let request = decode_and_validate(msg.as_bytes()).expect("chat message");
+                let request_proto = assert_matches!(&request, ChatMessage::Request(r) => r);
+                let delay_ms: u64 = request_proto
+                    .path()
+                    .strip_prefix("/delay/")
+                    .and_then(|ms| ms.parse().ok())
+                    .expect("path has a delay");
+                let message_proto =
+                    response_for_request(&request, StatusCode::OK).expect("is valid request");
+                tokio::time::sleep(Duration::from_millis(delay_ms)).await;
+                tx.send(warp::ws::Message::binary(message_proto.encode_to_vec()))
+                    .await
+                    .expect("can send response")
+            }
+        });
+        let delayed_request = |ms: u64| test_request(Method::GET, &format!("/delay/{ms}"));
+
+        const MIN: Duration = Duration::from_millis(100);
+        const MAX: Duration = Duration::from_secs(10);
+
+        let (ws_chat, _) = create_ws_chat_service(test_ws_config(), ws_server.clone()).await;
+        let chat = active_service(&ws_chat);
+
+        for ms in [300, 100, 500, 200] {
+            chat.send(delayed_request(ms), MAX).await.expect("response");
+        }
+        // Not enough samples yet.
+        assert_eq!(chat.health().latency_p95, None);
+        assert_eq!(chat.adaptive_timeout(MIN, MAX), MAX);
+
+        chat.send(delayed_request(400), MAX)
+            .await
+            .expect("response");
+        let health = chat.health();
+        assert_eq!(health.state, ConnectionState::Active);
+        assert_eq!(health.latency_p50, Some(Duration::from_millis(300)));
+        assert_eq!(health.latency_p95, Some(Duration::from_millis(500)));
+        assert_eq!(chat.adaptive_timeout(MIN, MAX), Duration::from_secs(1));
+
+        // The timeout is clamped at both ends.
+        assert_eq!(
+            chat.adaptive_timeout(MIN, Duration::from_millis(800)),
+            Duration::from_millis(800)
+        );
+        assert_eq!(
+            chat.adaptive_timeout(Duration::from_secs(2), MAX),
+            Duration::from_secs(2)
+        );
+
+        // A response slower than twice the 95th percentile times out.
+        let response = chat
+            .send_with_adaptive_timeout(delayed_request(1500), MIN, MAX)
+            .await;
+        assert_matches!(
+            response,
+            Err(ChatServiceError::Timeout { elapsed, .. }) if elapsed == Duration::from_secs(1)
+        );
         validate_server_running(server_res_rx).await;
+
+        // A new connection starts over.
+        let (ws_chat, _) = create_ws_chat_service(test_ws_config(), ws_server).await;
+        let chat = active_service(&ws_chat);
+        assert_eq!(chat.health().latency_p50, None);
+        assert_eq!(chat.adaptive_timeout(MIN, MAX), MAX);
+    }
+
+    #[tokio::test(flavor = "current_thread", start_paused = true)]
+    async fn ws_service_reports_trace_id_on_timeout() {
+        // creating a server that never responds, but reports the trace IDs it sees
+        let (trace_id_tx, mut trace_id_rx) = mpsc::unbounded_channel();
+        let (ws_server, _) = ws_warp_filter(move |websocket| {
+            let trace_id_tx = trace_id_tx.clone();
+            async move {
+                let (_tx, mut rx) = websocket.split();
+                while let Some(Ok(msg)) = rx.next().await {
+                    if let Ok(ChatMessage::Request(request)) = decode_and_validate(msg.as_bytes()) {
+                        trace_id_tx
+                            .send(trace_id_header(&request))
+                            .expect("test is listening");
+                    }
+                }
+            }
+        });
+
+        let ws_config = test_ws_config();
+        let (ws_chat, _) = create_ws_chat_service(ws_config, ws_server).await;
+
+        let response = ws_chat
+            .send(test_request(Method::GET, "/"), TIMEOUT_DURATION)
+            .await;
+        let trace_id = assert_matches!(
+            response,
+            Err(e @ ChatServiceError::Timeout { .. }) => e.trace_id().expect("has trace ID")
+        );
+        assert_eq!(
+            trace_id_rx.recv().await.expect("server saw request"),
+            Some(trace_id.to_string())
+        );
+    }
+
+    #[tokio::test(flavor = "current_thread", start_paused = true)]
+    async fn ws_service_sends_distinct_trace_id_per_request() {
+        let (trace_id_tx, mut trace_id_rx) = mpsc::unbounded_channel();
+        let (ws_server, _) = ws_warp_filter(move |websocket| {
+            let trace_id_tx = trace_id_tx.clone();
+            async move {
+                let (mut tx, mut rx) = websocket.split();
+                while let Some(Ok(msg)) = rx.next().await {
+                    if !msg.is_binary() {
+                        continue;
+                    }
+                    let request = decode_and_validate(msg.as_bytes()).expect("chat message");
+                    if let ChatMessage::Request(request_proto) = &request {
+                        trace_id_tx
+                            .send(trace_id_header(request_proto))
+                            .expect("test is listening");
+                    }
+                    let message_proto =
+                        response_for_request(&request, StatusCode::OK).expect("is valid request");
+                    tx.send(warp::ws::Message::binary(message_proto.encode_to_vec()))
+                        .await
+                        .expect("can send response")
+                }
+            }
+        });
+
+        let ws_config = test_ws_config();
+        let (ws_chat, _) = create_ws_chat_service(ws_config, ws_server).await;
+
+        for _ in 0..2 {
+            ws_chat
+                .send(test_request(Method::GET, "/"), TIMEOUT_DURATION)
+                .await
+                .expect("response");
+        }
+
+        let first = trace_id_rx.recv().await.expect("first request");
+        let second = trace_id_rx.recv().await.expect("second request");
+        assert_matches!(&first, Some(id) if id.len() == 16);
+        assert_matches!(&second, Some(id) if id.len() == 16);
+        assert_ne!(first, second);
+    }
+
+    #[test]
+    fn streaming_request_prefix_plus_body_is_request_message() {
+        let body = b"streamed body".to_vec();
+        let request = Request {
+            body: Some(body.clone().into()),
+            headers: http::HeaderMap::from_iter([(
+                http::header::CONTENT_TYPE,
+                http::HeaderValue::from_static("application/octet-stream"),
+            )]),
+            ..test_request(Method::PUT, "/v1/messages/multi_recipient")
+        };
+        const REQUEST_ID: RequestId = RequestId::new(17);
+
+        let mut streamed = streaming_request_prefix(
+            Request {
+                body: None,
+                ..request.clone()
+            },
+            REQUEST_ID,
+            body.len(),
+        )
+        .expect("valid headers");
+        streamed.extend_from_slice(&body);
+
+        assert_eq!(
+            MessageProto::decode(streamed.as_slice()).expect("valid message"),
+            request_to_websocket_proto(request, REQUEST_ID).expect("valid headers")
+        );
+    }
+
+    #[tokio::test(flavor = "current_thread", start_paused = true)]
+    async fn ws_service_streams_large_request_body() {
+        const BODY_LEN: usize = 2 * 1024 * 1024;
+        let body: Vec<u8> = (0..BODY_LEN).map(|i| (i % 251) as u8).collect();
+
+        // creating a server that reports the bodies it receives and responds with 200
+        let (body_tx, mut body_rx) = mpsc::unbounded_channel();
+        let (ws_server, _) = ws_warp_filter(move |websocket| {
+            let body_tx = body_tx.clone();
+            async move {
+                let (mut tx, mut rx) = websocket.split();
+                while let Some(Ok(msg)) = rx.next().await {
+                    if !msg.is_binary() {
+                        continue;
+                    }
+                    let request = decode_and_validate(msg.as_bytes()).expect("chat message");
+                    if let ChatMessage::Request(request_proto) = &request {
+                        body_tx
+                            .send(request_proto.body.clone())
+                            .expect("test is listening");
+                    }
+                    let message_proto =
+                        response_for_request(&request, StatusCode::OK).expect("is valid request");
+                    tx.send(warp::ws::Message::binary(message_proto.encode_to_vec()))
+                        .await
+                        .expect("can send response")
+                }
+            }
+        });
+
+        let ws_config = test_ws_config();
+        let (ws_chat, _) = create_ws_chat_service(ws_config, ws_server).await;
+
+        // Feed the body through a pipe much smaller than it, so it can't be read all at once.
+        let (mut body_writer, body_reader) = tokio::io::duplex(4096);
+        let body_for_writer = body.clone();
+        tokio::spawn(async move {
+            body_writer
+                .write_all(&body_for_writer)
+                .await
+                .expect("can write body");
+        });
+
+        let request = RequestWithBodyStream {
+            method: Method::PUT,
+            body: Box::new(body_reader),
+            body_len: BODY_LEN,
+            headers: Default::default(),
+            path: PathAndQuery::from_static("/v1/messages/multi_recipient"),
+        };
+        let response = ws_chat
+            .send_streaming(request, TIMEOUT_DURATION)
+            .await
+            .expect("response");
+        assert_eq!(response.status, StatusCode::OK);
+
+        let received = body_rx
+            .recv()
+            .await
+            .expect("server saw request")
+            .expect("has body");
+        assert_eq!(received.len(), BODY_LEN);
+        assert!(received == body, "received body differs");
+    }
+
+    #[tokio::test(flavor = "current_thread", start_paused = true)]
+    async fn ws_service_stops_if_request_body_stream_ends_early() {
+        let (ws_server, _) = ws_warp_filter(move |websocket| async move {
+            let (_tx, mut rx) = websocket.split();
+            while let Some(Ok(_)) = rx.next().await {}
+        });
+
+        let ws_config = test_ws_config();
+        let (ws_chat, _) = create_ws_chat_service(ws_config, ws_server).await;
+
+        let request = RequestWithBodyStream {
+            method: Method::PUT,
+            body: Box::new(std::io::Cursor::new(vec![0; 100])),
+            body_len: 200,
+            headers: Default::default(),
+            path: PathAndQuery::from_static("/"),
+        };
+        let response = ws_chat.send_streaming(request, TIMEOUT_DURATION).await;
+        assert_matches!(
+            response,
+            Err(ChatServiceError::WebSocket(WebSocketServiceError::Io(_)))
+        );
+        assert!(ws_chat.service_status().unwrap().is_cancelled());
+    }
+
+    fn trace_id_header(request: &RequestProto) -> Option<String> {
+        request.headers.iter().find_map(|header| {
+            let (name, value) = header.split_once(':')?;
+            name.eq_ignore_ascii_case(TRACE_ID_HEADER_NAME)
+                .then(|| value.trim().to_owned())
+        })
     }
 
     #[tokio::test(flavor = "current_thread", start_paused = true)]
@@ -695,12 +1586,7 @@ mod test {
         let response = ws_chat
             .send(test_request(Method::GET, "/"), TIMEOUT_DURATION)
             .await;
-        assert_matches!(
-            response,
-            Err(ChatServiceError::WebSocket(
-                WebSocketServiceError::ChannelClosed
-            ))
-        );
+        assert_matches!(response, Err(ChatServiceError::RequestChannelClosed { .. }));
         validate_server_stopped_successfully(server_res_rx).await;
     }
 
@@ -798,6 +1684,46 @@ mod test {
         );
     }
 
+    #[tokio::test(flavor = "current_thread", start_paused = true)]
+    async fn ws_service_stops_if_server_exceeds_message_size_limit() {
+        const LIMITS: WebSocketMessageLimits = WebSocketMessageLimits {
+            max_message_size: 1024,
+            max_frame_size: 1024,
+        };
+
+        // creating a server that responds to a request with a message that's too big.
+        let (ws_server, _) = ws_warp_filter(move |websocket| async move {
+            let (mut tx, mut rx) = websocket.split();
+            let _request = rx.next().await.expect("not closed").expect("not an error");
+            tx.send(warp::ws::Message::binary(vec![
+                0;
+                LIMITS.max_message_size + 1
+            ]))
+            .await
+            .expect("can send");
+            // Keep the connection open until the client gives up on it.
+            while let Some(Ok(_)) = rx.next().await {}
+        });
+
+        let ws_config = WebSocketConfig {
+            ws_config: LIMITS.ws_config(),
+            ..test_ws_config()
+        };
+        let (ws_chat, mut incoming_rx) = create_ws_chat_service(ws_config, ws_server).await;
+
+        let response = ws_chat
+            .send(test_request(Method::GET, "/"), TIMEOUT_DURATION)
+            .await;
+        assert_matches!(response, Err(ChatServiceError::RequestChannelClosed { .. }));
+
+        assert_matches!(
+            incoming_rx.recv().await,
+            Some(ServerEvent::Stopped(ChatServiceError::WebSocket(
+                WebSocketServiceError::Capacity(SpaceError::Capacity(_))
+            )))
+        );
+    }
+
     #[tokio::test(flavor = "current_thread", start_paused = true)]
     async fn ws_service_correctly_handles_multiple_in_flight_requests() {
         // creating a server that responds to requests with 200 after some request processing time
@@ -846,6 +1772,198 @@ mod test {
         assert_eq!(start + REQUEST_PROCESSING_DURATION, Instant::now());
     }
 
+    #[tokio::test(flavor = "current_thread", start_paused = true)]
+    async fn ws_service_paces_batched_requests() {
+        const REQUEST_PROCESSING_DURATION: Duration = Duration::from_millis(100);
+        const BATCH_SIZE: usize = 8;
+        let pacing = BatchPacing {
+            inter_frame_delay: Duration::from_millis(5),
+            max_in_flight: nonzero_ext::nonzero!(3usize),
+        };
+
+        let (arrivals_tx, mut arrivals_rx) = mpsc::unbounded_channel();
+        let (ws_server, _) = ws_warp_filter(move |websocket| {
+            let arrivals_tx = arrivals_tx.clone();
+            async move {
+                let (tx, mut rx) = websocket.split();
+                let shared_sender = Arc::new(Mutex::new(tx));
+                loop {
+                    let msg = rx.next().await.expect("not closed").expect("not an error");
+                    arrivals_tx.send(Instant::now()).expect("test is waiting");
+                    let request = decode_and_validate(msg.as_bytes()).expect("chat message");
+                    let response_proto =
+                        response_for_request(&request, StatusCode::OK).expect("response");
+                    let shared_sender = shared_sender.clone();
+                    tokio::spawn(async move {
+                        tokio::time::sleep(REQUEST_PROCESSING_DURATION).await;
+                        let mut sender = shared_sender.lock().await;
+                        let _ignore_result = (*sender)
+                            .send(warp::ws::Message::binary(response_proto.encode_to_vec()))
+                            .await;
+                    });
+                }
+            }
+        });
+
+        let (ws_chat, _) = create_ws_chat_service(test_ws_config(), ws_server).await;
+        let chat = active_service(&ws_chat);
+
+        let start = Instant::now();
+        let requests = (0..BATCH_SIZE)
+            .map(|_| test_request(Method::PUT, "/v1/receipt"))
+            .collect();
+        let results = chat.send_batched(requests, TIMEOUT_DURATION, pacing).await;
+        assert_eq!(results.len(), BATCH_SIZE);
+        for result in results {
+            assert_eq!(result.expect("success").status, StatusCode::OK);
+        }
+
+        // Each group of requests that fits under the cap is spread out by the delay, and the next
+        // group waits for the responses to the previous one.
+        let arrivals = std::iter::from_fn(|| arrivals_rx.try_recv().ok())
+            .map(|arrival| arrival - start)
+            .collect::<Vec<_>>();
+        let expected = (0..BATCH_SIZE)
+            .map(|i| {
+                let group = (i / pacing.max_in_flight.get()) as u32;
+                let position = (i % pacing.max_in_flight.get()) as u32;
+                REQUEST_PROCESSING_DURATION * group + pacing.inter_frame_delay * position
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(arrivals, expected);
+    }
+
+    #[tokio::test(flavor = "current_thread", start_paused = true)]
+    async fn ws_service_pauses_batch_when_rate_limited() {
+        const RETRY_AFTER: Duration = Duration::from_secs(2);
+        let pacing = BatchPacing {
+            inter_frame_delay: Duration::from_millis(5),
+            max_in_flight: nonzero_ext::nonzero!(1usize),
+        };
+
+        let (arrivals_tx, mut arrivals_rx) = mpsc::unbounded_channel();
+        let (ws_server, _) = ws_warp_filter(move |websocket| {
+            let arrivals_tx = arrivals_tx.clone();
+            async move {
+                let (mut tx, mut rx) = websocket.split();
+                loop {
+                    let msg = rx.next().await.expect("not closed").expect("not an error");
+                    arrivals_tx.send(Instant::now()).expect("test is waiting");
+                    let request = decode_and_validate(msg.as_bytes()).expect("chat message");
+                    let ChatMessage::Request(request_proto) = &request else {
+                        panic!("not a request");
+                    };
+                    let message_proto = if request_proto.path() == "/limited" {
+                        let mut message_proto =
+                            response_for_request(&request, StatusCode::TOO_MANY_REQUESTS)
+                                .expect("response");
+                        message_proto.response.as_mut().expect("response").headers =
+                            vec![format!("retry-after: {}", RETRY_AFTER.as_secs())];
+                        message_proto
+                    } else {
+                        response_for_request(&request, StatusCode::OK).expect("response")
+                    };
+                    tx.send(warp::ws::Message::binary(message_proto.encode_to_vec()))
+                        .await
+                        .expect("sent");
+                }
+            }
+        });
+
+        let (ws_chat, _) = create_ws_chat_service(test_ws_config(), ws_server).await;
+        let chat = active_service(&ws_chat);
+
+        let start = Instant::now();
+        let requests = ["/", "/limited", "/", "/"]
+            .into_iter()
+            .map(|path| test_request(Method::PUT, path))
+            .collect();
+        let results = chat.send_batched(requests, TIMEOUT_DURATION, pacing).await;
+        let statuses = results
+            .into_iter()
+            .map(|result| result.expect("success").status)
+            .collect::<Vec<_>>();
+        assert_eq!(
+            statuses,
+            [
+                StatusCode::OK,
+                StatusCode::TOO_MANY_REQUESTS,
+                StatusCode::OK,
+                StatusCode::OK
+            ]
+        );
+
+        let delay = pacing.inter_frame_delay;
+        let arrivals = std::iter::from_fn(|| arrivals_rx.try_recv().ok())
+            .map(|arrival| arrival - start)
+            .collect::<Vec<_>>();
+        assert_eq!(
+            arrivals,
+            [
+                Duration::ZERO,
+                delay,
+                delay + RETRY_AFTER,
+                delay * 2 + RETRY_AFTER
+            ]
+        );
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+    async fn ws_service_matches_responses_to_thousands_of_concurrent_requests() {
+        const REQUEST_COUNT: usize = 4000;
+        // Generous, since this runs in real time and everything is competing for the same threads.
+        const REQUEST_TIMEOUT: Duration = Duration::from_secs(30);
+
+        // creating a server that echoes each request's path back in the response message, after a
+        // delay that varies so the responses arrive out of order
+        let (ws_server, _) = ws_warp_filter(move |websocket| async move {
+            let (tx, mut rx) = websocket.split();
+            let shared_sender = Arc::new(Mutex::new(tx));
+            loop {
+                let msg = rx.next().await.expect("not closed").expect("not an error");
+                assert!(msg.is_binary(), "not binary: {msg:?}");
+                let request = match decode_and_validate(msg.as_bytes()).expect("chat message") {
+                    ChatMessage::Request(request) => request,
+                    ChatMessage::Response(_, _) => panic!("expected a request"),
+                };
+                let delay = Duration::from_micros(request.id.expect("has ID") % 997);
+                let response_proto = MessageProto {
+                    r#type: Some(ChatMessageType::Response.into()),
+                    request: None,
+                    response: Some(ResponseProto {
+                        id: request.id,
+                        status: Some(StatusCode::OK.as_u16().into()),
+                        message: request.path,
+                        headers: vec![],
+                        body: None,
+                    }),
+                };
+                let shared_sender = shared_sender.clone();
+                tokio::spawn(async move {
+                    tokio::time::sleep(delay).await;
+                    let mut sender = shared_sender.lock().await;
+                    let _ignore_result = (*sender)
+                        .send(warp::ws::Message::binary(response_proto.encode_to_vec()))
+                        .await;
+                });
+            }
+        });
+
+        let ws_config = test_ws_config();
+        let (ws_chat, _) = create_ws_chat_service(ws_config, ws_server).await;
+
+        let ws_chat = &ws_chat;
+        futures_util::future::join_all((0..REQUEST_COUNT).map(|i| async move {
+            let path = format!("/{i}");
+            let response = ws_chat
+                .send(test_request(Method::GET, &path), REQUEST_TIMEOUT)
+                .await
+                .expect("request completed successfully");
+            assert_eq!(response.message.as_deref(), Some(path.as_str()));
+        }))
+        .await;
+    }
+
     #[tokio::test(flavor = "current_thread", start_paused = true)]
     async fn ws_service_stops_on_malformed_data_from_server() {
         // creating a server that responds to requests with 200
@@ -863,13 +1981,189 @@ mod test {
         let response = ws_chat
             .send(test_request(Method::GET, "/"), TIMEOUT_DURATION)
             .await;
+        assert_matches!(response, Err(ChatServiceError::RequestChannelClosed { .. }));
+        validate_server_stopped_successfully(server_res_rx).await;
+    }
+
+    /// Hands out its credentials in order, moving on to the next set on each refresh.
+    struct QueuedAuthProvider {
+        credentials: std::sync::Mutex<VecDeque<Auth>>,
+        refresh_count: AtomicUsize,
+    }
+
+    impl QueuedAuthProvider {
+        fn new(passwords: &[&str]) -> Self {
+            Self {
+                credentials: std::sync::Mutex::new(
+                    passwords
+                        .iter()
+                        .map(|password| Auth {
+                            username: "user".to_owned(),
+                            password: password.to_string(),
+                        })
+                        .collect(),
+                ),
+                refresh_count: AtomicUsize::new(0),
+            }
+        }
+    }
+
+    impl AuthProvider for QueuedAuthProvider {
+        fn current(&self) -> Auth {
+            self.credentials
+                .lock()
+                .expect("not poisoned")
+                .front()
+                .expect("has credentials")
+                .clone()
+        }
+
+        fn refresh(&self) -> Option<Auth> {
+            self.refresh_count.fetch_add(1, Ordering::SeqCst);
+            let mut credentials = self.credentials.lock().expect("not poisoned");
+            credentials.pop_front();
+            credentials.front().cloned()
+        }
+    }
+
+    fn authorization(password: &str) -> String {
+        basic_authorization("user", password)
+            .to_str()
+            .expect("ASCII")
+            .to_owned()
+    }
+
+    /// Connects with credentials from `auth` to a server that only accepts the password
+    /// `accepted_password`, answering any other credentials with a 401.
+    ///
+    /// Returns the resulting service state along with the authorization headers the server saw, in
+    /// order.
+    async fn connect_with_auth(
+        auth: Arc<QueuedAuthProvider>,
+        accepted_password: &str,
+    ) -> (
+        Arc<ServiceState<ChatOverWebSocket<DuplexStream>, WebSocketConnectError>>,
+        Vec<String>,
+    ) {
+        let seen_authorization = Arc::new(std::sync::Mutex::new(vec![]));
+        let accepted_authorization = authorization(accepted_password);
+        let ws_server = warp::any()
+            .and(warp::header::<String>("authorization"))
+            .and(warp::ws())
+            .map({
+                let seen_authorization = seen_authorization.clone();
+                move |authorization: String, ws: warp::ws::Ws| -> Box<dyn Reply> {
+                    let accepted = authorization == accepted_authorization;
+                    seen_authorization
+                        .lock()
+                        .expect("not poisoned")
+                        .push(authorization);
+                    if accepted {
+                        Box::new(ws.on_upgrade(|_websocket| async {}))
+                    } else {
+                        Box::new(warp::http::StatusCode::UNAUTHORIZED)
+                    }
+                }
+            });
+
+        let (incoming_tx, _incoming_rx) = mpsc::channel::<ServerEvent<DuplexStream>>(1);
+        let ws_connector = ChatOverWebSocketServiceConnector::new(
+            WebSocketClientConnector::new(InMemoryWarpConnector::new(ws_server), test_ws_config()),
+            incoming_tx,
+        );
+        let ws_chat = NoReconnectService::start(
+            ServiceConnectorWithAuth::new(ws_connector, auth),
+            connection_manager(),
+        )
+        .await;
+
+        let seen_authorization = seen_authorization.lock().expect("not poisoned").clone();
+        (ws_chat.inner, seen_authorization)
+    }
+
+    #[tokio::test(flavor = "current_thread", start_paused = true)]
+    async fn ws_auth_connector_retries_with_refreshed_credentials() {
+        let auth = Arc::new(QueuedAuthProvider::new(&["stale", "fresh"]));
+        let (state, seen_authorization) = connect_with_auth(auth.clone(), "fresh").await;
+
+        assert_matches!(&*state, ServiceState::Active(_, _));
+        assert_eq!(
+            seen_authorization,
+            [authorization("stale"), authorization("fresh")]
+        );
+        assert_eq!(auth.refresh_count.load(Ordering::SeqCst), 1);
+    }
+
+    #[test_case(&["stale"], &["stale"]; "nothing to refresh to")]
+    #[test_case(&["stale", "also-stale", "fresh"], &["stale", "also-stale"]; "refreshed credentials also rejected")]
+    #[tokio::test(flavor = "current_thread", start_paused = true)]
+    async fn ws_auth_connector_refreshes_at_most_once(
+        passwords: &[&str],
+        expected_attempts: &[&str],
+    ) {
+        let auth = Arc::new(QueuedAuthProvider::new(passwords));
+        let (state, seen_authorization) = connect_with_auth(auth.clone(), "fresh").await;
+
         assert_matches!(
-            response,
-            Err(ChatServiceError::WebSocket(
-                WebSocketServiceError::ChannelClosed
-            ))
+            &*state,
+            ServiceState::Error(WebSocketConnectError::RejectedByServer { response, .. })
+                if response.status() == StatusCode::UNAUTHORIZED
         );
-        validate_server_stopped_successfully(server_res_rx).await;
+        assert_eq!(
+            seen_authorization,
+            expected_attempts
+                .iter()
+                .map(|password| authorization(password))
+                .collect::<Vec<_>>()
+        );
+        assert_eq!(auth.refresh_count.load(Ordering::SeqCst), 1);
+    }
+
+    /// Panics if one of its methods is called while another call is still in progress.
+    #[derive(Default)]
+    struct OverlapDetectingAuthProvider {
+        in_call: AtomicBool,
+    }
+
+    impl OverlapDetectingAuthProvider {
+        fn call(&self) {
+            assert!(
+                !self.in_call.swap(true, Ordering::SeqCst),
+                "concurrent call to auth provider"
+            );
+            std::thread::sleep(Duration::from_millis(10));
+            self.in_call.store(false, Ordering::SeqCst);
+        }
+    }
+
+    impl AuthProvider for OverlapDetectingAuthProvider {
+        fn current(&self) -> Auth {
+            self.call();
+            Auth {
+                username: "user".to_owned(),
+                password: "password".to_owned(),
+            }
+        }
+
+        fn refresh(&self) -> Option<Auth> {
+            self.call();
+            None
+        }
+    }
+
+    #[test]
+    fn ws_auth_connector_does_not_call_provider_concurrently() {
+        let connector =
+            ServiceConnectorWithAuth::new((), Arc::new(OverlapDetectingAuthProvider::default()));
+        std::thread::scope(|scope| {
+            for _ in 0..4 {
+                let connector = connector.clone();
+                scope.spawn(move || {
+                    connector.current_auth();
+                    connector.refresh_auth();
+                });
+            }
+        });
     }
 
     #[derive(Debug)]
@@ -938,6 +2232,18 @@ mod test {
         (ws_chat, incoming_rx)
     }
 
+    fn active_service<C>(ws_chat: &NoReconnectService<C>) -> C::Service
+    where
+        C: libsignal_net_infra::service::ServiceConnector<
+            Service = ChatOverWebSocket<DuplexStream>,
+        >,
+    {
+        match &*ws_chat.inner {
+            ServiceState::Active(service, _) => service.clone(),
+            _ => panic!("service is not active"),
+        }
+    }
+
     fn ws_warp_filter<F, T>(
         on_ws_upgrade_callback: F,
     ) -> (
@@ -0,0 +1,157 @@
+//
+// Copyright 2024 Signal Messenger, LLC.
+// SPDX-License-Identifier: AGPL-3.0-only
+//
+
+//! Request timeouts that follow how quickly the server has been responding.
+//!
+//! Every response received on a connection records how long it took, counted from when the
+//! request was registered. [`ChatOverWebSocket::send_with_adaptive_timeout`] bases its timeout on
+//! the 95th percentile of the most recent of these, so that a slow network doesn't cause spurious
+//! timeouts and a fast one doesn't take long to notice a lost response. The measurements belong to
+//! a single connection, so a new connection starts over.
+
+use std::collections::VecDeque;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use libsignal_net_infra::AsyncDuplexStream;
+
+use crate::chat::ws::{ChatOverWebSocket, ConnectionState};
+use crate::chat::{ChatService as _, ChatServiceError, Request, Response};
+
+/// The number of most recent request latencies kept for each connection.
+pub const LATENCY_WINDOW_SIZE: usize = 100;
+
+/// The fewest latencies needed before percentiles are reported.
+///
+/// Until then, [`ChatOverWebSocket::send_with_adaptive_timeout`] uses its maximum timeout.
+pub const MIN_LATENCY_SAMPLES: usize = 5;
+
+/// A snapshot of how a chat connection is doing.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ConnectionHealth {
+    pub state: ConnectionState,
+    /// See [`ChatService::rtt_estimate`](crate::chat::ChatService::rtt_estimate).
+    pub rtt_estimate: Option<Duration>,
+    /// The median of the recent request latencies, or `None` if there are fewer than
+    /// [`MIN_LATENCY_SAMPLES`] of them.
+    pub latency_p50: Option<Duration>,
+    /// The 95th percentile of the recent request latencies, or `None` if there are fewer than
+    /// [`MIN_LATENCY_SAMPLES`] of them.
+    pub latency_p95: Option<Duration>,
+}
+
+/// The latencies of the most recent requests on a single connection.
+///
+/// Clones share the same measurements.
+#[derive(Clone, Debug, Default)]
+pub(super) struct RequestLatencies(Arc<Mutex<VecDeque<Duration>>>);
+
+impl RequestLatencies {
+    pub(super) fn record(&self, latency: Duration) {
+        let mut samples = self.0.lock().expect("not poisoned");
+        if samples.len() == LATENCY_WINDOW_SIZE {
+            samples.pop_front();
+        }
+        samples.push_back(latency);
+    }
+
+    /// The nearest-rank `percent`th percentile of the recorded latencies.
+    fn percentile(&self, percent: u8) -> Option<Duration> {
+        let mut samples = Vec::from(self.0.lock().expect("not poisoned").clone());
+        if samples.len() < MIN_LATENCY_SAMPLES {
+            return None;
+        }
+        samples.sort_unstable();
+        let rank = (samples.len() * usize::from(percent)).div_ceil(100);
+        Some(samples[rank.clamp(1, samples.len()) - 1])
+    }
+
+    fn timeout(&self, min: Duration, max: Duration) -> Duration {
+        match self.percentile(95) {
+            Some(p95) => p95.saturating_mul(2).clamp(min, max),
+            None => max,
+        }
+    }
+}
+
+impl<S> ChatOverWebSocket<S> {
+    pub fn health(&self) -> ConnectionHealth {
+        ConnectionHealth {
+            state: self.state(),
+            rtt_estimate: self.rtt_estimator.estimate(),
+            latency_p50: self.latencies.percentile(50),
+            latency_p95: self.latencies.percentile(95),
+        }
+    }
+
+    /// The timeout [`Self::send_with_adaptive_timeout`] would use right now.
+    ///
+    /// This is twice the 95th percentile of the recent request latencies, kept between `min` and
+    /// `max`, or `max` if there aren't enough latencies yet.
+    ///
+    /// # Panics
+    ///
+    /// If `min` is greater than `max`.
+    pub fn adaptive_timeout(&self, min: Duration, max: Duration) -> Duration {
+        self.latencies.timeout(min, max)
+    }
+}
+
+impl<S: AsyncDuplexStream> ChatOverWebSocket<S> {
+    /// Sends `request` with a timeout based on how quickly the server has been responding.
+    ///
+    /// See [`Self::adaptive_timeout`].
+    pub async fn send_with_adaptive_timeout(
+        &self,
+        request: Request,
+        min: Duration,
+        max: Duration,
+    ) -> Result<Response, ChatServiceError> {
+        let timeout = self.adaptive_timeout(min, max);
+        self.send(request, timeout).await
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    const MIN: Duration = Duration::from_millis(100);
+    const MAX: Duration = Duration::from_secs(10);
+
+    fn latencies(millis: impl IntoIterator<Item = u64>) -> RequestLatencies {
+        let latencies = RequestLatencies::default();
+        for ms in millis {
+            latencies.record(Duration::from_millis(ms));
+        }
+        latencies
+    }
+
+    #[test]
+    fn too_few_samples_use_the_maximum() {
+        let latencies = latencies([10, 20, 30, 40]);
+        assert_eq!(latencies.percentile(95), None);
+        assert_eq!(latencies.timeout(MIN, MAX), MAX);
+    }
+
+    #[test]
+    fn percentiles_use_nearest_rank() {
+        let latencies = latencies((1..=20).rev().map(|i| i * 10));
+        assert_eq!(latencies.percentile(50), Some(Duration::from_millis(100)));
+        assert_eq!(latencies.percentile(95), Some(Duration::from_millis(190)));
+        assert_eq!(latencies.timeout(MIN, MAX), Duration::from_millis(380));
+    }
+
+    #[test]
+    fn only_the_most_recent_samples_count() {
+        let latencies = latencies(std::iter::repeat(5000).take(LATENCY_WINDOW_SIZE));
+        assert_eq!(latencies.timeout(MIN, MAX), MAX);
+        for _ in 0..LATENCY_WINDOW_SIZE {
+            latencies.record(Duration::from_millis(1));
+        }
+        assert_eq!(latencies.percentile(95), Some(Duration::from_millis(1)));
+        assert_eq!(latencies.timeout(MIN, MAX), MIN);
+    }
+}
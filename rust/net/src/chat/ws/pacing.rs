@@ -0,0 +1,106 @@
+//
+// Copyright 2024 Signal Messenger, LLC.
+// SPDX-License-Identifier: AGPL-3.0-only
+//
+
+//! Spreading out bursts of low-priority requests.
+//!
+//! Sending hundreds of requests at once (like read receipts after catching up on a conversation)
+//! puts hundreds of frames on the wire and hundreds of entries in the pending requests map, and
+//! can trip the server's rate limits. [`ChatOverWebSocket::send_batched`] sends them one at a time
+//! with a short delay in between, and with a limited number waiting for a response at once.
+
+use std::num::NonZeroUsize;
+use std::time::Duration;
+
+use futures_util::stream::FuturesUnordered;
+use futures_util::StreamExt as _;
+use http::StatusCode;
+use libsignal_net_infra::{extract_retry_after_seconds, logging, AsyncDuplexStream};
+use nonzero_ext::nonzero;
+use tokio::time::Instant;
+
+use crate::chat::ws::ChatOverWebSocket;
+use crate::chat::{ChatService as _, ChatServiceError, Request, Response};
+
+/// How long a batch is paused after a 429 response without a usable Retry-After header.
+const DEFAULT_RATE_LIMIT_PAUSE: Duration = Duration::from_secs(1);
+
+/// Limits on how quickly [`ChatOverWebSocket::send_batched`] sends requests.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct BatchPacing {
+    /// The minimum time between sending one request of the batch and the next.
+    pub inter_frame_delay: Duration,
+    /// The maximum number of requests of the batch waiting for a response at once.
+    pub max_in_flight: NonZeroUsize,
+}
+
+impl Default for BatchPacing {
+    fn default() -> Self {
+        Self {
+            inter_frame_delay: Duration::from_millis(5),
+            max_in_flight: nonzero!(8usize),
+        }
+    }
+}
+
+impl<S: AsyncDuplexStream> ChatOverWebSocket<S> {
+    /// Sends low-priority `requests` according to `pacing`, and returns their results in the same
+    /// order.
+    ///
+    /// If the server responds to any request with 429 Too Many Requests, no more requests are
+    /// sent until its Retry-After duration has passed. The rate-limited request isn't retried;
+    /// its response is returned like any other.
+    ///
+    /// Each request gets its own `timeout`, counted from when it's sent.
+    pub async fn send_batched(
+        &self,
+        requests: Vec<Request>,
+        timeout: Duration,
+        pacing: BatchPacing,
+    ) -> Vec<Result<Response, ChatServiceError>> {
+        let mut results = std::iter::repeat_with(|| None)
+            .take(requests.len())
+            .collect::<Vec<_>>();
+        let mut unsent = requests.into_iter().enumerate().peekable();
+        let mut in_flight = FuturesUnordered::new();
+        let mut next_send_at = Instant::now();
+
+        loop {
+            let can_send = unsent.peek().is_some() && in_flight.len() < pacing.max_in_flight.get();
+            tokio::select! {
+                biased;
+                Some((index, result)) = in_flight.next() => {
+                    if let Some(pause) = rate_limit_pause(&result) {
+                        logging::info!("chat batch rate limited; pausing for {pause:?}");
+                        next_send_at = next_send_at.max(Instant::now() + pause);
+                    }
+                    results[index] = Some(result);
+                }
+                () = tokio::time::sleep_until(next_send_at), if can_send => {
+                    let (index, request) = unsent.next().expect("checked above");
+                    in_flight.push(async move { (index, self.send(request, timeout).await) });
+                    next_send_at = Instant::now() + pacing.inter_frame_delay;
+                }
+                else => break,
+            }
+        }
+
+        results
+            .into_iter()
+            .map(|result| result.expect("every request finished"))
+            .collect()
+    }
+}
+
+fn rate_limit_pause(result: &Result<Response, ChatServiceError>) -> Option<Duration> {
+    let response = result.as_ref().ok()?;
+    if response.status != StatusCode::TOO_MANY_REQUESTS {
+        return None;
+    }
+    Some(
+        extract_retry_after_seconds(&response.headers)
+            .map(|seconds| Duration::from_secs(seconds.into()))
+            .unwrap_or(DEFAULT_RATE_LIMIT_PAUSE),
+    )
+}
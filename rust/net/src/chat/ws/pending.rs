@@ -0,0 +1,269 @@
+//
+// Copyright 2024 Signal Messenger, LLC.
+// SPDX-License-Identifier: AGPL-3.0-only
+//
+
+//! Bookkeeping for requests sent over a chat websocket that are still waiting for a response.
+//!
+//! Pending requests are kept in a sharded slab rather than a hash map. A request ID encodes where
+//! its responder lives, so looking one up is just indexing, and the reader task and the tasks
+//! sending requests only contend when they happen to touch the same shard. Once the slab has grown
+//! to the number of requests in flight, inserting and removing don't allocate.
+
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
+
+use tokio::sync::oneshot;
+
+use crate::chat::ResponseProto;
+
+const SHARD_BITS: u32 = 4;
+const SHARD_COUNT: usize = 1 << SHARD_BITS;
+const SLOT_BITS: u32 = 20;
+const MAX_SLOTS_PER_SHARD: usize = 1 << SLOT_BITS;
+const GENERATION_SHIFT: u32 = SHARD_BITS + SLOT_BITS;
+/// The last generation a slot may be used for, after which it is retired.
+///
+/// This stops one short of all ones, so no request ever gets the ID `u64::MAX`.
+const MAX_GENERATION: u64 = (1 << (u64::BITS - GENERATION_SHIFT)) - 2;
+
+#[derive(Debug, Default, Eq, Hash, PartialEq, Clone, Copy)]
+pub struct RequestId {
+    pub(super) id: u64,
+}
+
+impl RequestId {
+    pub(super) const fn new(id: u64) -> Self {
+        Self { id }
+    }
+
+    fn encode(shard: usize, slot: usize, generation: u64) -> Self {
+        debug_assert!(shard < SHARD_COUNT);
+        debug_assert!(slot < MAX_SLOTS_PER_SHARD);
+        debug_assert!(generation <= MAX_GENERATION);
+        Self::new((generation << GENERATION_SHIFT) | ((slot as u64) << SHARD_BITS) | shard as u64)
+    }
+
+    /// Splits the ID into its shard, slot, and generation.
+    ///
+    /// Any `u64` can be decoded, since IDs sent by the server aren't trusted; one that was never
+    /// issued just won't match a pending request.
+    fn decode(self) -> (usize, usize, u64) {
+        let shard = (self.id as usize) & (SHARD_COUNT - 1);
+        let slot = ((self.id >> SHARD_BITS) as usize) & (MAX_SLOTS_PER_SHARD - 1);
+        (shard, slot, self.id >> GENERATION_SHIFT)
+    }
+}
+
+#[derive(Debug)]
+pub struct NoMoreRequests;
+
+/// The responders for requests that are waiting for a response.
+///
+/// IDs are unique for the lifetime of the map: each time a slot is reused, its generation goes up,
+/// and a slot whose generations have run out is never used again.
+#[derive(Debug, Default)]
+pub struct PendingMessagesMap {
+    shards: [Mutex<Shard>; SHARD_COUNT],
+    next_shard: AtomicUsize,
+}
+
+#[derive(Debug, Default)]
+struct Shard {
+    slots: Vec<Slot>,
+    /// Indexes of the slots that are vacant and can be reused.
+    free: Vec<usize>,
+    /// Set by [`PendingMessagesMap::cancel_all`], after which nothing can be inserted.
+    cancelled: bool,
+}
+
+#[derive(Debug, Default)]
+struct Slot {
+    generation: u64,
+    responder: Option<oneshot::Sender<ResponseProto>>,
+}
+
+impl PendingMessagesMap {
+    pub fn insert(
+        &self,
+        responder: oneshot::Sender<ResponseProto>,
+    ) -> Result<RequestId, NoMoreRequests> {
+        let shard_index = self.next_shard.fetch_add(1, Ordering::Relaxed) % SHARD_COUNT;
+        let mut guard = self.shards[shard_index].lock().expect("not poisoned");
+        let shard = &mut *guard;
+        if shard.cancelled {
+            return Err(NoMoreRequests);
+        }
+
+        let slot_index = shard.free.pop().unwrap_or_else(|| {
+            assert!(
+                shard.slots.len() < MAX_SLOTS_PER_SHARD,
+                "too many requests in flight at once"
+            );
+            shard.slots.push(Slot::default());
+            shard.slots.len() - 1
+        });
+        let slot = &mut shard.slots[slot_index];
+        let prev = slot.responder.replace(responder);
+        assert!(prev.is_none(), "only vacant slots are on the free list");
+        Ok(RequestId::encode(shard_index, slot_index, slot.generation))
+    }
+
+    pub fn remove(&self, id: &RequestId) -> Option<oneshot::Sender<ResponseProto>> {
+        let (shard_index, slot_index, generation) = id.decode();
+        let mut guard = self.shards[shard_index].lock().expect("not poisoned");
+        let shard = &mut *guard;
+        let slot = shard.slots.get_mut(slot_index)?;
+        if slot.generation != generation {
+            return None;
+        }
+        let responder = slot.responder.take()?;
+
+        slot.generation += 1;
+        if slot.generation <= MAX_GENERATION {
+            shard.free.push(slot_index);
+        }
+        Some(responder)
+    }
+
+    pub fn cancel_all(&self) {
+        for shard in &self.shards {
+            let mut shard = shard.lock().expect("not poisoned");
+            shard.cancelled = true;
+            // Nothing can be inserted any more, so there's no need to keep the generations.
+            shard.slots.clear();
+            shard.free.clear();
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::collections::HashSet;
+    use std::sync::Arc;
+
+    use assert_matches::assert_matches;
+    use test_case::test_case;
+
+    use super::*;
+
+    fn responder() -> (
+        oneshot::Sender<ResponseProto>,
+        oneshot::Receiver<ResponseProto>,
+    ) {
+        oneshot::channel()
+    }
+
+    fn response(id: u64) -> ResponseProto {
+        ResponseProto {
+            id: Some(id),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn ids_are_unique_as_slots_are_reused() {
+        let map = PendingMessagesMap::default();
+        let mut seen = HashSet::new();
+        for _ in 0..10 * SHARD_COUNT {
+            let first = map.insert(responder().0).expect("not cancelled");
+            let second = map.insert(responder().0).expect("not cancelled");
+            assert!(seen.insert(first), "{first:?} reused");
+            assert!(seen.insert(second), "{second:?} reused");
+            map.remove(&first).expect("pending");
+            map.remove(&second).expect("pending");
+        }
+    }
+
+    #[test]
+    fn stale_id_does_not_match_reused_slot() {
+        let map = PendingMessagesMap::default();
+        let old = map.insert(responder().0).expect("not cancelled");
+        map.remove(&old).expect("pending");
+
+        // Go around the shards until the same slot is handed out again.
+        let new = loop {
+            let id = map.insert(responder().0).expect("not cancelled");
+            if id.decode().0 == old.decode().0 {
+                break id;
+            }
+        };
+        assert_eq!(new.decode().1, old.decode().1);
+        assert_ne!(new, old);
+
+        assert_matches!(map.remove(&old), None);
+        assert_matches!(map.remove(&new), Some(_));
+        assert_matches!(map.remove(&new), None);
+    }
+
+    #[test_case(0; "zero")]
+    #[test_case(12_345_678_901_234; "never issued")]
+    #[test_case(u64::MAX; "max")]
+    fn unknown_ids_are_ignored(id: u64) {
+        let map = PendingMessagesMap::default();
+        assert_matches!(map.remove(&RequestId::new(id)), None);
+    }
+
+    #[test]
+    fn exhausted_slot_is_retired() {
+        let map = PendingMessagesMap::default();
+        let id = map.insert(responder().0).expect("not cancelled");
+        let (shard_index, slot_index, _) = id.decode();
+        map.shards[shard_index].lock().expect("not poisoned").slots[slot_index].generation =
+            MAX_GENERATION;
+        let id = RequestId::encode(shard_index, slot_index, MAX_GENERATION);
+        assert_ne!(id.id, u64::MAX);
+        map.remove(&id).expect("pending");
+
+        let shard = map.shards[shard_index].lock().expect("not poisoned");
+        assert!(shard.free.is_empty(), "{:?}", shard.free);
+    }
+
+    #[test]
+    fn cancel_all_drops_responders_and_rejects_new_requests() {
+        let map = PendingMessagesMap::default();
+        let (tx, mut rx) = responder();
+        let id = map.insert(tx).expect("not cancelled");
+
+        map.cancel_all();
+        assert_matches!(rx.try_recv(), Err(oneshot::error::TryRecvError::Closed));
+        assert_matches!(map.remove(&id), None);
+        for _ in 0..SHARD_COUNT {
+            assert_matches!(map.insert(responder().0), Err(NoMoreRequests));
+        }
+    }
+
+    #[test]
+    fn concurrent_requests_get_their_own_responses() {
+        const THREADS: u64 = 8;
+        const ROUNDS: u64 = 200;
+        const IN_FLIGHT: u64 = 50;
+
+        let map = Arc::new(PendingMessagesMap::default());
+        let threads = (0..THREADS)
+            .map(|thread| {
+                let map = Arc::clone(&map);
+                std::thread::spawn(move || {
+                    for round in 0..ROUNDS {
+                        let pending = (0..IN_FLIGHT)
+                            .map(|i| {
+                                let (tx, rx) = responder();
+                                let tag = (thread * ROUNDS + round) * IN_FLIGHT + i;
+                                (map.insert(tx).expect("not cancelled"), tag, rx)
+                            })
+                            .collect::<Vec<_>>();
+                        for (id, tag, mut rx) in pending {
+                            let tx = map.remove(&id).expect("pending");
+                            tx.send(response(tag)).expect("receiver alive");
+                            assert_eq!(rx.try_recv().expect("sent").id, Some(tag));
+                        }
+                    }
+                })
+            })
+            .collect::<Vec<_>>();
+
+        for thread in threads {
+            thread.join().expect("no panics");
+        }
+    }
+}
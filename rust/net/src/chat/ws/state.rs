@@ -0,0 +1,235 @@
+//
+// Copyright 2024 Signal Messenger, LLC.
+// SPDX-License-Identifier: AGPL-3.0-only
+//
+
+//! The lifecycle of a single chat websocket connection.
+//!
+//! The reader task drives the connection from [`ConnectionState::Connecting`] to
+//! [`ConnectionState::Stopped`]; senders only ever look at the state, and register new requests
+//! while holding it so that a request can't slip in after the connection has stopped.
+
+use futures_util::FutureExt as _;
+use libsignal_net_infra::service::{CancellationReason, CancellationToken};
+use tokio::sync::watch;
+
+/// Why a chat connection stopped.
+///
+/// This is the same reason recorded on the connection's [`CancellationToken`].
+pub type StopReason = CancellationReason;
+
+/// Where a chat connection is in its lifecycle.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ConnectionState {
+    /// The websocket is established, but the task reading from it hasn't started yet.
+    ///
+    /// Requests can already be sent; their responses will be read once the task starts.
+    Connecting,
+    /// The connection is in use.
+    Active,
+    /// The server announced that it's shutting down. No new requests are accepted.
+    Draining,
+    /// The connection is closed for good.
+    Stopped(StopReason),
+}
+
+impl ConnectionState {
+    fn accepts_requests(&self) -> bool {
+        match self {
+            Self::Connecting | Self::Active => true,
+            Self::Draining | Self::Stopped(_) => false,
+        }
+    }
+}
+
+/// Shared between a [`ChatOverWebSocket`](super::ChatOverWebSocket) and its reader task.
+///
+/// The connection's [`CancellationToken`] is kept for the infra layer, which cancels it directly
+/// (on a close frame, or from [`Service::disconnect`](libsignal_net_infra::service::Service)).
+/// Every transition to [`ConnectionState::Stopped`] cancels the token as well, and the reader task
+/// notices cancellations made elsewhere and stops with the same reason.
+#[derive(Debug)]
+pub(super) struct ConnectionLifecycle {
+    state: watch::Sender<ConnectionState>,
+    service_cancellation: CancellationToken,
+}
+
+impl ConnectionLifecycle {
+    pub(super) fn new(service_cancellation: CancellationToken) -> Self {
+        Self {
+            state: watch::Sender::new(ConnectionState::Connecting),
+            service_cancellation,
+        }
+    }
+
+    pub(super) fn state(&self) -> ConnectionState {
+        self.state.borrow().clone()
+    }
+
+    /// Runs `register` if new requests are still accepted.
+    ///
+    /// The state can't change to [`ConnectionState::Stopped`] while `register` runs, so anything it
+    /// registers is guaranteed to be cleaned up by the reader task when the connection stops.
+    pub(super) fn register_if_accepting<T>(
+        &self,
+        register: impl FnOnce() -> T,
+    ) -> Result<T, ConnectionState> {
+        let state = self.state.borrow();
+        // The infra layer may have cancelled the token before the reader task has caught up.
+        if !state.accepts_requests() || self.service_cancellation.is_cancelled() {
+            return Err(state.clone());
+        }
+        Ok(register())
+    }
+
+    pub(super) fn set_active(&self) {
+        self.state.send_if_modified(|state| match state {
+            ConnectionState::Connecting => {
+                *state = ConnectionState::Active;
+                true
+            }
+            ConnectionState::Active | ConnectionState::Draining | ConnectionState::Stopped(_) => {
+                false
+            }
+        });
+    }
+
+    pub(super) fn set_draining(&self) {
+        self.state.send_if_modified(|state| match state {
+            ConnectionState::Connecting | ConnectionState::Active => {
+                *state = ConnectionState::Draining;
+                true
+            }
+            ConnectionState::Draining | ConnectionState::Stopped(_) => false,
+        });
+    }
+
+    /// Stops the connection, if it hasn't been stopped already.
+    ///
+    /// Returns the reason the connection actually stopped for, which is `reason` unless the
+    /// connection was already cancelled for some other reason.
+    pub(super) fn stop(&self, reason: StopReason) -> StopReason {
+        self.service_cancellation.cancel(reason);
+        let reason = self
+            .cancellation_reason()
+            .expect("cancelled immediately above");
+        self.state.send_if_modified(|state| match state {
+            ConnectionState::Stopped(_) => false,
+            ConnectionState::Connecting | ConnectionState::Active | ConnectionState::Draining => {
+                *state = ConnectionState::Stopped(reason.clone());
+                true
+            }
+        });
+        reason
+    }
+
+    /// The reason the connection's token was cancelled, if it has been.
+    pub(super) fn cancellation_reason(&self) -> Option<CancellationReason> {
+        self.service_cancellation.cancelled().now_or_never()
+    }
+
+    pub(super) async fn wait_for_stopped(&self) -> StopReason {
+        let mut state = self.state.subscribe();
+        let stopped = state
+            .wait_for(|state| matches!(state, ConnectionState::Stopped(_)))
+            .await
+            .expect("sender is alive as long as self");
+        match &*stopped {
+            ConnectionState::Stopped(reason) => reason.clone(),
+            ConnectionState::Connecting | ConnectionState::Active | ConnectionState::Draining => {
+                unreachable!("waited for Stopped")
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use assert_matches::assert_matches;
+
+    use super::*;
+
+    #[test]
+    fn stop_keeps_the_first_reason() {
+        let token = CancellationToken::new();
+        let lifecycle = ConnectionLifecycle::new(token.clone());
+        lifecycle.set_active();
+        assert_eq!(lifecycle.state(), ConnectionState::Active);
+
+        token.cancel(CancellationReason::ExplicitDisconnect);
+        assert_eq!(
+            lifecycle.stop(CancellationReason::RemoteClose),
+            CancellationReason::ExplicitDisconnect
+        );
+        assert_eq!(
+            lifecycle.state(),
+            ConnectionState::Stopped(CancellationReason::ExplicitDisconnect)
+        );
+
+        lifecycle.stop(CancellationReason::ProtocolError);
+        assert_eq!(
+            lifecycle.state(),
+            ConnectionState::Stopped(CancellationReason::ExplicitDisconnect)
+        );
+    }
+
+    #[test]
+    fn stopping_cancels_the_token() {
+        let token = CancellationToken::new();
+        let lifecycle = ConnectionLifecycle::new(token.clone());
+        lifecycle.stop(CancellationReason::ProtocolError);
+        assert_eq!(
+            token.cancelled().now_or_never(),
+            Some(CancellationReason::ProtocolError)
+        );
+    }
+
+    #[test]
+    fn registration_stops_with_the_connection() {
+        let token = CancellationToken::new();
+        let lifecycle = ConnectionLifecycle::new(token.clone());
+        assert_matches!(lifecycle.register_if_accepting(|| ()), Ok(()));
+        lifecycle.set_active();
+        assert_matches!(lifecycle.register_if_accepting(|| ()), Ok(()));
+
+        lifecycle.set_draining();
+        assert_matches!(
+            lifecycle.register_if_accepting(|| ()),
+            Err(ConnectionState::Draining)
+        );
+
+        lifecycle.stop(CancellationReason::ServerDraining);
+        lifecycle.set_active();
+        assert_matches!(
+            lifecycle.register_if_accepting(|| ()),
+            Err(ConnectionState::Stopped(CancellationReason::ServerDraining))
+        );
+    }
+
+    #[test]
+    fn registration_fails_once_the_token_is_cancelled() {
+        let token = CancellationToken::new();
+        let lifecycle = ConnectionLifecycle::new(token.clone());
+        lifecycle.set_active();
+        token.cancel(CancellationReason::RemoteClose);
+        assert_matches!(
+            lifecycle.register_if_accepting(|| ()),
+            Err(ConnectionState::Active)
+        );
+    }
+
+    #[tokio::test]
+    async fn wait_for_stopped_returns_the_reason() {
+        let lifecycle = ConnectionLifecycle::new(CancellationToken::new());
+        let (reason, ()) = tokio::join!(lifecycle.wait_for_stopped(), async {
+            lifecycle.stop(CancellationReason::RemoteClose);
+        });
+        assert_eq!(reason, CancellationReason::RemoteClose);
+
+        // And once stopped, it returns immediately.
+        assert_eq!(
+            lifecycle.wait_for_stopped().now_or_never(),
+            Some(CancellationReason::RemoteClose)
+        );
+    }
+}
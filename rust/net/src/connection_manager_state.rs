@@ -0,0 +1,77 @@
+//
+// Copyright 2024 Signal Messenger, LLC.
+// SPDX-License-Identifier: AGPL-3.0-only
+//
+
+//! Saving route cooldowns across restarts of the app.
+//!
+//! The app is expected to store the bytes produced by [`serialize`] somewhere persistent when it's
+//! about to be suspended or terminated, and pass them to [`deserialize`] when it starts again.
+
+use libsignal_net_infra::connection_manager::{ConnectionManagerState, RouteCooldownState};
+use prost::Message as _;
+
+use crate::proto::connection_manager_state as proto;
+
+pub fn serialize(state: &ConnectionManagerState) -> Vec<u8> {
+    proto::ConnectionManagerState {
+        routes: state
+            .routes
+            .iter()
+            .map(|route| proto::RouteCooldown {
+                route: route.route.clone(),
+                consecutive_failures: route.consecutive_failures.into(),
+                cooldown_until_unix_millis: route.cooldown_until_unix_millis,
+            })
+            .collect(),
+    }
+    .encode_to_vec()
+}
+
+pub fn deserialize(bytes: &[u8]) -> Result<ConnectionManagerState, prost::DecodeError> {
+    let proto::ConnectionManagerState { routes } = proto::ConnectionManagerState::decode(bytes)?;
+    Ok(ConnectionManagerState {
+        routes: routes
+            .into_iter()
+            .map(|route| RouteCooldownState {
+                route: route.route,
+                // Out-of-range counts are clamped when the state is imported anyway.
+                consecutive_failures: route.consecutive_failures.try_into().unwrap_or(u16::MAX),
+                cooldown_until_unix_millis: route.cooldown_until_unix_millis,
+            })
+            .collect(),
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn round_trip() {
+        let state = ConnectionManagerState {
+            routes: vec![
+                RouteCooldownState {
+                    route: "direct chat.signal.org via chat.signal.org:443".to_owned(),
+                    consecutive_failures: 3,
+                    cooldown_until_unix_millis: 1_700_000_002_000,
+                },
+                RouteCooldownState {
+                    route: "proxyg chat.signal.org via reflector.example:443".to_owned(),
+                    consecutive_failures: 1,
+                    cooldown_until_unix_millis: 1_700_000_000_000,
+                },
+            ],
+        };
+        assert_eq!(deserialize(&serialize(&state)).expect("valid"), state);
+        assert_eq!(
+            deserialize(&[]).expect("valid"),
+            ConnectionManagerState::default()
+        );
+    }
+
+    #[test]
+    fn rejects_garbage() {
+        deserialize(b"\xff\xff\xff").expect_err("invalid");
+    }
+}
@@ -0,0 +1,390 @@
+//
+// Copyright 2024 Signal Messenger, LLC.
+// SPDX-License-Identifier: AGPL-3.0-only
+//
+
+//! Reports which of Signal's services can be reached from the current network.
+//!
+//! None of the checks need credentials: each one stops as soon as the service has answered, so a
+//! service counts as reachable even if it would go on to reject an unauthenticated client.
+
+use std::borrow::Cow;
+use std::future::Future;
+use std::time::Duration;
+
+use bytes::Bytes;
+use futures_util::future::join_all;
+use http::uri::PathAndQuery;
+use http::HeaderMap;
+use libsignal_net_infra::errors::TransportConnectError;
+use libsignal_net_infra::http_client::{http2_client, HttpError};
+use libsignal_net_infra::service::ServiceConnector as _;
+use libsignal_net_infra::ws::{
+    WebSocketClientConnector, WebSocketConnectError, WebSocketServiceError,
+};
+use libsignal_net_infra::{make_ws_config, Alpn, ConnectionParams, TransportConnector};
+use tokio::time::Instant;
+
+use crate::enclave::{Cdsi, EnclaveKind as _};
+use crate::env::{ConnectionConfig, Env};
+
+/// HEAD responses don't have a body, but the client insists on a limit.
+const MAX_HEAD_RESPONSE_SIZE: usize = 1024;
+
+/// The services checked by [`run_connectivity_report`].
+#[derive(Clone)]
+pub struct ConnectivityTargets {
+    /// Checked with a TLS handshake.
+    pub chat: ConnectionConfig,
+    /// Checked with a websocket upgrade request to `cdsi_path`.
+    pub cdsi: ConnectionConfig,
+    pub cdsi_path: PathAndQuery,
+    /// Each checked with a HEAD request for `/`.
+    pub cdn: Vec<ConnectionConfig>,
+}
+
+impl ConnectivityTargets {
+    pub fn for_env<S>(env: &Env<'_, S>) -> Self {
+        Self {
+            chat: env.chat_domain_config.connect.clone(),
+            cdsi: env.cdsi.domain_config.connect.clone(),
+            cdsi_path: Cdsi::url_path(env.cdsi.params.mr_enclave.as_ref()),
+            cdn: env.cdn.to_vec(),
+        }
+    }
+}
+
+#[derive(Clone, Debug, serde::Serialize)]
+pub struct ConnectivityReport {
+    pub chat: ConnectivityCheck,
+    pub cdsi: ConnectivityCheck,
+    pub cdn: Vec<ConnectivityCheck>,
+}
+
+/// The outcome of checking a single service.
+#[derive(Clone, Debug, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ConnectivityCheck {
+    pub hostname: Cow<'static, str>,
+    /// How long the check took, whether or not it succeeded.
+    #[serde(rename = "latencyMs", serialize_with = "serialize_as_millis")]
+    pub latency: Duration,
+    /// Why the service couldn't be reached, or `None` if it could.
+    pub failure: Option<ConnectivityFailure>,
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, serde::Serialize, displaydoc::Display)]
+#[serde(rename_all = "camelCase")]
+pub enum ConnectivityFailure {
+    /// timed out
+    Timeout,
+    /// DNS lookup failed
+    Dns,
+    /// TCP connection failed
+    Tcp,
+    /// TLS handshake failed
+    Tls,
+    /// server certificate was not trusted
+    Certificate,
+    /// proxy handshake failed
+    Proxy,
+    /// invalid connection configuration
+    InvalidConfiguration,
+    /// server did not speak the expected protocol
+    Protocol,
+}
+
+impl From<&TransportConnectError> for ConnectivityFailure {
+    fn from(value: &TransportConnectError) -> Self {
+        match value {
+            TransportConnectError::InvalidConfiguration => Self::InvalidConfiguration,
+            TransportConnectError::TcpConnectionFailed => Self::Tcp,
+            TransportConnectError::DnsError => Self::Dns,
+            TransportConnectError::SslError(_) | TransportConnectError::SslFailedHandshake(_) => {
+                Self::Tls
+            }
+            TransportConnectError::CertError | TransportConnectError::UntrustedRoot => {
+                Self::Certificate
+            }
+            TransportConnectError::ProxyProtocol => Self::Proxy,
+        }
+    }
+}
+
+/// Checks all of `targets` concurrently, giving each check up to `timeout`.
+///
+/// Only direct connections are attempted, so that the report reflects the network itself rather
+/// than whichever fallback happened to work.
+pub async fn run_connectivity_report<T: TransportConnector>(
+    targets: &ConnectivityTargets,
+    transport_connector: &T,
+    timeout: Duration,
+) -> ConnectivityReport {
+    let ConnectivityTargets {
+        chat,
+        cdsi,
+        cdsi_path,
+        cdn,
+    } = targets;
+
+    let chat = check(chat, timeout, |params| {
+        tls_handshake(transport_connector, params)
+    });
+    let cdsi = check(cdsi, timeout, |params| {
+        websocket_upgrade(transport_connector, params, cdsi_path.clone(), timeout)
+    });
+    let cdn = join_all(
+        cdn.iter()
+            .map(|config| check(config, timeout, |params| head(transport_connector, params))),
+    );
+
+    let (chat, cdsi, cdn) = futures_util::join!(chat, cdsi, cdn);
+    ConnectivityReport { chat, cdsi, cdn }
+}
+
+async fn check<F: Future<Output = Result<(), ConnectivityFailure>>>(
+    config: &ConnectionConfig,
+    timeout: Duration,
+    attempt: impl FnOnce(ConnectionParams) -> F,
+) -> ConnectivityCheck {
+    let start = Instant::now();
+    let result = tokio::time::timeout(timeout, attempt(config.direct_connection_params()))
+        .await
+        .unwrap_or(Err(ConnectivityFailure::Timeout));
+    ConnectivityCheck {
+        hostname: config.hostname.clone(),
+        latency: start.elapsed(),
+        failure: result.err(),
+    }
+}
+
+async fn tls_handshake(
+    transport_connector: &impl TransportConnector,
+    params: ConnectionParams,
+) -> Result<(), ConnectivityFailure> {
+    transport_connector
+        .connect(&params.transport, Alpn::Http1_1)
+        .await
+        .map(|_| ())
+        .map_err(|e| (&e).into())
+}
+
+async fn websocket_upgrade<T: TransportConnector>(
+    transport_connector: &T,
+    params: ConnectionParams,
+    path: PathAndQuery,
+    timeout: Duration,
+) -> Result<(), ConnectivityFailure> {
+    let connector = WebSocketClientConnector::<_, WebSocketServiceError>::new(
+        transport_connector.clone(),
+        make_ws_config(path, timeout),
+    );
+    match connector.connect_channel(&params).await {
+        Ok(_) => Ok(()),
+        // Without credentials the upgrade is expected to be refused, but any HTTP response means
+        // the server was reached.
+        Err(
+            WebSocketConnectError::WebSocketError(tungstenite::Error::Http(_))
+            | WebSocketConnectError::RejectedByServer { .. },
+        ) => Ok(()),
+        Err(WebSocketConnectError::Transport(e)) => Err((&e).into()),
+        Err(WebSocketConnectError::Timeout) => Err(ConnectivityFailure::Timeout),
+        Err(WebSocketConnectError::WebSocketError(_) | WebSocketConnectError::SubProtocol(_)) => {
+            Err(ConnectivityFailure::Protocol)
+        }
+    }
+}
+
+async fn head(
+    transport_connector: &impl TransportConnector,
+    params: ConnectionParams,
+) -> Result<(), ConnectivityFailure> {
+    let client = http2_client(transport_connector, params, MAX_HEAD_RESPONSE_SIZE)
+        .await
+        .map_err(|e| match e {
+            HttpError::SslHandshakeFailed(e) => (&e).into(),
+            _ => ConnectivityFailure::Protocol,
+        })?;
+    let result = client
+        .send_request_aggregate_response(
+            PathAndQuery::from_static("/"),
+            http::Method::HEAD,
+            HeaderMap::new(),
+            Bytes::new(),
+        )
+        .await;
+    // Any response counts, whatever its status; the base URL isn't expected to serve anything.
+    // Errors after this point come from reading the (empty) body, which doesn't matter here.
+    match result {
+        Ok(_)
+        | Err(
+            HttpError::ContentLengthHeaderInvalid
+            | HttpError::FailedToReadContentOfKnownSize
+            | HttpError::FailedToReadContentOfUnknownSize
+            | HttpError::ResponseTooLarge,
+        ) => Ok(()),
+        Err(_) => Err(ConnectivityFailure::Protocol),
+    }
+}
+
+fn serialize_as_millis<S: serde::Serializer>(
+    duration: &Duration,
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    let millis = u64::try_from(duration.as_millis()).unwrap_or(u64::MAX);
+    serializer.serialize_u64(millis)
+}
+
+#[cfg(test)]
+mod test {
+    use std::num::NonZeroU16;
+
+    use async_trait::async_trait;
+    use libsignal_net_infra::testutil::InMemoryWarpConnector;
+    use libsignal_net_infra::ws::WebSocketMessageLimits;
+    use libsignal_net_infra::StreamAndInfo;
+    use tokio::io::DuplexStream;
+    use warp::{Filter, Reply};
+
+    use super::*;
+    use crate::certs::SIGNAL_ROOT_CERTIFICATES;
+
+    const TIMEOUT: Duration = Duration::from_secs(5);
+
+    /// Routes every connection to the same fake server, except for the listed hosts.
+    #[derive(Clone)]
+    struct FakeNetwork<F> {
+        server: InMemoryWarpConnector<F>,
+        refused: &'static [&'static str],
+        unresponsive: &'static [&'static str],
+    }
+
+    #[async_trait]
+    impl<F> TransportConnector for FakeNetwork<F>
+    where
+        F: Filter + Clone + Send + Sync + 'static,
+        F::Extract: Reply,
+    {
+        type Stream = DuplexStream;
+
+        async fn connect(
+            &self,
+            connection_params: &libsignal_net_infra::TransportConnectionParams,
+            alpn: Alpn,
+        ) -> Result<StreamAndInfo<Self::Stream>, TransportConnectError> {
+            let host = &*connection_params.sni;
+            if self.refused.contains(&host) {
+                return Err(TransportConnectError::TcpConnectionFailed);
+            }
+            if self.unresponsive.contains(&host) {
+                std::future::pending::<()>().await;
+            }
+            self.server.connect(connection_params, alpn).await
+        }
+    }
+
+    fn fake_network(
+        refused: &'static [&'static str],
+        unresponsive: &'static [&'static str],
+    ) -> FakeNetwork<impl Filter<Extract = impl Reply> + Clone + Send + Sync + 'static> {
+        // Like the real servers, refuse anything that isn't authenticated.
+        let server = warp::any()
+            .map(|| warp::reply::with_status(warp::reply(), http::StatusCode::UNAUTHORIZED));
+        FakeNetwork {
+            server: InMemoryWarpConnector::new(server),
+            refused,
+            unresponsive,
+        }
+    }
+
+    fn config(hostname: &'static str) -> ConnectionConfig {
+        ConnectionConfig {
+            hostname: Cow::Borrowed(hostname),
+            port: NonZeroU16::new(443).unwrap(),
+            cert: SIGNAL_ROOT_CERTIFICATES,
+            confirmation_header_name: None,
+            websocket_limits: WebSocketMessageLimits::DEFAULT,
+            proxy: None,
+        }
+    }
+
+    fn targets() -> ConnectivityTargets {
+        ConnectivityTargets {
+            chat: config("chat.test"),
+            cdsi: config("cdsi.test"),
+            cdsi_path: PathAndQuery::from_static("/v1/abcdef/discovery"),
+            cdn: vec![config("cdn.test"), config("cdn2.test")],
+        }
+    }
+
+    fn failures(report: &ConnectivityReport) -> Vec<(&str, Option<ConnectivityFailure>)> {
+        let ConnectivityReport { chat, cdsi, cdn } = report;
+        [chat, cdsi]
+            .into_iter()
+            .chain(cdn)
+            .map(|check| (check.hostname.as_ref(), check.failure))
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn all_reachable() {
+        let report = run_connectivity_report(&targets(), &fake_network(&[], &[]), TIMEOUT).await;
+        assert_eq!(
+            failures(&report),
+            [
+                ("chat.test", None),
+                ("cdsi.test", None),
+                ("cdn.test", None),
+                ("cdn2.test", None),
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn refused_connections_only_affect_their_own_check() {
+        let network = fake_network(&["cdsi.test", "cdn2.test"], &[]);
+        let report = run_connectivity_report(&targets(), &network, TIMEOUT).await;
+        assert_eq!(
+            failures(&report),
+            [
+                ("chat.test", None),
+                ("cdsi.test", Some(ConnectivityFailure::Tcp)),
+                ("cdn.test", None),
+                ("cdn2.test", Some(ConnectivityFailure::Tcp)),
+            ]
+        );
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn unresponsive_hosts_time_out() {
+        let network = fake_network(&[], &["chat.test"]);
+        let report = run_connectivity_report(&targets(), &network, TIMEOUT).await;
+        assert_eq!(report.chat.failure, Some(ConnectivityFailure::Timeout));
+        assert_eq!(report.chat.latency, TIMEOUT);
+        assert_eq!(
+            failures(&report)[1..],
+            [("cdsi.test", None), ("cdn.test", None), ("cdn2.test", None)]
+        );
+    }
+
+    #[test]
+    fn report_serialization() {
+        let report = ConnectivityReport {
+            chat: ConnectivityCheck {
+                hostname: "chat.test".into(),
+                latency: Duration::from_millis(120),
+                failure: None,
+            },
+            cdsi: ConnectivityCheck {
+                hostname: "cdsi.test".into(),
+                latency: Duration::from_millis(5),
+                failure: Some(ConnectivityFailure::Dns),
+            },
+            cdn: vec![],
+        };
+        assert_eq!(
+            serde_json::to_string(&report).expect("can serialize"),
+            r#"{"chat":{"hostname":"chat.test","latencyMs":120,"failure":null},"cdsi":{"hostname":"cdsi.test","latencyMs":5,"failure":"dns"},"cdn":[]}"#
+        );
+    }
+}
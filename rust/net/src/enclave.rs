@@ -3,6 +3,7 @@
 // SPDX-License-Identifier: AGPL-3.0-only
 //
 
+use std::borrow::Cow;
 use std::marker::PhantomData;
 use std::time::{Duration, SystemTime};
 
@@ -11,7 +12,8 @@ use attest::{cds2, enclave, nitro, tpm2snp};
 use derive_where::derive_where;
 use http::uri::PathAndQuery;
 use libsignal_net_infra::connection_manager::{
-    ConnectionManager, MultiRouteConnectionManager, SingleRouteThrottlingConnectionManager,
+    ConnectionManager, DeadlineConnectError, MultiRouteConnectionManager,
+    SingleRouteThrottlingConnectionManager,
 };
 use libsignal_net_infra::errors::LogSafeDisplay;
 use libsignal_net_infra::service::{
@@ -19,13 +21,14 @@ use libsignal_net_infra::service::{
 };
 use libsignal_net_infra::utils::ObservableEvent;
 use libsignal_net_infra::ws::{
-    AttestedConnection, AttestedConnectionError, WebSocketClientConnector, WebSocketConnectError,
-    WebSocketServiceError,
+    AttestedConnection, AttestedConnectionError, WebSocketClientConnector, WebSocketConfig,
+    WebSocketConnectError, WebSocketServiceError,
 };
 use libsignal_net_infra::{
-    make_ws_config, AsyncDuplexStream, ConnectionParams, EndpointConnection, HttpBasicAuth,
-    TransportConnector,
+    live_connections, logging, make_ws_config, AsyncDuplexStream, ConnectionParams,
+    EndpointConnection, HttpBasicAuth, TransportConnector,
 };
+use tokio::time::Instant;
 
 use crate::env::{DomainConfig, Svr3Env};
 use crate::svr::SvrConnection;
@@ -48,6 +51,8 @@ impl<'a> AsRaftConfig<'a> for &'a RaftConfig {
 
 pub trait EnclaveKind {
     type RaftConfigType: AsRaftConfig<'static> + Clone + Sync + Send;
+    /// How connections are counted by [`live_connections`](libsignal_net_infra::live_connections).
+    const CONNECTION_KIND: &'static str;
     fn url_path(enclave: &[u8]) -> PathAndQuery;
 }
 
@@ -65,6 +70,7 @@ pub enum Tpm2Snp {}
 
 impl EnclaveKind for Cdsi {
     type RaftConfigType = ();
+    const CONNECTION_KIND: &'static str = live_connections::CDSI;
     fn url_path(enclave: &[u8]) -> PathAndQuery {
         PathAndQuery::try_from(format!("/v1/{}/discovery", hex::encode(enclave))).unwrap()
     }
@@ -72,6 +78,7 @@ impl EnclaveKind for Cdsi {
 
 impl EnclaveKind for SgxPreQuantum {
     type RaftConfigType = &'static RaftConfig;
+    const CONNECTION_KIND: &'static str = live_connections::SVR;
     fn url_path(enclave: &[u8]) -> PathAndQuery {
         PathAndQuery::try_from(format!("/v1/{}", hex::encode(enclave))).unwrap()
     }
@@ -79,6 +86,7 @@ impl EnclaveKind for SgxPreQuantum {
 
 impl EnclaveKind for Sgx {
     type RaftConfigType = &'static RaftConfig;
+    const CONNECTION_KIND: &'static str = live_connections::SVR;
     fn url_path(enclave: &[u8]) -> PathAndQuery {
         PathAndQuery::try_from(format!("/v1/{}", hex::encode(enclave))).unwrap()
     }
@@ -86,6 +94,7 @@ impl EnclaveKind for Sgx {
 
 impl EnclaveKind for Nitro {
     type RaftConfigType = &'static RaftConfig;
+    const CONNECTION_KIND: &'static str = live_connections::SVR;
     fn url_path(enclave: &[u8]) -> PathAndQuery {
         PathAndQuery::try_from(format!(
             "/v1/{}",
@@ -97,6 +106,7 @@ impl EnclaveKind for Nitro {
 
 impl EnclaveKind for Tpm2Snp {
     type RaftConfigType = &'static RaftConfig;
+    const CONNECTION_KIND: &'static str = live_connections::SVR;
     fn url_path(enclave: &[u8]) -> PathAndQuery {
         PathAndQuery::try_from(format!(
             "/v1/{}",
@@ -221,7 +231,7 @@ impl<Bytes: AsRef<[u8]>, S> AsRef<[u8]> for MrEnclave<Bytes, S> {
 
 #[derive_where(Clone)]
 pub struct EndpointParams<'a, E: EnclaveKind> {
-    pub mr_enclave: MrEnclave<&'a [u8], E>,
+    pub mr_enclave: MrEnclave<Cow<'a, [u8]>, E>,
     pub raft_config: E::RaftConfigType,
 }
 
@@ -257,6 +267,8 @@ pub enum Error {
     AttestationError(attest::enclave::Error),
     /// Connection timeout
     ConnectionTimedOut,
+    /// Not connecting after repeated failures
+    CircuitOpen,
 }
 
 impl LogSafeDisplay for Error {}
@@ -272,6 +284,18 @@ impl From<AttestedConnectionError> for Error {
     }
 }
 
+impl<E: EnclaveKind, C> EnclaveEndpointConnection<E, C> {
+    /// The parameters of the enclave this connects to.
+    pub fn params(&self) -> &EndpointParams<'static, E> {
+        &self.params
+    }
+
+    /// The connection manager used to reach the enclave.
+    pub fn manager(&self) -> &C {
+        &self.endpoint_connection.manager
+    }
+}
+
 impl<E: EnclaveKind + NewHandshake, C: ConnectionManager> EnclaveEndpointConnection<E, C> {
     pub(crate) async fn connect<S: AsyncDuplexStream, T: TransportConnector<Stream = S>>(
         &self,
@@ -287,8 +311,63 @@ impl<E: EnclaveKind + NewHandshake, C: ConnectionManager> EnclaveEndpointConnect
         // unique `E: EnclaveKind`).
         connect_attested(
             &self.endpoint_connection,
+            E::CONNECTION_KIND,
             auth,
             transport_connector,
+            None,
+            false,
+            &move |attestation_message| E::new_handshake(&self.params, attestation_message),
+        )
+        .await
+    }
+
+    /// Like [`Self::connect`], but keeps trying routes until `deadline` instead of giving up as
+    /// soon as they're all in cooldown.
+    pub(crate) async fn connect_with_deadline<
+        S: AsyncDuplexStream,
+        T: TransportConnector<Stream = S>,
+    >(
+        &self,
+        auth: impl HttpBasicAuth,
+        transport_connector: T,
+        deadline: Instant,
+    ) -> Result<AttestedConnection<S>, Error>
+    where
+        C: ConnectionManager,
+    {
+        connect_attested(
+            &self.endpoint_connection,
+            E::CONNECTION_KIND,
+            auth,
+            transport_connector,
+            Some(deadline),
+            false,
+            &move |attestation_message| E::new_handshake(&self.params, attestation_message),
+        )
+        .await
+    }
+
+    /// Like [`Self::connect_with_deadline`], but also keeps the attestation message the server
+    /// sent, in [`AttestationInfo::raw_evidence`](libsignal_net_infra::ws::AttestationInfo).
+    pub(crate) async fn connect_capturing_evidence<
+        S: AsyncDuplexStream,
+        T: TransportConnector<Stream = S>,
+    >(
+        &self,
+        auth: impl HttpBasicAuth,
+        transport_connector: T,
+        deadline: Instant,
+    ) -> Result<AttestedConnection<S>, Error>
+    where
+        C: ConnectionManager,
+    {
+        connect_attested(
+            &self.endpoint_connection,
+            E::CONNECTION_KIND,
+            auth,
+            transport_connector,
+            Some(deadline),
+            true,
             &move |attestation_message| E::new_handshake(&self.params, attestation_message),
         )
         .await
@@ -299,14 +378,25 @@ impl<E: EnclaveKind + NewHandshake, C: ConnectionManager> EnclaveEndpointConnect
 ///
 /// Making the handshaker a concrete type (via `&dyn`) prevents this from being
 /// instantiated multiple times and duplicated in the generated code.
+#[cfg_attr(
+    feature = "tracing",
+    tracing::instrument(
+        name = "enclave.connect_attested",
+        skip_all,
+        fields(endpoint = %endpoint_connection.config.endpoint),
+    )
+)]
 async fn connect_attested<
     C: ConnectionManager,
     T: TransportConnector<Stream = S>,
     S: AsyncDuplexStream,
 >(
     endpoint_connection: &EndpointConnection<C>,
+    kind: &'static str,
     auth: impl HttpBasicAuth,
     transport_connector: T,
+    deadline: Option<Instant>,
+    capture_evidence: bool,
     do_handshake: &(dyn Sync + Fn(&[u8]) -> enclave::Result<enclave::Handshake>),
 ) -> Result<AttestedConnection<S>, Error> {
     let auth_decorator = auth.into();
@@ -318,18 +408,33 @@ async fn connect_attested<
         auth_decorator,
     );
     let service_initializer = ServiceInitializer::new(connector, &endpoint_connection.manager);
-    let connection_attempt_result = service_initializer.connect().await;
-    let websocket = match connection_attempt_result {
-        ServiceState::Active(websocket, _) => Ok(websocket),
-        ServiceState::Error(e) => Err(Error::WebSocketConnect(e)),
-        ServiceState::Cooldown(_) | ServiceState::ConnectionTimedOut => {
-            Err(Error::ConnectionTimedOut)
-        }
-        ServiceState::Inactive => {
-            unreachable!("can't be returned by the initializer")
-        }
+    let websocket = match deadline {
+        None => match service_initializer.connect().await {
+            ServiceState::Active(websocket, _) => Ok(websocket),
+            ServiceState::Error(e) => Err(Error::WebSocketConnect(e)),
+            ServiceState::Cooldown(_) | ServiceState::ConnectionTimedOut => {
+                Err(Error::ConnectionTimedOut)
+            }
+            ServiceState::Inactive => {
+                unreachable!("can't be returned by the initializer")
+            }
+        },
+        Some(deadline) => match service_initializer.connect_with_deadline(deadline).await {
+            Ok((websocket, _)) => Ok(websocket),
+            Err(DeadlineConnectError::Fatal(e)) => Err(Error::WebSocketConnect(e)),
+            Err(DeadlineConnectError::ConnectionTimedOut(attempts)) => {
+                logging::info!("enclave connection timed out: {attempts}");
+                Err(Error::ConnectionTimedOut)
+            }
+        },
     }?;
-    let attested = AttestedConnection::connect(websocket, do_handshake).await?;
+    let attested = AttestedConnection::connect_capturing_evidence(
+        websocket,
+        kind,
+        do_handshake,
+        capture_evidence,
+    )
+    .await?;
     Ok(attested)
 }
 
@@ -346,10 +451,13 @@ impl<E: EnclaveKind> EnclaveEndpointConnection<E, SingleRouteThrottlingConnectio
                     connect_timeout,
                     network_change_event,
                 ),
-                config: make_ws_config(
-                    E::url_path(endpoint.params.mr_enclave.as_ref()),
-                    connect_timeout,
-                ),
+                config: WebSocketConfig {
+                    ws_config: endpoint.domain_config.connect.websocket_limits.ws_config(),
+                    ..make_ws_config(
+                        E::url_path(endpoint.params.mr_enclave.as_ref()),
+                        connect_timeout,
+                    )
+                },
             },
             params: endpoint.params.clone(),
         }
@@ -367,10 +475,13 @@ impl<E: EnclaveKind> EnclaveEndpointConnection<E, MultiRouteConnectionManager> {
             endpoint_connection: EndpointConnection::new_multi(
                 connection_params,
                 one_route_connect_timeout,
-                make_ws_config(
-                    E::url_path(endpoint.params.mr_enclave.as_ref()),
-                    one_route_connect_timeout,
-                ),
+                WebSocketConfig {
+                    ws_config: endpoint.domain_config.connect.websocket_limits.ws_config(),
+                    ..make_ws_config(
+                        E::url_path(endpoint.params.mr_enclave.as_ref()),
+                        one_route_connect_timeout,
+                    )
+                },
                 network_change_event,
             ),
             params: endpoint.params.clone(),
@@ -471,15 +582,22 @@ mod test {
     use libsignal_net_infra::connection_manager::ConnectionAttemptOutcome;
     use libsignal_net_infra::errors::TransportConnectError;
     use libsignal_net_infra::host::Host;
+    use libsignal_net_infra::ws::testutil::{run_attested_server, AttestedServerOutput};
+    use libsignal_net_infra::ws::NextOrClose;
     use libsignal_net_infra::{
-        Alpn, HttpRequestDecoratorSeq, RouteType, StreamAndInfo, TransportConnectionParams,
+        Alpn, ConnectionInfo, DnsSource, HttpRequestDecoratorSeq, RouteType, StreamAndInfo,
+        TransportConnectionParams,
     };
     use nonzero_ext::nonzero;
+    use tokio::io::DuplexStream;
     use tokio::net::TcpStream;
+    use tokio::sync::{mpsc, oneshot};
     use tokio_boring_signal::SslStream;
+    use tungstenite::handshake::server::{ErrorResponse, Request, Response};
 
     use super::*;
     use crate::auth::Auth;
+    use crate::env::{EnvBuilder, STAGING};
 
     #[derive(Clone, Debug)]
     struct AlwaysFailingConnector;
@@ -497,12 +615,42 @@ mod test {
         }
     }
 
+    /// Hands the server end of each connection to the test, along with the
+    /// parameters the client used.
+    struct ChannelConnector(mpsc::UnboundedSender<(TransportConnectionParams, DuplexStream)>);
+
+    #[async_trait]
+    impl TransportConnector for ChannelConnector {
+        type Stream = DuplexStream;
+
+        async fn connect(
+            &self,
+            connection_params: &TransportConnectionParams,
+            _alpn: Alpn,
+        ) -> Result<StreamAndInfo<Self::Stream>, TransportConnectError> {
+            let (client, server) = tokio::io::duplex(1024 * 1024);
+            self.0
+                .send((connection_params.clone(), server))
+                .map_err(|_| TransportConnectError::TcpConnectionFailed)?;
+            Ok(StreamAndInfo(
+                client,
+                ConnectionInfo {
+                    route_type: RouteType::Test,
+                    dns_source: DnsSource::Test,
+                    address: connection_params.tcp_host.clone(),
+                    port: connection_params.port,
+                    websocket_sub_protocol: None,
+                },
+            ))
+        }
+    }
+
     const CONNECT_TIMEOUT: Duration = Duration::from_secs(10);
 
     async fn enclave_connect<C: ConnectionManager>(
         manager: C,
     ) -> Result<AttestedConnection<SslStream<TcpStream>>, Error> {
-        let mr_enclave = MrEnclave::new(b"abcdef".as_slice());
+        let mr_enclave = MrEnclave::new(Cow::Borrowed(b"abcdef".as_slice()));
         let connection = EnclaveEndpointConnection {
             endpoint_connection: EndpointConnection {
                 manager,
@@ -605,4 +753,88 @@ mod test {
         let result = enclave_connect(connection_manager).await;
         assert_matches!(result, Err(Error::ConnectionTimedOut));
     }
+
+    #[tokio::test]
+    async fn custom_env_enclave_completes_handshake() {
+        const HOST: &str = "cdsi.custom.example";
+        const MESSAGE: &[u8] = b"over the attested session";
+
+        let env = EnvBuilder::with_base(STAGING)
+            .cdsi(
+                HOST,
+                nonzero!(9443u16),
+                attest::sgx_session::testutil::mrenclave_bytes(),
+            )
+            .build()
+            .expect("valid env");
+        let connection =
+            EnclaveEndpointConnection::new(&env.cdsi, CONNECT_TIMEOUT, &ObservableEvent::default());
+
+        let (connections_tx, mut connections_rx) = mpsc::unbounded_channel();
+        let (request_tx, request_rx) = oneshot::channel();
+        tokio::spawn(async move {
+            let (params, stream) = connections_rx.recv().await.expect("client connected");
+            let websocket = tokio_tungstenite::accept_hdr_async(
+                stream,
+                |request: &Request, response: Response| -> Result<Response, ErrorResponse> {
+                    let _ = request_tx.send((
+                        params,
+                        request.uri().path().to_owned(),
+                        request.headers()[http::header::HOST].clone(),
+                    ));
+                    Ok(response)
+                },
+            )
+            .await
+            .expect("websocket handshake");
+            run_attested_server(
+                websocket,
+                attest::sgx_session::testutil::private_key(),
+                |message| match message {
+                    NextOrClose::Next(message) => AttestedServerOutput::message(message),
+                    NextOrClose::Close(close) => AttestedServerOutput::close(close),
+                },
+            )
+            .await;
+        });
+
+        // The test evidence has long since expired, so check it at a time it
+        // was valid, but against the MRENCLAVE from the custom environment.
+        let mut attested = connect_attested(
+            &connection.endpoint_connection,
+            Cdsi::CONNECTION_KIND,
+            Auth {
+                username: "user".to_string(),
+                password: "password".to_string(),
+            },
+            ChannelConnector(connections_tx),
+            None,
+            false,
+            &|_attestation_message| {
+                attest::sgx_session::testutil::handshake_from_tests_data_for_mrenclave(
+                    connection.params.mr_enclave.as_ref(),
+                )
+            },
+        )
+        .await
+        .expect("handshake completes");
+
+        attested.send_bytes(MESSAGE).await.expect("can send");
+        assert_matches!(
+            attested.receive_bytes().await,
+            Ok(NextOrClose::Next(echoed)) if echoed == MESSAGE
+        );
+
+        let (params, path, host) = request_rx.await.expect("request was seen");
+        assert_eq!(params.tcp_host, Host::Domain(HOST.into()));
+        assert_eq!(params.port, nonzero!(9443u16));
+        assert_eq!(
+            path,
+            format!(
+                "/v1/{}/discovery",
+                hex::encode(attest::sgx_session::testutil::mrenclave_bytes())
+            )
+        );
+        assert_eq!(host, HOST);
+    }
 }
@@ -3,6 +3,7 @@
 // SPDX-License-Identifier: AGPL-3.0-only
 //
 
+use std::borrow::Cow;
 use std::collections::HashMap;
 use std::iter;
 use std::net::{Ipv4Addr, Ipv6Addr};
@@ -13,6 +14,7 @@ use const_str::ip_addr;
 use libsignal_net_infra::certs::RootCertificates;
 use libsignal_net_infra::dns::lookup_result::LookupResult;
 use libsignal_net_infra::host::Host;
+use libsignal_net_infra::ws::WebSocketMessageLimits;
 use libsignal_net_infra::{
     ConnectionParams, DnsSource, HttpRequestDecorator, HttpRequestDecoratorSeq, RouteType,
     TransportConnectionParams,
@@ -26,8 +28,12 @@ use crate::enclave::{
     Cdsi, EnclaveEndpoint, EndpointParams, MrEnclave, Nitro, Sgx, SgxPreQuantum, Tpm2Snp,
 };
 
+mod builder;
+pub use builder::{EnvBuilder, EnvBuilderError};
+
 const DEFAULT_HTTPS_PORT: NonZeroU16 = nonzero!(443_u16);
 pub const TIMESTAMP_HEADER_NAME: &str = "x-signal-timestamp";
+pub const TRACE_ID_HEADER_NAME: &str = "x-signal-trace-id";
 pub const RECEIVE_STORIES_HEADER_NAME: &str = "x-signal-receive-stories";
 
 const DOMAIN_CONFIG_CHAT: DomainConfig = DomainConfig {
@@ -40,14 +46,15 @@ const DOMAIN_CONFIG_CHAT: DomainConfig = DomainConfig {
         ip_addr!(v6, "2600:9000:a61f:527c:d5eb:a431:5239:3232"),
     ],
     connect: ConnectionConfig {
-        hostname: "chat.signal.org",
+        hostname: Cow::Borrowed("chat.signal.org"),
         port: DEFAULT_HTTPS_PORT,
         cert: SIGNAL_ROOT_CERTIFICATES,
         confirmation_header_name: Some(TIMESTAMP_HEADER_NAME),
-        proxy: ConnectionProxyConfig {
+        websocket_limits: WebSocketMessageLimits::DEFAULT,
+        proxy: Some(ConnectionProxyConfig {
             path_prefix: "/service",
             configs: [PROXY_CONFIG_F_PROD, PROXY_CONFIG_G],
-        },
+        }),
     },
 };
 
@@ -61,27 +68,29 @@ const DOMAIN_CONFIG_CHAT_STAGING: DomainConfig = DomainConfig {
         ip_addr!(v6, "2600:9000:a61f:527c:2215:cd9:bac6:a2f8"),
     ],
     connect: ConnectionConfig {
-        hostname: "chat.staging.signal.org",
+        hostname: Cow::Borrowed("chat.staging.signal.org"),
         port: DEFAULT_HTTPS_PORT,
         cert: SIGNAL_ROOT_CERTIFICATES,
         confirmation_header_name: Some(TIMESTAMP_HEADER_NAME),
-        proxy: ConnectionProxyConfig {
+        websocket_limits: WebSocketMessageLimits::DEFAULT,
+        proxy: Some(ConnectionProxyConfig {
             path_prefix: "/service-staging",
             configs: [PROXY_CONFIG_F_STAGING, PROXY_CONFIG_G],
-        },
+        }),
     },
 };
 
 const DOMAIN_CONFIG_CDSI: DomainConfig = DomainConfig {
     connect: ConnectionConfig {
-        hostname: "cdsi.signal.org",
+        hostname: Cow::Borrowed("cdsi.signal.org"),
         port: DEFAULT_HTTPS_PORT,
         cert: SIGNAL_ROOT_CERTIFICATES,
         confirmation_header_name: None,
-        proxy: ConnectionProxyConfig {
+        websocket_limits: WebSocketMessageLimits::DEFAULT,
+        proxy: Some(ConnectionProxyConfig {
             path_prefix: "/cdsi",
             configs: [PROXY_CONFIG_F_PROD, PROXY_CONFIG_G],
-        },
+        }),
     },
     ip_v4: &[ip_addr!(v4, "40.122.45.194")],
     ip_v6: &[ip_addr!(v6, "2603:1030:7::1")],
@@ -89,14 +98,15 @@ const DOMAIN_CONFIG_CDSI: DomainConfig = DomainConfig {
 
 const DOMAIN_CONFIG_CDSI_STAGING: DomainConfig = DomainConfig {
     connect: ConnectionConfig {
-        hostname: "cdsi.staging.signal.org",
+        hostname: Cow::Borrowed("cdsi.staging.signal.org"),
         port: DEFAULT_HTTPS_PORT,
         cert: SIGNAL_ROOT_CERTIFICATES,
         confirmation_header_name: None,
-        proxy: ConnectionProxyConfig {
+        websocket_limits: WebSocketMessageLimits::DEFAULT,
+        proxy: Some(ConnectionProxyConfig {
             path_prefix: "/cdsi-staging",
             configs: [PROXY_CONFIG_F_STAGING, PROXY_CONFIG_G],
-        },
+        }),
     },
     ip_v4: &[ip_addr!(v4, "104.43.162.137")],
     ip_v6: &[ip_addr!(v6, "2603:1030:7::732")],
@@ -104,14 +114,15 @@ const DOMAIN_CONFIG_CDSI_STAGING: DomainConfig = DomainConfig {
 
 const DOMAIN_CONFIG_SVR2: DomainConfig = DomainConfig {
     connect: ConnectionConfig {
-        hostname: "svr2.signal.org",
+        hostname: Cow::Borrowed("svr2.signal.org"),
         port: DEFAULT_HTTPS_PORT,
         cert: SIGNAL_ROOT_CERTIFICATES,
         confirmation_header_name: None,
-        proxy: ConnectionProxyConfig {
+        websocket_limits: WebSocketMessageLimits::DEFAULT,
+        proxy: Some(ConnectionProxyConfig {
             path_prefix: "/svr2",
             configs: [PROXY_CONFIG_F_PROD, PROXY_CONFIG_G],
-        },
+        }),
     },
     ip_v4: &[ip_addr!(v4, "20.66.40.69")],
     ip_v6: &[],
@@ -119,14 +130,15 @@ const DOMAIN_CONFIG_SVR2: DomainConfig = DomainConfig {
 
 const DOMAIN_CONFIG_SVR2_STAGING: DomainConfig = DomainConfig {
     connect: ConnectionConfig {
-        hostname: "svr2.staging.signal.org",
+        hostname: Cow::Borrowed("svr2.staging.signal.org"),
         port: DEFAULT_HTTPS_PORT,
         cert: SIGNAL_ROOT_CERTIFICATES,
         confirmation_header_name: None,
-        proxy: ConnectionProxyConfig {
+        websocket_limits: WebSocketMessageLimits::DEFAULT,
+        proxy: Some(ConnectionProxyConfig {
             path_prefix: "/svr2-staging",
             configs: [PROXY_CONFIG_F_STAGING, PROXY_CONFIG_G],
-        },
+        }),
     },
     ip_v4: &[ip_addr!(v4, "20.253.229.239")],
     ip_v6: &[],
@@ -134,14 +146,15 @@ const DOMAIN_CONFIG_SVR2_STAGING: DomainConfig = DomainConfig {
 
 const DOMAIN_CONFIG_SVR3_SGX: DomainConfig = DomainConfig {
     connect: ConnectionConfig {
-        hostname: "backend1.svr3.signal.org",
+        hostname: Cow::Borrowed("backend1.svr3.signal.org"),
         port: DEFAULT_HTTPS_PORT,
         cert: SIGNAL_ROOT_CERTIFICATES,
         confirmation_header_name: None,
-        proxy: ConnectionProxyConfig {
+        websocket_limits: WebSocketMessageLimits::DEFAULT,
+        proxy: Some(ConnectionProxyConfig {
             path_prefix: "/svr3-sgx",
             configs: [PROXY_CONFIG_F_PROD, PROXY_CONFIG_G],
-        },
+        }),
     },
     ip_v4: &[ip_addr!(v4, "40.112.138.96")],
     ip_v6: &[],
@@ -149,14 +162,15 @@ const DOMAIN_CONFIG_SVR3_SGX: DomainConfig = DomainConfig {
 
 const DOMAIN_CONFIG_SVR3_SGX_STAGING: DomainConfig = DomainConfig {
     connect: ConnectionConfig {
-        hostname: "backend1.svr3.staging.signal.org",
+        hostname: Cow::Borrowed("backend1.svr3.staging.signal.org"),
         port: DEFAULT_HTTPS_PORT,
         cert: SIGNAL_ROOT_CERTIFICATES,
         confirmation_header_name: None,
-        proxy: ConnectionProxyConfig {
+        websocket_limits: WebSocketMessageLimits::DEFAULT,
+        proxy: Some(ConnectionProxyConfig {
             path_prefix: "/svr3-sgx-staging",
             configs: [PROXY_CONFIG_F_STAGING, PROXY_CONFIG_G],
-        },
+        }),
     },
     ip_v4: &[ip_addr!(v4, "13.88.63.29")],
     ip_v6: &[],
@@ -164,14 +178,15 @@ const DOMAIN_CONFIG_SVR3_SGX_STAGING: DomainConfig = DomainConfig {
 
 const DOMAIN_CONFIG_SVR3_NITRO: DomainConfig = DomainConfig {
     connect: ConnectionConfig {
-        hostname: "backend2.svr3.signal.org",
+        hostname: Cow::Borrowed("backend2.svr3.signal.org"),
         port: DEFAULT_HTTPS_PORT,
         cert: SIGNAL_ROOT_CERTIFICATES,
         confirmation_header_name: None,
-        proxy: ConnectionProxyConfig {
+        websocket_limits: WebSocketMessageLimits::DEFAULT,
+        proxy: Some(ConnectionProxyConfig {
             path_prefix: "/svr3-nitro",
             configs: [PROXY_CONFIG_F_PROD, PROXY_CONFIG_G],
-        },
+        }),
     },
     ip_v4: &[ip_addr!(v4, "75.2.91.98")],
     ip_v6: &[],
@@ -179,14 +194,15 @@ const DOMAIN_CONFIG_SVR3_NITRO: DomainConfig = DomainConfig {
 
 const DOMAIN_CONFIG_SVR3_NITRO_STAGING: DomainConfig = DomainConfig {
     connect: ConnectionConfig {
-        hostname: "backend2.svr3.staging.signal.org",
+        hostname: Cow::Borrowed("backend2.svr3.staging.signal.org"),
         port: DEFAULT_HTTPS_PORT,
         cert: SIGNAL_ROOT_CERTIFICATES,
         confirmation_header_name: None,
-        proxy: ConnectionProxyConfig {
+        websocket_limits: WebSocketMessageLimits::DEFAULT,
+        proxy: Some(ConnectionProxyConfig {
             path_prefix: "/svr3-nitro-staging",
             configs: [PROXY_CONFIG_F_STAGING, PROXY_CONFIG_G],
-        },
+        }),
     },
     ip_v4: &[ip_addr!(v4, "75.2.86.85"), ip_addr!(v4, "99.83.239.137")],
     ip_v6: &[],
@@ -194,14 +210,15 @@ const DOMAIN_CONFIG_SVR3_NITRO_STAGING: DomainConfig = DomainConfig {
 
 pub const DOMAIN_CONFIG_SVR3_TPM2SNP: DomainConfig = DomainConfig {
     connect: ConnectionConfig {
-        hostname: "backend3.svr3.signal.org",
+        hostname: Cow::Borrowed("backend3.svr3.signal.org"),
         port: DEFAULT_HTTPS_PORT,
         cert: SIGNAL_ROOT_CERTIFICATES,
         confirmation_header_name: None,
-        proxy: ConnectionProxyConfig {
+        websocket_limits: WebSocketMessageLimits::DEFAULT,
+        proxy: Some(ConnectionProxyConfig {
             path_prefix: "/svr3-tpm2snp",
             configs: [PROXY_CONFIG_F_PROD, PROXY_CONFIG_G],
-        },
+        }),
     },
     ip_v4: &[ip_addr!(v4, "34.144.241.251")],
     ip_v6: &[],
@@ -209,91 +226,135 @@ pub const DOMAIN_CONFIG_SVR3_TPM2SNP: DomainConfig = DomainConfig {
 
 pub const DOMAIN_CONFIG_SVR3_TPM2SNP_STAGING: DomainConfig = DomainConfig {
     connect: ConnectionConfig {
-        hostname: "backend3.svr3.staging.signal.org",
+        hostname: Cow::Borrowed("backend3.svr3.staging.signal.org"),
         port: DEFAULT_HTTPS_PORT,
         cert: SIGNAL_ROOT_CERTIFICATES,
         confirmation_header_name: None,
-        proxy: ConnectionProxyConfig {
+        websocket_limits: WebSocketMessageLimits::DEFAULT,
+        proxy: Some(ConnectionProxyConfig {
             path_prefix: "/svr3-tpm2snp-staging",
             configs: [PROXY_CONFIG_F_STAGING, PROXY_CONFIG_G],
-        },
+        }),
     },
     ip_v4: &[ip_addr!(v4, "13.88.30.76")],
     ip_v6: &[],
 };
 
+const fn cdn_connection_config(hostname: &'static str) -> ConnectionConfig {
+    ConnectionConfig {
+        hostname: Cow::Borrowed(hostname),
+        port: DEFAULT_HTTPS_PORT,
+        cert: RootCertificates::Native,
+        confirmation_header_name: None,
+        websocket_limits: WebSocketMessageLimits::DEFAULT,
+        proxy: None,
+    }
+}
+
+const CONNECTION_CONFIGS_CDN: &[ConnectionConfig] = &[
+    cdn_connection_config("cdn.signal.org"),
+    cdn_connection_config("cdn2.signal.org"),
+    cdn_connection_config("cdn3.signal.org"),
+];
+
+const CONNECTION_CONFIGS_CDN_STAGING: &[ConnectionConfig] = &[
+    cdn_connection_config("cdn-staging.signal.org"),
+    cdn_connection_config("cdn2-staging.signal.org"),
+    cdn_connection_config("cdn3-staging.signal.org"),
+];
+
 pub const PROXY_CONFIG_F_PROD: ProxyConfig = ProxyConfig {
     route_type: RouteType::ProxyF,
-    http_host: "reflector-signal.global.ssl.fastly.net",
-    sni_list: &["splashthat.com", "slate.com", "www.redditstatic.com"],
+    http_host: Cow::Borrowed("reflector-signal.global.ssl.fastly.net"),
+    sni_list: Cow::Borrowed(&[
+        Cow::Borrowed("splashthat.com"),
+        Cow::Borrowed("slate.com"),
+        Cow::Borrowed("www.redditstatic.com"),
+    ]),
     certs: RootCertificates::Native,
 };
 
 pub const PROXY_CONFIG_F_STAGING: ProxyConfig = ProxyConfig {
     route_type: RouteType::ProxyF,
-    http_host: "reflector-staging-signal.global.ssl.fastly.net",
-    sni_list: &["splashthat.com", "slate.com", "www.redditstatic.com"],
+    http_host: Cow::Borrowed("reflector-staging-signal.global.ssl.fastly.net"),
+    sni_list: Cow::Borrowed(&[
+        Cow::Borrowed("splashthat.com"),
+        Cow::Borrowed("slate.com"),
+        Cow::Borrowed("www.redditstatic.com"),
+    ]),
     certs: RootCertificates::Native,
 };
 
 pub const PROXY_CONFIG_G: ProxyConfig = ProxyConfig {
     route_type: RouteType::ProxyG,
-    http_host: "reflector-nrgwuv7kwq-uc.a.run.app",
-    sni_list: &[
-        "www.google.com",
-        "android.clients.google.com",
-        "clients3.google.com",
-        "clients4.google.com",
-        "inbox.google.com",
-    ],
+    http_host: Cow::Borrowed("reflector-nrgwuv7kwq-uc.a.run.app"),
+    sni_list: Cow::Borrowed(&[
+        Cow::Borrowed("www.google.com"),
+        Cow::Borrowed("android.clients.google.com"),
+        Cow::Borrowed("clients3.google.com"),
+        Cow::Borrowed("clients4.google.com"),
+        Cow::Borrowed("inbox.google.com"),
+    ]),
     certs: PROXY_G_ROOT_CERTIFICATES,
 };
 
 pub(crate) const ENDPOINT_PARAMS_CDSI_STAGING: EndpointParams<'static, Cdsi> = EndpointParams {
-    mr_enclave: MrEnclave::new(attest::constants::ENCLAVE_ID_CDSI_STAGING_AND_PROD),
+    mr_enclave: MrEnclave::new(Cow::Borrowed(
+        attest::constants::ENCLAVE_ID_CDSI_STAGING_AND_PROD,
+    )),
     raft_config: (),
 };
 
 pub(crate) const ENDPOINT_PARAMS_SVR2_STAGING: EndpointParams<'static, SgxPreQuantum> =
     EndpointParams {
-        mr_enclave: MrEnclave::new(attest::constants::ENCLAVE_ID_SVR2_STAGING),
+        mr_enclave: MrEnclave::new(Cow::Borrowed(attest::constants::ENCLAVE_ID_SVR2_STAGING)),
         raft_config: attest::constants::RAFT_CONFIG_SVR2_STAGING,
     };
 pub(crate) const ENDPOINT_PARAMS_SVR3_SGX_STAGING: EndpointParams<'static, Sgx> = EndpointParams {
-    mr_enclave: MrEnclave::new(attest::constants::ENCLAVE_ID_SVR3_SGX_STAGING),
+    mr_enclave: MrEnclave::new(Cow::Borrowed(
+        attest::constants::ENCLAVE_ID_SVR3_SGX_STAGING,
+    )),
     raft_config: attest::constants::RAFT_CONFIG_SVR3_SGX_STAGING,
 };
 pub(crate) const ENDPOINT_PARAMS_SVR3_NITRO_STAGING: EndpointParams<'static, Nitro> =
     EndpointParams {
-        mr_enclave: MrEnclave::new(attest::constants::ENCLAVE_ID_SVR3_NITRO_STAGING),
+        mr_enclave: MrEnclave::new(Cow::Borrowed(
+            attest::constants::ENCLAVE_ID_SVR3_NITRO_STAGING,
+        )),
         raft_config: attest::constants::RAFT_CONFIG_SVR3_NITRO_STAGING,
     };
 pub(crate) const ENDPOINT_PARAMS_SVR3_TPM2SNP_STAGING: EndpointParams<'static, Tpm2Snp> =
     EndpointParams {
-        mr_enclave: MrEnclave::new(attest::constants::ENCLAVE_ID_SVR3_TPM2SNP_STAGING),
+        mr_enclave: MrEnclave::new(Cow::Borrowed(
+            attest::constants::ENCLAVE_ID_SVR3_TPM2SNP_STAGING,
+        )),
         raft_config: attest::constants::RAFT_CONFIG_SVR3_TPM2SNP_STAGING,
     };
 
 pub(crate) const ENDPOINT_PARAMS_CDSI_PROD: EndpointParams<'static, Cdsi> = EndpointParams {
-    mr_enclave: MrEnclave::new(attest::constants::ENCLAVE_ID_CDSI_STAGING_AND_PROD),
+    mr_enclave: MrEnclave::new(Cow::Borrowed(
+        attest::constants::ENCLAVE_ID_CDSI_STAGING_AND_PROD,
+    )),
     raft_config: (),
 };
 pub(crate) const ENDPOINT_PARAMS_SVR2_PROD: EndpointParams<'static, SgxPreQuantum> =
     EndpointParams {
-        mr_enclave: MrEnclave::new(attest::constants::ENCLAVE_ID_SVR2_PROD),
+        mr_enclave: MrEnclave::new(Cow::Borrowed(attest::constants::ENCLAVE_ID_SVR2_PROD)),
         raft_config: attest::constants::RAFT_CONFIG_SVR2_PROD,
     };
 pub(crate) const ENDPOINT_PARAMS_SVR3_SGX_PROD: EndpointParams<'static, Sgx> = EndpointParams {
-    mr_enclave: MrEnclave::new(attest::constants::ENCLAVE_ID_SVR3_SGX_PROD),
+    mr_enclave: MrEnclave::new(Cow::Borrowed(attest::constants::ENCLAVE_ID_SVR3_SGX_PROD)),
     raft_config: attest::constants::RAFT_CONFIG_SVR3_SGX_PROD,
 };
 pub(crate) const ENDPOINT_PARAMS_SVR3_NITRO_PROD: EndpointParams<'static, Nitro> = EndpointParams {
-    mr_enclave: MrEnclave::new(attest::constants::ENCLAVE_ID_SVR3_NITRO_PROD),
+    mr_enclave: MrEnclave::new(Cow::Borrowed(attest::constants::ENCLAVE_ID_SVR3_NITRO_PROD)),
     raft_config: attest::constants::RAFT_CONFIG_SVR3_NITRO_PROD,
 };
 pub(crate) const ENDPOINT_PARAMS_SVR3_TPM2SNP_PROD: EndpointParams<'static, Tpm2Snp> =
     EndpointParams {
-        mr_enclave: MrEnclave::new(attest::constants::ENCLAVE_ID_SVR3_TPM2SNP_PROD),
+        mr_enclave: MrEnclave::new(Cow::Borrowed(
+            attest::constants::ENCLAVE_ID_SVR3_TPM2SNP_PROD,
+        )),
         raft_config: attest::constants::RAFT_CONFIG_SVR3_TPM2SNP_PROD,
     };
 
@@ -311,7 +372,7 @@ pub struct DomainConfig {
 #[derive(Clone)]
 pub struct ConnectionConfig {
     /// The domain name of the resource.
-    pub hostname: &'static str,
+    pub hostname: Cow<'static, str>,
     /// The port for the resource.
     pub port: NonZeroU16,
     /// Which certificates to use when connecting to the resource.
@@ -322,10 +383,14 @@ pub struct ConnectionConfig {
     /// indicates that the response came from the resource, not from a proxy or
     /// load balancer.
     pub confirmation_header_name: Option<&'static str>,
+    /// Limits on the size of websocket messages received from the resource.
+    pub websocket_limits: WebSocketMessageLimits,
 
     /// Additional configuration for connecting to the resource through a proxy
     /// if a direct connection fails.
-    pub proxy: ConnectionProxyConfig,
+    ///
+    /// If this is `None`, only direct connections will be attempted.
+    pub proxy: Option<ConnectionProxyConfig>,
 }
 
 #[derive(Clone)]
@@ -337,9 +402,9 @@ pub struct ConnectionProxyConfig {
 }
 
 impl DomainConfig {
-    pub fn static_fallback(&self) -> (&'static str, LookupResult) {
+    pub fn static_fallback(&self) -> (Cow<'static, str>, LookupResult) {
         (
-            self.connect.hostname,
+            self.connect.hostname.clone(),
             LookupResult::new(DnsSource::Static, self.ip_v4.into(), self.ip_v6.into()),
         )
     }
@@ -348,7 +413,7 @@ impl DomainConfig {
 impl ConnectionConfig {
     pub fn direct_connection_params(&self) -> ConnectionParams {
         let result = {
-            let hostname = self.hostname.into();
+            let hostname = Arc::<str>::from(self.hostname.as_ref());
             ConnectionParams {
                 route_type: RouteType::Direct,
                 transport: TransportConnectionParams {
@@ -370,12 +435,15 @@ impl ConnectionConfig {
 
     pub fn connection_params_with_fallback(&self) -> Vec<ConnectionParams> {
         let direct = self.direct_connection_params();
+        let Some(proxy) = &self.proxy else {
+            return vec![direct];
+        };
         let mut rng = thread_rng();
         // TODO use array::each_ref() once MSRV >= 1.77
-        let [params_a, params_b] = &self.proxy.configs;
+        let [params_a, params_b] = &proxy.configs;
         let [params_a, params_b] = [params_a, params_b].map(|config| {
             config.shuffled_connection_params(
-                self.proxy.path_prefix,
+                proxy.path_prefix,
                 self.confirmation_header_name,
                 &mut rng,
             )
@@ -404,14 +472,30 @@ pub fn add_user_agent_header(
 pub struct ProxyConfig {
     route_type: RouteType,
     /// The value of the HTTP Host header
-    http_host: &'static str,
+    http_host: Cow<'static, str>,
     /// Domain names to use for DNS resolution and TLS SNI.
-    sni_list: &'static [&'static str],
+    sni_list: Cow<'static, [Cow<'static, str>]>,
     /// TLS root certificates to use.
     certs: RootCertificates,
 }
 
 impl ProxyConfig {
+    /// Describes a domain-fronting proxy reachable through any of the names in
+    /// `sni_list`, which forwards requests to `http_host`.
+    pub fn new(
+        route_type: RouteType,
+        http_host: impl Into<Cow<'static, str>>,
+        sni_list: impl IntoIterator<Item = impl Into<Cow<'static, str>>>,
+        certs: RootCertificates,
+    ) -> Self {
+        Self {
+            route_type,
+            http_host: http_host.into(),
+            sni_list: sni_list.into_iter().map(Into::into).collect(),
+            certs,
+        }
+    }
+
     pub fn shuffled_connection_params(
         &self,
         proxy_path: &'static str,
@@ -419,7 +503,7 @@ impl ProxyConfig {
         rng: &mut impl Rng,
     ) -> impl Iterator<Item = ConnectionParams> {
         let route_type = self.route_type;
-        let http_host = Arc::from(self.http_host);
+        let http_host = Arc::<str>::from(self.http_host.as_ref());
         let certs = self.certs.clone();
 
         let mut sni_list = self.sni_list.to_vec();
@@ -430,7 +514,7 @@ impl ProxyConfig {
             // for the TLS connection. Then, once an encrypted connection is
             // established, the actual hostname should be used for the HTTP
             // header.
-            let sni_and_dns_host = (*sni).into();
+            let sni_and_dns_host = Arc::<str>::from(sni.as_ref());
             ConnectionParams {
                 route_type,
                 transport: TransportConnectionParams {
@@ -453,16 +537,32 @@ pub struct Env<'a, Svr3> {
     pub svr2: EnclaveEndpoint<'a, SgxPreQuantum>,
     pub svr3: Svr3,
     pub chat_domain_config: DomainConfig,
+    /// The CDNs attachments are stored on, in the order of [`CDN_NUMBERS`].
+    pub cdn: &'a [ConnectionConfig],
+}
+
+/// The numbers clients use to refer to each of the entries in [`Env::cdn`].
+pub const CDN_NUMBERS: [u32; 3] = [0, 2, 3];
+
+impl<'a, Svr3> Env<'a, Svr3> {
+    /// Returns the connection parameters for each CDN, along with its number.
+    pub fn cdn_connection_params(&self) -> impl Iterator<Item = (u32, ConnectionParams)> + 'a {
+        CDN_NUMBERS
+            .into_iter()
+            .zip(self.cdn)
+            .map(|(number, config)| (number, config.direct_connection_params()))
+    }
 }
 
 impl<'a> Env<'a, Svr3Env<'a>> {
     /// Returns a static mapping from hostnames to [`LookupResult`]s.
-    pub fn static_fallback(&self) -> HashMap<&'a str, LookupResult> {
+    pub fn static_fallback(&self) -> HashMap<Cow<'static, str>, LookupResult> {
         let Self {
             cdsi,
             svr2,
             svr3,
             chat_domain_config,
+            cdn: _,
         } = self;
         HashMap::from([
             cdsi.domain_config.static_fallback(),
@@ -500,6 +600,7 @@ impl<'a> Svr3Env<'a> {
 
 pub const STAGING: Env<'static, Svr3Env> = Env {
     chat_domain_config: DOMAIN_CONFIG_CHAT_STAGING,
+    cdn: CONNECTION_CONFIGS_CDN_STAGING,
     cdsi: EnclaveEndpoint {
         domain_config: DOMAIN_CONFIG_CDSI_STAGING,
         params: ENDPOINT_PARAMS_CDSI_STAGING,
@@ -526,6 +627,7 @@ pub const STAGING: Env<'static, Svr3Env> = Env {
 
 pub const PROD: Env<'static, Svr3Env> = Env {
     chat_domain_config: DOMAIN_CONFIG_CHAT,
+    cdn: CONNECTION_CONFIGS_CDN,
     cdsi: EnclaveEndpoint {
         domain_config: DOMAIN_CONFIG_CDSI,
         params: ENDPOINT_PARAMS_CDSI_PROD,
@@ -552,6 +654,16 @@ pub const PROD: Env<'static, Svr3Env> = Env {
 
 pub mod constants {
     pub const WEB_SOCKET_PATH: &str = "/v1/websocket/";
+    /// The websocket sub-protocols the chat server is asked to speak, most preferred first.
+    pub const CHAT_WEB_SOCKET_SUB_PROTOCOLS: &[&str] = &["signal-chat.v1"];
+    /// The websocket close code the chat server sends when it is shutting down gracefully.
+    ///
+    /// This is in the range reserved for private use by [RFC 6455][], so only the chat server
+    /// gives it this meaning. A connection closed with this code was healthy, and the client
+    /// should reconnect immediately (to another server instance) rather than backing off.
+    ///
+    /// [RFC 6455]: https://www.rfc-editor.org/rfc/rfc6455#section-7.4.2
+    pub const CHAT_SERVER_DRAINING_CLOSE_CODE: u16 = 4409;
 }
 
 #[cfg(test)]
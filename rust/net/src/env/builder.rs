@@ -0,0 +1,510 @@
+//
+// Copyright 2024 Signal Messenger, LLC.
+// SPDX-License-Identifier: AGPL-3.0-only
+//
+
+use std::borrow::Cow;
+use std::num::NonZeroU16;
+
+use attest::svr2::RaftConfig;
+use libsignal_net_infra::certs::RootCertificates;
+use libsignal_net_infra::ws::WebSocketMessageLimits;
+
+use super::{
+    ConnectionConfig, ConnectionProxyConfig, DomainConfig, Env, ProxyConfig, Svr3Env,
+    TIMESTAMP_HEADER_NAME,
+};
+use crate::certs::SIGNAL_ROOT_CERTIFICATES;
+use crate::enclave::{
+    Cdsi, EnclaveEndpoint, EnclaveKind, EndpointParams, MrEnclave, Nitro, Sgx, SgxPreQuantum,
+    Tpm2Snp,
+};
+
+/// Length of an SGX MRENCLAVE value.
+const SGX_MR_ENCLAVE_LEN: usize = 32;
+
+#[derive(Debug, PartialEq, Eq, thiserror::Error, displaydoc::Display)]
+pub enum EnvBuilderError {
+    /// no {0} endpoint was configured
+    MissingEndpoint(&'static str),
+    /// {0} hostname must not be empty
+    EmptyHostname(&'static str),
+    /// {0} MRENCLAVE must be 32 bytes, but was {1}
+    InvalidMrEnclaveLength(&'static str, usize),
+    /// {0} enclave ID must be a non-empty UTF-8 string
+    InvalidEnclaveId(&'static str),
+}
+
+/// Assembles an [`Env`] from values only known at runtime.
+///
+/// Every endpoint must be provided unless the builder was created with
+/// [`EnvBuilder::with_base`], in which case endpoints that aren't overridden
+/// are taken from the base environment as-is. The CDNs can't be customized;
+/// they're always taken from the base environment, if there is one.
+#[derive(Default)]
+pub struct EnvBuilder {
+    base: Option<Env<'static, Svr3Env<'static>>>,
+    root_certificate: Option<RootCertificates>,
+    proxy_configs: Option<[ProxyConfig; 2]>,
+    chat: Option<CustomEndpoint>,
+    cdsi: Option<CustomEnclave<Cdsi>>,
+    svr2: Option<CustomEnclave<SgxPreQuantum>>,
+    svr3_sgx: Option<CustomEnclave<Sgx>>,
+    svr3_nitro: Option<CustomEnclave<Nitro>>,
+    svr3_tpm2snp: Option<CustomEnclave<Tpm2Snp>>,
+}
+
+struct CustomEndpoint {
+    hostname: String,
+    port: NonZeroU16,
+}
+
+struct CustomEnclave<E: EnclaveKind> {
+    endpoint: CustomEndpoint,
+    mr_enclave: Vec<u8>,
+    raft_config: E::RaftConfigType,
+}
+
+/// How an enclave kind identifies the code it expects to be running.
+#[derive(Clone, Copy)]
+enum Measurement {
+    /// A 32-byte SGX MRENCLAVE.
+    SgxMrEnclave,
+    /// A version string, as used by Nitro and TPM2-SNP enclaves.
+    VersionString,
+}
+
+impl EnvBuilder {
+    /// Creates a builder for which every endpoint must be provided.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Creates a builder that starts from `base`, like [`super::STAGING`].
+    pub fn with_base(base: Env<'static, Svr3Env<'static>>) -> Self {
+        Self {
+            base: Some(base),
+            ..Self::default()
+        }
+    }
+
+    /// Root certificate to trust for direct connections to the endpoints set
+    /// on this builder.
+    ///
+    /// Defaults to Signal's root certificates. Endpoints taken from the base
+    /// environment are not affected.
+    pub fn root_certificate_der(mut self, der: impl Into<Vec<u8>>) -> Self {
+        self.root_certificate = Some(RootCertificates::FromDer(Cow::Owned(der.into())));
+        self
+    }
+
+    /// Domain-fronting proxies to fall back to for the endpoints set on this
+    /// builder.
+    ///
+    /// If not set, those endpoints will only be reached directly. Endpoints
+    /// taken from the base environment are not affected.
+    pub fn proxy_configs(mut self, configs: [ProxyConfig; 2]) -> Self {
+        self.proxy_configs = Some(configs);
+        self
+    }
+
+    pub fn chat(mut self, hostname: impl Into<String>, port: NonZeroU16) -> Self {
+        self.chat = Some(CustomEndpoint::new(hostname, port));
+        self
+    }
+
+    pub fn cdsi(
+        mut self,
+        hostname: impl Into<String>,
+        port: NonZeroU16,
+        mr_enclave: impl Into<Vec<u8>>,
+    ) -> Self {
+        self.cdsi = Some(CustomEnclave::new(hostname, port, mr_enclave, ()));
+        self
+    }
+
+    pub fn svr2(
+        mut self,
+        hostname: impl Into<String>,
+        port: NonZeroU16,
+        mr_enclave: impl Into<Vec<u8>>,
+        raft_config: &'static RaftConfig,
+    ) -> Self {
+        self.svr2 = Some(CustomEnclave::new(hostname, port, mr_enclave, raft_config));
+        self
+    }
+
+    pub fn svr3_sgx(
+        mut self,
+        hostname: impl Into<String>,
+        port: NonZeroU16,
+        mr_enclave: impl Into<Vec<u8>>,
+        raft_config: &'static RaftConfig,
+    ) -> Self {
+        self.svr3_sgx = Some(CustomEnclave::new(hostname, port, mr_enclave, raft_config));
+        self
+    }
+
+    pub fn svr3_nitro(
+        mut self,
+        hostname: impl Into<String>,
+        port: NonZeroU16,
+        enclave_id: impl Into<Vec<u8>>,
+        raft_config: &'static RaftConfig,
+    ) -> Self {
+        self.svr3_nitro = Some(CustomEnclave::new(hostname, port, enclave_id, raft_config));
+        self
+    }
+
+    pub fn svr3_tpm2snp(
+        mut self,
+        hostname: impl Into<String>,
+        port: NonZeroU16,
+        enclave_id: impl Into<Vec<u8>>,
+        raft_config: &'static RaftConfig,
+    ) -> Self {
+        self.svr3_tpm2snp = Some(CustomEnclave::new(hostname, port, enclave_id, raft_config));
+        self
+    }
+
+    /// Validates the configuration and produces an [`Env`].
+    pub fn build(self) -> Result<Env<'static, Svr3Env<'static>>, EnvBuilderError> {
+        let Self {
+            base,
+            root_certificate,
+            proxy_configs,
+            chat,
+            cdsi,
+            svr2,
+            svr3_sgx,
+            svr3_nitro,
+            svr3_tpm2snp,
+        } = self;
+
+        let (base_chat, base_cdsi, base_svr2, base_sgx, base_nitro, base_tpm2snp, base_cdn) =
+            match base {
+                Some(Env {
+                    chat_domain_config,
+                    cdsi,
+                    svr2,
+                    svr3: Svr3Env(sgx, nitro, tpm2snp),
+                    cdn,
+                }) => (
+                    Some(chat_domain_config),
+                    Some(cdsi),
+                    Some(svr2),
+                    Some(sgx),
+                    Some(nitro),
+                    Some(tpm2snp),
+                    cdn,
+                ),
+                None => Default::default(),
+            };
+
+        let chat = resolve("chat", chat, base_chat)?;
+        let cdsi = resolve("CDSI", cdsi, base_cdsi)?;
+        let svr2 = resolve("SVR2", svr2, base_svr2)?;
+        let svr3_sgx = resolve("SVR3 SGX", svr3_sgx, base_sgx)?;
+        let svr3_nitro = resolve("SVR3 Nitro", svr3_nitro, base_nitro)?;
+        let svr3_tpm2snp = resolve("SVR3 TPM2-SNP", svr3_tpm2snp, base_tpm2snp)?;
+
+        if let Resolved::Custom(chat) = &chat {
+            chat.validate("chat")?;
+        }
+        validate_enclave("CDSI", &cdsi, Measurement::SgxMrEnclave)?;
+        validate_enclave("SVR2", &svr2, Measurement::SgxMrEnclave)?;
+        validate_enclave("SVR3 SGX", &svr3_sgx, Measurement::SgxMrEnclave)?;
+        validate_enclave("SVR3 Nitro", &svr3_nitro, Measurement::VersionString)?;
+        validate_enclave("SVR3 TPM2-SNP", &svr3_tpm2snp, Measurement::VersionString)?;
+
+        let custom = CustomDomain {
+            cert: root_certificate.unwrap_or(SIGNAL_ROOT_CERTIFICATES),
+            proxy_configs,
+        };
+
+        Ok(Env {
+            chat_domain_config: match chat {
+                Resolved::Custom(chat) => {
+                    custom.domain_config(chat, "/service", Some(TIMESTAMP_HEADER_NAME))
+                }
+                Resolved::Base(base) => base,
+            },
+            cdsi: custom.enclave_endpoint(cdsi, "/cdsi"),
+            svr2: custom.enclave_endpoint(svr2, "/svr2"),
+            svr3: Svr3Env(
+                custom.enclave_endpoint(svr3_sgx, "/svr3-sgx"),
+                custom.enclave_endpoint(svr3_nitro, "/svr3-nitro"),
+                custom.enclave_endpoint(svr3_tpm2snp, "/svr3-tpm2snp"),
+            ),
+            cdn: base_cdn,
+        })
+    }
+}
+
+impl CustomEndpoint {
+    fn new(hostname: impl Into<String>, port: NonZeroU16) -> Self {
+        Self {
+            hostname: hostname.into(),
+            port,
+        }
+    }
+
+    fn validate(&self, name: &'static str) -> Result<(), EnvBuilderError> {
+        if self.hostname.is_empty() {
+            return Err(EnvBuilderError::EmptyHostname(name));
+        }
+        Ok(())
+    }
+}
+
+impl<E: EnclaveKind> CustomEnclave<E> {
+    fn new(
+        hostname: impl Into<String>,
+        port: NonZeroU16,
+        mr_enclave: impl Into<Vec<u8>>,
+        raft_config: E::RaftConfigType,
+    ) -> Self {
+        Self {
+            endpoint: CustomEndpoint::new(hostname, port),
+            mr_enclave: mr_enclave.into(),
+            raft_config,
+        }
+    }
+
+    fn validate(
+        &self,
+        name: &'static str,
+        measurement: Measurement,
+    ) -> Result<(), EnvBuilderError> {
+        self.endpoint.validate(name)?;
+        match measurement {
+            Measurement::SgxMrEnclave => {
+                if self.mr_enclave.len() != SGX_MR_ENCLAVE_LEN {
+                    return Err(EnvBuilderError::InvalidMrEnclaveLength(
+                        name,
+                        self.mr_enclave.len(),
+                    ));
+                }
+            }
+            Measurement::VersionString => {
+                if self.mr_enclave.is_empty() || std::str::from_utf8(&self.mr_enclave).is_err() {
+                    return Err(EnvBuilderError::InvalidEnclaveId(name));
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// An endpoint that was either set on the builder or inherited from its base.
+enum Resolved<C, B> {
+    Custom(C),
+    Base(B),
+}
+
+fn resolve<C, B>(
+    name: &'static str,
+    custom: Option<C>,
+    base: Option<B>,
+) -> Result<Resolved<C, B>, EnvBuilderError> {
+    match (custom, base) {
+        (Some(custom), _) => Ok(Resolved::Custom(custom)),
+        (None, Some(base)) => Ok(Resolved::Base(base)),
+        (None, None) => Err(EnvBuilderError::MissingEndpoint(name)),
+    }
+}
+
+fn validate_enclave<E: EnclaveKind, B>(
+    name: &'static str,
+    enclave: &Resolved<CustomEnclave<E>, B>,
+    measurement: Measurement,
+) -> Result<(), EnvBuilderError> {
+    match enclave {
+        Resolved::Custom(enclave) => enclave.validate(name, measurement),
+        Resolved::Base(_) => Ok(()),
+    }
+}
+
+/// Settings shared by every endpoint configured on the builder.
+struct CustomDomain {
+    cert: RootCertificates,
+    proxy_configs: Option<[ProxyConfig; 2]>,
+}
+
+impl CustomDomain {
+    fn domain_config(
+        &self,
+        endpoint: CustomEndpoint,
+        path_prefix: &'static str,
+        confirmation_header_name: Option<&'static str>,
+    ) -> DomainConfig {
+        let CustomEndpoint { hostname, port } = endpoint;
+        DomainConfig {
+            connect: ConnectionConfig {
+                hostname: hostname.into(),
+                port,
+                cert: self.cert.clone(),
+                confirmation_header_name,
+                websocket_limits: WebSocketMessageLimits::DEFAULT,
+                proxy: self
+                    .proxy_configs
+                    .clone()
+                    .map(|configs| ConnectionProxyConfig {
+                        path_prefix,
+                        configs,
+                    }),
+            },
+            ip_v4: &[],
+            ip_v6: &[],
+        }
+    }
+
+    fn enclave_endpoint<E: EnclaveKind>(
+        &self,
+        enclave: Resolved<CustomEnclave<E>, EnclaveEndpoint<'static, E>>,
+        path_prefix: &'static str,
+    ) -> EnclaveEndpoint<'static, E> {
+        let CustomEnclave {
+            endpoint,
+            mr_enclave,
+            raft_config,
+        } = match enclave {
+            Resolved::Custom(enclave) => enclave,
+            Resolved::Base(base) => return base,
+        };
+        EnclaveEndpoint {
+            domain_config: self.domain_config(endpoint, path_prefix, None),
+            params: EndpointParams {
+                mr_enclave: MrEnclave::new(Cow::Owned(mr_enclave)),
+                raft_config,
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use assert_matches::assert_matches;
+    use nonzero_ext::nonzero;
+
+    use super::*;
+    use crate::env::{PROXY_CONFIG_F_STAGING, PROXY_CONFIG_G, STAGING};
+
+    const PORT: NonZeroU16 = nonzero!(8443u16);
+    const MR_ENCLAVE: [u8; SGX_MR_ENCLAVE_LEN] = [0x11; SGX_MR_ENCLAVE_LEN];
+    const RAFT_CONFIG: &RaftConfig = &RaftConfig {
+        min_voting_replicas: 1,
+        max_voting_replicas: 3,
+        super_majority: 0,
+        group_id: 1,
+    };
+
+    fn full_builder() -> EnvBuilder {
+        EnvBuilder::new()
+            .chat("chat.example", PORT)
+            .cdsi("cdsi.example", PORT, MR_ENCLAVE)
+            .svr2("svr2.example", PORT, MR_ENCLAVE, RAFT_CONFIG)
+            .svr3_sgx("sgx.example", PORT, MR_ENCLAVE, RAFT_CONFIG)
+            .svr3_nitro("nitro.example", PORT, b"2024-01-01".as_slice(), RAFT_CONFIG)
+            .svr3_tpm2snp(
+                "tpm2snp.example",
+                PORT,
+                b"2024-01-01".as_slice(),
+                RAFT_CONFIG,
+            )
+    }
+
+    #[test]
+    fn build_without_base() {
+        let env = full_builder().build().expect("valid");
+
+        assert_eq!(env.chat_domain_config.connect.hostname, "chat.example");
+        assert_eq!(env.chat_domain_config.connect.port, PORT);
+        assert_eq!(
+            env.chat_domain_config.connect.confirmation_header_name,
+            Some(TIMESTAMP_HEADER_NAME)
+        );
+        assert_eq!(env.cdsi.domain_config.connect.hostname, "cdsi.example");
+        assert_eq!(env.cdsi.params.mr_enclave.as_ref(), MR_ENCLAVE);
+        assert_eq!(env.svr3.nitro().params.mr_enclave.as_ref(), b"2024-01-01");
+
+        // Without proxies, only the direct route should be offered.
+        assert_eq!(
+            env.chat_domain_config
+                .connect
+                .connection_params_with_fallback()
+                .len(),
+            1
+        );
+    }
+
+    #[test]
+    fn build_requires_every_endpoint_without_base() {
+        assert_matches!(
+            EnvBuilder::new().chat("chat.example", PORT).build(),
+            Err(EnvBuilderError::MissingEndpoint("CDSI"))
+        );
+    }
+
+    #[test]
+    fn build_with_base_overrides_only_what_was_set() {
+        let env = EnvBuilder::with_base(STAGING)
+            .cdsi("cdsi.example", PORT, MR_ENCLAVE)
+            .build()
+            .expect("valid");
+
+        assert_eq!(env.cdsi.domain_config.connect.hostname, "cdsi.example");
+        assert_eq!(
+            env.chat_domain_config.connect.hostname,
+            STAGING.chat_domain_config.connect.hostname
+        );
+        assert_eq!(
+            env.svr2.params.mr_enclave.as_ref(),
+            STAGING.svr2.params.mr_enclave.as_ref()
+        );
+    }
+
+    #[test]
+    fn build_applies_proxy_configs_to_custom_endpoints() {
+        let env = full_builder()
+            .proxy_configs([PROXY_CONFIG_F_STAGING, PROXY_CONFIG_G])
+            .build()
+            .expect("valid");
+        let proxy = env.cdsi.domain_config.connect.proxy.expect("has proxies");
+        assert_eq!(proxy.path_prefix, "/cdsi");
+    }
+
+    #[test]
+    fn build_rejects_bad_sgx_mr_enclave() {
+        assert_matches!(
+            full_builder()
+                .svr2("svr2.example", PORT, [0; 31], RAFT_CONFIG)
+                .build(),
+            Err(EnvBuilderError::InvalidMrEnclaveLength("SVR2", 31))
+        );
+    }
+
+    #[test]
+    fn build_rejects_bad_enclave_id() {
+        assert_matches!(
+            full_builder()
+                .svr3_nitro("nitro.example", PORT, [0xff], RAFT_CONFIG)
+                .build(),
+            Err(EnvBuilderError::InvalidEnclaveId("SVR3 Nitro"))
+        );
+        assert_matches!(
+            full_builder()
+                .svr3_tpm2snp("tpm2snp.example", PORT, Vec::new(), RAFT_CONFIG)
+                .build(),
+            Err(EnvBuilderError::InvalidEnclaveId("SVR3 TPM2-SNP"))
+        );
+    }
+
+    #[test]
+    fn build_rejects_empty_hostname() {
+        assert_matches!(
+            full_builder().chat("", PORT).build(),
+            Err(EnvBuilderError::EmptyHostname("chat"))
+        );
+    }
+}
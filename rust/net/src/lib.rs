@@ -4,13 +4,19 @@
 //
 
 pub mod auth;
+pub mod cdn;
 pub mod cdsi;
 pub mod certs;
 pub mod chat;
+pub mod connection_manager_state;
+pub mod diagnostics;
 pub mod enclave;
 pub mod env;
+pub mod network_hint;
 pub mod proto;
+pub mod proxy;
 pub mod svr;
+pub mod svr2;
 pub mod svr3;
 
 // Re-export from `libsignal_net_infra`.
@@ -0,0 +1,145 @@
+//
+// Copyright 2024 Signal Messenger, LLC.
+// SPDX-License-Identifier: AGPL-3.0-only
+//
+
+//! Tuning connection parameters based on what the OS says about the current network.
+//!
+//! Apps generally know whether they're on Wi-Fi or cellular, and whether the network is metered
+//! or has background data restricted. libsignal can't find that out on its own, so the app passes
+//! it in as a [`NetworkHint`], and [`NetworkHint::tuning`] decides what that means for new
+//! connections.
+
+use std::time::Duration;
+
+use libsignal_net_infra::timeouts::{ONE_ROUTE_CONNECTION_TIMEOUT, WS_KEEP_ALIVE_INTERVAL};
+
+/// The kind of network the device is using.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, num_enum::TryFromPrimitive)]
+#[repr(u8)]
+pub enum NetworkTransport {
+    /// Unknown, or anything other than Wi-Fi and cellular (e.g. Ethernet, or a VPN).
+    Other = 0,
+    Wifi = 1,
+    Cellular = 2,
+}
+
+/// What the app knows about the network connections are being made on.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct NetworkHint {
+    pub transport: NetworkTransport,
+    /// Whether the network is metered or has background data restricted.
+    pub constrained: bool,
+}
+
+impl Default for NetworkHint {
+    /// Nothing is known about the network.
+    fn default() -> Self {
+        Self {
+            transport: NetworkTransport::Other,
+            constrained: false,
+        }
+    }
+}
+
+/// Connection parameters derived from a [`NetworkHint`].
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct ConnectionTuning {
+    /// How long to wait for a connection to be established over a single route.
+    pub connect_timeout: Duration,
+    /// How often to send a WebSocket `PING` on an otherwise idle connection.
+    ///
+    /// Always less than
+    /// [`WS_MAX_IDLE_INTERVAL`](libsignal_net_infra::timeouts::WS_MAX_IDLE_INTERVAL), so that a
+    /// healthy connection is never considered idle.
+    pub keep_alive_interval: Duration,
+    /// Whether to try IPv6 addresses before IPv4 addresses when both are available.
+    pub prefer_ipv6: bool,
+}
+
+impl NetworkHint {
+    /// Decides how connections should be made on this network.
+    ///
+    /// The default hint produces the same parameters libsignal used before hints existed.
+    pub fn tuning(&self) -> ConnectionTuning {
+        let Self {
+            transport,
+            constrained,
+        } = *self;
+
+        let connect_timeout = match (transport, constrained) {
+            // Wi-Fi is usually fast and reliable; if a route hasn't connected in this time, it's
+            // better to move on to the next one.
+            (NetworkTransport::Wifi, false) => ONE_ROUTE_CONNECTION_TIMEOUT / 2,
+            (NetworkTransport::Wifi, true)
+            | (NetworkTransport::Cellular | NetworkTransport::Other, false) => {
+                ONE_ROUTE_CONNECTION_TIMEOUT
+            }
+            (NetworkTransport::Cellular | NetworkTransport::Other, true) => {
+                ONE_ROUTE_CONNECTION_TIMEOUT * 3 / 2
+            }
+        };
+
+        // Each ping wakes up the radio, which costs battery and data; on a constrained network,
+        // ping as rarely as the server's idle timeout allows.
+        let keep_alive_interval = if constrained {
+            WS_KEEP_ALIVE_INTERVAL * 2
+        } else {
+            WS_KEEP_ALIVE_INTERVAL
+        };
+
+        // Mobile carriers widely deploy IPv6 (often IPv6-only with NAT64), while home routers are
+        // the most common source of IPv6 that resolves but doesn't work.
+        let prefer_ipv6 = transport != NetworkTransport::Wifi;
+
+        ConnectionTuning {
+            connect_timeout,
+            keep_alive_interval,
+            prefer_ipv6,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use libsignal_net_infra::timeouts::WS_MAX_IDLE_INTERVAL;
+    use test_case::test_case;
+
+    use super::*;
+
+    const fn secs(n: u64) -> Duration {
+        Duration::from_secs(n)
+    }
+
+    #[test_case(NetworkTransport::Wifi, false => (secs(30), secs(15), false))]
+    #[test_case(NetworkTransport::Wifi, true => (secs(60), secs(30), false))]
+    #[test_case(NetworkTransport::Cellular, false => (secs(60), secs(15), true))]
+    #[test_case(NetworkTransport::Cellular, true => (secs(90), secs(30), true))]
+    #[test_case(NetworkTransport::Other, false => (secs(60), secs(15), true))]
+    #[test_case(NetworkTransport::Other, true => (secs(90), secs(30), true))]
+    fn tuning(transport: NetworkTransport, constrained: bool) -> (Duration, Duration, bool) {
+        let ConnectionTuning {
+            connect_timeout,
+            keep_alive_interval,
+            prefer_ipv6,
+        } = NetworkHint {
+            transport,
+            constrained,
+        }
+        .tuning();
+        assert!(keep_alive_interval < WS_MAX_IDLE_INTERVAL);
+        (connect_timeout, keep_alive_interval, prefer_ipv6)
+    }
+
+    #[test]
+    fn default_matches_fixed_parameters() {
+        assert_eq!(
+            NetworkHint::default().tuning(),
+            ConnectionTuning {
+                connect_timeout: ONE_ROUTE_CONNECTION_TIMEOUT,
+                keep_alive_interval: WS_KEEP_ALIVE_INTERVAL,
+                prefer_ipv6: true,
+            }
+        );
+    }
+}
@@ -5,3 +5,6 @@
 
 pub(crate) mod cds2;
 pub mod chat_websocket;
+pub(crate) mod connection_manager_state;
+pub(crate) mod envelope;
+pub(crate) mod svr2;
@@ -0,0 +1,359 @@
+//
+// Copyright 2024 Signal Messenger, LLC.
+// SPDX-License-Identifier: AGPL-3.0-only
+//
+
+//! Process-wide proxy configuration.
+//!
+//! Apps that let users configure a Signal TLS proxy need *every* connection
+//! libsignal makes to go through it; telling each kind of connection about the
+//! proxy separately makes it too easy to miss one. Instead, the proxy is
+//! recorded here once, and [`apply_global_proxy`] is consulted whenever a
+//! [`TcpSslConnector`] is handed out for a new connection.
+//!
+//! Users usually share proxies as links, which [`parse_proxy_url`] turns into a
+//! [`ProxyConfig`].
+
+use std::num::NonZeroU16;
+use std::sync::{Arc, OnceLock, RwLock};
+
+use libsignal_net_infra::host::Host;
+use libsignal_net_infra::tcp_ssl::proxy::tls::TlsProxyConnector;
+use libsignal_net_infra::tcp_ssl::TcpSslConnector;
+use libsignal_net_infra::utils::ObservableEvent;
+use nonzero_ext::nonzero;
+
+/// The address of a TLS proxy that all connections should go through.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ProxyConfig {
+    pub host: Host<Arc<str>>,
+    pub port: NonZeroU16,
+}
+
+/// The host in `https://signal.tube/#<proxy address>` links.
+pub const SIGNAL_TUBE_HOST: &str = "signal.tube";
+
+/// The scheme and host in `sgnl://proxy?host=<proxy address>` deep links.
+const DEEP_LINK_SCHEME: &str = "sgnl";
+const DEEP_LINK_HOST: &str = "proxy";
+
+/// Used when a proxy link doesn't specify a port.
+const DEFAULT_PROXY_PORT: NonZeroU16 = nonzero!(443u16);
+
+#[derive(Debug, displaydoc::Display, thiserror::Error, PartialEq, Eq)]
+pub enum ProxyUrlError {
+    /// not a valid URL
+    MalformedUrl,
+    /// not a Signal proxy link
+    NotAProxyLink,
+    /// proxy link has no host
+    MissingHost,
+    /// proxy address must not include user info
+    UserInfo,
+    /// proxy address must not include a path, query, or fragment
+    ExtraComponents,
+    /// proxy host is invalid
+    InvalidHost,
+    /// proxy port is invalid
+    InvalidPort,
+}
+
+/// Parses a link to a Signal TLS proxy.
+///
+/// Both `https://signal.tube/#<address>` and `sgnl://proxy?host=<address>` are accepted, where
+/// `<address>` is a host with an optional port (443 if not given). The host in the result is
+/// normalized (lowercased, with internationalized domain names converted to punycode), and is what
+/// will be sent as the SNI when connecting to the proxy.
+pub fn parse_proxy_url(url: &str) -> Result<ProxyConfig, ProxyUrlError> {
+    let url = url::Url::parse(url).map_err(|_| ProxyUrlError::MalformedUrl)?;
+    if !url.username().is_empty() || url.password().is_some() || url.port().is_some() {
+        return Err(ProxyUrlError::NotAProxyLink);
+    }
+
+    let address = match (url.scheme(), url.host_str()) {
+        ("https", Some(SIGNAL_TUBE_HOST)) => {
+            if url.path() != "/" || url.query().is_some() {
+                return Err(ProxyUrlError::NotAProxyLink);
+            }
+            url.fragment().unwrap_or_default()
+        }
+        (DEEP_LINK_SCHEME, Some(DEEP_LINK_HOST)) => {
+            if !url.path().is_empty() || url.fragment().is_some() {
+                return Err(ProxyUrlError::NotAProxyLink);
+            }
+            // Deliberately not using `query_pairs`: the address is percent-decoded when it's
+            // parsed below, and it shouldn't be decoded twice.
+            let mut params = url.query().unwrap_or_default().split('&');
+            let address = match params.next() {
+                None | Some("") => "",
+                Some(param) => param
+                    .strip_prefix("host=")
+                    .ok_or(ProxyUrlError::ExtraComponents)?,
+            };
+            if params.next().is_some() {
+                return Err(ProxyUrlError::ExtraComponents);
+            }
+            address
+        }
+        _ => return Err(ProxyUrlError::NotAProxyLink),
+    };
+
+    parse_proxy_address(address)
+}
+
+/// Parses `host[:port]`, reusing [`url`]'s host handling to get the same normalization a browser
+/// would do.
+fn parse_proxy_address(address: &str) -> Result<ProxyConfig, ProxyUrlError> {
+    if address.is_empty() {
+        return Err(ProxyUrlError::MissingHost);
+    }
+    // Check for these up front, since `url` would otherwise treat them as delimiters and quietly
+    // parse something other than what the link appears to say.
+    if address.contains('@') {
+        return Err(ProxyUrlError::UserInfo);
+    }
+    if address.contains(['/', '\\', '?', '#']) {
+        return Err(ProxyUrlError::ExtraComponents);
+    }
+
+    let url = url::Url::parse(&format!("https://{address}")).map_err(|e| match e {
+        url::ParseError::EmptyHost => ProxyUrlError::MissingHost,
+        url::ParseError::InvalidPort => ProxyUrlError::InvalidPort,
+        _ => ProxyUrlError::InvalidHost,
+    })?;
+    let host = match url.host().ok_or(ProxyUrlError::MissingHost)? {
+        url::Host::Domain(domain) => Host::Domain(domain.into()),
+        url::Host::Ipv4(ip) => Host::Ip(ip.into()),
+        url::Host::Ipv6(ip) => Host::Ip(ip.into()),
+    };
+    let port = match url.port() {
+        // `url` omits the port if it's the default for https.
+        None => DEFAULT_PROXY_PORT,
+        Some(port) => NonZeroU16::new(port).ok_or(ProxyUrlError::InvalidPort)?,
+    };
+
+    Ok(ProxyConfig { host, port })
+}
+
+/// Whether a particular connection should honor the global proxy setting.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum GlobalProxyPolicy {
+    /// Connect through the global proxy, if one is set.
+    Respect,
+    /// Ignore the global proxy.
+    ///
+    /// This is only appropriate for traffic needed to reach the proxy itself.
+    Bypass,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+enum GlobalProxySetting {
+    Proxy(ProxyConfig),
+    /// The app asked for a proxy, but its configuration couldn't be used.
+    ///
+    /// Connections will fail rather than silently bypassing the proxy.
+    Invalid,
+}
+
+static GLOBAL_PROXY: RwLock<Option<GlobalProxySetting>> = RwLock::new(None);
+
+/// Fired whenever the global proxy setting changes.
+///
+/// Subscribers should treat this like a network change: connections established
+/// under the old setting need to be re-established.
+pub fn global_proxy_changed_event() -> &'static ObservableEvent {
+    static EVENT: OnceLock<ObservableEvent> = OnceLock::new();
+    EVENT.get_or_init(ObservableEvent::new)
+}
+
+/// Sets (or, with `None`, clears) the proxy used for all new connections.
+pub fn set_global_proxy(proxy: Option<ProxyConfig>) {
+    replace_setting(proxy.map(GlobalProxySetting::Proxy))
+}
+
+/// Records that a proxy was requested, but its configuration was invalid.
+///
+/// Until the setting is replaced, all connections that respect the global proxy
+/// will fail with [`TransportConnectError::InvalidConfiguration`].
+///
+/// [`TransportConnectError::InvalidConfiguration`]: libsignal_net_infra::errors::TransportConnectError::InvalidConfiguration
+pub fn set_invalid_global_proxy() {
+    replace_setting(Some(GlobalProxySetting::Invalid))
+}
+
+/// Returns the current global proxy, if one is set and valid.
+pub fn global_proxy() -> Option<ProxyConfig> {
+    match &*GLOBAL_PROXY.read().expect("not poisoned") {
+        Some(GlobalProxySetting::Proxy(proxy)) => Some(proxy.clone()),
+        Some(GlobalProxySetting::Invalid) | None => None,
+    }
+}
+
+fn replace_setting(setting: Option<GlobalProxySetting>) {
+    let previous = std::mem::replace(
+        &mut *GLOBAL_PROXY.write().expect("not poisoned"),
+        setting.clone(),
+    );
+    // Fire outside the lock, since subscribers may want to read the new value.
+    if previous != setting {
+        log::info!("global proxy setting changed");
+        global_proxy_changed_event().fire();
+    }
+}
+
+/// Adjusts `connector` to account for the global proxy setting.
+///
+/// If a global proxy is set and `policy` is [`GlobalProxyPolicy::Respect`],
+/// returns a connector that goes through that proxy, reusing `connector`'s DNS
+/// resolver to find it. Otherwise, returns `connector` unchanged.
+///
+/// A connector that is already [`TcpSslConnector::Invalid`] stays that way; a
+/// more specific configuration failure shouldn't be papered over.
+pub fn apply_global_proxy(
+    connector: TcpSslConnector,
+    policy: GlobalProxyPolicy,
+) -> TcpSslConnector {
+    match policy {
+        GlobalProxyPolicy::Respect => {
+            let guard = GLOBAL_PROXY.read().expect("not poisoned");
+            apply_setting(connector, guard.as_ref())
+        }
+        GlobalProxyPolicy::Bypass => connector,
+    }
+}
+
+fn apply_setting(
+    connector: TcpSslConnector,
+    setting: Option<&GlobalProxySetting>,
+) -> TcpSslConnector {
+    let Some(setting) = setting else {
+        return connector;
+    };
+    if let TcpSslConnector::Invalid(_) = connector {
+        return connector;
+    }
+    let dns_resolver = connector.dns_resolver().clone();
+    match setting {
+        GlobalProxySetting::Proxy(ProxyConfig { host, port }) => {
+            TlsProxyConnector::new(dns_resolver, (host.clone(), *port)).into()
+        }
+        GlobalProxySetting::Invalid => TcpSslConnector::Invalid(dns_resolver),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use assert_matches::assert_matches;
+    use libsignal_net_infra::dns::DnsResolver;
+    use libsignal_net_infra::tcp_ssl::DirectConnector;
+    use test_case::test_case;
+
+    use super::*;
+
+    fn direct_connector() -> TcpSslConnector {
+        DirectConnector::new(DnsResolver::new(&ObservableEvent::new())).into()
+    }
+
+    fn proxy_setting() -> GlobalProxySetting {
+        GlobalProxySetting::Proxy(ProxyConfig {
+            host: Host::Domain("proxy.example".into()),
+            port: nonzero!(8443u16),
+        })
+    }
+
+    #[test]
+    fn no_setting_leaves_connector_alone() {
+        assert_matches!(
+            apply_setting(direct_connector(), None),
+            TcpSslConnector::Direct(_)
+        );
+    }
+
+    #[test]
+    fn proxy_setting_replaces_direct_and_proxied_connectors() {
+        let setting = proxy_setting();
+        assert_matches!(
+            apply_setting(direct_connector(), Some(&setting)),
+            TcpSslConnector::Proxied(_)
+        );
+
+        let per_connection_proxy = TlsProxyConnector::new(
+            DnsResolver::new(&ObservableEvent::new()),
+            (Host::Domain("other.example".into()), nonzero!(443u16)),
+        );
+        assert_matches!(
+            apply_setting(per_connection_proxy.into(), Some(&setting)),
+            TcpSslConnector::Proxied(_)
+        );
+    }
+
+    #[test]
+    fn invalid_setting_fails_closed() {
+        assert_matches!(
+            apply_setting(direct_connector(), Some(&GlobalProxySetting::Invalid)),
+            TcpSslConnector::Invalid(_)
+        );
+    }
+
+    #[test]
+    fn invalid_connector_stays_invalid() {
+        let invalid = TcpSslConnector::Invalid(DnsResolver::new(&ObservableEvent::new()));
+        assert_matches!(
+            apply_setting(invalid, Some(&proxy_setting())),
+            TcpSslConnector::Invalid(_)
+        );
+    }
+
+    fn domain(host: &str, port: u16) -> Result<ProxyConfig, ProxyUrlError> {
+        Ok(ProxyConfig {
+            host: Host::Domain(host.into()),
+            port: NonZeroU16::new(port).expect("nonzero"),
+        })
+    }
+
+    fn ip(host: &str, port: u16) -> Result<ProxyConfig, ProxyUrlError> {
+        Ok(ProxyConfig {
+            host: Host::Ip(host.parse().expect("valid IP")),
+            port: NonZeroU16::new(port).expect("nonzero"),
+        })
+    }
+
+    #[test_case("https://signal.tube/#proxy.example" => domain("proxy.example", 443); "signal.tube")]
+    #[test_case("https://signal.tube/#proxy.example:8443" => domain("proxy.example", 8443); "signal.tube with port")]
+    #[test_case("https://signal.tube/#Proxy.EXAMPLE" => domain("proxy.example", 443); "lowercased")]
+    #[test_case("https://signal.tube/#b\u{fc}cher.example" => domain("xn--bcher-kva.example", 443); "IDN")]
+    #[test_case("https://signal.tube/#192.0.2.1:8443" => ip("192.0.2.1", 8443); "IPv4")]
+    #[test_case("https://signal.tube/#[2001:db8::1]" => ip("2001:db8::1", 443); "IPv6")]
+    #[test_case("sgnl://proxy?host=proxy.example" => domain("proxy.example", 443); "deep link")]
+    #[test_case("sgnl://proxy?host=proxy.example:8443" => domain("proxy.example", 8443); "deep link with port")]
+    #[test_case("sgnl://proxy?host=b%C3%BCcher.example" => domain("xn--bcher-kva.example", 443); "deep link IDN")]
+    #[test_case("not a url" => Err(ProxyUrlError::MalformedUrl); "malformed")]
+    #[test_case("http://signal.tube/#proxy.example" => Err(ProxyUrlError::NotAProxyLink); "http")]
+    #[test_case("https://signal.tube.evil.example/#proxy.example" => Err(ProxyUrlError::NotAProxyLink); "lookalike host")]
+    #[test_case("https://evil.example@signal.tube/#proxy.example" => Err(ProxyUrlError::NotAProxyLink); "userinfo in link")]
+    #[test_case("https://signal.tube:8443/#proxy.example" => Err(ProxyUrlError::NotAProxyLink); "port in link")]
+    #[test_case("https://signal.tube/path#proxy.example" => Err(ProxyUrlError::NotAProxyLink); "path in link")]
+    #[test_case("https://signal.tube/?host=proxy.example" => Err(ProxyUrlError::NotAProxyLink); "query in link")]
+    #[test_case("sgnl://signal.tube/#proxy.example" => Err(ProxyUrlError::NotAProxyLink); "deep link to signal.tube")]
+    #[test_case("https://signal.tube/" => Err(ProxyUrlError::MissingHost); "no fragment")]
+    #[test_case("https://signal.tube/#:8443" => Err(ProxyUrlError::MissingHost); "only port")]
+    #[test_case("sgnl://proxy" => Err(ProxyUrlError::MissingHost); "no host param")]
+    #[test_case("sgnl://proxy?host=" => Err(ProxyUrlError::MissingHost); "empty host param")]
+    #[test_case("sgnl://proxy?port=443" => Err(ProxyUrlError::ExtraComponents); "wrong param")]
+    #[test_case("sgnl://proxy?host=proxy.example&host=evil.example" => Err(ProxyUrlError::ExtraComponents); "extra param")]
+    #[test_case("https://signal.tube/#evil.example@proxy.example" => Err(ProxyUrlError::UserInfo); "embedded @")]
+    #[test_case("sgnl://proxy?host=evil.example@proxy.example" => Err(ProxyUrlError::UserInfo); "deep link embedded @")]
+    #[test_case("sgnl://proxy?host=evil.example%40proxy.example" => Err(ProxyUrlError::InvalidHost); "deep link encoded @")]
+    #[test_case("https://signal.tube/#proxy.example/path" => Err(ProxyUrlError::ExtraComponents); "path")]
+    #[test_case("https://signal.tube/#proxy.example\\path" => Err(ProxyUrlError::ExtraComponents); "backslash")]
+    #[test_case("https://signal.tube/#proxy.example#evil.example" => Err(ProxyUrlError::ExtraComponents); "second fragment")]
+    #[test_case("https://signal.tube/#proxy.example:0" => Err(ProxyUrlError::InvalidPort); "port 0")]
+    #[test_case("https://signal.tube/#proxy.example:65536" => Err(ProxyUrlError::InvalidPort); "port too large")]
+    #[test_case("https://signal.tube/#proxy.example:https" => Err(ProxyUrlError::InvalidPort); "non-numeric port")]
+    #[test_case("https://signal.tube/#proxy example" => Err(ProxyUrlError::InvalidHost); "space")]
+    #[test_case("https://signal.tube/#proxy\u{202e}elpmaxe" => Err(ProxyUrlError::InvalidHost); "bidi override")]
+    #[test_case("https://signal.tube/#[2001:db8::1" => Err(ProxyUrlError::InvalidHost); "unterminated IPv6")]
+    fn proxy_url(url: &str) -> Result<ProxyConfig, ProxyUrlError> {
+        parse_proxy_url(url)
+    }
+}
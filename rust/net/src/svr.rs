@@ -4,32 +4,41 @@
 //
 
 use std::marker::PhantomData;
+use std::num::NonZeroU32;
 
 use libsignal_net_infra::connection_manager::ConnectionManager;
 use libsignal_net_infra::ws::AttestedConnection;
 use libsignal_net_infra::{AsyncDuplexStream, HttpBasicAuth, TransportConnector};
+use rand_core::CryptoRngCore;
+use signal_pin::PinHash;
+use tokio::time::Instant;
 
 pub use crate::enclave::Error;
-use crate::enclave::{EnclaveEndpointConnection, IntoAttestedConnection, NewHandshake, Svr3Flavor};
+use crate::enclave::{
+    EnclaveEndpointConnection, EnclaveKind, IntoAttestedConnection, NewHandshake,
+};
+use crate::svr2::{self, Svr2Client};
+use crate::svr3::traits::Backup;
+use crate::svr3::{self, OpaqueMaskedShareSet};
 
-pub struct SvrConnection<Flavor: Svr3Flavor, S> {
+pub struct SvrConnection<Flavor: EnclaveKind, S> {
     inner: AttestedConnection<S>,
     witness: PhantomData<Flavor>,
 }
 
-impl<Flavor: Svr3Flavor, S> From<SvrConnection<Flavor, S>> for AttestedConnection<S> {
+impl<Flavor: EnclaveKind, S> From<SvrConnection<Flavor, S>> for AttestedConnection<S> {
     fn from(conn: SvrConnection<Flavor, S>) -> Self {
         conn.inner
     }
 }
 
-impl<Flavor: Svr3Flavor, S: Send> IntoAttestedConnection for SvrConnection<Flavor, S> {
+impl<Flavor: EnclaveKind, S: Send> IntoAttestedConnection for SvrConnection<Flavor, S> {
     type Stream = S;
 }
 
-impl<E: Svr3Flavor, S: AsyncDuplexStream> SvrConnection<E, S>
+impl<E: EnclaveKind, S: AsyncDuplexStream> SvrConnection<E, S>
 where
-    E: Svr3Flavor + NewHandshake + Sized,
+    E: EnclaveKind + NewHandshake + Sized,
     S: AsyncDuplexStream,
 {
     pub async fn connect<C, T>(
@@ -49,4 +58,491 @@ where
                 witness: PhantomData,
             })
     }
+
+    /// Like [`Self::connect`], but keeps trying the endpoint's routes until `deadline`.
+    pub async fn connect_with_deadline<C, T>(
+        auth: impl HttpBasicAuth,
+        connection: &EnclaveEndpointConnection<E, C>,
+        transport_connector: T,
+        deadline: Instant,
+    ) -> Result<Self, Error>
+    where
+        C: ConnectionManager,
+        T: TransportConnector<Stream = S>,
+    {
+        connection
+            .connect_with_deadline(auth, transport_connector, deadline)
+            .await
+            .map(|inner| Self {
+                inner,
+                witness: PhantomData,
+            })
+    }
+}
+
+/// How many times each of the SVR3 backup and SVR2 delete steps of a pin migration is attempted
+/// before giving up on a transient error.
+///
+/// Both steps are idempotent, so retrying them is always safe. Restoring from SVR2 is never
+/// retried automatically, since a failed attempt may use up one of the user's tries.
+const PIN_MIGRATION_STEP_ATTEMPTS: usize = 3;
+
+/// A step of [`migrate_pin_to_svr3`], along with everything needed to perform it.
+#[derive(Clone)]
+pub enum PinMigrationStep {
+    /// Restore the secret from SVR2. Nothing has been changed yet.
+    RestoreFromSvr2,
+    /// Back up the restored secret to SVR3. The user is still only enrolled in SVR2.
+    BackupToSvr3 { secret: [u8; 32] },
+    /// Delete the backup from SVR2. The user is enrolled in both SVR2 and SVR3.
+    DeleteFromSvr2 {
+        secret: [u8; 32],
+        share_set: OpaqueMaskedShareSet,
+    },
+}
+
+// Hand-written to keep the secret out of logs.
+impl std::fmt::Debug for PinMigrationStep {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            Self::RestoreFromSvr2 => "RestoreFromSvr2",
+            Self::BackupToSvr3 { .. } => "BackupToSvr3",
+            Self::DeleteFromSvr2 { .. } => "DeleteFromSvr2",
+        })
+    }
+}
+
+/// The result of a completed pin migration.
+pub struct PinMigrated {
+    /// The secret that was stored in SVR2, and is now stored in SVR3.
+    pub secret: [u8; 32],
+    /// The share set for the new SVR3 backup, to be stored by the client.
+    pub share_set: OpaqueMaskedShareSet,
+}
+
+/// A pin migration that stopped partway through.
+#[derive(Debug, thiserror::Error, displaydoc::Display)]
+/// Pin migration stopped at {resume_from:?}: {error}
+pub struct PinMigrationError {
+    /// The step to pass to [`resume_pin_migration`] to continue the migration.
+    pub resume_from: PinMigrationStep,
+    /// The error that stopped the migration.
+    pub error: svr3::Error,
+}
+
+/// Moves a user's secret from SVR2 to SVR3.
+///
+/// This restores the secret from SVR2 with `pin`, backs it up to SVR3 using `pin` as the
+/// password, and finally deletes the SVR2 backup. `pin` must already be normalized.
+///
+/// The steps happen in this order so that the user is always enrolled in at least one of the
+/// services. If any step fails, the returned [`PinMigrationError`] says which step to continue
+/// from with [`resume_pin_migration`]; steps that have completed are not repeated.
+pub async fn migrate_pin_to_svr3<Svr2, Svr3>(
+    svr2: &Svr2,
+    svr3: &Svr3,
+    pin: &str,
+    max_tries: NonZeroU32,
+    rng: &mut (impl CryptoRngCore + Send),
+) -> Result<PinMigrated, PinMigrationError>
+where
+    Svr2: Svr2Client + Sync,
+    Svr3: Backup + Sync,
+{
+    resume_pin_migration(
+        PinMigrationStep::RestoreFromSvr2,
+        svr2,
+        svr3,
+        pin,
+        max_tries,
+        rng,
+    )
+    .await
+}
+
+/// Continues a [`migrate_pin_to_svr3`] operation from `step`.
+///
+/// `pin` and `max_tries` must be the same as for the original call.
+pub async fn resume_pin_migration<Svr2, Svr3>(
+    step: PinMigrationStep,
+    svr2: &Svr2,
+    svr3: &Svr3,
+    pin: &str,
+    max_tries: NonZeroU32,
+    rng: &mut (impl CryptoRngCore + Send),
+) -> Result<PinMigrated, PinMigrationError>
+where
+    Svr2: Svr2Client + Sync,
+    Svr3: Backup + Sync,
+{
+    let mut step = step;
+    loop {
+        let result = match &step {
+            PinMigrationStep::RestoreFromSvr2 => restore_from_svr2(svr2, pin)
+                .await
+                .map(|secret| PinMigrationStep::BackupToSvr3 { secret }),
+            PinMigrationStep::BackupToSvr3 { secret } => {
+                let secret = *secret;
+                let mut attempts = 0;
+                loop {
+                    attempts += 1;
+                    match svr3.backup(pin, secret, max_tries, rng).await {
+                        Err(e) if is_transient(&e) && attempts < PIN_MIGRATION_STEP_ATTEMPTS => {
+                            log::info!("SVR3 backup failed during pin migration, retrying: {e}");
+                        }
+                        result => {
+                            break result.map(|share_set| PinMigrationStep::DeleteFromSvr2 {
+                                secret,
+                                share_set,
+                            })
+                        }
+                    }
+                }
+            }
+            PinMigrationStep::DeleteFromSvr2 { secret, share_set } => {
+                let mut attempts = 0;
+                loop {
+                    attempts += 1;
+                    match svr2.delete().await {
+                        Err(e) if is_transient(&e) && attempts < PIN_MIGRATION_STEP_ATTEMPTS => {
+                            log::info!("SVR2 delete failed during pin migration, retrying: {e}");
+                        }
+                        Err(e) => break Err(e),
+                        Ok(()) => {
+                            return Ok(PinMigrated {
+                                secret: *secret,
+                                share_set: share_set.clone(),
+                            })
+                        }
+                    }
+                }
+            }
+        };
+        step = result.map_err(|error| PinMigrationError {
+            resume_from: step,
+            error,
+        })?;
+    }
+}
+
+async fn restore_from_svr2(
+    svr2: &(impl Svr2Client + Sync),
+    pin: &str,
+) -> Result<[u8; 32], svr3::Error> {
+    let pin_hash = PinHash::create(pin.as_bytes(), &svr2.pin_salt())
+        .map_err(|e| svr3::Error::Protocol(format!("failed to hash pin: {e}")))?;
+    let data = svr2.restore(pin_hash.access_key).await?;
+    svr2::decrypt_secret(&pin_hash.encryption_key, &data)
+}
+
+fn is_transient(error: &svr3::Error) -> bool {
+    match error {
+        svr3::Error::Connect(_) | svr3::Error::Service(_) | svr3::Error::ConnectionTimedOut => true,
+        svr3::Error::Protocol(_)
+        | svr3::Error::AttestationError(_)
+        | svr3::Error::RequestFailed(_)
+        | svr3::Error::RestoreFailed(_)
+        | svr3::Error::DataMissing
+        | svr3::Error::RotationMachineTooManySteps
+        | svr3::Error::CircuitOpen
+        | svr3::Error::PasswordRotationIncomplete { .. } => false,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::collections::VecDeque;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Mutex;
+
+    use assert_matches::assert_matches;
+    use async_trait::async_trait;
+    use nonzero_ext::nonzero;
+    use rand_core::OsRng;
+
+    use super::*;
+
+    const PIN: &str = "1234";
+    const SALT: [u8; 32] = [0x5a; 32];
+    const SECRET: [u8; 32] = [0x53; 32];
+    const MAX_TRIES: NonZeroU32 = nonzero!(10u32);
+
+    /// An SVR2 enclave holding a single backup, with injectable failures.
+    struct FakeSvr2 {
+        stored: Mutex<Option<([u8; 32], Vec<u8>)>>,
+        restore_failures: Mutex<VecDeque<svr3::Error>>,
+        delete_failures: Mutex<VecDeque<svr3::Error>>,
+        restore_calls: AtomicUsize,
+        delete_calls: AtomicUsize,
+    }
+
+    impl FakeSvr2 {
+        fn with_backup(pin: &str, secret: [u8; 32]) -> Self {
+            let pin_hash = PinHash::create(pin.as_bytes(), &SALT).expect("can hash");
+            let data = svr2::encrypt_secret(&pin_hash.encryption_key, &secret);
+            Self {
+                stored: Mutex::new(Some((pin_hash.access_key, data))),
+                restore_failures: Default::default(),
+                delete_failures: Default::default(),
+                restore_calls: Default::default(),
+                delete_calls: Default::default(),
+            }
+        }
+
+        fn has_backup(&self) -> bool {
+            self.stored.lock().unwrap().is_some()
+        }
+    }
+
+    #[async_trait]
+    impl Svr2Client for FakeSvr2 {
+        fn pin_salt(&self) -> [u8; 32] {
+            SALT
+        }
+
+        async fn restore(&self, access_key: [u8; 32]) -> Result<Vec<u8>, svr3::Error> {
+            self.restore_calls.fetch_add(1, Ordering::SeqCst);
+            if let Some(e) = self.restore_failures.lock().unwrap().pop_front() {
+                return Err(e);
+            }
+            match &*self.stored.lock().unwrap() {
+                None => Err(svr3::Error::DataMissing),
+                Some((expected_key, _)) if *expected_key != access_key => {
+                    Err(svr3::Error::RestoreFailed(9))
+                }
+                Some((_, data)) => Ok(data.clone()),
+            }
+        }
+
+        async fn delete(&self) -> Result<(), svr3::Error> {
+            self.delete_calls.fetch_add(1, Ordering::SeqCst);
+            if let Some(e) = self.delete_failures.lock().unwrap().pop_front() {
+                return Err(e);
+            }
+            *self.stored.lock().unwrap() = None;
+            Ok(())
+        }
+    }
+
+    /// A set of SVR3 enclaves that records backups, with injectable failures.
+    #[derive(Default)]
+    struct FakeSvr3 {
+        backup_failures: Mutex<VecDeque<svr3::Error>>,
+        backups: Mutex<Vec<(String, [u8; 32])>>,
+        backup_calls: AtomicUsize,
+    }
+
+    #[async_trait]
+    impl Backup for FakeSvr3 {
+        async fn backup(
+            &self,
+            password: &str,
+            secret: [u8; 32],
+            _max_tries: NonZeroU32,
+            _rng: &mut (impl CryptoRngCore + Send),
+        ) -> Result<OpaqueMaskedShareSet, svr3::Error> {
+            self.backup_calls.fetch_add(1, Ordering::SeqCst);
+            if let Some(e) = self.backup_failures.lock().unwrap().pop_front() {
+                return Err(e);
+            }
+            self.backups
+                .lock()
+                .unwrap()
+                .push((password.to_owned(), secret));
+            Ok(OpaqueMaskedShareSet::default())
+        }
+    }
+
+    fn failures(errors: impl IntoIterator<Item = svr3::Error>) -> Mutex<VecDeque<svr3::Error>> {
+        Mutex::new(errors.into_iter().collect())
+    }
+
+    async fn migrate(svr2: &FakeSvr2, svr3: &FakeSvr3) -> Result<PinMigrated, PinMigrationError> {
+        migrate_pin_to_svr3(svr2, svr3, PIN, MAX_TRIES, &mut OsRng).await
+    }
+
+    async fn resume(
+        step: PinMigrationStep,
+        svr2: &FakeSvr2,
+        svr3: &FakeSvr3,
+    ) -> Result<PinMigrated, PinMigrationError> {
+        resume_pin_migration(step, svr2, svr3, PIN, MAX_TRIES, &mut OsRng).await
+    }
+
+    #[tokio::test]
+    async fn migrate_success() {
+        let svr2 = FakeSvr2::with_backup(PIN, SECRET);
+        let svr3 = FakeSvr3::default();
+
+        let migrated = migrate(&svr2, &svr3).await.expect("success");
+        assert_eq!(migrated.secret, SECRET);
+        assert_eq!(*svr3.backups.lock().unwrap(), [(PIN.to_owned(), SECRET)]);
+        assert!(!svr2.has_backup());
+        assert_eq!(svr2.restore_calls.load(Ordering::SeqCst), 1);
+        assert_eq!(svr2.delete_calls.load(Ordering::SeqCst), 1);
+    }
+
+    #[tokio::test]
+    async fn migrate_wrong_pin() {
+        let svr2 = FakeSvr2::with_backup("5678", SECRET);
+        let svr3 = FakeSvr3::default();
+
+        let err = migrate(&svr2, &svr3).await.err().expect("failure");
+        assert_matches!(err.resume_from, PinMigrationStep::RestoreFromSvr2);
+        assert_matches!(err.error, svr3::Error::RestoreFailed(9));
+        assert_eq!(svr3.backup_calls.load(Ordering::SeqCst), 0);
+        assert!(svr2.has_backup());
+    }
+
+    #[tokio::test]
+    async fn migrate_restore_is_not_retried() {
+        let svr2 = FakeSvr2 {
+            restore_failures: failures([svr3::Error::ConnectionTimedOut]),
+            ..FakeSvr2::with_backup(PIN, SECRET)
+        };
+        let svr3 = FakeSvr3::default();
+
+        let err = migrate(&svr2, &svr3).await.err().expect("failure");
+        assert_matches!(err.resume_from, PinMigrationStep::RestoreFromSvr2);
+        assert_matches!(err.error, svr3::Error::ConnectionTimedOut);
+        assert_eq!(svr2.restore_calls.load(Ordering::SeqCst), 1);
+
+        let migrated = resume(err.resume_from, &svr2, &svr3)
+            .await
+            .expect("success");
+        assert_eq!(migrated.secret, SECRET);
+    }
+
+    #[tokio::test]
+    async fn migrate_corrupted_svr2_data() {
+        let svr2 = FakeSvr2::with_backup(PIN, SECRET);
+        if let Some((_, data)) = &mut *svr2.stored.lock().unwrap() {
+            data[20] ^= 1;
+        }
+        let svr3 = FakeSvr3::default();
+
+        let err = migrate(&svr2, &svr3).await.err().expect("failure");
+        assert_matches!(err.resume_from, PinMigrationStep::RestoreFromSvr2);
+        assert_matches!(err.error, svr3::Error::Protocol(_));
+        assert_eq!(svr3.backup_calls.load(Ordering::SeqCst), 0);
+    }
+
+    #[tokio::test]
+    async fn migrate_backup_transient_failure_is_retried() {
+        let svr2 = FakeSvr2::with_backup(PIN, SECRET);
+        let svr3 = FakeSvr3 {
+            backup_failures: failures([
+                svr3::Error::ConnectionTimedOut,
+                svr3::Error::ConnectionTimedOut,
+            ]),
+            ..FakeSvr3::default()
+        };
+
+        let migrated = migrate(&svr2, &svr3).await.expect("success");
+        assert_eq!(migrated.secret, SECRET);
+        assert_eq!(svr3.backup_calls.load(Ordering::SeqCst), 3);
+        assert!(!svr2.has_backup());
+    }
+
+    #[tokio::test]
+    async fn migrate_backup_failure_can_be_resumed() {
+        let svr2 = FakeSvr2::with_backup(PIN, SECRET);
+        let svr3 = FakeSvr3 {
+            backup_failures: failures(
+                std::iter::repeat_with(|| svr3::Error::ConnectionTimedOut)
+                    .take(PIN_MIGRATION_STEP_ATTEMPTS),
+            ),
+            ..FakeSvr3::default()
+        };
+
+        let err = migrate(&svr2, &svr3).await.err().expect("failure");
+        assert_matches!(err.resume_from, PinMigrationStep::BackupToSvr3 { secret } if secret == SECRET);
+        assert_matches!(err.error, svr3::Error::ConnectionTimedOut);
+        assert_eq!(
+            svr3.backup_calls.load(Ordering::SeqCst),
+            PIN_MIGRATION_STEP_ATTEMPTS
+        );
+        // The user must still be enrolled somewhere.
+        assert!(svr2.has_backup());
+
+        let migrated = resume(err.resume_from, &svr2, &svr3)
+            .await
+            .expect("success");
+        assert_eq!(migrated.secret, SECRET);
+        assert_eq!(svr2.restore_calls.load(Ordering::SeqCst), 1);
+        assert!(!svr2.has_backup());
+    }
+
+    #[tokio::test]
+    async fn migrate_backup_permanent_failure_is_not_retried() {
+        let svr2 = FakeSvr2::with_backup(PIN, SECRET);
+        let svr3 = FakeSvr3 {
+            backup_failures: failures([svr3::Error::Protocol("bad".to_owned())]),
+            ..FakeSvr3::default()
+        };
+
+        let err = migrate(&svr2, &svr3).await.err().expect("failure");
+        assert_matches!(err.resume_from, PinMigrationStep::BackupToSvr3 { .. });
+        assert_matches!(err.error, svr3::Error::Protocol(_));
+        assert_eq!(svr3.backup_calls.load(Ordering::SeqCst), 1);
+        assert!(svr2.has_backup());
+    }
+
+    #[tokio::test]
+    async fn migrate_delete_transient_failure_is_retried() {
+        let svr2 = FakeSvr2 {
+            delete_failures: failures([svr3::Error::ConnectionTimedOut]),
+            ..FakeSvr2::with_backup(PIN, SECRET)
+        };
+        let svr3 = FakeSvr3::default();
+
+        migrate(&svr2, &svr3).await.expect("success");
+        assert_eq!(svr2.delete_calls.load(Ordering::SeqCst), 2);
+        assert!(!svr2.has_backup());
+    }
+
+    #[tokio::test]
+    async fn migrate_delete_failure_can_be_resumed() {
+        let svr2 = FakeSvr2 {
+            delete_failures: failures(
+                std::iter::repeat_with(|| svr3::Error::ConnectionTimedOut)
+                    .take(PIN_MIGRATION_STEP_ATTEMPTS),
+            ),
+            ..FakeSvr2::with_backup(PIN, SECRET)
+        };
+        let svr3 = FakeSvr3::default();
+
+        let err = migrate(&svr2, &svr3).await.err().expect("failure");
+        assert_matches!(err.resume_from, PinMigrationStep::DeleteFromSvr2 { secret, .. } if secret == SECRET);
+        assert_eq!(svr3.backup_calls.load(Ordering::SeqCst), 1);
+        assert!(svr2.has_backup());
+
+        let migrated = resume(err.resume_from, &svr2, &svr3)
+            .await
+            .expect("success");
+        assert_eq!(migrated.secret, SECRET);
+        assert_eq!(migrated.share_set, OpaqueMaskedShareSet::default());
+        assert_eq!(svr2.restore_calls.load(Ordering::SeqCst), 1);
+        assert_eq!(svr3.backup_calls.load(Ordering::SeqCst), 1);
+        assert!(!svr2.has_backup());
+    }
+
+    #[tokio::test]
+    async fn resume_delete_when_already_deleted() {
+        let svr2 = FakeSvr2::with_backup(PIN, SECRET);
+        let svr3 = FakeSvr3::default();
+        svr2.delete().await.expect("can delete");
+
+        let migrated = resume(
+            PinMigrationStep::DeleteFromSvr2 {
+                secret: SECRET,
+                share_set: OpaqueMaskedShareSet::default(),
+            },
+            &svr2,
+            &svr3,
+        )
+        .await
+        .expect("success");
+        assert_eq!(migrated.secret, SECRET);
+    }
 }
@@ -0,0 +1,230 @@
+//
+// Copyright 2024 Signal Messenger, LLC.
+// SPDX-License-Identifier: AGPL-3.0-only
+//
+
+//! Client for SVR2, the previous generation of Secure Value Recovery.
+//!
+//! Only the operations needed to move users off of SVR2 are supported: restoring a backup and
+//! deleting it. See [`migrate_pin_to_svr3`](crate::svr::migrate_pin_to_svr3).
+
+use async_trait::async_trait;
+use hmac::{Hmac, Mac};
+use libsignal_net_infra::ws::AttestedConnection;
+use libsignal_net_infra::AsyncDuplexStream;
+use sha2::Sha256;
+
+use crate::enclave::{self, SgxPreQuantum};
+use crate::proto::svr2 as proto;
+use crate::svr::SvrConnection;
+use crate::svr3::Error;
+
+/// The length of the data stored in SVR2: a 16-byte synthetic IV followed by a 32-byte secret.
+const ENCRYPTED_SECRET_LEN: usize = 48;
+const IV_LEN: usize = 16;
+
+/// The operations supported by an SVR2 instance.
+///
+/// Errors are reported using the SVR3 error type, so that a single error type covers an entire
+/// SVR2-to-SVR3 migration.
+#[async_trait]
+pub trait Svr2Client {
+    /// The salt that pins must be hashed with before being used with this instance.
+    ///
+    /// See [`PinHash::make_salt`](signal_pin::PinHash::make_salt).
+    fn pin_salt(&self) -> [u8; 32];
+
+    /// Restores the user's backup, returning the encrypted data that was stored.
+    ///
+    /// Fails with [`Error::RestoreFailed`] if `access_key` is wrong, which uses up one try.
+    async fn restore(&self, access_key: [u8; 32]) -> Result<Vec<u8>, Error>;
+
+    /// Deletes the user's backup.
+    ///
+    /// Deleting a backup that doesn't exist succeeds, so this is safe to retry.
+    async fn delete(&self) -> Result<(), Error>;
+}
+
+/// Connects to a single SVR2 enclave.
+///
+/// Implementing this trait provides an implementation of [`Svr2Client`] that makes a new
+/// connection for each operation.
+#[async_trait]
+pub trait Svr2Connect {
+    type Stream;
+
+    /// See [`Svr2Client::pin_salt`].
+    fn pin_salt(&self) -> [u8; 32];
+
+    async fn connect(&self) -> Result<SvrConnection<SgxPreQuantum, Self::Stream>, enclave::Error>;
+}
+
+#[async_trait]
+impl<T> Svr2Client for T
+where
+    T: Svr2Connect + Sync,
+    T::Stream: AsyncDuplexStream + 'static,
+{
+    fn pin_salt(&self) -> [u8; 32] {
+        Svr2Connect::pin_salt(self)
+    }
+
+    async fn restore(&self, access_key: [u8; 32]) -> Result<Vec<u8>, Error> {
+        let request = proto::request::Inner::Restore(proto::RestoreRequest {
+            pin: access_key.to_vec(),
+        });
+        let proto::response::Inner::Restore(response) =
+            run_request(self.connect().await?, request).await?
+        else {
+            return Err(Error::Protocol("unexpected SVR2 response".to_owned()));
+        };
+
+        use proto::restore_response::Status;
+        match response.status() {
+            Status::Ok => Ok(response.data),
+            Status::Missing => Err(Error::DataMissing),
+            Status::PinMismatch => Err(Error::RestoreFailed(response.tries)),
+            status @ (Status::Unset | Status::RequestInvalid) => Err(Error::Protocol(format!(
+                "SVR2 restore failed with status {}",
+                status.as_str_name()
+            ))),
+        }
+    }
+
+    async fn delete(&self) -> Result<(), Error> {
+        let request = proto::request::Inner::Delete(proto::DeleteRequest {});
+        match run_request(self.connect().await?, request).await? {
+            proto::response::Inner::Delete(proto::DeleteResponse {}) => Ok(()),
+            _ => Err(Error::Protocol("unexpected SVR2 response".to_owned())),
+        }
+    }
+}
+
+async fn run_request<S: AsyncDuplexStream>(
+    connection: SvrConnection<SgxPreQuantum, S>,
+    request: proto::request::Inner,
+) -> Result<proto::response::Inner, Error> {
+    let mut connection = AttestedConnection::from(connection);
+    connection
+        .send(proto::Request {
+            inner: Some(request),
+        })
+        .await?;
+    let response = connection.receive().await;
+    connection.close().await;
+    let response: proto::Response =
+        response?.next_or_else(|_| Error::Protocol("SVR2 connection closed".to_owned()))?;
+    response
+        .inner
+        .ok_or_else(|| Error::Protocol("empty SVR2 response".to_owned()))
+}
+
+/// Decrypts a secret stored in SVR2 using the encryption key from the user's
+/// [`PinHash`](signal_pin::PinHash).
+///
+/// SVR2 clients encrypt their secret with HMAC-SIV before storing it, so that the data held by
+/// the enclave is useless without the pin.
+pub(crate) fn decrypt_secret(encryption_key: &[u8; 32], data: &[u8]) -> Result<[u8; 32], Error> {
+    let bad_data = || Error::Protocol("invalid SVR2 data".to_owned());
+
+    let data: &[u8; ENCRYPTED_SECRET_LEN] = data.try_into().map_err(|_| bad_data())?;
+    let (iv, ciphertext) = data.split_at(IV_LEN);
+    let (auth_key, enc_key) = hmac_siv_keys(encryption_key);
+
+    let keystream = hmac_sha256(&enc_key, iv);
+    let mut secret = [0; 32];
+    for ((s, c), k) in secret.iter_mut().zip(ciphertext).zip(keystream) {
+        *s = c ^ k;
+    }
+
+    let mut mac = Hmac::<Sha256>::new_from_slice(&auth_key).expect("any key length");
+    mac.update(&secret);
+    mac.verify_truncated_left(iv).map_err(|_| bad_data())?;
+    Ok(secret)
+}
+
+/// The inverse of [`decrypt_secret`], used to play the part of an SVR2 client in tests.
+#[cfg(test)]
+pub(crate) fn encrypt_secret(encryption_key: &[u8; 32], secret: &[u8; 32]) -> Vec<u8> {
+    let (auth_key, enc_key) = hmac_siv_keys(encryption_key);
+    let iv = &hmac_sha256(&auth_key, secret)[..IV_LEN];
+    let keystream = hmac_sha256(&enc_key, iv);
+    iv.iter()
+        .copied()
+        .chain(secret.iter().zip(keystream).map(|(s, k)| s ^ k))
+        .collect()
+}
+
+fn hmac_siv_keys(key: &[u8; 32]) -> ([u8; 32], [u8; 32]) {
+    (hmac_sha256(key, b"auth"), hmac_sha256(key, b"enc"))
+}
+
+fn hmac_sha256(key: &[u8], input: &[u8]) -> [u8; 32] {
+    let mut mac = Hmac::<Sha256>::new_from_slice(key).expect("any key length");
+    mac.update(input);
+    mac.finalize().into_bytes().into()
+}
+
+#[cfg(test)]
+mod test {
+    use assert_matches::assert_matches;
+    use hex_literal::hex;
+    use signal_pin::PinHash;
+    use test_case::test_case;
+
+    use super::*;
+
+    const KEY: [u8; 32] = [0x4b; 32];
+    const SECRET: [u8; 32] = [0x53; 32];
+
+    #[test]
+    fn secret_round_trip() {
+        let data = encrypt_secret(&KEY, &SECRET);
+        assert_eq!(data.len(), ENCRYPTED_SECRET_LEN);
+        assert_eq!(decrypt_secret(&KEY, &data).expect("valid"), SECRET);
+    }
+
+    // Data written by the Android client, from its PinHashing tests.
+    #[test_case(
+        b"password",
+        hex!("000102030405060708090a0b0c0d0e0f101112131415161718191a1b1c1d1e1f"),
+        hex!("3f33ce58eb25b40436592a30eae2a8fabab1899095f4e2fba6e2d0dc43b4a2d9cac5a3931748522393951e0e54dec769")
+        => hex!("202122232425262728292a2b2c2d2e2f303132333435363738393a3b3c3d3e3f");
+        "password"
+    )]
+    #[test_case(
+        b"anotherpassword",
+        hex!("202122232425262728292a2b2c2d2e2f303132333435363738393a3b3c3d3e3f"),
+        hex!("9d9b05402ea39c17ff1c9298c8a0e86784a352aa02a74943bf8bcf07ec0f4b574a5b786ad0182c8d308d9eb06538b8c9")
+        => hex!("88a787415a2ecd79da0d1016a82a27c5c695c9a19b88b0aa1d35683280aa9a67");
+        "anotherpassword"
+    )]
+    fn secret_from_android_client(pin: &[u8], salt: [u8; 32], data: [u8; 48]) -> [u8; 32] {
+        let pin_hash = PinHash::create(pin, &salt).expect("valid");
+        let secret = decrypt_secret(&pin_hash.encryption_key, &data).expect("valid");
+        assert_eq!(encrypt_secret(&pin_hash.encryption_key, &secret), data);
+        secret
+    }
+
+    #[test]
+    fn secret_with_wrong_key() {
+        let data = encrypt_secret(&KEY, &SECRET);
+        assert_matches!(decrypt_secret(&[0; 32], &data), Err(Error::Protocol(_)));
+    }
+
+    #[test]
+    fn secret_tampered() {
+        let mut data = encrypt_secret(&KEY, &SECRET);
+        data[IV_LEN] ^= 1;
+        assert_matches!(decrypt_secret(&KEY, &data), Err(Error::Protocol(_)));
+    }
+
+    #[test]
+    fn secret_wrong_length() {
+        let data = encrypt_secret(&KEY, &SECRET);
+        assert_matches!(
+            decrypt_secret(&KEY, &data[..ENCRYPTED_SECRET_LEN - 1]),
+            Err(Error::Protocol(_))
+        );
+    }
+}
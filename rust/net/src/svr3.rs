@@ -117,6 +117,22 @@ impl OpaqueMaskedShareSet {
     }
 }
 
+/// The outcome of [`Remove::remove`], which is attempted on every enclave even if some fail.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct RemoveResult {
+    /// Whether the data is now gone from each enclave, in the order of the enclave setup.
+    ///
+    /// Removing data that was never backed up (or was already removed) counts as a success.
+    pub removed: Vec<bool>,
+}
+
+impl RemoveResult {
+    /// Whether the data was removed from every enclave.
+    pub fn is_complete(&self) -> bool {
+        self.removed.iter().all(|removed| *removed)
+    }
+}
+
 /// SVR3-specific error type
 ///
 /// In its essence it is simply a union of three other error types:
@@ -152,6 +168,29 @@ pub enum Error {
     ConnectionTimedOut,
     /// Rotation machine took too many steps
     RotationMachineTooManySteps,
+    /// Not connecting to an enclave that has repeatedly failed recently
+    CircuitOpen,
+    /// Password rotation failed after the backup started being replaced: {cause}
+    ///
+    /// Some enclaves may hold the backup under the new password and others under the old one, in
+    /// which case it can't be restored with either. `secret` is what was restored with the old
+    /// password, so the backup can be created again from it to recover.
+    PasswordRotationIncomplete {
+        cause: Box<Error>,
+        secret: RecoveredSecret,
+    },
+}
+
+/// The secret restored by a [`RotatePassword::rotate_password`] that then failed partway.
+///
+/// Its `Debug` output leaves out the value, so that the error carrying it can be logged.
+#[derive(Clone, PartialEq, Eq)]
+pub struct RecoveredSecret(pub [u8; 32]);
+
+impl std::fmt::Debug for RecoveredSecret {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("RecoveredSecret(..)")
+    }
 }
 
 impl From<DeserializeError> for Error {
@@ -194,6 +233,7 @@ impl From<super::svr::Error> for Error {
             SvrError::Protocol => Self::Protocol("General SVR protocol error".to_string()),
             SvrError::AttestationError(inner) => Self::AttestationError(inner),
             SvrError::ConnectionTimedOut => Self::ConnectionTimedOut,
+            SvrError::CircuitOpen => Self::CircuitOpen,
         }
     }
 }
@@ -300,6 +340,7 @@ pub mod test_support {
 mod test {
     use assert_matches::assert_matches;
     use async_trait::async_trait;
+    use libsignal_svr3::QueryResult;
     use nonzero_ext::nonzero;
     use rand_core::{OsRng, RngCore};
 
@@ -344,7 +385,7 @@ mod test {
     struct TestSvr3Client {
         backup_fn: fn() -> Result<OpaqueMaskedShareSet, Error>,
         restore_fn: fn() -> Result<EvaluationResult, Error>,
-        remove_fn: fn() -> Result<(), Error>,
+        remove_fn: fn() -> Result<RemoveResult, Error>,
     }
 
     impl Default for TestSvr3Client {
@@ -372,7 +413,7 @@ mod test {
 
     #[async_trait]
     impl Remove for TestSvr3Client {
-        async fn remove(&self) -> Result<(), Error> {
+        async fn remove(&self) -> Result<RemoveResult, Error> {
             (self.remove_fn)()
         }
     }
@@ -391,7 +432,7 @@ mod test {
 
     #[async_trait]
     impl Query for TestSvr3Client {
-        async fn query(&self) -> Result<u32, Error> {
+        async fn query(&self) -> Result<QueryResult, Error> {
             unreachable!()
         }
     }
@@ -519,7 +560,11 @@ mod test {
     #[tokio::test]
     async fn migrate_backup_remove_success() {
         let source = TestSvr3Client {
-            remove_fn: || Ok(()),
+            remove_fn: || {
+                Ok(RemoveResult {
+                    removed: vec![true; 3],
+                })
+            },
             ..TestSvr3Client::default()
         };
         let destination = TestSvr3Client {
@@ -16,12 +16,12 @@ use libsignal_net_infra::host::Host;
 use libsignal_net_infra::ws::{run_attested_interaction, AttestedConnection, NextOrClose};
 use libsignal_net_infra::AsyncDuplexStream;
 use libsignal_svr3::{
-    Backup4, EvaluationResult, MaskedSecret, Query4, Remove4, Restore1, RotationMachine,
-    MAX_ROTATION_STEPS,
+    Backup4, EvaluationResult, MaskedSecret, Output4, Query4, QueryResult, Remove4, Restore1,
+    RotationMachine, MAX_ROTATION_STEPS,
 };
 use rand_core::CryptoRngCore;
 
-use super::{Error, OpaqueMaskedShareSet};
+use super::{Error, OpaqueMaskedShareSet, RecoveredSecret, RemoveResult};
 use crate::enclave::{ArrayIsh, IntoConnectionResults, PpssSetup};
 
 pub async fn do_backup<S: AsyncDuplexStream + 'static, Env: PpssSetup<S>>(
@@ -37,27 +37,33 @@ pub async fn do_backup<S: AsyncDuplexStream + 'static, Env: PpssSetup<S>>(
         errors,
     } = ConnectionContext::new(connect_results);
     if let Some(err) = errors.into_iter().next() {
+        close_all(connections).await;
         return Err(err);
     }
 
-    let server_ids = Env::server_ids();
-    let backup = Backup4::new(
-        server_ids.as_ref(),
-        password.as_bytes(),
-        &secret,
-        max_tries,
-        rng,
-    )?;
-    let futures = connections
-        .iter_mut()
-        .zip(&backup.requests)
-        .map(|(connection, request)| run_attested_interaction(connection, request));
-    let results = join_all(futures)
-        .await
-        .into_iter()
-        .collect::<Result<Vec<_>, _>>();
-    collect_responses(results?, addresses.iter())?;
-    Ok(OpaqueMaskedShareSet::new(backup.masked_secret))
+    let result = async {
+        let server_ids = Env::server_ids();
+        let backup = Backup4::new(
+            server_ids.as_ref(),
+            password.as_bytes(),
+            &secret,
+            max_tries,
+            rng,
+        )?;
+        let futures = connections
+            .iter_mut()
+            .zip(&backup.requests)
+            .map(|(connection, request)| run_attested_interaction(connection, request));
+        let results = join_all(futures)
+            .await
+            .into_iter()
+            .collect::<Result<Vec<_>, _>>();
+        collect_responses(results?, addresses.iter())?;
+        Ok::<_, Error>(OpaqueMaskedShareSet::new(backup.masked_secret))
+    }
+    .await;
+    close_all(connections).await;
+    result
 }
 
 pub async fn do_restore<S: AsyncDuplexStream + 'static>(
@@ -72,12 +78,41 @@ pub async fn do_restore<S: AsyncDuplexStream + 'static>(
         errors,
     } = ConnectionContext::new(connect_results);
     if let Some(err) = errors.into_iter().next() {
+        close_all(connections).await;
         return Err(err);
     }
 
     let masked_secret: MaskedSecret = share_set.into_inner();
 
-    let restore1 = Restore1::new(masked_secret.server_ids.as_ref(), password.as_bytes(), rng);
+    let result = restore_output(
+        &mut connections,
+        &addresses,
+        &masked_secret.server_ids,
+        password,
+        rng,
+    )
+    .await;
+    close_all(connections).await;
+
+    let (output, tries_remaining) = result?;
+    Ok(EvaluationResult {
+        value: output.unmask_secret(&masked_secret.masked_secret)?,
+        tries_remaining,
+    })
+}
+
+/// Restores the backup on an existing set of connections as far as recovering the key used to
+/// mask the secret.
+///
+/// Also returns the number of tries remaining, as reported by the enclaves.
+async fn restore_output<S: AsyncDuplexStream + 'static>(
+    connections: &mut [AttestedConnection<S>],
+    addresses: &[Host<Arc<str>>],
+    server_ids: &[u64],
+    password: &str,
+    rng: &mut (impl CryptoRngCore + Send),
+) -> Result<(Output4, u32), Error> {
+    let restore1 = Restore1::new(server_ids, password.as_bytes(), rng);
     let responses1 = {
         let futures = connections
             .iter_mut()
@@ -107,49 +142,115 @@ pub async fn do_restore<S: AsyncDuplexStream + 'static>(
             .collect::<Result<Vec<_>, _>>();
         collect_responses(results?, addresses.iter())?
     };
-    let output = restore2.restore(&responses2)?;
-
-    Ok(EvaluationResult {
-        value: output.unmask_secret(&masked_secret.masked_secret)?,
-        tries_remaining,
-    })
+    Ok((restore2.restore(&responses2)?, tries_remaining))
 }
 
-pub async fn do_remove<S: AsyncDuplexStream + 'static>(
+pub async fn do_rotate_password<S: AsyncDuplexStream + 'static>(
     connect_results: impl IntoConnectionResults<Stream = S>,
-) -> Result<(), Error> {
+    old_password: &str,
+    new_password: &str,
+    share_set: OpaqueMaskedShareSet,
+    max_tries: NonZeroU32,
+    rng: &mut (impl CryptoRngCore + Send),
+) -> Result<OpaqueMaskedShareSet, Error> {
     let ConnectionContext {
         mut connections,
         addresses,
         errors,
     } = ConnectionContext::new(connect_results);
-    for err in errors {
-        // For the remove operation we ignore connection failures
-        // and proceed to work with the successful connections.
-        log::debug!("Connection failure '{:?}' will be ignored.", &err);
+    if let Some(err) = errors.into_iter().next() {
+        close_all(connections).await;
+        return Err(err);
     }
 
-    let futures = connections
-        .iter_mut()
-        .zip(Remove4::requests())
-        .map(|(connection, request)| run_attested_interaction(connection, request));
-    let results = join_all(futures)
-        .await
+    let masked_secret: MaskedSecret = share_set.into_inner();
+
+    let result = async {
+        let (output, _tries_remaining) = restore_output(
+            &mut connections,
+            &addresses,
+            &masked_secret.server_ids,
+            old_password,
+            rng,
+        )
+        .await?;
+        let secret = output.unmask_secret(&masked_secret.masked_secret)?;
+        let backup =
+            output.change_password(&masked_secret, new_password.as_bytes(), max_tries, rng)?;
+
+        // From here on, any enclave may already have replaced its share, so a failure can leave
+        // the backup restorable with neither password. Hand back the secret so it isn't lost.
+        let futures = connections
+            .iter_mut()
+            .zip(&backup.requests)
+            .map(|(connection, request)| run_attested_interaction(connection, request));
+        let results = join_all(futures)
+            .await
+            .into_iter()
+            .collect::<Result<Vec<_>, _>>();
+        results
+            .map_err(Error::from)
+            .and_then(|results| collect_responses(results, addresses.iter()))
+            .map_err(|e| Error::PasswordRotationIncomplete {
+                cause: Box::new(e),
+                secret: RecoveredSecret(secret),
+            })?;
+        Ok::<_, Error>(OpaqueMaskedShareSet::new(backup.masked_secret))
+    }
+    .await;
+    close_all(connections).await;
+    result
+}
+
+pub async fn do_remove<S: AsyncDuplexStream + 'static>(
+    connect_results: impl IntoConnectionResults<Stream = S>,
+) -> Result<RemoveResult, Error> {
+    // Unlike the other operations, removal from each enclave is independent
+    // of the others, so failures (including connection failures) don't stop
+    // the remaining enclaves from being cleared.
+    let futures = connect_results
+        .into_connection_results()
         .into_iter()
-        .collect::<Result<Vec<_>, _>>();
-    let _responses = collect_responses(results?, addresses.iter())?;
-    Ok(())
+        .zip(Remove4::requests())
+        .map(|(connect_result, request)| async move {
+            let mut connection = connect_result?;
+            let result = run_attested_interaction(&mut connection, request).await;
+            let address = connection.remote_address().clone();
+            connection.close().await;
+            // The response carries no status: removing a missing record is
+            // also a success.
+            collect_responses([result?], [&address])?;
+            Ok::<_, Error>(())
+        });
+
+    let mut removed = Vec::new();
+    let mut first_error = None;
+    for result in join_all(futures).await {
+        match result {
+            Ok(()) => removed.push(true),
+            Err(err) => {
+                log::debug!("Removal failure '{:?}'", &err);
+                removed.push(false);
+                first_error.get_or_insert(err);
+            }
+        }
+    }
+    match first_error {
+        Some(err) if !removed.contains(&true) => Err(err),
+        _ => Ok(RemoveResult { removed }),
+    }
 }
 
 pub async fn do_query<S: AsyncDuplexStream + 'static>(
     connect_results: impl IntoConnectionResults<Stream = S>,
-) -> Result<u32, Error> {
+) -> Result<QueryResult, Error> {
     let ConnectionContext {
         mut connections,
         addresses,
         errors,
     } = ConnectionContext::new(connect_results);
     if let Some(err) = errors.into_iter().next() {
+        close_all(connections).await;
         return Err(err);
     }
 
@@ -161,6 +262,7 @@ pub async fn do_query<S: AsyncDuplexStream + 'static>(
         .await
         .into_iter()
         .collect::<Result<Vec<_>, _>>();
+    close_all(connections).await;
     let responses = collect_responses(results?, addresses.iter())?;
     Ok(Query4::finalize(&responses)?)
 }
@@ -176,28 +278,36 @@ pub async fn do_rotate<S: AsyncDuplexStream + 'static>(
         errors,
     } = ConnectionContext::new(connect_results);
     if let Some(err) = errors.into_iter().next() {
+        close_all(connections).await;
         return Err(err);
     }
     let masked_secret: MaskedSecret = share_set.into_inner();
 
     let mut rotation_machine = RotationMachine::new(masked_secret.server_ids.as_ref(), rng);
 
-    for _ in 0..MAX_ROTATION_STEPS {
-        if rotation_machine.is_done() {
-            break;
+    let result = async {
+        for _ in 0..MAX_ROTATION_STEPS {
+            if rotation_machine.is_done() {
+                break;
+            }
+            let requests = rotation_machine.requests();
+            let futures = connections
+                .iter_mut()
+                .zip(&requests)
+                .map(|(connection, request)| run_attested_interaction(connection, request));
+            let results = join_all(futures)
+                .await
+                .into_iter()
+                .collect::<Result<Vec<_>, _>>();
+            let responses = collect_responses(results?, addresses.iter())?;
+            rotation_machine.handle_responses(responses.as_ref())?;
         }
-        let requests = rotation_machine.requests();
-        let futures = connections
-            .iter_mut()
-            .zip(&requests)
-            .map(|(connection, request)| run_attested_interaction(connection, request));
-        let results = join_all(futures)
-            .await
-            .into_iter()
-            .collect::<Result<Vec<_>, _>>();
-        let responses = collect_responses(results?, addresses.iter())?;
-        rotation_machine.handle_responses(responses.as_ref())?;
+        Ok::<_, Error>(())
     }
+    .await;
+    close_all(connections).await;
+    result?;
+
     if rotation_machine.is_done() {
         Ok(())
     } else {
@@ -233,6 +343,11 @@ impl<S: AsyncDuplexStream + 'static> ConnectionContext<S> {
     }
 }
 
+/// Closes connections that are no longer needed, so that they aren't reported as leaked.
+async fn close_all<S: AsyncDuplexStream>(connections: Vec<AttestedConnection<S>>) {
+    join_all(connections.into_iter().map(AttestedConnection::close)).await;
+}
+
 fn collect_responses<'a>(
     results: impl IntoIterator<Item = NextOrClose<Vec<u8>>>,
     addresses: impl IntoIterator<Item = &'a Host<impl AsRef<str> + 'a>>,
@@ -248,11 +363,20 @@ fn collect_responses<'a>(
 
 #[cfg(test)]
 mod test {
+    use std::sync::atomic::{AtomicBool, Ordering};
+    use std::sync::{Mutex, OnceLock};
+
     use assert_matches::assert_matches;
     use attest::nitro::NitroError;
+    use libsignal_net_infra::live_connections;
+    use libsignal_net_infra::ws::testutil::{
+        fake_websocket, run_attested_server, websocket_test_client, AttestedServerOutput,
+    };
     use libsignal_net_infra::ws::DefaultStream;
+    use libsignal_svr3::testutil::TestServer;
     use nonzero_ext::nonzero;
     use rand_core::OsRng;
+    use tokio::io::DuplexStream;
 
     use super::*;
     use crate::enclave::Error;
@@ -310,16 +434,358 @@ mod test {
         assert_matches!(result, Err(crate::svr3::Error::ConnectionTimedOut));
     }
 
+    #[tokio::test]
+    async fn do_rotate_password_fails_with_the_first_error() {
+        let mut rng = OsRng;
+        let result = do_rotate_password(
+            NotConnectedResults,
+            "old",
+            "new",
+            OpaqueMaskedShareSet::default(),
+            nonzero!(1u32),
+            &mut rng,
+        )
+        .await;
+        assert_matches!(result, Err(crate::svr3::Error::ConnectionTimedOut));
+    }
+
     #[tokio::test]
     async fn do_query_fails_with_the_first_error() {
         let result = do_query(NotConnectedResults).await;
         assert_matches!(result, Err(crate::svr3::Error::ConnectionTimedOut));
     }
 
+    /// Connects to a fake enclave that answers every request, marking its
+    /// record as gone.
+    ///
+    /// Like a real enclave, it doesn't care whether there was anything to
+    /// remove in the first place.
+    async fn fake_enclave(
+        has_record: Arc<AtomicBool>,
+        kind: &'static str,
+    ) -> Result<AttestedConnection<DuplexStream>, Error> {
+        let (server, client) = fake_websocket().await;
+        tokio::spawn(run_attested_server(
+            server,
+            attest::sgx_session::testutil::private_key(),
+            move |message| match message {
+                NextOrClose::Next(_request) => {
+                    has_record.store(false, Ordering::SeqCst);
+                    AttestedServerOutput::message(b"removed".to_vec())
+                }
+                NextOrClose::Close(close) => AttestedServerOutput::close(close),
+            },
+        ));
+        let connection = AttestedConnection::connect(websocket_test_client(client), kind, |_| {
+            attest::sgx_session::testutil::handshake_from_tests_data()
+        })
+        .await
+        .expect("handshake succeeds");
+        Ok(connection)
+    }
+
+    /// Connects to a fake enclave that runs the SVR3 protocol against `server`.
+    ///
+    /// If `fail_at` is set, the enclave closes the connection instead of
+    /// answering the request with that index, counting from zero.
+    async fn fake_svr3_enclave(
+        server: Arc<Mutex<TestServer>>,
+        fail_at: Option<usize>,
+    ) -> Result<AttestedConnection<DuplexStream>, Error> {
+        let (server_stream, client) = fake_websocket().await;
+        // Restore2 requests are bound to the handshake hash, which the fake
+        // server doesn't expose, so take it from the client once connected.
+        let handshake_hash = Arc::new(OnceLock::<Vec<u8>>::new());
+        let mut requests_seen = 0;
+        tokio::spawn(run_attested_server(
+            server_stream,
+            attest::sgx_session::testutil::private_key(),
+            {
+                let handshake_hash = handshake_hash.clone();
+                move |message| match message {
+                    NextOrClose::Next(request) => {
+                        let index = requests_seen;
+                        requests_seen += 1;
+                        if fail_at == Some(index) {
+                            return AttestedServerOutput::close(None);
+                        }
+                        let handshake_hash = handshake_hash.get().expect("connected");
+                        AttestedServerOutput::message(
+                            server
+                                .lock()
+                                .expect("not poisoned")
+                                .handle(&request, handshake_hash),
+                        )
+                    }
+                    NextOrClose::Close(close) => AttestedServerOutput::close(close),
+                }
+            },
+        ));
+        let connection = AttestedConnection::connect(
+            websocket_test_client(client),
+            live_connections::SVR,
+            |_| attest::sgx_session::testutil::handshake_from_tests_data(),
+        )
+        .await
+        .expect("handshake succeeds");
+        handshake_hash
+            .set(connection.handshake_hash().to_vec())
+            .expect("set once");
+        Ok(connection)
+    }
+
+    const SECRET: [u8; 32] = [0x53; 32];
+
+    /// Backs up [`SECRET`] with `password` on two fresh servers.
+    fn backed_up_servers(password: &str) -> ([Arc<Mutex<TestServer>>; 2], OpaqueMaskedShareSet) {
+        let backup = Backup4::new(
+            &[1, 2],
+            password.as_bytes(),
+            &SECRET,
+            nonzero!(10u32),
+            &mut OsRng,
+        )
+        .expect("can create backup");
+        let servers = [(); 2].map(|()| Arc::new(Mutex::new(TestServer::new())));
+        for (server, request) in servers.iter().zip(&backup.requests) {
+            server.lock().expect("not poisoned").create(request);
+        }
+        (servers, OpaqueMaskedShareSet::new(backup.masked_secret))
+    }
+
+    async fn connect_svr3(
+        servers: &[Arc<Mutex<TestServer>>; 2],
+        fail_at: [Option<usize>; 2],
+    ) -> impl IntoConnectionResults<Stream = DuplexStream> {
+        (
+            fake_svr3_enclave(servers[0].clone(), fail_at[0]).await,
+            fake_svr3_enclave(servers[1].clone(), fail_at[1]).await,
+        )
+    }
+
     #[tokio::test]
-    async fn do_remove_does_not_fail_on_bad_connections() {
-        do_remove(NotConnectedResults)
+    async fn do_rotate_password_replaces_the_password() {
+        let (servers, share_set) = backed_up_servers("old");
+
+        let new_share_set = do_rotate_password(
+            connect_svr3(&servers, [None; 2]).await,
+            "old",
+            "new",
+            share_set.clone(),
+            nonzero!(5u32),
+            &mut OsRng,
+        )
+        .await
+        .expect("can rotate");
+
+        let restored = do_restore(
+            connect_svr3(&servers, [None; 2]).await,
+            "new",
+            new_share_set,
+            &mut OsRng,
+        )
+        .await
+        .expect("can restore with the new password");
+        assert_eq!(restored.value, SECRET);
+        // Backing up again reset the tries, less the one used by this restore.
+        assert_eq!(restored.tries_remaining, 4);
+
+        let result = do_restore(
+            connect_svr3(&servers, [None; 2]).await,
+            "old",
+            share_set,
+            &mut OsRng,
+        )
+        .await;
+        assert_matches!(result, Err(crate::svr3::Error::RestoreFailed(_)));
+    }
+
+    #[tokio::test]
+    async fn do_rotate_password_with_wrong_password_changes_nothing() {
+        let (servers, share_set) = backed_up_servers("old");
+
+        let result = do_rotate_password(
+            connect_svr3(&servers, [None; 2]).await,
+            "wrong",
+            "new",
+            share_set.clone(),
+            nonzero!(5u32),
+            &mut OsRng,
+        )
+        .await;
+        assert_matches!(result, Err(crate::svr3::Error::RestoreFailed(_)));
+
+        let restored = do_restore(
+            connect_svr3(&servers, [None; 2]).await,
+            "old",
+            share_set,
+            &mut OsRng,
+        )
+        .await
+        .expect("can still restore with the old password");
+        assert_eq!(restored.value, SECRET);
+    }
+
+    #[tokio::test]
+    async fn do_rotate_password_with_missing_share_reports_missing_data() {
+        let (servers, share_set) = backed_up_servers("old");
+        *servers[1].lock().expect("not poisoned") = TestServer::new();
+
+        let result = do_rotate_password(
+            connect_svr3(&servers, [None; 2]).await,
+            "old",
+            "new",
+            share_set,
+            nonzero!(5u32),
+            &mut OsRng,
+        )
+        .await;
+        assert_matches!(result, Err(crate::svr3::Error::DataMissing));
+    }
+
+    #[tokio::test]
+    async fn do_rotate_password_failing_during_restore_changes_nothing() {
+        let (servers, share_set) = backed_up_servers("old");
+
+        // Requests 0 and 1 restore the backup.
+        let result = do_rotate_password(
+            connect_svr3(&servers, [None, Some(1)]).await,
+            "old",
+            "new",
+            share_set.clone(),
+            nonzero!(5u32),
+            &mut OsRng,
+        )
+        .await;
+        assert_matches!(result, Err(e) if !matches!(e, crate::svr3::Error::PasswordRotationIncomplete { .. }));
+
+        let restored = do_restore(
+            connect_svr3(&servers, [None; 2]).await,
+            "old",
+            share_set,
+            &mut OsRng,
+        )
+        .await
+        .expect("can still restore with the old password");
+        assert_eq!(restored.value, SECRET);
+    }
+
+    #[tokio::test]
+    async fn do_rotate_password_failing_partway_is_reported() {
+        let (servers, share_set) = backed_up_servers("old");
+
+        // Request 2 creates the backup again, which only the first enclave
+        // gets to do.
+        let result = do_rotate_password(
+            connect_svr3(&servers, [None, Some(2)]).await,
+            "old",
+            "new",
+            share_set.clone(),
+            nonzero!(5u32),
+            &mut OsRng,
+        )
+        .await;
+        let secret = assert_matches!(
+            result,
+            Err(crate::svr3::Error::PasswordRotationIncomplete { secret, .. }) => secret
+        );
+        assert_eq!(secret, RecoveredSecret(SECRET));
+
+        // The enclaves now disagree, so the old password no longer works.
+        let result = do_restore(
+            connect_svr3(&servers, [None; 2]).await,
+            "old",
+            share_set,
+            &mut OsRng,
+        )
+        .await;
+        assert_matches!(result, Err(_));
+
+        // But the secret can be backed up again from what the error returned.
+        let backup = Backup4::new(&[1, 2], b"new", &secret.0, nonzero!(10u32), &mut OsRng)
+            .expect("can create backup");
+        for (server, request) in servers.iter().zip(&backup.requests) {
+            server.lock().expect("not poisoned").create(request);
+        }
+        let restored = do_restore(
+            connect_svr3(&servers, [None; 2]).await,
+            "new",
+            OpaqueMaskedShareSet::new(backup.masked_secret),
+            &mut OsRng,
+        )
+        .await
+        .expect("can restore the recreated backup");
+        assert_eq!(restored.value, SECRET);
+    }
+
+    #[tokio::test]
+    async fn do_remove_removes_from_every_enclave() {
+        let records = [(); 2].map(|()| Arc::new(AtomicBool::new(true)));
+        let connect_results = (
+            fake_enclave(records[0].clone(), live_connections::SVR).await,
+            fake_enclave(records[1].clone(), live_connections::SVR).await,
+        );
+
+        let result = do_remove(connect_results).await.expect("can remove");
+        assert_eq!(result.removed, [true, true]);
+        assert!(result.is_complete());
+        assert!(records
+            .iter()
+            .all(|has_record| !has_record.load(Ordering::SeqCst)));
+    }
+
+    #[tokio::test]
+    async fn do_remove_reports_unreachable_enclaves() {
+        let has_record = Arc::new(AtomicBool::new(true));
+        let connect_results = (
+            fake_enclave(has_record.clone(), live_connections::SVR).await,
+            Err(Error::ConnectionTimedOut),
+        );
+
+        let result = do_remove(connect_results)
             .await
-            .expect("Should ignore connection errors");
+            .expect("one enclave is enough to succeed");
+        assert_eq!(result.removed, [true, false]);
+        assert!(!result.is_complete());
+        assert!(!has_record.load(Ordering::SeqCst));
+    }
+
+    #[tokio::test]
+    async fn do_remove_is_idempotent() {
+        let records = [(); 2].map(|()| Arc::new(AtomicBool::new(true)));
+        for _ in 0..2 {
+            let connect_results = (
+                fake_enclave(records[0].clone(), live_connections::SVR).await,
+                fake_enclave(records[1].clone(), live_connections::SVR).await,
+            );
+            let result = do_remove(connect_results).await.expect("can remove");
+            assert_eq!(result.removed, [true, true]);
+        }
+    }
+
+    #[cfg(debug_assertions)]
+    #[tokio::test]
+    async fn do_remove_releases_its_connections() {
+        // Unique to this test, since the counts are shared by every test in the process.
+        const KIND: &str = "test-svr3-remove";
+
+        let records = [(); 2].map(|()| Arc::new(AtomicBool::new(true)));
+        let connect_results = (
+            fake_enclave(records[0].clone(), KIND).await,
+            fake_enclave(records[1].clone(), KIND).await,
+        );
+        assert_eq!(
+            live_connections::live_connection_counts().get(KIND),
+            Some(&2)
+        );
+
+        do_remove(connect_results).await.expect("can remove");
+        assert_eq!(live_connections::live_connection_counts().get(KIND), None);
+    }
+
+    #[tokio::test]
+    async fn do_remove_fails_if_no_enclave_is_reachable() {
+        let result = do_remove(NotConnectedResults).await;
+        assert_matches!(result, Err(crate::svr3::Error::ConnectionTimedOut));
     }
 }
@@ -11,10 +11,10 @@ use std::num::NonZeroU32;
 
 use async_trait::async_trait;
 use libsignal_net_infra::AsyncDuplexStream;
-use libsignal_svr3::EvaluationResult;
+use libsignal_svr3::{EvaluationResult, QueryResult};
 use rand_core::CryptoRngCore;
 
-use super::{ppss_ops, Error, OpaqueMaskedShareSet};
+use super::{ppss_ops, Error, OpaqueMaskedShareSet, RemoveResult};
 use crate::enclave::PpssSetup;
 
 #[async_trait]
@@ -40,12 +40,16 @@ pub trait Restore {
 
 #[async_trait]
 pub trait Query {
-    async fn query(&self) -> Result<u32, Error>;
+    /// Checks whether a backup exists without consuming a restore attempt.
+    async fn query(&self) -> Result<QueryResult, Error>;
 }
 
 #[async_trait]
 pub trait Remove {
-    async fn remove(&self) -> Result<(), Error>;
+    /// Removes the data from each enclave, reporting which of them succeeded.
+    ///
+    /// Fails only if the data could not be removed from any of the enclaves.
+    async fn remove(&self) -> Result<RemoveResult, Error>;
 }
 
 #[async_trait]
@@ -57,6 +61,33 @@ pub trait Rotate {
     ) -> Result<(), Error>;
 }
 
+#[async_trait]
+pub trait RotatePassword {
+    /// Protects the backup in `share_set` with `new_password` instead of `old_password`.
+    ///
+    /// The enclaves have no request that changes only the password, so this restores the backup
+    /// with `old_password` and then backs it up again with `new_password` over the same
+    /// connections. That is no different from a [`Restore`] followed by a [`Backup`]: it uses up a
+    /// try, uploads new shares of the secret, and resets the number of tries to `max_tries`.
+    ///
+    /// Fails with [`Error::DataMissing`] if any enclave no longer has the backup, and with
+    /// [`Error::RestoreFailed`] if `old_password` is wrong; either way nothing is created with the
+    /// new password. On success the returned share set replaces `share_set`, which can no longer
+    /// be used to restore.
+    ///
+    /// Replacing the backup isn't atomic across enclaves. If it fails partway, this returns
+    /// [`Error::PasswordRotationIncomplete`] with the secret that was restored, which should be
+    /// backed up again with [`Backup::backup`].
+    async fn rotate_password(
+        &self,
+        old_password: &str,
+        new_password: &str,
+        share_set: OpaqueMaskedShareSet,
+        max_tries: NonZeroU32,
+        rng: &mut (impl CryptoRngCore + Send),
+    ) -> Result<OpaqueMaskedShareSet, Error>;
+}
+
 #[async_trait]
 pub trait Svr3Connect {
     // Stream is needed for the blanket implementation,
@@ -112,7 +143,7 @@ where
     T: Svr3Connect + Sync,
     T::Stream: AsyncDuplexStream + 'static,
 {
-    async fn remove(&self) -> Result<(), Error> {
+    async fn remove(&self) -> Result<RemoveResult, Error> {
         ppss_ops::do_remove(self.connect().await).await
     }
 }
@@ -123,7 +154,7 @@ where
     T: Svr3Connect + Sync,
     T::Stream: AsyncDuplexStream + 'static,
 {
-    async fn query(&self) -> Result<u32, Error> {
+    async fn query(&self) -> Result<QueryResult, Error> {
         ppss_ops::do_query(self.connect().await).await
     }
 }
@@ -142,3 +173,29 @@ where
         ppss_ops::do_rotate(self.connect().await, share_set, rng).await
     }
 }
+
+#[async_trait]
+impl<T> RotatePassword for T
+where
+    T: Svr3Connect + Sync,
+    T::Stream: AsyncDuplexStream + 'static,
+{
+    async fn rotate_password(
+        &self,
+        old_password: &str,
+        new_password: &str,
+        share_set: OpaqueMaskedShareSet,
+        max_tries: NonZeroU32,
+        rng: &mut (impl CryptoRngCore + Send),
+    ) -> Result<OpaqueMaskedShareSet, Error> {
+        ppss_ops::do_rotate_password(
+            self.connect().await,
+            old_password,
+            new_password,
+            share_set,
+            max_tries,
+            rng,
+        )
+        .await
+    }
+}
@@ -0,0 +1,60 @@
+//
+// Copyright 2024 Signal Messenger, LLC.
+// SPDX-License-Identifier: AGPL-3.0-only
+//
+
+//! Checks the connection details reported in [`DebugInfo`] for chat connections.
+//!
+//! [`DebugInfo`]: libsignal_net::chat::DebugInfo
+
+use assert_matches::assert_matches;
+use libsignal_net::env::STAGING;
+use libsignal_net::infra::RouteType;
+
+mod fake_transport;
+use fake_transport::{allow_proxy_hosts, connect_websockets_on_incoming, Behavior, FakeDeps};
+
+#[test_log::test(tokio::test(start_paused = true))]
+async fn direct_connection_reports_direct_route() {
+    let chat_domain_config = STAGING.chat_domain_config;
+    let (deps, incoming_streams) = FakeDeps::new(&chat_domain_config);
+    deps.transport_connector.set_behaviors([(
+        chat_domain_config.connect.direct_connection_params().into(),
+        Behavior::ReturnStream(vec![]),
+    )]);
+    let chat = deps.make_chat();
+
+    tokio::spawn(connect_websockets_on_incoming(incoming_streams));
+    let debug_info = chat.connect_unauthenticated().await.expect("connected");
+
+    assert_eq!(debug_info.route_type, Some(RouteType::Direct));
+    // The fake transport reports the hostname it was asked to connect to
+    // rather than a resolved IP.
+    assert_eq!(debug_info.remote_address, None);
+    assert!(
+        debug_info.connection_info.starts_with("route=direct;"),
+        "{}",
+        debug_info.connection_info
+    );
+}
+
+#[test_log::test(tokio::test(start_paused = true))]
+async fn proxied_connection_reports_fronting_route() {
+    let (deps, incoming_streams) = FakeDeps::new(&STAGING.chat_domain_config);
+    deps.transport_connector
+        .set_behaviors(allow_proxy_hosts(&STAGING.chat_domain_config));
+    let chat = deps.make_chat();
+
+    tokio::spawn(connect_websockets_on_incoming(incoming_streams));
+    let debug_info = chat.connect_unauthenticated().await.expect("connected");
+
+    assert_matches!(
+        debug_info.route_type,
+        Some(RouteType::ProxyF | RouteType::ProxyG)
+    );
+    assert!(
+        debug_info.connection_info.starts_with("route=proxy"),
+        "{}",
+        debug_info.connection_info
+    );
+}
@@ -0,0 +1,60 @@
+//
+// Copyright 2024 Signal Messenger, LLC.
+// SPDX-License-Identifier: AGPL-3.0-only
+//
+
+//! Connects to fake servers configured through [`EnvBuilder`] rather than one
+//! of the built-in environments.
+
+use std::sync::{Arc, Mutex};
+
+use assert_matches::assert_matches;
+use futures_util::StreamExt as _;
+use libsignal_net::env::{Env, EnvBuilder, Svr3Env, STAGING};
+use libsignal_net::infra::host::Host;
+use nonzero_ext::nonzero;
+
+mod fake_transport;
+use fake_transport::{connect_websockets_on_incoming, Behavior, FakeDeps, FakeTransportTarget};
+
+const CHAT_HOST: &str = "chat.fork.example";
+const CDSI_HOST: &str = "cdsi.fork.example";
+const CDSI_MR_ENCLAVE: [u8; 32] = [0x5a; 32];
+
+fn custom_env() -> Env<'static, Svr3Env<'static>> {
+    EnvBuilder::with_base(STAGING)
+        .chat(CHAT_HOST, nonzero!(8443u16))
+        .cdsi(CDSI_HOST, nonzero!(9443u16), CDSI_MR_ENCLAVE)
+        .build()
+        .expect("valid env")
+}
+
+#[test_log::test(tokio::test(start_paused = true))]
+async fn chat_connects_to_custom_host() {
+    let env = custom_env();
+    let (deps, incoming_streams) = FakeDeps::new(&env.chat_domain_config);
+    deps.transport_connector.set_behaviors([(
+        env.chat_domain_config
+            .connect
+            .direct_connection_params()
+            .into(),
+        Behavior::ReturnStream(vec![]),
+    )]);
+    let chat = deps.make_chat();
+
+    let connected_targets = Arc::new(Mutex::new(Vec::new()));
+    tokio::spawn(connect_websockets_on_incoming(incoming_streams.inspect({
+        let connected_targets = Arc::clone(&connected_targets);
+        move |(target, _stream)| connected_targets.lock().unwrap().push(target.clone())
+    })));
+
+    assert_matches!(chat.connect_unauthenticated().await, Ok(_));
+
+    assert_eq!(
+        *connected_targets.lock().unwrap(),
+        [FakeTransportTarget {
+            host: Host::Domain(CHAT_HOST.into()),
+            port: nonzero!(8443u16),
+        }]
+    );
+}
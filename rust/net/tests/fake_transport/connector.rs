@@ -101,6 +101,8 @@ impl TransportConnector for FakeTransportConnector {
                 route_type: RouteType::Direct,
                 dns_source: DnsSource::Static,
                 address: tcp_host.clone(),
+                port: *port,
+                websocket_sub_protocol: None,
             },
         ))
     }
@@ -17,7 +17,7 @@ use rand_core::OsRng;
 use tokio::io::DuplexStream;
 use tokio::time::Duration;
 use tokio_stream::wrappers::UnboundedReceiverStream;
-use warp::Filter as _;
+use warp::{Filter as _, Reply as _};
 
 mod behavior;
 pub use behavior::Behavior;
@@ -44,12 +44,13 @@ pub fn allow_proxy_hosts(
                 hostname: _,
                 cert: _,
                 confirmation_header_name: _,
+                websocket_limits: _,
                 proxy,
             },
     } = domain_config;
     let allow_targets = proxy
-        .configs
         .iter()
+        .flat_map(|proxy| proxy.configs.iter())
         .flat_map(|config| {
             config
                 .shuffled_connection_params("", None, &mut OsRng)
@@ -82,6 +83,7 @@ pub fn error_all_hosts_after(
                 port,
                 cert: _,
                 confirmation_header_name: _,
+                websocket_limits: _,
             },
     } = domain_config;
     let direct_hosts = ip_v4
@@ -89,12 +91,12 @@ pub fn error_all_hosts_after(
         .copied()
         .map(|ip| Host::Ip(ip.into()))
         .chain(ip_v6.iter().copied().map(|ip| Host::Ip(ip.into())))
-        .chain([Host::Domain((*hostname).into())])
+        .chain([Host::Domain(hostname.as_ref().into())])
         .map(|host| FakeTransportTarget { host, port: *port });
 
     let targets = proxy
-        .configs
         .iter()
+        .flat_map(|proxy| proxy.configs.iter())
         .flat_map(|config| {
             config
                 .shuffled_connection_params("", None, &mut OsRng)
@@ -176,12 +178,26 @@ impl FakeDeps {
 pub async fn connect_websockets_on_incoming<S: AsyncDuplexStream + 'static>(
     incoming_streams: impl Stream<Item = (FakeTransportTarget, S)> + Send,
 ) {
-    let filter = warp::any().and(warp::ws()).map(|ws: warp::ws::Ws| {
-        ws.on_upgrade(|_ws| {
-            log::info!("serving websocket");
-            std::future::pending()
-        })
-    });
+    let filter = warp::any()
+        .and(warp::ws())
+        .and(warp::header::optional::<String>("sec-websocket-protocol"))
+        .map(|ws: warp::ws::Ws, offered: Option<String>| {
+            let reply = ws.on_upgrade(|_ws| {
+                log::info!("serving websocket");
+                std::future::pending()
+            });
+            // Accept whichever sub-protocol the client likes best.
+            match offered
+                .as_deref()
+                .and_then(|offered| offered.split(',').next())
+            {
+                Some(selected) => {
+                    warp::reply::with_header(reply, "sec-websocket-protocol", selected.trim())
+                        .into_response()
+                }
+                None => reply.into_response(),
+            }
+        });
     warp::serve(filter)
         .run_incoming(incoming_streams.map(|(host, stream)| {
             log::info!("serving websocket to {host}");
@@ -124,10 +124,16 @@ async fn svr3_integration() {
 
     println!("{}...", "Querying...".cyan());
     let query_result = client.query().await.expect("can query");
-    println!("{}: {}", "Tries remaining".cyan(), query_result);
+    assert!(query_result.present);
+    println!(
+        "{}: {:?}",
+        "Tries remaining".cyan(),
+        query_result.tries_remaining
+    );
 
     println!("{}...", "Removing the secret".cyan());
-    client.remove().await.expect("can remove");
+    let remove_result = client.remove().await.expect("can remove");
+    assert!(remove_result.is_complete(), "{remove_result:?}");
     // The next attempt to restore should fail
     {
         let failed_restore_result = client.restore(PASSWORD, opaque_share_set, &mut rng).await;
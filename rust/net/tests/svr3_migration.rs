@@ -42,8 +42,10 @@ use libsignal_net::infra::TransportConnector;
 use libsignal_net::svr::SvrConnection;
 use libsignal_net::svr3::direct::DirectConnect;
 use libsignal_net::svr3::traits::*;
-use libsignal_net::svr3::{migrate_backup, restore_with_fallback, OpaqueMaskedShareSet};
-use libsignal_svr3::EvaluationResult;
+use libsignal_net::svr3::{
+    migrate_backup, restore_with_fallback, OpaqueMaskedShareSet, RemoveResult,
+};
+use libsignal_svr3::{EvaluationResult, QueryResult};
 use nonzero_ext::nonzero;
 use rand_core::{CryptoRngCore, OsRng};
 
@@ -176,7 +178,7 @@ impl<T: Restore + Sync + Send> Restore for ValidatingClient<T> {
 
 #[async_trait]
 impl<T: Query + Sync + Send> Query for ValidatingClient<T> {
-    async fn query(&self) -> Result<u32, libsignal_net::svr3::Error> {
+    async fn query(&self) -> Result<QueryResult, libsignal_net::svr3::Error> {
         self.query_calls.fetch_add(1, Ordering::Relaxed);
         self.inner.query().await
     }
@@ -184,7 +186,7 @@ impl<T: Query + Sync + Send> Query for ValidatingClient<T> {
 
 #[async_trait]
 impl<T: Remove + Sync + Send> Remove for ValidatingClient<T> {
-    async fn remove(&self) -> Result<(), libsignal_net::svr3::Error> {
+    async fn remove(&self) -> Result<RemoveResult, libsignal_net::svr3::Error> {
         self.remove_calls.fetch_add(1, Ordering::Relaxed);
         self.inner.remove().await
     }
@@ -244,18 +246,18 @@ async fn svr3_single_enclave_migration() {
     log::info!("Checking the current environment pre-migration...");
     assert_matches!(
         current_client.query().await,
-        Err(libsignal_net::svr3::Error::DataMissing)
+        Ok(QueryResult { present: false, .. })
     );
     log::info!("DONE");
 
     log::info!("Checking the previous sgx pre-migration...");
     // Removing client refers to the Sgx part of the "previous" environment.
     // So there should be data there.
-    removing_client
-        .clone()
-        .query()
-        .await
-        .expect("Prev SGX should have data");
+    assert_matches!(
+        removing_client.clone().query().await,
+        Ok(QueryResult { present: true, .. }),
+        "Prev SGX should have data"
+    );
     log::info!("DONE");
 
     log::info!("Migrating...");
@@ -275,12 +277,12 @@ async fn svr3_single_enclave_migration() {
     log::info!("- Data should be gone from the prev sgx");
     assert_matches!(
         removing_client.query().await,
-        Err(libsignal_net::svr3::Error::DataMissing)
+        Ok(QueryResult { present: false, .. })
     );
     log::info!("- Query/restore from prev env should fail with DataMissing");
     assert_matches!(
         prev_client.query().await,
-        Err(libsignal_net::svr3::Error::DataMissing)
+        Ok(QueryResult { present: false, .. })
     );
 
     log::info!("- Can restore from the current env with the right remaining_tries");
@@ -354,7 +356,7 @@ async fn svr3_full_migration() {
     log::info!("Checking the current environment pre-migration...");
     assert_matches!(
         current_client.query().await,
-        Err(libsignal_net::svr3::Error::DataMissing)
+        Ok(QueryResult { present: false, .. })
     );
     log::info!("DONE");
 
@@ -374,7 +376,7 @@ async fn svr3_full_migration() {
     log::info!("- Query/restore from prev env should fail with DataMissing");
     assert_matches!(
         prev_client.query().await,
-        Err(libsignal_net::svr3::Error::DataMissing)
+        Ok(QueryResult { present: false, .. })
     );
 
     log::info!("- Can restore from the current env with the right remaining_tries");
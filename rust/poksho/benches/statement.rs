@@ -0,0 +1,156 @@
+//
+// Copyright 2024 Signal Messenger, LLC.
+// SPDX-License-Identifier: AGPL-3.0-only
+//
+
+//! Proving and verifying statements shaped like zkgroup's credential presentations.
+//!
+//! Run with and without the `small-memory` feature to compare. Criterion only measures time, so
+//! the peak heap use of a single proof is printed before each group of measurements.
+//!
+//! ```sh
+//! cargo bench -p poksho --bench statement
+//! cargo bench -p poksho --bench statement --features small-memory
+//! ```
+
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion};
+use curve25519_dalek::constants::RISTRETTO_BASEPOINT_POINT;
+use curve25519_dalek::ristretto::RistrettoPoint;
+use curve25519_dalek::scalar::Scalar;
+use poksho::{PointArgs, ScalarArgs, Statement};
+use rand::rngs::OsRng;
+use rand::RngCore;
+
+/// Tracks the most memory in use at once since the last [`CountingAllocator::peak_during`].
+struct CountingAllocator {
+    current: AtomicUsize,
+    peak: AtomicUsize,
+}
+
+impl CountingAllocator {
+    /// Returns the peak number of bytes allocated on top of what was in use at the start.
+    fn peak_during<T>(&self, f: impl FnOnce() -> T) -> usize {
+        let start = self.current.load(Ordering::SeqCst);
+        self.peak.store(start, Ordering::SeqCst);
+        let result = f();
+        let peak = self.peak.load(Ordering::SeqCst).saturating_sub(start);
+        drop(result);
+        peak
+    }
+}
+
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        let ptr = System.alloc(layout);
+        if !ptr.is_null() {
+            let current = self.current.fetch_add(layout.size(), Ordering::SeqCst) + layout.size();
+            self.peak.fetch_max(current, Ordering::SeqCst);
+        }
+        ptr
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout);
+        self.current.fetch_sub(layout.size(), Ordering::SeqCst);
+    }
+}
+
+#[global_allocator]
+static ALLOCATOR: CountingAllocator = CountingAllocator {
+    current: AtomicUsize::new(0),
+    peak: AtomicUsize::new(0),
+};
+
+fn random_scalar(rng: &mut OsRng) -> Scalar {
+    let mut bytes = [0u8; 64];
+    rng.fill_bytes(&mut bytes);
+    Scalar::from_bytes_mod_order_wide(&bytes)
+}
+
+/// Builds a single-equation statement `A = a0*P0 + a1*P1 + ...` with `term_count` terms.
+fn wide_statement(term_count: usize) -> (Statement, ScalarArgs, PointArgs) {
+    let mut rng = OsRng;
+    let scalar_names: Vec<String> = (0..term_count).map(|i| format!("a{i}")).collect();
+    let point_names: Vec<String> = (0..term_count).map(|i| format!("P{i}")).collect();
+
+    let mut scalar_args = ScalarArgs::new();
+    let mut point_args = PointArgs::new();
+    let mut lhs = RistrettoPoint::default();
+    for (scalar_name, point_name) in scalar_names.iter().zip(&point_names) {
+        let scalar = random_scalar(&mut rng);
+        let point = random_scalar(&mut rng) * RISTRETTO_BASEPOINT_POINT;
+        lhs += scalar * point;
+        scalar_args.add(scalar_name.clone(), scalar);
+        point_args.add(point_name.clone(), point);
+    }
+    point_args.add("A", lhs);
+
+    let terms: Vec<(&str, &str)> = scalar_names
+        .iter()
+        .zip(&point_names)
+        .map(|(s, p)| (s.as_str(), p.as_str()))
+        .collect();
+    let mut statement = Statement::new();
+    statement.add("A", &terms);
+
+    (statement, scalar_args, point_args)
+}
+
+fn bench_statement(c: &mut Criterion) {
+    let mut randomness = [0u8; 32];
+    OsRng.fill_bytes(&mut randomness);
+    let message = b"message";
+
+    let mut group = c.benchmark_group("statement");
+    for term_count in [2, 8, 32] {
+        let (statement, scalar_args, point_args) = wide_statement(term_count);
+
+        let prove_peak = ALLOCATOR.peak_during(|| {
+            statement
+                .prove(&scalar_args, &point_args, message, &randomness)
+                .expect("valid")
+        });
+        group.bench_with_input(
+            BenchmarkId::new("prove", term_count),
+            &term_count,
+            |b, _| {
+                b.iter(|| {
+                    statement
+                        .prove(&scalar_args, &point_args, message, &randomness)
+                        .expect("valid")
+                })
+            },
+        );
+
+        let proof = statement
+            .prove(&scalar_args, &point_args, message, &randomness)
+            .expect("valid");
+        let verify_peak = ALLOCATOR.peak_during(|| {
+            statement
+                .verify_proof(&proof, &point_args, message)
+                .expect("valid")
+        });
+        eprintln!(
+            "statement/{term_count}: peak heap use {prove_peak} bytes to prove, {verify_peak} bytes to verify"
+        );
+
+        group.bench_with_input(
+            BenchmarkId::new("verify", term_count),
+            &term_count,
+            |b, _| {
+                b.iter(|| {
+                    statement
+                        .verify_proof(&proof, &point_args, message)
+                        .expect("valid")
+                })
+            },
+        );
+    }
+    group.finish();
+}
+
+criterion_group!(benches, bench_statement);
+criterion_main!(benches);
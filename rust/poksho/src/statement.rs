@@ -8,6 +8,7 @@ use std::collections::HashMap;
 use curve25519_dalek::constants::RISTRETTO_BASEPOINT_POINT;
 use curve25519_dalek::ristretto::RistrettoPoint;
 use curve25519_dalek::scalar::Scalar;
+#[cfg(not(feature = "small-memory"))]
 use curve25519_dalek::traits::MultiscalarMul;
 // POKSHO implements the "Sigma protocol for arbitrary linear relations" described in section
 // 19.5.3 of https://crypto.stanford.edu/~dabo/cryptobook/BonehShoup_0_4.pdf
@@ -396,7 +397,19 @@ impl Statement {
 
             // Could use vartime_multiscalar_mul in some cases, but in the
             // general case points might be secret (not just scalars!)
+            #[cfg(not(feature = "small-memory"))]
             g2.push(RistrettoPoint::multiscalar_mul(scalar_iter, point_iter));
+
+            // multiscalar_mul builds a lookup table for every term up front.
+            // Multiplying term by term only needs one table at a time, at the
+            // cost of giving up the shared doublings.
+            #[cfg(feature = "small-memory")]
+            g2.push(
+                scalar_iter
+                    .zip(point_iter)
+                    .map(|(scalar, point)| scalar * point)
+                    .sum(),
+            );
         }
         g2
     }
@@ -97,6 +97,25 @@ pub fn v1(c: &mut Criterion) {
 
     c.bench_function("v1/encrypt", |b| b.iter(&mut encrypt_it));
     c.bench_function("v1/decrypt", |b| b.iter(&mut decrypt_it));
+
+    // Compare validating the sender certificate of a repeated sender with and without a cache.
+    let mut group = c.benchmark_group("v1/decrypt-and-validate");
+    for (name, cache) in [
+        ("uncached", SenderCertificateValidationCache::new(false)),
+        ("cached", SenderCertificateValidationCache::new(true)),
+    ] {
+        group.bench_function(name, |b| {
+            b.iter(|| {
+                let usmc = decrypt_it();
+                assert!(usmc
+                    .sender()
+                    .expect("valid")
+                    .validate_with_cache(&trust_root.public_key, expires, &cache)
+                    .expect("valid"));
+            })
+        });
+    }
+    group.finish();
 }
 
 pub fn v2(c: &mut Criterion) {
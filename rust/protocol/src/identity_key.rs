@@ -10,12 +10,16 @@
 use prost::Message;
 use rand::{CryptoRng, Rng};
 
+use crate::curve::curve25519;
 use crate::{proto, KeyPair, PrivateKey, PublicKey, Result, SignalProtocolError};
 
 // Used for domain separation between alternate-identity signatures and other key-to-key signatures.
 const ALTERNATE_IDENTITY_SIGNATURE_PREFIX_1: &[u8] = &[0xFF; 32];
 const ALTERNATE_IDENTITY_SIGNATURE_PREFIX_2: &[u8] = b"Signal_PNI_Signature";
 
+/// The length of a signature produced by [`create_pni_signature`].
+pub const PNI_SIGNATURE_LENGTH: usize = curve25519::SIGNATURE_LENGTH;
+
 /// A public key that represents the identity of a user.
 ///
 /// Wrapper for [`PublicKey`].
@@ -158,6 +162,38 @@ impl IdentityKeyPair {
     }
 }
 
+/// Generate the signature carried by a `PniSignatureMessage`, claiming that `pni_identity` and
+/// `aci_identity` belong to the same account.
+///
+/// This is the signature the server checks during change-number and phone number privacy flows.
+/// It's equivalent to calling [`IdentityKeyPair::sign_alternate_identity`] on `pni_identity`.
+pub fn create_pni_signature<R: Rng + CryptoRng>(
+    pni_identity: &IdentityKeyPair,
+    aci_identity: &IdentityKey,
+    rng: &mut R,
+) -> Result<Box<[u8]>> {
+    pni_identity.sign_alternate_identity(aci_identity, rng)
+}
+
+/// Verify a signature produced by [`create_pni_signature`].
+///
+/// Unlike [`IdentityKey::verify_alternate_identity`], a signature that isn't
+/// [`PNI_SIGNATURE_LENGTH`] bytes long is an error rather than a failed verification, since it
+/// can't have come from a correct implementation.
+pub fn verify_pni_signature(
+    aci_identity: &IdentityKey,
+    pni_identity: &IdentityKey,
+    signature: &[u8],
+) -> Result<bool> {
+    if signature.len() != PNI_SIGNATURE_LENGTH {
+        return Err(SignalProtocolError::InvalidArgument(format!(
+            "PNI signature must be {PNI_SIGNATURE_LENGTH} bytes, but was {}",
+            signature.len()
+        )));
+    }
+    pni_identity.verify_alternate_identity(aci_identity, signature)
+}
+
 impl TryFrom<&[u8]> for IdentityKeyPair {
     type Error = SignalProtocolError;
 
@@ -197,6 +233,8 @@ impl From<IdentityKeyPair> for KeyPair {
 
 #[cfg(test)]
 mod tests {
+    use assert_matches::assert_matches;
+    use hex_literal::hex;
     use rand::rngs::OsRng;
 
     use super::*;
@@ -261,4 +299,86 @@ mod tests {
 
         Ok(())
     }
+
+    // These values are shared with the Java, Swift, and TypeScript tests, so that every
+    // implementation is checked against the same signature.
+    const PNI_TEST_ACI_PRIVATE: [u8; 32] =
+        hex!("5882e1f3662ee3dd54b5d0b80f7d3f67277ee39a67d47697f78974879cd93266");
+    const PNI_TEST_ACI_PUBLIC: [u8; 33] =
+        hex!("0505c86d71f5ee1af068207fb1b50ba69d60fd70ffa78375eaa9f60f100ca65871");
+    const PNI_TEST_PNI_PRIVATE: [u8; 32] =
+        hex!("1823511d57e3ca8aafbe50a6d0a3f0ed4076eae74c5e0e2351d84eda70dc7d6f");
+    const PNI_TEST_PNI_PUBLIC: [u8; 33] =
+        hex!("0564a82fb9ceae27d3f741f728b3a3bc10a6446e4d702f3184b302a0fb5c93a424");
+    const PNI_TEST_SIGNATURE: [u8; PNI_SIGNATURE_LENGTH] = hex!(
+        "17e3c41803c64aad8effe9a4c516cc1452e1c1a5a1fafea6cd1cc55aa8f869b2"
+        "83f821cfd6eec07b43190acbd62274704c24928da000e322095e6ec690b9db0b"
+    );
+
+    fn pni_test_key_pairs() -> (IdentityKeyPair, IdentityKeyPair) {
+        let aci = IdentityKeyPair::try_from(
+            PrivateKey::deserialize(&PNI_TEST_ACI_PRIVATE).expect("valid"),
+        )
+        .expect("valid");
+        let pni = IdentityKeyPair::try_from(
+            PrivateKey::deserialize(&PNI_TEST_PNI_PRIVATE).expect("valid"),
+        )
+        .expect("valid");
+        assert_eq!(&*aci.identity_key().serialize(), PNI_TEST_ACI_PUBLIC);
+        assert_eq!(&*pni.identity_key().serialize(), PNI_TEST_PNI_PUBLIC);
+        (aci, pni)
+    }
+
+    #[test]
+    fn test_pni_signature_vector() -> Result<()> {
+        let (aci, pni) = pni_test_key_pairs();
+        assert!(verify_pni_signature(
+            aci.identity_key(),
+            pni.identity_key(),
+            &PNI_TEST_SIGNATURE
+        )?);
+        assert!(!verify_pni_signature(
+            pni.identity_key(),
+            aci.identity_key(),
+            &PNI_TEST_SIGNATURE
+        )?);
+
+        let mut modified = PNI_TEST_SIGNATURE;
+        modified[5] ^= 1;
+        assert!(!verify_pni_signature(
+            aci.identity_key(),
+            pni.identity_key(),
+            &modified
+        )?);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_pni_signature_round_trip() -> Result<()> {
+        let (aci, pni) = pni_test_key_pairs();
+        let signature = create_pni_signature(&pni, aci.identity_key(), &mut OsRng)?;
+        assert_eq!(signature.len(), PNI_SIGNATURE_LENGTH);
+        assert!(verify_pni_signature(
+            aci.identity_key(),
+            pni.identity_key(),
+            &signature
+        )?);
+        // The same wire format as the general alternate-identity signature.
+        assert!(pni
+            .identity_key()
+            .verify_alternate_identity(aci.identity_key(), &signature)?);
+        Ok(())
+    }
+
+    #[test]
+    fn test_pni_signature_wrong_length() {
+        let (aci, pni) = pni_test_key_pairs();
+        for len in [0, PNI_SIGNATURE_LENGTH - 1, PNI_SIGNATURE_LENGTH + 1] {
+            assert_matches!(
+                verify_pni_signature(aci.identity_key(), pni.identity_key(), &vec![0; len]),
+                Err(SignalProtocolError::InvalidArgument(_))
+            );
+        }
+    }
 }
@@ -35,6 +35,7 @@ mod proto;
 mod protocol;
 mod ratchet;
 mod sealed_sender;
+mod sender_certificate_cache;
 mod sender_keys;
 mod session;
 mod session_cipher;
@@ -50,7 +51,9 @@ pub use group_cipher::{
     create_sender_key_distribution_message, group_decrypt, group_encrypt,
     process_sender_key_distribution_message,
 };
-pub use identity_key::{IdentityKey, IdentityKeyPair};
+pub use identity_key::{
+    create_pni_signature, verify_pni_signature, IdentityKey, IdentityKeyPair, PNI_SIGNATURE_LENGTH,
+};
 pub use libsignal_core::{
     Aci, DeviceId, Pni, ProtocolAddress, ServiceId, ServiceIdFixedWidthBinaryBytes, ServiceIdKind,
 };
@@ -64,11 +67,13 @@ pub use ratchet::{
     BobSignalProtocolParameters,
 };
 pub use sealed_sender::{
-    sealed_sender_decrypt, sealed_sender_decrypt_to_usmc, sealed_sender_encrypt,
+    sealed_sender_decrypt, sealed_sender_decrypt_to_usmc,
+    sealed_sender_decrypt_with_validation_cache, sealed_sender_encrypt,
     sealed_sender_encrypt_from_usmc, sealed_sender_multi_recipient_encrypt, ContentHint,
     SealedSenderDecryptionResult, SealedSenderV2SentMessage, SealedSenderV2SentMessageRecipient,
     SenderCertificate, ServerCertificate, UnidentifiedSenderMessageContent,
 };
+pub use sender_certificate_cache::SenderCertificateValidationCache;
 pub use sender_keys::SenderKeyRecord;
 pub use session::{process_prekey, process_prekey_bundle};
 pub use session_cipher::{
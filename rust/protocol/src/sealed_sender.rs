@@ -19,9 +19,9 @@ use subtle::ConstantTimeEq;
 use crate::{
     crypto, curve, message_encrypt, proto, session_cipher, Aci, CiphertextMessageType, DeviceId,
     Direction, IdentityKey, IdentityKeyPair, IdentityKeyStore, KeyPair, KyberPreKeyStore,
-    PreKeySignalMessage, PreKeyStore, PrivateKey, ProtocolAddress, PublicKey, Result, ServiceId,
-    ServiceIdFixedWidthBinaryBytes, SessionRecord, SessionStore, SignalMessage,
-    SignalProtocolError, SignedPreKeyStore, Timestamp,
+    PreKeySignalMessage, PreKeyStore, PrivateKey, ProtocolAddress, PublicKey, Result,
+    SenderCertificateValidationCache, ServiceId, ServiceIdFixedWidthBinaryBytes, SessionRecord,
+    SessionStore, SignalMessage, SignalProtocolError, SignedPreKeyStore, Timestamp,
 };
 
 #[derive(Debug, Clone)]
@@ -263,6 +263,26 @@ impl SenderCertificate {
     }
 
     pub fn validate(&self, trust_root: &PublicKey, validation_time: Timestamp) -> Result<bool> {
+        Ok(self.validate_signatures(trust_root)? && self.validate_expiration(validation_time))
+    }
+
+    /// Like [`validate`](Self::validate), but reuses the result of checking the certificate's
+    /// signatures if `cache` has seen this certificate before.
+    ///
+    /// Expiration is always checked against `validation_time`, even on a cache hit.
+    pub fn validate_with_cache(
+        &self,
+        trust_root: &PublicKey,
+        validation_time: Timestamp,
+        cache: &SenderCertificateValidationCache,
+    ) -> Result<bool> {
+        Ok(cache.validate_signatures(self, trust_root)?
+            && self.validate_expiration(validation_time))
+    }
+
+    /// Checks the server certificate against `trust_root`, and this certificate against the
+    /// server certificate.
+    pub(crate) fn validate_signatures(&self, trust_root: &PublicKey) -> Result<bool> {
         if !self.signer.validate(trust_root)? {
             log::error!(
                 "sender certificate contained server certificate that wasn't signed by trust root"
@@ -279,16 +299,20 @@ impl SenderCertificate {
             return Ok(false);
         }
 
+        Ok(true)
+    }
+
+    fn validate_expiration(&self, validation_time: Timestamp) -> bool {
         if validation_time > self.expiration {
             log::error!(
                 "sender certificate is expired (expiration: {}, validation_time: {})",
                 self.expiration.epoch_millis(),
                 validation_time.epoch_millis()
             );
-            return Ok(false);
+            return false;
         }
 
-        Ok(true)
+        true
     }
 
     pub fn signer(&self) -> Result<&ServerCertificate> {
@@ -1919,10 +1943,47 @@ pub async fn sealed_sender_decrypt(
     pre_key_store: &mut dyn PreKeyStore,
     signed_pre_key_store: &dyn SignedPreKeyStore,
     kyber_pre_key_store: &mut dyn KyberPreKeyStore,
+) -> Result<SealedSenderDecryptionResult> {
+    sealed_sender_decrypt_with_validation_cache(
+        ciphertext,
+        trust_root,
+        timestamp,
+        &SenderCertificateValidationCache::new(false),
+        local_e164,
+        local_uuid,
+        local_device_id,
+        identity_store,
+        session_store,
+        pre_key_store,
+        signed_pre_key_store,
+        kyber_pre_key_store,
+    )
+    .await
+}
+
+/// Like [`sealed_sender_decrypt`], but uses `validation_cache` to avoid re-checking the signatures
+/// on sender certificates that have been seen before.
+#[allow(clippy::too_many_arguments)]
+pub async fn sealed_sender_decrypt_with_validation_cache(
+    ciphertext: &[u8],
+    trust_root: &PublicKey,
+    timestamp: Timestamp,
+    validation_cache: &SenderCertificateValidationCache,
+    local_e164: Option<String>,
+    local_uuid: String,
+    local_device_id: DeviceId,
+    identity_store: &mut dyn IdentityKeyStore,
+    session_store: &mut dyn SessionStore,
+    pre_key_store: &mut dyn PreKeyStore,
+    signed_pre_key_store: &dyn SignedPreKeyStore,
+    kyber_pre_key_store: &mut dyn KyberPreKeyStore,
 ) -> Result<SealedSenderDecryptionResult> {
     let usmc = sealed_sender_decrypt_to_usmc(ciphertext, identity_store).await?;
 
-    if !usmc.sender()?.validate(trust_root, timestamp)? {
+    if !usmc
+        .sender()?
+        .validate_with_cache(trust_root, timestamp, validation_cache)?
+    {
         return Err(SignalProtocolError::InvalidSealedSenderMessage(
             "trust root validation failed".to_string(),
         ));
@@ -0,0 +1,112 @@
+//
+// Copyright 2024 Signal Messenger, LLC.
+// SPDX-License-Identifier: AGPL-3.0-only
+//
+
+use std::num::NonZeroUsize;
+use std::sync::Mutex;
+
+use indexmap::IndexMap;
+use sha2::{Digest, Sha256};
+
+use crate::{PublicKey, Result, SenderCertificate, Timestamp};
+
+/// A bounded cache of sender certificate signature checks.
+///
+/// Every sealed sender message carries the sender's certificate, so a busy conversation means
+/// checking the same pair of signatures over and over. This cache remembers the outcome of those
+/// checks for the most recently seen certificates, evicting the least recently used entry once it
+/// is full.
+///
+/// Only the signature checks are cached. Expiration depends on the current time and is always
+/// checked by [`SenderCertificate::validate_with_cache`].
+pub struct SenderCertificateValidationCache {
+    entries: Option<Mutex<IndexMap<CacheKey, bool>>>,
+    capacity: usize,
+}
+
+#[derive(PartialEq, Eq, Hash)]
+struct CacheKey {
+    /// A digest of the trust root and the serialized certificate.
+    digest: [u8; 32],
+    expiration: Timestamp,
+}
+
+impl SenderCertificateValidationCache {
+    pub const DEFAULT_CAPACITY: NonZeroUsize = match NonZeroUsize::new(256) {
+        Some(capacity) => capacity,
+        None => unreachable!(),
+    };
+
+    /// Creates a cache with [the default capacity](Self::DEFAULT_CAPACITY).
+    ///
+    /// If `enabled` is false, nothing is cached and every certificate is checked in full, which
+    /// is useful for tests.
+    pub fn new(enabled: bool) -> Self {
+        if enabled {
+            Self::with_capacity(Self::DEFAULT_CAPACITY)
+        } else {
+            Self {
+                entries: None,
+                capacity: 0,
+            }
+        }
+    }
+
+    pub fn with_capacity(capacity: NonZeroUsize) -> Self {
+        Self {
+            entries: Some(Mutex::new(IndexMap::with_capacity(capacity.get()))),
+            capacity: capacity.get(),
+        }
+    }
+
+    /// The number of certificates currently cached.
+    pub fn len(&self) -> usize {
+        self.entries
+            .as_ref()
+            .map_or(0, |entries| entries.lock().expect("not poisoned").len())
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub(crate) fn validate_signatures(
+        &self,
+        certificate: &SenderCertificate,
+        trust_root: &PublicKey,
+    ) -> Result<bool> {
+        let Some(entries) = &self.entries else {
+            return certificate.validate_signatures(trust_root);
+        };
+
+        let key = CacheKey {
+            digest: Sha256::new()
+                .chain_update(trust_root.serialize())
+                .chain_update(certificate.serialized()?)
+                .finalize()
+                .into(),
+            expiration: certificate.expiration()?,
+        };
+
+        {
+            let mut entries = entries.lock().expect("not poisoned");
+            if let Some(valid) = entries.shift_remove(&key) {
+                // Re-insert to mark the entry as most recently used.
+                entries.insert(key, valid);
+                return Ok(valid);
+            }
+        }
+
+        // Check outside the lock; if another thread checks the same certificate concurrently,
+        // they'll reach the same answer.
+        let valid = certificate.validate_signatures(trust_root)?;
+
+        let mut entries = entries.lock().expect("not poisoned");
+        entries.insert(key, valid);
+        if entries.len() > self.capacity {
+            entries.shift_remove_index(0);
+        }
+        Ok(valid)
+    }
+}
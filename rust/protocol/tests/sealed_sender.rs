@@ -132,6 +132,106 @@ fn test_sender_cert() -> Result<(), SignalProtocolError> {
     Ok(())
 }
 
+fn make_sender_cert(
+    trust_root: &KeyPair,
+    expires: Timestamp,
+    rng: &mut OsRng,
+) -> Result<SenderCertificate, SignalProtocolError> {
+    let server_key = KeyPair::generate(rng);
+    let key = KeyPair::generate(rng);
+
+    let server_cert =
+        ServerCertificate::new(1, server_key.public_key, &trust_root.private_key, rng)?;
+
+    SenderCertificate::new(
+        "9d0652a3-dcc3-4d11-975f-74d61598733f".to_string(),
+        Some("+14152222222".to_string()),
+        key.public_key,
+        42.into(),
+        expires,
+        server_cert,
+        &server_key.private_key,
+        rng,
+    )
+}
+
+#[test]
+fn test_sender_cert_cache_checks_expiration_on_hit() -> Result<(), SignalProtocolError> {
+    let mut rng = OsRng;
+    let trust_root = KeyPair::generate(&mut rng);
+    let expires = Timestamp::from_epoch_millis(1605722925);
+    let sender_cert = make_sender_cert(&trust_root, expires, &mut rng)?;
+
+    let cache = SenderCertificateValidationCache::new(true);
+    assert!(sender_cert.validate_with_cache(&trust_root.public_key, expires, &cache)?);
+    assert_eq!(cache.len(), 1);
+
+    // The certificate has expired since it was cached.
+    assert!(!sender_cert.validate_with_cache(
+        &trust_root.public_key,
+        expires.add_millis(1),
+        &cache
+    )?);
+    assert_eq!(cache.len(), 1);
+
+    // A cached entry doesn't outlive expiration even when it is inserted by an expired check.
+    let cache = SenderCertificateValidationCache::new(true);
+    assert!(!sender_cert.validate_with_cache(
+        &trust_root.public_key,
+        expires.add_millis(1),
+        &cache
+    )?);
+    assert!(sender_cert.validate_with_cache(&trust_root.public_key, expires, &cache)?);
+    assert!(!sender_cert.validate_with_cache(
+        &trust_root.public_key,
+        expires.add_millis(1),
+        &cache
+    )?);
+
+    Ok(())
+}
+
+#[test]
+fn test_sender_cert_cache_is_keyed_by_trust_root() -> Result<(), SignalProtocolError> {
+    let mut rng = OsRng;
+    let trust_root = KeyPair::generate(&mut rng);
+    let other_trust_root = KeyPair::generate(&mut rng);
+    let expires = Timestamp::from_epoch_millis(1605722925);
+    let sender_cert = make_sender_cert(&trust_root, expires, &mut rng)?;
+
+    let cache = SenderCertificateValidationCache::new(true);
+    assert!(sender_cert.validate_with_cache(&trust_root.public_key, expires, &cache)?);
+    assert!(!sender_cert.validate_with_cache(&other_trust_root.public_key, expires, &cache)?);
+    assert_eq!(cache.len(), 2);
+
+    Ok(())
+}
+
+#[test]
+fn test_sender_cert_cache_is_bounded() -> Result<(), SignalProtocolError> {
+    let mut rng = OsRng;
+    let trust_root = KeyPair::generate(&mut rng);
+    let expires = Timestamp::from_epoch_millis(1605722925);
+    let certs = (0..3)
+        .map(|_| make_sender_cert(&trust_root, expires, &mut rng))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let cache = SenderCertificateValidationCache::with_capacity(2.try_into().expect("non-zero"));
+    for cert in &certs {
+        assert!(cert.validate_with_cache(&trust_root.public_key, expires, &cache)?);
+        assert!(cache.len() <= 2);
+    }
+    assert_eq!(cache.len(), 2);
+
+    let disabled = SenderCertificateValidationCache::new(false);
+    for cert in &certs {
+        assert!(cert.validate_with_cache(&trust_root.public_key, expires, &disabled)?);
+    }
+    assert!(disabled.is_empty());
+
+    Ok(())
+}
+
 #[test]
 fn test_sealed_sender() -> Result<(), SignalProtocolError> {
     async {
@@ -21,6 +21,8 @@ mod errors;
 pub use errors::{Error, ErrorStatus};
 mod proto;
 pub use proto::svr4::response4::Status as V4Status;
+#[cfg(any(test, feature = "test-util"))]
+pub mod testutil;
 
 const SECRET_BYTES: usize = 32;
 
@@ -61,23 +63,69 @@ impl Query4 {
         )
     }
 
-    pub fn finalize(responses: &[Vec<u8>]) -> Result<u32, Error> {
+    pub fn finalize(responses: &[Vec<u8>]) -> Result<QueryResult, Error> {
         assert!(!responses.is_empty());
-        responses
+        let shares = responses
             .iter()
             .map(|b| svr4::Response4::decode(b.as_ref()).map_err(|_| Error::BadData))
             .map(|rr| match rr?.inner {
                 Some(svr4::response4::Inner::Query(r)) => match status_error(r.status) {
-                    Ok(()) => Ok(r.tries_remaining),
+                    Ok(()) => Ok(Some(r.tries_remaining)),
+                    Err(Error::BadResponseStatus4(svr4::response4::Status::Missing)) => Ok(None),
                     Err(e) => Err(e),
                 },
                 _ => Err(Error::BadData),
             })
-            // Get the min tries_remaining while short circuiting on errors.
-            // Should never actually return u32::MAX, since there should be at least one
-            // response, which will either be an error (returning an error overall)
-            // or a value less than MAX.
-            .try_fold(u32::MAX, |acc, tr| Ok(std::cmp::min(acc, tr?)))
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(QueryResult::from_shares(&shares))
+    }
+}
+
+/// The state of a backup as reported by a `Query4` to each server.
+#[derive(Debug, PartialEq, Eq)]
+pub struct QueryResult {
+    /// The minimum number of tries remaining across the servers that have a share.
+    ///
+    /// `None` if no server has a share.
+    pub tries_remaining: Option<u32>,
+    /// Whether every server has a share, that is, whether the backup can be restored.
+    pub present: bool,
+    /// Whether some servers have a share and others don't.
+    ///
+    /// This can happen if a backup or removal did not complete on all servers.
+    pub degraded: bool,
+}
+
+impl QueryResult {
+    const PRESENT_FLAG: u8 = 0x1;
+    const DEGRADED_FLAG: u8 = 0x2;
+
+    /// Aggregates per-server results, where `None` means the server has no share.
+    fn from_shares(shares: &[Option<u32>]) -> Self {
+        let tries_remaining = shares.iter().flatten().copied().min();
+        let present_count = shares.iter().flatten().count();
+        Self {
+            tries_remaining,
+            present: present_count == shares.len(),
+            degraded: present_count != 0 && present_count != shares.len(),
+        }
+    }
+
+    /// Serializes as a flags byte followed by the big-endian tries remaining, if any.
+    pub fn serialize(&self) -> Vec<u8> {
+        let mut flags = 0;
+        if self.present {
+            flags |= Self::PRESENT_FLAG;
+        }
+        if self.degraded {
+            flags |= Self::DEGRADED_FLAG;
+        }
+        let mut bytes = Vec::with_capacity(1 + std::mem::size_of::<u32>());
+        bytes.push(flags);
+        if let Some(tries_remaining) = self.tries_remaining {
+            bytes.extend_from_slice(&tries_remaining.to_be_bytes());
+        }
+        bytes
     }
 }
 
@@ -312,6 +360,29 @@ impl Output4 {
         dec.verify_tag(tag).map_err(|_| Error::BadData)?;
         Ok(secret)
     }
+
+    /// Prepares to protect the secret in `masked_secret` with `new_password` instead.
+    ///
+    /// `self` must be the output of restoring `masked_secret` with the old password. The enclaves
+    /// have no way to replace just the auth commitments derived from the password, so the returned
+    /// requests create the backup again from scratch, resetting the number of tries to
+    /// `max_tries`. The secret itself is only unmasked and masked again locally.
+    pub fn change_password<R: CryptoRngCore>(
+        &self,
+        masked_secret: &MaskedSecret,
+        new_password: &[u8],
+        max_tries: NonZeroU32,
+        rng: &mut R,
+    ) -> Result<Backup4, Error> {
+        let secret = self.unmask_secret(&masked_secret.masked_secret)?;
+        Backup4::new(
+            &masked_secret.server_ids,
+            new_password,
+            &secret,
+            max_tries,
+            rng,
+        )
+    }
 }
 
 pub struct Restore1<'a> {
@@ -386,6 +457,16 @@ impl<'a> Restore1<'a> {
                 _ => Err(Error::BadResponse),
             })
             .collect::<Result<Vec<_>, _>>()?;
+        // If any server has lost its share, the backup can't be restored no matter the password.
+        // Check this first: the servers that still have a share report their tries remaining,
+        // which would otherwise turn this into RestoreFailed, and tell the user that their
+        // password was wrong when trying another one can't help.
+        if responses1
+            .iter()
+            .any(|r| r.status() == svr4::response4::Status::Missing)
+        {
+            return Err(Error::BadResponseStatus4(svr4::response4::Status::Missing));
+        }
         let tries_remaining = responses1
             .iter()
             .filter(|rr| {
@@ -874,6 +955,7 @@ impl<'a> RotationMachine<'a> {
 
 #[cfg(test)]
 mod test {
+    use assert_matches::assert_matches;
     use curve25519_dalek::scalar::Scalar;
     use hex_literal::hex;
     use nonzero_ext::nonzero;
@@ -882,10 +964,7 @@ mod test {
     use test_case::test_case;
 
     use super::*;
-
-    fn to_ristretto_scalar(b: &[u8]) -> Option<Scalar> {
-        Scalar::from_canonical_bytes(b.try_into().ok()?).into_option()
-    }
+    use crate::testutil::TestServer;
 
     #[test]
     fn scalars_summing_to_works() {
@@ -929,152 +1008,6 @@ mod test {
         );
     }
 
-    /// TestServer implements the server-side for a single-user interaction.
-    struct TestServer {
-        tries: u32,
-        versions: BTreeMap<u32, TestServerVersion>,
-        restore1_blinded: Vec<u8>,
-    }
-
-    /// TestServerVersion holds a single version's data for a single user.
-    struct TestServerVersion {
-        auth_commitment: RistrettoPoint,
-        oprf_secretshare: Scalar,
-        encryption_secretshare: [u8; 32],
-        zero_secretshare: Scalar,
-    }
-
-    impl TestServerVersion {
-        fn new(req: &svr4::request4::Create) -> Self {
-            Self {
-                auth_commitment: to_ristretto_pt(&req.auth_commitment)
-                    .expect("decode auth_commitment"),
-                oprf_secretshare: to_ristretto_scalar(&req.oprf_secretshare)
-                    .expect("decode oprf_secretshare"),
-                zero_secretshare: to_ristretto_scalar(&req.zero_secretshare)
-                    .expect("decode zero_secretshare"),
-                encryption_secretshare: req
-                    .encryption_secretshare
-                    .as_slice()
-                    .try_into()
-                    .expect("decode encryption_secretshare"),
-            }
-        }
-    }
-
-    impl TestServer {
-        fn new() -> Self {
-            Self {
-                tries: 0,
-                versions: BTreeMap::new(),
-                restore1_blinded: vec![],
-            }
-        }
-
-        /// Take in create request, return success/failure
-        fn create(&mut self, req_bytes: &[u8]) {
-            self.versions.clear();
-            let req = match svr4::Request4::decode(req_bytes)
-                .expect("decode Request4")
-                .inner
-            {
-                Some(svr4::request4::Inner::Create(r)) => r,
-                _ => {
-                    panic!("not Create");
-                }
-            };
-            self.versions
-                .insert(req.version, TestServerVersion::new(&req));
-            self.tries = req.max_tries;
-        }
-
-        /// Return a constant "hash" of a single user ID.
-        fn hashed_user_id(&self) -> [u8; 64] {
-            [1u8; 64] // SHA512(user_id)
-        }
-
-        /// Take in restore1 request, return restore1 response
-        fn restore1(&mut self, req_bytes: &[u8]) -> Vec<u8> {
-            let req = match svr4::Request4::decode(req_bytes)
-                .expect("decode Request4")
-                .inner
-            {
-                Some(svr4::request4::Inner::Restore1(r)) => r,
-                _ => {
-                    panic!("not Restore1");
-                }
-            };
-            assert!(self.tries > 0);
-            self.tries -= 1;
-            self.restore1_blinded = req.blinded.to_vec();
-
-            let userhash_pt = RistrettoPoint::from_uniform_bytes(&self.hashed_user_id());
-            let blinded = to_ristretto_pt(&req.blinded).expect("decode blinded");
-
-            let auths = self
-                .versions
-                .iter()
-                .map(|(version, state)| svr4::response4::restore1::Auth {
-                    version: *version,
-                    element: (blinded * state.oprf_secretshare
-                        + userhash_pt * state.zero_secretshare)
-                        .compress()
-                        .to_bytes()
-                        .to_vec(),
-                })
-                .collect::<Vec<_>>();
-
-            svr4::Response4 {
-                inner: Some(svr4::response4::Inner::Restore1(
-                    svr4::response4::Restore1 {
-                        status: svr4::response4::Status::Ok.into(),
-                        tries_remaining: self.tries,
-                        auth: auths,
-                    },
-                )),
-            }
-            .encode_to_vec()
-        }
-
-        /// Take in restore2 request, return restore2 response
-        fn restore2(&self, req_bytes: &[u8], handshake_hash: &[u8]) -> Vec<u8> {
-            let req = match svr4::Request4::decode(req_bytes)
-                .expect("decode Request4")
-                .inner
-            {
-                Some(svr4::request4::Inner::Restore2(r)) => r,
-                _ => {
-                    panic!("not Restore2");
-                }
-            };
-            let state = self.versions.get(&req.version).expect("version not set");
-            let auth_scalar = to_ristretto_scalar(&req.auth_scalar).expect("decode auth_scalar");
-            let auth_point = to_ristretto_pt(&req.auth_point).expect("decode auth_pt");
-
-            let scalar_hash_bytes: Vec<u8> = [
-                &req.auth_point as &[_],
-                &self.restore1_blinded,
-                handshake_hash,
-            ]
-            .concat();
-            let scalar_hash = Scalar::hash_from_bytes::<Sha512>(&scalar_hash_bytes);
-            let lhs = RISTRETTO_BASEPOINT_TABLE * &auth_scalar;
-            let rhs = state.auth_commitment * scalar_hash + auth_point;
-
-            assert_eq!(lhs, rhs);
-
-            svr4::Response4 {
-                inner: Some(svr4::response4::Inner::Restore2(
-                    svr4::response4::Restore2 {
-                        status: svr4::response4::Status::Ok.into(),
-                        encryption_secretshare: state.encryption_secretshare.to_vec(),
-                    },
-                )),
-            }
-            .encode_to_vec()
-        }
-    }
-
     #[test]
     fn full_create_restore() {
         let mut rng = OsRng;
@@ -1124,6 +1057,288 @@ mod test {
         );
     }
 
+    /// Runs both rounds of a restore against `servers`.
+    fn restore_from_test_servers(
+        server_ids: &[u64],
+        servers: &mut [TestServer],
+        password: &[u8],
+    ) -> Result<Output4, Error> {
+        let mut rng = OsRng;
+        let handshake_hashes = [&[1u8; 32][..], &[2u8; 32][..], &[3u8; 32][..]];
+        let restore1 = Restore1::new(server_ids, password, &mut rng);
+        let restore1_responses = servers
+            .iter_mut()
+            .zip(&restore1.requests)
+            .map(|(server, req)| server.restore1(req))
+            .collect::<Vec<_>>();
+        let restore2 = restore1.restore2(&restore1_responses, &handshake_hashes, &mut rng)?;
+        let restore2_responses = servers
+            .iter_mut()
+            .zip(&restore2.requests)
+            .zip(&handshake_hashes)
+            .map(|((server, req), hh)| server.restore2(req, hh))
+            .collect::<Vec<_>>();
+        restore2.restore(&restore2_responses)
+    }
+
+    #[test]
+    fn change_password() {
+        let mut rng = OsRng;
+        let server_ids = vec![1u64, 2u64, 3u64];
+        let mut servers = server_ids
+            .iter()
+            .map(|_| TestServer::new())
+            .collect::<Vec<_>>();
+        let secret = [3u8; 32];
+        let backup = Backup4::new(&server_ids, b"old", &secret, nonzero!(10u32), &mut rng)
+            .expect("create Backup4");
+        for (server, req) in servers.iter_mut().zip(backup.requests) {
+            server.create(&req);
+        }
+
+        let output = restore_from_test_servers(&server_ids, &mut servers, b"old")
+            .expect("can restore with old password");
+        let changed = output
+            .change_password(&backup.masked_secret, b"new", nonzero!(10u32), &mut rng)
+            .expect("can change password");
+        for (server, req) in servers.iter_mut().zip(changed.requests) {
+            server.create(&req);
+        }
+
+        let output = restore_from_test_servers(&server_ids, &mut servers, b"new")
+            .expect("can restore with new password");
+        assert_eq!(
+            output
+                .unmask_secret(&changed.masked_secret.masked_secret)
+                .expect("unmask"),
+            secret
+        );
+        assert_matches!(
+            restore_from_test_servers(&server_ids, &mut servers, b"old"),
+            Err(Error::RestoreFailed(_))
+        );
+    }
+
+    #[test]
+    fn change_password_with_wrong_share_set() {
+        let mut rng = OsRng;
+        let server_ids = vec![1u64, 2u64, 3u64];
+        let mut servers = server_ids
+            .iter()
+            .map(|_| TestServer::new())
+            .collect::<Vec<_>>();
+        let backup = Backup4::new(&server_ids, b"old", &[3u8; 32], nonzero!(10u32), &mut rng)
+            .expect("create Backup4");
+        for (server, req) in servers.iter_mut().zip(backup.requests) {
+            server.create(&req);
+        }
+        let other_backup = Backup4::new(&server_ids, b"old", &[3u8; 32], nonzero!(10u32), &mut rng)
+            .expect("create Backup4");
+
+        let output = restore_from_test_servers(&server_ids, &mut servers, b"old")
+            .expect("can restore with old password");
+        assert_matches!(
+            output.change_password(
+                &other_backup.masked_secret,
+                b"new",
+                nonzero!(10u32),
+                &mut rng
+            ),
+            Err(Error::BadData)
+        );
+    }
+
+    #[test]
+    fn restore_with_missing_share() {
+        let mut rng = OsRng;
+        let server_ids = vec![1u64, 2u64, 3u64];
+        let mut servers = server_ids
+            .iter()
+            .map(|_| TestServer::new())
+            .collect::<Vec<_>>();
+        let backup = Backup4::new(
+            &server_ids,
+            b"password",
+            &[3u8; 32],
+            nonzero!(10u32),
+            &mut rng,
+        )
+        .expect("create Backup4");
+        for (server, req) in servers.iter_mut().zip(backup.requests).skip(1) {
+            server.create(&req);
+        }
+
+        assert_matches!(
+            restore_from_test_servers(&server_ids, &mut servers, b"password"),
+            Err(Error::BadResponseStatus4(svr4::response4::Status::Missing))
+        );
+    }
+
+    fn restore1_response(status: V4Status) -> Vec<u8> {
+        svr4::Response4 {
+            inner: Some(svr4::response4::Inner::Restore1(
+                svr4::response4::Restore1 {
+                    status: status.into(),
+                    tries_remaining: 9,
+                    auth: vec![],
+                },
+            )),
+        }
+        .encode_to_vec()
+    }
+
+    #[test_case([V4Status::Missing; 3] => Error::BadResponseStatus4(V4Status::Missing); "all missing")]
+    #[test_case(
+        [V4Status::Ok, V4Status::Missing, V4Status::Ok]
+        => Error::BadResponseStatus4(V4Status::Missing);
+        "one missing"
+    )]
+    #[test_case(
+        [V4Status::Error, V4Status::Missing, V4Status::Ok]
+        => Error::BadResponseStatus4(V4Status::Missing);
+        "one missing, one wrong password"
+    )]
+    #[test_case(
+        [V4Status::Ok, V4Status::Error, V4Status::Ok] => Error::RestoreFailed(9);
+        "wrong password"
+    )]
+    fn restore2_status_errors(statuses: [V4Status; 3]) -> Error {
+        let mut rng = OsRng;
+        let server_ids = [1u64, 2u64, 3u64];
+        let handshake_hashes = [&[1u8; 32][..], &[2u8; 32][..], &[3u8; 32][..]];
+        let responses = statuses.map(restore1_response);
+        let restore1 = Restore1::new(&server_ids, b"password", &mut rng);
+        match restore1.restore2(&responses, &handshake_hashes, &mut rng) {
+            Ok(_) => panic!("should fail"),
+            Err(e) => e,
+        }
+    }
+
+    /// Creates a backup on `present` servers, and none on the rest.
+    fn query_servers(present: [bool; 3]) -> Vec<TestServer> {
+        let mut rng = OsRng;
+        let server_ids = [1u64, 2u64, 3u64];
+        let backup = Backup4::new(
+            &server_ids,
+            b"password",
+            &[3u8; 32],
+            nonzero!(10u32),
+            &mut rng,
+        )
+        .expect("create Backup4");
+        present
+            .into_iter()
+            .zip(backup.requests)
+            .map(|(present, req)| {
+                let mut server = TestServer::new();
+                if present {
+                    server.create(&req);
+                }
+                server
+            })
+            .collect()
+    }
+
+    fn query(servers: &[TestServer]) -> Result<QueryResult, Error> {
+        let responses = servers
+            .iter()
+            .zip(Query4::requests())
+            .map(|(server, req)| server.query(&req))
+            .collect::<Vec<_>>();
+        Query4::finalize(&responses)
+    }
+
+    #[test]
+    fn query_present() {
+        let mut servers = query_servers([true; 3]);
+        servers[1].tries = 7;
+        assert_eq!(
+            query(&servers),
+            Ok(QueryResult {
+                tries_remaining: Some(7),
+                present: true,
+                degraded: false,
+            })
+        );
+    }
+
+    #[test]
+    fn query_absent() {
+        let servers = query_servers([false; 3]);
+        assert_eq!(
+            query(&servers),
+            Ok(QueryResult {
+                tries_remaining: None,
+                present: false,
+                degraded: false,
+            })
+        );
+    }
+
+    #[test_case([true, true, false]; "last missing")]
+    #[test_case([false, true, true]; "first missing")]
+    #[test_case([false, true, false]; "only one present")]
+    fn query_inconsistent(present: [bool; 3]) {
+        let mut servers = query_servers(present);
+        for (tries, server) in (4..).zip(&mut servers) {
+            if !server.versions.is_empty() {
+                server.tries = tries;
+            }
+        }
+        let expected_tries = (4..).zip(present).find(|(_, p)| *p).map(|(t, _)| t);
+        assert_eq!(
+            query(&servers),
+            Ok(QueryResult {
+                tries_remaining: expected_tries,
+                present: false,
+                degraded: true,
+            })
+        );
+    }
+
+    #[test]
+    fn query_error_status() {
+        let servers = query_servers([true; 3]);
+        let mut responses = servers
+            .iter()
+            .zip(Query4::requests())
+            .map(|(server, req)| server.query(&req))
+            .collect::<Vec<_>>();
+        responses[2] = svr4::Response4 {
+            inner: Some(svr4::response4::Inner::Query(svr4::response4::Query {
+                status: svr4::response4::Status::Error.into(),
+                ..Default::default()
+            })),
+        }
+        .encode_to_vec();
+        assert_eq!(
+            Query4::finalize(&responses),
+            Err(Error::BadResponseStatus4(svr4::response4::Status::Error))
+        );
+    }
+
+    #[test]
+    fn query_result_serialization() {
+        let result = QueryResult {
+            tries_remaining: Some(0x0102_0304),
+            present: true,
+            degraded: false,
+        };
+        assert_eq!(result.serialize(), [0x1, 0x1, 0x2, 0x3, 0x4]);
+        let result = QueryResult {
+            tries_remaining: None,
+            present: false,
+            degraded: false,
+        };
+        assert_eq!(result.serialize(), [0x0]);
+        let result = QueryResult {
+            tries_remaining: Some(1),
+            present: false,
+            degraded: true,
+        };
+        assert_eq!(result.serialize(), [0x2, 0, 0, 0, 1]);
+    }
+
     /// Deterministic RNG for testing
     struct IncrementingRng {
         v: u64,
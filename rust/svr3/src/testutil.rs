@@ -0,0 +1,229 @@
+//
+// Copyright 2024 Signal Messenger, LLC.
+// SPDX-License-Identifier: AGPL-3.0-only
+//
+
+//! A fake SVR3 server, for testing clients without a real enclave.
+
+use std::collections::BTreeMap;
+
+use curve25519_dalek::constants::RISTRETTO_BASEPOINT_TABLE;
+use curve25519_dalek::ristretto::RistrettoPoint;
+use curve25519_dalek::scalar::Scalar;
+use prost::Message;
+use sha2::Sha512;
+
+use crate::proto::svr4;
+use crate::to_ristretto_pt;
+
+fn to_ristretto_scalar(b: &[u8]) -> Option<Scalar> {
+    Scalar::from_canonical_bytes(b.try_into().ok()?).into_option()
+}
+
+/// TestServer implements the server-side for a single-user interaction.
+pub struct TestServer {
+    pub(crate) tries: u32,
+    pub(crate) versions: BTreeMap<u32, TestServerVersion>,
+    restore1_blinded: Vec<u8>,
+}
+
+/// TestServerVersion holds a single version's data for a single user.
+pub(crate) struct TestServerVersion {
+    auth_commitment: RistrettoPoint,
+    oprf_secretshare: Scalar,
+    encryption_secretshare: [u8; 32],
+    zero_secretshare: Scalar,
+}
+
+impl TestServerVersion {
+    fn new(req: &svr4::request4::Create) -> Self {
+        Self {
+            auth_commitment: to_ristretto_pt(&req.auth_commitment).expect("decode auth_commitment"),
+            oprf_secretshare: to_ristretto_scalar(&req.oprf_secretshare)
+                .expect("decode oprf_secretshare"),
+            zero_secretshare: to_ristretto_scalar(&req.zero_secretshare)
+                .expect("decode zero_secretshare"),
+            encryption_secretshare: req
+                .encryption_secretshare
+                .as_slice()
+                .try_into()
+                .expect("decode encryption_secretshare"),
+        }
+    }
+}
+
+impl Default for TestServer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl TestServer {
+    pub fn new() -> Self {
+        Self {
+            tries: 0,
+            versions: BTreeMap::new(),
+            restore1_blinded: vec![],
+        }
+    }
+
+    /// Take in create request, return create response
+    pub fn create(&mut self, req_bytes: &[u8]) -> Vec<u8> {
+        self.versions.clear();
+        let req = match svr4::Request4::decode(req_bytes)
+            .expect("decode Request4")
+            .inner
+        {
+            Some(svr4::request4::Inner::Create(r)) => r,
+            _ => {
+                panic!("not Create");
+            }
+        };
+        self.versions
+            .insert(req.version, TestServerVersion::new(&req));
+        self.tries = req.max_tries;
+        svr4::Response4 {
+            inner: Some(svr4::response4::Inner::Create(svr4::response4::Create {
+                status: svr4::response4::Status::Ok.into(),
+                tries_remaining: self.tries,
+            })),
+        }
+        .encode_to_vec()
+    }
+
+    /// Take in query request, return query response
+    pub fn query(&self, req_bytes: &[u8]) -> Vec<u8> {
+        let req = svr4::Request4::decode(req_bytes).expect("decode Request4");
+        assert!(matches!(req.inner, Some(svr4::request4::Inner::Query(_))));
+        let status = if self.versions.is_empty() {
+            svr4::response4::Status::Missing
+        } else {
+            svr4::response4::Status::Ok
+        };
+        svr4::Response4 {
+            inner: Some(svr4::response4::Inner::Query(svr4::response4::Query {
+                status: status.into(),
+                tries_remaining: self.tries,
+                version: self.versions.keys().next().copied().unwrap_or_default(),
+                new_version: 0,
+            })),
+        }
+        .encode_to_vec()
+    }
+
+    /// Return a constant "hash" of a single user ID.
+    fn hashed_user_id(&self) -> [u8; 64] {
+        [1u8; 64] // SHA512(user_id)
+    }
+
+    /// Take in restore1 request, return restore1 response
+    pub fn restore1(&mut self, req_bytes: &[u8]) -> Vec<u8> {
+        let req = match svr4::Request4::decode(req_bytes)
+            .expect("decode Request4")
+            .inner
+        {
+            Some(svr4::request4::Inner::Restore1(r)) => r,
+            _ => {
+                panic!("not Restore1");
+            }
+        };
+        if self.versions.is_empty() {
+            return svr4::Response4 {
+                inner: Some(svr4::response4::Inner::Restore1(
+                    svr4::response4::Restore1 {
+                        status: svr4::response4::Status::Missing.into(),
+                        ..Default::default()
+                    },
+                )),
+            }
+            .encode_to_vec();
+        }
+        assert!(self.tries > 0);
+        self.tries -= 1;
+        self.restore1_blinded = req.blinded.to_vec();
+
+        let userhash_pt = RistrettoPoint::from_uniform_bytes(&self.hashed_user_id());
+        let blinded = to_ristretto_pt(&req.blinded).expect("decode blinded");
+
+        let auths = self
+            .versions
+            .iter()
+            .map(|(version, state)| svr4::response4::restore1::Auth {
+                version: *version,
+                element: (blinded * state.oprf_secretshare + userhash_pt * state.zero_secretshare)
+                    .compress()
+                    .to_bytes()
+                    .to_vec(),
+            })
+            .collect::<Vec<_>>();
+
+        svr4::Response4 {
+            inner: Some(svr4::response4::Inner::Restore1(
+                svr4::response4::Restore1 {
+                    status: svr4::response4::Status::Ok.into(),
+                    tries_remaining: self.tries,
+                    auth: auths,
+                },
+            )),
+        }
+        .encode_to_vec()
+    }
+
+    /// Take in restore2 request, return restore2 response
+    pub fn restore2(&self, req_bytes: &[u8], handshake_hash: &[u8]) -> Vec<u8> {
+        let req = match svr4::Request4::decode(req_bytes)
+            .expect("decode Request4")
+            .inner
+        {
+            Some(svr4::request4::Inner::Restore2(r)) => r,
+            _ => {
+                panic!("not Restore2");
+            }
+        };
+        let state = self.versions.get(&req.version).expect("version not set");
+        let auth_scalar = to_ristretto_scalar(&req.auth_scalar).expect("decode auth_scalar");
+        let auth_point = to_ristretto_pt(&req.auth_point).expect("decode auth_pt");
+
+        let scalar_hash_bytes: Vec<u8> = [
+            &req.auth_point as &[_],
+            &self.restore1_blinded,
+            handshake_hash,
+        ]
+        .concat();
+        let scalar_hash = Scalar::hash_from_bytes::<Sha512>(&scalar_hash_bytes);
+        let lhs = RISTRETTO_BASEPOINT_TABLE * &auth_scalar;
+        let rhs = state.auth_commitment * scalar_hash + auth_point;
+
+        // A wrong password produces a proof against the wrong auth commitment.
+        let response = if lhs == rhs {
+            svr4::response4::Restore2 {
+                status: svr4::response4::Status::Ok.into(),
+                encryption_secretshare: state.encryption_secretshare.to_vec(),
+            }
+        } else {
+            svr4::response4::Restore2 {
+                status: svr4::response4::Status::Error.into(),
+                encryption_secretshare: vec![],
+            }
+        };
+
+        svr4::Response4 {
+            inner: Some(svr4::response4::Inner::Restore2(response)),
+        }
+        .encode_to_vec()
+    }
+
+    /// Answers any request supported by this server, as an enclave would.
+    ///
+    /// `handshake_hash` is only used for Restore2 requests.
+    pub fn handle(&mut self, req_bytes: &[u8], handshake_hash: &[u8]) -> Vec<u8> {
+        let req = svr4::Request4::decode(req_bytes).expect("decode Request4");
+        match req.inner {
+            Some(svr4::request4::Inner::Create(_)) => self.create(req_bytes),
+            Some(svr4::request4::Inner::Query(_)) => self.query(req_bytes),
+            Some(svr4::request4::Inner::Restore1(_)) => self.restore1(req_bytes),
+            Some(svr4::request4::Inner::Restore2(_)) => self.restore2(req_bytes, handshake_hash),
+            other => panic!("unsupported request: {other:?}"),
+        }
+    }
+}
@@ -4,7 +4,7 @@
 //
 
 use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion};
-use rayon::iter::{IntoParallelRefIterator, ParallelIterator as _};
+use rayon::iter::{IntoParallelIterator as _, IntoParallelRefIterator, ParallelIterator as _};
 use zkgroup::SECONDS_PER_DAY;
 
 fn benchmark_integration_auth(c: &mut Criterion) {
@@ -406,10 +406,138 @@ pub fn benchmark_group_send_endorsements(c: &mut Criterion) {
     }
 }
 
+pub fn benchmark_receipt_presentation_batch(c: &mut Criterion) {
+    const BATCH_SIZE: u32 = 1000;
+
+    struct NothingRedeemed;
+    impl zkgroup::receipts::ReplayChecker for NothingRedeemed {
+        fn is_redeemed(&self, _receipt_serial_bytes: &zkgroup::ReceiptSerialBytes) -> bool {
+            false
+        }
+    }
+
+    let server_secret_params = zkgroup::ServerSecretParams::generate(zkgroup::TEST_ARRAY_32);
+    let server_public_params = server_secret_params.get_public_params();
+    let receipt_expiration_time = zkgroup::Timestamp::from_epoch_seconds(31337);
+
+    let presentations: Vec<_> = (0..BATCH_SIZE)
+        .into_par_iter()
+        .map(|i| {
+            let mut receipt_serial_bytes = [0; zkgroup::RECEIPT_SERIAL_LEN];
+            receipt_serial_bytes[..4].copy_from_slice(&i.to_be_bytes());
+            let context = server_public_params.create_receipt_credential_request_context(
+                zkgroup::TEST_ARRAY_32_1,
+                receipt_serial_bytes,
+            );
+            let response = server_secret_params.issue_receipt_credential(
+                zkgroup::TEST_ARRAY_32_2,
+                &context.get_request(),
+                receipt_expiration_time,
+                3,
+            );
+            let credential = server_public_params
+                .receive_receipt_credential(&context, &response)
+                .expect("valid issuance");
+            server_public_params
+                .create_receipt_credential_presentation(zkgroup::TEST_ARRAY_32_3, &credential)
+        })
+        .collect();
+
+    let mut benchmark_group = c.benchmark_group("receipt_presentation_batch");
+    benchmark_group.sample_size(10);
+
+    benchmark_group.bench_function(BenchmarkId::new("verify_one_by_one", BATCH_SIZE), |b| {
+        b.iter(|| {
+            for presentation in &presentations {
+                server_secret_params
+                    .verify_receipt_credential_presentation(presentation)
+                    .expect("valid");
+            }
+        })
+    });
+
+    benchmark_group.bench_function(BenchmarkId::new("verify_batch", BATCH_SIZE), |b| {
+        b.iter(|| {
+            server_secret_params
+                .verify_receipt_credential_presentation_batch(&presentations, &NothingRedeemed)
+        })
+    });
+}
+
+pub fn benchmark_group_delta(c: &mut Criterion) {
+    const GROUP_SIZE: u16 = 10_000;
+    const CHURN: u16 = 100;
+
+    let master_key = zkgroup::groups::GroupMasterKey::new(zkgroup::TEST_ARRAY_32_1);
+    let group_secret_params =
+        zkgroup::groups::GroupSecretParams::derive_from_master_key(master_key);
+
+    let members: Vec<_> = (0..GROUP_SIZE + CHURN)
+        .into_par_iter()
+        .map(|i| {
+            // Generate arbitrary v5 (hash-based) UUIDs for the synthetic group.
+            let aci = libsignal_core::Aci::from(uuid::Uuid::new_v5(
+                &uuid::Uuid::from_bytes(zkgroup::TEST_ARRAY_16_1),
+                &i.to_be_bytes(),
+            ));
+            let profile_key = zkgroup::profiles::ProfileKey::create(zkgroup::TEST_ARRAY_32_2);
+            (
+                group_secret_params.encrypt_service_id(aci.into()),
+                group_secret_params.encrypt_profile_key(profile_key, aci),
+            )
+        })
+        .collect();
+
+    // The new group drops the first CHURN members, adds CHURN more at the end, and is in reverse
+    // order, to make sure the comparison doesn't benefit from the lists lining up.
+    let old_members = &members[..GROUP_SIZE.into()];
+    let new_members: Vec<_> = members[CHURN.into()..].iter().rev().copied().collect();
+
+    let old_ciphertexts: Vec<_> = old_members.iter().map(|(uid, _)| *uid).collect();
+    let new_ciphertexts: Vec<_> = new_members.iter().map(|(uid, _)| *uid).collect();
+
+    let mut benchmark_group = c.benchmark_group("group_delta");
+    benchmark_group.sample_size(10);
+
+    benchmark_group.bench_function(BenchmarkId::new("compute", GROUP_SIZE), |b| {
+        b.iter(|| zkgroup::groups::GroupDelta::compute(&old_ciphertexts, &new_ciphertexts))
+    });
+
+    benchmark_group.bench_function(
+        BenchmarkId::new("compute_with_profile_keys", GROUP_SIZE),
+        |b| {
+            b.iter(|| {
+                zkgroup::groups::GroupDelta::compute_with_profile_keys(old_members, new_members)
+            })
+        },
+    );
+
+    // For comparison, the plaintext approach this replaces.
+    benchmark_group.bench_function(BenchmarkId::new("decrypt_and_compare", GROUP_SIZE), |b| {
+        b.iter(|| {
+            let decrypt_all = |ciphertexts: &[zkgroup::groups::UuidCiphertext]| {
+                ciphertexts
+                    .iter()
+                    .map(|uid| group_secret_params.decrypt_service_id(*uid).expect("valid"))
+                    .collect::<std::collections::HashSet<_>>()
+            };
+            let old = decrypt_all(&old_ciphertexts);
+            let new = decrypt_all(&new_ciphertexts);
+            (
+                new.difference(&old).count(),
+                old.difference(&new).count(),
+                old.intersection(&new).count(),
+            )
+        })
+    });
+}
+
 criterion_group!(
     benches,
     benchmark_integration_profile,
     benchmark_integration_auth,
     benchmark_group_send_endorsements,
+    benchmark_receipt_presentation_batch,
+    benchmark_group_delta,
 );
 criterion_main!(benches);
@@ -5,6 +5,7 @@
 
 pub mod auth_credential_presentation;
 pub mod auth_credential_with_pni;
+mod http_auth;
 
 pub use auth_credential_presentation::{
     AnyAuthCredentialPresentation, AuthCredentialWithPniPresentation,
@@ -12,5 +13,6 @@ pub use auth_credential_presentation::{
 pub use auth_credential_with_pni::{
     AuthCredentialWithPni, AuthCredentialWithPniResponse, AuthCredentialWithPniV0,
     AuthCredentialWithPniV0Response, AuthCredentialWithPniZkc,
-    AuthCredentialWithPniZkcPresentation, AuthCredentialWithPniZkcResponse,
+    AuthCredentialWithPniZkcPresentation, AuthCredentialWithPniZkcResponse, UpgradeOutcome,
 };
+pub use http_auth::presentation_to_http_auth;
@@ -8,7 +8,7 @@ use serde::{Deserialize, Serialize, Serializer};
 use crate::auth::AuthCredentialWithPniZkcPresentation;
 use crate::common::constants::*;
 use crate::common::errors::*;
-use crate::common::serialization::VersionByte;
+use crate::common::serialization::{check_version, VersionByte, VersionedSerialization};
 use crate::common::simple_types::*;
 use crate::{api, crypto};
 
@@ -58,8 +58,13 @@ enum PresentationVersion {
     V4 = PRESENTATION_VERSION_4,
 }
 
+impl VersionedSerialization for AnyAuthCredentialPresentation {
+    const SUPPORTED_VERSIONS: &'static [u8] = &[PRESENTATION_VERSION_3, PRESENTATION_VERSION_4];
+}
+
 impl AnyAuthCredentialPresentation {
     pub fn new(presentation_bytes: &[u8]) -> Result<Self, ZkGroupDeserializationFailure> {
+        check_version::<Self>(presentation_bytes)?;
         let first = *presentation_bytes
             .first()
             .ok_or(ZkGroupDeserializationFailure::new::<Self>())?;
@@ -15,7 +15,9 @@ pub use zkc::{
     AuthCredentialWithPniZkcResponse,
 };
 
-use crate::ZkGroupDeserializationFailure;
+use crate::common::serialization::{check_version, VersionedSerialization};
+use crate::common::simple_types::Timestamp;
+use crate::{ZkGroupDeserializationFailure, SECONDS_PER_DAY};
 
 #[derive(Clone, PartialDefault)]
 pub enum AuthCredentialWithPni {
@@ -31,6 +33,19 @@ pub enum AuthCredentialWithPniResponse {
     Zkc(AuthCredentialWithPniZkcResponse),
 }
 
+/// The result of [`AuthCredentialWithPni::upgrade_from_v0`].
+#[derive(Clone)]
+pub enum UpgradeOutcome {
+    /// The credential is already in the current format.
+    Converted(AuthCredentialWithPniZkc),
+    /// The credential has to be replaced by a new one from the server.
+    ///
+    /// A V0 credential is a MAC under one of the server's older keys, so there's no way to turn it
+    /// into an [`AuthCredentialWithPniZkc`] on the client. It can still be presented until
+    /// `expiration`, so the refresh should be scheduled before then.
+    RefreshRequired { expiration: Timestamp },
+}
+
 #[repr(u8)]
 #[derive(Copy, Clone, Debug, Eq, PartialEq, PartialDefault, TryFromPrimitive)]
 pub enum AuthCredentialWithPniVersion {
@@ -39,8 +54,21 @@ pub enum AuthCredentialWithPniVersion {
     Zkc = 3,
 }
 
+impl AuthCredentialWithPniVersion {
+    const ALL: &'static [u8] = &[Self::V0 as u8, Self::Zkc as u8];
+}
+
+impl VersionedSerialization for AuthCredentialWithPni {
+    const SUPPORTED_VERSIONS: &'static [u8] = AuthCredentialWithPniVersion::ALL;
+}
+
+impl VersionedSerialization for AuthCredentialWithPniResponse {
+    const SUPPORTED_VERSIONS: &'static [u8] = AuthCredentialWithPniVersion::ALL;
+}
+
 impl AuthCredentialWithPni {
     pub fn new(bytes: &[u8]) -> Result<Self, ZkGroupDeserializationFailure> {
+        check_version::<Self>(bytes)?;
         let first = bytes
             .first()
             .ok_or_else(ZkGroupDeserializationFailure::new::<Self>)?;
@@ -55,10 +83,26 @@ impl AuthCredentialWithPni {
             }
         }
     }
+
+    /// Checks whether a stored credential of any version can be used in the current format.
+    pub fn upgrade_from_v0(bytes: &[u8]) -> Result<UpgradeOutcome, ZkGroupDeserializationFailure> {
+        Ok(match Self::new(bytes)? {
+            Self::V0(credential) => UpgradeOutcome::RefreshRequired {
+                // Matches the window accepted by
+                // ServerSecretParams::check_auth_credential_redemption_time.
+                expiration: credential
+                    .redemption_time
+                    .checked_add_seconds(2 * SECONDS_PER_DAY)
+                    .unwrap_or(Timestamp::from_epoch_seconds(u64::MAX)),
+            },
+            Self::Zkc(credential) => UpgradeOutcome::Converted(credential),
+        })
+    }
 }
 
 impl AuthCredentialWithPniResponse {
     pub fn new(bytes: &[u8]) -> Result<Self, ZkGroupDeserializationFailure> {
+        check_version::<Self>(bytes)?;
         let first = bytes
             .first()
             .ok_or_else(ZkGroupDeserializationFailure::new::<Self>)?;
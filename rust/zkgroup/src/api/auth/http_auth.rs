@@ -0,0 +1,23 @@
+//
+// Copyright 2024 Signal Messenger, LLC.
+// SPDX-License-Identifier: AGPL-3.0-only
+//
+
+use crate::api::auth::AnyAuthCredentialPresentation;
+use crate::api::groups::GroupPublicParams;
+
+/// The HTTP Basic auth username and password for a group storage request authorized by
+/// `presentation`.
+///
+/// The username is the serialized public params of the group being accessed and the password is
+/// the serialized presentation, both hex-encoded in lowercase, which is what the storage service
+/// expects.
+pub fn presentation_to_http_auth(
+    presentation: &AnyAuthCredentialPresentation,
+    group_public_params: &GroupPublicParams,
+) -> (String, String) {
+    (
+        hex::encode(crate::serialize(group_public_params)),
+        hex::encode(crate::serialize(presentation)),
+    )
+}
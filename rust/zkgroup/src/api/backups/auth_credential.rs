@@ -22,7 +22,7 @@ use poksho::ShoApi;
 use serde::{Deserialize, Serialize};
 use sha2::Sha256;
 
-use crate::common::serialization::ReservedByte;
+use crate::common::serialization::{ReservedByte, VersionedSerialization};
 use crate::common::sho::Sho;
 use crate::common::simple_types::*;
 use crate::generic_server_params::{GenericServerPublicParams, GenericServerSecretParams};
@@ -89,6 +89,10 @@ pub struct BackupAuthCredentialRequestContext {
     key_pair: zkcredential::issuance::blind::BlindingKeyPair,
 }
 
+impl VersionedSerialization for BackupAuthCredentialRequestContext {
+    const SUPPORTED_VERSIONS: &'static [u8] = &[ReservedByte::VALUE];
+}
+
 impl BackupAuthCredentialRequestContext {
     /// Create a BackupAuthCredentialRequestContext
     ///
@@ -138,6 +142,10 @@ pub struct BackupAuthCredentialRequest {
     public_key: zkcredential::issuance::blind::BlindingPublicKey,
 }
 
+impl VersionedSerialization for BackupAuthCredentialRequest {
+    const SUPPORTED_VERSIONS: &'static [u8] = &[ReservedByte::VALUE];
+}
+
 impl BackupAuthCredentialRequest {
     pub fn issue(
         &self,
@@ -170,6 +178,10 @@ pub struct BackupAuthCredentialResponse {
     blinded_credential: zkcredential::issuance::blind::BlindedIssuanceProof,
 }
 
+impl VersionedSerialization for BackupAuthCredentialResponse {
+    const SUPPORTED_VERSIONS: &'static [u8] = &[ReservedByte::VALUE];
+}
+
 impl BackupAuthCredentialRequestContext {
     pub fn receive(
         self,
@@ -211,6 +223,10 @@ pub struct BackupAuthCredential {
     backup_id: [u8; 16],
 }
 
+impl VersionedSerialization for BackupAuthCredential {
+    const SUPPORTED_VERSIONS: &'static [u8] = &[ReservedByte::VALUE];
+}
+
 impl BackupAuthCredential {
     pub fn present(
         &self,
@@ -246,6 +262,10 @@ pub struct BackupAuthCredentialPresentation {
     backup_id: [u8; 16],
 }
 
+impl VersionedSerialization for BackupAuthCredentialPresentation {
+    const SUPPORTED_VERSIONS: &'static [u8] = &[ReservedByte::VALUE];
+}
+
 impl BackupAuthCredentialPresentation {
     pub fn verify(
         &self,
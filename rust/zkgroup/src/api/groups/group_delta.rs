@@ -0,0 +1,163 @@
+//
+// Copyright 2024 Signal Messenger, LLC.
+// SPDX-License-Identifier: AGPL-3.0-only
+//
+
+//! Computing membership changes between two versions of a group, without decrypting members.
+//!
+//! Encryption of service IDs and profile keys is deterministic for a given set of group params,
+//! so two ciphertexts produced with the same [`GroupSecretParams`] are equal exactly when their
+//! plaintexts are. That means membership can be compared directly on ciphertexts, which is much
+//! cheaper than decrypting every member of a large group.
+//!
+//! [`GroupSecretParams`]: super::GroupSecretParams
+
+use std::collections::{HashMap, HashSet};
+
+use zkcredential::attributes::Attribute;
+
+use crate::groups::{ProfileKeyCiphertext, UuidCiphertext};
+
+/// The change in membership between two versions of a group.
+///
+/// Both member lists must have been encrypted with the same group params. Members are expected to
+/// appear at most once in each list; repeated ciphertexts are treated as a single member.
+#[derive(Clone)]
+pub struct GroupDelta {
+    /// Members present in the new list but not the old one, in the order of the new list.
+    pub added: Vec<UuidCiphertext>,
+    /// Members present in the old list but not the new one, in the order of the old list.
+    pub removed: Vec<UuidCiphertext>,
+    /// The number of members present in both lists.
+    pub unchanged_count: usize,
+}
+
+/// Like [`GroupDelta`], but also tracking the profile key of each member.
+#[derive(Clone)]
+pub struct GroupDeltaWithProfileKeys {
+    /// Members present in the new list but not the old one, in the order of the new list.
+    pub added: Vec<(UuidCiphertext, ProfileKeyCiphertext)>,
+    /// Members present in the old list but not the new one, in the order of the old list.
+    pub removed: Vec<(UuidCiphertext, ProfileKeyCiphertext)>,
+    /// Members present in both lists whose profile key differs, along with their *new* profile
+    /// key, in the order of the new list.
+    ///
+    /// These members are also counted in `unchanged_count`.
+    pub profile_key_changed: Vec<(UuidCiphertext, ProfileKeyCiphertext)>,
+    /// The number of members present in both lists, whether or not their profile key changed.
+    pub unchanged_count: usize,
+}
+
+impl GroupDelta {
+    /// Computes the membership change between two versions of a group.
+    pub fn compute(old_members: &[UuidCiphertext], new_members: &[UuidCiphertext]) -> Self {
+        let DeltaIndices {
+            added,
+            removed,
+            unchanged,
+        } = DeltaIndices::compute(old_members, new_members, |member| member);
+
+        Self {
+            added: added.into_iter().map(|i| new_members[i]).collect(),
+            removed: removed.into_iter().map(|i| old_members[i]).collect(),
+            unchanged_count: unchanged.len(),
+        }
+    }
+
+    /// Computes the membership change between two versions of a group, also reporting members
+    /// whose profile key changed.
+    pub fn compute_with_profile_keys(
+        old_members: &[(UuidCiphertext, ProfileKeyCiphertext)],
+        new_members: &[(UuidCiphertext, ProfileKeyCiphertext)],
+    ) -> GroupDeltaWithProfileKeys {
+        let DeltaIndices {
+            added,
+            removed,
+            unchanged,
+        } = DeltaIndices::compute(old_members, new_members, |(member, _)| member);
+
+        let profile_key_changed = unchanged
+            .iter()
+            .filter(|(old_i, new_i)| {
+                CiphertextKey::new(&old_members[*old_i].1.ciphertext)
+                    != CiphertextKey::new(&new_members[*new_i].1.ciphertext)
+            })
+            .map(|(_, new_i)| new_members[*new_i])
+            .collect();
+
+        GroupDeltaWithProfileKeys {
+            added: added.into_iter().map(|i| new_members[i]).collect(),
+            removed: removed.into_iter().map(|i| old_members[i]).collect(),
+            profile_key_changed,
+            unchanged_count: unchanged.len(),
+        }
+    }
+}
+
+/// A hashable stand-in for a ciphertext, equal exactly when the ciphertexts are.
+#[derive(PartialEq, Eq, Hash)]
+struct CiphertextKey([[u8; 32]; 2]);
+
+impl CiphertextKey {
+    fn new(ciphertext: &impl Attribute) -> Self {
+        Self(
+            ciphertext
+                .as_points()
+                .map(|point| point.compress().to_bytes()),
+        )
+    }
+}
+
+/// The result of matching up two member lists, as indexes into those lists.
+struct DeltaIndices {
+    /// Indexes into the new list.
+    added: Vec<usize>,
+    /// Indexes into the old list.
+    removed: Vec<usize>,
+    /// Pairs of (old, new) indexes, in the order of the new list.
+    unchanged: Vec<(usize, usize)>,
+}
+
+impl DeltaIndices {
+    fn compute<T>(old: &[T], new: &[T], member: impl Fn(&T) -> &UuidCiphertext) -> Self {
+        // Maps each old member to its index, and whether it's been seen in the new list yet.
+        let mut old_by_key: HashMap<CiphertextKey, (usize, bool)> =
+            HashMap::with_capacity(old.len());
+        for (i, entry) in old.iter().enumerate() {
+            old_by_key
+                .entry(CiphertextKey::new(&member(entry).ciphertext))
+                .or_insert((i, false));
+        }
+
+        let mut added_keys = HashSet::new();
+        let mut added = Vec::new();
+        let mut unchanged = Vec::new();
+        for (new_i, entry) in new.iter().enumerate() {
+            let key = CiphertextKey::new(&member(entry).ciphertext);
+            match old_by_key.get_mut(&key) {
+                Some((_, true)) => {}
+                Some((old_i, seen @ false)) => {
+                    *seen = true;
+                    unchanged.push((*old_i, new_i));
+                }
+                None => {
+                    if added_keys.insert(key) {
+                        added.push(new_i);
+                    }
+                }
+            }
+        }
+
+        let mut removed: Vec<usize> = old_by_key
+            .into_values()
+            .filter_map(|(i, seen)| (!seen).then_some(i))
+            .collect();
+        removed.sort_unstable();
+
+        Self {
+            added,
+            removed,
+            unchanged,
+        }
+    }
+}
@@ -6,7 +6,7 @@
 use partial_default::PartialDefault;
 use serde::{Deserialize, Serialize};
 
-use crate::common::serialization::ReservedByte;
+use crate::common::serialization::{ReservedByte, VersionedSerialization};
 use crate::common::simple_types::*;
 use crate::crypto;
 
@@ -19,6 +19,10 @@ pub struct ExpiringProfileKeyCredential {
     pub(crate) credential_expiration_time: Timestamp,
 }
 
+impl VersionedSerialization for ExpiringProfileKeyCredential {
+    const SUPPORTED_VERSIONS: &'static [u8] = &[ReservedByte::VALUE];
+}
+
 impl ExpiringProfileKeyCredential {
     pub fn aci(&self) -> libsignal_core::Aci {
         uuid::Uuid::from_bytes(self.aci_bytes).into()
@@ -6,7 +6,7 @@
 use partial_default::PartialDefault;
 use serde::{Deserialize, Serialize};
 
-use crate::common::serialization::ReservedByte;
+use crate::common::serialization::{ReservedByte, VersionedSerialization};
 use crate::common::simple_types::*;
 use crate::crypto;
 
@@ -17,3 +17,7 @@ pub struct ExpiringProfileKeyCredentialResponse {
     pub(crate) credential_expiration_time: Timestamp,
     pub(crate) proof: crypto::proofs::ExpiringProfileKeyCredentialIssuanceProof,
 }
+
+impl VersionedSerialization for ExpiringProfileKeyCredentialResponse {
+    const SUPPORTED_VERSIONS: &'static [u8] = &[ReservedByte::VALUE];
+}
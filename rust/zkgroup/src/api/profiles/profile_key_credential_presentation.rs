@@ -8,7 +8,7 @@ use serde::{Deserialize, Serialize, Serializer};
 
 use crate::common::constants::*;
 use crate::common::errors::*;
-use crate::common::serialization::VersionByte;
+use crate::common::serialization::{check_version, VersionByte, VersionedSerialization};
 use crate::common::simple_types::*;
 use crate::{api, crypto};
 
@@ -96,9 +96,21 @@ pub enum AnyProfileKeyCredentialPresentation {
     V3(ExpiringProfileKeyCredentialPresentation),
 }
 
+impl VersionedSerialization for AnyProfileKeyCredentialPresentation {
+    const SUPPORTED_VERSIONS: &'static [u8] = &[
+        PRESENTATION_VERSION_1,
+        PRESENTATION_VERSION_2,
+        PRESENTATION_VERSION_3,
+    ];
+}
+
 impl AnyProfileKeyCredentialPresentation {
     pub fn new(presentation_bytes: &[u8]) -> Result<Self, ZkGroupDeserializationFailure> {
-        match presentation_bytes[0] {
+        check_version::<Self>(presentation_bytes)?;
+        let first = *presentation_bytes
+            .first()
+            .ok_or_else(ZkGroupDeserializationFailure::new::<Self>)?;
+        match first {
             PRESENTATION_VERSION_1 => {
                 crate::deserialize::<ProfileKeyCredentialPresentationV1>(presentation_bytes)
                     .map(AnyProfileKeyCredentialPresentation::V1)
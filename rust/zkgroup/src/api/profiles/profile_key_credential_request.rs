@@ -6,7 +6,7 @@
 use partial_default::PartialDefault;
 use serde::{Deserialize, Serialize};
 
-use crate::common::serialization::ReservedByte;
+use crate::common::serialization::{ReservedByte, VersionedSerialization};
 use crate::crypto;
 
 #[derive(Serialize, Deserialize, PartialDefault)]
@@ -16,3 +16,7 @@ pub struct ProfileKeyCredentialRequest {
     pub(crate) ciphertext: crypto::profile_key_credential_request::Ciphertext,
     pub(crate) proof: crypto::proofs::ProfileKeyCredentialRequestProof,
 }
+
+impl VersionedSerialization for ProfileKeyCredentialRequest {
+    const SUPPORTED_VERSIONS: &'static [u8] = &[ReservedByte::VALUE];
+}
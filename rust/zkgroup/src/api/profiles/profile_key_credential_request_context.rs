@@ -6,7 +6,7 @@
 use partial_default::PartialDefault;
 use serde::{Deserialize, Serialize};
 
-use crate::common::serialization::ReservedByte;
+use crate::common::serialization::{ReservedByte, VersionedSerialization};
 use crate::common::simple_types::*;
 use crate::{api, crypto};
 
@@ -21,6 +21,10 @@ pub struct ProfileKeyCredentialRequestContext {
     pub(crate) proof: crypto::proofs::ProfileKeyCredentialRequestProof,
 }
 
+impl VersionedSerialization for ProfileKeyCredentialRequestContext {
+    const SUPPORTED_VERSIONS: &'static [u8] = &[ReservedByte::VALUE];
+}
+
 impl ProfileKeyCredentialRequestContext {
     pub fn get_request(&self) -> api::profiles::ProfileKeyCredentialRequest {
         let ciphertext = self.ciphertext_with_secret_nonce.get_ciphertext();
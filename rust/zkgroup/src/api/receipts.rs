@@ -10,7 +10,9 @@ pub mod receipt_credential_request_context;
 pub mod receipt_credential_response;
 
 pub use receipt_credential::ReceiptCredential;
-pub use receipt_credential_presentation::ReceiptCredentialPresentation;
+pub use receipt_credential_presentation::{
+    ReceiptCredentialPresentation, ReceiptCredentialPresentationRejection, ReplayChecker,
+};
 pub use receipt_credential_request::ReceiptCredentialRequest;
 pub use receipt_credential_request_context::ReceiptCredentialRequestContext;
 pub use receipt_credential_response::ReceiptCredentialResponse;
@@ -6,7 +6,7 @@
 use partial_default::PartialDefault;
 use serde::{Deserialize, Serialize};
 
-use crate::common::serialization::ReservedByte;
+use crate::common::serialization::{ReservedByte, VersionedSerialization};
 use crate::common::simple_types::*;
 use crate::crypto;
 
@@ -19,6 +19,10 @@ pub struct ReceiptCredential {
     pub(crate) receipt_serial_bytes: ReceiptSerialBytes,
 }
 
+impl VersionedSerialization for ReceiptCredential {
+    const SUPPORTED_VERSIONS: &'static [u8] = &[ReservedByte::VALUE];
+}
+
 impl ReceiptCredential {
     pub fn get_receipt_expiration_time(&self) -> Timestamp {
         self.receipt_expiration_time
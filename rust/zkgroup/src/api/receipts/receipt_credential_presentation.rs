@@ -6,9 +6,9 @@
 use partial_default::PartialDefault;
 use serde::{Deserialize, Serialize};
 
-use crate::common::serialization::ReservedByte;
+use crate::common::serialization::{ReservedByte, VersionedSerialization};
 use crate::crypto::receipt_struct::ReceiptStruct;
-use crate::{crypto, ReceiptLevel, ReceiptSerialBytes, Timestamp};
+use crate::{crypto, ReceiptLevel, ReceiptSerialBytes, Timestamp, ZkGroupVerificationFailure};
 
 // Note that this type appears in gift badge messages, and thus in backups.
 // Therefore it must be possible to at least deserialize any past versions of it,
@@ -22,6 +22,10 @@ pub struct ReceiptCredentialPresentation {
     pub(crate) receipt_serial_bytes: ReceiptSerialBytes,
 }
 
+impl VersionedSerialization for ReceiptCredentialPresentation {
+    const SUPPORTED_VERSIONS: &'static [u8] = &[ReservedByte::VALUE];
+}
+
 impl ReceiptCredentialPresentation {
     pub fn get_receipt_struct(&self) -> ReceiptStruct {
         ReceiptStruct {
@@ -43,3 +47,25 @@ impl ReceiptCredentialPresentation {
         self.receipt_serial_bytes
     }
 }
+
+/// Lets a redemption server report which receipts have already been redeemed.
+///
+/// Used by [`ServerSecretParams::verify_receipt_credential_presentation_batch`] to reject replayed
+/// presentations as part of verification.
+///
+/// [`ServerSecretParams::verify_receipt_credential_presentation_batch`]: crate::ServerSecretParams::verify_receipt_credential_presentation_batch
+pub trait ReplayChecker {
+    /// Returns `true` if the receipt with the given serial has already been redeemed.
+    fn is_redeemed(&self, receipt_serial_bytes: &ReceiptSerialBytes) -> bool;
+}
+
+#[derive(Debug, thiserror::Error, displaydoc::Display)]
+/// Why a presentation in a batch was rejected
+pub enum ReceiptCredentialPresentationRejection {
+    /// {0}
+    VerificationFailure(#[from] ZkGroupVerificationFailure),
+    /// receipt serial was already presented at index {0} of the same batch
+    DuplicateInBatch(usize),
+    /// receipt has already been redeemed
+    AlreadyRedeemed,
+}
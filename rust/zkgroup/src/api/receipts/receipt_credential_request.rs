@@ -6,7 +6,7 @@
 use partial_default::PartialDefault;
 use serde::{Deserialize, Serialize};
 
-use crate::common::serialization::ReservedByte;
+use crate::common::serialization::{ReservedByte, VersionedSerialization};
 use crate::crypto;
 
 #[derive(Serialize, Deserialize, PartialDefault)]
@@ -25,3 +25,7 @@ pub struct ReceiptCredentialRequest {
     // wants to waste everybody's time by getting the server to issue a credential that it can't
     // use, so be it.)
 }
+
+impl VersionedSerialization for ReceiptCredentialRequest {
+    const SUPPORTED_VERSIONS: &'static [u8] = &[ReservedByte::VALUE];
+}
@@ -6,7 +6,7 @@
 use partial_default::PartialDefault;
 use serde::{Deserialize, Serialize};
 
-use crate::common::serialization::ReservedByte;
+use crate::common::serialization::{ReservedByte, VersionedSerialization};
 use crate::common::simple_types::*;
 use crate::{api, crypto};
 
@@ -19,6 +19,10 @@ pub struct ReceiptCredentialRequestContext {
         crypto::receipt_credential_request::CiphertextWithSecretNonce,
 }
 
+impl VersionedSerialization for ReceiptCredentialRequestContext {
+    const SUPPORTED_VERSIONS: &'static [u8] = &[ReservedByte::VALUE];
+}
+
 impl ReceiptCredentialRequestContext {
     pub fn get_request(&self) -> api::receipts::ReceiptCredentialRequest {
         let ciphertext = self.ciphertext_with_secret_nonce.get_ciphertext();
@@ -6,7 +6,7 @@
 use partial_default::PartialDefault;
 use serde::{Deserialize, Serialize};
 
-use crate::common::serialization::ReservedByte;
+use crate::common::serialization::{ReservedByte, VersionedSerialization};
 use crate::common::simple_types::*;
 use crate::crypto;
 
@@ -18,3 +18,7 @@ pub struct ReceiptCredentialResponse {
     pub(crate) blinded_credential: crypto::credentials::BlindedReceiptCredential,
     pub(crate) proof: crypto::proofs::ReceiptCredentialIssuanceProof,
 }
+
+impl VersionedSerialization for ReceiptCredentialResponse {
+    const SUPPORTED_VERSIONS: &'static [u8] = &[ReservedByte::VALUE];
+}
@@ -3,7 +3,10 @@
 // SPDX-License-Identifier: AGPL-3.0-only
 //
 
+use std::collections::{hash_map, HashMap};
+
 use partial_default::PartialDefault;
+use rayon::iter::{IntoParallelRefIterator as _, ParallelIterator as _};
 use serde::{Deserialize, Serialize};
 
 use crate::common::constants::*;
@@ -378,6 +381,52 @@ impl ServerSecretParams {
             presentation.get_receipt_struct(),
         )
     }
+
+    /// Verifies many receipt credential presentations at once, returning one result per
+    /// presentation in the same order.
+    ///
+    /// Besides checking each presentation's proof, this rejects any presentation whose receipt
+    /// serial matches an earlier valid presentation in the same batch, and then asks
+    /// `replay_checker` about the rest. That way storage is only consulted once per distinct
+    /// receipt, and only for presentations that would otherwise be accepted.
+    pub fn verify_receipt_credential_presentation_batch(
+        &self,
+        presentations: &[api::receipts::ReceiptCredentialPresentation],
+        replay_checker: &impl api::receipts::ReplayChecker,
+    ) -> Vec<Result<(), api::receipts::ReceiptCredentialPresentationRejection>> {
+        use api::receipts::ReceiptCredentialPresentationRejection as Rejection;
+
+        let verified: Vec<_> = presentations
+            .par_iter()
+            .map(|presentation| self.verify_receipt_credential_presentation(presentation))
+            .collect();
+
+        let mut first_index_by_serial = HashMap::new();
+        presentations
+            .iter()
+            .zip(verified)
+            .enumerate()
+            .map(
+                |(index, (presentation, verified))| -> Result<(), Rejection> {
+                    verified?;
+                    let serial = presentation.get_receipt_serial_bytes();
+                    match first_index_by_serial.entry(serial) {
+                        hash_map::Entry::Occupied(first) => {
+                            Err(Rejection::DuplicateInBatch(*first.get()))
+                        }
+                        hash_map::Entry::Vacant(entry) => {
+                            entry.insert(index);
+                            if replay_checker.is_redeemed(&serial) {
+                                Err(Rejection::AlreadyRedeemed)
+                            } else {
+                                Ok(())
+                            }
+                        }
+                    }
+                },
+            )
+            .collect()
+    }
 }
 
 impl ServerPublicParams {
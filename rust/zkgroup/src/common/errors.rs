@@ -16,11 +16,27 @@ impl From<VerificationFailure> for ZkGroupVerificationFailure {
 }
 
 #[derive(Debug, thiserror::Error, displaydoc::Display)]
-/// Failed to deserialize {0}
-pub struct ZkGroupDeserializationFailure(&'static str);
+pub enum ZkGroupDeserializationFailure {
+    /// Failed to deserialize {0}
+    Corrupted(&'static str),
+    /// Failed to deserialize {type_name}: unsupported version {found} (supported: {supported:?})
+    UnsupportedVersion {
+        type_name: &'static str,
+        found: u8,
+        supported: &'static [u8],
+    },
+}
 
 impl ZkGroupDeserializationFailure {
     pub fn new<T>() -> Self {
-        Self(std::any::type_name::<T>())
+        Self::Corrupted(std::any::type_name::<T>())
+    }
+
+    pub fn unsupported_version<T>(found: u8, supported: &'static [u8]) -> Self {
+        Self::UnsupportedVersion {
+            type_name: std::any::type_name::<T>(),
+            found,
+            supported,
+        }
     }
 }
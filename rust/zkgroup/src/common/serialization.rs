@@ -34,6 +34,39 @@ pub fn deserialize<'a, T: Deserialize<'a> + PartialDefault>(
     Ok(result)
 }
 
+/// A type whose serialized form starts with a version byte (or a [`ReservedByte`]).
+///
+/// Newer versions of zkgroup may produce data with a different leading byte. Checking it before
+/// deserializing lets that be reported as
+/// [`ZkGroupDeserializationFailure::UnsupportedVersion`] instead of as corrupted data.
+pub trait VersionedSerialization {
+    /// The leading bytes this version of zkgroup knows how to deserialize.
+    const SUPPORTED_VERSIONS: &'static [u8];
+}
+
+/// Checks that `bytes` starts with one of `T`'s supported version bytes.
+///
+/// Empty input is accepted here; it's left to the actual deserialization to reject it as
+/// corrupted.
+pub fn check_version<T: VersionedSerialization>(
+    bytes: &[u8],
+) -> Result<(), ZkGroupDeserializationFailure> {
+    match bytes.first() {
+        Some(found) if !T::SUPPORTED_VERSIONS.contains(found) => Err(
+            ZkGroupDeserializationFailure::unsupported_version::<T>(*found, T::SUPPORTED_VERSIONS),
+        ),
+        _ => Ok(()),
+    }
+}
+
+/// Like [`deserialize`], but reports data from an unsupported version distinctly.
+pub fn deserialize_versioned<'a, T: Deserialize<'a> + PartialDefault + VersionedSerialization>(
+    bytes: &'a [u8],
+) -> Result<T, ZkGroupDeserializationFailure> {
+    check_version::<T>(bytes)?;
+    deserialize(bytes)
+}
+
 /// Serializes a type using the standard zkgroup encoding (based on bincode).
 pub fn serialize<T: Serialize>(value: &T) -> Vec<u8> {
     zkgroup_bincode_options()
@@ -49,6 +82,11 @@ pub fn serialize<T: Serialize>(value: &T) -> Vec<u8> {
 #[derive(Copy, Clone, Debug, Default, Eq, PartialEq)]
 pub struct VersionByte<const C: u8>;
 
+impl<const C: u8> VersionByte<C> {
+    /// The byte this type serializes as.
+    pub const VALUE: u8 = C;
+}
+
 impl<const C: u8> From<VersionByte<C>> for u8 {
     fn from(VersionByte: VersionByte<C>) -> Self {
         C
@@ -12,5 +12,7 @@ pub mod crypto;
 pub use api::*;
 pub use common::constants::*;
 pub use common::errors::*;
-pub use common::serialization::{deserialize, serialize};
+pub use common::serialization::{
+    check_version, deserialize, deserialize_versioned, serialize, VersionedSerialization,
+};
 pub use common::simple_types::*;
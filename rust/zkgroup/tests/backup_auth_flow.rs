@@ -3,12 +3,15 @@
 // SPDX-License-Identifier: AGPL-3.0-only
 //
 
+use test_case::test_case;
+use zkgroup::backups::BackupLevel;
 use zkgroup::{RandomnessBytes, Timestamp, RANDOMNESS_LEN};
 
 const DAY_ALIGNED_TIMESTAMP: Timestamp = Timestamp::from_epoch_seconds(1681344000); // 2023-04-13 00:00:00 UTC
 
-#[test]
-fn test_backup_auth_request_response() {
+#[test_case(BackupLevel::Messages; "messages")]
+#[test_case(BackupLevel::Media; "media")]
+fn test_backup_auth_request_response(backup_level: BackupLevel) {
     let randomness1: RandomnessBytes = [0x43u8; RANDOMNESS_LEN];
     let randomness2: RandomnessBytes = [0x44u8; RANDOMNESS_LEN];
     let randomness3: RandomnessBytes = [0x45u8; RANDOMNESS_LEN];
@@ -21,7 +24,7 @@ fn test_backup_auth_request_response() {
 
     // client receives in response to initial request
     let redemption_time: Timestamp = DAY_ALIGNED_TIMESTAMP; // client validates it's day-aligned
-    let backup_level = zkgroup::backups::BackupLevel::Messages; // client validates it's a valid backup level
+    // (along with backup_level, which the client validates is a valid backup level)
 
     // client generated materials; issuance request
     let request_context =
@@ -48,8 +51,14 @@ fn test_backup_auth_request_response() {
 
     let presentation = credential.present(&server_public_params, randomness3);
 
+    // the presentation survives being sent to the verifying server
+    let presentation: zkgroup::backups::BackupAuthCredentialPresentation =
+        zkgroup::deserialize(&zkgroup::serialize(&presentation)).expect("valid presentation");
+
     // server verification of the credential presentation
     presentation
         .verify(redemption_time, &server_secret_params)
         .expect("presentation should be valid");
+    assert_eq!(presentation.backup_level(), backup_level);
+    assert_eq!(presentation.backup_id(), credential.backup_id());
 }
@@ -0,0 +1,164 @@
+//
+// Copyright 2024 Signal Messenger, LLC.
+// SPDX-License-Identifier: AGPL-3.0-only
+//
+
+use libsignal_core::ServiceId;
+use zkgroup::groups::{
+    GroupDelta, GroupMasterKey, GroupSecretParams, ProfileKeyCiphertext, UuidCiphertext,
+};
+use zkgroup::profiles::ProfileKey;
+
+fn group_secret_params() -> GroupSecretParams {
+    GroupSecretParams::derive_from_master_key(GroupMasterKey::new(zkgroup::TEST_ARRAY_32_1))
+}
+
+fn member(i: u8) -> libsignal_core::Aci {
+    libsignal_core::Aci::from_uuid_bytes([i; zkgroup::UUID_LEN])
+}
+
+fn encrypted_members(
+    params: &GroupSecretParams,
+    members: impl IntoIterator<Item = u8>,
+) -> Vec<UuidCiphertext> {
+    members
+        .into_iter()
+        .map(|i| params.encrypt_service_id(member(i).into()))
+        .collect()
+}
+
+fn encrypted_members_with_profile_keys(
+    params: &GroupSecretParams,
+    members: impl IntoIterator<Item = (u8, u8)>,
+) -> Vec<(UuidCiphertext, ProfileKeyCiphertext)> {
+    members
+        .into_iter()
+        .map(|(i, key)| {
+            (
+                params.encrypt_service_id(member(i).into()),
+                params.encrypt_profile_key(ProfileKey::create([key; 32]), member(i)),
+            )
+        })
+        .collect()
+}
+
+fn assert_same_members(actual: &[UuidCiphertext], expected: &[UuidCiphertext]) {
+    assert_eq!(
+        actual.iter().map(zkgroup::serialize).collect::<Vec<_>>(),
+        expected.iter().map(zkgroup::serialize).collect::<Vec<_>>(),
+    );
+}
+
+#[test]
+fn reordered_but_equal() {
+    let params = group_secret_params();
+    let old = encrypted_members(&params, [1, 2, 3, 4]);
+    let new = encrypted_members(&params, [3, 1, 4, 2]);
+
+    let delta = GroupDelta::compute(&old, &new);
+    assert_same_members(&delta.added, &[]);
+    assert_same_members(&delta.removed, &[]);
+    assert_eq!(delta.unchanged_count, 4);
+}
+
+#[test]
+fn added_and_removed() {
+    let params = group_secret_params();
+    let old = encrypted_members(&params, [1, 2, 3, 4]);
+    let new = encrypted_members(&params, [5, 4, 2, 6]);
+
+    let delta = GroupDelta::compute(&old, &new);
+    assert_same_members(&delta.added, &encrypted_members(&params, [5, 6]));
+    assert_same_members(&delta.removed, &encrypted_members(&params, [1, 3]));
+    assert_eq!(delta.unchanged_count, 2);
+}
+
+#[test]
+fn empty_groups() {
+    let params = group_secret_params();
+    let members = encrypted_members(&params, [1, 2]);
+
+    let delta = GroupDelta::compute(&[], &members);
+    assert_same_members(&delta.added, &members);
+    assert_same_members(&delta.removed, &[]);
+    assert_eq!(delta.unchanged_count, 0);
+
+    let delta = GroupDelta::compute(&members, &[]);
+    assert_same_members(&delta.added, &[]);
+    assert_same_members(&delta.removed, &members);
+    assert_eq!(delta.unchanged_count, 0);
+}
+
+#[test]
+fn repeated_members_count_once() {
+    let params = group_secret_params();
+    let old = encrypted_members(&params, [1, 1, 2]);
+    let new = encrypted_members(&params, [1, 3, 3, 1]);
+
+    let delta = GroupDelta::compute(&old, &new);
+    assert_same_members(&delta.added, &encrypted_members(&params, [3]));
+    assert_same_members(&delta.removed, &encrypted_members(&params, [2]));
+    assert_eq!(delta.unchanged_count, 1);
+}
+
+#[test]
+fn different_group_params_do_not_match() {
+    let params = group_secret_params();
+    let other_params =
+        GroupSecretParams::derive_from_master_key(GroupMasterKey::new(zkgroup::TEST_ARRAY_32_2));
+    let old = encrypted_members(&params, [1, 2]);
+    let new = encrypted_members(&other_params, [1, 2]);
+
+    let delta = GroupDelta::compute(&old, &new);
+    assert_eq!(delta.added.len(), 2);
+    assert_eq!(delta.removed.len(), 2);
+    assert_eq!(delta.unchanged_count, 0);
+}
+
+#[test]
+fn profile_key_changes() {
+    let params = group_secret_params();
+    let old = encrypted_members_with_profile_keys(&params, [(1, 10), (2, 20), (3, 30)]);
+    let new = encrypted_members_with_profile_keys(&params, [(4, 40), (3, 31), (1, 10)]);
+
+    let delta = GroupDelta::compute_with_profile_keys(&old, &new);
+    assert_eq!(delta.unchanged_count, 2);
+
+    let [(added_member, added_key)] = delta.added[..] else {
+        panic!("expected one added member");
+    };
+    let [(removed_member, removed_key)] = delta.removed[..] else {
+        panic!("expected one removed member");
+    };
+    let [(changed_member, changed_key)] = delta.profile_key_changed[..] else {
+        panic!("expected one changed profile key");
+    };
+
+    let decrypt_member =
+        |ciphertext: UuidCiphertext| params.decrypt_service_id(ciphertext).expect("valid member");
+    let decrypt_profile_key = |ciphertext: ProfileKeyCiphertext, i: u8| {
+        params
+            .decrypt_profile_key(ciphertext, member(i))
+            .expect("valid profile key")
+            .get_bytes()
+    };
+    assert_eq!(decrypt_member(added_member), ServiceId::from(member(4)));
+    assert_eq!(decrypt_profile_key(added_key, 4), [40; 32]);
+    assert_eq!(decrypt_member(removed_member), ServiceId::from(member(2)));
+    assert_eq!(decrypt_profile_key(removed_key, 2), [20; 32]);
+    assert_eq!(decrypt_member(changed_member), ServiceId::from(member(3)));
+    assert_eq!(decrypt_profile_key(changed_key, 3), [31; 32]);
+}
+
+#[test]
+fn reordered_but_equal_with_profile_keys() {
+    let params = group_secret_params();
+    let old = encrypted_members_with_profile_keys(&params, [(1, 10), (2, 20), (3, 30)]);
+    let new = encrypted_members_with_profile_keys(&params, [(2, 20), (3, 30), (1, 10)]);
+
+    let delta = GroupDelta::compute_with_profile_keys(&old, &new);
+    assert!(delta.added.is_empty());
+    assert!(delta.removed.is_empty());
+    assert!(delta.profile_key_changed.is_empty());
+    assert_eq!(delta.unchanged_count, 3);
+}
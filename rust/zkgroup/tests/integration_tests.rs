@@ -271,6 +271,119 @@ fn test_integration_auth_zkc() {
     auth_credential_bytes.copy_from_slice(&bincode::serialize(&auth_credential).unwrap());
 }
 
+#[test]
+fn test_presentation_http_auth() {
+    let master_key = zkgroup::groups::GroupMasterKey::new(zkgroup::TEST_ARRAY_32_1);
+    let group_public_params =
+        zkgroup::groups::GroupSecretParams::derive_from_master_key(master_key).get_public_params();
+    let presentation =
+        zkgroup::auth::AnyAuthCredentialPresentation::new(AUTH_CREDENTIAL_PRESENTATION_V4_RESULT)
+            .unwrap();
+
+    let (username, password) =
+        zkgroup::auth::presentation_to_http_auth(&presentation, &group_public_params);
+
+    // The client apps have always sent the hex-encoded bytes of each, with no separators.
+    assert_eq!(
+        username,
+        "0084e256730548f8ba09069b223eccc133f599f9827edc7084f8921e4a70cd9e4cd8c075c601cb280cc4f2c9c52c075fc5336c63fe27b935db3bcce18e01c90833ce1b7ad669a5db8b4addef7862f7ffbaab050226fd7cd776758a17fc95999c56"
+    );
+    assert_eq!(
+        password,
+        "035e3e79afda8dc0d489fcf7c78f71e1502f2e06e8aeb20149046f85b3004d3f7f982d57dfad49cd1e6c335755cef4cc5e8d3de1eb4f5e8f24d71cf9f2220ae750f47181d71aaabbd48a1916813ec08eea935eb013395bf72f9139da8ef4f9530d05000000000000007a080544e6ee8ee2ff0dc298f18841103a9f9ec38631df8682e241755f86f74e26301872f4f32a9bb80f5b17651c0c83253a8013532384061a1febf79e58e60fa215b31da678305fa2a271655e35824630d0804680ec0bf29b1c775652683c3a5cec537c3514df730f267371d909f29cc6252af30afe3ea846c0cf56478bdc5b7a7f983ea7c24ecef4b371286a6414b2c38a57a7f59a9df33e430736c1a2ca14e00000000000000015416082ff7e3a741a4c3c31be3c95d4a31f2cf742685e0b17cd7f7205230e0e4e67b4b6ed45e705de13a1cb7170897bb32c9db6f9a1108fddfc7fae9eb2ca0c5fc3d8ccbd79d992eeed333626a1f0c37f0b25625955611e5ba33c782c50550045923582280cd93c3e9555b4e36eec20993f60b6aeb9ddb7f2856c4659546f037b33534a0292c77a501a70796f24ff37c8311bdfea8bb6c78f909563fe6e3b0386f36adc92090694ebb106a837bac046ad26e2472ee16408e9fd84269fd78c00c5dde91fcf202a6afad3441b9e2a34f4831d5bf560c81b38d951cb7c88e4d701765de9df4cfa5487f360e29e99343e91811baec331c4680985e608ca5d408e21725c6aa1b61d5a8b48d75f4aaa9a3cbe88d3e0f1a54319081f77c72c8f525474de749a0fef17b06bbce74ca5d0f7a0c45f443a1901f1e3e016d3548e50a2fa19ce6b27ac467ed9c9f5018b2a4456b6c2b1a91454422fdd473c9636a8459e1c170060c77b02000000"
+    );
+    assert!(username
+        .chars()
+        .chain(password.chars())
+        .all(|c| c.is_ascii_digit() || ('a'..='f').contains(&c)));
+
+    let parsed: zkgroup::groups::GroupPublicParams =
+        zkgroup::deserialize(&hex::decode(&username).unwrap()).unwrap();
+    assert_eq!(
+        parsed.get_group_identifier(),
+        group_public_params.get_group_identifier()
+    );
+}
+
+#[test]
+fn test_auth_credential_with_pni_upgrade_from_v0() {
+    let server_secret_params = zkgroup::ServerSecretParams::generate(zkgroup::TEST_ARRAY_32);
+    let server_public_params = server_secret_params.get_public_params();
+
+    let master_key = zkgroup::groups::GroupMasterKey::new(zkgroup::TEST_ARRAY_32_1);
+    let group_secret_params =
+        zkgroup::groups::GroupSecretParams::derive_from_master_key(master_key);
+    let group_public_params = group_secret_params.get_public_params();
+
+    let aci = libsignal_core::Aci::from_uuid_bytes(zkgroup::TEST_ARRAY_16);
+    let pni = libsignal_core::Pni::from_uuid_bytes(zkgroup::TEST_ARRAY_16_1);
+    let redemption_time = zkgroup::Timestamp::from_epoch_seconds(123456 * SECONDS_PER_DAY);
+    let randomness = zkgroup::TEST_ARRAY_32_2;
+
+    // A V0 credential can't be converted, but is usable until the refresh deadline.
+    let v0_credential = server_public_params
+        .receive_auth_credential_with_pni_as_service_id(
+            aci,
+            pni,
+            redemption_time,
+            server_secret_params.issue_auth_credential_with_pni_as_service_id(
+                randomness,
+                aci,
+                pni,
+                redemption_time,
+            ),
+        )
+        .unwrap();
+    let v0_bytes = bincode::serialize(&v0_credential).unwrap();
+    let expiration = match zkgroup::auth::AuthCredentialWithPni::upgrade_from_v0(&v0_bytes).unwrap()
+    {
+        zkgroup::auth::UpgradeOutcome::RefreshRequired { expiration } => expiration,
+        zkgroup::auth::UpgradeOutcome::Converted(_) => panic!("V0 credential was converted"),
+    };
+    assert_eq!(expiration, redemption_time.add_seconds(2 * SECONDS_PER_DAY));
+
+    let presentation = server_public_params.create_auth_credential_with_pni_presentation(
+        zkgroup::TEST_ARRAY_32_5,
+        group_secret_params,
+        v0_credential,
+    );
+    server_secret_params
+        .verify_auth_credential_presentation(group_public_params, &presentation, expiration)
+        .expect("credential still valid at expiration");
+    server_secret_params
+        .verify_auth_credential_presentation(
+            group_public_params,
+            &presentation,
+            expiration.add_seconds(1),
+        )
+        .expect_err("credential not valid past expiration");
+
+    // A Zkc credential comes back as is.
+    let zkc_credential = zkgroup::auth::AuthCredentialWithPniZkcResponse::issue_credential(
+        aci,
+        pni,
+        redemption_time,
+        &server_secret_params,
+        randomness,
+    )
+    .receive(aci, pni, redemption_time, &server_public_params)
+    .unwrap();
+    let zkc_bytes = bincode::serialize(&zkc_credential).unwrap();
+    match zkgroup::auth::AuthCredentialWithPni::upgrade_from_v0(&zkc_bytes).unwrap() {
+        zkgroup::auth::UpgradeOutcome::Converted(converted) => {
+            assert_hex_eq!(bincode::serialize(&converted).unwrap(), zkc_bytes);
+        }
+        zkgroup::auth::UpgradeOutcome::RefreshRequired { .. } => {
+            panic!("Zkc credential needs a refresh")
+        }
+    }
+
+    assert!(zkgroup::auth::AuthCredentialWithPni::upgrade_from_v0(
+        &zkc_bytes[..zkc_bytes.len() - 1]
+    )
+    .is_err());
+}
+
 #[test]
 fn test_integration_expiring_profile() {
     // SERVER
@@ -3,7 +3,11 @@
 // SPDX-License-Identifier: AGPL-3.0-only
 //
 
-use zkgroup::api::receipts::ReceiptCredentialPresentation;
+use std::collections::HashSet;
+
+use zkgroup::api::receipts::{
+    ReceiptCredentialPresentation, ReceiptCredentialPresentationRejection, ReplayChecker,
+};
 use zkgroup::common::sho::Sho;
 use zkgroup::crypto::proofs::{ReceiptCredentialIssuanceProof, ReceiptCredentialPresentationProof};
 use zkgroup::crypto::receipt_struct::ReceiptStruct;
@@ -149,3 +153,81 @@ fn test_api() {
         .verify_receipt_credential_presentation(&bad_presentation)
         .expect_err("This Presentation Should Be Bad");
 }
+
+struct RedeemedSerials(HashSet<ReceiptSerialBytes>);
+
+impl ReplayChecker for RedeemedSerials {
+    fn is_redeemed(&self, receipt_serial_bytes: &ReceiptSerialBytes) -> bool {
+        self.0.contains(receipt_serial_bytes)
+    }
+}
+
+#[test]
+fn test_batch_verification() {
+    let server_secret_params = ServerSecretParams::generate([0x42u8; RANDOMNESS_LEN]);
+    let server_public_params = server_secret_params.get_public_params();
+
+    let present = |serial_byte: u8| {
+        let receipt_serial_bytes: ReceiptSerialBytes = [serial_byte; RECEIPT_SERIAL_LEN];
+        let context = server_public_params.create_receipt_credential_request_context(
+            [0x43u8; RANDOMNESS_LEN],
+            receipt_serial_bytes,
+        );
+        let response = server_secret_params.issue_receipt_credential(
+            [0x44u8; RANDOMNESS_LEN],
+            &context.get_request(),
+            Timestamp::from_epoch_seconds(31337),
+            3,
+        );
+        let credential = server_public_params
+            .receive_receipt_credential(&context, &response)
+            .expect("valid issuance");
+        server_public_params
+            .create_receipt_credential_presentation([0x45u8; RANDOMNESS_LEN], &credential)
+    };
+
+    let mut bad_presentation_bytes = bincode::serialize(&present(2)).unwrap();
+    let i = bad_presentation_bytes.len() - 17;
+    bad_presentation_bytes[i] += 1;
+
+    let presentations = [
+        present(1),
+        bincode::deserialize::<ReceiptCredentialPresentation>(&bad_presentation_bytes).unwrap(),
+        present(3),
+        present(1),
+        present(4),
+    ];
+    let redeemed = RedeemedSerials(HashSet::from([[4; RECEIPT_SERIAL_LEN]]));
+
+    let results = server_secret_params
+        .verify_receipt_credential_presentation_batch(&presentations, &redeemed);
+    assert_eq!(results.len(), presentations.len());
+    assert!(results[0].is_ok(), "{:?}", results[0]);
+    assert!(
+        matches!(
+            results[1],
+            Err(ReceiptCredentialPresentationRejection::VerificationFailure(
+                _
+            ))
+        ),
+        "{:?}",
+        results[1]
+    );
+    assert!(results[2].is_ok(), "{:?}", results[2]);
+    assert!(
+        matches!(
+            results[3],
+            Err(ReceiptCredentialPresentationRejection::DuplicateInBatch(0))
+        ),
+        "{:?}",
+        results[3]
+    );
+    assert!(
+        matches!(
+            results[4],
+            Err(ReceiptCredentialPresentationRejection::AlreadyRedeemed)
+        ),
+        "{:?}",
+        results[4]
+    );
+}
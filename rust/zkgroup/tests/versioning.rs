@@ -0,0 +1,273 @@
+//
+// Copyright 2024 Signal Messenger, LLC.
+// SPDX-License-Identifier: AGPL-3.0-only
+//
+
+use hex_literal::hex;
+use serde::Serialize;
+use zkgroup::auth::{
+    AnyAuthCredentialPresentation, AuthCredentialWithPni, AuthCredentialWithPniResponse,
+    AuthCredentialWithPniZkcResponse,
+};
+use zkgroup::backups::{
+    BackupAuthCredential, BackupAuthCredentialPresentation, BackupAuthCredentialRequest,
+    BackupAuthCredentialRequestContext, BackupAuthCredentialResponse, BackupLevel,
+};
+use zkgroup::generic_server_params::GenericServerSecretParams;
+use zkgroup::groups::{GroupMasterKey, GroupSecretParams};
+use zkgroup::profiles::{
+    AnyProfileKeyCredentialPresentation, ExpiringProfileKeyCredential,
+    ExpiringProfileKeyCredentialResponse, ProfileKey, ProfileKeyCredentialRequest,
+    ProfileKeyCredentialRequestContext,
+};
+use zkgroup::receipts::{
+    ReceiptCredential, ReceiptCredentialPresentation, ReceiptCredentialRequest,
+    ReceiptCredentialRequestContext, ReceiptCredentialResponse,
+};
+use zkgroup::{ServerSecretParams, Timestamp, ZkGroupDeserializationFailure, SECONDS_PER_DAY};
+
+/// A leading byte that no version of zkgroup has used yet.
+const FUTURE_VERSION: u8 = 0x7f;
+
+/// A [`BackupAuthCredentialRequest`] serialized by an earlier release, taken from the Java
+/// `BackupAuthTest`.
+const BACKUP_AUTH_CREDENTIAL_REQUEST_V0: &[u8] = &hex!("008482c506bc3ac16aa61b103f1aadcc9939fa39dda44dd2246e9b7dacd0077f7de007abb2adb235181c01106879d05ea047ca21776dcba5fb97af9388f140a83be88d148ef316993a92893af5920c92069fa11e325da33a3c7e21083c77c33401");
+
+#[track_caller]
+fn assert_rejects_future_version<T>(
+    value: &impl Serialize,
+    deserialize: impl Fn(&[u8]) -> Result<T, ZkGroupDeserializationFailure>,
+) {
+    let serialized = zkgroup::serialize(value);
+    assert!(deserialize(&serialized).is_ok(), "should round-trip");
+
+    let mut from_future = serialized.clone();
+    from_future[0] = FUTURE_VERSION;
+    match deserialize(&from_future) {
+        Err(ZkGroupDeserializationFailure::UnsupportedVersion {
+            type_name: _,
+            found,
+            supported,
+        }) => {
+            assert_eq!(found, FUTURE_VERSION);
+            assert!(supported.contains(&serialized[0]), "{supported:?}");
+        }
+        Err(e) => panic!("unexpected error: {e}"),
+        Ok(_) => panic!("should not deserialize"),
+    }
+
+    // Data with a supported version that's otherwise malformed is still reported as corrupted.
+    match deserialize(&serialized[..serialized.len() - 1]) {
+        Err(ZkGroupDeserializationFailure::Corrupted(_)) => {}
+        Err(e) => panic!("unexpected error: {e}"),
+        Ok(_) => panic!("should not deserialize"),
+    }
+}
+
+#[test]
+fn profile_key_credential_types() {
+    let server_secret_params = ServerSecretParams::generate(zkgroup::TEST_ARRAY_32);
+    let server_public_params = server_secret_params.get_public_params();
+    let group_secret_params =
+        GroupSecretParams::derive_from_master_key(GroupMasterKey::new(zkgroup::TEST_ARRAY_32_1));
+
+    let aci = libsignal_core::Aci::from_uuid_bytes(zkgroup::TEST_ARRAY_16);
+    let profile_key = ProfileKey::create(zkgroup::TEST_ARRAY_32_1);
+    let expiration = Timestamp::from_epoch_seconds(17 * SECONDS_PER_DAY);
+
+    let context = server_public_params.create_profile_key_credential_request_context(
+        zkgroup::TEST_ARRAY_32_2,
+        aci,
+        profile_key,
+    );
+    let request = context.get_request();
+    let response = server_secret_params
+        .issue_expiring_profile_key_credential(
+            zkgroup::TEST_ARRAY_32_3,
+            &request,
+            aci,
+            profile_key.get_commitment(aci),
+            expiration,
+        )
+        .expect("valid request");
+    let credential = server_public_params
+        .receive_expiring_profile_key_credential(
+            &context,
+            &response,
+            expiration.sub_seconds(SECONDS_PER_DAY),
+        )
+        .expect("valid response");
+    let presentation = server_public_params.create_expiring_profile_key_credential_presentation(
+        zkgroup::TEST_ARRAY_32_4,
+        group_secret_params,
+        credential,
+    );
+
+    assert_rejects_future_version(&context, |bytes| {
+        zkgroup::deserialize_versioned::<ProfileKeyCredentialRequestContext>(bytes)
+    });
+    assert_rejects_future_version(&request, |bytes| {
+        zkgroup::deserialize_versioned::<ProfileKeyCredentialRequest>(bytes)
+    });
+    assert_rejects_future_version(&response, |bytes| {
+        zkgroup::deserialize_versioned::<ExpiringProfileKeyCredentialResponse>(bytes)
+    });
+    assert_rejects_future_version(&credential, |bytes| {
+        zkgroup::deserialize_versioned::<ExpiringProfileKeyCredential>(bytes)
+    });
+    assert_rejects_future_version(&presentation, AnyProfileKeyCredentialPresentation::new);
+}
+
+#[test]
+fn receipt_credential_types() {
+    let server_secret_params = ServerSecretParams::generate(zkgroup::TEST_ARRAY_32);
+    let server_public_params = server_secret_params.get_public_params();
+
+    let context = server_public_params.create_receipt_credential_request_context(
+        zkgroup::TEST_ARRAY_32_1,
+        zkgroup::TEST_ARRAY_16,
+    );
+    let request = context.get_request();
+    let response = server_secret_params.issue_receipt_credential(
+        zkgroup::TEST_ARRAY_32_2,
+        &request,
+        Timestamp::from_epoch_seconds(31 * SECONDS_PER_DAY),
+        3,
+    );
+    let credential = server_public_params
+        .receive_receipt_credential(&context, &response)
+        .expect("valid response");
+    let presentation = server_public_params
+        .create_receipt_credential_presentation(zkgroup::TEST_ARRAY_32_3, &credential);
+
+    assert_rejects_future_version(&context, |bytes| {
+        zkgroup::deserialize_versioned::<ReceiptCredentialRequestContext>(bytes)
+    });
+    assert_rejects_future_version(&request, |bytes| {
+        zkgroup::deserialize_versioned::<ReceiptCredentialRequest>(bytes)
+    });
+    assert_rejects_future_version(&response, |bytes| {
+        zkgroup::deserialize_versioned::<ReceiptCredentialResponse>(bytes)
+    });
+    assert_rejects_future_version(&credential, |bytes| {
+        zkgroup::deserialize_versioned::<ReceiptCredential>(bytes)
+    });
+    assert_rejects_future_version(&presentation, |bytes| {
+        zkgroup::deserialize_versioned::<ReceiptCredentialPresentation>(bytes)
+    });
+}
+
+#[test]
+fn auth_credential_types() {
+    let server_secret_params = ServerSecretParams::generate(zkgroup::TEST_ARRAY_32);
+    let server_public_params = server_secret_params.get_public_params();
+    let group_secret_params =
+        GroupSecretParams::derive_from_master_key(GroupMasterKey::new(zkgroup::TEST_ARRAY_32_1));
+
+    let aci = libsignal_core::Aci::from_uuid_bytes(zkgroup::TEST_ARRAY_16);
+    let pni = libsignal_core::Pni::from_uuid_bytes(zkgroup::TEST_ARRAY_16_1);
+    let redemption_time = Timestamp::from_epoch_seconds(123456 * SECONDS_PER_DAY);
+
+    let response = AuthCredentialWithPniZkcResponse::issue_credential(
+        aci,
+        pni,
+        redemption_time,
+        &server_secret_params,
+        zkgroup::TEST_ARRAY_32_2,
+    );
+    let credential = response
+        .clone()
+        .receive(aci, pni, redemption_time, &server_public_params)
+        .expect("valid response");
+    let presentation = credential.present(
+        &server_public_params,
+        &group_secret_params,
+        zkgroup::TEST_ARRAY_32_3,
+    );
+
+    assert_rejects_future_version(&response, AuthCredentialWithPniResponse::new);
+    assert_rejects_future_version(&credential, AuthCredentialWithPni::new);
+    assert_rejects_future_version(&presentation, AnyAuthCredentialPresentation::new);
+}
+
+#[test]
+fn backup_auth_credential_types() {
+    let server_secret_params = GenericServerSecretParams::generate(zkgroup::TEST_ARRAY_32);
+    let server_public_params = server_secret_params.get_public_params();
+    let redemption_time = Timestamp::from_epoch_seconds(123456 * SECONDS_PER_DAY);
+
+    let context = BackupAuthCredentialRequestContext::new(
+        &zkgroup::TEST_ARRAY_32_1,
+        &uuid::Uuid::from_bytes(zkgroup::TEST_ARRAY_16),
+    );
+    let request = context.get_request();
+    let response = request.issue(
+        redemption_time,
+        BackupLevel::Media,
+        &server_secret_params,
+        zkgroup::TEST_ARRAY_32_2,
+    );
+
+    // Check these before they're consumed by receiving the credential.
+    assert_rejects_future_version(&context, |bytes| {
+        zkgroup::deserialize_versioned::<BackupAuthCredentialRequestContext>(bytes)
+    });
+    assert_rejects_future_version(&request, |bytes| {
+        zkgroup::deserialize_versioned::<BackupAuthCredentialRequest>(bytes)
+    });
+    assert_rejects_future_version(&response, |bytes| {
+        zkgroup::deserialize_versioned::<BackupAuthCredentialResponse>(bytes)
+    });
+
+    let credential = context
+        .receive(response, &server_public_params, redemption_time)
+        .expect("valid response");
+    let presentation = credential.present(&server_public_params, zkgroup::TEST_ARRAY_32_3);
+
+    assert_rejects_future_version(&credential, |bytes| {
+        zkgroup::deserialize_versioned::<BackupAuthCredential>(bytes)
+    });
+    assert_rejects_future_version(&presentation, |bytes| {
+        zkgroup::deserialize_versioned::<BackupAuthCredentialPresentation>(bytes)
+    });
+}
+
+#[test]
+fn backup_auth_credential_request_from_earlier_release() {
+    let request = zkgroup::deserialize_versioned::<BackupAuthCredentialRequest>(
+        BACKUP_AUTH_CREDENTIAL_REQUEST_V0,
+    )
+    .expect("supported version");
+    assert_eq!(
+        zkgroup::serialize(&request),
+        BACKUP_AUTH_CREDENTIAL_REQUEST_V0
+    );
+
+    // The same inputs still produce the same request.
+    let context = BackupAuthCredentialRequestContext::new(
+        &hex!("f9abbbffa7d424929765aecc84b604633c55ac1bce82e1ee06b79bc9a5629338"),
+        &uuid::Uuid::from_bytes(hex!("e74beed0e70f4cfdabbb7e3eb333bbac")),
+    );
+    assert_eq!(
+        zkgroup::serialize(&context.get_request()),
+        BACKUP_AUTH_CREDENTIAL_REQUEST_V0
+    );
+
+    // And the decoded request can still be issued and received.
+    let server_secret_params = GenericServerSecretParams::generate(zkgroup::TEST_ARRAY_32);
+    let redemption_time = Timestamp::from_epoch_seconds(123456 * SECONDS_PER_DAY);
+    let response = request.issue(
+        redemption_time,
+        BackupLevel::Messages,
+        &server_secret_params,
+        zkgroup::TEST_ARRAY_32_2,
+    );
+    let credential = context
+        .receive(
+            response,
+            &server_secret_params.get_public_params(),
+            redemption_time,
+        )
+        .expect("valid response");
+    assert_eq!(credential.backup_level(), BackupLevel::Messages);
+}
@@ -40,10 +40,18 @@ extension SignalCPromiseCdsiLookup: PromiseStruct {
     typealias Result = OpaquePointer
 }
 
+extension SignalCPromisePinMigrationOutcome: PromiseStruct {
+    typealias Result = OpaquePointer
+}
+
 extension SignalCPromiseFfiCdsiLookupResponse: PromiseStruct {
     typealias Result = SignalFfiCdsiLookupResponse
 }
 
+extension SignalCPromiseFfiCdsiLookupEntries: PromiseStruct {
+    typealias Result = SignalFfiCdsiLookupEntries
+}
+
 extension SignalCPromiseFfiChatResponse: PromiseStruct {
     typealias Result = SignalFfiChatResponse
 }
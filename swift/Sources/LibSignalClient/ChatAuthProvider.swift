@@ -0,0 +1,86 @@
+//
+// Copyright 2024 Signal Messenger, LLC.
+// SPDX-License-Identifier: AGPL-3.0-only
+//
+
+import Foundation
+import SignalFfi
+
+/// Supplies credentials for an ``AuthenticatedChatService``.
+///
+/// The current credentials are requested each time the service connects, so they can change
+/// without creating a new service. If the server rejects them, the provider gets one chance to
+/// refresh them before the connection attempt fails.
+///
+/// Both methods are called while connecting, from an arbitrary thread (but never concurrently), so
+/// they should return promptly.
+///
+/// This is currently only available in Swift; the Java and TypeScript APIs take fixed credentials.
+public protocol ChatAuthProvider: AnyObject {
+    /// The credentials to use for the next connection attempt.
+    func currentCredentials() -> (username: String, password: String)
+
+    /// Called when the server rejects the credentials from ``currentCredentials()``.
+    ///
+    /// Returns the credentials to retry with, or `nil` if there's nothing else to try.
+    func refreshCredentials() -> (username: String, password: String)?
+}
+
+internal final class ChatAuthProviderBridge {
+    let authProvider: any ChatAuthProvider
+
+    // Copies of the most recently provided credentials, kept alive until the next callback as
+    // required by SignalFfiChatAuthProviderStruct.
+    private var username: UnsafeMutablePointer<CChar>?
+    private var password: UnsafeMutablePointer<CChar>?
+
+    init(authProvider: any ChatAuthProvider) {
+        self.authProvider = authProvider
+    }
+
+    deinit {
+        free(self.username)
+        free(self.password)
+    }
+
+    private func provide(
+        _ credentials: (username: String, password: String),
+        username usernameOut: UnsafeMutablePointer<UnsafePointer<CChar>?>,
+        password passwordOut: UnsafeMutablePointer<UnsafePointer<CChar>?>
+    ) {
+        free(self.username)
+        free(self.password)
+        self.username = strdup(credentials.username)
+        self.password = strdup(credentials.password)
+        usernameOut.pointee = UnsafePointer(self.username)
+        passwordOut.pointee = UnsafePointer(self.password)
+    }
+
+    /// Creates an **owned** callback struct from this object.
+    ///
+    /// The resulting struct must eventually have its `destroy` callback invoked with its `ctx` as argument,
+    /// or the ChatAuthProviderBridge object used to construct it (`self`) will be leaked.
+    func makeAuthProviderStruct() -> SignalFfiChatAuthProviderStruct {
+        let current: SignalCurrentAuth = { rawCtx, usernameOut, passwordOut in
+            let bridge = Unmanaged<ChatAuthProviderBridge>.fromOpaque(rawCtx!).takeUnretainedValue()
+            bridge.provide(bridge.authProvider.currentCredentials(), username: usernameOut!, password: passwordOut!)
+        }
+        let refresh: SignalRefreshAuth = { rawCtx, usernameOut, passwordOut in
+            let bridge = Unmanaged<ChatAuthProviderBridge>.fromOpaque(rawCtx!).takeUnretainedValue()
+            guard let credentials = bridge.authProvider.refreshCredentials() else {
+                return false
+            }
+            bridge.provide(credentials, username: usernameOut!, password: passwordOut!)
+            return true
+        }
+
+        return .init(
+            ctx: Unmanaged.passRetained(self).toOpaque(),
+            current: current,
+            refresh: refresh,
+            destroy: { rawCtx in
+                _ = Unmanaged<AnyObject>.fromOpaque(rawCtx!).takeRetainedValue()
+            }
+        )
+    }
+}
@@ -28,7 +28,7 @@ public protocol ChatService: AnyObject {
 
     /// Sends a request to the Chat Service.
     ///
-    /// - Throws: ``SignalError/chatServiceInactive(_:)`` if you haven't called ``connect()``
+    /// - Throws: ``SignalError/chatServiceInactive(_:stableCode:)`` if you haven't called ``connect()``
     /// - Throws: Other ``SignalError``s for other kinds of failures.
     /// - SeeAlso: ``send(_:)``
     func send(_ request: Request) async throws -> Response
@@ -38,10 +38,17 @@ public protocol ChatService: AnyObject {
     /// In addition to the response, an object containing debug information about the request flow
     /// is returned.
     ///
-    /// - Throws: ``SignalError/chatServiceInactive(_:)`` if you haven't called ``connect()``
+    /// - Throws: ``SignalError/chatServiceInactive(_:stableCode:)`` if you haven't called ``connect()``
     /// - Throws: Other ``SignalError``s for other kinds of failures.
     /// - SeeAlso: ``send(_:)``
     func sendAndDebug(_ request: Request) async throws -> (Response, DebugInfo)
+
+    /// Returns a smoothed estimate of the round-trip time to the Chat Service, measured passively
+    /// over the current connection.
+    ///
+    /// Returns `nil` if there's no connection, or if the estimate isn't available yet. The estimate
+    /// starts over whenever the service reconnects.
+    func getRoundTripTimeEstimate() -> TimeInterval?
 }
 
 extension ChatService {
@@ -52,7 +59,8 @@ extension ChatService {
 
 /// Represents an API of authenticated communication with the Chat Service.
 ///
-/// An instance of this object is obtained via call to ``Net/createAuthenticatedChatService(username:password:receiveStories:)``.
+/// An instance of this object is obtained via call to ``Net/createAuthenticatedChatService(username:password:receiveStories:)``
+/// or ``Net/createAuthenticatedChatService(authProvider:receiveStories:)``.
 public class AuthenticatedChatService: NativeHandleOwner, ChatService {
     internal let tokioAsyncContext: TokioAsyncContext
 
@@ -65,6 +73,16 @@ public class AuthenticatedChatService: NativeHandleOwner, ChatService {
         super.init(owned: handle!)
     }
 
+    internal init(tokioAsyncContext: TokioAsyncContext, connectionManager: ConnectionManager, authProvider: any ChatAuthProvider, receiveStories: Bool) {
+        var handle: OpaquePointer?
+        connectionManager.withNativeHandle { connectionManager in
+            var authProviderStruct = ChatAuthProviderBridge(authProvider: authProvider).makeAuthProviderStruct()
+            failOnError(signal_chat_service_new_auth_with_provider(&handle, connectionManager, &authProviderStruct, receiveStories))
+        }
+        self.tokioAsyncContext = tokioAsyncContext
+        super.init(owned: handle!)
+    }
+
     internal required init(owned handle: OpaquePointer) {
         fatalError("should not be called directly for a ChatService")
     }
@@ -97,9 +115,9 @@ public class AuthenticatedChatService: NativeHandleOwner, ChatService {
     /// Calling this method will result in starting to accept incoming requests from the Chat
     /// Service. You should set a listener first using ``setListener(_:)``.
     ///
-    /// - Throws: ``SignalError/appExpired(_:)`` if the current app version is too old (as judged by
+    /// - Throws: ``SignalError/appExpired(_:stableCode:)`` if the current app version is too old (as judged by
     ///   the server).
-    /// - Throws: ``SignalError/deviceDeregistered(_:)`` if the current device has been deregistered
+    /// - Throws: ``SignalError/deviceDeregistered(_:stableCode:)`` if the current device has been deregistered
     ///   or delinked.
     /// - Throws: Other ``SignalError``s for other kinds of failures.
     @discardableResult
@@ -127,9 +145,23 @@ public class AuthenticatedChatService: NativeHandleOwner, ChatService {
         }
     }
 
+    public func getRoundTripTimeEstimate() -> TimeInterval? {
+        let millis = withNativeHandle { chatService in
+            failOnError {
+                try invokeFnReturningInteger {
+                    signal_chat_service_rtt_estimate_millis_auth($0, chatService)
+                }
+            }
+        }
+        if millis == 0xFFFF_FFFF {
+            return nil
+        }
+        return TimeInterval(millis) / 1000
+    }
+
     /// Sends request to the Chat Service over an authenticated channel.
     ///
-    /// - Throws: ``SignalError/chatServiceInactive(_:)`` if you haven't called ``connect()``
+    /// - Throws: ``SignalError/chatServiceInactive(_:stableCode:)`` if you haven't called ``connect()``
     /// - Throws: Other ``SignalError``s for other kinds of failures.
     /// - SeeAlso: ``sendAndDebug(_:)``
     public func send(_ request: Request) async throws -> Response {
@@ -150,7 +182,7 @@ public class AuthenticatedChatService: NativeHandleOwner, ChatService {
     /// In addition to the response, an object containing debug information about the request flow
     /// is returned.
     ///
-    /// - Throws: ``SignalError/chatServiceInactive(_:)`` if you haven't called ``connect()``
+    /// - Throws: ``SignalError/chatServiceInactive(_:stableCode:)`` if you haven't called ``connect()``
     /// - Throws: Other ``SignalError``s for other kinds of failures.
     /// - SeeAlso: ``send(_:)``
     public func sendAndDebug(_ request: Request) async throws -> (Response, DebugInfo) {
@@ -211,7 +243,7 @@ public class UnauthenticatedChatService: NativeHandleOwner, ChatService {
     /// if the connection is lost for any reason other than the call to ``disconnect()``, an
     /// automatic reconnect attempt will be made.
     ///
-    /// - Throws: ``SignalError/appExpired(_:)`` if the current app version is too old (as judged by
+    /// - Throws: ``SignalError/appExpired(_:stableCode:)`` if the current app version is too old (as judged by
     ///   the server).
     /// - Throws: Other ``SignalError``s for other kinds of failures.
     @discardableResult
@@ -239,9 +271,23 @@ public class UnauthenticatedChatService: NativeHandleOwner, ChatService {
         }
     }
 
+    public func getRoundTripTimeEstimate() -> TimeInterval? {
+        let millis = withNativeHandle { chatService in
+            failOnError {
+                try invokeFnReturningInteger {
+                    signal_chat_service_rtt_estimate_millis_unauth($0, chatService)
+                }
+            }
+        }
+        if millis == 0xFFFF_FFFF {
+            return nil
+        }
+        return TimeInterval(millis) / 1000
+    }
+
     /// Sends request to the Chat Service over an unauthenticated channel.
     ///
-    /// - Throws: ``SignalError/chatServiceInactive(_:)`` if you haven't called ``connect()``
+    /// - Throws: ``SignalError/chatServiceInactive(_:stableCode:)`` if you haven't called ``connect()``
     /// - Throws: Other ``SignalError``s for other kinds of failures.
     /// - SeeAlso: ``sendAndDebug(_:)``
     public func send(_ request: Request) async throws -> Response {
@@ -262,7 +308,7 @@ public class UnauthenticatedChatService: NativeHandleOwner, ChatService {
     /// In addition to the response, an object containing debug information about the request flow
     /// is returned.
     ///
-    /// - Throws: ``SignalError/chatServiceInactive(_:)`` if you haven't called ``connect()``
+    /// - Throws: ``SignalError/chatServiceInactive(_:stableCode:)`` if you haven't called ``connect()``
     /// - Throws: Other ``SignalError``s for other kinds of failures.
     /// - SeeAlso: ``send(_:)``
     public func sendAndDebug(_ request: Request) async throws -> (Response, DebugInfo) {
@@ -11,6 +11,11 @@ public enum IpType: UInt8 {
     case unknown, ipv4, ipv6
 }
 
+public enum RouteType: UInt8 {
+    // Must be kept in sync with route_type_code in the Rust bridge.
+    case unknown, direct, proxyF, proxyG, tlsProxy, socksProxy
+}
+
 public struct ChatRequest: Equatable {
     public var method: String
     public var pathAndQuery: String
@@ -114,12 +119,20 @@ public struct ChatResponse: Equatable {
 public struct ChatServiceDebugInfo: Equatable {
     public var ipType: IpType
     public var duration: TimeInterval
+    /// Superseded by the other fields; will be removed in a future release.
     public var connectionInfo: String
+    public var routeType: RouteType
+    /// The address of the proxy for proxied connections, or `nil` if no connection was made.
+    public var remoteIp: String?
+    public var remotePort: UInt16?
 
-    public init(ipType: IpType, duration: TimeInterval, connectionInfo: String) {
+    public init(ipType: IpType, duration: TimeInterval, connectionInfo: String, routeType: RouteType = .unknown, remoteIp: String? = nil, remotePort: UInt16? = nil) {
         self.ipType = ipType
         self.duration = duration
         self.connectionInfo = connectionInfo
+        self.routeType = routeType
+        self.remoteIp = remoteIp
+        self.remotePort = remotePort
     }
 
     internal init(consuming rawDebugInfo: SignalFfiChatServiceDebugInfo) {
@@ -128,6 +141,14 @@ public struct ChatServiceDebugInfo: Equatable {
         self.ipType = IpType(rawValue: rawDebugInfo.raw_ip_type) ?? .unknown
         self.duration = rawDebugInfo.duration_secs
         self.connectionInfo = String(cString: rawDebugInfo.connection_info)
+        self.routeType = RouteType(rawValue: rawDebugInfo.raw_route_type) ?? .unknown
+        if let remoteIp = rawDebugInfo.remote_ip {
+            self.remoteIp = String(cString: remoteIp)
+            self.remotePort = rawDebugInfo.remote_port
+        } else {
+            self.remoteIp = nil
+            self.remotePort = nil
+        }
     }
 }
 
@@ -151,6 +172,7 @@ extension SignalFfiChatResponse {
 extension SignalFfiChatServiceDebugInfo {
     fileprivate mutating func free() {
         signal_free_string(connection_info)
+        signal_free_string(remote_ip)
         // Zero out all the fields to be sure they won't be reused.
         self = .init()
     }
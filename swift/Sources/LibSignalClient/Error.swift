@@ -7,65 +7,145 @@ import Foundation
 import SignalFfi
 
 public enum SignalError: Error {
-    case invalidState(String)
-    case internalError(String)
-    case nullParameter(String)
-    case invalidArgument(String)
-    case invalidType(String)
-    case invalidUtf8String(String)
-    case protobufError(String)
-    case legacyCiphertextVersion(String)
-    case unknownCiphertextVersion(String)
-    case unrecognizedMessageVersion(String)
-    case invalidMessage(String)
-    case invalidKey(String)
-    case invalidSignature(String)
-    case invalidAttestationData(String)
-    case fingerprintVersionMismatch(String)
-    case fingerprintParsingError(String)
-    case sealedSenderSelfSend(String)
-    case untrustedIdentity(String)
-    case invalidKeyIdentifier(String)
-    case sessionNotFound(String)
-    case invalidSession(String)
-    case invalidRegistrationId(address: ProtocolAddress, message: String)
-    case invalidSenderKeySession(distributionId: UUID, message: String)
-    case duplicatedMessage(String)
-    case verificationFailed(String)
-    case nicknameCannotBeEmpty(String)
-    case nicknameCannotStartWithDigit(String)
-    case missingSeparator(String)
-    case badDiscriminatorCharacter(String)
-    case badNicknameCharacter(String)
-    case nicknameTooShort(String)
-    case nicknameTooLong(String)
-    case usernameLinkInvalidEntropyDataLength(String)
-    case usernameLinkInvalid(String)
-    case usernameDiscriminatorCannotBeEmpty(String)
-    case usernameDiscriminatorCannotBeZero(String)
-    case usernameDiscriminatorCannotBeSingleDigit(String)
-    case usernameDiscriminatorCannotHaveLeadingZeros(String)
-    case usernameDiscriminatorTooLarge(String)
-    case ioError(String)
-    case invalidMediaInput(String)
-    case unsupportedMediaInput(String)
-    case callbackError(String)
-    case webSocketError(String)
-    case connectionTimeoutError(String)
-    case connectionFailed(String)
-    case networkProtocolError(String)
-    case cdsiInvalidToken(String)
-    case rateLimitedError(retryAfter: TimeInterval, message: String)
-    case svrDataMissing(String)
-    case svrRestoreFailed(triesRemaining: UInt32, message: String)
-    case svrRotationMachineTooManySteps(String)
-    case chatServiceInactive(String)
-    case chatServiceIntentionallyDisconnected(String)
-    case appExpired(String)
-    case deviceDeregistered(String)
-    case backupValidation(unknownFields: [String], message: String)
+    case invalidState(String, stableCode: UInt32? = nil)
+    case internalError(String, stableCode: UInt32? = nil)
+    case nullParameter(String, stableCode: UInt32? = nil)
+    case invalidArgument(String, stableCode: UInt32? = nil)
+    case invalidType(String, stableCode: UInt32? = nil)
+    case invalidUtf8String(String, stableCode: UInt32? = nil)
+    case protobufError(String, stableCode: UInt32? = nil)
+    case legacyCiphertextVersion(String, stableCode: UInt32? = nil)
+    case unknownCiphertextVersion(String, stableCode: UInt32? = nil)
+    case unrecognizedMessageVersion(String, stableCode: UInt32? = nil)
+    case invalidMessage(String, stableCode: UInt32? = nil)
+    case invalidKey(String, stableCode: UInt32? = nil)
+    case invalidSignature(String, stableCode: UInt32? = nil)
+    case invalidAttestationData(String, stableCode: UInt32? = nil)
+    case attestationExpired(String, stableCode: UInt32? = nil)
+    case attestationMeasurementMismatch(String, stableCode: UInt32? = nil)
+    case fingerprintVersionMismatch(String, stableCode: UInt32? = nil)
+    case fingerprintParsingError(String, stableCode: UInt32? = nil)
+    case sealedSenderSelfSend(String, stableCode: UInt32? = nil)
+    case untrustedIdentity(String, stableCode: UInt32? = nil)
+    case invalidKeyIdentifier(String, stableCode: UInt32? = nil)
+    case sessionNotFound(String, stableCode: UInt32? = nil)
+    case invalidSession(String, stableCode: UInt32? = nil)
+    case invalidRegistrationId(address: ProtocolAddress, message: String, stableCode: UInt32? = nil)
+    case invalidSenderKeySession(distributionId: UUID, message: String, stableCode: UInt32? = nil)
+    case duplicatedMessage(String, stableCode: UInt32? = nil)
+    case verificationFailed(String, stableCode: UInt32? = nil)
+    case nicknameCannotBeEmpty(String, stableCode: UInt32? = nil)
+    case nicknameCannotStartWithDigit(String, stableCode: UInt32? = nil)
+    case missingSeparator(String, stableCode: UInt32? = nil)
+    case badDiscriminatorCharacter(String, stableCode: UInt32? = nil)
+    case badNicknameCharacter(String, stableCode: UInt32? = nil)
+    case nicknameTooShort(String, stableCode: UInt32? = nil)
+    case nicknameTooLong(String, stableCode: UInt32? = nil)
+    case usernameLinkInvalidEntropyDataLength(String, stableCode: UInt32? = nil)
+    case usernameLinkInvalid(String, stableCode: UInt32? = nil)
+    case usernameDiscriminatorCannotBeEmpty(String, stableCode: UInt32? = nil)
+    case usernameDiscriminatorCannotBeZero(String, stableCode: UInt32? = nil)
+    case usernameDiscriminatorCannotBeSingleDigit(String, stableCode: UInt32? = nil)
+    case usernameDiscriminatorCannotHaveLeadingZeros(String, stableCode: UInt32? = nil)
+    case usernameDiscriminatorTooLarge(String, stableCode: UInt32? = nil)
+    case ioError(String, stableCode: UInt32? = nil)
+    case invalidMediaInput(String, stableCode: UInt32? = nil)
+    case unsupportedMediaInput(String, stableCode: UInt32? = nil)
+    case callbackError(String, stableCode: UInt32? = nil)
+    case webSocketError(String, stableCode: UInt32? = nil)
+    case connectionTimeoutError(String, stableCode: UInt32? = nil)
+    case connectionFailed(String, stableCode: UInt32? = nil)
+    case networkProtocolError(String, stableCode: UInt32? = nil)
+    case cdsiInvalidToken(String, stableCode: UInt32? = nil)
+    case rateLimitedError(retryAfter: TimeInterval, message: String, stableCode: UInt32? = nil)
+    case svrDataMissing(String, stableCode: UInt32? = nil)
+    case svrRestoreFailed(triesRemaining: UInt32, message: String, stableCode: UInt32? = nil)
+    case svrRotationMachineTooManySteps(String, stableCode: UInt32? = nil)
+    case chatServiceInactive(String, stableCode: UInt32? = nil)
+    case chatServiceIntentionallyDisconnected(String, stableCode: UInt32? = nil)
+    case appExpired(String, stableCode: UInt32? = nil)
+    case deviceDeregistered(String, stableCode: UInt32? = nil)
+    case backupValidation(unknownFields: [String], message: String, stableCode: UInt32? = nil)
 
-    case unknown(UInt32, String)
+    case unknown(UInt32, String, stableCode: UInt32? = nil)
+}
+
+extension SignalError {
+    /// A number identifying the most specific cause of this error, even when its case is more
+    /// general.
+    ///
+    /// For example, a DNS failure while connecting to CDSI is thrown as
+    /// ``SignalError/ioError(_:stableCode:)``, but its stable code still identifies it as a DNS
+    /// failure. Stable codes keep their number and meaning once released, and numbers are never
+    /// reused, but new codes may be added at any time. See
+    /// `rust/bridge/shared/types/src/error_code.rs` for the full list.
+    ///
+    /// `nil` for errors that don't come from the Rust library.
+    public var stableCode: UInt32? {
+        switch self {
+        case .invalidState(_, let stableCode),
+             .internalError(_, let stableCode),
+             .nullParameter(_, let stableCode),
+             .invalidArgument(_, let stableCode),
+             .invalidType(_, let stableCode),
+             .invalidUtf8String(_, let stableCode),
+             .protobufError(_, let stableCode),
+             .legacyCiphertextVersion(_, let stableCode),
+             .unknownCiphertextVersion(_, let stableCode),
+             .unrecognizedMessageVersion(_, let stableCode),
+             .invalidMessage(_, let stableCode),
+             .invalidKey(_, let stableCode),
+             .invalidSignature(_, let stableCode),
+             .invalidAttestationData(_, let stableCode),
+             .attestationExpired(_, let stableCode),
+             .attestationMeasurementMismatch(_, let stableCode),
+             .fingerprintVersionMismatch(_, let stableCode),
+             .fingerprintParsingError(_, let stableCode),
+             .sealedSenderSelfSend(_, let stableCode),
+             .untrustedIdentity(_, let stableCode),
+             .invalidKeyIdentifier(_, let stableCode),
+             .sessionNotFound(_, let stableCode),
+             .invalidSession(_, let stableCode),
+             .invalidRegistrationId(_, _, let stableCode),
+             .invalidSenderKeySession(_, _, let stableCode),
+             .duplicatedMessage(_, let stableCode),
+             .verificationFailed(_, let stableCode),
+             .nicknameCannotBeEmpty(_, let stableCode),
+             .nicknameCannotStartWithDigit(_, let stableCode),
+             .missingSeparator(_, let stableCode),
+             .badDiscriminatorCharacter(_, let stableCode),
+             .badNicknameCharacter(_, let stableCode),
+             .nicknameTooShort(_, let stableCode),
+             .nicknameTooLong(_, let stableCode),
+             .usernameLinkInvalidEntropyDataLength(_, let stableCode),
+             .usernameLinkInvalid(_, let stableCode),
+             .usernameDiscriminatorCannotBeEmpty(_, let stableCode),
+             .usernameDiscriminatorCannotBeZero(_, let stableCode),
+             .usernameDiscriminatorCannotBeSingleDigit(_, let stableCode),
+             .usernameDiscriminatorCannotHaveLeadingZeros(_, let stableCode),
+             .usernameDiscriminatorTooLarge(_, let stableCode),
+             .ioError(_, let stableCode),
+             .invalidMediaInput(_, let stableCode),
+             .unsupportedMediaInput(_, let stableCode),
+             .callbackError(_, let stableCode),
+             .webSocketError(_, let stableCode),
+             .connectionTimeoutError(_, let stableCode),
+             .connectionFailed(_, let stableCode),
+             .networkProtocolError(_, let stableCode),
+             .cdsiInvalidToken(_, let stableCode),
+             .rateLimitedError(_, _, let stableCode),
+             .svrDataMissing(_, let stableCode),
+             .svrRestoreFailed(_, _, let stableCode),
+             .svrRotationMachineTooManySteps(_, let stableCode),
+             .chatServiceInactive(_, let stableCode),
+             .chatServiceIntentionallyDisconnected(_, let stableCode),
+             .appExpired(_, let stableCode),
+             .deviceDeregistered(_, let stableCode),
+             .backupValidation(_, _, let stableCode),
+             .unknown(_, _, let stableCode):
+            return stableCode
+        }
+    }
 }
 
 internal typealias SignalFfiErrorRef = OpaquePointer
@@ -87,6 +167,7 @@ internal func checkError(_ error: SignalFfiErrorRef?) throws {
     guard let error = error else { return }
 
     let errType = signal_error_get_type(error)
+    let stableCode = signal_error_get_stable_code(error)
     // If this actually throws we'd have an infinite loop before we hit the 'try!'.
     let errStr = try! invokeFnReturningString {
         signal_error_get_message(error, $0)
@@ -98,136 +179,140 @@ internal func checkError(_ error: SignalFfiErrorRef?) throws {
         // Special case: don't use SignalError for this one.
         throw CancellationError()
     case SignalErrorCodeInvalidState:
-        throw SignalError.invalidState(errStr)
+        throw SignalError.invalidState(errStr, stableCode: stableCode)
     case SignalErrorCodeInternalError:
-        throw SignalError.internalError(errStr)
+        throw SignalError.internalError(errStr, stableCode: stableCode)
     case SignalErrorCodeNullParameter:
-        throw SignalError.nullParameter(errStr)
+        throw SignalError.nullParameter(errStr, stableCode: stableCode)
     case SignalErrorCodeInvalidArgument:
-        throw SignalError.invalidArgument(errStr)
+        throw SignalError.invalidArgument(errStr, stableCode: stableCode)
     case SignalErrorCodeInvalidType:
-        throw SignalError.invalidType(errStr)
+        throw SignalError.invalidType(errStr, stableCode: stableCode)
     case SignalErrorCodeInvalidUtf8String:
-        throw SignalError.invalidUtf8String(errStr)
+        throw SignalError.invalidUtf8String(errStr, stableCode: stableCode)
     case SignalErrorCodeProtobufError:
-        throw SignalError.protobufError(errStr)
+        throw SignalError.protobufError(errStr, stableCode: stableCode)
     case SignalErrorCodeLegacyCiphertextVersion:
-        throw SignalError.legacyCiphertextVersion(errStr)
+        throw SignalError.legacyCiphertextVersion(errStr, stableCode: stableCode)
     case SignalErrorCodeUnknownCiphertextVersion:
-        throw SignalError.unknownCiphertextVersion(errStr)
+        throw SignalError.unknownCiphertextVersion(errStr, stableCode: stableCode)
     case SignalErrorCodeUnrecognizedMessageVersion:
-        throw SignalError.unrecognizedMessageVersion(errStr)
+        throw SignalError.unrecognizedMessageVersion(errStr, stableCode: stableCode)
     case SignalErrorCodeInvalidMessage:
-        throw SignalError.invalidMessage(errStr)
+        throw SignalError.invalidMessage(errStr, stableCode: stableCode)
     case SignalErrorCodeFingerprintParsingError:
-        throw SignalError.fingerprintParsingError(errStr)
+        throw SignalError.fingerprintParsingError(errStr, stableCode: stableCode)
     case SignalErrorCodeSealedSenderSelfSend:
-        throw SignalError.sealedSenderSelfSend(errStr)
+        throw SignalError.sealedSenderSelfSend(errStr, stableCode: stableCode)
     case SignalErrorCodeInvalidKey:
-        throw SignalError.invalidKey(errStr)
+        throw SignalError.invalidKey(errStr, stableCode: stableCode)
     case SignalErrorCodeInvalidSignature:
-        throw SignalError.invalidSignature(errStr)
+        throw SignalError.invalidSignature(errStr, stableCode: stableCode)
     case SignalErrorCodeInvalidAttestationData:
-        throw SignalError.invalidAttestationData(errStr)
+        throw SignalError.invalidAttestationData(errStr, stableCode: stableCode)
+    case SignalErrorCodeAttestationExpired:
+        throw SignalError.attestationExpired(errStr, stableCode: stableCode)
+    case SignalErrorCodeAttestationMeasurementMismatch:
+        throw SignalError.attestationMeasurementMismatch(errStr, stableCode: stableCode)
     case SignalErrorCodeFingerprintVersionMismatch:
-        throw SignalError.fingerprintVersionMismatch(errStr)
+        throw SignalError.fingerprintVersionMismatch(errStr, stableCode: stableCode)
     case SignalErrorCodeUntrustedIdentity:
-        throw SignalError.untrustedIdentity(errStr)
+        throw SignalError.untrustedIdentity(errStr, stableCode: stableCode)
     case SignalErrorCodeInvalidKeyIdentifier:
-        throw SignalError.invalidKeyIdentifier(errStr)
+        throw SignalError.invalidKeyIdentifier(errStr, stableCode: stableCode)
     case SignalErrorCodeSessionNotFound:
-        throw SignalError.sessionNotFound(errStr)
+        throw SignalError.sessionNotFound(errStr, stableCode: stableCode)
     case SignalErrorCodeInvalidSession:
-        throw SignalError.invalidSession(errStr)
+        throw SignalError.invalidSession(errStr, stableCode: stableCode)
     case SignalErrorCodeInvalidRegistrationId:
         let address: ProtocolAddress = try invokeFnReturningNativeHandle {
             signal_error_get_address(error, $0)
         }
-        throw SignalError.invalidRegistrationId(address: address, message: errStr)
+        throw SignalError.invalidRegistrationId(address: address, message: errStr, stableCode: stableCode)
     case SignalErrorCodeInvalidSenderKeySession:
         let distributionId = try invokeFnReturningUuid {
             signal_error_get_uuid(error, $0)
         }
-        throw SignalError.invalidSenderKeySession(distributionId: distributionId, message: errStr)
+        throw SignalError.invalidSenderKeySession(distributionId: distributionId, message: errStr, stableCode: stableCode)
     case SignalErrorCodeDuplicatedMessage:
-        throw SignalError.duplicatedMessage(errStr)
+        throw SignalError.duplicatedMessage(errStr, stableCode: stableCode)
     case SignalErrorCodeVerificationFailure:
-        throw SignalError.verificationFailed(errStr)
+        throw SignalError.verificationFailed(errStr, stableCode: stableCode)
     case SignalErrorCodeUsernameCannotBeEmpty:
-        throw SignalError.nicknameCannotBeEmpty(errStr)
+        throw SignalError.nicknameCannotBeEmpty(errStr, stableCode: stableCode)
     case SignalErrorCodeUsernameCannotStartWithDigit:
-        throw SignalError.nicknameCannotStartWithDigit(errStr)
+        throw SignalError.nicknameCannotStartWithDigit(errStr, stableCode: stableCode)
     case SignalErrorCodeUsernameMissingSeparator:
-        throw SignalError.missingSeparator(errStr)
+        throw SignalError.missingSeparator(errStr, stableCode: stableCode)
     case SignalErrorCodeUsernameBadDiscriminatorCharacter:
-        throw SignalError.badDiscriminatorCharacter(errStr)
+        throw SignalError.badDiscriminatorCharacter(errStr, stableCode: stableCode)
     case SignalErrorCodeUsernameBadNicknameCharacter:
-        throw SignalError.badNicknameCharacter(errStr)
+        throw SignalError.badNicknameCharacter(errStr, stableCode: stableCode)
     case SignalErrorCodeUsernameTooShort:
-        throw SignalError.nicknameTooShort(errStr)
+        throw SignalError.nicknameTooShort(errStr, stableCode: stableCode)
     case SignalErrorCodeUsernameTooLong:
-        throw SignalError.nicknameTooLong(errStr)
+        throw SignalError.nicknameTooLong(errStr, stableCode: stableCode)
     case SignalErrorCodeUsernameDiscriminatorCannotBeEmpty:
-        throw SignalError.usernameDiscriminatorCannotBeEmpty(errStr)
+        throw SignalError.usernameDiscriminatorCannotBeEmpty(errStr, stableCode: stableCode)
     case SignalErrorCodeUsernameDiscriminatorCannotBeZero:
-        throw SignalError.usernameDiscriminatorCannotBeZero(errStr)
+        throw SignalError.usernameDiscriminatorCannotBeZero(errStr, stableCode: stableCode)
     case SignalErrorCodeUsernameDiscriminatorCannotBeSingleDigit:
-        throw SignalError.usernameDiscriminatorCannotBeSingleDigit(errStr)
+        throw SignalError.usernameDiscriminatorCannotBeSingleDigit(errStr, stableCode: stableCode)
     case SignalErrorCodeUsernameDiscriminatorCannotHaveLeadingZeros:
-        throw SignalError.usernameDiscriminatorCannotHaveLeadingZeros(errStr)
+        throw SignalError.usernameDiscriminatorCannotHaveLeadingZeros(errStr, stableCode: stableCode)
     case SignalErrorCodeUsernameDiscriminatorTooLarge:
-        throw SignalError.usernameDiscriminatorTooLarge(errStr)
+        throw SignalError.usernameDiscriminatorTooLarge(errStr, stableCode: stableCode)
     case SignalErrorCodeUsernameLinkInvalidEntropyDataLength:
-        throw SignalError.usernameLinkInvalidEntropyDataLength(errStr)
+        throw SignalError.usernameLinkInvalidEntropyDataLength(errStr, stableCode: stableCode)
     case SignalErrorCodeUsernameLinkInvalid:
-        throw SignalError.usernameLinkInvalid(errStr)
+        throw SignalError.usernameLinkInvalid(errStr, stableCode: stableCode)
     case SignalErrorCodeIoError:
-        throw SignalError.ioError(errStr)
+        throw SignalError.ioError(errStr, stableCode: stableCode)
     case SignalErrorCodeInvalidMediaInput:
-        throw SignalError.invalidMediaInput(errStr)
+        throw SignalError.invalidMediaInput(errStr, stableCode: stableCode)
     case SignalErrorCodeUnsupportedMediaInput:
-        throw SignalError.unsupportedMediaInput(errStr)
+        throw SignalError.unsupportedMediaInput(errStr, stableCode: stableCode)
     case SignalErrorCodeCallbackError:
-        throw SignalError.callbackError(errStr)
+        throw SignalError.callbackError(errStr, stableCode: stableCode)
     case SignalErrorCodeWebSocket:
-        throw SignalError.webSocketError(errStr)
+        throw SignalError.webSocketError(errStr, stableCode: stableCode)
     case SignalErrorCodeConnectionTimedOut:
-        throw SignalError.connectionTimeoutError(errStr)
+        throw SignalError.connectionTimeoutError(errStr, stableCode: stableCode)
     case SignalErrorCodeConnectionFailed:
-        throw SignalError.connectionFailed(errStr)
+        throw SignalError.connectionFailed(errStr, stableCode: stableCode)
     case SignalErrorCodeNetworkProtocol:
-        throw SignalError.networkProtocolError(errStr)
+        throw SignalError.networkProtocolError(errStr, stableCode: stableCode)
     case SignalErrorCodeCdsiInvalidToken:
-        throw SignalError.cdsiInvalidToken(errStr)
+        throw SignalError.cdsiInvalidToken(errStr, stableCode: stableCode)
     case SignalErrorCodeRateLimited:
         let retryAfterSeconds = try invokeFnReturningInteger {
             signal_error_get_retry_after_seconds(error, $0)
         }
-        throw SignalError.rateLimitedError(retryAfter: TimeInterval(retryAfterSeconds), message: errStr)
+        throw SignalError.rateLimitedError(retryAfter: TimeInterval(retryAfterSeconds), message: errStr, stableCode: stableCode)
     case SignalErrorCodeSvrDataMissing:
-        throw SignalError.svrDataMissing(errStr)
+        throw SignalError.svrDataMissing(errStr, stableCode: stableCode)
     case SignalErrorCodeSvrRestoreFailed:
         let triesRemaining = try invokeFnReturningInteger {
             signal_error_get_tries_remaining(error, $0)
         }
-        throw SignalError.svrRestoreFailed(triesRemaining: triesRemaining, message: errStr)
+        throw SignalError.svrRestoreFailed(triesRemaining: triesRemaining, message: errStr, stableCode: stableCode)
     case SignalErrorCodeSvrRotationMachineTooManySteps:
-        throw SignalError.svrRotationMachineTooManySteps(errStr)
+        throw SignalError.svrRotationMachineTooManySteps(errStr, stableCode: stableCode)
     case SignalErrorCodeChatServiceInactive:
-        throw SignalError.chatServiceInactive(errStr)
+        throw SignalError.chatServiceInactive(errStr, stableCode: stableCode)
     case SignalErrorCodeChatServiceIntentionallyDisconnected:
-        throw SignalError.chatServiceIntentionallyDisconnected(errStr)
+        throw SignalError.chatServiceIntentionallyDisconnected(errStr, stableCode: stableCode)
     case SignalErrorCodeAppExpired:
-        throw SignalError.appExpired(errStr)
+        throw SignalError.appExpired(errStr, stableCode: stableCode)
     case SignalErrorCodeDeviceDeregistered:
-        throw SignalError.deviceDeregistered(errStr)
+        throw SignalError.deviceDeregistered(errStr, stableCode: stableCode)
     case SignalErrorCodeBackupValidation:
         let unknownFields = try invokeFnReturningStringArray {
             signal_error_get_unknown_fields(error, $0)
         }
-        throw SignalError.backupValidation(unknownFields: unknownFields, message: errStr)
+        throw SignalError.backupValidation(unknownFields: unknownFields, message: errStr, stableCode: stableCode)
     default:
-        throw SignalError.unknown(errType, errStr)
+        throw SignalError.unknown(errType, errStr, stableCode: stableCode)
     }
 }
 
@@ -30,6 +30,20 @@ public struct IdentityKey: Equatable {
         }
         return result
     }
+
+    /// Verifies a signature from ``IdentityKeyPair/createPniSignature(aciIdentity:)``, which claims
+    /// that `pniIdentity` belongs to the same account as this ACI identity key.
+    ///
+    /// Throws ``SignalError/invalidArgument(_:stableCode:)`` if the signature has the wrong length.
+    public func verifyPniSignature<Bytes: ContiguousBytes>(_ pniIdentity: IdentityKey, signature: Bytes) throws -> Bool {
+        var result = false
+        try withNativeHandles(publicKey, pniIdentity.publicKey) { aciHandle, pniHandle in
+            try signature.withUnsafeBorrowedBuffer { signatureBuffer in
+                try checkError(signal_pni_signature_verify(&result, aciHandle, pniHandle, signatureBuffer))
+            }
+        }
+        return result
+    }
 }
 
 public struct IdentityKeyPair {
@@ -81,4 +95,15 @@ public struct IdentityKeyPair {
             }
         }
     }
+
+    /// Signs `aciIdentity` with this PNI identity key pair, as sent in a `PniSignatureMessage`.
+    public func createPniSignature(aciIdentity: IdentityKey) -> [UInt8] {
+        return withNativeHandles(self.publicKey, self.privateKey, aciIdentity.publicKey) { publicKey, privateKey, aci in
+            failOnError {
+                try invokeFnReturningArray {
+                    signal_pni_signature_create($0, publicKey, privateKey, aci)
+                }
+            }
+        }
+    }
 }
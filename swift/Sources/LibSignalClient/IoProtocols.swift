@@ -30,6 +30,22 @@ public protocol SignalInputStream: AnyObject {
     func skip(by amount: UInt64) throws
 }
 
+/// An output stream of bytes.
+///
+/// This protocol is implemented for `FileHandle`.
+public protocol SignalOutputStream: AnyObject {
+    /// Write all of the given bytes to the output stream.
+    ///
+    /// - Parameter buffer: The bytes to write.
+    /// - Throws: If an I/O error occurred while writing to the output.
+    func write(from buffer: UnsafeRawBufferPointer) throws
+
+    /// Make sure everything written so far has reached its destination.
+    ///
+    /// - Throws: If an I/O error occurred while flushing the output.
+    func flush() throws
+}
+
 /// An error thrown by `SignalInputStreamAdapter`.
 public enum SignalInputStreamError: Error {
     /// The end of the input stream was reached while attempting to `skip()`.
@@ -47,6 +63,16 @@ extension FileHandle: SignalInputStream {
     }
 }
 
+extension FileHandle: SignalOutputStream {
+    public func write(from buffer: UnsafeRawBufferPointer) throws {
+        self.write(Data(buffer))
+    }
+
+    public func flush() throws {
+        self.synchronizeFile()
+    }
+}
+
 /// An adapter implementing `SignalInputStream` for any `Collection<UInt8>`.
 public class SignalInputStreamAdapter<Inner>: SignalInputStream where Inner: Collection<UInt8> {
     var inner: Inner.SubSequence
@@ -39,3 +39,34 @@ internal func withInputStream<Result>(_ stream: SignalInputStream, _ body: (Unsa
         return try body(&ffiStream)
     }
 }
+
+internal func withOutputStream<Result>(_ stream: SignalOutputStream, _ body: (UnsafePointer<SignalFfi.SignalOutputStream>) throws -> Result) throws -> Result {
+    func ffiShimWrite(
+        stream_ctx: UnsafeMutableRawPointer?,
+        pBuf: UnsafePointer<UInt8>?,
+        bufLen: Int
+    ) -> Int32 {
+        let streamContext = stream_ctx!.assumingMemoryBound(to: ErrorHandlingContext<SignalOutputStream>.self)
+        return streamContext.pointee.catchCallbackErrors { stream in
+            try stream.write(from: UnsafeRawBufferPointer(start: pBuf, count: bufLen))
+            return 0
+        }
+    }
+
+    func ffiShimFlush(stream_ctx: UnsafeMutableRawPointer?) -> Int32 {
+        let streamContext = stream_ctx!.assumingMemoryBound(to: ErrorHandlingContext<SignalOutputStream>.self)
+        return streamContext.pointee.catchCallbackErrors { stream in
+            try stream.flush()
+            return 0
+        }
+    }
+
+    return try rethrowCallbackErrors(stream) {
+        var ffiStream = SignalFfi.SignalOutputStream(
+            ctx: $0,
+            write: ffiShimWrite as SignalWrite,
+            flush: ffiShimFlush as SignalFlush
+        )
+        return try body(&ffiStream)
+    }
+}
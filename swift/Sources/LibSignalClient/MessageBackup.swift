@@ -59,14 +59,129 @@ public func validateMessageBackup(
         }
     }
 
-    if let errorMessage = outcome.errorMessage {
-        throw MessageBackupValidationError(errorMessage: errorMessage, unknownFields: outcome.unknownFields)
+    return try outcome.unknownFieldsOrThrow()
+}
+
+/// Validates a message backup file, reporting progress as it goes.
+///
+/// Behaves like ``validateMessageBackup(key:purpose:length:makeStream:)``, but also calls
+/// `onProgress` periodically as the input is read, and once more when all of it has been consumed.
+/// `onProgress` is called synchronously on the validating thread.
+///
+/// - Parameters:
+///  - key: The key used to decrypt the backup file.
+///  - purpose: Whether the backup is intended for transfer or remote storage.
+///  - length: The exact length of the backup file, in bytes.
+///  - makeStream: A callback that produces InputStreams needed for backups.
+///  - onProgress: A callback that receives progress updates.
+///
+/// - Returns: an object describing the validation outcome.
+///
+/// - Throws:
+///  - `SignalError.ioError`: If an IO error on the input occurs.
+///  - `MessageBackupValidationError`: If validation fails
+public func validateMessageBackup(
+    key: MessageBackupKey,
+    purpose: MessageBackupPurpose,
+    length: UInt64,
+    makeStream: () throws -> SignalInputStream,
+    onProgress: (MessageBackupValidationProgress) -> Void
+) throws -> MessageBackupUnknownFields {
+    typealias ProgressCallback = (MessageBackupValidationProgress) -> Void
+
+    let outcome: ValidationOutcome = try withInputStream(try makeStream()) { firstInput in
+        try withInputStream(try makeStream()) { secondInput in
+            try key.withNativeHandle { key in
+                try withoutActuallyEscaping(onProgress) { onProgress in
+                    var callback: ProgressCallback = onProgress
+                    return try withUnsafeMutablePointer(to: &callback) { callbackPtr in
+                        var listener = SignalFfiBackupProgressListenerStruct(
+                            ctx: UnsafeMutableRawPointer(callbackPtr),
+                            on_progress: { ctx, framesRead, bytesRead, totalBytes in
+                                let callback = ctx!.assumingMemoryBound(to: ProgressCallback.self).pointee
+                                callback(MessageBackupValidationProgress(
+                                    framesRead: framesRead,
+                                    bytesRead: bytesRead,
+                                    totalBytes: totalBytes
+                                ))
+                            }
+                        )
+                        return try invokeFnReturningNativeHandle {
+                            signal_message_backup_validator_validate_with_progress(
+                                $0, key, firstInput, secondInput, length, purpose.rawValue, &listener
+                            )
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    return try outcome.unknownFieldsOrThrow()
+}
+
+/// Encrypts a message backup, writing the result to `output`.
+///
+/// The frames are compressed, padded, and encrypted into the same format that
+/// ``validateMessageBackup(key:purpose:length:makeStream:)`` reads. `output` is flushed at the end.
+///
+/// - Parameters:
+///  - key: The key used to encrypt the backup file.
+///  - frames: The serialized frames of the backup, starting with the backup info, each prefixed
+///    with its varint-encoded length.
+///  - output: Where to write the encrypted backup.
+///
+/// - Throws:
+///  - `SignalError.ioError`: If an IO error on the input or output occurs.
+///  - Any error thrown by `frames` or `output`.
+public func writeEncryptedMessageBackup(
+    key: MessageBackupKey, frames: SignalInputStream, output: SignalOutputStream
+) throws {
+    try withInputStream(frames) { frames in
+        try withOutputStream(output) { output in
+            try key.withNativeHandle { key in
+                try checkError(signal_message_backup_write_encrypted(frames, key, output))
+            }
+        }
     }
-    return outcome.unknownFields
+}
+
+/// Progress reported while validating a message backup.
+public struct MessageBackupValidationProgress: Equatable {
+    /// The number of frames read so far.
+    public var framesRead: UInt64
+    /// The number of (encrypted) input bytes read so far.
+    public var bytesRead: UInt64
+    /// The total number of input bytes.
+    public var totalBytes: UInt64
 }
 
 /// The outcome of a failed validation attempt.
 public struct MessageBackupValidationError: Error {
+    /// Why validation failed.
+    public enum Kind: UInt8 {
+        // This needs to be kept in sync with the Rust version of the enum.
+
+        /// The backup couldn't be authenticated, usually because the wrong key was used.
+        case hmacMismatch = 0
+        /// The input is too short to be an encrypted backup.
+        case tooShort = 1
+        /// The decrypted contents couldn't be parsed as backup frames.
+        case corrupted = 2
+        /// The backup doesn't contain any frames.
+        case noFrames = 3
+        /// A frame's contents are invalid.
+        case invalidContents = 4
+        /// The backup is missing required frames.
+        case incomplete = 5
+        /// The backup uses a version of the format that isn't supported.
+        ///
+        /// If the backup is newer than the library, updating the app may allow it to be read.
+        case unsupportedVersion = 6
+    }
+
+    /// The kind of error that caused validation to fail.
+    public var kind: Kind
     /// The human-readable error that caused validation to fail.
     public var errorMessage: String
     /// Unknown fields encountered while validating.
@@ -98,6 +213,28 @@ private class ValidationOutcome: NativeHandleOwner {
         }
     }
 
+    public var errorKind: MessageBackupValidationError.Kind {
+        let rawValue = failOnError {
+            try self.withNativeHandle { result in
+                try invokeFnReturningInteger {
+                    signal_message_backup_validation_outcome_get_error_kind($0, result)
+                }
+            }
+        }
+        return MessageBackupValidationError.Kind(rawValue: rawValue)!
+    }
+
+    func unknownFieldsOrThrow() throws -> MessageBackupUnknownFields {
+        if let errorMessage = self.errorMessage {
+            throw MessageBackupValidationError(
+                kind: self.errorKind,
+                errorMessage: errorMessage,
+                unknownFields: self.unknownFields
+            )
+        }
+        return self.unknownFields
+    }
+
     override internal class func destroyNativeHandle(_ handle: OpaquePointer) -> SignalFfiErrorRef? {
         signal_message_backup_validation_outcome_destroy(handle)
     }
@@ -116,14 +116,19 @@ public class Net {
     ///   doSomething(entry.aci, entry.pni, entry.e164)
     /// }
     /// ```
+    ///
+    /// If a connection can't be established within `connectTimeout`, the lookup fails with
+    /// ``SignalError/connectionTimeoutError(_:stableCode:)``.
     public func cdsiLookup(
         auth: Auth,
-        request: CdsiLookupRequest
+        request: CdsiLookupRequest,
+        connectTimeout: TimeInterval = 3 * 60
     ) async throws -> CdsiLookup {
+        let timeoutMillis = UInt32(min(1000 * max(connectTimeout, 0), Double(UInt32.max)))
         let handle: OpaquePointer = try await self.asyncContext.invokeAsyncFunction { promise, asyncContext in
             self.connectionManager.withNativeHandle { connectionManager in
                 request.withNativeHandle { request in
-                    signal_cdsi_lookup_new(promise, asyncContext, connectionManager, auth.username, auth.password, request)
+                    signal_cdsi_lookup_new(promise, asyncContext, connectionManager, auth.username, auth.password, request, timeoutMillis)
                 }
             }
         }
@@ -134,6 +139,15 @@ public class Net {
         return AuthenticatedChatService(tokioAsyncContext: self.asyncContext, connectionManager: self.connectionManager, username: username, password: password, receiveStories: receiveStories)
     }
 
+    /// Like ``createAuthenticatedChatService(username:password:receiveStories:)``, but gets its
+    /// credentials from `authProvider` each time it connects.
+    ///
+    /// If the server rejects the credentials, `authProvider` is asked to refresh them and the
+    /// connection is retried once before the error is reported.
+    public func createAuthenticatedChatService(authProvider: any ChatAuthProvider, receiveStories: Bool) -> AuthenticatedChatService {
+        return AuthenticatedChatService(tokioAsyncContext: self.asyncContext, connectionManager: self.connectionManager, authProvider: authProvider, receiveStories: receiveStories)
+    }
+
     public func createUnauthenticatedChatService() -> UnauthenticatedChatService {
         return UnauthenticatedChatService(tokioAsyncContext: self.asyncContext, connectionManager: self.connectionManager)
     }
@@ -286,6 +300,22 @@ public class CdsiLookup {
 
         return CdsiLookupResponse(entries: LookupResponseEntryList(owned: response.entries), debugPermitsUsed: response.debug_permits_used)
     }
+
+    /// Like ``complete()``, but returns the entries as a list with explicitly optional IDs.
+    ///
+    /// After this method is called on a ``CdsiLookup`` object, the object
+    /// should not be used again.
+    ///
+    /// - Throws: ``SignalError`` if the request fails for any reason; see ``complete()``.
+    public func completeEntries() async throws -> CdsiLookupEntries {
+        let response: SignalFfiCdsiLookupEntries = try await self.asyncContext.invokeAsyncFunction { promise, asyncContext in
+            self.native.withNativeHandle { handle in
+                signal_cdsi_lookup_complete_entries(promise, asyncContext, handle)
+            }
+        }
+
+        return try CdsiLookupEntries(owned: response)
+    }
 }
 
 /// Response to the server produced by a completed ``CdsiLookup``.
@@ -298,6 +328,53 @@ public struct CdsiLookupResponse {
     public let debugPermitsUsed: Int32
 }
 
+/// Response to the server produced by a completed ``CdsiLookup``, as a list of entries in the
+/// order the server sent them.
+///
+/// Returned by ``CdsiLookup/completeEntries()`` on success. Unlike ``CdsiLookupResponse``, a
+/// missing ACI or PNI is always `nil`, never the nil UUID.
+public struct CdsiLookupEntries {
+    /// The entries received from the server.
+    public let entries: [CdsiLookupEntry]
+    /// How many "permits" were used in making the request.
+    public let debugPermitsUsed: Int32
+
+    /// The entries that have an ACI, in order.
+    public var entriesWithAci: [CdsiLookupEntry] {
+        self.entries.filter { $0.aci != nil }
+    }
+
+    /// Takes ownership of the entries in `owned`, freeing them.
+    internal init(owned: SignalFfiCdsiLookupEntries) throws {
+        defer { signal_free_lookup_entry_list(owned.entries) }
+        let buffer = UnsafeBufferPointer(start: owned.entries.base, count: Int(owned.entries.length))
+        self.entries = try buffer.map { try CdsiLookupEntry($0) }
+        self.debugPermitsUsed = owned.debug_permits_used
+    }
+}
+
+/// Entry contained in a successful CDSI lookup response.
+public struct CdsiLookupEntry: Equatable {
+    /// The unformatted phone number for the entry.
+    public let e164: UInt64
+    /// The ACI in the response, if there was any.
+    public let aci: Aci?
+    /// The PNI in the response, if there was any.
+    public let pni: Pni?
+
+    public init(e164: UInt64, aci: Aci?, pni: Pni?) {
+        self.e164 = e164
+        self.aci = aci
+        self.pni = pni
+    }
+
+    internal init(_ entry: SignalFfiCdsiLookupEntry) throws {
+        self.e164 = entry.e164
+        self.aci = try entry.has_aci ? Aci.parseFrom(fixedWidthBinary: entry.aci) : nil
+        self.pni = try entry.has_pni ? Pni.parseFrom(fixedWidthBinary: entry.pni) : nil
+    }
+}
+
 /// Entries received from the CDSI server in response to a lookup request.
 ///
 /// Contains a sequence of ``CdsiLookupResponseEntry`` values. Conforms
@@ -278,7 +278,8 @@ public class Svr3Client {
     ///
     /// ## Notes:
     ///   - The method will succeed even if the data has never been backed up
-    ///     in the first place.
+    ///     in the first place, or if it could be removed from only some of the
+    ///     enclaves.
     ///   - Error messages are log-safe and do not contain any sensitive data.
     ///   - Failures caused by the network issues (including a connection
     ///     timeout) can, in general, be retried, although there is already a
@@ -287,11 +288,12 @@ public class Svr3Client {
     ///     missing on the server. They are therefore non-actionable and are
     ///     guaranteed to be thrown again when retried.
     public func remove(auth: Auth) async throws {
-        _ = try await self.asyncContext.invokeAsyncFunction { promise, asyncContext in
+        let output = try await self.asyncContext.invokeAsyncFunction { promise, asyncContext in
             self.connectionManager.withNativeHandle { connectionManager in
                 signal_svr3_remove(promise, asyncContext, connectionManager, auth.username, auth.password)
             }
         }
+        signal_free_buffer(output.base, output.length)
     }
 
     /// Rotate the secret stored in SVR3.
@@ -348,6 +350,151 @@ public class Svr3Client {
             }
         }
     }
+
+    /// Move a pin-protected secret from SVR2 to SVR3.
+    ///
+    /// The secret is restored from SVR2 using `pin`, backed up to SVR3 with
+    /// `pin` as the password, and finally deleted from SVR2. Transient failures
+    /// while backing up or deleting are retried a few times before giving up.
+    ///
+    /// - Parameters:
+    ///   - pin: The user's pin, used both to restore from SVR2 and as the SVR3
+    ///     password.
+    ///   - maxTries: Number of times the secret will be allowed to be guessed
+    ///     in SVR3. Must be positive.
+    ///   - svr2Auth: Credentials for SVR2 obtained from the Chat Server.
+    ///   - svr3Auth: Credentials for SVR3 obtained from the Chat Server.
+    ///
+    /// - Returns:
+    ///   The outcome of the migration. If it is not complete, it can be passed
+    ///   to ``resumeMigrationFromSvr2(_:pin:maxTries:svr2Auth:svr3Auth:)`` to
+    ///   continue from the step that failed.
+    ///
+    /// - Throws:
+    ///   Only failures to restore from SVR2 are thrown, since there is nothing
+    ///   to resume at that point. Expected error cases are
+    ///   - `SignalError.svrRestoreFailed` if the pin is wrong.
+    ///   - `SignalError.svrDataMissing` if nothing is stored in SVR2.
+    ///   - `SignalError.networkError` for a network-level connectivity issue.
+    ///   - `SignalError.networkProtocolError` for an SVR2 or attested
+    ///     connection protocol issue.
+    public func migrateFromSvr2(
+        pin: String,
+        maxTries: UInt32,
+        svr2Auth: Auth,
+        svr3Auth: Auth
+    ) async throws -> PinMigrationOutcome {
+        let handle: OpaquePointer = try await self.asyncContext.invokeAsyncFunction { promise, asyncContext in
+            self.connectionManager.withNativeHandle { connectionManager in
+                signal_svr2_migrate_to_svr3(
+                    promise,
+                    asyncContext,
+                    connectionManager,
+                    pin,
+                    maxTries,
+                    svr2Auth.username,
+                    svr2Auth.password,
+                    svr3Auth.username,
+                    svr3Auth.password
+                )
+            }
+        }
+        return PinMigrationOutcome(owned: handle)
+    }
+
+    /// Continue a migration started by
+    /// ``migrateFromSvr2(pin:maxTries:svr2Auth:svr3Auth:)`` from the step
+    /// that failed.
+    ///
+    /// If `previousOutcome` is already complete, an equivalent outcome is
+    /// returned without contacting either service. The credentials do not have
+    /// to be the ones used originally.
+    public func resumeMigrationFromSvr2(
+        _ previousOutcome: PinMigrationOutcome,
+        pin: String,
+        maxTries: UInt32,
+        svr2Auth: Auth,
+        svr3Auth: Auth
+    ) async throws -> PinMigrationOutcome {
+        let handle: OpaquePointer = try await self.asyncContext.invokeAsyncFunction { promise, asyncContext in
+            self.connectionManager.withNativeHandle { connectionManager in
+                previousOutcome.withNativeHandle { previousOutcome in
+                    signal_svr2_resume_migration_to_svr3(
+                        promise,
+                        asyncContext,
+                        connectionManager,
+                        previousOutcome,
+                        pin,
+                        maxTries,
+                        svr2Auth.username,
+                        svr2Auth.password,
+                        svr3Auth.username,
+                        svr3Auth.password
+                    )
+                }
+            }
+        }
+        return PinMigrationOutcome(owned: handle)
+    }
+}
+
+/// The result of moving a pin-protected secret from SVR2 to SVR3.
+///
+/// Once the secret has been restored from SVR2 it is always available, even if
+/// a later step of the migration failed.
+public class PinMigrationOutcome: NativeHandleOwner {
+    override internal class func destroyNativeHandle(_ handle: OpaquePointer) -> SignalFfiErrorRef? {
+        return signal_pin_migration_outcome_destroy(handle)
+    }
+
+    /// Whether the secret has been backed up to SVR3 and deleted from SVR2.
+    ///
+    /// If not, ``errorMessage`` describes what went wrong.
+    public var isComplete: Bool {
+        return withNativeHandle { nativeHandle in
+            failOnError {
+                try invokeFnReturningBool {
+                    signal_pin_migration_outcome_is_complete($0, nativeHandle)
+                }
+            }
+        }
+    }
+
+    /// The 32-byte secret restored from SVR2.
+    public var secret: [UInt8] {
+        return withNativeHandle { nativeHandle in
+            failOnError {
+                try invokeFnReturningFixedLengthArray {
+                    signal_pin_migration_outcome_get_secret($0, nativeHandle)
+                }
+            }
+        }
+    }
+
+    /// The serialized masked share set produced by backing up to SVR3, or
+    /// `nil` if the backup step has not succeeded yet.
+    public var shareSet: [UInt8]? {
+        let shareSet = withNativeHandle { nativeHandle in
+            failOnError {
+                try invokeFnReturningArray {
+                    signal_pin_migration_outcome_get_share_set($0, nativeHandle)
+                }
+            }
+        }
+        return shareSet.isEmpty ? nil : shareSet
+    }
+
+    /// A log-safe description of the error that stopped the migration, or
+    /// `nil` if it is complete.
+    public var errorMessage: String? {
+        return withNativeHandle { nativeHandle in
+            failOnError {
+                try invokeFnReturningOptionalString {
+                    signal_pin_migration_outcome_get_error_message($0, nativeHandle)
+                }
+            }
+        }
+    }
 }
 
 public struct RestoredSecret {
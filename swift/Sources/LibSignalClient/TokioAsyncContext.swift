@@ -17,6 +17,17 @@ internal class TokioAsyncContext: NativeHandleOwner {
         signal_tokio_async_context_destroy(handle)
     }
 
+    /// Cancels all outstanding tasks and shuts down the runtime, waiting up to `timeoutMillis` for
+    /// them to finish.
+    ///
+    /// Any async calls made using this context afterwards will fail with
+    /// ``SignalError/invalidState(_:stableCode:)``.
+    internal func shutdown(timeoutMillis: UInt32) {
+        self.withNativeHandle {
+            failOnError(signal_tokio_async_context_shutdown($0, timeoutMillis))
+        }
+    }
+
     /// A thread-safe helper for translating Swift task cancellations into calls to
     /// `signal_tokio_async_context_cancel`.
     private class CancellationHandoffHelper {
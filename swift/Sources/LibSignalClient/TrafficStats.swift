@@ -0,0 +1,66 @@
+//
+// Copyright 2024 Signal Messenger, LLC.
+// SPDX-License-Identifier: AGPL-3.0-only
+//
+
+import Foundation
+import SignalFfi
+
+/// Bytes sent and received over one kind of connection.
+public struct TrafficCounts: Equatable {
+    public var sent: UInt64
+    public var received: UInt64
+
+    public init(sent: UInt64, received: UInt64) {
+        self.sent = sent
+        self.received = received
+    }
+}
+
+/// The bytes sent and received by each kind of connection libsignal makes, for the whole process.
+///
+/// The counts are taken on top of TLS, so they include HTTP and WebSocket framing but not TLS
+/// records or TCP/IP headers.
+public struct TrafficStats: Equatable {
+    public var chat: TrafficCounts
+    public var cdsi: TrafficCounts
+    public var svr: TrafficCounts
+    public var cdn: TrafficCounts
+
+    /// The totals since the process started or ``reset()`` was last called.
+    public static func snapshot() -> TrafficStats {
+        failOnError {
+            TrafficStats(serialized: try invokeFnReturningFixedLengthArray {
+                signal_traffic_stats_snapshot($0)
+            })
+        }
+    }
+
+    /// Starts all the totals over from zero, returning what they were.
+    ///
+    /// Bytes counted while this runs end up in exactly one of the result and the new totals.
+    public static func reset() -> TrafficStats {
+        failOnError {
+            TrafficStats(serialized: try invokeFnReturningFixedLengthArray {
+                signal_traffic_stats_reset($0)
+            })
+        }
+    }
+}
+
+extension TrafficStats {
+    /// Reads the counts for each kind in the order chat, CDSI, SVR, CDN, each as two big-endian
+    /// UInt64s (sent, then received).
+    internal init(serialized: [UInt8]) {
+        func readUInt64(at offset: Int) -> UInt64 {
+            serialized[offset..<offset + 8].reduce(0) { $0 << 8 | UInt64($1) }
+        }
+        func counts(at index: Int) -> TrafficCounts {
+            TrafficCounts(sent: readUInt64(at: 16 * index), received: readUInt64(at: 16 * index + 8))
+        }
+        self.chat = counts(at: 0)
+        self.cdsi = counts(at: 1)
+        self.svr = counts(at: 2)
+        self.cdn = counts(at: 3)
+    }
+}
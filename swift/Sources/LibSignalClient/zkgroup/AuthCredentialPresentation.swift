@@ -35,4 +35,16 @@ public class AuthCredentialPresentation: ByteArray {
         }
         return Date(timeIntervalSince1970: TimeInterval(secondsSinceEpoch))
     }
+
+    /// Returns the value of the `Authorization` header for a request to the group storage service
+    /// for the group with the given public params.
+    public func getHttpAuthorization(groupPublicParams: GroupPublicParams) throws -> String {
+        return try withUnsafeBorrowedBuffer { buffer in
+            try groupPublicParams.withUnsafePointerToSerialized { groupPublicParams in
+                try invokeFnReturningString {
+                    signal_auth_credential_presentation_get_http_authorization($0, buffer, groupPublicParams)
+                }
+            }
+        }
+    }
 }
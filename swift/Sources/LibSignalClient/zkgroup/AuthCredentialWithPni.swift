@@ -10,4 +10,20 @@ public class AuthCredentialWithPni: ByteArray {
     public required init(contents: [UInt8]) throws {
         try super.init(contents, checkValid: signal_auth_credential_with_pni_check_valid_contents)
     }
+
+    /// When this credential stops being accepted, if it's in an old format that has to be replaced
+    /// by fetching a new credential from the server.
+    ///
+    /// `nil` if the credential is already in the current format.
+    public func getRefreshDeadline() throws -> Date? {
+        let secondsSinceEpoch = try withUnsafeBorrowedBuffer { buffer in
+            try invokeFnReturningInteger {
+                signal_auth_credential_with_pni_get_refresh_deadline_or_zero($0, buffer)
+            }
+        }
+        if secondsSinceEpoch == 0 {
+            return nil
+        }
+        return Date(timeIntervalSince1970: TimeInterval(secondsSinceEpoch))
+    }
 }
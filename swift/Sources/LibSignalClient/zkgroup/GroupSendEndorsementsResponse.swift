@@ -84,7 +84,7 @@ public class GroupSendEndorsementsResponse: ByteArray {
     ///
     /// `localUser` should be included in `groupMembers`.
     ///
-    /// - Throws: ``SignalError/verificationFailed(_:)`` if the endorsements are not valid for any
+    /// - Throws: ``SignalError/verificationFailed(_:stableCode:)`` if the endorsements are not valid for any
     ///   reason
     public func receive(
         groupMembers: some Collection<ServiceId>,
@@ -124,7 +124,7 @@ public class GroupSendEndorsementsResponse: ByteArray {
     ///
     /// `localUser` should be included in `groupMembers`.
     ///
-    /// - Throws: ``SignalError/verificationFailed(_:)`` if the endorsements are not valid for any
+    /// - Throws: ``SignalError/verificationFailed(_:stableCode:)`` if the endorsements are not valid for any
     ///   reason
     public func receive(
         groupMembers: some Sequence<UuidCiphertext>,
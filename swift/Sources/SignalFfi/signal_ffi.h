@@ -155,6 +155,8 @@ typedef enum {
   SignalErrorCodeInvalidKey = 40,
   SignalErrorCodeInvalidSignature = 41,
   SignalErrorCodeInvalidAttestationData = 42,
+  SignalErrorCodeAttestationExpired = 43,
+  SignalErrorCodeAttestationMeasurementMismatch = 44,
   SignalErrorCodeFingerprintVersionMismatch = 51,
   SignalErrorCodeFingerprintParsingError = 52,
   SignalErrorCodeUntrustedIdentity = 60,
@@ -210,6 +212,8 @@ typedef struct SignalAes256GcmEncryption SignalAes256GcmEncryption;
 
 typedef struct SignalAes256GcmSiv SignalAes256GcmSiv;
 
+typedef struct SignalBackupKeyMaterial SignalBackupKeyMaterial;
+
 typedef struct SignalCdsiLookup SignalCdsiLookup;
 
 typedef struct SignalChatAuthChatService SignalChatAuthChatService;
@@ -230,6 +234,8 @@ typedef struct SignalHttpRequest SignalHttpRequest;
 
 typedef struct SignalIncrementalMac SignalIncrementalMac;
 
+typedef struct SignalIncrementalMacAndDigest SignalIncrementalMacAndDigest;
+
 typedef struct SignalKeyPair SignalKeyPair;
 
 typedef struct SignalKeySecret SignalKeySecret;
@@ -242,8 +248,12 @@ typedef struct SignalMessageBackupKey SignalMessageBackupKey;
 
 typedef struct SignalMessageBackupValidationOutcome SignalMessageBackupValidationOutcome;
 
+typedef struct SignalMultiRecipientMismatchedDevices SignalMultiRecipientMismatchedDevices;
+
 typedef struct SignalPinHash SignalPinHash;
 
+typedef struct SignalPinMigrationOutcome SignalPinMigrationOutcome;
+
 typedef struct SignalPlaintextContent SignalPlaintextContent;
 
 typedef struct SignalPreKeyBundle SignalPreKeyBundle;
@@ -261,6 +271,8 @@ typedef struct SignalProtocolAddress SignalProtocolAddress;
 
 typedef struct SignalPublicKey SignalPublicKey;
 
+typedef struct SignalRegistrationLockFailure SignalRegistrationLockFailure;
+
 #if defined(SIGNAL_MEDIA_SUPPORTED)
 typedef struct SignalSanitizedMetadata SignalSanitizedMetadata;
 #endif
@@ -304,6 +316,11 @@ typedef struct SignalSignedPreKeyRecord SignalSignedPreKeyRecord;
 
 typedef struct SignalTokioAsyncContext SignalTokioAsyncContext;
 
+/**
+ * A pool of unauthenticated connections for one-shot requests.
+ */
+typedef struct SignalUnauthChatPool SignalUnauthChatPool;
+
 typedef struct SignalUnidentifiedSenderMessageContent SignalUnidentifiedSenderMessageContent;
 
 typedef struct SignalValidatingMac SignalValidatingMac;
@@ -549,6 +566,21 @@ typedef struct {
   SignalCancellationId cancellation_id;
 } SignalCPromiseCdsiLookup;
 
+/**
+ * A C callback used to report the results of Rust futures.
+ *
+ * cbindgen will produce independent C types like `SignalCPromisei32` and
+ * `SignalCPromiseProtocolAddress`.
+ *
+ * This derives Copy because it behaves like a C type; nevertheless, a promise should still only be
+ * completed once.
+ */
+typedef struct {
+  void (*complete)(SignalFfiError *error, SignalPinMigrationOutcome *const *result, const void *context);
+  const void *context;
+  SignalCancellationId cancellation_id;
+} SignalCPromisePinMigrationOutcome;
+
 typedef struct {
   SignalOwnedBufferOfFfiCdsiLookupResponseEntry entries;
   int32_t debug_permits_used;
@@ -569,6 +601,54 @@ typedef struct {
   SignalCancellationId cancellation_id;
 } SignalCPromiseFfiCdsiLookupResponse;
 
+typedef struct {
+  /**
+   * Telephone number, as an unformatted e164.
+   */
+  uint64_t e164;
+  /**
+   * Only meaningful if `has_aci` is set.
+   */
+  SignalServiceIdFixedWidthBinaryBytes aci;
+  bool has_aci;
+  /**
+   * Only meaningful if `has_pni` is set.
+   */
+  SignalServiceIdFixedWidthBinaryBytes pni;
+  bool has_pni;
+} SignalFfiCdsiLookupEntry;
+
+/**
+ * A representation of a array allocated on the Rust heap for use in C code.
+ */
+typedef struct {
+  SignalFfiCdsiLookupEntry *base;
+  /**
+   * The number of elements in the buffer (not necessarily the number of bytes).
+   */
+  size_t length;
+} SignalOwnedBufferOfFfiCdsiLookupEntry;
+
+typedef struct {
+  SignalOwnedBufferOfFfiCdsiLookupEntry entries;
+  int32_t debug_permits_used;
+} SignalFfiCdsiLookupEntries;
+
+/**
+ * A C callback used to report the results of Rust futures.
+ *
+ * cbindgen will produce independent C types like `SignalCPromisei32` and
+ * `SignalCPromiseProtocolAddress`.
+ *
+ * This derives Copy because it behaves like a C type; nevertheless, a promise should still only be
+ * completed once.
+ */
+typedef struct {
+  void (*complete)(SignalFfiError *error, const SignalFfiCdsiLookupEntries *result, const void *context);
+  const void *context;
+  SignalCancellationId cancellation_id;
+} SignalCPromiseFfiCdsiLookupEntries;
+
 typedef SignalChatAuthChatService SignalAuthChat;
 
 typedef SignalChatUnauthChatService SignalUnauthChat;
@@ -577,6 +657,15 @@ typedef struct {
   uint8_t raw_ip_type;
   double duration_secs;
   const char *connection_info;
+  uint8_t raw_route_type;
+  /**
+   * `NULL` if the connection wasn't made to a known IP address.
+   */
+  const char *remote_ip;
+  /**
+   * 0 if the connection wasn't made to a known IP address.
+   */
+  uint16_t remote_port;
 } SignalFfiChatServiceDebugInfo;
 
 /**
@@ -661,6 +750,32 @@ typedef struct {
 
 typedef SignalFfiChatListenerStruct SignalFfiMakeChatListenerStruct;
 
+typedef void (*SignalCurrentAuth)(void *ctx, const char **username, const char **password);
+
+typedef bool (*SignalRefreshAuth)(void *ctx, const char **username, const char **password);
+
+typedef void (*SignalDestroyChatAuthProvider)(void *ctx);
+
+/**
+ * Callbacks for [`AuthProvider`].
+ *
+ * Both callbacks write credentials to `username` and `password` as NUL-terminated UTF-8 strings.
+ * These are copied as soon as the callback returns, so they only need to stay valid until the
+ * next callback is made. `refresh` returns `false` (and doesn't have to write anything) if there
+ * are no new credentials to try.
+ *
+ * Callbacks will not be made concurrently, but may not always happen on the same thread. They're
+ * made while connecting, so they should return promptly.
+ */
+typedef struct {
+  void *ctx;
+  SignalCurrentAuth current;
+  SignalRefreshAuth refresh;
+  SignalDestroyChatAuthProvider destroy;
+} SignalFfiChatAuthProviderStruct;
+
+typedef SignalFfiChatAuthProviderStruct SignalFfiMakeChatAuthProviderStruct;
+
 typedef int (*SignalRead)(void *ctx, uint8_t *buf, size_t buf_len, size_t *amount_read);
 
 typedef int (*SignalSkip)(void *ctx, uint64_t amount);
@@ -673,6 +788,23 @@ typedef struct {
 
 typedef SignalInputStream SignalSyncInputStream;
 
+typedef int (*SignalWrite)(void *ctx, const uint8_t *buf, size_t buf_len);
+
+typedef int (*SignalFlush)(void *ctx);
+
+typedef struct {
+  void *ctx;
+  SignalWrite write;
+  SignalFlush flush;
+} SignalOutputStream;
+
+typedef void (*SignalOnBackupProgress)(void *ctx, uint64_t frames_read, uint64_t bytes_read, uint64_t total_bytes);
+
+typedef struct {
+  void *ctx;
+  SignalOnBackupProgress on_progress;
+} SignalFfiBackupProgressListenerStruct;
+
 typedef uint8_t SignalRandomnessBytes[SignalRANDOMNESS_LEN];
 
 void signal_print_ptr(const void *p);
@@ -685,6 +817,8 @@ void signal_free_list_of_strings(SignalOwnedBufferOfCStringPtr buffer);
 
 void signal_free_lookup_response_entry_list(SignalOwnedBufferOfFfiCdsiLookupResponseEntry buffer);
 
+void signal_free_lookup_entry_list(SignalOwnedBufferOfFfiCdsiLookupEntry buffer);
+
 void signal_free_bytestring_array(SignalBytestringArray array);
 
 SignalFfiError *signal_error_get_message(const SignalFfiError *err, const char **out);
@@ -695,6 +829,8 @@ SignalFfiError *signal_error_get_uuid(const SignalFfiError *err, uint8_t (*out)[
 
 uint32_t signal_error_get_type(const SignalFfiError *err);
 
+uint32_t signal_error_get_stable_code(const SignalFfiError *err);
+
 SignalFfiError *signal_error_get_retry_after_seconds(const SignalFfiError *err, uint32_t *out);
 
 SignalFfiError *signal_error_get_tries_remaining(const SignalFfiError *err, uint32_t *out);
@@ -891,6 +1027,10 @@ SignalFfiError *signal_identitykeypair_sign_alternate_identity(SignalOwnedBuffer
 
 SignalFfiError *signal_identitykey_verify_alternate_identity(bool *out, const SignalPublicKey *public_key, const SignalPublicKey *other_identity, SignalBorrowedBuffer signature);
 
+SignalFfiError *signal_pni_signature_create(SignalOwnedBuffer *out, const SignalPublicKey *pni_public_key, const SignalPrivateKey *pni_private_key, const SignalPublicKey *aci_identity);
+
+SignalFfiError *signal_pni_signature_verify(bool *out, const SignalPublicKey *aci_identity, const SignalPublicKey *pni_identity, SignalBorrowedBuffer signature);
+
 SignalFfiError *signal_fingerprint_new(SignalFingerprint **out, uint32_t iterations, uint32_t version, SignalBorrowedBuffer local_identifier, const SignalPublicKey *local_key, SignalBorrowedBuffer remote_identifier, const SignalPublicKey *remote_key);
 
 SignalFfiError *signal_fingerprint_scannable_encoding(SignalOwnedBuffer *out, const SignalFingerprint *obj);
@@ -1289,6 +1429,8 @@ SignalFfiError *signal_server_secret_params_issue_auth_credential_with_pni_zkc_d
 
 SignalFfiError *signal_auth_credential_with_pni_check_valid_contents(SignalBorrowedBuffer bytes);
 
+SignalFfiError *signal_auth_credential_with_pni_get_refresh_deadline_or_zero(uint64_t *out, SignalBorrowedBuffer bytes);
+
 SignalFfiError *signal_auth_credential_with_pni_response_check_valid_contents(SignalBorrowedBuffer bytes);
 
 SignalFfiError *signal_server_secret_params_verify_auth_credential_presentation(const SignalServerSecretParams *server_secret_params, const unsigned char (*group_public_params)[SignalGROUP_PUBLIC_PARAMS_LEN], SignalBorrowedBuffer presentation_bytes, uint64_t current_time_in_seconds);
@@ -1313,6 +1455,8 @@ SignalFfiError *signal_auth_credential_presentation_get_pni_ciphertext_or_empty(
 
 SignalFfiError *signal_auth_credential_presentation_get_redemption_time(uint64_t *out, SignalBorrowedBuffer presentation_bytes);
 
+SignalFfiError *signal_auth_credential_presentation_get_http_authorization(const char **out, SignalBorrowedBuffer presentation_bytes, const unsigned char (*group_public_params)[SignalGROUP_PUBLIC_PARAMS_LEN]);
+
 SignalFfiError *signal_profile_key_credential_request_context_get_request(unsigned char (*out)[SignalPROFILE_KEY_CREDENTIAL_REQUEST_LEN], const unsigned char (*context)[SignalPROFILE_KEY_CREDENTIAL_REQUEST_CONTEXT_LEN]);
 
 SignalFfiError *signal_expiring_profile_key_credential_get_expiration_time(uint64_t *out, const unsigned char (*credential)[SignalEXPIRING_PROFILE_KEY_CREDENTIAL_LEN]);
@@ -1453,14 +1597,44 @@ SignalFfiError *signal_verify_signature(bool *out, SignalBorrowedBuffer cert_pem
 
 SignalFfiError *signal_connection_manager_destroy(SignalConnectionManager *p);
 
+SignalFfiError *signal_pin_migration_outcome_destroy(SignalPinMigrationOutcome *p);
+
 SignalFfiError *signal_connection_manager_new(SignalConnectionManager **out, uint8_t environment, const char *user_agent);
 
+SignalFfiError *signal_connection_manager_new_custom(SignalConnectionManager **out, uint8_t environment, const char *user_agent, const char *chat_host, int32_t chat_port, const char *cdsi_host, int32_t cdsi_port, SignalBorrowedBuffer cdsi_mr_enclave, SignalBorrowedBuffer root_certificate_der);
+
 SignalFfiError *signal_connection_manager_set_proxy(const SignalConnectionManager *connection_manager, const char *host, int32_t port);
 
 SignalFfiError *signal_connection_manager_clear_proxy(const SignalConnectionManager *connection_manager);
 
+SignalFfiError *signal_connection_manager_set_static_dns_entry(const SignalConnectionManager *connection_manager, const char *hostname, const char *ip_addresses);
+
+SignalFfiError *signal_connection_manager_set_svr3_retry_config(const SignalConnectionManager *connection_manager, uint32_t max_attempts, uint32_t failure_threshold);
+
 SignalFfiError *signal_connection_manager_on_network_change(const SignalConnectionManager *connection_manager);
 
+SignalFfiError *signal_connection_manager_export_cooldown_state(SignalOwnedBuffer *out, const SignalConnectionManager *connection_manager);
+
+SignalFfiError *signal_connection_manager_import_cooldown_state(const SignalConnectionManager *connection_manager, SignalBorrowedBuffer state);
+
+SignalFfiError *signal_connection_manager_run_connectivity_report(SignalCPromiseOwnedBufferOfc_uchar *promise, const SignalTokioAsyncContext *async_runtime, const SignalConnectionManager *connection_manager, uint32_t timeout_millis);
+
+SignalFfiError *signal_set_global_proxy(const char *host, int32_t port);
+
+SignalFfiError *signal_parse_proxy_url(const char **out, const char *url);
+
+SignalFfiError *signal_set_global_proxy_from_url(const char *url);
+
+SignalFfiError *signal_clear_global_proxy(void);
+
+SignalFfiError *signal_add_supplemental_root_certificate(SignalBorrowedBuffer der);
+
+SignalFfiError *signal_trusted_root_certificate_fingerprints(SignalOwnedBuffer *out);
+
+SignalFfiError *signal_traffic_stats_snapshot(uint8_t (*out)[64]);
+
+SignalFfiError *signal_traffic_stats_reset(uint8_t (*out)[64]);
+
 SignalFfiError *signal_create_otp(const char **out, const char *username, SignalBorrowedBuffer secret);
 
 SignalFfiError *signal_create_otp_from_base64(const char **out, const char *username, const char *secret);
@@ -1471,10 +1645,26 @@ SignalFfiError *signal_svr3_migrate(SignalCPromiseOwnedBufferOfc_uchar *promise,
 
 SignalFfiError *signal_svr3_restore(SignalCPromiseOwnedBufferOfc_uchar *promise, const SignalTokioAsyncContext *async_runtime, const SignalConnectionManager *connection_manager, const char *password, SignalBorrowedBuffer share_set, const char *username, const char *enclave_password);
 
-SignalFfiError *signal_svr3_remove(SignalCPromisebool *promise, const SignalTokioAsyncContext *async_runtime, const SignalConnectionManager *connection_manager, const char *username, const char *enclave_password);
+SignalFfiError *signal_svr3_remove(SignalCPromiseOwnedBufferOfc_uchar *promise, const SignalTokioAsyncContext *async_runtime, const SignalConnectionManager *connection_manager, const char *username, const char *enclave_password);
+
+SignalFfiError *signal_svr3_query(SignalCPromiseOwnedBufferOfc_uchar *promise, const SignalTokioAsyncContext *async_runtime, const SignalConnectionManager *connection_manager, const char *username, const char *enclave_password);
 
 SignalFfiError *signal_svr3_rotate(SignalCPromisebool *promise, const SignalTokioAsyncContext *async_runtime, const SignalConnectionManager *connection_manager, SignalBorrowedBuffer share_set, const char *username, const char *enclave_password);
 
+SignalFfiError *signal_svr2_migrate_to_svr3(SignalCPromisePinMigrationOutcome *promise, const SignalTokioAsyncContext *async_runtime, const SignalConnectionManager *connection_manager, const char *pin, uint32_t max_tries, const char *svr2_username, const char *svr2_password, const char *svr3_username, const char *svr3_password);
+
+SignalFfiError *signal_svr2_resume_migration_to_svr3(SignalCPromisePinMigrationOutcome *promise, const SignalTokioAsyncContext *async_runtime, const SignalConnectionManager *connection_manager, const SignalPinMigrationOutcome *previous_outcome, const char *pin, uint32_t max_tries, const char *svr2_username, const char *svr2_password, const char *svr3_username, const char *svr3_password);
+
+SignalFfiError *signal_pin_migration_outcome_is_complete(bool *out, const SignalPinMigrationOutcome *outcome);
+
+SignalFfiError *signal_pin_migration_outcome_get_secret(uint8_t (*out)[32], const SignalPinMigrationOutcome *outcome);
+
+SignalFfiError *signal_pin_migration_outcome_get_share_set(SignalOwnedBuffer *out, const SignalPinMigrationOutcome *outcome);
+
+SignalFfiError *signal_pin_migration_outcome_get_error_message(const char **out, const SignalPinMigrationOutcome *outcome);
+
+SignalFfiError *signal_cdn_download_attachment(SignalCPromiseOwnedBufferOfc_uchar *promise, const SignalTokioAsyncContext *async_runtime, const SignalConnectionManager *connection_manager, uint32_t cdn_number, const char *cdn_key, SignalBorrowedBuffer expected_digest);
+
 SignalFfiError *signal_lookup_request_destroy(SignalLookupRequest *p);
 
 SignalFfiError *signal_lookup_request_new(SignalLookupRequest **out);
@@ -1489,20 +1679,32 @@ SignalFfiError *signal_lookup_request_add_aci_and_access_key(const SignalLookupR
 
 SignalFfiError *signal_lookup_request_set_return_acis_without_uaks(const SignalLookupRequest *request, bool return_acis_without_uaks);
 
+SignalFfiError *signal_lookup_request_permit_cost_estimate(uint32_t *out, const SignalLookupRequest *request);
+
 SignalFfiError *signal_cdsi_lookup_destroy(SignalCdsiLookup *p);
 
-SignalFfiError *signal_cdsi_lookup_new(SignalCPromiseCdsiLookup *promise, const SignalTokioAsyncContext *async_runtime, const SignalConnectionManager *connection_manager, const char *username, const char *password, const SignalLookupRequest *request);
+SignalFfiError *signal_cdsi_lookup_new(SignalCPromiseCdsiLookup *promise, const SignalTokioAsyncContext *async_runtime, const SignalConnectionManager *connection_manager, const char *username, const char *password, const SignalLookupRequest *request, uint32_t timeout_millis);
+
+SignalFfiError *signal_cdsi_capture_attestation_evidence(SignalCPromiseOwnedBufferOfc_uchar *promise, const SignalTokioAsyncContext *async_runtime, const SignalConnectionManager *connection_manager, const char *username, const char *password, uint32_t timeout_millis);
 
 SignalFfiError *signal_cdsi_lookup_token(SignalOwnedBuffer *out, const SignalCdsiLookup *lookup);
 
 SignalFfiError *signal_cdsi_lookup_complete(SignalCPromiseFfiCdsiLookupResponse *promise, const SignalTokioAsyncContext *async_runtime, const SignalCdsiLookup *lookup);
 
+SignalFfiError *signal_cdsi_lookup_complete_entries(SignalCPromiseFfiCdsiLookupEntries *promise, const SignalTokioAsyncContext *async_runtime, const SignalCdsiLookup *lookup);
+
 SignalFfiError *signal_auth_chat_destroy(SignalAuthChat *p);
 
 SignalFfiError *signal_unauth_chat_destroy(SignalUnauthChat *p);
 
 SignalFfiError *signal_http_request_destroy(SignalHttpRequest *p);
 
+SignalFfiError *signal_multi_recipient_mismatched_devices_destroy(SignalMultiRecipientMismatchedDevices *p);
+
+SignalFfiError *signal_registration_lock_failure_destroy(SignalRegistrationLockFailure *p);
+
+SignalFfiError *signal_unauth_chat_pool_destroy(SignalUnauthChatPool *p);
+
 SignalFfiError *signal_http_request_new_with_body(SignalHttpRequest **out, const char *method, const char *path, SignalBorrowedBuffer body_as_slice);
 
 SignalFfiError *signal_http_request_new_without_body(SignalHttpRequest **out, const char *method, const char *path);
@@ -1513,10 +1715,16 @@ SignalFfiError *signal_chat_service_new_unauth(SignalUnauthChat **out, const Sig
 
 SignalFfiError *signal_chat_service_new_auth(SignalAuthChat **out, const SignalConnectionManager *connection_manager, const char *username, const char *password, bool receive_stories);
 
+SignalFfiError *signal_chat_service_new_auth_with_provider(SignalAuthChat **out, const SignalConnectionManager *connection_manager, const SignalFfiMakeChatAuthProviderStruct *auth_provider, bool receive_stories);
+
 SignalFfiError *signal_chat_service_disconnect_unauth(SignalCPromisebool *promise, const SignalTokioAsyncContext *async_runtime, const SignalUnauthChat *chat);
 
 SignalFfiError *signal_chat_service_disconnect_auth(SignalCPromisebool *promise, const SignalTokioAsyncContext *async_runtime, const SignalAuthChat *chat);
 
+SignalFfiError *signal_chat_service_rtt_estimate_millis_unauth(uint32_t *out, const SignalUnauthChat *chat);
+
+SignalFfiError *signal_chat_service_rtt_estimate_millis_auth(uint32_t *out, const SignalAuthChat *chat);
+
 SignalFfiError *signal_chat_service_connect_unauth(SignalCPromiseFfiChatServiceDebugInfo *promise, const SignalTokioAsyncContext *async_runtime, const SignalUnauthChat *chat);
 
 SignalFfiError *signal_chat_service_connect_auth(SignalCPromiseFfiChatServiceDebugInfo *promise, const SignalTokioAsyncContext *async_runtime, const SignalAuthChat *chat);
@@ -1529,10 +1737,42 @@ SignalFfiError *signal_chat_service_auth_send(SignalCPromiseFfiChatResponse *pro
 
 SignalFfiError *signal_chat_service_auth_send_and_debug(SignalCPromiseFfiResponseAndDebugInfo *promise, const SignalTokioAsyncContext *async_runtime, const SignalAuthChat *chat, const SignalHttpRequest *http_request, uint32_t timeout_millis);
 
+SignalFfiError *signal_unauth_chat_pool_new(SignalUnauthChatPool **out, const SignalTokioAsyncContext *async_runtime, const SignalConnectionManager *connection_manager);
+
+SignalFfiError *signal_unauth_chat_pool_send(SignalCPromiseFfiChatResponse *promise, const SignalTokioAsyncContext *async_runtime, const SignalUnauthChatPool *pool, const SignalHttpRequest *http_request, uint32_t timeout_millis);
+
+SignalFfiError *signal_unauth_chat_pool_shutdown(const SignalUnauthChatPool *pool);
+
 SignalFfiError *signal_chat_service_set_listener_auth(const SignalTokioAsyncContext *runtime, const SignalAuthChat *chat, const SignalFfiMakeChatListenerStruct *make_listener);
 
 SignalFfiError *signal_chat_service_set_listener_unauth(const SignalTokioAsyncContext *runtime, const SignalUnauthChat *chat, const SignalFfiMakeChatListenerStruct *make_listener);
 
+SignalFfiError *signal_multi_recipient_send_response_parse_unregistered(SignalBytestringArray *out, SignalBorrowedBuffer body);
+
+SignalFfiError *signal_multi_recipient_mismatched_devices_parse(SignalMultiRecipientMismatchedDevices **out, SignalBorrowedBuffer body);
+
+SignalFfiError *signal_multi_recipient_mismatched_devices_count(uint32_t *out, const SignalMultiRecipientMismatchedDevices *mismatched);
+
+SignalFfiError *signal_multi_recipient_mismatched_devices_get_service_id(SignalServiceIdFixedWidthBinaryBytes *out, const SignalMultiRecipientMismatchedDevices *mismatched, uint32_t index);
+
+SignalFfiError *signal_multi_recipient_mismatched_devices_get_missing_devices(SignalOwnedBuffer *out, const SignalMultiRecipientMismatchedDevices *mismatched, uint32_t index);
+
+SignalFfiError *signal_multi_recipient_mismatched_devices_get_extra_devices(SignalOwnedBuffer *out, const SignalMultiRecipientMismatchedDevices *mismatched, uint32_t index);
+
+SignalFfiError *signal_multi_recipient_mismatched_devices_get_stale_devices(SignalOwnedBuffer *out, const SignalMultiRecipientMismatchedDevices *mismatched, uint32_t index);
+
+SignalFfiError *signal_registration_lock_failure_parse(SignalRegistrationLockFailure **out, SignalBorrowedBuffer body);
+
+SignalFfiError *signal_registration_lock_failure_get_time_remaining_millis(uint64_t *out, const SignalRegistrationLockFailure *failure);
+
+SignalFfiError *signal_registration_lock_failure_get_svr2_username(const char **out, const SignalRegistrationLockFailure *failure);
+
+SignalFfiError *signal_registration_lock_failure_get_svr2_password(const char **out, const SignalRegistrationLockFailure *failure);
+
+SignalFfiError *signal_chat_service_auth_submit_captcha_challenge(SignalCPromisebool *promise, const SignalTokioAsyncContext *async_runtime, const SignalAuthChat *chat, const char *token, const char *captcha, uint32_t timeout_millis);
+
+SignalFfiError *signal_chat_service_auth_submit_push_challenge(SignalCPromisebool *promise, const SignalTokioAsyncContext *async_runtime, const SignalAuthChat *chat, const char *challenge, uint32_t timeout_millis);
+
 SignalFfiError *signal_server_message_ack_destroy(SignalServerMessageAck *p);
 
 SignalFfiError *signal_server_message_ack_send(SignalCPromisebool *promise, const SignalTokioAsyncContext *async_runtime, const SignalServerMessageAck *ack);
@@ -1543,6 +1783,8 @@ SignalFfiError *signal_tokio_async_context_new(SignalTokioAsyncContext **out);
 
 SignalFfiError *signal_tokio_async_context_cancel(const SignalTokioAsyncContext *context, uint64_t raw_cancellation_id);
 
+SignalFfiError *signal_tokio_async_context_shutdown(const SignalTokioAsyncContext *context, uint32_t timeout_millis);
+
 SignalFfiError *signal_pin_hash_destroy(SignalPinHash *p);
 
 SignalFfiError *signal_pin_hash_clone(SignalPinHash **new_obj, const SignalPinHash *obj);
@@ -1573,6 +1815,14 @@ SignalFfiError *signal_incremental_mac_update(SignalOwnedBuffer *out, SignalIncr
 
 SignalFfiError *signal_incremental_mac_finalize(SignalOwnedBuffer *out, SignalIncrementalMac *mac);
 
+SignalFfiError *signal_incremental_mac_and_digest_destroy(SignalIncrementalMacAndDigest *p);
+
+SignalFfiError *signal_incremental_mac_and_digest_initialize(SignalIncrementalMacAndDigest **out, SignalBorrowedBuffer key, uint32_t chunk_size);
+
+SignalFfiError *signal_incremental_mac_and_digest_update(SignalOwnedBuffer *out, SignalIncrementalMacAndDigest *mac, SignalBorrowedBuffer bytes, uint32_t offset, uint32_t length);
+
+SignalFfiError *signal_incremental_mac_and_digest_finalize(SignalOwnedBuffer *out, SignalIncrementalMacAndDigest *mac);
+
 SignalFfiError *signal_validating_mac_destroy(SignalValidatingMac *p);
 
 SignalFfiError *signal_validating_mac_initialize(SignalValidatingMac **out, SignalBorrowedBuffer key, uint32_t chunk_size, SignalBorrowedBuffer digests);
@@ -1583,16 +1833,32 @@ SignalFfiError *signal_validating_mac_finalize(int32_t *out, SignalValidatingMac
 
 SignalFfiError *signal_message_backup_key_destroy(SignalMessageBackupKey *p);
 
+SignalFfiError *signal_backup_key_material_destroy(SignalBackupKeyMaterial *p);
+
 SignalFfiError *signal_message_backup_validation_outcome_destroy(SignalMessageBackupValidationOutcome *p);
 
 SignalFfiError *signal_message_backup_key_new(SignalMessageBackupKey **out, const uint8_t (*master_key)[32], const SignalServiceIdFixedWidthBinaryBytes *aci);
 
+SignalFfiError *signal_message_backup_key_from_key_material(SignalMessageBackupKey **out, const SignalBackupKeyMaterial *material);
+
+SignalFfiError *signal_backup_key_material_new(SignalBackupKeyMaterial **out, const uint8_t (*master_key)[32], const SignalServiceIdFixedWidthBinaryBytes *aci);
+
+SignalFfiError *signal_backup_key_material_get_backup_key(uint8_t (*out)[32], const SignalBackupKeyMaterial *material);
+
+SignalFfiError *signal_backup_key_material_get_backup_id(uint8_t (*out)[16], const SignalBackupKeyMaterial *material);
+
 SignalFfiError *signal_message_backup_validation_outcome_get_error_message(const char **out, const SignalMessageBackupValidationOutcome *outcome);
 
+SignalFfiError *signal_message_backup_validation_outcome_get_error_kind(uint8_t *out, const SignalMessageBackupValidationOutcome *outcome);
+
 SignalFfiError *signal_message_backup_validation_outcome_get_unknown_fields(SignalStringArray *out, const SignalMessageBackupValidationOutcome *outcome);
 
 SignalFfiError *signal_message_backup_validator_validate(SignalMessageBackupValidationOutcome **out, const SignalMessageBackupKey *key, const SignalInputStream *first_stream, const SignalInputStream *second_stream, uint64_t len, uint8_t purpose);
 
+SignalFfiError *signal_message_backup_validator_validate_with_progress(SignalMessageBackupValidationOutcome **out, const SignalMessageBackupKey *key, const SignalInputStream *first_stream, const SignalInputStream *second_stream, uint64_t len, uint8_t purpose, const SignalFfiBackupProgressListenerStruct *progress_listener);
+
+SignalFfiError *signal_message_backup_write_encrypted(const SignalInputStream *frames_source, const SignalMessageBackupKey *key, const SignalOutputStream *output_stream);
+
 SignalFfiError *signal_username_hash(uint8_t (*out)[32], const char *username);
 
 SignalFfiError *signal_username_proof(SignalOwnedBuffer *out, const char *username, SignalBorrowedBuffer randomness);
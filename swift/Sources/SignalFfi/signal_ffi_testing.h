@@ -182,8 +182,20 @@ SignalFfiError *signal_comparable_backup_get_unknown_fields(SignalStringArray *o
 
 SignalFfiError *signal_testing_cdsi_lookup_response_convert(SignalCPromiseFfiCdsiLookupResponse *promise, const SignalTokioAsyncContext *async_runtime);
 
+SignalFfiError *signal_testing_cdsi_lookup_response_entries_convert(SignalCPromiseFfiCdsiLookupEntries *promise, const SignalTokioAsyncContext *async_runtime);
+
 SignalFfiError *signal_testing_only_completes_by_cancellation(SignalCPromisebool *promise, const SignalTokioAsyncContext *async_runtime);
 
+SignalFfiError *signal_testing_connection_manager_prewarm_dns(SignalCPromisebool *promise, const SignalTokioAsyncContext *async_runtime, const SignalConnectionManager *connection_manager, const char *hostname);
+
+SignalFfiError *signal_testing_net_new_tokio_async_context_with_paused_time(SignalTokioAsyncContext **out);
+
+SignalFfiError *signal_testing_net_set_time_auto_advance(const SignalTokioAsyncContext *async_runtime, bool auto_advance);
+
+SignalFfiError *signal_testing_net_advance_time(const SignalTokioAsyncContext *async_runtime, uint32_t millis);
+
+SignalFfiError *signal_testing_net_live_connection_count(uint32_t *out, const char *kind);
+
 SignalFfiError *signal_testing_cdsi_lookup_error_convert(const char *error_description);
 
 SignalFfiError *signal_testing_chat_service_error_convert(const char *error_description);
@@ -204,6 +216,8 @@ SignalFfiError *signal_testing_chat_request_get_body(SignalOwnedBuffer *out, con
 
 SignalFfiError *signal_testing_chat_service_inject_raw_server_request(const SignalAuthChat *chat, SignalBorrowedBuffer bytes);
 
+SignalFfiError *signal_testing_chat_service_inject_incoming_messages(const SignalAuthChat *chat, uint32_t count);
+
 SignalFfiError *signal_testing_chat_service_inject_connection_interrupted(const SignalAuthChat *chat);
 
 SignalFfiError *signal_testing_chat_service_inject_intentional_disconnect(const SignalAuthChat *chat);
@@ -48,6 +48,22 @@ final class BridgingTests: XCTestCase {
         }
     }
 
+    func testStableErrorCode() throws {
+        do {
+            try checkError(signal_testing_error_on_borrow_sync(nil))
+            XCTFail("should have failed")
+        } catch let error as SignalError {
+            guard case .invalidArgument(_, let stableCode) = error else {
+                XCTFail("unexpected error: \(error)")
+                return
+            }
+            XCTAssertEqual(stableCode, 2) // SignalErrorCode::InvalidArgument
+            XCTAssertEqual(error.stableCode, stableCode)
+        }
+
+        XCTAssertNil(SignalError.invalidArgument("not from Rust").stableCode)
+    }
+
     func testPanicOnBorrow() async throws {
         do {
             try checkError(signal_testing_panic_on_borrow_sync(nil))
@@ -24,6 +24,12 @@ extension AuthenticatedChatService {
         }
     }
 
+    func injectIncomingMessages(count: UInt32) {
+        withNativeHandle { handle in
+            failOnError(signal_testing_chat_service_inject_incoming_messages(handle, count))
+        }
+    }
+
     func injectConnectionInterrupted() {
         withNativeHandle { handle in
             failOnError(signal_testing_chat_service_inject_connection_interrupted(handle))
@@ -75,6 +81,9 @@ final class ChatServiceTests: TestCaseBase {
         XCTAssertEqual(.ipv4, debugInfo.ipType)
         XCTAssertEqual(0.2, debugInfo.duration)
         XCTAssertEqual("connection_info", debugInfo.connectionInfo)
+        XCTAssertEqual(.tlsProxy, debugInfo.routeType)
+        XCTAssertEqual("192.0.2.1", debugInfo.remoteIp)
+        XCTAssertEqual(443, debugInfo.remotePort)
     }
 
     func testConvertResponseAndDebugInfo() throws {
@@ -122,10 +131,17 @@ final class ChatServiceTests: TestCaseBase {
         } catch SignalError.networkProtocolError(_) {}
         do {
             try failWithError("Timeout")
-        } catch SignalError.connectionTimeoutError(_) {}
+        } catch SignalError.connectionTimeoutError(let message, _) {
+            XCTAssert(message.contains("000000000000002a"), message)
+        }
         do {
             try failWithError("TimeoutEstablishingConnection")
         } catch SignalError.connectionTimeoutError(_) {}
+        do {
+            try failWithError("RequestChannelClosed")
+        } catch SignalError.webSocketError(let message, _) {
+            XCTAssert(message.contains("000000000000002a"), message)
+        }
 
         do {
             try failWithError("FailedToPassMessageToIncomingChannel")
@@ -152,7 +168,7 @@ final class ChatServiceTests: TestCaseBase {
                 signal_testing_chat_request_get_body($0, internalRequest)
             })
             for (k, v) in Self.expectedHeaders {
-                XCTAssertEqual(v, try invokeFnReturningString {
+                XCTAssertEqual(v, try invokeFnReturningOptionalString {
                     signal_testing_chat_request_get_header_value($0, internalRequest, k)
                 })
             }
@@ -249,6 +265,46 @@ final class ChatServiceTests: TestCaseBase {
         XCTAssertEqual(listener.stage, 4)
     }
 
+    func testListenerCallbacksAreOrderedEvenWhenSlow() throws {
+        class Listener: ChatListener {
+            let count: UInt64
+            var received: [UInt64] = []
+            let allReceived: XCTestExpectation
+
+            init(count: UInt64, allReceived: XCTestExpectation) {
+                self.count = count
+                self.allReceived = allReceived
+            }
+
+            func chatService(_ chat: AuthenticatedChatService, didReceiveIncomingMessage envelope: Data, serverDeliveryTimestamp: UInt64, sendAck: () async throws -> Void) {
+                XCTAssertEqual(envelope, Data(String(serverDeliveryTimestamp).utf8))
+                Thread.sleep(forTimeInterval: TimeInterval.random(in: 0..<0.01))
+                self.received.append(serverDeliveryTimestamp)
+                if self.received.count == self.count {
+                    self.allReceived.fulfill()
+                }
+            }
+
+            func chatServiceDidReceiveQueueEmpty(_: AuthenticatedChatService) {
+                XCTFail("unexpected queue empty")
+            }
+
+            func connectionWasInterrupted(_: AuthenticatedChatService, error: Error?) {
+                XCTFail("unexpected interruption")
+            }
+        }
+
+        let net = Net(env: .staging, userAgent: Self.userAgent)
+        let chat = net.createAuthenticatedChatService(username: "", password: "", receiveStories: false)
+        let listener = Listener(count: 50, allReceived: expectation(description: "all messages received"))
+        chat.setListener(listener)
+
+        chat.injectIncomingMessages(count: 50)
+
+        waitForExpectations(timeout: 5)
+        XCTAssertEqual(listener.received, Array(0..<50))
+    }
+
 #endif
 
     func testListenerCleanup() throws {
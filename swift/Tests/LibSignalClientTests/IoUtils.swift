@@ -55,6 +55,30 @@ public class ThrowsAfterInputStream: SignalInputStream {
     private var readBeforeThrow: UInt64
 }
 
+public class DataOutputStream: SignalOutputStream {
+    public var data = Data()
+    public var flushed = false
+
+    public func write(from buffer: UnsafeRawBufferPointer) throws {
+        self.data.append(contentsOf: buffer)
+        self.flushed = false
+    }
+
+    public func flush() throws {
+        self.flushed = true
+    }
+}
+
+public class ErrorOutputStream: SignalOutputStream {
+    public func write(from buffer: UnsafeRawBufferPointer) throws {
+        throw TestIoError()
+    }
+
+    public func flush() throws {
+        throw TestIoError()
+    }
+}
+
 func readResource(forName name: String) -> Data {
     try! Data(
         contentsOf: URL(fileURLWithPath: #file)
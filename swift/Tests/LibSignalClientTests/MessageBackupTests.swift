@@ -15,6 +15,25 @@ class MessageBackupTests: TestCaseBase {
         XCTAssertEqual(outcome.fields, [])
     }
 
+    func testValidInputReportsProgress() throws {
+        let bytes = readResource(forName: "new_account.binproto.encrypted")
+
+        var reports: [MessageBackupValidationProgress] = []
+        let outcome = try validateMessageBackup(
+            key: MessageBackupKey.testKey(),
+            purpose: .remoteBackup,
+            length: UInt64(bytes.count),
+            makeStream: { SignalInputStreamAdapter(bytes) },
+            onProgress: { reports.append($0) }
+        )
+        XCTAssertEqual(outcome.fields, [])
+
+        let last = try XCTUnwrap(reports.last)
+        XCTAssertGreaterThan(last.framesRead, 0)
+        XCTAssertEqual(last.bytesRead, UInt64(bytes.count))
+        XCTAssertEqual(last.totalBytes, UInt64(bytes.count))
+    }
+
     func testInvalidInput() throws {
         // Start with a valid file, then overwrite some bytes
         var bytes = readResource(forName: "new_account.binproto.encrypted")
@@ -22,6 +41,7 @@ class MessageBackupTests: TestCaseBase {
         // Validation failed, so this should throw.
         XCTAssertThrowsError(try Self.validateBackup(bytes: bytes)) { error in
             if let error = error as? MessageBackupValidationError {
+                XCTAssertEqual(error.kind, .hmacMismatch)
                 XCTAssert(error.errorMessage.starts(with: "HMAC doesn't match"), "\(error.errorMessage)")
             } else {
                 XCTFail("\(error)")
@@ -32,6 +52,7 @@ class MessageBackupTests: TestCaseBase {
     func testEmptyInput() throws {
         XCTAssertThrowsError(try Self.validateBackup(bytes: [])) { error in
             if let error = error as? MessageBackupValidationError {
+                XCTAssertEqual(error.kind, .tooShort)
                 XCTAssertEqual(error.errorMessage, "not enough bytes for an HMAC")
             } else {
                 XCTFail("\(error)")
@@ -63,6 +84,25 @@ class MessageBackupTests: TestCaseBase {
         }
     }
 
+    func testWriteEncrypted() throws {
+        let frames = readResource(forName: "canonical-backup.binproto")
+        let output = DataOutputStream()
+        try writeEncryptedMessageBackup(key: MessageBackupKey.testKey(), frames: SignalInputStreamAdapter(frames), output: output)
+        XCTAssert(output.flushed)
+
+        let outcome = try Self.validateBackup(bytes: output.data)
+        XCTAssertEqual(outcome.fields, [])
+    }
+
+    func testWriteEncryptedOutputThrows() {
+        let frames = readResource(forName: "canonical-backup.binproto")
+        XCTAssertThrowsError(
+            try writeEncryptedMessageBackup(key: MessageBackupKey.testKey(), frames: SignalInputStreamAdapter(frames), output: ErrorOutputStream())
+        ) { error in
+            if error is TestIoError {} else { XCTFail("\(error)") }
+        }
+    }
+
 #if !os(iOS) || targetEnvironment(simulator)
     func testComparableBackup() throws {
         let bytes = readResource(forName: "canonical-backup.binproto")
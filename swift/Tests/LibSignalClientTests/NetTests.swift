@@ -41,6 +41,31 @@ final class NetTests: XCTestCase {
         XCTAssertEqual(expected, Array(entryList))
     }
 
+    func testCdsiLookupEntriesConversion() async throws {
+        let ACI_UUID = "9d0652a3-dcc3-4d11-975f-74d61598733f"
+        let PNI_UUID = "796abedb-ca4e-4f18-8803-1fde5b921f9f"
+
+        let aci = Aci(fromUUID: UUID(uuidString: ACI_UUID)!)
+        let pni = Pni(fromUUID: UUID(uuidString: PNI_UUID)!)
+
+        let asyncContext = TokioAsyncContext()
+
+        let output: SignalFfiCdsiLookupEntries = try await asyncContext.invokeAsyncFunction { promise, asyncContext in
+            signal_testing_cdsi_lookup_response_entries_convert(promise, asyncContext)
+        }
+        let response = try CdsiLookupEntries(owned: output)
+        XCTAssertEqual(response.debugPermitsUsed, 123)
+
+        let expected = [
+            CdsiLookupEntry(e164: 18_005_551_011, aci: aci, pni: pni),
+            CdsiLookupEntry(e164: 18_005_551_012, aci: nil, pni: pni),
+        ]
+        XCTAssertEqual(expected, response.entries)
+        // The PNI-only entry comes through with a nil ACI, not the nil UUID.
+        XCTAssertNil(response.entries[1].aci)
+        XCTAssertEqual([expected[0]], response.entriesWithAci)
+    }
+
     func testCdsiLookupErrorConversion() async throws {
         let failWithError = {
             try checkError(signal_testing_cdsi_lookup_error_convert($0))
@@ -48,57 +73,57 @@ final class NetTests: XCTestCase {
         }
         do {
             try failWithError("Protocol")
-        } catch SignalError.networkProtocolError(let message) {
+        } catch SignalError.networkProtocolError(let message, _) {
             XCTAssertEqual(message, "Protocol error: protocol error after establishing a connection")
         }
         do {
             try failWithError("AttestationDataError")
-        } catch SignalError.invalidAttestationData(let message) {
+        } catch SignalError.invalidAttestationData(let message, _) {
             XCTAssertEqual(message, "SGX operation failed: attestation data invalid: fake reason")
         }
         do {
             try failWithError("InvalidResponse")
-        } catch SignalError.networkProtocolError(let message) {
+        } catch SignalError.networkProtocolError(let message, _) {
             XCTAssertEqual(message, "Protocol error: invalid response received from the server")
         }
         do {
             try failWithError("RetryAfter42Seconds")
-        } catch SignalError.rateLimitedError(retryAfter: 42, let message) {
+        } catch SignalError.rateLimitedError(retryAfter: 42, let message, _) {
             XCTAssertEqual(message, "Rate limited; try again after 42s")
         }
         do {
             try failWithError("InvalidToken")
-        } catch SignalError.cdsiInvalidToken(let message) {
+        } catch SignalError.cdsiInvalidToken(let message, _) {
             XCTAssertEqual(message, "CDSI request token was invalid")
         }
         do {
             try failWithError("InvalidArgument")
-        } catch SignalError.invalidArgument(let message) {
+        } catch SignalError.invalidArgument(let message, _) {
             XCTAssertEqual(message, "invalid argument: request was invalid: fake reason")
         }
         do {
             try failWithError("Parse")
-        } catch SignalError.networkProtocolError(let message) {
+        } catch SignalError.networkProtocolError(let message, _) {
             XCTAssertEqual(message, "Protocol error: failed to parse the response from the server")
         }
         do {
             try failWithError("ConnectDnsFailed")
-        } catch SignalError.ioError(let message) {
+        } catch SignalError.ioError(let message, _) {
             XCTAssertEqual(message, "IO error: DNS lookup failed")
         }
         do {
             try failWithError("WebSocketIdleTooLong")
-        } catch SignalError.webSocketError(let message) {
+        } catch SignalError.webSocketError(let message, _) {
             XCTAssertEqual(message, "WebSocket error: channel was idle for too long")
         }
         do {
             try failWithError("ConnectionTimedOut")
-        } catch SignalError.connectionTimeoutError(let message) {
+        } catch SignalError.connectionTimeoutError(let message, _) {
             XCTAssertEqual(message, "Connect timed out")
         }
         do {
             try failWithError("ServerCrashed")
-        } catch SignalError.networkProtocolError(let message) {
+        } catch SignalError.networkProtocolError(let message, _) {
             XCTAssertEqual(message, "Protocol error: server error: crashed")
         }
     }
@@ -130,6 +155,33 @@ final class NetTests: XCTestCase {
         let net = Net(env: .staging, userAgent: userAgent)
         try net.networkDidChange()
     }
+
+    func testTrafficStatsLayout() {
+        var serialized = [UInt8](repeating: 0, count: 64)
+        for i in 0..<8 {
+            // Each count is big-endian, with its index in the last byte and 1 in the first.
+            serialized[8 * i] = 1
+            serialized[8 * i + 7] = UInt8(i)
+        }
+        let base: UInt64 = 1 << 56
+        XCTAssertEqual(TrafficStats(serialized: serialized), TrafficStats(
+            chat: TrafficCounts(sent: base, received: base + 1),
+            cdsi: TrafficCounts(sent: base + 2, received: base + 3),
+            svr: TrafficCounts(sent: base + 4, received: base + 5),
+            cdn: TrafficCounts(sent: base + 6, received: base + 7)
+        ))
+    }
+
+    func testTrafficStats() throws {
+        _ = TrafficStats.reset()
+        let snapshot = TrafficStats.snapshot()
+        // Counts only go up until they're reset, so the reset sees at least what the snapshot did.
+        let reset = TrafficStats.reset()
+        for (before, after) in [(snapshot.chat, reset.chat), (snapshot.cdsi, reset.cdsi), (snapshot.svr, reset.svr), (snapshot.cdn, reset.cdn)] {
+            XCTAssertLessThanOrEqual(before.sent, after.sent)
+            XCTAssertLessThanOrEqual(before.received, after.received)
+        }
+    }
 }
 
 final class Svr3Tests: TestCaseBase {
@@ -230,7 +282,7 @@ final class Svr3Tests: TestCaseBase {
                 auth: self.state!.auth
             )
             XCTFail("Should have thrown")
-        } catch SignalError.svrRestoreFailed(let triesRemaining, _) {
+        } catch SignalError.svrRestoreFailed(let triesRemaining, _, _) {
             // Success!
             XCTAssertEqual(triesRemaining, tries - 1)
         } catch {
@@ -255,7 +307,7 @@ final class Svr3Tests: TestCaseBase {
                 auth: self.state!.auth
             )
             XCTFail("Should have thrown")
-        } catch SignalError.svrRestoreFailed(_, _) {
+        } catch SignalError.svrRestoreFailed(_, _, _) {
             // Success!
         } catch {
             XCTFail("Unexpected exception: '\(error)'")
@@ -305,7 +357,7 @@ final class Svr3Tests: TestCaseBase {
                 auth: self.state!.auth
             )
             XCTFail("Should have thrown")
-        } catch SignalError.svrRestoreFailed(_, _) {
+        } catch SignalError.svrRestoreFailed(_, _, _) {
             // Success!
         } catch {
             XCTFail("Unexpected exception: '\(error)'")
@@ -370,7 +422,7 @@ final class Svr3Tests: TestCaseBase {
                 auth: auth
             )
             XCTFail("Should have failed")
-        } catch SignalError.webSocketError(let message) {
+        } catch SignalError.webSocketError(let message, _) {
             XCTAssert(message.contains("401"))
         } catch {
             XCTFail("Unexpected error: \(error)")
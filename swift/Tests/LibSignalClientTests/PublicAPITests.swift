@@ -487,6 +487,34 @@ class PublicAPITests: TestCaseBase {
         XCTAssert(try! secondary.identityKey.verifyAlternateIdentity(primary.identityKey, signature: signature))
     }
 
+    func testPniSignatureVector() throws {
+        // Shared with the Rust, Java, and TypeScript tests.
+        let aci = IdentityKeyPair(
+            publicKey: try PublicKey([UInt8](fromHexString: "0505c86d71f5ee1af068207fb1b50ba69d60fd70ffa78375eaa9f60f100ca65871")!),
+            privateKey: try PrivateKey([UInt8](fromHexString: "5882e1f3662ee3dd54b5d0b80f7d3f67277ee39a67d47697f78974879cd93266")!)
+        )
+        let pni = IdentityKeyPair(
+            publicKey: try PublicKey([UInt8](fromHexString: "0564a82fb9ceae27d3f741f728b3a3bc10a6446e4d702f3184b302a0fb5c93a424")!),
+            privateKey: try PrivateKey([UInt8](fromHexString: "1823511d57e3ca8aafbe50a6d0a3f0ed4076eae74c5e0e2351d84eda70dc7d6f")!)
+        )
+        XCTAssertEqual(aci.privateKey.publicKey, aci.publicKey)
+        XCTAssertEqual(pni.privateKey.publicKey, pni.publicKey)
+        let signature = [UInt8](fromHexString: "17e3c41803c64aad8effe9a4c516cc1452e1c1a5a1fafea6cd1cc55aa8f869b283f821cfd6eec07b43190acbd62274704c24928da000e322095e6ec690b9db0b")!
+
+        XCTAssert(try aci.identityKey.verifyPniSignature(pni.identityKey, signature: signature))
+        XCTAssertFalse(try pni.identityKey.verifyPniSignature(aci.identityKey, signature: signature))
+
+        let fresh = pni.createPniSignature(aciIdentity: aci.identityKey)
+        XCTAssert(try aci.identityKey.verifyPniSignature(pni.identityKey, signature: fresh))
+
+        XCTAssertThrowsError(try aci.identityKey.verifyPniSignature(pni.identityKey, signature: signature.dropLast())) {
+            guard case SignalError.invalidArgument(_) = $0 else {
+                XCTFail("wrong error: \($0)")
+                return
+            }
+        }
+    }
+
     func testPreKeyBundleAccessors() {
         let registrationId: UInt32 = 123
         let deviceId: UInt32 = 5
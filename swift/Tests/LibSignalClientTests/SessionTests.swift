@@ -464,7 +464,7 @@ class SessionTests: TestCaseBase {
                 context: NullContext()
             )
             XCTFail("should have thrown")
-        } catch SignalError.invalidRegistrationId(address: let address, message: _) {
+        } catch SignalError.invalidRegistrationId(address: let address, message: _, stableCode: _) {
             XCTAssertEqual(address, bob_address)
         }
     }